@@ -0,0 +1,30 @@
+//! Block validation: merkle root, transaction signatures and proof of work
+//! التحقق من الكتل: جذر ميركل وتواقيع المعاملات وإثبات العمل
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use opensyria_benches::{keypairs, signed_transfers};
+use opensyria_consensus::ProofOfWork;
+use opensyria_core::Block;
+
+fn block_validation(c: &mut Criterion) {
+    // 1,000 transactions: 100 senders with 10 each
+    let transactions = signed_transfers(&keypairs(100), 10);
+    let block = Block::new([0u8; 32], transactions, 16);
+    let pow = ProofOfWork::new(16);
+
+    let mut group = c.benchmark_group("block_validation");
+    group.throughput(Throughput::Elements(block.transactions.len() as u64));
+    group.bench_function("merkle_root", |b| {
+        b.iter(|| black_box(&block).verify_merkle_root())
+    });
+    group.bench_function("transactions", |b| {
+        b.iter(|| black_box(&block).verify_transactions())
+    });
+    group.bench_function("proof_of_work", |b| {
+        b.iter(|| pow.validate(black_box(&block)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, block_validation);
+criterion_main!(benches);
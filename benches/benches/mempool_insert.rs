@@ -0,0 +1,81 @@
+//! Mempool insertion into an empty pool and into a full one that must evict
+//! إدراج المعاملات في مجمع فارغ وفي مجمع ممتلئ يتطلب الإخلاء
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use opensyria_benches::{funded_state, keypairs, signed_transfer, signed_transfers, FIXTURE_FEE};
+use opensyria_mempool::{Mempool, MempoolConfig};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+use tokio::sync::RwLock;
+
+fn mempool_insert(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("tokio runtime");
+    // 1,000 senders for the base load and 100 more that displace it
+    let senders = keypairs(1_100);
+    let (base, newcomers) = senders.split_at(1_000);
+    let dir = tempfile::tempdir().expect("temp dir");
+    let state = Arc::new(RwLock::new(funded_state(
+        dir.path(),
+        &senders,
+        1_000_000_000,
+    )));
+
+    // 1,000 transactions: one per sender, so sender limits never apply
+    let transactions = signed_transfers(base, 1);
+    // Higher-fee transactions from other senders, so each can evict one
+    let displacing: Vec<_> = newcomers
+        .iter()
+        .map(|sender| signed_transfer(sender, 0, FIXTURE_FEE * 10))
+        .collect();
+    let full_config = MempoolConfig {
+        max_size: transactions.len(),
+        ..MempoolConfig::default()
+    };
+
+    let mut group = c.benchmark_group("mempool_insert");
+    group.sample_size(20);
+
+    group.throughput(Throughput::Elements(transactions.len() as u64));
+    group.bench_function("empty_pool_1k", |b| {
+        b.iter_batched(
+            || Mempool::new(MempoolConfig::default(), state.clone()),
+            |mut mempool| {
+                runtime.block_on(async {
+                    for tx in &transactions {
+                        mempool.add_transaction(tx.clone()).await.expect("accepted");
+                    }
+                });
+                mempool
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.throughput(Throughput::Elements(displacing.len() as u64));
+    group.bench_function("full_pool_evict_100", |b| {
+        b.iter_batched(
+            || {
+                let mut mempool = Mempool::new(full_config.clone(), state.clone());
+                runtime.block_on(async {
+                    for tx in &transactions {
+                        mempool.add_transaction(tx.clone()).await.expect("accepted");
+                    }
+                });
+                mempool
+            },
+            |mut mempool| {
+                runtime.block_on(async {
+                    for tx in &displacing {
+                        mempool.add_transaction(tx.clone()).await.expect("evicts");
+                    }
+                });
+                mempool
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, mempool_insert);
+criterion_main!(benches);
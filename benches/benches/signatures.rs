@@ -0,0 +1,32 @@
+//! Transaction signature checks one at a time and as an ed25519 batch
+//! التحقق من تواقيع المعاملات فردياً ودفعة واحدة
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use opensyria_benches::{keypairs, signed_transfers};
+use opensyria_core::crypto::{verify_batch, PublicKey};
+use opensyria_mempool::DEFAULT_SIGNATURE_BATCH_SIZE;
+
+fn signatures(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signatures");
+    for size in [1, 16, DEFAULT_SIGNATURE_BATCH_SIZE, 256] {
+        let transactions = signed_transfers(&keypairs(size), 1);
+        let messages: Vec<[u8; 32]> = transactions.iter().map(|tx| tx.signing_hash()).collect();
+        let items: Vec<(PublicKey, &[u8], &[u8])> = transactions
+            .iter()
+            .zip(&messages)
+            .map(|(tx, message)| (tx.from, message.as_slice(), tx.signature.as_slice()))
+            .collect();
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("single", size), &transactions, |b, txs| {
+            b.iter(|| txs.iter().all(|tx| black_box(tx).verify().is_ok()))
+        });
+        group.bench_with_input(BenchmarkId::new("batch", size), &items, |b, items| {
+            b.iter(|| verify_batch(black_box(items)).is_ok())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, signatures);
+criterion_main!(benches);
@@ -0,0 +1,35 @@
+//! Applying a block's transactions to state in one atomic batch
+//! تطبيق معاملات كتلة على الحالة دفعة ذرية واحدة
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use opensyria_benches::{funded_state, keypairs, signed_transfers};
+
+fn apply_block_atomic(c: &mut Criterion) {
+    let senders = keypairs(100);
+    // 1,000 transactions: 100 senders with nonces 0..10
+    let transactions = signed_transfers(&senders, 10);
+
+    let mut group = c.benchmark_group("state_apply");
+    group.throughput(Throughput::Elements(transactions.len() as u64));
+    group.sample_size(20);
+    group.bench_function("apply_block_atomic_1k", |b| {
+        b.iter_batched(
+            || {
+                // Fresh state every iteration so nonces start at zero
+                let dir = tempfile::tempdir().expect("temp dir");
+                let state = funded_state(dir.path(), &senders, 1_000_000_000);
+                (dir, state)
+            },
+            |(_dir, state)| {
+                state
+                    .apply_block_atomic(&transactions)
+                    .expect("apply block")
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, apply_block_atomic);
+criterion_main!(benches);
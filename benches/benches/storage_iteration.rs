@@ -0,0 +1,49 @@
+//! RocksDB access patterns over account state: full scans, paged scans
+//! and point reads
+//! أنماط الوصول إلى RocksDB: المسح الكامل والمسح المقسم والقراءات المفردة
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use opensyria_benches::{funded_state, keypairs};
+
+const ACCOUNTS: usize = 10_000;
+
+fn storage_iteration(c: &mut Criterion) {
+    let accounts = keypairs(ACCOUNTS);
+    let dir = tempfile::tempdir().expect("temp dir");
+    let state = funded_state(dir.path(), &accounts, 1_000_000);
+    let addresses: Vec<_> = accounts.iter().map(|key| key.public_key()).collect();
+
+    let mut group = c.benchmark_group("storage_iteration");
+    group.throughput(Throughput::Elements(ACCOUNTS as u64));
+    group.bench_function("full_scan_10k", |b| {
+        b.iter(|| state.get_all_balances().expect("scan").len())
+    });
+    group.bench_function("paged_scan_10k", |b| {
+        b.iter(|| {
+            let mut start = None;
+            let mut seen = 0;
+            loop {
+                let (page, next) = state
+                    .get_balances_paginated(start.as_ref(), 1_000)
+                    .expect("page");
+                seen += page.len();
+                match next {
+                    Some(key) => start = Some(key),
+                    None => break seen,
+                }
+            }
+        })
+    });
+    group.bench_function("point_reads_10k", |b| {
+        b.iter(|| {
+            addresses
+                .iter()
+                .map(|address| state.get_balance(black_box(address)).expect("read"))
+                .sum::<u64>()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, storage_iteration);
+criterion_main!(benches);
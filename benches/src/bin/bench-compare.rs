@@ -0,0 +1,92 @@
+//! Fail when a benchmark regressed against a saved baseline
+//! الفشل عند تراجع أداء قياس مقارنة بخط أساسي محفوظ
+//!
+//! ```bash
+//! cargo bench -p opensyria-benches -- --save-baseline main   # on main
+//! cargo bench -p opensyria-benches -- --save-baseline pr     # on the change
+//! cargo run -p opensyria-benches --bin bench-compare -- --baseline main --candidate pr
+//! ```
+
+use clap::Parser;
+use opensyria_benches::compare::compare;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(about = "Compare two saved criterion baselines")]
+struct Args {
+    /// Baseline to compare against
+    #[arg(long)]
+    baseline: String,
+
+    /// Baseline under test (`new` is the latest run)
+    #[arg(long, default_value = "new")]
+    candidate: String,
+
+    /// Slowdown in percent that counts as a regression
+    #[arg(long, default_value_t = 10.0)]
+    threshold: f64,
+
+    /// Criterion output directory
+    #[arg(long, default_value = "target/criterion")]
+    criterion_dir: PathBuf,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let results = match compare(&args.criterion_dir, &args.baseline, &args.candidate) {
+        Ok(results) => results,
+        Err(e) => {
+            eprintln!(
+                "Failed to read criterion results in {}: {}",
+                args.criterion_dir.display(),
+                e
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    if results.is_empty() {
+        eprintln!(
+            "No saved baselines named {} or {} in {}",
+            args.baseline,
+            args.candidate,
+            args.criterion_dir.display()
+        );
+        return ExitCode::FAILURE;
+    }
+
+    let format_ns = |ns: Option<f64>| ns.map_or("-".to_string(), |ns| format!("{:.0} ns", ns));
+    println!(
+        "{:<48} {:>14} {:>14} {:>9}",
+        "benchmark", args.baseline, args.candidate, "change"
+    );
+    let mut regressions = 0;
+    for result in &results {
+        let change = result
+            .change_percent()
+            .map_or("-".to_string(), |change| format!("{:+.1}%", change));
+        let flag = if result.is_regression(args.threshold) {
+            regressions += 1;
+            "  REGRESSION"
+        } else {
+            ""
+        };
+        println!(
+            "{:<48} {:>14} {:>14} {:>9}{}",
+            result.benchmark,
+            format_ns(result.baseline_ns),
+            format_ns(result.candidate_ns),
+            change,
+            flag
+        );
+    }
+
+    if regressions > 0 {
+        eprintln!(
+            "{} benchmark(s) slower than {} by more than {}%",
+            regressions, args.baseline, args.threshold
+        );
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,141 @@
+//! Compare two saved criterion baselines
+//! مقارنة خطين أساسيين محفوظين من criterion
+//!
+//! Criterion keeps each run under
+//! `target/criterion/<benchmark id>/<baseline>/estimates.json`. This reads
+//! the mean time of every benchmark in two baselines so CI can fail when one
+//! got slower than a threshold, and print the numbers either way.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Mean time of one benchmark in two baselines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Benchmark ID, e.g. `block_validation/transactions`
+    pub benchmark: String,
+    /// Mean nanoseconds in the baseline, if it ran there
+    pub baseline_ns: Option<f64>,
+    /// Mean nanoseconds in the candidate, if it ran there
+    pub candidate_ns: Option<f64>,
+}
+
+impl Comparison {
+    /// Change from baseline to candidate in percent (positive is slower)
+    pub fn change_percent(&self) -> Option<f64> {
+        match (self.baseline_ns, self.candidate_ns) {
+            (Some(base), Some(candidate)) if base > 0.0 => Some((candidate - base) / base * 100.0),
+            _ => None,
+        }
+    }
+
+    /// Whether the candidate is slower than the baseline by more than
+    /// `threshold_percent`
+    pub fn is_regression(&self, threshold_percent: f64) -> bool {
+        self.change_percent()
+            .is_some_and(|change| change > threshold_percent)
+    }
+}
+
+/// Mean time in nanoseconds of every benchmark saved under `baseline`
+pub fn load_baseline(
+    criterion_dir: &Path,
+    baseline: &str,
+) -> std::io::Result<BTreeMap<String, f64>> {
+    let mut means = BTreeMap::new();
+    let mut pending: Vec<PathBuf> = vec![criterion_dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let estimates = path.join("estimates.json");
+            if path.file_name().is_some_and(|name| name == baseline) && estimates.is_file() {
+                if let Some(mean) = read_mean(&estimates)? {
+                    let id = dir
+                        .strip_prefix(criterion_dir)
+                        .unwrap_or(&dir)
+                        .to_string_lossy()
+                        .replace('\\', "/");
+                    means.insert(id, mean);
+                }
+            } else {
+                pending.push(path);
+            }
+        }
+    }
+    Ok(means)
+}
+
+fn read_mean(estimates: &Path) -> std::io::Result<Option<f64>> {
+    let json: Value = serde_json::from_str(&std::fs::read_to_string(estimates)?)?;
+    Ok(json["mean"]["point_estimate"].as_f64())
+}
+
+/// Compare every benchmark found in either baseline, sorted by ID
+pub fn compare(
+    criterion_dir: &Path,
+    baseline: &str,
+    candidate: &str,
+) -> std::io::Result<Vec<Comparison>> {
+    let base = load_baseline(criterion_dir, baseline)?;
+    let new = load_baseline(criterion_dir, candidate)?;
+
+    let mut ids: Vec<&String> = base.keys().chain(new.keys()).collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ids
+        .into_iter()
+        .map(|id| Comparison {
+            benchmark: id.clone(),
+            baseline_ns: base.get(id).copied(),
+            candidate_ns: new.get(id).copied(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save(root: &Path, id: &str, baseline: &str, mean: f64) {
+        let dir = root.join(id).join(baseline);
+        std::fs::create_dir_all(&dir).unwrap();
+        let json = serde_json::json!({ "mean": { "point_estimate": mean } });
+        std::fs::write(dir.join("estimates.json"), json.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_compare_baselines() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        save(root, "block_validation/transactions", "main", 1_000.0);
+        save(root, "block_validation/transactions", "pr", 1_250.0);
+        save(root, "signatures/batch/64", "main", 400.0);
+        save(root, "signatures/batch/64", "pr", 390.0);
+        save(root, "mempool_insert/empty_pool", "pr", 10.0);
+        // Criterion's HTML output is not a baseline
+        std::fs::create_dir_all(root.join("report")).unwrap();
+
+        let results = compare(root, "main", "pr").unwrap();
+        let ids: Vec<&str> = results.iter().map(|c| c.benchmark.as_str()).collect();
+        assert_eq!(
+            ids,
+            vec![
+                "block_validation/transactions",
+                "mempool_insert/empty_pool",
+                "signatures/batch/64",
+            ]
+        );
+
+        assert_eq!(results[0].change_percent(), Some(25.0));
+        assert!(results[0].is_regression(10.0));
+        assert!(!results[0].is_regression(30.0));
+        // Only in the candidate: reported, never a regression
+        assert_eq!(results[1].change_percent(), None);
+        assert!(!results[1].is_regression(0.0));
+        assert!(!results[2].is_regression(0.0));
+    }
+}
@@ -0,0 +1,64 @@
+//! Shared fixtures for the hot-path benchmarks
+//! تجهيزات مشتركة لقياسات أداء المسارات الحرجة
+//!
+//! Keys are derived from their index so every run signs the same
+//! transactions and results are comparable across machines and commits.
+
+pub mod compare;
+
+use opensyria_core::crypto::{KeyPair, PublicKey};
+use opensyria_core::Transaction;
+use opensyria_storage::StateStorage;
+use std::path::Path;
+
+/// Fee paid by fixture transactions, above the default mempool minimum
+pub const FIXTURE_FEE: u64 = 1_000;
+
+/// Amount moved by fixture transactions, above the default dust limit
+pub const FIXTURE_AMOUNT: u64 = 10_000;
+
+/// Deterministic key pair for `index`
+pub fn keypair(index: u64) -> KeyPair {
+    let mut seed = [0x5au8; 32];
+    seed[..8].copy_from_slice(&index.to_le_bytes());
+    KeyPair::from_bytes(&seed).expect("any 32 bytes are a valid signing key")
+}
+
+/// Deterministic key pairs for indices `0..count`
+pub fn keypairs(count: usize) -> Vec<KeyPair> {
+    (0..count as u64).map(keypair).collect()
+}
+
+/// Address every fixture transfer pays to
+pub fn recipient() -> PublicKey {
+    keypair(u64::MAX).public_key()
+}
+
+/// Signed transfer from `sender` with the given nonce and fee
+pub fn signed_transfer(sender: &KeyPair, nonce: u64, fee: u64) -> Transaction {
+    let tx = Transaction::new(sender.public_key(), recipient(), FIXTURE_AMOUNT, fee, nonce);
+    let signature = sender.sign(&tx.signing_hash());
+    tx.with_signature(signature)
+}
+
+/// `per_sender` signed transfers from each sender, in nonce order
+pub fn signed_transfers(senders: &[KeyPair], per_sender: u64) -> Vec<Transaction> {
+    (0..per_sender)
+        .flat_map(|nonce| {
+            senders
+                .iter()
+                .map(move |sender| signed_transfer(sender, nonce, FIXTURE_FEE))
+        })
+        .collect()
+}
+
+/// Open state storage under `dir` with every sender holding `balance`
+pub fn funded_state(dir: &Path, senders: &[KeyPair], balance: u64) -> StateStorage {
+    let state = StateStorage::open(dir.to_path_buf()).expect("open state storage");
+    for sender in senders {
+        state
+            .set_balance(&sender.public_key(), balance)
+            .expect("fund sender");
+    }
+    state
+}
@@ -1,3 +1,12 @@
+use opensyria_core::constants::{CHAIN_ID_MAINNET, CHAIN_ID_TESTNET};
+use opensyria_core::crypto::{KeyPair, PublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+
+/// Domain separator for checkpoint signatures
+const CHECKPOINT_SIGNING_DOMAIN: &[u8] = b"opensyria-checkpoint-v2";
+
 /// Checkpoint for preventing long-range attacks
 /// نقطة فحص لمنع الهجمات طويلة المدى
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -34,6 +43,16 @@ pub enum CheckpointError {
         expected: [u8; 32],
         got: [u8; 32],
     },
+    /// Signed checkpoint lacks enough valid authority signatures
+    InsufficientSignatures { got: usize, required: usize },
+    /// Checkpoint authority configuration is unusable
+    InvalidAuthority(&'static str),
+    /// No checkpoint authority is configured to verify signed checkpoints
+    NoAuthority,
+    /// A different hash is already checkpointed at this height
+    Conflict { height: u64 },
+    /// The checkpoint was signed for another chain
+    WrongChain { chain_id: u32 },
 }
 
 impl std::fmt::Display for CheckpointError {
@@ -48,6 +67,23 @@ impl std::fmt::Display for CheckpointError {
                     &got[..4]
                 )
             }
+            CheckpointError::InsufficientSignatures { got, required } => {
+                write!(
+                    f,
+                    "Checkpoint has {} valid signatures, {} required",
+                    got, required
+                )
+            }
+            CheckpointError::InvalidAuthority(reason) => {
+                write!(f, "Invalid checkpoint authority: {}", reason)
+            }
+            CheckpointError::NoAuthority => write!(f, "No checkpoint authority configured"),
+            CheckpointError::Conflict { height } => {
+                write!(f, "Conflicting checkpoint at height {}", height)
+            }
+            CheckpointError::WrongChain { chain_id } => {
+                write!(f, "Checkpoint is for chain ID {}", chain_id)
+            }
         }
     }
 }
@@ -82,10 +118,321 @@ pub fn verify_checkpoint(
     Ok(())
 }
 
+/// Signature by one checkpoint authority key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct CheckpointSignature {
+    pub signer: PublicKey,
+    pub signature: Vec<u8>,
+}
+
+/// Checkpoint signed by checkpoint authority keys and distributed over gossip
+/// نقطة فحص موقعة من مفاتيح سلطة نقاط الفحص
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct SignedCheckpoint {
+    /// Chain the checkpoint belongs to, so it cannot be replayed on another
+    pub chain_id: u32,
+    pub height: u64,
+    pub hash: [u8; 32],
+    pub signatures: Vec<CheckpointSignature>,
+}
+
+impl SignedCheckpoint {
+    /// Create an unsigned checkpoint for the chain `chain_id`
+    pub fn new(chain_id: u32, height: u64, hash: [u8; 32]) -> Self {
+        Self {
+            chain_id,
+            height,
+            hash,
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Message signed by authority keys
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(CHECKPOINT_SIGNING_DOMAIN);
+        hasher.update(self.chain_id.to_le_bytes());
+        hasher.update(self.height.to_le_bytes());
+        hasher.update(self.hash);
+        hasher.finalize().into()
+    }
+
+    /// Add a signature from an authority key, replacing any earlier one by the same key
+    /// إضافة توقيع من مفتاح سلطة
+    pub fn sign(&mut self, keypair: &KeyPair) {
+        let signer = keypair.public_key();
+        let signature = keypair.sign(&self.signing_hash());
+        self.signatures.retain(|sig| sig.signer != signer);
+        self.signatures
+            .push(CheckpointSignature { signer, signature });
+    }
+
+    /// Merge signatures collected by another copy of the same checkpoint
+    pub fn merge_signatures(&mut self, other: &SignedCheckpoint) {
+        if other.chain_id != self.chain_id || other.height != self.height || other.hash != self.hash
+        {
+            return;
+        }
+        for sig in &other.signatures {
+            if !self.signatures.iter().any(|own| own.signer == sig.signer) {
+                self.signatures.push(sig.clone());
+            }
+        }
+    }
+
+    /// The unsigned checkpoint
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            height: self.height,
+            hash: self.hash,
+        }
+    }
+}
+
+/// M-of-N set of keys allowed to issue checkpoints
+/// مجموعة مفاتيح مخولة بإصدار نقاط الفحص (M من N)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckpointAuthority {
+    keys: Vec<PublicKey>,
+    threshold: usize,
+}
+
+impl CheckpointAuthority {
+    /// Create an authority requiring `threshold` of `keys` to sign
+    pub fn new(keys: Vec<PublicKey>, threshold: usize) -> Result<Self, CheckpointError> {
+        let mut unique = HashSet::new();
+        if !keys.iter().all(|key| unique.insert(*key)) {
+            return Err(CheckpointError::InvalidAuthority("duplicate authority key"));
+        }
+        if threshold == 0 || threshold > keys.len() {
+            return Err(CheckpointError::InvalidAuthority(
+                "threshold must be between 1 and the number of keys",
+            ));
+        }
+        Ok(Self { keys, threshold })
+    }
+
+    /// Authority keys
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    /// Signatures required
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// Check that at least `threshold` distinct authority keys signed the checkpoint
+    /// التحقق من توقيع عدد كافٍ من مفاتيح السلطة
+    pub fn verify(&self, checkpoint: &SignedCheckpoint) -> Result<(), CheckpointError> {
+        let message = checkpoint.signing_hash();
+        let mut signers = HashSet::new();
+
+        for sig in &checkpoint.signatures {
+            if self.keys.contains(&sig.signer)
+                && !signers.contains(&sig.signer)
+                && sig.signer.verify(&message, &sig.signature).is_ok()
+            {
+                signers.insert(sig.signer);
+            }
+        }
+
+        if signers.len() < self.threshold {
+            return Err(CheckpointError::InsufficientSignatures {
+                got: signers.len(),
+                required: self.threshold,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Built-in checkpoints plus signed checkpoints received from the network
+/// and checkpoints published by the emergency council
+/// نقاط الفحص المدمجة بالإضافة إلى النقاط الموقعة المستلمة من الشبكة
+#[derive(Debug, Clone)]
+pub struct CheckpointRegistry {
+    chain_id: u32,
+    built_in: &'static [Checkpoint],
+    council: BTreeMap<u64, [u8; 32]>,
+    received: BTreeMap<u64, SignedCheckpoint>,
+    authority: Option<CheckpointAuthority>,
+}
+
+impl CheckpointRegistry {
+    /// Registry with the built-in checkpoints for the network
+    pub fn new(use_testnet: bool) -> Self {
+        Self {
+            chain_id: if use_testnet {
+                CHAIN_ID_TESTNET
+            } else {
+                CHAIN_ID_MAINNET
+            },
+            built_in: if use_testnet {
+                TESTNET_CHECKPOINTS
+            } else {
+                MAINNET_CHECKPOINTS
+            },
+            council: BTreeMap::new(),
+            received: BTreeMap::new(),
+            authority: None,
+        }
+    }
+
+    /// Accept signed checkpoints verified against `authority`
+    pub fn with_authority(mut self, authority: CheckpointAuthority) -> Self {
+        self.authority = Some(authority);
+        self
+    }
+
+    /// Configured checkpoint authority
+    pub fn authority(&self) -> Option<&CheckpointAuthority> {
+        self.authority.as_ref()
+    }
+
+    /// Chain ID signed checkpoints must carry
+    pub fn chain_id(&self) -> u32 {
+        self.chain_id
+    }
+
+    /// Verify and record a signed checkpoint
+    /// التحقق من نقطة فحص موقعة وتسجيلها
+    ///
+    /// Returns `Ok(false)` if the same checkpoint is already known. A checkpoint
+    /// for another chain, or conflicting with a built-in or previously
+    /// accepted one, is rejected.
+    pub fn add_signed(&mut self, checkpoint: SignedCheckpoint) -> Result<bool, CheckpointError> {
+        let authority = self
+            .authority
+            .as_ref()
+            .ok_or(CheckpointError::NoAuthority)?;
+        if checkpoint.chain_id != self.chain_id {
+            return Err(CheckpointError::WrongChain {
+                chain_id: checkpoint.chain_id,
+            });
+        }
+        authority.verify(&checkpoint)?;
+
+        if let Some(existing) = self.get(checkpoint.height) {
+            if existing.hash != checkpoint.hash {
+                return Err(CheckpointError::Conflict {
+                    height: checkpoint.height,
+                });
+            }
+            return Ok(false);
+        }
+
+        self.received.insert(checkpoint.height, checkpoint);
+        Ok(true)
+    }
+
+    /// Record a checkpoint published by an executed emergency council motion
+    /// تسجيل نقطة فحص نشرها مجلس الطوارئ
+    ///
+    /// The council's M-of-N approval is checked by governance before the
+    /// motion executes, so no signature is verified here. Returns `Ok(false)`
+    /// if the same checkpoint is already known.
+    pub fn add_council(&mut self, checkpoint: Checkpoint) -> Result<bool, CheckpointError> {
+        if let Some(existing) = self.get(checkpoint.height) {
+            if existing.hash != checkpoint.hash {
+                return Err(CheckpointError::Conflict {
+                    height: checkpoint.height,
+                });
+            }
+            return Ok(false);
+        }
+
+        self.council.insert(checkpoint.height, checkpoint.hash);
+        Ok(true)
+    }
+
+    /// Replace the council checkpoints, dropping those of revoked motions
+    ///
+    /// Checkpoints conflicting with a built-in or received one are skipped
+    /// and returned with the error.
+    pub fn set_council(
+        &mut self,
+        checkpoints: impl IntoIterator<Item = Checkpoint>,
+    ) -> Vec<(Checkpoint, CheckpointError)> {
+        self.council.clear();
+        checkpoints
+            .into_iter()
+            .filter_map(|checkpoint| {
+                let error = self.add_council(checkpoint.clone()).err()?;
+                Some((checkpoint, error))
+            })
+            .collect()
+    }
+
+    /// Checkpoint at `height`; built-in checkpoints take precedence over
+    /// council ones, which take precedence over received ones
+    pub fn get(&self, height: u64) -> Option<Checkpoint> {
+        self.built_in
+            .iter()
+            .find(|checkpoint| checkpoint.height == height)
+            .cloned()
+            .or_else(|| {
+                self.council.get(&height).map(|hash| Checkpoint {
+                    height,
+                    hash: *hash,
+                })
+            })
+            .or_else(|| self.received.get(&height).map(SignedCheckpoint::checkpoint))
+    }
+
+    /// Highest known checkpoint
+    pub fn latest(&self) -> Option<Checkpoint> {
+        let built_in = self.built_in.iter().map(|c| c.height).max();
+        let council = self.council.keys().next_back().copied();
+        let received = self.received.keys().next_back().copied();
+        let height = [built_in, council, received].into_iter().flatten().max()?;
+        self.get(height)
+    }
+
+    /// Signed checkpoints received from the network, by height
+    pub fn received(&self) -> impl Iterator<Item = &SignedCheckpoint> {
+        self.received.values()
+    }
+
+    /// Every known checkpoint, by height, built-in checkpoints taking precedence
+    pub fn all(&self) -> Vec<Checkpoint> {
+        let mut heights: Vec<u64> = self
+            .built_in
+            .iter()
+            .map(|checkpoint| checkpoint.height)
+            .chain(self.council.keys().copied())
+            .chain(self.received.keys().copied())
+            .collect();
+        heights.sort_unstable();
+        heights.dedup();
+        heights
+            .into_iter()
+            .filter_map(|height| self.get(height))
+            .collect()
+    }
+
+    /// Verify block hash against built-in and received checkpoints
+    /// التحقق من تجزئة الكتلة مقابل جميع نقاط الفحص المعروفة
+    pub fn verify(&self, height: u64, hash: &[u8; 32]) -> Result<(), CheckpointError> {
+        match self.get(height) {
+            Some(checkpoint) if checkpoint.hash != *hash => Err(CheckpointError::Mismatch {
+                height,
+                expected: checkpoint.hash,
+                got: *hash,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn authority_keys(n: usize) -> Vec<KeyPair> {
+        (0..n).map(|_| KeyPair::generate()).collect()
+    }
+
     #[test]
     fn test_genesis_checkpoint_verification() {
         let genesis_hash = [0u8; 32];
@@ -119,4 +466,133 @@ mod tests {
         assert!(verify_checkpoint(5, &any_hash, false).is_ok());
         assert!(verify_checkpoint(100, &any_hash, false).is_ok());
     }
+
+    #[test]
+    fn test_signed_checkpoint_requires_threshold() {
+        let keys = authority_keys(3);
+        let authority =
+            CheckpointAuthority::new(keys.iter().map(|k| k.public_key()).collect(), 2).unwrap();
+
+        let mut checkpoint = SignedCheckpoint::new(CHAIN_ID_MAINNET, 10_000, [7u8; 32]);
+        checkpoint.sign(&keys[0]);
+        // Repeated signatures by the same key count once
+        checkpoint.sign(&keys[0]);
+        assert_eq!(
+            authority.verify(&checkpoint),
+            Err(CheckpointError::InsufficientSignatures {
+                got: 1,
+                required: 2
+            })
+        );
+
+        // Signatures from keys outside the authority are ignored
+        checkpoint.sign(&KeyPair::generate());
+        assert!(authority.verify(&checkpoint).is_err());
+
+        checkpoint.sign(&keys[2]);
+        assert!(authority.verify(&checkpoint).is_ok());
+
+        // Signatures do not carry over to a different hash or chain
+        let mut forged = checkpoint.clone();
+        forged.hash = [8u8; 32];
+        assert!(authority.verify(&forged).is_err());
+        let mut replayed = checkpoint.clone();
+        replayed.chain_id = CHAIN_ID_TESTNET;
+        assert!(authority.verify(&replayed).is_err());
+    }
+
+    #[test]
+    fn test_registry_accepts_and_enforces_received_checkpoints() {
+        let keys = authority_keys(2);
+        let authority =
+            CheckpointAuthority::new(keys.iter().map(|k| k.public_key()).collect(), 2).unwrap();
+        let mut registry = CheckpointRegistry::new(false);
+
+        let mut checkpoint = SignedCheckpoint::new(CHAIN_ID_MAINNET, 500, [3u8; 32]);
+        checkpoint.sign(&keys[0]);
+        checkpoint.sign(&keys[1]);
+        assert_eq!(
+            registry.add_signed(checkpoint.clone()),
+            Err(CheckpointError::NoAuthority)
+        );
+
+        let mut registry = registry.with_authority(authority);
+        assert_eq!(registry.add_signed(checkpoint.clone()), Ok(true));
+        assert_eq!(registry.add_signed(checkpoint), Ok(false));
+        assert_eq!(registry.latest().unwrap().height, 500);
+        let heights: Vec<u64> = registry.all().iter().map(|c| c.height).collect();
+        assert_eq!(heights, vec![0, 500]);
+
+        assert!(registry.verify(500, &[3u8; 32]).is_ok());
+        assert!(matches!(
+            registry.verify(500, &[4u8; 32]),
+            Err(CheckpointError::Mismatch { height: 500, .. })
+        ));
+        // Built-in checkpoints still apply
+        assert!(registry.verify(0, &[1u8; 32]).is_err());
+
+        let mut conflicting = SignedCheckpoint::new(CHAIN_ID_MAINNET, 500, [4u8; 32]);
+        conflicting.sign(&keys[0]);
+        conflicting.sign(&keys[1]);
+        assert_eq!(
+            registry.add_signed(conflicting),
+            Err(CheckpointError::Conflict { height: 500 })
+        );
+
+        // A checkpoint signed for testnet is refused on mainnet
+        let mut testnet = SignedCheckpoint::new(CHAIN_ID_TESTNET, 600, [5u8; 32]);
+        testnet.sign(&keys[0]);
+        testnet.sign(&keys[1]);
+        assert_eq!(
+            registry.add_signed(testnet),
+            Err(CheckpointError::WrongChain {
+                chain_id: CHAIN_ID_TESTNET
+            })
+        );
+    }
+
+    #[test]
+    fn test_registry_enforces_council_checkpoints() {
+        let keys = authority_keys(1);
+        let authority = CheckpointAuthority::new(vec![keys[0].public_key()], 1).unwrap();
+        let mut registry = CheckpointRegistry::new(false).with_authority(authority);
+
+        let council = Checkpoint {
+            height: 700,
+            hash: [6u8; 32],
+        };
+        assert_eq!(registry.add_council(council.clone()), Ok(true));
+        assert_eq!(registry.add_council(council.clone()), Ok(false));
+        assert_eq!(registry.latest(), Some(council));
+        assert!(registry.verify(700, &[6u8; 32]).is_ok());
+        assert!(registry.verify(700, &[1u8; 32]).is_err());
+
+        // A signed checkpoint cannot override the council's
+        let mut signed = SignedCheckpoint::new(CHAIN_ID_MAINNET, 700, [1u8; 32]);
+        signed.sign(&keys[0]);
+        assert_eq!(
+            registry.add_signed(signed),
+            Err(CheckpointError::Conflict { height: 700 })
+        );
+        assert_eq!(
+            registry.add_council(Checkpoint {
+                height: 700,
+                hash: [1u8; 32],
+            }),
+            Err(CheckpointError::Conflict { height: 700 })
+        );
+
+        // Revoking the motion drops its checkpoint
+        assert!(registry.set_council(Vec::new()).is_empty());
+        assert!(registry.verify(700, &[1u8; 32]).is_ok());
+        assert_eq!(registry.latest(), registry.get(0));
+    }
+
+    #[test]
+    fn test_authority_rejects_bad_threshold() {
+        let keys: Vec<_> = authority_keys(2).iter().map(|k| k.public_key()).collect();
+        assert!(CheckpointAuthority::new(keys.clone(), 0).is_err());
+        assert!(CheckpointAuthority::new(keys.clone(), 3).is_err());
+        assert!(CheckpointAuthority::new(vec![keys[0], keys[0]], 1).is_err());
+    }
 }
@@ -2,7 +2,12 @@
 
 pub mod pow;
 pub mod checkpoints;
+pub mod template;
 
 pub use pow::{DifficultyAdjuster, MiningStats, ProofOfWork};
-pub use checkpoints::{verify_checkpoint, Checkpoint, CheckpointError, MAINNET_CHECKPOINTS, TESTNET_CHECKPOINTS};
+pub use checkpoints::{
+    verify_checkpoint, Checkpoint, CheckpointAuthority, CheckpointError, CheckpointRegistry,
+    CheckpointSignature, SignedCheckpoint, MAINNET_CHECKPOINTS, TESTNET_CHECKPOINTS,
+};
+pub use template::{RefreshReason, TemplateInfo, TemplatePolicy};
 
@@ -1,4 +1,6 @@
-use opensyria_core::{Block, DIFFICULTY_ADJUSTMENT_INTERVAL, MAX_DIFFICULTY, MAX_DIFFICULTY_ADJUSTMENT, MIN_DIFFICULTY, TARGET_BLOCK_TIME_SECS};
+use opensyria_core::{
+    Block, ChainParams, MAX_DIFFICULTY, MAX_DIFFICULTY_ADJUSTMENT, MIN_DIFFICULTY,
+};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
@@ -147,6 +149,58 @@ impl ProofOfWork {
         (block, stats)
     }
 
+    /// Mine until a valid nonce is found or `abandon` asks to stop
+    /// التعدين حتى العثور على رقم صالح أو طلب التوقف
+    ///
+    /// `abandon` is called roughly every `check_interval`, e.g. to see whether
+    /// a better block template exists. Returns `None` if the work was abandoned
+    /// or the nonce space was exhausted.
+    pub fn mine_until<F>(
+        &self,
+        mut block: Block,
+        check_interval: Duration,
+        mut abandon: F,
+    ) -> Option<(Block, MiningStats)>
+    where
+        F: FnMut() -> bool,
+    {
+        block.header.difficulty = self.difficulty;
+        let start = Instant::now();
+        let mut next_check = start + check_interval;
+        let mut hashes = 0u64;
+
+        for nonce in 0..u64::MAX {
+            block.header.nonce = nonce;
+            hashes += 1;
+
+            if block.header.meets_difficulty() {
+                let duration = start.elapsed();
+                let stats = MiningStats {
+                    hashes_computed: hashes,
+                    duration,
+                    hash_rate: hashes as f64 / duration.as_secs_f64(),
+                    nonce_found: nonce,
+                };
+                return Some((block, stats));
+            }
+
+            // Reading the clock every hash would slow mining down
+            if hashes.is_multiple_of(10_000) && Instant::now() >= next_check {
+                if abandon() {
+                    tracing::debug!("Abandoned mining after {} hashes", hashes);
+                    return None;
+                }
+                next_check = Instant::now() + check_interval;
+            }
+        }
+
+        tracing::warn!(
+            "Exhausted nonce space (2^64 attempts) in mine_until at difficulty {}",
+            self.difficulty
+        );
+        None
+    }
+
     /// Mine using multiple threads for improved performance (8-16x speedup)
     /// 
     /// PERF-MED-001 FIX: Parallel mining implementation
@@ -290,6 +344,8 @@ impl ProofOfWork {
 pub struct DifficultyAdjuster {
     target_block_time: Duration,
     adjustment_interval: u32, // blocks between adjustments
+    min_difficulty: u32,
+    max_difficulty: u32,
 }
 
 impl DifficultyAdjuster {
@@ -297,12 +353,25 @@ impl DifficultyAdjuster {
         Self {
             target_block_time: Duration::from_secs(target_block_time_secs),
             adjustment_interval,
+            min_difficulty: MIN_DIFFICULTY,
+            max_difficulty: MAX_DIFFICULTY,
+        }
+    }
+
+    /// Create adjuster from a network's chain parameters
+    /// إنشاء أداة تعديل الصعوبة من معاملات السلسلة
+    pub fn from_params(params: &ChainParams) -> Self {
+        Self {
+            target_block_time: Duration::from_secs(params.target_block_time_secs),
+            adjustment_interval: params.difficulty_adjustment_interval,
+            min_difficulty: params.min_difficulty,
+            max_difficulty: params.max_difficulty,
         }
     }
 
     /// Create adjuster with default protocol parameters
     pub fn default() -> Self {
-        Self::new(TARGET_BLOCK_TIME_SECS, DIFFICULTY_ADJUSTMENT_INTERVAL)
+        Self::from_params(&ChainParams::mainnet())
     }
 
     /// Calculate new difficulty based on actual mining times
@@ -337,7 +406,7 @@ impl DifficultyAdjuster {
                 "Difficulty calculation overflow: {} > u32::MAX, clamping to MAX_DIFFICULTY",
                 new_difficulty_u128
             );
-            MAX_DIFFICULTY
+            self.max_difficulty
         } else {
             new_difficulty_u128 as u32
         };
@@ -350,8 +419,8 @@ impl DifficultyAdjuster {
         let decrease_amount = (current_difficulty as u64 * adjustment_factor_num as u64) / adjustment_factor_den as u64;
         let increase_amount = (current_difficulty as u64 * adjustment_factor_num as u64) / adjustment_factor_den as u64;
         
-        let min_diff = (current_difficulty as u64).saturating_sub(decrease_amount).max(MIN_DIFFICULTY as u64) as u32;
-        let max_diff = (current_difficulty as u64).saturating_add(increase_amount).min(MAX_DIFFICULTY as u64) as u32;
+        let min_diff = (current_difficulty as u64).saturating_sub(decrease_amount).max(self.min_difficulty as u64) as u32;
+        let max_diff = (current_difficulty as u64).saturating_add(increase_amount).min(self.max_difficulty as u64) as u32;
 
         // Ensure min <= max before clamping (can happen with extreme values)
         let (min_diff, max_diff) = if min_diff > max_diff {
@@ -408,6 +477,25 @@ mod tests {
         assert!(mined.verify_merkle_root());
     }
 
+    #[test]
+    fn test_mine_until_abandons_stale_work() {
+        let mut block = Block::genesis();
+        block.header.difficulty = 8;
+        let (mined, _) = ProofOfWork::new(8)
+            .mine_until(block.clone(), Duration::from_secs(60), || false)
+            .unwrap();
+        assert!(ProofOfWork::new(8).validate(&mined));
+
+        // Practically unminable; the first check abandons it
+        let mut checks = 0;
+        let result = ProofOfWork::new(64).mine_until(block, Duration::ZERO, || {
+            checks += 1;
+            true
+        });
+        assert!(result.is_none());
+        assert_eq!(checks, 1);
+    }
+
     #[test]
     fn test_validation_rejects_insufficient_difficulty() {
         let pow = ProofOfWork::new(16);
@@ -0,0 +1,180 @@
+//! Block template refresh policy for miners
+//! سياسة تحديث قوالب الكتل للمعدنين
+//!
+//! A miner works on one candidate block (template) at a time. The template
+//! goes stale when a new tip arrives, when better-paying transactions reach
+//! the mempool, or when it has been mined for too long; the miner should then
+//! abandon it and build a new one.
+
+use opensyria_core::Transaction;
+use std::time::{Duration, Instant};
+
+/// Fee increase (percent of the template's fees) that justifies a rebuild
+pub const DEFAULT_MIN_FEE_INCREASE_PERCENT: u64 = 10;
+
+/// How often a miner compares its template against the mempool and tip
+pub const DEFAULT_TEMPLATE_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Longest a template is mined before its timestamp is refreshed
+pub const DEFAULT_TEMPLATE_MAX_AGE: Duration = Duration::from_secs(60);
+
+/// Why a block template was abandoned
+/// سبب التخلي عن قالب الكتلة
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshReason {
+    /// The chain tip moved; work on the old parent is wasted
+    NewTip,
+    /// Mempool transactions pay materially more than the template
+    FeeIncrease,
+    /// The template reached its maximum age
+    Expired,
+}
+
+impl RefreshReason {
+    /// Label used in logs and metrics
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RefreshReason::NewTip => "new_tip",
+            RefreshReason::FeeIncrease => "fee_increase",
+            RefreshReason::Expired => "expired",
+        }
+    }
+}
+
+impl std::fmt::Display for RefreshReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a candidate block was built from
+/// ما بنيت منه الكتلة المرشحة
+#[derive(Debug, Clone)]
+pub struct TemplateInfo {
+    /// Parent block hash
+    pub tip: [u8; 32],
+    /// Sum of the fees of the template's transactions
+    pub total_fees: u64,
+    /// When the template was built
+    pub created: Instant,
+}
+
+impl TemplateInfo {
+    /// Describe a template built on `tip` from `transactions`
+    pub fn new(tip: [u8; 32], transactions: &[Transaction]) -> Self {
+        Self {
+            tip,
+            total_fees: total_fees(transactions),
+            created: Instant::now(),
+        }
+    }
+}
+
+/// Total fees paid by a set of transactions
+pub fn total_fees(transactions: &[Transaction]) -> u64 {
+    transactions
+        .iter()
+        .fold(0u64, |total, tx| total.saturating_add(tx.fee))
+}
+
+/// Decides when a block template should be rebuilt
+/// يقرر متى يجب إعادة بناء قالب الكتلة
+#[derive(Debug, Clone)]
+pub struct TemplatePolicy {
+    /// Minimum fee increase, in percent of the template's fees (0 = any increase)
+    pub min_fee_increase_percent: u64,
+    /// How often the miner checks its template
+    pub check_interval: Duration,
+    /// Longest a template is mined before it is rebuilt
+    pub max_age: Duration,
+}
+
+impl Default for TemplatePolicy {
+    fn default() -> Self {
+        Self {
+            min_fee_increase_percent: DEFAULT_MIN_FEE_INCREASE_PERCENT,
+            check_interval: DEFAULT_TEMPLATE_CHECK_INTERVAL,
+            max_age: DEFAULT_TEMPLATE_MAX_AGE,
+        }
+    }
+}
+
+impl TemplatePolicy {
+    /// Check a template against the current tip and the transactions a new
+    /// template would include; returns why it should be rebuilt, if at all
+    pub fn check(
+        &self,
+        template: &TemplateInfo,
+        tip: [u8; 32],
+        candidates: &[Transaction],
+    ) -> Option<RefreshReason> {
+        if tip != template.tip {
+            return Some(RefreshReason::NewTip);
+        }
+
+        let fees = total_fees(candidates) as u128;
+        let current = template.total_fees as u128;
+        let required = current * (100 + self.min_fee_increase_percent as u128) / 100;
+        if fees > current && fees >= required {
+            return Some(RefreshReason::FeeIncrease);
+        }
+
+        if template.created.elapsed() >= self.max_age {
+            return Some(RefreshReason::Expired);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    fn with_fee(fee: u64) -> Transaction {
+        let sender = KeyPair::generate();
+        Transaction::new(sender.public_key(), sender.public_key(), 1_000, fee, 0)
+    }
+
+    #[test]
+    fn test_template_refresh_reasons() {
+        let policy = TemplatePolicy::default();
+        let mut txs = vec![with_fee(1_000), with_fee(1_000)];
+        let template = TemplateInfo::new([1u8; 32], &txs);
+        assert_eq!(template.total_fees, 2_000);
+
+        // Unchanged mempool and tip
+        assert_eq!(policy.check(&template, [1u8; 32], &txs), None);
+        assert_eq!(
+            policy.check(&template, [2u8; 32], &txs),
+            Some(RefreshReason::NewTip)
+        );
+
+        // 5% more fees is not material, 10% is
+        txs.push(with_fee(100));
+        assert_eq!(policy.check(&template, [1u8; 32], &txs), None);
+        txs.push(with_fee(100));
+        assert_eq!(
+            policy.check(&template, [1u8; 32], &txs),
+            Some(RefreshReason::FeeIncrease)
+        );
+
+        // Any fees justify replacing an empty template
+        let empty = TemplateInfo::new([1u8; 32], &[]);
+        assert_eq!(
+            policy.check(&empty, [1u8; 32], &txs[..1]),
+            Some(RefreshReason::FeeIncrease)
+        );
+        assert_eq!(policy.check(&empty, [1u8; 32], &[]), None);
+
+        let expired = TemplatePolicy {
+            max_age: Duration::ZERO,
+            ..Default::default()
+        };
+        assert_eq!(
+            expired.check(&empty, [1u8; 32], &[]),
+            Some(RefreshReason::Expired)
+        );
+    }
+}
@@ -0,0 +1,34 @@
+//! Contract execution errors
+//! أخطاء تنفيذ العقود
+
+use thiserror::Error;
+
+/// Why a contract transaction failed; the fee is paid but nothing else
+/// changes
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionFailure {
+    #[error("Invalid contract module: {0}")]
+    InvalidModule(String),
+
+    #[error("No contract at the called address")]
+    NoContract,
+
+    #[error("Contract exports no method `{0}` taking and returning nothing")]
+    MissingMethod(String),
+
+    #[error("Out of gas")]
+    OutOfGas,
+
+    #[error("Contract trapped: {0}")]
+    Trap(String),
+}
+
+/// Node storage failed while a contract ran
+///
+/// Unlike [`ExecutionFailure`] this says nothing about the transaction, so
+/// the block being applied must be abandoned rather than the call failed.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Contract host failure: {0}")]
+pub struct HostFailure(pub String);
+
+impl wasmi::core::HostError for HostFailure {}
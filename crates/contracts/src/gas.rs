@@ -0,0 +1,35 @@
+//! Gas schedule and sandbox limits
+//! جدول الغاز وحدود بيئة التنفيذ
+//!
+//! Wasm instructions cost the interpreter's fuel, roughly one gas per
+//! instruction. Host functions add the costs below on top, so storage and
+//! transfers are priced by the work they cause for every node, and every
+//! execution first pays for compiling the module it runs.
+
+/// Every host function call
+pub const HOST_CALL_GAS: u64 = 100;
+/// Each byte copied between the contract's memory and the host
+pub const HOST_BYTE_GAS: u64 = 1;
+/// Reading one storage key
+pub const STORAGE_READ_GAS: u64 = 500;
+/// Writing or removing one storage key
+pub const STORAGE_WRITE_GAS: u64 = 2_000;
+/// Each byte of key and value written to storage
+pub const STORAGE_BYTE_GAS: u64 = 20;
+/// Looking up an account balance
+pub const BALANCE_GAS: u64 = 300;
+/// Paying from the contract's balance
+pub const TRANSFER_GAS: u64 = 1_000;
+/// Each byte of code stored by a deployment
+pub const DEPLOY_BYTE_GAS: u64 = 50;
+/// Each byte of code compiled before a deployment or call runs
+pub const COMPILE_BYTE_GAS: u64 = 10;
+
+/// Largest linear memory a contract may use, in 64 KiB pages
+pub const MAX_MEMORY_PAGES: usize = 16;
+/// Longest storage key
+pub const MAX_STORAGE_KEY_LEN: usize = 256;
+/// Longest storage value
+pub const MAX_STORAGE_VALUE_LEN: usize = 16 * 1024;
+/// Longest call output
+pub const MAX_OUTPUT_LEN: usize = 16 * 1024;
@@ -0,0 +1,36 @@
+//! State a contract can read
+//! الحالة التي يقرؤها العقد
+
+use crate::error::HostFailure;
+use opensyria_core::crypto::PublicKey;
+
+/// Read access to chain state during one contract execution
+///
+/// The VM buffers every write and transfer itself and hands them back in
+/// [`crate::Effects`], so a host only answers reads. Reads must reflect every
+/// earlier transaction in the block.
+pub trait ContractHost {
+    /// Value stored under `key` by `contract`
+    fn storage_read(
+        &self,
+        contract: &PublicKey,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, HostFailure>;
+
+    /// Balance of `address`, including the value sent with this call
+    fn balance(&self, address: &PublicKey) -> Result<u64, HostFailure>;
+}
+
+/// Facts about the call the contract can ask for
+/// معلومات الاستدعاء المتاحة للعقد
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallContext {
+    /// Contract being run
+    pub contract: PublicKey,
+    /// Sender of the transaction
+    pub caller: PublicKey,
+    /// Amount sent with the transaction
+    pub value: u64,
+    /// Height of the block being applied
+    pub height: u64,
+}
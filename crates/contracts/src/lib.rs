@@ -0,0 +1,37 @@
+//! Wasm smart contract execution
+//! تنفيذ العقود الذكية بلغة Wasm
+//!
+//! A minimal, deterministic contract VM. Contracts are Wasm modules without
+//! floating point that export a `memory` and methods taking and returning
+//! nothing; deploying runs the optional `init` export. Transactions carry
+//! deployments and calls as [`opensyria_core::ContractCall`] payloads and
+//! the state storage runs them while applying blocks.
+//!
+//! Host functions, imported from module `env` (pointers are offsets into
+//! the contract's memory, addresses are 32 bytes):
+//!
+//! | Function | Signature | Purpose |
+//! |---|---|---|
+//! | `input_len` | `() -> i32` | Length of the call arguments |
+//! | `input_read` | `(ptr)` | Copy the call arguments to `ptr` |
+//! | `output_write` | `(ptr, len)` | Set the call's return data |
+//! | `storage_read` | `(key, key_len, value, cap) -> i32` | Copy up to `cap` bytes of a value; returns its full length, or -1 if absent |
+//! | `storage_write` | `(key, key_len, value, value_len)` | Store a value |
+//! | `storage_remove` | `(key, key_len)` | Delete a value |
+//! | `caller` | `(ptr)` | Sender of the transaction |
+//! | `self_address` | `(ptr)` | The contract's own address |
+//! | `value` | `() -> i64` | Amount sent with the call |
+//! | `height` | `() -> i64` | Height of the block being applied |
+//! | `balance` | `(address) -> i64` | Balance of any account |
+//! | `transfer` | `(address, amount: i64) -> i32` | Pay from the contract's balance; 0 on success, 1 if it is too low |
+//!
+//! Gas is the interpreter's fuel plus the host costs in [`gas`].
+
+pub mod error;
+pub mod gas;
+pub mod host;
+pub mod vm;
+
+pub use error::{ExecutionFailure, HostFailure};
+pub use host::{CallContext, ContractHost};
+pub use vm::{ContractVm, Effects, Execution};
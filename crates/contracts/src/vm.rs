@@ -0,0 +1,658 @@
+//! Sandboxed Wasm interpreter with gas metering
+//! مفسّر Wasm معزول مع قياس الغاز
+//!
+//! Each execution gets a fresh instance with no imports beyond the host
+//! functions in the crate docs, no floating point, bounded memory and fuel
+//! equal to the gas limit less the cost of compiling the module. Nothing
+//! outside the VM changes while a contract
+//! runs: writes and transfers are journaled and returned as [`Effects`]
+//! only when the call succeeds.
+
+use crate::error::{ExecutionFailure, HostFailure};
+use crate::gas::*;
+use crate::host::{CallContext, ContractHost};
+use opensyria_core::crypto::PublicKey;
+use std::collections::BTreeMap;
+use wasmi::core::TrapCode;
+use wasmi::{
+    Caller, CompilationMode, Config, Engine, Error, Extern, Linker, Memory, Module, Store,
+    StoreLimits, StoreLimitsBuilder,
+};
+
+/// Bytes in a Wasm memory page
+const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+/// Changes made by a successful execution, for the caller to apply
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Effects {
+    /// Storage writes in key order; `None` removes the key
+    pub writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// Payments from the contract's balance, in order
+    pub transfers: Vec<(PublicKey, u64)>,
+    /// Bytes passed to `output_write`
+    pub output: Vec<u8>,
+}
+
+/// Result of running a contract
+/// نتيجة تنفيذ العقد
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    /// Gas consumed, the whole limit when it ran out
+    pub gas_used: u64,
+    pub outcome: Result<Effects, ExecutionFailure>,
+}
+
+/// Per-execution state reachable from host functions
+struct Session<'a> {
+    host: &'a dyn ContractHost,
+    context: CallContext,
+    input: Vec<u8>,
+    effects: Effects,
+    limits: StoreLimits,
+}
+
+impl Session<'_> {
+    fn storage_read(&self, key: &[u8]) -> Result<Option<Vec<u8>>, HostFailure> {
+        match self.effects.writes.get(key) {
+            Some(value) => Ok(value.clone()),
+            None => self.host.storage_read(&self.context.contract, key),
+        }
+    }
+
+    /// Balance of `address` after the transfers journaled so far
+    fn balance(&self, address: &PublicKey) -> Result<u64, HostFailure> {
+        let mut balance = self.host.balance(address)?;
+        for (to, amount) in &self.effects.transfers {
+            if *address == self.context.contract {
+                balance -= amount;
+            }
+            if to == address {
+                balance += amount;
+            }
+        }
+        Ok(balance)
+    }
+}
+
+/// Why a run stopped short
+enum Failure {
+    Execution(ExecutionFailure),
+    Host(HostFailure),
+}
+
+impl From<ExecutionFailure> for Failure {
+    fn from(failure: ExecutionFailure) -> Self {
+        Failure::Execution(failure)
+    }
+}
+
+/// Wasm contract virtual machine
+/// الآلة الافتراضية لعقود Wasm
+pub struct ContractVm {
+    engine: Engine,
+}
+
+impl Default for ContractVm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContractVm {
+    pub fn new() -> Self {
+        let mut config = Config::default();
+        config
+            .consume_fuel(true)
+            .floats(false)
+            .compilation_mode(CompilationMode::Eager);
+        Self {
+            engine: Engine::new(&config),
+        }
+    }
+
+    /// Check that `code` is a module the VM accepts
+    pub fn validate(&self, code: &[u8]) -> Result<(), ExecutionFailure> {
+        Module::new(&self.engine, code)
+            .map(|_| ())
+            .map_err(|e| ExecutionFailure::InvalidModule(e.to_string()))
+    }
+
+    /// Run a deployment: charge for storing `code`, then run its `init`
+    /// export if it has one
+    pub fn deploy(
+        &self,
+        code: &[u8],
+        context: CallContext,
+        host: &dyn ContractHost,
+        gas_limit: u64,
+    ) -> Result<Execution, HostFailure> {
+        let storage_gas = (code.len() as u64).saturating_mul(DEPLOY_BYTE_GAS);
+        if storage_gas > gas_limit {
+            return Ok(Execution {
+                gas_used: gas_limit,
+                outcome: Err(ExecutionFailure::OutOfGas),
+            });
+        }
+        let mut execution = self.run(
+            code,
+            "init",
+            false,
+            Vec::new(),
+            context,
+            host,
+            gas_limit - storage_gas,
+        )?;
+        execution.gas_used += storage_gas;
+        Ok(execution)
+    }
+
+    /// Run the exported `method` of `code` with `input`
+    pub fn call(
+        &self,
+        code: &[u8],
+        method: &str,
+        input: &[u8],
+        context: CallContext,
+        host: &dyn ContractHost,
+        gas_limit: u64,
+    ) -> Result<Execution, HostFailure> {
+        self.run(code, method, true, input.to_vec(), context, host, gas_limit)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn run(
+        &self,
+        code: &[u8],
+        method: &str,
+        required: bool,
+        input: Vec<u8>,
+        context: CallContext,
+        host: &dyn ContractHost,
+        gas_limit: u64,
+    ) -> Result<Execution, HostFailure> {
+        // The module is compiled again for every execution
+        let compile_gas = (code.len() as u64).saturating_mul(COMPILE_BYTE_GAS);
+        let Some(fuel) = gas_limit.checked_sub(compile_gas) else {
+            return Ok(Execution {
+                gas_used: gas_limit,
+                outcome: Err(ExecutionFailure::OutOfGas),
+            });
+        };
+
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_PAGES * WASM_PAGE_SIZE)
+            .instances(1)
+            .memories(1)
+            .tables(1)
+            .build();
+        let mut store = Store::new(
+            &self.engine,
+            Session {
+                host,
+                context,
+                input,
+                effects: Effects::default(),
+                limits,
+            },
+        );
+        store.limiter(|session| &mut session.limits);
+        store.set_fuel(fuel).expect("fuel metering is enabled");
+
+        let result = self.execute(&mut store, code, method, required);
+        let gas_used = gas_limit - store.get_fuel().expect("fuel metering is enabled");
+        let outcome = match result {
+            Ok(()) => Ok(store.into_data().effects),
+            Err(Failure::Host(failure)) => return Err(failure),
+            Err(Failure::Execution(ExecutionFailure::OutOfGas)) => {
+                return Ok(Execution {
+                    gas_used: gas_limit,
+                    outcome: Err(ExecutionFailure::OutOfGas),
+                })
+            }
+            Err(Failure::Execution(failure)) => Err(failure),
+        };
+        Ok(Execution { gas_used, outcome })
+    }
+
+    fn execute(
+        &self,
+        store: &mut Store<Session<'_>>,
+        code: &[u8],
+        method: &str,
+        required: bool,
+    ) -> Result<(), Failure> {
+        let module = Module::new(&self.engine, code)
+            .map_err(|e| ExecutionFailure::InvalidModule(e.to_string()))?;
+        let instance = self
+            .linker()
+            .instantiate(&mut *store, &module)
+            .map_err(|e| ExecutionFailure::InvalidModule(e.to_string()))?
+            .start(&mut *store)
+            .map_err(classify)?;
+
+        let func = match instance.get_typed_func::<(), ()>(&*store, method) {
+            Ok(func) => func,
+            Err(_) if !required && instance.get_export(&*store, method).is_none() => return Ok(()),
+            Err(_) => return Err(ExecutionFailure::MissingMethod(method.to_string()).into()),
+        };
+        func.call(&mut *store, ()).map_err(classify)
+    }
+
+    /// Host functions, all in the `env` module
+    fn linker<'a>(&self) -> Linker<Session<'a>> {
+        let mut linker = Linker::new(&self.engine);
+        let wrapped = "host function names are unique";
+
+        linker
+            .func_wrap(
+                "env",
+                "input_len",
+                |mut caller: Caller<'_, Session<'a>>| -> Result<i32, Error> {
+                    charge(&mut caller, HOST_CALL_GAS)?;
+                    Ok(caller.data().input.len() as i32)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "input_read",
+                |mut caller: Caller<'_, Session<'a>>, ptr: i32| -> Result<(), Error> {
+                    let input = caller.data().input.clone();
+                    charge(&mut caller, HOST_CALL_GAS + byte_gas(input.len()))?;
+                    write_memory(&mut caller, ptr, &input)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "output_write",
+                |mut caller: Caller<'_, Session<'a>>, ptr: i32, len: i32| -> Result<(), Error> {
+                    let len = checked_len(len, MAX_OUTPUT_LEN)?;
+                    charge(&mut caller, HOST_CALL_GAS + byte_gas(len))?;
+                    let output = read_memory(&caller, ptr, len)?;
+                    caller.data_mut().effects.output = output;
+                    Ok(())
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "storage_read",
+                |mut caller: Caller<'_, Session<'a>>,
+                 key_ptr: i32,
+                 key_len: i32,
+                 value_ptr: i32,
+                 value_cap: i32|
+                 -> Result<i32, Error> {
+                    let key_len = checked_len(key_len, MAX_STORAGE_KEY_LEN)?;
+                    charge(&mut caller, STORAGE_READ_GAS + byte_gas(key_len))?;
+                    let key = read_memory(&caller, key_ptr, key_len)?;
+                    let Some(value) = caller.data().storage_read(&key).map_err(Error::host)? else {
+                        return Ok(-1);
+                    };
+                    let copied = value.len().min(checked_len(value_cap, usize::MAX)?);
+                    charge(&mut caller, byte_gas(copied))?;
+                    write_memory(&mut caller, value_ptr, &value[..copied])?;
+                    Ok(value.len() as i32)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "storage_write",
+                |mut caller: Caller<'_, Session<'a>>,
+                 key_ptr: i32,
+                 key_len: i32,
+                 value_ptr: i32,
+                 value_len: i32|
+                 -> Result<(), Error> {
+                    let key_len = checked_len(key_len, MAX_STORAGE_KEY_LEN)?;
+                    let value_len = checked_len(value_len, MAX_STORAGE_VALUE_LEN)?;
+                    charge(
+                        &mut caller,
+                        STORAGE_WRITE_GAS + (key_len + value_len) as u64 * STORAGE_BYTE_GAS,
+                    )?;
+                    let key = read_memory(&caller, key_ptr, key_len)?;
+                    let value = read_memory(&caller, value_ptr, value_len)?;
+                    caller.data_mut().effects.writes.insert(key, Some(value));
+                    Ok(())
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "storage_remove",
+                |mut caller: Caller<'_, Session<'a>>,
+                 key_ptr: i32,
+                 key_len: i32|
+                 -> Result<(), Error> {
+                    let key_len = checked_len(key_len, MAX_STORAGE_KEY_LEN)?;
+                    charge(
+                        &mut caller,
+                        STORAGE_WRITE_GAS + key_len as u64 * STORAGE_BYTE_GAS,
+                    )?;
+                    let key = read_memory(&caller, key_ptr, key_len)?;
+                    caller.data_mut().effects.writes.insert(key, None);
+                    Ok(())
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "caller",
+                |mut caller: Caller<'_, Session<'a>>, ptr: i32| -> Result<(), Error> {
+                    charge(&mut caller, HOST_CALL_GAS + byte_gas(32))?;
+                    let address = caller.data().context.caller;
+                    write_memory(&mut caller, ptr, &address.0)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "self_address",
+                |mut caller: Caller<'_, Session<'a>>, ptr: i32| -> Result<(), Error> {
+                    charge(&mut caller, HOST_CALL_GAS + byte_gas(32))?;
+                    let address = caller.data().context.contract;
+                    write_memory(&mut caller, ptr, &address.0)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "value",
+                |mut caller: Caller<'_, Session<'a>>| -> Result<i64, Error> {
+                    charge(&mut caller, HOST_CALL_GAS)?;
+                    Ok(caller.data().context.value as i64)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "height",
+                |mut caller: Caller<'_, Session<'a>>| -> Result<i64, Error> {
+                    charge(&mut caller, HOST_CALL_GAS)?;
+                    Ok(caller.data().context.height as i64)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "balance",
+                |mut caller: Caller<'_, Session<'a>>, ptr: i32| -> Result<i64, Error> {
+                    charge(&mut caller, BALANCE_GAS + byte_gas(32))?;
+                    let address = read_address(&caller, ptr)?;
+                    let balance = caller.data().balance(&address).map_err(Error::host)?;
+                    Ok(balance as i64)
+                },
+            )
+            .expect(wrapped);
+        linker
+            .func_wrap(
+                "env",
+                "transfer",
+                |mut caller: Caller<'_, Session<'a>>,
+                 ptr: i32,
+                 amount: i64|
+                 -> Result<i32, Error> {
+                    let amount = u64::try_from(amount)
+                        .map_err(|_| Error::new("negative transfer amount"))?;
+                    charge(&mut caller, TRANSFER_GAS + byte_gas(32))?;
+                    let to = read_address(&caller, ptr)?;
+                    let contract = caller.data().context.contract;
+                    let available = caller.data().balance(&contract).map_err(Error::host)?;
+                    if available < amount {
+                        return Ok(1);
+                    }
+                    caller.data_mut().effects.transfers.push((to, amount));
+                    Ok(0)
+                },
+            )
+            .expect(wrapped);
+
+        linker
+    }
+}
+
+/// Map a Wasm error to the failure it stands for
+fn classify(error: Error) -> Failure {
+    if let Some(failure) = error.downcast_ref::<HostFailure>() {
+        return Failure::Host(failure.clone());
+    }
+    match error.as_trap_code() {
+        Some(TrapCode::OutOfFuel) => ExecutionFailure::OutOfGas.into(),
+        _ => ExecutionFailure::Trap(error.to_string()).into(),
+    }
+}
+
+fn byte_gas(len: usize) -> u64 {
+    len as u64 * HOST_BYTE_GAS
+}
+
+/// Take `gas` from the remaining fuel, trapping when there is not enough
+fn charge(caller: &mut Caller<'_, Session<'_>>, gas: u64) -> Result<(), Error> {
+    let fuel = caller.get_fuel().expect("fuel metering is enabled");
+    let remaining = fuel.checked_sub(gas);
+    caller
+        .set_fuel(remaining.unwrap_or(0))
+        .expect("fuel metering is enabled");
+    remaining
+        .map(|_| ())
+        .ok_or(Error::from(TrapCode::OutOfFuel))
+}
+
+/// Length argument from the contract, at most `max`
+fn checked_len(len: i32, max: usize) -> Result<usize, Error> {
+    usize::try_from(len)
+        .ok()
+        .filter(|len| *len <= max)
+        .ok_or_else(|| Error::new(format!("length {} is out of range", len)))
+}
+
+fn memory(caller: &Caller<'_, Session<'_>>) -> Result<Memory, Error> {
+    caller
+        .get_export("memory")
+        .and_then(Extern::into_memory)
+        .ok_or_else(|| Error::new("contract exports no memory"))
+}
+
+fn read_memory(caller: &Caller<'_, Session<'_>>, ptr: i32, len: usize) -> Result<Vec<u8>, Error> {
+    let mut buffer = vec![0; len];
+    memory(caller)?
+        .read(caller, ptr as u32 as usize, &mut buffer)
+        .map_err(|_| Error::from(TrapCode::MemoryOutOfBounds))?;
+    Ok(buffer)
+}
+
+fn write_memory(caller: &mut Caller<'_, Session<'_>>, ptr: i32, bytes: &[u8]) -> Result<(), Error> {
+    memory(caller)?
+        .write(caller, ptr as u32 as usize, bytes)
+        .map_err(|_| Error::from(TrapCode::MemoryOutOfBounds))
+}
+
+fn read_address(caller: &Caller<'_, Session<'_>>, ptr: i32) -> Result<PublicKey, Error> {
+    let bytes = read_memory(caller, ptr, 32)?;
+    Ok(PublicKey(bytes.try_into().expect("32 bytes were read")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Counter that adds the first input byte to the value under key "n"
+    /// and returns the new value
+    const COUNTER: &str = r#"
+        (module
+          (import "env" "input_read" (func $input_read (param i32)))
+          (import "env" "storage_read" (func $storage_read (param i32 i32 i32 i32) (result i32)))
+          (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32)))
+          (import "env" "output_write" (func $output_write (param i32 i32)))
+          (memory (export "memory") 1)
+          (data (i32.const 0) "n")
+          (func (export "init")
+            (i64.store (i32.const 8) (i64.const 100))
+            (call $storage_write (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8)))
+          (func (export "add")
+            (drop (call $storage_read (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8)))
+            (call $input_read (i32.const 16))
+            (i64.store (i32.const 8)
+              (i64.add (i64.load (i32.const 8)) (i64.load8_u (i32.const 16))))
+            (call $storage_write (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8))
+            (call $output_write (i32.const 8) (i32.const 8)))
+          (func (export "spin") (loop $forever (br $forever)))
+          (func (export "crash") unreachable))
+    "#;
+
+    /// Pays the caller the value sent, then tries to pay far more
+    const PAYER: &str = r#"
+        (module
+          (import "env" "caller" (func $caller (param i32)))
+          (import "env" "value" (func $value (result i64)))
+          (import "env" "transfer" (func $transfer (param i32 i64) (result i32)))
+          (import "env" "output_write" (func $output_write (param i32 i32)))
+          (memory (export "memory") 1)
+          (func (export "refund")
+            (call $caller (i32.const 0))
+            (i32.store (i32.const 32)
+              (call $transfer (i32.const 0) (call $value)))
+            (i32.store (i32.const 36)
+              (call $transfer (i32.const 0) (i64.const 1000000000)))
+            (call $output_write (i32.const 32) (i32.const 8))))
+    "#;
+
+    #[derive(Default)]
+    struct TestHost {
+        storage: HashMap<Vec<u8>, Vec<u8>>,
+        balances: HashMap<PublicKey, u64>,
+    }
+
+    impl ContractHost for TestHost {
+        fn storage_read(
+            &self,
+            _contract: &PublicKey,
+            key: &[u8],
+        ) -> Result<Option<Vec<u8>>, HostFailure> {
+            Ok(self.storage.get(key).cloned())
+        }
+
+        fn balance(&self, address: &PublicKey) -> Result<u64, HostFailure> {
+            Ok(self.balances.get(address).copied().unwrap_or(0))
+        }
+    }
+
+    fn context(value: u64) -> CallContext {
+        CallContext {
+            contract: PublicKey([7; 32]),
+            caller: PublicKey([9; 32]),
+            value,
+            height: 10,
+        }
+    }
+
+    #[test]
+    fn test_deploy_init_and_call_update_storage() {
+        let vm = ContractVm::new();
+        let code = wat::parse_str(COUNTER).unwrap();
+        let mut host = TestHost::default();
+
+        let deployed = vm.deploy(&code, context(0), &host, 1_000_000).unwrap();
+        let effects = deployed.outcome.unwrap();
+        assert!(deployed.gas_used >= code.len() as u64 * DEPLOY_BYTE_GAS);
+        let stored = effects.writes[&b"n".to_vec()].clone().unwrap();
+        assert_eq!(stored, 100u64.to_le_bytes());
+        host.storage.insert(b"n".to_vec(), stored);
+
+        let called = vm
+            .call(&code, "add", &[5], context(0), &host, 100_000)
+            .unwrap();
+        let effects = called.outcome.clone().unwrap();
+        assert_eq!(effects.output, 105u64.to_le_bytes());
+        assert_eq!(
+            effects.writes[&b"n".to_vec()],
+            Some(105u64.to_le_bytes().to_vec())
+        );
+        let compile_gas = code.len() as u64 * COMPILE_BYTE_GAS;
+        assert!(called.gas_used > compile_gas + STORAGE_READ_GAS + STORAGE_WRITE_GAS);
+
+        // Same call, same gas
+        let again = vm
+            .call(&code, "add", &[5], context(0), &host, 100_000)
+            .unwrap();
+        assert_eq!(again, called);
+    }
+
+    #[test]
+    fn test_failures_are_reported_not_raised() {
+        let vm = ContractVm::new();
+        let code = wat::parse_str(COUNTER).unwrap();
+        let host = TestHost::default();
+
+        let spin = vm
+            .call(&code, "spin", &[], context(0), &host, 50_000)
+            .unwrap();
+        assert_eq!(spin.gas_used, 50_000);
+        assert_eq!(spin.outcome, Err(ExecutionFailure::OutOfGas));
+
+        let crash = vm
+            .call(&code, "crash", &[], context(0), &host, 50_000)
+            .unwrap();
+        assert!(matches!(crash.outcome, Err(ExecutionFailure::Trap(_))));
+        assert!(crash.gas_used < 50_000);
+
+        let missing = vm
+            .call(&code, "nope", &[], context(0), &host, 50_000)
+            .unwrap();
+        assert_eq!(
+            missing.outcome,
+            Err(ExecutionFailure::MissingMethod("nope".to_string()))
+        );
+
+        // Compiling and host costs count against the limit too
+        let compile_gas = code.len() as u64 * COMPILE_BYTE_GAS;
+        let uncompiled = vm
+            .call(&code, "add", &[1], context(0), &host, compile_gas - 1)
+            .unwrap();
+        assert_eq!(uncompiled.gas_used, compile_gas - 1);
+        assert_eq!(uncompiled.outcome, Err(ExecutionFailure::OutOfGas));
+        let starved = vm
+            .call(&code, "add", &[1], context(0), &host, compile_gas + 600)
+            .unwrap();
+        assert_eq!(starved.outcome, Err(ExecutionFailure::OutOfGas));
+
+        // Floating point is not deterministic enough to allow
+        let floats = wat::parse_str(
+            r#"(module (func (export "f") (drop (f64.add (f64.const 1) (f64.const 2)))))"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            vm.validate(&floats),
+            Err(ExecutionFailure::InvalidModule(_))
+        ));
+        assert!(vm.validate(&code).is_ok());
+    }
+
+    #[test]
+    fn test_transfers_limited_to_contract_balance() {
+        let vm = ContractVm::new();
+        let code = wat::parse_str(PAYER).unwrap();
+        let mut host = TestHost::default();
+        let ctx = context(2_500);
+        host.balances.insert(ctx.contract, 10_000);
+
+        let execution = vm.call(&code, "refund", &[], ctx, &host, 100_000).unwrap();
+        let effects = execution.outcome.unwrap();
+        assert_eq!(effects.transfers, vec![(ctx.caller, 2_500)]);
+        // First transfer succeeded, the oversized one was refused
+        assert_eq!(effects.output, [0, 0, 0, 0, 1, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,352 @@
+//! Signed chain-state anchors published every N blocks
+//! مراسي حالة السلسلة الموقعة المنشورة كل عدد من الكتل
+//!
+//! A node with anchoring enabled commits to its view of the chain at every
+//! multiple of the anchor interval: the tip hash, the state root and the
+//! total supply are hashed into one digest, signed with the node's anchor
+//! key and written to `anchors/<height>.json` in the data directory. Two
+//! independent nodes agree on the chain up to a height exactly when their
+//! anchors at that height carry the same digest, so comparing anchors
+//! detects a silent divergence long before it shows up in balances.
+
+use crate::crypto::{KeyPair, PublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Domain separator of anchor digests
+pub const ANCHOR_DOMAIN: &[u8] = b"OpenSyria-ChainAnchor-v1";
+
+/// Directory, relative to the data directory, holding published anchors
+pub const ANCHOR_DIR: &str = "anchors";
+
+/// File, relative to the data directory, holding the anchor signing key
+pub const ANCHOR_KEY_FILE: &str = "anchor.key";
+
+/// Errors publishing or reading anchors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnchorError {
+    /// An anchor file or the key file could not be read or written
+    Io(String),
+    /// An anchor file or the key file is malformed
+    Malformed(String),
+    /// The digest does not match the anchored fields
+    DigestMismatch { height: u64 },
+    /// The signature does not verify against the signer
+    InvalidSignature { height: u64 },
+}
+
+impl std::fmt::Display for AnchorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnchorError::Io(e) => write!(f, "Anchor I/O error: {}", e),
+            AnchorError::Malformed(e) => write!(f, "Malformed anchor: {}", e),
+            AnchorError::DigestMismatch { height } => {
+                write!(f, "Anchor at height {} does not match its digest", height)
+            }
+            AnchorError::InvalidSignature { height } => {
+                write!(f, "Anchor at height {} has an invalid signature", height)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnchorError {}
+
+impl From<std::io::Error> for AnchorError {
+    fn from(e: std::io::Error) -> Self {
+        AnchorError::Io(e.to_string())
+    }
+}
+
+/// What a node commits to at an anchor height
+/// حالة السلسلة عند ارتفاع المرساة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainAnchor {
+    pub height: u64,
+    /// Hash of the block at `height` (hex)
+    pub block_hash: String,
+    /// State root after applying that block (hex)
+    pub state_root: String,
+    pub total_supply: u64,
+}
+
+impl ChainAnchor {
+    pub fn new(
+        height: u64,
+        block_hash: &[u8; 32],
+        state_root: &[u8; 32],
+        total_supply: u64,
+    ) -> Self {
+        Self {
+            height,
+            block_hash: hex::encode(block_hash),
+            state_root: hex::encode(state_root),
+            total_supply,
+        }
+    }
+
+    /// Whether `height` is an anchor height for `interval` (0 disables anchoring)
+    pub fn is_due(height: u64, interval: u64) -> bool {
+        interval != 0 && height != 0 && height.is_multiple_of(interval)
+    }
+
+    /// Digest two nodes compare to check they agree
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(ANCHOR_DOMAIN);
+        hasher.update(self.height.to_le_bytes());
+        hasher.update(self.block_hash.as_bytes());
+        hasher.update(self.state_root.as_bytes());
+        hasher.update(self.total_supply.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// An anchor signed by the node that computed it
+/// مرساة موقعة من العقدة التي حسبتها
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedAnchor {
+    #[serde(flatten)]
+    pub anchor: ChainAnchor,
+    /// [`ChainAnchor::digest`] (hex)
+    pub digest: String,
+    /// Anchor key of the publishing node (hex)
+    pub signer: String,
+    /// Signature over the digest (hex)
+    pub signature: String,
+    /// When the anchor was written (Unix seconds)
+    pub published_at: u64,
+}
+
+impl SignedAnchor {
+    pub fn sign(anchor: ChainAnchor, keypair: &KeyPair) -> Self {
+        let digest = anchor.digest();
+        let published_at = crate::clock::system_time();
+
+        Self {
+            anchor,
+            digest: hex::encode(digest),
+            signer: keypair.public_key().to_hex(),
+            signature: hex::encode(keypair.sign(&digest)),
+            published_at,
+        }
+    }
+
+    /// Check the digest and signature; whether the signer is a node worth
+    /// comparing against is up to the caller
+    pub fn verify(&self) -> Result<(), AnchorError> {
+        let height = self.anchor.height;
+        let digest = self.anchor.digest();
+        if hex::encode(digest) != self.digest {
+            return Err(AnchorError::DigestMismatch { height });
+        }
+
+        let signer = PublicKey::from_hex(&self.signer)
+            .map_err(|_| AnchorError::InvalidSignature { height })?;
+        let signature =
+            hex::decode(&self.signature).map_err(|_| AnchorError::InvalidSignature { height })?;
+        signer
+            .verify(&digest, &signature)
+            .map_err(|_| AnchorError::InvalidSignature { height })
+    }
+}
+
+/// Signs and writes anchors into a data directory
+/// ناشر المراسي في مجلد البيانات
+pub struct AnchorPublisher {
+    dir: PathBuf,
+    interval: u64,
+    keypair: KeyPair,
+}
+
+impl AnchorPublisher {
+    /// Publish every `interval` blocks into `data_dir`
+    ///
+    /// The signing key is read from [`ANCHOR_KEY_FILE`], or generated there
+    /// on first use, so a node keeps signing with the same key across
+    /// restarts.
+    pub fn open(data_dir: &Path, interval: u64) -> Result<Self, AnchorError> {
+        let dir = data_dir.join(ANCHOR_DIR);
+        fs::create_dir_all(&dir)?;
+        let keypair = load_or_create_key(&data_dir.join(ANCHOR_KEY_FILE))?;
+
+        Ok(Self {
+            dir,
+            interval,
+            keypair,
+        })
+    }
+
+    /// Directory anchors are written to
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Key that signs the anchors
+    pub fn signer(&self) -> PublicKey {
+        self.keypair.public_key()
+    }
+
+    /// Whether an anchor is due at `height`
+    pub fn is_due(&self, height: u64) -> bool {
+        ChainAnchor::is_due(height, self.interval)
+    }
+
+    /// Sign `anchor` and write it, replacing an anchor at the same height
+    /// left by a chain that was since reorganised
+    pub fn publish(&self, anchor: ChainAnchor) -> Result<SignedAnchor, AnchorError> {
+        let signed = SignedAnchor::sign(anchor, &self.keypair);
+        let path = anchor_path(&self.dir, signed.anchor.height);
+        let json = serde_json::to_vec_pretty(&signed)
+            .map_err(|e| AnchorError::Malformed(e.to_string()))?;
+
+        // Write to a temp file first so readers never see a truncated anchor
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &path)?;
+        Ok(signed)
+    }
+
+    /// Delete the anchor at `height`, published for a block that was since
+    /// reverted; returns whether there was one
+    pub fn retract(&self, height: u64) -> Result<bool, AnchorError> {
+        match fs::remove_file(anchor_path(&self.dir, height)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn anchor_path(dir: &Path, height: u64) -> PathBuf {
+    dir.join(format!("{}.json", height))
+}
+
+/// Read the anchor published at `height` from an anchor directory
+pub fn load_anchor(dir: &Path, height: u64) -> Result<Option<SignedAnchor>, AnchorError> {
+    let path = anchor_path(dir, height);
+    if !path.exists() {
+        return Ok(None);
+    }
+    read_anchor(&path).map(Some)
+}
+
+/// Read one anchor file, such as one copied from another node's anchor directory
+pub fn read_anchor(path: &Path) -> Result<SignedAnchor, AnchorError> {
+    let bytes = fs::read(path)?;
+    serde_json::from_slice(&bytes)
+        .map_err(|e| AnchorError::Malformed(format!("{}: {}", path.display(), e)))
+}
+
+/// Heights with a published anchor, ascending
+pub fn anchor_heights(dir: &Path) -> Result<Vec<u64>, AnchorError> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut heights = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let height = name
+            .to_str()
+            .and_then(|name| name.strip_suffix(".json"))
+            .and_then(|stem| stem.parse::<u64>().ok());
+        if let Some(height) = height {
+            heights.push(height);
+        }
+    }
+    heights.sort_unstable();
+    Ok(heights)
+}
+
+/// Read the hex-encoded key at `path`, generating it if the file is missing
+fn load_or_create_key(path: &Path) -> Result<KeyPair, AnchorError> {
+    if path.exists() {
+        let text = fs::read_to_string(path)?;
+        let bytes: [u8; 32] = hex::decode(text.trim())
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| AnchorError::Malformed(format!("{}: bad key", path.display())))?;
+        return KeyPair::from_bytes(&bytes)
+            .map_err(|e| AnchorError::Malformed(format!("{}: {:?}", path.display(), e)));
+    }
+
+    let keypair = KeyPair::generate();
+    let tmp = path.with_extension("tmp");
+    keypair.with_private_key(|key| fs::write(&tmp, hex::encode(key)))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+    }
+    fs::rename(&tmp, path)?;
+    Ok(keypair)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anchor(height: u64, supply: u64) -> ChainAnchor {
+        ChainAnchor::new(height, &[1u8; 32], &[2u8; 32], supply)
+    }
+
+    #[test]
+    fn test_signed_anchor_detects_tampering() {
+        let keypair = KeyPair::generate();
+        let signed = SignedAnchor::sign(anchor(100, 5_000), &keypair);
+        assert!(signed.verify().is_ok());
+
+        let mut inflated = signed.clone();
+        inflated.anchor.total_supply += 1;
+        assert_eq!(
+            inflated.verify(),
+            Err(AnchorError::DigestMismatch { height: 100 })
+        );
+
+        // Recomputing the digest does not help without the key
+        inflated.digest = hex::encode(inflated.anchor.digest());
+        assert_eq!(
+            inflated.verify(),
+            Err(AnchorError::InvalidSignature { height: 100 })
+        );
+
+        // Nodes that agree produce the same digest under different keys
+        let other = SignedAnchor::sign(anchor(100, 5_000), &KeyPair::generate());
+        assert_eq!(other.digest, signed.digest);
+        assert_ne!(other.signature, signed.signature);
+    }
+
+    #[test]
+    fn test_publisher_writes_anchors_and_keeps_its_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let publisher = AnchorPublisher::open(dir.path(), 10).unwrap();
+        assert!(!publisher.is_due(0));
+        assert!(!publisher.is_due(15));
+        assert!(publisher.is_due(20));
+
+        publisher.publish(anchor(20, 1)).unwrap();
+        publisher.publish(anchor(10, 1)).unwrap();
+        let replaced = publisher.publish(anchor(20, 2)).unwrap();
+
+        let anchors = dir.path().join(ANCHOR_DIR);
+        assert_eq!(anchor_heights(&anchors).unwrap(), vec![10, 20]);
+        let loaded = load_anchor(&anchors, 20).unwrap().unwrap();
+        assert_eq!(loaded, replaced);
+        assert!(loaded.verify().is_ok());
+        assert_eq!(load_anchor(&anchors, 30).unwrap(), None);
+
+        assert!(publisher.retract(20).unwrap());
+        assert!(!publisher.retract(20).unwrap());
+        assert_eq!(anchor_heights(&anchors).unwrap(), vec![10]);
+
+        let reopened = AnchorPublisher::open(dir.path(), 10).unwrap();
+        assert_eq!(reopened.signer(), publisher.signer());
+    }
+}
@@ -0,0 +1,205 @@
+//! Deterministic block assembly
+//! تجميع الكتل بشكل حتمي
+//!
+//! Transactions are selected in a fixed order that depends only on the
+//! candidate set, never on how it was stored or iterated:
+//!
+//! 1. Higher fee rate first (see [`Transaction::fee_rate`])
+//! 2. Then lower nonce
+//! 3. Then lower transaction hash
+//!
+//! A sender's transactions are only eligible in nonce order, so a high-fee
+//! transaction waits until the sender's lower nonces have been selected.
+//! Selection for a sender stops at the first nonce gap, and of two
+//! transactions with the same sender and nonce only the one ranking first
+//! is considered. A contract transaction that would take the block past
+//! [`MAX_GAS_PER_BLOCK`] ends selection for its sender.
+
+use crate::contract::{transaction_gas, MAX_GAS_PER_BLOCK};
+use crate::Transaction;
+use std::cmp::Reverse;
+use std::collections::{btree_map::Entry, BTreeMap, BinaryHeap, VecDeque};
+
+/// Selection rank; greater ranks are selected first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank {
+    fee_rate: u64,
+    nonce: Reverse<u64>,
+    hash: Reverse<[u8; 32]>,
+}
+
+impl Rank {
+    fn of(tx: &Transaction) -> Self {
+        Self {
+            fee_rate: tx.fee_rate(),
+            nonce: Reverse(tx.nonce),
+            hash: Reverse(tx.hash()),
+        }
+    }
+}
+
+/// Select up to `max_count` transactions for a block, in block order
+/// اختيار معاملات الكتلة بترتيب حتمي
+///
+/// Coinbase transactions among the candidates are ignored; the caller adds
+/// its own coinbase in front of the result.
+pub fn select_transactions(
+    candidates: impl IntoIterator<Item = Transaction>,
+    max_count: usize,
+) -> Vec<Transaction> {
+    let mut by_sender: BTreeMap<[u8; 32], BTreeMap<u64, Transaction>> = BTreeMap::new();
+    for tx in candidates.into_iter().filter(|tx| !tx.is_coinbase()) {
+        match by_sender.entry(tx.from.0).or_default().entry(tx.nonce) {
+            Entry::Vacant(slot) => {
+                slot.insert(tx);
+            }
+            Entry::Occupied(mut slot) => {
+                if Rank::of(&tx) > Rank::of(slot.get()) {
+                    slot.insert(tx);
+                }
+            }
+        }
+    }
+
+    // Each sender's queue holds consecutive nonces only
+    let mut queues: BTreeMap<[u8; 32], VecDeque<Transaction>> = by_sender
+        .into_iter()
+        .map(|(sender, txs)| {
+            let mut queue = VecDeque::new();
+            for tx in txs.into_values() {
+                let contiguous = queue
+                    .back()
+                    .is_none_or(|last: &Transaction| last.nonce.checked_add(1) == Some(tx.nonce));
+                if !contiguous {
+                    break;
+                }
+                queue.push_back(tx);
+            }
+            (sender, queue)
+        })
+        .collect();
+
+    let mut heads: BinaryHeap<(Rank, [u8; 32])> = queues
+        .iter()
+        .filter_map(|(sender, queue)| queue.front().map(|tx| (Rank::of(tx), *sender)))
+        .collect();
+
+    let mut selected = Vec::with_capacity(max_count.min(heads.len()));
+    let mut gas = 0u64;
+    while selected.len() < max_count {
+        let Some((_, sender)) = heads.pop() else {
+            break;
+        };
+        let Some(queue) = queues.get_mut(&sender) else {
+            continue;
+        };
+        if let Some(tx) = queue.pop_front() {
+            let tx_gas = transaction_gas(&tx);
+            if gas.saturating_add(tx_gas) > MAX_GAS_PER_BLOCK {
+                continue;
+            }
+            gas += tx_gas;
+            selected.push(tx);
+        }
+        if let Some(next) = queue.front() {
+            heads.push((Rank::of(next), sender));
+        }
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Block, KeyPair};
+
+    fn signed(sender: &KeyPair, fee: u64, nonce: u64) -> Transaction {
+        let to = KeyPair::generate().public_key();
+        let tx = Transaction::new(sender.public_key(), to, 10_000, fee, nonce);
+        let signature = sender.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    fn hashes(txs: &[Transaction]) -> Vec<[u8; 32]> {
+        txs.iter().map(|tx| tx.hash()).collect()
+    }
+
+    #[test]
+    fn test_fee_rate_then_sender_nonce_order() {
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate();
+        let a0 = signed(&alice, 1_000, 0);
+        let a1 = signed(&alice, 9_000, 1);
+        let b0 = signed(&bob, 5_000, 0);
+
+        // Alice's high-fee nonce 1 waits for her nonce 0
+        let selected = select_transactions(vec![a1.clone(), b0.clone(), a0.clone()], 10);
+        assert_eq!(hashes(&selected), hashes(&[b0, a0, a1]));
+    }
+
+    #[test]
+    fn test_nonce_gaps_and_duplicates() {
+        let alice = KeyPair::generate();
+        let a0 = signed(&alice, 1_000, 0);
+        let a0_bumped = signed(&alice, 2_000, 0);
+        let a2 = signed(&alice, 1_000, 2);
+
+        let selected = select_transactions(vec![a0, a2, a0_bumped.clone()], 10);
+        assert_eq!(hashes(&selected), hashes(&[a0_bumped]));
+    }
+
+    #[test]
+    fn test_block_gas_limit_respected() {
+        use crate::contract::{gas_fee, ContractCall, MAX_GAS_PER_TRANSACTION, MIN_GAS_PRICE};
+
+        let call = ContractCall::Call {
+            method: "run".to_string(),
+            args: Vec::new(),
+            gas_limit: MAX_GAS_PER_TRANSACTION,
+        };
+        let fee = gas_fee(MAX_GAS_PER_TRANSACTION, MIN_GAS_PRICE);
+        let contract = KeyPair::generate().public_key();
+        let candidates: Vec<Transaction> = (0..6)
+            .map(|_| {
+                let sender = KeyPair::generate();
+                let tx = call.call_transaction(963, sender.public_key(), contract, 0, fee, 0);
+                let signature = sender.sign(&tx.signing_hash());
+                tx.with_signature(signature)
+            })
+            .collect();
+
+        let limit = (MAX_GAS_PER_BLOCK / MAX_GAS_PER_TRANSACTION) as usize;
+        let selected = select_transactions(candidates, 10);
+        assert_eq!(selected.len(), limit);
+        assert!(crate::contract::block_gas(&selected) <= MAX_GAS_PER_BLOCK);
+    }
+
+    #[test]
+    fn test_assembled_template_is_reproducible() {
+        let senders: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate()).collect();
+        let mut candidates = Vec::new();
+        for (i, sender) in senders.iter().enumerate() {
+            for nonce in 0..3 {
+                // Equal fees across senders exercise the hash tie-break
+                candidates.push(signed(sender, 1_000 * (1 + (i as u64 % 2)), nonce));
+            }
+        }
+
+        let template = |txs: Vec<Transaction>| {
+            let selected = select_transactions(txs, 8);
+            Block::new([7u8; 32], selected, 16).header.merkle_root
+        };
+        let expected = template(candidates.clone());
+
+        for shift in 1..candidates.len() {
+            let mut permuted = candidates.clone();
+            permuted.rotate_left(shift);
+            assert_eq!(template(permuted.clone()), expected);
+            permuted.reverse();
+            assert_eq!(template(permuted), expected);
+        }
+
+        assert_eq!(select_transactions(candidates, 8).len(), 8);
+    }
+}
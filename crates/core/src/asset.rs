@@ -0,0 +1,387 @@
+//! Native assets
+//! الأصول الأصلية
+//!
+//! Anyone can issue a token without writing a contract. An asset operation
+//! is a zero-amount transfer to self whose data is [`ASSET_TAG`] followed by
+//! the bincode-encoded [`AssetOperation`]; the sender is the actor and pays
+//! the fee in the native coin as usual.
+//!
+//! Creating an asset makes the sender its issuer. Only the issuer can mint,
+//! up to the asset's `max_supply`, and hand the role to another key or
+//! renounce it, which fixes the supply for good. Holders transfer and burn
+//! their own balances. The asset id is derived from the creator and the
+//! creating transaction's nonce, so it is known before the block is mined.
+
+use crate::crypto::PublicKey;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data prefix marking an asset operation
+pub const ASSET_TAG: &[u8] = b"opensyria-asset-v1";
+/// Longest asset symbol, e.g. `ALEPPO`
+pub const MAX_ASSET_SYMBOL_LEN: usize = 12;
+/// Longest asset name
+pub const MAX_ASSET_NAME_LEN: usize = 64;
+/// Most decimal places an asset may display
+pub const MAX_ASSET_DECIMALS: u8 = 18;
+
+const ASSET_ID_DOMAIN: &[u8] = b"opensyria-asset-id-v1";
+
+/// Identifier of an issued asset
+/// معرّف الأصل
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct AssetId(pub [u8; 32]);
+
+impl AssetId {
+    /// Id of the asset created by `issuer` in its transaction with `nonce`
+    pub fn derive(issuer: &PublicKey, nonce: u64) -> Self {
+        let mut hasher = Sha256::new();
+        hasher.update(ASSET_ID_DOMAIN);
+        hasher.update(issuer.0);
+        hasher.update(nonce.to_le_bytes());
+        AssetId(hasher.finalize().into())
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self, AssetError> {
+        let bytes = hex::decode(s)
+            .map_err(|_| AssetError::InvalidOperation("asset id is not hex".to_string()))?;
+        let id: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| AssetError::InvalidOperation("asset id must be 32 bytes".to_string()))?;
+        Ok(AssetId(id))
+    }
+}
+
+/// Registry entry of an asset
+/// سجل الأصل
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct AssetInfo {
+    /// Key allowed to mint; `None` once the issuer renounced the role
+    pub issuer: Option<PublicKey>,
+    pub symbol: String,
+    pub name: String,
+    /// Decimal places wallets display; amounts are always integers
+    pub decimals: u8,
+    /// Most that may ever be in circulation
+    pub max_supply: u64,
+    /// Minted minus burned
+    pub supply: u64,
+}
+
+impl AssetInfo {
+    /// Check that `signer` may act as issuer
+    pub fn authorize_issuer(&self, signer: &PublicKey) -> Result<(), AssetError> {
+        if self.issuer.as_ref() != Some(signer) {
+            return Err(AssetError::NotIssuer(signer.to_hex()));
+        }
+        Ok(())
+    }
+
+    /// Supply after minting `amount`, within the cap
+    pub fn minted_supply(&self, amount: u64) -> Result<u64, AssetError> {
+        self.supply
+            .checked_add(amount)
+            .filter(|supply| *supply <= self.max_supply)
+            .ok_or(AssetError::SupplyCapExceeded {
+                max_supply: self.max_supply,
+                supply: self.supply,
+                requested: amount,
+            })
+    }
+}
+
+/// Asset operation carried in a transaction
+/// عملية على أصل
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub enum AssetOperation {
+    /// Register a new asset with the sender as issuer and no supply
+    Create {
+        symbol: String,
+        name: String,
+        decimals: u8,
+        max_supply: u64,
+    },
+    /// Issue `amount` to `to`; issuer only
+    Mint {
+        asset: AssetId,
+        to: PublicKey,
+        amount: u64,
+    },
+    /// Move `amount` of the sender's balance to `to`
+    Transfer {
+        asset: AssetId,
+        to: PublicKey,
+        amount: u64,
+    },
+    /// Destroy `amount` of the sender's balance
+    Burn { asset: AssetId, amount: u64 },
+    /// Hand the issuer role to another key, or renounce it with `None`;
+    /// issuer only
+    SetIssuer {
+        asset: AssetId,
+        issuer: Option<PublicKey>,
+    },
+}
+
+impl AssetOperation {
+    /// Check the operation is well formed on its own
+    pub fn validate(&self) -> Result<(), AssetError> {
+        let invalid = |reason: &str| Err(AssetError::InvalidOperation(reason.to_string()));
+        match self {
+            AssetOperation::Create {
+                symbol,
+                name,
+                decimals,
+                max_supply,
+            } => {
+                if symbol.is_empty()
+                    || symbol.len() > MAX_ASSET_SYMBOL_LEN
+                    || !symbol
+                        .bytes()
+                        .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+                {
+                    return invalid("symbol must be 1-12 uppercase letters or digits");
+                }
+                if name.trim().is_empty() || name.len() > MAX_ASSET_NAME_LEN {
+                    return invalid("name must be 1-64 bytes");
+                }
+                if *decimals > MAX_ASSET_DECIMALS {
+                    return invalid("at most 18 decimals");
+                }
+                if *max_supply == 0 {
+                    return invalid("max supply must be positive");
+                }
+            }
+            AssetOperation::Mint { to, amount, .. }
+            | AssetOperation::Transfer { to, amount, .. } => {
+                if to.is_zero() {
+                    return invalid("recipient must be a non-zero key");
+                }
+                if *amount == 0 {
+                    return invalid("amount must be positive");
+                }
+            }
+            AssetOperation::Burn { amount, .. } => {
+                if *amount == 0 {
+                    return invalid("amount must be positive");
+                }
+            }
+            AssetOperation::SetIssuer { issuer, .. } => {
+                if issuer.is_some_and(|issuer| issuer.is_zero()) {
+                    return invalid("issuer must be a non-zero key");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsigned transaction performing this operation as `sender`
+    /// إنشاء معاملة عملية الأصل
+    pub fn transaction(
+        &self,
+        chain_id: u32,
+        sender: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = ASSET_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("asset operation encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, sender, sender, 0, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is an asset operation, well formed or not
+    pub fn is_asset(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(ASSET_TAG))
+    }
+
+    /// Operation carried by `tx`, or `None` if it is not an asset operation
+    pub fn from_transaction(tx: &Transaction) -> Option<Result<AssetOperation, AssetError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(ASSET_TAG)?;
+        if tx.to != tx.from || tx.amount != 0 {
+            return Some(Err(AssetError::InvalidOperation(
+                "asset operation must be a zero-amount transfer to self".to_string(),
+            )));
+        }
+        let decoded =
+            bincode::decode_from_slice::<AssetOperation, _>(payload, bincode::config::standard());
+        Some(match decoded {
+            Ok((operation, read)) if read == payload.len() => {
+                operation.validate().map(|_| operation)
+            }
+            _ => Err(AssetError::InvalidOperation(
+                "malformed asset encoding".to_string(),
+            )),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssetError {
+    InvalidOperation(String),
+    UnknownAsset(String),
+    AssetExists(String),
+    NotIssuer(String),
+    SupplyCapExceeded {
+        max_supply: u64,
+        supply: u64,
+        requested: u64,
+    },
+    InsufficientAssetBalance {
+        available: u64,
+        requested: u64,
+    },
+}
+
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetError::InvalidOperation(reason) => {
+                write!(f, "Invalid asset operation: {}", reason)
+            }
+            AssetError::UnknownAsset(id) => write!(f, "Unknown asset {}", id),
+            AssetError::AssetExists(id) => write!(f, "Asset {} already exists", id),
+            AssetError::NotIssuer(key) => write!(f, "Key {} is not the asset issuer", key),
+            AssetError::SupplyCapExceeded {
+                max_supply,
+                supply,
+                requested,
+            } => write!(
+                f,
+                "Minting {} would exceed the max supply {} (current supply {})",
+                requested, max_supply, supply
+            ),
+            AssetError::InsufficientAssetBalance {
+                available,
+                requested,
+            } => write!(
+                f,
+                "Insufficient asset balance: {} available, {} requested",
+                available, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn create() -> AssetOperation {
+        AssetOperation::Create {
+            symbol: "ALEPPO".to_string(),
+            name: "Aleppo Soap Cooperative".to_string(),
+            decimals: 2,
+            max_supply: 1_000_000,
+        }
+    }
+
+    #[test]
+    fn test_operation_round_trip() {
+        let sender = KeyPair::generate().public_key();
+        let tx = create().transaction(963, sender, 100, 4);
+        assert!(AssetOperation::is_asset(&tx));
+        assert_eq!(AssetOperation::from_transaction(&tx), Some(Ok(create())));
+        assert_ne!(AssetId::derive(&sender, 4), AssetId::derive(&sender, 5));
+
+        let id = AssetId::derive(&sender, 4);
+        assert_eq!(AssetId::from_hex(&id.to_hex()), Ok(id));
+
+        // Operations must be zero-amount transfers to self
+        let mut paid = tx.clone();
+        paid.amount = 1;
+        assert!(matches!(
+            AssetOperation::from_transaction(&paid),
+            Some(Err(AssetError::InvalidOperation(_)))
+        ));
+
+        let plain = Transaction::new(sender, sender, 0, 100, 4);
+        assert_eq!(AssetOperation::from_transaction(&plain), None);
+    }
+
+    #[test]
+    fn test_invalid_operations_rejected() {
+        let lowercase = AssetOperation::Create {
+            symbol: "soap".to_string(),
+            name: "Soap".to_string(),
+            decimals: 0,
+            max_supply: 10,
+        };
+        assert!(lowercase.validate().is_err());
+        let uncapped = AssetOperation::Create {
+            symbol: "SOAP".to_string(),
+            name: "Soap".to_string(),
+            decimals: 0,
+            max_supply: 0,
+        };
+        assert!(uncapped.validate().is_err());
+
+        let asset = AssetId([1; 32]);
+        let empty = AssetOperation::Transfer {
+            asset,
+            to: KeyPair::generate().public_key(),
+            amount: 0,
+        };
+        assert!(empty.validate().is_err());
+        let unowned = AssetOperation::Mint {
+            asset,
+            to: PublicKey::zero(),
+            amount: 5,
+        };
+        assert!(unowned.validate().is_err());
+    }
+
+    #[test]
+    fn test_issuer_and_supply_cap() {
+        let issuer = KeyPair::generate().public_key();
+        let info = AssetInfo {
+            issuer: Some(issuer),
+            symbol: "SOAP".to_string(),
+            name: "Soap".to_string(),
+            decimals: 0,
+            max_supply: 100,
+            supply: 90,
+        };
+        assert!(info.authorize_issuer(&issuer).is_ok());
+        assert!(matches!(
+            info.authorize_issuer(&KeyPair::generate().public_key()),
+            Err(AssetError::NotIssuer(_))
+        ));
+        assert_eq!(info.minted_supply(10), Ok(100));
+        assert!(matches!(
+            info.minted_supply(11),
+            Err(AssetError::SupplyCapExceeded { supply: 90, .. })
+        ));
+
+        let renounced = AssetInfo {
+            issuer: None,
+            ..info
+        };
+        assert!(renounced.authorize_issuer(&issuer).is_err());
+    }
+}
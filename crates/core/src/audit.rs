@@ -0,0 +1,446 @@
+//! Tamper-evident audit log of state mutations
+//! سجل تدقيق مقاوم للتلاعب لتغييرات الحالة
+//!
+//! Compliance deployments can attach an [`AuditLog`] to the state and
+//! governance subsystems. Every applied transaction and governance execution
+//! is appended as one JSON line, transactions together with the balances
+//! before and after the change. Each entry commits to the hash of
+//! the previous one, so editing, dropping or reordering lines is detected by
+//! [`verify_audit_log`].
+
+use crate::crypto::PublicKey;
+use crate::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// `prev_hash` of the first entry in a log
+pub const AUDIT_GENESIS_HASH: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Errors writing or verifying an audit log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditError {
+    /// The log file could not be read or written
+    Io(String),
+    /// A line is not a valid audit entry
+    Parse { line: u64, reason: String },
+    /// Entry sequence numbers are not contiguous
+    SequenceGap { expected: u64, got: u64 },
+    /// Entry does not link to the hash of its predecessor
+    BrokenChain { seq: u64 },
+    /// Entry contents do not match its recorded hash
+    HashMismatch { seq: u64 },
+}
+
+impl std::fmt::Display for AuditError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuditError::Io(e) => write!(f, "Audit log I/O error: {}", e),
+            AuditError::Parse { line, reason } => {
+                write!(f, "Malformed audit entry on line {}: {}", line, reason)
+            }
+            AuditError::SequenceGap { expected, got } => {
+                write!(
+                    f,
+                    "Audit sequence gap: expected entry {}, found {}",
+                    expected, got
+                )
+            }
+            AuditError::BrokenChain { seq } => {
+                write!(f, "Audit entry {} does not link to its predecessor", seq)
+            }
+            AuditError::HashMismatch { seq } => {
+                write!(f, "Audit entry {} has been modified", seq)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AuditError {}
+
+impl From<std::io::Error> for AuditError {
+    fn from(e: std::io::Error) -> Self {
+        AuditError::Io(e.to_string())
+    }
+}
+
+/// Balance of one account before and after a mutation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    /// Account address (hex public key)
+    pub address: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl BalanceChange {
+    pub fn new(address: &PublicKey, before: u64, after: u64) -> Self {
+        Self {
+            address: address.to_hex(),
+            before,
+            after,
+        }
+    }
+}
+
+/// A recorded state mutation
+/// تغيير حالة مسجل
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A transaction was applied to (or reverted from) account state
+    Transaction {
+        tx_hash: String,
+        from: String,
+        to: String,
+        amount: u64,
+        fee: u64,
+        /// Account that paid the fee, when not the sender
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        fee_payer: Option<String>,
+        nonce: u64,
+        /// True when the transaction was undone by a reorganization
+        reverted: bool,
+        balances: Vec<BalanceChange>,
+    },
+    /// A passed governance proposal was executed
+    GovernanceExecution {
+        proposal_id: u64,
+        height: u64,
+        proposal_type: String,
+    },
+    /// An emergency council motion was executed
+    EmergencyAction {
+        motion_id: u64,
+        height: u64,
+        action: String,
+        reason: String,
+        /// Council members that approved the motion (hex)
+        approvers: Vec<String>,
+    },
+    /// An emergency council motion was revoked by a governance vote
+    EmergencyRevocation {
+        motion_id: u64,
+        proposal_id: u64,
+        height: u64,
+    },
+}
+
+impl AuditEvent {
+    /// Event for a transaction with the resulting balance changes
+    pub fn transaction(tx: &Transaction, reverted: bool, balances: Vec<BalanceChange>) -> Self {
+        AuditEvent::Transaction {
+            tx_hash: hex::encode(tx.hash()),
+            from: tx.from.to_hex(),
+            to: tx.to.to_hex(),
+            amount: tx.amount,
+            fee: tx.fee,
+            fee_payer: tx.sponsor.as_ref().map(|sponsor| sponsor.payer.to_hex()),
+            nonce: tx.nonce,
+            reverted,
+            balances,
+        }
+    }
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Position in the log, starting at 0
+    pub seq: u64,
+    /// Unix time the entry was written
+    pub timestamp: u64,
+    /// Hash of the previous entry (hex)
+    pub prev_hash: String,
+    pub event: AuditEvent,
+    /// SHA-256 over all other fields (hex)
+    pub hash: String,
+}
+
+#[derive(Serialize)]
+struct HashedFields<'a> {
+    seq: u64,
+    timestamp: u64,
+    prev_hash: &'a str,
+    event: &'a AuditEvent,
+}
+
+impl AuditEntry {
+    fn compute_hash(seq: u64, timestamp: u64, prev_hash: &str, event: &AuditEvent) -> String {
+        let fields = HashedFields {
+            seq,
+            timestamp,
+            prev_hash,
+            event,
+        };
+        let bytes = serde_json::to_vec(&fields).expect("audit event serializes to JSON");
+        hex::encode(Sha256::digest(&bytes))
+    }
+
+    /// Whether the recorded hash matches the entry contents
+    pub fn hash_is_valid(&self) -> bool {
+        Self::compute_hash(self.seq, self.timestamp, &self.prev_hash, &self.event) == self.hash
+    }
+}
+
+/// Result of a successful verification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditSummary {
+    /// Number of entries in the log
+    pub entries: u64,
+    /// Hash of the last entry (genesis hash for an empty log)
+    pub last_hash: String,
+}
+
+/// Verify every entry of an audit log file
+/// التحقق من سلامة سجل التدقيق
+pub fn verify_audit_log(path: &Path) -> Result<AuditSummary, AuditError> {
+    scan_audit_log(path).map(|(summary, _)| summary)
+}
+
+/// Verify a log, also returning its last event
+fn scan_audit_log(path: &Path) -> Result<(AuditSummary, Option<AuditEvent>), AuditError> {
+    let file =
+        File::open(path).map_err(|e| AuditError::Io(format!("{}: {}", path.display(), e)))?;
+
+    let mut summary = AuditSummary {
+        entries: 0,
+        last_hash: AUDIT_GENESIS_HASH.to_string(),
+    };
+    let mut last_event = None;
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line).map_err(|e| AuditError::Parse {
+            line: index as u64 + 1,
+            reason: e.to_string(),
+        })?;
+
+        if entry.seq != summary.entries {
+            return Err(AuditError::SequenceGap {
+                expected: summary.entries,
+                got: entry.seq,
+            });
+        }
+        if entry.prev_hash != summary.last_hash {
+            return Err(AuditError::BrokenChain { seq: entry.seq });
+        }
+        if !entry.hash_is_valid() {
+            return Err(AuditError::HashMismatch { seq: entry.seq });
+        }
+
+        summary.entries += 1;
+        summary.last_hash = entry.hash;
+        last_event = Some(entry.event);
+    }
+    Ok((summary, last_event))
+}
+
+struct AuditWriter {
+    file: File,
+    next_seq: u64,
+    last_hash: String,
+    last_event: Option<AuditEvent>,
+}
+
+/// Append-only, hash-chained audit log file
+/// ملف سجل تدقيق للإلحاق فقط ومتسلسل بالتجزئة
+///
+/// Share one instance between subsystems with `Arc`; appends are serialized
+/// internally and synced to disk before `record` returns.
+pub struct AuditLog {
+    path: PathBuf,
+    writer: Mutex<AuditWriter>,
+}
+
+impl AuditLog {
+    /// Open (or create) the log at `path`
+    ///
+    /// An existing log is verified first, so a node never extends a chain
+    /// that has already been tampered with.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, AuditError> {
+        let path = path.into();
+        let (summary, last_event) = if path.exists() {
+            scan_audit_log(&path)?
+        } else {
+            let summary = AuditSummary {
+                entries: 0,
+                last_hash: AUDIT_GENESIS_HASH.to_string(),
+            };
+            (summary, None)
+        };
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| AuditError::Io(format!("{}: {}", path.display(), e)))?;
+
+        Ok(Self {
+            path,
+            writer: Mutex::new(AuditWriter {
+                file,
+                next_seq: summary.entries,
+                last_hash: summary.last_hash,
+                last_event,
+            }),
+        })
+    }
+
+    /// Path of the log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an event, returning the written entry
+    pub fn record(&self, event: AuditEvent) -> Result<AuditEntry, AuditError> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let hash = AuditEntry::compute_hash(writer.next_seq, timestamp, &writer.last_hash, &event);
+        let entry = AuditEntry {
+            seq: writer.next_seq,
+            timestamp,
+            prev_hash: writer.last_hash.clone(),
+            event,
+            hash,
+        };
+
+        let mut line = serde_json::to_vec(&entry).map_err(|e| AuditError::Io(e.to_string()))?;
+        line.push(b'\n');
+        writer.file.write_all(&line)?;
+        writer.file.sync_data()?;
+
+        writer.next_seq += 1;
+        writer.last_hash = entry.hash.clone();
+        writer.last_event = Some(entry.event.clone());
+        Ok(entry)
+    }
+
+    /// Event of the last entry written, if any
+    ///
+    /// Lets a writer that stages events elsewhere before appending them
+    /// tell whether an append interrupted by a crash already went through.
+    pub fn last_event(&self) -> Option<AuditEvent> {
+        self.writer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_event
+            .clone()
+    }
+
+    /// Number of entries written so far
+    pub fn len(&self) -> u64 {
+        self.writer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .next_seq
+    }
+
+    /// Whether the log has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl std::fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn governance_event(proposal_id: u64) -> AuditEvent {
+        AuditEvent::GovernanceExecution {
+            proposal_id,
+            height: 42,
+            proposal_type: "TextProposal".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_and_verify_across_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+
+        let from = KeyPair::generate().public_key();
+        let to = KeyPair::generate().public_key();
+        let tx = Transaction::new(from, to, 1_000, 100, 0);
+        {
+            let log = AuditLog::open(&path).unwrap();
+            let changes = vec![
+                BalanceChange::new(&from, 5_000, 3_900),
+                BalanceChange::new(&to, 0, 1_000),
+            ];
+            let first = log
+                .record(AuditEvent::transaction(&tx, false, changes))
+                .unwrap();
+            assert_eq!(first.prev_hash, AUDIT_GENESIS_HASH);
+            log.record(governance_event(10)).unwrap();
+        }
+
+        let log = AuditLog::open(&path).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.last_event(), Some(governance_event(10)));
+        let third = log.record(governance_event(1)).unwrap();
+        assert_eq!(third.seq, 2);
+
+        let summary = verify_audit_log(&path).unwrap();
+        assert_eq!(summary.entries, 3);
+        assert_eq!(summary.last_hash, third.hash);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let log = AuditLog::open(&path).unwrap();
+        for proposal_id in [100, 200, 300] {
+            log.record(governance_event(proposal_id)).unwrap();
+        }
+        drop(log);
+
+        let original = std::fs::read_to_string(&path).unwrap();
+
+        // Edited amount
+        std::fs::write(
+            &path,
+            original.replacen("\"proposal_id\":200", "\"proposal_id\":900", 1),
+        )
+        .unwrap();
+        assert_eq!(
+            verify_audit_log(&path),
+            Err(AuditError::HashMismatch { seq: 1 })
+        );
+
+        // Dropped entry
+        let lines: Vec<&str> = original.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert_eq!(
+            verify_audit_log(&path),
+            Err(AuditError::SequenceGap {
+                expected: 1,
+                got: 2
+            })
+        );
+
+        // A broken log cannot be extended
+        assert!(AuditLog::open(&path).is_err());
+    }
+}
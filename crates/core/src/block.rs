@@ -1,11 +1,10 @@
 use crate::constants::{GENESIS_DIFFICULTY, GENESIS_NONCE, GENESIS_TIMESTAMP};
-use crate::transaction::Transaction;
+use crate::transaction::{Transaction, TransactionV1};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Block header containing metadata
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[derive(bincode::Encode, bincode::Decode)]
 pub struct BlockHeader {
     /// Block version for protocol upgrades
@@ -59,6 +58,24 @@ impl BlockHeader {
 
         true
     }
+
+    /// Expected number of hashes behind this header's proof of work
+    /// العمل المتوقع خلف إثبات العمل لهذا الرأس
+    ///
+    /// A difficulty of `d` leading zero bits takes 2^d hashes on average.
+    /// Saturates at `u128::MAX` from 128 bits up.
+    pub fn work(&self) -> u128 {
+        1u128.checked_shl(self.difficulty).unwrap_or(u128::MAX)
+    }
+}
+
+/// Total work behind `blocks`, the quantity fork choice compares
+/// مجموع العمل خلف الكتل، وهو ما يقارنه اختيار الفرع
+pub fn chain_work<'a>(blocks: impl IntoIterator<Item = &'a Block>) -> u128 {
+    blocks
+        .into_iter()
+        .map(|block| block.header.work())
+        .fold(0, u128::saturating_add)
 }
 
 /// Complete block with header and transactions
@@ -69,14 +86,32 @@ pub struct Block {
     pub transactions: Vec<Transaction>,
 }
 
+/// Block whose transactions use the legacy [`TransactionV1`] layout
+/// كتلة بمعاملات بالترميز القديم
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct BlockV1 {
+    pub header: BlockHeader,
+    pub transactions: Vec<TransactionV1>,
+}
+
+impl From<BlockV1> for Block {
+    fn from(block: BlockV1) -> Self {
+        Self {
+            header: block.header,
+            transactions: block
+                .transactions
+                .into_iter()
+                .map(Transaction::from)
+                .collect(),
+        }
+    }
+}
+
 impl Block {
     /// Create new block
     pub fn new(previous_hash: [u8; 32], transactions: Vec<Transaction>, difficulty: u32) -> Self {
         let merkle_root = Self::calculate_merkle_root(&transactions);
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
-            .as_secs();
+        let timestamp = crate::clock::now();
 
         let header = BlockHeader {
             version: 1,
@@ -117,7 +152,7 @@ impl Block {
             return [0u8; 32];
         }
 
-        let mut hashes: Vec<[u8; 32]> = transactions.iter().map(|tx| tx.hash()).collect();
+        let mut hashes: Vec<[u8; 32]> = transactions.iter().map(|tx| tx.witness_hash()).collect();
 
         while hashes.len() > 1 {
             let mut new_hashes = Vec::new();
@@ -142,9 +177,20 @@ impl Block {
         self.header.hash()
     }
 
+    /// Miner of the block, taken from its first coinbase
+    /// منتج الكتلة من أول معاملة كوين بيس
+    pub fn producer(&self) -> Option<crate::coinbase::BlockProducer> {
+        let coinbase = self.transactions.first().filter(|tx| tx.is_coinbase())?;
+        Some(crate::coinbase::BlockProducer {
+            address: coinbase.to,
+            extra_data: coinbase.coinbase_extra_data().to_vec(),
+        })
+    }
+
     /// Verify all transactions in block
     pub fn verify_transactions(&self) -> Result<(), BlockError> {
-        for tx in &self.transactions {
+        // Coinbase carries no signature; `validate_coinbase` checks it
+        for tx in self.transactions.iter().filter(|tx| !tx.is_coinbase()) {
             tx.verify().map_err(|_| BlockError::InvalidTransaction)?;
         }
         Ok(())
@@ -166,10 +212,7 @@ impl Block {
     pub fn validate_timestamp(&self, previous_timestamp: u64) -> Result<(), BlockError> {
         use crate::constants::{MAX_FUTURE_DRIFT_SECS, MAX_TIMESTAMP_INCREASE_SECS};
         
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|_| BlockError::InvalidTimestamp)?
-            .as_secs();
+        let now = crate::clock::now();
 
         // Rule 1: Not too far in future (reduced to 60 seconds for security)
         if self.header.timestamp > now + MAX_FUTURE_DRIFT_SECS {
@@ -202,10 +245,7 @@ impl Block {
     ) -> Result<(), BlockError> {
         use crate::constants::{MEDIAN_TIME_SPAN, MAX_FUTURE_DRIFT_SECS, MAX_TIMESTAMP_INCREASE_SECS};
         
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|_| BlockError::InvalidTimestamp)?
-            .as_secs();
+        let now = crate::clock::now();
 
         // Rule 1: Not too far in future
         if self.header.timestamp > now + MAX_FUTURE_DRIFT_SECS {
@@ -244,8 +284,17 @@ impl Block {
     /// This method now accepts current_supply parameter to verify that minting new coins
     /// will not exceed MAX_SUPPLY (100M SYL). Prevents inflation attacks.
     pub fn validate_coinbase(&self, block_height: u64, current_supply: u64) -> Result<(), BlockError> {
-        use crate::constants::{calculate_block_reward, MAX_SUPPLY};
+        self.validate_coinbase_with_params(block_height, current_supply, &crate::ChainParams::mainnet())
+    }
 
+    /// Validate coinbase against the reward schedule and supply cap of `params`
+    /// التحقق من معاملة الكوين بيس وفق معاملات السلسلة
+    pub fn validate_coinbase_with_params(
+        &self,
+        block_height: u64,
+        current_supply: u64,
+        params: &crate::ChainParams,
+    ) -> Result<(), BlockError> {
         // Genesis block has no coinbase
         if block_height == 0 {
             return Ok(());
@@ -256,49 +305,76 @@ impl Block {
             return Err(BlockError::MissingCoinbase);
         }
 
-        // First transaction must be coinbase
-        let coinbase = &self.transactions[0];
-        if !coinbase.is_coinbase() {
+        // Block starts with one coinbase per payout address
+        let outputs = self
+            .transactions
+            .iter()
+            .take_while(|tx| tx.is_coinbase())
+            .count();
+        if outputs == 0 {
             return Err(BlockError::MissingCoinbase);
         }
+        // Before the split activates a block has exactly one coinbase
+        if outputs > 1 && !params.coinbase_split_active(block_height) {
+            return Err(BlockError::MultipleCoinbase);
+        }
+        if outputs > crate::coinbase::MAX_COINBASE_OUTPUTS {
+            return Err(BlockError::InvalidCoinbaseOutputs);
+        }
+        let coinbase = &self.transactions[..outputs];
+        let limit_extra_data = params.coinbase_extra_data_active(block_height);
+        for (i, tx) in coinbase.iter().enumerate() {
+            if coinbase[..i].iter().any(|other| other.to == tx.to) {
+                return Err(BlockError::InvalidCoinbaseOutputs);
+            }
+            let extra_data = tx.coinbase_extra_data().len();
+            if limit_extra_data && extra_data > crate::coinbase::MAX_COINBASE_EXTRA_DATA {
+                return Err(BlockError::CoinbaseExtraDataTooLong(extra_data));
+            }
+        }
+
+        // Ensure no other coinbase transactions
+        for tx in self.transactions.iter().skip(outputs) {
+            if tx.is_coinbase() {
+                return Err(BlockError::MultipleCoinbase);
+            }
+        }
 
         // Calculate expected reward
-        let block_reward = calculate_block_reward(block_height);
+        let block_reward = params.block_reward(block_height);
         
         // SECURITY: Use checked_add to prevent overflow in fee summation
         let total_fees = self.transactions.iter()
-            .skip(1) // Skip coinbase itself
+            .skip(outputs) // Skip coinbase outputs
             .try_fold(0u64, |acc, tx| acc.checked_add(tx.fee))
             .ok_or(BlockError::InvalidCoinbaseAmount)?;
 
         let expected_reward = block_reward.checked_add(total_fees)
             .ok_or(BlockError::InvalidCoinbaseAmount)?;
 
-        // Validate coinbase amount
-        if coinbase.amount != expected_reward {
+        // Validate coinbase amount, summed across payout addresses
+        let coinbase_amount = coinbase
+            .iter()
+            .try_fold(0u64, |acc, tx| acc.checked_add(tx.amount))
+            .ok_or(BlockError::InvalidCoinbaseAmount)?;
+        if coinbase_amount != expected_reward {
             return Err(BlockError::InvalidCoinbaseAmount);
         }
 
         // SECURITY FIX (CRITICAL-004): Enforce MAX_SUPPLY to prevent inflation
         // Check that minting this coinbase will not exceed maximum supply
-        let new_supply = current_supply.checked_add(coinbase.amount)
+        let new_supply = current_supply
+            .checked_add(coinbase_amount)
             .ok_or(BlockError::SupplyOverflow)?;
         
-        if new_supply > MAX_SUPPLY {
+        if new_supply > params.max_supply {
             return Err(BlockError::MaxSupplyExceeded {
                 current: current_supply,
-                attempted: coinbase.amount,
-                max: MAX_SUPPLY,
+                attempted: coinbase_amount,
+                max: params.max_supply,
             });
         }
 
-        // Ensure no other coinbase transactions
-        for tx in self.transactions.iter().skip(1) {
-            if tx.is_coinbase() {
-                return Err(BlockError::MultipleCoinbase);
-            }
-        }
-
         Ok(())
     }
 }
@@ -317,6 +393,10 @@ pub enum BlockError {
     MissingCoinbase,
     InvalidCoinbaseAmount,
     MultipleCoinbase,
+    /// Too many coinbase outputs, or two paying the same address
+    InvalidCoinbaseOutputs,
+    /// Coinbase extra data longer than the limit
+    CoinbaseExtraDataTooLong(usize),
     SupplyOverflow,
     MaxSupplyExceeded { current: u64, attempted: u64, max: u64 },
 }
@@ -336,6 +416,15 @@ impl std::fmt::Display for BlockError {
             BlockError::MissingCoinbase => write!(f, "Block missing coinbase transaction"),
             BlockError::InvalidCoinbaseAmount => write!(f, "Coinbase amount incorrect"),
             BlockError::MultipleCoinbase => write!(f, "Block contains multiple coinbase transactions"),
+            BlockError::InvalidCoinbaseOutputs => {
+                write!(f, "Coinbase outputs exceed the limit or repeat an address")
+            }
+            BlockError::CoinbaseExtraDataTooLong(len) => write!(
+                f,
+                "Coinbase extra data is {} bytes (max {})",
+                len,
+                crate::coinbase::MAX_COINBASE_EXTRA_DATA
+            ),
             BlockError::SupplyOverflow => write!(f, "Supply calculation overflow"),
             BlockError::MaxSupplyExceeded { current, attempted, max } => {
                 write!(
@@ -394,6 +483,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chain_work() {
+        let block = |difficulty| Block::new([0u8; 32], vec![], difficulty);
+
+        // One block at difficulty 20 outweighs three at 16
+        let heavy = [block(20)];
+        let long = [block(16), block(16), block(16)];
+        assert_eq!(chain_work(&heavy), 1 << 20);
+        assert_eq!(chain_work(&long), 3 << 16);
+        assert!(chain_work(&heavy) > chain_work(&long));
+
+        assert_eq!(block(200).header.work(), u128::MAX);
+        assert_eq!(chain_work(&[block(200), block(8)]), u128::MAX);
+    }
+
     #[test]
     fn test_merkle_root_changes_with_transactions() {
         use crate::{crypto::KeyPair, transaction::Transaction};
@@ -0,0 +1,66 @@
+//! Node clock with an optional mock time for regtest
+//! ساعة العقدة مع وقت وهمي اختياري لشبكة الاختبار المحلية
+//!
+//! Block timestamps and their validation read the time from [`now`]. A
+//! regtest node can pin it with [`set_mock_time`] so integration tests see
+//! the same timestamps on every run. The mock time is process-wide, like
+//! the system clock it replaces, and is off until set.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Mock Unix time in seconds; 0 means the system clock is used
+static MOCK_TIME: AtomicU64 = AtomicU64::new(0);
+
+/// Current Unix time in seconds, or the mock time if one is set
+pub fn now() -> u64 {
+    mock_time().unwrap_or_else(system_time)
+}
+
+/// Unix time of the system clock in seconds
+pub fn system_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Unix time of the system clock in milliseconds, for latency measurements
+pub fn system_time_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Pin [`now`] to `timestamp`; `None` (or 0) returns to the system clock
+/// تثبيت الوقت الحالي على قيمة محددة
+pub fn set_mock_time(timestamp: Option<u64>) {
+    MOCK_TIME.store(timestamp.unwrap_or(0), Ordering::SeqCst);
+}
+
+/// The mock time, if one is set
+pub fn mock_time() -> Option<u64> {
+    match MOCK_TIME.load(Ordering::SeqCst) {
+        0 => None,
+        timestamp => Some(timestamp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_time() {
+        // Pin to the real time so concurrently running tests are unaffected
+        let pinned = system_time();
+        set_mock_time(Some(pinned));
+        assert_eq!(mock_time(), Some(pinned));
+        assert_eq!(now(), pinned);
+
+        set_mock_time(None);
+        assert_eq!(mock_time(), None);
+        assert!(now() >= pinned);
+    }
+}
@@ -0,0 +1,382 @@
+//! Coinbase payout splitting
+//! تقسيم مكافأة الكتلة بين عدة عناوين
+//!
+//! A block may start with up to [`MAX_COINBASE_OUTPUTS`] coinbase
+//! transactions, one per payout address, whose amounts add up to the block
+//! reward plus fees. Miners describe the split as (address, percentage)
+//! pairs, e.g. an operator and an infrastructure provider.
+//!
+//! Coinbase data starts with the block height and a timestamp and may be
+//! followed by up to [`MAX_COINBASE_EXTRA_DATA`] bytes chosen by the miner,
+//! usually a pool or miner tag. The first payout address and that extra data
+//! identify the block's [`BlockProducer`].
+
+use crate::constants::calculate_block_reward;
+use crate::crypto::PublicKey;
+use crate::transaction::{Transaction, TransactionError};
+use serde::{Deserialize, Serialize};
+
+/// Maximum coinbase transactions (payout addresses) in one block
+pub const MAX_COINBASE_OUTPUTS: usize = 8;
+
+/// Bytes of coinbase data before the extra data: height and timestamp (u64 LE)
+pub const COINBASE_DATA_HEADER_LEN: usize = 16;
+
+/// Maximum miner-chosen extra data in one coinbase
+pub const MAX_COINBASE_EXTRA_DATA: usize = 32;
+
+/// Miner of a block, as identified by its coinbase
+/// منتج الكتلة كما تحدده معاملة الكوين بيس
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct BlockProducer {
+    /// First payout address
+    pub address: PublicKey,
+    /// Extra data of the first coinbase, empty if the miner set none
+    pub extra_data: Vec<u8>,
+}
+
+impl BlockProducer {
+    /// Extra data as text, if it is non-empty UTF-8
+    pub fn tag(&self) -> Option<&str> {
+        if self.extra_data.is_empty() {
+            return None;
+        }
+        std::str::from_utf8(&self.extra_data).ok()
+    }
+}
+
+/// One payout address and its share of the coinbase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PayoutShare {
+    /// Address receiving the share
+    pub address: PublicKey,
+    /// Share of the coinbase in percent (1-100)
+    pub percent: u8,
+}
+
+/// Errors in a coinbase split configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoinbaseSplitError {
+    /// No payout addresses
+    Empty,
+    /// More payout addresses than [`MAX_COINBASE_OUTPUTS`]
+    TooManyOutputs(usize),
+    /// A share of 0 percent
+    ZeroShare,
+    /// The same address appears twice
+    DuplicateAddress(PublicKey),
+    /// Percentages do not add up to 100
+    InvalidTotal(u32),
+    /// Extra data longer than [`MAX_COINBASE_EXTRA_DATA`]
+    ExtraDataTooLong(usize),
+}
+
+impl std::fmt::Display for CoinbaseSplitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoinbaseSplitError::Empty => write!(f, "Coinbase split has no payout addresses"),
+            CoinbaseSplitError::TooManyOutputs(n) => write!(
+                f,
+                "Coinbase split has {} payout addresses (max {})",
+                n, MAX_COINBASE_OUTPUTS
+            ),
+            CoinbaseSplitError::ZeroShare => write!(f, "Coinbase split share must be above 0%"),
+            CoinbaseSplitError::DuplicateAddress(address) => {
+                write!(f, "Coinbase split repeats address {}", address.to_hex())
+            }
+            CoinbaseSplitError::InvalidTotal(total) => {
+                write!(f, "Coinbase split adds up to {}%, not 100%", total)
+            }
+            CoinbaseSplitError::ExtraDataTooLong(len) => write!(
+                f,
+                "Coinbase extra data is {} bytes (max {})",
+                len, MAX_COINBASE_EXTRA_DATA
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CoinbaseSplitError {}
+
+/// Validated split of the coinbase across payout addresses
+/// تقسيم مكافأة الكتلة بعد التحقق من صحته
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinbaseSplit {
+    shares: Vec<PayoutShare>,
+    extra_data: Vec<u8>,
+}
+
+impl CoinbaseSplit {
+    /// Validate payout shares: 1 to [`MAX_COINBASE_OUTPUTS`] distinct
+    /// addresses whose percentages add up to 100
+    pub fn new(shares: Vec<PayoutShare>) -> Result<Self, CoinbaseSplitError> {
+        if shares.is_empty() {
+            return Err(CoinbaseSplitError::Empty);
+        }
+        if shares.len() > MAX_COINBASE_OUTPUTS {
+            return Err(CoinbaseSplitError::TooManyOutputs(shares.len()));
+        }
+        for (i, share) in shares.iter().enumerate() {
+            if share.percent == 0 {
+                return Err(CoinbaseSplitError::ZeroShare);
+            }
+            if shares[..i].iter().any(|s| s.address == share.address) {
+                return Err(CoinbaseSplitError::DuplicateAddress(share.address));
+            }
+        }
+        let total: u32 = shares.iter().map(|s| s.percent as u32).sum();
+        if total != 100 {
+            return Err(CoinbaseSplitError::InvalidTotal(total));
+        }
+        Ok(Self {
+            shares,
+            extra_data: Vec::new(),
+        })
+    }
+
+    /// Pay the whole coinbase to one address
+    pub fn single(address: PublicKey) -> Self {
+        Self {
+            shares: vec![PayoutShare {
+                address,
+                percent: 100,
+            }],
+            extra_data: Vec::new(),
+        }
+    }
+
+    /// Append `extra_data` (e.g. a pool tag) to every coinbase
+    pub fn with_extra_data(mut self, extra_data: Vec<u8>) -> Result<Self, CoinbaseSplitError> {
+        if extra_data.len() > MAX_COINBASE_EXTRA_DATA {
+            return Err(CoinbaseSplitError::ExtraDataTooLong(extra_data.len()));
+        }
+        self.extra_data = extra_data;
+        Ok(self)
+    }
+
+    /// Payout shares in configuration order
+    pub fn shares(&self) -> &[PayoutShare] {
+        &self.shares
+    }
+
+    /// Extra data appended to every coinbase
+    pub fn extra_data(&self) -> &[u8] {
+        &self.extra_data
+    }
+
+    /// Divide `total` by the shares; rounding dust goes to the first address
+    /// and addresses whose share rounds to zero are left out
+    pub fn amounts(&self, total: u64) -> Vec<(PublicKey, u64)> {
+        let mut amounts: Vec<(PublicKey, u64)> = self
+            .shares
+            .iter()
+            .map(|share| {
+                let amount = total as u128 * share.percent as u128 / 100;
+                (share.address, amount as u64)
+            })
+            .collect();
+        let paid: u64 = amounts.iter().map(|(_, amount)| amount).sum();
+        amounts[0].1 += total - paid;
+
+        let first = amounts[0].0;
+        amounts.retain(|(address, amount)| *amount > 0 || *address == first);
+        amounts
+    }
+
+    /// Coinbase transactions paying the reward for `block_height` plus
+    /// `transaction_fees`, one per payout address
+    pub fn coinbase_transactions(
+        &self,
+        chain_id: u32,
+        block_height: u64,
+        transaction_fees: u64,
+    ) -> Result<Vec<Transaction>, TransactionError> {
+        let reward = calculate_block_reward(block_height);
+        self.coinbase_transactions_with_reward(chain_id, block_height, reward, transaction_fees)
+    }
+
+    /// Coinbase transactions paying `block_reward` plus `transaction_fees`,
+    /// for chains whose reward schedule differs from mainnet's
+    pub fn coinbase_transactions_with_reward(
+        &self,
+        chain_id: u32,
+        block_height: u64,
+        block_reward: u64,
+        transaction_fees: u64,
+    ) -> Result<Vec<Transaction>, TransactionError> {
+        let total = block_reward
+            .checked_add(transaction_fees)
+            .ok_or(TransactionError::RewardOverflow)?;
+
+        self.amounts(total)
+            .into_iter()
+            .map(|(address, amount)| {
+                let mut coinbase =
+                    Transaction::coinbase(chain_id, address, block_height, transaction_fees)?;
+                coinbase.amount = amount;
+                if let Some(data) = coinbase.data.as_mut() {
+                    data.extend_from_slice(&self.extra_data);
+                }
+                Ok(coinbase)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+    use crate::{Block, ChainParams, CHAIN_ID_MAINNET};
+
+    fn share(percent: u8) -> PayoutShare {
+        PayoutShare {
+            address: KeyPair::generate().public_key(),
+            percent,
+        }
+    }
+
+    #[test]
+    fn test_split_validation() {
+        assert!(CoinbaseSplit::new(vec![share(90), share(10)]).is_ok());
+        assert_eq!(CoinbaseSplit::new(vec![]), Err(CoinbaseSplitError::Empty));
+        assert_eq!(
+            CoinbaseSplit::new(vec![share(90), share(9)]),
+            Err(CoinbaseSplitError::InvalidTotal(99))
+        );
+        assert_eq!(
+            CoinbaseSplit::new(vec![share(100), share(0)]),
+            Err(CoinbaseSplitError::ZeroShare)
+        );
+        assert_eq!(
+            CoinbaseSplit::new((0..10).map(|_| share(10)).collect()),
+            Err(CoinbaseSplitError::TooManyOutputs(10))
+        );
+
+        let operator = share(50);
+        assert_eq!(
+            CoinbaseSplit::new(vec![operator, operator]),
+            Err(CoinbaseSplitError::DuplicateAddress(operator.address))
+        );
+    }
+
+    #[test]
+    fn test_split_amounts() {
+        let split = CoinbaseSplit::new(vec![share(34), share(33), share(33)]).unwrap();
+        let amounts = split.amounts(1_000);
+        assert_eq!(
+            amounts.iter().map(|(_, a)| *a).collect::<Vec<_>>(),
+            vec![340, 330, 330]
+        );
+
+        // Rounding dust goes to the first address
+        let amounts = split.amounts(101);
+        assert_eq!(
+            amounts.iter().map(|(_, a)| *a).collect::<Vec<_>>(),
+            vec![35, 33, 33]
+        );
+
+        // Shares too small to pay anything are dropped
+        let amounts = split.amounts(2);
+        assert_eq!(amounts, vec![(split.shares()[0].address, 2)]);
+    }
+
+    #[test]
+    fn test_split_coinbase_passes_block_validation() {
+        let split = CoinbaseSplit::new(vec![share(90), share(10)]).unwrap();
+        let fees = 1_234;
+        let coinbase = split
+            .coinbase_transactions(CHAIN_ID_MAINNET, 1, fees)
+            .unwrap();
+        assert_eq!(coinbase.len(), 2);
+        assert!(coinbase.iter().all(Transaction::is_coinbase));
+
+        let sender = KeyPair::generate();
+        let mut tx = Transaction::new(
+            sender.public_key(),
+            split.shares()[0].address,
+            1_000,
+            fees,
+            0,
+        );
+        tx.signature = sender.sign(&tx.signing_hash());
+
+        // Testnet has mainnet's economics with the split active from genesis
+        let testnet = ChainParams::testnet();
+        let mut transactions = coinbase.clone();
+        transactions.push(tx.clone());
+        let block = Block::new([0u8; 32], transactions, 16);
+        assert!(block.validate_coinbase_with_params(1, 0, &testnet).is_ok());
+
+        // Mainnet keeps one coinbase per block until the split activates
+        assert!(!ChainParams::mainnet().coinbase_split_active(1));
+        assert_eq!(
+            block.validate_coinbase(1, 0),
+            Err(crate::block::BlockError::MultipleCoinbase)
+        );
+
+        // A coinbase after a regular transaction is still rejected
+        let block = Block::new(
+            [0u8; 32],
+            vec![coinbase[0].clone(), tx, coinbase[1].clone()],
+            16,
+        );
+        assert_eq!(
+            block.validate_coinbase_with_params(1, 0, &testnet),
+            Err(crate::block::BlockError::MultipleCoinbase)
+        );
+    }
+
+    #[test]
+    fn test_extra_data_identifies_producer() {
+        let split = CoinbaseSplit::new(vec![share(90), share(10)])
+            .unwrap()
+            .with_extra_data(b"/syria-pool/".to_vec())
+            .unwrap();
+        let coinbase = split.coinbase_transactions(CHAIN_ID_MAINNET, 1, 0).unwrap();
+        assert!(coinbase
+            .iter()
+            .all(|tx| tx.coinbase_extra_data() == b"/syria-pool/"));
+
+        let block = Block::new([0u8; 32], coinbase, 16);
+        assert!(block
+            .validate_coinbase_with_params(1, 0, &ChainParams::testnet())
+            .is_ok());
+        let producer = block.producer().unwrap();
+        assert_eq!(producer.address, split.shares()[0].address);
+        assert_eq!(producer.tag(), Some("/syria-pool/"));
+
+        // Untagged coinbases identify the miner by address only
+        let plain = Transaction::coinbase(CHAIN_ID_MAINNET, producer.address, 1, 0).unwrap();
+        let block = Block::new([0u8; 32], vec![plain], 16);
+        assert_eq!(block.producer().unwrap().tag(), None);
+        assert!(Block::genesis().producer().is_none());
+    }
+
+    #[test]
+    fn test_extra_data_size_limit() {
+        let too_long = vec![b'x'; MAX_COINBASE_EXTRA_DATA + 1];
+        assert_eq!(
+            CoinbaseSplit::single(share(100).address).with_extra_data(too_long.clone()),
+            Err(CoinbaseSplitError::ExtraDataTooLong(
+                MAX_COINBASE_EXTRA_DATA + 1
+            ))
+        );
+
+        // Consensus rejects an oversized tag written by hand
+        let mut coinbase =
+            Transaction::coinbase(CHAIN_ID_MAINNET, share(100).address, 1, 0).unwrap();
+        coinbase.data.as_mut().unwrap().extend_from_slice(&too_long);
+        let block = Block::new([0u8; 32], vec![coinbase], 16);
+        assert_eq!(
+            block.validate_coinbase_with_params(1, 0, &ChainParams::testnet()),
+            Err(crate::block::BlockError::CoinbaseExtraDataTooLong(
+                MAX_COINBASE_EXTRA_DATA + 1
+            ))
+        );
+
+        // Mainnet blocks mined before the limit activates are still valid
+        assert!(!ChainParams::mainnet().coinbase_extra_data_active(1));
+        assert!(block.validate_coinbase(1, 0).is_ok());
+    }
+}
@@ -0,0 +1,179 @@
+//! Confirmation status shown to users
+//! حالة تأكيد المعاملات المعروضة للمستخدمين
+//!
+//! A transaction is `pending` while it waits in the mempool, `confirming
+//! (n/N)` once a block includes it, and `final` after `N` confirmations.
+//! Confirmations count the including block, so a transaction in the tip
+//! block has one. Block rewards use a longer target (coinbase maturity) than
+//! ordinary transfers. The wallet API, explorer and CLI all derive their
+//! display from [`ConfirmationStatus`] so they agree on when funds are final.
+
+use crate::{ChainParams, Transaction};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Confirmations a block at `block_height` has with the chain tip at
+/// `tip_height` (zero if the block is above the tip, e.g. during a reorg)
+pub fn confirmations(block_height: u64, tip_height: u64) -> u64 {
+    if block_height > tip_height {
+        0
+    } else {
+        tip_height - block_height + 1
+    }
+}
+
+/// Display state of a transaction relative to the live tip
+/// حالة المعاملة بالنسبة لرأس السلسلة
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ConfirmationStatus {
+    /// Waiting in the mempool
+    Pending,
+    /// Included in a block, short of the target
+    Confirming { confirmations: u64, target: u64 },
+    /// Reached the confirmation target
+    Final,
+}
+
+impl ConfirmationStatus {
+    /// Status of a transaction included at `block_height` (`None` if
+    /// unconfirmed) given the tip and the confirmation target
+    pub fn new(block_height: Option<u64>, tip_height: u64, target: u64) -> Self {
+        let Some(block_height) = block_height else {
+            return Self::Pending;
+        };
+        match confirmations(block_height, tip_height) {
+            0 => Self::Pending,
+            n if n >= target => Self::Final,
+            n => Self::Confirming {
+                confirmations: n,
+                target,
+            },
+        }
+    }
+
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Final)
+    }
+}
+
+impl fmt::Display for ConfirmationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Confirming {
+                confirmations,
+                target,
+            } => write!(f, "confirming ({}/{})", confirmations, target),
+            Self::Final => write!(f, "final"),
+        }
+    }
+}
+
+/// Confirmation targets for display
+/// أهداف التأكيد المستخدمة في العرض
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfirmationTargets {
+    /// Confirmations before a transfer is final
+    pub transaction: u64,
+    /// Confirmations before a block reward is final
+    pub coinbase: u64,
+}
+
+impl ConfirmationTargets {
+    /// Targets configured in the chain parameters
+    pub fn from_params(params: &ChainParams) -> Self {
+        Self {
+            transaction: params.confirmation_target,
+            coinbase: params.coinbase_maturity,
+        }
+    }
+
+    /// Target that applies to `tx`
+    pub fn target_for(&self, tx: &Transaction) -> u64 {
+        if tx.is_coinbase() {
+            self.coinbase
+        } else {
+            self.transaction
+        }
+    }
+
+    /// Status of `tx` included at `block_height` given the tip
+    pub fn status(
+        &self,
+        tx: &Transaction,
+        block_height: Option<u64>,
+        tip_height: u64,
+    ) -> ConfirmationStatus {
+        ConfirmationStatus::new(block_height, tip_height, self.target_for(tx))
+    }
+}
+
+impl Default for ConfirmationTargets {
+    fn default() -> Self {
+        Self::from_params(&ChainParams::mainnet())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{CHAIN_ID_MAINNET, COINBASE_MATURITY, CONFIRMATION_TARGET};
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_status_progression() {
+        assert_eq!(
+            ConfirmationStatus::new(None, 10, 6),
+            ConfirmationStatus::Pending
+        );
+        // Above the tip after a reorg
+        assert_eq!(
+            ConfirmationStatus::new(Some(11), 10, 6),
+            ConfirmationStatus::Pending
+        );
+
+        let tip_block = ConfirmationStatus::new(Some(10), 10, 6);
+        assert_eq!(tip_block.to_string(), "confirming (1/6)");
+        assert_eq!(
+            ConfirmationStatus::new(Some(6), 10, 6),
+            ConfirmationStatus::Confirming {
+                confirmations: 5,
+                target: 6
+            }
+        );
+        assert!(ConfirmationStatus::new(Some(5), 10, 6).is_final());
+        assert_eq!(
+            ConfirmationStatus::new(Some(10), 10, 0).to_string(),
+            "final"
+        );
+
+        assert_eq!(
+            serde_json::to_string(&tip_block).unwrap(),
+            r#"{"state":"confirming","confirmations":1,"target":6}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&ConfirmationStatus::Final).unwrap(),
+            r#"{"state":"final"}"#
+        );
+    }
+
+    #[test]
+    fn test_coinbase_uses_maturity() {
+        let targets = ConfirmationTargets::default();
+        let miner = KeyPair::generate().public_key();
+        let coinbase = Transaction::coinbase(CHAIN_ID_MAINNET, miner, 1, 0).unwrap();
+        let transfer = Transaction::new(miner, KeyPair::generate().public_key(), 10, 100, 0);
+
+        assert_eq!(targets.target_for(&coinbase), COINBASE_MATURITY);
+        assert!(targets
+            .status(&transfer, Some(1), CONFIRMATION_TARGET)
+            .is_final());
+        assert!(!targets
+            .status(&coinbase, Some(1), CONFIRMATION_TARGET)
+            .is_final());
+
+        let regtest = ConfirmationTargets::from_params(&ChainParams::regtest());
+        assert!(regtest.status(&transfer, Some(1), 1).is_final());
+    }
+}
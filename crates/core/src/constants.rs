@@ -7,6 +7,29 @@ pub const CHAIN_ID_MAINNET: u32 = 963; // Syria country code +963
 /// Chain identifier for testnet
 pub const CHAIN_ID_TESTNET: u32 = 963_000; // Testnet variant
 
+/// Chain identifier for local regression-test chains
+pub const CHAIN_ID_REGTEST: u32 = 963_001; // Regtest variant
+
+/// Mainnet height from which every transaction must carry the mainnet chain
+/// ID and multisig signatures must commit to it
+/// ارتفاع تفعيل الحماية من إعادة تشغيل المعاملات عبر الشبكات
+pub const REPLAY_PROTECTION_HEIGHT: u64 = 300_000;
+
+/// Mainnet height from which block difficulty must follow the retarget
+/// schedule
+/// ارتفاع تفعيل إلزام الكتل بجدول تعديل الصعوبة
+pub const DIFFICULTY_SCHEDULE_HEIGHT: u64 = 300_000;
+
+/// Mainnet height from which a block may split its reward across several
+/// coinbase outputs
+/// ارتفاع تفعيل توزيع مكافأة الكتلة على عدة مخرجات كوين بيس
+pub const COINBASE_SPLIT_HEIGHT: u64 = 300_000;
+
+/// Mainnet height from which coinbase extra data is limited to
+/// `MAX_COINBASE_EXTRA_DATA` bytes
+/// ارتفاع تفعيل حد حجم البيانات الإضافية في الكوين بيس
+pub const COINBASE_EXTRA_DATA_HEIGHT: u64 = 300_000;
+
 /// Target block time in seconds (2 minutes)
 /// وقت الكتلة المستهدف بالثواني (دقيقتان)
 pub const TARGET_BLOCK_TIME_SECS: u64 = 120;
@@ -21,6 +44,14 @@ pub const DIFFICULTY_ADJUSTMENT_INTERVAL: u32 = 100;
 /// Prevents 51% attacks from rewriting entire blockchain history
 pub const MAX_REORG_DEPTH: u64 = 100;
 
+/// Confirmations after which a transaction is shown as final
+/// عدد التأكيدات اللازمة لعرض المعاملة كنهائية
+pub const CONFIRMATION_TARGET: u64 = 6;
+
+/// Confirmations after which a block reward is shown as mature
+/// عدد التأكيدات اللازمة لنضج مكافأة الكتلة
+pub const COINBASE_MATURITY: u64 = 100;
+
 /// Genesis block timestamp (fixed for deterministic genesis)
 /// November 18, 2025 00:00:00 UTC - Official Launch Date
 pub const GENESIS_TIMESTAMP: u64 = 1763452800;
@@ -80,6 +111,10 @@ pub const UNITS_PER_LIRA: u64 = 1_000_000;
 /// رسوم المعاملة الدنيا
 pub const MIN_TRANSACTION_FEE: u64 = 100;
 
+/// Dust threshold: transfers below this amount are rejected by default (0.001 Lira)
+/// حد الغبار: التحويلات الأقل من هذا المبلغ تُرفض افتراضياً
+pub const DUST_THRESHOLD: u64 = 1_000;
+
 /// Maximum transaction size in bytes (100 KB)
 pub const MAX_TRANSACTION_SIZE: usize = 100_000;
 
@@ -192,4 +227,3 @@ mod tests {
         assert_eq!(calculate_block_reward(0), 0);
     }
 }
-
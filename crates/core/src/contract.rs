@@ -0,0 +1,330 @@
+//! Smart contract transactions
+//! معاملات العقود الذكية
+//!
+//! A contract deployment or call is an ordinary transfer whose data is
+//! [`CONTRACT_TAG`] followed by the bincode-encoded [`ContractCall`]. A
+//! deployment is sent to [`contract_address`] of the sender and nonce, and
+//! its amount becomes the contract's opening balance. A call is sent to the
+//! contract, and its amount is the value handed to the contract.
+//!
+//! The fee pays for the full gas limit up front at a price of at least
+//! [`MIN_GAS_PRICE`]; unused gas is not refunded. A contract that traps or
+//! runs out of gas keeps none of its storage writes or transfers and does
+//! not receive the value, but the fee is still paid. The gas limits of a
+//! block's contract transactions may add up to at most [`MAX_GAS_PER_BLOCK`].
+
+use crate::crypto::PublicKey;
+use crate::fees::FeeEstimate;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data prefix marking a contract transaction
+pub const CONTRACT_TAG: &[u8] = b"opensyria-contract-v1";
+/// Largest Wasm module a deployment may carry
+pub const MAX_CONTRACT_CODE_SIZE: usize = 64 * 1024;
+/// Most gas one transaction may buy
+pub const MAX_GAS_PER_TRANSACTION: u64 = 10_000_000;
+/// Most gas the transactions of one block may buy together
+pub const MAX_GAS_PER_BLOCK: u64 = 50_000_000;
+/// Longest exported method name a call may name
+pub const MAX_METHOD_NAME_LEN: usize = 64;
+/// Lowest gas price consensus accepts, in base units per 1000 gas
+pub const MIN_GAS_PRICE: u64 = 100;
+
+const CONTRACT_ADDRESS_DOMAIN: &[u8] = b"opensyria-contract-address-v1";
+
+/// Address of the contract deployed by `deployer` with `nonce`
+/// عنوان العقد المنشور
+pub fn contract_address(deployer: &PublicKey, nonce: u64) -> PublicKey {
+    let mut hasher = Sha256::new();
+    hasher.update(CONTRACT_ADDRESS_DOMAIN);
+    hasher.update(deployer.0);
+    hasher.update(nonce.to_le_bytes());
+    PublicKey(hasher.finalize().into())
+}
+
+/// Fee for `gas` at `gas_price` base units per 1000 gas
+pub fn gas_fee(gas: u64, gas_price: u64) -> u64 {
+    (gas as u128 * gas_price as u128).div_ceil(1000) as u64
+}
+
+/// Gas price suggested by the fee market
+/// سعر الغاز المقترح من سوق الرسوم
+///
+/// A unit of gas is priced like a byte of transaction weight, so the
+/// typical recent fee rate is used, never below [`MIN_GAS_PRICE`].
+pub fn suggested_gas_price(estimate: Option<&FeeEstimate>) -> u64 {
+    estimate.map_or(MIN_GAS_PRICE, |estimate| estimate.medium.max(MIN_GAS_PRICE))
+}
+
+/// Contract deployment or call carried in a transaction
+/// نشر عقد أو استدعاؤه
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub enum ContractCall {
+    /// Store `code` at the contract address and run its `init` export, if any
+    Deploy { code: Vec<u8>, gas_limit: u64 },
+    /// Run the exported `method` with `args` as input
+    Call {
+        method: String,
+        args: Vec<u8>,
+        gas_limit: u64,
+    },
+}
+
+impl ContractCall {
+    /// Gas bought by the transaction
+    pub fn gas_limit(&self) -> u64 {
+        match self {
+            ContractCall::Deploy { gas_limit, .. } | ContractCall::Call { gas_limit, .. } => {
+                *gas_limit
+            }
+        }
+    }
+
+    /// Check the payload is well formed
+    pub fn validate(&self) -> Result<(), ContractError> {
+        let gas_limit = self.gas_limit();
+        if gas_limit == 0 || gas_limit > MAX_GAS_PER_TRANSACTION {
+            return Err(ContractError::InvalidContract(format!(
+                "gas limit {} is outside 1..={}",
+                gas_limit, MAX_GAS_PER_TRANSACTION
+            )));
+        }
+        match self {
+            ContractCall::Deploy { code, .. } => {
+                if code.is_empty() || code.len() > MAX_CONTRACT_CODE_SIZE {
+                    return Err(ContractError::InvalidContract(format!(
+                        "code is {} bytes (max {})",
+                        code.len(),
+                        MAX_CONTRACT_CODE_SIZE
+                    )));
+                }
+            }
+            ContractCall::Call { method, .. } => {
+                if method.is_empty() || method.len() > MAX_METHOD_NAME_LEN {
+                    return Err(ContractError::InvalidContract(format!(
+                        "method name must be 1 to {} bytes",
+                        MAX_METHOD_NAME_LEN
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsigned transaction deploying this payload from `deployer`
+    /// إنشاء معاملة نشر العقد
+    pub fn deploy_transaction(
+        &self,
+        chain_id: u32,
+        deployer: PublicKey,
+        endowment: u64,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let contract = contract_address(&deployer, nonce);
+        self.transaction(chain_id, deployer, contract, endowment, fee, nonce)
+    }
+
+    /// Unsigned transaction sending this payload to `contract` with `value`
+    /// إنشاء معاملة استدعاء العقد
+    pub fn call_transaction(
+        &self,
+        chain_id: u32,
+        caller: PublicKey,
+        contract: PublicKey,
+        value: u64,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        self.transaction(chain_id, caller, contract, value, fee, nonce)
+    }
+
+    fn transaction(
+        &self,
+        chain_id: u32,
+        from: PublicKey,
+        to: PublicKey,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = CONTRACT_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("contract call encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, from, to, amount, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is a contract transaction, well formed or not
+    pub fn is_contract(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(CONTRACT_TAG))
+    }
+
+    /// Payload carried by `tx`, or `None` if it is not a contract transaction
+    ///
+    /// Also checks that a deployment goes to its contract address and that
+    /// the fee covers the gas limit at [`MIN_GAS_PRICE`].
+    pub fn from_transaction(tx: &Transaction) -> Option<Result<ContractCall, ContractError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(CONTRACT_TAG)?;
+        let decoded =
+            bincode::decode_from_slice::<ContractCall, _>(payload, bincode::config::standard());
+        let call = match decoded {
+            Ok((call, read)) if read == payload.len() => call,
+            _ => {
+                return Some(Err(ContractError::InvalidContract(
+                    "malformed contract encoding".to_string(),
+                )))
+            }
+        };
+        Some(call.validate().and_then(|_| {
+            if matches!(call, ContractCall::Deploy { .. })
+                && tx.to != contract_address(&tx.from, tx.nonce)
+            {
+                return Err(ContractError::InvalidContract(
+                    "deployment must be sent to its contract address".to_string(),
+                ));
+            }
+            let required = gas_fee(call.gas_limit(), MIN_GAS_PRICE);
+            if tx.fee < required {
+                return Err(ContractError::GasFeeTooLow {
+                    required,
+                    got: tx.fee,
+                });
+            }
+            Ok(call)
+        }))
+    }
+}
+
+/// Gas bought by `tx`; zero unless it is a well-formed contract transaction
+pub fn transaction_gas(tx: &Transaction) -> u64 {
+    match ContractCall::from_transaction(tx) {
+        Some(Ok(call)) => call.gas_limit(),
+        _ => 0,
+    }
+}
+
+/// Gas bought by all of a block's transactions together
+/// مجموع الغاز الذي تشتريه معاملات الكتلة
+pub fn block_gas(transactions: &[Transaction]) -> u64 {
+    transactions
+        .iter()
+        .map(transaction_gas)
+        .fold(0, u64::saturating_add)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractError {
+    InvalidContract(String),
+    GasFeeTooLow { required: u64, got: u64 },
+}
+
+impl std::fmt::Display for ContractError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContractError::InvalidContract(reason) => {
+                write!(f, "Invalid contract transaction: {}", reason)
+            }
+            ContractError::GasFeeTooLow { required, got } => write!(
+                f,
+                "Fee {} does not cover the gas limit (minimum {})",
+                got, required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ContractError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_deploy_and_call_round_trip() {
+        let deployer = KeyPair::generate().public_key();
+        let deploy = ContractCall::Deploy {
+            code: b"\0asm\x01\0\0\0".to_vec(),
+            gas_limit: 100_000,
+        };
+        let tx = deploy.deploy_transaction(963, deployer, 5_000, 10_000, 3);
+        assert_eq!(tx.to, contract_address(&deployer, 3));
+        assert!(ContractCall::is_contract(&tx));
+        assert_eq!(
+            ContractCall::from_transaction(&tx),
+            Some(Ok(deploy.clone()))
+        );
+
+        // A deployment sent anywhere else is rejected
+        let mut misdirected = tx.clone();
+        misdirected.to = KeyPair::generate().public_key();
+        assert!(matches!(
+            ContractCall::from_transaction(&misdirected),
+            Some(Err(ContractError::InvalidContract(_)))
+        ));
+
+        let call = ContractCall::Call {
+            method: "increment".to_string(),
+            args: vec![1, 2, 3],
+            gas_limit: 50_000,
+        };
+        let tx = call.call_transaction(963, deployer, tx.to, 0, 5_000, 4);
+        assert_eq!(ContractCall::from_transaction(&tx), Some(Ok(call)));
+
+        let plain = Transaction::new(deployer, deployer, 0, 100, 4);
+        assert_eq!(ContractCall::from_transaction(&plain), None);
+    }
+
+    #[test]
+    fn test_fee_must_cover_gas_limit() {
+        let caller = KeyPair::generate().public_key();
+        let call = ContractCall::Call {
+            method: "run".to_string(),
+            args: Vec::new(),
+            gas_limit: 1_000_000,
+        };
+        let required = gas_fee(1_000_000, MIN_GAS_PRICE);
+        assert_eq!(required, 100_000);
+
+        let tx = call.call_transaction(963, caller, caller, 0, required - 1, 0);
+        assert_eq!(
+            ContractCall::from_transaction(&tx),
+            Some(Err(ContractError::GasFeeTooLow {
+                required,
+                got: required - 1,
+            }))
+        );
+        let tx = call.call_transaction(963, caller, caller, 0, required, 0);
+        assert!(matches!(ContractCall::from_transaction(&tx), Some(Ok(_))));
+
+        let unbounded = ContractCall::Call {
+            method: "run".to_string(),
+            args: Vec::new(),
+            gas_limit: MAX_GAS_PER_TRANSACTION + 1,
+        };
+        assert!(unbounded.validate().is_err());
+    }
+
+    #[test]
+    fn test_gas_price_follows_fee_market() {
+        assert_eq!(suggested_gas_price(None), MIN_GAS_PRICE);
+        let estimate = FeeEstimate {
+            low: 200,
+            medium: 700,
+            high: 2_000,
+            blocks: 10,
+        };
+        assert_eq!(suggested_gas_price(Some(&estimate)), 700);
+        let quiet = FeeEstimate {
+            medium: 1,
+            ..estimate
+        };
+        assert_eq!(suggested_gas_price(Some(&quiet)), MIN_GAS_PRICE);
+    }
+}
@@ -148,6 +148,36 @@ impl PublicKey {
     }
 }
 
+/// Verify many signatures at once with ed25519 batch verification
+/// التحقق من عدة تواقيع دفعة واحدة
+///
+/// Each item is `(public key, message, signature)`. Several times faster
+/// than verifying one by one, but only tells whether *all* signatures are
+/// valid; callers fall back to [`PublicKey::verify`] to find the bad ones.
+/// Weak (small-order) keys are rejected outright, since the batch equation
+/// is more lenient towards them than single verification.
+pub fn verify_batch(items: &[(PublicKey, &[u8], &[u8])]) -> Result<(), CryptoError> {
+    let mut messages = Vec::with_capacity(items.len());
+    let mut signatures = Vec::with_capacity(items.len());
+    let mut keys = Vec::with_capacity(items.len());
+
+    for (public_key, message, signature) in items {
+        let verifying_key =
+            VerifyingKey::from_bytes(&public_key.0).map_err(|_| CryptoError::InvalidPublicKey)?;
+        if verifying_key.is_weak() {
+            return Err(CryptoError::InvalidPublicKey);
+        }
+        let signature =
+            Signature::from_slice(signature).map_err(|_| CryptoError::InvalidSignature)?;
+        messages.push(*message);
+        signatures.push(signature);
+        keys.push(verifying_key);
+    }
+
+    ed25519_dalek::verify_batch(&messages, &signatures, &keys)
+        .map_err(|_| CryptoError::VerificationFailed)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CryptoError {
     InvalidPublicKey,
@@ -182,6 +212,36 @@ mod tests {
         assert!(kp.public_key().verify(message, &signature).is_ok());
     }
 
+    #[test]
+    fn test_verify_batch() {
+        let keys: Vec<KeyPair> = (0..8).map(|_| KeyPair::generate()).collect();
+        let messages: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 16]).collect();
+        let mut signatures: Vec<Vec<u8>> = keys
+            .iter()
+            .zip(&messages)
+            .map(|(kp, message)| kp.sign(message))
+            .collect();
+
+        let check = |signatures: &[Vec<u8>]| -> Result<(), CryptoError> {
+            let items: Vec<(PublicKey, &[u8], &[u8])> = keys
+                .iter()
+                .zip(&messages)
+                .zip(signatures)
+                .map(|((kp, message), signature)| {
+                    (kp.public_key(), message.as_slice(), signature.as_slice())
+                })
+                .collect();
+            verify_batch(&items)
+        };
+        assert!(check(&signatures).is_ok());
+
+        // One bad signature fails the whole batch
+        signatures[3][0] ^= 1;
+        assert_eq!(check(&signatures), Err(CryptoError::VerificationFailed));
+
+        assert!(verify_batch(&[]).is_ok());
+    }
+
     #[test]
     fn test_public_key_hex_conversion() {
         let kp = KeyPair::generate();
@@ -0,0 +1,282 @@
+//! Hot key delegation
+//! تفويض المفتاح الساخن
+//!
+//! A cold account, such as a mining pool operator's, lets a hot key sign
+//! transfers from it with a delegation record. The cold key publishes the
+//! record in a zero-amount transfer to itself whose data is
+//! [`DELEGATION_TAG`] followed by the bincode-encoded
+//! `Option<HotKeyDelegation>`; `None` revokes the delegation.
+//!
+//! Transfers signed by the hot key name it in [`Transaction::delegate`] and
+//! together may move at most `daily_allowance` per
+//! [`BLOCKS_PER_DAY`](crate::policy::BLOCKS_PER_DAY) window, fees included.
+//! The hot key cannot change the delegation or the account's spending policy,
+//! so a leaked hot key costs at most one day's allowance.
+
+use crate::asset::AssetOperation;
+use crate::crypto::PublicKey;
+use crate::dex::DexOperation;
+use crate::oracle::OracleUpdate;
+use crate::policy::SpendingPolicy;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// Data prefix marking a delegation update transaction
+pub const DELEGATION_TAG: &[u8] = b"opensyria-delegation-v1";
+
+/// Hot key allowed to spend from a cold account
+/// مفتاح ساخن مفوّض بالإنفاق من حساب بارد
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct HotKeyDelegation {
+    /// Key that may sign transfers from the cold account
+    pub hot_key: PublicKey,
+    /// Most the hot key may move, fees included, per day window
+    pub daily_allowance: u64,
+}
+
+impl HotKeyDelegation {
+    /// Check the delegation is well formed for `account`
+    pub fn validate(&self, account: &PublicKey) -> Result<(), DelegationError> {
+        if self.hot_key.is_zero() || self.hot_key == *account {
+            return Err(DelegationError::InvalidDelegation(
+                "hot key must be a different, non-zero key".to_string(),
+            ));
+        }
+        if self.daily_allowance == 0 {
+            return Err(DelegationError::InvalidDelegation(
+                "daily allowance must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Unsigned transaction, to be signed by the cold key, delegating to this
+    /// hot key
+    /// إنشاء معاملة تفويض المفتاح الساخن
+    pub fn grant_transaction(
+        &self,
+        chain_id: u32,
+        account: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        Self::update_transaction(Some(self), chain_id, account, fee, nonce)
+    }
+
+    /// Unsigned transaction revoking `account`'s delegation
+    pub fn revoke_transaction(
+        chain_id: u32,
+        account: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        Self::update_transaction(None, chain_id, account, fee, nonce)
+    }
+
+    fn update_transaction(
+        delegation: Option<&Self>,
+        chain_id: u32,
+        account: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = DELEGATION_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(delegation.cloned(), bincode::config::standard())
+                .expect("delegation encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, account, account, 0, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is a delegation update, well formed or not
+    pub fn is_update(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(DELEGATION_TAG))
+    }
+
+    /// Delegation set by `tx` (`None` revokes), or `None` if it is not a
+    /// delegation update
+    pub fn from_update(
+        tx: &Transaction,
+    ) -> Option<Result<Option<HotKeyDelegation>, DelegationError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(DELEGATION_TAG)?;
+        if tx.to != tx.from || tx.amount != 0 {
+            return Some(Err(DelegationError::InvalidDelegation(
+                "delegation update must be a zero-amount transfer to self".to_string(),
+            )));
+        }
+        let decoded = bincode::decode_from_slice::<Option<HotKeyDelegation>, _>(
+            payload,
+            bincode::config::standard(),
+        );
+        Some(match decoded {
+            Ok((delegation, read)) if read == payload.len() => match &delegation {
+                Some(granted) => granted.validate(&tx.from).map(|_| delegation),
+                None => Ok(None),
+            },
+            _ => Err(DelegationError::InvalidDelegation(
+                "malformed delegation encoding".to_string(),
+            )),
+        })
+    }
+
+    /// Check a transaction signed by a delegate against the sender's
+    /// delegation, given what the hot key already moved in the current day
+    /// window; transactions without a delegate always pass
+    /// التحقق من معاملة موقعة بمفتاح مفوّض
+    pub fn authorize(
+        delegation: Option<&HotKeyDelegation>,
+        tx: &Transaction,
+        spent_today: u64,
+    ) -> Result<(), DelegationError> {
+        let Some(delegate) = tx.delegate else {
+            return Ok(());
+        };
+        let delegation = delegation
+            .filter(|delegation| delegation.hot_key == delegate)
+            .ok_or_else(|| DelegationError::NotDelegated(delegate.to_hex()))?;
+
+        // Asset balances and escrowed orders fall outside the allowance, so
+        // only the cold key moves them; oracle rates are published by the
+        // whitelisted key itself
+        if Self::is_update(tx)
+            || SpendingPolicy::is_update(tx)
+            || AssetOperation::is_asset(tx)
+            || DexOperation::is_dex(tx)
+            || OracleUpdate::is_oracle(tx)
+        {
+            return Err(DelegationError::ColdKeyRequired);
+        }
+
+        let requested = tx.debit(&tx.from);
+        if spent_today.saturating_add(requested) > delegation.daily_allowance {
+            return Err(DelegationError::AllowanceExceeded {
+                allowance: delegation.daily_allowance,
+                spent: spent_today,
+                requested,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelegationError {
+    InvalidDelegation(String),
+    NotDelegated(String),
+    ColdKeyRequired,
+    AllowanceExceeded {
+        allowance: u64,
+        spent: u64,
+        requested: u64,
+    },
+}
+
+impl std::fmt::Display for DelegationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DelegationError::InvalidDelegation(reason) => {
+                write!(f, "Invalid hot key delegation: {}", reason)
+            }
+            DelegationError::NotDelegated(key) => {
+                write!(f, "Key {} is not delegated by the sender", key)
+            }
+            DelegationError::ColdKeyRequired => {
+                write!(
+                    f,
+                    "Only the cold key can change delegations or policies or move assets"
+                )
+            }
+            DelegationError::AllowanceExceeded {
+                allowance,
+                spent,
+                requested,
+            } => write!(
+                f,
+                "Daily allowance {} exceeded: {} spent, {} requested",
+                allowance, spent, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DelegationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_grant_and_revoke_round_trip() {
+        let cold = KeyPair::generate().public_key();
+        let delegation = HotKeyDelegation {
+            hot_key: KeyPair::generate().public_key(),
+            daily_allowance: 50_000_000,
+        };
+
+        let grant = delegation.grant_transaction(963, cold, 100, 0);
+        assert!(HotKeyDelegation::is_update(&grant));
+        assert_eq!(
+            HotKeyDelegation::from_update(&grant),
+            Some(Ok(Some(delegation.clone())))
+        );
+
+        let revoke = HotKeyDelegation::revoke_transaction(963, cold, 100, 1);
+        assert_eq!(HotKeyDelegation::from_update(&revoke), Some(Ok(None)));
+
+        let to_self = HotKeyDelegation {
+            hot_key: cold,
+            ..delegation
+        };
+        assert!(matches!(
+            HotKeyDelegation::from_update(&to_self.grant_transaction(963, cold, 100, 2)),
+            Some(Err(DelegationError::InvalidDelegation(_)))
+        ));
+    }
+
+    #[test]
+    fn test_allowance_bounds_hot_key() {
+        let cold = KeyPair::generate().public_key();
+        let hot = KeyPair::generate().public_key();
+        let miner = KeyPair::generate().public_key();
+        let delegation = HotKeyDelegation {
+            hot_key: hot,
+            daily_allowance: 10_000,
+        };
+
+        let plain = Transaction::new(cold, miner, 1_000_000, 100, 0);
+        assert_eq!(HotKeyDelegation::authorize(None, &plain, 0), Ok(()));
+
+        let payout = Transaction::new(cold, miner, 4_900, 100, 0).with_delegate(hot);
+        assert_eq!(
+            HotKeyDelegation::authorize(Some(&delegation), &payout, 5_000),
+            Ok(())
+        );
+        assert_eq!(
+            HotKeyDelegation::authorize(Some(&delegation), &payout, 5_001),
+            Err(DelegationError::AllowanceExceeded {
+                allowance: 10_000,
+                spent: 5_001,
+                requested: 5_000,
+            })
+        );
+        assert!(matches!(
+            HotKeyDelegation::authorize(None, &payout, 0),
+            Err(DelegationError::NotDelegated(_))
+        ));
+
+        // The hot key cannot lift its own limits
+        let widen = HotKeyDelegation {
+            hot_key: hot,
+            daily_allowance: u64::MAX,
+        }
+        .grant_transaction(963, cold, 100, 0)
+        .with_delegate(hot);
+        assert_eq!(
+            HotKeyDelegation::authorize(Some(&delegation), &widen, 0),
+            Err(DelegationError::ColdKeyRequired)
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! Governance proposal deposits
+//! ودائع اقتراحات الحوكمة
+//!
+//! A proposer locks the deposit with a signed transfer of the deposit amount
+//! to [`escrow_account`] whose data is [`DEPOSIT_TAG`] followed by the
+//! bincode-encoded [`DepositOperation::Escrow`]. The escrow is identified by
+//! that transaction's id and names the height before which it cannot be
+//! settled; governance only accepts escrows held until voting closes.
+//!
+//! Once voting closes, the node running governance settles the escrow with
+//! an unsigned settlement transaction from the escrow account: a refund pays
+//! the amount back to the proposer, a burn destroys it. Settlements carry no
+//! fee and take the escrow account's nonces. Consensus does not see
+//! governance, so it does not decide between the two; it only checks that a
+//! settlement names an open, released escrow, moves exactly its amount and
+//! pays it to no one but its proposer, once. Which of the two a block
+//! carries is trusted to the miners, as with any transaction they choose.
+
+use crate::crypto::PublicKey;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data prefix marking a deposit operation
+pub const DEPOSIT_TAG: &[u8] = b"opensyria-deposit-v1";
+
+const ESCROW_ACCOUNT_DOMAIN: &[u8] = b"opensyria-deposit-escrow-v1";
+
+/// Account holding escrowed deposits; derived from a hash, so no one holds
+/// its key
+/// حساب الضمان الذي يحتجز الودائع
+pub fn escrow_account() -> PublicKey {
+    PublicKey(Sha256::digest(ESCROW_ACCOUNT_DOMAIN).into())
+}
+
+/// Deposit operation carried in a transaction
+/// عملية على وديعة اقتراح
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub enum DepositOperation {
+    /// Lock the transaction's amount in escrow until `release_height`;
+    /// signed by the proposer
+    Escrow { release_height: u64 },
+    /// Pay the deposit locked by transaction `escrow` back to its proposer
+    Refund { escrow: [u8; 32] },
+    /// Destroy the deposit locked by transaction `escrow`
+    Burn { escrow: [u8; 32] },
+}
+
+impl DepositOperation {
+    /// Unsigned transaction escrowing `amount` from `proposer` until
+    /// `release_height`
+    /// إنشاء معاملة حجز وديعة
+    pub fn escrow(
+        chain_id: u32,
+        proposer: PublicKey,
+        amount: u64,
+        release_height: u64,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let operation = DepositOperation::Escrow { release_height };
+        Transaction::new_with_chain_id(chain_id, proposer, escrow_account(), amount, fee, nonce)
+            .with_data(operation.data())
+    }
+
+    /// Settlement transaction moving `amount` out of escrow
+    /// إنشاء معاملة تسوية وديعة
+    ///
+    /// A refund pays `proposer`; a burn sends the amount back to the escrow
+    /// account, which consensus then destroys. `nonce` is the escrow
+    /// account's next nonce.
+    pub fn settlement(
+        &self,
+        chain_id: u32,
+        proposer: PublicKey,
+        amount: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let to = match self {
+            DepositOperation::Refund { .. } => proposer,
+            _ => escrow_account(),
+        };
+        Transaction::new_with_chain_id(chain_id, escrow_account(), to, amount, 0, nonce)
+            .with_data(self.data())
+    }
+
+    fn data(&self) -> Vec<u8> {
+        let mut data = DEPOSIT_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("deposit operation encoding cannot fail"),
+        );
+        data
+    }
+
+    /// Whether `tx` is a deposit operation, well formed or not
+    pub fn is_deposit(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(DEPOSIT_TAG))
+    }
+
+    /// Whether `tx` is an unsigned settlement from the escrow account
+    pub fn is_settlement(tx: &Transaction) -> bool {
+        tx.from == escrow_account() && tx.signature.is_empty() && Self::is_deposit(tx)
+    }
+
+    /// Operation carried by `tx`, or `None` if it is not a deposit operation
+    pub fn from_transaction(tx: &Transaction) -> Option<Result<DepositOperation, DepositError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(DEPOSIT_TAG)?;
+        let invalid = |reason: &str| Some(Err(DepositError::InvalidOperation(reason.to_string())));
+        let decoded =
+            bincode::decode_from_slice::<DepositOperation, _>(payload, bincode::config::standard());
+        let operation = match decoded {
+            Ok((operation, read)) if read == payload.len() => operation,
+            _ => return invalid("malformed deposit encoding"),
+        };
+        if tx.amount == 0 {
+            return invalid("deposit amount must be positive");
+        }
+
+        let escrow = escrow_account();
+        match operation {
+            DepositOperation::Escrow { .. } => {
+                if tx.to != escrow || tx.from == escrow {
+                    return invalid("an escrow is a transfer to the escrow account");
+                }
+            }
+            DepositOperation::Refund { .. } | DepositOperation::Burn { .. } => {
+                if !Self::is_settlement(tx) || tx.fee != 0 {
+                    return invalid("a settlement is an unsigned, fee-less escrow transfer");
+                }
+                let burn = matches!(operation, DepositOperation::Burn { .. });
+                if burn != (tx.to == escrow) {
+                    return invalid("only a burn pays the escrow account");
+                }
+            }
+        }
+        Some(Ok(operation))
+    }
+}
+
+/// An escrow as recorded in state
+/// وديعة محتجزة كما هي مسجلة في الحالة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct DepositEscrow {
+    pub proposer: PublicKey,
+    pub amount: u64,
+    /// First height a settlement may be included at
+    pub release_height: u64,
+    /// How it was closed, once it is
+    pub settlement: Option<DepositSettlement>,
+}
+
+/// How an escrow was closed
+/// طريقة تسوية الوديعة
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub enum DepositSettlement {
+    Refunded,
+    Burned,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DepositError {
+    InvalidOperation(String),
+    UnknownEscrow(String),
+    WrongSettlement(String),
+}
+
+impl std::fmt::Display for DepositError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DepositError::InvalidOperation(reason) => {
+                write!(f, "Invalid deposit operation: {}", reason)
+            }
+            DepositError::UnknownEscrow(id) => write!(f, "No open deposit escrow {}", id),
+            DepositError::WrongSettlement(reason) => {
+                write!(f, "Settlement does not match its escrow: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DepositError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_escrow_and_settlement_shapes() {
+        let proposer = KeyPair::generate().public_key();
+        let escrow = DepositOperation::escrow(963, proposer, 1_000, 500, 100, 0);
+        assert_eq!(escrow.to, escrow_account());
+        assert_eq!(
+            DepositOperation::from_transaction(&escrow),
+            Some(Ok(DepositOperation::Escrow {
+                release_height: 500
+            }))
+        );
+        assert!(!DepositOperation::is_settlement(&escrow));
+
+        let id = escrow.hash();
+        let refund = DepositOperation::Refund { escrow: id }.settlement(963, proposer, 1_000, 0);
+        assert_eq!(refund.to, proposer);
+        assert!(DepositOperation::is_settlement(&refund));
+        assert!(refund.validate_fee().is_ok());
+        assert_eq!(
+            DepositOperation::from_transaction(&refund),
+            Some(Ok(DepositOperation::Refund { escrow: id }))
+        );
+        let burn = DepositOperation::Burn { escrow: id }.settlement(963, proposer, 1_000, 1);
+        assert_eq!(burn.to, escrow_account());
+        assert!(DepositOperation::from_transaction(&burn).unwrap().is_ok());
+
+        // Settlements must be unsigned and free, and only burns pay escrow
+        let mut signed = refund.clone();
+        signed.signature = vec![1; 64];
+        assert!(DepositOperation::from_transaction(&signed)
+            .unwrap()
+            .is_err());
+        let mut paid = refund.clone();
+        paid.fee = 1;
+        assert!(DepositOperation::from_transaction(&paid).unwrap().is_err());
+        let mut misrouted = burn.clone();
+        misrouted.to = proposer;
+        assert!(DepositOperation::from_transaction(&misrouted)
+            .unwrap()
+            .is_err());
+        assert!(DepositOperation::from_transaction(&Transaction::new(
+            proposer, proposer, 1, 100, 0
+        ))
+        .is_none());
+    }
+}
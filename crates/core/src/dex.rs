@@ -0,0 +1,353 @@
+//! On-chain order book for trading assets against SYL
+//! دفتر أوامر على السلسلة لتداول الأصول مقابل الليرة
+//!
+//! A DEX operation is a zero-amount transfer to self whose data is
+//! [`DEX_TAG`] followed by the bincode-encoded [`DexOperation`]. Placing an
+//! order locks what it offers in the [`escrow_address`]: a buy order locks
+//! `price * amount` SYL, a sell order locks `amount` of the asset.
+//!
+//! Orders are matched while the block is applied, in price-time priority:
+//! the best price first, and among equal prices the order placed first. A
+//! trade executes at the resting (maker) order's price, so a buyer who bids
+//! above the best ask gets the difference back. Whatever is not filled
+//! rests on the book until it is filled or cancelled, except after
+//! [`MAX_FILLS_PER_ORDER`] fills: matching then stops and the rest of the
+//! order is cancelled, so one transaction cannot sweep an unbounded number
+//! of resting orders.
+
+use crate::asset::AssetId;
+use crate::crypto::PublicKey;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data prefix marking a DEX operation
+pub const DEX_TAG: &[u8] = b"opensyria-dex-v1";
+
+/// Resting orders one placement may fill before the rest of it is cancelled
+pub const MAX_FILLS_PER_ORDER: usize = 64;
+
+const ESCROW_DOMAIN: &[u8] = b"opensyria-dex-escrow-v1";
+
+/// Account holding the funds locked by open orders
+/// حساب الضمان للأوامر المفتوحة
+///
+/// Derived from a hash, so no one holds its key; only order matching and
+/// cancellation move funds out of it.
+pub fn escrow_address() -> PublicKey {
+    PublicKey(Sha256::digest(ESCROW_DOMAIN).into())
+}
+
+/// SYL paid for `amount` units at `price`, if it fits a u64
+pub fn quote(price: u64, amount: u64) -> Option<u64> {
+    price.checked_mul(amount)
+}
+
+/// Identifier of an order: the hash of the transaction that placed it
+/// معرّف الأمر
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct OrderId(pub [u8; 32]);
+
+impl OrderId {
+    pub fn of(tx: &Transaction) -> Self {
+        OrderId(tx.hash())
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self, DexError> {
+        let bytes = hex::decode(s)
+            .map_err(|_| DexError::InvalidOperation("order id is not hex".to_string()))?;
+        let id: [u8; 32] = bytes
+            .try_into()
+            .map_err(|_| DexError::InvalidOperation("order id must be 32 bytes".to_string()))?;
+        Ok(OrderId(id))
+    }
+}
+
+/// Which side of the book an order is on
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderSide {
+    /// Pay SYL for the asset
+    Buy,
+    /// Pay the asset for SYL
+    Sell,
+}
+
+impl OrderSide {
+    /// Side an incoming order matches against
+    pub fn opposite(self) -> Self {
+        match self {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        }
+    }
+
+    /// Whether an order on this side at `limit` trades with a resting order
+    /// at `resting`
+    pub fn crosses(self, limit: u64, resting: u64) -> bool {
+        match self {
+            OrderSide::Buy => resting <= limit,
+            OrderSide::Sell => resting >= limit,
+        }
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    Open,
+    Filled,
+    Cancelled,
+}
+
+/// An order as recorded in state
+/// أمر في دفتر الأوامر
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct Order {
+    pub id: OrderId,
+    pub owner: PublicKey,
+    pub asset: AssetId,
+    pub side: OrderSide,
+    /// SYL base units per asset base unit
+    pub price: u64,
+    /// Asset units ordered
+    pub amount: u64,
+    /// Asset units not yet filled
+    pub remaining: u64,
+    /// Block and position in the block of the placing transaction, for time
+    /// priority
+    pub height: u64,
+    pub index: u32,
+    pub status: OrderStatus,
+}
+
+impl Order {
+    /// SYL still locked by a buy order, or asset units by a sell order
+    pub fn locked(&self) -> u64 {
+        match self.side {
+            // Placing checked that price * amount fits
+            OrderSide::Buy => self.price * self.remaining,
+            OrderSide::Sell => self.remaining,
+        }
+    }
+}
+
+/// A fill between a resting order and an incoming one
+/// صفقة منفذة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct Trade {
+    pub asset: AssetId,
+    /// Execution price, the maker's limit
+    pub price: u64,
+    pub amount: u64,
+    /// Resting order that was filled
+    pub maker: OrderId,
+    /// Incoming order that filled it
+    pub taker: OrderId,
+    pub buyer: PublicKey,
+    pub seller: PublicKey,
+    pub height: u64,
+}
+
+/// DEX operation carried in a transaction
+/// عملية على دفتر الأوامر
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub enum DexOperation {
+    /// Place a limit order for `amount` units of `asset` at `price`
+    Place {
+        asset: AssetId,
+        side: OrderSide,
+        price: u64,
+        amount: u64,
+    },
+    /// Cancel the sender's open order and release what it still locks
+    Cancel { order: OrderId },
+}
+
+impl DexOperation {
+    /// Check the operation is well formed on its own
+    pub fn validate(&self) -> Result<(), DexError> {
+        if let DexOperation::Place { price, amount, .. } = self {
+            if *price == 0 || *amount == 0 {
+                return Err(DexError::InvalidOperation(
+                    "price and amount must be positive".to_string(),
+                ));
+            }
+            if quote(*price, *amount).is_none() {
+                return Err(DexError::InvalidOperation(
+                    "order value overflows".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Unsigned transaction performing this operation as `sender`
+    /// إنشاء معاملة عملية التداول
+    pub fn transaction(
+        &self,
+        chain_id: u32,
+        sender: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = DEX_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("dex operation encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, sender, sender, 0, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is a DEX operation, well formed or not
+    pub fn is_dex(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(DEX_TAG))
+    }
+
+    /// Operation carried by `tx`, or `None` if it is not a DEX operation
+    pub fn from_transaction(tx: &Transaction) -> Option<Result<DexOperation, DexError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(DEX_TAG)?;
+        if tx.to != tx.from || tx.amount != 0 {
+            return Some(Err(DexError::InvalidOperation(
+                "dex operation must be a zero-amount transfer to self".to_string(),
+            )));
+        }
+        let decoded =
+            bincode::decode_from_slice::<DexOperation, _>(payload, bincode::config::standard());
+        Some(match decoded {
+            Ok((operation, read)) if read == payload.len() => {
+                operation.validate().map(|_| operation)
+            }
+            _ => Err(DexError::InvalidOperation(
+                "malformed dex encoding".to_string(),
+            )),
+        })
+    }
+
+    /// SYL that `tx` locks in escrow, on top of its fee
+    pub fn escrowed_syl(tx: &Transaction) -> u64 {
+        match DexOperation::from_transaction(tx) {
+            Some(Ok(DexOperation::Place {
+                side: OrderSide::Buy,
+                price,
+                amount,
+                ..
+            })) => quote(price, amount).unwrap_or(u64::MAX),
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexError {
+    InvalidOperation(String),
+    UnknownOrder(String),
+    NotOrderOwner(String),
+    OrderClosed(String),
+}
+
+impl std::fmt::Display for DexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DexError::InvalidOperation(reason) => write!(f, "Invalid dex operation: {}", reason),
+            DexError::UnknownOrder(id) => write!(f, "Unknown order {}", id),
+            DexError::NotOrderOwner(key) => write!(f, "Key {} does not own the order", key),
+            DexError::OrderClosed(id) => write!(f, "Order {} is no longer open", id),
+        }
+    }
+}
+
+impl std::error::Error for DexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn place(side: OrderSide, price: u64, amount: u64) -> DexOperation {
+        DexOperation::Place {
+            asset: AssetId([7; 32]),
+            side,
+            price,
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_operation_round_trip() {
+        let sender = KeyPair::generate().public_key();
+        let tx = place(OrderSide::Buy, 250, 40).transaction(963, sender, 100, 2);
+        assert!(DexOperation::is_dex(&tx));
+        assert_eq!(
+            DexOperation::from_transaction(&tx),
+            Some(Ok(place(OrderSide::Buy, 250, 40)))
+        );
+        assert_eq!(DexOperation::escrowed_syl(&tx), 10_000);
+
+        let id = OrderId::of(&tx);
+        assert_eq!(OrderId::from_hex(&id.to_hex()), Ok(id));
+
+        let sell = place(OrderSide::Sell, 250, 40).transaction(963, sender, 100, 3);
+        assert_eq!(DexOperation::escrowed_syl(&sell), 0);
+
+        let plain = Transaction::new(sender, sender, 0, 100, 4);
+        assert_eq!(DexOperation::from_transaction(&plain), None);
+    }
+
+    #[test]
+    fn test_invalid_orders_rejected() {
+        assert!(place(OrderSide::Buy, 0, 10).validate().is_err());
+        assert!(place(OrderSide::Sell, 10, 0).validate().is_err());
+        assert!(place(OrderSide::Buy, u64::MAX, 2).validate().is_err());
+
+        let sender = KeyPair::generate().public_key();
+        let mut paid = place(OrderSide::Sell, 10, 10).transaction(963, sender, 100, 0);
+        paid.amount = 1;
+        assert!(matches!(
+            DexOperation::from_transaction(&paid),
+            Some(Err(DexError::InvalidOperation(_)))
+        ));
+    }
+
+    #[test]
+    fn test_sides_cross() {
+        assert!(OrderSide::Buy.crosses(100, 90));
+        assert!(OrderSide::Buy.crosses(100, 100));
+        assert!(!OrderSide::Buy.crosses(100, 101));
+        assert!(OrderSide::Sell.crosses(100, 110));
+        assert!(!OrderSide::Sell.crosses(100, 99));
+        assert_eq!(OrderSide::Buy.opposite(), OrderSide::Sell);
+    }
+}
@@ -0,0 +1,329 @@
+//! Machine-readable error codes shared by every crate and API
+//! رموز الأخطاء المقروءة آلياً المشتركة بين الحزم والواجهات
+//!
+//! Error messages are written for people and change between releases.
+//! Clients branch on an [`ErrorCode`] instead: a stable string such as
+//! `fee_too_low`, a stable number, a [`ErrorCategory`] and whether retrying
+//! the same request later can succeed. Every code is listed in [`REGISTRY`].
+//!
+//! Numbers are grouped by where the error comes from:
+//!
+//! | Range | Source |
+//! |-------|--------|
+//! | 1xxx  | API requests and generic failures |
+//! | 2xxx  | transaction validation (mempool and block checks) |
+//! | 3xxx  | mempool admission policy |
+//! | 4xxx  | chain and storage |
+//! | 5xxx  | governance |
+//!
+//! Codes are never renumbered or reused; a retired code stays reserved.
+
+use serde::Serialize;
+
+/// Broad kind of failure, for clients that do not know every code
+/// الفئة العامة للخطأ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// The request is malformed; fix it before sending it again
+    InvalidRequest,
+    /// The request is well-formed but breaks a consensus or validation rule
+    Rejected,
+    /// Refused by local node policy; another node may accept it
+    Policy,
+    /// The requested object does not exist
+    NotFound,
+    /// The request conflicts with current state
+    Conflict,
+    /// The node cannot serve the request right now
+    Unavailable,
+    /// The node failed internally
+    Internal,
+}
+
+/// Stable identity of an error
+/// الهوية الثابتة للخطأ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub struct ErrorCode {
+    /// Stable string code, e.g. `fee_too_low`
+    pub code: &'static str,
+    /// Stable numeric code
+    pub number: u16,
+    pub category: ErrorCategory,
+    /// Whether the same request may succeed if sent again later
+    pub retryable: bool,
+}
+
+impl ErrorCode {
+    const fn new(
+        code: &'static str,
+        number: u16,
+        category: ErrorCategory,
+        retryable: bool,
+    ) -> Self {
+        Self {
+            code,
+            number,
+            category,
+            retryable,
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.code, self.number)
+    }
+}
+
+/// Errors that carry a registered code
+/// الأخطاء التي تحمل رمزاً مسجلاً
+pub trait CodedError: std::error::Error {
+    fn error_code(&self) -> ErrorCode;
+}
+
+use ErrorCategory::*;
+
+// API requests and generic failures
+pub const INVALID_REQUEST: ErrorCode =
+    ErrorCode::new("invalid_request", 1000, InvalidRequest, false);
+pub const UNAUTHORIZED: ErrorCode = ErrorCode::new("unauthorized", 1001, InvalidRequest, false);
+pub const FORBIDDEN: ErrorCode = ErrorCode::new("forbidden", 1002, InvalidRequest, false);
+pub const NOT_FOUND: ErrorCode = ErrorCode::new("not_found", 1003, NotFound, false);
+pub const CONFLICT: ErrorCode = ErrorCode::new("conflict", 1004, Conflict, false);
+pub const PAYLOAD_TOO_LARGE: ErrorCode =
+    ErrorCode::new("payload_too_large", 1005, InvalidRequest, false);
+pub const RATE_LIMITED: ErrorCode = ErrorCode::new("rate_limited", 1006, Unavailable, true);
+pub const UNAVAILABLE: ErrorCode = ErrorCode::new("unavailable", 1007, Unavailable, true);
+pub const INTERNAL_ERROR: ErrorCode = ErrorCode::new("internal_error", 1008, Internal, false);
+pub const STORAGE_ERROR: ErrorCode = ErrorCode::new("storage_error", 1009, Internal, true);
+
+// Transaction validation
+pub const INVALID_SIGNATURE: ErrorCode = ErrorCode::new("invalid_signature", 2000, Rejected, false);
+pub const INSUFFICIENT_BALANCE: ErrorCode =
+    ErrorCode::new("insufficient_balance", 2001, Rejected, false);
+pub const INVALID_NONCE: ErrorCode = ErrorCode::new("invalid_nonce", 2002, Rejected, false);
+pub const NONCE_TOO_FAR: ErrorCode = ErrorCode::new("nonce_too_far", 2003, Policy, true);
+pub const WRONG_CHAIN_ID: ErrorCode = ErrorCode::new("wrong_chain_id", 2004, Rejected, false);
+pub const ACCOUNT_FROZEN: ErrorCode = ErrorCode::new("account_frozen", 2005, Rejected, false);
+pub const POLICY_VIOLATION: ErrorCode = ErrorCode::new("policy_violation", 2006, Rejected, false);
+pub const DELEGATION_VIOLATION: ErrorCode =
+    ErrorCode::new("delegation_violation", 2007, Rejected, false);
+pub const INVALID_CONTRACT: ErrorCode = ErrorCode::new("invalid_contract", 2008, Rejected, false);
+pub const INVALID_ASSET: ErrorCode = ErrorCode::new("invalid_asset", 2009, Rejected, false);
+pub const INVALID_ORDER: ErrorCode = ErrorCode::new("invalid_order", 2010, Rejected, false);
+pub const INVALID_ORACLE_UPDATE: ErrorCode =
+    ErrorCode::new("invalid_oracle_update", 2011, Rejected, false);
+pub const EXPIRED: ErrorCode = ErrorCode::new("expired", 2012, Rejected, false);
+pub const BALANCE_OVERFLOW: ErrorCode = ErrorCode::new("balance_overflow", 2013, Rejected, false);
+pub const INVALID_DEPOSIT: ErrorCode = ErrorCode::new("invalid_deposit", 2014, Rejected, false);
+
+// Mempool admission policy
+pub const DUPLICATE: ErrorCode = ErrorCode::new("duplicate", 3000, Conflict, false);
+pub const MEMPOOL_FULL: ErrorCode = ErrorCode::new("mempool_full", 3001, Unavailable, true);
+pub const SENDER_LIMIT: ErrorCode = ErrorCode::new("sender_limit", 3002, Policy, true);
+pub const FEE_TOO_LOW: ErrorCode = ErrorCode::new("fee_too_low", 3003, Policy, false);
+pub const DUST_AMOUNT: ErrorCode = ErrorCode::new("dust_amount", 3004, Policy, false);
+pub const RELAY_POLICY: ErrorCode = ErrorCode::new("relay_policy", 3005, Policy, false);
+
+// Chain and storage
+pub const DATABASE_ERROR: ErrorCode = ErrorCode::new("database_error", 4000, Internal, true);
+pub const SERIALIZATION_ERROR: ErrorCode =
+    ErrorCode::new("serialization_error", 4001, Internal, false);
+pub const BLOCK_NOT_FOUND: ErrorCode = ErrorCode::new("block_not_found", 4002, NotFound, false);
+pub const INVALID_CHAIN: ErrorCode = ErrorCode::new("invalid_chain", 4003, Rejected, false);
+pub const INVALID_PROOF_OF_WORK: ErrorCode =
+    ErrorCode::new("invalid_proof_of_work", 4004, Rejected, false);
+pub const UNEXPECTED_DIFFICULTY: ErrorCode =
+    ErrorCode::new("unexpected_difficulty", 4005, Rejected, false);
+pub const INVALID_BLOCK_TRANSACTION: ErrorCode =
+    ErrorCode::new("invalid_block_transaction", 4006, Rejected, false);
+pub const INVALID_MERKLE_ROOT: ErrorCode =
+    ErrorCode::new("invalid_merkle_root", 4007, Rejected, false);
+pub const TIMESTAMP_TOO_FAR_FUTURE: ErrorCode =
+    ErrorCode::new("timestamp_too_far_future", 4008, Rejected, true);
+pub const TIMESTAMP_DECREASED: ErrorCode =
+    ErrorCode::new("timestamp_decreased", 4009, Rejected, false);
+pub const MISSING_COINBASE: ErrorCode = ErrorCode::new("missing_coinbase", 4010, Rejected, false);
+pub const INVALID_COINBASE_AMOUNT: ErrorCode =
+    ErrorCode::new("invalid_coinbase_amount", 4011, Rejected, false);
+pub const MULTIPLE_COINBASE: ErrorCode = ErrorCode::new("multiple_coinbase", 4012, Rejected, false);
+pub const CHECKPOINT_MISMATCH: ErrorCode =
+    ErrorCode::new("checkpoint_mismatch", 4013, Rejected, false);
+pub const REORG_TOO_DEEP: ErrorCode = ErrorCode::new("reorg_too_deep", 4014, Rejected, false);
+pub const COLUMN_FAMILY_MISSING: ErrorCode =
+    ErrorCode::new("column_family_missing", 4015, Internal, false);
+pub const AUDIT_ERROR: ErrorCode = ErrorCode::new("audit_error", 4016, Internal, false);
+pub const ANCHOR_ERROR: ErrorCode = ErrorCode::new("anchor_error", 4017, Internal, false);
+pub const INVALID_SNAPSHOT: ErrorCode = ErrorCode::new("invalid_snapshot", 4018, Rejected, false);
+pub const CONTRACT_ERROR: ErrorCode = ErrorCode::new("contract_error", 4019, Rejected, false);
+pub const REPLICATION_ERROR: ErrorCode = ErrorCode::new("replication_error", 4020, Internal, true);
+pub const SCHEMA_TOO_NEW: ErrorCode = ErrorCode::new("schema_too_new", 4021, Internal, false);
+pub const MIGRATION_ERROR: ErrorCode = ErrorCode::new("migration_error", 4022, Internal, false);
+pub const STORAGE_TIER_ERROR: ErrorCode =
+    ErrorCode::new("storage_tier_error", 4023, Internal, false);
+pub const THIN_NODE: ErrorCode = ErrorCode::new("thin_node", 4024, Policy, false);
+
+// Governance
+pub const PROPOSAL_NOT_FOUND: ErrorCode =
+    ErrorCode::new("proposal_not_found", 5000, NotFound, false);
+pub const VOTING_NOT_ACTIVE: ErrorCode = ErrorCode::new("voting_not_active", 5001, Conflict, true);
+pub const VOTING_ENDED: ErrorCode = ErrorCode::new("voting_ended", 5002, Conflict, false);
+pub const ALREADY_VOTED: ErrorCode = ErrorCode::new("already_voted", 5003, Conflict, false);
+pub const INSUFFICIENT_STAKE: ErrorCode =
+    ErrorCode::new("insufficient_stake", 5004, Rejected, false);
+pub const INVALID_PROPOSAL: ErrorCode = ErrorCode::new("invalid_proposal", 5005, Rejected, false);
+pub const NOT_PROPOSER: ErrorCode = ErrorCode::new("not_proposer", 5006, Rejected, false);
+pub const CANNOT_CANCEL: ErrorCode = ErrorCode::new("cannot_cancel", 5007, Conflict, false);
+pub const NOT_READY_FOR_EXECUTION: ErrorCode =
+    ErrorCode::new("not_ready_for_execution", 5008, Conflict, true);
+pub const EXECUTION_FAILED: ErrorCode = ErrorCode::new("execution_failed", 5009, Internal, false);
+pub const INVALID_PARAMETERS: ErrorCode =
+    ErrorCode::new("invalid_parameters", 5010, Rejected, false);
+pub const DELEGATION_LOOP: ErrorCode = ErrorCode::new("delegation_loop", 5011, Rejected, false);
+pub const DELEGATION_TO_SELF: ErrorCode =
+    ErrorCode::new("delegation_to_self", 5012, Rejected, false);
+pub const NOT_ELIGIBLE_TO_VOTE: ErrorCode =
+    ErrorCode::new("not_eligible_to_vote", 5013, Rejected, false);
+pub const NO_EMERGENCY_COUNCIL: ErrorCode =
+    ErrorCode::new("no_emergency_council", 5014, Conflict, false);
+pub const NOT_COUNCIL_MEMBER: ErrorCode =
+    ErrorCode::new("not_council_member", 5015, Rejected, false);
+pub const MOTION_NOT_FOUND: ErrorCode = ErrorCode::new("motion_not_found", 5016, NotFound, false);
+pub const MOTION_CLOSED: ErrorCode = ErrorCode::new("motion_closed", 5017, Conflict, false);
+pub const PROPOSAL_PAUSED: ErrorCode = ErrorCode::new("proposal_paused", 5018, Conflict, true);
+
+/// Every registered code, in number order
+pub const REGISTRY: &[ErrorCode] = &[
+    INVALID_REQUEST,
+    UNAUTHORIZED,
+    FORBIDDEN,
+    NOT_FOUND,
+    CONFLICT,
+    PAYLOAD_TOO_LARGE,
+    RATE_LIMITED,
+    UNAVAILABLE,
+    INTERNAL_ERROR,
+    STORAGE_ERROR,
+    INVALID_SIGNATURE,
+    INSUFFICIENT_BALANCE,
+    INVALID_NONCE,
+    NONCE_TOO_FAR,
+    WRONG_CHAIN_ID,
+    ACCOUNT_FROZEN,
+    POLICY_VIOLATION,
+    DELEGATION_VIOLATION,
+    INVALID_CONTRACT,
+    INVALID_ASSET,
+    INVALID_ORDER,
+    INVALID_ORACLE_UPDATE,
+    EXPIRED,
+    BALANCE_OVERFLOW,
+    INVALID_DEPOSIT,
+    DUPLICATE,
+    MEMPOOL_FULL,
+    SENDER_LIMIT,
+    FEE_TOO_LOW,
+    DUST_AMOUNT,
+    RELAY_POLICY,
+    DATABASE_ERROR,
+    SERIALIZATION_ERROR,
+    BLOCK_NOT_FOUND,
+    INVALID_CHAIN,
+    INVALID_PROOF_OF_WORK,
+    UNEXPECTED_DIFFICULTY,
+    INVALID_BLOCK_TRANSACTION,
+    INVALID_MERKLE_ROOT,
+    TIMESTAMP_TOO_FAR_FUTURE,
+    TIMESTAMP_DECREASED,
+    MISSING_COINBASE,
+    INVALID_COINBASE_AMOUNT,
+    MULTIPLE_COINBASE,
+    CHECKPOINT_MISMATCH,
+    REORG_TOO_DEEP,
+    COLUMN_FAMILY_MISSING,
+    AUDIT_ERROR,
+    ANCHOR_ERROR,
+    INVALID_SNAPSHOT,
+    CONTRACT_ERROR,
+    REPLICATION_ERROR,
+    SCHEMA_TOO_NEW,
+    MIGRATION_ERROR,
+    STORAGE_TIER_ERROR,
+    THIN_NODE,
+    PROPOSAL_NOT_FOUND,
+    VOTING_NOT_ACTIVE,
+    VOTING_ENDED,
+    ALREADY_VOTED,
+    INSUFFICIENT_STAKE,
+    INVALID_PROPOSAL,
+    NOT_PROPOSER,
+    CANNOT_CANCEL,
+    NOT_READY_FOR_EXECUTION,
+    EXECUTION_FAILED,
+    INVALID_PARAMETERS,
+    DELEGATION_LOOP,
+    DELEGATION_TO_SELF,
+    NOT_ELIGIBLE_TO_VOTE,
+    NO_EMERGENCY_COUNCIL,
+    NOT_COUNCIL_MEMBER,
+    MOTION_NOT_FOUND,
+    MOTION_CLOSED,
+    PROPOSAL_PAUSED,
+];
+
+/// Registered code with the string `code`
+/// البحث عن رمز مسجل
+pub fn lookup(code: &str) -> Option<ErrorCode> {
+    REGISTRY.iter().find(|entry| entry.code == code).copied()
+}
+
+/// Generic code for an HTTP status, for API errors without a domain error
+pub fn for_http_status(status: u16) -> ErrorCode {
+    match status {
+        401 => UNAUTHORIZED,
+        403 => FORBIDDEN,
+        404 => NOT_FOUND,
+        409 => CONFLICT,
+        413 => PAYLOAD_TOO_LARGE,
+        429 => RATE_LIMITED,
+        503 | 504 => UNAVAILABLE,
+        400..=499 => INVALID_REQUEST,
+        _ => INTERNAL_ERROR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_registry_codes_are_unique_and_ordered() {
+        let mut codes = HashSet::new();
+        for pair in REGISTRY.windows(2) {
+            assert!(pair[0].number < pair[1].number, "{} out of order", pair[1]);
+        }
+        for entry in REGISTRY {
+            assert!(codes.insert(entry.code), "{} registered twice", entry.code);
+            assert_eq!(lookup(entry.code), Some(*entry));
+        }
+        assert_eq!(lookup("no_such_code"), None);
+    }
+
+    #[test]
+    fn test_error_code_serialization() {
+        let json = serde_json::to_value(FEE_TOO_LOW).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "code": "fee_too_low",
+                "number": 3003,
+                "category": "policy",
+                "retryable": false,
+            })
+        );
+        assert_eq!(for_http_status(429), RATE_LIMITED);
+        assert_eq!(for_http_status(422), INVALID_REQUEST);
+    }
+}
@@ -0,0 +1,306 @@
+//! Node event bus
+//! ناقل أحداث العقدة
+//!
+//! A broadcast channel carrying typed events between node subsystems. The
+//! network layer, mempool, miner, explorer and wallet API each hold a clone of
+//! the same [`EventBus`] and subscribe independently, instead of wiring a
+//! dedicated channel between every producer and consumer.
+
+use crate::{safe_mode::Anomaly, Block, Transaction};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tokio::sync::broadcast;
+
+/// Default number of events buffered per subscriber before it starts lagging
+pub const DEFAULT_EVENT_BUS_CAPACITY: usize = 1024;
+
+/// Why a transaction left the mempool without being mined by us
+/// سبب إزالة المعاملة من مجمع المعاملات
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Included in a connected block
+    Confirmed,
+    /// Evicted to make room for a higher fee transaction
+    Evicted,
+    /// Exceeded the maximum mempool age
+    Expired,
+    /// Replaced by a higher fee transaction with the same nonce
+    Replaced,
+    /// Removed explicitly (e.g. mempool cleared)
+    Removed,
+    /// Signature accepted by batch verification failed the strict check
+    Invalid,
+}
+
+/// Peer connectivity events
+/// أحداث اتصال الأقران
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PeerEvent {
+    /// A peer connected (peer ID in its string form)
+    Connected(String),
+    /// A peer disconnected
+    Disconnected(String),
+}
+
+/// Governance proposal lifecycle transitions
+/// انتقالات دورة حياة اقتراحات الحوكمة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum GovernanceEvent {
+    /// A proposal was created and is open for voting
+    VotingStarted {
+        proposal_id: u64,
+        title: String,
+        voting_start: u64,
+        voting_end: u64,
+    },
+    /// Voting ended with quorum and threshold met
+    Passed {
+        proposal_id: u64,
+        height: u64,
+        votes_yes: u64,
+        votes_no: u64,
+        votes_abstain: u64,
+    },
+    /// Voting ended without quorum or threshold
+    Rejected {
+        proposal_id: u64,
+        height: u64,
+        votes_yes: u64,
+        votes_no: u64,
+        votes_abstain: u64,
+    },
+    /// A passed proposal's execution delay elapsed
+    Executable { proposal_id: u64, height: u64 },
+    /// A proposal was executed
+    Executed { proposal_id: u64, height: u64 },
+    /// Voting met quorum, so the proposer's deposit was returned
+    DepositRefunded {
+        proposal_id: u64,
+        height: u64,
+        amount: u64,
+    },
+    /// Voting closed without quorum, so the proposer's deposit was burned
+    DepositBurned {
+        proposal_id: u64,
+        height: u64,
+        amount: u64,
+    },
+}
+
+impl GovernanceEvent {
+    /// Proposal the event is about
+    pub fn proposal_id(&self) -> u64 {
+        match self {
+            Self::VotingStarted { proposal_id, .. }
+            | Self::Passed { proposal_id, .. }
+            | Self::Rejected { proposal_id, .. }
+            | Self::Executable { proposal_id, .. }
+            | Self::Executed { proposal_id, .. }
+            | Self::DepositRefunded { proposal_id, .. }
+            | Self::DepositBurned { proposal_id, .. } => *proposal_id,
+        }
+    }
+}
+
+impl fmt::Display for GovernanceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VotingStarted {
+                proposal_id,
+                title,
+                voting_end,
+                ..
+            } => write!(
+                f,
+                "proposal #{} \"{}\" open for voting until block {}",
+                proposal_id, title, voting_end
+            ),
+            Self::Passed {
+                proposal_id,
+                votes_yes,
+                votes_no,
+                votes_abstain,
+                ..
+            } => write!(
+                f,
+                "proposal #{} passed (yes {}, no {}, abstain {})",
+                proposal_id, votes_yes, votes_no, votes_abstain
+            ),
+            Self::Rejected {
+                proposal_id,
+                votes_yes,
+                votes_no,
+                votes_abstain,
+                ..
+            } => write!(
+                f,
+                "proposal #{} rejected (yes {}, no {}, abstain {})",
+                proposal_id, votes_yes, votes_no, votes_abstain
+            ),
+            Self::Executable {
+                proposal_id,
+                height,
+            } => write!(
+                f,
+                "proposal #{} executable at block {}",
+                proposal_id, height
+            ),
+            Self::Executed {
+                proposal_id,
+                height,
+            } => write!(f, "proposal #{} executed at block {}", proposal_id, height),
+            Self::DepositRefunded {
+                proposal_id,
+                amount,
+                ..
+            } => write!(
+                f,
+                "proposal #{} deposit of {} refunded",
+                proposal_id, amount
+            ),
+            Self::DepositBurned {
+                proposal_id,
+                amount,
+                ..
+            } => write!(f, "proposal #{} deposit of {} burned", proposal_id, amount),
+        }
+    }
+}
+
+/// Typed events published on the node event bus
+/// الأحداث المنشورة على ناقل أحداث العقدة
+#[derive(Debug, Clone)]
+pub enum NodeEvent {
+    /// A block was appended to the active chain
+    BlockConnected { height: u64, block: Block },
+
+    /// A block was removed from the active chain (reorganization)
+    BlockDisconnected { height: u64, block: Block },
+
+    /// A transaction was accepted into the mempool
+    TxAdded(Transaction),
+
+    /// A transaction left the mempool
+    TxDropped { hash: [u8; 32], reason: DropReason },
+
+    /// Peer connectivity changed
+    Peer(PeerEvent),
+
+    /// The node entered safe mode and stopped mining and relaying
+    SafeModeEntered(Anomaly),
+
+    /// A governance proposal changed state
+    Governance(GovernanceEvent),
+}
+
+/// Broadcast bus shared by node subsystems
+/// ناقل بث مشترك بين أنظمة العقدة الفرعية
+///
+/// Cloning the bus is cheap; all clones publish to the same subscribers.
+/// Publishing never blocks: if nobody is subscribed the event is discarded,
+/// and slow subscribers observe `RecvError::Lagged` rather than stalling
+/// producers.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl EventBus {
+    /// Create a bus buffering up to `capacity` events per subscriber
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    /// Publish an event to all current subscribers
+    /// Returns the number of subscribers that will receive it
+    pub fn publish(&self, event: NodeEvent) -> usize {
+        self.sender.send(event).unwrap_or(0)
+    }
+
+    /// Subscribe to events published after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Number of active subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_BUS_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn sample_tx() -> Transaction {
+        let from = KeyPair::generate();
+        let to = KeyPair::generate();
+        Transaction::new(from.public_key(), to.public_key(), 1_000, 100, 0)
+    }
+
+    #[test]
+    fn test_publish_without_subscribers() {
+        let bus = EventBus::default();
+        assert_eq!(bus.publish(NodeEvent::TxAdded(sample_tx())), 0);
+    }
+
+    #[test]
+    fn test_all_subscribers_receive_event() {
+        let bus = EventBus::default();
+        let mut first = bus.subscribe();
+        let mut second = bus.clone().subscribe();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        let delivered = bus.publish(NodeEvent::Peer(PeerEvent::Connected("peer-a".into())));
+        assert_eq!(delivered, 2);
+
+        for rx in [&mut first, &mut second] {
+            match rx.try_recv().unwrap() {
+                NodeEvent::Peer(PeerEvent::Connected(id)) => assert_eq!(id, "peer-a"),
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_slow_subscriber_lags() {
+        let bus = EventBus::new(2);
+        let mut rx = bus.subscribe();
+
+        for _ in 0..3 {
+            bus.publish(NodeEvent::TxDropped {
+                hash: [0u8; 32],
+                reason: DropReason::Expired,
+            });
+        }
+
+        assert!(matches!(
+            rx.try_recv(),
+            Err(broadcast::error::TryRecvError::Lagged(1))
+        ));
+        assert!(rx.try_recv().is_ok());
+    }
+
+    #[test]
+    fn test_governance_event_json() {
+        let event = GovernanceEvent::Executable {
+            proposal_id: 3,
+            height: 120,
+        };
+        assert_eq!(event.proposal_id(), 3);
+        assert_eq!(event.to_string(), "proposal #3 executable at block 120");
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#"{"kind":"executable","proposal_id":3,"height":120}"#
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Fee-rate statistics
+//! إحصاءات معدلات الرسوم
+//!
+//! Fee rates are measured in base units per 1000 bytes of transaction weight
+//! (see [`Transaction::fee_rate`]). The indexer records one [`FeeRateStats`]
+//! per block, which the explorer reports and the wallet turns into a
+//! [`FeeEstimate`].
+
+use crate::{Block, Transaction};
+use serde::{Deserialize, Serialize};
+
+/// Fee-rate distribution of the non-coinbase transactions in one block
+/// توزيع معدلات الرسوم لمعاملات كتلة واحدة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct FeeRateStats {
+    pub height: u64,
+    pub tx_count: usize,
+    pub total_weight: u64,
+    pub total_fees: u64,
+    pub min: u64,
+    pub median: u64,
+    pub p90: u64,
+    pub max: u64,
+}
+
+impl FeeRateStats {
+    /// Compute statistics for `block` at `height`
+    pub fn from_block(block: &Block, height: u64) -> Self {
+        let txs: Vec<&Transaction> = block
+            .transactions
+            .iter()
+            .filter(|tx| !tx.is_coinbase())
+            .collect();
+        let mut rates: Vec<u64> = txs.iter().map(|tx| tx.fee_rate()).collect();
+        rates.sort_unstable();
+
+        Self {
+            height,
+            tx_count: txs.len(),
+            total_weight: txs.iter().map(|tx| tx.weight() as u64).sum(),
+            total_fees: txs.iter().map(|tx| tx.fee).sum(),
+            min: rates.first().copied().unwrap_or(0),
+            median: percentile(&rates, 50),
+            p90: percentile(&rates, 90),
+            max: rates.last().copied().unwrap_or(0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of an ascending slice (0 when empty)
+pub fn percentile(sorted: &[u64], pct: u8) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (sorted.len() * pct.min(100) as usize).div_ceil(100);
+    sorted[rank.saturating_sub(1)]
+}
+
+/// Suggested fee rates derived from recent blocks
+/// معدلات الرسوم المقترحة بناءً على الكتل الأخيرة
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    /// Rate likely to confirm eventually
+    pub low: u64,
+    /// Rate paid by a typical recent transaction
+    pub medium: u64,
+    /// Rate likely to confirm in the next block
+    pub high: u64,
+    /// Number of non-empty blocks the estimate is based on
+    pub blocks: usize,
+}
+
+impl FeeEstimate {
+    /// Estimate from per-block statistics, ignoring empty blocks
+    ///
+    /// Each level is the median across blocks of the corresponding per-block
+    /// percentile (min, median and 90th), so a single outlier block does not
+    /// skew the suggestion.
+    pub fn from_stats(stats: &[FeeRateStats]) -> Option<Self> {
+        let blocks: Vec<&FeeRateStats> = stats.iter().filter(|s| s.tx_count > 0).collect();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        let across = |field: fn(&FeeRateStats) -> u64| {
+            let mut values: Vec<u64> = blocks.iter().map(|s| field(s)).collect();
+            values.sort_unstable();
+            percentile(&values, 50)
+        };
+
+        Some(Self {
+            low: across(|s| s.min),
+            medium: across(|s| s.median),
+            high: across(|s| s.p90),
+            blocks: blocks.len(),
+        })
+    }
+
+    /// Fee for a transaction of `weight` bytes at `rate`, never below `min_fee`
+    pub fn fee_for(rate: u64, weight: usize, min_fee: u64) -> u64 {
+        (rate.saturating_mul(weight as u64))
+            .div_ceil(1000)
+            .max(min_fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn signed_tx(fee: u64) -> Transaction {
+        let from = KeyPair::generate();
+        let tx = Transaction::new(
+            from.public_key(),
+            KeyPair::generate().public_key(),
+            5_000,
+            fee,
+            0,
+        );
+        let signature = from.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let values: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 50), 5);
+        assert_eq!(percentile(&values, 90), 9);
+        assert_eq!(percentile(&values, 100), 10);
+        assert_eq!(percentile(&[7], 90), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn test_block_stats_skip_coinbase() {
+        let coinbase = Transaction::coinbase(1, KeyPair::generate().public_key(), 1, 0).unwrap();
+        let mut transactions = vec![coinbase];
+        transactions.extend([1_000, 2_000, 3_000].map(signed_tx));
+        let block = Block::new([0u8; 32], transactions, 16);
+
+        let stats = FeeRateStats::from_block(&block, 7);
+        assert_eq!(stats.height, 7);
+        assert_eq!(stats.tx_count, 3);
+        assert_eq!(stats.total_fees, 6_000);
+        assert!(stats.min < stats.median && stats.median < stats.max);
+        assert_eq!(stats.p90, stats.max);
+    }
+
+    #[test]
+    fn test_estimate_ignores_empty_blocks() {
+        let stats = |min, median, p90| FeeRateStats {
+            height: 0,
+            tx_count: 1,
+            total_weight: 0,
+            total_fees: 0,
+            min,
+            median,
+            p90,
+            max: p90,
+        };
+        let empty = FeeRateStats::from_block(&Block::genesis(), 1);
+        assert_eq!(FeeEstimate::from_stats(std::slice::from_ref(&empty)), None);
+
+        let estimate = FeeEstimate::from_stats(&[
+            stats(10, 20, 30),
+            empty,
+            stats(1, 2, 3),
+            stats(100, 200, 900),
+        ])
+        .unwrap();
+        assert_eq!(
+            estimate,
+            FeeEstimate {
+                low: 10,
+                medium: 20,
+                high: 30,
+                blocks: 3
+            }
+        );
+
+        assert_eq!(FeeEstimate::fee_for(20, 150, 100), 100);
+        assert_eq!(FeeEstimate::fee_for(2_000, 150, 100), 300);
+    }
+}
@@ -0,0 +1,147 @@
+//! Sparse fieldsets for API responses
+//! اختيار الحقول في استجابات الواجهة البرمجية
+//!
+//! Clients on slow links ask for only the fields they use with
+//! `?fields=height,hash,transactions.amount`. Names are comma separated and
+//! dots select inside nested objects. A selection applies to every element
+//! of an array, so `transactions.amount` keeps just the amounts of a block's
+//! transactions. Unknown names are ignored and a name without a dot keeps
+//! the whole value. The wallet API and explorer apply this to the JSON body
+//! of successful GET responses, so handlers do not need to know about it.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Most field paths honoured in one selection; the rest are ignored
+pub const MAX_SELECTED_FIELDS: usize = 64;
+
+/// Deepest field path honoured (`a.b.c.d` is depth 4)
+pub const MAX_FIELD_DEPTH: usize = 8;
+
+/// Tree of selected fields
+/// شجرة الحقول المختارة
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FieldSelection {
+    /// Selected names; an empty subtree keeps the whole value
+    fields: BTreeMap<String, FieldSelection>,
+}
+
+impl FieldSelection {
+    /// Parse a `fields` parameter; `None` if it names no field
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut selection = Self::default();
+        let paths = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .take(MAX_SELECTED_FIELDS);
+        for path in paths {
+            let names: Vec<&str> = path.split('.').map(str::trim).collect();
+            if names.len() > MAX_FIELD_DEPTH || names.iter().any(|name| name.is_empty()) {
+                continue;
+            }
+            selection.insert(&names);
+        }
+        (!selection.fields.is_empty()).then_some(selection)
+    }
+
+    fn insert(&mut self, names: &[&str]) {
+        let Some((first, rest)) = names.split_first() else {
+            return;
+        };
+        if rest.is_empty() {
+            // A whole value wins over any narrower selection inside it
+            self.fields.insert(first.to_string(), Self::default());
+            return;
+        }
+        match self.fields.get_mut(*first) {
+            Some(child) if child.fields.is_empty() => {}
+            Some(child) => child.insert(rest),
+            None => {
+                let mut child = Self::default();
+                child.insert(rest);
+                self.fields.insert(first.to_string(), child);
+            }
+        }
+    }
+
+    /// Drop every unselected field from `value`
+    /// حذف الحقول غير المختارة
+    pub fn apply(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|name, _| self.fields.contains_key(name));
+                for (name, child) in map.iter_mut() {
+                    let selection = &self.fields[name];
+                    if !selection.fields.is_empty() {
+                        selection.apply(child);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.apply(item);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_selects_nested_fields() {
+        let mut block = json!({
+            "height": 7,
+            "hash": "00ab",
+            "size": 512,
+            "transactions": [
+                {"hash": "01", "amount": 5, "fee": 1},
+                {"hash": "02", "amount": 9, "fee": 1}
+            ]
+        });
+        FieldSelection::parse("height, transactions.amount")
+            .unwrap()
+            .apply(&mut block);
+        assert_eq!(
+            block,
+            json!({"height": 7, "transactions": [{"amount": 5}, {"amount": 9}]})
+        );
+    }
+
+    #[test]
+    fn test_applies_to_each_array_element() {
+        let mut blocks = json!([{"height": 1, "hash": "a"}, {"height": 2, "hash": "b"}]);
+        FieldSelection::parse("height").unwrap().apply(&mut blocks);
+        assert_eq!(blocks, json!([{"height": 1}, {"height": 2}]));
+    }
+
+    #[test]
+    fn test_whole_value_wins_over_narrower_path() {
+        let mut value = json!({"header": {"height": 3, "nonce": 42}});
+        let expected = value.clone();
+        for spec in ["header,header.height", "header.height,header"] {
+            let mut value = value.clone();
+            FieldSelection::parse(spec).unwrap().apply(&mut value);
+            assert_eq!(value, expected);
+        }
+
+        FieldSelection::parse("header.height,missing")
+            .unwrap()
+            .apply(&mut value);
+        assert_eq!(value, json!({"header": {"height": 3}}));
+    }
+
+    #[test]
+    fn test_empty_or_malformed_selection() {
+        assert_eq!(FieldSelection::parse(""), None);
+        assert_eq!(FieldSelection::parse(" , ,"), None);
+        assert_eq!(FieldSelection::parse("a..b"), None);
+        let too_deep = "a.".repeat(MAX_FIELD_DEPTH) + "a";
+        assert_eq!(FieldSelection::parse(&too_deep), None);
+    }
+}
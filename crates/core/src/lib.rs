@@ -1,11 +1,63 @@
+pub mod anchor;
+pub mod assembly;
+pub mod asset;
+pub mod audit;
 pub mod block;
+pub mod clock;
+pub mod coinbase;
+pub mod confirmations;
 pub mod constants;
+pub mod contract;
 pub mod crypto;
+pub mod delegation;
+pub mod deposit;
+pub mod dex;
+pub mod error_code;
+pub mod events;
+pub mod fees;
+pub mod fields;
+pub mod locale;
+pub mod memo;
+pub mod message;
 pub mod multisig;
+pub mod oracle;
+pub mod params;
+pub mod policy;
+pub mod rawtx;
+pub mod safe_mode;
+pub mod state_proof;
+pub mod stealth;
 pub mod transaction;
 
-pub use block::{Block, BlockHeader};
+pub use anchor::{AnchorError, AnchorPublisher, ChainAnchor, SignedAnchor};
+pub use assembly::select_transactions;
+pub use asset::{AssetError, AssetId, AssetInfo, AssetOperation};
+pub use audit::{verify_audit_log, AuditEntry, AuditError, AuditEvent, AuditLog, BalanceChange};
+pub use block::{Block, BlockHeader, BlockV1};
+pub use coinbase::{
+    BlockProducer, CoinbaseSplit, CoinbaseSplitError, PayoutShare, MAX_COINBASE_EXTRA_DATA,
+    MAX_COINBASE_OUTPUTS,
+};
+pub use confirmations::{ConfirmationStatus, ConfirmationTargets};
 pub use constants::*;
+pub use contract::{contract_address, ContractCall, ContractError};
 pub use crypto::KeyPair;
+pub use delegation::{DelegationError, HotKeyDelegation};
+pub use deposit::{DepositError, DepositEscrow, DepositOperation, DepositSettlement};
+pub use dex::{DexError, DexOperation, Order, OrderId, OrderSide, OrderStatus, Trade};
+pub use error_code::{CodedError, ErrorCategory, ErrorCode};
+pub use events::{DropReason, EventBus, GovernanceEvent, NodeEvent, PeerEvent};
+pub use fees::{FeeEstimate, FeeRateStats};
+pub use fields::FieldSelection;
+pub use locale::{Calendar, DisplayFormat, Locale};
+pub use memo::{EncryptedMemo, Memo, MemoError};
+pub use message::{MessageError, SignedMessage};
 pub use multisig::{MultisigAccount, MultisigError, MultisigTransaction};
-pub use transaction::Transaction;
+pub use oracle::{OracleError, OracleRate, OracleUpdate, RateAttestation, RateQuote};
+pub use params::{ChainParams, ParamsError};
+pub use policy::{PolicyError, SpendingPolicy};
+pub use rawtx::RawTxError;
+pub use safe_mode::{Anomaly, SafeMode, SafeModeRecord};
+pub use state_proof::{AccountLeaf, StateProof, StateTree};
+pub use stealth::{StealthAddress, StealthAnnouncement, StealthError, StealthKeys};
+pub use transaction::{FeeSponsor, Transaction, TransactionV1};
@@ -0,0 +1,503 @@
+//! Localized display strings for API responses
+//! نصوص العرض المحلية لاستجابات الواجهة البرمجية
+//!
+//! Responses always carry locale-neutral raw values: amounts in base units,
+//! times as Unix seconds. With `?locale=ar-SY` (or `en`) the wallet API and
+//! explorer add a `<field>_display` string next to each amount, timestamp
+//! and count they recognize, so a page can show them without its own
+//! formatting code. `ar-SY` writes Arabic-Indic digits and Damascus time
+//! (UTC+3), `en` Western digits and UTC. `&calendar=hijri` renders dates in
+//! the tabular Islamic calendar, which may differ by a day from the sighted
+//! calendar.
+//!
+//! Amounts are in Lira unless the object holding them says otherwise: one
+//! with a `decimals` field (an asset balance, a market) is written in the
+//! asset's own decimals and `symbol`, and one with an `asset_id` but no
+//! `decimals` (an order, a trade) gets no amount display strings, since its
+//! unit is not in the response. Nested objects inherit the unit.
+
+use crate::UNITS_PER_LIRA;
+use serde_json::Value;
+
+/// Fields holding amounts in base units
+pub const AMOUNT_FIELDS: &[&str] = &[
+    "amount",
+    "balance",
+    "fee",
+    "fees_paid",
+    "max_supply",
+    "received",
+    "refund",
+    "reward",
+    "sent",
+    "supply",
+    "total_fees",
+    "total_received",
+    "total_sent",
+];
+
+/// Fields holding Unix timestamps in seconds
+pub const TIMESTAMP_FIELDS: &[&str] = &[
+    "created_at",
+    "expires_at",
+    "last_sent_at",
+    "latest_block_timestamp",
+    "period_end",
+    "period_start",
+    "timestamp",
+];
+
+/// Fields holding plain counts or heights
+pub const COUNT_FIELDS: &[&str] = &[
+    "block_height",
+    "confirmations",
+    "height",
+    "total_blocks",
+    "total_transactions",
+    "transaction_count",
+];
+
+/// Suffix of added display fields
+pub const DISPLAY_SUFFIX: &str = "_display";
+
+/// Decimal places of a Lira amount in base units
+const LIRA_DECIMALS: u32 = UNITS_PER_LIRA.ilog10();
+
+/// Offset of Damascus time from UTC, in seconds
+const SYRIA_UTC_OFFSET_SECS: i64 = 3 * 3600;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+const GREGORIAN_MONTHS_EN: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Month names as used in Syria
+const GREGORIAN_MONTHS_AR: [&str; 12] = [
+    "كانون الثاني",
+    "شباط",
+    "آذار",
+    "نيسان",
+    "أيار",
+    "حزيران",
+    "تموز",
+    "آب",
+    "أيلول",
+    "تشرين الأول",
+    "تشرين الثاني",
+    "كانون الأول",
+];
+
+const HIJRI_MONTHS_EN: [&str; 12] = [
+    "Muharram",
+    "Safar",
+    "Rabi al-Awwal",
+    "Rabi al-Thani",
+    "Jumada al-Ula",
+    "Jumada al-Akhirah",
+    "Rajab",
+    "Shaban",
+    "Ramadan",
+    "Shawwal",
+    "Dhu al-Qadah",
+    "Dhu al-Hijjah",
+];
+
+const HIJRI_MONTHS_AR: [&str; 12] = [
+    "محرم",
+    "صفر",
+    "ربيع الأول",
+    "ربيع الآخر",
+    "جمادى الأولى",
+    "جمادى الآخرة",
+    "رجب",
+    "شعبان",
+    "رمضان",
+    "شوال",
+    "ذو القعدة",
+    "ذو الحجة",
+];
+
+/// Language and region of display strings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    English,
+    ArabicSyria,
+}
+
+impl Locale {
+    /// Parse a language tag; any `ar` tag is Syrian Arabic, any `en` tag
+    /// English
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "ar" => Some(Locale::ArabicSyria),
+            "en" => Some(Locale::English),
+            _ => None,
+        }
+    }
+
+    /// Canonical tag, e.g. for `Content-Language`
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::ArabicSyria => "ar-SY",
+        }
+    }
+}
+
+/// Calendar dates are rendered in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Calendar {
+    #[default]
+    Gregorian,
+    /// Tabular Islamic calendar
+    Hijri,
+}
+
+impl Calendar {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "gregorian" => Some(Calendar::Gregorian),
+            "hijri" | "islamic" => Some(Calendar::Hijri),
+            _ => None,
+        }
+    }
+}
+
+/// How display strings are written
+/// طريقة كتابة نصوص العرض
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplayFormat {
+    pub locale: Locale,
+    pub calendar: Calendar,
+}
+
+impl DisplayFormat {
+    pub fn new(locale: Locale, calendar: Calendar) -> Self {
+        Self { locale, calendar }
+    }
+
+    /// Format from `locale` and `calendar` query parameters; `None` without
+    /// a supported locale. An unknown calendar falls back to Gregorian.
+    pub fn from_params(locale: Option<&str>, calendar: Option<&str>) -> Option<Self> {
+        let locale = Locale::parse(locale?)?;
+        let calendar = calendar.and_then(Calendar::parse).unwrap_or_default();
+        Some(Self::new(locale, calendar))
+    }
+
+    /// Integer with thousands separators
+    pub fn format_integer(&self, n: u64) -> String {
+        let separator = match self.locale {
+            Locale::English => ',',
+            Locale::ArabicSyria => '\u{066C}',
+        };
+        let digits = n.to_string();
+        let mut grouped = String::with_capacity(digits.len() * 2);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        self.localize_digits(&grouped)
+    }
+
+    /// Amount in base units as Lira, without trailing zero decimals
+    /// تنسيق المبلغ بالليرة
+    pub fn format_amount(&self, units: u64) -> String {
+        let unit = match self.locale {
+            Locale::English => "SYL",
+            Locale::ArabicSyria => "ل.س",
+        };
+        self.format_asset_amount(units, LIRA_DECIMALS, unit)
+    }
+
+    /// Amount of an asset with `decimals` places, followed by `symbol`
+    /// تنسيق مبلغ أصل بمنازله العشرية ورمزه
+    pub fn format_asset_amount(&self, units: u64, decimals: u32, symbol: &str) -> String {
+        let (whole, fraction) = match 10u64.checked_pow(decimals) {
+            Some(scale) => (units / scale, units % scale),
+            None => (0, units),
+        };
+        let mut text = self.format_integer(whole);
+        let fraction = format!("{:0width$}", fraction, width = decimals as usize);
+        let fraction = fraction.trim_end_matches('0');
+        if !fraction.is_empty() {
+            text.push(match self.locale {
+                Locale::English => '.',
+                Locale::ArabicSyria => '\u{066B}',
+            });
+            text.push_str(&self.localize_digits(fraction));
+        }
+        if !symbol.is_empty() {
+            text.push(' ');
+            text.push_str(symbol);
+        }
+        text
+    }
+
+    /// Date and time of a Unix timestamp
+    /// تنسيق التاريخ والوقت
+    pub fn format_timestamp(&self, timestamp: u64) -> String {
+        let offset = match self.locale {
+            Locale::English => 0,
+            Locale::ArabicSyria => SYRIA_UTC_OFFSET_SECS,
+        };
+        let local = i64::try_from(timestamp)
+            .unwrap_or(i64::MAX)
+            .saturating_add(offset);
+        let days = local.div_euclid(SECS_PER_DAY);
+        let secs = local.rem_euclid(SECS_PER_DAY);
+        let time = self.localize_digits(&format!("{:02}:{:02}", secs / 3600, secs % 3600 / 60));
+
+        let (year, month, day) = match self.calendar {
+            Calendar::Gregorian => gregorian_date(days),
+            Calendar::Hijri => hijri_date(days),
+        };
+        let month = (month - 1) as usize;
+        match (self.locale, self.calendar) {
+            (Locale::English, Calendar::Gregorian) => format!(
+                "{} {} {}, {} UTC",
+                day, GREGORIAN_MONTHS_EN[month], year, time
+            ),
+            (Locale::English, Calendar::Hijri) => {
+                format!(
+                    "{} {} {} AH, {} UTC",
+                    day, HIJRI_MONTHS_EN[month], year, time
+                )
+            }
+            (Locale::ArabicSyria, Calendar::Gregorian) => format!(
+                "{} {} {}، {}",
+                self.localize_digits(&day.to_string()),
+                GREGORIAN_MONTHS_AR[month],
+                self.localize_digits(&year.to_string()),
+                time
+            ),
+            (Locale::ArabicSyria, Calendar::Hijri) => format!(
+                "{} {} {} هـ، {}",
+                self.localize_digits(&day.to_string()),
+                HIJRI_MONTHS_AR[month],
+                self.localize_digits(&year.to_string()),
+                time
+            ),
+        }
+    }
+
+    /// Add a display string next to every recognized field of `value`
+    /// إضافة نصوص العرض إلى الحقول المعروفة
+    pub fn annotate(&self, value: &mut Value) {
+        self.annotate_in(value, &Denomination::Lira);
+    }
+
+    fn annotate_in(&self, value: &mut Value, outer: &Denomination) {
+        match value {
+            Value::Object(map) => {
+                let denomination = Denomination::of(map).unwrap_or_else(|| outer.clone());
+                let mut displays = Vec::new();
+                for (name, field) in map.iter_mut() {
+                    match field.as_u64() {
+                        Some(n) => {
+                            let display = if AMOUNT_FIELDS.contains(&name.as_str()) {
+                                match &denomination {
+                                    Denomination::Lira => self.format_amount(n),
+                                    Denomination::Asset { decimals, symbol } => {
+                                        self.format_asset_amount(n, *decimals, symbol)
+                                    }
+                                    Denomination::Unknown => continue,
+                                }
+                            } else if TIMESTAMP_FIELDS.contains(&name.as_str()) {
+                                self.format_timestamp(n)
+                            } else if COUNT_FIELDS.contains(&name.as_str()) {
+                                self.format_integer(n)
+                            } else {
+                                continue;
+                            };
+                            displays.push((format!("{}{}", name, DISPLAY_SUFFIX), display));
+                        }
+                        None => self.annotate_in(field, &denomination),
+                    }
+                }
+                for (name, display) in displays {
+                    // Never shadow a field the response already has
+                    map.entry(name).or_insert(Value::String(display));
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    self.annotate_in(item, outer);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn localize_digits(&self, text: &str) -> String {
+        match self.locale {
+            Locale::English => text.to_string(),
+            Locale::ArabicSyria => text
+                .chars()
+                .map(|c| match c.to_digit(10) {
+                    Some(d) => char::from_u32(0x0660 + d).unwrap_or(c),
+                    None => c,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Unit of the amounts in a JSON object
+#[derive(Debug, Clone)]
+enum Denomination {
+    Lira,
+    /// A native asset with its own decimal places and symbol
+    Asset {
+        decimals: u32,
+        symbol: String,
+    },
+    /// An asset whose decimals are not in the response
+    Unknown,
+}
+
+impl Denomination {
+    /// Unit an object declares for itself, if any
+    fn of(map: &serde_json::Map<String, Value>) -> Option<Self> {
+        if let Some(decimals) = map.get("decimals").and_then(Value::as_u64) {
+            let symbol = map
+                .get("symbol")
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            // Asset decimals are a u8; anything wider is not an asset
+            return Some(match u8::try_from(decimals) {
+                Ok(decimals) => Denomination::Asset {
+                    decimals: decimals.into(),
+                    symbol: symbol.to_string(),
+                },
+                Err(_) => Denomination::Unknown,
+            });
+        }
+        map.contains_key("asset_id")
+            .then_some(Denomination::Unknown)
+    }
+}
+
+/// Gregorian (year, month, day) of a day count since 1970-01-01
+pub fn gregorian_date(days: i64) -> (i64, u32, u32) {
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Tabular Islamic (year, month, day) of a day count since 1970-01-01
+pub fn hijri_date(days: i64) -> (i64, u32, u32) {
+    // Julian day number, then the civil (Friday epoch) arithmetic calendar
+    let jd = days + 2_440_588;
+    let mut l = jd - 1_948_440 + 10_632;
+    let n = (l - 1).div_euclid(10_631);
+    l = l - 10_631 * n + 354;
+    let j = ((10_985 - l) / 5316) * ((50 * l) / 17_719) + (l / 5670) * ((43 * l) / 15_238);
+    l = l - ((30 - j) / 15) * ((17_719 * j) / 50) - (j / 16) * ((15_238 * j) / 43) + 29;
+    let month = (24 * l) / 709;
+    let day = l - (709 * month) / 24;
+    let year = 30 * n + j - 30;
+    (year, month as u32, day as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// 2026-10-18 04:00 UTC
+    const TIMESTAMP: u64 = 1_792_296_000;
+
+    #[test]
+    fn test_calendars() {
+        assert_eq!(gregorian_date(0), (1970, 1, 1));
+        assert_eq!(gregorian_date(10_957), (2000, 1, 1));
+        assert_eq!(gregorian_date(11_016), (2000, 2, 29));
+        assert_eq!(gregorian_date(-1), (1969, 12, 31));
+
+        assert_eq!(hijri_date(0), (1389, 10, 22));
+        assert_eq!(hijri_date(10_957), (1420, 9, 24));
+        // 1 Ramadan 1446 was 2025-03-01
+        assert_eq!(hijri_date(20_148), (1446, 9, 1));
+    }
+
+    #[test]
+    fn test_formats() {
+        let en = DisplayFormat::new(Locale::English, Calendar::Gregorian);
+        let ar = DisplayFormat::new(Locale::ArabicSyria, Calendar::Gregorian);
+        let ar_hijri = DisplayFormat::new(Locale::ArabicSyria, Calendar::Hijri);
+
+        assert_eq!(en.format_integer(1_234_567), "1,234,567");
+        assert_eq!(ar.format_integer(1_234_567), "١٬٢٣٤٬٥٦٧");
+        assert_eq!(en.format_amount(1_234_500_000), "1,234.5 SYL");
+        assert_eq!(en.format_amount(50_000_000), "50 SYL");
+        assert_eq!(ar.format_amount(1_234_500_000), "١٬٢٣٤٫٥ ل.س");
+        assert_eq!(ar.format_amount(100), "٠٫٠٠٠١ ل.س");
+
+        assert_eq!(en.format_timestamp(TIMESTAMP), "18 October 2026, 04:00 UTC");
+        assert_eq!(ar.format_timestamp(TIMESTAMP), "١٨ تشرين الأول ٢٠٢٦، ٠٧:٠٠");
+        assert_eq!(
+            ar_hijri.format_timestamp(TIMESTAMP),
+            "٦ جمادى الأولى ١٤٤٨ هـ، ٠٧:٠٠"
+        );
+    }
+
+    #[test]
+    fn test_annotate_keeps_raw_values() {
+        let format = DisplayFormat::from_params(Some("ar-SY"), Some("bogus")).unwrap();
+        assert_eq!(format.calendar, Calendar::Gregorian);
+        let mut block = json!({
+            "height": 1200,
+            "hash": "00ab",
+            "timestamp": TIMESTAMP,
+            "transactions": [{"amount": 5_000_000, "fee": 100, "nonce": 3}],
+            "height_display": "kept"
+        });
+        format.annotate(&mut block);
+        assert_eq!(block["height"], 1200);
+        assert_eq!(block["height_display"], "kept");
+        assert_eq!(block["timestamp_display"], "١٨ تشرين الأول ٢٠٢٦، ٠٧:٠٠");
+        assert_eq!(block["transactions"][0]["amount"], 5_000_000);
+        assert_eq!(block["transactions"][0]["amount_display"], "٥ ل.س");
+        assert!(block["transactions"][0].get("nonce_display").is_none());
+
+        // Asset amounts keep their own unit
+        let mut balances = json!({
+            "balance": 2_500_000,
+            "assets": [{"asset_id": "aa", "symbol": "GOLD", "decimals": 2, "balance": 12_345}],
+            "order": {"asset_id": "aa", "amount": 7, "trades": [{"amount": 7}]}
+        });
+        format.annotate(&mut balances);
+        assert_eq!(balances["balance_display"], "٢٫٥ ل.س");
+        assert_eq!(balances["assets"][0]["balance_display"], "١٢٣٫٤٥ GOLD");
+        assert!(balances["order"].get("amount_display").is_none());
+        assert!(balances["order"]["trades"][0]
+            .get("amount_display")
+            .is_none());
+
+        assert_eq!(DisplayFormat::from_params(Some("fr"), None), None);
+        assert_eq!(DisplayFormat::from_params(None, Some("hijri")), None);
+    }
+}
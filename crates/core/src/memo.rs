@@ -0,0 +1,315 @@
+//! Transaction memos
+//! ملاحظات المعاملات
+//!
+//! A memo is carried in a transfer's `data`, either as plain UTF-8 or
+//! encrypted to the recipient. Encryption converts the recipient's Ed25519
+//! key to its X25519 form and agrees a key with a fresh ephemeral X25519 key,
+//! so only the recipient can read the memo and nothing in it links two memos
+//! from the same sender. The memo is sealed with AES-256-GCM.
+//!
+//! Data layout: [`MEMO_TAG`], version (1 byte), then per version. Version 1:
+//! ephemeral X25519 key (32 bytes), ciphertext with its 16-byte tag.
+//! Readers reject versions they do not know rather than guess.
+
+use crate::crypto::{KeyPair, PublicKey};
+use crate::transaction::Transaction;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::hazmat::ExpandedSecretKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use zeroize::Zeroize;
+
+/// Data prefix marking an encrypted memo
+pub const MEMO_TAG: &[u8] = b"opensyria-memo";
+
+/// Encrypted memo format written by this version
+pub const ENCRYPTED_MEMO_VERSION: u8 = 1;
+
+/// Longest memo, in bytes before encryption
+pub const MAX_MEMO_LEN: usize = 128;
+
+/// Data prefix of protocol operations (assets, orders, contracts, ...),
+/// which plain memos must not start with
+pub const RESERVED_DATA_PREFIX: &[u8] = b"opensyria-";
+
+const KEY_DOMAIN: &[u8] = b"opensyria-memo-key-v1";
+
+/// AES-GCM authentication tag length
+const AUTH_TAG_LEN: usize = 16;
+
+/// X25519 form of an Ed25519 public key, rejecting invalid and small-order
+/// keys
+fn to_x25519(key: &PublicKey) -> Option<MontgomeryPoint> {
+    CompressedEdwardsY(key.0)
+        .decompress()
+        .filter(|point| !point.is_small_order())
+        .map(|point| point.to_montgomery())
+}
+
+/// AES key for a memo from the X25519 shared secret
+fn memo_cipher(
+    shared: &MontgomeryPoint,
+    ephemeral: &[u8; 32],
+    recipient: &MontgomeryPoint,
+) -> Aes256Gcm {
+    let mut key: [u8; 32] = Sha256::new()
+        .chain_update(KEY_DOMAIN)
+        .chain_update(shared.as_bytes())
+        .chain_update(ephemeral)
+        .chain_update(recipient.as_bytes())
+        .finalize()
+        .into();
+    let cipher = Aes256Gcm::new_from_slice(&key).expect("32-byte key");
+    key.zeroize();
+    cipher
+}
+
+/// A memo encrypted to the recipient of a transaction
+/// ملاحظة مشفرة للمستلم
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedMemo {
+    pub version: u8,
+    /// Sender's one-time X25519 key
+    pub ephemeral: [u8; 32],
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedMemo {
+    /// Encrypt `memo` so that only the holder of `recipient`'s key reads it
+    pub fn encrypt(recipient: &PublicKey, memo: &[u8]) -> Result<Self, MemoError> {
+        if memo.len() > MAX_MEMO_LEN {
+            return Err(MemoError::TooLong(memo.len()));
+        }
+        let recipient = to_x25519(recipient).ok_or(MemoError::InvalidRecipient)?;
+
+        let mut wide = [0u8; 64];
+        OsRng.fill_bytes(&mut wide);
+        let mut secret = Scalar::from_bytes_mod_order_wide(&wide);
+        wide.zeroize();
+        let ephemeral = EdwardsPoint::mul_base(&secret).to_montgomery().to_bytes();
+        let shared = recipient * secret;
+        secret.zeroize();
+
+        let mut encrypted = Self {
+            version: ENCRYPTED_MEMO_VERSION,
+            ephemeral,
+            ciphertext: Vec::new(),
+        };
+        // Each memo has its own key, so a fixed nonce is never reused
+        encrypted.ciphertext = memo_cipher(&shared, &ephemeral, &recipient)
+            .encrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: memo,
+                    aad: &encrypted.header(),
+                },
+            )
+            .map_err(|_| MemoError::Malformed)?;
+        Ok(encrypted)
+    }
+
+    /// Decrypt with the recipient's key
+    pub fn decrypt(&self, keypair: &KeyPair) -> Result<Vec<u8>, MemoError> {
+        if self.version != ENCRYPTED_MEMO_VERSION {
+            return Err(MemoError::UnsupportedVersion(self.version));
+        }
+        let recipient = to_x25519(&keypair.public_key()).ok_or(MemoError::InvalidRecipient)?;
+        let mut secret = keypair.with_private_key(|seed| ExpandedSecretKey::from(seed).scalar);
+        let shared = MontgomeryPoint(self.ephemeral) * secret;
+        secret.zeroize();
+        // A small-order ephemeral key yields no secret at all
+        if shared.as_bytes() == &[0u8; 32] {
+            return Err(MemoError::DecryptionFailed);
+        }
+
+        memo_cipher(&shared, &self.ephemeral, &recipient)
+            .decrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: &self.header(),
+                },
+            )
+            .map_err(|_| MemoError::DecryptionFailed)
+    }
+
+    /// Whether `data` is an encrypted memo of any version
+    pub fn is_encrypted_memo(data: &[u8]) -> bool {
+        data.starts_with(MEMO_TAG)
+    }
+
+    /// Tag, version and ephemeral key, authenticated along with the
+    /// ciphertext
+    fn header(&self) -> Vec<u8> {
+        let mut header = MEMO_TAG.to_vec();
+        header.push(self.version);
+        header.extend_from_slice(&self.ephemeral);
+        header
+    }
+
+    /// Transaction data carrying the memo
+    pub fn to_data(&self) -> Vec<u8> {
+        let mut data = self.header();
+        data.extend_from_slice(&self.ciphertext);
+        data
+    }
+
+    /// Parse transaction data written by [`EncryptedMemo::to_data`]
+    pub fn from_data(data: &[u8]) -> Result<Self, MemoError> {
+        let payload = data.strip_prefix(MEMO_TAG).ok_or(MemoError::Malformed)?;
+        let (&version, payload) = payload.split_first().ok_or(MemoError::Malformed)?;
+        if version != ENCRYPTED_MEMO_VERSION {
+            return Err(MemoError::UnsupportedVersion(version));
+        }
+        if payload.len() < 32 + AUTH_TAG_LEN || payload.len() > 32 + AUTH_TAG_LEN + MAX_MEMO_LEN {
+            return Err(MemoError::Malformed);
+        }
+
+        let mut ephemeral = [0u8; 32];
+        ephemeral.copy_from_slice(&payload[..32]);
+        Ok(Self {
+            version,
+            ephemeral,
+            ciphertext: payload[32..].to_vec(),
+        })
+    }
+}
+
+/// Memo of a transaction, as found in its data
+/// ملاحظة المعاملة
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Memo {
+    Plain(Vec<u8>),
+    Encrypted(EncryptedMemo),
+}
+
+impl Memo {
+    /// Memo carried in `data`, if any. Protocol operations carry none
+    pub fn from_data(data: &[u8]) -> Result<Option<Self>, MemoError> {
+        if EncryptedMemo::is_encrypted_memo(data) {
+            return EncryptedMemo::from_data(data).map(|memo| Some(Memo::Encrypted(memo)));
+        }
+        if data.is_empty() || data.starts_with(RESERVED_DATA_PREFIX) {
+            return Ok(None);
+        }
+        Ok(Some(Memo::Plain(data.to_vec())))
+    }
+
+    /// Memo carried by `tx`, if any
+    pub fn from_transaction(tx: &Transaction) -> Result<Option<Self>, MemoError> {
+        match tx.data.as_deref() {
+            Some(data) => Self::from_data(data),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoError {
+    TooLong(usize),
+    /// The recipient address is not an account key memos can be encrypted to
+    InvalidRecipient,
+    UnsupportedVersion(u8),
+    Malformed,
+    DecryptionFailed,
+}
+
+impl std::fmt::Display for MemoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoError::TooLong(len) => {
+                write!(f, "Memo is {} bytes, at most {} allowed", len, MAX_MEMO_LEN)
+            }
+            MemoError::InvalidRecipient => {
+                write!(f, "Memos cannot be encrypted to this recipient")
+            }
+            MemoError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported encrypted memo version {}", version)
+            }
+            MemoError::Malformed => write!(f, "Malformed encrypted memo"),
+            MemoError::DecryptionFailed => write!(f, "Memo could not be decrypted"),
+        }
+    }
+}
+
+impl std::error::Error for MemoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_recipient_decrypts_memo() {
+        let recipient = KeyPair::generate();
+        let other = KeyPair::generate();
+
+        let memo = EncryptedMemo::encrypt(&recipient.public_key(), b"Invoice 7").unwrap();
+        let data = memo.to_data();
+        assert!(EncryptedMemo::is_encrypted_memo(&data));
+        assert!(!data.windows(3).any(|window| window == b"Inv"));
+
+        let parsed = EncryptedMemo::from_data(&data).unwrap();
+        assert_eq!(parsed, memo);
+        assert_eq!(parsed.decrypt(&recipient).unwrap(), b"Invoice 7");
+        assert_eq!(parsed.decrypt(&other), Err(MemoError::DecryptionFailed));
+
+        // Every memo uses a fresh ephemeral key
+        let again = EncryptedMemo::encrypt(&recipient.public_key(), b"Invoice 7").unwrap();
+        assert_ne!(again.ephemeral, memo.ephemeral);
+        assert_ne!(again.ciphertext, memo.ciphertext);
+
+        // Tampering with the ciphertext or header is detected
+        let mut tampered = memo.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(
+            tampered.decrypt(&recipient),
+            Err(MemoError::DecryptionFailed)
+        );
+        let mut tampered = memo;
+        tampered.ephemeral[0] ^= 1;
+        assert!(tampered.decrypt(&recipient).is_err());
+    }
+
+    #[test]
+    fn test_memo_format_and_versions() {
+        let recipient = KeyPair::generate().public_key();
+        let memo = EncryptedMemo::encrypt(&recipient, b"").unwrap();
+        let mut data = memo.to_data();
+        assert_eq!(data.len(), MEMO_TAG.len() + 1 + 32 + AUTH_TAG_LEN);
+
+        data[MEMO_TAG.len()] = 2;
+        assert_eq!(
+            EncryptedMemo::from_data(&data),
+            Err(MemoError::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Memo::from_data(&memo.to_data()[..MEMO_TAG.len() + 10]),
+            Err(MemoError::Malformed)
+        );
+
+        assert_eq!(
+            EncryptedMemo::encrypt(&recipient, &[0u8; MAX_MEMO_LEN + 1]),
+            Err(MemoError::TooLong(MAX_MEMO_LEN + 1))
+        );
+        assert_eq!(
+            EncryptedMemo::encrypt(&PublicKey::zero(), b"hi"),
+            Err(MemoError::InvalidRecipient)
+        );
+
+        assert_eq!(
+            Memo::from_data(b"Order 42"),
+            Ok(Some(Memo::Plain(b"Order 42".to_vec())))
+        );
+        assert_eq!(Memo::from_data(b"opensyria-dex-v1"), Ok(None));
+        assert_eq!(Memo::from_data(b""), Ok(None));
+        assert!(matches!(
+            Memo::from_data(&memo.to_data()),
+            Ok(Some(Memo::Encrypted(_)))
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+//! Signed messages proving control of an address
+//! الرسائل الموقعة لإثبات ملكية عنوان
+//!
+//! Exchanges and other services ask users to sign a challenge text with the
+//! key behind an address. The signature covers a digest of the text under
+//! [`MESSAGE_DOMAIN`], so a signed message can never be replayed as a
+//! transaction signature (or the reverse), and the envelope is plain JSON:
+//!
+//! ```json
+//! {"address": "<hex public key>", "message": "<text>", "signature": "<hex>"}
+//! ```
+
+use crate::crypto::{KeyPair, PublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Domain separator of signed message digests
+pub const MESSAGE_DOMAIN: &[u8] = b"opensyria-signed-message-v1";
+
+/// Longest message that can be signed, in bytes
+pub const MAX_MESSAGE_LEN: usize = 4096;
+
+/// Errors signing or verifying messages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageError {
+    /// The message is longer than [`MAX_MESSAGE_LEN`]
+    TooLong { len: usize },
+    /// The address is not a hex public key
+    InvalidAddress,
+    /// The signature is malformed or was not made by the address
+    InvalidSignature,
+}
+
+impl std::fmt::Display for MessageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageError::TooLong { len } => write!(
+                f,
+                "Message is {} bytes; at most {} can be signed",
+                len, MAX_MESSAGE_LEN
+            ),
+            MessageError::InvalidAddress => write!(f, "Invalid address"),
+            MessageError::InvalidSignature => {
+                write!(f, "Signature does not match the address and message")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+/// Digest a message is signed over
+pub fn message_digest(message: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(MESSAGE_DOMAIN);
+    hasher.update((message.len() as u64).to_le_bytes());
+    hasher.update(message.as_bytes());
+    hasher.finalize().into()
+}
+
+/// A message signed by the key of an address
+/// رسالة موقعة بمفتاح عنوان
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedMessage {
+    /// Signing address (hex public key)
+    pub address: String,
+    pub message: String,
+    /// Signature over [`message_digest`] (hex)
+    pub signature: String,
+}
+
+impl SignedMessage {
+    pub fn sign(message: &str, keypair: &KeyPair) -> Result<Self, MessageError> {
+        if message.len() > MAX_MESSAGE_LEN {
+            return Err(MessageError::TooLong { len: message.len() });
+        }
+
+        Ok(Self {
+            address: keypair.public_key().to_hex(),
+            message: message.to_string(),
+            signature: hex::encode(keypair.sign(&message_digest(message))),
+        })
+    }
+
+    /// Check the signature, returning the address it proves control of
+    pub fn verify(&self) -> Result<PublicKey, MessageError> {
+        if self.message.len() > MAX_MESSAGE_LEN {
+            return Err(MessageError::TooLong {
+                len: self.message.len(),
+            });
+        }
+
+        let address =
+            PublicKey::from_hex(&self.address).map_err(|_| MessageError::InvalidAddress)?;
+        let signature = hex::decode(&self.signature).map_err(|_| MessageError::InvalidSignature)?;
+        address
+            .verify(&message_digest(&self.message), &signature)
+            .map_err(|_| MessageError::InvalidSignature)?;
+        Ok(address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transaction::Transaction;
+
+    #[test]
+    fn test_sign_and_verify_message() {
+        let keypair = KeyPair::generate();
+        let signed = SignedMessage::sign("withdraw to exchange #4821", &keypair).unwrap();
+        assert_eq!(signed.verify().unwrap(), keypair.public_key());
+
+        let json = serde_json::to_string(&signed).unwrap();
+        let parsed: SignedMessage = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, signed);
+
+        let mut tampered = signed.clone();
+        tampered.message.push('!');
+        assert_eq!(tampered.verify(), Err(MessageError::InvalidSignature));
+
+        let mut other = signed;
+        other.address = KeyPair::generate().public_key().to_hex();
+        assert_eq!(other.verify(), Err(MessageError::InvalidSignature));
+
+        let long = "a".repeat(MAX_MESSAGE_LEN + 1);
+        assert_eq!(
+            SignedMessage::sign(&long, &keypair),
+            Err(MessageError::TooLong {
+                len: MAX_MESSAGE_LEN + 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_transaction_signature_is_not_a_message_signature() {
+        let keypair = KeyPair::generate();
+        let to = KeyPair::generate().public_key();
+        let tx = Transaction::new(keypair.public_key(), to, 1, 100, 0);
+        let hash = tx.signing_hash();
+        let tx = tx.with_signature(keypair.sign(&hash));
+
+        // The signing hash as message text does not produce the same digest
+        let forged = SignedMessage {
+            address: keypair.public_key().to_hex(),
+            message: hex::encode(hash),
+            signature: hex::encode(&tx.signature),
+        };
+        assert_eq!(forged.verify(), Err(MessageError::InvalidSignature));
+    }
+}
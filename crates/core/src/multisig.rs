@@ -1,7 +1,11 @@
 use crate::crypto::PublicKey;
+use crate::params::ChainParams;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+/// Domain tag for the chain ID in multisig signing hashes
+const MULTISIG_CHAIN_DOMAIN: &[u8] = b"opensyria-multisig-chain-v1";
+
 /// Multi-signature account configuration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[derive(bincode::Encode, bincode::Decode)]
@@ -97,6 +101,10 @@ pub struct MultisigTransaction {
     pub data: Option<Vec<u8>>,
     /// Expiry block height (transaction invalid after this)
     pub expiry_height: Option<u64>,
+    /// Chain the signatures are bound to; unbound transactions are only
+    /// accepted before the replay protection fork
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u32>,
 }
 
 /// Single signature entry with signer identification
@@ -121,9 +129,19 @@ impl MultisigTransaction {
             signatures: Vec::new(),
             data: None,
             expiry_height: None,
+            chain_id: None,
         }
     }
 
+    /// Bind the signatures to a chain
+    /// ربط التواقيع بسلسلة محددة
+    ///
+    /// Set before collecting signatures, since the signing hash covers it.
+    pub fn with_chain_id(mut self, chain_id: u32) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
     /// Set expiry block height
     pub fn with_expiry(mut self, expiry_height: u64) -> Self {
         self.expiry_height = Some(expiry_height);
@@ -146,9 +164,17 @@ impl MultisigTransaction {
     }
 
     /// Get signing hash (what each signer signs)
+    ///
+    /// Only transactions bound to a chain commit to its ID, so the hash of an
+    /// unbound transaction is unchanged.
     pub fn signing_hash(&self) -> [u8; 32] {
         let mut hasher = Sha256::new();
 
+        if let Some(chain_id) = self.chain_id {
+            hasher.update(MULTISIG_CHAIN_DOMAIN);
+            hasher.update(chain_id.to_le_bytes());
+        }
+
         // Include multisig address
         let address = self.account.address();
         hasher.update(address.0);
@@ -244,6 +270,26 @@ impl MultisigTransaction {
     pub fn is_ready(&self) -> bool {
         self.signatures.len() >= self.account.threshold as usize
     }
+
+    /// Reject signatures bound to another chain, and unbound ones once
+    /// replay protection is active at `height`
+    /// رفض التواقيع المرتبطة بسلسلة أخرى أو غير المرتبطة بعد التفعيل
+    pub fn validate_chain_id(
+        &self,
+        params: &ChainParams,
+        height: u64,
+    ) -> Result<(), MultisigError> {
+        match self.chain_id {
+            Some(chain_id) if chain_id != params.chain_id => Err(MultisigError::WrongChainId {
+                expected: params.chain_id,
+                got: chain_id,
+            }),
+            None if params.replay_protection_active(height) => Err(MultisigError::UnboundChainId {
+                expected: params.chain_id,
+            }),
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -256,6 +302,8 @@ pub enum MultisigError {
     DuplicateSignature,
     InvalidSignature,
     InsufficientSignatures { required: u8, provided: u8 },
+    WrongChainId { expected: u32, got: u32 },
+    UnboundChainId { expected: u32 },
 }
 
 impl std::fmt::Display for MultisigError {
@@ -283,6 +331,20 @@ impl std::fmt::Display for MultisigError {
                     required, provided
                 )
             }
+            MultisigError::WrongChainId { expected, got } => {
+                write!(
+                    f,
+                    "Multisig transaction signed for chain {}, expected {}",
+                    got, expected
+                )
+            }
+            MultisigError::UnboundChainId { expected } => {
+                write!(
+                    f,
+                    "Multisig transaction must be signed for chain {}",
+                    expected
+                )
+            }
         }
     }
 }
@@ -395,6 +457,38 @@ mod tests {
         assert!(tx.verify().is_ok());
     }
 
+    #[test]
+    fn test_multisig_chain_binding() {
+        use crate::constants::CHAIN_ID_TESTNET;
+
+        let signer = KeyPair::generate();
+        let recipient = KeyPair::generate();
+        let account = MultisigAccount::new(vec![signer.public_key()], 1).unwrap();
+        let mainnet = ChainParams::mainnet();
+        let fork = mainnet.replay_protection_height;
+
+        let unbound = MultisigTransaction::new(account, recipient.public_key(), 1_000_000, 100, 0);
+        let testnet = unbound.clone().with_chain_id(CHAIN_ID_TESTNET);
+        let bound = unbound.clone().with_chain_id(mainnet.chain_id);
+
+        // Binding changes what signers sign, so old signatures do not carry over
+        assert_ne!(unbound.signing_hash(), bound.signing_hash());
+        assert_ne!(testnet.signing_hash(), bound.signing_hash());
+
+        assert!(unbound.validate_chain_id(&mainnet, fork - 1).is_ok());
+        assert_eq!(
+            unbound.validate_chain_id(&mainnet, fork),
+            Err(MultisigError::UnboundChainId {
+                expected: mainnet.chain_id
+            })
+        );
+        assert!(matches!(
+            testnet.validate_chain_id(&mainnet, 1),
+            Err(MultisigError::WrongChainId { .. })
+        ));
+        assert!(bound.validate_chain_id(&mainnet, fork).is_ok());
+    }
+
     #[test]
     fn test_multisig_unauthorized_signer() {
         let signer1 = KeyPair::generate();
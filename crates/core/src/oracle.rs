@@ -0,0 +1,327 @@
+//! Exchange rate oracle
+//! أوراكل أسعار الصرف
+//!
+//! Rates such as `SYP/USD` are published on chain by oracle keys that
+//! governance has whitelisted. An oracle update is a zero-amount transfer to
+//! self whose data is [`ORACLE_TAG`] followed by the bincode-encoded
+//! [`OracleUpdate`]. Each [`RateQuote`] must be newer than the last rate on
+//! chain for its pair and dated no more than [`MAX_RATE_FUTURE_SECS`] after
+//! the block carrying it; the newest one is what other subsystems read.
+//!
+//! Off chain, a [`RateAttestation`] is a quote signed by an oracle key, which
+//! anyone can check without trusting the server that handed it out.
+
+use crate::crypto::{KeyPair, PublicKey};
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Data prefix marking an oracle update
+pub const ORACLE_TAG: &[u8] = b"opensyria-oracle-v1";
+
+/// Rates are fixed point: units of the quote currency per unit of the base
+/// currency, times `RATE_SCALE`
+pub const RATE_SCALE: u64 = 100_000_000;
+
+/// Most quotes in one oracle update
+pub const MAX_RATES_PER_UPDATE: usize = 32;
+
+/// How far ahead of a block timestamp or node clock a quote may be dated
+pub const MAX_RATE_FUTURE_SECS: u64 = 600;
+
+const ATTESTATION_DOMAIN: &[u8] = b"opensyria-rate-attestation-v1";
+
+/// Check a currency pair is written `BASE/QUOTE`, each 2-10 uppercase
+/// letters or digits
+pub fn validate_pair(pair: &str) -> Result<(), OracleError> {
+    let code = |s: &str| {
+        (2..=10).contains(&s.len())
+            && s.bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+    };
+    match pair.split_once('/') {
+        Some((base, quote)) if code(base) && code(quote) && base != quote => Ok(()),
+        _ => Err(OracleError::InvalidUpdate(format!(
+            "pair '{}' must be BASE/QUOTE",
+            pair
+        ))),
+    }
+}
+
+/// Format a fixed-point rate as a decimal, without trailing zeros
+pub fn format_rate(rate: u64) -> String {
+    let fraction = format!("{:08}", rate % RATE_SCALE);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        (rate / RATE_SCALE).to_string()
+    } else {
+        format!("{}.{}", rate / RATE_SCALE, fraction)
+    }
+}
+
+/// A rate for one currency pair at a point in time
+/// سعر صرف لزوج عملات
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct RateQuote {
+    /// Currency pair, e.g. `SYP/USD`
+    pub pair: String,
+    /// Fixed-point rate, see [`RATE_SCALE`]
+    pub rate: u64,
+    /// Unix time in seconds the rate was observed
+    pub timestamp: u64,
+}
+
+impl RateQuote {
+    pub fn validate(&self) -> Result<(), OracleError> {
+        validate_pair(&self.pair)?;
+        if self.rate == 0 {
+            return Err(OracleError::InvalidUpdate(format!(
+                "rate for {} must be positive",
+                self.pair
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Rates published in one transaction
+/// تحديث أسعار الصرف
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct OracleUpdate {
+    pub rates: Vec<RateQuote>,
+}
+
+impl OracleUpdate {
+    /// Check the update is well formed on its own
+    pub fn validate(&self) -> Result<(), OracleError> {
+        if self.rates.is_empty() || self.rates.len() > MAX_RATES_PER_UPDATE {
+            return Err(OracleError::InvalidUpdate(format!(
+                "update must carry 1 to {} rates",
+                MAX_RATES_PER_UPDATE
+            )));
+        }
+        let mut pairs = Vec::with_capacity(self.rates.len());
+        for quote in &self.rates {
+            quote.validate()?;
+            if pairs.contains(&&quote.pair) {
+                return Err(OracleError::InvalidUpdate(format!(
+                    "duplicate pair {}",
+                    quote.pair
+                )));
+            }
+            pairs.push(&quote.pair);
+        }
+        Ok(())
+    }
+
+    /// Check no quote is dated more than [`MAX_RATE_FUTURE_SECS`] after `now`
+    ///
+    /// Consensus applies this against the block timestamp and mempools
+    /// against their own clock, so a quote cannot block its pair until a
+    /// far-off timestamp passes.
+    pub fn check_clock(&self, now: u64) -> Result<(), OracleError> {
+        match self
+            .rates
+            .iter()
+            .find(|quote| quote.timestamp > now.saturating_add(MAX_RATE_FUTURE_SECS))
+        {
+            Some(quote) => Err(OracleError::InvalidUpdate(format!(
+                "rate for {} is dated in the future",
+                quote.pair
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Unsigned transaction publishing these rates from `oracle`
+    /// إنشاء معاملة نشر الأسعار
+    pub fn transaction(
+        &self,
+        chain_id: u32,
+        oracle: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = ORACLE_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("oracle update encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, oracle, oracle, 0, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is an oracle update, well formed or not
+    pub fn is_oracle(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(ORACLE_TAG))
+    }
+
+    /// Update carried by `tx`, or `None` if it is not an oracle update
+    pub fn from_transaction(tx: &Transaction) -> Option<Result<OracleUpdate, OracleError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(ORACLE_TAG)?;
+        if tx.to != tx.from || tx.amount != 0 {
+            return Some(Err(OracleError::InvalidUpdate(
+                "oracle update must be a zero-amount transfer to self".to_string(),
+            )));
+        }
+        let decoded =
+            bincode::decode_from_slice::<OracleUpdate, _>(payload, bincode::config::standard());
+        Some(match decoded {
+            Ok((update, read)) if read == payload.len() => update.validate().map(|_| update),
+            _ => Err(OracleError::InvalidUpdate(
+                "malformed oracle encoding".to_string(),
+            )),
+        })
+    }
+}
+
+/// A rate as recorded in state
+/// سعر صرف منشور على السلسلة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct OracleRate {
+    pub quote: RateQuote,
+    /// Oracle key that published it
+    pub publisher: PublicKey,
+    /// Block that included it
+    pub height: u64,
+}
+
+/// A quote signed by an oracle key
+/// شهادة سعر موقعة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateAttestation {
+    pub quote: RateQuote,
+    pub signer: PublicKey,
+    pub signature: Vec<u8>,
+}
+
+impl RateAttestation {
+    /// Hash an attestation signs: the domain followed by the encoded quote
+    pub fn signing_hash(quote: &RateQuote) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(ATTESTATION_DOMAIN);
+        hasher.update(
+            bincode::encode_to_vec(quote, bincode::config::standard())
+                .expect("rate quote encoding cannot fail"),
+        );
+        hasher.finalize().into()
+    }
+
+    pub fn sign(quote: RateQuote, keypair: &KeyPair) -> Self {
+        let signature = keypair.sign(&Self::signing_hash(&quote));
+        Self {
+            quote,
+            signer: keypair.public_key(),
+            signature,
+        }
+    }
+
+    /// Check the signature; whether the signer is a trusted oracle is up to
+    /// the caller
+    pub fn verify(&self) -> Result<(), OracleError> {
+        self.signer
+            .verify(&Self::signing_hash(&self.quote), &self.signature)
+            .map_err(|_| OracleError::InvalidAttestation(self.signer.to_hex()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OracleError {
+    InvalidUpdate(String),
+    NotAnOracle(String),
+    StaleRate {
+        pair: String,
+        timestamp: u64,
+        latest: u64,
+    },
+    InvalidAttestation(String),
+}
+
+impl std::fmt::Display for OracleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OracleError::InvalidUpdate(reason) => write!(f, "Invalid oracle update: {}", reason),
+            OracleError::NotAnOracle(key) => write!(f, "Key {} is not a whitelisted oracle", key),
+            OracleError::StaleRate {
+                pair,
+                timestamp,
+                latest,
+            } => write!(
+                f,
+                "Rate for {} at {} is not newer than the rate at {}",
+                pair, timestamp, latest
+            ),
+            OracleError::InvalidAttestation(key) => {
+                write!(f, "Invalid rate attestation signature from {}", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OracleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(pair: &str, rate: u64) -> RateQuote {
+        RateQuote {
+            pair: pair.to_string(),
+            rate,
+            timestamp: 1_760_000_000,
+        }
+    }
+
+    #[test]
+    fn test_update_round_trip() {
+        let oracle = KeyPair::generate().public_key();
+        let update = OracleUpdate {
+            rates: vec![quote("SYP/USD", 1_300_000_000_000), quote("SYP/EUR", 42)],
+        };
+        let tx = update.transaction(963, oracle, 100, 0);
+        assert!(OracleUpdate::is_oracle(&tx));
+        assert_eq!(OracleUpdate::from_transaction(&tx), Some(Ok(update)));
+
+        let plain = Transaction::new(oracle, oracle, 0, 100, 1);
+        assert_eq!(OracleUpdate::from_transaction(&plain), None);
+    }
+
+    #[test]
+    fn test_invalid_updates_rejected() {
+        for pair in ["SYPUSD", "syp/usd", "S/USD", "USD/USD", "SYP/USD/EUR"] {
+            assert!(validate_pair(pair).is_err(), "{}", pair);
+        }
+        assert!(quote("SYP/USD", 0).validate().is_err());
+        assert!(OracleUpdate { rates: vec![] }.validate().is_err());
+        let duplicate = OracleUpdate {
+            rates: vec![quote("SYP/USD", 1), quote("SYP/USD", 2)],
+        };
+        assert!(duplicate.validate().is_err());
+
+        let update = OracleUpdate {
+            rates: vec![quote("SYP/USD", 1)],
+        };
+        assert!(update.check_clock(1_760_000_000).is_ok());
+        assert!(update
+            .check_clock(1_760_000_000 - MAX_RATE_FUTURE_SECS - 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_attestation_signature() {
+        let keypair = KeyPair::generate();
+        let mut attestation =
+            RateAttestation::sign(quote("SYP/USD", 13_000 * RATE_SCALE), &keypair);
+        assert!(attestation.verify().is_ok());
+
+        attestation.quote.rate += 1;
+        assert!(matches!(
+            attestation.verify(),
+            Err(OracleError::InvalidAttestation(_))
+        ));
+
+        assert_eq!(format_rate(13_000 * RATE_SCALE), "13000");
+        assert_eq!(format_rate(RATE_SCALE / 4), "0.25");
+    }
+}
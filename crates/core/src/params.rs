@@ -0,0 +1,392 @@
+//! Chain parameter registry
+//! سجل معاملات السلسلة
+//!
+//! `ChainParams` collects the consensus and policy values that were
+//! previously spread across crates as hard-coded constants. Each network has
+//! a built-in parameter set, and a JSON config may override individual fields
+//! on top of a named base network. The constants in [`crate::constants`]
+//! remain as the mainnet defaults.
+
+use crate::constants::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const PARAMS_HASH_DOMAIN: &[u8] = b"opensyria-chain-params-v1";
+
+/// Errors loading or validating chain parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamsError {
+    /// Config names a network that has no built-in parameters
+    UnknownNetwork(String),
+    /// Config could not be read or parsed
+    Parse(String),
+    /// A parameter is outside its allowed range
+    Invalid(&'static str),
+}
+
+impl std::fmt::Display for ParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamsError::UnknownNetwork(name) => write!(f, "Unknown network: {}", name),
+            ParamsError::Parse(e) => write!(f, "Failed to parse chain parameters: {}", e),
+            ParamsError::Invalid(reason) => write!(f, "Invalid chain parameters: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError {}
+
+/// Consensus and policy parameters for one network
+/// معاملات الإجماع والسياسة لشبكة واحدة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainParams {
+    /// Network name (mainnet, testnet, regtest)
+    pub network: String,
+    /// Chain identifier for replay protection
+    pub chain_id: u32,
+    /// Height from which transactions signed for another chain are rejected
+    pub replay_protection_height: u64,
+
+    /// Target block time in seconds
+    pub target_block_time_secs: u64,
+    /// Blocks between difficulty adjustments
+    pub difficulty_adjustment_interval: u32,
+    /// Height from which block difficulty must follow the retarget schedule
+    pub difficulty_schedule_height: u64,
+    /// Height from which a block may carry more than one coinbase output
+    pub coinbase_split_height: u64,
+    /// Height from which coinbase extra data is size-limited
+    pub coinbase_extra_data_height: u64,
+    /// Difficulty of the genesis block
+    pub genesis_difficulty: u32,
+    /// Lower difficulty bound
+    pub min_difficulty: u32,
+    /// Upper difficulty bound
+    pub max_difficulty: u32,
+    /// Maximum reorganization depth
+    pub max_reorg_depth: u64,
+    /// Confirmations before a transaction is displayed as final
+    pub confirmation_target: u64,
+    /// Confirmations before a block reward is displayed as final
+    pub coinbase_maturity: u64,
+
+    /// Initial block reward in base units
+    pub initial_block_reward: u64,
+    /// Blocks between reward halvings
+    pub halving_interval: u64,
+    /// Maximum total supply in base units
+    pub max_supply: u64,
+
+    /// Minimum transaction fee accepted by consensus
+    pub min_transaction_fee: u64,
+    /// Transfers below this amount are treated as dust
+    pub dust_threshold: u64,
+    /// Maximum block size in bytes
+    pub max_block_size: usize,
+    /// Maximum transactions per block
+    pub max_transactions_per_block: usize,
+}
+
+impl ChainParams {
+    /// Mainnet parameters (the protocol constants)
+    pub fn mainnet() -> Self {
+        Self {
+            network: "mainnet".to_string(),
+            chain_id: CHAIN_ID_MAINNET,
+            replay_protection_height: REPLAY_PROTECTION_HEIGHT,
+            target_block_time_secs: TARGET_BLOCK_TIME_SECS,
+            difficulty_adjustment_interval: DIFFICULTY_ADJUSTMENT_INTERVAL,
+            difficulty_schedule_height: DIFFICULTY_SCHEDULE_HEIGHT,
+            coinbase_split_height: COINBASE_SPLIT_HEIGHT,
+            coinbase_extra_data_height: COINBASE_EXTRA_DATA_HEIGHT,
+            genesis_difficulty: GENESIS_DIFFICULTY,
+            min_difficulty: MIN_DIFFICULTY,
+            max_difficulty: MAX_DIFFICULTY,
+            max_reorg_depth: MAX_REORG_DEPTH,
+            confirmation_target: CONFIRMATION_TARGET,
+            coinbase_maturity: COINBASE_MATURITY,
+            initial_block_reward: INITIAL_BLOCK_REWARD,
+            halving_interval: HALVING_INTERVAL,
+            max_supply: MAX_SUPPLY,
+            min_transaction_fee: MIN_TRANSACTION_FEE,
+            dust_threshold: DUST_THRESHOLD,
+            max_block_size: MAX_BLOCK_SIZE,
+            max_transactions_per_block: MAX_TRANSACTIONS_PER_BLOCK,
+        }
+    }
+
+    /// Testnet parameters: same economics, separate chain ID, faster blocks
+    /// that retarget sooner from an easier start
+    pub fn testnet() -> Self {
+        Self {
+            network: "testnet".to_string(),
+            chain_id: CHAIN_ID_TESTNET,
+            replay_protection_height: 0,
+            target_block_time_secs: 30,
+            difficulty_adjustment_interval: 50,
+            difficulty_schedule_height: 0,
+            coinbase_split_height: 0,
+            coinbase_extra_data_height: 0,
+            genesis_difficulty: 12,
+            ..Self::mainnet()
+        }
+    }
+
+    /// Local regression-test parameters: difficulty pinned to the minimum so
+    /// blocks can be generated as fast as tests need them
+    pub fn regtest() -> Self {
+        Self {
+            network: "regtest".to_string(),
+            chain_id: CHAIN_ID_REGTEST,
+            replay_protection_height: 0,
+            difficulty_adjustment_interval: 10,
+            difficulty_schedule_height: 0,
+            coinbase_split_height: 0,
+            coinbase_extra_data_height: 0,
+            genesis_difficulty: MIN_DIFFICULTY,
+            max_difficulty: MIN_DIFFICULTY,
+            halving_interval: 150,
+            confirmation_target: 1,
+            coinbase_maturity: 10,
+            dust_threshold: 0,
+            ..Self::mainnet()
+        }
+    }
+
+    /// Built-in parameters for a network name
+    pub fn for_network(name: &str) -> Result<Self, ParamsError> {
+        match name {
+            "mainnet" => Ok(Self::mainnet()),
+            "testnet" => Ok(Self::testnet()),
+            "regtest" => Ok(Self::regtest()),
+            other => Err(ParamsError::UnknownNetwork(other.to_string())),
+        }
+    }
+
+    /// Built-in parameters for a chain ID
+    pub fn for_chain_id(chain_id: u32) -> Option<Self> {
+        [Self::mainnet(), Self::testnet(), Self::regtest()]
+            .into_iter()
+            .find(|params| params.chain_id == chain_id)
+    }
+
+    /// Load parameters from JSON
+    /// تحميل المعاملات من JSON
+    ///
+    /// The object's `network` field (default `mainnet`) selects the base
+    /// parameter set; every other field present overrides that base.
+    pub fn from_json(json: &str) -> Result<Self, ParamsError> {
+        let overrides: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| ParamsError::Parse(e.to_string()))?;
+        let overrides = overrides
+            .as_object()
+            .ok_or_else(|| ParamsError::Parse("expected a JSON object".to_string()))?;
+
+        let network = overrides
+            .get("network")
+            .and_then(|v| v.as_str())
+            .unwrap_or("mainnet");
+        let mut merged = serde_json::to_value(Self::for_network(network)?)
+            .map_err(|e| ParamsError::Parse(e.to_string()))?;
+        if let Some(base) = merged.as_object_mut() {
+            for (key, value) in overrides {
+                if !base.contains_key(key) {
+                    return Err(ParamsError::Parse(format!("unknown parameter `{}`", key)));
+                }
+                base.insert(key.clone(), value.clone());
+            }
+        }
+
+        let params: Self =
+            serde_json::from_value(merged).map_err(|e| ParamsError::Parse(e.to_string()))?;
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Load parameters from a JSON file
+    pub fn from_file(path: &Path) -> Result<Self, ParamsError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ParamsError::Parse(format!("{}: {}", path.display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    /// Check that parameters are internally consistent
+    pub fn validate(&self) -> Result<(), ParamsError> {
+        if self.target_block_time_secs == 0 {
+            return Err(ParamsError::Invalid(
+                "target_block_time_secs must be positive",
+            ));
+        }
+        if self.difficulty_adjustment_interval == 0 {
+            return Err(ParamsError::Invalid(
+                "difficulty_adjustment_interval must be positive",
+            ));
+        }
+        if self.min_difficulty > self.max_difficulty {
+            return Err(ParamsError::Invalid(
+                "min_difficulty exceeds max_difficulty",
+            ));
+        }
+        if self.genesis_difficulty < self.min_difficulty
+            || self.genesis_difficulty > self.max_difficulty
+        {
+            return Err(ParamsError::Invalid(
+                "genesis_difficulty outside difficulty bounds",
+            ));
+        }
+        if self.halving_interval == 0 {
+            return Err(ParamsError::Invalid("halving_interval must be positive"));
+        }
+        if self.initial_block_reward > self.max_supply {
+            return Err(ParamsError::Invalid(
+                "initial_block_reward exceeds max_supply",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Whether transactions in a block at `height` must carry this chain's ID
+    /// هل يجب أن تحمل المعاملات معرّف هذه السلسلة عند الارتفاع المحدد
+    pub fn replay_protection_active(&self, height: u64) -> bool {
+        height >= self.replay_protection_height
+    }
+
+    /// Whether a block at `height` must carry the difficulty the retarget
+    /// schedule gives it
+    /// هل يجب أن تتبع صعوبة الكتلة جدول التعديل عند الارتفاع المحدد
+    pub fn difficulty_schedule_active(&self, height: u64) -> bool {
+        height >= self.difficulty_schedule_height
+    }
+
+    /// Whether a block at `height` may split its reward across several
+    /// coinbase outputs
+    /// هل يجوز توزيع مكافأة الكتلة على عدة مخرجات عند الارتفاع المحدد
+    pub fn coinbase_split_active(&self, height: u64) -> bool {
+        height >= self.coinbase_split_height
+    }
+
+    /// Whether coinbase extra data in a block at `height` is size-limited
+    /// هل يُطبَّق حد البيانات الإضافية في الكوين بيس عند الارتفاع المحدد
+    pub fn coinbase_extra_data_active(&self, height: u64) -> bool {
+        height >= self.coinbase_extra_data_height
+    }
+
+    /// Block reward at `height` under these parameters
+    /// مكافأة الكتلة عند الارتفاع المحدد
+    pub fn block_reward(&self, height: u64) -> u64 {
+        if height == 0 {
+            return 0;
+        }
+        let halvings = (height - 1) / self.halving_interval;
+        if halvings >= 64 {
+            return 0;
+        }
+        self.initial_block_reward >> halvings
+    }
+
+    /// Fingerprint of every parameter value
+    /// بصمة جميع قيم المعاملات
+    ///
+    /// Two nodes agree on consensus rules only if their hashes match; builds
+    /// and release manifests record the hash of the network they target.
+    pub fn params_hash(&self) -> [u8; 32] {
+        let encoded = serde_json::to_vec(self).expect("chain params serialize to JSON");
+        let mut hasher = Sha256::new();
+        hasher.update(PARAMS_HASH_DOMAIN);
+        hasher.update(&encoded);
+        hasher.finalize().into()
+    }
+}
+
+impl Default for ChainParams {
+    fn default() -> Self {
+        Self::mainnet()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mainnet_matches_constants() {
+        let params = ChainParams::mainnet();
+        assert_eq!(params.chain_id, CHAIN_ID_MAINNET);
+        for height in [
+            0,
+            1,
+            HALVING_INTERVAL,
+            HALVING_INTERVAL + 1,
+            64 * HALVING_INTERVAL + 1,
+        ] {
+            assert_eq!(params.block_reward(height), calculate_block_reward(height));
+        }
+        assert!(params.validate().is_ok());
+    }
+
+    #[test]
+    fn test_testnet_retargets_faster() {
+        let (mainnet, testnet) = (ChainParams::mainnet(), ChainParams::testnet());
+        assert!(testnet.validate().is_ok());
+        assert!(testnet.target_block_time_secs < mainnet.target_block_time_secs);
+        assert!(testnet.difficulty_adjustment_interval < mainnet.difficulty_adjustment_interval);
+        assert_eq!(testnet.max_supply, mainnet.max_supply);
+    }
+
+    #[test]
+    fn test_lookup_by_chain_id() {
+        assert_eq!(
+            ChainParams::for_chain_id(CHAIN_ID_TESTNET).unwrap().network,
+            "testnet"
+        );
+        assert_eq!(
+            ChainParams::for_chain_id(CHAIN_ID_REGTEST).unwrap().network,
+            "regtest"
+        );
+        assert!(ChainParams::for_chain_id(1).is_none());
+    }
+
+    #[test]
+    fn test_json_overrides_base_network() {
+        let params =
+            ChainParams::from_json(r#"{"network": "testnet", "target_block_time_secs": 30}"#)
+                .unwrap();
+        assert_eq!(params.chain_id, CHAIN_ID_TESTNET);
+        assert_eq!(params.target_block_time_secs, 30);
+        assert_eq!(params.max_supply, MAX_SUPPLY);
+    }
+
+    #[test]
+    fn test_params_hash_tracks_values() {
+        assert_eq!(
+            ChainParams::mainnet().params_hash(),
+            ChainParams::mainnet().params_hash()
+        );
+        assert_ne!(
+            ChainParams::mainnet().params_hash(),
+            ChainParams::testnet().params_hash()
+        );
+        let tweaked = ChainParams {
+            max_reorg_depth: MAX_REORG_DEPTH + 1,
+            ..ChainParams::mainnet()
+        };
+        assert_ne!(tweaked.params_hash(), ChainParams::mainnet().params_hash());
+    }
+
+    #[test]
+    fn test_json_rejects_bad_config() {
+        assert!(matches!(
+            ChainParams::from_json(r#"{"network": "devnet"}"#),
+            Err(ParamsError::UnknownNetwork(_))
+        ));
+        assert!(matches!(
+            ChainParams::from_json(r#"{"block_time": 30}"#),
+            Err(ParamsError::Parse(_))
+        ));
+        assert!(matches!(
+            ChainParams::from_json(r#"{"halving_interval": 0}"#),
+            Err(ParamsError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! Account spending policies
+//! سياسات الإنفاق للحسابات
+//!
+//! An account attaches a [`SpendingPolicy`] to itself with a policy update: a
+//! zero-amount transfer to itself whose data is [`POLICY_UPDATE_TAG`]
+//! followed by the bincode-encoded policy. From the next transaction on,
+//! every spend from the account is checked against the policy when blocks are
+//! applied and when the mempool admits transactions.
+//!
+//! A policy with a cosigner can only be changed or removed with the
+//! cosigner's signature, so a stolen primary key alone cannot lift it.
+//! Without a cosigner the primary key can replace the policy at any time.
+
+use crate::constants::TARGET_BLOCK_TIME_SECS;
+use crate::crypto::PublicKey;
+use crate::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// Data prefix marking a policy update transaction
+pub const POLICY_UPDATE_TAG: &[u8] = b"opensyria-policy-v1";
+/// Length of a daily limit window in blocks
+pub const BLOCKS_PER_DAY: u64 = 86_400 / TARGET_BLOCK_TIME_SECS;
+/// Most recipients a policy can allow
+pub const MAX_POLICY_ALLOWLIST: usize = 64;
+
+/// Day window a block height falls in
+pub fn policy_day(height: u64) -> u64 {
+    height / BLOCKS_PER_DAY
+}
+
+/// Restrictions on spending from an account
+/// قيود الإنفاق من الحساب
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub struct SpendingPolicy {
+    /// Most the account may spend, fees included, per [`BLOCKS_PER_DAY`] window
+    pub daily_limit: Option<u64>,
+    /// Key that must co-sign transfers above `cosign_threshold` and any
+    /// policy change
+    pub cosigner: Option<PublicKey>,
+    /// Largest amount that may be sent without the cosigner
+    pub cosign_threshold: u64,
+    /// Recipients the account may send to; empty allows any
+    pub allowlist: Vec<PublicKey>,
+}
+
+impl SpendingPolicy {
+    /// Whether the policy restricts nothing; setting it removes the policy
+    pub fn is_unrestricted(&self) -> bool {
+        self.daily_limit.is_none() && self.cosigner.is_none() && self.allowlist.is_empty()
+    }
+
+    /// Check the policy is well formed
+    pub fn validate(&self) -> Result<(), PolicyError> {
+        if self.cosigner.is_some_and(|cosigner| cosigner.is_zero()) {
+            return Err(PolicyError::InvalidPolicy(
+                "cosigner cannot be the zero key".to_string(),
+            ));
+        }
+        if self.allowlist.len() > MAX_POLICY_ALLOWLIST {
+            return Err(PolicyError::InvalidPolicy(format!(
+                "allowlist has {} recipients (max {})",
+                self.allowlist.len(),
+                MAX_POLICY_ALLOWLIST
+            )));
+        }
+        let mut recipients = self.allowlist.clone();
+        recipients.sort_by_key(|recipient| recipient.0);
+        recipients.dedup();
+        if recipients.len() != self.allowlist.len() {
+            return Err(PolicyError::InvalidPolicy(
+                "duplicate allowlist recipient".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Unsigned transaction setting this policy on `account`
+    /// إنشاء معاملة تحديث السياسة
+    pub fn update_transaction(
+        &self,
+        chain_id: u32,
+        account: PublicKey,
+        fee: u64,
+        nonce: u64,
+    ) -> Transaction {
+        let mut data = POLICY_UPDATE_TAG.to_vec();
+        data.extend(
+            bincode::encode_to_vec(self, bincode::config::standard())
+                .expect("policy encoding cannot fail"),
+        );
+        Transaction::new_with_chain_id(chain_id, account, account, 0, fee, nonce).with_data(data)
+    }
+
+    /// Whether `tx` is a policy update, well formed or not
+    pub fn is_update(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(POLICY_UPDATE_TAG))
+    }
+
+    /// Policy set by `tx`, or `None` if it is not a policy update
+    pub fn from_update(tx: &Transaction) -> Option<Result<SpendingPolicy, PolicyError>> {
+        let payload = tx.data.as_ref()?.strip_prefix(POLICY_UPDATE_TAG)?;
+        if tx.to != tx.from || tx.amount != 0 {
+            return Some(Err(PolicyError::InvalidPolicy(
+                "policy update must be a zero-amount transfer to self".to_string(),
+            )));
+        }
+        let decoded =
+            bincode::decode_from_slice::<SpendingPolicy, _>(payload, bincode::config::standard());
+        Some(match decoded {
+            Ok((policy, read)) if read == payload.len() => policy.validate().map(|_| policy),
+            _ => Err(PolicyError::InvalidPolicy(
+                "malformed policy encoding".to_string(),
+            )),
+        })
+    }
+
+    /// Check a transaction sent by the account holding this policy, given
+    /// what it already spent in the current day window
+    /// التحقق من معاملة صادرة وفق السياسة
+    pub fn check(&self, tx: &Transaction, spent_today: u64) -> Result<(), PolicyError> {
+        let update = Self::is_update(tx);
+        if !update
+            && tx.to != tx.from
+            && !self.allowlist.is_empty()
+            && !self.allowlist.contains(&tx.to)
+        {
+            return Err(PolicyError::RecipientNotAllowed(tx.to.to_hex()));
+        }
+
+        if let Some(cosigner) = self.cosigner {
+            if update || tx.amount > self.cosign_threshold {
+                let cosignature = tx
+                    .cosignature
+                    .as_ref()
+                    .ok_or(PolicyError::CosignatureRequired)?;
+                cosigner
+                    .verify(&tx.cosigning_hash(), cosignature)
+                    .map_err(|_| PolicyError::InvalidCosignature)?;
+            }
+        }
+
+        self.check_daily(tx.debit(&tx.from), spent_today)
+    }
+
+    /// Check spending `debit` more in a day window that already saw `spent_today`
+    pub fn check_daily(&self, debit: u64, spent_today: u64) -> Result<(), PolicyError> {
+        match self.daily_limit {
+            Some(limit) if spent_today.saturating_add(debit) > limit => {
+                Err(PolicyError::DailyLimitExceeded {
+                    limit,
+                    spent: spent_today,
+                    requested: debit,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyError {
+    InvalidPolicy(String),
+    RecipientNotAllowed(String),
+    CosignatureRequired,
+    InvalidCosignature,
+    DailyLimitExceeded {
+        limit: u64,
+        spent: u64,
+        requested: u64,
+    },
+}
+
+impl std::fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolicyError::InvalidPolicy(reason) => write!(f, "Invalid spending policy: {}", reason),
+            PolicyError::RecipientNotAllowed(recipient) => {
+                write!(
+                    f,
+                    "Recipient {} is not on the account's allowlist",
+                    recipient
+                )
+            }
+            PolicyError::CosignatureRequired => {
+                write!(f, "Spending policy requires the cosigner's signature")
+            }
+            PolicyError::InvalidCosignature => write!(f, "Invalid cosigner signature"),
+            PolicyError::DailyLimitExceeded {
+                limit,
+                spent,
+                requested,
+            } => write!(
+                f,
+                "Daily limit {} exceeded: {} spent, {} requested",
+                limit, spent, requested
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    #[test]
+    fn test_update_round_trips() {
+        let account = KeyPair::generate().public_key();
+        let policy = SpendingPolicy {
+            daily_limit: Some(5_000_000),
+            cosigner: Some(KeyPair::generate().public_key()),
+            cosign_threshold: 1_000_000,
+            allowlist: vec![KeyPair::generate().public_key()],
+        };
+
+        let tx = policy.update_transaction(963, account, 100, 4);
+        assert!(SpendingPolicy::is_update(&tx));
+        assert_eq!(SpendingPolicy::from_update(&tx), Some(Ok(policy)));
+
+        let mut misdirected = tx.clone();
+        misdirected.to = KeyPair::generate().public_key();
+        assert!(matches!(
+            SpendingPolicy::from_update(&misdirected),
+            Some(Err(PolicyError::InvalidPolicy(_)))
+        ));
+
+        let plain = Transaction::new(account, account, 0, 100, 4);
+        assert_eq!(SpendingPolicy::from_update(&plain), None);
+    }
+
+    #[test]
+    fn test_cosigner_required_above_threshold() {
+        let sender = KeyPair::generate();
+        let cosigner = KeyPair::generate();
+        let recipient = KeyPair::generate().public_key();
+        let policy = SpendingPolicy {
+            cosigner: Some(cosigner.public_key()),
+            cosign_threshold: 1_000,
+            ..Default::default()
+        };
+
+        let small = Transaction::new(sender.public_key(), recipient, 1_000, 100, 0);
+        assert_eq!(policy.check(&small, 0), Ok(()));
+
+        let large = Transaction::new(sender.public_key(), recipient, 1_001, 100, 0);
+        assert_eq!(
+            policy.check(&large, 0),
+            Err(PolicyError::CosignatureRequired)
+        );
+        let forged = large
+            .clone()
+            .with_cosignature(sender.sign(&large.cosigning_hash()));
+        assert_eq!(
+            policy.check(&forged, 0),
+            Err(PolicyError::InvalidCosignature)
+        );
+        let cosigned = large
+            .clone()
+            .with_cosignature(cosigner.sign(&large.cosigning_hash()));
+        assert_eq!(policy.check(&cosigned, 0), Ok(()));
+
+        // Lifting the policy needs the cosigner whatever the amount
+        let removal =
+            SpendingPolicy::default().update_transaction(963, sender.public_key(), 100, 1);
+        assert_eq!(
+            policy.check(&removal, 0),
+            Err(PolicyError::CosignatureRequired)
+        );
+    }
+
+    #[test]
+    fn test_allowlist_and_daily_limit() {
+        let sender = KeyPair::generate().public_key();
+        let allowed = KeyPair::generate().public_key();
+        let policy = SpendingPolicy {
+            daily_limit: Some(10_000),
+            allowlist: vec![allowed],
+            ..Default::default()
+        };
+
+        let other = Transaction::new(sender, KeyPair::generate().public_key(), 1_000, 100, 0);
+        assert!(matches!(
+            policy.check(&other, 0),
+            Err(PolicyError::RecipientNotAllowed(_))
+        ));
+
+        let tx = Transaction::new(sender, allowed, 4_900, 100, 0);
+        assert_eq!(policy.check(&tx, 5_000), Ok(()));
+        assert_eq!(
+            policy.check(&tx, 5_001),
+            Err(PolicyError::DailyLimitExceeded {
+                limit: 10_000,
+                spent: 5_001,
+                requested: 5_000,
+            })
+        );
+
+        assert_eq!(policy_day(BLOCKS_PER_DAY - 1), 0);
+        assert_eq!(policy_day(BLOCKS_PER_DAY), 1);
+    }
+}
@@ -0,0 +1,194 @@
+//! Canonical raw transaction encoding
+//! الترميز الخام القياسي للمعاملات
+//!
+//! A raw transaction is the hex encoding of
+//!
+//! ```text
+//! version (1 byte) || bincode(transaction) || checksum (4 bytes)
+//! ```
+//!
+//! where the checksum is the first four bytes of
+//! `SHA256(SHA256(version || payload))`. Decoding rejects unknown versions,
+//! checksum mismatches and trailing bytes, so a raw transaction has exactly
+//! one valid encoding.
+
+use crate::constants::MAX_TRANSACTION_SIZE;
+use crate::Transaction;
+use sha2::{Digest, Sha256};
+
+/// Current raw transaction format version
+pub const RAW_TX_VERSION: u8 = 1;
+
+/// Checksum length in bytes
+pub const RAW_TX_CHECKSUM_LEN: usize = 4;
+
+/// Errors decoding a raw transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawTxError {
+    /// Input is not valid hex
+    InvalidHex,
+    /// Input is too short to hold a version, payload and checksum
+    TooShort,
+    /// Input exceeds the maximum transaction size
+    TooLarge,
+    /// Version byte is not supported
+    UnsupportedVersion(u8),
+    /// Checksum does not match the content
+    ChecksumMismatch,
+    /// Payload is not a valid transaction encoding
+    Decode(String),
+    /// Payload has bytes after the transaction
+    TrailingBytes,
+}
+
+impl std::fmt::Display for RawTxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RawTxError::InvalidHex => write!(f, "Raw transaction is not valid hex"),
+            RawTxError::TooShort => write!(f, "Raw transaction is too short"),
+            RawTxError::TooLarge => write!(f, "Raw transaction exceeds maximum size"),
+            RawTxError::UnsupportedVersion(v) => {
+                write!(f, "Unsupported raw transaction version: {}", v)
+            }
+            RawTxError::ChecksumMismatch => write!(f, "Raw transaction checksum mismatch"),
+            RawTxError::Decode(e) => write!(f, "Invalid raw transaction payload: {}", e),
+            RawTxError::TrailingBytes => write!(f, "Raw transaction has trailing bytes"),
+        }
+    }
+}
+
+impl std::error::Error for RawTxError {}
+
+fn checksum(content: &[u8]) -> [u8; RAW_TX_CHECKSUM_LEN] {
+    let digest = Sha256::digest(Sha256::digest(content));
+    let mut out = [0u8; RAW_TX_CHECKSUM_LEN];
+    out.copy_from_slice(&digest[..RAW_TX_CHECKSUM_LEN]);
+    out
+}
+
+impl Transaction {
+    /// Encode as canonical raw bytes (version, payload, checksum)
+    pub fn to_raw_bytes(&self) -> Vec<u8> {
+        let mut raw = vec![RAW_TX_VERSION];
+        raw.extend(bincode::encode_to_vec(self, bincode::config::standard()).unwrap_or_default());
+        let sum = checksum(&raw);
+        raw.extend_from_slice(&sum);
+        raw
+    }
+
+    /// Encode as canonical raw hex
+    /// ترميز المعاملة كسلسلة سداسية عشرية خام
+    pub fn to_raw_hex(&self) -> String {
+        hex::encode(self.to_raw_bytes())
+    }
+
+    /// Decode canonical raw bytes, verifying version and checksum
+    pub fn from_raw_bytes(raw: &[u8]) -> Result<Self, RawTxError> {
+        if raw.len() < 1 + RAW_TX_CHECKSUM_LEN + 1 {
+            return Err(RawTxError::TooShort);
+        }
+        if raw.len() > MAX_TRANSACTION_SIZE + 1 + RAW_TX_CHECKSUM_LEN {
+            return Err(RawTxError::TooLarge);
+        }
+
+        let (content, sum) = raw.split_at(raw.len() - RAW_TX_CHECKSUM_LEN);
+        if checksum(content) != sum {
+            return Err(RawTxError::ChecksumMismatch);
+        }
+        if content[0] != RAW_TX_VERSION {
+            return Err(RawTxError::UnsupportedVersion(content[0]));
+        }
+
+        let payload = &content[1..];
+        let (tx, read): (Transaction, usize) =
+            bincode::decode_from_slice(payload, bincode::config::standard())
+                .map_err(|e| RawTxError::Decode(e.to_string()))?;
+        if read != payload.len() {
+            return Err(RawTxError::TrailingBytes);
+        }
+        Ok(tx)
+    }
+
+    /// Decode canonical raw hex (surrounding whitespace and a `0x` prefix are ignored)
+    /// فك ترميز معاملة من سلسلة سداسية عشرية خام
+    pub fn from_raw_hex(raw: &str) -> Result<Self, RawTxError> {
+        let raw = raw.trim();
+        let raw = raw.strip_prefix("0x").unwrap_or(raw);
+        let bytes = hex::decode(raw).map_err(|_| RawTxError::InvalidHex)?;
+        Self::from_raw_bytes(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn signed_tx() -> Transaction {
+        let sender = KeyPair::generate();
+        let tx = Transaction::new(
+            sender.public_key(),
+            KeyPair::generate().public_key(),
+            250_000,
+            1_000,
+            7,
+        )
+        .with_data(b"invoice 42".to_vec());
+        let signature = sender.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_raw_hex_roundtrip() {
+        let tx = signed_tx();
+        let raw = tx.to_raw_hex();
+        assert!(raw.starts_with("01"));
+
+        let decoded = Transaction::from_raw_hex(&format!("0x{}\n", raw)).unwrap();
+        assert_eq!(decoded.hash(), tx.hash());
+        assert_eq!(decoded.data, tx.data);
+        assert!(decoded.verify().is_ok());
+        assert_eq!(decoded.to_raw_hex(), raw);
+    }
+
+    #[test]
+    fn test_raw_hex_rejects_corruption() {
+        let raw = signed_tx().to_raw_bytes();
+
+        let mut flipped = raw.clone();
+        flipped[10] ^= 0x01;
+        assert_eq!(
+            Transaction::from_raw_bytes(&flipped).unwrap_err(),
+            RawTxError::ChecksumMismatch
+        );
+
+        // A valid checksum over an unknown version
+        let mut versioned = raw[..raw.len() - RAW_TX_CHECKSUM_LEN].to_vec();
+        versioned[0] = 2;
+        let sum = checksum(&versioned);
+        versioned.extend_from_slice(&sum);
+        assert_eq!(
+            Transaction::from_raw_bytes(&versioned).unwrap_err(),
+            RawTxError::UnsupportedVersion(2)
+        );
+
+        // A valid checksum over extra payload bytes
+        let mut padded = raw[..raw.len() - RAW_TX_CHECKSUM_LEN].to_vec();
+        padded.push(0);
+        let sum = checksum(&padded);
+        padded.extend_from_slice(&sum);
+        assert_eq!(
+            Transaction::from_raw_bytes(&padded).unwrap_err(),
+            RawTxError::TrailingBytes
+        );
+
+        assert_eq!(
+            Transaction::from_raw_hex("zz").unwrap_err(),
+            RawTxError::InvalidHex
+        );
+        assert_eq!(
+            Transaction::from_raw_bytes(&raw[..4]).unwrap_err(),
+            RawTxError::TooShort
+        );
+    }
+}
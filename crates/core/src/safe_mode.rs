@@ -0,0 +1,184 @@
+//! Safe mode after irreconcilable anomalies
+//! الوضع الآمن بعد اكتشاف حالات شاذة لا يمكن التوفيق بينها
+//!
+//! When a node finds its chain or state contradicting something it must
+//! trust — a checkpoint, or the supply invariant — continuing to mine or
+//! relay would spread the damage. The node instead enters safe mode: it
+//! stops mining and relaying blocks and transactions, keeps serving read
+//! requests, and raises an alert. Safe mode is a marker file in the data
+//! directory, so it survives restarts and is only lifted by an operator
+//! running `node-cli resume` after reviewing the anomaly.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Marker file inside the data directory; the node is in safe mode while it exists
+pub const SAFE_MODE_FILE: &str = "safe_mode.json";
+
+/// Anomaly that forces a node into safe mode
+/// حالة شاذة تجبر العقدة على الدخول في الوضع الآمن
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Anomaly {
+    /// The stored block at a checkpointed height has a different hash
+    CheckpointMismatch {
+        height: u64,
+        expected: [u8; 32],
+        got: [u8; 32],
+    },
+
+    /// Account balances add up to more than the recorded supply
+    SupplyInvariant { recorded: u64, computed: u64 },
+}
+
+impl std::fmt::Display for Anomaly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Anomaly::CheckpointMismatch {
+                height,
+                expected,
+                got,
+            } => write!(
+                f,
+                "Checkpoint mismatch at height {}: expected {}, stored {}",
+                height,
+                hex::encode(expected),
+                hex::encode(got)
+            ),
+            Anomaly::SupplyInvariant { recorded, computed } => write!(
+                f,
+                "Supply invariant violated: balances total {} but recorded supply is {}",
+                computed, recorded
+            ),
+        }
+    }
+}
+
+/// Why and when a node entered safe mode
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SafeModeRecord {
+    pub anomaly: Anomaly,
+    /// Chain height when the anomaly was detected
+    pub height: u64,
+    /// Unix timestamp (seconds) when safe mode was entered
+    pub entered_at: u64,
+}
+
+/// Safe mode marker for one data directory
+/// مؤشر الوضع الآمن لدليل بيانات واحد
+///
+/// The marker is re-read on every check, so `node-cli resume` lifts safe
+/// mode in a running node without a restart.
+#[derive(Debug, Clone)]
+pub struct SafeMode {
+    path: PathBuf,
+}
+
+impl SafeMode {
+    /// Safe mode marker in `data_dir`
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(SAFE_MODE_FILE),
+        }
+    }
+
+    /// Path of the marker file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Check if the node is in safe mode
+    pub fn is_active(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Anomaly that put the node into safe mode, if any
+    pub fn record(&self) -> std::io::Result<Option<SafeModeRecord>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&std::fs::read(&self.path)?)?))
+    }
+
+    /// Enter safe mode because of `anomaly` detected at `height`
+    ///
+    /// Returns `Ok(false)` if the node is already in safe mode; the first
+    /// anomaly is kept so the operator reviews the original cause.
+    pub fn enter(&self, anomaly: Anomaly, height: u64) -> std::io::Result<bool> {
+        if self.is_active() {
+            return Ok(false);
+        }
+
+        let record = SafeModeRecord {
+            anomaly,
+            height,
+            entered_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_vec_pretty(&record)?)?;
+        Ok(true)
+    }
+
+    /// Leave safe mode, returning the record that was cleared
+    pub fn resume(&self) -> std::io::Result<Option<SafeModeRecord>> {
+        let record = self.record()?;
+        if record.is_some() {
+            std::fs::remove_file(&self.path)?;
+        }
+        Ok(record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter_and_resume() {
+        let dir = tempfile::tempdir().unwrap();
+        let safe_mode = SafeMode::new(dir.path());
+        assert!(!safe_mode.is_active());
+        assert_eq!(safe_mode.resume().unwrap(), None);
+
+        let first = Anomaly::CheckpointMismatch {
+            height: 10,
+            expected: [1u8; 32],
+            got: [2u8; 32],
+        };
+        assert!(safe_mode.enter(first.clone(), 12).unwrap());
+
+        // A second anomaly does not overwrite the first
+        let second = Anomaly::SupplyInvariant {
+            recorded: 100,
+            computed: 150,
+        };
+        assert!(!safe_mode.enter(second, 13).unwrap());
+
+        // Another handle on the same directory sees the marker
+        let other = SafeMode::new(dir.path());
+        assert!(other.is_active());
+        let record = other.record().unwrap().unwrap();
+        assert_eq!(record.anomaly, first);
+        assert_eq!(record.height, 12);
+
+        assert_eq!(other.resume().unwrap(), Some(record));
+        assert!(!safe_mode.is_active());
+    }
+
+    #[test]
+    fn test_anomaly_display() {
+        let anomaly = Anomaly::SupplyInvariant {
+            recorded: 100,
+            computed: 150,
+        };
+        assert_eq!(
+            anomaly.to_string(),
+            "Supply invariant violated: balances total 150 but recorded supply is 100"
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Account state root and balance proofs
+//! جذر حالة الحسابات وإثباتات الأرصدة
+//!
+//! Accounts are sorted by address and hashed into a binary Merkle tree. A
+//! [`StateProof`] carries one account leaf and its sibling path, so a light
+//! wallet can check a balance returned by an untrusted API against a state
+//! root it already trusts. An unpaired node at the end of a level is carried
+//! up unchanged rather than duplicated, so every tree shape has one root.
+
+use crate::crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const LEAF_DOMAIN: &[u8] = b"opensyria-state-leaf-v1";
+const NODE_DOMAIN: &[u8] = b"opensyria-state-node-v1";
+const EMPTY_DOMAIN: &[u8] = b"opensyria-state-empty-v1";
+
+/// One account as committed in the state root
+/// حساب واحد كما هو ملتزم به في جذر الحالة
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountLeaf {
+    pub address: PublicKey,
+    pub balance: u64,
+    pub nonce: u64,
+}
+
+impl AccountLeaf {
+    /// Leaf hash
+    pub fn hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(LEAF_DOMAIN);
+        hasher.update(self.address.0);
+        hasher.update(self.balance.to_le_bytes());
+        hasher.update(self.nonce.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(NODE_DOMAIN);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Root of a state with no accounts
+pub fn empty_state_root() -> [u8; 32] {
+    Sha256::digest(EMPTY_DOMAIN).into()
+}
+
+/// Merkle tree over every account
+/// شجرة ميركل لجميع الحسابات
+#[derive(Debug, Clone)]
+pub struct StateTree {
+    leaves: Vec<AccountLeaf>,
+    /// Hashes per level, leaves first; the last level holds the root
+    levels: Vec<Vec<[u8; 32]>>,
+}
+
+impl StateTree {
+    /// Build the tree; accounts may be given in any order, one per address
+    pub fn new(leaves: impl IntoIterator<Item = AccountLeaf>) -> Self {
+        let mut leaves: Vec<AccountLeaf> = leaves.into_iter().collect();
+        leaves.sort_by_key(|leaf| leaf.address.0);
+        leaves.dedup_by(|a, b| a.address == b.address);
+
+        let mut levels = vec![leaves.iter().map(AccountLeaf::hash).collect::<Vec<_>>()];
+        while levels.last().is_some_and(|level| level.len() > 1) {
+            let next = levels
+                .last()
+                .expect("level exists")
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!("chunks of two"),
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Self { leaves, levels }
+    }
+
+    /// State root
+    pub fn root(&self) -> [u8; 32] {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .copied()
+            .unwrap_or_else(empty_state_root)
+    }
+
+    /// Number of accounts
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    /// Whether the state has no accounts
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    /// Inclusion proof for `address`, if it has an account
+    /// إثبات وجود حساب العنوان في الحالة
+    pub fn prove(&self, address: &PublicKey) -> Option<StateProof> {
+        let index = self
+            .leaves
+            .binary_search_by(|leaf| leaf.address.0.cmp(&address.0))
+            .ok()?;
+
+        let mut siblings = Vec::new();
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+
+        Some(StateProof {
+            leaf: self.leaves[index],
+            index: index as u64,
+            leaf_count: self.leaves.len() as u64,
+            siblings,
+        })
+    }
+}
+
+/// Account leaf with the Merkle path to a state root
+/// ورقة حساب مع مسار ميركل إلى جذر الحالة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateProof {
+    pub leaf: AccountLeaf,
+    /// Position of the leaf among accounts sorted by address
+    pub index: u64,
+    /// Number of accounts in the tree
+    pub leaf_count: u64,
+    /// Sibling hashes from the leaf level up; levels where the node has no
+    /// sibling are skipped
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl StateProof {
+    /// Root the proof leads to, or `None` if the path has the wrong length
+    pub fn root(&self) -> Option<[u8; 32]> {
+        if self.index >= self.leaf_count {
+            return None;
+        }
+
+        let mut hash = self.leaf.hash();
+        let mut position = self.index;
+        let mut width = self.leaf_count;
+        let mut siblings = self.siblings.iter();
+        while width > 1 {
+            if position % 2 == 1 {
+                hash = node_hash(siblings.next()?, &hash);
+            } else if position + 1 < width {
+                hash = node_hash(&hash, siblings.next()?);
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+
+        siblings.next().is_none().then_some(hash)
+    }
+
+    /// Whether the proof shows the leaf is committed in `state_root`
+    /// التحقق من أن الورقة ملتزم بها في جذر الحالة
+    pub fn verify(&self, state_root: &[u8; 32]) -> bool {
+        self.root().as_ref() == Some(state_root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::KeyPair;
+
+    fn accounts(count: u64) -> Vec<AccountLeaf> {
+        (0..count)
+            .map(|i| AccountLeaf {
+                address: KeyPair::generate().public_key(),
+                balance: 1_000 * (i + 1),
+                nonce: i,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_every_account_proves_for_all_tree_sizes() {
+        for count in 1..=9 {
+            let leaves = accounts(count);
+            let tree = StateTree::new(leaves.clone());
+            let root = tree.root();
+            for leaf in &leaves {
+                let proof = tree.prove(&leaf.address).unwrap();
+                assert_eq!(proof.leaf, *leaf);
+                assert!(proof.verify(&root), "{} accounts", count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_root_is_order_independent() {
+        let mut leaves = accounts(5);
+        let root = StateTree::new(leaves.clone()).root();
+        leaves.reverse();
+        assert_eq!(StateTree::new(leaves).root(), root);
+        assert_eq!(StateTree::new(Vec::new()).root(), empty_state_root());
+    }
+
+    #[test]
+    fn test_tampered_proof_fails() {
+        let leaves = accounts(6);
+        let tree = StateTree::new(leaves.clone());
+        let root = tree.root();
+        let proof = tree.prove(&leaves[2].address).unwrap();
+
+        let mut inflated = proof.clone();
+        inflated.leaf.balance += 1;
+        assert!(!inflated.verify(&root));
+
+        let mut moved = proof.clone();
+        moved.index = (moved.index + 1) % moved.leaf_count;
+        assert!(!moved.verify(&root));
+
+        let mut extended = proof.clone();
+        extended.siblings.push([0u8; 32]);
+        assert_eq!(extended.root(), None);
+
+        let mut truncated = proof;
+        truncated.siblings.pop();
+        assert_eq!(truncated.root(), None);
+
+        assert!(tree.prove(&KeyPair::generate().public_key()).is_none());
+    }
+}
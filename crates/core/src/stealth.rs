@@ -0,0 +1,588 @@
+//! Stealth addresses
+//! العناوين الخفية
+//!
+//! A stealth address publishes two keys: a scan key `A = a·G` and a spend key
+//! `B = b·G`. To pay it, the sender picks a random `r`, sends to the one-time
+//! address `P = B + H(r·A)·G` and attaches `R = r·G` to the transaction data
+//! as a [`StealthAnnouncement`]. The recipient computes `H(a·R)` for each
+//! announcement to find its payments, and spends from `P` with the key
+//! `b + H(a·R)`. Nothing on chain links `P` to the stealth address or to
+//! other payments to it.
+//!
+//! The scan key alone detects payments, so it can be given to a watcher
+//! without giving up spending. A one-byte view tag lets a scanner skip most
+//! announcements after one hash, and the memo is sealed with AES-256-GCM
+//! under a key from the shared secret so only the recipient reads it and a
+//! changed memo is detected.
+//!
+//! Data layout: [`STEALTH_TAG`], `R` (32 bytes), view tag (1 byte), memo
+//! ciphertext with its 16-byte authentication tag.
+
+use crate::crypto::{KeyPair, PublicKey};
+use crate::transaction::Transaction;
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use ed25519_dalek::hazmat::{raw_sign, ExpandedSecretKey};
+use ed25519_dalek::VerifyingKey;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha2::{Digest, Sha256, Sha512};
+use zeroize::Zeroize;
+
+/// Data prefix marking a stealth payment
+pub const STEALTH_TAG: &[u8] = b"opensyria-stealth-v1";
+
+/// Prefix of encoded stealth addresses, followed by the scan and spend keys
+/// in hex
+pub const STEALTH_ADDRESS_PREFIX: &str = "stealth";
+
+/// Longest memo, in bytes, a stealth payment carries
+pub const MAX_STEALTH_MEMO_LEN: usize = 128;
+
+const KEY_DOMAIN: &[u8] = b"opensyria-stealth-key-v1";
+const SECRET_DOMAIN: &[u8] = b"opensyria-stealth-secret-v1";
+const VIEW_TAG_DOMAIN: &[u8] = b"opensyria-stealth-view-tag-v1";
+const MEMO_DOMAIN: &[u8] = b"opensyria-stealth-memo-v1";
+const NONCE_DOMAIN: &[u8] = b"opensyria-stealth-nonce-v1";
+
+/// Bytes after the tag before the memo: ephemeral key and view tag
+const ANNOUNCEMENT_LEN: usize = 33;
+
+/// AES-GCM authentication tag length
+const AUTH_TAG_LEN: usize = 16;
+
+fn hash_to_scalar(parts: &[&[u8]]) -> Scalar {
+    let mut hasher = Sha512::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    Scalar::from_bytes_mod_order_wide(&hasher.finalize().into())
+}
+
+fn to_public_key(point: &EdwardsPoint) -> PublicKey {
+    PublicKey(point.compress().to_bytes())
+}
+
+/// Curve point of a key, rejecting invalid and small-order keys
+fn decompress(key: &PublicKey) -> Option<EdwardsPoint> {
+    CompressedEdwardsY(key.0)
+        .decompress()
+        .filter(|point| !point.is_small_order())
+}
+
+/// The point `r·A = a·R` both sides of a payment derive
+struct SharedSecret([u8; 32]);
+
+impl SharedSecret {
+    fn new(scalar: &Scalar, point: &EdwardsPoint) -> Self {
+        Self((scalar * point).compress().to_bytes())
+    }
+
+    /// Scalar added to the spend key
+    fn tweak(&self) -> Scalar {
+        hash_to_scalar(&[SECRET_DOMAIN, &self.0])
+    }
+
+    fn view_tag(&self) -> u8 {
+        Sha256::new()
+            .chain_update(VIEW_TAG_DOMAIN)
+            .chain_update(self.0)
+            .finalize()[0]
+    }
+
+    /// AES key for the memo of the payment announced with `ephemeral`
+    fn memo_cipher(&self, ephemeral: &PublicKey) -> Aes256Gcm {
+        let mut key: [u8; 32] = Sha256::new()
+            .chain_update(MEMO_DOMAIN)
+            .chain_update(self.0)
+            .chain_update(ephemeral.0)
+            .finalize()
+            .into();
+        let cipher = Aes256Gcm::new_from_slice(&key).expect("32-byte key");
+        key.zeroize();
+        cipher
+    }
+}
+
+/// Authenticated data of a memo: everything before it in the announcement
+fn memo_header(ephemeral: &PublicKey, view_tag: u8) -> Vec<u8> {
+    let mut header = STEALTH_TAG.to_vec();
+    header.extend(ephemeral.0);
+    header.push(view_tag);
+    header
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// A stealth address: the scan and spend keys of a recipient
+/// عنوان خفي
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StealthAddress {
+    pub scan: PublicKey,
+    pub spend: PublicKey,
+}
+
+impl StealthAddress {
+    /// Whether `s` is written as a stealth address rather than a plain one
+    pub fn is_stealth_address(s: &str) -> bool {
+        s.trim().starts_with(STEALTH_ADDRESS_PREFIX)
+    }
+
+    /// Parse `stealth` followed by the scan and spend keys in hex
+    pub fn parse(s: &str) -> Result<Self, StealthError> {
+        let invalid = || StealthError::InvalidAddress(s.to_string());
+        let keys = s
+            .trim()
+            .strip_prefix(STEALTH_ADDRESS_PREFIX)
+            .ok_or_else(invalid)?;
+        let bytes = hex::decode(keys).map_err(|_| invalid())?;
+        if bytes.len() != 64 {
+            return Err(invalid());
+        }
+
+        let mut scan = [0u8; 32];
+        let mut spend = [0u8; 32];
+        scan.copy_from_slice(&bytes[..32]);
+        spend.copy_from_slice(&bytes[32..]);
+        let address = Self {
+            scan: PublicKey(scan),
+            spend: PublicKey(spend),
+        };
+        if decompress(&address.scan).is_none() || decompress(&address.spend).is_none() {
+            return Err(invalid());
+        }
+        Ok(address)
+    }
+
+    /// Derive a fresh one-time address to pay
+    /// إنشاء عنوان لمرة واحدة للدفع
+    pub fn pay(&self) -> Result<StealthPayment, StealthError> {
+        let mut seed = [0u8; 64];
+        OsRng.fill_bytes(&mut seed);
+        let mut ephemeral = Scalar::from_bytes_mod_order_wide(&seed);
+        seed.zeroize();
+
+        let payment = self.pay_with(&ephemeral);
+        ephemeral.zeroize();
+        payment
+    }
+
+    fn pay_with(&self, ephemeral: &Scalar) -> Result<StealthPayment, StealthError> {
+        let invalid = || StealthError::InvalidAddress(self.to_string());
+        let scan = decompress(&self.scan).ok_or_else(invalid)?;
+        let spend = decompress(&self.spend).ok_or_else(invalid)?;
+
+        let shared = SharedSecret::new(ephemeral, &scan);
+        let one_time = spend + EdwardsPoint::mul_base(&shared.tweak());
+        Ok(StealthPayment {
+            address: to_public_key(&one_time),
+            ephemeral: to_public_key(&EdwardsPoint::mul_base(ephemeral)),
+            view_tag: shared.view_tag(),
+            shared,
+        })
+    }
+}
+
+impl std::fmt::Display for StealthAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            STEALTH_ADDRESS_PREFIX,
+            self.scan.to_hex(),
+            self.spend.to_hex()
+        )
+    }
+}
+
+/// A payment to a one-time address, as prepared by the sender
+pub struct StealthPayment {
+    /// One-time address to send to
+    pub address: PublicKey,
+    /// `R`, published so the recipient can find the payment
+    pub ephemeral: PublicKey,
+    pub view_tag: u8,
+    shared: SharedSecret,
+}
+
+impl StealthPayment {
+    /// Transaction data announcing the payment, with `memo` encrypted for
+    /// the recipient
+    pub fn data(&self, memo: Option<&[u8]>) -> Result<Vec<u8>, StealthError> {
+        let memo = memo.unwrap_or_default();
+        if memo.len() > MAX_STEALTH_MEMO_LEN {
+            return Err(StealthError::MemoTooLong(memo.len()));
+        }
+
+        let mut data = memo_header(&self.ephemeral, self.view_tag);
+        // Each payment has its own ephemeral key, so a fixed nonce is never
+        // reused
+        let encrypted = self
+            .shared
+            .memo_cipher(&self.ephemeral)
+            .encrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: memo,
+                    aad: &data,
+                },
+            )
+            .expect("memo within AES-GCM limits");
+        data.extend(encrypted);
+        Ok(data)
+    }
+
+    /// Unsigned transfer of `amount` from `from` to the one-time address
+    /// إنشاء معاملة الدفع الخفي
+    pub fn transaction(
+        &self,
+        from: PublicKey,
+        amount: u64,
+        fee: u64,
+        nonce: u64,
+        memo: Option<&[u8]>,
+    ) -> Result<Transaction, StealthError> {
+        Ok(Transaction::new(from, self.address, amount, fee, nonce).with_data(self.data(memo)?))
+    }
+}
+
+/// What a stealth payment publishes in its transaction data
+/// إعلان الدفع الخفي
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StealthAnnouncement {
+    pub ephemeral: PublicKey,
+    pub view_tag: u8,
+    /// Memo ciphertext and authentication tag, sealed for the recipient
+    pub memo: Vec<u8>,
+}
+
+impl StealthAnnouncement {
+    /// Whether `tx` carries stealth payment data, well formed or not
+    pub fn is_stealth(tx: &Transaction) -> bool {
+        tx.data
+            .as_ref()
+            .is_some_and(|data| data.starts_with(STEALTH_TAG))
+    }
+
+    /// Announcement in transaction data, or `None` if there is none
+    pub fn from_data(data: &[u8]) -> Option<Self> {
+        let payload = data.strip_prefix(STEALTH_TAG)?;
+        if payload.len() < ANNOUNCEMENT_LEN + AUTH_TAG_LEN
+            || payload.len() > ANNOUNCEMENT_LEN + AUTH_TAG_LEN + MAX_STEALTH_MEMO_LEN
+        {
+            return None;
+        }
+
+        let mut ephemeral = [0u8; 32];
+        ephemeral.copy_from_slice(&payload[..32]);
+        Some(Self {
+            ephemeral: PublicKey(ephemeral),
+            view_tag: payload[32],
+            memo: payload[ANNOUNCEMENT_LEN..].to_vec(),
+        })
+    }
+
+    pub fn from_transaction(tx: &Transaction) -> Option<Self> {
+        Self::from_data(tx.data.as_deref()?)
+    }
+}
+
+/// A payment found by scanning
+/// دفعة خفية مستلمة
+#[derive(Clone)]
+pub struct StealthOutput {
+    /// One-time address holding the payment
+    pub address: PublicKey,
+    /// Decrypted memo, empty if the sender left none or it fails
+    /// authentication
+    pub memo: Vec<u8>,
+    tweak: Scalar,
+}
+
+impl Drop for StealthOutput {
+    fn drop(&mut self) {
+        self.tweak.zeroize();
+    }
+}
+
+/// Detects payments to a stealth address, but cannot spend them
+/// مفتاح المسح: يكشف الدفعات دون القدرة على إنفاقها
+#[derive(Clone)]
+pub struct StealthScanKey {
+    scan: Scalar,
+    spend: PublicKey,
+}
+
+impl Drop for StealthScanKey {
+    fn drop(&mut self) {
+        self.scan.zeroize();
+    }
+}
+
+impl StealthScanKey {
+    pub fn address(&self) -> StealthAddress {
+        StealthAddress {
+            scan: to_public_key(&EdwardsPoint::mul_base(&self.scan)),
+            spend: self.spend,
+        }
+    }
+
+    /// Check whether a payment to `to` announced by `announcement` is ours
+    pub fn detect(
+        &self,
+        to: &PublicKey,
+        announcement: &StealthAnnouncement,
+    ) -> Option<StealthOutput> {
+        let ephemeral = decompress(&announcement.ephemeral)?;
+        let shared = SharedSecret::new(&self.scan, &ephemeral);
+        if shared.view_tag() != announcement.view_tag {
+            return None;
+        }
+
+        let tweak = shared.tweak();
+        let one_time = decompress(&self.spend)? + EdwardsPoint::mul_base(&tweak);
+        if to_public_key(&one_time) != *to {
+            return None;
+        }
+
+        // A memo that fails authentication is dropped, not the payment
+        let memo = shared
+            .memo_cipher(&announcement.ephemeral)
+            .decrypt(
+                &Nonce::default(),
+                Payload {
+                    msg: &announcement.memo,
+                    aad: &memo_header(&announcement.ephemeral, announcement.view_tag),
+                },
+            )
+            .unwrap_or_default();
+        Some(StealthOutput {
+            address: *to,
+            memo,
+            tweak,
+        })
+    }
+
+    /// Payment in `tx` to this address, if any
+    pub fn scan_transaction(&self, tx: &Transaction) -> Option<StealthOutput> {
+        self.detect(&tx.to, &StealthAnnouncement::from_transaction(tx)?)
+    }
+}
+
+/// Scan and spend keys of a stealth address
+/// مفاتيح العنوان الخفي
+pub struct StealthKeys {
+    scan_key: StealthScanKey,
+    spend: Scalar,
+}
+
+impl Drop for StealthKeys {
+    fn drop(&mut self) {
+        self.spend.zeroize();
+    }
+}
+
+impl StealthKeys {
+    /// Keys of an account's stealth address, derived from its private key so
+    /// that backing up the account backs up the stealth address too
+    pub fn from_account(keypair: &KeyPair) -> Self {
+        keypair.with_private_key(|secret| {
+            let scan = hash_to_scalar(&[KEY_DOMAIN, b"scan", secret]);
+            let spend = hash_to_scalar(&[KEY_DOMAIN, b"spend", secret]);
+            Self {
+                scan_key: StealthScanKey {
+                    scan,
+                    spend: to_public_key(&EdwardsPoint::mul_base(&spend)),
+                },
+                spend,
+            }
+        })
+    }
+
+    pub fn address(&self) -> StealthAddress {
+        self.scan_key.address()
+    }
+
+    pub fn scan_key(&self) -> &StealthScanKey {
+        &self.scan_key
+    }
+
+    /// Key that spends from the one-time address of `output`
+    pub fn spend_key(&self, output: &StealthOutput) -> StealthSpendKey {
+        let mut scalar = self.spend + output.tweak;
+        let key = StealthSpendKey::new(&scalar);
+        scalar.zeroize();
+        key
+    }
+}
+
+/// Signing key of one one-time address
+/// مفتاح إنفاق عنوان لمرة واحدة
+pub struct StealthSpendKey {
+    expanded: ExpandedSecretKey,
+    verifying_key: VerifyingKey,
+}
+
+impl StealthSpendKey {
+    fn new(scalar: &Scalar) -> Self {
+        // The signing nonce prefix must be secret and fixed per key, so it is
+        // derived from the scalar the way ed25519 derives it from the seed
+        let mut hash: [u8; 64] = Sha512::new()
+            .chain_update(NONCE_DOMAIN)
+            .chain_update(scalar.as_bytes())
+            .finalize()
+            .into();
+        let mut hash_prefix = [0u8; 32];
+        hash_prefix.copy_from_slice(&hash[..32]);
+        hash.zeroize();
+
+        let point = EdwardsPoint::mul_base(scalar);
+        Self {
+            expanded: ExpandedSecretKey {
+                scalar: *scalar,
+                hash_prefix,
+            },
+            verifying_key: VerifyingKey::from(point),
+        }
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(self.verifying_key.to_bytes())
+    }
+
+    /// Sign a message, verifiable with [`PublicKey::verify`]
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        raw_sign::<Sha512>(&self.expanded, message, &self.verifying_key)
+            .to_bytes()
+            .to_vec()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StealthError {
+    InvalidAddress(String),
+    MemoTooLong(usize),
+}
+
+impl std::fmt::Display for StealthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StealthError::InvalidAddress(address) => {
+                write!(f, "Invalid stealth address: {}", address)
+            }
+            StealthError::MemoTooLong(len) => write!(
+                f,
+                "Stealth memo is {} bytes, at most {} allowed",
+                len, MAX_STEALTH_MEMO_LEN
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StealthError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payment_detected_and_spendable() {
+        let recipient = StealthKeys::from_account(&KeyPair::generate());
+        let sender = KeyPair::generate().public_key();
+
+        let payment = recipient.address().pay().unwrap();
+        let tx = payment
+            .transaction(sender, 5_000_000, 100, 0, Some(b"Invoice 7"))
+            .unwrap();
+        assert!(StealthAnnouncement::is_stealth(&tx));
+        assert_ne!(tx.to, recipient.address().spend);
+
+        let output = recipient.scan_key().scan_transaction(&tx).unwrap();
+        assert_eq!(output.address, tx.to);
+        assert_eq!(output.memo, b"Invoice 7");
+
+        // A flipped memo bit fails authentication; the payment is still found
+        let mut tampered = tx.clone();
+        *tampered.data.as_mut().unwrap().last_mut().unwrap() ^= 1;
+        let found = recipient.scan_key().scan_transaction(&tampered).unwrap();
+        assert!(found.memo.is_empty());
+
+        // The one-time key signs transactions from the one-time address
+        let spend_key = recipient.spend_key(&output);
+        assert_eq!(spend_key.public_key(), tx.to);
+        let sweep = Transaction::new(tx.to, sender, 4_999_900, 100, 0);
+        let signature = spend_key.sign(&sweep.signing_hash());
+        assert!(sweep.with_signature(signature).verify().is_ok());
+
+        // Each payment goes to a different address
+        assert_ne!(recipient.address().pay().unwrap().address, tx.to);
+    }
+
+    #[test]
+    fn test_only_recipient_detects_payment() {
+        let keypair = KeyPair::generate();
+        let recipient = StealthKeys::from_account(&keypair);
+        let other = StealthKeys::from_account(&KeyPair::generate());
+
+        let payment = recipient.address().pay().unwrap();
+        let tx = payment
+            .transaction(other.address().spend, 1, 100, 0, Some(b"Invoice 7"))
+            .unwrap();
+        assert!(other.scan_key().scan_transaction(&tx).is_none());
+        assert!(!tx
+            .data
+            .as_ref()
+            .unwrap()
+            .windows(3)
+            .any(|window| window == b"Inv"));
+
+        // Keys are derived from the account, so they can be recreated
+        let restored = StealthKeys::from_account(&keypair);
+        assert_eq!(restored.address(), recipient.address());
+        assert!(restored.scan_key().scan_transaction(&tx).is_some());
+
+        // A payment redirected to another address is not ours
+        let mut redirected = tx.clone();
+        redirected.to = other.address().spend;
+        assert!(recipient.scan_key().scan_transaction(&redirected).is_none());
+
+        let plain = Transaction::new(other.address().spend, tx.to, 1, 100, 0);
+        assert!(recipient.scan_key().scan_transaction(&plain).is_none());
+
+        let too_long = vec![0u8; MAX_STEALTH_MEMO_LEN + 1];
+        assert_eq!(
+            payment.data(Some(&too_long)),
+            Err(StealthError::MemoTooLong(MAX_STEALTH_MEMO_LEN + 1))
+        );
+    }
+
+    #[test]
+    fn test_address_encoding() {
+        let address = StealthKeys::from_account(&KeyPair::generate()).address();
+        let encoded = address.to_string();
+        assert!(StealthAddress::is_stealth_address(&encoded));
+        assert_eq!(StealthAddress::parse(&encoded), Ok(address));
+
+        let plain = address.spend.to_hex();
+        assert!(!StealthAddress::is_stealth_address(&plain));
+        for bad in [
+            plain,
+            format!("{}{}", STEALTH_ADDRESS_PREFIX, address.scan.to_hex()),
+            format!("{}zz{}", STEALTH_ADDRESS_PREFIX, &encoded[9..]),
+            format!(
+                "{}{}{}",
+                STEALTH_ADDRESS_PREFIX,
+                address.scan.to_hex(),
+                PublicKey::zero().to_hex()
+            ),
+        ] {
+            assert!(StealthAddress::parse(&bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -1,9 +1,17 @@
 use crate::constants::{calculate_block_reward, CHAIN_ID_MAINNET, MAX_TRANSACTION_SIZE, MIN_TRANSACTION_FEE};
 use crate::crypto::PublicKey;
+use crate::params::ChainParams;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Domain tag for fee sponsorship in signing hashes
+const SPONSOR_DOMAIN: &[u8] = b"opensyria-fee-sponsor-v1";
+/// Domain tag for spending policy cosignatures
+const COSIGN_DOMAIN: &[u8] = b"opensyria-cosign-v1";
+/// Domain tag for hot key delegates in signing hashes
+const DELEGATE_DOMAIN: &[u8] = b"opensyria-delegate-v1";
+
 /// Transaction transferring Digital Lira
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[derive(bincode::Encode, bincode::Decode)]
@@ -24,6 +32,63 @@ pub struct Transaction {
     pub signature: Vec<u8>,
     /// Optional transaction metadata
     pub data: Option<Vec<u8>>,
+    /// Third party paying the fee instead of the sender
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sponsor: Option<FeeSponsor>,
+    /// Signature by the cosigner of the sender's spending policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosignature: Option<Vec<u8>>,
+    /// Hot key signing for the sender under a delegation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delegate: Option<PublicKey>,
+}
+
+/// Account paying a transaction's fee on the sender's behalf
+/// الحساب الذي يدفع رسوم المعاملة نيابة عن المرسل
+///
+/// The sender's signature commits to the payer, and the payer signs
+/// [`Transaction::sponsor_signing_hash`], so neither side can be swapped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct FeeSponsor {
+    /// Account the fee is charged to
+    pub payer: PublicKey,
+    /// Payer's signature over the sponsor signing hash
+    pub signature: Vec<u8>,
+}
+
+/// Transaction as encoded before fee sponsors, cosignatures and delegates
+/// المعاملة بترميزها السابق لرعاية الرسوم والتوقيع المشترك والتفويض
+///
+/// Blocks written by older releases carry this layout. Its hash equals that
+/// of the converted [`Transaction`], whose new fields are all `None`.
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct TransactionV1 {
+    pub chain_id: u32,
+    pub from: PublicKey,
+    pub to: PublicKey,
+    pub amount: u64,
+    pub fee: u64,
+    pub nonce: u64,
+    pub signature: Vec<u8>,
+    pub data: Option<Vec<u8>>,
+}
+
+impl From<TransactionV1> for Transaction {
+    fn from(tx: TransactionV1) -> Self {
+        Self {
+            chain_id: tx.chain_id,
+            from: tx.from,
+            to: tx.to,
+            amount: tx.amount,
+            fee: tx.fee,
+            nonce: tx.nonce,
+            signature: tx.signature,
+            data: tx.data,
+            sponsor: None,
+            cosignature: None,
+            delegate: None,
+        }
+    }
 }
 
 impl Transaction {
@@ -50,6 +115,9 @@ impl Transaction {
             nonce,
             signature: Vec::new(),
             data: None,
+            sponsor: None,
+            cosignature: None,
+            delegate: None,
         }
     }
 
@@ -65,6 +133,68 @@ impl Transaction {
         self
     }
 
+    /// Charge the fee to `payer` instead of the sender
+    /// تحميل الرسوم على حساب آخر بدلاً من المرسل
+    ///
+    /// Set before the sender signs, since the sender's signature covers the
+    /// payer; the payer then signs with [`Self::with_sponsor_signature`].
+    pub fn with_fee_payer(mut self, payer: PublicKey) -> Self {
+        self.sponsor = Some(FeeSponsor {
+            payer,
+            signature: Vec::new(),
+        });
+        self
+    }
+
+    /// Set the fee payer's signature; no effect without a fee payer
+    pub fn with_sponsor_signature(mut self, signature: Vec<u8>) -> Self {
+        if let Some(sponsor) = &mut self.sponsor {
+            sponsor.signature = signature;
+        }
+        self
+    }
+
+    /// Set the policy cosigner's signature over [`Self::cosigning_hash`]
+    pub fn with_cosignature(mut self, signature: Vec<u8>) -> Self {
+        self.cosignature = Some(signature);
+        self
+    }
+
+    /// Sign with `delegate`, a hot key the sender delegated to, instead of
+    /// the sender's own key
+    /// التوقيع بمفتاح ساخن مفوّض بدلاً من مفتاح المرسل
+    ///
+    /// Set before signing, since the signing hash covers the delegate.
+    pub fn with_delegate(mut self, delegate: PublicKey) -> Self {
+        self.delegate = Some(delegate);
+        self
+    }
+
+    /// Key whose signature authorizes the transaction
+    pub fn signer(&self) -> PublicKey {
+        self.delegate.unwrap_or(self.from)
+    }
+
+    /// Account the fee is charged to
+    pub fn fee_payer(&self) -> PublicKey {
+        self.sponsor
+            .as_ref()
+            .map_or(self.from, |sponsor| sponsor.payer)
+    }
+
+    /// Amount this transaction takes from `address`: the amount if it is the
+    /// sender, plus the fee if it pays the fee
+    pub fn debit(&self, address: &PublicKey) -> u64 {
+        let mut debit = 0u64;
+        if self.from == *address {
+            debit = debit.saturating_add(self.amount);
+        }
+        if self.fee_payer() == *address {
+            debit = debit.saturating_add(self.fee);
+        }
+        debit
+    }
+
     /// Get signing hash (what gets signed by sender)
     /// Includes chain_id for replay protection
     pub fn signing_hash(&self) -> [u8; 32] {
@@ -86,6 +216,34 @@ impl Transaction {
                 hasher.update(&[0u8]); // Marker for None
             }
         }
+        // Only sponsored transactions commit to a payer, so the hash of an
+        // unsponsored transaction is unchanged
+        if let Some(sponsor) = &self.sponsor {
+            hasher.update(SPONSOR_DOMAIN);
+            hasher.update(sponsor.payer.0);
+        }
+        if let Some(delegate) = &self.delegate {
+            hasher.update(DELEGATE_DOMAIN);
+            hasher.update(delegate.0);
+        }
+        hasher.finalize().into()
+    }
+
+    /// Hash the fee payer signs, committing to the sender-approved transaction
+    /// التجزئة التي يوقعها دافع الرسوم
+    pub fn sponsor_signing_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(SPONSOR_DOMAIN);
+        hasher.update(self.signing_hash());
+        hasher.finalize().into()
+    }
+
+    /// Hash a spending policy cosigner signs
+    /// التجزئة التي يوقعها الموقّع المشارك لسياسة الإنفاق
+    pub fn cosigning_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(COSIGN_DOMAIN);
+        hasher.update(self.signing_hash());
         hasher.finalize().into()
     }
 
@@ -95,20 +253,62 @@ impl Transaction {
             return Err(TransactionError::MissingSignature);
         }
 
+        if self
+            .delegate
+            .is_some_and(|delegate| delegate == self.from || delegate.is_zero())
+        {
+            return Err(TransactionError::InvalidDelegate);
+        }
+
         let message = self.signing_hash();
-        self.from
+        self.signer()
             .verify(&message, &self.signature)
             .map_err(|_| TransactionError::InvalidSignature)?;
 
+        if let Some(sponsor) = &self.sponsor {
+            if sponsor.payer == self.from || sponsor.payer.is_zero() {
+                return Err(TransactionError::InvalidSponsor);
+            }
+            if sponsor.signature.is_empty() {
+                return Err(TransactionError::MissingSponsorSignature);
+            }
+            sponsor
+                .payer
+                .verify(&self.sponsor_signing_hash(), &sponsor.signature)
+                .map_err(|_| TransactionError::InvalidSponsorSignature)?;
+        }
+
         Ok(())
     }
 
     /// Calculate transaction hash (includes signature for uniqueness)
+    ///
+    /// The cosignature is left out: nothing signs it, so anyone relaying the
+    /// transaction could strip it and change the ID.
     pub fn hash(&self) -> [u8; 32] {
+        self.hasher().finalize().into()
+    }
+
+    /// Hash that also covers the cosignature, used as the merkle leaf so a
+    /// block commits to every signature it carries
+    /// تجزئة تشمل التوقيع المشارك وتُستخدم كورقة في شجرة ميركل
+    pub fn witness_hash(&self) -> [u8; 32] {
+        let mut hasher = self.hasher();
+        if let Some(cosignature) = &self.cosignature {
+            hasher.update(COSIGN_DOMAIN);
+            hasher.update(cosignature);
+        }
+        hasher.finalize().into()
+    }
+
+    fn hasher(&self) -> Sha256 {
         let mut hasher = Sha256::new();
         hasher.update(self.signing_hash());
         hasher.update(&self.signature);
-        hasher.finalize().into()
+        if let Some(sponsor) = &self.sponsor {
+            hasher.update(&sponsor.signature);
+        }
+        hasher
     }
 
     /// Check if this is a coinbase transaction (creates new coins)
@@ -116,6 +316,17 @@ impl Transaction {
         self.from.is_zero() && self.signature.is_empty()
     }
 
+    /// Miner-chosen bytes after the height and timestamp of a coinbase;
+    /// empty for other transactions
+    pub fn coinbase_extra_data(&self) -> &[u8] {
+        match &self.data {
+            Some(data) if self.is_coinbase() => data
+                .get(crate::coinbase::COINBASE_DATA_HEADER_LEN..)
+                .unwrap_or(&[]),
+            _ => &[],
+        }
+    }
+
     /// Create coinbase transaction for block reward
     /// مكافأة المُعدِّن - إنشاء معاملة كوين بيس
     pub fn coinbase(
@@ -147,9 +358,33 @@ impl Transaction {
             nonce: block_height, // Use height as unique identifier
             signature: Vec::new(), // No signature (validated by consensus)
             data: Some(coinbase_data),
+            sponsor: None,
+            cosignature: None,
+            delegate: None,
         })
     }
 
+    /// Reject a transaction signed for another chain once replay protection
+    /// is active at `height`
+    /// رفض معاملة موقعة لسلسلة أخرى بعد تفعيل الحماية من إعادة التشغيل
+    ///
+    /// The signing hash always covers `chain_id`, but before the fork height
+    /// blocks may carry any chain ID, so a transaction built with the mainnet
+    /// default on testnet could be replayed on mainnet.
+    pub fn validate_chain_id(
+        &self,
+        params: &ChainParams,
+        height: u64,
+    ) -> Result<(), TransactionError> {
+        if params.replay_protection_active(height) && self.chain_id != params.chain_id {
+            return Err(TransactionError::WrongChainId {
+                expected: params.chain_id,
+                got: self.chain_id,
+            });
+        }
+        Ok(())
+    }
+
     /// Validate transaction size to prevent DoS attacks
     pub fn validate_size(&self) -> Result<(), TransactionError> {
         // Bincode 2.0: encode and check size
@@ -162,11 +397,28 @@ impl Transaction {
         Ok(())
     }
 
+    /// Serialized size in bytes, used as the transaction's weight
+    pub fn weight(&self) -> usize {
+        bincode::encode_to_vec(self, bincode::config::standard())
+            .map(|encoded| encoded.len())
+            .unwrap_or(MAX_TRANSACTION_SIZE)
+    }
+
+    /// Fee rate in base units per 1000 bytes of weight
+    /// معدل الرسوم لكل 1000 بايت
+    pub fn fee_rate(&self) -> u64 {
+        let weight = self.weight().max(1) as u128;
+        (self.fee as u128 * 1000 / weight) as u64
+    }
+
     /// Validate minimum fee requirement (skip for coinbase)
     pub fn validate_fee(&self) -> Result<(), TransactionError> {
         if self.is_coinbase() {
             return Ok(()); // Coinbase has no fee requirement
         }
+        if crate::deposit::DepositOperation::is_settlement(self) {
+            return Ok(()); // Deposit settlements are issued by the node, not paid for
+        }
         if self.fee < MIN_TRANSACTION_FEE {
             return Err(TransactionError::FeeTooLow);
         }
@@ -184,6 +436,11 @@ pub enum TransactionError {
     InvalidSize,
     TooLarge,
     FeeTooLow,
+    InvalidSponsor,
+    MissingSponsorSignature,
+    InvalidSponsorSignature,
+    InvalidDelegate,
+    WrongChainId { expected: u32, got: u32 },
 }
 
 impl std::fmt::Display for TransactionError {
@@ -197,6 +454,23 @@ impl std::fmt::Display for TransactionError {
             TransactionError::InvalidSize => write!(f, "Cannot calculate transaction size"),
             TransactionError::TooLarge => write!(f, "Transaction exceeds maximum size"),
             TransactionError::FeeTooLow => write!(f, "Transaction fee below minimum"),
+            TransactionError::InvalidSponsor => {
+                write!(f, "Fee payer must be a different, non-zero account")
+            }
+            TransactionError::MissingSponsorSignature => {
+                write!(f, "Sponsored transaction missing fee payer signature")
+            }
+            TransactionError::InvalidSponsorSignature => write!(f, "Invalid fee payer signature"),
+            TransactionError::InvalidDelegate => {
+                write!(f, "Delegate must be a different, non-zero key")
+            }
+            TransactionError::WrongChainId { expected, got } => {
+                write!(
+                    f,
+                    "Transaction signed for chain {}, expected {}",
+                    got, expected
+                )
+            }
         }
     }
 }
@@ -297,6 +571,36 @@ mod tests {
         assert_ne!(tx_mainnet.signing_hash(), tx_testnet.signing_hash());
     }
 
+    #[test]
+    fn test_chain_id_enforced_from_fork_height() {
+        use crate::constants::CHAIN_ID_TESTNET;
+
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        let mainnet = ChainParams::mainnet();
+        let fork = mainnet.replay_protection_height;
+
+        let tx = Transaction::new_with_chain_id(
+            CHAIN_ID_TESTNET,
+            sender.public_key(),
+            receiver.public_key(),
+            1_000_000,
+            100,
+            0,
+        );
+
+        // Tolerated before the fork, rejected from it on
+        assert!(tx.validate_chain_id(&mainnet, fork - 1).is_ok());
+        assert_eq!(
+            tx.validate_chain_id(&mainnet, fork),
+            Err(TransactionError::WrongChainId {
+                expected: mainnet.chain_id,
+                got: CHAIN_ID_TESTNET,
+            })
+        );
+        assert!(tx.validate_chain_id(&ChainParams::testnet(), 1).is_ok());
+    }
+
     #[test]
     fn test_coinbase_transaction() {
         use crate::constants::CHAIN_ID_MAINNET;
@@ -432,4 +736,144 @@ mod tests {
             "None and Some(vec![]) must have different signing hashes"
         );
     }
+
+    #[test]
+    fn test_sponsored_transaction_requires_both_signatures() {
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        let sponsor = KeyPair::generate();
+
+        let unsponsored =
+            Transaction::new(sender.public_key(), receiver.public_key(), 1_000, 100, 0);
+        let mut tx = unsponsored.clone().with_fee_payer(sponsor.public_key());
+        // The sender commits to who pays the fee
+        assert_ne!(tx.signing_hash(), unsponsored.signing_hash());
+
+        let signature = sender.sign(&tx.signing_hash());
+        tx = tx.with_signature(signature);
+        assert_eq!(tx.verify(), Err(TransactionError::MissingSponsorSignature));
+
+        let forged = tx
+            .clone()
+            .with_sponsor_signature(sender.sign(&tx.sponsor_signing_hash()));
+        assert_eq!(
+            forged.verify(),
+            Err(TransactionError::InvalidSponsorSignature)
+        );
+
+        tx = tx
+            .clone()
+            .with_sponsor_signature(sponsor.sign(&tx.sponsor_signing_hash()));
+        assert!(tx.verify().is_ok());
+        assert_eq!(tx.fee_payer(), sponsor.public_key());
+        assert_eq!(tx.debit(&sender.public_key()), 1_000);
+        assert_eq!(tx.debit(&sponsor.public_key()), 100);
+
+        // Swapping the payer breaks the sender's signature
+        let mut swapped = tx.clone();
+        swapped.sponsor.as_mut().unwrap().payer = receiver.public_key();
+        assert_eq!(swapped.verify(), Err(TransactionError::InvalidSignature));
+
+        let encoded = bincode::encode_to_vec(&tx, bincode::config::standard()).unwrap();
+        let (decoded, _): (Transaction, usize) =
+            bincode::decode_from_slice(&encoded, bincode::config::standard()).unwrap();
+        assert_eq!(decoded.hash(), tx.hash());
+    }
+
+    #[test]
+    fn test_delegate_signs_for_sender() {
+        let cold = KeyPair::generate();
+        let hot = KeyPair::generate();
+        let miner = KeyPair::generate().public_key();
+
+        let tx = Transaction::new(cold.public_key(), miner, 1_000, 100, 0)
+            .with_delegate(hot.public_key());
+        assert_eq!(tx.signer(), hot.public_key());
+
+        let by_cold = tx.clone().with_signature(cold.sign(&tx.signing_hash()));
+        assert_eq!(by_cold.verify(), Err(TransactionError::InvalidSignature));
+        let by_hot = tx.clone().with_signature(hot.sign(&tx.signing_hash()));
+        assert!(by_hot.verify().is_ok());
+
+        // The hot key's signature is bound to it being named as delegate
+        let mut undelegated = by_hot.clone();
+        undelegated.delegate = None;
+        assert_eq!(
+            undelegated.verify(),
+            Err(TransactionError::InvalidSignature)
+        );
+
+        let to_self = Transaction::new(cold.public_key(), miner, 1_000, 100, 0)
+            .with_delegate(cold.public_key());
+        let to_self = to_self
+            .clone()
+            .with_signature(cold.sign(&to_self.signing_hash()));
+        assert_eq!(to_self.verify(), Err(TransactionError::InvalidDelegate));
+    }
+
+    #[test]
+    fn test_self_sponsorship_rejected() {
+        let sender = KeyPair::generate();
+        let mut tx = Transaction::new(
+            sender.public_key(),
+            KeyPair::generate().public_key(),
+            1_000,
+            100,
+            0,
+        )
+        .with_fee_payer(sender.public_key());
+        let signature = sender.sign(&tx.signing_hash());
+        tx = tx.with_signature(signature);
+        tx = tx
+            .clone()
+            .with_sponsor_signature(sender.sign(&tx.sponsor_signing_hash()));
+        assert_eq!(tx.verify(), Err(TransactionError::InvalidSponsor));
+        assert_eq!(tx.debit(&sender.public_key()), 1_100);
+    }
+
+    #[test]
+    fn test_cosignature_outside_transaction_id() {
+        let sender = KeyPair::generate();
+        let cosigner = KeyPair::generate();
+        let tx = Transaction::new(sender.public_key(), cosigner.public_key(), 1_000, 100, 0);
+        let tx = tx.clone().with_signature(sender.sign(&tx.signing_hash()));
+        let cosigned = tx
+            .clone()
+            .with_cosignature(cosigner.sign(&tx.cosigning_hash()));
+
+        // Stripping the cosignature keeps the ID but not the merkle leaf
+        assert_eq!(cosigned.hash(), tx.hash());
+        assert_eq!(tx.witness_hash(), tx.hash());
+        assert_ne!(cosigned.witness_hash(), tx.witness_hash());
+    }
+
+    #[test]
+    fn test_legacy_layout_decodes() {
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        let mut tx = Transaction::new(sender.public_key(), receiver.public_key(), 1_000, 100, 3)
+            .with_data(b"rent".to_vec());
+        tx = tx.clone().with_signature(sender.sign(&tx.signing_hash()));
+
+        let legacy = TransactionV1 {
+            chain_id: tx.chain_id,
+            from: tx.from,
+            to: tx.to,
+            amount: tx.amount,
+            fee: tx.fee,
+            nonce: tx.nonce,
+            signature: tx.signature.clone(),
+            data: tx.data.clone(),
+        };
+        let config = bincode::config::standard();
+        let encoded = bincode::encode_to_vec(&legacy, config).unwrap();
+        let (decoded, _): (TransactionV1, usize) =
+            bincode::decode_from_slice(&encoded, config).unwrap();
+        let decoded = Transaction::from(decoded);
+        assert_eq!(decoded.hash(), tx.hash());
+        assert!(decoded.verify().is_ok());
+
+        // The current layout reads past the end of a legacy encoding
+        assert!(bincode::decode_from_slice::<Transaction, _>(&encoded, config).is_err());
+    }
 }
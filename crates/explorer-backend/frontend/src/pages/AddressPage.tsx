@@ -46,6 +46,11 @@ export function AddressPage() {
           <div className="page-header">
             <h1 className="page-title">{t('address.address')}</h1>
             <div className="address-display monospace">{data.address}</div>
+            {data.frozen_by_proposal != null && (
+              <div className="frozen-banner">
+                {t('address.frozen', { proposal: data.frozen_by_proposal })}
+              </div>
+            )}
           </div>
 
           <div className="balance-cards">
@@ -78,6 +83,25 @@ export function AddressPage() {
             </div>
           </div>
 
+          {data.assets && data.assets.length > 0 && (
+            <div className="detail-card">
+              <h2 className="card-title">{t('address.assets')}</h2>
+              <div className="asset-list">
+                {data.assets.map((asset) => (
+                  <div key={asset.asset_id} className="asset-item">
+                    <div className="asset-symbol">{asset.symbol}</div>
+                    <div className="asset-id monospace">{asset.asset_id.slice(0, 16)}...</div>
+                    <div className="asset-balance">
+                      {(asset.balance / 10 ** asset.decimals).toLocaleString(undefined, {
+                        maximumFractionDigits: asset.decimals,
+                      })}
+                    </div>
+                  </div>
+                ))}
+              </div>
+            </div>
+          )}
+
           <div className="detail-card">
             <h2 className="card-title">{t('address.history')}</h2>
             {data.transactions && data.transactions.length > 0 ? (
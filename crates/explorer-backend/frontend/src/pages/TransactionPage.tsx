@@ -51,6 +51,11 @@ export function TransactionPage() {
               <span className={`status-badge ${isConfirmed ? 'confirmed' : 'pending'}`}>
                 {isConfirmed ? t('transaction.confirmed') : t('transaction.pending')}
               </span>
+              {tx.stealth && (
+                <span className="status-badge stealth" title={t('transaction.stealthHint')}>
+                  {t('transaction.stealth')}
+                </span>
+              )}
             </div>
           </div>
 
@@ -68,7 +73,7 @@ export function TransactionPage() {
                 linkTo={`/address/${tx.from}`}
               />
               <DetailRow
-                label={t('transaction.to')}
+                label={tx.stealth ? t('transaction.oneTimeAddress') : t('transaction.to')}
                 value={tx.to}
                 mono
                 linkTo={`/address/${tx.to}`}
@@ -93,6 +98,18 @@ export function TransactionPage() {
                   linkTo={`/block/${tx.block_height}`}
                 />
               )}
+              {tx.memo?.kind === 'plain' && (
+                <DetailRow label={t('transaction.memo')} value={tx.memo.text} />
+              )}
+              {tx.memo?.kind === 'encrypted' && (
+                <DetailRow
+                  label={`${t('transaction.encryptedMemo')}${
+                    tx.memo.version !== null ? ` (v${tx.memo.version})` : ''
+                  }`}
+                  value={tx.memo.ciphertext}
+                  mono
+                />
+              )}
               <DetailRow
                 label={t('block.confirmations')}
                 value={(tx.confirmations || 0).toLocaleString()}
@@ -123,7 +140,9 @@ export function TransactionPage() {
               </div>
               
               <div className="flow-node">
-                <div className="flow-label">{t('transaction.to')}</div>
+                <div className="flow-label">
+                  {tx.stealth ? t('transaction.oneTimeAddress') : t('transaction.to')}
+                </div>
                 <Link to={`/address/${tx.to}`} className="flow-address monospace">
                   {tx.to}
                 </Link>
@@ -38,8 +38,16 @@ export interface Transaction {
   signature: string;
   block_height?: number;
   confirmations?: number;
+  /** Paid to a one-time stealth address that does not identify the recipient */
+  stealth?: boolean;
+  memo?: Memo;
 }
 
+/** Transaction memo; encrypted memos are only shown as ciphertext */
+export type Memo =
+  | { kind: 'plain'; text: string }
+  | { kind: 'encrypted'; version: number | null; ciphertext: string };
+
 export interface AddressInfo {
   address: string;
   balance: number;
@@ -47,6 +55,15 @@ export interface AddressInfo {
   total_received: number;
   transaction_count: number;
   transactions: Transaction[];
+  frozen_by_proposal?: number | null;
+  assets?: AssetBalance[];
+}
+
+export interface AssetBalance {
+  asset_id: string;
+  symbol: string;
+  decimals: number;
+  balance: number;
 }
 
 export interface SearchResult {
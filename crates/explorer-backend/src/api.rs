@@ -1,8 +1,19 @@
 //! API route definitions
 
+use crate::cache::{
+    cached_block_by_hash, cached_block_by_height, cached_transaction, CACHE_VOLATILE,
+};
+use crate::graphql::{build_schema, graphiql, graphql_handler};
 use crate::handlers::*;
+use crate::trace::{trace_address, trace_transaction};
 use crate::websocket::{ws_handler, WsState};
-use axum::{routing::get, Router};
+use axum::{
+    http::{header, HeaderValue},
+    routing::get,
+    Router,
+};
+use opensyria_http::{FieldsLayer, LocaleLayer};
+use tower_http::set_header::SetResponseHeaderLayer;
 
 /// Create API router with all routes
 pub fn create_router(state: AppState) -> Router {
@@ -10,24 +21,63 @@ pub fn create_router(state: AppState) -> Router {
     let ws_state = WsState {
         blockchain: state.blockchain.clone(),
         state: state.state.clone(),
+        live_events: state.live_events.clone(),
     };
+    let schema = build_schema(state.clone());
 
     Router::new()
         // WebSocket for real-time updates (separate state)
         .route("/ws", get(ws_handler).with_state(ws_state))
         // Chain statistics
         .route("/api/stats", get(get_chain_stats))
+        .route("/api/stats/fees", get(get_fee_stats))
+        .route("/api/stats/miners", get(get_miner_stats))
+        .route("/api/stats/supply", get(get_supply_report))
+        .route("/api/stats/latency", get(crate::latency::endpoint_latency))
         // Blocks
         .route("/api/blocks", get(get_recent_blocks))
-        .route("/api/blocks/:height", get(get_block_by_height))
-        .route("/api/blocks/hash/:hash", get(get_block_by_hash))
+        .route("/api/blocks/stream", get(crate::stream::stream_blocks))
+        .route("/api/blocks/stale", get(get_stale_blocks))
+        .route("/api/blocks/:height", get(cached_block_by_height))
+        .route("/api/blocks/:height/journal", get(get_block_journal))
+        .route("/api/blocks/hash/:hash", get(cached_block_by_hash))
         // Transactions
-        .route("/api/transactions/:hash", get(get_transaction))
+        .route("/api/transactions/:hash", get(cached_transaction))
         // Address
         .route("/api/address/:address", get(get_address_info))
+        .route("/api/addresses/top", get(get_top_addresses))
+        // Native assets
+        .route("/api/assets", get(get_assets))
+        .route("/api/assets/:id", get(get_asset))
+        // Order book
+        .route("/api/markets", get(get_markets))
+        .route("/api/markets/:id", get(get_market))
+        .route("/api/markets/:id/trades", get(get_market_trades))
+        .route("/api/orders/:id", get(get_order))
+        // Funds flow tracing
+        .route("/api/trace/address/:address", get(trace_address))
+        .route("/api/trace/tx/:hash", get(trace_transaction))
+        // Governance
+        .route("/api/governance/analytics", get(get_governance_analytics))
         // Mempool
         .route("/api/mempool", get(get_mempool))
+        .route("/api/mempool/summary", get(get_mempool_summary))
+        .route("/api/mempool/transactions", get(get_mempool_transactions))
         // Search
         .route("/api/search/:query", get(search))
+        // GraphQL (separate state)
+        .route(
+            "/api/graphql",
+            get(graphiql).post(graphql_handler).with_state(schema),
+        )
+        // `?fields=` trims JSON bodies of GET responses
+        .layer(FieldsLayer)
+        // `?locale=` adds display strings to what is left
+        .layer(LocaleLayer)
+        // Anything that did not set its own caching policy changes with the tip
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_VOLATILE),
+        ))
         .with_state(state)
 }
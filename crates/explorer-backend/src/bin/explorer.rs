@@ -1,14 +1,44 @@
+use opensyria_explorer_backend::object_cache::{ObjectCache, CACHE_URL_ENV};
 use opensyria_explorer_backend::ExplorerServer;
+use opensyria_metrics::server::{MetricsAuth, MetricsServerConfig};
+use opensyria_metrics::telemetry::{init_tracing, OtlpConfig};
+use opensyria_node::{NodeConfig, NodeHandle};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::fmt::init();
+    // Parse command line arguments (simple version): `--node` runs a full
+    // node in this process for live blocks, mempool and event feeds instead
+    // of reading the data directory of a stopped one; `--testnet` with it
+    // joins the test network; `--metrics <addr>` serves the Prometheus
+    // scrape on a separate listener, behind a bearer token when
+    // OPENSYRIA_EXPLORER_METRICS_TOKEN is set; `--otlp-endpoint <url>`
+    // exports request spans, joined to callers' W3C trace context, to an
+    // OTLP/HTTP collector, sampling `--otlp-sample-ratio` of them
+    let mut args: Vec<String> = std::env::args().collect();
+    let sample_ratio = match take_value(&mut args, "--otlp-sample-ratio") {
+        Some(ratio) => ratio.parse()?,
+        None => 1.0,
+    };
+    let otlp = take_value(&mut args, "--otlp-endpoint").map(|endpoint| OtlpConfig {
+        sample_ratio,
+        ..OtlpConfig::new(endpoint, "opensyria-explorer")
+    });
+    let _telemetry = init_tracing("info", otlp)?;
 
-    // Parse command line arguments (simple version)
-    let args: Vec<String> = std::env::args().collect();
+    let embedded_node = take_flag(&mut args, "--node");
+    let testnet = take_flag(&mut args, "--testnet");
+    let metrics = match take_value(&mut args, "--metrics") {
+        Some(addr) => {
+            let mut config = MetricsServerConfig::new(addr.parse()?);
+            if let Ok(token) = std::env::var(METRICS_TOKEN_ENV) {
+                config.auth = MetricsAuth::Bearer { token };
+            }
+            Some(config)
+        }
+        None => None,
+    };
 
     let data_dir = if args.len() > 1 {
         PathBuf::from(&args[1])
@@ -36,13 +66,66 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("📂 Data directory: {}", data_dir.display());
     println!("🌐 Server address: http://{}", addr);
     println!("📁 Static files: {}", static_dir.display());
+
+    // In-memory LRU unless a cache URL such as redis://127.0.0.1/ is set
+    let cache = match std::env::var(CACHE_URL_ENV) {
+        Ok(url) => {
+            println!("🗄️  Cache: {}", url);
+            ObjectCache::open(&url).await?
+        }
+        Err(_) => ObjectCache::default(),
+    };
     println!();
     println!("Open your browser to: http://localhost:{}", port);
     println!();
 
-    let server = ExplorerServer::new(data_dir, addr)?.with_static_dir(static_dir);
+    if !embedded_node {
+        let mut server = ExplorerServer::new(data_dir, addr)?
+            .with_static_dir(static_dir)
+            .with_cache(cache);
+        if let Some(metrics) = metrics {
+            server = server.with_metrics(metrics);
+        }
+        server.run().await?;
+        return Ok(());
+    }
 
-    server.run().await?;
+    let config = if testnet {
+        NodeConfig::testnet(&data_dir)
+    } else {
+        NodeConfig::new(&data_dir)
+    };
+    let mut node = NodeHandle::open(config).await?;
+    node.start_network().await?;
+    println!("🔗 Embedded node: {}", node.local_peer_id());
+
+    let mut server = ExplorerServer::for_node(&node, addr)
+        .await?
+        .with_mempool(node.mempool())
+        .with_event_bus(node.event_bus().clone())
+        .with_static_dir(static_dir)
+        .with_cache(cache);
+    if let Some(metrics) = metrics {
+        server = server.with_metrics(metrics);
+    }
+    let served = server.run().await;
+    node.shutdown().await?;
+    served
+}
+
+/// Bearer token required to scrape the `--metrics` listener
+const METRICS_TOKEN_ENV: &str = "OPENSYRIA_EXPLORER_METRICS_TOKEN";
+
+/// Remove `flag` and the value after it from `args`
+fn take_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let index = args.iter().position(|arg| arg == flag)?;
+    args.remove(index);
+    (index < args.len()).then(|| args.remove(index))
+}
 
-    Ok(())
+/// Remove `flag` from `args`, returning whether it was present
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() != before
 }
@@ -0,0 +1,354 @@
+//! HTTP caching for explorer responses
+//!
+//! Blocks and confirmed transactions never change once they are buried deeper
+//! than the maximum reorganization depth, so their responses carry a strong
+//! ETag derived from the block hash and long-lived cache headers. Shallower
+//! data is still validated via ETag but only cached briefly, and everything
+//! else is served with `no-cache`. A transaction still short of its
+//! confirmation target changes with every block, so its ETag includes the
+//! confirmation count.
+
+use crate::handlers::{
+    get_block_by_hash, get_block_by_height, get_transaction, ApiError, AppState,
+};
+use crate::types::{BlockInfo, TransactionInfo};
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use opensyria_core::{ConfirmationStatus, MAX_REORG_DEPTH};
+use serde::Serialize;
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Cache-Control for data that can no longer be reorganized away
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// Cache-Control for confirmed data that could still be reorganized
+const CACHE_RECENT: &str = "public, max-age=30, must-revalidate";
+
+/// Cache-Control for data that changes with every block
+pub const CACHE_VOLATILE: &str = "no-cache";
+
+/// How long a response may be cached, based on how likely it is to change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutability {
+    /// Buried deeper than `MAX_REORG_DEPTH`
+    Immutable,
+    /// Confirmed, but within reorganization range
+    Recent,
+    /// Changes with chain tip or mempool
+    Volatile,
+}
+
+impl Mutability {
+    /// Classify data included at `height` given the current chain tip
+    pub fn for_height(height: u64, tip_height: u64) -> Self {
+        if tip_height.saturating_sub(height) >= MAX_REORG_DEPTH {
+            Mutability::Immutable
+        } else {
+            Mutability::Recent
+        }
+    }
+
+    fn cache_control(self) -> &'static str {
+        match self {
+            Mutability::Immutable => CACHE_IMMUTABLE,
+            Mutability::Recent => CACHE_RECENT,
+            Mutability::Volatile => CACHE_VOLATILE,
+        }
+    }
+}
+
+/// Validators attached to a cacheable response
+#[derive(Debug, Clone)]
+pub struct CacheValidators {
+    /// Strong entity tag, including surrounding quotes
+    pub etag: String,
+    /// Unix timestamp the entity was last modified
+    pub last_modified: Option<u64>,
+    pub mutability: Mutability,
+}
+
+impl CacheValidators {
+    /// Validators for a block, keyed on its hash
+    pub fn for_block(info: &BlockInfo, tip_height: u64) -> Self {
+        Self {
+            etag: format!("\"{}\"", info.hash),
+            last_modified: Some(info.timestamp),
+            mutability: Mutability::for_height(info.height, tip_height),
+        }
+    }
+
+    /// Validators for a transaction, keyed on the hash of its containing block
+    /// so that a reorg invalidates cached copies
+    pub fn for_transaction(info: &TransactionInfo, tip_height: u64) -> Self {
+        match (&info.block_hash, info.block_height) {
+            (Some(block_hash), Some(height)) => {
+                let (etag, mutability) = match info.status {
+                    ConfirmationStatus::Confirming { confirmations, .. } => (
+                        format!("\"{}-{}-{}\"", info.hash, block_hash, confirmations),
+                        Mutability::Recent,
+                    ),
+                    _ => (
+                        format!("\"{}-{}\"", info.hash, block_hash),
+                        Mutability::for_height(height, tip_height),
+                    ),
+                };
+                Self {
+                    etag,
+                    last_modified: info.timestamp,
+                    mutability,
+                }
+            }
+            _ => Self {
+                etag: format!("\"{}\"", info.hash),
+                last_modified: None,
+                mutability: Mutability::Volatile,
+            },
+        }
+    }
+
+    /// Whether the client's cached copy is still current
+    pub fn is_not_modified(&self, headers: &HeaderMap) -> bool {
+        // If-None-Match takes precedence over If-Modified-Since (RFC 9110 §13.2.2)
+        if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+            let Ok(value) = if_none_match.to_str() else {
+                return false;
+            };
+            return value.split(',').map(str::trim).any(|tag| {
+                tag == "*" || tag == self.etag || tag.strip_prefix("W/") == Some(&self.etag)
+            });
+        }
+
+        match (self.last_modified, headers.get(header::IF_MODIFIED_SINCE)) {
+            (Some(last_modified), Some(since)) => since
+                .to_str()
+                .ok()
+                .and_then(|s| httpdate::parse_http_date(s).ok())
+                .and_then(|since| since.duration_since(UNIX_EPOCH).ok())
+                .is_some_and(|since| last_modified <= since.as_secs()),
+            _ => false,
+        }
+    }
+
+    fn apply(&self, headers: &mut HeaderMap) {
+        if let Ok(etag) = HeaderValue::from_str(&self.etag) {
+            headers.insert(header::ETAG, etag);
+        }
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(self.mutability.cache_control()),
+        );
+        if let Some(last_modified) = self.last_modified {
+            let date = httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(last_modified));
+            if let Ok(value) = HeaderValue::from_str(&date) {
+                headers.insert(header::LAST_MODIFIED, value);
+            }
+        }
+    }
+}
+
+/// Build a JSON response honouring conditional request headers
+pub fn conditional_json<T: Serialize>(
+    request_headers: &HeaderMap,
+    validators: &CacheValidators,
+    body: T,
+) -> Response {
+    let mut response = if validators.is_not_modified(request_headers) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body).into_response()
+    };
+
+    validators.apply(response.headers_mut());
+    response
+}
+
+/// Current chain height, used to decide whether data is final
+async fn tip_height(state: &AppState) -> u64 {
+    state
+        .blockchain
+        .read()
+        .await
+        .get_chain_height()
+        .unwrap_or(0)
+}
+
+/// GET /api/blocks/:height with ETag and cache headers
+pub async fn cached_block_by_height(
+    Path(height): Path<u64>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Json(info) = get_block_by_height(Path(height), State(state.clone())).await?;
+    let validators = CacheValidators::for_block(&info, tip_height(&state).await);
+    Ok(conditional_json(&headers, &validators, info))
+}
+
+/// GET /api/blocks/hash/:hash with ETag and cache headers
+pub async fn cached_block_by_hash(
+    Path(hash): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Json(info) = get_block_by_hash(Path(hash), State(state.clone())).await?;
+    let validators = CacheValidators::for_block(&info, tip_height(&state).await);
+    Ok(conditional_json(&headers, &validators, info))
+}
+
+/// GET /api/transactions/:hash with ETag and cache headers
+pub async fn cached_transaction(
+    Path(hash): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Response, ApiError> {
+    let Json(info) = get_transaction(Path(hash), State(state.clone())).await?;
+    let validators = CacheValidators::for_transaction(&info, tip_height(&state).await);
+    Ok(conditional_json(&headers, &validators, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_info(height: u64) -> BlockInfo {
+        BlockInfo {
+            hash: "ab".repeat(32),
+            height,
+            timestamp: 1_763_452_800,
+            difficulty: 16,
+            nonce: 0,
+            previous_hash: "00".repeat(32),
+            merkle_root: "00".repeat(32),
+            transaction_count: 0,
+            transactions: vec![],
+            miner: None,
+            miner_tag: None,
+        }
+    }
+
+    #[test]
+    fn test_mutability_by_depth() {
+        assert_eq!(Mutability::for_height(10, 10), Mutability::Recent);
+        assert_eq!(
+            Mutability::for_height(10, 10 + MAX_REORG_DEPTH),
+            Mutability::Immutable
+        );
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let validators = CacheValidators::for_block(&block_info(1), 500);
+        let mut headers = HeaderMap::new();
+        assert!(!validators.is_not_modified(&headers));
+
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&validators.etag).unwrap(),
+        );
+        assert!(validators.is_not_modified(&headers));
+
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"other\""));
+        assert!(!validators.is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_if_modified_since() {
+        let validators = CacheValidators::for_block(&block_info(1), 500);
+        let mut headers = HeaderMap::new();
+
+        let later = httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(1_763_452_900));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&later).unwrap(),
+        );
+        assert!(validators.is_not_modified(&headers));
+
+        let earlier = httpdate::fmt_http_date(UNIX_EPOCH + Duration::from_secs(1_763_452_700));
+        headers.insert(
+            header::IF_MODIFIED_SINCE,
+            HeaderValue::from_str(&earlier).unwrap(),
+        );
+        assert!(!validators.is_not_modified(&headers));
+    }
+
+    #[test]
+    fn test_not_modified_response_headers() {
+        let info = block_info(1);
+        let validators = CacheValidators::for_block(&info, 500);
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&validators.etag).unwrap(),
+        );
+
+        let response = conditional_json(&headers, &validators, info);
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(
+            response.headers().get(header::CACHE_CONTROL).unwrap(),
+            CACHE_IMMUTABLE
+        );
+        assert!(response.headers().get(header::ETAG).is_some());
+    }
+
+    #[test]
+    fn test_unconfirmed_transaction_is_volatile() {
+        let info = TransactionInfo {
+            hash: "cd".repeat(32),
+            from: "00".repeat(32),
+            to: "11".repeat(32),
+            amount: 1,
+            fee: 1,
+            nonce: 0,
+            signature: String::new(),
+            timestamp: None,
+            block_hash: None,
+            block_height: None,
+            status: ConfirmationStatus::Pending,
+            stealth: false,
+            memo: None,
+        };
+
+        let validators = CacheValidators::for_transaction(&info, 500);
+        assert_eq!(validators.mutability, Mutability::Volatile);
+    }
+
+    #[test]
+    fn test_confirming_transaction_etag_tracks_depth() {
+        let mut info = TransactionInfo {
+            hash: "cd".repeat(32),
+            from: "00".repeat(32),
+            to: "11".repeat(32),
+            amount: 1,
+            fee: 1,
+            nonce: 0,
+            signature: String::new(),
+            timestamp: Some(1_763_452_800),
+            block_hash: Some("ab".repeat(32)),
+            block_height: Some(10),
+            status: ConfirmationStatus::Confirming {
+                confirmations: MAX_REORG_DEPTH + 1,
+                target: 200,
+            },
+            stealth: false,
+            memo: None,
+        };
+
+        // Still confirming past the reorg depth: never immutable
+        let first = CacheValidators::for_transaction(&info, 10 + MAX_REORG_DEPTH);
+        assert_eq!(first.mutability, Mutability::Recent);
+
+        info.status = ConfirmationStatus::Confirming {
+            confirmations: MAX_REORG_DEPTH + 2,
+            target: 200,
+        };
+        let second = CacheValidators::for_transaction(&info, 11 + MAX_REORG_DEPTH);
+        assert_ne!(first.etag, second.etag);
+
+        info.status = ConfirmationStatus::Final;
+        let last = CacheValidators::for_transaction(&info, 199 + MAX_REORG_DEPTH);
+        assert_eq!(last.mutability, Mutability::Immutable);
+    }
+}
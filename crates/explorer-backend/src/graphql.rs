@@ -0,0 +1,858 @@
+//! GraphQL API for explorer data
+//! واجهة GraphQL لبيانات المستكشف
+//!
+//! `POST /api/graphql` answers queries over blocks, transactions, addresses,
+//! governance proposals and identity tokens; `GET /api/graphql` serves
+//! GraphiQL. Objects link to each other (a transaction's block, an address's
+//! tokens, a vote's voter), so clients fetch exactly the fields they need in
+//! one request. Lists are Relay-style connections capped at
+//! [`MAX_PER_PAGE`] items, and every query is checked against depth and
+//! complexity limits before it runs. The route sits behind the same per-IP
+//! rate limiter as the REST API.
+
+use crate::handlers::{AppState, MAX_PER_PAGE};
+use crate::types::MemoInfo;
+use async_graphql::{
+    connection::{query, Connection, Edge},
+    http::GraphiQLSource,
+    Context, EmptyMutation, EmptySubscription, Object, Result, Schema,
+};
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+    Json,
+};
+use opensyria_core::{
+    confirmations::confirmations, crypto::PublicKey, Block, StealthAnnouncement, Transaction,
+};
+use opensyria_governance::{GovernanceSnapshot, Proposal, VoteRecord};
+use opensyria_identity::{IdentityToken, Transfer};
+
+/// Maximum nesting depth of a query
+pub const MAX_QUERY_DEPTH: usize = 10;
+
+/// Maximum complexity (roughly, fields resolved) of a query
+pub const MAX_QUERY_COMPLEXITY: usize = 2_000;
+
+/// Page size when `first` is not given
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+/// Complexity charged per transaction of a block, which is not paginated
+const BLOCK_TRANSACTIONS_COMPLEXITY: usize = 10;
+
+pub type ExplorerSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Build the schema over the explorer's storage
+pub fn build_schema(state: AppState) -> ExplorerSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .limit_complexity(MAX_QUERY_COMPLEXITY)
+        .finish()
+}
+
+/// POST /api/graphql - Execute a GraphQL query
+pub async fn graphql_handler(
+    State(schema): State<ExplorerSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request).await)
+}
+
+/// GET /api/graphql - GraphiQL playground
+pub async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/api/graphql").finish())
+}
+
+/// Root query type
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Current chain height
+    async fn height(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(app(ctx).blockchain.read().await.get_chain_height()?)
+    }
+
+    /// Block at the chain tip
+    async fn tip(&self, ctx: &Context<'_>) -> Result<Option<BlockNode>> {
+        let height = self.height(ctx).await?;
+        load_block(ctx, height).await
+    }
+
+    /// Block by height or hash
+    async fn block(
+        &self,
+        ctx: &Context<'_>,
+        height: Option<u64>,
+        hash: Option<String>,
+    ) -> Result<Option<BlockNode>> {
+        match (height, hash) {
+            (Some(height), None) => load_block(ctx, height).await,
+            (None, Some(hash)) => {
+                let hash = parse_hash(&hash)?;
+                match app(ctx).indexer.get_block_height(&hash)? {
+                    Some(height) => load_block(ctx, height).await,
+                    None => Ok(None),
+                }
+            }
+            _ => Err("Specify exactly one of height or hash".into()),
+        }
+    }
+
+    /// Blocks, newest first; the cursor is the block height, and a cursor
+    /// past the tip starts at the tip
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn blocks(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<u64, BlockNode>> {
+        let limit = page_size(first)?;
+        let state = app(ctx).clone();
+
+        query(
+            after,
+            None,
+            None,
+            None,
+            |after: Option<u64>, _, _, _| async move {
+                let blockchain = state.blockchain.read().await;
+                let tip = blockchain.get_chain_height()?;
+                let start = match after {
+                    Some(after) => after.saturating_sub(1).min(tip),
+                    None => tip,
+                };
+
+                // Walk exactly one page of heights, even where blocks are missing
+                let end = start.saturating_sub(limit as u64);
+                let mut connection = Connection::new(start < tip, end > 0);
+                for height in (end + 1..=start).rev() {
+                    if let Some(block) = blockchain.get_block_by_height(height)? {
+                        connection
+                            .edges
+                            .push(Edge::new(height, BlockNode { height, block }));
+                    }
+                }
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    /// Confirmed transaction by hash
+    async fn transaction(
+        &self,
+        ctx: &Context<'_>,
+        hash: String,
+    ) -> Result<Option<TransactionNode>> {
+        load_transaction(app(ctx), &parse_hash(&hash)?).await
+    }
+
+    /// Account by hex address
+    async fn address(&self, address: String) -> Result<AddressNode> {
+        Ok(AddressNode(PublicKey(parse_hash(&address)?)))
+    }
+
+    /// Governance proposal by ID
+    async fn proposal(&self, ctx: &Context<'_>, id: u64) -> Result<Option<ProposalNode>> {
+        let snapshot = load_governance(ctx)?;
+        Ok(snapshot
+            .proposals
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| ProposalNode::new(p, &snapshot)))
+    }
+
+    /// Governance proposals in ID order, optionally filtered by status
+    /// (e.g. "Active", "Passed"); the cursor is the proposal ID
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn proposals(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<String>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<u64, ProposalNode>> {
+        let limit = page_size(first)?;
+        let snapshot = load_governance(ctx)?;
+
+        query(
+            after,
+            None,
+            None,
+            None,
+            |after: Option<u64>, _, _, _| async move {
+                let mut proposals: Vec<&Proposal> = snapshot
+                    .proposals
+                    .iter()
+                    .filter(|p| after.is_none_or(|after| p.id > after))
+                    .filter(|p| {
+                        status
+                            .as_ref()
+                            .is_none_or(|s| format!("{:?}", p.status).eq_ignore_ascii_case(s))
+                    })
+                    .collect();
+                proposals.sort_by_key(|p| p.id);
+
+                let mut connection = Connection::new(after.is_some(), proposals.len() > limit);
+                connection.edges.extend(
+                    proposals
+                        .into_iter()
+                        .take(limit)
+                        .map(|p| Edge::new(p.id, ProposalNode::new(p, &snapshot))),
+                );
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    /// Identity token by ID
+    async fn identity_token(
+        &self,
+        ctx: &Context<'_>,
+        id: String,
+    ) -> Result<Option<IdentityTokenNode>> {
+        let Some(identity) = &app(ctx).identity else {
+            return Ok(None);
+        };
+        Ok(identity.get_token(&id)?.map(IdentityTokenNode))
+    }
+
+    /// Identity tokens in ID order; the cursor is the token ID
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn identity_tokens(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<String, IdentityTokenNode>> {
+        let limit = page_size(first)?;
+        let identity = app(ctx).identity.clone();
+
+        query(
+            after,
+            None,
+            None,
+            None,
+            |after: Option<String>, _, _, _| async move {
+                let mut tokens = match &identity {
+                    Some(identity) => identity.list_tokens(after.as_deref(), limit + 1)?,
+                    None => Vec::new(),
+                };
+
+                let has_next = tokens.len() > limit;
+                tokens.truncate(limit);
+
+                let mut connection = Connection::new(after.is_some(), has_next);
+                connection.edges.extend(
+                    tokens
+                        .into_iter()
+                        .map(|token| Edge::new(token.id.clone(), IdentityTokenNode(token))),
+                );
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+}
+
+/// Block on the active chain
+pub struct BlockNode {
+    height: u64,
+    block: Block,
+}
+
+#[Object(name = "Block")]
+impl BlockNode {
+    async fn height(&self) -> u64 {
+        self.height
+    }
+
+    async fn hash(&self) -> String {
+        hex::encode(self.block.hash())
+    }
+
+    async fn previous_hash(&self) -> String {
+        hex::encode(self.block.header.previous_hash)
+    }
+
+    async fn merkle_root(&self) -> String {
+        hex::encode(self.block.header.merkle_root)
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.block.header.timestamp
+    }
+
+    async fn difficulty(&self) -> u32 {
+        self.block.header.difficulty
+    }
+
+    async fn nonce(&self) -> u64 {
+        self.block.header.nonce
+    }
+
+    async fn transaction_count(&self) -> usize {
+        self.block.transactions.len()
+    }
+
+    /// Transactions in block order
+    #[graphql(complexity = "BLOCK_TRANSACTIONS_COMPLEXITY * child_complexity")]
+    async fn transactions(&self) -> Vec<TransactionNode> {
+        self.block
+            .transactions
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| TransactionNode {
+                tx: tx.clone(),
+                position: TxPosition {
+                    height: self.height,
+                    index,
+                    timestamp: self.block.header.timestamp,
+                },
+            })
+            .collect()
+    }
+
+    /// Previous block
+    async fn parent(&self, ctx: &Context<'_>) -> Result<Option<BlockNode>> {
+        match self.height.checked_sub(1) {
+            Some(height) if height > 0 => load_block(ctx, height).await,
+            _ => Ok(None),
+        }
+    }
+
+    /// Recipient of the coinbase reward
+    async fn miner(&self) -> Option<AddressNode> {
+        self.block
+            .transactions
+            .iter()
+            .find(|tx| tx.is_coinbase())
+            .map(|tx| AddressNode(tx.to))
+    }
+}
+
+/// Where a transaction was confirmed
+#[derive(Clone, Copy)]
+struct TxPosition {
+    height: u64,
+    index: usize,
+    timestamp: u64,
+}
+
+/// Confirmed transaction
+pub struct TransactionNode {
+    tx: Transaction,
+    position: TxPosition,
+}
+
+#[Object(name = "Transaction")]
+impl TransactionNode {
+    async fn hash(&self) -> String {
+        hex::encode(self.tx.hash())
+    }
+
+    async fn from(&self) -> AddressNode {
+        AddressNode(self.tx.from)
+    }
+
+    async fn to(&self) -> AddressNode {
+        AddressNode(self.tx.to)
+    }
+
+    async fn amount(&self) -> u64 {
+        self.tx.amount
+    }
+
+    async fn fee(&self) -> u64 {
+        self.tx.fee
+    }
+
+    async fn nonce(&self) -> u64 {
+        self.tx.nonce
+    }
+
+    async fn is_coinbase(&self) -> bool {
+        self.tx.is_coinbase()
+    }
+
+    /// Paid to a one-time stealth address
+    async fn is_stealth(&self) -> bool {
+        StealthAnnouncement::is_stealth(&self.tx)
+    }
+
+    /// Plain text memo
+    async fn memo(&self) -> Option<String> {
+        match MemoInfo::from_transaction(&self.tx)? {
+            MemoInfo::Plain { text } => Some(text),
+            MemoInfo::Encrypted { .. } => None,
+        }
+    }
+
+    /// Hex ciphertext of a memo encrypted to the recipient
+    async fn encrypted_memo(&self) -> Option<String> {
+        match MemoInfo::from_transaction(&self.tx)? {
+            MemoInfo::Encrypted { ciphertext, .. } => Some(ciphertext),
+            MemoInfo::Plain { .. } => None,
+        }
+    }
+
+    async fn block_height(&self) -> u64 {
+        self.position.height
+    }
+
+    async fn index_in_block(&self) -> usize {
+        self.position.index
+    }
+
+    /// Timestamp of the confirming block
+    async fn timestamp(&self) -> u64 {
+        self.position.timestamp
+    }
+
+    /// Blocks on top of and including the confirming block
+    async fn confirmations(&self, ctx: &Context<'_>) -> Result<u64> {
+        let tip_height = app(ctx).blockchain.read().await.get_chain_height()?;
+        Ok(confirmations(self.position.height, tip_height))
+    }
+
+    /// `confirming (n/N)` or `final`
+    async fn status(&self, ctx: &Context<'_>) -> Result<String> {
+        let state = app(ctx);
+        let tip_height = state.blockchain.read().await.get_chain_height()?;
+        let status =
+            state
+                .confirmation_targets
+                .status(&self.tx, Some(self.position.height), tip_height);
+        Ok(status.to_string())
+    }
+
+    /// Confirming block
+    async fn block(&self, ctx: &Context<'_>) -> Result<Option<BlockNode>> {
+        load_block(ctx, self.position.height).await
+    }
+}
+
+/// Account state and history
+pub struct AddressNode(PublicKey);
+
+#[Object(name = "Address")]
+impl AddressNode {
+    async fn address(&self) -> String {
+        self.0.to_hex()
+    }
+
+    async fn balance(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(app(ctx).state.read().await.get_balance(&self.0)?)
+    }
+
+    async fn nonce(&self, ctx: &Context<'_>) -> Result<u64> {
+        Ok(app(ctx).state.read().await.get_nonce(&self.0)?)
+    }
+
+    /// Governance proposal that froze this address, if frozen
+    async fn frozen_by_proposal(&self, ctx: &Context<'_>) -> Result<Option<u64>> {
+        Ok(app(ctx).state.read().await.get_freeze(&self.0)?)
+    }
+
+    async fn transaction_count(&self, ctx: &Context<'_>) -> Result<usize> {
+        Ok(app(ctx).indexer.get_address_tx_count(&self.0)?)
+    }
+
+    /// Transactions touching this address, oldest first; the cursor is the
+    /// position in the address history
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<usize, TransactionNode>> {
+        let limit = page_size(first)?;
+        let state = app(ctx).clone();
+        let address = self.0;
+
+        query(
+            after,
+            None,
+            None,
+            None,
+            |after: Option<usize>, _, _, _| async move {
+                let offset = after.map_or(0, |after| after.saturating_add(1));
+                let (hashes, total) = state
+                    .indexer
+                    .get_address_tx_hashes_paginated(&address, offset, limit)?;
+
+                let mut connection = Connection::new(offset > 0, offset + hashes.len() < total);
+                for (i, hash) in hashes.iter().enumerate() {
+                    if let Some(tx) = load_transaction(&state, hash).await? {
+                        connection.edges.push(Edge::new(offset + i, tx));
+                    }
+                }
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+
+    /// Identity tokens currently owned by this address, at most
+    /// [`MAX_PER_PAGE`]
+    #[graphql(complexity = "MAX_PER_PAGE * child_complexity")]
+    async fn identity_tokens(&self, ctx: &Context<'_>) -> Result<Vec<IdentityTokenNode>> {
+        let Some(identity) = &app(ctx).identity else {
+            return Ok(Vec::new());
+        };
+        Ok(identity
+            .get_tokens_by_owner(&self.0)?
+            .into_iter()
+            // The owner index keeps entries for previous owners
+            .filter(|token| token.owner == self.0)
+            .take(MAX_PER_PAGE)
+            .map(IdentityTokenNode)
+            .collect())
+    }
+}
+
+/// Governance proposal with its votes
+pub struct ProposalNode {
+    proposal: Proposal,
+    votes: Vec<VoteRecord>,
+}
+
+impl ProposalNode {
+    fn new(proposal: &Proposal, snapshot: &GovernanceSnapshot) -> Self {
+        Self {
+            proposal: proposal.clone(),
+            votes: snapshot
+                .votes
+                .iter()
+                .filter(|(id, _, _)| *id == proposal.id)
+                .map(|(_, _, record)| record.clone())
+                .collect(),
+        }
+    }
+}
+
+#[Object(name = "Proposal")]
+impl ProposalNode {
+    async fn id(&self) -> u64 {
+        self.proposal.id
+    }
+
+    async fn proposer(&self) -> AddressNode {
+        AddressNode(self.proposal.proposer)
+    }
+
+    async fn title(&self) -> &str {
+        &self.proposal.title
+    }
+
+    async fn description(&self) -> &str {
+        &self.proposal.description
+    }
+
+    /// Proposal type and parameters as JSON
+    async fn proposal_type(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.proposal.proposal_type)?)
+    }
+
+    async fn status(&self) -> String {
+        format!("{:?}", self.proposal.status)
+    }
+
+    async fn created_at(&self) -> u64 {
+        self.proposal.created_at
+    }
+
+    async fn voting_start(&self) -> u64 {
+        self.proposal.voting_start
+    }
+
+    async fn voting_end(&self) -> u64 {
+        self.proposal.voting_end
+    }
+
+    async fn required_quorum(&self) -> u64 {
+        self.proposal.required_quorum
+    }
+
+    async fn required_threshold(&self) -> u64 {
+        self.proposal.required_threshold
+    }
+
+    async fn votes_yes(&self) -> u64 {
+        self.proposal.votes_yes
+    }
+
+    async fn votes_no(&self) -> u64 {
+        self.proposal.votes_no
+    }
+
+    async fn votes_abstain(&self) -> u64 {
+        self.proposal.votes_abstain
+    }
+
+    async fn total_voting_power(&self) -> u64 {
+        self.proposal.total_voting_power
+    }
+
+    /// Deposit escrowed from the proposer
+    async fn deposit(&self) -> u64 {
+        self.proposal.deposit
+    }
+
+    /// `Escrowed`, `Refunded` or `Burned`
+    async fn deposit_status(&self) -> String {
+        format!("{:?}", self.proposal.deposit_status)
+    }
+
+    /// Votes cast, in recorded order; the cursor is the vote position
+    #[graphql(complexity = "page_complexity(first, child_complexity)")]
+    async fn votes(
+        &self,
+        first: Option<i32>,
+        after: Option<String>,
+    ) -> Result<Connection<usize, VoteNode>> {
+        let limit = page_size(first)?;
+
+        query(
+            after,
+            None,
+            None,
+            None,
+            |after: Option<usize>, _, _, _| async move {
+                let offset = after.map_or(0, |after| after.saturating_add(1));
+                let more = offset.saturating_add(limit) < self.votes.len();
+                let mut connection = Connection::new(offset > 0, more);
+                connection.edges.extend(
+                    self.votes
+                        .iter()
+                        .enumerate()
+                        .skip(offset)
+                        .take(limit)
+                        .map(|(i, vote)| Edge::new(i, VoteNode(vote.clone()))),
+                );
+                Ok::<_, async_graphql::Error>(connection)
+            },
+        )
+        .await
+    }
+}
+
+/// Vote on a governance proposal
+pub struct VoteNode(VoteRecord);
+
+#[Object(name = "Vote")]
+impl VoteNode {
+    async fn voter(&self) -> AddressNode {
+        AddressNode(self.0.voter)
+    }
+
+    /// "Yes", "No" or "Abstain"
+    async fn vote(&self) -> String {
+        format!("{:?}", self.0.vote)
+    }
+
+    async fn voting_power(&self) -> u64 {
+        self.0.voting_power
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    /// Delegator whose voting power was used, for delegated votes
+    async fn delegated_from(&self) -> Option<AddressNode> {
+        self.0.delegated_from.map(AddressNode)
+    }
+}
+
+/// Cultural identity token
+pub struct IdentityTokenNode(IdentityToken);
+
+#[Object(name = "IdentityToken")]
+impl IdentityTokenNode {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn owner(&self) -> AddressNode {
+        AddressNode(self.0.owner)
+    }
+
+    async fn creator(&self) -> AddressNode {
+        AddressNode(self.0.creator)
+    }
+
+    async fn token_type(&self) -> String {
+        format!("{:?}", self.0.token_type)
+    }
+
+    async fn category(&self) -> String {
+        format!("{:?}", self.0.category)
+    }
+
+    async fn name(&self) -> &str {
+        &self.0.metadata.name
+    }
+
+    async fn name_ar(&self) -> Option<&str> {
+        self.0.metadata.name_ar.as_deref()
+    }
+
+    async fn description(&self) -> &str {
+        &self.0.metadata.description
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.metadata.tags
+    }
+
+    async fn ipfs_cid(&self) -> Option<&str> {
+        self.0.ipfs_cid.as_deref()
+    }
+
+    async fn royalty_percentage(&self) -> u8 {
+        self.0.royalty_percentage
+    }
+
+    async fn created_at(&self) -> u64 {
+        self.0.created_at
+    }
+
+    async fn minted_at_height(&self) -> u64 {
+        self.0.minted_at_height
+    }
+
+    /// Ownership transfers, newest [`MAX_PER_PAGE`] of them, oldest first
+    #[graphql(complexity = "MAX_PER_PAGE * child_complexity")]
+    async fn provenance(&self) -> Vec<TransferNode> {
+        let skip = self.0.provenance.len().saturating_sub(MAX_PER_PAGE);
+        self.0
+            .provenance
+            .iter()
+            .skip(skip)
+            .cloned()
+            .map(TransferNode)
+            .collect()
+    }
+}
+
+/// Identity token ownership transfer
+pub struct TransferNode(Transfer);
+
+#[Object(name = "TokenTransfer")]
+impl TransferNode {
+    async fn from(&self) -> AddressNode {
+        AddressNode(self.0.from)
+    }
+
+    async fn to(&self) -> AddressNode {
+        AddressNode(self.0.to)
+    }
+
+    async fn price(&self) -> Option<u64> {
+        self.0.price
+    }
+
+    async fn royalty_paid(&self) -> Option<u64> {
+        self.0.royalty_paid
+    }
+
+    async fn timestamp(&self) -> u64 {
+        self.0.timestamp
+    }
+
+    async fn block_height(&self) -> u64 {
+        self.0.block_height
+    }
+}
+
+fn app<'a>(ctx: &Context<'a>) -> &'a AppState {
+    ctx.data_unchecked::<AppState>()
+}
+
+/// Validate `first` against the page size limit
+fn page_size(first: Option<i32>) -> Result<usize> {
+    match first {
+        None => Ok(DEFAULT_PAGE_SIZE),
+        Some(n) if n < 0 => Err("first must not be negative".into()),
+        Some(n) if n as usize > MAX_PER_PAGE => {
+            Err(format!("first cannot exceed {} (requested: {})", MAX_PER_PAGE, n).into())
+        }
+        Some(n) => Ok(n as usize),
+    }
+}
+
+/// A page costs its size times the cost of one item
+fn page_complexity(first: Option<i32>, child_complexity: usize) -> usize {
+    let items = first.map_or(DEFAULT_PAGE_SIZE, |n| {
+        n.clamp(0, MAX_PER_PAGE as i32) as usize
+    });
+    items.max(1).saturating_mul(child_complexity)
+}
+
+fn parse_hash(value: &str) -> Result<[u8; 32]> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| "Expected 32 bytes of hex".into())
+}
+
+async fn load_block(ctx: &Context<'_>, height: u64) -> Result<Option<BlockNode>> {
+    let block = app(ctx)
+        .blockchain
+        .read()
+        .await
+        .get_block_by_height(height)?;
+    Ok(block.map(|block| BlockNode { height, block }))
+}
+
+async fn load_transaction(state: &AppState, hash: &[u8; 32]) -> Result<Option<TransactionNode>> {
+    let Some(location) = state.indexer.get_tx_location(hash)? else {
+        return Ok(None);
+    };
+
+    let block = state
+        .blockchain
+        .read()
+        .await
+        .get_block_by_height(location.block_height)?;
+    Ok(block.and_then(|block| {
+        block
+            .transactions
+            .get(location.tx_index)
+            .map(|tx| TransactionNode {
+                tx: tx.clone(),
+                position: TxPosition {
+                    height: location.block_height,
+                    index: location.tx_index,
+                    timestamp: block.header.timestamp,
+                },
+            })
+    }))
+}
+
+/// Current governance state (empty if the node has none yet)
+fn load_governance(ctx: &Context<'_>) -> Result<GovernanceSnapshot> {
+    let Some(governance) = &app(ctx).governance else {
+        return Err("Governance data is not available on this explorer".into());
+    };
+
+    match governance.load_snapshot() {
+        Ok(snapshot) => Ok(snapshot),
+        Err(opensyria_governance::StorageError::NotFound) => Ok(GovernanceSnapshot {
+            proposals: Vec::new(),
+            votes: Vec::new(),
+            balance_snapshots: Vec::new(),
+            next_proposal_id: 1,
+            config: Default::default(),
+            council: None,
+            motions: Vec::new(),
+        }),
+        Err(e) => Err(e.into()),
+    }
+}
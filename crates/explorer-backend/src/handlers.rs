@@ -1,17 +1,26 @@
 //! HTTP request handlers
 
+use crate::mempool_view::{MempoolSummary, MempoolView, PendingTransactionInfo};
+use crate::object_cache::{
+    block_height_is_final, keys, ObjectCache, BLOCK_TTL, STATS_TTL, TIP_TTL,
+};
 use crate::types::*;
+use crate::websocket::WsMessage;
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::{IntoResponse, Response},
     Json,
 };
+use opensyria_core::contract::suggested_gas_price;
+use opensyria_core::{AssetId, ConfirmationTargets, FeeEstimate, FeeRateStats, OrderId, OrderSide};
+use opensyria_governance::GovernanceStorage;
+use opensyria_identity::IdentityStorage;
 use opensyria_mempool::Mempool;
-use opensyria_storage::{BlockchainIndexer, BlockchainStorage, StateStorage};
+use opensyria_storage::{BlockchainIndexer, BlockchainStorage, StateStorage, SupplyReport};
 use serde::Deserialize;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
 
 /// Shared application state
 #[derive(Clone)]
@@ -20,6 +29,19 @@ pub struct AppState {
     pub state: Arc<RwLock<StateStorage>>,
     pub indexer: Arc<BlockchainIndexer>,
     pub mempool: Arc<RwLock<Mempool>>,
+    /// Governance state, if the data directory has one
+    pub governance: Option<Arc<GovernanceStorage>>,
+    /// Identity token registry, if the data directory has one
+    pub identity: Option<Arc<IdentityStorage>>,
+    /// Pending transactions mirrored from the node event bus
+    pub mempool_view: Arc<RwLock<MempoolView>>,
+    /// `mempoolTx` and `governance` messages for WebSocket clients
+    pub live_events: broadcast::Sender<WsMessage>,
+    /// Confirmations before transactions and rewards are shown as final
+    pub confirmation_targets: ConfirmationTargets,
+    /// Hot objects (stats, recent blocks, top addresses), invalidated as
+    /// blocks are connected and disconnected
+    pub cache: ObjectCache,
 }
 
 /// Pagination query parameters
@@ -38,7 +60,7 @@ fn default_per_page() -> usize {
     20
 }
 
-const MAX_PER_PAGE: usize = 100;
+pub(crate) const MAX_PER_PAGE: usize = 100;
 const MAX_ADDRESS_TX_HISTORY: usize = 100;
 
 impl Pagination {
@@ -71,21 +93,21 @@ pub struct ApiError {
 }
 
 impl ApiError {
-    fn not_found(msg: impl Into<String>) -> Self {
+    pub(crate) fn not_found(msg: impl Into<String>) -> Self {
         Self {
             status: StatusCode::NOT_FOUND,
             message: msg.into(),
         }
     }
 
-    fn internal_error(msg: impl Into<String>) -> Self {
+    pub(crate) fn internal_error(msg: impl Into<String>) -> Self {
         Self {
             status: StatusCode::INTERNAL_SERVER_ERROR,
             message: msg.into(),
         }
     }
 
-    fn bad_request(msg: impl Into<String>) -> Self {
+    pub(crate) fn bad_request(msg: impl Into<String>) -> Self {
         Self {
             status: StatusCode::BAD_REQUEST,
             message: msg.into(),
@@ -102,13 +124,14 @@ impl IntoResponse for ApiError {
 
 /// GET /api/stats - Get blockchain statistics
 pub async fn get_chain_stats(State(state): State<AppState>) -> ApiResult<ChainStats> {
-    // Try cache first
-    if let Ok(Some(cached_stats)) = state.indexer.get_cached_stats() {
-        if let Ok(stats) = serde_json::from_str::<ChainStats>(&cached_stats) {
-            return Ok(Json(stats));
-        }
-    }
+    let stats = state
+        .cache
+        .get_or_load(keys::STATS, STATS_TTL, || load_chain_stats(&state))
+        .await?;
+    Ok(Json(stats))
+}
 
+async fn load_chain_stats(state: &AppState) -> Result<ChainStats, ApiError> {
     let blockchain = state.blockchain.read().await;
 
     let height = blockchain
@@ -132,21 +155,14 @@ pub async fn get_chain_stats(State(state): State<AppState>) -> ApiResult<ChainSt
         .map(|block| block.transactions.len())
         .sum::<usize>() as u64;
 
-    let stats = ChainStats {
+    Ok(ChainStats {
         height,
         total_blocks: height + 1,
         total_transactions,
         difficulty: tip_block.header.difficulty,
         latest_block_hash: hex::encode(tip_hash),
         latest_block_timestamp: tip_block.header.timestamp,
-    };
-
-    // Cache for 10 seconds
-    let _ = state
-        .indexer
-        .cache_stats(&serde_json::to_string(&stats).unwrap_or_default());
-
-    Ok(Json(stats))
+    })
 }
 
 /// GET /api/blocks/:height - Get block by height
@@ -154,14 +170,45 @@ pub async fn get_block_by_height(
     Path(height): Path<u64>,
     State(state): State<AppState>,
 ) -> ApiResult<BlockInfo> {
-    let blockchain = state.blockchain.read().await;
+    let load = || async {
+        let blockchain = state.blockchain.read().await;
+        let block = blockchain
+            .get_block_by_height(height)
+            .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+            .ok_or_else(|| ApiError::not_found(format!("Block at height {} not found", height)))?;
+        Ok(BlockInfo::from_block(&block, height))
+    };
 
-    let block = blockchain
-        .get_block_by_height(height)
-        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
-        .ok_or_else(|| ApiError::not_found(format!("Block at height {} not found", height)))?;
+    // A reorg may still replace recent blocks, unnoticed without an event bus
+    let tip = state
+        .blockchain
+        .read()
+        .await
+        .get_chain_height()
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+    let info = if block_height_is_final(height, tip) {
+        let key = keys::block_by_height(height);
+        state.cache.get_or_load(&key, BLOCK_TTL, load).await?
+    } else {
+        load().await?
+    };
 
-    Ok(Json(BlockInfo::from_block(&block, height)))
+    Ok(Json(info))
+}
+
+/// GET /api/blocks/:height/journal - Debits and credits posted by a block
+pub async fn get_block_journal(
+    Path(height): Path<u64>,
+    State(state): State<AppState>,
+) -> ApiResult<BlockJournalInfo> {
+    let journal = state
+        .state
+        .read()
+        .await
+        .get_block_journal(height)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+        .ok_or_else(|| ApiError::not_found(format!("No journal at height {}", height)))?;
+    Ok(Json(journal.into()))
 }
 
 /// GET /api/blocks/hash/:hash - Get block by hash
@@ -179,20 +226,59 @@ pub async fn get_block_by_hash(
     let mut hash = [0u8; 32];
     hash.copy_from_slice(&hash_bytes);
 
-    let blockchain = state.blockchain.read().await;
-    let block = blockchain
-        .get_block(&hash)
-        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
-        .ok_or_else(|| ApiError::not_found("Block not found"))?;
+    let key = keys::block_by_hash(&hex::encode(hash));
+    let info = state
+        .cache
+        .get_or_load(&key, BLOCK_TTL, || async {
+            let blockchain = state.blockchain.read().await;
+            let block = blockchain
+                .get_block(&hash)
+                .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+                .ok_or_else(|| ApiError::not_found("Block not found"))?;
+
+            // Use index for O(1) height lookup
+            let height = state
+                .indexer
+                .get_block_height(&hash)
+                .map_err(|e| ApiError::internal_error(format!("Index error: {}", e)))?
+                .ok_or_else(|| ApiError::internal_error("Block exists but not indexed"))?;
+
+            Ok(BlockInfo::from_block(&block, height))
+        })
+        .await?;
 
-    // Use index for O(1) height lookup
-    let height = state
-        .indexer
-        .get_block_height(&hash)
-        .map_err(|e| ApiError::internal_error(format!("Index error: {}", e)))?
-        .ok_or_else(|| ApiError::internal_error("Block exists but not indexed"))?;
+    Ok(Json(info))
+}
 
-    Ok(Json(BlockInfo::from_block(&block, height)))
+/// Stale block query parameters
+#[derive(Debug, Deserialize)]
+pub struct StaleBlocksQuery {
+    /// Height the blocks were reverted from; omit for the most recent ones
+    pub height: Option<u64>,
+}
+
+const RECENT_STALE_BLOCKS: usize = 50;
+
+/// GET /api/blocks/stale?height=N - Blocks reverted from the main chain
+pub async fn get_stale_blocks(
+    Query(query): Query<StaleBlocksQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<Vec<BlockInfo>> {
+    let blockchain = state.blockchain.read().await;
+    let stale = match query.height {
+        Some(height) => blockchain
+            .get_stale_blocks(height)
+            .map(|blocks| blocks.into_iter().map(|block| (height, block)).collect()),
+        None => blockchain.get_recent_stale_blocks(RECENT_STALE_BLOCKS),
+    }
+    .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+
+    Ok(Json(
+        stale
+            .iter()
+            .map(|(height, block)| BlockInfo::from_block(block, *height))
+            .collect(),
+    ))
 }
 
 /// GET /api/blocks - Get recent blocks with pagination
@@ -201,7 +287,19 @@ pub async fn get_recent_blocks(
     State(state): State<AppState>,
 ) -> ApiResult<PaginatedResponse<BlockInfo>> {
     pagination.validate()?;
-    
+
+    let key = keys::recent_blocks(pagination.page, pagination.per_page);
+    let page = state
+        .cache
+        .get_or_load(&key, TIP_TTL, || load_recent_blocks(&state, &pagination))
+        .await?;
+    Ok(Json(page))
+}
+
+async fn load_recent_blocks(
+    state: &AppState,
+    pagination: &Pagination,
+) -> Result<PaginatedResponse<BlockInfo>, ApiError> {
     let blockchain = state.blockchain.read().await;
 
     let total = blockchain
@@ -213,7 +311,7 @@ pub async fn get_recent_blocks(
     let end_height = start_height.saturating_sub(per_page as u64);
 
     let mut blocks = Vec::new();
-    for block_height in (end_height..start_height).rev() {
+    for block_height in (end_height + 1..=start_height).rev() {
         if blocks.len() >= per_page {
             break;
         }
@@ -223,12 +321,12 @@ pub async fn get_recent_blocks(
         }
     }
 
-    Ok(Json(PaginatedResponse::new(
+    Ok(PaginatedResponse::new(
         blocks,
         total as usize,
         pagination.page,
         per_page,
-    )))
+    ))
 }
 
 /// GET /api/transactions/:hash - Get transaction by hash
@@ -264,7 +362,13 @@ pub async fn get_transaction(
         .get(location.tx_index)
         .ok_or_else(|| ApiError::not_found("Transaction index out of bounds"))?;
 
-    let info = TransactionInfo::from_transaction(tx).with_block_info(&block, location.block_height);
+    let tip_height = blockchain
+        .get_chain_height()
+        .map_err(|e| ApiError::internal_error(format!("Failed to get height: {}", e)))?;
+
+    let info = TransactionInfo::from_transaction(tx)
+        .with_block_info(&block, location.block_height)
+        .with_status(tx, tip_height, &state.confirmation_targets);
     Ok(Json(info))
 }
 
@@ -298,6 +402,10 @@ pub async fn get_address_info(
         .get_nonce(&public_key)
         .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
 
+    let frozen_by_proposal = state_db
+        .get_freeze(&public_key)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+
     // Use index for O(k) lookup where k = tx count for address
     let tx_hashes = state
         .indexer
@@ -306,11 +414,282 @@ pub async fn get_address_info(
 
     let transaction_count = tx_hashes.len().min(MAX_ADDRESS_TX_HISTORY);
 
+    let assets = asset_balances(&state_db, &public_key)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+
     Ok(Json(AddressInfo {
         address: address_str,
         balance,
         nonce,
         transaction_count,
+        frozen_by_proposal,
+        assets,
+    }))
+}
+
+/// Native asset holdings of `address`, with each asset's symbol
+fn asset_balances(
+    state_db: &StateStorage,
+    address: &opensyria_core::crypto::PublicKey,
+) -> Result<Vec<AssetBalanceInfo>, opensyria_storage::StorageError> {
+    let mut assets = Vec::new();
+    for (id, balance) in state_db.get_asset_balances(address)? {
+        if let Some(info) = state_db.get_asset(&id)? {
+            assets.push(AssetBalanceInfo {
+                asset_id: id.to_hex(),
+                symbol: info.symbol,
+                decimals: info.decimals,
+                balance,
+            });
+        }
+    }
+    Ok(assets)
+}
+
+/// Top addresses query parameters
+#[derive(Debug, Deserialize)]
+pub struct TopAddressesQuery {
+    #[serde(default = "default_top_addresses_limit")]
+    pub limit: usize,
+}
+
+fn default_top_addresses_limit() -> usize {
+    20
+}
+
+pub(crate) const MAX_TOP_ADDRESSES: usize = 100;
+
+/// GET /api/addresses/top?limit=N - Richest addresses by SYL balance
+pub async fn get_top_addresses(
+    Query(query): Query<TopAddressesQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<Vec<TopAddress>> {
+    if query.limit == 0 || query.limit > MAX_TOP_ADDRESSES {
+        return Err(ApiError::bad_request(format!(
+            "limit must be between 1 and {}",
+            MAX_TOP_ADDRESSES
+        )));
+    }
+
+    // The full list is cached once and cut to each request's limit
+    let mut top: Vec<TopAddress> = state
+        .cache
+        .get_or_load(keys::TOP_ADDRESSES, TIP_TTL, || async {
+            let state_db = state.state.read().await;
+            top_addresses(&state_db, MAX_TOP_ADDRESSES)
+                .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))
+        })
+        .await?;
+    top.truncate(query.limit);
+    Ok(Json(top))
+}
+
+/// The `count` largest non-zero balances, largest first
+fn top_addresses(
+    state_db: &StateStorage,
+    count: usize,
+) -> Result<Vec<TopAddress>, opensyria_storage::StorageError> {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    const SCAN_PAGE: usize = 1000;
+
+    let mut heap = BinaryHeap::with_capacity(count + 1);
+    let mut start = None;
+    loop {
+        let (balances, last) = state_db.get_balances_paginated(start.as_ref(), SCAN_PAGE)?;
+        // Pages after the first start at the previous page's last key
+        let skip = usize::from(start.is_some());
+        let fetched = balances.len();
+        for (address, balance) in balances.into_iter().skip(skip) {
+            if balance == 0 {
+                continue;
+            }
+            heap.push(Reverse((balance, address.0)));
+            if heap.len() > count {
+                heap.pop();
+            }
+        }
+        if fetched < SCAN_PAGE || last.is_none() {
+            break;
+        }
+        start = last;
+    }
+
+    Ok(heap
+        .into_sorted_vec()
+        .into_iter()
+        .enumerate()
+        .map(|(i, Reverse((balance, address)))| TopAddress {
+            rank: i + 1,
+            address: hex::encode(address),
+            balance,
+        })
+        .collect())
+}
+
+/// GET /api/assets - All native assets
+pub async fn get_assets(State(state): State<AppState>) -> ApiResult<Vec<AssetDetail>> {
+    let assets = state
+        .state
+        .read()
+        .await
+        .get_assets()
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+    Ok(Json(
+        assets
+            .into_iter()
+            .map(|(id, info)| AssetDetail::new(&id, info))
+            .collect(),
+    ))
+}
+
+/// GET /api/assets/:id - Native asset registry entry
+pub async fn get_asset(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<AssetDetail> {
+    let id = AssetId::from_hex(&id).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let info = state
+        .state
+        .read()
+        .await
+        .get_asset(&id)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+        .ok_or_else(|| ApiError::not_found("Asset not found"))?;
+    Ok(Json(AssetDetail::new(&id, info)))
+}
+
+/// Order book of `asset`, or `None` if it was never traded
+fn market_depth(
+    state_db: &StateStorage,
+    asset: &AssetId,
+    info: &opensyria_core::AssetInfo,
+) -> Result<Option<MarketDepth>, opensyria_storage::StorageError> {
+    let bids = state_db.get_open_orders(asset, OrderSide::Buy)?;
+    let asks = state_db.get_open_orders(asset, OrderSide::Sell)?;
+    let last_price = state_db
+        .get_trades(asset, 1)?
+        .first()
+        .map(|trade| trade.price);
+    if bids.is_empty() && asks.is_empty() && last_price.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(MarketDepth {
+        summary: MarketSummary {
+            asset_id: asset.to_hex(),
+            symbol: info.symbol.clone(),
+            decimals: info.decimals,
+            best_bid: bids.first().map(|order| order.price),
+            best_ask: asks.first().map(|order| order.price),
+            last_price,
+            open_orders: bids.len() + asks.len(),
+        },
+        bids: PriceLevel::from_orders(&bids),
+        asks: PriceLevel::from_orders(&asks),
+    }))
+}
+
+/// GET /api/markets - Assets with open orders or trades
+pub async fn get_markets(State(state): State<AppState>) -> ApiResult<Vec<MarketSummary>> {
+    let state_db = state.state.read().await;
+    let db_error = |e: opensyria_storage::StorageError| {
+        ApiError::internal_error(format!("Database error: {}", e))
+    };
+    let mut markets = Vec::new();
+    for (id, info) in state_db.get_assets().map_err(db_error)? {
+        if let Some(depth) = market_depth(&state_db, &id, &info).map_err(db_error)? {
+            markets.push(depth.summary);
+        }
+    }
+    Ok(Json(markets))
+}
+
+/// GET /api/markets/:id - Order book depth of one asset
+pub async fn get_market(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<MarketDepth> {
+    let id = AssetId::from_hex(&id).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let state_db = state.state.read().await;
+    let db_error = |e: opensyria_storage::StorageError| {
+        ApiError::internal_error(format!("Database error: {}", e))
+    };
+    let info = state_db
+        .get_asset(&id)
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::not_found("Asset not found"))?;
+    let depth = market_depth(&state_db, &id, &info)
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::not_found("Market not found"))?;
+    Ok(Json(depth))
+}
+
+/// Trade history query parameters
+#[derive(Debug, Deserialize)]
+pub struct TradesQuery {
+    #[serde(default = "default_trades_limit")]
+    pub limit: usize,
+}
+
+fn default_trades_limit() -> usize {
+    50
+}
+
+const MAX_TRADES_LIMIT: usize = 500;
+
+/// GET /api/markets/:id/trades?limit=N - Most recent trades, newest first
+pub async fn get_market_trades(
+    Path(id): Path<String>,
+    Query(query): Query<TradesQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<Vec<TradeInfo>> {
+    if query.limit == 0 || query.limit > MAX_TRADES_LIMIT {
+        return Err(ApiError::bad_request(format!(
+            "limit must be between 1 and {}",
+            MAX_TRADES_LIMIT
+        )));
+    }
+    let id = AssetId::from_hex(&id).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let trades = state
+        .state
+        .read()
+        .await
+        .get_trades(&id, query.limit)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+    Ok(Json(trades.into_iter().map(TradeInfo::from).collect()))
+}
+
+/// GET /api/orders/:id - Order status and its settlement receipt
+pub async fn get_order(
+    Path(id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<OrderInfo> {
+    let id = OrderId::from_hex(&id).map_err(|e| ApiError::bad_request(e.to_string()))?;
+    let state_db = state.state.read().await;
+    let db_error = |e: opensyria_storage::StorageError| {
+        ApiError::internal_error(format!("Database error: {}", e))
+    };
+    let order = state_db
+        .get_order(&id)
+        .map_err(db_error)?
+        .ok_or_else(|| ApiError::not_found("Order not found"))?;
+    let receipt = state_db
+        .get_dex_receipt(&id.0)
+        .map_err(db_error)?
+        .unwrap_or_default();
+    Ok(Json(OrderInfo {
+        order_id: id.to_hex(),
+        owner: order.owner.to_hex(),
+        asset_id: order.asset.to_hex(),
+        side: order.side,
+        price: order.price,
+        amount: order.amount,
+        remaining: order.remaining,
+        status: order.status,
+        block_height: order.height,
+        trades: receipt.trades.into_iter().map(TradeInfo::from).collect(),
+        refund: receipt.refund,
     }))
 }
 
@@ -342,7 +721,8 @@ pub async fn search(
                     let tx_hash = hex::encode(tx.hash());
                     if tx_hash.starts_with(&prefix_lower) {
                         let info = TransactionInfo::from_transaction(tx)
-                            .with_block_info(&block, block_height);
+                            .with_block_info(&block, block_height)
+                            .with_status(tx, height, &state.confirmation_targets);
                         return Ok(Json(SearchResult::Transaction { info }));
                     }
                 }
@@ -371,8 +751,10 @@ pub async fn search(
                 let blockchain = state.blockchain.read().await;
                 if let Ok(Some(block)) = blockchain.get_block_by_height(location.block_height) {
                     if let Some(tx) = block.transactions.get(location.tx_index) {
+                        let tip_height = blockchain.get_chain_height().unwrap_or(0);
                         let info = TransactionInfo::from_transaction(tx)
-                            .with_block_info(&block, location.block_height);
+                            .with_block_info(&block, location.block_height)
+                            .with_status(tx, tip_height, &state.confirmation_targets);
                         return Ok(Json(SearchResult::Transaction { info }));
                     }
                 }
@@ -389,6 +771,8 @@ pub async fn search(
                             balance,
                             nonce,
                             transaction_count: 0,
+                            frozen_by_proposal: state_db.get_freeze(&public_key).ok().flatten(),
+                            assets: asset_balances(&state_db, &public_key).unwrap_or_default(),
                         },
                     }));
                 }
@@ -409,6 +793,182 @@ pub async fn search(
     Ok(Json(SearchResult::NotFound))
 }
 
+/// Fee statistics query parameters
+#[derive(Debug, Deserialize)]
+pub struct FeeStatsQuery {
+    #[serde(default = "default_fee_blocks")]
+    pub blocks: u64,
+}
+
+fn default_fee_blocks() -> u64 {
+    10
+}
+
+const MAX_FEE_STATS_BLOCKS: u64 = 1000;
+
+/// GET /api/stats/fees?blocks=N - Fee-rate percentiles for the last N blocks
+pub async fn get_fee_stats(
+    Query(query): Query<FeeStatsQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<FeeStatsResponse> {
+    if query.blocks == 0 || query.blocks > MAX_FEE_STATS_BLOCKS {
+        return Err(ApiError::bad_request(format!(
+            "blocks must be between 1 and {}",
+            MAX_FEE_STATS_BLOCKS
+        )));
+    }
+
+    let blockchain = state.blockchain.read().await;
+    let height = blockchain
+        .get_chain_height()
+        .map_err(|e| ApiError::internal_error(format!("Failed to get height: {}", e)))?;
+
+    let mut blocks = Vec::new();
+    for block_height in (height.saturating_sub(query.blocks - 1)..=height).rev() {
+        // Prefer stats recorded at index time; fall back to the block itself
+        let indexed = state.indexer.get_fee_stats(block_height).ok().flatten();
+        let stats = match indexed {
+            Some(stats) => Some(stats),
+            None => blockchain
+                .get_block_by_height(block_height)
+                .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+                .map(|block| FeeRateStats::from_block(&block, block_height)),
+        };
+        blocks.extend(stats);
+    }
+
+    let estimate = FeeEstimate::from_stats(&blocks);
+    let gas_price = suggested_gas_price(estimate.as_ref());
+    Ok(Json(FeeStatsResponse {
+        blocks,
+        estimate,
+        gas_price,
+    }))
+}
+
+/// Miner statistics query parameters
+#[derive(Debug, Deserialize)]
+pub struct MinerStatsQuery {
+    #[serde(default = "default_miner_blocks")]
+    pub blocks: u64,
+}
+
+fn default_miner_blocks() -> u64 {
+    1000
+}
+
+const MAX_MINER_STATS_BLOCKS: u64 = 10_000;
+
+/// GET /api/stats/miners?blocks=N - Blocks produced per miner over the last N blocks
+pub async fn get_miner_stats(
+    Query(query): Query<MinerStatsQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<MinerStatsResponse> {
+    if query.blocks == 0 || query.blocks > MAX_MINER_STATS_BLOCKS {
+        return Err(ApiError::bad_request(format!(
+            "blocks must be between 1 and {}",
+            MAX_MINER_STATS_BLOCKS
+        )));
+    }
+
+    let blockchain = state.blockchain.read().await;
+    let height = blockchain
+        .get_chain_height()
+        .map_err(|e| ApiError::internal_error(format!("Failed to get height: {}", e)))?;
+    let from_height = height.saturating_sub(query.blocks - 1);
+    let producers = blockchain
+        .get_block_producers(from_height, height)
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+
+    // Heights ascend, so the last tag seen for an address is its latest
+    let mut miners: Vec<MinerStats> = Vec::new();
+    for (block_height, producer) in &producers {
+        let address = producer.address.to_hex();
+        let index = match miners.iter().position(|m| m.address == address) {
+            Some(index) => index,
+            None => {
+                miners.push(MinerStats {
+                    address,
+                    tag: None,
+                    blocks: 0,
+                    share: 0.0,
+                    last_height: 0,
+                });
+                miners.len() - 1
+            }
+        };
+        let miner = &mut miners[index];
+        miner.blocks += 1;
+        miner.last_height = *block_height;
+        if let Some(tag) = producer.tag() {
+            miner.tag = Some(tag.to_string());
+        }
+    }
+    let blocks = producers.len() as u64;
+    for miner in &mut miners {
+        miner.share = miner.blocks as f64 * 100.0 / blocks as f64;
+    }
+    miners.sort_by(|a, b| {
+        b.blocks
+            .cmp(&a.blocks)
+            .then_with(|| a.address.cmp(&b.address))
+    });
+
+    Ok(Json(MinerStatsResponse {
+        from_height,
+        to_height: height,
+        blocks,
+        miners,
+    }))
+}
+
+/// GET /api/stats/supply - Supply, issuance by era and projected emission
+pub async fn get_supply_report(State(state): State<AppState>) -> ApiResult<SupplyReport> {
+    let report = state
+        .cache
+        .get_or_load(keys::SUPPLY, TIP_TTL, || async {
+            let blockchain = state.blockchain.read().await;
+            let state_storage = state.state.read().await;
+            SupplyReport::build(&blockchain, &state_storage)
+                .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))
+        })
+        .await?;
+    Ok(Json(report))
+}
+
+/// Governance analytics query parameters
+#[derive(Debug, Deserialize)]
+pub struct GovernanceAnalyticsQuery {
+    /// Only list proposals created at or after this height
+    #[serde(default)]
+    pub since: u64,
+}
+
+/// GET /api/governance/analytics?since=H - Turnout per proposal and
+/// participation totals
+pub async fn get_governance_analytics(
+    Query(query): Query<GovernanceAnalyticsQuery>,
+    State(state): State<AppState>,
+) -> ApiResult<GovernanceAnalyticsResponse> {
+    let Some(governance) = &state.governance else {
+        return Err(ApiError::not_found(
+            "Governance data is not available on this explorer",
+        ));
+    };
+    let analytics = governance
+        .load_analytics()
+        .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?;
+
+    Ok(Json(GovernanceAnalyticsResponse {
+        summary: analytics.summary(),
+        proposals: analytics
+            .proposals()
+            .filter(|p| p.created_at >= query.since)
+            .cloned()
+            .collect(),
+    }))
+}
+
 /// GET /api/mempool - Get mempool status and pending transactions
 pub async fn get_mempool(State(state): State<AppState>) -> ApiResult<MempoolInfo> {
     let mempool = state.mempool.read().await;
@@ -428,3 +988,26 @@ pub async fn get_mempool(State(state): State<AppState>) -> ApiResult<MempoolInfo
         transactions,
     }))
 }
+
+/// GET /api/mempool/summary - Pending transaction count, size and fee histogram
+pub async fn get_mempool_summary(State(state): State<AppState>) -> ApiResult<MempoolSummary> {
+    Ok(Json(state.mempool_view.read().await.summary()))
+}
+
+/// GET /api/mempool/transactions - Pending transactions, newest first
+pub async fn get_mempool_transactions(
+    Query(pagination): Query<Pagination>,
+    State(state): State<AppState>,
+) -> ApiResult<PaginatedResponse<PendingTransactionInfo>> {
+    pagination.validate()?;
+
+    let view = state.mempool_view.read().await;
+    let items = view.page(pagination.offset(), pagination.per_page);
+
+    Ok(Json(PaginatedResponse::new(
+        items,
+        view.len(),
+        pagination.page,
+        pagination.per_page,
+    )))
+}
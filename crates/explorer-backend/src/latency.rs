@@ -0,0 +1,22 @@
+//! Request latency labels for the explorer
+//! تسميات قياس زمن الطلبات للمستكشف
+//!
+//! The middleware and the `/metrics` handlers live in [`opensyria_http`],
+//! shared with the wallet API.
+
+use axum::{extract::MatchedPath, http::Extensions};
+use opensyria_http::LatencyLayer;
+
+pub use opensyria_http::{endpoint_latency, prometheus_metrics};
+
+/// Service label for explorer endpoints
+pub const SERVICE: &str = "explorer";
+
+/// Time every request under its route template and status
+pub fn latency_layer() -> LatencyLayer {
+    LatencyLayer::new(SERVICE, route_template)
+}
+
+fn route_template(extensions: &Extensions) -> Option<&str> {
+    extensions.get::<MatchedPath>().map(MatchedPath::as_str)
+}
@@ -1,9 +1,16 @@
 //! OpenSyria Explorer Backend
 
 pub mod api;
+pub mod cache;
+pub mod graphql;
 pub mod handlers;
+pub mod latency;
+pub mod mempool_view;
+pub mod object_cache;
 pub mod rate_limit;
 pub mod server;
+pub mod stream;
+pub mod trace;
 pub mod types;
 pub mod websocket;
 
@@ -0,0 +1,396 @@
+//! Pending transaction view fed by the node event bus
+//! عرض المعاملات المعلقة من ناقل أحداث العقدة
+//!
+//! The explorer keeps its own copy of the node's mempool instead of locking
+//! the pool on every request. `TxAdded` inserts a transaction, `TxDropped`
+//! removes it, and `BlockConnected` removes everything the block confirmed.
+//! Each change is also broadcast to WebSocket clients as a `mempoolTx`
+//! message. If the feed lags behind the bus the view is resynchronized
+//! from the mempool itself; transactions that left the pool in the meantime
+//! are reported as removed, whatever the reason was.
+
+use crate::types::TransactionInfo;
+use crate::websocket::WsMessage;
+use opensyria_core::{DropReason, NodeEvent, Transaction};
+use opensyria_mempool::Mempool;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::RwLock;
+
+/// Lower bounds of the fee histogram buckets (base units per KB)
+pub const FEE_HISTOGRAM_BOUNDS: [u64; 9] =
+    [0, 500, 1_000, 2_000, 5_000, 10_000, 20_000, 50_000, 100_000];
+
+/// Capacity of the `mempoolTx` broadcast channel per WebSocket client
+pub const MEMPOOL_EVENT_CAPACITY: usize = 1024;
+
+/// One fee histogram bucket
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBucket {
+    /// Inclusive lower bound (base units per KB)
+    pub min_fee_rate: u64,
+    /// Exclusive upper bound, absent for the last bucket
+    pub max_fee_rate: Option<u64>,
+    pub count: usize,
+    pub bytes: usize,
+}
+
+/// Response for `GET /api/mempool/summary`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MempoolSummary {
+    pub count: usize,
+    pub bytes: usize,
+    pub total_fees: u64,
+    pub fee_histogram: Vec<FeeBucket>,
+}
+
+/// Pending transaction with its mempool metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransactionInfo {
+    #[serde(flatten)]
+    pub transaction: TransactionInfo,
+    /// Encoded size in bytes
+    pub size: usize,
+    /// Fee rate in base units per KB
+    pub fee_rate: u64,
+}
+
+/// Lifecycle status carried by `mempoolTx` messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MempoolTxStatus {
+    Added,
+    Confirmed,
+    Evicted,
+    Expired,
+    Replaced,
+    Removed,
+    Invalid,
+}
+
+impl From<DropReason> for MempoolTxStatus {
+    fn from(reason: DropReason) -> Self {
+        match reason {
+            DropReason::Confirmed => Self::Confirmed,
+            DropReason::Evicted => Self::Evicted,
+            DropReason::Expired => Self::Expired,
+            DropReason::Replaced => Self::Replaced,
+            DropReason::Removed => Self::Removed,
+            DropReason::Invalid => Self::Invalid,
+        }
+    }
+}
+
+struct Entry {
+    tx: Transaction,
+    seq: u64,
+    size: usize,
+    fee_rate: u64,
+}
+
+/// Explorer copy of the node mempool, newest transactions first
+#[derive(Default)]
+pub struct MempoolView {
+    entries: HashMap<[u8; 32], Entry>,
+    /// Arrival sequence number -> hash
+    order: BTreeMap<u64, [u8; 32]>,
+    next_seq: u64,
+    bytes: usize,
+}
+
+impl MempoolView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of pending transactions
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Add a transaction; returns false if it is already present
+    pub fn insert(&mut self, tx: Transaction) -> bool {
+        let hash = tx.hash();
+        if self.entries.contains_key(&hash) {
+            return false;
+        }
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        let size = tx.weight();
+        let fee_rate = tx.fee_rate();
+        self.bytes += size;
+        self.order.insert(seq, hash);
+        self.entries.insert(
+            hash,
+            Entry {
+                tx,
+                seq,
+                size,
+                fee_rate,
+            },
+        );
+        true
+    }
+
+    /// Remove a transaction by hash
+    pub fn remove(&mut self, hash: &[u8; 32]) -> Option<Transaction> {
+        let entry = self.entries.remove(hash)?;
+        self.order.remove(&entry.seq);
+        self.bytes -= entry.size;
+        Some(entry.tx)
+    }
+
+    /// Apply a node event, returning the `mempoolTx` messages it produced
+    pub fn apply(&mut self, event: &NodeEvent) -> Vec<WsMessage> {
+        match event {
+            NodeEvent::TxAdded(tx) => {
+                if self.insert(tx.clone()) {
+                    vec![mempool_tx_message(tx, MempoolTxStatus::Added)]
+                } else {
+                    Vec::new()
+                }
+            }
+            NodeEvent::TxDropped { hash, reason } => self
+                .remove(hash)
+                .map(|tx| mempool_tx_message(&tx, (*reason).into()))
+                .into_iter()
+                .collect(),
+            // The pool reports confirmations as drops too, but only after the
+            // block event; whichever arrives first removes the transaction
+            NodeEvent::BlockConnected { block, .. } => block
+                .transactions
+                .iter()
+                .filter_map(|tx| self.remove(&tx.hash()))
+                .map(|tx| mempool_tx_message(&tx, MempoolTxStatus::Confirmed))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Match the view to the pool's current `pending` transactions after
+    /// missed events, returning the `mempoolTx` messages for the difference
+    pub fn resync(&mut self, pending: Vec<Transaction>) -> Vec<WsMessage> {
+        let current: HashSet<[u8; 32]> = pending.iter().map(Transaction::hash).collect();
+        let gone: Vec<[u8; 32]> = self
+            .entries
+            .keys()
+            .filter(|hash| !current.contains(*hash))
+            .copied()
+            .collect();
+
+        let mut messages: Vec<WsMessage> = gone
+            .iter()
+            .filter_map(|hash| self.remove(hash))
+            .map(|tx| mempool_tx_message(&tx, MempoolTxStatus::Removed))
+            .collect();
+        for tx in pending {
+            let message = mempool_tx_message(&tx, MempoolTxStatus::Added);
+            if self.insert(tx) {
+                messages.push(message);
+            }
+        }
+        messages
+    }
+
+    /// Count, size and fee-rate distribution of the pending transactions
+    pub fn summary(&self) -> MempoolSummary {
+        let mut fee_histogram: Vec<FeeBucket> = FEE_HISTOGRAM_BOUNDS
+            .iter()
+            .enumerate()
+            .map(|(i, &min_fee_rate)| FeeBucket {
+                min_fee_rate,
+                max_fee_rate: FEE_HISTOGRAM_BOUNDS.get(i + 1).copied(),
+                count: 0,
+                bytes: 0,
+            })
+            .collect();
+
+        let mut total_fees = 0u64;
+        for entry in self.entries.values() {
+            let index = FEE_HISTOGRAM_BOUNDS
+                .iter()
+                .rposition(|&bound| entry.fee_rate >= bound)
+                .unwrap_or(0);
+            fee_histogram[index].count += 1;
+            fee_histogram[index].bytes += entry.size;
+            total_fees = total_fees.saturating_add(entry.tx.fee);
+        }
+
+        MempoolSummary {
+            count: self.entries.len(),
+            bytes: self.bytes,
+            total_fees,
+            fee_histogram,
+        }
+    }
+
+    /// Pending transactions, newest first
+    pub fn page(&self, offset: usize, limit: usize) -> Vec<PendingTransactionInfo> {
+        self.order
+            .values()
+            .rev()
+            .skip(offset)
+            .take(limit)
+            .filter_map(|hash| self.entries.get(hash))
+            .map(|entry| PendingTransactionInfo {
+                transaction: TransactionInfo::from_transaction(&entry.tx),
+                size: entry.size,
+                fee_rate: entry.fee_rate,
+            })
+            .collect()
+    }
+}
+
+fn mempool_tx_message(tx: &Transaction, status: MempoolTxStatus) -> WsMessage {
+    WsMessage::MempoolTx {
+        hash: hex::encode(tx.hash()),
+        from: hex::encode(tx.from.0),
+        to: hex::encode(tx.to.0),
+        amount: tx.amount,
+        fee: tx.fee,
+        size: tx.weight(),
+        status,
+    }
+}
+
+/// Keep `view` in sync with the node event bus and fan changes out to
+/// WebSocket clients, resynchronizing from `mempool` if the feed lags
+pub async fn run_mempool_feed(
+    mut events: broadcast::Receiver<NodeEvent>,
+    mempool: Arc<RwLock<Mempool>>,
+    view: Arc<RwLock<MempoolView>>,
+    ws_events: broadcast::Sender<WsMessage>,
+) {
+    loop {
+        let messages = match events.recv().await {
+            Ok(event) => view.write().await.apply(&event),
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Mempool feed lagged, skipped {} node events; resynchronizing",
+                    skipped
+                );
+                let pending = mempool.read().await.get_all_transactions();
+                view.write().await.resync(pending)
+            }
+            Err(RecvError::Closed) => break,
+        };
+        for message in messages {
+            // No WebSocket clients is not an error
+            let _ = ws_events.send(message);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+    use opensyria_core::Block;
+
+    fn signed_tx(fee: u64, nonce: u64) -> Transaction {
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        let mut tx = Transaction::new(
+            sender.public_key(),
+            receiver.public_key(),
+            1_000,
+            fee,
+            nonce,
+        );
+        let sig_hash = tx.signing_hash();
+        tx.signature = sender.sign(&sig_hash);
+        tx
+    }
+
+    #[test]
+    fn test_view_tracks_events() {
+        let mut view = MempoolView::new();
+        let cheap = signed_tx(100, 0);
+        let pricey = signed_tx(100_000, 0);
+
+        let added = view.apply(&NodeEvent::TxAdded(cheap.clone()));
+        assert!(matches!(
+            added.as_slice(),
+            [WsMessage::MempoolTx {
+                status: MempoolTxStatus::Added,
+                ..
+            }]
+        ));
+        assert!(view.apply(&NodeEvent::TxAdded(cheap.clone())).is_empty());
+        view.apply(&NodeEvent::TxAdded(pricey.clone()));
+
+        let summary = view.summary();
+        assert_eq!(summary.count, 2);
+        assert_eq!(summary.bytes, cheap.weight() + pricey.weight());
+        assert_eq!(summary.total_fees, 100_100);
+        assert_eq!(summary.fee_histogram.len(), FEE_HISTOGRAM_BOUNDS.len());
+        assert_eq!(
+            summary.fee_histogram.iter().map(|b| b.count).sum::<usize>(),
+            2
+        );
+        assert_eq!(summary.fee_histogram.last().unwrap().count, 1);
+
+        // Newest first
+        let page = view.page(0, 10);
+        assert_eq!(page[0].transaction.hash, hex::encode(pricey.hash()));
+        assert_eq!(view.page(1, 10).len(), 1);
+
+        let dropped = view.apply(&NodeEvent::TxDropped {
+            hash: pricey.hash(),
+            reason: DropReason::Evicted,
+        });
+        assert!(matches!(
+            dropped.as_slice(),
+            [WsMessage::MempoolTx {
+                status: MempoolTxStatus::Evicted,
+                ..
+            }]
+        ));
+
+        let block = Block::new([0u8; 32], vec![cheap.clone()], 16);
+        let confirmed = view.apply(&NodeEvent::BlockConnected { height: 1, block });
+        assert_eq!(confirmed.len(), 1);
+        assert!(view
+            .apply(&NodeEvent::TxDropped {
+                hash: cheap.hash(),
+                reason: DropReason::Confirmed,
+            })
+            .is_empty());
+        assert!(view.is_empty());
+        assert_eq!(view.summary().bytes, 0);
+    }
+
+    #[test]
+    fn test_resync_reports_the_difference() {
+        let mut view = MempoolView::new();
+        let kept = signed_tx(100, 0);
+        let gone = signed_tx(200, 0);
+        let missed = signed_tx(300, 0);
+        view.insert(kept.clone());
+        view.insert(gone.clone());
+
+        let messages = view.resync(vec![kept.clone(), missed.clone()]);
+        let statuses: Vec<(String, MempoolTxStatus)> = messages
+            .into_iter()
+            .map(|message| match message {
+                WsMessage::MempoolTx { hash, status, .. } => (hash, status),
+                other => panic!("unexpected message: {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (hex::encode(gone.hash()), MempoolTxStatus::Removed),
+                (hex::encode(missed.hash()), MempoolTxStatus::Added),
+            ]
+        );
+        assert_eq!(view.len(), 2);
+        assert_eq!(view.summary().bytes, kept.weight() + missed.weight());
+    }
+}
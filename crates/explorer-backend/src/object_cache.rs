@@ -0,0 +1,496 @@
+//! Server-side cache of hot explorer objects
+//! ذاكرة مؤقتة للكائنات الأكثر طلباً في المستكشف
+//!
+//! Chain statistics, recent blocks and the richest addresses are requested
+//! far more often than they change, and each request reads RocksDB. The
+//! [`ObjectCache`] keeps their JSON in an in-process LRU by default, or in
+//! Redis when built with the `redis` feature and opened with a `redis://`
+//! URL, so several explorer instances can share one cache.
+//!
+//! Entries expire after a TTL and are also dropped as the chain moves:
+//! [`run_cache_invalidation`] follows the node event bus and, for every
+//! block connected or disconnected by a reorg, drops the block at that
+//! height and everything that depends on the tip. Without an event bus the
+//! TTLs bound how stale an entry gets, so blocks a reorg could still replace
+//! are never cached by height (see [`block_height_is_final`]). Backend
+//! errors never fail a request; they are logged and count as misses.
+
+use async_trait::async_trait;
+use lru::LruCache;
+use opensyria_core::{Block, NodeEvent, MAX_REORG_DEPTH};
+use opensyria_metrics::EXPLORER_CACHE_LOOKUPS;
+use serde::{de::DeserializeOwned, Serialize};
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Entries kept by the in-memory cache
+pub const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// Environment variable holding the cache URL of the `explorer` binary
+pub const CACHE_URL_ENV: &str = "OPENSYRIA_EXPLORER_CACHE";
+
+/// Chain statistics
+pub const STATS_TTL: Duration = Duration::from_secs(10);
+
+/// Recent block pages and top addresses, which change with every block
+pub const TIP_TTL: Duration = Duration::from_secs(60);
+
+/// Blocks by height or hash, which only change in a reorg
+pub const BLOCK_TTL: Duration = Duration::from_secs(3600);
+
+/// Prefix of every key in Redis, so the database can be shared
+#[cfg(feature = "redis")]
+const REDIS_NAMESPACE: &str = "opensyria:explorer:";
+
+/// Cache keys
+pub mod keys {
+    pub const STATS: &str = "stats";
+    pub const TOP_ADDRESSES: &str = "addresses:top";
+    pub const SUPPLY: &str = "supply";
+    pub const RECENT_BLOCKS: &str = "blocks:recent:";
+    pub const BLOCK: &str = "block:";
+
+    pub fn recent_blocks(page: usize, per_page: usize) -> String {
+        format!("{}{}:{}", RECENT_BLOCKS, page, per_page)
+    }
+
+    pub fn block_by_height(height: u64) -> String {
+        format!("{}height:{}", BLOCK, height)
+    }
+
+    /// `hash` in lowercase hex
+    pub fn block_by_hash(hash: &str) -> String {
+        format!("{}hash:{}", BLOCK, hash)
+    }
+}
+
+/// Whether the block at `height` is at least [`MAX_REORG_DEPTH`] below
+/// `tip`, so caching it under its height cannot outlive a reorg
+pub fn block_height_is_final(height: u64, tip: u64) -> bool {
+    tip.checked_sub(MAX_REORG_DEPTH)
+        .is_some_and(|deepest| height <= deepest)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheError(pub String);
+
+impl std::fmt::Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cache error: {}", self.0)
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+/// Where cached objects are stored
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError>;
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), CacheError>;
+
+    /// Remove `keys`, ignoring missing ones
+    async fn remove(&self, keys: &[String]) -> Result<(), CacheError>;
+
+    /// Remove every key starting with `prefix`
+    async fn remove_prefix(&self, prefix: &str) -> Result<(), CacheError>;
+}
+
+/// In-process LRU cache
+pub struct MemoryCache {
+    entries: Mutex<LruCache<String, (Instant, Vec<u8>)>>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, LruCache<String, (Instant, Vec<u8>)>> {
+        // The cache holds no invariants a panicking holder could break
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        let mut entries = self.entries();
+        match entries.get(key) {
+            Some((expires, value)) if *expires > Instant::now() => Ok(Some(value.clone())),
+            Some(_) => {
+                entries.pop(key);
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), CacheError> {
+        self.entries()
+            .put(key.to_string(), (Instant::now() + ttl, value));
+        Ok(())
+    }
+
+    async fn remove(&self, keys: &[String]) -> Result<(), CacheError> {
+        let mut entries = self.entries();
+        for key in keys {
+            entries.pop(key);
+        }
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<(), CacheError> {
+        let mut entries = self.entries();
+        let matching: Vec<String> = entries
+            .iter()
+            .filter(|(key, _)| key.starts_with(prefix))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in matching {
+            entries.pop(&key);
+        }
+        Ok(())
+    }
+}
+
+/// Redis or any server speaking its protocol
+#[cfg(feature = "redis")]
+pub struct RedisCache {
+    connection: redis::aio::MultiplexedConnection,
+}
+
+#[cfg(feature = "redis")]
+impl RedisCache {
+    pub async fn connect(url: &str) -> Result<Self, CacheError> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(redis_error)?;
+        Ok(Self { connection })
+    }
+
+    fn key(key: &str) -> String {
+        format!("{}{}", REDIS_NAMESPACE, key)
+    }
+}
+
+#[cfg(feature = "redis")]
+fn redis_error(e: redis::RedisError) -> CacheError {
+    CacheError(e.to_string())
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl CacheBackend for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, CacheError> {
+        let value: Option<Vec<u8>> = redis::cmd("GET")
+            .arg(Self::key(key))
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(value)
+    }
+
+    async fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) -> Result<(), CacheError> {
+        let ttl_ms = ttl.as_millis().max(1) as u64;
+        let _: () = redis::cmd("SET")
+            .arg(Self::key(key))
+            .arg(value)
+            .arg("PX")
+            .arg(ttl_ms)
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn remove(&self, keys: &[String]) -> Result<(), CacheError> {
+        if keys.is_empty() {
+            return Ok(());
+        }
+        let keys: Vec<String> = keys.iter().map(|key| Self::key(key)).collect();
+        let _: () = redis::cmd("DEL")
+            .arg(keys)
+            .query_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)?;
+        Ok(())
+    }
+
+    async fn remove_prefix(&self, prefix: &str) -> Result<(), CacheError> {
+        let mut connection = self.connection.clone();
+        let pattern = format!("{}*", Self::key(prefix));
+        let mut cursor = 0u64;
+        loop {
+            let (next, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(500)
+                .query_async(&mut connection)
+                .await
+                .map_err(redis_error)?;
+            if !keys.is_empty() {
+                let _: () = redis::cmd("DEL")
+                    .arg(keys)
+                    .query_async(&mut connection)
+                    .await
+                    .map_err(redis_error)?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+}
+
+/// JSON cache of explorer objects over a [`CacheBackend`]
+#[derive(Clone)]
+pub struct ObjectCache {
+    backend: Arc<dyn CacheBackend>,
+}
+
+impl Default for ObjectCache {
+    fn default() -> Self {
+        Self::memory(DEFAULT_CACHE_CAPACITY)
+    }
+}
+
+impl ObjectCache {
+    pub fn new(backend: Arc<dyn CacheBackend>) -> Self {
+        Self { backend }
+    }
+
+    pub fn memory(capacity: usize) -> Self {
+        Self::new(Arc::new(MemoryCache::new(capacity)))
+    }
+
+    /// Open a cache from its URL
+    ///
+    /// - `memory` for the in-process LRU
+    /// - `redis://host:port/db` (or `rediss://` for TLS) for Redis, if built
+    ///   with the `redis` feature
+    pub async fn open(url: &str) -> Result<Self, CacheError> {
+        if url == "memory" {
+            return Ok(Self::default());
+        }
+        if url.starts_with("redis://") || url.starts_with("rediss://") {
+            #[cfg(feature = "redis")]
+            return Ok(Self::new(Arc::new(RedisCache::connect(url).await?)));
+            #[cfg(not(feature = "redis"))]
+            return Err(CacheError(
+                "explorer was built without the `redis` feature".to_string(),
+            ));
+        }
+        Err(CacheError(format!("Unsupported cache '{}'", url)))
+    }
+
+    /// Cached object under `key`, if present and readable
+    pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = match self.backend.get(key).await {
+            Ok(value) => value.and_then(|bytes| serde_json::from_slice(&bytes).ok()),
+            Err(e) => {
+                tracing::warn!("Explorer cache read failed: {}", e);
+                None
+            }
+        };
+        let result = if value.is_some() { "hit" } else { "miss" };
+        EXPLORER_CACHE_LOOKUPS
+            .with_label_values(&[object_kind(key), result])
+            .inc();
+        value
+    }
+
+    pub async fn insert<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        let Ok(bytes) = serde_json::to_vec(value) else {
+            return;
+        };
+        if let Err(e) = self.backend.set(key, bytes, ttl).await {
+            tracing::warn!("Explorer cache write failed: {}", e);
+        }
+    }
+
+    /// Cached object under `key`, or the result of `load`, which is cached
+    /// for `ttl` if it succeeds
+    pub async fn get_or_load<T, E, F, Fut>(&self, key: &str, ttl: Duration, load: F) -> Result<T, E>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.get(key).await {
+            return Ok(value);
+        }
+        let value = load().await?;
+        self.insert(key, &value, ttl).await;
+        Ok(value)
+    }
+
+    /// Drop everything that changes when the tip moves
+    pub async fn invalidate_tip(&self) {
+        self.remove(&[
+            keys::STATS.to_string(),
+            keys::TOP_ADDRESSES.to_string(),
+            keys::SUPPLY.to_string(),
+        ])
+        .await;
+        self.remove_prefix(keys::RECENT_BLOCKS).await;
+    }
+
+    /// Drop a block connected or disconnected at `height`, and the tip
+    pub async fn invalidate_block(&self, height: u64, block: &Block) {
+        self.remove(&[
+            keys::block_by_height(height),
+            keys::block_by_hash(&hex::encode(block.hash())),
+        ])
+        .await;
+        self.invalidate_tip().await;
+    }
+
+    /// Drop every block and tip entry, when blocks may have been missed
+    pub async fn invalidate_chain(&self) {
+        self.remove_prefix(keys::BLOCK).await;
+        self.invalidate_tip().await;
+    }
+
+    async fn remove(&self, keys: &[String]) {
+        if let Err(e) = self.backend.remove(keys).await {
+            tracing::warn!("Explorer cache invalidation failed: {}", e);
+        }
+    }
+
+    async fn remove_prefix(&self, prefix: &str) {
+        if let Err(e) = self.backend.remove_prefix(prefix).await {
+            tracing::warn!("Explorer cache invalidation failed: {}", e);
+        }
+    }
+}
+
+/// Metric label of a key: its first segment
+fn object_kind(key: &str) -> &str {
+    key.split(':').next().unwrap_or(key)
+}
+
+/// Invalidate cached objects as the node connects and disconnects blocks
+pub async fn run_cache_invalidation(
+    mut events: broadcast::Receiver<NodeEvent>,
+    cache: ObjectCache,
+) {
+    loop {
+        match events.recv().await {
+            Ok(NodeEvent::BlockConnected { height, block })
+            | Ok(NodeEvent::BlockDisconnected { height, block }) => {
+                cache.invalidate_block(height, &block).await;
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!(
+                    "Cache invalidation lagged, skipped {} node events; clearing blocks",
+                    skipped
+                );
+                cache.invalidate_chain().await;
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::EventBus;
+
+    #[tokio::test]
+    async fn test_memory_cache_expiry_and_eviction() {
+        let cache = MemoryCache::new(2);
+        cache.set("a", b"1".to_vec(), BLOCK_TTL).await.unwrap();
+        cache.set("b", b"2".to_vec(), Duration::ZERO).await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), Some(b"1".to_vec()));
+        assert_eq!(cache.get("b").await.unwrap(), None);
+
+        // Least recently used entries are evicted first
+        cache.set("c", b"3".to_vec(), BLOCK_TTL).await.unwrap();
+        cache.set("d", b"4".to_vec(), BLOCK_TTL).await.unwrap();
+        assert_eq!(cache.get("a").await.unwrap(), None);
+        assert_eq!(cache.get("d").await.unwrap(), Some(b"4".to_vec()));
+
+        cache.remove_prefix("c").await.unwrap();
+        assert_eq!(cache.get("c").await.unwrap(), None);
+        assert_eq!(cache.get("d").await.unwrap(), Some(b"4".to_vec()));
+    }
+
+    #[tokio::test]
+    async fn test_get_or_load_caches_successes() {
+        let cache = ObjectCache::default();
+        let loaded: Result<u64, ()> = cache.get_or_load("n", TIP_TTL, || async { Ok(7) }).await;
+        assert_eq!(loaded, Ok(7));
+        let cached: Result<u64, ()> = cache.get_or_load("n", TIP_TTL, || async { Ok(8) }).await;
+        assert_eq!(cached, Ok(7));
+
+        let failed: Result<u64, &str> = cache
+            .get_or_load("e", TIP_TTL, || async { Err("down") })
+            .await;
+        assert_eq!(failed, Err("down"));
+        assert_eq!(cache.get::<u64>("e").await, None);
+    }
+
+    #[test]
+    fn test_only_final_heights_are_cacheable() {
+        assert!(block_height_is_final(0, MAX_REORG_DEPTH));
+        assert!(!block_height_is_final(1, MAX_REORG_DEPTH));
+        assert!(!block_height_is_final(u64::MAX, u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_invalidate_tip_and_height() {
+        let cache = ObjectCache::default();
+        let block = Block::genesis();
+        let hash_key = keys::block_by_hash(&hex::encode(block.hash()));
+        for key in [
+            keys::STATS.to_string(),
+            keys::TOP_ADDRESSES.to_string(),
+            keys::recent_blocks(1, 20),
+            keys::block_by_height(5),
+            keys::block_by_height(4),
+            hash_key.clone(),
+        ] {
+            cache.insert(&key, &1u8, BLOCK_TTL).await;
+        }
+
+        let bus = EventBus::new(16);
+        let task = tokio::spawn(run_cache_invalidation(bus.subscribe(), cache.clone()));
+        bus.publish(NodeEvent::BlockDisconnected {
+            height: 5,
+            block: block.clone(),
+        });
+        drop(bus);
+        task.await.unwrap();
+
+        for key in [
+            keys::STATS.to_string(),
+            keys::TOP_ADDRESSES.to_string(),
+            keys::recent_blocks(1, 20),
+            keys::block_by_height(5),
+            hash_key,
+        ] {
+            assert_eq!(cache.get::<u8>(&key).await, None, "{}", key);
+        }
+        assert_eq!(cache.get::<u8>(&keys::block_by_height(4)).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_open_urls() {
+        assert!(ObjectCache::open("memory").await.is_ok());
+        assert!(ObjectCache::open("memcached://localhost").await.is_err());
+    }
+}
@@ -2,15 +2,25 @@
 
 use crate::api::create_router;
 use crate::handlers::AppState;
+use crate::mempool_view::{run_mempool_feed, MempoolView, MEMPOOL_EVENT_CAPACITY};
+use crate::object_cache::{run_cache_invalidation, ObjectCache};
 use crate::rate_limit::ExplorerRateLimiter;
+use crate::websocket::run_governance_feed;
 use axum::{http::{header, HeaderValue}, middleware, routing::Router};
+use opensyria_core::{ConfirmationTargets, EventBus, NodeEvent};
+use opensyria_governance::GovernanceStorage;
+use opensyria_identity::IdentityStorage;
 use opensyria_mempool::Mempool;
+use opensyria_metrics::server::{start_metrics_server_with_config, MetricsServerConfig};
+use opensyria_node::NodeHandle;
 use opensyria_storage::{BlockchainIndexer, BlockchainStorage, StateStorage};
 use std::net::SocketAddr;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::RwLock;
 use tower::ServiceBuilder;
+use tower_http::compression::CompressionLayer;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::services::ServeDir;
 use tower_http::set_header::SetResponseHeaderLayer;
@@ -22,9 +32,15 @@ pub struct ExplorerServer {
     state: Arc<RwLock<StateStorage>>,
     indexer: Arc<BlockchainIndexer>,
     mempool: Arc<RwLock<Mempool>>,
+    governance: Option<Arc<GovernanceStorage>>,
+    identity: Option<Arc<IdentityStorage>>,
+    event_bus: Option<EventBus>,
+    confirmation_targets: ConfirmationTargets,
+    cache: ObjectCache,
     addr: SocketAddr,
     static_dir: Option<PathBuf>,
     allowed_origins: Vec<String>,
+    metrics: Option<MetricsServerConfig>,
 }
 
 impl ExplorerServer {
@@ -36,35 +52,115 @@ impl ExplorerServer {
 
         let blockchain = BlockchainStorage::open(blockchain_dir)?;
         let state = StateStorage::open(state_dir)?;
-        let indexer = BlockchainIndexer::open(index_dir)?;
-        let state_arc = Arc::new(RwLock::new(state));
-        let mempool = Mempool::new(
-            opensyria_mempool::MempoolConfig::default(),
-            state_arc.clone(),
+        let indexer = Self::open_indexer(index_dir, &blockchain)?;
+        let confirmation_targets = ConfirmationTargets::from_params(blockchain.chain_params());
+
+        // Governance and identity data are optional
+        let governance_dir = data_dir.join("governance");
+        let governance = if governance_dir.exists() {
+            Some(Arc::new(GovernanceStorage::open(governance_dir)?))
+        } else {
+            None
+        };
+
+        let mut server = Self::assemble(
+            Arc::new(RwLock::new(blockchain)),
+            Arc::new(RwLock::new(state)),
+            indexer,
+            confirmation_targets,
+            addr,
         );
+        server.governance = governance;
+        server.identity = Self::open_identity(&data_dir)?;
+        Ok(server)
+    }
 
-        // Build indexes if needed
-        tracing::info!("Checking blockchain indexes...");
-        let height = blockchain.get_chain_height().unwrap_or(0);
-        let has_genesis_indexed = indexer.get_block_height(&[0u8; 32]).ok().flatten().is_some();
-        
-        if !has_genesis_indexed && height > 0 {
-            tracing::info!("Building indexes for {} blocks...", height + 1);
-            indexer.rebuild_indexes(
-                |h| blockchain.get_block_by_height(h),
-                height,
-            )?;
-        }
+    /// Serve the chain of a node running in this process
+    /// خدمة سلسلة عقدة تعمل داخل العملية نفسها
+    ///
+    /// Blocks and state are read from the node's own storage, so they are
+    /// live; pass the node's mempool and event bus with [`Self::with_mempool`]
+    /// and [`Self::with_event_bus`] for the mempool endpoints and WebSocket
+    /// feeds. Governance pages stay empty: the node opens governance storage
+    /// itself at every block.
+    pub async fn for_node(
+        node: &NodeHandle,
+        addr: SocketAddr,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let state = node
+            .state_storage()
+            .ok_or("a relay-only node keeps no account state to explore")?;
+        let blockchain = node.blockchain();
+        let (indexer, confirmation_targets) = {
+            let chain = blockchain.read().await;
+            let indexer = Self::open_indexer(node.data_dir().join("explorer-index"), &chain)?;
+            (
+                indexer,
+                ConfirmationTargets::from_params(chain.chain_params()),
+            )
+        };
+
+        let mut server = Self::assemble(blockchain, state, indexer, confirmation_targets, addr);
+        server.identity = Self::open_identity(node.data_dir())?;
+        Ok(server)
+    }
 
-        Ok(Self {
-            blockchain: Arc::new(RwLock::new(blockchain)),
-            state: state_arc,
+    fn assemble(
+        blockchain: Arc<RwLock<BlockchainStorage>>,
+        state: Arc<RwLock<StateStorage>>,
+        indexer: BlockchainIndexer,
+        confirmation_targets: ConfirmationTargets,
+        addr: SocketAddr,
+    ) -> Self {
+        let mempool = Mempool::new(opensyria_mempool::MempoolConfig::default(), state.clone());
+
+        Self {
+            blockchain,
+            state,
             indexer: Arc::new(indexer),
             mempool: Arc::new(RwLock::new(mempool)),
+            governance: None,
+            identity: None,
+            event_bus: None,
+            confirmation_targets,
+            cache: ObjectCache::default(),
             addr,
             static_dir: None,
             allowed_origins: vec!["http://localhost:3000".to_string()],
-        })
+            metrics: None,
+        }
+    }
+
+    /// Open the block indexes, building them if needed
+    fn open_indexer(
+        index_dir: PathBuf,
+        blockchain: &BlockchainStorage,
+    ) -> Result<BlockchainIndexer, Box<dyn std::error::Error>> {
+        let indexer = BlockchainIndexer::open(index_dir)?;
+
+        tracing::info!("Checking blockchain indexes...");
+        let height = blockchain.get_chain_height().unwrap_or(0);
+        let has_genesis_indexed = indexer
+            .get_block_height(&[0u8; 32])
+            .ok()
+            .flatten()
+            .is_some();
+
+        if !has_genesis_indexed && height > 0 {
+            tracing::info!("Building indexes for {} blocks...", height + 1);
+            indexer.rebuild_indexes(|h| blockchain.get_block_by_height(h), height)?;
+        }
+        Ok(indexer)
+    }
+
+    fn open_identity(
+        data_dir: &Path,
+    ) -> Result<Option<Arc<IdentityStorage>>, Box<dyn std::error::Error>> {
+        let identity_dir = data_dir.join("identity");
+        if !identity_dir.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Arc::new(IdentityStorage::open(identity_dir)?)))
     }
 
     /// Set static files directory
@@ -79,15 +175,97 @@ impl ExplorerServer {
         self
     }
 
+    /// Serve a running node's mempool instead of an empty local one
+    pub fn with_mempool(mut self, mempool: Arc<RwLock<Mempool>>) -> Self {
+        self.mempool = mempool;
+        self
+    }
+
+    /// Mirror the node mempool from its event bus for the
+    /// `/api/mempool/summary` and `/api/mempool/transactions` endpoints
+    /// and WebSocket `mempoolTx` messages
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    /// Override the confirmations shown before transactions and block
+    /// rewards are final
+    pub fn with_confirmation_targets(mut self, targets: ConfirmationTargets) -> Self {
+        self.confirmation_targets = targets;
+        self
+    }
+
+    /// Cache hot objects in `cache` instead of an in-memory LRU, e.g. a
+    /// Redis cache shared by several explorers
+    pub fn with_cache(mut self, cache: ObjectCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Serve the Prometheus scrape on its own listener, with that listener's
+    /// TLS, credentials and client allowlist; the public API has no `/metrics`
+    pub fn with_metrics(mut self, config: MetricsServerConfig) -> Self {
+        self.metrics = Some(config);
+        self
+    }
+
     /// Start the server
     pub async fn run(self) -> Result<(), Box<dyn std::error::Error>> {
         let rate_limiter = Arc::new(ExplorerRateLimiter::new());
+
+        if let Some(metrics) = self.metrics.clone() {
+            tokio::spawn(async move {
+                if let Err(e) = start_metrics_server_with_config(metrics).await {
+                    tracing::error!("Metrics server failed: {}", e);
+                }
+            });
+        }
+
+        // Subscribe before seeding so no transaction falls between the two
+        let events = self.event_bus.as_ref().map(EventBus::subscribe);
+        let governance_events = self.event_bus.as_ref().map(EventBus::subscribe);
+        let cache_events = self.event_bus.as_ref().map(EventBus::subscribe);
+        let index_events = self.event_bus.as_ref().map(EventBus::subscribe);
+        let mut view = MempoolView::new();
+        for tx in self.mempool.read().await.get_all_transactions() {
+            view.insert(tx);
+        }
+        let mempool_view = Arc::new(RwLock::new(view));
+        let (live_events, _) = broadcast::channel(MEMPOOL_EVENT_CAPACITY);
+        if let Some(events) = events {
+            tokio::spawn(run_mempool_feed(
+                events,
+                self.mempool.clone(),
+                mempool_view.clone(),
+                live_events.clone(),
+            ));
+        }
+        if let Some(events) = governance_events {
+            tokio::spawn(run_governance_feed(events, live_events.clone()));
+        }
+        if let Some(events) = cache_events {
+            tokio::spawn(run_cache_invalidation(events, self.cache.clone()));
+        }
+        if let Some(events) = index_events {
+            tokio::spawn(run_index_feed(
+                events,
+                self.blockchain.clone(),
+                self.indexer.clone(),
+            ));
+        }
         
         let app_state = AppState {
             blockchain: self.blockchain.clone(),
             state: self.state.clone(),
             indexer: self.indexer.clone(),
             mempool: self.mempool.clone(),
+            governance: self.governance.clone(),
+            identity: self.identity.clone(),
+            mempool_view,
+            live_events,
+            confirmation_targets: self.confirmation_targets,
+            cache: self.cache.clone(),
         };
 
         let api_router = create_router(app_state);
@@ -135,6 +313,8 @@ impl ExplorerServer {
                 let limiter = rate_limiter.clone();
                 crate::rate_limit::rate_limit_middleware(limiter, req, next)
             }))
+            // Per-endpoint latency, including requests rejected by the limiter
+            .layer(crate::latency::latency_layer())
             // Security headers
             .layer(security_headers)
             // Enable CORS with specific origins
@@ -143,10 +323,18 @@ impl ExplorerServer {
                     .allow_origin(allow_origin)
                     .allow_methods([
                         axum::http::Method::GET,
+                        axum::http::Method::POST,
                         axum::http::Method::OPTIONS,
                     ])
-                    .allow_headers([axum::http::header::CONTENT_TYPE]),
+                    .allow_headers([
+                        axum::http::header::CONTENT_TYPE,
+                        axum::http::header::IF_NONE_MATCH,
+                        axum::http::header::IF_MODIFIED_SINCE,
+                    ])
+                    .expose_headers([axum::http::header::ETAG, axum::http::header::LAST_MODIFIED]),
             )
+            // Compress responses (gzip/brotli, negotiated via Accept-Encoding)
+            .layer(CompressionLayer::new())
             // Add tracing
             .layer(TraceLayer::new_for_http());
 
@@ -154,6 +342,7 @@ impl ExplorerServer {
         tracing::info!("📊 Rate limit: 60 requests per minute per IP");
         tracing::info!("🔐 CORS origins: {:?}", self.allowed_origins);
         tracing::info!("⚡ Indexes ready for fast lookups");
+        tracing::info!("🗜️  Response compression (gzip, br) and ETag caching enabled");
         tracing::info!("🛡️  Security headers enabled: X-Frame-Options, X-Content-Type-Options");
 
         let listener = tokio::net::TcpListener::bind(self.addr).await?;
@@ -162,3 +351,38 @@ impl ExplorerServer {
         Ok(())
     }
 }
+
+/// Keep the block indexes in step with the blocks the node connects and
+/// disconnects
+async fn run_index_feed(
+    mut events: broadcast::Receiver<NodeEvent>,
+    blockchain: Arc<RwLock<BlockchainStorage>>,
+    indexer: Arc<BlockchainIndexer>,
+) {
+    loop {
+        let result = match events.recv().await {
+            Ok(NodeEvent::BlockConnected { height, block }) => indexer.index_block(&block, height),
+            Ok(NodeEvent::BlockDisconnected { height, block }) => {
+                indexer.remove_block_from_index(&block, height)
+            }
+            Ok(_) => Ok(()),
+            Err(RecvError::Lagged(skipped)) => {
+                // Each missed block was one of the skipped events, and
+                // indexing a block twice is harmless
+                tracing::warn!("Index feed lagged, re-indexing the last {} blocks", skipped);
+                let chain = blockchain.read().await;
+                let tip = chain.get_chain_height().unwrap_or(0);
+                (tip.saturating_sub(skipped)..=tip).try_for_each(|height| {
+                    match chain.get_block_by_height(height)? {
+                        Some(block) => indexer.index_block(&block, height),
+                        None => Ok(()),
+                    }
+                })
+            }
+            Err(RecvError::Closed) => break,
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to update block indexes: {}", e);
+        }
+    }
+}
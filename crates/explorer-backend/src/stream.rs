@@ -0,0 +1,165 @@
+//! Bulk block streaming for archive consumers
+//! بث الكتل بالجملة لمستهلكي الأرشيف
+//!
+//! `GET /api/blocks/stream?from=<height>&to=<height>` returns a contiguous
+//! height range as one chunked binary response instead of one JSON request per
+//! block. Each frame is
+//!
+//! ```text
+//! height: u64 (little endian) | length: u32 (little endian) | bincode(Block)
+//! ```
+//!
+//! Blocks are read in small batches and pushed through a bounded channel, so a
+//! slow client stalls the reader instead of buffering the chain in memory. A
+//! dropped connection can be resumed with `from` set to the last height
+//! received plus one.
+
+use crate::handlers::{ApiError, AppState};
+use axum::{
+    body::{Body, Bytes},
+    extract::{Query, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Response},
+};
+use opensyria_core::Block;
+use serde::Deserialize;
+
+/// Content type of the framed block stream
+pub const BLOCK_STREAM_CONTENT_TYPE: &str = "application/x-opensyria-blocks";
+
+/// Frames buffered ahead of the client before the reader waits
+const STREAM_BUFFER_FRAMES: usize = 32;
+
+/// Blocks read per storage lock acquisition
+const STREAM_READ_BATCH: u64 = 64;
+
+/// Frame header: height (8 bytes) + payload length (4 bytes)
+const FRAME_HEADER_LEN: usize = 12;
+
+/// Range query for the block stream
+#[derive(Debug, Deserialize)]
+pub struct StreamRange {
+    /// First height to send (default: genesis)
+    #[serde(default)]
+    pub from: u64,
+    /// Last height to send, inclusive (default: chain tip)
+    pub to: Option<u64>,
+}
+
+/// Encode one block as a stream frame
+pub fn encode_frame(height: u64, block: &Block) -> Result<Vec<u8>, bincode::error::EncodeError> {
+    let payload = bincode::encode_to_vec(block, bincode::config::standard())?;
+
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&height.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// Decode the frame at the start of `buf`
+///
+/// Returns the height, block and bytes consumed, or `None` if `buf` does not
+/// yet hold a complete frame.
+pub fn decode_frame(
+    buf: &[u8],
+) -> Option<Result<(u64, Block, usize), bincode::error::DecodeError>> {
+    if buf.len() < FRAME_HEADER_LEN {
+        return None;
+    }
+
+    let height = u64::from_le_bytes(buf[..8].try_into().unwrap());
+    let len = u32::from_le_bytes(buf[8..FRAME_HEADER_LEN].try_into().unwrap()) as usize;
+    let end = FRAME_HEADER_LEN + len;
+    if buf.len() < end {
+        return None;
+    }
+
+    Some(
+        bincode::decode_from_slice(&buf[FRAME_HEADER_LEN..end], bincode::config::standard())
+            .map(|(block, _)| (height, block, end)),
+    )
+}
+
+/// GET /api/blocks/stream - Stream a range of blocks in binary frames
+pub async fn stream_blocks(
+    State(state): State<AppState>,
+    Query(range): Query<StreamRange>,
+) -> Result<Response, ApiError> {
+    let tip = state
+        .blockchain
+        .read()
+        .await
+        .get_chain_height()
+        .map_err(|e| ApiError::internal_error(format!("Failed to get height: {}", e)))?;
+
+    let to = range.to.unwrap_or(tip).min(tip);
+    if range.from > to {
+        return Err(ApiError::bad_request(format!(
+            "Invalid range {}..={} (chain tip is {})",
+            range.from, to, tip
+        )));
+    }
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<Bytes>(STREAM_BUFFER_FRAMES);
+    let blockchain = state.blockchain.clone();
+    let from = range.from;
+
+    tokio::spawn(async move {
+        let mut height = from;
+        while height <= to {
+            let batch_end = (height + STREAM_READ_BATCH - 1).min(to);
+
+            // Release the lock before waiting on the client
+            let mut frames = Vec::new();
+            let mut complete = true;
+            {
+                let blockchain = blockchain.read().await;
+                for h in height..=batch_end {
+                    let frame = match blockchain.get_block_by_height(h) {
+                        Ok(Some(block)) => encode_frame(h, &block).map_err(|e| e.to_string()),
+                        Ok(None) => Err("chain shortened by reorganization".to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+
+                    match frame {
+                        Ok(frame) => frames.push(Bytes::from(frame)),
+                        // End the stream early; the client resumes from its last height
+                        Err(e) => {
+                            tracing::warn!("Block stream stopped at height {}: {}", h, e);
+                            complete = false;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            for frame in frames {
+                if tx.send(frame).await.is_err() {
+                    return; // Client went away
+                }
+            }
+
+            if !complete {
+                return;
+            }
+            height = batch_end + 1;
+        }
+    });
+
+    let body = Body::from_stream(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv()
+            .await
+            .map(|frame| (Ok::<_, std::convert::Infallible>(frame), rx))
+    }));
+
+    let mut response = body.into_response();
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(BLOCK_STREAM_CONTENT_TYPE),
+    );
+    headers.insert("x-stream-from", HeaderValue::from(from));
+    headers.insert("x-stream-to", HeaderValue::from(to));
+    Ok(response)
+}
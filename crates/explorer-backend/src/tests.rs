@@ -1,19 +1,50 @@
 #[cfg(test)]
 use opensyria_consensus::ProofOfWork;
-use opensyria_core::Block;
+use opensyria_core::{Block, Transaction, CHAIN_ID_MAINNET};
 use opensyria_storage::{BlockchainIndexer, BlockchainStorage, StateStorage};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use crate::handlers::AppState;
 
 static TEST_COUNTER: AtomicUsize = AtomicUsize::new(0);
 
-fn setup_test_blockchain() -> PathBuf {
+fn fresh_test_dir() -> PathBuf {
     let test_id = TEST_COUNTER.fetch_add(1, Ordering::SeqCst);
     let test_dir =
         std::env::temp_dir().join(format!("explorer_test_{}_{}", std::process::id(), test_id));
-
     let _ = std::fs::remove_dir_all(&test_dir);
+    test_dir
+}
+
+/// Explorer state over the stores in `test_dir`, with no governance or
+/// identity data and default feeds; tests override the fields they need
+fn test_app_state(test_dir: &Path) -> AppState {
+    let state = Arc::new(RwLock::new(
+        StateStorage::open(test_dir.join("state")).unwrap(),
+    ));
+    let mempool =
+        opensyria_mempool::Mempool::new(opensyria_mempool::MempoolConfig::default(), state.clone());
+    AppState {
+        blockchain: Arc::new(RwLock::new(
+            BlockchainStorage::open(test_dir.join("blocks")).unwrap(),
+        )),
+        state,
+        indexer: Arc::new(BlockchainIndexer::open(test_dir.join("index")).unwrap()),
+        mempool: Arc::new(RwLock::new(mempool)),
+        governance: None,
+        identity: None,
+        mempool_view: Default::default(),
+        live_events: tokio::sync::broadcast::channel(16).0,
+        confirmation_targets: Default::default(),
+        cache: Default::default(),
+    }
+}
+
+fn setup_test_blockchain() -> PathBuf {
+    let test_dir = fresh_test_dir();
 
     // Create blockchain with a few blocks
     let blocks_dir = test_dir.join("blocks");
@@ -29,11 +60,15 @@ fn setup_test_blockchain() -> PathBuf {
     let (mined_genesis, _) = pow.mine(genesis);
     blockchain.append_block(&mined_genesis, None).unwrap();
 
-    // Mine a few more blocks
-    for _ in 0..3 {
+    // Mine a few more blocks, each paying its miner and spaced a minute
+    // after its parent
+    let miner = opensyria_core::crypto::PublicKey([7; 32]);
+    for height in 2..5 {
         let tip_hash = blockchain.get_chain_tip().unwrap().unwrap();
         let prev_block = blockchain.get_block(&tip_hash).unwrap().unwrap();
-        let new_block = Block::new(prev_block.hash(), vec![], 16);
+        let coinbase = Transaction::coinbase(CHAIN_ID_MAINNET, miner, height, 0).unwrap();
+        let mut new_block = Block::new(prev_block.hash(), vec![coinbase], 16);
+        new_block.header.timestamp = prev_block.header.timestamp + 60;
         let (mined_block, _) = pow.mine(new_block);
         blockchain.append_block(&mined_block, None).unwrap();
     }
@@ -45,28 +80,11 @@ fn setup_test_blockchain() -> PathBuf {
 #[tokio::test]
 async fn test_explorer_stats() {
     use crate::handlers::get_chain_stats;
-    use crate::handlers::AppState;
     use axum::extract::State;
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
 
     let test_dir = setup_test_blockchain();
 
-    let blockchain = BlockchainStorage::open(test_dir.join("blocks")).unwrap();
-    let state = StateStorage::open(test_dir.join("state")).unwrap();
-    let indexer = BlockchainIndexer::open(test_dir.join("index")).unwrap();
-    let state_arc = Arc::new(RwLock::new(state));
-    let mempool = opensyria_mempool::Mempool::new(
-        opensyria_mempool::MempoolConfig::default(),
-        state_arc.clone(),
-    );
-
-    let app_state = AppState {
-        blockchain: Arc::new(RwLock::new(blockchain)),
-        state: state_arc,
-        indexer: Arc::new(indexer),
-        mempool: Arc::new(RwLock::new(mempool)),
-    };
+    let app_state = test_app_state(&test_dir);
 
     let result = get_chain_stats(State(app_state)).await;
     assert!(result.is_ok());
@@ -81,28 +99,12 @@ async fn test_explorer_stats() {
 
 #[tokio::test]
 async fn test_get_block_by_height() {
-    use crate::handlers::{get_block_by_height, AppState};
+    use crate::handlers::get_block_by_height;
     use axum::extract::{Path, State};
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
 
     let test_dir = setup_test_blockchain();
 
-    let blockchain = BlockchainStorage::open(test_dir.join("blocks")).unwrap();
-    let state = StateStorage::open(test_dir.join("state")).unwrap();
-    let indexer = BlockchainIndexer::open(test_dir.join("index")).unwrap();
-    let state_arc = Arc::new(RwLock::new(state));
-    let mempool = opensyria_mempool::Mempool::new(
-        opensyria_mempool::MempoolConfig::default(),
-        state_arc.clone(),
-    );
-
-    let app_state = AppState {
-        blockchain: Arc::new(RwLock::new(blockchain)),
-        state: state_arc,
-        indexer: Arc::new(indexer),
-        mempool: Arc::new(RwLock::new(mempool)),
-    };
+    let app_state = test_app_state(&test_dir);
 
     // Test genesis block (height 1)
     let result = get_block_by_height(Path(1), State(app_state.clone())).await;
@@ -122,28 +124,12 @@ async fn test_get_block_by_height() {
 
 #[tokio::test]
 async fn test_get_recent_blocks() {
-    use crate::handlers::{get_recent_blocks, AppState, Pagination};
+    use crate::handlers::{get_recent_blocks, Pagination};
     use axum::extract::{Query, State};
-    use std::sync::Arc;
-    use tokio::sync::RwLock;
 
     let test_dir = setup_test_blockchain();
 
-    let blockchain = BlockchainStorage::open(test_dir.join("blocks")).unwrap();
-    let state = StateStorage::open(test_dir.join("state")).unwrap();
-    let indexer = BlockchainIndexer::open(test_dir.join("index")).unwrap();
-    let state_arc = Arc::new(RwLock::new(state));
-    let mempool = opensyria_mempool::Mempool::new(
-        opensyria_mempool::MempoolConfig::default(),
-        state_arc.clone(),
-    );
-
-    let app_state = AppState {
-        blockchain: Arc::new(RwLock::new(blockchain)),
-        state: state_arc,
-        indexer: Arc::new(indexer),
-        mempool: Arc::new(RwLock::new(mempool)),
-    };
+    let app_state = test_app_state(&test_dir);
 
     let pagination = Pagination {
         page: 1,
@@ -154,14 +140,571 @@ async fn test_get_recent_blocks() {
     assert!(result.is_ok());
 
     let paginated = result.unwrap().0;
-    assert_eq!(paginated.total, 5); // Total blocks
+    assert_eq!(paginated.total, 4); // Genesis + 3 blocks
     assert_eq!(paginated.page, 1);
-    assert!(paginated.items.len() <= 5);
+    assert_eq!(paginated.items.len(), 4);
 
     // Most recent block should be first
-    if !paginated.items.is_empty() {
-        assert_eq!(paginated.items[0].height, 4);
+    assert_eq!(paginated.items[0].height, 4);
+    assert_eq!(paginated.items[3].height, 1);
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_stream_blocks_range_and_resume() {
+    use crate::stream::{decode_frame, stream_blocks, StreamRange};
+    use axum::extract::{Query, State};
+
+    let test_dir = setup_test_blockchain();
+
+    let app_state = test_app_state(&test_dir);
+
+    async fn collect(app_state: AppState, from: u64, to: Option<u64>) -> Vec<(u64, Block)> {
+        let response = stream_blocks(State(app_state), Query(StreamRange { from, to }))
+            .await
+            .unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+        while let Some(frame) = decode_frame(&body[offset..]) {
+            let (height, block, consumed) = frame.unwrap();
+            blocks.push((height, block));
+            offset += consumed;
+        }
+        assert_eq!(offset, body.len());
+        blocks
+    }
+
+    // Whole chain up to the tip
+    let all = collect(app_state.clone(), 1, None).await;
+    assert_eq!(
+        all.iter().map(|(h, _)| *h).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4]
+    );
+    let expected = app_state
+        .blockchain
+        .read()
+        .await
+        .get_block_by_height(3)
+        .unwrap()
+        .unwrap();
+    assert_eq!(all[2].1.hash(), expected.hash());
+
+    // Resuming after height 2 picks up exactly where the client stopped
+    let resumed = collect(app_state.clone(), 3, Some(100)).await;
+    assert_eq!(
+        resumed.iter().map(|(h, _)| *h).collect::<Vec<_>>(),
+        vec![3, 4]
+    );
+
+    // Starting past the tip is rejected
+    let result = stream_blocks(State(app_state), Query(StreamRange { from: 10, to: None })).await;
+    assert!(result.is_err());
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_graphql_blocks_pagination_and_limits() {
+    use crate::graphql::build_schema;
+
+    let test_dir = setup_test_blockchain();
+    let app_state = test_app_state(&test_dir);
+    let schema = build_schema(app_state);
+
+    // Newest first, with nested parent resolution
+    let response = schema
+        .execute(
+            "{ blocks(first: 2) { edges { cursor node { height parent { height } } } \
+             pageInfo { hasNextPage } } }",
+        )
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    let edges = data["blocks"]["edges"].as_array().unwrap();
+    let heights: Vec<_> = edges
+        .iter()
+        .map(|e| e["node"]["height"].as_u64().unwrap())
+        .collect();
+    assert_eq!(heights, vec![4, 3]);
+    assert_eq!(edges[0]["node"]["parent"]["height"], 3);
+    assert_eq!(data["blocks"]["pageInfo"]["hasNextPage"], true);
+
+    // The next page continues after the last cursor
+    let cursor = edges[1]["cursor"].as_str().unwrap();
+    let response = schema
+        .execute(format!(
+            "{{ blocks(first: 10, after: \"{}\") {{ edges {{ node {{ height }} }} \
+             pageInfo {{ hasNextPage }} }} }}",
+            cursor
+        ))
+        .await;
+    let data = response.data.into_json().unwrap();
+    let heights: Vec<_> = data["blocks"]["edges"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["node"]["height"].as_u64().unwrap())
+        .collect();
+    assert_eq!(heights, vec![2, 1]);
+    assert_eq!(data["blocks"]["pageInfo"]["hasNextPage"], false);
+
+    // A cursor far past the tip starts at the tip instead of walking down to it
+    let response = schema
+        .execute(format!(
+            "{{ blocks(first: 2, after: \"{}\") {{ edges {{ node {{ height }} }} }} }}",
+            u64::MAX
+        ))
+        .await;
+    assert!(response.errors.is_empty(), "{:?}", response.errors);
+    let data = response.data.into_json().unwrap();
+    assert_eq!(data["blocks"]["edges"][0]["node"]["height"], 4);
+
+    // Oversized pages, runaway nesting and fan-out are refused
+    let too_large = schema
+        .execute("{ blocks(first: 1000) { edges { cursor } } }")
+        .await;
+    assert!(!too_large.errors.is_empty());
+
+    let too_deep = schema
+        .execute(
+            "{ tip { parent { parent { parent { parent { parent { parent { parent { parent \
+             { parent { parent { height } } } } } } } } } } } }",
+        )
+        .await;
+    assert!(!too_deep.errors.is_empty());
+
+    let too_complex = schema
+        .execute(
+            "{ blocks(first: 100) { edges { node { transactions { from { \
+             transactions(first: 100) { edges { node { hash } } } } } } } } }",
+        )
+        .await;
+    assert!(!too_complex.errors.is_empty());
+
+    // Governance is reported as unavailable when the explorer has no governance data
+    let response = schema.execute("{ proposals { edges { cursor } } }").await;
+    assert!(!response.errors.is_empty());
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_mempool_endpoints_follow_event_bus() {
+    use crate::handlers::{get_mempool_summary, get_mempool_transactions, Pagination};
+    use crate::mempool_view::{run_mempool_feed, MempoolTxStatus};
+    use crate::websocket::WsMessage;
+    use axum::extract::{Query, State};
+    use opensyria_core::crypto::KeyPair;
+    use opensyria_core::{DropReason, EventBus, NodeEvent};
+
+    let test_dir = setup_test_blockchain();
+    let app_state = test_app_state(&test_dir);
+
+    let bus = EventBus::default();
+    let mut ws_rx = app_state.live_events.subscribe();
+    tokio::spawn(run_mempool_feed(
+        bus.subscribe(),
+        app_state.mempool.clone(),
+        app_state.mempool_view.clone(),
+        app_state.live_events.clone(),
+    ));
+
+    let sender = KeyPair::generate();
+    let txs: Vec<Transaction> = (0..3)
+        .map(|nonce| {
+            Transaction::new(
+                sender.public_key(),
+                KeyPair::generate().public_key(),
+                10,
+                100,
+                nonce,
+            )
+        })
+        .collect();
+    for tx in &txs {
+        bus.publish(NodeEvent::TxAdded(tx.clone()));
     }
+    bus.publish(NodeEvent::TxDropped {
+        hash: txs[0].hash(),
+        reason: DropReason::Expired,
+    });
+
+    let mut statuses = Vec::new();
+    for _ in 0..4 {
+        match ws_rx.recv().await.unwrap() {
+            WsMessage::MempoolTx { status, .. } => statuses.push(status),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+    assert_eq!(statuses[3], MempoolTxStatus::Expired);
+    assert!(statuses[..3].iter().all(|s| *s == MempoolTxStatus::Added));
+
+    let summary = get_mempool_summary(State(app_state.clone()))
+        .await
+        .unwrap()
+        .0;
+    assert_eq!(summary.count, 2);
+    assert_eq!(summary.bytes, txs[1].weight() + txs[2].weight());
+
+    let page = get_mempool_transactions(
+        Query(Pagination {
+            page: 1,
+            per_page: 1,
+        }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(page.total, 2);
+    assert_eq!(page.total_pages, 2);
+    assert_eq!(page.items[0].transaction.hash, hex::encode(txs[2].hash()));
+
+    let too_large = get_mempool_transactions(
+        Query(Pagination {
+            page: 1,
+            per_page: 1000,
+        }),
+        State(app_state),
+    )
+    .await;
+    assert!(too_large.is_err());
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_governance_events_reach_websocket_clients() {
+    use crate::websocket::{run_governance_feed, WsMessage};
+    use opensyria_core::{EventBus, GovernanceEvent, NodeEvent};
+    use tokio::sync::broadcast;
+
+    let bus = EventBus::default();
+    let (live_events, _) = broadcast::channel(16);
+    let mut ws_rx = live_events.subscribe();
+    tokio::spawn(run_governance_feed(bus.subscribe(), live_events));
+
+    bus.publish(NodeEvent::TxDropped {
+        hash: [0u8; 32],
+        reason: opensyria_core::DropReason::Expired,
+    });
+    bus.publish(NodeEvent::Governance(GovernanceEvent::Executable {
+        proposal_id: 7,
+        height: 120,
+    }));
+
+    let message = ws_rx.recv().await.unwrap();
+    assert!(matches!(
+        &message,
+        WsMessage::Governance {
+            event: GovernanceEvent::Executable { proposal_id: 7, .. }
+        }
+    ));
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(json["type"], "governance");
+    assert_eq!(json["event"]["kind"], "executable");
+    assert_eq!(json["event"]["height"], 120);
+}
+
+#[tokio::test]
+async fn test_top_addresses_cached_until_next_block() {
+    use crate::handlers::{get_top_addresses, TopAddressesQuery};
+    use axum::extract::{Query, State};
+    use opensyria_core::crypto::PublicKey;
+    use opensyria_core::{EventBus, NodeEvent};
+
+    // Balances only, no blocks needed
+    let test_dir = fresh_test_dir();
+    let app_state = test_app_state(&test_dir);
+    {
+        let state = app_state.state.read().await;
+        for (i, balance) in [(1u8, 500u64), (2, 0), (3, 900), (4, 100)] {
+            state.set_balance(&PublicKey([i; 32]), balance).unwrap();
+        }
+    }
+    let top =
+        |limit| get_top_addresses(Query(TopAddressesQuery { limit }), State(app_state.clone()));
+
+    let first = top(2).await.unwrap().0;
+    assert_eq!(first.len(), 2);
+    assert_eq!((first[0].rank, first[0].balance), (1, 900));
+    assert_eq!(first[0].address, hex::encode([3u8; 32]));
+    assert_eq!((first[1].rank, first[1].balance), (2, 500));
+    // Zero balances are left out
+    assert_eq!(top(10).await.unwrap().0.len(), 3);
+    assert!(top(0).await.is_err());
+    assert!(top(101).await.is_err());
+
+    // Served from the cache until a block invalidates it
+    app_state
+        .state
+        .read()
+        .await
+        .set_balance(&PublicKey([2; 32]), 1_000)
+        .unwrap();
+    assert_eq!(top(1).await.unwrap().0[0].balance, 900);
+
+    let bus = EventBus::new(16);
+    let task = tokio::spawn(crate::object_cache::run_cache_invalidation(
+        bus.subscribe(),
+        app_state.cache.clone(),
+    ));
+    bus.publish(NodeEvent::BlockConnected {
+        height: 5,
+        block: Block::genesis(),
+    });
+    drop(bus);
+    task.await.unwrap();
+    assert_eq!(top(1).await.unwrap().0[0].balance, 1_000);
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_block_journal_endpoint() {
+    use crate::handlers::get_block_journal;
+    use axum::extract::{Path, State};
+    use opensyria_core::crypto::PublicKey;
+    use opensyria_storage::{JournalReason, JournalSide};
+
+    // State only, no blocks needed
+    let test_dir = fresh_test_dir();
+    let app_state = test_app_state(&test_dir);
+    {
+        let state = app_state.state.read().await;
+        let (alice, bob) = (PublicKey([1; 32]), PublicKey([2; 32]));
+        state.set_balance(&alice, 1_000).unwrap();
+        state
+            .apply_block_at_height(1, &[Transaction::new(alice, bob, 600, 10, 0)])
+            .unwrap();
+    }
+
+    let journal = get_block_journal(Path(1), State(app_state.clone()))
+        .await
+        .unwrap()
+        .0;
+    assert!(journal.balanced);
+    assert_eq!((journal.total_debits, journal.total_credits), (610, 610));
+    let fee_credit = journal
+        .lines
+        .iter()
+        .find(|line| line.reason == JournalReason::Fee && line.side == JournalSide::Credit)
+        .unwrap();
+    assert_eq!(
+        (fee_credit.account.as_str(), fee_credit.amount),
+        ("fees", 10)
+    );
+    assert!(get_block_journal(Path(2), State(app_state)).await.is_err());
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_miner_stats_endpoint() {
+    use crate::handlers::{get_miner_stats, MinerStatsQuery};
+    use axum::extract::{Query, State};
+    use opensyria_core::crypto::PublicKey;
+    use opensyria_core::CoinbaseSplit;
+
+    let test_dir = fresh_test_dir();
+
+    // Blocks are stored and indexed directly, without chain validation
+    let blockchain = BlockchainStorage::open(test_dir.join("blocks")).unwrap();
+    let genesis = Block::genesis();
+    blockchain.append_block(&genesis, None).unwrap();
+    let (pool, solo) = (PublicKey([1; 32]), PublicKey([2; 32]));
+    let mut previous = genesis.hash();
+    for (height, (miner, tag)) in [(pool, "/pool/"), (solo, ""), (pool, "/pool/")]
+        .into_iter()
+        .enumerate()
+    {
+        let coinbase = CoinbaseSplit::single(miner)
+            .with_extra_data(tag.as_bytes().to_vec())
+            .unwrap()
+            .coinbase_transactions(CHAIN_ID_MAINNET, height as u64 + 2, 0)
+            .unwrap();
+        let block = Block::new(previous, coinbase, 16);
+        blockchain.put_block(&block).unwrap();
+        previous = block.hash();
+    }
+    blockchain.set_chain_tip(&previous).unwrap();
+    blockchain.rebuild_indexes().unwrap();
+    drop(blockchain);
+    let app_state = test_app_state(&test_dir);
+
+    let stats = get_miner_stats(
+        Query(MinerStatsQuery { blocks: 1000 }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(
+        (stats.from_height, stats.to_height, stats.blocks),
+        (0, 4, 3)
+    );
+    assert_eq!(stats.miners.len(), 2);
+    assert_eq!(stats.miners[0].address, pool.to_hex());
+    assert_eq!(stats.miners[0].tag.as_deref(), Some("/pool/"));
+    assert_eq!(
+        (stats.miners[0].blocks, stats.miners[0].last_height),
+        (2, 4)
+    );
+    assert_eq!(stats.miners[1].tag, None);
+
+    // Only the most recent block
+    let stats = get_miner_stats(
+        Query(MinerStatsQuery { blocks: 1 }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(stats.miners.len(), 1);
+    assert_eq!(stats.miners[0].share, 100.0);
+    assert!(
+        get_miner_stats(Query(MinerStatsQuery { blocks: 0 }), State(app_state))
+            .await
+            .is_err()
+    );
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_stale_blocks_endpoint() {
+    use crate::handlers::{get_stale_blocks, StaleBlocksQuery};
+    use axum::extract::{Query, State};
+
+    let test_dir = fresh_test_dir();
+
+    let blockchain = BlockchainStorage::open(test_dir.join("blocks")).unwrap();
+    let genesis = Block::genesis();
+    blockchain.append_block(&genesis, None).unwrap();
+    let block2 = Block::new(genesis.hash(), vec![], 16);
+    let block3 = Block::new(block2.hash(), vec![], 16);
+    blockchain.put_block(&block2).unwrap();
+    blockchain.put_block(&block3).unwrap();
+    blockchain.set_chain_tip(&block3.hash()).unwrap();
+    blockchain.rebuild_indexes().unwrap();
+    blockchain.revert_to_height(2).unwrap();
+    drop(blockchain);
+    let app_state = test_app_state(&test_dir);
+
+    let stale = get_stale_blocks(
+        Query(StaleBlocksQuery { height: Some(3) }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].hash, hex::encode(block3.hash()));
+    assert_eq!(stale[0].height, 3);
+
+    let recent = get_stale_blocks(
+        Query(StaleBlocksQuery { height: None }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(recent.len(), 1);
+    let none = get_stale_blocks(
+        Query(StaleBlocksQuery { height: Some(2) }),
+        State(app_state),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert!(none.is_empty());
+
+    std::fs::remove_dir_all(&test_dir).ok();
+}
+
+#[tokio::test]
+async fn test_governance_analytics_endpoint() {
+    use crate::handlers::{get_governance_analytics, GovernanceAnalyticsQuery};
+    use axum::extract::{Query, State};
+    use opensyria_core::crypto::PublicKey;
+    use opensyria_governance::{
+        GovernanceSnapshot, GovernanceStorage, Proposal, ProposalType, Vote, VoteRecord,
+    };
+
+    let test_dir = fresh_test_dir();
+
+    let proposal = |id, created_at| {
+        Proposal::new(
+            id,
+            PublicKey([9; 32]),
+            ProposalType::TextProposal {
+                description: "Test".to_string(),
+            },
+            format!("Proposal {}", id),
+            "Description".to_string(),
+            created_at,
+            1000,
+            100,
+            1_000_000,
+        )
+    };
+    let mut snapshot = GovernanceSnapshot {
+        proposals: vec![proposal(1, 10), proposal(2, 500)],
+        votes: vec![],
+        balance_snapshots: vec![],
+        next_proposal_id: 3,
+        config: Default::default(),
+        council: None,
+        motions: vec![],
+    };
+    let voter = PublicKey([1; 32]);
+    snapshot.proposals[1].votes_yes = 250_000;
+    snapshot.votes.push((
+        2,
+        voter,
+        VoteRecord {
+            voter,
+            vote: Vote::Yes,
+            voting_power: 250_000,
+            snapshot_balance: 250_000,
+            timestamp: 500,
+            delegated_from: None,
+        },
+    ));
+    let governance = GovernanceStorage::open(test_dir.join("governance")).unwrap();
+    governance.save_snapshot(&snapshot).unwrap();
+    let app_state = AppState {
+        governance: Some(Arc::new(governance)),
+        ..test_app_state(&test_dir)
+    };
+
+    let analytics = get_governance_analytics(
+        Query(GovernanceAnalyticsQuery { since: 0 }),
+        State(app_state.clone()),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(analytics.proposals.len(), 2);
+    assert_eq!(analytics.summary.unique_voters, 1);
+    assert_eq!(analytics.proposals[1].turnout_bps, 2_500);
+
+    // Proposals created before `since` are left out, the totals are not
+    let analytics = get_governance_analytics(
+        Query(GovernanceAnalyticsQuery { since: 100 }),
+        State(app_state),
+    )
+    .await
+    .unwrap()
+    .0;
+    assert_eq!(analytics.proposals.len(), 1);
+    assert_eq!(analytics.proposals[0].proposal_id, 2);
+    assert_eq!(analytics.summary.proposals, 2);
 
     std::fs::remove_dir_all(&test_dir).ok();
 }
@@ -0,0 +1,508 @@
+//! Funds flow tracing for compliance tooling
+//! تتبع تدفق الأموال لأدوات الامتثال
+//!
+//! `GET /api/trace/address/:address` and `GET /api/trace/tx/:hash` walk the
+//! address index breadth-first and return the graph of addresses (nodes) and
+//! confirmed transfers (edges) reachable within `depth` hops.
+//!
+//! `direction=forward` (default) follows where funds went: from each address,
+//! transfers it sent at or after the height the traced funds arrived.
+//! `direction=backward` follows where funds came from: transfers received at
+//! or before the height they left. Each address is expanded once, through at
+//! most `fanout` transfers (earliest first going forward, latest first going
+//! backward); nodes and the graph are flagged `truncated` when a limit cut
+//! the walk short. Only an address's most recent indexed transactions are
+//! inspected. The walk reads RocksDB synchronously, so it runs on a blocking
+//! thread with a bounded cache of the blocks it has read.
+
+use crate::handlers::{ApiError, AppState};
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use lru::LruCache;
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::{Block, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroUsize;
+
+pub const DEFAULT_TRACE_DEPTH: u32 = 3;
+pub const MAX_TRACE_DEPTH: u32 = 6;
+pub const DEFAULT_TRACE_FANOUT: usize = 20;
+pub const MAX_TRACE_FANOUT: usize = 100;
+/// Edges in one response; the walk stops once reached
+pub const MAX_TRACE_EDGES: usize = 500;
+/// Indexed transactions inspected per address, newest first
+const MAX_ADDRESS_SCAN: usize = 1000;
+/// Blocks kept in memory during one walk
+const BLOCK_CACHE_SIZE: usize = 256;
+
+/// Which way to follow funds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceDirection {
+    /// Where funds went
+    #[default]
+    Forward,
+    /// Where funds came from
+    Backward,
+}
+
+/// Trace query parameters
+#[derive(Debug, Deserialize)]
+pub struct TraceQuery {
+    #[serde(default)]
+    pub direction: TraceDirection,
+    pub depth: Option<u32>,
+    pub fanout: Option<usize>,
+}
+
+/// Bounds on one walk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceLimits {
+    pub depth: u32,
+    pub fanout: usize,
+    pub max_edges: usize,
+}
+
+impl TraceQuery {
+    fn limits(&self) -> Result<TraceLimits, ApiError> {
+        let depth = self.depth.unwrap_or(DEFAULT_TRACE_DEPTH);
+        if depth == 0 || depth > MAX_TRACE_DEPTH {
+            return Err(ApiError::bad_request(format!(
+                "depth must be between 1 and {}",
+                MAX_TRACE_DEPTH
+            )));
+        }
+        let fanout = self.fanout.unwrap_or(DEFAULT_TRACE_FANOUT);
+        if fanout == 0 || fanout > MAX_TRACE_FANOUT {
+            return Err(ApiError::bad_request(format!(
+                "fanout must be between 1 and {}",
+                MAX_TRACE_FANOUT
+            )));
+        }
+        Ok(TraceLimits {
+            depth,
+            fanout,
+            max_edges: MAX_TRACE_EDGES,
+        })
+    }
+}
+
+/// Address reached by the trace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceNode {
+    pub address: String,
+    /// Hops from the starting point
+    pub depth: u32,
+    /// Block reward source; never expanded
+    pub coinbase: bool,
+    /// Some of this address's transfers were not followed
+    pub truncated: bool,
+}
+
+/// Confirmed transfer between two traced addresses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceEdge {
+    pub tx_hash: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub height: u64,
+}
+
+/// Funds flow graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceGraph {
+    pub direction: TraceDirection,
+    /// Address or transaction hash the trace started from
+    pub root: String,
+    pub nodes: Vec<TraceNode>,
+    pub edges: Vec<TraceEdge>,
+    /// The edge limit stopped the walk before `depth` was exhausted
+    pub truncated: bool,
+}
+
+/// Where a trace starts
+pub enum TraceStart {
+    Address(PublicKey),
+    /// A confirmed transaction and its height
+    Transaction(Transaction, u64),
+}
+
+/// Confirmed transactions touching an address, with their heights, and
+/// whether the list was cut short
+pub type AddressTransactions = (Vec<(Transaction, u64)>, bool);
+
+/// Breadth-first walk of the funds flow graph
+///
+/// `fetch` returns the confirmed transactions touching an address, in any
+/// order.
+pub fn trace_funds<E>(
+    start: TraceStart,
+    direction: TraceDirection,
+    limits: TraceLimits,
+    mut fetch: impl FnMut(&PublicKey) -> Result<AddressTransactions, E>,
+) -> Result<TraceGraph, E> {
+    let mut walk = Walk {
+        direction,
+        nodes: Vec::new(),
+        index: HashMap::new(),
+        edges: Vec::new(),
+        queue: VecDeque::new(),
+    };
+
+    let root = match start {
+        TraceStart::Address(address) => {
+            let bound = match direction {
+                TraceDirection::Forward => 0,
+                TraceDirection::Backward => u64::MAX,
+            };
+            walk.visit(address, 0, bound);
+            address.to_hex()
+        }
+        TraceStart::Transaction(tx, height) => {
+            let (origin, _) = walk.ends(&tx);
+            walk.visit(origin, 0, height);
+            // The starting transaction is the only hop taken from `origin`
+            walk.queue.clear();
+            walk.follow(&tx, height, 1);
+            hex::encode(tx.hash())
+        }
+    };
+
+    let mut truncated = false;
+    while let Some((address, depth, bound)) = walk.queue.pop_front() {
+        if depth >= limits.depth {
+            continue;
+        }
+        let (transactions, partial) = fetch(&address)?;
+        let mut flows: Vec<(Transaction, u64)> = transactions
+            .into_iter()
+            .filter(|(tx, height)| match direction {
+                TraceDirection::Forward => tx.from == address && *height >= bound,
+                TraceDirection::Backward => tx.to == address && *height <= bound,
+            })
+            .collect();
+        match direction {
+            TraceDirection::Forward => flows.sort_by_key(|(_, height)| *height),
+            TraceDirection::Backward => flows.sort_by_key(|(_, height)| u64::MAX - height),
+        }
+
+        let node = walk.index[&address];
+        walk.nodes[node].truncated = partial || flows.len() > limits.fanout;
+        for (tx, height) in flows.into_iter().take(limits.fanout) {
+            if walk.edges.len() >= limits.max_edges {
+                truncated = true;
+                break;
+            }
+            walk.follow(&tx, height, depth + 1);
+        }
+        if truncated {
+            break;
+        }
+    }
+
+    Ok(TraceGraph {
+        direction,
+        root,
+        nodes: walk.nodes,
+        edges: walk.edges,
+        truncated,
+    })
+}
+
+struct Walk {
+    direction: TraceDirection,
+    nodes: Vec<TraceNode>,
+    index: HashMap<PublicKey, usize>,
+    edges: Vec<TraceEdge>,
+    /// Addresses to expand, with their depth and height bound
+    queue: VecDeque<(PublicKey, u32, u64)>,
+}
+
+impl Walk {
+    /// Address the trace arrives from and the one it moves to along `tx`
+    fn ends(&self, tx: &Transaction) -> (PublicKey, PublicKey) {
+        match self.direction {
+            TraceDirection::Forward => (tx.from, tx.to),
+            TraceDirection::Backward => (tx.to, tx.from),
+        }
+    }
+
+    fn visit(&mut self, address: PublicKey, depth: u32, bound: u64) {
+        if self.index.contains_key(&address) {
+            return;
+        }
+        let coinbase = address.is_zero();
+        self.index.insert(address, self.nodes.len());
+        self.nodes.push(TraceNode {
+            address: address.to_hex(),
+            depth,
+            coinbase,
+            truncated: false,
+        });
+        if !coinbase {
+            self.queue.push_back((address, depth, bound));
+        }
+    }
+
+    fn follow(&mut self, tx: &Transaction, height: u64, depth: u32) {
+        self.edges.push(TraceEdge {
+            tx_hash: hex::encode(tx.hash()),
+            from: tx.from.to_hex(),
+            to: tx.to.to_hex(),
+            amount: tx.amount,
+            fee: tx.fee,
+            height,
+        });
+        let (_, next) = self.ends(tx);
+        self.visit(next, depth, height);
+    }
+}
+
+/// Read an address's latest indexed transactions, caching blocks across
+/// calls
+fn address_transactions(
+    state: &AppState,
+    blockchain: &opensyria_storage::BlockchainStorage,
+    blocks: &mut LruCache<u64, Block>,
+    address: &PublicKey,
+) -> Result<AddressTransactions, ApiError> {
+    let hashes = state
+        .indexer
+        .get_address_tx_hashes(address)
+        .map_err(|e| ApiError::internal_error(format!("Index error: {}", e)))?;
+
+    // The index lists transactions in the order they were confirmed
+    let mut transactions = Vec::new();
+    for hash in hashes.iter().rev().take(MAX_ADDRESS_SCAN) {
+        let Some(location) = state
+            .indexer
+            .get_tx_location(hash)
+            .map_err(|e| ApiError::internal_error(format!("Index error: {}", e)))?
+        else {
+            continue;
+        };
+        if !blocks.contains(&location.block_height) {
+            match blockchain
+                .get_block_by_height(location.block_height)
+                .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+            {
+                Some(block) => blocks.put(location.block_height, block),
+                None => continue,
+            };
+        }
+        let tx = blocks
+            .get(&location.block_height)
+            .and_then(|block| block.transactions.get(location.tx_index));
+        if let Some(tx) = tx {
+            transactions.push((tx.clone(), location.block_height));
+        }
+    }
+    Ok((transactions, hashes.len() > MAX_ADDRESS_SCAN))
+}
+
+/// Walk the graph on a blocking thread, reading blocks under one lock
+async fn trace_blocking(
+    state: AppState,
+    start: impl FnOnce(&opensyria_storage::BlockchainStorage) -> Result<TraceStart, ApiError>
+        + Send
+        + 'static,
+    direction: TraceDirection,
+    limits: TraceLimits,
+) -> Result<TraceGraph, ApiError> {
+    tokio::task::spawn_blocking(move || {
+        let blockchain = state.blockchain.blocking_read();
+        let start = start(&blockchain)?;
+        let capacity = NonZeroUsize::new(BLOCK_CACHE_SIZE).unwrap_or(NonZeroUsize::MIN);
+        let mut blocks = LruCache::new(capacity);
+        trace_funds(start, direction, limits, |address| {
+            address_transactions(&state, &blockchain, &mut blocks, address)
+        })
+    })
+    .await
+    .map_err(|e| ApiError::internal_error(format!("Trace failed: {}", e)))?
+}
+
+fn parse_hash(value: &str, what: &str) -> Result<[u8; 32], ApiError> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| ApiError::bad_request(format!("{} must be 32 bytes of hex", what)))
+}
+
+/// GET /api/trace/address/:address - Funds flow graph around an address
+pub async fn trace_address(
+    Path(address): Path<String>,
+    Query(query): Query<TraceQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<TraceGraph>, ApiError> {
+    let limits = query.limits()?;
+    let address = PublicKey(parse_hash(&address, "Address")?);
+
+    let start = move |_: &opensyria_storage::BlockchainStorage| Ok(TraceStart::Address(address));
+    let graph = trace_blocking(state, start, query.direction, limits).await?;
+    Ok(Json(graph))
+}
+
+/// GET /api/trace/tx/:hash - Funds flow graph starting from a transaction
+pub async fn trace_transaction(
+    Path(hash): Path<String>,
+    Query(query): Query<TraceQuery>,
+    State(state): State<AppState>,
+) -> Result<Json<TraceGraph>, ApiError> {
+    let limits = query.limits()?;
+    let hash = parse_hash(&hash, "Transaction hash")?;
+
+    let location = state
+        .indexer
+        .get_tx_location(&hash)
+        .map_err(|e| ApiError::internal_error(format!("Index error: {}", e)))?
+        .ok_or_else(|| ApiError::not_found("Transaction not found"))?;
+    let start = move |blockchain: &opensyria_storage::BlockchainStorage| {
+        let tx = blockchain
+            .get_block_by_height(location.block_height)
+            .map_err(|e| ApiError::internal_error(format!("Database error: {}", e)))?
+            .and_then(|block| block.transactions.get(location.tx_index).cloned())
+            .ok_or_else(|| ApiError::not_found("Transaction not found"))?;
+        Ok(TraceStart::Transaction(tx, location.block_height))
+    };
+
+    let graph = trace_blocking(state, start, query.direction, limits).await?;
+    Ok(Json(graph))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    fn transfer(from: PublicKey, to: PublicKey, amount: u64, nonce: u64) -> Transaction {
+        Transaction::new(from, to, amount, 100, nonce)
+    }
+
+    /// a -> b (h1), b -> c (h2), b -> d (h0, before funds arrived), c -> a (h3)
+    fn ledger() -> (Vec<PublicKey>, Vec<(Transaction, u64)>) {
+        let [a, b, c, d] = [(); 4].map(|_| KeyPair::generate().public_key());
+        let txs = vec![
+            (transfer(b, d, 5, 0), 0),
+            (transfer(a, b, 50, 0), 1),
+            (transfer(b, c, 20, 1), 2),
+            (transfer(c, a, 10, 0), 3),
+        ];
+        (vec![a, b, c, d], txs)
+    }
+
+    fn run(
+        start: TraceStart,
+        direction: TraceDirection,
+        limits: TraceLimits,
+        txs: &[(Transaction, u64)],
+    ) -> TraceGraph {
+        trace_funds::<()>(start, direction, limits, |address| {
+            let touching = txs
+                .iter()
+                .filter(|(tx, _)| tx.from == *address || tx.to == *address)
+                .cloned()
+                .collect();
+            Ok((touching, false))
+        })
+        .unwrap()
+    }
+
+    fn limits(depth: u32, fanout: usize) -> TraceLimits {
+        TraceLimits {
+            depth,
+            fanout,
+            max_edges: MAX_TRACE_EDGES,
+        }
+    }
+
+    #[test]
+    fn test_forward_trace_respects_height_order() {
+        let (keys, txs) = ledger();
+        let graph = run(
+            TraceStart::Address(keys[0]),
+            TraceDirection::Forward,
+            limits(3, 10),
+            &txs,
+        );
+
+        let heights: Vec<u64> = graph.edges.iter().map(|edge| edge.height).collect();
+        assert_eq!(heights, vec![1, 2, 3]);
+        // b -> d happened before b received the traced funds
+        assert!(!graph
+            .nodes
+            .iter()
+            .any(|node| node.address == keys[3].to_hex()));
+        let depths: Vec<u32> = graph.nodes.iter().map(|node| node.depth).collect();
+        assert_eq!(depths, vec![0, 1, 2]);
+        assert!(!graph.truncated);
+    }
+
+    #[test]
+    fn test_backward_trace_from_transaction() {
+        let (keys, txs) = ledger();
+        let (tx, height) = txs[2].clone();
+        let graph = run(
+            TraceStart::Transaction(tx.clone(), height),
+            TraceDirection::Backward,
+            limits(2, 10),
+            &txs,
+        );
+
+        assert_eq!(graph.root, hex::encode(tx.hash()));
+        assert_eq!(graph.nodes[0].address, keys[2].to_hex());
+        let sources: Vec<&str> = graph.edges.iter().map(|edge| edge.from.as_str()).collect();
+        assert_eq!(sources, vec![keys[1].to_hex(), keys[0].to_hex()]);
+    }
+
+    #[test]
+    fn test_limits_truncate() {
+        let (keys, txs) = ledger();
+        let graph = run(
+            TraceStart::Address(keys[1]),
+            TraceDirection::Backward,
+            limits(1, 1),
+            &txs,
+        );
+        assert_eq!(graph.edges.len(), 1);
+        assert_eq!(graph.nodes.len(), 2);
+
+        let graph = run(
+            TraceStart::Address(keys[0]),
+            TraceDirection::Forward,
+            TraceLimits {
+                max_edges: 2,
+                ..limits(6, 10)
+            },
+            &txs,
+        );
+        assert_eq!(graph.edges.len(), 2);
+        assert!(graph.truncated);
+
+        let query = TraceQuery {
+            direction: TraceDirection::Forward,
+            depth: Some(MAX_TRACE_DEPTH + 1),
+            fanout: None,
+        };
+        assert!(query.limits().is_err());
+    }
+
+    #[test]
+    fn test_coinbase_is_not_expanded() {
+        let miner = KeyPair::generate().public_key();
+        let reward = Transaction::coinbase(1, miner, 1, 0).unwrap();
+        let graph = run(
+            TraceStart::Address(miner),
+            TraceDirection::Backward,
+            limits(3, 10),
+            &[(reward, 1)],
+        );
+        assert_eq!(graph.edges.len(), 1);
+        assert!(graph.nodes[1].coinbase);
+    }
+}
@@ -1,6 +1,12 @@
 //! API response types
 
-use opensyria_core::{Block, Transaction};
+use opensyria_core::{
+    memo::MEMO_TAG, AssetId, AssetInfo, Block, ConfirmationStatus, ConfirmationTargets,
+    FeeEstimate, FeeRateStats, Memo, Order, OrderSide, OrderStatus, StealthAnnouncement, Trade,
+    Transaction,
+};
+use opensyria_governance::{ParticipationSummary, ProposalTurnout};
+use opensyria_storage::{BlockJournal, JournalLine, JournalReason, JournalSide};
 use serde::{Deserialize, Serialize};
 
 /// Block information response
@@ -15,10 +21,17 @@ pub struct BlockInfo {
     pub merkle_root: String,
     pub transaction_count: usize,
     pub transactions: Vec<String>, // Transaction hashes
+    /// First coinbase payout address
+    #[serde(default)]
+    pub miner: Option<String>,
+    /// Coinbase extra data as text, if the miner set a UTF-8 tag
+    #[serde(default)]
+    pub miner_tag: Option<String>,
 }
 
 impl BlockInfo {
     pub fn from_block(block: &Block, height: u64) -> Self {
+        let producer = block.producer();
         Self {
             hash: hex::encode(block.hash()),
             height,
@@ -33,6 +46,8 @@ impl BlockInfo {
                 .iter()
                 .map(|tx| hex::encode(tx.hash()))
                 .collect(),
+            miner: producer.as_ref().map(|p| p.address.to_hex()),
+            miner_tag: producer.as_ref().and_then(|p| p.tag()).map(str::to_string),
         }
     }
 }
@@ -50,6 +65,52 @@ pub struct TransactionInfo {
     pub timestamp: Option<u64>, // Block timestamp if confirmed
     pub block_hash: Option<String>,
     pub block_height: Option<u64>,
+    /// Pending, confirming (n/N) or final. Final transactions omit the
+    /// count so their responses stay cacheable
+    pub status: ConfirmationStatus,
+    /// Paid to a one-time stealth address, which does not identify the
+    /// recipient
+    #[serde(default)]
+    pub stealth: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<MemoInfo>,
+}
+
+/// Memo of a transaction. Encrypted memos are only shown as ciphertext:
+/// the explorer holds no keys to read them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MemoInfo {
+    Plain {
+        text: String,
+    },
+    Encrypted {
+        /// Format version, `None` if the memo is malformed
+        version: Option<u8>,
+        /// Hex ciphertext
+        ciphertext: String,
+    },
+}
+
+impl MemoInfo {
+    pub fn from_transaction(tx: &Transaction) -> Option<Self> {
+        let data = tx.data.as_deref()?;
+        match Memo::from_data(data) {
+            Ok(Some(Memo::Plain(text))) => Some(MemoInfo::Plain {
+                text: String::from_utf8_lossy(&text).into_owned(),
+            }),
+            Ok(Some(Memo::Encrypted(memo))) => Some(MemoInfo::Encrypted {
+                version: Some(memo.version),
+                ciphertext: hex::encode(&memo.ciphertext),
+            }),
+            Ok(None) => None,
+            // Unknown versions are shown as they are
+            Err(_) => Some(MemoInfo::Encrypted {
+                version: data.get(MEMO_TAG.len()).copied(),
+                ciphertext: hex::encode(data.get(MEMO_TAG.len() + 1..).unwrap_or_default()),
+            }),
+        }
+    }
 }
 
 impl TransactionInfo {
@@ -65,6 +126,9 @@ impl TransactionInfo {
             timestamp: None,
             block_hash: None,
             block_height: None,
+            status: ConfirmationStatus::Pending,
+            stealth: StealthAnnouncement::is_stealth(tx),
+            memo: MemoInfo::from_transaction(tx),
         }
     }
 
@@ -74,6 +138,17 @@ impl TransactionInfo {
         self.block_height = Some(height);
         self
     }
+
+    /// Set the confirmation status of `tx` relative to the chain tip
+    pub fn with_status(
+        mut self,
+        tx: &Transaction,
+        tip_height: u64,
+        targets: &ConfirmationTargets,
+    ) -> Self {
+        self.status = targets.status(tx, self.block_height, tip_height);
+        self
+    }
 }
 
 /// Chain statistics response
@@ -94,6 +169,212 @@ pub struct AddressInfo {
     pub balance: u64,
     pub nonce: u64,
     pub transaction_count: usize,
+    /// Governance proposal that froze this address, if frozen
+    pub frozen_by_proposal: Option<u64>,
+    /// Native asset holdings
+    pub assets: Vec<AssetBalanceInfo>,
+}
+
+/// Address in the richest-addresses list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopAddress {
+    /// 1 for the largest balance
+    pub rank: usize,
+    pub address: String,
+    pub balance: u64,
+}
+
+/// Balance of one native asset held by an address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetBalanceInfo {
+    pub asset_id: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub balance: u64,
+}
+
+/// Native asset registry entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetDetail {
+    pub asset_id: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    /// Key allowed to mint, absent once the issuer renounced the role
+    pub issuer: Option<String>,
+    pub max_supply: u64,
+    pub supply: u64,
+}
+
+impl AssetDetail {
+    pub fn new(id: &AssetId, info: AssetInfo) -> Self {
+        Self {
+            asset_id: id.to_hex(),
+            symbol: info.symbol,
+            name: info.name,
+            decimals: info.decimals,
+            issuer: info.issuer.map(|issuer| issuer.to_hex()),
+            max_supply: info.max_supply,
+            supply: info.supply,
+        }
+    }
+}
+
+/// Order book summary of one asset traded against SYL
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketSummary {
+    pub asset_id: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Highest open buy price, in SYL base units per asset unit
+    pub best_bid: Option<u64>,
+    /// Lowest open sell price
+    pub best_ask: Option<u64>,
+    /// Price of the most recent trade
+    pub last_price: Option<u64>,
+    pub open_orders: usize,
+}
+
+/// Open quantity at one price
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriceLevel {
+    pub price: u64,
+    pub amount: u64,
+    pub orders: usize,
+}
+
+impl PriceLevel {
+    /// Aggregate orders sorted best price first into levels
+    pub fn from_orders(orders: &[Order]) -> Vec<Self> {
+        let mut levels: Vec<PriceLevel> = Vec::new();
+        for order in orders {
+            match levels.last_mut() {
+                Some(level) if level.price == order.price => {
+                    level.amount += order.remaining;
+                    level.orders += 1;
+                }
+                _ => levels.push(PriceLevel {
+                    price: order.price,
+                    amount: order.remaining,
+                    orders: 1,
+                }),
+            }
+        }
+        levels
+    }
+}
+
+/// Order book depth of one market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketDepth {
+    #[serde(flatten)]
+    pub summary: MarketSummary,
+    /// Buy levels, highest price first
+    pub bids: Vec<PriceLevel>,
+    /// Sell levels, lowest price first
+    pub asks: Vec<PriceLevel>,
+}
+
+/// A settled trade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeInfo {
+    pub asset_id: String,
+    pub price: u64,
+    pub amount: u64,
+    /// Resting order that was filled
+    pub maker_order: String,
+    /// Incoming order that filled it
+    pub taker_order: String,
+    pub buyer: String,
+    pub seller: String,
+    pub block_height: u64,
+}
+
+impl From<Trade> for TradeInfo {
+    fn from(trade: Trade) -> Self {
+        Self {
+            asset_id: trade.asset.to_hex(),
+            price: trade.price,
+            amount: trade.amount,
+            maker_order: trade.maker.to_hex(),
+            taker_order: trade.taker.to_hex(),
+            buyer: trade.buyer.to_hex(),
+            seller: trade.seller.to_hex(),
+            block_height: trade.height,
+        }
+    }
+}
+
+/// One debit or credit of a block journal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalLineInfo {
+    /// Transaction hash; absent for net contract or DEX settlement and
+    /// governance movements
+    pub tx_hash: Option<String>,
+    /// Address (hex) or system account: `issuance`, `fees`, `treasury`, `burned`
+    pub account: String,
+    pub reason: JournalReason,
+    pub side: JournalSide,
+    pub amount: u64,
+}
+
+impl From<JournalLine> for JournalLineInfo {
+    fn from(line: JournalLine) -> Self {
+        Self {
+            tx_hash: line.tx_hash.map(hex::encode),
+            account: line.account.to_string(),
+            reason: line.reason,
+            side: line.side,
+            amount: line.amount,
+        }
+    }
+}
+
+/// Double-entry journal of a block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockJournalInfo {
+    pub height: u64,
+    pub total_debits: u128,
+    pub total_credits: u128,
+    /// Debits equal credits
+    pub balanced: bool,
+    pub lines: Vec<JournalLineInfo>,
+}
+
+impl From<BlockJournal> for BlockJournalInfo {
+    fn from(journal: BlockJournal) -> Self {
+        let (total_debits, total_credits) = journal.totals();
+        Self {
+            height: journal.height,
+            total_debits,
+            total_credits,
+            balanced: total_debits == total_credits,
+            lines: journal
+                .lines
+                .into_iter()
+                .map(JournalLineInfo::from)
+                .collect(),
+        }
+    }
+}
+
+/// An order and the settlement of the transaction that placed it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderInfo {
+    pub order_id: String,
+    pub owner: String,
+    pub asset_id: String,
+    pub side: OrderSide,
+    pub price: u64,
+    pub amount: u64,
+    pub remaining: u64,
+    pub status: OrderStatus,
+    pub block_height: u64,
+    /// Fills made when the order was placed; later fills against it appear
+    /// in the taking orders
+    pub trades: Vec<TradeInfo>,
+    /// SYL returned because fills were below the limit price
+    pub refund: u64,
 }
 
 /// Mempool information response
@@ -103,6 +384,51 @@ pub struct MempoolInfo {
     pub transactions: Vec<TransactionInfo>,
 }
 
+/// Fee-rate statistics over recent blocks (rates in base units per KB)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeeStatsResponse {
+    /// Per-block statistics, newest first
+    pub blocks: Vec<FeeRateStats>,
+    /// Suggested rates, absent when none of the blocks contain transactions
+    pub estimate: Option<FeeEstimate>,
+    /// Suggested contract gas price, in base units per 1000 gas
+    pub gas_price: u64,
+}
+
+/// Blocks produced by one miner address
+/// الكتل التي أنتجها عنوان معدّن
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinerStats {
+    /// First coinbase payout address
+    pub address: String,
+    /// Most recent tag the address put in its coinbase
+    pub tag: Option<String>,
+    pub blocks: u64,
+    /// Share of the blocks in the window, in percent
+    pub share: f64,
+    pub last_height: u64,
+}
+
+/// Block production per miner over recent blocks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MinerStatsResponse {
+    pub from_height: u64,
+    pub to_height: u64,
+    /// Blocks with a coinbase in the window
+    pub blocks: u64,
+    /// Most blocks first
+    pub miners: Vec<MinerStats>,
+}
+
+/// Governance participation over time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GovernanceAnalyticsResponse {
+    /// Totals across every proposal
+    pub summary: ParticipationSummary,
+    /// Turnout per proposal, oldest first
+    pub proposals: Vec<ProposalTurnout>,
+}
+
 /// Search result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -1,3 +1,4 @@
+use crate::mempool_view::MempoolTxStatus;
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
@@ -7,10 +8,12 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use futures::{sink::SinkExt, stream::StreamExt};
+use opensyria_core::{GovernanceEvent, NodeEvent};
 use opensyria_storage::{BlockchainStorage, StateStorage};
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::sync::RwLock;
 use tokio::time::{interval, Duration};
 
@@ -50,6 +53,20 @@ pub enum WsMessage {
         pending_count: usize,
         total_fees: u64,
     },
+    /// Mempool transaction added, confirmed or dropped
+    #[serde(rename = "mempoolTx")]
+    MempoolTx {
+        hash: String,
+        from: String,
+        to: String,
+        amount: u64,
+        fee: u64,
+        size: usize,
+        status: MempoolTxStatus,
+    },
+    /// Governance proposal opened, passed, rejected, became executable or
+    /// was executed
+    Governance { event: GovernanceEvent },
     /// Client ping
     Ping,
     /// Server pong
@@ -61,6 +78,30 @@ pub enum WsMessage {
 pub struct WsState {
     pub blockchain: Arc<RwLock<BlockchainStorage>>,
     pub state: Arc<RwLock<StateStorage>>,
+    /// `mempoolTx` and `governance` messages from the node event bus feeds
+    pub live_events: broadcast::Sender<WsMessage>,
+}
+
+/// Forward governance lifecycle events from the node event bus to
+/// WebSocket clients
+/// تمرير أحداث دورة حياة الحوكمة إلى عملاء WebSocket
+pub async fn run_governance_feed(
+    mut events: broadcast::Receiver<NodeEvent>,
+    ws_events: broadcast::Sender<WsMessage>,
+) {
+    loop {
+        match events.recv().await {
+            Ok(NodeEvent::Governance(event)) => {
+                // No WebSocket clients is not an error
+                let _ = ws_events.send(WsMessage::Governance { event });
+            }
+            Ok(_) => {}
+            Err(RecvError::Lagged(skipped)) => {
+                tracing::warn!("Governance feed lagged, skipped {} node events", skipped);
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
 }
 
 /// WebSocket handler with connection limiting
@@ -106,10 +147,29 @@ async fn handle_socket(socket: WebSocket, state: WsState) {
     // Spawn task to send periodic updates
     let mut update_interval = interval(Duration::from_secs(10));
     let sender_state = state.clone();
+    let mut live_events = state.live_events.subscribe();
     
     let mut send_task = tokio::spawn(async move {
         loop {
-            update_interval.tick().await;
+            tokio::select! {
+                _ = update_interval.tick() => {}
+                event = live_events.recv() => {
+                    match event {
+                        Ok(msg) => {
+                            if let Ok(json) = serde_json::to_string(&msg) {
+                                if sender.send(Message::Text(json)).await.is_err() {
+                                    break; // Connection closed
+                                }
+                            }
+                        }
+                        // A slow client misses some transactions rather than
+                        // holding up the others
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                    continue;
+                }
+            }
             
             // Send stats update
             if let Ok(msg) = get_stats_message(&sender_state).await {
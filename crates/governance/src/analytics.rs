@@ -0,0 +1,285 @@
+//! Governance participation analytics
+//! تحليلات المشاركة في الحوكمة
+//!
+//! Turnout for each proposal plus voter counts across all of governance,
+//! updated whenever a governance snapshot is saved. A proposal's record is
+//! final once its voting has closed and is never recomputed, so an update
+//! only revisits open proposals and ones it has not seen before, and history
+//! outlives votes dropped from later snapshots.
+
+use crate::manager::GovernanceSnapshot;
+use crate::types::{ProposalId, ProposalStatus, VoteRecord};
+use opensyria_core::crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Basis points making up 100%
+pub const BPS_SCALE: u64 = 10_000;
+
+/// Turnout of one proposal
+/// نسبة المشاركة في مقترح واحد
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct ProposalTurnout {
+    pub proposal_id: ProposalId,
+    pub title: String,
+    /// Block height the proposal was created at
+    pub created_at: u64,
+    pub voting_end: u64,
+    pub status: ProposalStatus,
+    /// Addresses that voted
+    pub voters: u64,
+    /// Voters whose first recorded vote was on this proposal
+    pub new_voters: u64,
+    /// Votes a delegate cast on another address's behalf
+    pub delegated_votes: u64,
+    /// Voting power of all votes cast
+    pub votes_cast: u64,
+    /// Voting power eligible when the proposal was created
+    pub total_voting_power: u64,
+    /// Share of eligible voting power that voted, in basis points
+    pub turnout_bps: u64,
+    /// Mean voting power per vote
+    pub average_voting_power: u64,
+}
+
+/// Participation totals across all proposals
+/// إجمالي المشاركة عبر جميع المقترحات
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParticipationSummary {
+    pub proposals: u64,
+    /// Distinct addresses that have ever voted
+    pub unique_voters: u64,
+    pub votes: u64,
+    pub delegated_votes: u64,
+    /// Share of votes cast by a delegate, in basis points
+    pub delegation_bps: u64,
+    /// Mean voting power per vote
+    pub average_voting_power: u64,
+    /// Mean turnout of proposals whose voting has closed, in basis points
+    pub average_turnout_bps: u64,
+}
+
+/// Incrementally maintained governance participation history
+/// سجل المشاركة في الحوكمة المحدث تدريجياً
+#[derive(Debug, Clone, Default, bincode::Encode, bincode::Decode)]
+pub struct GovernanceAnalytics {
+    turnout: BTreeMap<ProposalId, ProposalTurnout>,
+    /// Proposal each address first voted on
+    first_votes: HashMap<PublicKey, ProposalId>,
+}
+
+impl GovernanceAnalytics {
+    /// Analytics of a snapshot from scratch
+    pub fn from_snapshot(snapshot: &GovernanceSnapshot) -> Self {
+        let mut analytics = Self::default();
+        analytics.update(snapshot);
+        analytics
+    }
+
+    /// Fold a newer snapshot in, recomputing only proposals still open or
+    /// not yet recorded
+    pub fn update(&mut self, snapshot: &GovernanceSnapshot) {
+        let mut votes: HashMap<ProposalId, Vec<&VoteRecord>> = HashMap::new();
+        for (proposal_id, _, record) in &snapshot.votes {
+            votes.entry(*proposal_id).or_default().push(record);
+        }
+
+        let mut proposals: Vec<_> = snapshot.proposals.iter().collect();
+        proposals.sort_by_key(|p| p.id);
+        for proposal in proposals {
+            let closed = self
+                .turnout
+                .get(&proposal.id)
+                .is_some_and(|t| t.status != ProposalStatus::Active);
+            if closed {
+                continue;
+            }
+
+            let mut records = votes.remove(&proposal.id).unwrap_or_default();
+            records.sort_by_key(|r| r.timestamp);
+            let mut new_voters = 0;
+            for record in &records {
+                let first = *self.first_votes.entry(record.voter).or_insert(proposal.id);
+                if first == proposal.id {
+                    new_voters += 1;
+                }
+            }
+
+            let voters = records.len() as u64;
+            let votes_cast = proposal
+                .votes_yes
+                .saturating_add(proposal.votes_no)
+                .saturating_add(proposal.votes_abstain);
+            self.turnout.insert(
+                proposal.id,
+                ProposalTurnout {
+                    proposal_id: proposal.id,
+                    title: proposal.title.clone(),
+                    created_at: proposal.created_at,
+                    voting_end: proposal.voting_end,
+                    status: proposal.status,
+                    voters,
+                    new_voters,
+                    delegated_votes: records
+                        .iter()
+                        .filter(|r| r.delegated_from.is_some())
+                        .count() as u64,
+                    votes_cast,
+                    total_voting_power: proposal.total_voting_power,
+                    turnout_bps: bps(votes_cast, proposal.total_voting_power),
+                    average_voting_power: votes_cast.checked_div(voters).unwrap_or(0),
+                },
+            );
+        }
+    }
+
+    /// Turnout of every recorded proposal, oldest first
+    pub fn proposals(&self) -> impl Iterator<Item = &ProposalTurnout> {
+        self.turnout.values()
+    }
+
+    /// Turnout of one proposal
+    pub fn proposal(&self, id: ProposalId) -> Option<&ProposalTurnout> {
+        self.turnout.get(&id)
+    }
+
+    /// Totals across all recorded proposals
+    pub fn summary(&self) -> ParticipationSummary {
+        let votes: u64 = self.turnout.values().map(|t| t.voters).sum();
+        let delegated_votes: u64 = self.turnout.values().map(|t| t.delegated_votes).sum();
+        let power: u128 = self.turnout.values().map(|t| t.votes_cast as u128).sum();
+        let closed: Vec<_> = self
+            .turnout
+            .values()
+            .filter(|t| t.status != ProposalStatus::Active)
+            .collect();
+        let closed_turnout: u64 = closed.iter().map(|t| t.turnout_bps).sum();
+
+        ParticipationSummary {
+            proposals: self.turnout.len() as u64,
+            unique_voters: self.first_votes.len() as u64,
+            votes,
+            delegated_votes,
+            delegation_bps: bps(delegated_votes, votes),
+            average_voting_power: power
+                .checked_div(votes as u128)
+                .map_or(0, |average| average as u64),
+            average_turnout_bps: closed_turnout.checked_div(closed.len() as u64).unwrap_or(0),
+        }
+    }
+}
+
+/// `part` as basis points of `whole`, zero when `whole` is
+fn bps(part: u64, whole: u64) -> u64 {
+    if whole == 0 {
+        return 0;
+    }
+    (part as u128 * BPS_SCALE as u128 / whole as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{GovernanceConfig, Proposal, ProposalType, Vote};
+    use opensyria_core::crypto::KeyPair;
+
+    fn proposal(id: ProposalId, created_at: u64) -> Proposal {
+        Proposal::new(
+            id,
+            KeyPair::generate().public_key(),
+            ProposalType::TextProposal {
+                description: "Test".to_string(),
+            },
+            format!("Proposal {}", id),
+            "Description".to_string(),
+            created_at,
+            1000,
+            100,
+            1_000_000,
+        )
+    }
+
+    fn vote(
+        snapshot: &mut GovernanceSnapshot,
+        id: ProposalId,
+        voter: PublicKey,
+        power: u64,
+        delegated_from: Option<PublicKey>,
+    ) {
+        let proposal = snapshot.proposals.iter_mut().find(|p| p.id == id).unwrap();
+        proposal.votes_yes += power;
+        snapshot.votes.push((
+            id,
+            voter,
+            VoteRecord {
+                voter,
+                vote: Vote::Yes,
+                voting_power: power,
+                snapshot_balance: power,
+                timestamp: proposal.created_at,
+                delegated_from,
+            },
+        ));
+    }
+
+    #[test]
+    fn test_turnout_and_summary() {
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        let carol = KeyPair::generate().public_key();
+        let mut snapshot = GovernanceSnapshot {
+            proposals: vec![proposal(1, 100), proposal(2, 2000)],
+            votes: vec![],
+            balance_snapshots: vec![],
+            next_proposal_id: 3,
+            config: GovernanceConfig::default(),
+            council: None,
+            motions: vec![],
+        };
+        vote(&mut snapshot, 1, alice, 200_000, None);
+        vote(&mut snapshot, 2, alice, 300_000, None);
+        vote(&mut snapshot, 2, bob, 100_000, Some(carol));
+        snapshot.proposals[0].finalize(1100);
+
+        let analytics = GovernanceAnalytics::from_snapshot(&snapshot);
+        let first = analytics.proposal(1).unwrap();
+        assert_eq!((first.voters, first.new_voters), (1, 1));
+        assert_eq!(first.turnout_bps, 2_000);
+        let second = analytics.proposal(2).unwrap();
+        assert_eq!((second.voters, second.new_voters), (2, 1));
+        assert_eq!(second.delegated_votes, 1);
+        assert_eq!(second.average_voting_power, 200_000);
+
+        let summary = analytics.summary();
+        assert_eq!((summary.unique_voters, summary.votes), (2, 3));
+        assert_eq!(summary.delegation_bps, 3_333);
+        assert_eq!(summary.average_voting_power, 200_000);
+        // Only the closed first proposal counts towards average turnout
+        assert_eq!(summary.average_turnout_bps, 2_000);
+    }
+
+    #[test]
+    fn test_closed_proposals_are_final() {
+        let alice = KeyPair::generate().public_key();
+        let mut snapshot = GovernanceSnapshot {
+            proposals: vec![proposal(1, 100)],
+            votes: vec![],
+            balance_snapshots: vec![],
+            next_proposal_id: 2,
+            config: GovernanceConfig::default(),
+            council: None,
+            motions: vec![],
+        };
+        vote(&mut snapshot, 1, alice, 500_000, None);
+        snapshot.proposals[0].finalize(1100);
+        let mut analytics = GovernanceAnalytics::from_snapshot(&snapshot);
+
+        // Votes of a closed proposal dropped from a later snapshot are kept
+        snapshot.votes.clear();
+        snapshot.proposals.push(proposal(2, 2000));
+        analytics.update(&snapshot);
+        assert_eq!(analytics.proposal(1).unwrap().voters, 1);
+        assert_eq!(analytics.proposals().count(), 2);
+        assert_eq!(analytics.summary().unique_voters, 1);
+    }
+}
@@ -1,6 +1,6 @@
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use opensyria_core::crypto::KeyPair;
+use opensyria_core::crypto::{KeyPair, PublicKey};
 use opensyria_governance::{
     GovernanceConfig, GovernanceManager, GovernanceStorage, ProposalType, Vote,
 };
@@ -37,6 +37,14 @@ enum Commands {
         /// Proposal type
         #[arg(short = 't', long)]
         proposal_type: String,
+
+        /// Proposer public key (hex)
+        #[arg(long)]
+        proposer: String,
+
+        /// Transaction that escrowed the proposer's deposit (hex)
+        #[arg(long)]
+        deposit: String,
     },
 
     /// List all proposals
@@ -75,7 +83,16 @@ fn main() {
             title,
             description,
             proposal_type,
-        } => create_proposal(&cli.data_dir, title, description, proposal_type),
+            proposer,
+            deposit,
+        } => create_proposal(
+            &cli.data_dir,
+            title,
+            description,
+            proposal_type,
+            proposer,
+            deposit,
+        ),
         Commands::List { status } => list_proposals(&cli.data_dir, status),
         Commands::Show { proposal_id } => show_proposal(&cli.data_dir, proposal_id),
         Commands::Vote {
@@ -124,7 +141,14 @@ fn load_manager(data_dir: &PathBuf) -> Result<GovernanceManager, String> {
     Ok(GovernanceManager::from_snapshot(snapshot))
 }
 
-fn create_proposal(data_dir: &PathBuf, title: String, description: String, type_str: String) {
+fn create_proposal(
+    data_dir: &PathBuf,
+    title: String,
+    description: String,
+    type_str: String,
+    proposer_hex: String,
+    deposit_hex: String,
+) {
     let mut manager = match load_manager(data_dir) {
         Ok(m) => m,
         Err(e) => {
@@ -146,7 +170,26 @@ fn create_proposal(data_dir: &PathBuf, title: String, description: String, type_
         }
     };
 
-    let proposer = KeyPair::generate(); // In real use, load from wallet
+    let proposer = match PublicKey::from_hex(&proposer_hex) {
+        Ok(proposer) => proposer,
+        Err(e) => {
+            eprintln!("{} Invalid proposer key: {}", "Error:".red(), e);
+            return;
+        }
+    };
+    let deposit_escrow: [u8; 32] = match hex::decode(&deposit_hex)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+    {
+        Some(txid) => txid,
+        None => {
+            eprintln!(
+                "{} Deposit must be a 32-byte transaction ID",
+                "Error:".red()
+            );
+            return;
+        }
+    };
     let current_height = 1000; // In real use, get from blockchain
     let total_voting_power = 100_000_000_000; // In real use, get from state
     
@@ -162,13 +205,14 @@ fn create_proposal(data_dir: &PathBuf, title: String, description: String, type_
     };
 
     match manager.create_proposal(
-        proposer.public_key(),
+        proposer,
         2_000_000_000, // Proposer stake
         proposal_type,
         title.clone(),
         description,
         current_height,
         total_voting_power,
+        deposit_escrow,
         &state_storage,
     ) {
         Ok(id) => {
@@ -0,0 +1,378 @@
+//! Emergency council
+//! مجلس الطوارئ
+//!
+//! A configured M-of-N council can fast-track a narrow set of emergency
+//! actions without waiting for a full voting period:
+//!
+//! - Pausing execution of a proposal (e.g. a harmful parameter change)
+//! - Publishing a checkpoint
+//! - Freezing an account
+//!
+//! A member opens a motion by signing it; once `threshold` members have
+//! signed, the motion executes after the council's short execution delay.
+//! Motions and approvals are kept in the governance snapshot and every
+//! execution and revocation is written to the audit log. Any motion can be
+//! revoked by a regular governance vote
+//! ([`ProposalType::RevokeEmergencyAction`](crate::ProposalType::RevokeEmergencyAction)),
+//! which also undoes its effect.
+
+use crate::state::GovernanceError;
+use crate::types::ProposalId;
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::multisig::MultisigAccount;
+use opensyria_storage::StateStorage;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Unique identifier for an emergency motion
+pub type MotionId = u64;
+
+/// Domain separator for council approval signatures
+const COUNCIL_SIGNING_DOMAIN: &[u8] = b"opensyria-emergency-council-v1";
+
+/// Freeze records written by the council carry this bit, so revoking a
+/// motion never lifts a freeze made by a full governance vote
+pub const EMERGENCY_FREEZE_FLAG: u64 = 1 << 63;
+
+/// Default blocks between approval and execution (~10 minutes)
+pub const DEFAULT_EMERGENCY_DELAY: u64 = 10;
+
+/// Default blocks a motion may collect approvals (~1 day)
+pub const DEFAULT_APPROVAL_WINDOW: u64 = 1_440;
+
+/// Actions the council may take
+/// الإجراءات المسموح بها لمجلس الطوارئ
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, bincode::Encode, bincode::Decode)]
+pub enum EmergencyAction {
+    /// Hold a proposal so it cannot be executed
+    PauseProposal { proposal_id: ProposalId },
+
+    /// Publish a checkpoint for the block at `height`; nodes reject any
+    /// other block there until the motion is revoked
+    PublishCheckpoint { height: u64, hash: [u8; 32] },
+
+    /// Freeze an address so it can no longer spend
+    FreezeAccount { address: PublicKey },
+}
+
+impl EmergencyAction {
+    /// Apply an executed action to the account state
+    ///
+    /// Returns `Ok(false)` for actions that do not touch the frozen set, or
+    /// when the address is already frozen.
+    pub fn apply_to_state(
+        &self,
+        motion_id: MotionId,
+        state: &StateStorage,
+    ) -> Result<bool, opensyria_storage::StorageError> {
+        match self {
+            EmergencyAction::FreezeAccount { address } => {
+                if state.is_frozen(address)? {
+                    return Ok(false);
+                }
+                state.freeze_account(address, EMERGENCY_FREEZE_FLAG | motion_id)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Undo the state effect of a revoked action
+    ///
+    /// Only a freeze recorded by this motion is lifted.
+    pub fn revert_state(
+        &self,
+        motion_id: MotionId,
+        state: &StateStorage,
+    ) -> Result<bool, opensyria_storage::StorageError> {
+        match self {
+            EmergencyAction::FreezeAccount { address } => {
+                if state.get_freeze(address)? != Some(EMERGENCY_FREEZE_FLAG | motion_id) {
+                    return Ok(false);
+                }
+                state.unfreeze_account(address)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Emergency council configuration
+/// إعدادات مجلس الطوارئ
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, bincode::Encode, bincode::Decode)]
+pub struct EmergencyCouncil {
+    /// Council members and approval threshold (M-of-N)
+    pub multisig: MultisigAccount,
+    /// Blocks between reaching the threshold and execution
+    pub execution_delay: u64,
+    /// Blocks a motion may collect approvals before it expires
+    pub approval_window: u64,
+}
+
+impl EmergencyCouncil {
+    /// Council with the default delays
+    pub fn new(members: Vec<PublicKey>, threshold: u8) -> Result<Self, GovernanceError> {
+        let multisig = MultisigAccount::new(members, threshold)
+            .map_err(|e| GovernanceError::InvalidParameters(e.to_string()))?;
+        Ok(Self {
+            multisig,
+            execution_delay: DEFAULT_EMERGENCY_DELAY,
+            approval_window: DEFAULT_APPROVAL_WINDOW,
+        })
+    }
+
+    /// Check if `key` is a council member
+    pub fn is_member(&self, key: &PublicKey) -> bool {
+        self.multisig.is_signer(key)
+    }
+
+    /// Approvals required to pass a motion
+    pub fn threshold(&self) -> usize {
+        self.multisig.threshold as usize
+    }
+}
+
+/// Emergency motion status
+#[derive(
+    Debug, Clone, Copy, Serialize, Deserialize, PartialEq, bincode::Encode, bincode::Decode,
+)]
+pub enum MotionStatus {
+    /// Collecting approvals
+    Pending,
+    /// Threshold reached, waiting for the execution delay
+    Approved,
+    /// Action has been carried out
+    Executed,
+    /// Approval window ended below the threshold
+    Expired,
+    /// Revoked by a governance vote
+    Revoked,
+}
+
+/// Council motion for an emergency action
+/// اقتراح طارئ من المجلس
+#[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct EmergencyMotion {
+    pub id: MotionId,
+    pub proposer: PublicKey,
+    pub action: EmergencyAction,
+    pub reason: String,
+    pub created_at: u64, // Block height
+    pub approvals: Vec<(PublicKey, Vec<u8>)>,
+    pub approved_at: Option<u64>,
+    pub executed_at: Option<u64>,
+    /// Governance proposal that revoked this motion
+    pub revoked_by: Option<ProposalId>,
+    pub status: MotionStatus,
+}
+
+/// Hash council members sign to approve a motion
+pub fn motion_signing_hash(id: MotionId, action: &EmergencyAction, reason: &str) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(COUNCIL_SIGNING_DOMAIN);
+    hasher.update(id.to_le_bytes());
+    hasher.update(bincode::encode_to_vec(action, bincode::config::standard()).unwrap_or_default());
+    hasher.update(reason.as_bytes());
+    hasher.finalize().into()
+}
+
+impl EmergencyMotion {
+    /// Create a new motion without approvals
+    pub fn new(
+        id: MotionId,
+        proposer: PublicKey,
+        action: EmergencyAction,
+        reason: String,
+        created_at: u64,
+    ) -> Self {
+        Self {
+            id,
+            proposer,
+            action,
+            reason,
+            created_at,
+            approvals: Vec::new(),
+            approved_at: None,
+            executed_at: None,
+            revoked_by: None,
+            status: MotionStatus::Pending,
+        }
+    }
+
+    /// Hash council members sign to approve this motion
+    pub fn signing_hash(&self) -> [u8; 32] {
+        motion_signing_hash(self.id, &self.action, &self.reason)
+    }
+
+    /// Check if `member` has approved this motion
+    pub fn has_approved(&self, member: &PublicKey) -> bool {
+        self.approvals.iter().any(|(key, _)| key == member)
+    }
+
+    /// Council members that approved this motion
+    pub fn approvers(&self) -> impl Iterator<Item = &PublicKey> {
+        self.approvals.iter().map(|(key, _)| key)
+    }
+
+    /// Add a member's signed approval, moving to `Approved` at the threshold
+    pub fn approve(
+        &mut self,
+        council: &EmergencyCouncil,
+        member: PublicKey,
+        signature: Vec<u8>,
+        current_height: u64,
+    ) -> Result<(), GovernanceError> {
+        if self.status != MotionStatus::Pending || self.is_expired(council, current_height) {
+            return Err(GovernanceError::MotionClosed(self.id));
+        }
+        if !council.is_member(&member) {
+            return Err(GovernanceError::NotCouncilMember);
+        }
+        if self.has_approved(&member) {
+            return Err(GovernanceError::AlreadyVoted);
+        }
+        member
+            .verify(&self.signing_hash(), &signature)
+            .map_err(|_| GovernanceError::InvalidSignature)?;
+
+        self.approvals.push((member, signature));
+        if self.approvals.len() >= council.threshold() {
+            self.status = MotionStatus::Approved;
+            self.approved_at = Some(current_height);
+        }
+        Ok(())
+    }
+
+    /// Check if the approval window has passed without reaching the threshold
+    pub fn is_expired(&self, council: &EmergencyCouncil, current_height: u64) -> bool {
+        self.status == MotionStatus::Pending
+            && current_height >= self.created_at.saturating_add(council.approval_window)
+    }
+
+    /// Check if the motion is approved and past the execution delay
+    pub fn ready_for_execution(&self, council: &EmergencyCouncil, current_height: u64) -> bool {
+        self.status == MotionStatus::Approved
+            && self
+                .approved_at
+                .is_some_and(|at| current_height >= at.saturating_add(council.execution_delay))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    fn council(members: &[KeyPair], threshold: u8) -> EmergencyCouncil {
+        EmergencyCouncil::new(members.iter().map(|k| k.public_key()).collect(), threshold).unwrap()
+    }
+
+    fn freeze_motion(proposer: &KeyPair) -> EmergencyMotion {
+        EmergencyMotion::new(
+            1,
+            proposer.public_key(),
+            EmergencyAction::FreezeAccount {
+                address: KeyPair::generate().public_key(),
+            },
+            "Exchange hot wallet drained".to_string(),
+            100,
+        )
+    }
+
+    #[test]
+    fn test_motion_reaches_threshold() {
+        let members: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let council = council(&members, 2);
+        let mut motion = freeze_motion(&members[0]);
+        let hash = motion.signing_hash();
+
+        motion
+            .approve(
+                &council,
+                members[0].public_key(),
+                members[0].sign(&hash),
+                100,
+            )
+            .unwrap();
+        assert_eq!(motion.status, MotionStatus::Pending);
+        assert!(matches!(
+            motion.approve(
+                &council,
+                members[0].public_key(),
+                members[0].sign(&hash),
+                101
+            ),
+            Err(GovernanceError::AlreadyVoted)
+        ));
+
+        motion
+            .approve(
+                &council,
+                members[1].public_key(),
+                members[1].sign(&hash),
+                102,
+            )
+            .unwrap();
+        assert_eq!(motion.status, MotionStatus::Approved);
+        assert!(!motion.ready_for_execution(&council, 102 + DEFAULT_EMERGENCY_DELAY - 1));
+        assert!(motion.ready_for_execution(&council, 102 + DEFAULT_EMERGENCY_DELAY));
+
+        // No further approvals once the threshold is reached
+        assert!(matches!(
+            motion.approve(
+                &council,
+                members[2].public_key(),
+                members[2].sign(&hash),
+                103
+            ),
+            Err(GovernanceError::MotionClosed(1))
+        ));
+    }
+
+    #[test]
+    fn test_motion_rejects_bad_approvals() {
+        let members: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate()).collect();
+        let council = council(&members, 2);
+        let mut motion = freeze_motion(&members[0]);
+        let hash = motion.signing_hash();
+
+        let outsider = KeyPair::generate();
+        assert!(matches!(
+            motion.approve(&council, outsider.public_key(), outsider.sign(&hash), 100),
+            Err(GovernanceError::NotCouncilMember)
+        ));
+
+        // A signature over another motion does not count
+        let other = motion_signing_hash(2, &motion.action, &motion.reason);
+        assert!(matches!(
+            motion.approve(
+                &council,
+                members[1].public_key(),
+                members[1].sign(&other),
+                100
+            ),
+            Err(GovernanceError::InvalidSignature)
+        ));
+
+        let late = 100 + council.approval_window;
+        assert!(motion.is_expired(&council, late));
+        assert!(matches!(
+            motion.approve(
+                &council,
+                members[1].public_key(),
+                members[1].sign(&hash),
+                late
+            ),
+            Err(GovernanceError::MotionClosed(1))
+        ));
+    }
+
+    #[test]
+    fn test_council_requires_valid_threshold() {
+        let members: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate()).collect();
+        let keys: Vec<PublicKey> = members.iter().map(|k| k.public_key()).collect();
+        assert!(EmergencyCouncil::new(keys.clone(), 3).is_err());
+        assert!(EmergencyCouncil::new(keys, 0).is_err());
+    }
+}
@@ -1,5 +1,7 @@
 // Governance system for on-chain proposals and voting
 
+pub mod analytics;
+pub mod council;
 pub mod manager;
 pub mod state;
 pub mod storage;
@@ -7,11 +9,16 @@ pub mod treasury;
 pub mod types;
 pub mod validation;
 
+pub use analytics::{GovernanceAnalytics, ParticipationSummary, ProposalTurnout};
+pub use council::{
+    EmergencyAction, EmergencyCouncil, EmergencyMotion, MotionId, MotionStatus,
+};
 pub use manager::{GovernanceManager, GovernanceSnapshot};
 pub use state::{GovernanceError, GovernanceState, GovernanceStats};
 pub use storage::{GovernanceStorage, StorageError};
 pub use treasury::{Treasury, TreasuryError, TreasurySpending, TreasuryStats};
 pub use types::{
-    GovernanceConfig, Proposal, ProposalId, ProposalStatus, ProposalType, Vote, VoteRecord,
+    DepositStatus, GovernanceConfig, Proposal, ProposalId, ProposalStatus, ProposalType, Vote,
+    VoteRecord,
 };
 pub use validation::{ProposalValidator, ProposalValidationError};
@@ -1,15 +1,33 @@
+use crate::council::{EmergencyAction, EmergencyCouncil, EmergencyMotion, MotionId, MotionStatus};
 use crate::state::{GovernanceError, GovernanceState, GovernanceStats};
 use crate::types::{
-    GovernanceConfig, Proposal, ProposalId, ProposalStatus, ProposalType, Vote, VoteRecord,
+    DepositStatus, GovernanceConfig, Proposal, ProposalId, ProposalStatus, ProposalType, Vote,
+    VoteRecord,
 };
+use opensyria_core::audit::{AuditEvent, AuditLog};
 use opensyria_core::crypto::PublicKey;
+use opensyria_core::deposit::{escrow_account, DepositOperation, DepositSettlement};
+use opensyria_core::{GovernanceEvent, Transaction};
 use opensyria_storage::StateStorage;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
 /// Main governance manager
 pub struct GovernanceManager {
     state: GovernanceState,
     config: GovernanceConfig,
+    /// Optional compliance audit trail for executed proposals
+    audit: Option<Arc<AuditLog>>,
+    /// Emergency council, if one is configured
+    council: Option<EmergencyCouncil>,
+    /// Emergency council motions by ID
+    motions: BTreeMap<MotionId, EmergencyMotion>,
+    /// Lifecycle events not yet taken by the caller
+    events: Vec<GovernanceEvent>,
+    /// Height of the last `process_proposals` call, so proposals are
+    /// announced executable only once (kept outside the snapshot)
+    processed_height: Option<u64>,
 }
 
 impl GovernanceManager {
@@ -18,10 +36,43 @@ impl GovernanceManager {
         Self {
             state: GovernanceState::new(),
             config,
+            audit: None,
+            council: None,
+            motions: BTreeMap::new(),
+            events: Vec::new(),
+            processed_height: None,
         }
     }
 
-    /// Create a new proposal
+    /// Take the lifecycle events recorded since the last call
+    /// أخذ أحداث دورة الحياة المسجلة منذ آخر استدعاء
+    pub fn take_events(&mut self) -> Vec<GovernanceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Height proposals were last processed at
+    pub fn processed_height(&self) -> Option<u64> {
+        self.processed_height
+    }
+
+    /// Restore the processed height after loading a snapshot
+    pub fn set_processed_height(&mut self, height: Option<u64>) {
+        self.processed_height = height;
+    }
+
+    /// Record every proposal execution in an audit log
+    /// تسجيل تنفيذ الاقتراحات في سجل التدقيق
+    pub fn set_audit_log(&mut self, audit: Arc<AuditLog>) {
+        self.audit = Some(audit);
+    }
+
+    /// Create a new proposal backed by the deposit escrowed on chain by
+    /// transaction `deposit_escrow`
+    /// إنشاء اقتراح جديد مدعوم بوديعة محتجزة على السلسلة
+    ///
+    /// The escrow must be the proposer's, still open, hold at least
+    /// `min_proposal_stake`, stay locked until voting closes and back no
+    /// other proposal.
     #[allow(clippy::too_many_arguments)]
     pub fn create_proposal(
         &mut self,
@@ -32,6 +83,7 @@ impl GovernanceManager {
         description: String,
         current_height: u64,
         total_voting_power: u64,
+        deposit_escrow: [u8; 32],
         state_storage: &StateStorage,
     ) -> Result<ProposalId, GovernanceError> {
         // Check if governance is enabled
@@ -54,7 +106,44 @@ impl GovernanceManager {
             .validate()
             .map_err(|_| GovernanceError::InvalidProposal)?;
 
-        let proposal = Proposal::new(
+        if let ProposalType::RevokeEmergencyAction { motion_id, .. } = &proposal_type {
+            let motion = self
+                .motions
+                .get(motion_id)
+                .ok_or(GovernanceError::MotionNotFound(*motion_id))?;
+            if matches!(motion.status, MotionStatus::Expired | MotionStatus::Revoked) {
+                return Err(GovernanceError::MotionClosed(*motion_id));
+            }
+        }
+
+        // The deposit must be locked on chain, not just declared
+        let invalid = |msg: &str| GovernanceError::InvalidDeposit(msg.to_string());
+        let escrow = state_storage
+            .get_deposit_escrow(&deposit_escrow)
+            .map_err(|e| GovernanceError::ExecutionFailed(e.to_string()))?
+            .ok_or_else(|| invalid("no such escrow"))?;
+        if escrow.proposer != proposer {
+            return Err(invalid("escrowed by another account"));
+        }
+        if escrow.settlement.is_some() {
+            return Err(invalid("escrow already settled"));
+        }
+        if escrow.amount < self.config.min_proposal_stake {
+            return Err(GovernanceError::InsufficientStake);
+        }
+        if escrow.release_height < current_height + self.config.default_voting_period {
+            return Err(invalid("escrow released before voting closes"));
+        }
+        if self
+            .state
+            .get_all_proposals()
+            .iter()
+            .any(|p| p.deposit_escrow == deposit_escrow)
+        {
+            return Err(invalid("escrow already backs a proposal"));
+        }
+
+        let mut proposal = Proposal::new(
             self.state.next_proposal_id(),
             proposer,
             proposal_type,
@@ -65,14 +154,25 @@ impl GovernanceManager {
             self.config.default_execution_delay,
             total_voting_power,
         );
+        proposal.deposit = escrow.amount;
+        proposal.deposit_escrow = deposit_escrow;
 
         let id = self.state.add_proposal(proposal);
-        
+
         // SECURITY: Snapshot all account balances at proposal creation time
         // This prevents flash loan attacks where attackers borrow tokens,
         // vote with inflated balance, then return tokens in same block
         self.state.snapshot_balances(id, state_storage)?;
-        
+
+        if let Some(proposal) = self.state.get_proposal(id) {
+            self.events.push(GovernanceEvent::VotingStarted {
+                proposal_id: id,
+                title: proposal.title.clone(),
+                voting_start: proposal.voting_start,
+                voting_end: proposal.voting_end,
+            });
+        }
+
         Ok(id)
     }
 
@@ -86,7 +186,7 @@ impl GovernanceManager {
         proposal_id: ProposalId,
         voter: PublicKey,
         vote: Vote,
-        _state_storage: &StateStorage,
+        state_storage: &StateStorage,
         current_height: u64,
     ) -> Result<(), GovernanceError> {
         let proposal = self
@@ -105,8 +205,17 @@ impl GovernanceManager {
 
         // SECURITY FIX: Only allow voting with pre-snapshotted balances to prevent flash-loan attacks
         // Snapshot must be taken at proposal creation time, not at vote time
-        let snapshot_balance = self.state.get_snapshot_balance(proposal_id, &voter)
-            .ok_or(GovernanceError::NotEligibleToVote)?;
+        let mut snapshot_balance = self
+            .state
+            .get_snapshot_balance(proposal_id, &voter, state_storage)?
+            .unwrap_or(0);
+        // The proposer's escrowed deposit still counts as voting power
+        if voter == proposal.proposer {
+            snapshot_balance = snapshot_balance.saturating_add(proposal.deposit);
+        }
+        if snapshot_balance == 0 {
+            return Err(GovernanceError::NotEligibleToVote);
+        }
 
         let vote_record = VoteRecord {
             voter,
@@ -139,13 +248,189 @@ impl GovernanceManager {
     }
 
     /// Process proposals at current block height (finalize ended proposals)
+    ///
+    /// Records `Passed`/`Rejected` for proposals whose voting ended and
+    /// `Executable` for proposals whose execution delay elapsed since the
+    /// previous call; collect them with [`Self::take_events`].
     pub fn process_proposals(&mut self, current_height: u64) {
-        self.state.finalize_proposals(current_height);
+        for id in self.state.finalize_proposals(current_height) {
+            let Some(proposal) = self.state.get_proposal(id) else {
+                continue;
+            };
+            let event = if proposal.status == ProposalStatus::Passed {
+                GovernanceEvent::Passed {
+                    proposal_id: id,
+                    height: current_height,
+                    votes_yes: proposal.votes_yes,
+                    votes_no: proposal.votes_no,
+                    votes_abstain: proposal.votes_abstain,
+                }
+            } else {
+                GovernanceEvent::Rejected {
+                    proposal_id: id,
+                    height: current_height,
+                    votes_yes: proposal.votes_yes,
+                    votes_no: proposal.votes_no,
+                    votes_abstain: proposal.votes_abstain,
+                }
+            };
+            self.events.push(event);
+        }
+
+        let since = self.processed_height;
+        let executable: Vec<GovernanceEvent> = self
+            .get_ready_for_execution(current_height)
+            .into_iter()
+            .filter(|p| since.is_none_or(|since| p.voting_end + p.execution_delay > since))
+            .map(|p| GovernanceEvent::Executable {
+                proposal_id: p.id,
+                height: current_height,
+            })
+            .collect();
+        self.events.extend(executable);
+        self.processed_height = Some(since.map_or(current_height, |h| h.max(current_height)));
+
+        // Motions that missed their approval window can no longer pass
+        if let Some(council) = &self.council {
+            for motion in self.motions.values_mut() {
+                if motion.is_expired(council, current_height) {
+                    motion.status = MotionStatus::Expired;
+                }
+            }
+        }
     }
 
-    /// Get proposals ready for execution
+    /// Get proposals ready for execution (paused proposals are held back)
     pub fn get_ready_for_execution(&self, current_height: u64) -> Vec<&Proposal> {
-        self.state.get_ready_for_execution(current_height)
+        self.state
+            .get_ready_for_execution(current_height)
+            .into_iter()
+            .filter(|p| !self.is_proposal_paused(p.id))
+            .collect()
+    }
+
+    /// Settlement transactions for the deposits of proposals whose voting
+    /// has closed, for inclusion in the block at `height`
+    /// معاملات تسوية ودائع الاقتراحات التي انتهى التصويت عليها
+    ///
+    /// A deposit is refunded if voting met quorum, whatever the outcome, and
+    /// burned otherwise (including proposals cancelled before quorum). Only
+    /// escrows still open and released at `height` are settled; the
+    /// transactions take the escrow account's nonces in proposal order, so
+    /// the same ones are built until a block includes them.
+    pub fn deposit_settlements(
+        &self,
+        state_storage: &StateStorage,
+        height: u64,
+        chain_id: u32,
+    ) -> Result<Vec<Transaction>, GovernanceError> {
+        let storage_error =
+            |e: opensyria_storage::StorageError| GovernanceError::ExecutionFailed(e.to_string());
+        let mut unsettled: Vec<&Proposal> = self
+            .state
+            .get_all_proposals()
+            .into_iter()
+            .filter(|p| p.deposit_unsettled())
+            .collect();
+        unsettled.sort_by_key(|p| p.id);
+
+        let mut nonce = state_storage
+            .get_nonce(&escrow_account())
+            .map_err(storage_error)?;
+        let mut settlements = Vec::new();
+        for proposal in unsettled {
+            let Some(escrow) = state_storage
+                .get_deposit_escrow(&proposal.deposit_escrow)
+                .map_err(storage_error)?
+            else {
+                continue;
+            };
+            if escrow.settlement.is_some() || height < escrow.release_height {
+                continue;
+            }
+            let operation = if proposal.meets_quorum() {
+                DepositOperation::Refund {
+                    escrow: proposal.deposit_escrow,
+                }
+            } else {
+                DepositOperation::Burn {
+                    escrow: proposal.deposit_escrow,
+                }
+            };
+            settlements.push(operation.settlement(chain_id, escrow.proposer, escrow.amount, nonce));
+            nonce += 1;
+        }
+        Ok(settlements)
+    }
+
+    /// Record the deposits whose settlement has reached the chain
+    /// تسجيل الودائع التي وصلت تسويتها إلى السلسلة
+    ///
+    /// Marks each proposal's deposit refunded or burned as its escrow was
+    /// closed and announces it at `current_height`. Each deposit is recorded
+    /// once; returns how many were.
+    pub fn settle_deposits(
+        &mut self,
+        state_storage: &StateStorage,
+        current_height: u64,
+    ) -> Result<usize, GovernanceError> {
+        let mut unsettled: Vec<(ProposalId, [u8; 32])> = self
+            .state
+            .get_all_proposals()
+            .into_iter()
+            .filter(|p| p.deposit_unsettled())
+            .map(|p| (p.id, p.deposit_escrow))
+            .collect();
+        unsettled.sort_by_key(|&(id, _)| id);
+
+        let mut settled = 0;
+        for (proposal_id, deposit_escrow) in unsettled {
+            let escrow = state_storage
+                .get_deposit_escrow(&deposit_escrow)
+                .map_err(|e| GovernanceError::ExecutionFailed(e.to_string()))?;
+            let Some((settlement, amount)) =
+                escrow.and_then(|escrow| Some((escrow.settlement?, escrow.amount)))
+            else {
+                continue;
+            };
+            let (status, event) = match settlement {
+                DepositSettlement::Refunded => (
+                    DepositStatus::Refunded,
+                    GovernanceEvent::DepositRefunded {
+                        proposal_id,
+                        height: current_height,
+                        amount,
+                    },
+                ),
+                DepositSettlement::Burned => (
+                    DepositStatus::Burned,
+                    GovernanceEvent::DepositBurned {
+                        proposal_id,
+                        height: current_height,
+                        amount,
+                    },
+                ),
+            };
+            if let Some(proposal) = self.state.get_proposal_mut(proposal_id) {
+                proposal.deposit_status = status;
+            }
+            self.events.push(event);
+            settled += 1;
+        }
+
+        Ok(settled)
+    }
+
+    /// Oldest state balance snapshot a proposal still open for voting reads
+    ///
+    /// Kept balances from earlier snapshots can be pruned from state storage.
+    pub fn oldest_open_balance_snapshot(&self) -> Option<u64> {
+        self.state
+            .get_all_proposals()
+            .into_iter()
+            .filter(|p| p.status == ProposalStatus::Active)
+            .filter_map(|p| self.state.balance_snapshot(p.id))
+            .min()
     }
 
     /// Execute a proposal (mark as executed, actual execution happens externally)
@@ -171,7 +456,27 @@ impl GovernanceManager {
             return Err(GovernanceError::NotReadyForExecution);
         }
 
-        self.state.mark_executed(proposal_id)
+        if self.is_proposal_paused(proposal_id) {
+            return Err(GovernanceError::ProposalPaused(proposal_id));
+        }
+
+        let proposal_type = format!("{:?}", proposal.proposal_type);
+        self.state.mark_executed(proposal_id)?;
+        self.events.push(GovernanceEvent::Executed {
+            proposal_id,
+            height: current_height,
+        });
+
+        if let Some(audit) = &self.audit {
+            audit
+                .record(AuditEvent::GovernanceExecution {
+                    proposal_id,
+                    height: current_height,
+                    proposal_type,
+                })
+                .map_err(|e| GovernanceError::ExecutionFailed(e.to_string()))?;
+        }
+        Ok(())
     }
 
     /// Cancel a proposal
@@ -227,6 +532,225 @@ impl GovernanceManager {
     pub fn update_config(&mut self, new_config: GovernanceConfig) {
         self.config = new_config;
     }
+
+    // ===== Emergency Council =====
+
+    /// Configure the emergency council
+    /// تعيين مجلس الطوارئ
+    pub fn set_emergency_council(&mut self, council: EmergencyCouncil) {
+        self.council = Some(council);
+    }
+
+    /// Configured emergency council
+    pub fn emergency_council(&self) -> Option<&EmergencyCouncil> {
+        self.council.as_ref()
+    }
+
+    /// ID the next emergency motion will get (needed to sign it)
+    pub fn next_motion_id(&self) -> MotionId {
+        self.motions.keys().next_back().map_or(1, |id| id + 1)
+    }
+
+    /// Open an emergency motion, signed by the proposing council member
+    /// فتح اقتراح طارئ موقع من أحد أعضاء المجلس
+    pub fn propose_emergency_action(
+        &mut self,
+        proposer: PublicKey,
+        action: EmergencyAction,
+        reason: String,
+        signature: Vec<u8>,
+        current_height: u64,
+    ) -> Result<MotionId, GovernanceError> {
+        let council = self
+            .council
+            .as_ref()
+            .ok_or(GovernanceError::NoEmergencyCouncil)?;
+
+        // Emergency actions must be justified on-chain
+        if reason.trim().is_empty() {
+            return Err(GovernanceError::InvalidParameters(
+                "reason must not be empty".to_string(),
+            ));
+        }
+
+        if let EmergencyAction::PauseProposal { proposal_id } = &action {
+            let proposal = self
+                .state
+                .get_proposal(*proposal_id)
+                .ok_or(GovernanceError::ProposalNotFound(*proposal_id))?;
+            if !matches!(
+                proposal.status,
+                ProposalStatus::Active | ProposalStatus::Passed
+            ) {
+                return Err(GovernanceError::NotReadyForExecution);
+            }
+        }
+
+        let id = self.next_motion_id();
+        let mut motion = EmergencyMotion::new(id, proposer, action, reason, current_height);
+        motion.approve(council, proposer, signature, current_height)?;
+        self.motions.insert(id, motion);
+        Ok(id)
+    }
+
+    /// Add a council member's signed approval to a motion
+    pub fn approve_emergency_action(
+        &mut self,
+        motion_id: MotionId,
+        member: PublicKey,
+        signature: Vec<u8>,
+        current_height: u64,
+    ) -> Result<MotionStatus, GovernanceError> {
+        let council = self
+            .council
+            .as_ref()
+            .ok_or(GovernanceError::NoEmergencyCouncil)?;
+        let motion = self
+            .motions
+            .get_mut(&motion_id)
+            .ok_or(GovernanceError::MotionNotFound(motion_id))?;
+
+        motion.approve(council, member, signature, current_height)?;
+        Ok(motion.status)
+    }
+
+    /// Get approved motions whose execution delay has passed
+    pub fn get_ready_emergency_actions(&self, current_height: u64) -> Vec<&EmergencyMotion> {
+        let Some(council) = &self.council else {
+            return Vec::new();
+        };
+        self.motions
+            .values()
+            .filter(|m| m.ready_for_execution(council, current_height))
+            .collect()
+    }
+
+    /// Mark an emergency motion as executed (actual execution happens externally)
+    pub fn mark_emergency_executed(
+        &mut self,
+        motion_id: MotionId,
+        current_height: u64,
+    ) -> Result<(), GovernanceError> {
+        let council = self
+            .council
+            .as_ref()
+            .ok_or(GovernanceError::NoEmergencyCouncil)?;
+        let motion = self
+            .motions
+            .get_mut(&motion_id)
+            .ok_or(GovernanceError::MotionNotFound(motion_id))?;
+
+        if !motion.ready_for_execution(council, current_height) {
+            return Err(GovernanceError::NotReadyForExecution);
+        }
+
+        motion.status = MotionStatus::Executed;
+        motion.executed_at = Some(current_height);
+
+        if let Some(audit) = &self.audit {
+            audit
+                .record(AuditEvent::EmergencyAction {
+                    motion_id,
+                    height: current_height,
+                    action: format!("{:?}", motion.action),
+                    reason: motion.reason.clone(),
+                    approvers: motion.approvers().map(|key| key.to_hex()).collect(),
+                })
+                .map_err(|e| GovernanceError::ExecutionFailed(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Revoke a motion on behalf of an executed governance proposal
+    /// إلغاء اقتراح طارئ بتصويت الحوكمة
+    ///
+    /// Returns the motion as it was before revocation, so the caller can undo
+    /// the effect of an already executed action.
+    pub fn revoke_emergency_action(
+        &mut self,
+        motion_id: MotionId,
+        proposal_id: ProposalId,
+        current_height: u64,
+    ) -> Result<EmergencyMotion, GovernanceError> {
+        let motion = self
+            .motions
+            .get_mut(&motion_id)
+            .ok_or(GovernanceError::MotionNotFound(motion_id))?;
+
+        if matches!(motion.status, MotionStatus::Expired | MotionStatus::Revoked) {
+            return Err(GovernanceError::MotionClosed(motion_id));
+        }
+
+        let before = motion.clone();
+        motion.status = MotionStatus::Revoked;
+        motion.revoked_by = Some(proposal_id);
+
+        if let Some(audit) = &self.audit {
+            audit
+                .record(AuditEvent::EmergencyRevocation {
+                    motion_id,
+                    proposal_id,
+                    height: current_height,
+                })
+                .map_err(|e| GovernanceError::ExecutionFailed(e.to_string()))?;
+        }
+
+        // A lifted pause makes a proposal executable that was held back
+        let lifted = match before.action {
+            EmergencyAction::PauseProposal { proposal_id }
+                if before.status == MotionStatus::Executed =>
+            {
+                Some(proposal_id)
+            }
+            _ => None,
+        };
+        if let Some(paused) = lifted.filter(|id| !self.is_proposal_paused(*id)) {
+            let ready = self
+                .state
+                .get_proposal(paused)
+                .is_some_and(|p| p.ready_for_execution(current_height));
+            if ready {
+                self.events.push(GovernanceEvent::Executable {
+                    proposal_id: paused,
+                    height: current_height,
+                });
+            }
+        }
+        Ok(before)
+    }
+
+    /// Check if an executed, unrevoked council motion pauses a proposal
+    pub fn is_proposal_paused(&self, proposal_id: ProposalId) -> bool {
+        self.motions.values().any(|m| {
+            m.status == MotionStatus::Executed
+                && m.action == EmergencyAction::PauseProposal { proposal_id }
+        })
+    }
+
+    /// Get emergency motion by ID
+    pub fn get_emergency_motion(&self, motion_id: MotionId) -> Option<&EmergencyMotion> {
+        self.motions.get(&motion_id)
+    }
+
+    /// Get all emergency motions, oldest first
+    pub fn get_emergency_motions(&self) -> Vec<&EmergencyMotion> {
+        self.motions.values().collect()
+    }
+
+    /// Checkpoints published by executed council motions, as `(height, hash)`
+    /// نقاط الفحص التي نشرها مجلس الطوارئ
+    ///
+    /// A revoked motion is no longer executed, so its checkpoint drops out.
+    pub fn council_checkpoints(&self) -> Vec<(u64, [u8; 32])> {
+        self.motions
+            .values()
+            .filter(|motion| motion.status == MotionStatus::Executed)
+            .filter_map(|motion| match motion.action {
+                EmergencyAction::PublishCheckpoint { height, hash } => Some((height, hash)),
+                _ => None,
+            })
+            .collect()
+    }
 }
 
 /// Serializable governance snapshot for storage
@@ -234,9 +758,11 @@ impl GovernanceManager {
 pub struct GovernanceSnapshot {
     pub proposals: Vec<Proposal>,
     pub votes: Vec<(ProposalId, PublicKey, VoteRecord)>,
-    pub balance_snapshots: Vec<(ProposalId, PublicKey, u64)>, // Store snapshots for each proposal
+    pub balance_snapshots: Vec<(ProposalId, u64)>, // State snapshot ID for each proposal
     pub next_proposal_id: ProposalId,
     pub config: GovernanceConfig,
+    pub council: Option<EmergencyCouncil>,
+    pub motions: Vec<EmergencyMotion>,
 }
 
 impl GovernanceManager {
@@ -260,6 +786,8 @@ impl GovernanceManager {
             balance_snapshots: self.state.get_all_snapshots(),
             next_proposal_id: self.state.next_proposal_id(),
             config: self.config.clone(),
+            council: self.council.clone(),
+            motions: self.motions.values().cloned().collect(),
         }
     }
 
@@ -278,10 +806,19 @@ impl GovernanceManager {
         }
 
         // Restore balance snapshots
-        for (proposal_id, address, balance) in snapshot.balance_snapshots {
-            manager.state.store_snapshot(proposal_id, &address, balance);
+        for (proposal_id, state_snapshot) in snapshot.balance_snapshots {
+            manager
+                .state
+                .set_balance_snapshot(proposal_id, state_snapshot);
         }
 
+        manager.council = snapshot.council;
+        manager.motions = snapshot
+            .motions
+            .into_iter()
+            .map(|motion| (motion.id, motion))
+            .collect();
+
         manager
     }
 }
@@ -290,7 +827,7 @@ impl GovernanceManager {
 mod tests {
     use super::*;
     use opensyria_core::crypto::KeyPair;
-    use opensyria_storage::StateStorage;
+    use opensyria_storage::{JournalAccount, StateStorage};
 
     // Helper to create a test StateStorage
     fn create_test_state() -> StateStorage {
@@ -303,13 +840,51 @@ mod tests {
         StateStorage::open(temp_dir).unwrap()
     }
 
+    // Helper to create a proposer that can pay the default deposit twice
+    fn funded_proposer(state: &StateStorage) -> KeyPair {
+        let proposer = KeyPair::generate();
+        state
+            .set_balance(&proposer.public_key(), 5_000_000_000)
+            .unwrap();
+        proposer
+    }
+
+    // Helper to escrow the default deposit on chain, released long after
+    // the proposals tests create
+    fn escrow_deposit(state: &StateStorage, proposer: &KeyPair) -> [u8; 32] {
+        escrow_deposit_until(state, proposer, 100_000)
+    }
+
+    fn escrow_deposit_until(
+        state: &StateStorage,
+        proposer: &KeyPair,
+        release_height: u64,
+    ) -> [u8; 32] {
+        let escrow = DepositOperation::escrow(
+            963,
+            proposer.public_key(),
+            GovernanceConfig::default().min_proposal_stake,
+            release_height,
+            100,
+            state.get_nonce(&proposer.public_key()).unwrap(),
+        );
+        let escrow = escrow
+            .clone()
+            .with_signature(proposer.sign(&escrow.signing_hash()));
+        let height = state.get_state_height().unwrap() + 1;
+        state
+            .apply_block_at_height(height, std::slice::from_ref(&escrow))
+            .unwrap();
+        escrow.hash()
+    }
+
     #[test]
     fn test_create_proposal() {
         let config = GovernanceConfig::default();
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let result = manager.create_proposal(
             proposer.public_key(),
             2_000_000_000, // Enough stake
@@ -320,6 +895,7 @@ mod tests {
             "This is a test".to_string(),
             100,
             10_000_000_000,
+            escrow_deposit(&state, &proposer),
             &state,
         );
 
@@ -345,6 +921,7 @@ mod tests {
             "Desc".to_string(),
             100,
             10_000_000_000,
+            [0; 32],
             &state,
         );
 
@@ -361,7 +938,7 @@ mod tests {
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let voter = KeyPair::generate();
         
         // Set voter balance for voting power
@@ -378,6 +955,7 @@ mod tests {
                 "Desc".to_string(),
                 100,
                 10_000_000_000,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
@@ -391,6 +969,18 @@ mod tests {
         assert!(vote.is_some());
         assert_eq!(vote.unwrap().vote, Vote::Yes);
         assert_eq!(vote.unwrap().voting_power, 500_000); // Validated power
+
+        // The proposer's escrowed deposit still counts toward their power
+        manager
+            .vote_blocking(proposal_id, proposer.public_key(), Vote::Yes, &state, 150)
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_vote(proposal_id, &proposer.public_key())
+                .unwrap()
+                .voting_power,
+            5_000_000_000 - 100
+        );
     }
 
     #[test]
@@ -399,7 +989,7 @@ mod tests {
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let voter = KeyPair::generate();
         state.set_balance(&voter.public_key(), 500_000).unwrap();
 
@@ -414,6 +1004,7 @@ mod tests {
                 "Desc".to_string(),
                 100,
                 10_000_000_000,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
@@ -429,7 +1020,7 @@ mod tests {
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let voter = KeyPair::generate();
         state.set_balance(&voter.public_key(), 500_000).unwrap();
 
@@ -444,6 +1035,7 @@ mod tests {
                 "Desc".to_string(),
                 100,
                 10_000_000_000,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
@@ -460,7 +1052,7 @@ mod tests {
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let total_power = 10_000_000_000;
 
         let proposal_id = manager
@@ -472,6 +1064,7 @@ mod tests {
                 "Increase the minimum fee".to_string(),
                 100,
                 total_power,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
@@ -499,13 +1092,210 @@ mod tests {
         assert_eq!(proposal.status, ProposalStatus::Passed);
     }
 
+    #[test]
+    fn test_lifecycle_events() {
+        let mut manager = GovernanceManager::new(GovernanceConfig::default());
+        let state = create_test_state();
+        let proposer = funded_proposer(&state);
+        let total_power = 10_000_000_000;
+
+        // Voting power comes from balances at creation
+        let voters: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate()).collect();
+        for voter in &voters {
+            state
+                .set_balance(&voter.public_key(), total_power / 10)
+                .unwrap();
+        }
+
+        let proposal_id = manager
+            .create_proposal(
+                proposer.public_key(),
+                2_000_000_000,
+                ProposalType::MinimumFee { new_fee: 5000 },
+                "Increase Fee".to_string(),
+                "Increase the minimum fee".to_string(),
+                100,
+                total_power,
+                escrow_deposit(&state, &proposer),
+                &state,
+            )
+            .unwrap();
+        let events = manager.take_events();
+        assert!(matches!(
+            events.as_slice(),
+            [GovernanceEvent::VotingStarted { proposal_id: id, .. }] if *id == proposal_id
+        ));
+        assert!(manager.take_events().is_empty());
+
+        for voter in &voters {
+            manager
+                .vote_blocking(proposal_id, voter.public_key(), Vote::Yes, &state, 150)
+                .unwrap();
+        }
+
+        let proposal = manager.get_proposal(proposal_id).unwrap();
+        let ended = proposal.voting_end + 1;
+        let executable_at = proposal.voting_end + proposal.execution_delay;
+        manager.process_proposals(ended);
+        assert!(matches!(
+            manager.take_events().as_slice(),
+            [GovernanceEvent::Passed { votes_yes, .. }] if *votes_yes == 4 * (total_power / 10)
+        ));
+
+        // Announced executable once, at the first height past the delay
+        manager.process_proposals(executable_at);
+        manager.process_proposals(executable_at + 1);
+        let events = manager.take_events();
+        assert_eq!(events.len(), 1);
+        assert!(
+            matches!(events[0], GovernanceEvent::Executable { height, .. } if height == executable_at)
+        );
+
+        manager
+            .mark_proposal_executed(proposal_id, executable_at + 1)
+            .unwrap();
+        assert!(matches!(
+            manager.take_events().as_slice(),
+            [GovernanceEvent::Executed { .. }]
+        ));
+        assert_eq!(manager.processed_height(), Some(executable_at + 1));
+    }
+
+    #[test]
+    fn test_deposit_refunded_on_quorum_and_burned_otherwise() {
+        let mut manager = GovernanceManager::new(GovernanceConfig::default());
+        let state = create_test_state();
+        let deposit = manager.config.min_proposal_stake;
+        let release = 100 + manager.config.default_voting_period;
+        state.increase_supply(10_000_000_000).unwrap();
+
+        let proposer = funded_proposer(&state);
+        let stranger = funded_proposer(&state);
+        let voter = KeyPair::generate();
+        state
+            .set_balance(&voter.public_key(), 3_000_000_000)
+            .unwrap();
+        let mut create = |title: &str, escrow: [u8; 32]| {
+            manager.create_proposal(
+                proposer.public_key(),
+                2_000_000_000,
+                ProposalType::TextProposal {
+                    description: "Deposit test".to_string(),
+                },
+                title.to_string(),
+                "Deposit test".to_string(),
+                100,
+                10_000_000_000,
+                escrow,
+                &state,
+            )
+        };
+
+        // The escrow must exist, be the proposer's, outlast voting and back
+        // one proposal only
+        let early = escrow_deposit_until(&state, &proposer, release - 1);
+        let foreign = escrow_deposit_until(&state, &stranger, release);
+        for (title, escrow) in [("Early", early), ("Missing", [9; 32]), ("Foreign", foreign)] {
+            assert!(matches!(
+                create(title, escrow),
+                Err(GovernanceError::InvalidDeposit(_))
+            ));
+        }
+        let first = escrow_deposit_until(&state, &proposer, release);
+        let second = escrow_deposit_until(&state, &proposer, release);
+        let quorate = create("Quorate", first).unwrap();
+        assert!(matches!(
+            create("Reused", first),
+            Err(GovernanceError::InvalidDeposit(_))
+        ));
+        let ignored = create("Ignored", second).unwrap();
+        assert_eq!(
+            state.get_balance(&proposer.public_key()).unwrap(),
+            5_000_000_000 - 3 * deposit - 300
+        );
+        assert_eq!(
+            manager.get_proposal(quorate).unwrap().deposit_status,
+            DepositStatus::Escrowed
+        );
+
+        // Rejected, but with 30% participation against a 20% quorum
+        manager
+            .vote_blocking(quorate, voter.public_key(), Vote::No, &state, 150)
+            .unwrap();
+        assert!(manager
+            .deposit_settlements(&state, 150, 963)
+            .unwrap()
+            .is_empty());
+
+        let ended = manager.get_proposal(quorate).unwrap().voting_end;
+        manager.process_proposals(ended);
+        manager.take_events();
+        assert!(manager
+            .deposit_settlements(&state, release - 1, 963)
+            .unwrap()
+            .is_empty());
+        let settlements = manager.deposit_settlements(&state, ended, 963).unwrap();
+        assert_eq!(settlements.len(), 2);
+
+        // Deposits are settled once the transactions reach the chain
+        assert_eq!(manager.settle_deposits(&state, ended).unwrap(), 0);
+        state.apply_block_at_height(ended, &settlements).unwrap();
+        assert!(manager
+            .deposit_settlements(&state, ended + 1, 963)
+            .unwrap()
+            .is_empty());
+        assert_eq!(manager.settle_deposits(&state, ended + 1).unwrap(), 2);
+        assert_eq!(manager.settle_deposits(&state, ended + 2).unwrap(), 0);
+
+        assert_eq!(
+            manager.get_proposal(quorate).unwrap().status,
+            ProposalStatus::Rejected
+        );
+        assert_eq!(
+            manager.get_proposal(quorate).unwrap().deposit_status,
+            DepositStatus::Refunded
+        );
+        assert_eq!(
+            manager.get_proposal(ignored).unwrap().deposit_status,
+            DepositStatus::Burned
+        );
+        assert_eq!(
+            state.get_balance(&proposer.public_key()).unwrap(),
+            5_000_000_000 - 2 * deposit - 300
+        );
+        assert_eq!(state.get_total_supply().unwrap(), 10_000_000_000 - deposit);
+
+        // The refund and burn are posted to the journal of their block
+        let settled = state.get_block_journal(ended).unwrap().unwrap();
+        assert!(settled.is_balanced());
+        assert_eq!(
+            settled.net_change(&JournalAccount::Treasury),
+            -2 * deposit as i128
+        );
+        assert_eq!(settled.net_change(&JournalAccount::Burned), deposit as i128);
+        assert!(matches!(
+            manager.take_events().as_slice(),
+            [
+                GovernanceEvent::DepositRefunded { proposal_id: a, .. },
+                GovernanceEvent::DepositBurned { proposal_id: b, .. },
+            ] if *a == quorate && *b == ignored
+        ));
+
+        // Settlement state survives a snapshot
+        let restored = GovernanceManager::from_snapshot(manager.create_snapshot());
+        assert_eq!(
+            restored.get_proposal(ignored).unwrap().deposit_status,
+            DepositStatus::Burned
+        );
+    }
+
     #[test]
     fn test_snapshot_and_restore() {
         let config = GovernanceConfig::default();
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let proposal_id = manager
             .create_proposal(
                 proposer.public_key(),
@@ -517,6 +1307,7 @@ mod tests {
                 "Desc".to_string(),
                 100,
                 10_000_000_000,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
@@ -561,7 +1352,7 @@ mod tests {
         let mut manager = GovernanceManager::new(config);
         let state = create_test_state();
 
-        let proposer = KeyPair::generate();
+        let proposer = funded_proposer(&state);
         let attacker = KeyPair::generate();
         let victim = KeyPair::generate();
         
@@ -581,13 +1372,20 @@ mod tests {
                 "Testing flash loan prevention".to_string(),
                 100,
                 10_000_000_000,
+                escrow_deposit(&state, &proposer),
                 &state,
             )
             .unwrap();
         
         // Verify snapshots were created correctly
-        let attacker_snapshot = manager.state.get_snapshot_balance(proposal_id, &attacker.public_key());
-        let victim_snapshot = manager.state.get_snapshot_balance(proposal_id, &victim.public_key());
+        let attacker_snapshot = manager
+            .state
+            .get_snapshot_balance(proposal_id, &attacker.public_key(), &state)
+            .unwrap();
+        let victim_snapshot = manager
+            .state
+            .get_snapshot_balance(proposal_id, &victim.public_key(), &state)
+            .unwrap();
         assert_eq!(attacker_snapshot, Some(100), "Attacker snapshot should be 100");
         assert_eq!(victim_snapshot, Some(900), "Victim snapshot should be 900");
         
@@ -647,4 +1445,159 @@ mod tests {
         println!("  - Attacker voting power: {} (snapshot enforced)", attacker_voting_power);
         println!("  - Attack prevented: voting power locked to snapshot");
     }
+
+    #[test]
+    fn test_emergency_pause_and_revocation() {
+        use crate::council::EmergencyCouncil;
+
+        let mut manager = GovernanceManager::new(GovernanceConfig::default());
+        let state = create_test_state();
+        let members: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        manager.set_emergency_council(
+            EmergencyCouncil::new(members.iter().map(|k| k.public_key()).collect(), 2).unwrap(),
+        );
+
+        let proposer = funded_proposer(&state);
+        let fee_id = manager
+            .create_proposal(
+                proposer.public_key(),
+                2_000_000_000,
+                ProposalType::MinimumFee { new_fee: 900_000 },
+                "Raise fee".to_string(),
+                "Raise the minimum fee".to_string(),
+                100,
+                10_000_000_000,
+                escrow_deposit(&state, &proposer),
+                &state,
+            )
+            .unwrap();
+
+        let action = EmergencyAction::PauseProposal {
+            proposal_id: fee_id,
+        };
+        let reason = "Fee change would halt payments".to_string();
+        let hash = crate::council::motion_signing_hash(manager.next_motion_id(), &action, &reason);
+
+        // Outsiders cannot open motions
+        let outsider = KeyPair::generate();
+        assert!(matches!(
+            manager.propose_emergency_action(
+                outsider.public_key(),
+                action.clone(),
+                reason.clone(),
+                outsider.sign(&hash),
+                150
+            ),
+            Err(GovernanceError::NotCouncilMember)
+        ));
+
+        let motion_id = manager
+            .propose_emergency_action(
+                members[0].public_key(),
+                action,
+                reason,
+                members[0].sign(&hash),
+                150,
+            )
+            .unwrap();
+        assert_eq!(
+            manager
+                .approve_emergency_action(
+                    motion_id,
+                    members[1].public_key(),
+                    members[1].sign(&hash),
+                    151
+                )
+                .unwrap(),
+            MotionStatus::Approved
+        );
+
+        let delay = manager.emergency_council().unwrap().execution_delay;
+        assert!(manager.get_ready_emergency_actions(151).is_empty());
+        assert_eq!(manager.get_ready_emergency_actions(151 + delay).len(), 1);
+        manager
+            .mark_emergency_executed(motion_id, 151 + delay)
+            .unwrap();
+        assert!(manager.is_proposal_paused(fee_id));
+
+        // The paused proposal passes but is held back from execution
+        manager.state.get_proposal_mut(fee_id).unwrap().votes_yes = 5_000_000_000;
+        let after_vote = 100 + 10_080 + 1_440;
+        manager.process_proposals(after_vote);
+        assert_eq!(
+            manager.get_proposal(fee_id).unwrap().status,
+            ProposalStatus::Passed
+        );
+        assert!(manager.get_ready_for_execution(after_vote).is_empty());
+        assert!(matches!(
+            manager.mark_proposal_executed(fee_id, after_vote),
+            Err(GovernanceError::ProposalPaused(_))
+        ));
+
+        // A governance vote revokes the pause and survives a snapshot
+        let revoke_id = manager
+            .create_proposal(
+                proposer.public_key(),
+                2_000_000_000,
+                ProposalType::RevokeEmergencyAction {
+                    motion_id,
+                    reason: "Council overreach".to_string(),
+                },
+                "Lift pause".to_string(),
+                "Let the fee change proceed".to_string(),
+                after_vote,
+                10_000_000_000,
+                escrow_deposit(&state, &proposer),
+                &state,
+            )
+            .unwrap();
+        let revoked = manager
+            .revoke_emergency_action(motion_id, revoke_id, after_vote)
+            .unwrap();
+        assert_eq!(revoked.status, MotionStatus::Executed);
+        assert!(!manager.is_proposal_paused(fee_id));
+        assert_eq!(manager.get_ready_for_execution(after_vote).len(), 1);
+        assert!(manager.take_events().iter().any(|event| matches!(
+            event,
+            GovernanceEvent::Executable { proposal_id, .. } if *proposal_id == fee_id
+        )));
+
+        let restored = GovernanceManager::from_snapshot(manager.create_snapshot());
+        let motion = restored.get_emergency_motion(motion_id).unwrap();
+        assert_eq!(motion.status, MotionStatus::Revoked);
+        assert_eq!(motion.revoked_by, Some(revoke_id));
+        assert_eq!(motion.approvals.len(), 2);
+        assert!(restored.emergency_council().is_some());
+        assert_eq!(restored.next_motion_id(), motion_id + 1);
+    }
+
+    #[test]
+    fn test_council_checkpoints() {
+        use crate::council::EmergencyCouncil;
+
+        let mut manager = GovernanceManager::new(GovernanceConfig::default());
+        let member = KeyPair::generate();
+        manager.set_emergency_council(EmergencyCouncil::new(vec![member.public_key()], 1).unwrap());
+
+        let action = EmergencyAction::PublishCheckpoint {
+            height: 500,
+            hash: [4u8; 32],
+        };
+        let reason = "Pin the honest chain".to_string();
+        let hash = crate::council::motion_signing_hash(manager.next_motion_id(), &action, &reason);
+        let motion_id = manager
+            .propose_emergency_action(member.public_key(), action, reason, member.sign(&hash), 100)
+            .unwrap();
+
+        // Only executed motions publish their checkpoint
+        assert!(manager.council_checkpoints().is_empty());
+        let delay = manager.emergency_council().unwrap().execution_delay;
+        manager
+            .mark_emergency_executed(motion_id, 100 + delay)
+            .unwrap();
+        assert_eq!(manager.council_checkpoints(), vec![(500, [4u8; 32])]);
+
+        manager.revoke_emergency_action(motion_id, 1, 200).unwrap();
+        assert!(manager.council_checkpoints().is_empty());
+    }
 }
@@ -1,5 +1,6 @@
 use crate::types::{Proposal, ProposalId, ProposalStatus, Vote, VoteRecord};
 use opensyria_core::crypto::PublicKey;
+use opensyria_core::error_code::{self, CodedError, ErrorCode};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -23,6 +24,13 @@ pub enum GovernanceError {
     DelegationLoop,
     DelegationToSelf,
     NotEligibleToVote, // Address not snapshotted at proposal creation
+    NoEmergencyCouncil,
+    NotCouncilMember,
+    InvalidSignature,
+    MotionNotFound(u64),
+    MotionClosed(u64),
+    ProposalPaused(ProposalId),
+    InvalidDeposit(String),
 }
 
 impl std::fmt::Display for GovernanceError {
@@ -42,12 +50,49 @@ impl std::fmt::Display for GovernanceError {
             Self::DelegationLoop => write!(f, "Delegation would create a loop"),
             Self::DelegationToSelf => write!(f, "Cannot delegate to self"),
             Self::NotEligibleToVote => write!(f, "Address not eligible to vote (not snapshotted at proposal creation)"),
+            Self::NoEmergencyCouncil => write!(f, "No emergency council configured"),
+            Self::NotCouncilMember => write!(f, "Address is not an emergency council member"),
+            Self::InvalidSignature => write!(f, "Invalid council signature"),
+            Self::MotionNotFound(id) => write!(f, "Emergency motion {} not found", id),
+            Self::MotionClosed(id) => write!(f, "Emergency motion {} is no longer open", id),
+            Self::ProposalPaused(id) => {
+                write!(f, "Proposal {} is paused by the emergency council", id)
+            }
+            Self::InvalidDeposit(msg) => write!(f, "Invalid proposal deposit: {}", msg),
         }
     }
 }
 
 impl std::error::Error for GovernanceError {}
 
+impl CodedError for GovernanceError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            Self::ProposalNotFound(_) => error_code::PROPOSAL_NOT_FOUND,
+            Self::VotingNotActive => error_code::VOTING_NOT_ACTIVE,
+            Self::VotingEnded => error_code::VOTING_ENDED,
+            Self::AlreadyVoted => error_code::ALREADY_VOTED,
+            Self::InsufficientStake => error_code::INSUFFICIENT_STAKE,
+            Self::InvalidProposal => error_code::INVALID_PROPOSAL,
+            Self::NotProposer => error_code::NOT_PROPOSER,
+            Self::CannotCancel => error_code::CANNOT_CANCEL,
+            Self::NotReadyForExecution => error_code::NOT_READY_FOR_EXECUTION,
+            Self::ExecutionFailed(_) => error_code::EXECUTION_FAILED,
+            Self::InvalidParameters(_) => error_code::INVALID_PARAMETERS,
+            Self::DelegationLoop => error_code::DELEGATION_LOOP,
+            Self::DelegationToSelf => error_code::DELEGATION_TO_SELF,
+            Self::NotEligibleToVote => error_code::NOT_ELIGIBLE_TO_VOTE,
+            Self::NoEmergencyCouncil => error_code::NO_EMERGENCY_COUNCIL,
+            Self::NotCouncilMember => error_code::NOT_COUNCIL_MEMBER,
+            Self::InvalidSignature => error_code::INVALID_SIGNATURE,
+            Self::MotionNotFound(_) => error_code::MOTION_NOT_FOUND,
+            Self::MotionClosed(_) => error_code::MOTION_CLOSED,
+            Self::ProposalPaused(_) => error_code::PROPOSAL_PAUSED,
+            Self::InvalidDeposit(_) => error_code::INVALID_DEPOSIT,
+        }
+    }
+}
+
 /// In-memory governance state manager
 /// 
 /// ✅  SECURITY FIX (CRITICAL-006): Thread-safe vote recording with per-proposal locking
@@ -68,8 +113,8 @@ pub struct GovernanceState {
     /// Vote delegations: delegator -> delegate
     delegations: HashMap<PublicKey, PublicKey>,
 
-    /// Balance snapshots: proposal_id -> address -> balance
-    balance_snapshots: HashMap<ProposalId, HashMap<PublicKey, u64>>,
+    /// Balance snapshots: proposal_id -> state snapshot ID
+    balance_snapshots: HashMap<ProposalId, u64>,
 
     /// Next proposal ID
     next_proposal_id: ProposalId,
@@ -107,71 +152,69 @@ impl GovernanceState {
         self.active_proposals.push(id);
         self.proposals.insert(id, proposal);
         self.votes.insert(id, HashMap::new());
-        self.balance_snapshots.insert(id, HashMap::new()); // Initialize snapshot storage
         self.next_proposal_id = id + 1;
         id
     }
 
-    /// Store balance snapshot for a proposal
-    pub fn store_snapshot(&mut self, proposal_id: ProposalId, address: &PublicKey, balance: u64) {
-        self.balance_snapshots
-            .entry(proposal_id)
-            .or_insert_with(HashMap::new)
-            .insert(*address, balance);
-    }
-
     /// Snapshot all account balances for a proposal (flash loan attack prevention)
-    /// 
-    /// This method captures the balance of every account at proposal creation time.
-    /// Votes are weighted by these snapshot balances, not current balances.
-    /// This prevents flash loan attacks where attackers:
+    ///
+    /// Opens a copy-on-write balance snapshot in state storage instead of
+    /// copying every balance, so creating a proposal costs the same however
+    /// many accounts exist. Votes are weighted by the balance each voter held
+    /// when the snapshot opened, not their current balance. This prevents
+    /// flash loan attacks where attackers:
     /// 1. Borrow large token amounts
     /// 2. Vote with inflated balance
     /// 3. Return tokens in same block
     pub fn snapshot_balances(
-        &mut self, 
-        proposal_id: ProposalId, 
-        state_storage: &opensyria_storage::StateStorage
+        &mut self,
+        proposal_id: ProposalId,
+        state_storage: &opensyria_storage::StateStorage,
     ) -> Result<(), GovernanceError> {
-        const PAGE_SIZE: usize = 1000;
-        let mut start_key: Option<PublicKey> = None;
-        
-        loop {
-            // Get paginated balances to avoid OOM with large account sets
-            let (balances, last_key) = state_storage
-                .get_balances_paginated(start_key.as_ref(), PAGE_SIZE)
-                .map_err(|_| GovernanceError::InvalidProposal)?;
-            
-            // Store snapshots for this page
-            for (address, balance) in balances {
-                self.store_snapshot(proposal_id, &address, balance);
-            }
-            
-            // Check if we're done
-            if last_key.is_none() {
-                break;
-            }
-            start_key = last_key;
-        }
-        
+        let snapshot = state_storage
+            .open_balance_snapshot()
+            .map_err(|_| GovernanceError::InvalidProposal)?;
+        self.balance_snapshots.insert(proposal_id, snapshot);
         Ok(())
     }
 
+    /// Record the state snapshot a proposal votes against (for restore)
+    pub fn set_balance_snapshot(&mut self, proposal_id: ProposalId, snapshot: u64) {
+        self.balance_snapshots.insert(proposal_id, snapshot);
+    }
+
+    /// State snapshot ID for a proposal
+    pub fn balance_snapshot(&self, proposal_id: ProposalId) -> Option<u64> {
+        self.balance_snapshots.get(&proposal_id).copied()
+    }
+
     /// Get snapshot balance for an address at proposal creation
-    pub fn get_snapshot_balance(&self, proposal_id: ProposalId, address: &PublicKey) -> Option<u64> {
-        self.balance_snapshots
-            .get(&proposal_id)
-            .and_then(|snapshots| snapshots.get(address).copied())
+    ///
+    /// Returns `None` if the proposal has no snapshot or the address held
+    /// nothing when it was taken.
+    pub fn get_snapshot_balance(
+        &self,
+        proposal_id: ProposalId,
+        address: &PublicKey,
+        state_storage: &opensyria_storage::StateStorage,
+    ) -> Result<Option<u64>, GovernanceError> {
+        let Some(snapshot) = self.balance_snapshot(proposal_id) else {
+            return Ok(None);
+        };
+        let balance = state_storage
+            .get_balance_at_snapshot(address, snapshot)
+            .map_err(|_| GovernanceError::InvalidProposal)?;
+        Ok((balance > 0).then_some(balance))
     }
 
     /// Get all balance snapshots (for persistence)
-    pub fn get_all_snapshots(&self) -> Vec<(ProposalId, PublicKey, u64)> {
-        let mut snapshots = Vec::new();
-        for (proposal_id, balances) in &self.balance_snapshots {
-            for (address, balance) in balances {
-                snapshots.push((*proposal_id, *address, *balance));
-            }
-        }
+    pub fn get_all_snapshots(&self) -> Vec<(ProposalId, u64)> {
+        let mut snapshots: Vec<_> = self
+            .balance_snapshots
+            .iter()
+            .map(|(proposal_id, snapshot)| (*proposal_id, *snapshot))
+            .collect();
+        snapshots.sort_unstable();
         snapshots
     }
 
@@ -290,8 +333,8 @@ impl GovernanceState {
             .unwrap_or_default()
     }
 
-    /// Finalize all proposals that have ended
-    pub fn finalize_proposals(&mut self, current_height: u64) {
+    /// Finalize all proposals that have ended, returning their IDs
+    pub fn finalize_proposals(&mut self, current_height: u64) -> Vec<ProposalId> {
         let proposal_ids: Vec<ProposalId> = self.active_proposals.clone();
         let mut newly_passed = Vec::new();
         let mut to_remove = Vec::new();
@@ -311,12 +354,13 @@ impl GovernanceState {
         }
 
         // Remove from active list
-        for id in to_remove {
-            self.active_proposals.retain(|pid| *pid != id);
+        for id in &to_remove {
+            self.active_proposals.retain(|pid| pid != id);
         }
 
         // Add to pending execution
         self.pending_execution.extend(newly_passed);
+        to_remove
     }
 
     /// Get proposals ready for execution
@@ -412,22 +456,6 @@ impl GovernanceState {
         current
     }
 
-    /// Store balance snapshot for a proposal
-    pub fn store_balance_snapshot(&mut self, proposal_id: ProposalId, address: PublicKey, balance: u64) {
-        self.balance_snapshots
-            .entry(proposal_id)
-            .or_default()
-            .insert(address, balance);
-    }
-
-    /// Get balance snapshot for a proposal
-    pub fn get_balance_snapshot(&self, proposal_id: ProposalId, address: &PublicKey) -> Option<u64> {
-        self.balance_snapshots
-            .get(&proposal_id)?
-            .get(address)
-            .copied()
-    }
-
     /// Get total number of proposals
     pub fn total_proposals(&self) -> usize {
         self.proposals.len()
@@ -597,7 +625,9 @@ mod tests {
 
         let result = state.record_vote_blocking(id, vote2);
         assert!(result.is_err());
-        assert!(matches!(result.unwrap_err(), GovernanceError::AlreadyVoted));
+        let err = result.unwrap_err();
+        assert!(matches!(err, GovernanceError::AlreadyVoted));
+        assert_eq!(err.error_code().code, "already_voted");
     }
 
     #[test]
@@ -651,7 +681,7 @@ mod tests {
             .unwrap();
 
         // Finalize after voting ends
-        state.finalize_proposals(1100);
+        assert_eq!(state.finalize_proposals(1100), vec![id]);
 
         let proposal = state.get_proposal(id).unwrap();
         assert_eq!(proposal.status, ProposalStatus::Passed);
@@ -1,8 +1,14 @@
+use crate::analytics::GovernanceAnalytics;
 use crate::manager::GovernanceSnapshot;
 use bincode;
-use rocksdb::{Options, DB, BlockBasedOptions};
+use opensyria_core::GovernanceEvent;
+use rocksdb::{BlockBasedOptions, Options, WriteBatch, DB};
 use std::path::Path;
 
+/// Lifecycle events kept for a network node to publish; older ones are
+/// dropped once no node has taken them for this long
+pub const MAX_QUEUED_EVENTS: usize = 1000;
+
 /// Storage errors
 #[derive(Debug)]
 pub enum StorageError {
@@ -65,11 +71,33 @@ impl GovernanceStorage {
         Ok(Self { db })
     }
 
+    /// Open governance storage read-only, alongside a node that holds it
+    /// فتح تخزين الحوكمة للقراءة فقط بجانب عقدة عاملة
+    ///
+    /// Reads see the state as of opening; writes fail.
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
+        let db = DB::open_for_read_only(&Options::default(), path, false)?;
+        Ok(Self { db })
+    }
+
     /// Save governance snapshot
+    ///
+    /// Participation analytics are updated from the snapshot in the same write.
     pub fn save_snapshot(&self, snapshot: &GovernanceSnapshot) -> Result<(), StorageError> {
         let config = bincode::config::standard();
-        let encoded = bincode::encode_to_vec(snapshot, config)?;
-        self.db.put(b"governance_snapshot", encoded)?;
+        let mut analytics = self.stored_analytics()?.unwrap_or_default();
+        analytics.update(snapshot);
+
+        let mut batch = WriteBatch::default();
+        batch.put(
+            b"governance_snapshot",
+            bincode::encode_to_vec(snapshot, config)?,
+        );
+        batch.put(
+            b"governance_analytics",
+            bincode::encode_to_vec(&analytics, config)?,
+        );
+        self.db.write(batch)?;
         Ok(())
     }
 
@@ -90,9 +118,98 @@ impl GovernanceStorage {
         Ok(self.db.get(b"governance_snapshot")?.is_some())
     }
 
+    /// Load participation analytics
+    /// تحميل تحليلات المشاركة
+    ///
+    /// Data saved before analytics existed is analysed from the current
+    /// snapshot; the result is stored with the next saved snapshot.
+    pub fn load_analytics(&self) -> Result<GovernanceAnalytics, StorageError> {
+        if let Some(analytics) = self.stored_analytics()? {
+            return Ok(analytics);
+        }
+        match self.load_snapshot() {
+            Ok(snapshot) => Ok(GovernanceAnalytics::from_snapshot(&snapshot)),
+            Err(StorageError::NotFound) => Ok(GovernanceAnalytics::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn stored_analytics(&self) -> Result<Option<GovernanceAnalytics>, StorageError> {
+        let Some(data) = self.db.get(b"governance_analytics")? else {
+            return Ok(None);
+        };
+        let config = bincode::config::standard();
+        let (analytics, _) = bincode::decode_from_slice(&data, config)?;
+        Ok(Some(analytics))
+    }
+
+    /// Save the height proposals were last processed at
+    pub fn save_processed_height(&self, height: u64) -> Result<(), StorageError> {
+        self.db
+            .put(b"governance_processed_height", height.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Load the height proposals were last processed at, if recorded
+    pub fn load_processed_height(&self) -> Result<Option<u64>, StorageError> {
+        let Some(data) = self.db.get(b"governance_processed_height")? else {
+            return Ok(None);
+        };
+        let bytes: [u8; 8] = data.as_slice().try_into().map_err(|_| {
+            StorageError::SerializationError("Invalid processed height".to_string())
+        })?;
+        Ok(Some(u64::from_le_bytes(bytes)))
+    }
+
+    /// Move the processed height back to `height` after the blocks above it
+    /// were disconnected, so proposals are announced again on the new chain
+    pub fn rewind_processed_height(&self, height: u64) -> Result<(), StorageError> {
+        let processed = self.load_processed_height()?;
+        if processed.is_some_and(|processed| processed > height) {
+            self.save_processed_height(height)?;
+        }
+        Ok(())
+    }
+
+    /// Queue lifecycle events recorded by a command for the network node to
+    /// publish; see [`Self::take_events`]
+    pub fn queue_events(&self, events: &[GovernanceEvent]) -> Result<(), StorageError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut queued = self.queued_events()?;
+        queued.extend_from_slice(events);
+        let excess = queued.len().saturating_sub(MAX_QUEUED_EVENTS);
+        queued.drain(..excess);
+
+        let data = serde_json::to_vec(&queued)
+            .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+        self.db.put(b"governance_events", data)?;
+        Ok(())
+    }
+
+    /// Take the queued lifecycle events, oldest first
+    pub fn take_events(&self) -> Result<Vec<GovernanceEvent>, StorageError> {
+        let queued = self.queued_events()?;
+        if !queued.is_empty() {
+            self.db.delete(b"governance_events")?;
+        }
+        Ok(queued)
+    }
+
+    fn queued_events(&self) -> Result<Vec<GovernanceEvent>, StorageError> {
+        let Some(data) = self.db.get(b"governance_events")? else {
+            return Ok(Vec::new());
+        };
+        serde_json::from_slice(&data).map_err(|e| StorageError::SerializationError(e.to_string()))
+    }
+
     /// Clear all governance data
     pub fn clear(&self) -> Result<(), StorageError> {
         self.db.delete(b"governance_snapshot")?;
+        self.db.delete(b"governance_processed_height")?;
+        self.db.delete(b"governance_events")?;
+        self.db.delete(b"governance_analytics")?;
         Ok(())
     }
 }
@@ -132,6 +249,8 @@ mod tests {
             balance_snapshots: vec![],
             next_proposal_id: 2,
             config: GovernanceConfig::default(),
+            council: None,
+            motions: vec![],
         };
 
         storage.save_snapshot(&snapshot).unwrap();
@@ -140,6 +259,10 @@ mod tests {
         assert_eq!(loaded.proposals.len(), 1);
         assert_eq!(loaded.next_proposal_id, 2);
 
+        // Saving the snapshot recorded its proposal's turnout
+        let analytics = storage.load_analytics().unwrap();
+        assert_eq!(analytics.proposal(1).unwrap().voters, 0);
+
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
 
@@ -158,6 +281,8 @@ mod tests {
             balance_snapshots: vec![],
             next_proposal_id: 2,
             config: GovernanceConfig::default(),
+            council: None,
+            motions: vec![],
         };
 
         storage.save_snapshot(&snapshot).unwrap();
@@ -179,13 +304,34 @@ mod tests {
             balance_snapshots: vec![],
             next_proposal_id: 1,
             config: GovernanceConfig::default(),
+            council: None,
+            motions: vec![],
         };
 
         storage.save_snapshot(&snapshot).unwrap();
         assert!(storage.has_snapshot().unwrap());
 
+        storage.save_processed_height(42).unwrap();
+        assert_eq!(storage.load_processed_height().unwrap(), Some(42));
+        storage.rewind_processed_height(50).unwrap();
+        assert_eq!(storage.load_processed_height().unwrap(), Some(42));
+        storage.rewind_processed_height(40).unwrap();
+        assert_eq!(storage.load_processed_height().unwrap(), Some(40));
+
+        let executed = GovernanceEvent::Executed {
+            proposal_id: 1,
+            height: 40,
+        };
+        storage
+            .queue_events(std::slice::from_ref(&executed))
+            .unwrap();
+        assert_eq!(storage.take_events().unwrap(), vec![executed]);
+        assert!(storage.take_events().unwrap().is_empty());
+
         storage.clear().unwrap();
         assert!(!storage.has_snapshot().unwrap());
+        assert_eq!(storage.load_processed_height().unwrap(), None);
+        assert_eq!(storage.load_analytics().unwrap().proposals().count(), 0);
 
         let _ = std::fs::remove_dir_all(&temp_dir);
     }
@@ -1,4 +1,5 @@
 use opensyria_core::crypto::PublicKey;
+use opensyria_storage::StateStorage;
 use serde::{Deserialize, Serialize};
 
 /// Unique identifier for a proposal
@@ -40,6 +41,21 @@ pub enum ProposalType {
 
     /// Custom text proposal (non-binding)
     TextProposal { description: String },
+
+    /// Freeze an address so it can no longer spend (e.g. stolen funds)
+    FreezeAccount { address: PublicKey, reason: String },
+
+    /// Lift a previous freeze
+    UnfreezeAccount { address: PublicKey, reason: String },
+
+    /// Revoke an emergency council motion and undo its effect
+    RevokeEmergencyAction { motion_id: u64, reason: String },
+
+    /// Whitelist a key to publish exchange rates on chain
+    AddOracle { oracle: PublicKey, reason: String },
+
+    /// Remove a key from the oracle whitelist
+    RemoveOracle { oracle: PublicKey, reason: String },
 }
 
 /// Voting choice
@@ -76,6 +92,20 @@ pub enum ProposalStatus {
     Executed,
 }
 
+/// Where a proposal's escrowed deposit stands
+/// حالة وديعة الاقتراح المحجوزة
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub enum DepositStatus {
+    /// Held while the proposal is open
+    Escrowed,
+    /// Returned to the proposer because voting met quorum
+    Refunded,
+    /// Destroyed because voting closed without quorum
+    Burned,
+}
+
 /// Governance proposal
 #[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
 pub struct Proposal {
@@ -95,6 +125,9 @@ pub struct Proposal {
     pub votes_no: u64,
     pub votes_abstain: u64,
     pub total_voting_power: u64, // Total stake at proposal creation
+    pub deposit: u64,            // Escrowed from the proposer at creation
+    pub deposit_status: DepositStatus,
+    pub deposit_escrow: [u8; 32], // Transaction that escrowed the deposit
 }
 
 impl Proposal {
@@ -120,6 +153,11 @@ impl Proposal {
             ProposalType::BlockSizeLimit { .. } => (30, 60),
             ProposalType::BlockReward { .. } => (40, 66),
             ProposalType::TextProposal { .. } => (20, 50), // Simple majority
+            ProposalType::FreezeAccount { .. } => (40, 66),
+            ProposalType::UnfreezeAccount { .. } => (30, 60),
+            ProposalType::RevokeEmergencyAction { .. } => (40, 66),
+            ProposalType::AddOracle { .. } => (40, 66),
+            ProposalType::RemoveOracle { .. } => (30, 60),
         };
 
         Self {
@@ -139,6 +177,9 @@ impl Proposal {
             votes_no: 0,
             votes_abstain: 0,
             total_voting_power,
+            deposit: 0,
+            deposit_status: DepositStatus::Escrowed,
+            deposit_escrow: [0; 32],
         }
     }
 
@@ -213,6 +254,11 @@ impl Proposal {
         }
     }
 
+    /// Whether voting has closed and the escrowed deposit awaits refund or burn
+    pub fn deposit_unsettled(&self) -> bool {
+        self.deposit_status == DepositStatus::Escrowed && self.status != ProposalStatus::Active
+    }
+
     /// Check if proposal is ready for execution
     pub fn ready_for_execution(&self, current_height: u64) -> bool {
         self.status == ProposalStatus::Passed
@@ -223,7 +269,8 @@ impl Proposal {
 /// Governance configuration parameters
 #[derive(Debug, Clone, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
 pub struct GovernanceConfig {
-    /// Minimum stake required to create a proposal
+    /// Minimum stake required to create a proposal, escrowed as a deposit
+    /// that is refunded if voting meets quorum and burned otherwise
     pub min_proposal_stake: u64,
 
     /// Default voting period in blocks
@@ -442,6 +489,64 @@ mod tests {
         assert_eq!(text.required_quorum, 20);
         assert_eq!(text.required_threshold, 50);
     }
+
+    #[test]
+    fn test_freeze_proposal_updates_frozen_set() {
+        let dir = std::env::temp_dir().join(format!(
+            "gov_freeze_{}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        let state = StateStorage::open(dir.clone()).unwrap();
+        let address = opensyria_core::crypto::KeyPair::generate().public_key();
+
+        let freeze = ProposalType::FreezeAccount {
+            address,
+            reason: "Funds stolen in exchange breach".to_string(),
+        };
+        assert!(freeze.validate().is_ok());
+        assert!(freeze.apply_to_state(3, &state).unwrap());
+        assert_eq!(state.get_freeze(&address).unwrap(), Some(3));
+
+        assert!(ProposalType::UnfreezeAccount {
+            address,
+            reason: "Funds returned".to_string(),
+        }
+        .apply_to_state(4, &state)
+        .unwrap());
+        assert!(!state.is_frozen(&address).unwrap());
+
+        assert!(ProposalType::FreezeAccount {
+            address,
+            reason: " ".to_string(),
+        }
+        .validate()
+        .is_err());
+
+        assert!(ProposalType::AddOracle {
+            oracle: address,
+            reason: "Central bank rate feed".to_string(),
+        }
+        .apply_to_state(5, &state)
+        .unwrap());
+        // Whitelist changes take effect with the next block
+        assert!(state.get_oracles().unwrap().is_empty());
+        state.apply_block_at_height(1, &[]).unwrap();
+        assert_eq!(state.get_oracles().unwrap(), vec![(address, 5)]);
+        assert!(ProposalType::RemoveOracle {
+            oracle: address,
+            reason: "Feed discontinued".to_string(),
+        }
+        .apply_to_state(6, &state)
+        .unwrap());
+        state.apply_block_at_height(2, &[]).unwrap();
+        assert!(!state.is_oracle(&address).unwrap());
+
+        drop(state);
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
 
 impl ProposalType {
@@ -501,6 +606,40 @@ impl ProposalType {
                 // Text proposals are non-binding, no validation needed
                 Ok(())
             }
+            ProposalType::FreezeAccount { reason, .. }
+            | ProposalType::UnfreezeAccount { reason, .. }
+            | ProposalType::RevokeEmergencyAction { reason, .. }
+            | ProposalType::AddOracle { reason, .. }
+            | ProposalType::RemoveOracle { reason, .. } => {
+                // Freezing funds and trusting oracles must be justified on-chain
+                if reason.trim().is_empty() {
+                    return Err("reason must not be empty");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply an executed freeze, unfreeze or oracle whitelist change to the
+    /// state
+    /// تطبيق قرار التجميد أو رفعه أو تعديل قائمة الأوراكل على الحالة
+    ///
+    /// Returns `Ok(false)` for proposals that do not touch the frozen set or
+    /// the oracle whitelist.
+    pub fn apply_to_state(
+        &self,
+        proposal_id: ProposalId,
+        state: &StateStorage,
+    ) -> Result<bool, opensyria_storage::StorageError> {
+        match self {
+            ProposalType::FreezeAccount { address, .. } => {
+                state.freeze_account(address, proposal_id)?
+            }
+            ProposalType::UnfreezeAccount { address, .. } => state.unfreeze_account(address)?,
+            ProposalType::AddOracle { oracle, .. } => state.add_oracle(oracle, proposal_id)?,
+            ProposalType::RemoveOracle { oracle, .. } => state.remove_oracle(oracle)?,
+            _ => return Ok(false),
         }
+        Ok(true)
     }
 }
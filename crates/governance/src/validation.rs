@@ -259,6 +259,17 @@ impl ProposalValidator {
                 // Text proposals have minimal validation (already covered by general description check)
                 Ok(())
             }
+
+            ProposalType::FreezeAccount { reason, .. }
+            | ProposalType::UnfreezeAccount { reason, .. }
+            | ProposalType::RevokeEmergencyAction { reason, .. }
+            | ProposalType::AddOracle { reason, .. }
+            | ProposalType::RemoveOracle { reason, .. } => {
+                if reason.trim().is_empty() {
+                    return Err(ProposalValidationError::EmptyDescription);
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -0,0 +1,168 @@
+//! `?fields=` sparse fieldsets for GET responses
+//! اختيار الحقول في استجابات GET
+//!
+//! Any GET endpoint returning JSON honours `?fields=a,b.c` (see
+//! [`opensyria_core::fields`]), e.g.
+//! `/api/blocks/42?fields=height,hash,transactions.hash`. The body is trimmed
+//! after the handler (and any caching layer inside this one) has run, so
+//! handlers and ETags stay unaware of it. Error responses and non-JSON bodies
+//! such as server-sent events pass through untouched.
+
+use crate::json::{map_json, query_params, ResponseBody};
+use crate::ResponseFuture;
+use http::{Method, Request, Response, Uri};
+use http_body::Body;
+use http_body_util::Either;
+use opensyria_core::FieldSelection;
+use std::fmt::Display;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Keep only the requested fields of a GET request's JSON response
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FieldsLayer;
+
+impl<S> Layer<S> for FieldsLayer {
+    type Service = Fields<S>;
+
+    fn layer(&self, inner: S) -> Fields<S> {
+        Fields { inner }
+    }
+}
+
+/// Service applying [`FieldsLayer`]
+#[derive(Debug, Clone)]
+pub struct Fields<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Fields<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: Body + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Display,
+{
+    type Response = Response<ResponseBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<Self::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let selection = if request.method() == Method::GET {
+            requested_fields(request.uri())
+        } else {
+            None
+        };
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            Ok(match selection {
+                Some(selection) => map_json(response, |value| selection.apply(value)).await,
+                None => response.map(Either::Left),
+            })
+        })
+    }
+}
+
+/// Field selection from the `fields` query parameter, if any
+fn requested_fields(uri: &Uri) -> Option<FieldSelection> {
+    FieldSelection::parse(query_params(uri).get("fields")?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::{to_bytes, Body},
+        http::StatusCode,
+        response::Json,
+        routing::get,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    fn router() -> Router {
+        Router::new()
+            .route(
+                "/account",
+                get(|| async { Json(json!({"address": "7d86", "balance": 5, "nonce": 2})) })
+                    .post(|| async { Json(json!({"address": "7d86", "balance": 5})) }),
+            )
+            .route(
+                "/missing",
+                get(|| async {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(json!({"error": "not found", "code": 404})),
+                    )
+                }),
+            )
+            .layer(FieldsLayer)
+    }
+
+    async fn call(method: Method, uri: &str) -> Value {
+        let request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap();
+        let response = router().oneshot(request).await.unwrap();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_response_trimmed_to_fields() {
+        assert_eq!(
+            call(Method::GET, "/account?fields=balance%2Cnonce").await,
+            json!({"balance": 5, "nonce": 2})
+        );
+        assert_eq!(
+            call(Method::GET, "/account").await,
+            json!({"address": "7d86", "balance": 5, "nonce": 2})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_and_other_methods_untouched() {
+        assert_eq!(
+            call(Method::GET, "/missing?fields=code").await,
+            json!({"error": "not found", "code": 404})
+        );
+        assert_eq!(
+            call(Method::POST, "/account?fields=balance").await,
+            json!({"address": "7d86", "balance": 5})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_older_axum_routers() {
+        use axum07::{response::Json, routing::get, Router};
+
+        let router = Router::new()
+            .route(
+                "/block",
+                get(|| async { Json(json!({"height": 42, "hash": "00ab"})) }),
+            )
+            .layer(FieldsLayer);
+        let request = Request::builder()
+            .uri("/block?fields=height")
+            .body(axum07::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let bytes = axum07::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::from_slice::<Value>(&bytes).unwrap(),
+            json!({"height": 42})
+        );
+    }
+}
@@ -0,0 +1,78 @@
+//! Rewriting JSON response bodies
+
+use bytes::Bytes;
+use http::{header, Response, StatusCode, Uri};
+use http_body::Body;
+use http_body_util::{BodyExt, Either, Full};
+use opensyria_core::error_code::{self, ErrorCode};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+/// Body of a response passed through a layer: the inner body untouched, or
+/// a rewritten one
+pub type ResponseBody<B> = Either<B, Full<Bytes>>;
+
+/// Query parameters of a request URI
+pub(crate) fn query_params(uri: &Uri) -> HashMap<String, String> {
+    form_urlencoded::parse(uri.query().unwrap_or_default().as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Rewrite the JSON body of a successful response; other responses pass
+/// through untouched
+pub(crate) async fn map_json<B>(
+    response: Response<B>,
+    rewrite: impl FnOnce(&mut Value),
+) -> Response<ResponseBody<B>>
+where
+    B: Body,
+    B::Error: Display,
+{
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !response.status().is_success() || !is_json {
+        return response.map(Either::Left);
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match body.collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(e) => return internal_error(format!("Failed to read response: {}", e)),
+    };
+    let Ok(mut value) = serde_json::from_slice::<Value>(&bytes) else {
+        return Response::from_parts(parts, Either::Right(Full::new(bytes)));
+    };
+
+    rewrite(&mut value);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    let body = serde_json::to_vec(&value).expect("JSON values serialize");
+    Response::from_parts(parts, Either::Right(Full::new(Bytes::from(body))))
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+    #[serde(flatten)]
+    code: ErrorCode,
+}
+
+fn internal_error<B>(error: String) -> Response<ResponseBody<B>> {
+    let body = serde_json::to_vec(&ErrorResponse {
+        error,
+        code: error_code::INTERNAL_ERROR,
+    })
+    .expect("error responses serialize");
+    let mut response = Response::new(Either::Right(Full::new(Bytes::from(body))));
+    *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    response
+}
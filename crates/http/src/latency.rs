@@ -0,0 +1,118 @@
+//! Request latency middleware feeding the shared Prometheus histograms
+//! وسيط قياس زمن الطلبات لمقاييس Prometheus
+
+use crate::ResponseFuture;
+use http::{header, Extensions, HeaderValue, Request, Response};
+use opensyria_metrics::http::{endpoint_latencies, RequestTimer};
+use opensyria_metrics::telemetry::link_remote_parent;
+use serde_json::{json, Value};
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::Instrument;
+
+/// Route template of a request, read from the router's own extension
+/// (axum's `MatchedPath`, whose type differs between releases)
+pub type RouteTemplate = fn(&Extensions) -> Option<&str>;
+
+/// Time every request under its service label, route template and status
+/// قياس زمن كل طلب حسب الخدمة والمسار والحالة
+#[derive(Debug, Clone, Copy)]
+pub struct LatencyLayer {
+    service: &'static str,
+    route: RouteTemplate,
+}
+
+impl LatencyLayer {
+    pub fn new(service: &'static str, route: RouteTemplate) -> Self {
+        Self { service, route }
+    }
+}
+
+impl<S> Layer<S> for LatencyLayer {
+    type Service = Latency<S>;
+
+    fn layer(&self, inner: S) -> Latency<S> {
+        Latency {
+            inner,
+            layer: *self,
+        }
+    }
+}
+
+/// Service applying [`LatencyLayer`]
+#[derive(Debug, Clone)]
+pub struct Latency<S> {
+    inner: S,
+    layer: LatencyLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Latency<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // Route templates keep label cardinality bounded (no raw addresses)
+        let endpoint = (self.layer.route)(request.extensions()).unwrap_or("unmatched");
+        let timer = RequestTimer::start(self.layer.service, endpoint, request.method().as_str());
+
+        // Join the caller's distributed trace when it sends W3C trace context
+        let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok());
+        link_remote_parent(timer.span(), header("traceparent"), header("tracestate"));
+
+        let span = timer.span().clone();
+        let response = span.in_scope(|| self.inner.call(request)).instrument(span);
+        Box::pin(async move {
+            let response = response.await?;
+            timer.finish(response.status().as_u16());
+            Ok(response)
+        })
+    }
+}
+
+/// GET /metrics - Prometheus scrape of this process
+pub async fn prometheus_metrics() -> Response<String> {
+    let mut response = Response::new(opensyria_metrics::gather_metrics());
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; version=0.0.4"),
+    );
+    response
+}
+
+/// Per-endpoint p50/p95/p99 latency in milliseconds, slowest p95 first
+/// زمن الاستجابة لكل نقطة نهاية، الأبطأ أولاً
+pub async fn endpoint_latency() -> Response<String> {
+    let mut latencies = endpoint_latencies();
+    latencies.sort_by(|a, b| b.p95.total_cmp(&a.p95));
+
+    let endpoints: Vec<Value> = latencies
+        .into_iter()
+        .map(|l| {
+            json!({
+                "service": l.service,
+                "endpoint": l.endpoint,
+                "requests": l.count,
+                "p50_ms": l.p50 * 1000.0,
+                "p95_ms": l.p95 * 1000.0,
+                "p99_ms": l.p99 * 1000.0,
+            })
+        })
+        .collect();
+
+    let mut response = Response::new(json!({ "endpoints": endpoints }).to_string());
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("application/json"),
+    );
+    response
+}
@@ -0,0 +1,26 @@
+//! HTTP middleware shared by the wallet API and the block explorer
+//! الوسائط المشتركة بين واجهة المحفظة ومستكشف الكتل
+//!
+//! The two servers are built on different axum releases, so everything here
+//! is a tower [`Layer`](tower_layer::Layer) over plain `http` requests and
+//! responses, and plugs into either router with `.layer(...)`:
+//!
+//! - [`FieldsLayer`] trims GET responses to `?fields=`
+//! - [`LocaleLayer`] adds `?locale=` display strings
+//! - [`LatencyLayer`] records per-endpoint latency and joins W3C traces
+
+pub mod fields;
+mod json;
+pub mod latency;
+pub mod locale;
+
+pub use fields::FieldsLayer;
+pub use json::ResponseBody;
+pub use latency::{endpoint_latency, prometheus_metrics, LatencyLayer};
+pub use locale::LocaleLayer;
+
+use std::future::Future;
+use std::pin::Pin;
+
+/// Response future of the layers' services
+pub type ResponseFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
@@ -0,0 +1,132 @@
+//! `?locale=` display strings in JSON responses
+//! نصوص العرض المحلية في الاستجابات
+//!
+//! With `?locale=ar-SY` (or `en`), and optionally `&calendar=hijri`, every
+//! successful JSON response gains `<field>_display` strings next to the
+//! amounts, timestamps and counts it carries (see
+//! [`opensyria_core::locale`]). Raw values are never changed. Placed outside
+//! [`FieldsLayer`](crate::FieldsLayer), display strings follow the selected
+//! fields.
+
+use crate::json::{map_json, query_params, ResponseBody};
+use crate::ResponseFuture;
+use http::{header, HeaderValue, Request, Response, Uri};
+use http_body::Body;
+use http_body_util::Either;
+use opensyria_core::DisplayFormat;
+use std::fmt::Display;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Add display strings for the requested locale to a JSON response
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LocaleLayer;
+
+impl<S> Layer<S> for LocaleLayer {
+    type Service = Locale<S>;
+
+    fn layer(&self, inner: S) -> Locale<S> {
+        Locale { inner }
+    }
+}
+
+/// Service applying [`LocaleLayer`]
+#[derive(Debug, Clone)]
+pub struct Locale<S> {
+    inner: S,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for Locale<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: Body + Send + 'static,
+    ResBody::Data: Send,
+    ResBody::Error: Display,
+{
+    type Response = Response<ResponseBody<ResBody>>;
+    type Error = S::Error;
+    type Future = ResponseFuture<Self::Response, S::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let format = requested_format(request.uri());
+        let response = self.inner.call(request);
+        Box::pin(async move {
+            let response = response.await?;
+            let Some(format) = format else {
+                return Ok(response.map(Either::Left));
+            };
+            let mut response = map_json(response, |value| format.annotate(value)).await;
+            response.headers_mut().insert(
+                header::CONTENT_LANGUAGE,
+                HeaderValue::from_static(format.locale.tag()),
+            );
+            Ok(response)
+        })
+    }
+}
+
+/// Display format from the `locale` and `calendar` query parameters, if any
+fn requested_format(uri: &Uri) -> Option<DisplayFormat> {
+    let params = query_params(uri);
+    DisplayFormat::from_params(
+        params.get("locale").map(String::as_str),
+        params.get("calendar").map(String::as_str),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FieldsLayer;
+    use axum::{
+        body::{to_bytes, Body},
+        response::Json,
+        routing::get,
+        Router,
+    };
+    use serde_json::{json, Value};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_display_strings_follow_fields() {
+        let router = Router::new()
+            .route(
+                "/balance",
+                get(|| async {
+                    Json(json!({"address": "7d86", "balance": 2_500_000, "nonce": 4}))
+                }),
+            )
+            .layer(FieldsLayer)
+            .layer(LocaleLayer);
+        let call = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+                let response = router.oneshot(request).await.unwrap();
+                let language = response.headers().get(header::CONTENT_LANGUAGE).cloned();
+                let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                (serde_json::from_slice::<Value>(&bytes).unwrap(), language)
+            }
+        };
+
+        let (body, language) = call("/balance?locale=ar-SY&fields=balance").await;
+        assert_eq!(
+            body,
+            json!({"balance": 2_500_000, "balance_display": "٢٫٥ ل.س"})
+        );
+        assert_eq!(language.unwrap(), "ar-SY");
+
+        let (body, language) = call("/balance").await;
+        assert_eq!(
+            body,
+            json!({"address": "7d86", "balance": 2_500_000, "nonce": 4})
+        );
+        assert!(language.is_none());
+    }
+}
@@ -1,6 +1,6 @@
 use crate::token::IdentityToken;
 use opensyria_core::crypto::PublicKey;
-use rocksdb::{DB, Options, BlockBasedOptions};
+use rocksdb::{BlockBasedOptions, Direction, IteratorMode, Options, DB};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -198,6 +198,41 @@ impl IdentityStorage {
         Ok(count)
     }
 
+    /// List tokens in ID order, starting after `after` (for pagination)
+    pub fn list_tokens(
+        &self,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<IdentityToken>, StorageError> {
+        let prefix = b"token:";
+        let start = match after {
+            Some(id) => Self::token_key(id),
+            None => prefix.to_vec(),
+        };
+
+        let mut tokens = Vec::new();
+        let iter = self
+            .db
+            .iterator(IteratorMode::From(&start, Direction::Forward));
+        for item in iter {
+            let (key, value) = item.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            if !key.starts_with(prefix) || tokens.len() >= limit {
+                break;
+            }
+            // The cursor itself was returned on the previous page
+            if after.is_some() && *key == *start {
+                continue;
+            }
+
+            let (token, _): (IdentityToken, _) =
+                bincode::decode_from_slice(&value, bincode::config::standard())
+                    .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+            tokens.push(token);
+        }
+
+        Ok(tokens)
+    }
+
     // Helper methods for key generation
     fn token_key(token_id: &str) -> Vec<u8> {
         format!("token:{}", token_id).into_bytes()
@@ -308,6 +343,40 @@ mod tests {
         assert_eq!(tokens.len(), 3);
     }
 
+    #[test]
+    fn test_list_tokens_paginated() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = IdentityStorage::open(temp_dir.path()).unwrap();
+
+        let metadata = HeritageMetadata::new(
+            "Test".to_string(),
+            "Test Description".to_string(),
+            Some("تجريبي".to_string()),
+        );
+
+        for i in 0..5 {
+            let token = IdentityToken::new(
+                format!("token-{}", i),
+                PublicKey([i as u8; 32]),
+                TokenType::HeritageSite,
+                CulturalCategory::Ancient,
+                metadata.clone(),
+                0,
+                1000,
+            )
+            .unwrap();
+            storage.store_token(&token).unwrap();
+        }
+
+        let first = storage.list_tokens(None, 2).unwrap();
+        let ids: Vec<_> = first.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["token-0", "token-1"]);
+
+        let rest = storage.list_tokens(Some("token-1"), 10).unwrap();
+        let ids: Vec<_> = rest.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["token-2", "token-3", "token-4"]);
+    }
+
     #[test]
     fn test_delete_token() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,242 @@
+//! Incremental export driven by the sink cursor
+//! تصدير تدريجي يعتمد على مؤشر المصرف
+
+use crate::rows::{BlockExport, GovernanceExport};
+use crate::sink::ExportSink;
+use anyhow::{anyhow, Result};
+use opensyria_core::Block;
+use opensyria_governance::GovernanceSnapshot;
+use opensyria_storage::BlockchainStorage;
+
+/// Default number of blocks exported per catch-up round
+pub const DEFAULT_EXPORT_BATCH: u64 = 500;
+
+/// Read access to the active chain (heights start at 1 for genesis)
+pub trait BlockSource {
+    fn tip_height(&self) -> Result<u64>;
+    fn block_hash_at(&self, height: u64) -> Result<Option<[u8; 32]>>;
+    fn block_at(&self, height: u64) -> Result<Option<Block>>;
+}
+
+impl BlockSource for BlockchainStorage {
+    fn tip_height(&self) -> Result<u64> {
+        Ok(self.get_chain_height()?)
+    }
+
+    fn block_hash_at(&self, height: u64) -> Result<Option<[u8; 32]>> {
+        Ok(self.get_block_hash_by_height(height)?)
+    }
+
+    fn block_at(&self, height: u64) -> Result<Option<Block>> {
+        Ok(self.get_block_by_height(height)?)
+    }
+}
+
+/// Outcome of one catch-up round
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportReport {
+    /// Blocks written this round
+    pub exported: u64,
+    /// Blocks rolled back because the chain reorganized under the cursor
+    pub rewound: u64,
+    /// Cursor height after the round
+    pub height: u64,
+    /// Chain tip seen at the start of the round
+    pub tip: u64,
+}
+
+impl ExportReport {
+    /// Whether the sink has reached the tip
+    pub fn caught_up(&self) -> bool {
+        self.height >= self.tip
+    }
+}
+
+/// Mirrors the chain into an [`ExportSink`]
+/// ينسخ السلسلة إلى مصرف التصدير
+pub struct ExportPipeline {
+    sink: Box<dyn ExportSink>,
+    batch: u64,
+}
+
+impl ExportPipeline {
+    /// Wrap a sink, creating its tables
+    pub async fn new(sink: Box<dyn ExportSink>, batch: u64) -> Result<Self> {
+        sink.init().await?;
+        Ok(Self {
+            sink,
+            batch: batch.max(1),
+        })
+    }
+
+    pub fn sink(&self) -> &dyn ExportSink {
+        self.sink.as_ref()
+    }
+
+    /// Export up to one batch of blocks after the cursor
+    ///
+    /// If the block under the cursor is no longer on the active chain, the
+    /// sink is first rewound to the last height both sides agree on.
+    pub async fn sync_blocks(&self, source: &impl BlockSource) -> Result<ExportReport> {
+        let tip = source.tip_height()?;
+        let mut height = self.sink.cursor().await?.map_or(0, |c| c.height);
+
+        let fork = self.find_fork_point(source, height).await?;
+        let rewound = height - fork;
+        if rewound > 0 {
+            tracing::warn!(
+                "Chain reorganized below export cursor, rewinding {} -> {}",
+                height,
+                fork
+            );
+            self.sink.rewind(fork).await?;
+            height = fork;
+        }
+
+        let end = tip.min(height + self.batch);
+        for h in height + 1..=end {
+            let block = source
+                .block_at(h)?
+                .ok_or_else(|| anyhow!("Block {} missing from storage", h))?;
+            self.sink
+                .write_block(&BlockExport::from_block(h, &block)?)
+                .await?;
+        }
+
+        Ok(ExportReport {
+            exported: end - height,
+            rewound,
+            height: end,
+            tip,
+        })
+    }
+
+    /// Export everything up to the current tip
+    pub async fn catch_up(&self, source: &impl BlockSource) -> Result<ExportReport> {
+        let mut total = ExportReport::default();
+        loop {
+            let report = self.sync_blocks(source).await?;
+            total.exported += report.exported;
+            total.rewound += report.rewound;
+            total.height = report.height;
+            total.tip = report.tip;
+            if report.exported == 0 || report.caught_up() {
+                return Ok(total);
+            }
+        }
+    }
+
+    /// Upsert the current governance proposals and votes
+    pub async fn sync_governance(&self, snapshot: &GovernanceSnapshot) -> Result<usize> {
+        let export = GovernanceExport::from_snapshot(snapshot)?;
+        self.sink.write_governance(&export).await?;
+        Ok(export.proposals.len())
+    }
+
+    /// Highest height at or below `height` whose hash matches the chain
+    async fn find_fork_point(&self, source: &impl BlockSource, height: u64) -> Result<u64> {
+        let mut h = height;
+        while h > 0 {
+            let exported = self.sink.block_hash(h).await?;
+            let on_chain = source.block_hash_at(h)?.map(hex::encode);
+            if exported.is_some() && exported == on_chain {
+                break;
+            }
+            h -= 1;
+        }
+        Ok(h)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SqliteSink;
+    use opensyria_core::{crypto::KeyPair, Transaction};
+
+    /// In-memory chain; index 0 holds height 1
+    struct MemoryChain(Vec<Block>);
+
+    impl MemoryChain {
+        fn extend(&mut self, count: usize) {
+            for _ in 0..count {
+                let previous = self.0.last().map_or([0u8; 32], |b| b.hash());
+                let height = self.0.len() as u64 + 1;
+                let miner = KeyPair::generate().public_key();
+                let coinbase = Transaction::coinbase(1, miner, height, 0).unwrap();
+                self.0.push(Block::new(previous, vec![coinbase], 16));
+            }
+        }
+    }
+
+    impl BlockSource for MemoryChain {
+        fn tip_height(&self) -> Result<u64> {
+            Ok(self.0.len() as u64)
+        }
+
+        fn block_hash_at(&self, height: u64) -> Result<Option<[u8; 32]>> {
+            Ok(self.block_at(height)?.map(|b| b.hash()))
+        }
+
+        fn block_at(&self, height: u64) -> Result<Option<Block>> {
+            Ok(height
+                .checked_sub(1)
+                .and_then(|i| self.0.get(i as usize))
+                .cloned())
+        }
+    }
+
+    async fn pipeline(batch: u64) -> ExportPipeline {
+        ExportPipeline::new(Box::new(SqliteSink::in_memory().unwrap()), batch)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_resumes_from_cursor() {
+        let mut chain = MemoryChain(Vec::new());
+        chain.extend(5);
+        let pipeline = pipeline(2).await;
+
+        // One batch at a time
+        let report = pipeline.sync_blocks(&chain).await.unwrap();
+        assert_eq!(report.exported, 2);
+        assert!(!report.caught_up());
+
+        let report = pipeline.catch_up(&chain).await.unwrap();
+        assert_eq!(report.exported, 3);
+        assert_eq!(report.height, 5);
+
+        // New blocks after "downtime" continue from the cursor
+        chain.extend(3);
+        let report = pipeline.catch_up(&chain).await.unwrap();
+        assert_eq!(report.exported, 3);
+
+        let cursor = pipeline.sink().cursor().await.unwrap().unwrap();
+        assert_eq!(cursor.height, 8);
+        assert_eq!(cursor.block_hash, hex::encode(chain.0[7].hash()));
+    }
+
+    #[tokio::test]
+    async fn test_reorg_rewinds_sink() {
+        let mut chain = MemoryChain(Vec::new());
+        chain.extend(4);
+        let pipeline = pipeline(DEFAULT_EXPORT_BATCH).await;
+        pipeline.catch_up(&chain).await.unwrap();
+
+        // Replace the last two blocks with a longer fork
+        chain.0.truncate(2);
+        chain.extend(3);
+
+        let report = pipeline.catch_up(&chain).await.unwrap();
+        assert_eq!(report.rewound, 2);
+        assert_eq!(report.exported, 3);
+
+        for height in 1..=5 {
+            assert_eq!(
+                pipeline.sink().block_hash(height).await.unwrap(),
+                Some(hex::encode(chain.0[height as usize - 1].hash()))
+            );
+        }
+    }
+}
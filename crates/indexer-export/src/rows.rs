@@ -0,0 +1,248 @@
+//! Flattened rows written to the analytics schema
+//! صفوف مسطحة تُكتب إلى مخطط التحليلات
+//!
+//! Hashes and addresses are lowercase hex. Integers are stored as SQL
+//! `BIGINT`, so values beyond `i64::MAX` are rejected rather than wrapped.
+
+use anyhow::{anyhow, Result};
+use opensyria_core::{crypto::PublicKey, Block};
+use opensyria_governance::{GovernanceSnapshot, Proposal, VoteRecord};
+use std::collections::BTreeMap;
+
+/// One row of the `blocks` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockRow {
+    pub height: i64,
+    pub hash: String,
+    pub previous_hash: String,
+    pub timestamp: i64,
+    pub difficulty: i64,
+    pub nonce: i64,
+    pub tx_count: i64,
+    pub total_fees: i64,
+}
+
+/// One row of the `transactions` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxRow {
+    pub hash: String,
+    pub block_height: i64,
+    pub tx_index: i64,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: i64,
+    pub fee: i64,
+    pub nonce: i64,
+    pub is_coinbase: bool,
+}
+
+/// Net balance change of one address in one block (`balance_changes` table)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceDelta {
+    pub block_height: i64,
+    pub address: String,
+    pub delta: i64,
+}
+
+/// Everything a single block contributes to the export
+/// كل ما تضيفه كتلة واحدة إلى التصدير
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockExport {
+    pub block: BlockRow,
+    pub transactions: Vec<TxRow>,
+    pub balance_deltas: Vec<BalanceDelta>,
+}
+
+impl BlockExport {
+    /// Flatten a block at `height` of the active chain
+    pub fn from_block(height: u64, block: &Block) -> Result<Self> {
+        let height = to_i64(height, "height")?;
+
+        let mut transactions = Vec::with_capacity(block.transactions.len());
+        let mut deltas: BTreeMap<String, i128> = BTreeMap::new();
+        let mut total_fees: u64 = 0;
+
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let is_coinbase = tx.is_coinbase();
+
+            // Same accounting as StateStorage::apply_block_atomic
+            *deltas.entry(tx.to.to_hex()).or_insert(0) += tx.amount as i128;
+            if !is_coinbase {
+                *deltas.entry(tx.from.to_hex()).or_insert(0) -= tx.amount as i128 + tx.fee as i128;
+                total_fees = total_fees.saturating_add(tx.fee);
+            }
+
+            transactions.push(TxRow {
+                hash: hex::encode(tx.hash()),
+                block_height: height,
+                tx_index: index as i64,
+                from_address: tx.from.to_hex(),
+                to_address: tx.to.to_hex(),
+                amount: to_i64(tx.amount, "amount")?,
+                fee: to_i64(tx.fee, "fee")?,
+                nonce: to_i64(tx.nonce, "nonce")?,
+                is_coinbase,
+            });
+        }
+
+        let balance_deltas = deltas
+            .into_iter()
+            .filter(|(_, delta)| *delta != 0)
+            .map(|(address, delta)| {
+                Ok(BalanceDelta {
+                    block_height: height,
+                    delta: i64::try_from(delta)
+                        .map_err(|_| anyhow!("Balance change {} out of range", delta))?,
+                    address,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            block: BlockRow {
+                height,
+                hash: hex::encode(block.hash()),
+                previous_hash: hex::encode(block.header.previous_hash),
+                timestamp: to_i64(block.header.timestamp, "timestamp")?,
+                difficulty: block.header.difficulty as i64,
+                nonce: to_i64(block.header.nonce, "nonce")?,
+                tx_count: block.transactions.len() as i64,
+                total_fees: to_i64(total_fees, "total fees")?,
+            },
+            transactions,
+            balance_deltas,
+        })
+    }
+}
+
+/// One row of the `governance_proposals` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalRow {
+    pub id: i64,
+    pub proposer: String,
+    /// Proposal type and parameters as JSON
+    pub proposal_type: String,
+    pub title: String,
+    pub status: String,
+    pub created_at: i64,
+    pub voting_start: i64,
+    pub voting_end: i64,
+    pub votes_yes: i64,
+    pub votes_no: i64,
+    pub votes_abstain: i64,
+    pub total_voting_power: i64,
+}
+
+impl ProposalRow {
+    pub fn from_proposal(proposal: &Proposal) -> Result<Self> {
+        Ok(Self {
+            id: to_i64(proposal.id, "proposal id")?,
+            proposer: proposal.proposer.to_hex(),
+            proposal_type: serde_json::to_string(&proposal.proposal_type)?,
+            title: proposal.title.clone(),
+            status: format!("{:?}", proposal.status),
+            created_at: to_i64(proposal.created_at, "created_at")?,
+            voting_start: to_i64(proposal.voting_start, "voting_start")?,
+            voting_end: to_i64(proposal.voting_end, "voting_end")?,
+            votes_yes: to_i64(proposal.votes_yes, "votes_yes")?,
+            votes_no: to_i64(proposal.votes_no, "votes_no")?,
+            votes_abstain: to_i64(proposal.votes_abstain, "votes_abstain")?,
+            total_voting_power: to_i64(proposal.total_voting_power, "total_voting_power")?,
+        })
+    }
+}
+
+/// One row of the `governance_votes` table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteRow {
+    pub proposal_id: i64,
+    pub voter: String,
+    pub vote: String,
+    pub voting_power: i64,
+    pub timestamp: i64,
+    pub delegated_from: Option<String>,
+}
+
+impl VoteRow {
+    pub fn from_record(proposal_id: u64, record: &VoteRecord) -> Result<Self> {
+        Ok(Self {
+            proposal_id: to_i64(proposal_id, "proposal id")?,
+            voter: record.voter.to_hex(),
+            vote: format!("{:?}", record.vote),
+            voting_power: to_i64(record.voting_power, "voting_power")?,
+            timestamp: to_i64(record.timestamp, "timestamp")?,
+            delegated_from: record.delegated_from.as_ref().map(PublicKey::to_hex),
+        })
+    }
+}
+
+/// Governance state flattened for export
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GovernanceExport {
+    pub proposals: Vec<ProposalRow>,
+    pub votes: Vec<VoteRow>,
+}
+
+impl GovernanceExport {
+    pub fn from_snapshot(snapshot: &GovernanceSnapshot) -> Result<Self> {
+        Ok(Self {
+            proposals: snapshot
+                .proposals
+                .iter()
+                .map(ProposalRow::from_proposal)
+                .collect::<Result<_>>()?,
+            votes: snapshot
+                .votes
+                .iter()
+                .map(|(proposal_id, _, record)| VoteRow::from_record(*proposal_id, record))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Convert to SQL BIGINT, refusing values that would wrap
+pub(crate) fn to_i64(value: u64, field: &str) -> Result<i64> {
+    i64::try_from(value).map_err(|_| anyhow!("{} {} exceeds BIGINT range", field, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::{crypto::KeyPair, Transaction};
+
+    #[test]
+    fn test_block_export_balance_deltas() {
+        let miner = KeyPair::generate().public_key();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+
+        let coinbase = Transaction::coinbase(1, miner, 2, 10).unwrap();
+        let reward = coinbase.amount as i64;
+        let transfer = Transaction::new(alice, bob, 1_000, 10, 0);
+        let block = Block::new([0u8; 32], vec![coinbase, transfer], 16);
+
+        let export = BlockExport::from_block(2, &block).unwrap();
+        assert_eq!(export.block.height, 2);
+        assert_eq!(export.block.tx_count, 2);
+        assert_eq!(export.block.total_fees, 10);
+        assert!(export.transactions[0].is_coinbase);
+        assert_eq!(export.transactions[1].tx_index, 1);
+
+        let delta = |address: &PublicKey| {
+            export
+                .balance_deltas
+                .iter()
+                .find(|d| d.address == address.to_hex())
+                .map(|d| d.delta)
+        };
+        assert_eq!(delta(&miner), Some(reward));
+        assert_eq!(delta(&alice), Some(-1_010));
+        assert_eq!(delta(&bob), Some(1_000));
+    }
+
+    #[test]
+    fn test_to_i64_rejects_overflow() {
+        assert_eq!(to_i64(42, "x").unwrap(), 42);
+        assert!(to_i64(u64::MAX, "x").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+//! Export sink abstraction and the shared SQL schema
+//! واجهة مصرف التصدير والمخطط المشترك
+
+use crate::rows::{BlockExport, GovernanceExport};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Tables created by every sink. Kept to types both SQLite and Postgres
+/// understand so analysts can run the same queries against either.
+pub const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS export_cursor (
+        id INTEGER PRIMARY KEY,
+        height BIGINT NOT NULL,
+        block_hash TEXT NOT NULL,
+        updated_at BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS blocks (
+        height BIGINT PRIMARY KEY,
+        hash TEXT NOT NULL UNIQUE,
+        previous_hash TEXT NOT NULL,
+        timestamp BIGINT NOT NULL,
+        difficulty BIGINT NOT NULL,
+        nonce BIGINT NOT NULL,
+        tx_count BIGINT NOT NULL,
+        total_fees BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS transactions (
+        hash TEXT NOT NULL,
+        block_height BIGINT NOT NULL,
+        tx_index BIGINT NOT NULL,
+        from_address TEXT NOT NULL,
+        to_address TEXT NOT NULL,
+        amount BIGINT NOT NULL,
+        fee BIGINT NOT NULL,
+        nonce BIGINT NOT NULL,
+        is_coinbase BOOLEAN NOT NULL,
+        PRIMARY KEY (block_height, tx_index)
+    )",
+    "CREATE INDEX IF NOT EXISTS idx_transactions_hash ON transactions (hash)",
+    "CREATE INDEX IF NOT EXISTS idx_transactions_from ON transactions (from_address)",
+    "CREATE INDEX IF NOT EXISTS idx_transactions_to ON transactions (to_address)",
+    "CREATE TABLE IF NOT EXISTS balance_changes (
+        block_height BIGINT NOT NULL,
+        address TEXT NOT NULL,
+        delta BIGINT NOT NULL,
+        PRIMARY KEY (block_height, address)
+    )",
+    "CREATE TABLE IF NOT EXISTS balances (
+        address TEXT PRIMARY KEY,
+        balance BIGINT NOT NULL,
+        updated_height BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS governance_proposals (
+        id BIGINT PRIMARY KEY,
+        proposer TEXT NOT NULL,
+        proposal_type TEXT NOT NULL,
+        title TEXT NOT NULL,
+        status TEXT NOT NULL,
+        created_at BIGINT NOT NULL,
+        voting_start BIGINT NOT NULL,
+        voting_end BIGINT NOT NULL,
+        votes_yes BIGINT NOT NULL,
+        votes_no BIGINT NOT NULL,
+        votes_abstain BIGINT NOT NULL,
+        total_voting_power BIGINT NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS governance_votes (
+        proposal_id BIGINT NOT NULL,
+        voter TEXT NOT NULL,
+        vote TEXT NOT NULL,
+        voting_power BIGINT NOT NULL,
+        timestamp BIGINT NOT NULL,
+        delegated_from TEXT,
+        PRIMARY KEY (proposal_id, voter)
+    )",
+];
+
+/// Last block mirrored into the sink
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportCursor {
+    pub height: u64,
+    /// Hex hash of the block at `height`, used to detect reorganizations
+    pub block_hash: String,
+}
+
+/// Destination of the analytics export
+/// وجهة تصدير بيانات التحليلات
+///
+/// Every write commits together with the cursor, so an interrupted export
+/// resumes exactly after the last block that made it into the database.
+#[async_trait]
+pub trait ExportSink: Send + Sync {
+    /// Create tables if they don't exist yet
+    async fn init(&self) -> Result<()>;
+
+    /// Current cursor, `None` before the first block is exported
+    async fn cursor(&self) -> Result<Option<ExportCursor>>;
+
+    /// Hex hash of an exported block
+    async fn block_hash(&self, height: u64) -> Result<Option<String>>;
+
+    /// Write one block and advance the cursor in a single transaction
+    async fn write_block(&self, export: &BlockExport) -> Result<()>;
+
+    /// Drop everything above `height`, reversing its balance changes,
+    /// and move the cursor back (chain reorganization)
+    async fn rewind(&self, height: u64) -> Result<()>;
+
+    /// Upsert proposals and votes
+    async fn write_governance(&self, export: &GovernanceExport) -> Result<()>;
+}
@@ -0,0 +1,312 @@
+//! SQLite export sink
+//! مصرف تصدير SQLite
+
+use crate::rows::{BlockExport, GovernanceExport};
+use crate::sink::{ExportCursor, ExportSink, SCHEMA};
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Export into a local SQLite database file
+pub struct SqliteSink {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteSink {
+    /// Open or create the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open SQLite database {}", path.display()))?;
+        // Readers can query while the exporter writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// In-memory database (tests)
+    pub fn in_memory() -> Result<Self> {
+        Ok(Self {
+            conn: Mutex::new(Connection::open_in_memory()?),
+        })
+    }
+
+    fn conn(&self) -> Result<std::sync::MutexGuard<'_, Connection>> {
+        self.conn
+            .lock()
+            .map_err(|_| anyhow!("SQLite connection poisoned"))
+    }
+}
+
+#[async_trait]
+impl ExportSink for SqliteSink {
+    async fn init(&self) -> Result<()> {
+        let conn = self.conn()?;
+        for statement in SCHEMA {
+            conn.execute(statement, [])?;
+        }
+        Ok(())
+    }
+
+    async fn cursor(&self) -> Result<Option<ExportCursor>> {
+        let cursor = self
+            .conn()?
+            .query_row(
+                "SELECT height, block_hash FROM export_cursor WHERE id = 1",
+                [],
+                |row| {
+                    Ok(ExportCursor {
+                        height: row.get::<_, i64>(0)? as u64,
+                        block_hash: row.get(1)?,
+                    })
+                },
+            )
+            .optional()?;
+        Ok(cursor)
+    }
+
+    async fn block_hash(&self, height: u64) -> Result<Option<String>> {
+        let hash = self
+            .conn()?
+            .query_row(
+                "SELECT hash FROM blocks WHERE height = ?1",
+                params![height as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(hash)
+    }
+
+    async fn write_block(&self, export: &BlockExport) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+        let block = &export.block;
+
+        tx.execute(
+            "INSERT INTO blocks (height, hash, previous_hash, timestamp, difficulty, nonce, tx_count, total_fees)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                block.height,
+                block.hash,
+                block.previous_hash,
+                block.timestamp,
+                block.difficulty,
+                block.nonce,
+                block.tx_count,
+                block.total_fees
+            ],
+        )?;
+
+        for row in &export.transactions {
+            tx.execute(
+                "INSERT INTO transactions (hash, block_height, tx_index, from_address, to_address, amount, fee, nonce, is_coinbase)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                params![
+                    row.hash,
+                    row.block_height,
+                    row.tx_index,
+                    row.from_address,
+                    row.to_address,
+                    row.amount,
+                    row.fee,
+                    row.nonce,
+                    row.is_coinbase
+                ],
+            )?;
+        }
+
+        for delta in &export.balance_deltas {
+            tx.execute(
+                "INSERT INTO balance_changes (block_height, address, delta) VALUES (?1, ?2, ?3)",
+                params![delta.block_height, delta.address, delta.delta],
+            )?;
+            tx.execute(
+                "INSERT INTO balances (address, balance, updated_height) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (address) DO UPDATE
+                 SET balance = balance + excluded.balance, updated_height = excluded.updated_height",
+                params![delta.address, delta.delta, delta.block_height],
+            )?;
+        }
+
+        tx.execute(
+            "INSERT INTO export_cursor (id, height, block_hash, updated_at) VALUES (1, ?1, ?2, strftime('%s', 'now'))
+             ON CONFLICT (id) DO UPDATE
+             SET height = excluded.height, block_hash = excluded.block_hash, updated_at = excluded.updated_at",
+            params![block.height, block.hash],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn rewind(&self, height: u64) -> Result<()> {
+        let height = height as i64;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        tx.execute(
+            "UPDATE balances SET balance = balance - (
+                 SELECT COALESCE(SUM(delta), 0) FROM balance_changes
+                 WHERE balance_changes.address = balances.address AND block_height > ?1
+             )
+             WHERE address IN (SELECT address FROM balance_changes WHERE block_height > ?1)",
+            params![height],
+        )?;
+        tx.execute(
+            "UPDATE balances SET updated_height = COALESCE((
+                 SELECT MAX(block_height) FROM balance_changes
+                 WHERE balance_changes.address = balances.address AND block_height <= ?1
+             ), 0)
+             WHERE updated_height > ?1",
+            params![height],
+        )?;
+        tx.execute(
+            "DELETE FROM balance_changes WHERE block_height > ?1",
+            params![height],
+        )?;
+        tx.execute(
+            "DELETE FROM transactions WHERE block_height > ?1",
+            params![height],
+        )?;
+        tx.execute("DELETE FROM blocks WHERE height > ?1", params![height])?;
+
+        let hash: Option<String> = tx
+            .query_row(
+                "SELECT hash FROM blocks WHERE height = ?1",
+                params![height],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match hash {
+            Some(hash) => tx.execute(
+                "UPDATE export_cursor SET height = ?1, block_hash = ?2, updated_at = strftime('%s', 'now')
+                 WHERE id = 1",
+                params![height, hash],
+            )?,
+            None => tx.execute("DELETE FROM export_cursor", [])?,
+        };
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    async fn write_governance(&self, export: &GovernanceExport) -> Result<()> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction()?;
+
+        for p in &export.proposals {
+            tx.execute(
+                "INSERT INTO governance_proposals (id, proposer, proposal_type, title, status, created_at,
+                     voting_start, voting_end, votes_yes, votes_no, votes_abstain, total_voting_power)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+                 ON CONFLICT (id) DO UPDATE SET
+                     status = excluded.status, votes_yes = excluded.votes_yes,
+                     votes_no = excluded.votes_no, votes_abstain = excluded.votes_abstain,
+                     total_voting_power = excluded.total_voting_power",
+                params![
+                    p.id,
+                    p.proposer,
+                    p.proposal_type,
+                    p.title,
+                    p.status,
+                    p.created_at,
+                    p.voting_start,
+                    p.voting_end,
+                    p.votes_yes,
+                    p.votes_no,
+                    p.votes_abstain,
+                    p.total_voting_power
+                ],
+            )?;
+        }
+
+        for v in &export.votes {
+            tx.execute(
+                "INSERT INTO governance_votes (proposal_id, voter, vote, voting_power, timestamp, delegated_from)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                 ON CONFLICT (proposal_id, voter) DO UPDATE SET
+                     vote = excluded.vote, voting_power = excluded.voting_power,
+                     timestamp = excluded.timestamp, delegated_from = excluded.delegated_from",
+                params![
+                    v.proposal_id,
+                    v.voter,
+                    v.vote,
+                    v.voting_power,
+                    v.timestamp,
+                    v.delegated_from
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rows::{BalanceDelta, BlockRow};
+
+    fn export(height: i64, deltas: &[(&str, i64)]) -> BlockExport {
+        BlockExport {
+            block: BlockRow {
+                height,
+                hash: format!("hash{}", height),
+                previous_hash: format!("hash{}", height - 1),
+                timestamp: height,
+                difficulty: 16,
+                nonce: 0,
+                tx_count: 0,
+                total_fees: 0,
+            },
+            transactions: Vec::new(),
+            balance_deltas: deltas
+                .iter()
+                .map(|(address, delta)| BalanceDelta {
+                    block_height: height,
+                    address: address.to_string(),
+                    delta: *delta,
+                })
+                .collect(),
+        }
+    }
+
+    fn balance(sink: &SqliteSink, address: &str) -> Option<(i64, i64)> {
+        sink.conn()
+            .unwrap()
+            .query_row(
+                "SELECT balance, updated_height FROM balances WHERE address = ?1",
+                params![address],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_balances_follow_writes_and_rewinds() {
+        let sink = SqliteSink::in_memory().unwrap();
+        sink.init().await.unwrap();
+
+        sink.write_block(&export(1, &[("alice", 100)]))
+            .await
+            .unwrap();
+        sink.write_block(&export(2, &[("alice", -30), ("bob", 30)]))
+            .await
+            .unwrap();
+        assert_eq!(balance(&sink, "alice"), Some((70, 2)));
+        assert_eq!(balance(&sink, "bob"), Some((30, 2)));
+
+        sink.rewind(1).await.unwrap();
+        assert_eq!(balance(&sink, "alice"), Some((100, 1)));
+        assert_eq!(balance(&sink, "bob"), Some((0, 0)));
+        assert_eq!(sink.cursor().await.unwrap().unwrap().height, 1);
+        assert_eq!(sink.block_hash(2).await.unwrap(), None);
+
+        sink.rewind(0).await.unwrap();
+        assert_eq!(sink.cursor().await.unwrap(), None);
+    }
+}
@@ -0,0 +1,364 @@
+//! Mempool policy conformance vectors
+//! متجهات اختبار توافق سياسة مجمع المعاملات
+//!
+//! A suite is JSON: account fixtures (balance, nonce, frozen) and vectors,
+//! each a raw transaction hex, optional raw transactions already pending,
+//! and the expected outcome: `accept`, `malformed` for bytes that do not
+//! decode, or the [`MempoolError::code`] submission returns. Every vector runs
+//! against a fresh mempool over the same fixture state, so vectors do not
+//! depend on each other. Suites are capped at [`MAX_SUITE_VECTORS`] vectors,
+//! [`MAX_SUITE_ACCOUNTS`] accounts and [`MAX_VECTOR_PENDING`] pending
+//! transactions per vector, and a [`ConformanceRunner`] keeps one fixture
+//! database for every suite it runs.
+//!
+//! [`BUILTIN_VECTORS`] pins the default policy and runs with this crate's
+//! tests, so a change to what the pool accepts shows up as a failing vector.
+
+use crate::{Mempool, MempoolConfig};
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::Transaction;
+use opensyria_storage::StateStorage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tempfile::TempDir;
+use thiserror::Error;
+use tokio::sync::{Mutex, RwLock};
+
+/// Vectors for the default [`MempoolConfig`], shipped with the crate
+pub const BUILTIN_VECTORS: &str = include_str!("../vectors/policy.json");
+
+/// Expected outcome of a transaction the pool accepts
+pub const EXPECT_ACCEPT: &str = "accept";
+
+/// Expected outcome of bytes that are not a canonical raw transaction
+pub const EXPECT_MALFORMED: &str = "malformed";
+
+/// Most vectors a suite may hold
+pub const MAX_SUITE_VECTORS: usize = 1_000;
+
+/// Most account fixtures a suite may hold
+pub const MAX_SUITE_ACCOUNTS: usize = 1_000;
+
+/// Most pending transactions a vector may add before its own
+pub const MAX_VECTOR_PENDING: usize = 100;
+
+/// Errors that stop a suite from running at all
+#[derive(Error, Debug, Clone)]
+pub enum ConformanceError {
+    #[error("Invalid conformance suite: {0}")]
+    InvalidSuite(String),
+
+    #[error("Storage error: {0}")]
+    Storage(String),
+}
+
+/// Account state the vectors run against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountFixture {
+    /// Hex public key
+    pub address: String,
+    pub balance: u64,
+    #[serde(default)]
+    pub nonce: u64,
+    #[serde(default)]
+    pub frozen: bool,
+}
+
+/// One transaction and the outcome the pool must produce
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    /// Raw transactions added first; each must be accepted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending: Vec<String>,
+    /// Raw transaction hex under test
+    pub tx: String,
+    /// `accept`, `malformed` or a rejection code such as `fee_too_low`
+    pub expect: String,
+}
+
+/// A set of vectors sharing one fixture state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConformanceSuite {
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountFixture>,
+    pub vectors: Vec<TestVector>,
+}
+
+impl ConformanceSuite {
+    /// Parse a suite from JSON
+    pub fn from_json(json: &str) -> Result<Self, ConformanceError> {
+        serde_json::from_str(json).map_err(|e| ConformanceError::InvalidSuite(e.to_string()))
+    }
+
+    /// The vectors shipped with this release
+    pub fn builtin() -> Self {
+        Self::from_json(BUILTIN_VECTORS).expect("built-in conformance vectors are valid")
+    }
+
+    /// Refuse suites over the size limits
+    fn check_limits(&self) -> Result<(), ConformanceError> {
+        let too_large = |what: &str, count: usize, max: usize| {
+            ConformanceError::InvalidSuite(format!("{} {} (max {})", count, what, max))
+        };
+        if self.vectors.len() > MAX_SUITE_VECTORS {
+            return Err(too_large("vectors", self.vectors.len(), MAX_SUITE_VECTORS));
+        }
+        if self.accounts.len() > MAX_SUITE_ACCOUNTS {
+            return Err(too_large(
+                "accounts",
+                self.accounts.len(),
+                MAX_SUITE_ACCOUNTS,
+            ));
+        }
+        if let Some(vector) = self
+            .vectors
+            .iter()
+            .find(|vector| vector.pending.len() > MAX_VECTOR_PENDING)
+        {
+            let what = format!("pending transactions in vector '{}'", vector.name);
+            return Err(too_large(&what, vector.pending.len(), MAX_VECTOR_PENDING));
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of one vector
+#[derive(Debug, Clone, Serialize)]
+pub struct VectorResult {
+    pub name: String,
+    pub expected: String,
+    /// `accept`, `malformed` or the rejection code
+    pub actual: String,
+    /// Error message for rejections and setup failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub passed: bool,
+}
+
+/// Outcome of a whole suite
+#[derive(Debug, Clone, Serialize)]
+pub struct ConformanceReport {
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<VectorResult>,
+}
+
+impl ConformanceReport {
+    /// Whether every vector produced its expected outcome
+    pub fn is_success(&self) -> bool {
+        self.failed == 0
+    }
+}
+
+/// Run every vector in `suite` against the pool policy in `config`
+/// تشغيل متجهات الاختبار على سياسة مجمع المعاملات
+///
+/// Runs once in a fresh [`ConformanceRunner`]; keep a runner to reuse its
+/// fixture database across suites.
+pub async fn run_suite(
+    suite: &ConformanceSuite,
+    config: &MempoolConfig,
+) -> Result<ConformanceReport, ConformanceError> {
+    ConformanceRunner::new(config.clone()).run(suite).await
+}
+
+/// Runs suites against one pool policy over a shared fixture database
+/// مشغّل متجهات الاختبار بقاعدة بيانات مشتركة
+///
+/// The database is a temporary one opened by the first run and removed with
+/// the runner. Runs take turns; each resets the accounts the previous one
+/// set. Signature batching is turned off so results do not depend on timing,
+/// and the chain ID check so the same vectors serve every network.
+pub struct ConformanceRunner {
+    config: MempoolConfig,
+    fixture: Mutex<Option<Fixture>>,
+}
+
+struct Fixture {
+    state: Arc<RwLock<StateStorage>>,
+    /// Accounts the last run set
+    accounts: HashSet<PublicKey>,
+    _dir: TempDir,
+}
+
+impl Fixture {
+    fn open() -> Result<Self, ConformanceError> {
+        let dir = tempfile::tempdir().map_err(|e| ConformanceError::Storage(e.to_string()))?;
+        let state = StateStorage::open(dir.path().join("state")).map_err(storage_error)?;
+        Ok(Self {
+            state: Arc::new(RwLock::new(state)),
+            accounts: HashSet::new(),
+            _dir: dir,
+        })
+    }
+
+    /// Replace the previous run's accounts with `accounts`
+    async fn load(&mut self, accounts: &[AccountFixture]) -> Result<(), ConformanceError> {
+        let mut fixtures = Vec::with_capacity(accounts.len());
+        for account in accounts {
+            let address = PublicKey::from_hex(&account.address).map_err(|_| {
+                ConformanceError::InvalidSuite(format!("Invalid address: {}", account.address))
+            })?;
+            fixtures.push((address, account));
+        }
+
+        let state = self.state.write().await;
+        for address in self.accounts.drain() {
+            state.set_balance(&address, 0).map_err(storage_error)?;
+            state.set_nonce(&address, 0).map_err(storage_error)?;
+            state.unfreeze_account(&address).map_err(storage_error)?;
+        }
+        for (address, account) in fixtures {
+            state
+                .set_balance(&address, account.balance)
+                .map_err(storage_error)?;
+            state
+                .set_nonce(&address, account.nonce)
+                .map_err(storage_error)?;
+            if account.frozen {
+                state.freeze_account(&address, 0).map_err(storage_error)?;
+            }
+            self.accounts.insert(address);
+        }
+        Ok(())
+    }
+}
+
+fn storage_error(e: opensyria_storage::StorageError) -> ConformanceError {
+    ConformanceError::Storage(e.to_string())
+}
+
+impl ConformanceRunner {
+    /// Runner for the pool policy in `config`, normally the node's own
+    pub fn new(config: MempoolConfig) -> Self {
+        Self {
+            config: MempoolConfig {
+                signature_batching: None,
+                chain_params: None,
+                ..config
+            },
+            fixture: Mutex::new(None),
+        }
+    }
+
+    /// Run every vector in `suite`
+    pub async fn run(
+        &self,
+        suite: &ConformanceSuite,
+    ) -> Result<ConformanceReport, ConformanceError> {
+        suite.check_limits()?;
+
+        let mut fixture = self.fixture.lock().await;
+        let fixture = match fixture.as_mut() {
+            Some(fixture) => fixture,
+            None => fixture.insert(Fixture::open()?),
+        };
+        fixture.load(&suite.accounts).await?;
+
+        let mut results = Vec::with_capacity(suite.vectors.len());
+        for vector in &suite.vectors {
+            let mut mempool = Mempool::new(self.config.clone(), fixture.state.clone());
+            let (actual, message) = match add_pending(&mut mempool, &vector.pending).await {
+                Ok(()) => match Transaction::from_raw_hex(&vector.tx) {
+                    Ok(tx) => match mempool.add_transaction(tx).await {
+                        Ok(()) => (EXPECT_ACCEPT.to_string(), None),
+                        Err(e) => (e.code().to_string(), Some(e.to_string())),
+                    },
+                    Err(e) => (EXPECT_MALFORMED.to_string(), Some(e.to_string())),
+                },
+                Err(message) => ("setup_failed".to_string(), Some(message)),
+            };
+            results.push(VectorResult {
+                name: vector.name.clone(),
+                passed: actual == vector.expect,
+                expected: vector.expect.clone(),
+                actual,
+                message,
+            });
+        }
+
+        let passed = results.iter().filter(|r| r.passed).count();
+        Ok(ConformanceReport {
+            passed,
+            failed: results.len() - passed,
+            results,
+        })
+    }
+}
+
+/// Add a vector's pending transactions, describing the first that fails
+async fn add_pending(mempool: &mut Mempool, pending: &[String]) -> Result<(), String> {
+    for (index, raw) in pending.iter().enumerate() {
+        let tx = Transaction::from_raw_hex(raw)
+            .map_err(|e| format!("Pending transaction {} is malformed: {}", index, e))?;
+        mempool
+            .add_transaction(tx)
+            .await
+            .map_err(|e| format!("Pending transaction {} was rejected: {}", index, e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_builtin_vectors_pass() {
+        let report = run_suite(&ConformanceSuite::builtin(), &MempoolConfig::default())
+            .await
+            .unwrap();
+        let failures: Vec<_> = report.results.iter().filter(|r| !r.passed).collect();
+        assert!(failures.is_empty(), "Failing vectors: {:#?}", failures);
+        assert!(report.passed >= 20);
+    }
+
+    #[tokio::test]
+    async fn test_mismatch_is_reported() {
+        let mut suite = ConformanceSuite::builtin();
+        suite.vectors.truncate(1);
+        suite.vectors[0].expect = "fee_too_low".to_string();
+
+        let report = run_suite(&suite, &MempoolConfig::default()).await.unwrap();
+        assert!(!report.is_success());
+        assert_eq!(report.results[0].actual, EXPECT_ACCEPT);
+
+        // A stricter policy turns the same vector into the expected rejection
+        let strict = MempoolConfig {
+            min_fee: 10_000,
+            ..MempoolConfig::default()
+        };
+        let report = run_suite(&suite, &strict).await.unwrap();
+        assert!(report.is_success());
+    }
+
+    #[tokio::test]
+    async fn test_runner_reuses_fixture_state() {
+        let runner = ConformanceRunner::new(MempoolConfig::default());
+        let builtin = ConformanceSuite::builtin();
+        assert!(runner.run(&builtin).await.unwrap().is_success());
+
+        // The next run does not see the previous suite's accounts
+        let mut unfunded = builtin.clone();
+        unfunded.accounts.clear();
+        unfunded.vectors.truncate(1);
+        let report = runner.run(&unfunded).await.unwrap();
+        assert_eq!(report.results[0].actual, "insufficient_balance");
+
+        assert!(runner.run(&builtin).await.unwrap().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_suite_is_refused() {
+        let mut suite = ConformanceSuite::builtin();
+        let vector = suite.vectors[0].clone();
+        suite.vectors = vec![vector; MAX_SUITE_VECTORS + 1];
+
+        let result = run_suite(&suite, &MempoolConfig::default()).await;
+        assert!(matches!(result, Err(ConformanceError::InvalidSuite(_))));
+    }
+}
@@ -1,3 +1,4 @@
+use opensyria_core::error_code::{self, CodedError, ErrorCode};
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, MempoolError>;
@@ -13,6 +14,15 @@ pub enum MempoolError {
     #[error("Mempool is full (max: {max}, current: {current})")]
     MempoolFull { max: usize, current: usize },
 
+    #[error("Mempool byte limit reached (max {max_bytes} bytes, current {current_bytes})")]
+    MempoolBytesFull {
+        max_bytes: usize,
+        current_bytes: usize,
+    },
+
+    #[error("Sender mempool limit reached (max {max_count} transactions, {max_bytes} bytes)")]
+    SenderLimitExceeded { max_count: usize, max_bytes: usize },
+
     #[error("Insufficient balance: required {required}, available {available}")]
     InsufficientBalance { required: u64, available: u64 },
 
@@ -29,6 +39,36 @@ pub enum MempoolError {
     #[error("Transaction fee too low: minimum {min}, got {got}")]
     FeeTooLow { min: u64, got: u64 },
 
+    #[error("Dust amount: {amount} is below the dust limit {limit}")]
+    DustAmount { amount: u64, limit: u64 },
+
+    #[error("Transaction signed for chain {got}, expected {expected}")]
+    WrongChainId { expected: u32, got: u32 },
+
+    #[error("Sender account {0} is frozen by governance")]
+    AccountFrozen(String),
+
+    #[error("Spending policy violation: {0}")]
+    PolicyViolation(String),
+
+    #[error("Hot key delegation violation: {0}")]
+    DelegationViolation(String),
+
+    #[error("Invalid contract transaction: {0}")]
+    InvalidContract(String),
+
+    #[error("Invalid asset operation: {0}")]
+    InvalidAsset(String),
+
+    #[error("Invalid order: {0}")]
+    InvalidOrder(String),
+
+    #[error("Invalid oracle update: {0}")]
+    InvalidOracleUpdate(String),
+
+    #[error("Rejected by relay policy {policy}: {reason}")]
+    RelayPolicy { policy: String, reason: String },
+
     #[error("Transaction expired")]
     Expired,
 
@@ -41,3 +81,45 @@ pub enum MempoolError {
     #[error("Invalid transaction")]
     InvalidTransaction,
 }
+
+impl MempoolError {
+    /// Stable machine-readable reason, e.g. `fee_too_low`
+    ///
+    /// Wallets and the conformance vectors match on these rather than on
+    /// messages, so existing codes must not change.
+    pub fn code(&self) -> &'static str {
+        self.error_code().code
+    }
+}
+
+impl CodedError for MempoolError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            MempoolError::DuplicateTransaction(_) => error_code::DUPLICATE,
+            MempoolError::ValidationFailed(_) | MempoolError::InvalidTransaction => {
+                error_code::INVALID_SIGNATURE
+            }
+            MempoolError::MempoolFull { .. } | MempoolError::MempoolBytesFull { .. } => {
+                error_code::MEMPOOL_FULL
+            }
+            MempoolError::SenderLimitExceeded { .. } => error_code::SENDER_LIMIT,
+            MempoolError::InsufficientBalance { .. } => error_code::INSUFFICIENT_BALANCE,
+            MempoolError::InvalidNonce { .. } => error_code::INVALID_NONCE,
+            MempoolError::NonceTooFar { .. } => error_code::NONCE_TOO_FAR,
+            MempoolError::FeeTooLow { .. } => error_code::FEE_TOO_LOW,
+            MempoolError::DustAmount { .. } => error_code::DUST_AMOUNT,
+            MempoolError::WrongChainId { .. } => error_code::WRONG_CHAIN_ID,
+            MempoolError::AccountFrozen(_) => error_code::ACCOUNT_FROZEN,
+            MempoolError::PolicyViolation(_) => error_code::POLICY_VIOLATION,
+            MempoolError::DelegationViolation(_) => error_code::DELEGATION_VIOLATION,
+            MempoolError::InvalidContract(_) => error_code::INVALID_CONTRACT,
+            MempoolError::InvalidAsset(_) => error_code::INVALID_ASSET,
+            MempoolError::InvalidOrder(_) => error_code::INVALID_ORDER,
+            MempoolError::InvalidOracleUpdate(_) => error_code::INVALID_ORACLE_UPDATE,
+            MempoolError::RelayPolicy { .. } => error_code::RELAY_POLICY,
+            MempoolError::Expired => error_code::EXPIRED,
+            MempoolError::Storage(_) => error_code::STORAGE_ERROR,
+            MempoolError::NotFound => error_code::NOT_FOUND,
+        }
+    }
+}
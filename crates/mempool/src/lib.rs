@@ -2,12 +2,26 @@
 //!
 //! Manages pending transactions before they are included in blocks.
 
+mod conformance;
 mod error;
+mod policy;
 mod pool;
 mod validator;
 mod orphan;
 
+pub use conformance::{
+    run_suite, AccountFixture, ConformanceError, ConformanceReport, ConformanceRunner,
+    ConformanceSuite, TestVector, VectorResult, BUILTIN_VECTORS, EXPECT_ACCEPT, EXPECT_MALFORMED,
+    MAX_SUITE_ACCOUNTS, MAX_SUITE_VECTORS, MAX_VECTOR_PENDING,
+};
 pub use error::{MempoolError, Result};
-pub use pool::{Mempool, MempoolConfig, TransactionStatus};
-pub use validator::TransactionValidator;
+pub use policy::{DefaultPolicy, PolicyEngine};
+pub use pool::{
+    check_replacement, replacement_fee, Mempool, MempoolConfig, TransactionStatus,
+    MIN_REPLACEMENT_BUMP_PERCENT,
+};
+pub use validator::{
+    SignatureBatchConfig, SignatureBatchStats, SignatureBatcher, TransactionValidator,
+    VerifiedTransaction, DEFAULT_SIGNATURE_BATCH_SIZE, DEFAULT_SIGNATURE_BATCH_WINDOW,
+};
 pub use orphan::{OrphanPool, OrphanPoolStats};
@@ -0,0 +1,234 @@
+//! Pluggable relay policy
+//! سياسة الترحيل القابلة للاستبدال
+//!
+//! Relay rules decide which valid transactions this node is willing to hold
+//! and gossip, as opposed to the consensus rules every block must follow.
+//! A public node relays anything that pays the minimum fee and is not dust;
+//! an institutional sidechain may also want a KYC allowlist or a memo on
+//! every transfer. Embedders implement [`PolicyEngine`] and pass it in
+//! [`MempoolConfig::policy`](crate::MempoolConfig::policy) instead of
+//! forking the mempool.
+//!
+//! The engine replaces [`DefaultPolicy`]; wrap one inside a custom engine to
+//! keep the fee and dust rules. Signature, balance, nonce, chain ID and the
+//! other consensus checks always run regardless of the engine.
+
+use crate::{MempoolError, Result};
+use opensyria_core::asset::AssetOperation;
+use opensyria_core::contract::ContractCall;
+use opensyria_core::delegation::HotKeyDelegation;
+use opensyria_core::dex::DexOperation;
+use opensyria_core::oracle::OracleUpdate;
+use opensyria_core::policy::SpendingPolicy;
+use opensyria_core::Transaction;
+
+/// Relay rules applied to transactions before they enter the mempool
+/// قواعد الترحيل المطبقة على المعاملات قبل دخولها مجمع المعاملات
+///
+/// Checks run with the signature already verified but before any state is
+/// read, so they must only look at the transaction itself. Reject with
+/// [`MempoolError::RelayPolicy`] for custom rules so callers get a stable
+/// `relay_policy` code.
+pub trait PolicyEngine: Send + Sync {
+    /// Short name for logs and rejection messages
+    fn name(&self) -> &str;
+
+    /// Accept or reject a transaction for relay
+    fn check(&self, tx: &Transaction) -> Result<()>;
+}
+
+impl std::fmt::Debug for dyn PolicyEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PolicyEngine({})", self.name())
+    }
+}
+
+/// Public chain relay policy: minimum fee and dust limit
+/// سياسة الترحيل للشبكة العامة: الحد الأدنى للرسوم وحد الغبار
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DefaultPolicy {
+    min_fee: u64,
+    /// Minimum transfer amount; 0 disables the dust check
+    dust_limit: u64,
+}
+
+impl DefaultPolicy {
+    /// Require at least `min_fee`, with the dust check disabled
+    pub fn new(min_fee: u64) -> Self {
+        Self {
+            min_fee,
+            dust_limit: 0,
+        }
+    }
+
+    /// Reject transfers below `dust_limit` (0 disables the check)
+    pub fn with_dust_limit(mut self, dust_limit: u64) -> Self {
+        self.dust_limit = dust_limit;
+        self
+    }
+
+    pub fn min_fee(&self) -> u64 {
+        self.min_fee
+    }
+
+    pub fn dust_limit(&self) -> u64 {
+        self.dust_limit
+    }
+}
+
+impl PolicyEngine for DefaultPolicy {
+    fn name(&self) -> &str {
+        "default"
+    }
+
+    fn check(&self, tx: &Transaction) -> Result<()> {
+        if tx.fee < self.min_fee {
+            return Err(MempoolError::FeeTooLow {
+                min: self.min_fee,
+                got: tx.fee,
+            });
+        }
+
+        // Reject dust transfers that only bloat state; policy and
+        // delegation updates, contract calls, asset operations, orders and
+        // oracle updates carry no amount by design
+        if tx.amount < self.dust_limit
+            && !SpendingPolicy::is_update(tx)
+            && !HotKeyDelegation::is_update(tx)
+            && !ContractCall::is_contract(tx)
+            && !AssetOperation::is_asset(tx)
+            && !DexOperation::is_dex(tx)
+            && !OracleUpdate::is_oracle(tx)
+        {
+            return Err(MempoolError::DustAmount {
+                amount: tx.amount,
+                limit: self.dust_limit,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Mempool, MempoolConfig};
+    use opensyria_core::crypto::{KeyPair, PublicKey};
+    use opensyria_storage::StateStorage;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    /// Sidechain rules: known senders only, and every transfer needs a memo
+    struct KycPolicy {
+        allowed: HashSet<PublicKey>,
+        inner: DefaultPolicy,
+    }
+
+    impl PolicyEngine for KycPolicy {
+        fn name(&self) -> &str {
+            "kyc"
+        }
+
+        fn check(&self, tx: &Transaction) -> Result<()> {
+            self.inner.check(tx)?;
+            if !self.allowed.contains(&tx.from) {
+                return Err(MempoolError::RelayPolicy {
+                    policy: self.name().to_string(),
+                    reason: format!("sender {} is not on the allowlist", tx.from.to_hex()),
+                });
+            }
+            if tx.data.as_ref().is_none_or(|data| data.is_empty()) {
+                return Err(MempoolError::RelayPolicy {
+                    policy: self.name().to_string(),
+                    reason: "memo required".to_string(),
+                });
+            }
+            Ok(())
+        }
+    }
+
+    fn signed(sender: &KeyPair, fee: u64, memo: Option<&[u8]>) -> Transaction {
+        let mut tx = Transaction::new(
+            sender.public_key(),
+            KeyPair::generate().public_key(),
+            50_000,
+            fee,
+            0,
+        );
+        if let Some(memo) = memo {
+            tx = tx.with_data(memo.to_vec());
+        }
+        let signature = sender.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_default_policy() {
+        let sender = KeyPair::generate();
+        let policy = DefaultPolicy::new(100).with_dust_limit(10_000);
+
+        assert!(policy.check(&signed(&sender, 100, None)).is_ok());
+        assert!(matches!(
+            policy.check(&signed(&sender, 99, None)),
+            Err(MempoolError::FeeTooLow { min: 100, got: 99 })
+        ));
+
+        let mut dust = Transaction::new(sender.public_key(), sender.public_key(), 1, 100, 0);
+        dust.signature = sender.sign(&dust.signing_hash());
+        assert!(matches!(
+            policy.check(&dust),
+            Err(MempoolError::DustAmount {
+                amount: 1,
+                limit: 10_000
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_custom_policy_engine() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let member = KeyPair::generate();
+        let outsider = KeyPair::generate();
+        for key in [&member, &outsider] {
+            state.set_balance(&key.public_key(), 1_000_000).unwrap();
+        }
+
+        let policy = KycPolicy {
+            allowed: HashSet::from([member.public_key()]),
+            inner: DefaultPolicy::new(1_000),
+        };
+        let config = MempoolConfig {
+            policy: Some(Arc::new(policy)),
+            ..Default::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        let err = mempool
+            .add_transaction(signed(&outsider, 1_000, Some(b"invoice 7")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "relay_policy");
+
+        let err = mempool
+            .add_transaction(signed(&member, 1_000, None))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("memo required"));
+
+        // The wrapped default rules still apply
+        let err = mempool
+            .add_transaction(signed(&member, 10, Some(b"invoice 7")))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), "fee_too_low");
+
+        mempool
+            .add_transaction(signed(&member, 1_000, Some(b"invoice 7")))
+            .await
+            .unwrap();
+        assert_eq!(mempool.size(), 1);
+    }
+}
@@ -1,5 +1,10 @@
-use crate::{MempoolError, Result, TransactionValidator};
-use opensyria_core::Transaction;
+use crate::validator::{SignatureBatchConfig, VerifiedTransaction};
+use crate::{MempoolError, PolicyEngine, Result, TransactionValidator};
+use opensyria_core::{
+    select_transactions, Block, ChainParams, DropReason, EventBus, NodeEvent, Transaction,
+    DUST_THRESHOLD,
+};
+use std::collections::HashSet;
 use opensyria_storage::StateStorage;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
@@ -32,8 +37,27 @@ pub struct MempoolConfig {
     /// Maximum transactions per sender
     pub max_per_sender: usize,
 
+    /// Maximum total transaction weight (bytes) per sender
+    pub max_bytes_per_sender: usize,
+
+    /// Maximum total transaction weight (bytes) across all senders
+    pub max_total_bytes: usize,
+
     /// Maximum nonce gap allowed
     pub max_nonce_gap: u64,
+
+    /// Minimum transfer amount accepted (0 disables dust rejection)
+    pub dust_limit: u64,
+
+    /// Network whose chain ID transactions must carry (None accepts any)
+    pub chain_params: Option<ChainParams>,
+
+    /// Verify signatures from concurrent submitters in batches (None = one at a time)
+    pub signature_batching: Option<SignatureBatchConfig>,
+
+    /// Relay rules replacing the `min_fee` and `dust_limit` checks
+    /// (None applies [`DefaultPolicy`](crate::DefaultPolicy))
+    pub policy: Option<Arc<dyn PolicyEngine>>,
 }
 
 impl Default for MempoolConfig {
@@ -43,7 +67,27 @@ impl Default for MempoolConfig {
             min_fee: 1_000,     // 0.001 SYL
             max_age_secs: 3600, // 1 hour
             max_per_sender: 100, // Prevent spam
+            max_bytes_per_sender: 500_000,
+            max_total_bytes: 64 * 1024 * 1024,
             max_nonce_gap: 10,   // Prevent nonce gap attacks
+            dust_limit: DUST_THRESHOLD,
+            chain_params: None,
+            signature_batching: None,
+            policy: None,
+        }
+    }
+}
+
+impl MempoolConfig {
+    /// Default policy with fee and dust limits and the chain ID taken from
+    /// network parameters
+    /// الإعدادات الافتراضية مع حدود الرسوم والغبار من معاملات الشبكة
+    pub fn from_params(params: &ChainParams) -> Self {
+        Self {
+            min_fee: params.min_transaction_fee,
+            dust_limit: params.dust_threshold,
+            chain_params: Some(params.clone()),
+            ..Self::default()
         }
     }
 }
@@ -64,30 +108,106 @@ pub struct Mempool {
     /// Key: sender public key, Value: list of (nonce, tx_hash)
     by_sender: HashMap<[u8; 32], Vec<(u64, [u8; 32])>>,
 
+    /// Total transaction weight per sender
+    sender_bytes: HashMap<[u8; 32], usize>,
+
+    /// Total transaction weight across all senders
+    total_bytes: usize,
+
     /// Transaction validator
     validator: Arc<TransactionValidator>,
 
+    /// Transactions admitted on batch verification alone, awaiting a strict check
+    batch_only: HashSet<[u8; 32]>,
+
     /// Transaction insertion timestamps
     timestamps: HashMap<[u8; 32], u64>,
+
+    /// Optional event bus for TxAdded / TxDropped notifications
+    event_bus: Option<EventBus>,
 }
 
 impl Mempool {
     /// Create a new mempool
+    ///
+    /// With `signature_batching` set this must run inside a Tokio runtime.
     pub fn new(config: MempoolConfig, state: Arc<RwLock<StateStorage>>) -> Self {
-        let validator = Arc::new(TransactionValidator::new(state, config.min_fee));
+        let validator = TransactionValidator::new(state, config.min_fee);
+        Self::with_validator(config, validator)
+    }
+
+    /// Create a mempool for a node without account state
+    ///
+    /// It verifies signatures for relaying; adding a transaction needs
+    /// balances and nonces, so it always fails.
+    pub fn stateless(config: MempoolConfig) -> Self {
+        let validator = TransactionValidator::stateless(config.min_fee);
+        Self::with_validator(config, validator)
+    }
+
+    fn with_validator(config: MempoolConfig, validator: TransactionValidator) -> Self {
+        let mut validator = validator.with_dust_limit(config.dust_limit);
+        if let Some(params) = config.chain_params.clone() {
+            validator = validator.with_chain_params(params);
+        }
+        if let Some(batching) = config.signature_batching {
+            validator = validator.with_signature_batching(batching);
+        }
+        if let Some(policy) = config.policy.clone() {
+            validator = validator.with_policy_engine(policy);
+        }
+        let validator = Arc::new(validator);
 
         Self {
             config,
             transactions: HashMap::new(),
             priority_queue: BTreeMap::new(),
             by_sender: HashMap::new(),
+            sender_bytes: HashMap::new(),
+            total_bytes: 0,
             validator,
+            batch_only: HashSet::new(),
             timestamps: HashMap::new(),
+            event_bus: None,
+        }
+    }
+
+    /// Publish mempool changes on the given event bus
+    /// نشر تغييرات مجمع المعاملات على ناقل الأحداث
+    pub fn with_event_bus(mut self, event_bus: EventBus) -> Self {
+        self.event_bus = Some(event_bus);
+        self
+    }
+
+    fn publish(&self, event: NodeEvent) {
+        if let Some(bus) = &self.event_bus {
+            bus.publish(event);
         }
     }
 
+    /// Validator shared with callers that check signatures before locking the mempool
+    pub fn validator(&self) -> Arc<TransactionValidator> {
+        self.validator.clone()
+    }
+
     /// Add a transaction to the mempool
     pub async fn add_transaction(&mut self, tx: Transaction) -> Result<()> {
+        // Check if already in mempool
+        let tx_hash = tx.hash();
+        if self.transactions.contains_key(&tx_hash) {
+            return Err(MempoolError::DuplicateTransaction(hex::encode(tx_hash)));
+        }
+
+        let verified = self.validator.verify_signature(tx).await?;
+        self.add_verified_transaction(verified).await
+    }
+
+    /// Add a transaction whose signature was checked with
+    /// [`TransactionValidator::verify_signature`]
+    /// إضافة معاملة تم التحقق من توقيعها مسبقاً
+    pub async fn add_verified_transaction(&mut self, verified: VerifiedTransaction) -> Result<()> {
+        let batch_only = verified.is_batch_only();
+        let tx = verified.into_inner();
         let tx_hash = tx.hash();
 
         // Check if already in mempool
@@ -95,20 +215,58 @@ impl Mempool {
             return Err(MempoolError::DuplicateTransaction(hex::encode(tx_hash)));
         }
 
-        // Validate transaction
-        self.validator.validate(&tx).await?;
+        self.check_admission(&tx).await?;
 
-        // Check per-sender limit (DoS protection)
-        let sender_key = tx.from.0;
-        if let Some(sender_txs) = self.by_sender.get(&sender_key) {
-            if sender_txs.len() >= self.config.max_per_sender {
-                return Err(MempoolError::MempoolFull {
-                    max: self.config.max_per_sender,
-                    current: sender_txs.len(),
+        // Check per-sender limits (DoS protection)
+        let weight = tx.weight();
+        self.make_room_for_sender(&tx, weight)?;
+
+        // Check mempool size - evict if full
+        if self.transactions.len() >= self.config.max_size && !self.evict_for_transaction(&tx) {
+            return Err(MempoolError::MempoolFull {
+                max: self.config.max_size,
+                current: self.transactions.len(),
+            });
+        }
+
+        // Check total mempool bytes - evict cheaper transactions until it fits
+        while self.total_bytes + weight > self.config.max_total_bytes {
+            if !self.evict_for_transaction(&tx) {
+                return Err(MempoolError::MempoolBytesFull {
+                    max_bytes: self.config.max_total_bytes,
+                    current_bytes: self.total_bytes,
                 });
             }
         }
 
+        if batch_only {
+            self.batch_only.insert(tx_hash);
+        }
+        self.insert(tx, weight);
+        Ok(())
+    }
+
+    /// Check `tx` as [`Self::add_transaction`] would, without adding it
+    /// فحص المعاملة كما في الإضافة دون إضافتها
+    ///
+    /// Capacity is left out: a full mempool makes room by evicting cheaper
+    /// transactions, which a dry run does not do.
+    pub async fn check_transaction(&self, tx: &Transaction) -> Result<()> {
+        let tx_hash = tx.hash();
+        if self.transactions.contains_key(&tx_hash) {
+            return Err(MempoolError::DuplicateTransaction(hex::encode(tx_hash)));
+        }
+
+        let verified = self.validator.verify_signature(tx.clone()).await?;
+        self.check_admission(verified.transaction()).await
+    }
+
+    /// Fee, dust, balance, nonce and asset checks on top of the sender's
+    /// pending transactions
+    async fn check_admission(&self, tx: &Transaction) -> Result<()> {
+        let pending = self.get_sender_transactions(&tx.from.0);
+        self.validator.validate_with_pending(tx, &pending).await?;
+
         // Check nonce gap (prevent nonce gap attacks)
         let current_nonce = self.validator.get_current_nonce(&tx.from).await?;
         if tx.nonce > current_nonce + self.config.max_nonce_gap {
@@ -117,17 +275,39 @@ impl Mempool {
                 got: tx.nonce,
             });
         }
+        Ok(())
+    }
 
-        // Check mempool size - evict if full
-        if self.transactions.len() >= self.config.max_size {
-            // Try to evict lowest fee transaction
-            if !self.evict_lowest_fee_transaction(&tx) {
-                return Err(MempoolError::MempoolFull {
-                    max: self.config.max_size,
-                    current: self.transactions.len(),
-                });
+    /// Strictly re-verify signatures admitted by batch verification alone
+    /// إعادة التحقق الصارم من التواقيع المقبولة بالتحقق الدفعي
+    ///
+    /// Batch verification accepts a few crafted signatures that single
+    /// verification, and therefore block validation, rejects. Run this
+    /// before building a block template; returns how many were dropped.
+    pub fn recheck_signatures(&mut self) -> usize {
+        let pending: Vec<[u8; 32]> = self.batch_only.drain().collect();
+        let mut dropped = 0;
+        for hash in pending {
+            let invalid = self
+                .transactions
+                .get(&hash)
+                .is_some_and(|tx| tx.verify().is_err());
+            if invalid {
+                warn!(
+                    "Dropping transaction {}: signature fails strict verification",
+                    hex::encode(hash)
+                );
+                self.drop_transaction(&hash, DropReason::Invalid);
+                dropped += 1;
             }
         }
+        dropped
+    }
+
+    /// Index an already validated transaction
+    fn insert(&mut self, tx: Transaction, weight: usize) {
+        let tx_hash = tx.hash();
+        let sender_key = tx.from.0;
 
         info!(
             "Adding transaction to mempool: {} SYL from {}... to {}...",
@@ -136,10 +316,8 @@ impl Mempool {
             hex::encode(&tx.to.0[..8])
         );
 
-        // Calculate fee density (fee per byte) for priority
-        let config = bincode::config::standard();
-        let tx_size = bincode::encode_to_vec(&tx, config).map_err(|_| MempoolError::InvalidTransaction)?.len();
-        let fee_density = (tx.fee as f64 / tx_size as f64 * 1000.0) as u64; // fee per KB
+        // Fee density (fee per KB) for priority, same rate the indexer reports
+        let fee_density = tx.fee_rate();
 
         // Add to priority queue (higher fee density = higher priority)
         let priority_key = (u64::MAX - fee_density, tx_hash);
@@ -150,6 +328,8 @@ impl Mempool {
             .entry(sender_key)
             .or_default()
             .push((tx.nonce, tx_hash));
+        *self.sender_bytes.entry(sender_key).or_default() += weight;
+        self.total_bytes += weight;
 
         // Add timestamp
         let now = std::time::SystemTime::now()
@@ -159,52 +339,119 @@ impl Mempool {
         self.timestamps.insert(tx_hash, now);
 
         // Add transaction
+        self.publish(NodeEvent::TxAdded(tx.clone()));
         self.transactions.insert(tx_hash, tx);
 
         debug!("Mempool size: {}", self.transactions.len());
+    }
+
+    /// Keep the sender of `tx` within `max_per_sender` and `max_bytes_per_sender`
+    ///
+    /// At the cap, the sender's own transactions with nonces above `tx.nonce`
+    /// are evicted, highest first; a transaction that would itself be the
+    /// sender's highest nonce is rejected. Only the sender's transactions are
+    /// touched, so one sender's chain can never push out anyone else's.
+    fn make_room_for_sender(&mut self, tx: &Transaction, weight: usize) -> Result<()> {
+        let max_count = self.config.max_per_sender;
+        let max_bytes = self.config.max_bytes_per_sender;
+        let sender_key = tx.from.0;
 
+        let mut pending = self.by_sender.get(&sender_key).cloned().unwrap_or_default();
+        let mut bytes = self.sender_bytes.get(&sender_key).copied().unwrap_or(0);
+        pending.sort_unstable_by_key(|(nonce, _)| *nonce);
+
+        let fits = |count: usize, bytes: usize| count < max_count && bytes + weight <= max_bytes;
+        let mut victims = Vec::new();
+        while !fits(pending.len(), bytes) {
+            match pending.last() {
+                Some((nonce, hash)) if *nonce > tx.nonce => {
+                    let weight = self.transactions.get(hash).map_or(0, |tx| tx.weight());
+                    bytes = bytes.saturating_sub(weight);
+                    victims.push(*hash);
+                    pending.pop();
+                }
+                _ => {
+                    return Err(MempoolError::SenderLimitExceeded {
+                        max_count,
+                        max_bytes,
+                    })
+                }
+            }
+        }
+
+        for hash in victims {
+            warn!(
+                "Evicting transaction {} to keep sender {}... within its mempool limits",
+                hex::encode(&hash[..8]),
+                hex::encode(&sender_key[..8])
+            );
+            self.drop_transaction(&hash, DropReason::Evicted);
+        }
         Ok(())
     }
 
-    /// Evict lowest fee transaction if new transaction has higher fee
-    /// Returns true if eviction successful, false if new tx has lower fee
-    fn evict_lowest_fee_transaction(&mut self, new_tx: &Transaction) -> bool {
-        // Get the lowest fee transaction
-        if let Some((lowest_key, _)) = self.priority_queue.iter().next_back() {
-            let lowest_hash = lowest_key.1;
-            
-            if let Some(lowest_tx) = self.transactions.get(&lowest_hash) {
-                // Calculate fee densities
-                let config = bincode::config::standard();
-                let new_tx_size = bincode::encode_to_vec(new_tx, config).unwrap_or_default().len().max(1);
-                let lowest_tx_size = bincode::encode_to_vec(lowest_tx, config).unwrap_or_default().len().max(1);
-                
-                let new_fee_density = new_tx.fee as f64 / new_tx_size as f64;
-                let lowest_fee_density = lowest_tx.fee as f64 / lowest_tx_size as f64;
-
-                // Only evict if new transaction has higher fee density
-                if new_fee_density > lowest_fee_density {
-                    warn!("Evicting transaction {} (fee density: {:.2}) for higher fee transaction (fee density: {:.2})",
-                        hex::encode(&lowest_hash[..8]),
-                        lowest_fee_density,
-                        new_fee_density
-                    );
-                    self.remove_transaction(&lowest_hash);
-                    return true;
-                }
+    /// Make room in a full mempool if `new_tx` pays a higher fee rate
+    ///
+    /// Candidates are the highest-nonce transaction of the sender holding the
+    /// most bytes, then that of the sender of the lowest fee-rate transaction.
+    /// Evicting from the top of a sender's chain keeps the rest of it minable.
+    /// Returns true if a transaction was evicted.
+    fn evict_for_transaction(&mut self, new_tx: &Transaction) -> bool {
+        let new_fee_rate = new_tx.fee_rate();
+        let largest_sender = self
+            .sender_bytes
+            .iter()
+            .max_by_key(|(_, bytes)| **bytes)
+            .map(|(sender, _)| *sender);
+        let cheapest_sender = self
+            .priority_queue
+            .keys()
+            .next_back()
+            .and_then(|(_, hash)| self.transactions.get(hash))
+            .map(|tx| tx.from.0);
+
+        for sender in [largest_sender, cheapest_sender].into_iter().flatten() {
+            // A sender cannot make room for itself at the cost of a nonce gap
+            if sender == new_tx.from.0 {
+                continue;
+            }
+            let Some(hash) = self.highest_nonce_transaction(&sender) else {
+                continue;
+            };
+            let fee_rate = self.transactions.get(&hash).map_or(0, |tx| tx.fee_rate());
+            if new_fee_rate > fee_rate {
+                warn!(
+                    "Evicting transaction {} (fee rate: {}) for higher fee transaction (fee rate: {})",
+                    hex::encode(&hash[..8]),
+                    fee_rate,
+                    new_fee_rate
+                );
+                self.drop_transaction(&hash, DropReason::Evicted);
+                return true;
             }
         }
         false
     }
 
+    /// Pending transaction of `sender` with the highest nonce
+    fn highest_nonce_transaction(&self, sender: &[u8; 32]) -> Option<[u8; 32]> {
+        self.by_sender
+            .get(sender)?
+            .iter()
+            .max_by_key(|(nonce, _)| *nonce)
+            .map(|(_, hash)| *hash)
+    }
+
     /// Remove a transaction from the mempool
     pub fn remove_transaction(&mut self, tx_hash: &[u8; 32]) -> Option<Transaction> {
+        self.drop_transaction(tx_hash, DropReason::Removed)
+    }
+
+    /// Remove a transaction and publish why it left the mempool
+    fn drop_transaction(&mut self, tx_hash: &[u8; 32], reason: DropReason) -> Option<Transaction> {
         if let Some(tx) = self.transactions.remove(tx_hash) {
-            // Remove from priority queue
-            let config = bincode::config::standard();
-            let tx_size = bincode::encode_to_vec(&tx, config).unwrap_or_default().len().max(1);
-            let fee_density = (tx.fee as f64 / tx_size as f64 * 1000.0) as u64;
-            let priority_key = (u64::MAX - fee_density, *tx_hash);
+            // Remove from priority queue (same key as on insertion)
+            let priority_key = (u64::MAX - tx.fee_rate(), *tx_hash);
             self.priority_queue.remove(&priority_key);
 
             // Remove from sender index
@@ -214,11 +461,23 @@ impl Mempool {
                     self.by_sender.remove(&tx.from.0);
                 }
             }
+            self.total_bytes = self.total_bytes.saturating_sub(tx.weight());
+            if let Some(bytes) = self.sender_bytes.get_mut(&tx.from.0) {
+                *bytes = bytes.saturating_sub(tx.weight());
+                if !self.by_sender.contains_key(&tx.from.0) {
+                    self.sender_bytes.remove(&tx.from.0);
+                }
+            }
 
             // Remove timestamp
             self.timestamps.remove(tx_hash);
+            self.batch_only.remove(tx_hash);
 
             info!("Removed transaction from mempool: {}", hex::encode(tx_hash));
+            self.publish(NodeEvent::TxDropped {
+                hash: *tx_hash,
+                reason,
+            });
 
             Some(tx)
         } else {
@@ -227,61 +486,60 @@ impl Mempool {
     }
 
     /// Replace a transaction with a higher fee version (RBF - Replace-by-Fee)
-    /// Returns Ok if replacement successful, Err if fee not higher or tx not found
+    /// استبدال معاملة معلقة بأخرى ذات رسوم أعلى
+    ///
+    /// The pending transaction with the same sender and nonce is replaced if
+    /// `new_tx` passes [`check_replacement`]; without one, `new_tx` is added
+    /// like any other transaction.
     pub async fn replace_transaction(&mut self, new_tx: Transaction) -> Result<()> {
+        let verified = self.validator.verify_signature(new_tx).await?;
+        self.replace_verified_transaction(verified).await
+    }
+
+    /// [`Self::replace_transaction`] for a transaction whose signature was
+    /// checked with [`TransactionValidator::verify_signature`]
+    pub async fn replace_verified_transaction(
+        &mut self,
+        verified: VerifiedTransaction,
+    ) -> Result<()> {
+        let new_tx = verified.transaction();
         let new_hash = new_tx.hash();
-        let sender_key = new_tx.from.0;
 
         // Find existing transaction with same nonce from same sender
-        let existing_tx_hash = self
-            .by_sender
-            .get(&sender_key)
-            .and_then(|txs| {
-                txs.iter()
-                    .find(|(nonce, _)| *nonce == new_tx.nonce)
-                    .map(|(_, hash)| *hash)
-            });
-
-        if let Some(old_hash) = existing_tx_hash {
-            if let Some(old_tx) = self.transactions.get(&old_hash) {
-                // Calculate fee densities
-                let config = bincode::config::standard();
-                let old_size = bincode::encode_to_vec(old_tx, config).unwrap_or_default().len().max(1);
-                let new_size = bincode::encode_to_vec(&new_tx, config).unwrap_or_default().len().max(1);
-                
-                let old_fee_density = old_tx.fee as f64 / old_size as f64;
-                let new_fee_density = new_tx.fee as f64 / new_size as f64;
-
-                // Require at least 10% higher fee density
-                if new_fee_density <= old_fee_density * 1.1 {
-                    return Err(MempoolError::FeeTooLow {
-                        min: (old_fee_density * 1.1) as u64,
-                        got: new_fee_density as u64,
-                    });
-                }
-
-                // Clone fee for logging before removing
-                let old_fee = old_tx.fee;
-                let new_fee = new_tx.fee;
-
-                // Drop immutable borrow before calling remove_transaction
-                let _ = old_tx;
-
-                // Remove old transaction
-                self.remove_transaction(&old_hash);
-
-                info!(
-                    "Replaced transaction {} with {} (fee: {} -> {})",
-                    hex::encode(&old_hash[..8]),
-                    hex::encode(&new_hash[..8]),
-                    old_fee,
-                    new_fee
-                );
-            }
+        let existing_tx_hash = self.by_sender.get(&new_tx.from.0).and_then(|txs| {
+            txs.iter()
+                .find(|(nonce, _)| *nonce == new_tx.nonce)
+                .map(|(_, hash)| *hash)
+        });
+
+        let Some(old_tx) = existing_tx_hash.and_then(|hash| self.transactions.get(&hash).cloned())
+        else {
+            return self.add_verified_transaction(verified).await;
+        };
+        let old_hash = old_tx.hash();
+        if old_hash == new_hash {
+            return Err(MempoolError::DuplicateTransaction(hex::encode(new_hash)));
+        }
+        check_replacement(&old_tx, new_tx)?;
+
+        // Remove old transaction, restoring it if the replacement is refused so
+        // a failed replacement never leaves the nonce empty
+        self.drop_transaction(&old_hash, DropReason::Replaced);
+        let (old_fee, new_fee) = (old_tx.fee, new_tx.fee);
+        if let Err(e) = self.add_verified_transaction(verified).await {
+            let weight = old_tx.weight();
+            self.insert(old_tx, weight);
+            return Err(e);
         }
 
-        // Add new transaction
-        self.add_transaction(new_tx).await
+        info!(
+            "Replaced transaction {} with {} (fee: {} -> {})",
+            hex::encode(&old_hash[..8]),
+            hex::encode(&new_hash[..8]),
+            old_fee,
+            new_fee
+        );
+        Ok(())
     }
 
     /// Get priority transactions ordered by priority (highest fee first)
@@ -293,6 +551,16 @@ impl Mempool {
             .collect()
     }
 
+    /// Transactions for the next block, in deterministic block order
+    /// معاملات الكتلة التالية بترتيب حتمي
+    ///
+    /// Unlike [`Self::get_priority_transactions`], each sender's transactions
+    /// follow nonce order; see [`opensyria_core::assembly`]. Call
+    /// [`Self::recheck_signatures`] first when signature batching is enabled.
+    pub fn select_block_transactions(&self, max_count: usize) -> Vec<Transaction> {
+        select_transactions(self.transactions.values().cloned(), max_count)
+    }
+
     /// Get all pending transactions
     pub fn get_all_transactions(&self) -> Vec<Transaction> {
         self.transactions.values().cloned().collect()
@@ -319,6 +587,11 @@ impl Mempool {
         self.transactions.len()
     }
 
+    /// Total weight of pending transactions in bytes
+    pub fn size_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
     /// Check if mempool is empty
     pub fn is_empty(&self) -> bool {
         self.transactions.is_empty()
@@ -340,7 +613,7 @@ impl Mempool {
         }
 
         for hash in &expired {
-            self.remove_transaction(hash);
+            self.drop_transaction(hash, DropReason::Expired);
         }
 
         if !expired.is_empty() {
@@ -356,19 +629,136 @@ impl Mempool {
             "Clearing mempool ({} transactions)",
             self.transactions.len()
         );
+        if self.event_bus.is_some() {
+            for hash in self.transactions.keys() {
+                self.publish(NodeEvent::TxDropped {
+                    hash: *hash,
+                    reason: DropReason::Removed,
+                });
+            }
+        }
         self.transactions.clear();
         self.priority_queue.clear();
         self.by_sender.clear();
+        self.sender_bytes.clear();
+        self.total_bytes = 0;
         self.timestamps.clear();
+        self.batch_only.clear();
     }
 
     /// Remove transactions that are included in a block
     pub fn remove_confirmed_transactions(&mut self, transactions: &[Transaction]) {
         for tx in transactions {
             let hash = tx.hash();
-            self.remove_transaction(&hash);
+            self.drop_transaction(&hash, DropReason::Confirmed);
+        }
+    }
+
+    /// Re-validate and reinsert transactions from blocks removed by a reorg
+    /// إعادة إدراج معاملات الكتل المفصولة بعد إعادة تنظيم السلسلة
+    ///
+    /// Coinbase transactions and transactions already pending are skipped.
+    /// Transactions that no longer validate against current state (e.g. a
+    /// conflicting spend on the new branch) are discarded. State must already
+    /// be rolled back to the fork point. Returns hashes of reinserted transactions.
+    pub async fn resurrect_transactions(&mut self, transactions: &[Transaction]) -> Vec<[u8; 32]> {
+        let mut candidates: Vec<&Transaction> =
+            transactions.iter().filter(|tx| !tx.is_coinbase()).collect();
+        // Lower nonces first so each sender's sequence stays gap-free
+        candidates.sort_by_key(|tx| (tx.from.0, tx.nonce));
+
+        let mut resurrected = Vec::new();
+        for tx in candidates {
+            let tx_hash = tx.hash();
+            if self.transactions.contains_key(&tx_hash) {
+                continue;
+            }
+            match self.add_transaction(tx.clone()).await {
+                Ok(()) => resurrected.push(tx_hash),
+                Err(e) => debug!(
+                    "Not resurrecting transaction {}: {}",
+                    hex::encode(&tx_hash[..8]),
+                    e
+                ),
+            }
+        }
+
+        if !resurrected.is_empty() {
+            info!(
+                "Resurrected {} transactions from disconnected blocks",
+                resurrected.len()
+            );
+        }
+
+        resurrected
+    }
+
+    /// Update the mempool after a chain reorganization
+    /// تحديث مجمع المعاملات بعد إعادة تنظيم السلسلة
+    ///
+    /// Transactions confirmed by the `connected` branch are removed, and
+    /// transactions from `disconnected` blocks that the new branch did not
+    /// include are reinserted. Returns hashes of reinserted transactions.
+    pub async fn handle_reorg(
+        &mut self,
+        disconnected: &[Block],
+        connected: &[Block],
+    ) -> Vec<[u8; 32]> {
+        let mut confirmed = HashSet::new();
+        for block in connected {
+            self.remove_confirmed_transactions(&block.transactions);
+            confirmed.extend(block.transactions.iter().map(|tx| tx.hash()));
         }
+
+        let orphaned: Vec<Transaction> = disconnected
+            .iter()
+            .flat_map(|block| block.transactions.iter())
+            .filter(|tx| !confirmed.contains(&tx.hash()))
+            .cloned()
+            .collect();
+
+        self.resurrect_transactions(&orphaned).await
     }
+
+    /// Apply a block event from the node event bus
+    ///
+    /// Connected blocks evict their transactions. Disconnected blocks are left
+    /// to [`Self::handle_reorg`], which the node calls once the state is
+    /// rolled back; their events arrive before that, tip first.
+    pub async fn handle_event(&mut self, event: &NodeEvent) {
+        if let NodeEvent::BlockConnected { block, .. } = event {
+            self.remove_confirmed_transactions(&block.transactions);
+        }
+    }
+}
+
+/// Smallest fee rate increase, in percent, for a pending transaction to be
+/// replaced
+pub const MIN_REPLACEMENT_BUMP_PERCENT: u64 = 10;
+
+/// Lowest fee that replaces `replaced` with a transaction of `weight` bytes
+/// أدنى رسوم لاستبدال معاملة معلقة
+pub fn replacement_fee(replaced: &Transaction, weight: usize) -> u64 {
+    let fee = replaced.fee as u128 * weight as u128 * (100 + MIN_REPLACEMENT_BUMP_PERCENT) as u128
+        / (replaced.weight().max(1) as u128 * 100);
+    (fee + 1).min(u64::MAX as u128) as u64
+}
+
+/// Check that `replacement` pays a fee rate more than
+/// [`MIN_REPLACEMENT_BUMP_PERCENT`] above `replaced`
+/// التحقق من أن المعاملة البديلة تدفع رسوماً كافية
+///
+/// Rates are compared exactly, without rounding to the per-1000-byte
+/// [`Transaction::fee_rate`]; the error names the lowest fee that would do.
+pub fn check_replacement(replaced: &Transaction, replacement: &Transaction) -> Result<()> {
+    let min = replacement_fee(replaced, replacement.weight());
+    if replacement.fee < min {
+        return Err(MempoolError::FeeTooLow {
+            min,
+            got: replacement.fee,
+        });
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -404,8 +794,16 @@ mod tests {
         let sig = sender.sign(&msg);
         tx.signature = sig;
 
-        assert!(mempool.add_transaction(tx).await.is_ok());
+        // A dry run accepts it without adding it
+        assert!(mempool.check_transaction(&tx).await.is_ok());
+        assert!(mempool.is_empty());
+
+        assert!(mempool.add_transaction(tx.clone()).await.is_ok());
         assert_eq!(mempool.size(), 1);
+        assert!(matches!(
+            mempool.check_transaction(&tx).await,
+            Err(MempoolError::DuplicateTransaction(_))
+        ));
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
@@ -497,4 +895,502 @@ mod tests {
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
+
+    #[tokio::test]
+    async fn test_event_bus_notifications() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_events_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+        state.set_nonce(&sender.public_key(), 0).unwrap();
+
+        let bus = EventBus::default();
+        let mut events = bus.subscribe();
+        let state = Arc::new(RwLock::new(state));
+        let mut mempool = Mempool::new(MempoolConfig::default(), state).with_event_bus(bus);
+
+        let mut tx = Transaction::new(
+            sender.public_key(),
+            receiver.public_key(),
+            100_000,
+            1_000,
+            0,
+        );
+        tx.signature = sender.sign(&tx.signing_hash());
+        let tx_hash = tx.hash();
+
+        mempool.add_transaction(tx.clone()).await.unwrap();
+        assert!(
+            matches!(events.try_recv().unwrap(), NodeEvent::TxAdded(added) if added.hash() == tx_hash)
+        );
+
+        mempool.remove_confirmed_transactions(&[tx]);
+        match events.try_recv().unwrap() {
+            NodeEvent::TxDropped { hash, reason } => {
+                assert_eq!(hash, tx_hash);
+                assert_eq!(reason, DropReason::Confirmed);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_handle_reorg_resurrects_orphaned_transactions() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_reorg_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate();
+        let receiver = KeyPair::generate();
+
+        for sender in [&alice, &bob] {
+            state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+            state.set_nonce(&sender.public_key(), 0).unwrap();
+        }
+
+        let state = Arc::new(RwLock::new(state));
+        let mut mempool = Mempool::new(MempoolConfig::default(), state);
+
+        let signed = |sender: &KeyPair| {
+            let mut tx =
+                Transaction::new(sender.public_key(), receiver.public_key(), 10_000, 1_000, 0);
+            tx.signature = sender.sign(&tx.signing_hash());
+            tx
+        };
+        let orphaned = signed(&alice);
+        let reconfirmed = signed(&bob);
+
+        let fork_point = Block::genesis();
+        let old_branch = Block::new(
+            fork_point.hash(),
+            vec![orphaned.clone(), reconfirmed.clone()],
+            16,
+        );
+        let new_branch = Block::new(fork_point.hash(), vec![reconfirmed.clone()], 16);
+
+        let resurrected = mempool.handle_reorg(&[old_branch], &[new_branch]).await;
+
+        assert_eq!(resurrected, vec![orphaned.hash()]);
+        assert!(mempool.get_transaction(&orphaned.hash()).is_some());
+        assert!(mempool.get_transaction(&reconfirmed.hash()).is_none());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_reorg_resurrects_after_state_revert() {
+        use opensyria_core::{crypto::PublicKey, CHAIN_ID_MAINNET};
+        use opensyria_storage::BlockchainStorage;
+
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain = BlockchainStorage::open(dir.path().join("blockchain")).unwrap();
+        let state = StateStorage::open(dir.path().join("state")).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        let (miner, rival) = (
+            KeyPair::generate().public_key(),
+            KeyPair::generate().public_key(),
+        );
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+
+        let genesis = Block::genesis();
+        blockchain.append_block(&genesis, None).unwrap();
+        state
+            .apply_block_at_height(1, &genesis.transactions)
+            .unwrap();
+
+        let mine =
+            |parent: &Block, height: u64, miner: PublicKey, mut transactions: Vec<Transaction>| {
+                let fees = transactions.iter().map(|tx| tx.fee).sum();
+                let coinbase =
+                    Transaction::coinbase(CHAIN_ID_MAINNET, miner, height, fees).unwrap();
+                transactions.insert(0, coinbase);
+                let mut block = Block::new(
+                    parent.hash(),
+                    transactions,
+                    blockchain.next_difficulty().unwrap(),
+                );
+                block.header.timestamp = parent.header.timestamp + 60;
+                while !block.header.meets_difficulty() {
+                    block.header.nonce += 1;
+                }
+                block
+            };
+
+        // The old branch confirms two spends, with state following it
+        let spends = [
+            signed(&sender, &receiver, 100_000, 1_000, 0),
+            signed(&sender, &receiver, 100_000, 1_000, 1),
+        ];
+        let block2 = mine(&genesis, 2, miner, vec![spends[0].clone()]);
+        let block3 = mine(&block2, 3, miner, vec![spends[1].clone()]);
+        for (height, block) in [(2, &block2), (3, &block3)] {
+            blockchain.append_block(block, Some(&state)).unwrap();
+            state
+                .apply_block_at_height(height, &block.transactions)
+                .unwrap();
+        }
+
+        // A longer branch without them replaces it
+        let fork2 = mine(&genesis, 2, rival, vec![]);
+        let fork3 = mine(&fork2, 3, rival, vec![]);
+        let fork4 = mine(&fork3, 4, rival, vec![]);
+        let branch = vec![fork2, fork3, fork4];
+        let disconnected = blockchain
+            .reorganize(1, branch.clone(), Some(&state))
+            .unwrap();
+        assert_eq!(state.get_state_height().unwrap(), 4);
+        assert_eq!(state.get_nonce(&sender.public_key()).unwrap(), 0);
+        assert_eq!(state.get_balance(&sender.public_key()).unwrap(), 1_000_000);
+
+        // Both spends validate against the new tip and come back in order
+        let state = Arc::new(RwLock::new(state));
+        let mut mempool = Mempool::new(MempoolConfig::default(), state);
+        let resurrected = mempool.handle_reorg(&disconnected, &branch).await;
+        assert_eq!(resurrected, vec![spends[0].hash(), spends[1].hash()]);
+    }
+
+    fn signed(sender: &KeyPair, to: &KeyPair, amount: u64, fee: u64, nonce: u64) -> Transaction {
+        let mut tx = Transaction::new(sender.public_key(), to.public_key(), amount, fee, nonce);
+        tx.signature = sender.sign(&tx.signing_hash());
+        tx
+    }
+
+    #[tokio::test]
+    async fn test_sender_limit_evicts_own_highest_nonce() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_sender_limit_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let spammer = KeyPair::generate();
+        let competitor = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        for sender in [&spammer, &competitor] {
+            state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+            state.set_nonce(&sender.public_key(), 0).unwrap();
+        }
+
+        let config = MempoolConfig {
+            max_per_sender: 3,
+            ..MempoolConfig::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        // The spammer fills its quota with future nonces
+        for nonce in 1..=3 {
+            mempool
+                .add_transaction(signed(&spammer, &receiver, 10_000, 1_000, nonce))
+                .await
+                .unwrap();
+        }
+        let over_quota = signed(&spammer, &receiver, 10_000, 1_000, 4);
+        assert!(matches!(
+            mempool.add_transaction(over_quota).await,
+            Err(MempoolError::SenderLimitExceeded { max_count: 3, .. })
+        ));
+
+        // Others are unaffected by the spammer's quota
+        let competing = signed(&competitor, &receiver, 10_000, 1_000, 0);
+        mempool.add_transaction(competing.clone()).await.unwrap();
+
+        // A lower nonce displaces the spammer's own highest one
+        let top = signed(&spammer, &receiver, 10_000, 1_000, 3);
+        mempool
+            .add_transaction(signed(&spammer, &receiver, 10_000, 1_000, 0))
+            .await
+            .unwrap();
+        let mut nonces: Vec<u64> = mempool
+            .get_sender_transactions(&spammer.public_key().0)
+            .iter()
+            .map(|tx| tx.nonce)
+            .collect();
+        nonces.sort_unstable();
+        assert_eq!(nonces, vec![0, 1, 2]);
+        assert!(mempool.get_transaction(&top.hash()).is_none());
+        assert!(mempool.get_transaction(&competing.hash()).is_some());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_sender_byte_limit() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_sender_bytes_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+        state.set_nonce(&sender.public_key(), 0).unwrap();
+
+        let weight = signed(&sender, &receiver, 10_000, 1_000, 0).weight();
+        let config = MempoolConfig {
+            max_bytes_per_sender: weight * 2 + weight / 2,
+            ..MempoolConfig::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        for nonce in 0..2 {
+            mempool
+                .add_transaction(signed(&sender, &receiver, 10_000, 1_000, nonce))
+                .await
+                .unwrap();
+        }
+        let result = mempool
+            .add_transaction(signed(&sender, &receiver, 10_000, 1_000, 2))
+            .await;
+        assert!(matches!(
+            result,
+            Err(MempoolError::SenderLimitExceeded { .. })
+        ));
+
+        // Confirming a transaction frees the sender's budget
+        let confirmed = signed(&sender, &receiver, 10_000, 1_000, 0);
+        mempool.remove_confirmed_transactions(&[confirmed]);
+        mempool
+            .add_transaction(signed(&sender, &receiver, 10_000, 1_000, 2))
+            .await
+            .unwrap();
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_full_pool_evicts_from_largest_sender() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_full_evict_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let spammer = KeyPair::generate();
+        let competitor = KeyPair::generate();
+        let newcomer = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        for sender in [&spammer, &competitor, &newcomer] {
+            state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+            state.set_nonce(&sender.public_key(), 0).unwrap();
+        }
+
+        let config = MempoolConfig {
+            max_size: 4,
+            ..MempoolConfig::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        let competing = signed(&competitor, &receiver, 10_000, 1_000, 0);
+        mempool.add_transaction(competing.clone()).await.unwrap();
+        for nonce in 0..3 {
+            mempool
+                .add_transaction(signed(&spammer, &receiver, 10_000, 2_000, nonce))
+                .await
+                .unwrap();
+        }
+
+        // A cheap transaction cannot displace anything
+        let cheap = signed(&newcomer, &receiver, 10_000, 1_000, 0);
+        assert!(matches!(
+            mempool.add_transaction(cheap).await,
+            Err(MempoolError::MempoolFull { .. })
+        ));
+
+        // A better-paying one takes the top of the spammer's chain, not the
+        // competitor's only transaction
+        let spam_top = signed(&spammer, &receiver, 10_000, 2_000, 2);
+        mempool
+            .add_transaction(signed(&newcomer, &receiver, 10_000, 5_000, 0))
+            .await
+            .unwrap();
+        assert_eq!(mempool.size(), 4);
+        assert!(mempool.get_transaction(&spam_top.hash()).is_none());
+        assert!(mempool.get_transaction(&competing.hash()).is_some());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_byte_limit_evicts_cheaper_transactions() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_byte_limit_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let cheap = KeyPair::generate();
+        let generous = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        for sender in [&cheap, &generous] {
+            state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+            state.set_nonce(&sender.public_key(), 0).unwrap();
+        }
+
+        let weight = signed(&cheap, &receiver, 10_000, 1_000, 0).weight();
+        let config = MempoolConfig {
+            max_total_bytes: weight * 2,
+            ..MempoolConfig::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        for nonce in 0..2 {
+            mempool
+                .add_transaction(signed(&cheap, &receiver, 10_000, 1_000, nonce))
+                .await
+                .unwrap();
+        }
+        assert_eq!(mempool.size_bytes(), weight * 2);
+        assert!(matches!(
+            mempool
+                .add_transaction(signed(&generous, &receiver, 10_000, 1_000, 0))
+                .await,
+            Err(MempoolError::MempoolBytesFull { .. })
+        ));
+
+        let better = signed(&generous, &receiver, 10_000, 5_000, 0);
+        mempool.add_transaction(better.clone()).await.unwrap();
+        assert_eq!(mempool.size(), 2);
+        assert_eq!(mempool.size_bytes(), weight * 2);
+        assert!(mempool.get_transaction(&better.hash()).is_some());
+
+        mempool.remove_transaction(&better.hash());
+        assert_eq!(mempool.size_bytes(), weight);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_refused_replacement_keeps_original() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_replace_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let other = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        for account in [&sender, &other] {
+            state.set_balance(&account.public_key(), 1_000_000).unwrap();
+            state.set_nonce(&account.public_key(), 0).unwrap();
+        }
+        let mut mempool = Mempool::new(MempoolConfig::default(), Arc::new(RwLock::new(state)));
+
+        let original = signed(&sender, &receiver, 100_000, 1_000, 0);
+        let competing = signed(&other, &receiver, 100_000, 1_000, 0);
+        mempool.add_transaction(original.clone()).await.unwrap();
+        mempool.add_transaction(competing.clone()).await.unwrap();
+
+        // Higher fee but unaffordable: refused, and the original stays
+        let unaffordable = signed(&sender, &receiver, 2_000_000, 5_000, 0);
+        assert!(mempool.replace_transaction(unaffordable).await.is_err());
+        assert!(mempool.get_transaction(&original.hash()).is_some());
+
+        // Replacements only ever match the signer's own nonces
+        let bump = signed(&other, &receiver, 100_000, 5_000, 0);
+        mempool.replace_transaction(bump.clone()).await.unwrap();
+        assert!(mempool.get_transaction(&competing.hash()).is_none());
+        assert!(mempool.get_transaction(&bump.hash()).is_some());
+        assert!(mempool.get_transaction(&original.hash()).is_some());
+        assert_eq!(mempool.size(), 2);
+
+        // A bump under 10% of the fee rate is refused with the fee it needs
+        let small_bump = signed(&other, &receiver, 100_000, 5_400, 0);
+        let min = replacement_fee(&bump, small_bump.weight());
+        assert!(small_bump.fee > bump.fee && small_bump.fee < min);
+        assert!(matches!(
+            mempool.replace_transaction(small_bump).await,
+            Err(MempoolError::FeeTooLow { min: m, got: 5_400 }) if m == min
+        ));
+        assert!(mempool.get_transaction(&bump.hash()).is_some());
+        let enough = signed(&other, &receiver, 100_000, min, 0);
+        mempool.replace_transaction(enough.clone()).await.unwrap();
+        assert!(mempool.get_transaction(&enough.hash()).is_some());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_block_selection_follows_sender_nonces() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_selection_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+        state.set_nonce(&sender.public_key(), 0).unwrap();
+        let mut mempool = Mempool::new(MempoolConfig::default(), Arc::new(RwLock::new(state)));
+
+        let first = signed(&sender, &receiver, 10_000, 1_000, 0);
+        let second = signed(&sender, &receiver, 10_000, 5_000, 1);
+        mempool.add_transaction(second.clone()).await.unwrap();
+        mempool.add_transaction(first.clone()).await.unwrap();
+
+        // Priority order puts the higher fee first; block order cannot
+        assert_eq!(
+            mempool.get_priority_transactions(2)[0].hash(),
+            second.hash()
+        );
+        let selected: Vec<_> = mempool
+            .select_block_transactions(10)
+            .iter()
+            .map(|tx| tx.hash())
+            .collect();
+        assert_eq!(selected, vec![first.hash(), second.hash()]);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+    #[tokio::test]
+    async fn test_batch_verified_admission_is_rechecked() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_batch_admission_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let receiver = KeyPair::generate();
+        let senders: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate()).collect();
+        for sender in &senders {
+            state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+        }
+        let config = MempoolConfig {
+            signature_batching: Some(SignatureBatchConfig {
+                max_batch: 4,
+                window: std::time::Duration::from_millis(50),
+            }),
+            ..Default::default()
+        };
+        let mut mempool = Mempool::new(config, Arc::new(RwLock::new(state)));
+
+        // Verify outside the pool, as the network does, so the checks share a batch
+        let validator = mempool.validator();
+        let handles: Vec<_> = senders
+            .iter()
+            .map(|sender| {
+                let validator = validator.clone();
+                let tx = signed(sender, &receiver, 10_000, 1_000, 0);
+                tokio::spawn(async move { validator.verify_signature(tx).await })
+            })
+            .collect();
+        for handle in handles {
+            let verified = handle.await.unwrap().unwrap();
+            assert!(verified.is_batch_only());
+            mempool.add_verified_transaction(verified).await.unwrap();
+        }
+        assert_eq!(mempool.batch_only.len(), 4);
+
+        // Valid signatures survive the strict re-check
+        assert_eq!(mempool.recheck_signatures(), 0);
+        assert!(mempool.batch_only.is_empty());
+        assert_eq!(mempool.select_block_transactions(10).len(), 4);
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
 }
@@ -1,23 +1,333 @@
+use crate::policy::{DefaultPolicy, PolicyEngine};
 use crate::{MempoolError, Result};
-use opensyria_core::Transaction;
-use opensyria_storage::StateStorage;
+use opensyria_core::asset::AssetOperation;
+use opensyria_core::contract::ContractCall;
+use opensyria_core::crypto::{verify_batch, PublicKey};
+use opensyria_core::delegation::{DelegationError, HotKeyDelegation};
+use opensyria_core::dex::DexOperation;
+use opensyria_core::oracle::{OracleError, OracleUpdate};
+use opensyria_core::policy::{policy_day, SpendingPolicy};
+use opensyria_core::{ChainParams, Transaction};
+use opensyria_storage::{StateStorage, StorageError};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, RwLock, RwLockReadGuard};
 
 /// Maximum allowed nonce gap for pending transactions
 /// Prevents DoS attacks via unbounded future-nonce transactions
 const MAX_NONCE_GAP: u64 = 5;
 
+/// Maximum number of signatures verified in one batch (default)
+pub const DEFAULT_SIGNATURE_BATCH_SIZE: usize = 64;
+
+/// How long the first signature of a batch waits for others (default)
+pub const DEFAULT_SIGNATURE_BATCH_WINDOW: Duration = Duration::from_millis(2);
+
+/// Transaction whose signature was checked by a [`TransactionValidator`]
+/// معاملة تم التحقق من توقيعها
+///
+/// Only the validator can create one, so holding it proves the signature
+/// check happened, possibly outside the mempool lock.
+#[derive(Debug, Clone)]
+pub struct VerifiedTransaction {
+    tx: Transaction,
+    /// Accepted by batch verification only, which is slightly more lenient
+    /// than the single verification used for blocks
+    batch_only: bool,
+}
+
+impl VerifiedTransaction {
+    /// The verified transaction
+    pub fn transaction(&self) -> &Transaction {
+        &self.tx
+    }
+
+    /// Whether the signature still needs a single (strict) verification
+    /// before the transaction goes into a block
+    pub fn is_batch_only(&self) -> bool {
+        self.batch_only
+    }
+
+    /// Unwrap the transaction
+    pub fn into_inner(self) -> Transaction {
+        self.tx
+    }
+}
+
+/// Settings for batching signature checks across concurrent callers
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureBatchConfig {
+    /// Maximum signatures per batch
+    pub max_batch: usize,
+    /// How long the first signature waits for more to arrive
+    pub window: Duration,
+}
+
+impl Default for SignatureBatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch: DEFAULT_SIGNATURE_BATCH_SIZE,
+            window: DEFAULT_SIGNATURE_BATCH_WINDOW,
+        }
+    }
+}
+
+/// Counters for batch signature verification
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SignatureBatchStats {
+    /// Batches verified
+    pub batches: u64,
+    /// Signatures checked across all batches
+    pub signatures: u64,
+    /// Batches that failed and were re-checked one signature at a time
+    pub fallbacks: u64,
+}
+
+#[derive(Debug, Default)]
+struct BatchCounters {
+    batches: AtomicU64,
+    signatures: AtomicU64,
+    fallbacks: AtomicU64,
+}
+
+/// Outcome of a signature check: `Some(batch_only)` when valid
+type SignatureCheck = Option<bool>;
+
+struct SignatureRequest {
+    tx: Transaction,
+    reply: oneshot::Sender<(Transaction, SignatureCheck)>,
+}
+
+/// Verify transaction signatures together with ed25519 batch verification
+/// التحقق من تواقيع المعاملات دفعة واحدة
+///
+/// If the batch fails, each signature is verified on its own so only the
+/// invalid ones are rejected; those results are strict.
+fn verify_signatures(
+    transactions: &[&Transaction],
+    counters: &BatchCounters,
+) -> Vec<SignatureCheck> {
+    counters.batches.fetch_add(1, Ordering::Relaxed);
+
+    // Sponsored transactions add the fee payer's signature to the batch
+    let signers: Vec<(PublicKey, [u8; 32], &[u8])> = transactions
+        .iter()
+        .flat_map(|tx| {
+            let sponsor = tx.sponsor.as_ref().map(|sponsor| {
+                (
+                    sponsor.payer,
+                    tx.sponsor_signing_hash(),
+                    sponsor.signature.as_slice(),
+                )
+            });
+            std::iter::once((tx.signer(), tx.signing_hash(), tx.signature.as_slice()))
+                .chain(sponsor)
+        })
+        .collect();
+    let items: Vec<(PublicKey, &[u8], &[u8])> = signers
+        .iter()
+        .map(|(signer, message, signature)| (*signer, message.as_slice(), *signature))
+        .collect();
+    counters
+        .signatures
+        .fetch_add(items.len() as u64, Ordering::Relaxed);
+
+    if transactions.len() > 1 && verify_batch(&items).is_ok() {
+        return vec![Some(true); transactions.len()];
+    }
+
+    if transactions.len() > 1 {
+        counters.fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+    transactions
+        .iter()
+        .map(|tx| tx.verify().is_ok().then_some(false))
+        .collect()
+}
+
+/// Collects signatures arriving within a short window and verifies them as one batch
+/// تجميع التواقيع الواردة خلال نافذة قصيرة والتحقق منها دفعة واحدة
+#[derive(Clone)]
+pub struct SignatureBatcher {
+    sender: mpsc::UnboundedSender<SignatureRequest>,
+    counters: Arc<BatchCounters>,
+}
+
+impl SignatureBatcher {
+    /// Start the batching task on the current Tokio runtime
+    pub fn spawn(config: SignatureBatchConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let counters = Arc::new(BatchCounters::default());
+        tokio::spawn(Self::run(receiver, config, counters.clone()));
+        Self { sender, counters }
+    }
+
+    async fn run(
+        mut receiver: mpsc::UnboundedReceiver<SignatureRequest>,
+        config: SignatureBatchConfig,
+        counters: Arc<BatchCounters>,
+    ) {
+        // Ends once every batcher handle has been dropped
+        while let Some(first) = receiver.recv().await {
+            let deadline = tokio::time::Instant::now() + config.window;
+            let mut batch = vec![first];
+            while batch.len() < config.max_batch.max(1) {
+                match tokio::time::timeout_at(deadline, receiver.recv()).await {
+                    Ok(Some(request)) => batch.push(request),
+                    _ => break,
+                }
+            }
+
+            let transactions: Vec<&Transaction> = batch.iter().map(|request| &request.tx).collect();
+            let checks = verify_signatures(&transactions, &counters);
+            for (request, check) in batch.into_iter().zip(checks) {
+                let _ = request.reply.send((request.tx, check));
+            }
+        }
+    }
+
+    /// Queue a signature for the next batch and wait for its result
+    async fn verify(&self, tx: Transaction) -> (Transaction, SignatureCheck) {
+        let (reply, result) = oneshot::channel();
+        let fallback = tx.clone();
+        if self.sender.send(SignatureRequest { tx, reply }).is_err() {
+            let check = fallback.verify().is_ok().then_some(false);
+            return (fallback, check);
+        }
+        match result.await {
+            Ok(outcome) => outcome,
+            Err(_) => {
+                let check = fallback.verify().is_ok().then_some(false);
+                (fallback, check)
+            }
+        }
+    }
+
+    /// Batching counters since the batcher was started
+    pub fn stats(&self) -> SignatureBatchStats {
+        SignatureBatchStats {
+            batches: self.counters.batches.load(Ordering::Relaxed),
+            signatures: self.counters.signatures.load(Ordering::Relaxed),
+            fallbacks: self.counters.fallbacks.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// Validates transactions before adding to mempool
 pub struct TransactionValidator {
-    state: Arc<RwLock<StateStorage>>,
-    min_fee: u64,
+    /// Account state; None on relay nodes, which only check signatures
+    state: Option<Arc<RwLock<StateStorage>>>,
+    /// Fee and dust rules, used unless a custom engine is set
+    default_policy: DefaultPolicy,
+    /// Relay rules replacing `default_policy`
+    policy: Option<Arc<dyn PolicyEngine>>,
+    /// Network whose chain ID transactions must carry; None accepts any
+    chain_params: Option<ChainParams>,
+    /// Batches signature checks across concurrent callers when set
+    signatures: Option<SignatureBatcher>,
 }
 
 impl TransactionValidator {
     /// Create a new transaction validator
     pub fn new(state: Arc<RwLock<StateStorage>>, min_fee: u64) -> Self {
-        Self { state, min_fee }
+        Self::with_state(Some(state), min_fee)
+    }
+
+    /// Validator for a node without account state
+    ///
+    /// Signatures are checked as usual; anything needing balances or nonces
+    /// is refused.
+    pub fn stateless(min_fee: u64) -> Self {
+        Self::with_state(None, min_fee)
+    }
+
+    fn with_state(state: Option<Arc<RwLock<StateStorage>>>, min_fee: u64) -> Self {
+        Self {
+            state,
+            default_policy: DefaultPolicy::new(min_fee),
+            policy: None,
+            chain_params: None,
+            signatures: None,
+        }
+    }
+
+    /// Read access to the account state
+    async fn read_state(&self) -> Result<RwLockReadGuard<'_, StateStorage>> {
+        match &self.state {
+            Some(state) => Ok(state.read().await),
+            None => Err(MempoolError::Storage(
+                "relay node keeps no account state".to_string(),
+            )),
+        }
+    }
+
+    /// Reject transfers below `dust_limit` (0 disables the check)
+    pub fn with_dust_limit(mut self, dust_limit: u64) -> Self {
+        self.default_policy = self.default_policy.with_dust_limit(dust_limit);
+        self
+    }
+
+    /// Apply `policy` instead of the default fee and dust rules
+    pub fn with_policy_engine(mut self, policy: Arc<dyn PolicyEngine>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Relay rules in effect
+    pub fn policy_engine(&self) -> &dyn PolicyEngine {
+        match &self.policy {
+            Some(policy) => policy.as_ref(),
+            None => &self.default_policy,
+        }
+    }
+
+    /// Reject transactions signed for another chain once `params` enables
+    /// replay protection
+    pub fn with_chain_params(mut self, params: ChainParams) -> Self {
+        self.chain_params = Some(params);
+        self
+    }
+
+    /// Batch signature checks from concurrent callers (needs a Tokio runtime)
+    pub fn with_signature_batching(mut self, config: SignatureBatchConfig) -> Self {
+        self.signatures = Some(SignatureBatcher::spawn(config));
+        self
+    }
+
+    /// Batching counters, if signature batching is enabled
+    pub fn signature_batch_stats(&self) -> Option<SignatureBatchStats> {
+        self.signatures.as_ref().map(SignatureBatcher::stats)
+    }
+
+    /// Check a transaction's signature
+    /// التحقق من توقيع المعاملة
+    ///
+    /// With batching enabled, concurrent calls arriving within the batch
+    /// window are verified together. Call this before taking the mempool
+    /// lock so a burst of transactions can share one batch.
+    pub async fn verify_signature(&self, tx: Transaction) -> Result<VerifiedTransaction> {
+        let (tx, check) = match &self.signatures {
+            Some(batcher) => batcher.verify(tx).await,
+            None => {
+                let check = tx.verify().is_ok().then_some(false);
+                (tx, check)
+            }
+        };
+
+        match check {
+            Some(batch_only) => Ok(VerifiedTransaction { tx, batch_only }),
+            None => Err(Self::signature_error(&tx)),
+        }
+    }
+
+    fn signature_error(tx: &Transaction) -> MempoolError {
+        let reason = tx
+            .verify()
+            .err()
+            .map(|e| e.to_string())
+            .unwrap_or_else(|| "rejected by batch verification".to_string());
+        MempoolError::ValidationFailed(format!("Invalid signature: {}", reason))
     }
 
     /// Validate a transaction
@@ -30,22 +340,93 @@ impl TransactionValidator {
             )));
         }
 
-        // 2. Check minimum fee
-        if tx.fee < self.min_fee {
-            return Err(MempoolError::FeeTooLow {
-                min: self.min_fee,
-                got: tx.fee,
-            });
+        self.validate_verified(tx).await
+    }
+
+    /// Validate a transaction whose signature was already checked
+    pub async fn validate_verified(&self, tx: &Transaction) -> Result<()> {
+        self.validate_with_pending(tx, &[]).await
+    }
+
+    /// Validate a transaction whose signature was already checked, on top of
+    /// the sender's transactions already waiting in the pool
+    /// التحقق من معاملة فوق معاملات المرسل المعلقة في المجمع
+    ///
+    /// Asset and order operations are checked against the state after the
+    /// sender's pending operations with lower nonces, so a mint may follow a
+    /// pending create. Assets other senders are about to transfer in are not
+    /// counted.
+    #[tracing::instrument(name = "tx_validation", skip_all, fields(nonce = tx.nonce))]
+    pub async fn validate_with_pending(
+        &self,
+        tx: &Transaction,
+        pending: &[Transaction],
+    ) -> Result<()> {
+        // 2. Relay policy: fee and dust limits unless the embedder replaced them
+        self.policy_engine().check(tx)?;
+
+        // Contract, asset, order and oracle payloads must decode, a
+        // contract's fee must cover its gas limit, and rates may not be dated
+        // ahead of this node's clock
+        if let Some(Err(e)) = ContractCall::from_transaction(tx) {
+            return Err(MempoolError::InvalidContract(e.to_string()));
+        }
+        if let Some(Err(e)) = AssetOperation::from_transaction(tx) {
+            return Err(MempoolError::InvalidAsset(e.to_string()));
+        }
+        if let Some(Err(e)) = DexOperation::from_transaction(tx) {
+            return Err(MempoolError::InvalidOrder(e.to_string()));
+        }
+        if let Some(update) = OracleUpdate::from_transaction(tx) {
+            let now = opensyria_core::clock::now();
+            update
+                .and_then(|update| update.check_clock(now))
+                .map_err(|e| MempoolError::InvalidOracleUpdate(e.to_string()))?;
         }
 
         // 3. Check sender balance and nonce
-        let state = self.state.read().await;
+        let state = self.read_state().await?;
+
+        // Transactions signed for another network are not replayed here
+        if let Some(params) = &self.chain_params {
+            let height = state
+                .get_state_height()
+                .map_err(|e| MempoolError::Storage(e.to_string()))?
+                + 1;
+            if params.replay_protection_active(height) && tx.chain_id != params.chain_id {
+                return Err(MempoolError::WrongChainId {
+                    expected: params.chain_id,
+                    got: tx.chain_id,
+                });
+            }
+        }
+
+        // Only whitelisted oracle keys may publish rates
+        if OracleUpdate::is_oracle(tx)
+            && !state
+                .is_oracle(&tx.from)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?
+        {
+            return Err(MempoolError::InvalidOracleUpdate(
+                OracleError::NotAnOracle(tx.from.to_hex()).to_string(),
+            ));
+        }
+
+        if state
+            .is_frozen(&tx.from)
+            .map_err(|e| MempoolError::Storage(e.to_string()))?
+        {
+            return Err(MempoolError::AccountFrozen(tx.from.to_hex()));
+        }
 
         let balance = state
             .get_balance(&tx.from)
             .map_err(|e| MempoolError::Storage(e.to_string()))?;
 
-        let required = tx.amount + tx.fee;
+        // Buy orders also lock their value in escrow
+        let required = tx
+            .debit(&tx.from)
+            .saturating_add(DexOperation::escrowed_syl(tx));
         if balance < required {
             return Err(MempoolError::InsufficientBalance {
                 required,
@@ -53,6 +434,78 @@ impl TransactionValidator {
             });
         }
 
+        // A sponsor must be able to spend the fee
+        let payer = tx.fee_payer();
+        if payer != tx.from {
+            if state
+                .is_frozen(&payer)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?
+            {
+                return Err(MempoolError::AccountFrozen(payer.to_hex()));
+            }
+            let payer_balance = state
+                .get_balance(&payer)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?;
+            if payer_balance < tx.fee {
+                return Err(MempoolError::InsufficientBalance {
+                    required: tx.fee,
+                    available: payer_balance,
+                });
+            }
+        }
+
+        // Spending policies, checked against the day the next block falls in
+        if let Some(Err(e)) = SpendingPolicy::from_update(tx) {
+            return Err(MempoolError::PolicyViolation(e.to_string()));
+        }
+        let day = policy_day(
+            state
+                .get_state_height()
+                .map_err(|e| MempoolError::Storage(e.to_string()))?
+                + 1,
+        );
+        if let Some(policy) = state
+            .get_spending_policy(&tx.from)
+            .map_err(|e| MempoolError::Storage(e.to_string()))?
+        {
+            let spent = state
+                .get_policy_spent(&tx.from, day)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?;
+            policy
+                .check(tx, spent)
+                .map_err(|e| MempoolError::PolicyViolation(e.to_string()))?;
+        }
+        if payer != tx.from {
+            if let Some(policy) = state
+                .get_spending_policy(&payer)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?
+            {
+                let spent = state
+                    .get_policy_spent(&payer, day)
+                    .map_err(|e| MempoolError::Storage(e.to_string()))?;
+                policy
+                    .check_daily(tx.fee, spent)
+                    .map_err(|e| MempoolError::PolicyViolation(e.to_string()))?;
+            }
+        }
+
+        // Hot keys spend within the allowance their cold key delegated
+        let delegation_violation =
+            |e: DelegationError| MempoolError::DelegationViolation(e.to_string());
+        if let Some(Err(e)) = HotKeyDelegation::from_update(tx) {
+            return Err(delegation_violation(e));
+        }
+        if tx.delegate.is_some() {
+            let delegation = state
+                .get_delegation(&tx.from)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?;
+            let spent = state
+                .get_delegation_spent(&tx.from, day)
+                .map_err(|e| MempoolError::Storage(e.to_string()))?;
+            HotKeyDelegation::authorize(delegation.as_ref(), tx, spent)
+                .map_err(delegation_violation)?;
+        }
+
         let current_nonce = state
             .get_nonce(&tx.from)
             .map_err(|e| MempoolError::Storage(e.to_string()))?;
@@ -76,21 +529,55 @@ impl TransactionValidator {
             });
         }
 
+        // Asset operations must hold against the state and the sender's
+        // earlier pending operations: the asset exists, the issuer signs
+        // mints, the cap holds and sell orders can escrow their amount
+        if AssetOperation::from_transaction(tx).is_some()
+            || DexOperation::from_transaction(tx).is_some()
+        {
+            let mut operations: Vec<Transaction> = pending
+                .iter()
+                .filter(|p| p.from == tx.from && p.nonce < tx.nonce)
+                .filter(|p| {
+                    AssetOperation::from_transaction(p).is_some()
+                        || DexOperation::from_transaction(p).is_some()
+                })
+                .cloned()
+                .collect();
+            operations.sort_by_key(|p| p.nonce);
+            operations.push(tx.clone());
+            state
+                .check_asset_operations(&operations)
+                .map_err(|e| match e {
+                    StorageError::AssetViolation(reason) => MempoolError::InvalidAsset(reason),
+                    StorageError::DexViolation(reason) => MempoolError::InvalidOrder(reason),
+                    e => MempoolError::Storage(e.to_string()),
+                })?;
+        }
+
         Ok(())
     }
 
-    /// Validate multiple transactions
+    /// Validate multiple transactions, verifying their signatures as one batch
     pub async fn validate_batch(&self, transactions: &[Transaction]) -> Vec<Result<()>> {
+        let counters = BatchCounters::default();
+        let signed: Vec<&Transaction> = transactions.iter().collect();
+        let checks = verify_signatures(&signed, &counters);
+
         let mut results = Vec::with_capacity(transactions.len());
-        for tx in transactions {
-            results.push(self.validate(tx).await);
+        for (tx, check) in transactions.iter().zip(checks) {
+            let result = match check {
+                Some(_) => self.validate_verified(tx).await,
+                None => Err(Self::signature_error(tx)),
+            };
+            results.push(result);
         }
         results
     }
 
     /// Get current nonce for an address (public for mempool)
     pub async fn get_current_nonce(&self, address: &opensyria_core::crypto::PublicKey) -> Result<u64> {
-        let state = self.state.read().await;
+        let state = self.read_state().await?;
         state
             .get_nonce(address)
             .map_err(|e| MempoolError::Storage(e.to_string()))
@@ -131,6 +618,46 @@ mod tests {
         std::fs::remove_dir_all(&temp_dir).ok();
     }
 
+    #[tokio::test]
+    async fn test_reject_wrong_chain_id() {
+        let temp_dir = std::env::temp_dir().join("mempool_validator_chain_id");
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+        state.set_nonce(&sender.public_key(), 0).unwrap();
+
+        let state = Arc::new(RwLock::new(state));
+        let testnet = ChainParams::testnet();
+        let validator = TransactionValidator::new(state, 100).with_chain_params(testnet.clone());
+
+        // Signed with the mainnet default, so not valid on testnet
+        let mut tx = Transaction::new(sender.public_key(), receiver.public_key(), 500_000, 100, 0);
+        tx.signature = sender.sign(&tx.signing_hash());
+        match validator.validate(&tx).await {
+            Err(MempoolError::WrongChainId { expected, .. }) => {
+                assert_eq!(expected, testnet.chain_id)
+            }
+            other => panic!("Expected WrongChainId error, got {:?}", other),
+        }
+
+        let mut tx = Transaction::new_with_chain_id(
+            testnet.chain_id,
+            sender.public_key(),
+            receiver.public_key(),
+            500_000,
+            100,
+            0,
+        );
+        tx.signature = sender.sign(&tx.signing_hash());
+        assert!(validator.validate(&tx).await.is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
     #[tokio::test]
     async fn test_reject_insufficient_balance() {
         let temp_dir = std::env::temp_dir().join("mempool_validator_insufficient");
@@ -234,4 +761,410 @@ mod tests {
 
         std::fs::remove_dir_all(&temp_dir).ok();
     }
+
+    #[tokio::test]
+    async fn test_reject_dust_amount() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_dust_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+        state.set_nonce(&sender.public_key(), 0).unwrap();
+
+        let state = Arc::new(RwLock::new(state));
+        let mut tx = Transaction::new(sender.public_key(), receiver.public_key(), 1, 100, 0);
+        tx.signature = sender.sign(&tx.signing_hash());
+
+        let strict = TransactionValidator::new(state.clone(), 100)
+            .with_dust_limit(opensyria_core::DUST_THRESHOLD);
+        assert!(matches!(
+            strict.validate(&tx).await,
+            Err(MempoolError::DustAmount { amount: 1, .. })
+        ));
+
+        // Dust check disabled
+        let permissive = TransactionValidator::new(state, 100);
+        assert!(permissive.validate(&tx).await.is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_contract_gas_fee_checked() {
+        use opensyria_core::contract::ContractCall;
+
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_contract_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+
+        let state = Arc::new(RwLock::new(state));
+        let validator =
+            TransactionValidator::new(state, 100).with_dust_limit(opensyria_core::DUST_THRESHOLD);
+        let call = ContractCall::Call {
+            method: "run".to_string(),
+            args: Vec::new(),
+            gas_limit: 100_000,
+        };
+        let contract = KeyPair::generate().public_key();
+        let signed = |fee| {
+            let mut tx = call.call_transaction(963, sender.public_key(), contract, 0, fee, 0);
+            tx.signature = sender.sign(&tx.signing_hash());
+            tx
+        };
+
+        // Zero-value calls are not dust, but the fee must cover the gas limit
+        assert!(matches!(
+            validator.validate(&signed(100)).await,
+            Err(MempoolError::InvalidContract(_))
+        ));
+        assert!(validator.validate(&signed(10_000)).await.is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_reject_frozen_sender() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_frozen_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+        state.freeze_account(&sender.public_key(), 1).unwrap();
+
+        let state = Arc::new(RwLock::new(state));
+        let validator = TransactionValidator::new(state, 100);
+
+        let mut tx = Transaction::new(sender.public_key(), receiver.public_key(), 500_000, 100, 0);
+        tx.signature = sender.sign(&tx.signing_hash());
+        assert!(matches!(
+            validator.validate(&tx).await,
+            Err(MempoolError::AccountFrozen(_))
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+    #[tokio::test]
+    async fn test_asset_operations_checked_against_pending() {
+        use opensyria_core::asset::AssetId;
+        use opensyria_core::OrderSide;
+
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_assets_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let issuer = KeyPair::generate();
+        let other = KeyPair::generate();
+        state.set_balance(&issuer.public_key(), 1_000_000).unwrap();
+        state.set_balance(&other.public_key(), 1_000_000).unwrap();
+
+        let state = Arc::new(RwLock::new(state));
+        let validator = TransactionValidator::new(state, 100);
+
+        let sign = |key: &KeyPair, operation: AssetOperation, nonce: u64| {
+            let mut tx = operation.transaction(0, key.public_key(), 100, nonce);
+            tx.signature = key.sign(&tx.signing_hash());
+            tx
+        };
+        let create = sign(
+            &issuer,
+            AssetOperation::Create {
+                symbol: "OLIVE".to_string(),
+                name: "Olive oil".to_string(),
+                decimals: 2,
+                max_supply: 1_000,
+            },
+            0,
+        );
+        let asset = AssetId::derive(&issuer.public_key(), 0);
+        let mint = |amount| {
+            sign(
+                &issuer,
+                AssetOperation::Mint {
+                    asset,
+                    to: issuer.public_key(),
+                    amount,
+                },
+                1,
+            )
+        };
+
+        // A mint needs the asset, which only exists once the create is pending
+        assert!(matches!(
+            validator.validate(&mint(500)).await,
+            Err(MempoolError::InvalidAsset(_))
+        ));
+        let pending = vec![create];
+        assert!(validator
+            .validate_with_pending(&mint(500), &pending)
+            .await
+            .is_ok());
+        assert!(matches!(
+            validator
+                .validate_with_pending(&mint(5_000), &pending)
+                .await,
+            Err(MempoolError::InvalidAsset(_))
+        ));
+
+        // Only the issuer mints, and nobody transfers or sells what they lack
+        let foreign_mint = sign(
+            &other,
+            AssetOperation::Mint {
+                asset,
+                to: other.public_key(),
+                amount: 1,
+            },
+            0,
+        );
+        assert!(matches!(
+            validator
+                .validate_with_pending(&foreign_mint, &pending)
+                .await,
+            Err(MempoolError::InvalidAsset(_))
+        ));
+        let pending = vec![pending[0].clone(), mint(500)];
+        let transfer = |amount| {
+            sign(
+                &issuer,
+                AssetOperation::Transfer {
+                    asset,
+                    to: other.public_key(),
+                    amount,
+                },
+                2,
+            )
+        };
+        assert!(validator
+            .validate_with_pending(&transfer(500), &pending)
+            .await
+            .is_ok());
+        assert!(matches!(
+            validator
+                .validate_with_pending(&transfer(501), &pending)
+                .await,
+            Err(MempoolError::InvalidAsset(_))
+        ));
+        let mut sell = DexOperation::Place {
+            asset,
+            side: OrderSide::Sell,
+            price: 10,
+            amount: 1,
+        }
+        .transaction(0, other.public_key(), 100, 0);
+        sell.signature = other.sign(&sell.signing_hash());
+        assert!(matches!(
+            validator.validate_with_pending(&sell, &pending).await,
+            Err(MempoolError::InvalidAsset(_) | MempoolError::InvalidOrder(_))
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_sponsored_fee_checked_against_payer() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_sponsor_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let sponsor = KeyPair::generate();
+        let receiver = KeyPair::generate();
+
+        // The sender holds exactly the amount, nothing for the fee
+        state.set_balance(&sender.public_key(), 500_000).unwrap();
+        let state = Arc::new(RwLock::new(state));
+        let validator = TransactionValidator::new(state.clone(), 100);
+
+        let mut tx = Transaction::new(sender.public_key(), receiver.public_key(), 500_000, 100, 0)
+            .with_fee_payer(sponsor.public_key());
+        tx.signature = sender.sign(&tx.signing_hash());
+        let sponsor_signature = sponsor.sign(&tx.sponsor_signing_hash());
+        let tx = tx.with_sponsor_signature(sponsor_signature);
+
+        match validator.validate(&tx).await {
+            Err(MempoolError::InsufficientBalance { required: 100, .. }) => {}
+            other => panic!("Expected sponsor InsufficientBalance, got {:?}", other),
+        }
+
+        state
+            .read()
+            .await
+            .set_balance(&sponsor.public_key(), 100)
+            .unwrap();
+        assert!(validator.validate(&tx).await.is_ok());
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_spending_policy_checked() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_policy_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let sender = KeyPair::generate();
+        let cosigner = KeyPair::generate();
+        let receiver = KeyPair::generate();
+        state.set_balance(&sender.public_key(), 10_000_000).unwrap();
+
+        let policy = SpendingPolicy {
+            daily_limit: Some(1_000_000),
+            cosigner: Some(cosigner.public_key()),
+            cosign_threshold: 500_000,
+            allowlist: Vec::new(),
+        };
+        let update = policy.update_transaction(963, sender.public_key(), 100, 0);
+        state.apply_block_at_height(1, &[update]).unwrap();
+        let state = Arc::new(RwLock::new(state));
+        let validator = TransactionValidator::new(state.clone(), 100);
+
+        let sign = |amount| {
+            let mut tx =
+                Transaction::new(sender.public_key(), receiver.public_key(), amount, 100, 1);
+            tx.signature = sender.sign(&tx.signing_hash());
+            tx
+        };
+
+        assert!(validator.validate(&sign(500_000)).await.is_ok());
+        let large = sign(600_000);
+        match validator.validate(&large).await {
+            Err(e @ MempoolError::PolicyViolation(_)) => assert_eq!(e.code(), "policy_violation"),
+            other => panic!("Expected PolicyViolation, got {:?}", other),
+        }
+        let cosignature = cosigner.sign(&large.cosigning_hash());
+        assert!(validator
+            .validate(&large.with_cosignature(cosignature))
+            .await
+            .is_ok());
+
+        let over_limit = sign(1_000_000);
+        let cosignature = cosigner.sign(&over_limit.cosigning_hash());
+        assert!(matches!(
+            validator
+                .validate(&over_limit.with_cosignature(cosignature))
+                .await,
+            Err(MempoolError::PolicyViolation(_))
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_delegated_payout_checked() {
+        let temp_dir = std::env::temp_dir().join(format!(
+            "mempool_validator_delegation_{}",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let cold = KeyPair::generate();
+        let hot = KeyPair::generate();
+        let miner = KeyPair::generate();
+        state.set_balance(&cold.public_key(), 100_000_000).unwrap();
+        let state = Arc::new(RwLock::new(state));
+        let validator = TransactionValidator::new(state.clone(), 100);
+
+        let payout = |amount| {
+            let mut tx = Transaction::new(cold.public_key(), miner.public_key(), amount, 100, 1)
+                .with_delegate(hot.public_key());
+            tx.signature = hot.sign(&tx.signing_hash());
+            tx
+        };
+        match validator.validate(&payout(1_000_000)).await {
+            Err(e @ MempoolError::DelegationViolation(_)) => {
+                assert_eq!(e.code(), "delegation_violation")
+            }
+            other => panic!("Expected DelegationViolation, got {:?}", other),
+        }
+
+        let delegation = HotKeyDelegation {
+            hot_key: hot.public_key(),
+            daily_allowance: 5_000_000,
+        };
+        let mut grant = delegation.grant_transaction(963, cold.public_key(), 100, 0);
+        grant.signature = cold.sign(&grant.signing_hash());
+        assert!(validator.validate(&grant).await.is_ok());
+        state
+            .read()
+            .await
+            .apply_block_at_height(1, &[grant])
+            .unwrap();
+
+        assert!(validator.validate(&payout(1_000_000)).await.is_ok());
+        assert!(matches!(
+            validator.validate(&payout(5_000_000)).await,
+            Err(MempoolError::DelegationViolation(_))
+        ));
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_batched_signature_verification() {
+        let temp_dir =
+            std::env::temp_dir().join(format!("mempool_validator_batch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+
+        let state = StateStorage::open(temp_dir.clone()).unwrap();
+        let receiver = KeyPair::generate();
+        let mut transactions = Vec::new();
+        for _ in 0..8 {
+            let sender = KeyPair::generate();
+            state.set_balance(&sender.public_key(), 1_000_000).unwrap();
+            let mut tx =
+                Transaction::new(sender.public_key(), receiver.public_key(), 500_000, 100, 0);
+            tx.signature = sender.sign(&tx.signing_hash());
+            transactions.push(tx);
+        }
+        // Corrupt one signature
+        transactions[3].amount += 1;
+
+        let state = Arc::new(RwLock::new(state));
+        let validator = Arc::new(
+            TransactionValidator::new(state, 100).with_signature_batching(SignatureBatchConfig {
+                max_batch: 8,
+                window: Duration::from_millis(50),
+            }),
+        );
+
+        let handles: Vec<_> = transactions
+            .iter()
+            .cloned()
+            .map(|tx| {
+                let validator = validator.clone();
+                tokio::spawn(async move { validator.verify_signature(tx).await })
+            })
+            .collect();
+        for (i, handle) in handles.into_iter().enumerate() {
+            let result = handle.await.unwrap();
+            assert_eq!(result.is_ok(), i != 3, "transaction {}", i);
+        }
+
+        // The failed batch was re-checked one signature at a time
+        let stats = validator.signature_batch_stats().unwrap();
+        assert_eq!(stats.signatures, 8);
+        assert!(stats.batches < 8);
+        assert!(stats.fallbacks >= 1);
+
+        // validate_batch rejects only the bad signature
+        let results = validator.validate_batch(&transactions).await;
+        for (i, result) in results.iter().enumerate() {
+            assert_eq!(result.is_ok(), i != 3, "transaction {}", i);
+        }
+
+        std::fs::remove_dir_all(&temp_dir).ok();
+    }
 }
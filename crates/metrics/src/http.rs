@@ -0,0 +1,225 @@
+//! Per-endpoint request latency for the API services
+//! زمن استجابة الطلبات لكل نقطة نهاية في خدمات الواجهة البرمجية
+//!
+//! The wallet API, explorer and JSON-RPC layer wrap each request in a
+//! [`RequestTimer`]. It opens an `http_request` tracing span carrying the same
+//! service/endpoint/method labels as the histogram sample, so a slow bucket on
+//! a dashboard can be matched to the spans that produced it.
+
+use crate::{HTTP_REQUEST_DURATION, HTTP_REQUEST_LATENCY_QUANTILE};
+use prometheus::core::Collector;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use tracing::Span;
+
+/// Quantiles exported as gauges and returned by [`endpoint_latencies`]
+pub const LATENCY_QUANTILES: [(&str, f64); 3] = [("p50", 0.50), ("p95", 0.95), ("p99", 0.99)];
+
+/// Times one request and records it on completion
+/// قياس زمن طلب واحد وتسجيله عند الانتهاء
+pub struct RequestTimer {
+    service: &'static str,
+    endpoint: String,
+    method: String,
+    started: Instant,
+    span: Span,
+}
+
+impl RequestTimer {
+    /// Start timing a request; `endpoint` should be a route template, not a raw path
+    pub fn start(service: &'static str, endpoint: &str, method: &str) -> Self {
+        let span = tracing::info_span!(
+            "http_request",
+            service,
+            endpoint,
+            method,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+
+        Self {
+            service,
+            endpoint: endpoint.to_string(),
+            method: method.to_string(),
+            started: Instant::now(),
+            span,
+        }
+    }
+
+    /// Span to instrument the request future with
+    pub fn span(&self) -> &Span {
+        &self.span
+    }
+
+    /// Record the response status and elapsed time
+    pub fn finish(self, status: u16) -> Duration {
+        let elapsed = self.started.elapsed();
+
+        HTTP_REQUEST_DURATION
+            .with_label_values(&[
+                self.service,
+                &self.endpoint,
+                &self.method,
+                &status.to_string(),
+            ])
+            .observe(elapsed.as_secs_f64());
+
+        self.span.record("status", status);
+        self.span
+            .record("latency_ms", elapsed.as_secs_f64() * 1000.0);
+        tracing::debug!(parent: &self.span, "request completed");
+
+        elapsed
+    }
+}
+
+/// Aggregated latency of one endpoint across methods and statuses
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndpointLatency {
+    pub service: String,
+    pub endpoint: String,
+    pub count: u64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// Bucket counts of one endpoint summed across methods and statuses
+struct MergedHistogram {
+    bounds: Vec<f64>,
+    cumulative: Vec<u64>,
+    count: u64,
+}
+
+/// Estimate p50/p95/p99 latency (seconds) per endpoint from the histogram
+/// تقدير زمن الاستجابة لكل نقطة نهاية من المدرج التكراري
+pub fn endpoint_latencies() -> Vec<EndpointLatency> {
+    let mut merged: BTreeMap<(String, String), MergedHistogram> = BTreeMap::new();
+
+    for family in HTTP_REQUEST_DURATION.collect() {
+        for metric in family.get_metric() {
+            let label = |name: &str| {
+                metric
+                    .get_label()
+                    .iter()
+                    .find(|l| l.get_name() == name)
+                    .map(|l| l.get_value().to_string())
+                    .unwrap_or_default()
+            };
+            let histogram = metric.get_histogram();
+            let entry = merged
+                .entry((label("service"), label("endpoint")))
+                .or_insert_with(|| MergedHistogram {
+                    bounds: histogram
+                        .get_bucket()
+                        .iter()
+                        .map(|b| b.get_upper_bound())
+                        .collect(),
+                    cumulative: vec![0; histogram.get_bucket().len()],
+                    count: 0,
+                });
+
+            for (slot, bucket) in entry.cumulative.iter_mut().zip(histogram.get_bucket()) {
+                *slot += bucket.get_cumulative_count();
+            }
+            entry.count += histogram.get_sample_count();
+        }
+    }
+
+    merged
+        .into_iter()
+        .filter(|(_, merged)| merged.count > 0)
+        .map(|((service, endpoint), merged)| {
+            let quantile =
+                |q| histogram_quantile(q, &merged.bounds, &merged.cumulative, merged.count);
+            EndpointLatency {
+                service,
+                endpoint,
+                count: merged.count,
+                p50: quantile(0.50),
+                p95: quantile(0.95),
+                p99: quantile(0.99),
+            }
+        })
+        .collect()
+}
+
+/// Refresh the quantile gauges before a scrape
+pub(crate) fn refresh_latency_quantiles() {
+    for latency in endpoint_latencies() {
+        for ((quantile, _), value) in
+            LATENCY_QUANTILES
+                .iter()
+                .zip([latency.p50, latency.p95, latency.p99])
+        {
+            HTTP_REQUEST_LATENCY_QUANTILE
+                .with_label_values(&[&latency.service, &latency.endpoint, quantile])
+                .set(value);
+        }
+    }
+}
+
+/// Linear interpolation within the bucket holding the target rank,
+/// matching PromQL's `histogram_quantile`
+fn histogram_quantile(q: f64, bounds: &[f64], cumulative: &[u64], count: u64) -> f64 {
+    if count == 0 || bounds.is_empty() {
+        return 0.0;
+    }
+
+    let rank = q * count as f64;
+    let mut lower = 0.0;
+    let mut below = 0u64;
+
+    for (&upper, &cum) in bounds.iter().zip(cumulative) {
+        if cum as f64 >= rank {
+            let in_bucket = (cum - below) as f64;
+            if in_bucket == 0.0 {
+                return upper;
+            }
+            return lower + (upper - lower) * (rank - below as f64) / in_bucket;
+        }
+        lower = upper;
+        below = cum;
+    }
+
+    // Rank falls in the +Inf bucket: report the largest finite bound
+    lower
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_quantile_interpolation() {
+        let bounds = [0.1, 0.5, 1.0];
+        // 50 samples <= 0.1s, 40 in (0.1, 0.5], 10 in (0.5, 1.0]
+        let cumulative = [50, 90, 100];
+
+        assert!((histogram_quantile(0.50, &bounds, &cumulative, 100) - 0.1).abs() < 1e-9);
+        assert!((histogram_quantile(0.70, &bounds, &cumulative, 100) - 0.3).abs() < 1e-9);
+        assert!((histogram_quantile(0.95, &bounds, &cumulative, 100) - 0.75).abs() < 1e-9);
+
+        // Samples beyond the last bucket report the last finite bound
+        assert_eq!(histogram_quantile(0.99, &bounds, &[10, 20, 30], 100), 1.0);
+        assert_eq!(histogram_quantile(0.5, &bounds, &[0, 0, 0], 0), 0.0);
+    }
+
+    #[test]
+    fn test_request_timer_records_endpoint_latency() {
+        for status in [200, 200, 500] {
+            RequestTimer::start("test-service", "/api/v1/test/{id}", "GET").finish(status);
+        }
+
+        let latency = endpoint_latencies()
+            .into_iter()
+            .find(|l| l.service == "test-service" && l.endpoint == "/api/v1/test/{id}")
+            .unwrap();
+        assert_eq!(latency.count, 3);
+        assert!(latency.p50 <= latency.p95 && latency.p95 <= latency.p99);
+
+        let metrics = crate::gather_metrics();
+        assert!(metrics.contains("opensyria_http_request_duration_seconds"));
+        assert!(metrics.contains("opensyria_http_request_latency_quantile_seconds"));
+    }
+}
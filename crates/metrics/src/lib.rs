@@ -4,13 +4,15 @@
 /// ✅  OPERATIONAL READINESS (P1-005): Prometheus metrics for monitoring
 /// Provides comprehensive metrics for Grafana dashboards and alerting
 
+pub mod http;
 pub mod server;
+pub mod telemetry;
 
 use lazy_static::lazy_static;
 use prometheus::{
-    register_gauge, register_histogram_vec, register_int_counter_vec,
-    register_int_gauge, register_int_gauge_vec, Encoder, Gauge, HistogramVec,
-    IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+    register_gauge, register_gauge_vec, register_histogram_vec, register_int_counter,
+    register_int_counter_vec, register_int_gauge, register_int_gauge_vec, Encoder, Gauge,
+    GaugeVec, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
 };
 
 lazy_static! {
@@ -43,6 +45,20 @@ lazy_static! {
     )
     .unwrap();
 
+    /// Blocks disconnected from the main chain by reorganizations
+    pub static ref STALE_BLOCKS: IntCounter = register_int_counter!(
+        "opensyria_stale_blocks_total",
+        "Blocks disconnected from the main chain and kept as stale"
+    )
+    .unwrap();
+
+    /// Stale blocks per block produced over recent heights
+    pub static ref ORPHAN_RATE: Gauge = register_gauge!(
+        "opensyria_orphan_rate",
+        "Fraction of recently produced blocks that went stale"
+    )
+    .unwrap();
+
     // Network metrics
     /// Number of connected peers
     pub static ref PEER_COUNT: IntGauge = register_int_gauge!(
@@ -81,6 +97,51 @@ lazy_static! {
     )
     .unwrap();
 
+    /// Current bandwidth use against the configured budgets
+    pub static ref NETWORK_BANDWIDTH_RATE: IntGaugeVec = register_int_gauge_vec!(
+        "opensyria_network_bandwidth_bytes_per_second",
+        "Current network throughput in bytes per second",
+        &["direction"]
+    )
+    .unwrap();
+
+    /// Transfers throttled by the bandwidth budget
+    pub static ref NETWORK_BANDWIDTH_THROTTLED: IntCounterVec = register_int_counter_vec!(
+        "opensyria_network_bandwidth_throttled_total",
+        "Transfers refused or deferred to stay within the bandwidth budget",
+        &["direction"]
+    )
+    .unwrap();
+
+    /// Peers in the Kademlia routing table
+    pub static ref KADEMLIA_ROUTING_PEERS: IntGauge = register_int_gauge!(
+        "opensyria_kademlia_routing_peers",
+        "Number of peers in the Kademlia routing table"
+    )
+    .unwrap();
+
+    /// Peers in each non-empty Kademlia k-bucket
+    pub static ref KADEMLIA_BUCKET_PEERS: IntGaugeVec = register_int_gauge_vec!(
+        "opensyria_kademlia_bucket_peers",
+        "Number of peers in each Kademlia k-bucket",
+        &["bucket"]
+    )
+    .unwrap();
+
+    /// Median gossip propagation delay over the last adjustment interval
+    pub static ref GOSSIP_PROPAGATION_DELAY: Gauge = register_gauge!(
+        "opensyria_gossip_propagation_delay_seconds",
+        "Median delay between a gossip message being published and received"
+    )
+    .unwrap();
+
+    /// Peers relayed to outside the gossip mesh while propagation is slow
+    pub static ref GOSSIP_EXTRA_FANOUT: IntGauge = register_int_gauge!(
+        "opensyria_gossip_extra_fanout",
+        "Number of peers relayed to in addition to the gossip mesh"
+    )
+    .unwrap();
+
     // Mempool metrics
     /// Current mempool size (number of transactions)
     pub static ref MEMPOOL_SIZE: IntGauge = register_int_gauge!(
@@ -128,6 +189,14 @@ lazy_static! {
     )
     .unwrap();
 
+    /// Block templates abandoned and rebuilt by the miner
+    pub static ref MINING_TEMPLATE_REFRESHES: IntCounterVec = register_int_counter_vec!(
+        "opensyria_mining_template_refreshes_total",
+        "Block templates abandoned for a newer one",
+        &["reason"]
+    )
+    .unwrap();
+
     // Storage metrics
     /// Database size in bytes
     pub static ref DB_SIZE: IntGaugeVec = register_int_gauge_vec!(
@@ -153,6 +222,14 @@ lazy_static! {
     )
     .unwrap();
 
+    /// Explorer object cache lookups
+    pub static ref EXPLORER_CACHE_LOOKUPS: IntCounterVec = register_int_counter_vec!(
+        "opensyria_explorer_cache_lookups_total",
+        "Explorer object cache lookups",
+        &["object", "result"]
+    )
+    .unwrap();
+
     // Transaction metrics
     /// Transactions processed
     pub static ref TRANSACTIONS_PROCESSED: IntCounterVec = register_int_counter_vec!(
@@ -169,6 +246,14 @@ lazy_static! {
     )
     .unwrap();
 
+    /// Fee-rate percentiles of the latest block (base units per KB)
+    pub static ref BLOCK_FEE_RATE: IntGaugeVec = register_int_gauge_vec!(
+        "opensyria_block_fee_rate",
+        "Fee rate percentiles of the latest block in base units per KB",
+        &["percentile"]
+    )
+    .unwrap();
+
     // Governance metrics
     /// Active governance proposals
     pub static ref ACTIVE_PROPOSALS: IntGauge = register_int_gauge!(
@@ -221,6 +306,39 @@ lazy_static! {
     )
     .unwrap();
 
+    /// HTTP/RPC request latency per service endpoint
+    pub static ref HTTP_REQUEST_DURATION: HistogramVec = register_histogram_vec!(
+        "opensyria_http_request_duration_seconds",
+        "Time to serve an API request in seconds",
+        &["service", "endpoint", "method", "status"],
+        vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap();
+
+    /// Estimated p50/p95/p99 latency per service endpoint
+    pub static ref HTTP_REQUEST_LATENCY_QUANTILE: GaugeVec = register_gauge_vec!(
+        "opensyria_http_request_latency_quantile_seconds",
+        "Estimated request latency quantiles per endpoint in seconds",
+        &["service", "endpoint", "quantile"]
+    )
+    .unwrap();
+
+    /// API requests rejected with 503 while overloaded
+    pub static ref HTTP_REQUESTS_SHED: IntCounterVec = register_int_counter_vec!(
+        "opensyria_http_requests_shed_total",
+        "API requests shed under load",
+        &["service", "priority", "reason"]
+    )
+    .unwrap();
+
+    /// API requests being served
+    pub static ref HTTP_REQUESTS_IN_FLIGHT: IntGaugeVec = register_int_gauge_vec!(
+        "opensyria_http_requests_in_flight",
+        "API requests currently being served",
+        &["service"]
+    )
+    .unwrap();
+
     // Sync metrics
     /// Sync progress percentage (0-100)
     pub static ref SYNC_PROGRESS: Gauge = register_gauge!(
@@ -243,10 +361,19 @@ lazy_static! {
         "Node uptime in seconds"
     )
     .unwrap();
+
+    /// 1 while the node is in safe mode (mining and relaying stopped)
+    pub static ref SAFE_MODE: IntGauge = register_int_gauge!(
+        "opensyria_safe_mode",
+        "Whether the node is in safe mode after an anomaly (1 = yes)"
+    )
+    .unwrap();
 }
 
 /// Get all metrics in Prometheus text format
 pub fn gather_metrics() -> String {
+    http::refresh_latency_quantiles();
+
     let encoder = TextEncoder::new();
     let metric_families = prometheus::gather();
     let mut buffer = Vec::new();
@@ -261,6 +388,16 @@ pub fn update_chain_metrics(height: u64, supply: u64, difficulty: u64) {
     DIFFICULTY.set(difficulty as i64);
 }
 
+/// Count a block disconnected by a reorganization
+pub fn record_stale_block() {
+    STALE_BLOCKS.inc();
+}
+
+/// Update the orphan rate over recent heights
+pub fn update_orphan_rate_metrics(rate: f64) {
+    ORPHAN_RATE.set(rate);
+}
+
 /// Update network metrics
 pub fn update_network_metrics(total_peers: usize, inbound: usize, outbound: usize) {
     PEER_COUNT.set(total_peers as i64);
@@ -268,12 +405,71 @@ pub fn update_network_metrics(total_peers: usize, inbound: usize, outbound: usiz
     OUTBOUND_PEERS.set(outbound as i64);
 }
 
+/// Update bandwidth metrics from the limiter's running totals
+pub fn update_bandwidth_metrics(
+    upload_rate: u64,
+    download_rate: u64,
+    upload_throttled: u64,
+    download_throttled: u64,
+) {
+    NETWORK_BANDWIDTH_RATE
+        .with_label_values(&["upload"])
+        .set(upload_rate as i64);
+    NETWORK_BANDWIDTH_RATE
+        .with_label_values(&["download"])
+        .set(download_rate as i64);
+
+    for (direction, total) in [
+        ("upload", upload_throttled),
+        ("download", download_throttled),
+    ] {
+        let counter = NETWORK_BANDWIDTH_THROTTLED.with_label_values(&[direction]);
+        counter.inc_by(total.saturating_sub(counter.get()));
+    }
+}
+
+/// Update Kademlia routing table metrics from (bucket index, peers) pairs
+pub fn update_kademlia_metrics(buckets: &[(u32, usize)]) {
+    KADEMLIA_BUCKET_PEERS.reset();
+    for (index, peers) in buckets {
+        KADEMLIA_BUCKET_PEERS
+            .with_label_values(&[&index.to_string()])
+            .set(*peers as i64);
+    }
+    KADEMLIA_ROUTING_PEERS.set(buckets.iter().map(|(_, peers)| *peers as i64).sum());
+}
+
+/// Update gossip propagation metrics; the delay is left as is until measured
+pub fn update_gossip_metrics(median_delay_ms: Option<u64>, extra_fanout: usize) {
+    if let Some(delay) = median_delay_ms {
+        GOSSIP_PROPAGATION_DELAY.set(delay as f64 / 1000.0);
+    }
+    GOSSIP_EXTRA_FANOUT.set(extra_fanout as i64);
+}
+
 /// Update mempool metrics
 pub fn update_mempool_metrics(tx_count: usize, total_bytes: usize) {
     MEMPOOL_SIZE.set(tx_count as i64);
     MEMPOOL_BYTES.set(total_bytes as i64);
 }
 
+/// Update fee-rate percentile metrics for the latest block
+pub fn update_fee_rate_metrics(min: u64, median: u64, p90: u64) {
+    BLOCK_FEE_RATE.with_label_values(&["min"]).set(min as i64);
+    BLOCK_FEE_RATE.with_label_values(&["median"]).set(median as i64);
+    BLOCK_FEE_RATE.with_label_values(&["p90"]).set(p90 as i64);
+}
+
+/// Update safe mode metrics
+pub fn update_safe_mode_metrics(active: bool) {
+    SAFE_MODE.set(active as i64);
+}
+
+/// Count a block template rebuilt by the miner (`reason`: new_tip, fee_increase, expired)
+pub fn record_template_refresh(reason: &str) {
+    MINING_TEMPLATE_REFRESHES.with_label_values(&[reason]).inc();
+}
+
 /// Update sync metrics
 pub fn update_sync_metrics(current_height: u64, target_height: u64) {
     if target_height > 0 {
@@ -287,6 +483,29 @@ pub fn update_sync_metrics(current_height: u64, target_height: u64) {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_bandwidth_metrics() {
+        update_bandwidth_metrics(1_000, 2_000, 3, 0);
+        update_bandwidth_metrics(1_500, 2_000, 5, 1);
+
+        assert_eq!(
+            NETWORK_BANDWIDTH_RATE.with_label_values(&["upload"]).get(),
+            1_500
+        );
+        assert_eq!(
+            NETWORK_BANDWIDTH_THROTTLED
+                .with_label_values(&["upload"])
+                .get(),
+            5
+        );
+        assert_eq!(
+            NETWORK_BANDWIDTH_THROTTLED
+                .with_label_values(&["download"])
+                .get(),
+            1
+        );
+    }
+
     #[test]
     fn test_chain_metrics() {
         update_chain_metrics(1000, 50_000_000_000_000, 12345);
@@ -302,6 +521,40 @@ mod tests {
         assert_eq!(OUTBOUND_PEERS.get(), 10);
     }
 
+    #[test]
+    fn test_kademlia_metrics() {
+        update_kademlia_metrics(&[(250, 3), (254, 8)]);
+        update_kademlia_metrics(&[(254, 6), (255, 12)]);
+
+        assert_eq!(KADEMLIA_ROUTING_PEERS.get(), 18);
+        assert_eq!(KADEMLIA_BUCKET_PEERS.with_label_values(&["255"]).get(), 12);
+        // Buckets that emptied are dropped rather than left stale
+        assert_eq!(KADEMLIA_BUCKET_PEERS.with_label_values(&["250"]).get(), 0);
+    }
+
+    #[test]
+    fn test_gossip_metrics() {
+        update_gossip_metrics(Some(1_500), 2);
+        update_gossip_metrics(None, 3);
+        assert_eq!(GOSSIP_PROPAGATION_DELAY.get(), 1.5);
+        assert_eq!(GOSSIP_EXTRA_FANOUT.get(), 3);
+    }
+
+    #[test]
+    fn test_template_refresh_metrics() {
+        let before = MINING_TEMPLATE_REFRESHES
+            .with_label_values(&["new_tip"])
+            .get();
+        record_template_refresh("new_tip");
+        record_template_refresh("new_tip");
+        assert_eq!(
+            MINING_TEMPLATE_REFRESHES
+                .with_label_values(&["new_tip"])
+                .get(),
+            before + 2
+        );
+    }
+
     #[test]
     fn test_mempool_metrics() {
         update_mempool_metrics(100, 50000);
@@ -309,6 +562,21 @@ mod tests {
         assert_eq!(MEMPOOL_BYTES.get(), 50000);
     }
 
+    #[test]
+    fn test_fee_rate_metrics() {
+        update_fee_rate_metrics(10, 250, 900);
+        assert_eq!(BLOCK_FEE_RATE.with_label_values(&["median"]).get(), 250);
+        assert_eq!(BLOCK_FEE_RATE.with_label_values(&["p90"]).get(), 900);
+    }
+
+    #[test]
+    fn test_safe_mode_metrics() {
+        update_safe_mode_metrics(true);
+        assert_eq!(SAFE_MODE.get(), 1);
+        update_safe_mode_metrics(false);
+        assert_eq!(SAFE_MODE.get(), 0);
+    }
+
     #[test]
     fn test_sync_metrics() {
         update_sync_metrics(500, 1000);
@@ -1,43 +1,231 @@
 /// HTTP server for Prometheus metrics endpoint
 /// خادم HTTP لنقطة نهاية مقاييس Prometheus
 
+use base64::Engine;
 use http_body_util::Full;
 use hyper::body::Bytes;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
-use hyper::{Request, Response, StatusCode};
+use hyper::{header, Request, Response, StatusCode};
 use hyper_util::rt::TokioIo;
 use std::convert::Infallible;
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpListener;
+use tokio_rustls::rustls;
+use tokio_rustls::TlsAcceptor;
+
+/// Metrics server configuration
+/// تكوين خادم المقاييس
+#[derive(Debug, Clone)]
+pub struct MetricsServerConfig {
+    /// Address to bind the HTTP listener to
+    pub bind_addr: SocketAddr,
+
+    /// Serve over HTTPS when set
+    pub tls: Option<MetricsTlsConfig>,
+
+    /// Credentials required to scrape `/metrics`
+    pub auth: MetricsAuth,
+
+    /// Client IPs allowed to scrape `/metrics` (empty = any client)
+    pub allowed_ips: Vec<IpAddr>,
+}
+
+impl MetricsServerConfig {
+    /// Plain HTTP, no auth, any client (the historical behaviour)
+    pub fn new(bind_addr: SocketAddr) -> Self {
+        Self {
+            bind_addr,
+            tls: None,
+            auth: MetricsAuth::None,
+            allowed_ips: Vec::new(),
+        }
+    }
+}
+
+/// PEM certificate chain and private key for HTTPS
+#[derive(Debug, Clone)]
+pub struct MetricsTlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+/// Authentication scheme for the `/metrics` endpoint
+/// مخطط المصادقة لنقطة نهاية المقاييس
+#[derive(Clone, Default)]
+pub enum MetricsAuth {
+    /// No credentials required
+    #[default]
+    None,
+    /// HTTP basic auth (`Authorization: Basic ...`)
+    Basic { username: String, password: String },
+    /// Static bearer token (`Authorization: Bearer ...`)
+    Bearer { token: String },
+}
+
+impl std::fmt::Debug for MetricsAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print credentials into logs
+        match self {
+            MetricsAuth::None => write!(f, "None"),
+            MetricsAuth::Basic { username, .. } => write!(f, "Basic({})", username),
+            MetricsAuth::Bearer { .. } => write!(f, "Bearer(***)"),
+        }
+    }
+}
 
 /// Start Prometheus metrics HTTP server
 /// بدء خادم HTTP لمقاييس Prometheus
 pub async fn start_metrics_server(addr: SocketAddr) -> Result<(), Box<dyn std::error::Error>> {
-    let listener = TcpListener::bind(addr).await?;
-    println!("📊 Metrics server listening on http://{}/metrics", addr);
-    println!("   مقاييس السيرفر تعمل على http://{}/metrics", addr);
+    start_metrics_server_with_config(MetricsServerConfig::new(addr)).await
+}
+
+/// Start metrics server with TLS, auth and client allowlist
+/// بدء خادم المقاييس مع TLS والمصادقة وقائمة العملاء المسموح بهم
+pub async fn start_metrics_server_with_config(
+    config: MetricsServerConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let acceptor = match &config.tls {
+        Some(tls) => Some(load_tls_acceptor(tls)?),
+        None => None,
+    };
+    let scheme = if acceptor.is_some() { "https" } else { "http" };
+
+    let listener = TcpListener::bind(config.bind_addr).await?;
+    println!(
+        "📊 Metrics server listening on {}://{}/metrics",
+        scheme, config.bind_addr
+    );
+    println!(
+        "   مقاييس السيرفر تعمل على {}://{}/metrics",
+        scheme, config.bind_addr
+    );
+
+    let config = Arc::new(config);
 
     loop {
-        let (stream, _) = listener.accept().await?;
-        let io = TokioIo::new(stream);
+        let (stream, peer) = listener.accept().await?;
+        let config = Arc::clone(&config);
+        let acceptor = acceptor.clone();
 
         tokio::task::spawn(async move {
-            if let Err(err) = http1::Builder::new()
-                .serve_connection(io, service_fn(handle_request))
-                .await
-            {
-                eprintln!("Error serving connection: {:?}", err);
+            match acceptor {
+                Some(acceptor) => match acceptor.accept(stream).await {
+                    Ok(tls_stream) => serve_connection(tls_stream, peer, config).await,
+                    Err(err) => eprintln!("TLS handshake with {} failed: {:?}", peer, err),
+                },
+                None => serve_connection(stream, peer, config).await,
             }
         });
     }
 }
 
-async fn handle_request(
-    req: Request<hyper::body::Incoming>,
+async fn serve_connection<S>(stream: S, peer: SocketAddr, config: Arc<MetricsServerConfig>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let io = TokioIo::new(stream);
+    let service = service_fn(move |req| handle_request(req, peer.ip(), Arc::clone(&config)));
+
+    if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
+        eprintln!("Error serving connection: {:?}", err);
+    }
+}
+
+fn load_tls_acceptor(tls: &MetricsTlsConfig) -> Result<TlsAcceptor, Box<dyn std::error::Error>> {
+    let mut cert_reader = std::io::BufReader::new(std::fs::File::open(&tls.cert_path)?);
+    let certs = rustls_pemfile::certs(&mut cert_reader).collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", tls.cert_path.display()).into());
+    }
+
+    let mut key_reader = std::io::BufReader::new(std::fs::File::open(&tls.key_path)?);
+    let key = rustls_pemfile::private_key(&mut key_reader)?
+        .ok_or_else(|| format!("No private key found in {}", tls.key_path.display()))?;
+
+    let server_config = rustls::ServerConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()?
+    .with_no_client_auth()
+    .with_single_cert(certs, key)?;
+
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+/// Check the client IP allowlist and credentials for a scrape request
+fn authorize<B>(
+    req: &Request<B>,
+    client_ip: IpAddr,
+    config: &MetricsServerConfig,
+) -> Result<(), StatusCode> {
+    if !config.allowed_ips.is_empty() && !config.allowed_ips.contains(&client_ip) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let credentials = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok());
+
+    let authorized = match &config.auth {
+        MetricsAuth::None => true,
+        MetricsAuth::Bearer { token } => credentials
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|provided| constant_time_eq(provided.as_bytes(), token.as_bytes())),
+        MetricsAuth::Basic { username, password } => credentials
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|encoded| {
+                base64::engine::general_purpose::STANDARD
+                    .decode(encoded.trim())
+                    .ok()
+            })
+            .is_some_and(|decoded| {
+                let expected = format!("{}:{}", username, password);
+                constant_time_eq(&decoded, expected.as_bytes())
+            }),
+    };
+
+    if authorized {
+        Ok(())
+    } else {
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Compare secrets without leaking the mismatch position through timing
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+async fn handle_request<B>(
+    req: Request<B>,
+    client_ip: IpAddr,
+    config: Arc<MetricsServerConfig>,
 ) -> Result<Response<Full<Bytes>>, Infallible> {
     match req.uri().path() {
         "/metrics" => {
+            if let Err(status) = authorize(&req, client_ip, &config) {
+                let mut response = Response::builder().status(status);
+                if status == StatusCode::UNAUTHORIZED {
+                    if let MetricsAuth::Basic { .. } = config.auth {
+                        response =
+                            response.header(header::WWW_AUTHENTICATE, "Basic realm=\"metrics\"");
+                    }
+                }
+                return Ok(response
+                    .body(Full::new(Bytes::from(
+                        status.canonical_reason().unwrap_or(""),
+                    )))
+                    .unwrap());
+            }
+
             let metrics = crate::gather_metrics();
             Ok(Response::builder()
                 .status(StatusCode::OK)
@@ -60,14 +248,82 @@ async fn handle_request(
 mod tests {
     use super::*;
 
+    fn scrape(authorization: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder().uri("/metrics");
+        if let Some(value) = authorization {
+            builder = builder.header(header::AUTHORIZATION, value);
+        }
+        builder.body(()).unwrap()
+    }
+
+    fn config(auth: MetricsAuth, allowed_ips: Vec<IpAddr>) -> Arc<MetricsServerConfig> {
+        Arc::new(MetricsServerConfig {
+            auth,
+            allowed_ips,
+            ..MetricsServerConfig::new("127.0.0.1:9100".parse().unwrap())
+        })
+    }
+
     #[tokio::test]
     async fn test_handle_metrics_request() {
-        let req = Request::builder()
-            .uri("/metrics")
-            .body(http_body_util::Empty::<hyper::body::Bytes>::new())
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+        let response = handle_request(scrape(None), local, config(MetricsAuth::None, vec![]))
+            .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_allowlist() {
+        let prometheus: IpAddr = "10.0.0.5".parse().unwrap();
+        let other: IpAddr = "10.0.0.6".parse().unwrap();
+        let config = config(MetricsAuth::None, vec![prometheus]);
+
+        let allowed = handle_request(scrape(None), prometheus, Arc::clone(&config)).await;
+        assert_eq!(allowed.unwrap().status(), StatusCode::OK);
+
+        let denied = handle_request(scrape(None), other, Arc::clone(&config)).await;
+        assert_eq!(denied.unwrap().status(), StatusCode::FORBIDDEN);
+
+        // Health checks are not subject to the scrape allowlist
+        let health = Request::builder().uri("/health").body(()).unwrap();
+        let health = handle_request(health, other, config).await;
+        assert_eq!(health.unwrap().status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_metrics_auth() {
+        let local: IpAddr = "127.0.0.1".parse().unwrap();
+
+        let bearer = config(
+            MetricsAuth::Bearer {
+                token: "s3cret".to_string(),
+            },
+            vec![],
+        );
+        assert!(authorize(&scrape(Some("Bearer s3cret")), local, &bearer).is_ok());
+        assert_eq!(
+            authorize(&scrape(Some("Bearer wrong")), local, &bearer),
+            Err(StatusCode::UNAUTHORIZED)
+        );
+        assert_eq!(
+            authorize(&scrape(None), local, &bearer),
+            Err(StatusCode::UNAUTHORIZED)
+        );
 
-        // Note: Can't easily test without full hyper setup
-        // This is more of a smoke test
+        let basic = config(
+            MetricsAuth::Basic {
+                username: "prometheus".to_string(),
+                password: "scrape".to_string(),
+            },
+            vec![],
+        );
+        let good = base64::engine::general_purpose::STANDARD.encode("prometheus:scrape");
+        let bad = base64::engine::general_purpose::STANDARD.encode("prometheus:guess");
+        assert!(authorize(&scrape(Some(&format!("Basic {}", good))), local, &basic).is_ok());
+        assert_eq!(
+            authorize(&scrape(Some(&format!("Basic {}", bad))), local, &basic),
+            Err(StatusCode::UNAUTHORIZED)
+        );
     }
 }
@@ -0,0 +1,212 @@
+//! Tracing subscriber setup with optional OpenTelemetry export
+//! إعداد التتبع مع تصدير OpenTelemetry اختياري
+//!
+//! Every binary installs its subscriber through [`init_tracing`]. Without an
+//! OTLP endpoint this is the usual formatted log output. With one, spans are
+//! also batched to an OTLP/HTTP collector (Jaeger, Tempo, ...), so a request can
+//! be followed from the wallet API through the node into storage.
+
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use std::collections::HashMap;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Path the OTLP/HTTP protocol uses for trace export
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+/// OTLP trace exporter configuration
+/// تكوين مصدّر التتبع OTLP
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Collector URL, e.g. `http://localhost:4318`
+    pub endpoint: String,
+
+    /// `service.name` reported to the collector
+    pub service_name: String,
+
+    /// Fraction of root traces to export (0.0 - 1.0)
+    pub sample_ratio: f64,
+}
+
+impl OtlpConfig {
+    pub fn new(endpoint: impl Into<String>, service_name: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            service_name: service_name.into(),
+            sample_ratio: 1.0,
+        }
+    }
+
+    /// Full trace export URL
+    fn traces_url(&self) -> String {
+        let endpoint = self.endpoint.trim_end_matches('/');
+        if endpoint.ends_with(OTLP_TRACES_PATH) {
+            endpoint.to_string()
+        } else {
+            format!("{}{}", endpoint, OTLP_TRACES_PATH)
+        }
+    }
+}
+
+/// Flushes pending spans when dropped; keep it alive for the life of `main`
+#[must_use = "dropping the guard stops trace export"]
+pub struct TelemetryGuard {
+    provider: Option<SdkTracerProvider>,
+    filter: reload::Handle<EnvFilter, Registry>,
+}
+
+impl TelemetryGuard {
+    /// Whether spans are being exported to a collector
+    pub fn is_exporting(&self) -> bool {
+        self.provider.is_some()
+    }
+
+    /// Replace the log filter of the running process, e.g. `info,opensyria_network=debug`
+    /// استبدال مرشح السجلات أثناء التشغيل
+    pub fn set_filter(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.filter.reload(filter).map_err(|e| e.to_string())
+    }
+}
+
+/// Check log filter directives without installing them
+pub fn validate_filter(directives: &str) -> Result<(), String> {
+    EnvFilter::try_new(directives)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OpenTelemetry spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global tracing subscriber
+/// تثبيت مشترك التتبع العام
+///
+/// `RUST_LOG` overrides `default_filter` when set.
+pub fn init_tracing(
+    default_filter: &str,
+    otlp: Option<OtlpConfig>,
+) -> Result<TelemetryGuard, Box<dyn std::error::Error>> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    // Swappable so daemons can change log levels on a config reload
+    let (filter, filter_handle) = reload::Layer::new(filter);
+    let fmt = tracing_subscriber::fmt::layer().with_target(false);
+
+    let Some(otlp) = otlp else {
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt)
+            .try_init()?;
+        return Ok(TelemetryGuard {
+            provider: None,
+            filter: filter_handle,
+        });
+    };
+
+    if !(0.0..=1.0).contains(&otlp.sample_ratio) {
+        return Err(format!(
+            "OTLP sample ratio must be within 0.0-1.0, got {}",
+            otlp.sample_ratio
+        )
+        .into());
+    }
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_http()
+        .with_endpoint(otlp.traces_url())
+        .build()?;
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            otlp.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(otlp.service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let tracer = provider.tracer(otlp.service_name.clone());
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt)
+        .with(tracing_opentelemetry::layer().with_tracer(tracer))
+        .try_init()?;
+
+    Ok(TelemetryGuard {
+        provider: Some(provider),
+        filter: filter_handle,
+    })
+}
+
+/// Continue a caller's trace from its W3C `traceparent`/`tracestate` headers
+/// متابعة تتبع المستدعي من رؤوس W3C
+pub fn link_remote_parent(span: &Span, traceparent: Option<&str>, tracestate: Option<&str>) {
+    let Some(traceparent) = traceparent else {
+        return;
+    };
+
+    let mut carrier = HashMap::new();
+    carrier.insert("traceparent".to_string(), traceparent.to_string());
+    if let Some(tracestate) = tracestate {
+        carrier.insert("tracestate".to_string(), tracestate.to_string());
+    }
+
+    let context = TraceContextPropagator::new().extract(&carrier);
+    // Fails only when no OpenTelemetry layer is installed
+    let _ = span.set_parent(context);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traces_url() {
+        assert_eq!(
+            OtlpConfig::new("http://localhost:4318", "node").traces_url(),
+            "http://localhost:4318/v1/traces"
+        );
+        assert_eq!(
+            OtlpConfig::new("http://tempo:4318/v1/traces/", "node").traces_url(),
+            "http://tempo:4318/v1/traces"
+        );
+    }
+
+    #[test]
+    fn test_validate_filter() {
+        assert!(validate_filter("info,opensyria_network=debug").is_ok());
+        assert!(validate_filter("info,opensyria_network=loud").is_err());
+    }
+
+    #[test]
+    fn test_link_remote_parent_without_exporter() {
+        // No OpenTelemetry layer installed: linking must be a harmless no-op
+        let span = tracing::info_span!("request");
+        link_remote_parent(
+            &span,
+            Some("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"),
+            None,
+        );
+        link_remote_parent(&span, None, None);
+    }
+}
@@ -31,6 +31,9 @@ pub enum PoolError {
     #[error("Insufficient balance for payout")]
     InsufficientBalance,
 
+    #[error("Payout must be signed by {expected}")]
+    WrongPayoutKey { expected: String },
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -1,5 +1,6 @@
 use crate::{error::*, types::*};
-use opensyria_core::crypto::PublicKey;
+use opensyria_core::crypto::{KeyPair, PublicKey};
+use opensyria_core::Transaction;
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -330,6 +331,45 @@ impl MiningPool {
         Ok(payout)
     }
 
+    /// Process payout for a miner and build the signed transfer from the
+    /// operator account
+    /// إنشاء معاملة الدفع من حساب المشغل
+    ///
+    /// With a `payout_key` configured, `signer` is that hot key and signs
+    /// under the operator's on-chain delegation, so a leaked hot key can move
+    /// at most the delegated daily allowance. Otherwise `signer` is the
+    /// operator key.
+    pub fn payout_transaction(
+        &mut self,
+        miner: &PublicKey,
+        signer: &KeyPair,
+        chain_id: u32,
+        fee: u64,
+        nonce: u64,
+    ) -> Result<Transaction> {
+        let expected = self.config.payout_key.unwrap_or(self.config.operator);
+        if signer.public_key() != expected {
+            return Err(PoolError::WrongPayoutKey {
+                expected: expected.to_hex(),
+            });
+        }
+
+        let amount = self.process_payout(miner)?;
+        let mut tx = Transaction::new_with_chain_id(
+            chain_id,
+            self.config.operator,
+            *miner,
+            amount,
+            fee,
+            nonce,
+        );
+        if let Some(payout_key) = self.config.payout_key {
+            tx = tx.with_delegate(payout_key);
+        }
+        let signature = signer.sign(&tx.signing_hash());
+        Ok(tx.with_signature(signature))
+    }
+
     /// Get pool statistics
     pub fn get_stats(&self) -> PoolStats {
         let now = SystemTime::now()
@@ -492,6 +532,35 @@ mod tests {
         assert_eq!(pool.miners.get(&miner).unwrap().pending_rewards, 0);
     }
 
+    #[test]
+    fn test_payout_signed_by_delegated_hot_key() {
+        let operator = KeyPair::generate();
+        let hot = KeyPair::generate();
+        let config = PoolConfig {
+            operator: operator.public_key(),
+            payout_key: Some(hot.public_key()),
+            ..Default::default()
+        };
+
+        let mut pool = MiningPool::new(config);
+        let miner = KeyPair::generate().public_key();
+        pool.register_miner(miner);
+        pool.miners.get_mut(&miner).unwrap().pending_rewards = 1_500_000;
+
+        // The cold operator key stays offline
+        assert!(matches!(
+            pool.payout_transaction(&miner, &operator, 963, 100, 0),
+            Err(PoolError::WrongPayoutKey { .. })
+        ));
+        assert_eq!(pool.miners.get(&miner).unwrap().pending_rewards, 1_500_000);
+
+        let tx = pool.payout_transaction(&miner, &hot, 963, 100, 0).unwrap();
+        assert_eq!(tx.from, operator.public_key());
+        assert_eq!(tx.amount, 1_500_000);
+        assert_eq!(tx.delegate, Some(hot.public_key()));
+        assert!(tx.verify().is_ok());
+    }
+
     #[test]
     fn test_pow_verification() {
         let config = PoolConfig::default();
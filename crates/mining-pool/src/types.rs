@@ -101,6 +101,10 @@ pub struct PoolConfig {
     pub reward_method: RewardMethod,
     /// Pool server address
     pub server_address: String,
+    /// Hot key that signs payouts under the operator's delegation; `None`
+    /// pays out with the operator key itself
+    #[serde(default)]
+    pub payout_key: Option<PublicKey>,
 }
 
 impl Default for PoolConfig {
@@ -112,6 +116,7 @@ impl Default for PoolConfig {
             share_difficulty: 12,  // Easier than typical block difficulty
             reward_method: RewardMethod::Proportional,
             server_address: "0.0.0.0:3333".to_string(),
+            payout_key: None,
         }
     }
 }
@@ -0,0 +1,349 @@
+//! Upload/download bandwidth budgets
+//! ميزانيات عرض النطاق للرفع والتنزيل
+//!
+//! Token buckets per peer and for the node as a whole keep block sync from
+//! saturating constrained links. Unlike [`RateLimiter`](crate::RateLimiter),
+//! which protects us from abusive peers, these limits protect our own uplink
+//! and downlink, so exceeding them throttles rather than penalizes.
+
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Bandwidth status file name inside the network data directory
+pub const BANDWIDTH_STATUS_FILE: &str = "bandwidth.json";
+
+/// Bandwidth budgets in bytes per second (None = unlimited)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BandwidthConfig {
+    /// Total upload budget
+    pub upload_bytes_per_sec: Option<u64>,
+    /// Total download budget
+    pub download_bytes_per_sec: Option<u64>,
+    /// Upload budget for each peer
+    pub peer_upload_bytes_per_sec: Option<u64>,
+    /// Download budget for each peer
+    pub peer_download_bytes_per_sec: Option<u64>,
+}
+
+/// Traffic direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+/// Token bucket holding up to one second of budget
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(bytes_per_sec: u64) -> Self {
+        Self {
+            rate: bytes_per_sec as f64,
+            tokens: bytes_per_sec as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let elapsed = self.last_refill.elapsed().as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = Instant::now();
+    }
+
+    /// Whether `bytes` may be sent now. Messages larger than the one-second
+    /// burst are admitted from a full bucket and paid back as debt.
+    fn has_budget(&mut self, bytes: u64) -> bool {
+        self.refill();
+        self.tokens >= (bytes as f64).min(self.rate)
+    }
+
+    /// Charge bytes, possibly going into debt
+    fn charge(&mut self, bytes: u64) {
+        self.refill();
+        self.tokens -= bytes as f64;
+    }
+}
+
+/// Upload and download buckets
+#[derive(Debug, Clone, Default)]
+struct BucketPair {
+    upload: Option<TokenBucket>,
+    download: Option<TokenBucket>,
+}
+
+impl BucketPair {
+    fn new(upload: Option<u64>, download: Option<u64>) -> Self {
+        Self {
+            upload: upload.map(TokenBucket::new),
+            download: download.map(TokenBucket::new),
+        }
+    }
+
+    fn get(&mut self, direction: Direction) -> Option<&mut TokenBucket> {
+        match direction {
+            Direction::Upload => self.upload.as_mut(),
+            Direction::Download => self.download.as_mut(),
+        }
+    }
+}
+
+/// Throughput measured over roughly one-second windows
+#[derive(Debug, Clone)]
+struct RateMeter {
+    window_start: Instant,
+    window_bytes: u64,
+    last_rate: u64,
+}
+
+impl RateMeter {
+    fn new() -> Self {
+        Self {
+            window_start: Instant::now(),
+            window_bytes: 0,
+            last_rate: 0,
+        }
+    }
+
+    fn roll(&mut self) {
+        let elapsed = self.window_start.elapsed().as_secs_f64();
+        if elapsed >= 1.0 {
+            self.last_rate = (self.window_bytes as f64 / elapsed) as u64;
+            self.window_bytes = 0;
+            self.window_start = Instant::now();
+        }
+    }
+
+    fn record(&mut self, bytes: u64) {
+        self.roll();
+        self.window_bytes += bytes;
+    }
+
+    fn rate(&mut self) -> u64 {
+        self.roll();
+        self.last_rate
+    }
+}
+
+/// Live bandwidth counters
+/// عدادات عرض النطاق الحية
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BandwidthStats {
+    /// Bytes sent since start
+    pub bytes_uploaded: u64,
+    /// Bytes received since start
+    pub bytes_downloaded: u64,
+    /// Current upload rate (bytes/sec)
+    pub upload_rate: u64,
+    /// Current download rate (bytes/sec)
+    pub download_rate: u64,
+    /// Responses refused because the upload budget was spent
+    pub upload_throttled: u64,
+    /// Messages dropped or requests deferred because the download budget was spent
+    pub download_throttled: u64,
+    /// Configured total upload budget (bytes/sec)
+    pub upload_limit: Option<u64>,
+    /// Configured total download budget (bytes/sec)
+    pub download_limit: Option<u64>,
+    /// Unix timestamp the counters were taken at
+    #[serde(default)]
+    pub updated_at: u64,
+}
+
+impl BandwidthStats {
+    /// Write the counters for `network status` to read
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read counters written by a running node
+    pub fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_slice(&std::fs::read(path)?)?))
+    }
+}
+
+/// Enforces bandwidth budgets per peer and globally
+/// فرض ميزانيات عرض النطاق لكل نظير وعلى مستوى العقدة
+pub struct BandwidthLimiter {
+    config: BandwidthConfig,
+    global: BucketPair,
+    peers: HashMap<PeerId, BucketPair>,
+    upload_meter: RateMeter,
+    download_meter: RateMeter,
+    stats: BandwidthStats,
+}
+
+impl BandwidthLimiter {
+    pub fn new(config: BandwidthConfig) -> Self {
+        Self {
+            global: BucketPair::new(config.upload_bytes_per_sec, config.download_bytes_per_sec),
+            peers: HashMap::new(),
+            upload_meter: RateMeter::new(),
+            download_meter: RateMeter::new(),
+            stats: BandwidthStats {
+                upload_limit: config.upload_bytes_per_sec,
+                download_limit: config.download_bytes_per_sec,
+                ..Default::default()
+            },
+            config,
+        }
+    }
+
+    /// Whether both the peer and global budgets can take `bytes` right now
+    pub fn has_budget(&mut self, peer_id: &PeerId, direction: Direction, bytes: u64) -> bool {
+        let config = self.config;
+        let peer = self.peers.entry(*peer_id).or_insert_with(|| {
+            BucketPair::new(
+                config.peer_upload_bytes_per_sec,
+                config.peer_download_bytes_per_sec,
+            )
+        });
+
+        peer.get(direction).is_none_or(|b| b.has_budget(bytes))
+            && self
+                .global
+                .get(direction)
+                .is_none_or(|b| b.has_budget(bytes))
+    }
+
+    /// Charge `bytes` if the budgets allow it; counts a throttle otherwise
+    /// Returns true if the transfer may proceed
+    pub fn try_consume(&mut self, peer_id: &PeerId, direction: Direction, bytes: u64) -> bool {
+        if !self.has_budget(peer_id, direction, bytes) {
+            self.record_throttle(direction);
+            return false;
+        }
+
+        self.record(Some(peer_id), direction, bytes);
+        true
+    }
+
+    /// Account for bytes that were transferred regardless of budget
+    /// (responses already received, gossip we publish to all peers)
+    pub fn record(&mut self, peer_id: Option<&PeerId>, direction: Direction, bytes: u64) {
+        if let Some(bucket) = peer_id
+            .and_then(|peer_id| self.peers.get_mut(peer_id))
+            .and_then(|p| p.get(direction))
+        {
+            bucket.charge(bytes);
+        }
+        if let Some(bucket) = self.global.get(direction) {
+            bucket.charge(bytes);
+        }
+
+        match direction {
+            Direction::Upload => {
+                self.stats.bytes_uploaded += bytes;
+                self.upload_meter.record(bytes);
+            }
+            Direction::Download => {
+                self.stats.bytes_downloaded += bytes;
+                self.download_meter.record(bytes);
+            }
+        }
+    }
+
+    /// Count a transfer that was skipped to stay within budget
+    pub fn record_throttle(&mut self, direction: Direction) {
+        match direction {
+            Direction::Upload => self.stats.upload_throttled += 1,
+            Direction::Download => self.stats.download_throttled += 1,
+        }
+    }
+
+    /// Forget a disconnected peer's buckets
+    pub fn remove_peer(&mut self, peer_id: &PeerId) {
+        self.peers.remove(peer_id);
+    }
+
+    /// Current counters and rates
+    pub fn stats(&mut self) -> BandwidthStats {
+        BandwidthStats {
+            upload_rate: self.upload_meter.rate(),
+            download_rate: self.download_meter.rate(),
+            updated_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            ..self.stats.clone()
+        }
+    }
+}
+
+impl Default for BandwidthLimiter {
+    fn default() -> Self {
+        Self::new(BandwidthConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unlimited_by_default() {
+        let mut limiter = BandwidthLimiter::default();
+        let peer = PeerId::random();
+
+        for _ in 0..100 {
+            assert!(limiter.try_consume(&peer, Direction::Upload, 10_000_000));
+        }
+        assert_eq!(limiter.stats().bytes_uploaded, 1_000_000_000);
+        assert_eq!(limiter.stats().upload_throttled, 0);
+    }
+
+    #[test]
+    fn test_peer_budget() {
+        let mut limiter = BandwidthLimiter::new(BandwidthConfig {
+            peer_upload_bytes_per_sec: Some(1_000),
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+        let other = PeerId::random();
+
+        assert!(limiter.try_consume(&peer, Direction::Upload, 600));
+        assert!(!limiter.try_consume(&peer, Direction::Upload, 600));
+        // Budgets are per peer and per direction
+        assert!(limiter.try_consume(&other, Direction::Upload, 600));
+        assert!(limiter.try_consume(&peer, Direction::Download, 600));
+
+        assert_eq!(limiter.stats().upload_throttled, 1);
+    }
+
+    #[test]
+    fn test_global_budget() {
+        let mut limiter = BandwidthLimiter::new(BandwidthConfig {
+            download_bytes_per_sec: Some(1_000),
+            ..Default::default()
+        });
+
+        assert!(limiter.try_consume(&PeerId::random(), Direction::Download, 800));
+        assert!(!limiter.try_consume(&PeerId::random(), Direction::Download, 800));
+        assert_eq!(limiter.stats().download_throttled, 1);
+    }
+
+    #[test]
+    fn test_oversized_transfer_goes_into_debt() {
+        let mut limiter = BandwidthLimiter::new(BandwidthConfig {
+            upload_bytes_per_sec: Some(1_000),
+            ..Default::default()
+        });
+        let peer = PeerId::random();
+
+        // Larger than the burst, admitted from a full bucket...
+        assert!(limiter.try_consume(&peer, Direction::Upload, 5_000));
+        // ...and paid back before anything else goes out
+        assert!(!limiter.has_budget(&peer, Direction::Upload, 1));
+    }
+}
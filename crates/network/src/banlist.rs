@@ -0,0 +1,635 @@
+//! Signed ban lists shared between cooperating operators
+//! قوائم الحظر الموقعة المتبادلة بين المشغلين المتعاونين
+//!
+//! An operator exports the bans its node holds as a [`SignedBanList`]: peer
+//! IDs and IP addresses, each with a reason and an absolute expiry, signed
+//! with the node's identity key so the issuer is its peer ID. Other
+//! operators trust that peer ID in a [`BanListPolicy`] and either import a
+//! list once or subscribe to wherever it is published
+//! ([`BanListSubscriptions`]).
+//!
+//! Merging never lets a remote operator ban for longer than local policy
+//! allows: an imported ban ends at the earlier of its own expiry and
+//! [`BanListPolicy::max_ban`] after the list was issued, and never shortens
+//! a ban already in force. The cap runs from the signed issue time, so
+//! serving an old list again cannot keep a peer banned; only a freshly
+//! issued list can.
+
+use crate::reputation::PeerReputation;
+use libp2p::identity::{Keypair, PublicKey};
+use libp2p::multiaddr::Protocol;
+use libp2p::{Multiaddr, PeerId};
+use opensyria_core::clock::system_time;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+/// Domain separator of ban list digests
+pub const BAN_LIST_DOMAIN: &[u8] = b"OpenSyria-BanList-v1";
+
+/// Entries a list may carry
+pub const MAX_BAN_LIST_ENTRIES: usize = 10_000;
+
+/// Longest reason accepted per entry
+pub const MAX_BAN_REASON_LEN: usize = 256;
+
+/// Longest ban an imported list may impose unless configured otherwise
+pub const DEFAULT_MAX_IMPORTED_BAN_SECS: u64 = 24 * 3600;
+
+/// Default interval between fetches of subscribed lists
+pub const DEFAULT_BAN_LIST_REFRESH_SECS: u64 = 15 * 60;
+
+/// Largest list body a subscription fetch reads
+const MAX_BAN_LIST_BYTES: usize = 4 * 1024 * 1024;
+
+/// Errors exporting, fetching or accepting ban lists
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BanListError {
+    /// A list file could not be read or written
+    Io(String),
+    /// A subscribed list could not be downloaded
+    Fetch(String),
+    /// The list is not valid JSON or breaks the size limits
+    Malformed(String),
+    /// The identity key could not sign the list
+    Signing(String),
+    /// The signature does not verify against the embedded key
+    InvalidSignature,
+    /// The embedded key does not belong to the claimed issuer
+    IssuerMismatch { issuer: String, key_owner: String },
+    /// The issuer is not a trusted operator
+    Untrusted(String),
+}
+
+impl std::fmt::Display for BanListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BanListError::Io(e) => write!(f, "Ban list I/O error: {}", e),
+            BanListError::Fetch(e) => write!(f, "Failed to fetch ban list: {}", e),
+            BanListError::Malformed(e) => write!(f, "Malformed ban list: {}", e),
+            BanListError::Signing(e) => write!(f, "Failed to sign ban list: {}", e),
+            BanListError::InvalidSignature => write!(f, "Ban list has an invalid signature"),
+            BanListError::IssuerMismatch { issuer, key_owner } => write!(
+                f,
+                "Ban list claims issuer {} but is signed by {}",
+                issuer, key_owner
+            ),
+            BanListError::Untrusted(issuer) => {
+                write!(f, "Ban list issuer {} is not trusted", issuer)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BanListError {}
+
+impl From<std::io::Error> for BanListError {
+    fn from(e: std::io::Error) -> Self {
+        BanListError::Io(e.to_string())
+    }
+}
+
+/// What a ban applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BanTarget {
+    /// A peer ID (base58)
+    Peer(String),
+    /// Connections from an IP address
+    Ip(IpAddr),
+}
+
+impl std::fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BanTarget::Peer(peer_id) => write!(f, "peer {}", peer_id),
+            BanTarget::Ip(ip) => write!(f, "ip {}", ip),
+        }
+    }
+}
+
+/// One ban on a list
+/// إدخال حظر واحد
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanEntry {
+    pub target: BanTarget,
+    pub reason: String,
+    /// When the issuer's ban ends (Unix seconds)
+    pub expires_at: u64,
+}
+
+/// Bans an operator vouches for
+/// قائمة الحظر الصادرة عن مشغل
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BanList {
+    /// Peer ID of the issuing node
+    pub issuer: String,
+    /// When the list was issued (Unix seconds)
+    pub issued_at: u64,
+    pub entries: Vec<BanEntry>,
+}
+
+impl BanList {
+    /// The bans `reputation` holds, issued now by `issuer`
+    pub fn from_reputation(issuer: PeerId, reputation: &PeerReputation) -> Self {
+        let peers = reputation
+            .snapshot()
+            .into_iter()
+            .filter_map(|(peer_id, record)| {
+                Some(BanEntry {
+                    target: BanTarget::Peer(peer_id.to_string()),
+                    reason: record.ban_reason.unwrap_or_default(),
+                    expires_at: record.banned_until?,
+                })
+            });
+        let ips = reputation.ip_bans().into_iter().map(|(ip, ban)| BanEntry {
+            target: BanTarget::Ip(ip),
+            reason: ban.reason,
+            expires_at: ban.banned_until,
+        });
+
+        let mut entries: Vec<BanEntry> = peers.chain(ips).collect();
+        entries.sort_by(|a, b| a.target.cmp(&b.target));
+        Self {
+            issuer: issuer.to_string(),
+            issued_at: system_time(),
+            entries,
+        }
+    }
+
+    /// Digest the issuer signs
+    pub fn digest(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(BAN_LIST_DOMAIN);
+        hasher.update(self.issuer.as_bytes());
+        hasher.update(self.issued_at.to_le_bytes());
+        hasher.update((self.entries.len() as u64).to_le_bytes());
+        for entry in &self.entries {
+            match &entry.target {
+                BanTarget::Peer(peer_id) => {
+                    hasher.update([0u8]);
+                    hasher.update((peer_id.len() as u64).to_le_bytes());
+                    hasher.update(peer_id.as_bytes());
+                }
+                BanTarget::Ip(ip) => {
+                    let ip = ip.to_string();
+                    hasher.update([1u8]);
+                    hasher.update((ip.len() as u64).to_le_bytes());
+                    hasher.update(ip.as_bytes());
+                }
+            }
+            hasher.update((entry.reason.len() as u64).to_le_bytes());
+            hasher.update(entry.reason.as_bytes());
+            hasher.update(entry.expires_at.to_le_bytes());
+        }
+        hasher.finalize().into()
+    }
+
+    fn check_limits(&self) -> Result<(), BanListError> {
+        if self.entries.len() > MAX_BAN_LIST_ENTRIES {
+            return Err(BanListError::Malformed(format!(
+                "{} entries, at most {} allowed",
+                self.entries.len(),
+                MAX_BAN_LIST_ENTRIES
+            )));
+        }
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|entry| entry.reason.len() > MAX_BAN_REASON_LEN)
+        {
+            return Err(BanListError::Malformed(format!(
+                "reason for {} is longer than {} bytes",
+                entry.target, MAX_BAN_REASON_LEN
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A ban list signed with the issuer's identity key
+/// قائمة حظر موقعة بمفتاح هوية المصدر
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedBanList {
+    #[serde(flatten)]
+    pub list: BanList,
+    /// Protobuf-encoded identity public key of the issuer (hex)
+    pub public_key: String,
+    /// Signature over [`BanList::digest`] (hex)
+    pub signature: String,
+}
+
+impl SignedBanList {
+    pub fn sign(list: BanList, keypair: &Keypair) -> Result<Self, BanListError> {
+        list.check_limits()?;
+        let signature = keypair
+            .sign(&list.digest())
+            .map_err(|e| BanListError::Signing(e.to_string()))?;
+
+        Ok(Self {
+            list,
+            public_key: hex::encode(keypair.public().encode_protobuf()),
+            signature: hex::encode(signature),
+        })
+    }
+
+    /// Check the signature and that the key belongs to the issuer; whether
+    /// the issuer is trusted is up to [`BanListPolicy::accept`]
+    pub fn verify(&self) -> Result<PeerId, BanListError> {
+        self.list.check_limits()?;
+        let public_key = hex::decode(&self.public_key)
+            .ok()
+            .and_then(|bytes| PublicKey::try_decode_protobuf(&bytes).ok())
+            .ok_or(BanListError::InvalidSignature)?;
+        let key_owner = public_key.to_peer_id();
+        if key_owner.to_string() != self.list.issuer {
+            return Err(BanListError::IssuerMismatch {
+                issuer: self.list.issuer.clone(),
+                key_owner: key_owner.to_string(),
+            });
+        }
+
+        let signature = hex::decode(&self.signature).map_err(|_| BanListError::InvalidSignature)?;
+        if !public_key.verify(&self.list.digest(), &signature) {
+            return Err(BanListError::InvalidSignature);
+        }
+        Ok(key_owner)
+    }
+
+    pub fn from_json(bytes: &[u8]) -> Result<Self, BanListError> {
+        serde_json::from_slice(bytes).map_err(|e| BanListError::Malformed(e.to_string()))
+    }
+
+    pub fn to_json(&self) -> Result<String, BanListError> {
+        serde_json::to_string_pretty(self).map_err(|e| BanListError::Malformed(e.to_string()))
+    }
+}
+
+/// What merging one list changed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Bans imposed or lengthened
+    pub applied: usize,
+    /// Of those, bans shortened to the local maximum
+    pub capped: usize,
+    /// Entries already over, or over once capped
+    pub expired: usize,
+    /// Entries left alone: the local node, trusted issuers, loopback
+    /// addresses, unparsable peer IDs and targets already banned for longer
+    pub skipped: usize,
+}
+
+/// Which issuers are trusted and how far their bans may reach
+/// سياسة قبول قوائم الحظر
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanListPolicy {
+    pub trusted_issuers: HashSet<PeerId>,
+    /// Longest ban an imported list may impose, counted from its issue time
+    pub max_ban: Duration,
+}
+
+impl BanListPolicy {
+    pub fn new(trusted_issuers: impl IntoIterator<Item = PeerId>) -> Self {
+        Self {
+            trusted_issuers: trusted_issuers.into_iter().collect(),
+            max_ban: Duration::from_secs(DEFAULT_MAX_IMPORTED_BAN_SECS),
+        }
+    }
+
+    pub fn with_max_ban(mut self, max_ban: Duration) -> Self {
+        self.max_ban = max_ban;
+        self
+    }
+
+    /// Verify `signed` and check its issuer is trusted
+    /// التحقق من القائمة وثقة المصدر
+    pub fn accept<'a>(&self, signed: &'a SignedBanList) -> Result<&'a BanList, BanListError> {
+        let issuer = signed.verify()?;
+        if !self.trusted_issuers.contains(&issuer) {
+            return Err(BanListError::Untrusted(issuer.to_string()));
+        }
+        Ok(&signed.list)
+    }
+
+    /// Apply the bans of an accepted list to `reputation`
+    /// دمج الحظر المستورد ضمن حدود السياسة المحلية
+    pub fn merge(&self, list: &BanList, reputation: &mut PeerReputation) -> MergeReport {
+        self.merge_at(list, reputation, system_time())
+    }
+
+    fn merge_at(&self, list: &BanList, reputation: &mut PeerReputation, now: u64) -> MergeReport {
+        // An issuer clock running ahead must not push the cap forward
+        let cap = list
+            .issued_at
+            .min(now)
+            .saturating_add(self.max_ban.as_secs());
+        let mut report = MergeReport::default();
+
+        for entry in &list.entries {
+            let expires_at = entry.expires_at.min(cap);
+            if expires_at <= now {
+                report.expired += 1;
+                continue;
+            }
+            let duration = Duration::from_secs(expires_at - now);
+            let reason = format!("{} (via {})", entry.reason, list.issuer);
+
+            let applied = match &entry.target {
+                BanTarget::Peer(peer_id) => match peer_id.parse::<PeerId>() {
+                    // One trusted operator cannot cut another off
+                    Ok(peer_id) if !self.trusted_issuers.contains(&peer_id) => {
+                        reputation.ban_at_least(peer_id, duration, reason)
+                    }
+                    _ => false,
+                },
+                BanTarget::Ip(ip) if ip.is_loopback() || ip.is_unspecified() => false,
+                BanTarget::Ip(ip) => reputation.ban_ip_at_least(*ip, duration, reason),
+            };
+
+            if applied {
+                report.applied += 1;
+                if expires_at < entry.expires_at {
+                    report.capped += 1;
+                }
+            } else {
+                report.skipped += 1;
+            }
+        }
+        report
+    }
+}
+
+/// Lists a running node fetches and merges periodically
+/// قوائم الحظر المشترك بها
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BanListSubscriptions {
+    /// URLs (`http://`, `https://`) or file paths of signed lists
+    pub sources: Vec<String>,
+    pub policy: BanListPolicy,
+    /// Interval between fetches
+    pub refresh: Duration,
+}
+
+impl BanListSubscriptions {
+    pub fn new(sources: Vec<String>, policy: BanListPolicy) -> Self {
+        Self {
+            sources,
+            policy,
+            refresh: Duration::from_secs(DEFAULT_BAN_LIST_REFRESH_SECS),
+        }
+    }
+}
+
+/// Read a signed list from a URL or a file path, reading at most
+/// `MAX_BAN_LIST_BYTES` whatever the server claims the length is
+pub async fn fetch(http: &reqwest::Client, source: &str) -> Result<SignedBanList, BanListError> {
+    let too_large = || BanListError::Malformed("list too large".to_string());
+    let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+        let mut response = http
+            .get(source)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| BanListError::Fetch(e.to_string()))?;
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_BAN_LIST_BYTES as u64)
+        {
+            return Err(too_large());
+        }
+        // Chunked or lying responses are cut off at the limit as they stream
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| BanListError::Fetch(e.to_string()))?
+        {
+            if bytes.len() + chunk.len() > MAX_BAN_LIST_BYTES {
+                return Err(too_large());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        bytes
+    } else {
+        let mut bytes = Vec::new();
+        tokio::fs::File::open(source)
+            .await?
+            .take(MAX_BAN_LIST_BYTES as u64 + 1)
+            .read_to_end(&mut bytes)
+            .await?;
+        bytes
+    };
+
+    if bytes.len() > MAX_BAN_LIST_BYTES {
+        return Err(too_large());
+    }
+    SignedBanList::from_json(&bytes)
+}
+
+/// IP address a multiaddr connects to, if it names one
+pub fn multiaddr_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    addr.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) => Some(IpAddr::V4(ip)),
+        Protocol::Ip6(ip) => Some(IpAddr::V6(ip)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+
+    fn list(issuer: PeerId, issued_at: u64, entries: Vec<BanEntry>) -> BanList {
+        BanList {
+            issuer: issuer.to_string(),
+            issued_at,
+            entries,
+        }
+    }
+
+    fn entry(target: BanTarget, expires_at: u64) -> BanEntry {
+        BanEntry {
+            target,
+            reason: "Invalid blocks".to_string(),
+            expires_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_stops_at_size_limit() {
+        use tokio::io::AsyncWriteExt;
+
+        // Chunked response with no Content-Length, larger than the limit
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let _ = socket
+                .write_all(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n")
+                .await;
+            let chunk = vec![b'['; 64 * 1024];
+            for _ in 0..(MAX_BAN_LIST_BYTES / chunk.len() + 2) {
+                let header = format!("{:x}\r\n", chunk.len());
+                if socket.write_all(header.as_bytes()).await.is_err()
+                    || socket.write_all(&chunk).await.is_err()
+                    || socket.write_all(b"\r\n").await.is_err()
+                {
+                    return;
+                }
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+        let result = fetch(&reqwest::Client::new(), &format!("http://{}/bans", addr)).await;
+        assert!(matches!(result, Err(BanListError::Malformed(_))));
+
+        let path = std::env::temp_dir().join(format!("banlist_{}.json", std::process::id()));
+        std::fs::write(&path, vec![b' '; MAX_BAN_LIST_BYTES + 1]).unwrap();
+        let result = fetch(&reqwest::Client::new(), path.to_str().unwrap()).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(BanListError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_signed_list_detects_tampering() {
+        let keypair = Keypair::generate_ed25519();
+        let issuer = keypair.public().to_peer_id();
+        let mut reputation = PeerReputation::for_local_peer(issuer);
+        let banned = PeerId::random();
+        reputation.ban(banned, Duration::from_secs(HOUR));
+        reputation.ban_ip_at_least(
+            "203.0.113.9".parse().unwrap(),
+            Duration::from_secs(HOUR),
+            "Spam",
+        );
+
+        let signed =
+            SignedBanList::sign(BanList::from_reputation(issuer, &reputation), &keypair).unwrap();
+        assert_eq!(signed.list.entries.len(), 2);
+        assert_eq!(signed.verify(), Ok(issuer));
+        let parsed = SignedBanList::from_json(signed.to_json().unwrap().as_bytes()).unwrap();
+        assert_eq!(parsed, signed);
+
+        let mut extended = signed.clone();
+        extended.list.entries[0].expires_at += HOUR;
+        assert_eq!(extended.verify(), Err(BanListError::InvalidSignature));
+
+        // Re-signing under another key does not pass as the issuer
+        let other = Keypair::generate_ed25519();
+        let forged = SignedBanList::sign(signed.list.clone(), &other).unwrap();
+        assert!(matches!(
+            forged.verify(),
+            Err(BanListError::IssuerMismatch { .. })
+        ));
+
+        let policy = BanListPolicy::new([other.public().to_peer_id()]);
+        assert_eq!(
+            policy.accept(&signed),
+            Err(BanListError::Untrusted(issuer.to_string()))
+        );
+        assert!(BanListPolicy::new([issuer]).accept(&signed).is_ok());
+    }
+
+    #[test]
+    fn test_merge_caps_imported_bans() {
+        let issuer = PeerId::random();
+        let local = PeerId::random();
+        let now = system_time();
+        let policy = BanListPolicy::new([issuer]).with_max_ban(Duration::from_secs(2 * HOUR));
+
+        let long = PeerId::random();
+        let short = PeerId::random();
+        let over = PeerId::random();
+        let incoming = list(
+            issuer,
+            now - HOUR,
+            vec![
+                // Issued an hour ago: capped to one more hour
+                entry(BanTarget::Peer(long.to_string()), now + 30 * 24 * HOUR),
+                entry(BanTarget::Peer(short.to_string()), now + HOUR / 2),
+                entry(BanTarget::Peer(over.to_string()), now - 1),
+                entry(BanTarget::Peer(local.to_string()), now + HOUR),
+                entry(BanTarget::Peer(issuer.to_string()), now + HOUR),
+                entry(BanTarget::Peer("not-a-peer".to_string()), now + HOUR),
+                entry(BanTarget::Ip("127.0.0.1".parse().unwrap()), now + HOUR),
+                entry(BanTarget::Ip("198.51.100.1".parse().unwrap()), now + HOUR),
+            ],
+        );
+
+        let mut reputation = PeerReputation::for_local_peer(local);
+        let report = policy.merge_at(&incoming, &mut reputation, now);
+        assert_eq!(
+            report,
+            MergeReport {
+                applied: 3,
+                capped: 1,
+                expired: 1,
+                skipped: 4,
+            }
+        );
+        assert!(reputation.is_banned(&long));
+        assert!(reputation.is_banned(&short));
+        assert!(!reputation.is_banned(&over));
+        assert!(!reputation.is_banned(&local));
+        assert!(reputation.is_ip_banned(&"198.51.100.1".parse().unwrap()));
+
+        let (_, record) = reputation
+            .snapshot()
+            .into_iter()
+            .find(|(peer_id, _)| *peer_id == long)
+            .unwrap();
+        assert!(record.banned_until.unwrap() <= now + HOUR + 1);
+        assert_eq!(
+            record.ban_reason,
+            Some(format!("Invalid blocks (via {})", issuer))
+        );
+
+        // A replay of the same list cannot lengthen what it already imposed
+        assert_eq!(policy.merge_at(&incoming, &mut reputation, now).applied, 0);
+
+        // Nor can a list issued far enough in the past
+        let stale = list(
+            issuer,
+            now - 3 * HOUR,
+            vec![entry(
+                BanTarget::Peer(PeerId::random().to_string()),
+                now + HOUR,
+            )],
+        );
+        assert_eq!(policy.merge_at(&stale, &mut reputation, now).expired, 1);
+    }
+
+    #[test]
+    fn test_merge_keeps_longer_local_bans() {
+        let issuer = PeerId::random();
+        let peer_id = PeerId::random();
+        let now = system_time();
+        let mut reputation = PeerReputation::new();
+        reputation.ban(peer_id, Duration::from_secs(48 * HOUR));
+
+        let incoming = list(
+            issuer,
+            now,
+            vec![entry(BanTarget::Peer(peer_id.to_string()), now + HOUR)],
+        );
+        let report = BanListPolicy::new([issuer]).merge_at(&incoming, &mut reputation, now);
+        assert_eq!(report.skipped, 1);
+
+        let (_, record) = reputation.snapshot().pop().unwrap();
+        assert_eq!(record.ban_reason.as_deref(), Some("Banned by operator"));
+        assert!(record.banned_until.unwrap() > now + 47 * HOUR);
+
+        let addr: Multiaddr = "/ip4/198.51.100.1/tcp/9000".parse().unwrap();
+        assert_eq!(multiaddr_ip(&addr), Some("198.51.100.1".parse().unwrap()));
+        assert_eq!(
+            multiaddr_ip(&"/dns/example.org/tcp/9000".parse().unwrap()),
+            None
+        );
+    }
+}
@@ -1,13 +1,20 @@
+use crate::protocol::{MAX_BLOCKS_PER_REQUEST, MAX_HEADERS_PER_REQUEST};
+use crate::{gossip::GossipConfig, latency::PING_INTERVAL_SECS, routing::KADEMLIA_PROTOCOL};
 use libp2p::{
-    gossipsub::{self, IdentTopic, MessageAuthenticity, ValidationMode},
+    gossipsub::{self, IdentTopic, MessageAuthenticity},
     identify,
     kad::{self, store::MemoryStore},
     mdns, ping,
     request_response::{self, cbor, ProtocolSupport},
-    swarm::NetworkBehaviour,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour},
     StreamProtocol,
 };
+use opensyria_core::constants::MAX_BLOCK_SIZE;
+use opensyria_core::{block::BlockHeader, Transaction};
+use opensyria_storage::snapshot::SNAPSHOT_CHUNK_BYTES;
+use opensyria_storage::SnapshotManifest;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// OpenSyria network behavior combining multiple protocols
 #[derive(NetworkBehaviour)]
@@ -18,8 +25,8 @@ pub struct OpenSyriaBehaviour {
     /// mDNS for local peer discovery
     pub mdns: mdns::tokio::Behaviour,
 
-    /// Kademlia DHT for peer discovery and routing
-    pub kademlia: kad::Behaviour<MemoryStore>,
+    /// Kademlia DHT for peer discovery and routing (disabled on private networks)
+    pub kademlia: Toggle<kad::Behaviour<MemoryStore>>,
 
     /// Request-response for block sync
     pub request_response: cbor::Behaviour<NetworkRequest, NetworkResponse>,
@@ -40,42 +47,138 @@ pub enum NetworkRequest {
     },
     GetChainTip,
     GetPeers,
+    /// Headers after the fork point of `locator`; a zero `stop_hash` means no stop
+    GetHeaders {
+        locator: Vec<[u8; 32]>,
+        stop_hash: [u8; 32],
+    },
+    /// Manifest of the state snapshot taken at `height`
+    GetSnapshotManifest {
+        height: u64,
+    },
+    /// One hash-verified piece of the state snapshot at `height`
+    GetSnapshotChunk {
+        height: u64,
+        index: u32,
+    },
+    /// Direct delivery of a payment to the node announced for its recipient
+    DeliverTransaction {
+        transaction: Transaction,
+    },
+}
+
+impl NetworkRequest {
+    /// Largest response payload the request can be answered with, checked
+    /// against the bandwidth budget before the request is sent or served
+    pub fn expected_response_size(&self) -> u64 {
+        let size = match self {
+            NetworkRequest::GetBlocks { max_blocks, .. } => {
+                (*max_blocks).min(MAX_BLOCKS_PER_REQUEST) * MAX_BLOCK_SIZE
+            }
+            NetworkRequest::GetChainTip => 40,
+            // Peer IDs are 52 characters in base58
+            NetworkRequest::GetPeers => 64 * 52,
+            NetworkRequest::GetHeaders { .. } => MAX_HEADERS_PER_REQUEST * 88,
+            NetworkRequest::GetSnapshotManifest { .. }
+            | NetworkRequest::GetSnapshotChunk { .. } => SNAPSHOT_CHUNK_BYTES,
+            NetworkRequest::DeliverTransaction { .. } => 33,
+        };
+        size as u64
+    }
 }
 
 /// Response types for request-response protocol
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum NetworkResponse {
     Blocks { blocks: Vec<Vec<u8>> }, // Serialized blocks
-    ChainTip { height: u64, block_hash: [u8; 32] },
-    Peers { peers: Vec<String> },
-    Error { message: String },
+    /// Also serves as the sync handshake, advertising the snapshots the peer can serve
+    ChainTip {
+        height: u64,
+        block_hash: [u8; 32],
+        #[serde(default)]
+        snapshot_heights: Vec<u64>,
+    },
+    Peers {
+        peers: Vec<String>,
+    },
+    Headers {
+        headers: Vec<BlockHeader>,
+    },
+    SnapshotManifest {
+        manifest: SnapshotManifest,
+    },
+    SnapshotChunk {
+        height: u64,
+        index: u32,
+        data: Vec<u8>,
+    },
+    /// Whether a directly delivered transaction was taken into the mempool
+    TransactionDelivered {
+        tx_hash: [u8; 32],
+        accepted: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+impl NetworkResponse {
+    /// Approximate payload size in bytes, for bandwidth accounting
+    pub fn payload_size(&self) -> u64 {
+        let size = match self {
+            NetworkResponse::Blocks { blocks } => blocks.iter().map(Vec::len).sum(),
+            NetworkResponse::ChainTip {
+                snapshot_heights, ..
+            } => 40 + snapshot_heights.len() * 8,
+            NetworkResponse::Peers { peers } => peers.iter().map(String::len).sum(),
+            NetworkResponse::Headers { headers } => headers
+                .iter()
+                .filter_map(|h| bincode::encode_to_vec(h, bincode::config::standard()).ok())
+                .map(|encoded| encoded.len())
+                .sum(),
+            NetworkResponse::SnapshotManifest { manifest } => {
+                48 + manifest.chunk_count() * 32 + manifest.window_headers.len() * 88
+            }
+            NetworkResponse::SnapshotChunk { data, .. } => data.len(),
+            NetworkResponse::TransactionDelivered { .. } => 33,
+            NetworkResponse::Error { message } => message.len(),
+        };
+        size as u64
+    }
 }
 
+/// Protocol version announced through identify; peers announcing another
+/// one are disconnected
+///
+/// 2.0.0 encodes transactions with their fee sponsor, cosignature and
+/// delegate, which 1.0.0 nodes cannot decode.
+pub const PROTOCOL_VERSION: &str = "/opensyria/2.0.0";
+
+/// Request-response protocol for block and state sync
+pub const SYNC_PROTOCOL: StreamProtocol = StreamProtocol::new("/opensyria/sync/2.0.0");
+
 /// Gossipsub topics
-pub const TOPIC_BLOCKS: &str = "opensyria/blocks/1.0.0";
-pub const TOPIC_TRANSACTIONS: &str = "opensyria/transactions/1.0.0";
+pub const TOPIC_BLOCKS: &str = "opensyria/blocks/2.0.0";
+pub const TOPIC_TRANSACTIONS: &str = "opensyria/transactions/2.0.0";
+pub const TOPIC_CHECKPOINTS: &str = "opensyria/checkpoints/2.0.0";
 
 impl OpenSyriaBehaviour {
     /// Create a new network behaviour
-    pub fn new(local_key: &libp2p::identity::Keypair) -> Result<Self, String> {
-        // Configure Gossipsub
-        let gossipsub_config = gossipsub::ConfigBuilder::default()
-            .heartbeat_interval(std::time::Duration::from_secs(10))
-            .validation_mode(ValidationMode::Strict)
-            .message_id_fn(|message: &gossipsub::Message| {
-                use std::hash::{Hash, Hasher};
-                let mut hasher = std::collections::hash_map::DefaultHasher::new();
-                message.data.hash(&mut hasher);
-                gossipsub::MessageId::from(hasher.finish().to_string())
-            })
-            .build()
-            .map_err(|e| format!("Gossipsub config error: {}", e))?;
-
+    pub fn new(
+        local_key: &libp2p::identity::Keypair,
+        enable_kademlia: bool,
+        gossip: &GossipConfig,
+    ) -> Result<Self, String> {
+        // Configure Gossipsub, scoring peers by their reputation
         let mut gossipsub = gossipsub::Behaviour::new(
             MessageAuthenticity::Signed(local_key.clone()),
-            gossipsub_config,
+            gossip.build()?,
         )
         .map_err(|e| format!("Gossipsub error: {}", e))?;
+        let (score_params, score_thresholds) = crate::gossip::peer_score_params();
+        gossipsub
+            .with_peer_score(score_params, score_thresholds)
+            .map_err(|e| format!("Gossipsub scoring error: {}", e))?;
 
         // Subscribe to topics
         gossipsub
@@ -84,34 +187,46 @@ impl OpenSyriaBehaviour {
         gossipsub
             .subscribe(&IdentTopic::new(TOPIC_TRANSACTIONS))
             .map_err(|e| format!("Subscribe error: {}", e))?;
+        gossipsub
+            .subscribe(&IdentTopic::new(TOPIC_CHECKPOINTS))
+            .map_err(|e| format!("Subscribe error: {}", e))?;
 
         // Configure mDNS
         let mdns =
             mdns::tokio::Behaviour::new(mdns::Config::default(), local_key.public().to_peer_id())
                 .map_err(|e| format!("mDNS error: {}", e))?;
 
-        // Configure Kademlia DHT
+        // Configure Kademlia DHT; inbound records are stored only after the
+        // node has checked them (see `directory`)
         let local_peer_id = local_key.public().to_peer_id();
         let store = MemoryStore::new(local_peer_id);
-        let kademlia = kad::Behaviour::new(local_peer_id, store);
+        let mut kad_config = kad::Config::default();
+        kad_config.set_protocol_names(vec![KADEMLIA_PROTOCOL]);
+        kad_config.set_record_filtering(kad::StoreInserts::FilterBoth);
+        let kademlia = Toggle::from(enable_kademlia.then(|| {
+            let mut kademlia = kad::Behaviour::with_config(local_peer_id, store, kad_config);
+            // Full nodes listen publicly, so serve DHT queries without waiting
+            // for an external address to be confirmed
+            kademlia.set_mode(Some(kad::Mode::Server));
+            kademlia
+        }));
 
         // Configure request-response
         let request_response = cbor::Behaviour::new(
-            [(
-                StreamProtocol::new("/opensyria/sync/1.0.0"),
-                ProtocolSupport::Full,
-            )],
+            [(SYNC_PROTOCOL, ProtocolSupport::Full)],
             request_response::Config::default(),
         );
 
         // Configure identify
         let identify = identify::Behaviour::new(identify::Config::new(
-            "/opensyria/1.0.0".to_string(),
+            PROTOCOL_VERSION.to_string(),
             local_key.public(),
         ));
 
-        // Configure ping
-        let ping = ping::Behaviour::new(ping::Config::new());
+        // Configure ping; round trips feed peer latency
+        let ping = ping::Behaviour::new(
+            ping::Config::new().with_interval(Duration::from_secs(PING_INTERVAL_SECS)),
+        );
 
         Ok(Self {
             gossipsub,
@@ -132,4 +247,9 @@ impl OpenSyriaBehaviour {
     pub fn transactions_topic() -> IdentTopic {
         IdentTopic::new(TOPIC_TRANSACTIONS)
     }
+
+    /// Get signed checkpoints topic
+    pub fn checkpoints_topic() -> IdentTopic {
+        IdentTopic::new(TOPIC_CHECKPOINTS)
+    }
 }
@@ -0,0 +1,187 @@
+//! Wallet key to peer ID records for direct transaction delivery
+//! سجلات ربط مفاتيح المحافظ بمعرفات الأقران للتسليم المباشر
+//!
+//! A wallet may opt in to publishing a Kademlia record naming the node that
+//! receives its payments. The record is signed by the wallet key, so only the
+//! wallet owner can point its address at a peer. A sender looks the record up
+//! and delivers the transaction (with any invoice in its data field) straight
+//! to that node, in addition to gossiping it.
+
+use anyhow::{anyhow, bail, Result};
+use libp2p::{kad, Multiaddr, PeerId};
+use opensyria_core::crypto::{KeyPair, PublicKey};
+
+/// DHT key prefix for wallet records
+pub const WALLET_RECORD_PREFIX: &[u8] = b"/opensyria/wallet-peer/";
+
+/// Domain separator for wallet record signatures
+const WALLET_RECORD_DOMAIN: &[u8] = b"opensyria-wallet-peer-v1";
+
+/// How long a wallet record stays valid after signing (24 hours)
+pub const WALLET_RECORD_TTL_SECS: u64 = 24 * 60 * 60;
+
+/// How far in the future a record's timestamp may be (clock skew)
+const MAX_RECORD_CLOCK_SKEW_SECS: u64 = 300;
+
+/// Most addresses carried in one record
+pub const MAX_RECORD_ADDRESSES: usize = 8;
+
+/// Signed statement that payments to `wallet` should be delivered to `peer_id`
+/// بيان موقع بأن المدفوعات إلى المحفظة تسلم إلى هذا النظير
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct WalletPeerRecord {
+    /// Wallet public key the record speaks for
+    pub wallet: PublicKey,
+    /// Peer ID of the receiving node (multihash bytes)
+    pub peer_id: Vec<u8>,
+    /// Addresses the node can be dialed on
+    pub addresses: Vec<String>,
+    /// Unix time the record was signed
+    pub issued_at: u64,
+    /// Wallet signature over all fields above
+    pub signature: Vec<u8>,
+}
+
+impl WalletPeerRecord {
+    /// Sign a record pointing `wallet` at `peer_id`
+    pub fn new(wallet: &KeyPair, peer_id: PeerId, addresses: &[Multiaddr], issued_at: u64) -> Self {
+        let mut record = Self {
+            wallet: wallet.public_key(),
+            peer_id: peer_id.to_bytes(),
+            addresses: addresses
+                .iter()
+                .take(MAX_RECORD_ADDRESSES)
+                .map(|addr| addr.to_string())
+                .collect(),
+            issued_at,
+            signature: Vec::new(),
+        };
+        record.signature = wallet.sign(&record.signing_message());
+        record
+    }
+
+    /// DHT key under which the record for `wallet` is stored
+    pub fn dht_key(wallet: &PublicKey) -> kad::RecordKey {
+        let mut key = WALLET_RECORD_PREFIX.to_vec();
+        key.extend_from_slice(&wallet.0);
+        kad::RecordKey::new(&key)
+    }
+
+    fn signing_message(&self) -> Vec<u8> {
+        let mut message = WALLET_RECORD_DOMAIN.to_vec();
+        message.extend_from_slice(&self.wallet.0);
+        message.extend_from_slice(&(self.peer_id.len() as u64).to_le_bytes());
+        message.extend_from_slice(&self.peer_id);
+        message.extend_from_slice(&(self.addresses.len() as u64).to_le_bytes());
+        for address in &self.addresses {
+            message.extend_from_slice(&(address.len() as u64).to_le_bytes());
+            message.extend_from_slice(address.as_bytes());
+        }
+        message.extend_from_slice(&self.issued_at.to_le_bytes());
+        message
+    }
+
+    /// Check the signature and age; returns the peer to deliver to
+    pub fn verify(&self, now: u64) -> Result<PeerId> {
+        if self.addresses.len() > MAX_RECORD_ADDRESSES {
+            bail!("Wallet record has {} addresses", self.addresses.len());
+        }
+        if self.issued_at > now + MAX_RECORD_CLOCK_SKEW_SECS {
+            bail!("Wallet record is signed in the future");
+        }
+        if now.saturating_sub(self.issued_at) > WALLET_RECORD_TTL_SECS {
+            bail!("Wallet record has expired");
+        }
+        self.wallet
+            .verify(&self.signing_message(), &self.signature)
+            .map_err(|_| anyhow!("Invalid wallet record signature"))?;
+        PeerId::from_bytes(&self.peer_id).map_err(|e| anyhow!("Invalid peer ID: {}", e))
+    }
+
+    /// Dialable addresses; unparsable entries are skipped
+    pub fn multiaddrs(&self) -> Vec<Multiaddr> {
+        self.addresses
+            .iter()
+            .filter_map(|addr| addr.parse().ok())
+            .collect()
+    }
+
+    /// Encode for storage in the DHT
+    pub fn encode(&self) -> Vec<u8> {
+        bincode::encode_to_vec(self, bincode::config::standard()).unwrap_or_default()
+    }
+
+    /// Decode a record fetched from the DHT
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let (record, read) = bincode::decode_from_slice(bytes, bincode::config::standard())
+            .map_err(|e| anyhow!("Malformed wallet record: {}", e))?;
+        if read != bytes.len() {
+            bail!("Wallet record has trailing bytes");
+        }
+        Ok(record)
+    }
+
+    /// Decode and verify a DHT record, checking it is stored under its wallet's key
+    pub fn from_dht(record: &kad::Record, now: u64) -> Result<(Self, PeerId)> {
+        let wallet_record = Self::decode(&record.value)?;
+        if record.key != Self::dht_key(&wallet_record.wallet) {
+            bail!("Wallet record stored under another key");
+        }
+        let peer_id = wallet_record.verify(now)?;
+        Ok((wallet_record, peer_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wallet_record_roundtrip() {
+        let wallet = KeyPair::generate();
+        let peer_id = PeerId::random();
+        let addr: Multiaddr = "/ip4/10.0.0.1/tcp/9000".parse().unwrap();
+        let now = 1_700_000_000;
+
+        let record = WalletPeerRecord::new(&wallet, peer_id, std::slice::from_ref(&addr), now);
+        assert_eq!(record.verify(now).unwrap(), peer_id);
+        assert_eq!(record.multiaddrs(), vec![addr]);
+
+        let dht = kad::Record::new(
+            WalletPeerRecord::dht_key(&wallet.public_key()),
+            record.encode(),
+        );
+        let (decoded, decoded_peer) = WalletPeerRecord::from_dht(&dht, now).unwrap();
+        assert_eq!(decoded, record);
+        assert_eq!(decoded_peer, peer_id);
+
+        // Stored under another wallet's key
+        let other = KeyPair::generate();
+        let misplaced = kad::Record::new(
+            WalletPeerRecord::dht_key(&other.public_key()),
+            record.encode(),
+        );
+        assert!(WalletPeerRecord::from_dht(&misplaced, now).is_err());
+    }
+
+    #[test]
+    fn test_wallet_record_rejects_tampering_and_age() {
+        let wallet = KeyPair::generate();
+        let now = 1_700_000_000;
+        let record = WalletPeerRecord::new(&wallet, PeerId::random(), &[], now);
+
+        // Redirecting payments to another peer breaks the signature
+        let mut redirected = record.clone();
+        redirected.peer_id = PeerId::random().to_bytes();
+        assert!(redirected.verify(now).is_err());
+
+        // Signed by a key other than the wallet's
+        let mut forged = WalletPeerRecord::new(&KeyPair::generate(), PeerId::random(), &[], now);
+        forged.wallet = wallet.public_key();
+        assert!(forged.verify(now).is_err());
+
+        assert!(record.verify(now + WALLET_RECORD_TTL_SECS).is_ok());
+        assert!(record.verify(now + WALLET_RECORD_TTL_SECS + 1).is_err());
+        assert!(record.verify(now - MAX_RECORD_CLOCK_SKEW_SECS - 1).is_err());
+    }
+}
@@ -0,0 +1,506 @@
+//! Gossipsub tuning, peer scoring and adaptive fanout
+//! ضبط بروتوكول النشر وتقييم الأقران والانتشار التكيفي
+//!
+//! Mesh sizes, heartbeat and fanout TTL come from [`GossipConfig`]. Peer
+//! reputation is fed to gossipsub as the application-specific score, so peers
+//! that send invalid data lose their mesh slots before they are banned.
+//!
+//! Published messages carry a trailing send timestamp. Older nodes never see
+//! it: `NetworkMessage::from_bytes` stops at the end of the encoded message.
+//! Receivers keep the delays in a window, and when the median exceeds the
+//! target they also relay to a few well-reputed peers outside the mesh
+//! (gossipsub explicit peers). The extra fanout is halved whenever the
+//! bandwidth budget throttled traffic, and shrinks again once propagation is
+//! fast.
+//!
+//! Relay-only nodes also remember the hashes of the blocks and transactions
+//! they handled in a bounded [`SeenCache`], and drop repeats whichever peer
+//! or encoding they arrive with.
+
+use crate::latency::compare_latency;
+use libp2p::{
+    gossipsub::{self, PeerScoreParams, PeerScoreThresholds, ValidationMode},
+    PeerId,
+};
+use opensyria_core::clock::system_time_millis;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+/// Marker in front of the send timestamp appended to published gossip
+pub const PROPAGATION_STAMP_MAGIC: [u8; 4] = *b"OSpt";
+
+/// Bytes appended to published gossip: marker and big-endian send time in ms
+pub const PROPAGATION_STAMP_LEN: usize = PROPAGATION_STAMP_MAGIC.len() + 8;
+
+/// How often the fanout is adjusted and application scores refreshed (seconds)
+pub const FANOUT_ADJUST_INTERVAL_SECS: u64 = 30;
+
+/// Delay samples kept for the propagation estimate
+const DELAY_WINDOW: usize = 256;
+
+/// Delays above this are taken for clock skew and ignored (ms)
+const MAX_PLAUSIBLE_DELAY_MS: u64 = 60_000;
+
+/// Reputation scores beyond this are clamped before scaling
+const APP_SCORE_CLAMP: i32 = 100;
+
+/// Gossipsub score per reputation point
+const APP_SCORE_PER_REPUTATION: f64 = 0.1;
+
+/// Gossipsub parameters and adaptive fanout bounds
+/// معاملات بروتوكول النشر وحدود الانتشار التكيفي
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GossipConfig {
+    /// Target number of mesh peers per topic (D)
+    pub mesh_n: usize,
+    /// Graft more peers when the mesh falls below this (D_low)
+    pub mesh_n_low: usize,
+    /// Prune peers when the mesh grows above this (D_high)
+    pub mesh_n_high: usize,
+    /// Gossipsub heartbeat interval
+    pub heartbeat_interval: Duration,
+    /// How long fanout peers are kept for topics we publish to without joining
+    pub fanout_ttl: Duration,
+    /// Relay to extra peers while propagation is slow
+    pub adaptive_fanout: bool,
+    /// Median propagation delay above which the fanout grows (ms)
+    pub target_propagation_ms: u64,
+    /// Most peers relayed to outside the mesh
+    pub max_extra_fanout: usize,
+}
+
+impl Default for GossipConfig {
+    fn default() -> Self {
+        Self {
+            mesh_n: 6,
+            mesh_n_low: 5,
+            mesh_n_high: 12,
+            heartbeat_interval: Duration::from_secs(10),
+            fanout_ttl: Duration::from_secs(60),
+            adaptive_fanout: true,
+            target_propagation_ms: 2_000,
+            max_extra_fanout: 6,
+        }
+    }
+}
+
+impl GossipConfig {
+    /// Check that the mesh sizes and intervals are consistent
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mesh_n_low == 0 {
+            return Err("Gossip mesh_n_low must be at least 1".to_string());
+        }
+        if !(self.mesh_n_low <= self.mesh_n && self.mesh_n <= self.mesh_n_high) {
+            return Err(format!(
+                "Gossip mesh sizes must satisfy low <= n <= high (got {} <= {} <= {})",
+                self.mesh_n_low, self.mesh_n, self.mesh_n_high
+            ));
+        }
+        if self.heartbeat_interval.is_zero() {
+            return Err("Gossip heartbeat interval must be positive".to_string());
+        }
+        if self.fanout_ttl.is_zero() {
+            return Err("Gossip fanout TTL must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Build the gossipsub configuration
+    pub fn build(&self) -> Result<gossipsub::Config, String> {
+        self.validate()?;
+        // Outbound mesh quota, kept within gossipsub's `<= D/2` and `<= D_low` rules
+        let mesh_outbound_min = 2.min(self.mesh_n / 2).min(self.mesh_n_low);
+        gossipsub::ConfigBuilder::default()
+            .mesh_n(self.mesh_n)
+            .mesh_n_low(self.mesh_n_low)
+            .mesh_n_high(self.mesh_n_high)
+            .mesh_outbound_min(mesh_outbound_min)
+            .heartbeat_interval(self.heartbeat_interval)
+            .fanout_ttl(self.fanout_ttl)
+            .validation_mode(ValidationMode::Strict)
+            .message_id_fn(|message: &gossipsub::Message| message_id(&message.data))
+            .build()
+            .map_err(|e| format!("Gossipsub config error: {}", e))
+    }
+}
+
+/// Peer scoring with reputation as the only application input. IP
+/// colocation is not penalized; ASN diversity is enforced on connect.
+pub fn peer_score_params() -> (PeerScoreParams, PeerScoreThresholds) {
+    let params = PeerScoreParams {
+        app_specific_weight: 1.0,
+        ip_colocation_factor_weight: 0.0,
+        ..Default::default()
+    };
+    (params, PeerScoreThresholds::default())
+}
+
+/// Gossipsub application score for a reputation score: a banned peer
+/// (-100) reaches the gossip threshold, a warned one (-50) is half way
+pub fn application_score(reputation: i32) -> f64 {
+    reputation.clamp(-APP_SCORE_CLAMP, APP_SCORE_CLAMP) as f64 * APP_SCORE_PER_REPUTATION
+}
+
+/// Message ID over the payload, so a timestamp does not change it
+fn message_id(data: &[u8]) -> gossipsub::MessageId {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    split_stamp(data).0.hash(&mut hasher);
+    gossipsub::MessageId::from(hasher.finish().to_string())
+}
+
+/// Append the current time to an encoded gossip message
+pub fn stamp(mut data: Vec<u8>) -> Vec<u8> {
+    data.extend_from_slice(&PROPAGATION_STAMP_MAGIC);
+    data.extend_from_slice(&system_time_millis().to_be_bytes());
+    data
+}
+
+/// Payload and send time (ms) of a gossip message, if it is stamped
+pub fn split_stamp(data: &[u8]) -> (&[u8], Option<u64>) {
+    let Some(split) = data.len().checked_sub(PROPAGATION_STAMP_LEN) else {
+        return (data, None);
+    };
+    let (payload, trailer) = data.split_at(split);
+    let (magic, time) = trailer.split_at(PROPAGATION_STAMP_MAGIC.len());
+    if magic != PROPAGATION_STAMP_MAGIC {
+        return (data, None);
+    }
+    let sent_at = u64::from_be_bytes(time.try_into().expect("8-byte trailer"));
+    (payload, Some(sent_at))
+}
+
+/// Block and transaction hashes a relay-only node remembers
+pub const RELAY_SEEN_CAPACITY: usize = 16_384;
+
+/// Recently seen item hashes, oldest forgotten first
+/// بصمات العناصر التي شوهدت مؤخراً، تُنسى الأقدم أولاً
+#[derive(Debug)]
+pub struct SeenCache {
+    capacity: usize,
+    hashes: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+}
+
+impl SeenCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            hashes: HashSet::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Remember `hash`; false if it was already seen
+    pub fn insert(&mut self, hash: [u8; 32]) -> bool {
+        if !self.hashes.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.hashes.remove(&oldest);
+            }
+        }
+        true
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
+/// Propagation and fanout figures, for metrics
+/// أرقام الانتشار وعدد الأقران الإضافيين
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GossipStats {
+    /// Median delay over the last adjustment interval (ms)
+    pub median_delay_ms: Option<u64>,
+    /// Stamped messages received since start
+    pub samples: u64,
+    /// Peers relayed to outside the mesh
+    pub extra_fanout: usize,
+}
+
+/// Propagation delay tracker and adaptive fanout controller
+/// متتبع زمن الانتشار ومتحكم الانتشار التكيفي
+#[derive(Debug)]
+pub struct GossipTuner {
+    config: GossipConfig,
+    delays: VecDeque<u64>,
+    samples: u64,
+    last_median: Option<u64>,
+    extra_fanout: usize,
+    boosted: HashSet<PeerId>,
+    last_throttled: u64,
+}
+
+impl GossipTuner {
+    /// Create a tuner with no extra fanout
+    pub fn new(config: GossipConfig) -> Self {
+        Self {
+            config,
+            delays: VecDeque::with_capacity(DELAY_WINDOW),
+            samples: 0,
+            last_median: None,
+            extra_fanout: 0,
+            boosted: HashSet::new(),
+            last_throttled: 0,
+        }
+    }
+
+    /// Record how long a received message took to reach us
+    pub fn record(&mut self, data: &[u8]) -> Option<u64> {
+        self.record_at(data, system_time_millis())
+    }
+
+    fn record_at(&mut self, data: &[u8], now_ms: u64) -> Option<u64> {
+        let sent_at = split_stamp(data).1?;
+        let delay = now_ms.checked_sub(sent_at)?;
+        if delay > MAX_PLAUSIBLE_DELAY_MS {
+            return None;
+        }
+        if self.delays.len() == DELAY_WINDOW {
+            self.delays.pop_front();
+        }
+        self.delays.push_back(delay);
+        self.samples += 1;
+        Some(delay)
+    }
+
+    /// Median of the delays recorded since the last adjustment
+    pub fn median_delay_ms(&self) -> Option<u64> {
+        let mut delays: Vec<u64> = self.delays.iter().copied().collect();
+        if delays.is_empty() {
+            return None;
+        }
+        delays.sort_unstable();
+        Some(delays[delays.len() / 2])
+    }
+
+    /// Decide the extra fanout for the next interval from the delays seen
+    /// and the running total of transfers throttled by the bandwidth budget
+    pub fn adjust(&mut self, throttled_total: u64) -> usize {
+        let throttled = throttled_total > self.last_throttled;
+        self.last_throttled = throttled_total;
+        let median = self.median_delay_ms();
+        self.delays.clear();
+        if median.is_some() {
+            self.last_median = median;
+        }
+
+        if !self.config.adaptive_fanout {
+            self.extra_fanout = 0;
+        } else if throttled {
+            self.extra_fanout /= 2;
+        } else if let Some(median) = median {
+            if median > self.config.target_propagation_ms {
+                self.extra_fanout = (self.extra_fanout + 1).min(self.config.max_extra_fanout);
+            } else if median <= self.config.target_propagation_ms / 2 {
+                self.extra_fanout = self.extra_fanout.saturating_sub(1);
+            }
+        }
+        self.extra_fanout
+    }
+
+    /// Choose the peers to relay to outside the mesh: the best-reputed
+    /// connected peers with a non-negative score, nearest first among equal
+    /// scores. Candidates are `(peer, reputation, ping latency)`. Returns the
+    /// peers to add and to remove as explicit peers.
+    pub fn rebalance(
+        &mut self,
+        mut candidates: Vec<(PeerId, i32, Option<Duration>)>,
+        mesh: &HashSet<PeerId>,
+    ) -> (Vec<PeerId>, Vec<PeerId>) {
+        candidates.retain(|(peer_id, score, _)| *score >= 0 && !mesh.contains(peer_id));
+        candidates.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| compare_latency(a.2, b.2))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        let wanted: HashSet<PeerId> = candidates
+            .into_iter()
+            .take(self.extra_fanout)
+            .map(|(peer_id, _, _)| peer_id)
+            .collect();
+
+        let added = wanted.difference(&self.boosted).copied().collect();
+        let removed = self.boosted.difference(&wanted).copied().collect();
+        self.boosted = wanted;
+        (added, removed)
+    }
+
+    /// Drop a disconnected peer; returns whether it was being relayed to
+    pub fn forget(&mut self, peer_id: &PeerId) -> bool {
+        self.boosted.remove(peer_id)
+    }
+
+    /// Current figures
+    pub fn stats(&self) -> GossipStats {
+        GossipStats {
+            median_delay_ms: self.last_median,
+            samples: self.samples,
+            extra_fanout: self.boosted.len(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gossip_config_validation() {
+        assert!(GossipConfig::default().build().is_ok());
+
+        let inverted = GossipConfig {
+            mesh_n_low: 8,
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+
+        let small = GossipConfig {
+            mesh_n: 1,
+            mesh_n_low: 1,
+            mesh_n_high: 2,
+            ..Default::default()
+        };
+        assert!(small.build().is_ok());
+    }
+
+    #[test]
+    fn test_stamp_round_trip() {
+        let payload = vec![1, 2, 3];
+        let stamped = stamp(payload.clone());
+        assert_eq!(stamped.len(), payload.len() + PROPAGATION_STAMP_LEN);
+
+        let (body, sent_at) = split_stamp(&stamped);
+        assert_eq!(body, &payload[..]);
+        assert!(sent_at.unwrap() <= system_time_millis());
+
+        // Unstamped messages from older nodes pass through whole
+        assert_eq!(split_stamp(&payload), (&payload[..], None));
+        assert_eq!(message_id(&stamped), message_id(&payload));
+    }
+
+    #[test]
+    fn test_application_score() {
+        assert_eq!(application_score(0), 0.0);
+        assert_eq!(application_score(-100), -10.0);
+        assert_eq!(application_score(-500), -10.0);
+        assert_eq!(application_score(1_000), 10.0);
+    }
+
+    #[test]
+    fn test_seen_cache_forgets_oldest() {
+        let mut seen = SeenCache::new(2);
+        assert!(seen.insert([1; 32]));
+        assert!(!seen.insert([1; 32]));
+        assert!(seen.insert([2; 32]));
+        assert!(seen.insert([3; 32]));
+        assert_eq!(seen.len(), 2);
+
+        // The first hash was evicted, the later ones are still known
+        assert!(!seen.insert([3; 32]));
+        assert!(seen.insert([1; 32]));
+        assert!(!seen.insert([3; 32]));
+        assert!(seen.insert([2; 32]));
+    }
+
+    #[test]
+    fn test_fanout_follows_propagation_delay() {
+        let mut tuner = GossipTuner::new(GossipConfig {
+            max_extra_fanout: 2,
+            ..Default::default()
+        });
+        let slow = |tuner: &mut GossipTuner| {
+            let mut data = vec![0u8];
+            data.extend_from_slice(&PROPAGATION_STAMP_MAGIC);
+            data.extend_from_slice(&10_000u64.to_be_bytes());
+            assert_eq!(tuner.record_at(&data, 15_000), Some(5_000));
+        };
+
+        slow(&mut tuner);
+        assert_eq!(tuner.adjust(0), 1);
+        slow(&mut tuner);
+        assert_eq!(tuner.adjust(0), 2);
+        slow(&mut tuner);
+        assert_eq!(tuner.adjust(0), 2);
+
+        // Bandwidth pressure halves the extra fanout even while slow
+        slow(&mut tuner);
+        assert_eq!(tuner.adjust(3), 1);
+
+        // No samples keeps the current fanout; fast propagation shrinks it
+        assert_eq!(tuner.adjust(3), 1);
+        let mut fast = vec![0u8];
+        fast.extend_from_slice(&PROPAGATION_STAMP_MAGIC);
+        fast.extend_from_slice(&10_000u64.to_be_bytes());
+        tuner.record_at(&fast, 10_200);
+        assert_eq!(tuner.adjust(3), 0);
+        assert_eq!(tuner.stats().median_delay_ms, Some(200));
+
+        // Skewed clocks are ignored
+        assert_eq!(tuner.record_at(&fast, 9_000), None);
+        assert_eq!(tuner.record_at(&fast, 100_000), None);
+    }
+
+    #[test]
+    fn test_rebalance_prefers_reputable_peers_outside_mesh() {
+        let mut tuner = GossipTuner::new(GossipConfig::default());
+        let (good, better, meshed, bad) = (
+            PeerId::random(),
+            PeerId::random(),
+            PeerId::random(),
+            PeerId::random(),
+        );
+        let candidates = vec![
+            (good, 5, None),
+            (better, 20, None),
+            (meshed, 50, None),
+            (bad, -10, None),
+        ];
+        let mesh: HashSet<PeerId> = [meshed].into_iter().collect();
+
+        tuner.extra_fanout = 1;
+        let (added, removed) = tuner.rebalance(candidates.clone(), &mesh);
+        assert_eq!(added, vec![better]);
+        assert!(removed.is_empty());
+
+        tuner.extra_fanout = 3;
+        let (added, removed) = tuner.rebalance(candidates.clone(), &mesh);
+        assert_eq!(added, vec![good]);
+        assert!(removed.is_empty());
+        assert_eq!(tuner.stats().extra_fanout, 2);
+
+        tuner.extra_fanout = 0;
+        let (added, removed) = tuner.rebalance(candidates, &mesh);
+        assert!(added.is_empty());
+        assert_eq!(removed.len(), 2);
+        assert!(!tuner.forget(&good));
+    }
+
+    #[test]
+    fn test_rebalance_prefers_nearby_peers_among_equals() {
+        let mut tuner = GossipTuner::new(GossipConfig::default());
+        let (near, far, unmeasured) = (PeerId::random(), PeerId::random(), PeerId::random());
+        let candidates = vec![
+            (unmeasured, 10, None),
+            (far, 10, Some(Duration::from_millis(240))),
+            (near, 10, Some(Duration::from_millis(12))),
+        ];
+
+        tuner.extra_fanout = 1;
+        let (added, _) = tuner.rebalance(candidates.clone(), &HashSet::new());
+        assert_eq!(added, vec![near]);
+
+        tuner.extra_fanout = 2;
+        let (added, _) = tuner.rebalance(candidates, &HashSet::new());
+        assert_eq!(added, vec![far]);
+    }
+}
@@ -0,0 +1,440 @@
+//! Persistent node identity key, encrypted at rest
+//! مفتاح هوية العقدة الدائم، مشفر عند التخزين
+//!
+//! The libp2p keypair determines the node's peer ID. Keeping it on disk means
+//! the ID survives restarts, so remote peers' reputation records and peer caches
+//! keep pointing at the same node.
+//!
+//! The key is never encrypted with an empty passphrase. Without one from the
+//! operator, [`NodeIdentity::open`] generates a random passphrase and stores it
+//! in [`PASSPHRASE_FILE`] next to the key, readable by the owner only. Keys
+//! written with an empty passphrase by earlier releases are re-encrypted the
+//! same way on their next start.
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use argon2::Argon2;
+use libp2p::{identity::Keypair, PeerId};
+use opensyria_core::clock::system_time;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Identity key file name inside the network data directory
+pub const IDENTITY_FILE: &str = "identity.key";
+
+/// Environment variable holding the identity key passphrase
+pub const IDENTITY_PASSPHRASE_ENV: &str = "OPENSYRIA_NODE_KEY_PASSPHRASE";
+
+/// Generated passphrase file inside the network data directory, used when
+/// the operator supplies none
+pub const PASSPHRASE_FILE: &str = "identity.passphrase";
+
+/// Current on-disk format version
+const IDENTITY_VERSION: u32 = 1;
+
+/// On-disk representation of the identity key
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedIdentity {
+    version: u32,
+    /// Peer ID of the stored key (readable without the passphrase)
+    peer_id: String,
+    /// Argon2 salt
+    salt: [u8; 16],
+    /// AES-GCM nonce (96 bits / 12 bytes)
+    nonce: [u8; 12],
+    /// Protobuf-encoded keypair encrypted with AES-256-GCM
+    encrypted_key: Vec<u8>,
+    created_at: u64,
+}
+
+/// Node identity backed by a keypair file
+/// هوية العقدة المدعومة بملف زوج المفاتيح
+pub struct NodeIdentity {
+    keypair: Keypair,
+    path: PathBuf,
+    created_at: u64,
+}
+
+impl NodeIdentity {
+    /// Identity key path for a network data directory
+    pub fn path_in(data_dir: &Path) -> PathBuf {
+        data_dir.join(IDENTITY_FILE)
+    }
+
+    /// Load the identity from `data_dir`, creating one on first start
+    /// فتح هوية العقدة بعبارة المرور المعطاة أو المولدة
+    ///
+    /// With no `passphrase`, the one in [`PASSPHRASE_FILE`] is used, and
+    /// generated on first start or when upgrading a key encrypted with an
+    /// empty passphrase. An empty `passphrase` is refused.
+    pub fn open(data_dir: &Path, passphrase: Option<&str>) -> Result<Self> {
+        if let Some(passphrase) = passphrase {
+            if passphrase.is_empty() {
+                return Err(anyhow!("Node identity passphrase must not be empty"));
+            }
+            return Self::load_or_generate(data_dir, passphrase);
+        }
+        if let Some(stored) = read_passphrase(data_dir)? {
+            return Self::load_or_generate(data_dir, &stored);
+        }
+
+        let generated = hex::encode(rand::random::<[u8; 32]>());
+        if !Self::path_in(data_dir).exists() {
+            write_passphrase(data_dir, &generated)?;
+            return Self::generate(data_dir, &generated);
+        }
+
+        // Written before passphrases were required
+        let identity = Self::load(data_dir, "").with_context(|| {
+            format!(
+                "Node identity is encrypted; set {} to its passphrase",
+                IDENTITY_PASSPHRASE_ENV
+            )
+        })?;
+        write_passphrase(data_dir, &generated)?;
+        identity.save(&generated)?;
+        tracing::warn!(
+            "Node identity had an empty passphrase; re-encrypted it with one stored in {}",
+            passphrase_path(data_dir).display()
+        );
+        Ok(identity)
+    }
+
+    /// Passphrase [`Self::open`] uses: `passphrase` or the stored one
+    pub fn passphrase(data_dir: &Path, passphrase: Option<&str>) -> Result<String> {
+        match passphrase {
+            Some(passphrase) => Ok(passphrase.to_string()),
+            None => read_passphrase(data_dir)?.ok_or_else(|| {
+                anyhow!(
+                    "No passphrase for the node identity; set {} or start the node once",
+                    IDENTITY_PASSPHRASE_ENV
+                )
+            }),
+        }
+    }
+
+    /// Load the identity from `data_dir` with `passphrase`, creating one on
+    /// first start
+    /// تحميل الهوية أو إنشاؤها عند أول تشغيل
+    pub fn load_or_generate(data_dir: &Path, passphrase: &str) -> Result<Self> {
+        let path = Self::path_in(data_dir);
+        if path.exists() {
+            Self::load(data_dir, passphrase)
+        } else {
+            Self::generate(data_dir, passphrase)
+        }
+    }
+
+    /// Load and decrypt an existing identity
+    pub fn load(data_dir: &Path, passphrase: &str) -> Result<Self> {
+        let path = Self::path_in(data_dir);
+        let data = fs::read(&path)
+            .with_context(|| format!("Failed to read node identity {}", path.display()))?;
+        let stored: EncryptedIdentity = serde_json::from_slice(&data)
+            .with_context(|| format!("Corrupted node identity {}", path.display()))?;
+
+        if stored.version != IDENTITY_VERSION {
+            return Err(anyhow!(
+                "Unsupported node identity version {}",
+                stored.version
+            ));
+        }
+
+        let cipher = cipher_for(passphrase, &stored.salt)?;
+        let encoded = cipher
+            .decrypt(
+                Nonce::from_slice(&stored.nonce),
+                stored.encrypted_key.as_ref(),
+            )
+            .map_err(|_| anyhow!("Failed to decrypt node identity - wrong passphrase?"))?;
+        let keypair = Keypair::from_protobuf_encoding(&encoded)
+            .map_err(|e| anyhow!("Invalid node identity key: {}", e))?;
+
+        // The public peer ID must match the encrypted key
+        let peer_id = keypair.public().to_peer_id();
+        if peer_id.to_string() != stored.peer_id {
+            return Err(anyhow!(
+                "Node identity peer ID mismatch: file says {}, key is {}",
+                stored.peer_id,
+                peer_id
+            ));
+        }
+
+        Ok(Self {
+            keypair,
+            path,
+            created_at: stored.created_at,
+        })
+    }
+
+    /// Generate a new ed25519 identity and write it to `data_dir`
+    pub fn generate(data_dir: &Path, passphrase: &str) -> Result<Self> {
+        let identity = Self {
+            keypair: Keypair::generate_ed25519(),
+            path: Self::path_in(data_dir),
+            created_at: system_time(),
+        };
+        identity.save(passphrase)?;
+
+        tracing::info!("Generated node identity {}", identity.peer_id());
+        Ok(identity)
+    }
+
+    /// Replace the identity with a fresh key, keeping the old file as a backup
+    /// تدوير مفتاح الهوية مع الاحتفاظ بنسخة من المفتاح القديم
+    ///
+    /// Returns the retired peer ID and the new identity. The passphrase must
+    /// unlock the current key so a rotation can't be triggered blindly.
+    pub fn rotate(data_dir: &Path, passphrase: &str) -> Result<(PeerId, Self)> {
+        let old = Self::load(data_dir, passphrase)?;
+        let old_peer_id = old.peer_id();
+
+        let backup = data_dir.join(format!("{}.{}.old", IDENTITY_FILE, old_peer_id));
+        fs::rename(&old.path, &backup)
+            .with_context(|| format!("Failed to back up node identity to {}", backup.display()))?;
+
+        let new = Self::generate(data_dir, passphrase)?;
+        tracing::info!("Rotated node identity {} -> {}", old_peer_id, new.peer_id());
+
+        Ok((old_peer_id, new))
+    }
+
+    /// Read the peer ID of a stored identity without decrypting it
+    pub fn stored_peer_id(data_dir: &Path) -> Result<Option<PeerId>> {
+        let path = Self::path_in(data_dir);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let stored: EncryptedIdentity = serde_json::from_slice(&fs::read(&path)?)?;
+        let peer_id = stored
+            .peer_id
+            .parse()
+            .map_err(|e| anyhow!("Invalid peer ID in node identity: {}", e))?;
+        Ok(Some(peer_id))
+    }
+
+    /// Libp2p keypair used for transport authentication and message signing
+    pub fn keypair(&self) -> &Keypair {
+        &self.keypair
+    }
+
+    /// Stable peer ID derived from the keypair
+    pub fn peer_id(&self) -> PeerId {
+        self.keypair.public().to_peer_id()
+    }
+
+    /// Path of the identity key file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Unix timestamp at which the key was generated
+    pub fn created_at(&self) -> u64 {
+        self.created_at
+    }
+
+    /// Encrypt and write the keypair
+    fn save(&self, passphrase: &str) -> Result<()> {
+        if passphrase.is_empty() {
+            return Err(anyhow!("Node identity passphrase must not be empty"));
+        }
+        self.write_encrypted(passphrase)
+    }
+
+    fn write_encrypted(&self, passphrase: &str) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+
+        let encoded = self
+            .keypair
+            .to_protobuf_encoding()
+            .map_err(|e| anyhow!("Failed to encode node identity: {}", e))?;
+        let encrypted_key = cipher_for(passphrase, &salt)?
+            .encrypt(Nonce::from_slice(&nonce), encoded.as_ref())
+            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+
+        let stored = EncryptedIdentity {
+            version: IDENTITY_VERSION,
+            peer_id: self.peer_id().to_string(),
+            salt,
+            nonce,
+            encrypted_key,
+            created_at: self.created_at,
+        };
+
+        // Write to a temp file first so a crash never leaves a truncated key
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_vec_pretty(&stored)?)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&tmp, fs::Permissions::from_mode(0o600))?;
+        }
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+}
+
+fn passphrase_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(PASSPHRASE_FILE)
+}
+
+/// Generated passphrase stored in `data_dir`, if any
+fn read_passphrase(data_dir: &Path) -> Result<Option<String>> {
+    let path = passphrase_path(data_dir);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let passphrase =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let passphrase = passphrase.trim();
+    if passphrase.is_empty() {
+        return Err(anyhow!("Empty identity passphrase in {}", path.display()));
+    }
+    Ok(Some(passphrase.to_string()))
+}
+
+/// Store a generated passphrase, readable by the owner only
+fn write_passphrase(data_dir: &Path, passphrase: &str) -> Result<()> {
+    use std::io::Write;
+
+    fs::create_dir_all(data_dir)?;
+    let path = passphrase_path(data_dir);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(passphrase.as_bytes())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Derive the AES-256 key from the passphrase with Argon2
+fn cipher_for(passphrase: &str, salt: &[u8]) -> Result<Aes256Gcm> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive identity key: {}", e))?;
+    Aes256Gcm::new_from_slice(&key).map_err(|e| anyhow!("Failed to create cipher: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_identity_persists_across_loads() {
+        let dir = TempDir::new().unwrap();
+
+        let first = NodeIdentity::load_or_generate(dir.path(), "passphrase").unwrap();
+        let second = NodeIdentity::load_or_generate(dir.path(), "passphrase").unwrap();
+
+        assert_eq!(first.peer_id(), second.peer_id());
+        assert_eq!(
+            NodeIdentity::stored_peer_id(dir.path()).unwrap(),
+            Some(first.peer_id())
+        );
+
+        // Key material is not stored in the clear
+        let raw = fs::read(NodeIdentity::path_in(dir.path())).unwrap();
+        let secret = first.keypair().to_protobuf_encoding().unwrap();
+        assert!(!raw.windows(secret.len()).any(|w| w == secret.as_slice()));
+    }
+
+    #[test]
+    fn test_identity_generated_passphrase() {
+        let dir = TempDir::new().unwrap();
+        let first = NodeIdentity::open(dir.path(), None).unwrap();
+        let stored = NodeIdentity::passphrase(dir.path(), None).unwrap();
+        assert_eq!(stored.len(), 64);
+        assert!(NodeIdentity::load(dir.path(), "").is_err());
+        assert_eq!(
+            NodeIdentity::load(dir.path(), &stored).unwrap().peer_id(),
+            first.peer_id()
+        );
+        assert_eq!(
+            NodeIdentity::open(dir.path(), None).unwrap().peer_id(),
+            first.peer_id()
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(dir.path().join(PASSPHRASE_FILE))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        assert!(NodeIdentity::open(TempDir::new().unwrap().path(), Some("")).is_err());
+    }
+
+    #[test]
+    fn test_empty_passphrase_key_is_reencrypted() {
+        let dir = TempDir::new().unwrap();
+        let legacy = NodeIdentity {
+            keypair: Keypair::generate_ed25519(),
+            path: NodeIdentity::path_in(dir.path()),
+            created_at: system_time(),
+        };
+        legacy.write_encrypted("").unwrap();
+
+        let opened = NodeIdentity::open(dir.path(), None).unwrap();
+        assert_eq!(opened.peer_id(), legacy.peer_id());
+        assert!(NodeIdentity::load(dir.path(), "").is_err());
+        let stored = NodeIdentity::passphrase(dir.path(), None).unwrap();
+        assert!(NodeIdentity::load(dir.path(), &stored).is_ok());
+    }
+
+    #[test]
+    fn test_identity_wrong_passphrase() {
+        let dir = TempDir::new().unwrap();
+        NodeIdentity::generate(dir.path(), "correct").unwrap();
+
+        assert!(NodeIdentity::load(dir.path(), "wrong").is_err());
+    }
+
+    #[test]
+    fn test_identity_rotate() {
+        let dir = TempDir::new().unwrap();
+        let original = NodeIdentity::generate(dir.path(), "passphrase").unwrap();
+
+        assert!(NodeIdentity::rotate(dir.path(), "wrong").is_err());
+
+        let (retired, rotated) = NodeIdentity::rotate(dir.path(), "passphrase").unwrap();
+        assert_eq!(retired, original.peer_id());
+        assert_ne!(rotated.peer_id(), original.peer_id());
+
+        let reloaded = NodeIdentity::load(dir.path(), "passphrase").unwrap();
+        assert_eq!(reloaded.peer_id(), rotated.peer_id());
+        assert!(dir
+            .path()
+            .join(format!("{}.{}.old", IDENTITY_FILE, retired))
+            .exists());
+    }
+}
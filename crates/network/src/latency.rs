@@ -0,0 +1,161 @@
+//! Peer round-trip latency for peer selection
+//! زمن الاستجابة للأقران لاختيار الأقرب
+//!
+//! libp2p ping measures the round trip to every connected peer each
+//! [`PING_INTERVAL_SECS`]. Samples are folded into an exponentially weighted
+//! moving average, so one slow ping does not demote a nearby peer. The
+//! averages are kept in the peer cache across restarts, and nearby peers are
+//! preferred for block download and for relay outside the gossip mesh.
+
+use crate::peer_cache::CachedLatency;
+use libp2p::PeerId;
+use opensyria_core::clock::system_time;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Seconds between pings to each connected peer
+pub const PING_INTERVAL_SECS: u64 = 15;
+
+/// Weight of a new sample in the rolling average
+pub const LATENCY_SAMPLE_WEIGHT: f64 = 0.2;
+
+/// Averages not refreshed for this long are dropped on restore (7 days)
+pub const LATENCY_MAX_AGE_SECS: u64 = 7 * 24 * 3600;
+
+/// Rolling ping latency per peer
+/// متوسط زمن الاستجابة لكل نظير
+#[derive(Debug, Default)]
+pub struct PeerLatency {
+    peers: HashMap<PeerId, CachedLatency>,
+}
+
+impl PeerLatency {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fold a ping round trip into the peer's average
+    pub fn record(&mut self, peer_id: PeerId, rtt: Duration) {
+        let sample_ms = rtt.as_secs_f64() * 1000.0;
+        let now = system_time();
+        self.peers
+            .entry(peer_id)
+            .and_modify(|stats| {
+                stats.avg_ms += LATENCY_SAMPLE_WEIGHT * (sample_ms - stats.avg_ms);
+                stats.last_ms = sample_ms;
+                stats.samples = stats.samples.saturating_add(1);
+                stats.updated_at = now;
+            })
+            .or_insert(CachedLatency {
+                avg_ms: sample_ms,
+                last_ms: sample_ms,
+                samples: 1,
+                updated_at: now,
+            });
+    }
+
+    /// Rolling average round trip, if the peer was ever pinged
+    pub fn average(&self, peer_id: &PeerId) -> Option<Duration> {
+        self.peers
+            .get(peer_id)
+            .map(|stats| Duration::from_secs_f64(stats.avg_ms / 1000.0))
+    }
+
+    /// Compare two peers, nearer first; peers never pinged sort last
+    pub fn compare(&self, a: &PeerId, b: &PeerId) -> Ordering {
+        compare_latency(self.average(a), self.average(b))
+    }
+
+    /// Nearest of `peers`; the first one wins a tie
+    /// أقرب نظير من القائمة
+    pub fn nearest(&self, peers: impl IntoIterator<Item = PeerId>) -> Option<PeerId> {
+        peers
+            .into_iter()
+            .reduce(|best, peer| match self.compare(&peer, &best) {
+                Ordering::Less => peer,
+                _ => best,
+            })
+    }
+
+    /// Export the averages for the peer cache
+    pub fn snapshot(&self) -> Vec<(PeerId, CachedLatency)> {
+        self.peers
+            .iter()
+            .map(|(peer_id, stats)| (*peer_id, stats.clone()))
+            .collect()
+    }
+
+    /// Restore averages saved by [`snapshot`](Self::snapshot), skipping
+    /// those older than [`LATENCY_MAX_AGE_SECS`]
+    pub fn restore(&mut self, records: Vec<(PeerId, CachedLatency)>) {
+        let now = system_time();
+        self.peers.extend(
+            records
+                .into_iter()
+                .filter(|(_, stats)| now.saturating_sub(stats.updated_at) < LATENCY_MAX_AGE_SECS),
+        );
+    }
+}
+
+/// Order two optional latencies, nearer first and unknown last
+pub fn compare_latency(a: Option<Duration>, b: Option<Duration>) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_average_smooths_spikes() {
+        let mut latency = PeerLatency::new();
+        let peer = PeerId::random();
+        for _ in 0..10 {
+            latency.record(peer, Duration::from_millis(20));
+        }
+        latency.record(peer, Duration::from_millis(520));
+
+        // One slow ping moves the average by a fifth of the difference
+        let average = latency.average(&peer).unwrap();
+        assert!((average.as_secs_f64() * 1000.0 - 120.0).abs() < 1e-6);
+        assert_eq!(latency.snapshot()[0].1.samples, 11);
+    }
+
+    #[test]
+    fn test_nearest_prefers_measured_peers() {
+        let mut latency = PeerLatency::new();
+        let (near, far, unknown) = (PeerId::random(), PeerId::random(), PeerId::random());
+        latency.record(near, Duration::from_millis(15));
+        latency.record(far, Duration::from_millis(180));
+
+        assert_eq!(latency.nearest([unknown, far, near]), Some(near));
+        assert_eq!(latency.nearest([unknown, far]), Some(far));
+        assert_eq!(latency.nearest([unknown]), Some(unknown));
+        assert_eq!(latency.nearest([]), None);
+    }
+
+    #[test]
+    fn test_restore_drops_stale_averages() {
+        let (fresh, stale) = (PeerId::random(), PeerId::random());
+        let record = |updated_at| CachedLatency {
+            avg_ms: 40.0,
+            last_ms: 40.0,
+            samples: 3,
+            updated_at,
+        };
+
+        let mut latency = PeerLatency::new();
+        latency.restore(vec![
+            (fresh, record(system_time())),
+            (stale, record(system_time() - LATENCY_MAX_AGE_SECS - 1)),
+        ]);
+        assert_eq!(latency.average(&fresh).map(|d| d.as_millis()), Some(40));
+        assert_eq!(latency.average(&stale), None);
+    }
+}
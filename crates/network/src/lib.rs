@@ -1,16 +1,42 @@
+pub mod bandwidth;
+pub mod banlist;
 pub mod behaviour;
 pub mod bootstrap;
+pub mod directory;
+pub mod gossip;
+pub mod identity;
+pub mod latency;
 pub mod node;
 pub mod peer_cache;
 pub mod protocol;
 pub mod rate_limiter;
+pub mod replication;
 pub mod reputation;
+pub mod routing;
+pub mod telemetry;
+pub mod transport;
 
-pub use behaviour::{NetworkRequest, NetworkResponse, OpenSyriaBehaviour};
+pub use bandwidth::{BandwidthConfig, BandwidthLimiter, BandwidthStats};
+pub use banlist::{
+    BanEntry, BanList, BanListError, BanListPolicy, BanListSubscriptions, BanTarget, MergeReport,
+    SignedBanList,
+};
+pub use behaviour::{NetworkRequest, NetworkResponse, OpenSyriaBehaviour, PROTOCOL_VERSION};
 pub use bootstrap::{get_bootstrap_peers, has_bootstrap_peers, NetworkType, MAINNET_BOOTSTRAP_NODES, TESTNET_BOOTSTRAP_NODES};
-pub use node::{NetworkEvent, NetworkNode, NodeConfig};
+pub use directory::WalletPeerRecord;
+pub use gossip::{GossipConfig, GossipStats};
+pub use identity::NodeIdentity;
+pub use latency::PeerLatency;
+pub use node::{NetworkCommand, NetworkEvent, NetworkNode, NodeConfig};
 pub use peer_cache::PeerCache;
 pub use protocol::{NetworkMessage, PeerInfo, ProtocolConfig};
 pub use rate_limiter::{MessageType, RateLimiter};
+pub use replication::{ReplicationFollower, ReplicationReport, ReplicationServer};
 pub use reputation::PeerReputation;
+pub use routing::{KademliaStats, KADEMLIA_PROTOCOL};
+pub use telemetry::{
+    NetworkLatency, PropagationReport, TelemetryAggregator, TelemetryConfig, TelemetryReporter,
+};
+pub use transport::TransportConfig;
+pub use libp2p::{Multiaddr, PeerId};
 
@@ -1,27 +1,109 @@
 use crate::{
-    behaviour::{NetworkRequest, NetworkResponse, OpenSyriaBehaviour},
-    protocol::NetworkMessage,
+    bandwidth::{
+        BandwidthConfig, BandwidthLimiter, BandwidthStats, Direction, BANDWIDTH_STATUS_FILE,
+    },
+    banlist::{self, BanListSubscriptions, SignedBanList, DEFAULT_BAN_LIST_REFRESH_SECS},
+    behaviour::{NetworkRequest, NetworkResponse, OpenSyriaBehaviour, PROTOCOL_VERSION},
+    directory::{WalletPeerRecord, WALLET_RECORD_TTL_SECS},
+    gossip::{
+        self, GossipConfig, GossipStats, GossipTuner, SeenCache, FANOUT_ADJUST_INTERVAL_SECS,
+        RELAY_SEEN_CAPACITY,
+    },
+    identity::NodeIdentity,
+    latency::PeerLatency,
+    peer_cache::{PeerCache, PEER_CACHE_FILE},
+    protocol::{NetworkMessage, MAX_BLOCKS_PER_REQUEST, MAX_HEADERS_PER_REQUEST},
     rate_limiter::{MessageType, RateLimiter},
     reputation::PeerReputation,
+    routing::{self, KademliaStats, KADEMLIA_PROTOCOL, KADEMLIA_REFRESH_INTERVAL_SECS},
+    telemetry::{ItemKind, TelemetryConfig, TelemetryReporter, DEFAULT_TELEMETRY_INTERVAL_SECS},
+    transport::{self, TransportConfig},
 };
 use anyhow::Result;
 use futures::StreamExt;
 use libp2p::{
-    core::upgrade,
     gossipsub::{self},
-    identity, noise, tcp, yamux, Multiaddr, PeerId, Swarm, Transport,
+    kad::{
+        self,
+        store::{MemoryStore, RecordStore},
+    },
+    Multiaddr, PeerId, Swarm,
+};
+use opensyria_consensus::{CheckpointAuthority, CheckpointRegistry, SignedCheckpoint};
+use opensyria_core::{
+    block::chain_work,
+    clock::system_time,
+    crypto::{KeyPair, PublicKey},
+    events::PeerEvent,
+    AnchorPublisher, Anomaly, Block, ChainAnchor, DepositOperation, EventBus, NodeEvent, SafeMode,
+    Transaction,
+};
+use opensyria_mempool::{Mempool, MempoolConfig, PolicyEngine, VerifiedTransaction};
+use opensyria_storage::{
+    BlockchainStorage, SnapshotAssembler, SnapshotManifest, SnapshotStore, StateOptions,
+    StateStorage, StorageError, StorageLimits, StorageResources, Watchlist,
 };
-use opensyria_core::{Block, Transaction};
-use opensyria_mempool::{Mempool, MempoolConfig};
-use opensyria_storage::{BlockchainStorage, StateStorage};
 use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tokio::sync::{mpsc, RwLock};
-use tracing::{debug, info, warn};
+use tokio::sync::{mpsc, oneshot, RwLock, RwLockReadGuard};
+use tracing::{debug, error, info, warn};
+
+/// How often peer reputation is written to the peer cache
+const REPUTATION_PERSIST_INTERVAL_SECS: u64 = 60;
+
+/// How often bandwidth counters are written for `network status`
+const BANDWIDTH_STATUS_INTERVAL_SECS: u64 = 5;
+
+/// How often the chain is audited for safe mode anomalies
+const SAFE_MODE_AUDIT_INTERVAL_SECS: u64 = 60;
+
+/// How often we check whether a new state snapshot is due
+const SNAPSHOT_CHECK_INTERVAL_SECS: u64 = 300;
+
+/// Blocks between state snapshots served to peers (default)
+pub const DEFAULT_SNAPSHOT_INTERVAL: u64 = 1_000;
+
+/// Number of state snapshots kept on disk
+const SNAPSHOTS_KEPT: usize = 2;
+
+/// Snapshot chunk requests outstanding at once during state sync
+const SNAPSHOT_CHUNKS_IN_FLIGHT: usize = 4;
+
+/// Wallet-to-peer mappings kept from DHT lookups
+const MAX_CACHED_WALLET_PEERS: usize = 1_000;
+
+/// How long a wallet-to-peer mapping is used before the DHT is asked again,
+/// so a wallet that moves to another node is followed
+const WALLET_PEER_CACHE_SECS: u64 = 10 * 60;
+
+/// Wallet record lookups in flight at once for direct delivery
+const MAX_WALLET_LOOKUPS: usize = 64;
+
+/// Gossiped transaction signature checks in flight at once
+const MAX_PENDING_VERIFICATIONS: usize = 256;
+
+/// Sender, hash and signature check of a gossiped transaction
+type VerifiedGossip = (
+    PeerId,
+    [u8; 32],
+    opensyria_mempool::Result<VerifiedTransaction>,
+);
+
+/// A state snapshot being fetched from one peer
+struct StateSyncSession {
+    peer: PeerId,
+    height: u64,
+    /// Set once the manifest has been received and checked
+    assembler: Option<SnapshotAssembler>,
+    /// Block at the snapshot height, matched against the manifest
+    base_block: Option<Block>,
+    /// Chunk indices requested but not yet received
+    in_flight: HashSet<u32>,
+}
 
 /// P2P Network Node
 pub struct NetworkNode {
@@ -34,9 +116,8 @@ pub struct NetworkNode {
     /// Blockchain storage
     blockchain: Arc<RwLock<BlockchainStorage>>,
 
-    /// State storage
-    #[allow(dead_code)]
-    state: Arc<RwLock<StateStorage>>,
+    /// State storage (None on relay-only nodes)
+    state: Option<Arc<RwLock<StateStorage>>>,
 
     /// Transaction mempool
     mempool: Arc<RwLock<Mempool>>,
@@ -50,18 +131,95 @@ pub struct NetworkNode {
     /// Outbound peer connections
     outbound_peers: Arc<RwLock<HashSet<PeerId>>>,
 
+    /// Built-in and network-distributed checkpoints
+    checkpoints: Arc<RwLock<CheckpointRegistry>>,
+
     /// Pending block requests
     pending_blocks: Arc<RwLock<HashMap<PeerId, u64>>>,
 
     /// Event sender
     event_tx: mpsc::UnboundedSender<NetworkEvent>,
 
+    /// Node-wide event bus shared with other subsystems
+    event_bus: EventBus,
+
     /// Peer reputation system
     reputation: Arc<RwLock<PeerReputation>>,
 
+    /// On-disk peer cache holding reputation across restarts
+    peer_cache: Arc<RwLock<PeerCache>>,
+
+    /// Rolling ping latency per peer, for choosing nearby peers
+    latency: Arc<RwLock<PeerLatency>>,
+
+    /// Chain heights connected peers last reported
+    peer_heights: HashMap<PeerId, u64>,
+
     /// Message rate limiter
     rate_limiter: Arc<RwLock<RateLimiter>>,
 
+    /// Upload/download bandwidth budgets
+    bandwidth: Arc<RwLock<BandwidthLimiter>>,
+
+    /// Safe mode marker; while set, blocks and transactions are not relayed
+    safe_mode: SafeMode,
+
+    /// Whether we are subscribed to block and transaction gossip
+    relaying: bool,
+
+    /// State snapshots served to peers
+    snapshots: SnapshotStore,
+
+    /// State snapshot currently being fetched, if any
+    state_sync: Option<StateSyncSession>,
+
+    /// Gossiped transactions whose signatures were checked off the event
+    /// loop, with their hash; at most [`MAX_PENDING_VERIFICATIONS`] queued
+    verified_tx: mpsc::Sender<VerifiedGossip>,
+    verified_rx: mpsc::Receiver<VerifiedGossip>,
+
+    /// Hashes of gossiped transactions whose signature check is in flight
+    verifying: HashSet<[u8; 32]>,
+
+    /// Subscribed ban lists fetched off the event loop, with their source
+    ban_list_tx: mpsc::UnboundedSender<(String, SignedBanList)>,
+    ban_list_rx: mpsc::UnboundedReceiver<(String, SignedBanList)>,
+
+    /// Supply invariant scan running on a blocking thread, if any
+    supply_check: Option<tokio::task::JoinHandle<Result<Option<Anomaly>, StorageError>>>,
+
+    /// Wallets announced in the DHT; only payments to these are accepted by direct delivery
+    announced_wallets: HashSet<PublicKey>,
+
+    /// Verified wallet-to-peer mappings found in the DHT, with the time
+    /// each stops being used
+    wallet_peers: HashMap<PublicKey, (PeerId, Instant)>,
+
+    /// Wallet record lookups with the payments waiting on them
+    wallet_lookups: HashMap<kad::QueryId, (PublicKey, Vec<Transaction>)>,
+
+    /// Whether this node's full node provider record has been published
+    providing: bool,
+
+    /// Routing table occupancy, refreshed for metrics
+    kademlia_stats: Arc<RwLock<KademliaStats>>,
+
+    /// Propagation delay tracking and extra fanout
+    gossip: GossipTuner,
+
+    /// Blocks and transactions already relayed (relay-only nodes)
+    relay_seen: SeenCache,
+
+    /// Propagation figures, refreshed for metrics
+    gossip_stats: Arc<RwLock<GossipStats>>,
+
+    /// First-seen times of gossiped hashes, reported to the telemetry collector
+    telemetry: Option<TelemetryReporter>,
+
+    /// Requests from other tasks, served by the event loop
+    command_tx: mpsc::UnboundedSender<NetworkCommand>,
+    command_rx: mpsc::UnboundedReceiver<NetworkCommand>,
+
     /// Node configuration for connection limits
     config: NodeConfig,
 }
@@ -88,6 +246,53 @@ pub enum NetworkEvent {
     SyncProgress { current: u64, target: u64 },
 }
 
+/// Requests served by a running node's event loop
+/// طلبات تخدمها حلقة أحداث العقدة العاملة
+///
+/// `run` holds the node mutably, so other tasks reach it through the sender
+/// from [`NetworkNode::command_sender`].
+#[derive(Debug)]
+pub enum NetworkCommand {
+    /// Gossip a transaction already accepted into the shared mempool
+    BroadcastTransaction {
+        tx: Transaction,
+        reply: oneshot::Sender<Result<()>>,
+    },
+
+    /// Replace the pending transaction with the same sender and nonce by a
+    /// higher-fee one (see [`Mempool::replace_transaction`]) and gossip it
+    ReplaceTransaction {
+        tx: Transaction,
+        reply: oneshot::Sender<Result<()>>,
+    },
+
+    /// Check a transaction against the mempool without adding it; replies
+    /// with the sender's pending transactions it would follow
+    CheckTransaction {
+        tx: Transaction,
+        reply: oneshot::Sender<opensyria_mempool::Result<Vec<Transaction>>>,
+    },
+
+    /// Dial a peer
+    Dial {
+        addr: Multiaddr,
+        reply: oneshot::Sender<Result<()>>,
+    },
+
+    /// Ban a peer for a while and disconnect it
+    BanPeer {
+        peer_id: PeerId,
+        duration: Duration,
+        reply: oneshot::Sender<Result<()>>,
+    },
+
+    /// Number of connected peers
+    PeerCount { reply: oneshot::Sender<usize> },
+
+    /// Save the peer cache and return from `run`
+    Shutdown,
+}
+
 /// Network node configuration
 #[derive(Debug, Clone)]
 pub struct NodeConfig {
@@ -103,6 +308,9 @@ pub struct NodeConfig {
     /// Enable mDNS discovery
     pub enable_mdns: bool,
 
+    /// Enable the Kademlia DHT for peer routing (disable for private networks)
+    pub enable_kademlia: bool,
+
     /// Maximum inbound peer connections (default: 50)
     pub max_inbound_peers: usize,
 
@@ -111,6 +319,68 @@ pub struct NodeConfig {
 
     /// Maximum peers from same ASN for diversity (default: 5)
     pub max_peers_per_asn: usize,
+
+    /// Network whose built-in checkpoints apply
+    pub network: crate::bootstrap::NetworkType,
+
+    /// Keys allowed to issue signed checkpoints (None ignores checkpoint gossip)
+    pub checkpoint_authority: Option<CheckpointAuthority>,
+
+    /// Passphrase protecting the identity key in `data_dir` (None uses one
+    /// generated and stored next to the key, see [`NodeIdentity::open`])
+    pub identity_passphrase: Option<String>,
+
+    /// Upload/download bandwidth budgets (default: unlimited)
+    pub bandwidth: BandwidthConfig,
+
+    /// Transports to run (default: TCP and QUIC)
+    pub transports: TransportConfig,
+
+    /// Gossipsub mesh sizes, heartbeat, fanout TTL and adaptive fanout
+    pub gossip: GossipConfig,
+
+    /// Start an empty node from a peer's state snapshot instead of replaying from genesis
+    pub state_sync: bool,
+
+    /// Anchors from a trusted source that a snapshot must match; state sync
+    /// only restores snapshots at these heights, with the anchor's block
+    /// hash and state root
+    pub trusted_anchors: Vec<ChainAnchor>,
+
+    /// Blocks between state snapshots served to peers (0 disables serving)
+    pub snapshot_interval: u64,
+
+    /// Send propagation latency reports to a collector (None keeps them local)
+    pub telemetry: Option<TelemetryConfig>,
+
+    /// Blocks between signed chain-state anchors written to `data_dir`
+    /// (0 disables anchoring)
+    pub anchor_interval: u64,
+
+    /// Relay blocks and transactions without keeping account state: blocks
+    /// are checked statelessly (PoW, header chain, merkle root, signatures)
+    /// and transactions only for their signature
+    pub relay_only: bool,
+
+    /// Store only block headers and the transactions of these addresses
+    /// (None stores full blocks); needs `relay_only`, and peers cannot sync
+    /// blocks from this node
+    pub thin: Option<Watchlist>,
+
+    /// Block cache, write buffer and open-file budgets shared by both databases
+    pub storage_limits: StorageLimits,
+
+    /// Fast path and column family tuning for the state database
+    pub state_options: StateOptions,
+
+    /// Maximum total size of pending transactions in bytes
+    pub mempool_max_bytes: usize,
+
+    /// Relay rules replacing the mempool's fee and dust checks (None keeps them)
+    pub mempool_policy: Option<Arc<dyn PolicyEngine>>,
+
+    /// Fetch and merge ban lists signed by trusted operators (None disables)
+    pub ban_lists: Option<BanListSubscriptions>,
 }
 
 impl Default for NodeConfig {
@@ -127,9 +397,28 @@ impl NodeConfig {
             bootstrap_peers: crate::bootstrap::get_bootstrap_peers(network),
             data_dir: PathBuf::from("~/.opensyria/network"),
             enable_mdns: true,
+            enable_kademlia: true,
             max_inbound_peers: 50,
             max_outbound_peers: 10,
             max_peers_per_asn: 5,
+            network,
+            checkpoint_authority: None,
+            identity_passphrase: None,
+            bandwidth: BandwidthConfig::default(),
+            transports: TransportConfig::default(),
+            gossip: GossipConfig::default(),
+            state_sync: false,
+            trusted_anchors: Vec::new(),
+            snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+            telemetry: None,
+            anchor_interval: 0,
+            relay_only: false,
+            thin: None,
+            storage_limits: StorageLimits::default(),
+            state_options: StateOptions::default(),
+            mempool_max_bytes: MempoolConfig::default().max_total_bytes,
+            mempool_policy: None,
+            ban_lists: None,
         }
     }
 
@@ -147,21 +436,20 @@ impl NodeConfig {
 impl NetworkNode {
     /// Create a new network node
     pub async fn new(config: NodeConfig) -> Result<(Self, mpsc::UnboundedReceiver<NetworkEvent>)> {
-        // Generate or load keypair
-        let local_key = identity::Keypair::generate_ed25519();
-        let local_peer_id = local_key.public().to_peer_id();
+        // Load the persistent identity so the peer ID is stable across restarts
+        let node_identity =
+            NodeIdentity::open(&config.data_dir, config.identity_passphrase.as_deref())?;
+        let local_key = node_identity.keypair().clone();
+        let local_peer_id = node_identity.peer_id();
 
         info!("Local peer ID: {}", local_peer_id);
 
-        // Create transport
-        let transport = tcp::tokio::Transport::default()
-            .upgrade(upgrade::Version::V1Lazy)
-            .authenticate(noise::Config::new(&local_key)?)
-            .multiplex(yamux::Config::default())
-            .boxed();
+        // Create transport (TCP and/or QUIC)
+        let transport = transport::build_transport(&local_key, config.transports)?;
 
         // Create behaviour
-        let behaviour = OpenSyriaBehaviour::new(&local_key).map_err(|e| anyhow::anyhow!(e))?;
+        let behaviour = OpenSyriaBehaviour::new(&local_key, config.enable_kademlia, &config.gossip)
+            .map_err(|e| anyhow::anyhow!(e))?;
 
         // Create swarm
         let mut swarm_config = libp2p::swarm::Config::with_executor(Box::new(|fut| {
@@ -169,22 +457,110 @@ impl NetworkNode {
         }));
         swarm_config = swarm_config.with_idle_connection_timeout(Duration::from_secs(60));
 
-        let swarm = Swarm::new(transport, behaviour, local_peer_id, swarm_config);
+        let mut swarm = Swarm::new(transport, behaviour, local_peer_id, swarm_config);
 
-        // Open storage
-        let blockchain = Arc::new(RwLock::new(BlockchainStorage::open(
-            config.data_dir.join("blockchain"),
-        )?));
-        let state = Arc::new(RwLock::new(StateStorage::open(
-            config.data_dir.join("state"),
-        )?));
+        // Seed the DHT with bootstrap peers whose addresses name their peer ID
+        if let Some(kademlia) = swarm.behaviour_mut().kademlia.as_mut() {
+            for addr in &config.bootstrap_peers {
+                if let Some(peer_id) = routing::peer_id_from_addr(addr) {
+                    kademlia.add_address(&peer_id, addr.clone());
+                }
+            }
+        }
 
-        // Create mempool
-        let mempool_config = MempoolConfig::default();
-        let mempool = Arc::new(RwLock::new(Mempool::new(mempool_config, state.clone())));
+        // Open storage; block connects/disconnects are published on the event bus
+        let event_bus = EventBus::default();
+        let resources = StorageResources::new(config.storage_limits);
+        let blockchain_dir = config.data_dir.join("blockchain");
+        let mut blockchain_storage = match &config.thin {
+            Some(watchlist) => {
+                if !config.relay_only || config.state_sync {
+                    anyhow::bail!(
+                        "A thin node keeps no account state; run it relay-only without state sync"
+                    );
+                }
+                BlockchainStorage::open_thin(blockchain_dir, &resources, watchlist)?
+            }
+            None => BlockchainStorage::open_with_resources(blockchain_dir, &resources)?,
+        };
+        if config.thin.is_none() && blockchain_storage.is_thin() {
+            anyhow::bail!(
+                "{} holds a thin chain; start the node in thin mode or resync into an empty directory",
+                config.data_dir.display()
+            );
+        }
+        blockchain_storage.set_event_bus(event_bus.clone());
+
+        // Built-in checkpoints plus signed ones persisted from earlier sessions
+        let mut checkpoints =
+            CheckpointRegistry::new(config.network == crate::bootstrap::NetworkType::Testnet);
+        if let Some(authority) = config.checkpoint_authority.clone() {
+            checkpoints = checkpoints.with_authority(authority);
+            for checkpoint in blockchain_storage.get_signed_checkpoints()? {
+                let height = checkpoint.height;
+                if let Err(e) = checkpoints.add_signed(checkpoint) {
+                    warn!("Ignoring stored checkpoint at height {}: {}", height, e);
+                }
+            }
+        }
+
+        let chain_params = blockchain_storage.chain_params().clone();
+        let blockchain = Arc::new(RwLock::new(blockchain_storage));
+        // Relay-only nodes keep no account state, so the database is never opened
+        let state = if config.relay_only {
+            None
+        } else {
+            let mut state_storage = StateStorage::open_with_options(
+                config.data_dir.join("state"),
+                &resources,
+                &config.state_options,
+            )?;
+            if config.anchor_interval > 0 {
+                let anchors = AnchorPublisher::open(&config.data_dir, config.anchor_interval)?;
+                info!(
+                    "Publishing chain-state anchors every {} blocks to {}",
+                    config.anchor_interval,
+                    anchors.dir().display()
+                );
+                state_storage.set_anchor_publisher(Arc::new(anchors));
+            }
+            Some(Arc::new(RwLock::new(state_storage)))
+        };
+
+        // Create mempool; gossip signatures are verified in batches, and
+        // transactions signed for another network are dropped
+        let mempool_config = MempoolConfig {
+            signature_batching: Some(Default::default()),
+            chain_params: Some(chain_params),
+            max_total_bytes: config.mempool_max_bytes,
+            policy: config.mempool_policy.clone(),
+            ..Default::default()
+        };
+        let mempool = match &state {
+            Some(state) => Mempool::new(mempool_config, state.clone()),
+            None => Mempool::stateless(mempool_config),
+        };
+        let mempool = Arc::new(RwLock::new(mempool.with_event_bus(event_bus.clone())));
+
+        // Evict transactions as their blocks connect
+        Self::spawn_mempool_sync(event_bus.subscribe(), Arc::downgrade(&mempool));
+
+        // Scores and bans from the previous session, decayed for the time we were down
+        let mut peer_cache = PeerCache::new(config.data_dir.join(PEER_CACHE_FILE));
+        if let Err(e) = peer_cache.load() {
+            warn!("Ignoring unreadable peer cache: {}", e);
+        }
+        let mut reputation = PeerReputation::for_local_peer(local_peer_id);
+        reputation.restore(peer_cache.reputation());
+        reputation.restore_ip_bans(peer_cache.ip_bans());
+        let mut latency = PeerLatency::new();
+        latency.restore(peer_cache.latency());
 
         // Create event channel
         let (event_tx, event_rx) = mpsc::unbounded_channel();
+        let (verified_tx, verified_rx) = mpsc::channel(MAX_PENDING_VERIFICATIONS);
+        let (ban_list_tx, ban_list_rx) = mpsc::unbounded_channel();
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
 
         let node = Self {
             swarm,
@@ -195,16 +571,86 @@ impl NetworkNode {
             peers: Arc::new(RwLock::new(HashSet::new())),
             inbound_peers: Arc::new(RwLock::new(HashSet::new())),
             outbound_peers: Arc::new(RwLock::new(HashSet::new())),
+            checkpoints: Arc::new(RwLock::new(checkpoints)),
             pending_blocks: Arc::new(RwLock::new(HashMap::new())),
             event_tx,
-            reputation: Arc::new(RwLock::new(PeerReputation::new())),
+            event_bus,
+            reputation: Arc::new(RwLock::new(reputation)),
+            peer_cache: Arc::new(RwLock::new(peer_cache)),
+            latency: Arc::new(RwLock::new(latency)),
+            peer_heights: HashMap::new(),
             rate_limiter: Arc::new(RwLock::new(RateLimiter::new())),
+            bandwidth: Arc::new(RwLock::new(BandwidthLimiter::new(config.bandwidth))),
+            safe_mode: SafeMode::new(&config.data_dir),
+            relaying: true,
+            snapshots: SnapshotStore::new(config.data_dir.join("snapshots")),
+            state_sync: None,
+            verified_tx,
+            verified_rx,
+            verifying: HashSet::new(),
+            ban_list_tx,
+            ban_list_rx,
+            supply_check: None,
+            announced_wallets: HashSet::new(),
+            wallet_peers: HashMap::new(),
+            wallet_lookups: HashMap::new(),
+            providing: false,
+            kademlia_stats: Arc::new(RwLock::new(KademliaStats::default())),
+            gossip: GossipTuner::new(config.gossip),
+            relay_seen: SeenCache::new(RELAY_SEEN_CAPACITY),
+            gossip_stats: Arc::new(RwLock::new(GossipStats::default())),
+            telemetry: config
+                .telemetry
+                .clone()
+                .map(|telemetry| TelemetryReporter::new(telemetry, local_peer_id.to_string())),
+            command_tx,
+            command_rx,
             config,
         };
 
         Ok((node, event_rx))
     }
 
+    /// Evict confirmed transactions; disconnected blocks are handed to the
+    /// mempool by [`Self::reorganize`] once the state is rolled back
+    fn spawn_mempool_sync(
+        mut events: tokio::sync::broadcast::Receiver<NodeEvent>,
+        mempool: std::sync::Weak<RwLock<Mempool>>,
+    ) {
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(
+                        event @ (NodeEvent::BlockConnected { .. }
+                        | NodeEvent::BlockDisconnected { .. }),
+                    ) => {
+                        // The mempool holds a bus sender, so stop once the node drops it
+                        let Some(mempool) = mempool.upgrade() else {
+                            break;
+                        };
+                        mempool.write().await.handle_event(&event).await;
+                    }
+                    Ok(_) => {}
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Mempool sync lagged behind event bus by {} events", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// Node-wide event bus; clone it to publish from other subsystems
+    /// ناقل أحداث العقدة
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Subscribe to node events (blocks, mempool, peers)
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<NodeEvent> {
+        self.event_bus.subscribe()
+    }
+
     /// Check if we can accept a new inbound connection
     async fn can_accept_inbound(&self) -> bool {
         let inbound = self.inbound_peers.read().await;
@@ -258,13 +704,27 @@ impl NetworkNode {
         }
     }
 
-    /// Start listening for connections
+    /// Start listening for connections on the QUIC and TCP forms of `addr`,
+    /// as enabled
     pub async fn listen(&mut self, addr: Multiaddr) -> Result<()> {
-        self.swarm.listen_on(addr)?;
+        for addr in transport::listen_addrs(&addr, self.config.transports) {
+            self.swarm.listen_on(addr)?;
+        }
         info!("Listening on all configured addresses");
         Ok(())
     }
 
+    /// Ban a peer for `duration` and drop its connections
+    /// حظر نظير وقطع اتصالاته
+    pub async fn ban_peer(&mut self, peer_id: PeerId, duration: Duration) -> Result<()> {
+        if !self.reputation.write().await.ban(peer_id, duration) {
+            return Err(anyhow::anyhow!("Cannot ban the local peer"));
+        }
+        let _ = self.swarm.disconnect_peer_id(peer_id);
+        warn!("Banned peer {} for {}s", peer_id, duration.as_secs());
+        Ok(())
+    }
+
     /// Dial a peer
     pub async fn dial(&mut self, addr: Multiaddr) -> Result<()> {
         // Check outbound connection limit before dialing
@@ -282,6 +742,10 @@ impl NetworkNode {
 
     /// Broadcast a new block
     pub async fn broadcast_block(&mut self, block: &Block) -> Result<()> {
+        if self.safe_mode.is_active() {
+            anyhow::bail!("Node is in safe mode; not broadcasting blocks");
+        }
+
         // Validate block before broadcasting to prevent DoS attacks
         if let Err(e) = self.validate_block_before_broadcast(block).await {
             warn!("Block failed validation before broadcast: {}", e);
@@ -291,12 +755,14 @@ impl NetworkNode {
         let msg = NetworkMessage::NewBlock {
             block: block.clone(),
         };
-        let data = msg.to_bytes()?;
+        let data = gossip::stamp(msg.to_bytes()?);
+        let size = data.len();
 
         self.swarm
             .behaviour_mut()
             .gossipsub
             .publish(OpenSyriaBehaviour::blocks_topic(), data)?;
+        self.record_broadcast(size).await;
 
         debug!("Broadcast new block");
         Ok(())
@@ -316,27 +782,50 @@ impl NetworkNode {
             return Err(anyhow::anyhow!("Invalid merkle root"));
         }
 
-        // Verify all transaction signatures
+        // Verify all transaction signatures; deposit settlements carry none
+        // and are checked against their escrow by state
         for tx in &block.transactions {
-            if !tx.is_coinbase() {
+            if !tx.is_coinbase() && !DepositOperation::is_settlement(tx) {
                 if let Err(e) = tx.verify() {
                     return Err(anyhow::anyhow!("Invalid transaction signature: {}", e));
                 }
             }
         }
 
-        // Verify coinbase is first and only
-        let mut coinbase_count = 0;
-        for (i, tx) in block.transactions.iter().enumerate() {
-            if tx.is_coinbase() {
-                if i != 0 {
-                    return Err(anyhow::anyhow!("Coinbase must be first transaction"));
-                }
-                coinbase_count += 1;
-            }
+        // Verify coinbase outputs come first, one per payout address
+        let coinbase_count = block
+            .transactions
+            .iter()
+            .take_while(|tx| tx.is_coinbase())
+            .count();
+        if block.transactions[coinbase_count..]
+            .iter()
+            .any(|tx| tx.is_coinbase())
+        {
+            return Err(anyhow::anyhow!("Coinbase must be first transaction"));
         }
-        if coinbase_count > 1 {
-            return Err(anyhow::anyhow!("Multiple coinbase transactions"));
+
+        // Split and extra-data limits apply from their activation heights
+        let blockchain = self.blockchain.read().await;
+        let height = match blockchain.get_block_height_by_hash(&block.header.previous_hash)? {
+            Some(parent) => parent + 1,
+            None => blockchain.get_chain_height()? + 1,
+        };
+        let params = blockchain.chain_params();
+        let max_outputs = if params.coinbase_split_active(height) {
+            opensyria_core::MAX_COINBASE_OUTPUTS
+        } else {
+            1
+        };
+        if coinbase_count > max_outputs {
+            return Err(anyhow::anyhow!("Too many coinbase outputs"));
+        }
+        if params.coinbase_extra_data_active(height)
+            && block.transactions[..coinbase_count]
+                .iter()
+                .any(|tx| tx.coinbase_extra_data().len() > opensyria_core::MAX_COINBASE_EXTRA_DATA)
+        {
+            return Err(anyhow::anyhow!("Coinbase extra data too long"));
         }
 
         Ok(())
@@ -344,20 +833,610 @@ impl NetworkNode {
 
     /// Broadcast a new transaction
     pub async fn broadcast_transaction(&mut self, tx: &Transaction) -> Result<()> {
+        if self.safe_mode.is_active() {
+            anyhow::bail!("Node is in safe mode; not relaying transactions");
+        }
+
         let msg = NetworkMessage::NewTransaction {
             transaction: tx.clone(),
         };
-        let data = msg.to_bytes()?;
+        let data = gossip::stamp(msg.to_bytes()?);
+        let size = data.len();
 
         self.swarm
             .behaviour_mut()
             .gossipsub
             .publish(OpenSyriaBehaviour::transactions_topic(), data)?;
+        self.record_broadcast(size).await;
 
         debug!("Broadcast transaction");
         Ok(())
     }
 
+    /// Broadcast a signed checkpoint and record it locally
+    /// بث نقطة فحص موقعة وتسجيلها محلياً
+    pub async fn broadcast_checkpoint(&mut self, checkpoint: &SignedCheckpoint) -> Result<()> {
+        self.accept_checkpoint(checkpoint.clone()).await?;
+
+        let msg = NetworkMessage::Checkpoint {
+            checkpoint: checkpoint.clone(),
+        };
+        let data = gossip::stamp(msg.to_bytes()?);
+        let size = data.len();
+
+        self.swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(OpenSyriaBehaviour::checkpoints_topic(), data)?;
+        self.record_broadcast(size).await;
+
+        debug!("Broadcast checkpoint at height {}", checkpoint.height);
+        Ok(())
+    }
+
+    /// Verify a signed checkpoint and persist it if new
+    async fn accept_checkpoint(&self, checkpoint: SignedCheckpoint) -> Result<bool> {
+        let added = self
+            .checkpoints
+            .write()
+            .await
+            .add_signed(checkpoint.clone())
+            .map_err(|e| anyhow::anyhow!(e))?;
+        if added {
+            self.blockchain
+                .read()
+                .await
+                .put_signed_checkpoint(&checkpoint)?;
+            info!("Accepted signed checkpoint at height {}", checkpoint.height);
+
+            // A checkpoint for a height we already stored may contradict our chain
+            let conflict = self
+                .blockchain
+                .read()
+                .await
+                .find_checkpoint_conflict(&*self.checkpoints.read().await)?;
+            if let Some(anomaly) = conflict {
+                self.enter_safe_mode(anomaly).await?;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Safe mode marker for this node
+    pub fn safe_mode(&self) -> &SafeMode {
+        &self.safe_mode
+    }
+
+    /// Check the chain against checkpoints and the supply invariant
+    /// تدقيق السلسلة مقابل نقاط الفحص وثبات العرض الكلي
+    ///
+    /// Enters safe mode on the first anomaly found and returns it. The supply
+    /// invariant reads every balance, so it is scanned on a blocking thread:
+    /// each call collects the previous scan's result and starts the next.
+    pub async fn audit_chain(&mut self) -> Result<Option<Anomaly>> {
+        let mut anomaly = self
+            .blockchain
+            .read()
+            .await
+            .find_checkpoint_conflict(&*self.checkpoints.read().await)?;
+
+        if matches!(&self.supply_check, Some(scan) if scan.is_finished()) {
+            if let Some(scan) = self.supply_check.take() {
+                let found = scan.await??;
+                anomaly = anomaly.or(found);
+            }
+        }
+        if self.supply_check.is_none() {
+            let check = self.read_state().await.map(|state| state.supply_check());
+            self.supply_check = check.map(|check| tokio::task::spawn_blocking(move || check.run()));
+        }
+
+        if let Some(anomaly) = &anomaly {
+            self.enter_safe_mode(anomaly.clone()).await?;
+        }
+        Ok(anomaly)
+    }
+
+    /// Stop mining and relaying until an operator runs `node-cli resume`
+    /// الدخول في الوضع الآمن حتى يراجع المشغل الحالة الشاذة
+    pub async fn enter_safe_mode(&self, anomaly: Anomaly) -> Result<()> {
+        let height = self.blockchain.read().await.get_chain_height()?;
+        if self.safe_mode.enter(anomaly.clone(), height)? {
+            error!(
+                "Entering safe mode at height {}: {}. Mining and relaying stopped; run `resume` after review",
+                height, anomaly
+            );
+            self.event_bus.publish(NodeEvent::SafeModeEntered(anomaly));
+        }
+        Ok(())
+    }
+
+    /// Follow the safe mode marker: leave block and transaction gossip while
+    /// it is set, rejoin once it is cleared
+    fn sync_relaying(&mut self) {
+        let relay = !self.safe_mode.is_active();
+        if relay == self.relaying {
+            return;
+        }
+
+        let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+        for topic in [
+            OpenSyriaBehaviour::blocks_topic(),
+            OpenSyriaBehaviour::transactions_topic(),
+        ] {
+            let result = if relay {
+                gossipsub
+                    .subscribe(&topic)
+                    .map_err(|e| anyhow::anyhow!("{:?}", e))
+            } else {
+                gossipsub
+                    .unsubscribe(&topic)
+                    .map_err(|e| anyhow::anyhow!("{:?}", e))
+            };
+            if let Err(e) = result {
+                warn!("Failed to update subscription to {}: {}", topic, e);
+            }
+        }
+        self.relaying = relay;
+        if relay {
+            info!("Safe mode cleared, relaying blocks and transactions again");
+        }
+    }
+
+    /// Append a block, replaying it on the state while the state follows the chain
+    ///
+    /// State built from genesis or restored from a snapshot is kept in step
+    /// block by block, and a block breaking a state rule is rejected before
+    /// it is stored; state that was never in step is left alone. Relay
+    /// nodes pass no state and only get the stateless checks.
+    fn connect_block(
+        blockchain: &BlockchainStorage,
+        state: Option<&StateStorage>,
+        checkpoints: &CheckpointRegistry,
+        block: &Block,
+    ) -> Result<(), StorageError> {
+        let height = blockchain.get_chain_height()?;
+        let Some(state) = state else {
+            return blockchain.append_block_with_registry(block, checkpoints, None);
+        };
+        if state.get_state_height()? != height {
+            return blockchain.append_block_with_registry(block, checkpoints, None);
+        }
+
+        state.check_block(height + 1, block).map_err(|violation| {
+            warn!(
+                "Block at height {} breaks state rule {}: {}",
+                height + 1,
+                violation.rule,
+                violation.error
+            );
+            violation.error
+        })?;
+        blockchain.append_block_with_registry(block, checkpoints, Some(state))?;
+        if let Err(e) = state.apply_block(height + 1, block) {
+            warn!(
+                "State stopped following the chain at height {}: {}",
+                height + 1,
+                e
+            );
+        } else {
+            state.publish_anchor(height + 1, &block.hash());
+        }
+        Ok(())
+    }
+
+    /// Fork height of `branch` if it forks off below our tip and carries
+    /// strictly more work than the part of our chain it would replace
+    fn branch_fork_height(
+        blockchain: &BlockchainStorage,
+        branch: &[Block],
+    ) -> Result<Option<u64>, StorageError> {
+        let Some(first) = branch.first() else {
+            return Ok(None);
+        };
+        let parent = first.header.previous_hash;
+        if blockchain.get_chain_tip()? == Some(parent) {
+            return Ok(None);
+        }
+        let Some(fork_height) = blockchain.get_block_height_by_hash(&parent)? else {
+            return Ok(None);
+        };
+        let height = blockchain.get_chain_height()?;
+        if fork_height >= height {
+            return Ok(None);
+        }
+        let heavier = chain_work(branch) > blockchain.work_above(fork_height)?;
+        Ok(heavier.then_some(fork_height))
+    }
+
+    /// Switch to `branch`, a chain with more work forking off at `fork_height`
+    /// التحول إلى فرع يحمل عملاً أكثر من السلسلة
+    ///
+    /// Our blocks above the fork are disconnected and the state rolled back
+    /// with them, then the branch is connected block by block. If the blocks
+    /// that connect carry no more work than ours, ours are connected again.
+    /// Once the state matches the new tip, the mempool takes back what only
+    /// the old blocks confirmed, oldest block first.
+    async fn reorganize(&self, fork_height: u64, branch: Vec<Block>) -> Result<()> {
+        let (disconnected, connected) = {
+            let checkpoints = self.checkpoints.read().await;
+            let blockchain = self.blockchain.write().await;
+            let state = self.read_state().await;
+            let state = state.as_deref();
+
+            let disconnected = blockchain.disconnect_to_height(fork_height, state)?;
+            let mut connected = Vec::new();
+            for block in branch {
+                if let Err(e) = Self::connect_block(&blockchain, state, &checkpoints, &block) {
+                    warn!(
+                        "Branch block at height {} rejected: {}",
+                        fork_height + connected.len() as u64 + 1,
+                        e
+                    );
+                    break;
+                }
+                connected.push(block);
+            }
+
+            if chain_work(&connected) <= chain_work(&disconnected) {
+                warn!(
+                    "Branch at height {} carries no more work than our chain, switching back",
+                    fork_height
+                );
+                blockchain.disconnect_to_height(fork_height, state)?;
+                for block in &disconnected {
+                    Self::connect_block(&blockchain, state, &checkpoints, block)?;
+                }
+                return Ok(());
+            }
+            (disconnected, connected)
+        };
+
+        warn!(
+            "Reorganized at height {}: {} blocks disconnected, {} connected",
+            fork_height,
+            disconnected.len(),
+            connected.len()
+        );
+        self.mempool
+            .write()
+            .await
+            .handle_reorg(&disconnected, &connected)
+            .await;
+        Ok(())
+    }
+
+    /// Snapshot the state if `snapshot_interval` blocks passed since the last one
+    /// إنشاء لقطة للحالة عند حلول موعدها
+    pub async fn create_snapshot_if_due(&self) -> Result<Option<u64>> {
+        if self.config.snapshot_interval == 0
+            || self.config.relay_only
+            || self.safe_mode.is_active()
+        {
+            return Ok(None);
+        }
+
+        let Some(state) = self.read_state().await else {
+            return Ok(None);
+        };
+        let blockchain = self.blockchain.read().await;
+        let height = blockchain.get_chain_height()?;
+        if height == 0 || state.get_state_height()? != height {
+            return Ok(None);
+        }
+        let last = self.snapshots.heights()?.last().copied().unwrap_or(0);
+        if height < last + self.config.snapshot_interval {
+            return Ok(None);
+        }
+        let Some(block_hash) = blockchain.get_block_hash_by_height(height)? else {
+            return Ok(None);
+        };
+
+        let window_headers = blockchain.snapshot_window_headers(height)?;
+        let (manifest, chunks) = state.create_snapshot(height, block_hash, window_headers)?;
+        self.snapshots.save(&manifest, &chunks)?;
+        self.snapshots.prune(SNAPSHOTS_KEPT)?;
+        info!(
+            "Created state snapshot at height {} ({} entries, {} chunks)",
+            height,
+            manifest.entries,
+            chunks.len()
+        );
+        Ok(Some(height))
+    }
+
+    /// Trusted anchor at `height` a snapshot there must match
+    fn trusted_anchor(&self, height: u64) -> Option<&ChainAnchor> {
+        self.config
+            .trusted_anchors
+            .iter()
+            .find(|anchor| anchor.height == height)
+    }
+
+    /// Begin fetching the snapshot at `height` from `peer`
+    fn start_state_sync(&mut self, peer: PeerId, height: u64) {
+        info!(
+            "Starting state sync from {} at snapshot height {}",
+            peer, height
+        );
+        self.state_sync = Some(StateSyncSession {
+            peer,
+            height,
+            assembler: None,
+            base_block: None,
+            in_flight: HashSet::new(),
+        });
+        self.swarm
+            .behaviour_mut()
+            .request_response
+            .send_request(&peer, NetworkRequest::GetSnapshotManifest { height });
+    }
+
+    /// Give up on the current state sync; the next chain tip exchange retries
+    async fn abort_state_sync(&mut self, reason: &str, penalize: bool) {
+        if let Some(session) = self.state_sync.take() {
+            warn!(
+                "State sync from {} at height {} aborted: {}",
+                session.peer, session.height, reason
+            );
+            if penalize {
+                let mut reputation = self.reputation.write().await;
+                reputation.penalize_invalid_block(&session.peer);
+            }
+        }
+    }
+
+    /// Check a snapshot manifest, then request the base block and first chunks
+    async fn handle_snapshot_manifest(&mut self, peer: PeerId, manifest: SnapshotManifest) {
+        let Some(session) = self.state_sync.as_ref() else {
+            return;
+        };
+        if session.peer != peer || session.assembler.is_some() {
+            return;
+        }
+        if manifest.height != session.height {
+            self.abort_state_sync("manifest for the wrong height", true)
+                .await;
+            return;
+        }
+        let interval = self
+            .blockchain
+            .read()
+            .await
+            .chain_params()
+            .difficulty_adjustment_interval;
+        if manifest.window_headers.len() > interval as usize {
+            self.abort_state_sync("manifest carries too many window headers", true)
+                .await;
+            return;
+        }
+
+        // The trusted anchor pins the block the state belongs to; a
+        // checkpoint at the same height must agree with it
+        let anchored = self
+            .trusted_anchor(manifest.height)
+            .is_some_and(|anchor| anchor.block_hash == hex::encode(manifest.block_hash));
+        if !anchored {
+            self.abort_state_sync("manifest does not match a trusted anchor", true)
+                .await;
+            return;
+        }
+        let checkpoint = self.checkpoints.read().await.get(manifest.height);
+        if let Some(checkpoint) = checkpoint {
+            if checkpoint.hash != manifest.block_hash {
+                self.abort_state_sync("manifest contradicts checkpoint", true)
+                    .await;
+                return;
+            }
+        }
+
+        let height = manifest.height;
+        let assembler = match SnapshotAssembler::new(manifest) {
+            Ok(assembler) => assembler,
+            Err(e) => {
+                self.abort_state_sync(&e.to_string(), true).await;
+                return;
+            }
+        };
+        info!(
+            "Snapshot manifest from {}: {} chunks at height {}",
+            peer,
+            assembler.manifest().chunk_count(),
+            height
+        );
+        if let Some(session) = self.state_sync.as_mut() {
+            session.assembler = Some(assembler);
+        }
+
+        self.swarm.behaviour_mut().request_response.send_request(
+            &peer,
+            NetworkRequest::GetBlocks {
+                start_height: height,
+                max_blocks: 1,
+            },
+        );
+        self.request_snapshot_chunks();
+    }
+
+    /// Keep up to [`SNAPSHOT_CHUNKS_IN_FLIGHT`] chunk requests outstanding
+    fn request_snapshot_chunks(&mut self) {
+        let Some(session) = self.state_sync.as_mut() else {
+            return;
+        };
+        let Some(assembler) = session.assembler.as_ref() else {
+            return;
+        };
+
+        let wanted = SNAPSHOT_CHUNKS_IN_FLIGHT.saturating_sub(session.in_flight.len());
+        let indices: Vec<u32> = assembler
+            .missing(usize::MAX)
+            .into_iter()
+            .filter(|index| !session.in_flight.contains(index))
+            .take(wanted)
+            .collect();
+        let (peer, height) = (session.peer, session.height);
+        session.in_flight.extend(&indices);
+
+        for index in indices {
+            self.swarm
+                .behaviour_mut()
+                .request_response
+                .send_request(&peer, NetworkRequest::GetSnapshotChunk { height, index });
+        }
+    }
+
+    /// Verify a received chunk against the manifest
+    async fn handle_snapshot_chunk(
+        &mut self,
+        peer: PeerId,
+        height: u64,
+        index: u32,
+        data: Vec<u8>,
+    ) {
+        let Some(session) = self.state_sync.as_mut() else {
+            return;
+        };
+        let Some(assembler) = session.assembler.as_mut() else {
+            return;
+        };
+        if session.peer != peer || session.height != height {
+            return;
+        }
+
+        session.in_flight.remove(&index);
+        if let Err(e) = assembler.add_chunk(index, data) {
+            self.abort_state_sync(&e.to_string(), true).await;
+            return;
+        }
+        debug!(
+            "Snapshot chunk {} verified ({}/{})",
+            index,
+            assembler.received(),
+            assembler.manifest().chunk_count()
+        );
+
+        self.request_snapshot_chunks();
+        self.finish_state_sync().await;
+    }
+
+    /// Accept the block at the snapshot height if it is the one the manifest names
+    async fn handle_snapshot_base_block(&mut self, block: Block) {
+        let Some(session) = self.state_sync.as_mut() else {
+            return;
+        };
+        let Some(assembler) = session.assembler.as_ref() else {
+            return;
+        };
+
+        if block.hash() != assembler.manifest().block_hash || !block.header.meets_difficulty() {
+            self.abort_state_sync("base block does not match manifest", true)
+                .await;
+            return;
+        }
+        session.base_block = Some(block);
+        self.finish_state_sync().await;
+    }
+
+    /// Restore the state and chain tip once every piece has arrived, then
+    /// fetch the blocks above the snapshot height
+    async fn finish_state_sync(&mut self) {
+        let complete = self.state_sync.as_ref().is_some_and(|session| {
+            session.base_block.is_some()
+                && session
+                    .assembler
+                    .as_ref()
+                    .is_some_and(SnapshotAssembler::is_complete)
+        });
+        if !complete {
+            return;
+        }
+        let Some(session) = self.state_sync.take() else {
+            return;
+        };
+        let (Some(assembler), Some(base_block)) = (session.assembler, session.base_block) else {
+            return;
+        };
+        let Some((manifest, chunks)) = assembler.into_chunks() else {
+            return;
+        };
+
+        let result: Result<(), StorageError> = async {
+            let anchor = self
+                .trusted_anchor(manifest.height)
+                .ok_or_else(|| StorageError::InvalidSnapshot("no trusted anchor".to_string()))?;
+            let state_root = hex::decode(&anchor.state_root)
+                .ok()
+                .and_then(|root| <[u8; 32]>::try_from(root).ok())
+                .ok_or_else(|| {
+                    StorageError::InvalidSnapshot("malformed anchor state root".to_string())
+                })?;
+            let blockchain = self.blockchain.read().await;
+            if blockchain.get_chain_height()? != 0 {
+                return Err(StorageError::InvalidChain);
+            }
+            self.read_state()
+                .await
+                .ok_or(StorageError::InvalidChain)?
+                .restore_snapshot(&manifest, &chunks, &state_root)?;
+            blockchain.import_snapshot_base(anchor, &base_block, &manifest.window_headers)
+        }
+        .await;
+
+        match result {
+            Ok(()) => {
+                info!(
+                    "State restored from snapshot at height {} ({} entries), replaying later blocks",
+                    manifest.height, manifest.entries
+                );
+                if let Err(e) = self.request_headers(session.peer, [0u8; 32]).await {
+                    warn!("Failed to request headers after state sync: {}", e);
+                }
+            }
+            Err(e) => {
+                error!(
+                    "Failed to restore state snapshot from {}: {}",
+                    session.peer, e
+                );
+                let mut reputation = self.reputation.write().await;
+                reputation.penalize_invalid_block(&session.peer);
+            }
+        }
+    }
+
+    /// Checkpoints consulted when accepting blocks
+    pub fn checkpoints(&self) -> Arc<RwLock<CheckpointRegistry>> {
+        self.checkpoints.clone()
+    }
+
+    /// Shared handle to the blockchain storage (for embedders and queries)
+    pub fn blockchain(&self) -> Arc<RwLock<BlockchainStorage>> {
+        self.blockchain.clone()
+    }
+
+    /// Shared handle to the state storage (None on relay-only nodes)
+    pub fn state(&self) -> Option<Arc<RwLock<StateStorage>>> {
+        self.state.clone()
+    }
+
+    /// Read lock on the state storage, if this node keeps one
+    async fn read_state(&self) -> Option<RwLockReadGuard<'_, StateStorage>> {
+        match &self.state {
+            Some(state) => Some(state.read().await),
+            None => None,
+        }
+    }
+
+    /// Shared handle to the mempool
+    pub fn mempool(&self) -> Arc<RwLock<Mempool>> {
+        self.mempool.clone()
+    }
+
+    /// Sender for requests to the event loop once `run` is going
+    /// مرسل الطلبات إلى حلقة الأحداث
+    pub fn command_sender(&self) -> mpsc::UnboundedSender<NetworkCommand> {
+        self.command_tx.clone()
+    }
+
     /// Get local peer ID
     pub fn local_peer_id(&self) -> PeerId {
         self.local_peer_id
@@ -377,12 +1456,28 @@ impl NetworkNode {
     }
 
     /// Request blocks from a peer
+    #[tracing::instrument(name = "network_request", skip(self), fields(kind = "get_blocks"))]
     pub async fn request_blocks(&mut self, peer_id: PeerId, start_height: u64, max_blocks: usize) {
         let request = NetworkRequest::GetBlocks {
             start_height,
             max_blocks,
         };
 
+        // Hold off until the budget can take the largest answer; the next
+        // sync retries
+        {
+            let mut bandwidth = self.bandwidth.write().await;
+            let expected = request.expected_response_size();
+            if !bandwidth.has_budget(&peer_id, Direction::Download, expected) {
+                bandwidth.record_throttle(Direction::Download);
+                debug!(
+                    "Download budget spent, deferring block request to {}",
+                    peer_id
+                );
+                return;
+            }
+        }
+
         let _request_id = self
             .swarm
             .behaviour_mut()
@@ -401,6 +1496,26 @@ impl NetworkNode {
             .insert(peer_id, start_height);
     }
 
+    /// Request headers from a peer, starting at the fork point of our locator
+    /// طلب رؤوس الكتل من نظير بدءاً من نقطة التفرع
+    #[tracing::instrument(
+        name = "network_request",
+        skip(self, stop_hash),
+        fields(kind = "get_headers")
+    )]
+    pub async fn request_headers(&mut self, peer_id: PeerId, stop_hash: [u8; 32]) -> Result<()> {
+        let locator = self.blockchain.read().await.get_block_locator()?;
+        let request = NetworkRequest::GetHeaders { locator, stop_hash };
+
+        self.swarm
+            .behaviour_mut()
+            .request_response
+            .send_request(&peer_id, request);
+
+        debug!("Requested headers from {}", peer_id);
+        Ok(())
+    }
+
     /// Sync with network
     pub async fn sync(&mut self) -> Result<()> {
         info!("Starting blockchain sync");
@@ -408,8 +1523,12 @@ impl NetworkNode {
         let local_height = self.get_chain_height().await?;
         info!("Local chain height: {}", local_height);
 
-        // Request chain tip from all peers
-        let peers: Vec<PeerId> = self.peers.read().await.iter().cloned().collect();
+        // Request chain tip from all peers, nearest first
+        let mut peers: Vec<PeerId> = self.peers.read().await.iter().cloned().collect();
+        {
+            let latency = self.latency.read().await;
+            peers.sort_by(|a, b| latency.compare(a, b));
+        }
 
         for peer in peers {
             let request = NetworkRequest::GetChainTip;
@@ -419,33 +1538,430 @@ impl NetworkNode {
                 .send_request(&peer, request);
         }
 
-        Ok(())
+        Ok(())
+    }
+
+    /// Add transaction to mempool and broadcast to network
+    pub async fn submit_transaction(&mut self, tx: Transaction) -> Result<()> {
+        if self.safe_mode.is_active() {
+            anyhow::bail!("Node is in safe mode; not accepting transactions");
+        }
+
+        // Add to mempool
+        let mut mempool = self.mempool.write().await;
+        mempool
+            .add_transaction(tx.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to add transaction to mempool: {}", e))?;
+        drop(mempool);
+
+        // Broadcast to network
+        self.broadcast_transaction(&tx).await?;
+
+        info!(
+            "Transaction submitted and broadcast: {} SYL",
+            tx.amount as f64 / 1_000_000.0
+        );
+        Ok(())
+    }
+
+    /// Replace a pending transaction by fee and broadcast the replacement
+    /// استبدال معاملة معلقة برسوم أعلى وبث البديلة
+    ///
+    /// Peers apply the same replacement rule to what they receive, so the
+    /// replacement spreads like a new transaction.
+    pub async fn replace_transaction(&mut self, tx: Transaction) -> Result<()> {
+        if self.safe_mode.is_active() {
+            anyhow::bail!("Node is in safe mode; not accepting transactions");
+        }
+
+        self.mempool
+            .write()
+            .await
+            .replace_transaction(tx.clone())
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to replace transaction in mempool: {}", e))?;
+        self.broadcast_transaction(&tx).await?;
+
+        info!("Replacement transaction submitted and broadcast");
+        Ok(())
+    }
+
+    /// The Kademlia DHT, unless disabled
+    fn kademlia(&mut self) -> Option<&mut kad::Behaviour<MemoryStore>> {
+        self.swarm.behaviour_mut().kademlia.as_mut()
+    }
+
+    /// Routing table occupancy, for metrics
+    pub fn kademlia_stats(&self) -> Arc<RwLock<KademliaStats>> {
+        self.kademlia_stats.clone()
+    }
+
+    /// Refresh the routing table, and look for other full nodes while
+    /// outbound slots are free
+    /// تحديث جدول التوجيه والبحث عن عقد كاملة أخرى
+    async fn refresh_routing(&mut self) {
+        let network = self.config.network;
+        let short_of_peers = self.can_create_outbound().await;
+        let Some(kademlia) = self.kademlia() else {
+            return;
+        };
+        if kademlia.bootstrap().is_err() {
+            debug!("No known DHT peers, skipping Kademlia bootstrap");
+            return;
+        }
+        if short_of_peers {
+            kademlia.get_providers(routing::full_node_key(network));
+        }
+    }
+
+    /// Advertise this node as a full node of its network; Kademlia
+    /// republishes the record from then on
+    fn advertise_full_node(&mut self) {
+        if self.providing {
+            return;
+        }
+        let key = routing::full_node_key(self.config.network);
+        let Some(kademlia) = self.kademlia() else {
+            return;
+        };
+        match kademlia.start_providing(key) {
+            Ok(_) => self.providing = true,
+            Err(e) => warn!("Failed to advertise full node in the DHT: {}", e),
+        }
+    }
+
+    /// Propagation delay and extra fanout, for metrics
+    pub fn gossip_stats(&self) -> Arc<RwLock<GossipStats>> {
+        self.gossip_stats.clone()
+    }
+
+    /// Feed peer reputation to gossipsub scoring and resize the extra fanout
+    /// from the propagation delays and bandwidth pressure seen since last time
+    /// مواءمة تقييم الأقران وتعديل الانتشار الإضافي حسب زمن الانتشار
+    async fn adjust_gossip(&mut self) {
+        let peers: Vec<PeerId> = self.peers.read().await.iter().copied().collect();
+        let candidates: Vec<(PeerId, i32, Option<Duration>)> = {
+            let reputation = self.reputation.read().await;
+            let latency = self.latency.read().await;
+            peers
+                .into_iter()
+                .map(|peer_id| {
+                    let score = reputation.get_score(&peer_id).map_or(0, |s| s.score);
+                    (peer_id, score, latency.average(&peer_id))
+                })
+                .collect()
+        };
+
+        let stats = self.bandwidth_stats().await;
+        let extra = self
+            .gossip
+            .adjust(stats.upload_throttled + stats.download_throttled);
+
+        let gossipsub = &mut self.swarm.behaviour_mut().gossipsub;
+        for (peer_id, score, _) in &candidates {
+            gossipsub.set_application_score(peer_id, gossip::application_score(*score));
+        }
+        let mesh: HashSet<PeerId> = gossipsub.all_mesh_peers().copied().collect();
+        let (added, removed) = self.gossip.rebalance(candidates, &mesh);
+        for peer_id in &removed {
+            gossipsub.remove_explicit_peer(peer_id);
+        }
+        for peer_id in &added {
+            gossipsub.add_explicit_peer(peer_id);
+        }
+        if !added.is_empty() || !removed.is_empty() {
+            debug!("Gossip extra fanout now {} peers", extra);
+        }
+
+        *self.gossip_stats.write().await = self.gossip.stats();
+    }
+
+    /// Snapshot the routing table for metrics
+    async fn update_kademlia_stats(&mut self) {
+        let stats = self
+            .kademlia()
+            .map(KademliaStats::from_behaviour)
+            .unwrap_or_default();
+        *self.kademlia_stats.write().await = stats;
+    }
+
+    /// Dial a peer found through the DHT if it is new and a slot is free
+    async fn dial_discovered(&mut self, peer: PeerId) {
+        if peer == self.local_peer_id || self.peers.read().await.contains(&peer) {
+            return;
+        }
+        if !self.can_create_outbound().await {
+            return;
+        }
+        debug!("Dialing peer found via Kademlia: {}", peer);
+        if let Err(e) = self.swarm.dial(peer) {
+            debug!("Failed to dial {}: {}", peer, e);
+        }
+    }
+
+    /// Publish a record, signed by `wallet`, naming this node as the place to
+    /// deliver its payments. Records expire after a day; announce again to stay
+    /// reachable. Until a wallet is announced, direct deliveries to it are refused.
+    /// نشر سجل يربط المحفظة بهذه العقدة لاستقبال المدفوعات مباشرة
+    pub fn announce_wallet(&mut self, wallet: &KeyPair) -> Result<()> {
+        let mut addresses: Vec<Multiaddr> = self.swarm.external_addresses().cloned().collect();
+        if addresses.is_empty() {
+            addresses = self.swarm.listeners().cloned().collect();
+        }
+        let addresses = transport::prefer_quic(addresses, self.config.transports);
+
+        let record = WalletPeerRecord::new(wallet, self.local_peer_id, &addresses, system_time());
+        let dht_record =
+            kad::Record::new(WalletPeerRecord::dht_key(&record.wallet), record.encode());
+        let Some(kademlia) = self.kademlia() else {
+            anyhow::bail!("Kademlia DHT is disabled; wallets cannot be announced");
+        };
+        kademlia
+            .put_record(dht_record, kad::Quorum::One)
+            .map_err(|e| anyhow::anyhow!("Failed to publish wallet record: {}", e))?;
+
+        self.announced_wallets.insert(record.wallet);
+        info!(
+            "Announced wallet {} for direct delivery",
+            record.wallet.to_hex()
+        );
+        Ok(())
+    }
+
+    /// Gossip a transaction and also deliver it straight to the node its
+    /// recipient announced, looking the wallet up in the DHT if needed
+    /// بث المعاملة وتسليمها مباشرة إلى عقدة المستلم
+    pub async fn send_transaction_direct(&mut self, tx: Transaction) -> Result<()> {
+        let recipient = tx.to;
+        self.submit_transaction(tx.clone()).await?;
+
+        match self.wallet_peers.get(&recipient).copied() {
+            Some((peer, expires)) if expires > Instant::now() => {
+                self.deliver_transaction(peer, tx);
+                return Ok(());
+            }
+            Some(_) => {
+                self.wallet_peers.remove(&recipient);
+            }
+            None => {}
+        }
+
+        // Wait on a lookup already running for this wallet
+        if let Some((_, pending)) = self
+            .wallet_lookups
+            .values_mut()
+            .find(|(wallet, _)| *wallet == recipient)
+        {
+            pending.push(tx);
+            return Ok(());
+        }
+
+        if self.wallet_lookups.len() >= MAX_WALLET_LOOKUPS {
+            debug!("Too many wallet lookups running, relying on gossip alone");
+            return Ok(());
+        }
+        if let Some(kademlia) = self.kademlia() {
+            let query = kademlia.get_record(WalletPeerRecord::dht_key(&recipient));
+            self.wallet_lookups.insert(query, (recipient, vec![tx]));
+        }
+        Ok(())
+    }
+
+    /// Send a transaction to the node announced for its recipient
+    fn deliver_transaction(&mut self, peer: PeerId, transaction: Transaction) {
+        // Our own wallet: the transaction is already in our mempool
+        if peer == self.local_peer_id {
+            return;
+        }
+        debug!("Delivering transaction directly to {}", peer);
+        self.swarm
+            .behaviour_mut()
+            .request_response
+            .send_request(&peer, NetworkRequest::DeliverTransaction { transaction });
+    }
+
+    /// Handle routing updates, provider lookups, wallet record lookups and
+    /// records other peers ask us to store
+    async fn handle_kademlia_event(&mut self, event: kad::Event) {
+        match event {
+            kad::Event::RoutingUpdated {
+                peer,
+                is_new_peer: true,
+                ..
+            } => {
+                self.dial_discovered(peer).await;
+            }
+
+            kad::Event::OutboundQueryProgressed {
+                result: kad::QueryResult::Bootstrap(result),
+                step,
+                ..
+            } => match result {
+                Ok(_) if step.last => self.advertise_full_node(),
+                Ok(_) => {}
+                Err(e) => debug!("Kademlia bootstrap failed: {}", e),
+            },
+
+            kad::Event::OutboundQueryProgressed {
+                result:
+                    kad::QueryResult::GetProviders(Ok(kad::GetProvidersOk::FoundProviders {
+                        providers,
+                        ..
+                    })),
+                ..
+            } => {
+                for peer in providers {
+                    self.dial_discovered(peer).await;
+                }
+            }
+
+            kad::Event::OutboundQueryProgressed {
+                id,
+                result: kad::QueryResult::GetRecord(result),
+                ..
+            } => match result {
+                Ok(kad::GetRecordOk::FoundRecord(kad::PeerRecord { record, .. })) => {
+                    let Some(wallet) = self.wallet_lookups.get(&id).map(|(wallet, _)| *wallet)
+                    else {
+                        return;
+                    };
+                    let (wallet_record, peer) =
+                        match WalletPeerRecord::from_dht(&record, system_time()) {
+                            Ok(found) if found.0.wallet == wallet => found,
+                            Ok(_) => return,
+                            Err(e) => {
+                                debug!("Ignoring wallet record: {}", e);
+                                return;
+                            }
+                        };
+
+                    // The first valid record is enough
+                    let addresses =
+                        transport::prefer_quic(wallet_record.multiaddrs(), self.config.transports);
+                    if let Some(kademlia) = self.kademlia() {
+                        if let Some(mut query) = kademlia.query_mut(&id) {
+                            query.finish();
+                        }
+                        for address in addresses {
+                            kademlia.add_address(&peer, address);
+                        }
+                    }
+
+                    // Kept no longer than the record itself stays valid
+                    let now = Instant::now();
+                    let valid_until = wallet_record.issued_at + WALLET_RECORD_TTL_SECS;
+                    let remaining = valid_until.saturating_sub(system_time());
+                    let ttl = remaining.min(WALLET_PEER_CACHE_SECS);
+                    if self.wallet_peers.len() >= MAX_CACHED_WALLET_PEERS {
+                        self.wallet_peers.retain(|_, (_, expires)| *expires > now);
+                    }
+                    if self.wallet_peers.len() >= MAX_CACHED_WALLET_PEERS {
+                        self.wallet_peers.clear();
+                    }
+                    let expires = now + Duration::from_secs(ttl);
+                    self.wallet_peers.insert(wallet, (peer, expires));
+                    if let Some((_, pending)) = self.wallet_lookups.remove(&id) {
+                        for transaction in pending {
+                            self.deliver_transaction(peer, transaction);
+                        }
+                    }
+                }
+                Ok(kad::GetRecordOk::FinishedWithNoAdditionalRecord { .. }) | Err(_) => {
+                    if let Some((wallet, pending)) = self.wallet_lookups.remove(&id) {
+                        debug!(
+                            "No wallet record for {}, {} transaction(s) sent by gossip only",
+                            wallet.to_hex(),
+                            pending.len()
+                        );
+                    }
+                }
+            },
+
+            // Records are filtered: only verified wallet records are stored
+            kad::Event::InboundRequest {
+                request:
+                    kad::InboundRequest::PutRecord {
+                        source,
+                        record: Some(record),
+                        ..
+                    },
+            } => match WalletPeerRecord::from_dht(&record, system_time()) {
+                Ok(_) => {
+                    if let Some(Err(e)) = self.kademlia().map(|k| k.store_mut().put(record)) {
+                        debug!("Failed to store wallet record from {}: {}", source, e);
+                    }
+                }
+                Err(e) => debug!("Rejected DHT record from {}: {}", source, e),
+            },
+
+            // Only full node advertisements for our network are stored
+            kad::Event::InboundRequest {
+                request:
+                    kad::InboundRequest::AddProvider {
+                        record: Some(record),
+                    },
+            } => {
+                if record.key == routing::full_node_key(self.config.network) {
+                    let stored = self.kademlia().map(|k| k.store_mut().add_provider(record));
+                    if let Some(Err(e)) = stored {
+                        debug!("Failed to store provider record: {}", e);
+                    }
+                }
+            }
+
+            _ => {}
+        }
     }
 
-    /// Add transaction to mempool and broadcast to network
-    pub async fn submit_transaction(&mut self, tx: Transaction) -> Result<()> {
-        // Add to mempool
+    /// Get pending transactions from mempool, in deterministic block order
+    pub async fn get_pending_transactions(&self, max_count: usize) -> Vec<Transaction> {
         let mut mempool = self.mempool.write().await;
-        mempool
-            .add_transaction(tx.clone())
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to add transaction to mempool: {}", e))?;
-        drop(mempool);
+        mempool.recheck_signatures();
+        mempool.select_block_transactions(max_count)
+    }
 
-        // Broadcast to network
-        self.broadcast_transaction(&tx).await?;
+    /// Add a gossiped transaction once its signature check completes
+    async fn handle_verified_transaction(
+        &mut self,
+        peer_id: PeerId,
+        verified: opensyria_mempool::Result<VerifiedTransaction>,
+    ) {
+        let result = match verified {
+            // Relays forward anything correctly signed without a mempool check
+            Ok(verified) if self.config.relay_only => Ok(verified.transaction().clone()),
+            // A transaction reusing a pending nonce is taken as a replacement
+            Ok(verified) => {
+                let transaction = verified.transaction().clone();
+                let mut mempool = self.mempool.write().await;
+                mempool
+                    .replace_verified_transaction(verified)
+                    .await
+                    .map(|_| transaction)
+            }
+            Err(e) => Err(e),
+        };
 
-        info!(
-            "Transaction submitted and broadcast: {} SYL",
-            tx.amount as f64 / 1_000_000.0
-        );
-        Ok(())
-    }
+        match result {
+            Ok(transaction) => {
+                info!("Added transaction to mempool from network");
 
-    /// Get pending transactions from mempool
-    pub async fn get_pending_transactions(&self, max_count: usize) -> Vec<Transaction> {
-        let mempool = self.mempool.read().await;
-        mempool.get_priority_transactions(max_count)
+                // Reward peer for valid transaction
+                let mut reputation = self.reputation.write().await;
+                reputation.reward_valid_tx(&peer_id);
+
+                let _ = self
+                    .event_tx
+                    .send(NetworkEvent::NewTransaction(transaction));
+            }
+            Err(e) => {
+                warn!("Failed to add transaction to mempool: {}", e);
+
+                // Penalize for invalid transaction
+                let mut reputation = self.reputation.write().await;
+                reputation.penalize_invalid_tx(&peer_id);
+            }
+        }
     }
 
     /// Get mempool size
@@ -460,17 +1976,232 @@ impl NetworkNode {
         mempool.remove_confirmed_transactions(transactions);
     }
 
+    /// Account for a gossip publish, which is sent to every connected peer
+    async fn record_broadcast(&self, size: usize) {
+        let peers = self.peers.read().await.len().max(1);
+        self.bandwidth
+            .write()
+            .await
+            .record(None, Direction::Upload, (size * peers) as u64);
+    }
+
+    /// Live bandwidth counters
+    /// عدادات عرض النطاق الحية
+    pub async fn bandwidth_stats(&self) -> BandwidthStats {
+        self.bandwidth.write().await.stats()
+    }
+
+    /// Shared handle to the bandwidth limiter (for metrics exporters)
+    pub fn bandwidth(&self) -> Arc<RwLock<BandwidthLimiter>> {
+        self.bandwidth.clone()
+    }
+
+    /// Shared handle to the peer latency averages
+    pub fn latency(&self) -> Arc<RwLock<PeerLatency>> {
+        self.latency.clone()
+    }
+
     /// Run the network node event loop
     pub async fn run(&mut self) -> Result<()> {
+        let mut persist_interval =
+            tokio::time::interval(Duration::from_secs(REPUTATION_PERSIST_INTERVAL_SECS));
+        let mut status_interval =
+            tokio::time::interval(Duration::from_secs(BANDWIDTH_STATUS_INTERVAL_SECS));
+        // The first tick fires immediately, auditing the chain on startup
+        let mut audit_interval =
+            tokio::time::interval(Duration::from_secs(SAFE_MODE_AUDIT_INTERVAL_SECS));
+        let mut snapshot_interval =
+            tokio::time::interval(Duration::from_secs(SNAPSHOT_CHECK_INTERVAL_SECS));
+        let mut kademlia_interval =
+            tokio::time::interval(Duration::from_secs(KADEMLIA_REFRESH_INTERVAL_SECS));
+        let mut fanout_interval =
+            tokio::time::interval(Duration::from_secs(FANOUT_ADJUST_INTERVAL_SECS));
+        let telemetry_period = self.telemetry.as_ref().map_or(
+            Duration::from_secs(DEFAULT_TELEMETRY_INTERVAL_SECS),
+            TelemetryReporter::interval,
+        );
+        let mut telemetry_interval = tokio::time::interval(telemetry_period);
+        // The first tick fetches subscribed ban lists on startup
+        let ban_list_period = self.config.ban_lists.as_ref().map_or(
+            Duration::from_secs(DEFAULT_BAN_LIST_REFRESH_SECS),
+            |subscriptions| subscriptions.refresh,
+        );
+        let mut ban_list_interval = tokio::time::interval(ban_list_period);
+
         loop {
             tokio::select! {
                 event = self.swarm.select_next_some() => {
                     self.handle_swarm_event(event).await?;
                 }
+                _ = persist_interval.tick() => {
+                    if let Err(e) = self.save_peer_cache().await {
+                        warn!("Failed to persist peer reputation: {}", e);
+                    }
+                }
+                _ = status_interval.tick() => {
+                    let stats = self.bandwidth_stats().await;
+                    let path = self.config.data_dir.join(BANDWIDTH_STATUS_FILE);
+                    if let Err(e) = stats.save(&path) {
+                        debug!("Failed to write bandwidth status: {}", e);
+                    }
+                    self.update_kademlia_stats().await;
+                    self.sync_relaying();
+                }
+                _ = audit_interval.tick() => {
+                    if let Err(e) = self.audit_chain().await {
+                        warn!("Chain audit failed: {}", e);
+                    }
+                    self.sync_relaying();
+                }
+                _ = snapshot_interval.tick() => {
+                    if let Err(e) = self.create_snapshot_if_due().await {
+                        warn!("Failed to create state snapshot: {}", e);
+                    }
+                }
+                _ = kademlia_interval.tick() => {
+                    self.refresh_routing().await;
+                }
+                _ = fanout_interval.tick() => {
+                    self.adjust_gossip().await;
+                }
+                _ = telemetry_interval.tick() => {
+                    if let Some(telemetry) = &mut self.telemetry {
+                        telemetry.flush();
+                    }
+                }
+                _ = ban_list_interval.tick() => {
+                    self.fetch_ban_lists();
+                }
+                Some((source, signed)) = self.ban_list_rx.recv() => {
+                    self.apply_ban_list(&source, signed).await;
+                }
+                Some((peer_id, hash, verified)) = self.verified_rx.recv() => {
+                    self.verifying.remove(&hash);
+                    self.handle_verified_transaction(peer_id, verified).await;
+                }
+                Some(command) = self.command_rx.recv() => {
+                    if !self.handle_command(command).await {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serve one request; returns false once asked to shut down
+    async fn handle_command(&mut self, command: NetworkCommand) -> bool {
+        match command {
+            NetworkCommand::BroadcastTransaction { tx, reply } => {
+                let _ = reply.send(self.broadcast_transaction(&tx).await);
+            }
+            NetworkCommand::ReplaceTransaction { tx, reply } => {
+                let _ = reply.send(self.replace_transaction(tx).await);
+            }
+            NetworkCommand::CheckTransaction { tx, reply } => {
+                let mempool = self.mempool.read().await;
+                let result = mempool
+                    .check_transaction(&tx)
+                    .await
+                    .map(|()| mempool.get_sender_transactions(&tx.from.0));
+                let _ = reply.send(result);
+            }
+            NetworkCommand::Dial { addr, reply } => {
+                let _ = reply.send(self.dial(addr).await);
+            }
+            NetworkCommand::BanPeer {
+                peer_id,
+                duration,
+                reply,
+            } => {
+                let _ = reply.send(self.ban_peer(peer_id, duration).await);
+            }
+            NetworkCommand::PeerCount { reply } => {
+                let _ = reply.send(self.peer_count().await);
+            }
+            NetworkCommand::Shutdown => {
+                if let Err(e) = self.save_peer_cache().await {
+                    warn!("Failed to persist peer reputation: {}", e);
+                }
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Fetch the subscribed ban lists in the background; each one fetched
+    /// is handed back to the event loop to merge
+    fn fetch_ban_lists(&self) {
+        let Some(subscriptions) = &self.config.ban_lists else {
+            return;
+        };
+        let sources = subscriptions.sources.clone();
+        let ban_list_tx = self.ban_list_tx.clone();
+        tokio::spawn(async move {
+            let http = reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default();
+            for source in sources {
+                match banlist::fetch(&http, &source).await {
+                    Ok(signed) => {
+                        let _ = ban_list_tx.send((source, signed));
+                    }
+                    Err(e) => warn!("Ban list subscription {}: {}", source, e),
+                }
+            }
+        });
+    }
+
+    /// Merge a fetched ban list from a trusted issuer and drop connections
+    /// to the peers it bans
+    /// دمج قائمة حظر موثوقة وقطع اتصال الأقران المحظورين
+    async fn apply_ban_list(&mut self, source: &str, signed: SignedBanList) {
+        let Some(subscriptions) = &self.config.ban_lists else {
+            return;
+        };
+        let list = match subscriptions.policy.accept(&signed) {
+            Ok(list) => list,
+            Err(e) => {
+                warn!("Rejected ban list from {}: {}", source, e);
+                return;
+            }
+        };
+
+        let mut reputation = self.reputation.write().await;
+        let report = subscriptions.policy.merge(list, &mut reputation);
+        info!(
+            "Merged ban list from {} (issuer {}): {} applied, {} capped, {} expired, {} skipped",
+            source, list.issuer, report.applied, report.capped, report.expired, report.skipped
+        );
+        if report.applied == 0 {
+            return;
+        }
+
+        // IP bans apply as peers reconnect
+        let connected: Vec<PeerId> = self.swarm.connected_peers().copied().collect();
+        for peer_id in connected {
+            if reputation.is_banned(&peer_id) {
+                warn!("Disconnecting peer {} banned by {}", peer_id, list.issuer);
+                let _ = self.swarm.disconnect_peer_id(peer_id);
             }
         }
     }
 
+    /// Write peer reputation, bans and latency to the peer cache
+    /// حفظ سمعة الأقران في ذاكرة الأقران
+    pub async fn save_peer_cache(&self) -> Result<()> {
+        let reputation = self.reputation.read().await;
+        let snapshot = reputation.snapshot();
+        let ip_bans = reputation.ip_bans();
+        drop(reputation);
+        let latency = self.latency.read().await.snapshot();
+        let mut peer_cache = self.peer_cache.write().await;
+        peer_cache.set_reputation(snapshot);
+        peer_cache.set_ip_bans(ip_bans);
+        peer_cache.set_latency(latency);
+        peer_cache.save()
+    }
+
     /// Handle swarm events
     async fn handle_swarm_event(
         &mut self,
@@ -493,6 +2224,19 @@ impl NetworkNode {
                 num_established,
                 .. 
             } => {
+                // Bans follow the authenticated peer ID; address bans only
+                // come from imported ban lists
+                let mut reputation = self.reputation.write().await;
+                let banned = reputation.is_banned(&peer_id)
+                    || banlist::multiaddr_ip(endpoint.get_remote_address())
+                        .is_some_and(|ip| reputation.is_ip_banned(&ip));
+                drop(reputation);
+                if banned {
+                    warn!("Disconnecting banned peer: {}", peer_id);
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return Ok(());
+                }
+
                 // Only track first connection to this peer
                 if num_established.get() == 1 {
                     // Determine if inbound or outbound based on endpoint
@@ -509,8 +2253,13 @@ impl NetworkNode {
                     match result {
                         Ok(_) => {
                             info!("Connected to peer: {} ({})", peer_id, if is_dialer { "outbound" } else { "inbound" });
+                            // The peer ID was authenticated by the noise handshake
+                            self.reputation.write().await.add_peer(peer_id);
                             self.peers.write().await.insert(peer_id);
                             let _ = self.event_tx.send(NetworkEvent::PeerConnected(peer_id));
+                            self.event_bus.publish(NodeEvent::Peer(PeerEvent::Connected(
+                                peer_id.to_string(),
+                            )));
                         }
                         Err(e) => {
                             warn!("Connection limit exceeded for {}: {}", peer_id, e);
@@ -526,8 +2275,27 @@ impl NetworkNode {
                 if num_established == 0 {
                     info!("Disconnected from peer: {}", peer_id);
                     self.peers.write().await.remove(&peer_id);
+                    self.peer_heights.remove(&peer_id);
                     self.unregister_peer(&peer_id).await;
+                    self.bandwidth.write().await.remove_peer(&peer_id);
+                    if self.gossip.forget(&peer_id) {
+                        self.swarm
+                            .behaviour_mut()
+                            .gossipsub
+                            .remove_explicit_peer(&peer_id);
+                    }
+                    if self
+                        .state_sync
+                        .as_ref()
+                        .is_some_and(|session| session.peer == peer_id)
+                    {
+                        self.abort_state_sync("peer disconnected", false).await;
+                    }
                     let _ = self.event_tx.send(NetworkEvent::PeerDisconnected(peer_id));
+                    self.event_bus
+                        .publish(NodeEvent::Peer(PeerEvent::Disconnected(
+                            peer_id.to_string(),
+                        )));
                 }
             }
 
@@ -575,11 +2343,38 @@ impl NetworkNode {
                 self.handle_request_response(peer, message).await?;
             }
 
+            OpenSyriaBehaviourEvent::Kademlia(event) => {
+                self.handle_kademlia_event(event).await;
+            }
+
+            OpenSyriaBehaviourEvent::Ping(event) => match event.result {
+                Ok(rtt) => self.latency.write().await.record(event.peer, rtt),
+                Err(e) => debug!("Ping to {} failed: {}", event.peer, e),
+            },
+
             OpenSyriaBehaviourEvent::Identify(libp2p::identify::Event::Received {
                 peer_id,
                 info,
             }) => {
                 debug!("Identified peer {}: {:?}", peer_id, info.protocol_version);
+                if info.protocol_version != PROTOCOL_VERSION {
+                    warn!(
+                        "Disconnecting {}: protocol {} is not {}",
+                        peer_id, info.protocol_version, PROTOCOL_VERSION
+                    );
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return Ok(());
+                }
+
+                // Make peers that connected to us routable through the DHT
+                if info.protocols.contains(&KADEMLIA_PROTOCOL) {
+                    let addrs = transport::prefer_quic(info.listen_addrs, self.config.transports);
+                    if let Some(kademlia) = self.kademlia() {
+                        for addr in addrs {
+                            kademlia.add_address(&peer_id, addr);
+                        }
+                    }
+                }
             }
 
             _ => {}
@@ -601,6 +2396,18 @@ impl NetworkNode {
             }
         }
 
+        // Gossip over our download budget is dropped; missed blocks come back through sync
+        if !self.bandwidth.write().await.try_consume(
+            &peer_id,
+            Direction::Download,
+            message.data.len() as u64,
+        ) {
+            debug!("Download budget spent, dropping gossip from {}", peer_id);
+            return Ok(());
+        }
+
+        self.gossip.record(&message.data);
+
         // Deserialize and validate message size
         let network_msg = match NetworkMessage::from_bytes(&message.data) {
             Ok(msg) => msg,
@@ -619,6 +2426,7 @@ impl NetworkNode {
         let msg_type = match &network_msg {
             NetworkMessage::NewBlock { .. } => MessageType::Block,
             NetworkMessage::NewTransaction { .. } => MessageType::Transaction,
+            NetworkMessage::Checkpoint { .. } => MessageType::Block,
             _ => {
                 warn!("Unexpected message type in gossipsub from {}", peer_id);
                 return Ok(());
@@ -636,6 +2444,40 @@ impl NetworkNode {
             }
         }
 
+        // Relays handle each block and transaction once, so repeats from
+        // other peers are not verified and forwarded again
+        if self.config.relay_only {
+            let hash = match &network_msg {
+                NetworkMessage::NewBlock { block } => Some(block.hash()),
+                NetworkMessage::NewTransaction { transaction } => Some(transaction.hash()),
+                _ => None,
+            };
+            if hash.is_some_and(|hash| !self.relay_seen.insert(hash)) {
+                debug!("Dropping repeated gossip from {}", peer_id);
+                return Ok(());
+            }
+        }
+
+        if let Some(telemetry) = &mut self.telemetry {
+            let sent_at = gossip::split_stamp(&message.data).1;
+            match &network_msg {
+                NetworkMessage::NewBlock { block } => {
+                    telemetry.observe(ItemKind::Block, block.hash(), sent_at);
+                }
+                NetworkMessage::NewTransaction { transaction } => {
+                    telemetry.observe(ItemKind::Transaction, transaction.hash(), sent_at);
+                }
+                _ => {}
+            }
+        }
+
+        // In safe mode only checkpoints are processed; messages already
+        // in flight when we left the gossip topics are dropped
+        if self.safe_mode.is_active() && !matches!(network_msg, NetworkMessage::Checkpoint { .. }) {
+            debug!("Safe mode: ignoring gossip from {}", peer_id);
+            return Ok(());
+        }
+
         match network_msg {
             NetworkMessage::NewBlock { block } => {
                 debug!("Received new block from gossipsub");
@@ -663,8 +2505,11 @@ impl NetworkNode {
                 drop(blockchain);
 
                 // Try to append block (additional validation happens here)
+                let checkpoints = self.checkpoints.read().await;
                 let blockchain = self.blockchain.write().await;
-                match blockchain.append_block(&block, None) {
+                let state = self.read_state().await;
+                let state = state.as_deref();
+                match Self::connect_block(&blockchain, state, &checkpoints, &block) {
                     Ok(()) => {
                         let new_height = blockchain.get_chain_height()?;
                         info!("Added new block at height {}", new_height);
@@ -688,26 +2533,53 @@ impl NetworkNode {
             NetworkMessage::NewTransaction { transaction } => {
                 debug!("Received transaction from gossipsub");
 
-                // Add to mempool
-                let mut mempool = self.mempool.write().await;
-                match mempool.add_transaction(transaction.clone()).await {
+                // Skip transactions already pending or being checked, and
+                // drop new ones while too many checks are in flight
+                let hash = transaction.hash();
+                if self.verifying.contains(&hash)
+                    || self.mempool.read().await.get_transaction(&hash).is_some()
+                {
+                    return Ok(());
+                }
+                if self.verifying.len() >= MAX_PENDING_VERIFICATIONS {
+                    debug!("Dropping gossiped transaction: too many signature checks pending");
+                    return Ok(());
+                }
+                self.verifying.insert(hash);
+
+                // Verify the signature off the event loop so concurrent
+                // arrivals share one batch; the result comes back through
+                // `verified_rx`, which has room for every check in flight
+                let validator = self.mempool.read().await.validator();
+                let verified_tx = self.verified_tx.clone();
+                tokio::spawn(async move {
+                    let verified = validator.verify_signature(transaction).await;
+                    let _ = verified_tx.send((peer_id, hash, verified)).await;
+                });
+            }
+
+            NetworkMessage::Checkpoint { checkpoint } => {
+                let height = checkpoint.height;
+                if self.checkpoints.read().await.authority().is_none() {
+                    debug!(
+                        "Ignoring checkpoint at height {}: no authority configured",
+                        height
+                    );
+                    return Ok(());
+                }
+
+                match self.accept_checkpoint(checkpoint).await {
                     Ok(_) => {
-                        info!("Added transaction to mempool from network");
-                        
-                        // Reward peer for valid transaction
                         let mut reputation = self.reputation.write().await;
-                        reputation.reward_valid_tx(&peer_id);
-                        
-                        let _ = self
-                            .event_tx
-                            .send(NetworkEvent::NewTransaction(transaction));
+                        reputation.reward_valid_block(&peer_id);
                     }
                     Err(e) => {
-                        warn!("Failed to add transaction to mempool: {}", e);
-                        
-                        // Penalize for invalid transaction
+                        warn!(
+                            "Rejected checkpoint at height {} from {}: {}",
+                            height, peer_id, e
+                        );
                         let mut reputation = self.reputation.write().await;
-                        reputation.penalize_invalid_tx(&peer_id);
+                        reputation.penalize_invalid_block(&peer_id);
                     }
                 }
             }
@@ -721,6 +2593,7 @@ impl NetworkNode {
     }
 
     /// Handle request-response messages
+    #[tracing::instrument(name = "network_request", skip_all, fields(peer = %peer))]
     async fn handle_request_response(
         &mut self,
         peer: PeerId,
@@ -732,7 +2605,44 @@ impl NetworkNode {
             Message::Request {
                 request, channel, ..
             } => {
-                let response = self.handle_request(request).await;
+                if let NetworkRequest::DeliverTransaction { transaction } = &request {
+                    if let Err(message) = self.admit_delivery(&peer, transaction).await {
+                        let response = NetworkResponse::Error { message };
+                        let _ = self
+                            .swarm
+                            .behaviour_mut()
+                            .request_response
+                            .send_response(channel, response);
+                        return Ok(());
+                    }
+                }
+
+                // Refuse rather than queue, and before reading anything,
+                // when our upload budget cannot take the largest answer
+                let expected = request.expected_response_size();
+                let has_budget = {
+                    let mut bandwidth = self.bandwidth.write().await;
+                    let has_budget = bandwidth.has_budget(&peer, Direction::Upload, expected);
+                    if !has_budget {
+                        bandwidth.record_throttle(Direction::Upload);
+                    }
+                    has_budget
+                };
+                let response = if has_budget {
+                    let response = self.handle_request(request).await;
+                    self.bandwidth.write().await.record(
+                        Some(&peer),
+                        Direction::Upload,
+                        response.payload_size(),
+                    );
+                    response
+                } else {
+                    debug!("Upload budget spent, refusing request from {}", peer);
+                    NetworkResponse::Error {
+                        message: "Bandwidth budget exhausted, retry later".to_string(),
+                    }
+                };
+
                 let _ = self
                     .swarm
                     .behaviour_mut()
@@ -741,6 +2651,11 @@ impl NetworkNode {
             }
 
             Message::Response { response, .. } => {
+                self.bandwidth.write().await.record(
+                    Some(&peer),
+                    Direction::Download,
+                    response.payload_size(),
+                );
                 self.handle_response(peer, response).await?;
             }
         }
@@ -748,6 +2663,35 @@ impl NetworkNode {
         Ok(())
     }
 
+    /// Charge a directly delivered transaction to the sender's download
+    /// budget and transaction rate limit, as if it had been gossiped
+    async fn admit_delivery(
+        &self,
+        peer: &PeerId,
+        transaction: &Transaction,
+    ) -> std::result::Result<(), String> {
+        if self.reputation.write().await.is_banned(peer) {
+            return Err("Peer is banned".to_string());
+        }
+        let size = transaction.weight() as u64;
+        if !self
+            .bandwidth
+            .write()
+            .await
+            .try_consume(peer, Direction::Download, size)
+        {
+            debug!("Download budget spent, refusing delivery from {}", peer);
+            return Err("Bandwidth budget exhausted, retry later".to_string());
+        }
+        let mut rate_limiter = self.rate_limiter.write().await;
+        if !rate_limiter.check_rate_limit(peer, MessageType::Transaction) {
+            warn!("Rate limit exceeded for peer {}", peer);
+            self.reputation.write().await.penalize_rate_limit(peer);
+            return Err("Rate limit exceeded".to_string());
+        }
+        Ok(())
+    }
+
     /// Handle incoming requests
     async fn handle_request(&self, request: NetworkRequest) -> NetworkResponse {
         match request {
@@ -756,9 +2700,15 @@ impl NetworkNode {
                 max_blocks,
             } => {
                 let blockchain = self.blockchain.read().await;
+                if blockchain.is_thin() {
+                    return NetworkResponse::Error {
+                        message: StorageError::ThinNode("serving blocks".to_string()).to_string(),
+                    };
+                }
                 let mut blocks = Vec::new();
 
-                for height in start_height..start_height + max_blocks as u64 {
+                let max_blocks = max_blocks.min(MAX_BLOCKS_PER_REQUEST);
+                for height in start_height..start_height.saturating_add(max_blocks as u64) {
                     if let Ok(Some(block)) = blockchain.get_block_by_height(height) {
                         let config = bincode::config::standard();
                         if let Ok(serialized) = bincode::encode_to_vec(&block, config) {
@@ -776,10 +2726,11 @@ impl NetworkNode {
                 let blockchain = self.blockchain.read().await;
                 match blockchain.get_chain_height() {
                     Ok(height) => {
-                        if let Ok(Some(block)) = blockchain.get_block_by_height(height) {
+                        if let Ok(Some(block_hash)) = blockchain.get_block_hash_by_height(height) {
                             NetworkResponse::ChainTip {
                                 height,
-                                block_hash: block.hash(),
+                                block_hash,
+                                snapshot_heights: self.snapshots.heights().unwrap_or_default(),
                             }
                         } else {
                             NetworkResponse::Error {
@@ -793,6 +2744,92 @@ impl NetworkNode {
                 }
             }
 
+            NetworkRequest::GetHeaders { locator, stop_hash } => {
+                if locator.len() > opensyria_storage::MAX_LOCATOR_HASHES {
+                    return NetworkResponse::Error {
+                        message: format!("Locator has {} hashes", locator.len()),
+                    };
+                }
+
+                let stop_hash = (stop_hash != [0u8; 32]).then_some(stop_hash);
+                let blockchain = self.blockchain.read().await;
+                match blockchain.get_headers_after_locator(
+                    &locator,
+                    stop_hash,
+                    MAX_HEADERS_PER_REQUEST,
+                ) {
+                    Ok(headers) => NetworkResponse::Headers { headers },
+                    Err(e) => NetworkResponse::Error {
+                        message: format!("Failed to get headers: {}", e),
+                    },
+                }
+            }
+
+            NetworkRequest::GetSnapshotManifest { height } => {
+                match self.snapshots.manifest(height) {
+                    Ok(Some(manifest)) => NetworkResponse::SnapshotManifest { manifest },
+                    Ok(None) => NetworkResponse::Error {
+                        message: format!("No snapshot at height {}", height),
+                    },
+                    Err(e) => NetworkResponse::Error {
+                        message: format!("Failed to read snapshot: {}", e),
+                    },
+                }
+            }
+
+            NetworkRequest::GetSnapshotChunk { height, index } => {
+                match self.snapshots.chunk(height, index) {
+                    Ok(Some(data)) => NetworkResponse::SnapshotChunk {
+                        height,
+                        index,
+                        data,
+                    },
+                    Ok(None) => NetworkResponse::Error {
+                        message: format!("No chunk {} in snapshot at height {}", index, height),
+                    },
+                    Err(e) => NetworkResponse::Error {
+                        message: format!("Failed to read snapshot: {}", e),
+                    },
+                }
+            }
+
+            NetworkRequest::DeliverTransaction { transaction } => {
+                let tx_hash = transaction.hash();
+                if !self.announced_wallets.contains(&transaction.to) {
+                    return NetworkResponse::Error {
+                        message: "Recipient wallet is not announced by this node".to_string(),
+                    };
+                }
+                if self.safe_mode.is_active() {
+                    return NetworkResponse::TransactionDelivered {
+                        tx_hash,
+                        accepted: false,
+                    };
+                }
+
+                let mut mempool = self.mempool.write().await;
+                // Gossip may have got here first
+                if mempool.get_transaction(&tx_hash).is_some() {
+                    return NetworkResponse::TransactionDelivered {
+                        tx_hash,
+                        accepted: true,
+                    };
+                }
+                let accepted = match mempool.add_transaction(transaction.clone()).await {
+                    Ok(()) => {
+                        let _ = self
+                            .event_tx
+                            .send(NetworkEvent::NewTransaction(transaction));
+                        true
+                    }
+                    Err(e) => {
+                        debug!("Rejected directly delivered transaction: {}", e);
+                        false
+                    }
+                };
+                NetworkResponse::TransactionDelivered { tx_hash, accepted }
+            }
+
             NetworkRequest::GetPeers => {
                 let peers: Vec<String> = self
                     .peers
@@ -811,15 +2848,53 @@ impl NetworkNode {
             NetworkResponse::Blocks { blocks } => {
                 info!("Received {} blocks from {}", blocks.len(), peer);
 
+                // Keep the chain as it was when the anomaly was found
+                if self.safe_mode.is_active() {
+                    warn!("Safe mode: not applying {} synced blocks", blocks.len());
+                    return Ok(());
+                }
+
+                // The block at the snapshot height completes a state sync
+                if self.state_sync.as_ref().is_some_and(|session| {
+                    session.peer == peer
+                        && session.assembler.is_some()
+                        && session.base_block.is_none()
+                }) {
+                    let config = bincode::config::standard();
+                    match blocks
+                        .first()
+                        .and_then(|data| bincode::decode_from_slice::<Block, _>(data, config).ok())
+                    {
+                        Some((block, _)) => self.handle_snapshot_base_block(block).await,
+                        None => self.abort_state_sync("peer sent no base block", true).await,
+                    }
+                    return Ok(());
+                }
+
+                let config = bincode::config::standard();
+                let blocks: Vec<Block> = blocks
+                    .iter()
+                    .filter_map(|data| bincode::decode_from_slice::<Block, _>(data, config).ok())
+                    .map(|(block, _)| block)
+                    .collect();
+
+                // A longer branch forking below our tip replaces our blocks
+                let fork_height =
+                    Self::branch_fork_height(&*self.blockchain.read().await, &blocks)?;
+                if let Some(fork_height) = fork_height {
+                    self.reorganize(fork_height, blocks).await?;
+                    return Ok(());
+                }
+
+                let checkpoints = self.checkpoints.read().await;
                 let blockchain = self.blockchain.write().await;
+                let state = self.read_state().await;
+                let state = state.as_deref();
                 let mut added = 0;
 
-                for block_data in blocks {
-                    let config = bincode::config::standard();
-                    if let Ok((block, _)) = bincode::decode_from_slice::<Block, _>(&block_data, config) {
-                        if let Ok(()) = blockchain.append_block(&block, None) {
-                            added += 1;
-                        }
+                for block in &blocks {
+                    if let Ok(()) = Self::connect_block(&blockchain, state, &checkpoints, block) {
+                        added += 1;
                     }
                 }
 
@@ -828,26 +2903,153 @@ impl NetworkNode {
 
             NetworkResponse::ChainTip {
                 height,
-                block_hash: _,
+                block_hash,
+                snapshot_heights,
             } => {
                 info!("Peer {} has chain height {}", peer, height);
 
+                // Block sync waits until the snapshot in progress is restored
+                if self.state_sync.is_some() {
+                    return Ok(());
+                }
+
                 let local_height = self.get_chain_height().await?;
+                let snapshot_height = snapshot_heights
+                    .into_iter()
+                    .filter(|snapshot| *snapshot > 0 && *snapshot <= height)
+                    .filter(|snapshot| self.trusted_anchor(*snapshot).is_some())
+                    .max();
+                if let Some(snapshot_height) = snapshot_height {
+                    if self.config.state_sync
+                        && local_height == 0
+                        && !self.safe_mode.is_active()
+                        && self
+                            .read_state()
+                            .await
+                            .map(|state| state.is_empty())
+                            .transpose()?
+                            == Some(true)
+                    {
+                        self.start_state_sync(peer, snapshot_height);
+                        return Ok(());
+                    }
+                }
+
+                self.peer_heights.insert(peer, height);
                 if height > local_height {
+                    // Leave the download to a strictly nearer peer that is
+                    // also ahead; it is fetched from when its own tip arrives
+                    let defer = {
+                        let latency = self.latency.read().await;
+                        let ahead = self
+                            .peer_heights
+                            .iter()
+                            .filter(|(_, tip)| **tip > local_height)
+                            .map(|(peer_id, _)| *peer_id);
+                        latency
+                            .nearest(ahead)
+                            .is_some_and(|nearest| latency.compare(&nearest, &peer).is_lt())
+                    };
+                    if defer {
+                        debug!("Leaving block download to a nearer peer than {}", peer);
+                        return Ok(());
+                    }
+
                     info!(
-                        "Peer ahead by {} blocks, requesting sync",
+                        "Peer ahead by {} blocks, locating fork point",
                         height - local_height
                     );
-                    self.request_blocks(peer, local_height + 1, 500).await;
+                    self.request_headers(peer, block_hash).await?;
+                }
+            }
+
+            NetworkResponse::Headers { headers } => {
+                let Some(first) = headers.first() else {
+                    debug!("Peer {} has no headers beyond our locator", peer);
+                    return Ok(());
+                };
+
+                // Headers must link together and carry valid proof of work
+                let linked = headers
+                    .windows(2)
+                    .all(|pair| pair[1].previous_hash == pair[0].hash());
+                if !linked || !headers.iter().all(|header| header.meets_difficulty()) {
+                    warn!("Peer {} sent invalid header chain", peer);
+                    let mut reputation = self.reputation.write().await;
+                    reputation.penalize_invalid_block(&peer);
+                    return Ok(());
+                }
+
+                let fork_height = if first.previous_hash == [0u8; 32] {
+                    0
+                } else {
+                    match self
+                        .blockchain
+                        .read()
+                        .await
+                        .get_block_height_by_hash(&first.previous_hash)?
+                    {
+                        Some(height) => height,
+                        None => {
+                            warn!("Headers from {} do not connect to our chain", peer);
+                            return Ok(());
+                        }
+                    }
+                };
+
+                let local_height = self.get_chain_height().await?;
+                if fork_height < local_height {
+                    warn!(
+                        "Peer {} diverges from our chain at height {} ({} blocks deep)",
+                        peer,
+                        fork_height,
+                        local_height - fork_height
+                    );
                 }
+
+                info!(
+                    "Received {} headers from {}, requesting blocks from height {}",
+                    headers.len(),
+                    peer,
+                    fork_height + 1
+                );
+                self.request_blocks(peer, fork_height + 1, headers.len())
+                    .await;
             }
 
             NetworkResponse::Peers { peers } => {
                 debug!("Received {} peer addresses", peers.len());
             }
 
+            NetworkResponse::SnapshotManifest { manifest } => {
+                self.handle_snapshot_manifest(peer, manifest).await;
+            }
+
+            NetworkResponse::SnapshotChunk {
+                height,
+                index,
+                data,
+            } => {
+                self.handle_snapshot_chunk(peer, height, index, data).await;
+            }
+
+            NetworkResponse::TransactionDelivered { accepted, .. } => {
+                if accepted {
+                    debug!("Transaction delivered directly to {}", peer);
+                } else {
+                    warn!("Peer {} refused directly delivered transaction", peer);
+                }
+            }
+
             NetworkResponse::Error { message } => {
                 warn!("Peer {} returned error: {}", peer, message);
+                if self
+                    .state_sync
+                    .as_ref()
+                    .is_some_and(|session| session.peer == peer)
+                {
+                    self.abort_state_sync(&message, false).await;
+                }
             }
         }
 
@@ -4,6 +4,7 @@
 use libp2p::{Multiaddr, PeerId};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use anyhow::Result;
@@ -23,12 +24,80 @@ pub struct CachedPeer {
     pub failure_count: u32,
 }
 
+/// Peer cache file name inside the network data directory
+pub const PEER_CACHE_FILE: &str = "peer_cache.json";
+
+/// Persisted reputation of a peer
+/// سمعة النظير المحفوظة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedReputation {
+    /// Reputation score at the time of saving
+    pub score: i32,
+    /// Count of invalid blocks sent
+    pub invalid_blocks: u32,
+    /// Count of invalid transactions sent
+    pub invalid_txs: u32,
+    /// Count of valid blocks sent
+    pub valid_blocks: u32,
+    /// Count of valid transactions sent
+    pub valid_txs: u32,
+    /// Unix timestamp the ban expires at, if banned
+    pub banned_until: Option<u64>,
+    /// Why the peer was banned
+    #[serde(default)]
+    pub ban_reason: Option<String>,
+    /// Unix timestamp the record was saved at (decay resumes from here)
+    pub updated_at: u64,
+}
+
+/// Persisted ban of an IP address
+/// حظر عنوان IP المحفوظ
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedBan {
+    /// Unix timestamp the ban expires at
+    pub banned_until: u64,
+    /// Why the address was banned
+    pub reason: String,
+}
+
+/// Rolling ping latency of a peer
+/// متوسط زمن استجابة النظير
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedLatency {
+    /// Exponentially weighted average round trip in milliseconds
+    pub avg_ms: f64,
+    /// Most recent round trip in milliseconds
+    pub last_ms: f64,
+    /// Pings measured
+    pub samples: u64,
+    /// Unix timestamp of the most recent ping
+    pub updated_at: u64,
+}
+
+/// On-disk layout of the peer cache
+#[derive(Serialize, Deserialize)]
+struct PeerCacheFile {
+    peers: HashMap<String, CachedPeer>,
+    #[serde(default)]
+    reputation: HashMap<String, CachedReputation>,
+    #[serde(default)]
+    latency: HashMap<String, CachedLatency>,
+    #[serde(default)]
+    ip_bans: HashMap<IpAddr, CachedBan>,
+}
+
 /// Peer cache manager
 pub struct PeerCache {
     /// Cache file path
     cache_path: PathBuf,
     /// Cached peers
     peers: HashMap<String, CachedPeer>,
+    /// Reputation records keyed by peer ID
+    reputation: HashMap<String, CachedReputation>,
+    /// Ping latency averages keyed by peer ID
+    latency: HashMap<String, CachedLatency>,
+    /// IP bans keyed by address
+    ip_bans: HashMap<IpAddr, CachedBan>,
 }
 
 impl PeerCache {
@@ -37,6 +106,9 @@ impl PeerCache {
         Self {
             cache_path,
             peers: HashMap::new(),
+            reputation: HashMap::new(),
+            latency: HashMap::new(),
+            ip_bans: HashMap::new(),
         }
     }
 
@@ -47,10 +119,26 @@ impl PeerCache {
         }
 
         let data = std::fs::read_to_string(&self.cache_path)?;
-        let peers: HashMap<String, CachedPeer> = serde_json::from_str(&data)?;
-        self.peers = peers;
-
-        tracing::info!("Loaded {} cached peers", self.peers.len());
+        let file = match serde_json::from_str::<PeerCacheFile>(&data) {
+            Ok(file) => file,
+            // Caches written before reputation was persisted are a bare peer map
+            Err(_) => PeerCacheFile {
+                peers: serde_json::from_str(&data)?,
+                reputation: HashMap::new(),
+                latency: HashMap::new(),
+                ip_bans: HashMap::new(),
+            },
+        };
+        self.peers = file.peers;
+        self.reputation = file.reputation;
+        self.latency = file.latency;
+        self.ip_bans = file.ip_bans;
+
+        tracing::info!(
+            "Loaded {} cached peers, {} reputation records",
+            self.peers.len(),
+            self.reputation.len()
+        );
         Ok(())
     }
 
@@ -61,7 +149,13 @@ impl PeerCache {
             std::fs::create_dir_all(parent)?;
         }
 
-        let data = serde_json::to_string_pretty(&self.peers)?;
+        let file = PeerCacheFile {
+            peers: self.peers.clone(),
+            reputation: self.reputation.clone(),
+            latency: self.latency.clone(),
+            ip_bans: self.ip_bans.clone(),
+        };
+        let data = serde_json::to_string_pretty(&file)?;
         std::fs::write(&self.cache_path, data)?;
 
         tracing::debug!("Saved {} peers to cache", self.peers.len());
@@ -170,6 +264,51 @@ impl PeerCache {
         }
     }
 
+    /// Replace the stored reputation records
+    pub fn set_reputation(&mut self, records: Vec<(PeerId, CachedReputation)>) {
+        self.reputation = records
+            .into_iter()
+            .map(|(peer_id, record)| (peer_id.to_string(), record))
+            .collect();
+    }
+
+    /// Stored reputation records (entries with unparsable peer IDs are skipped)
+    pub fn reputation(&self) -> Vec<(PeerId, CachedReputation)> {
+        self.reputation
+            .iter()
+            .filter_map(|(key, record)| Some((key.parse().ok()?, record.clone())))
+            .collect()
+    }
+
+    /// Replace the stored latency averages
+    pub fn set_latency(&mut self, records: Vec<(PeerId, CachedLatency)>) {
+        self.latency = records
+            .into_iter()
+            .map(|(peer_id, record)| (peer_id.to_string(), record))
+            .collect();
+    }
+
+    /// Stored latency averages (entries with unparsable peer IDs are skipped)
+    pub fn latency(&self) -> Vec<(PeerId, CachedLatency)> {
+        self.latency
+            .iter()
+            .filter_map(|(key, record)| Some((key.parse().ok()?, record.clone())))
+            .collect()
+    }
+
+    /// Replace the stored IP bans
+    pub fn set_ip_bans(&mut self, bans: Vec<(IpAddr, CachedBan)>) {
+        self.ip_bans = bans.into_iter().collect();
+    }
+
+    /// Stored IP bans
+    pub fn ip_bans(&self) -> Vec<(IpAddr, CachedBan)> {
+        self.ip_bans
+            .iter()
+            .map(|(ip, ban)| (*ip, ban.clone()))
+            .collect()
+    }
+
     /// Get total cached peer count
     pub fn len(&self) -> usize {
         self.peers.len()
@@ -205,6 +344,79 @@ mod tests {
         assert_eq!(cache2.len(), 1);
     }
 
+    #[test]
+    fn test_peer_cache_reputation_roundtrip() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("peers.json");
+
+        let peer_id = PeerId::random();
+        let record = CachedReputation {
+            score: -120,
+            invalid_blocks: 12,
+            invalid_txs: 0,
+            valid_blocks: 0,
+            valid_txs: 0,
+            banned_until: Some(2_000_000_000),
+            ban_reason: Some("Banned by operator".to_string()),
+            updated_at: 1_700_000_000,
+        };
+        let ip: IpAddr = "198.51.100.4".parse().unwrap();
+        let ban = CachedBan {
+            banned_until: 2_000_000_000,
+            reason: "Eclipse attempt".to_string(),
+        };
+
+        let mut cache = PeerCache::new(cache_path.clone());
+        cache.set_reputation(vec![(peer_id, record.clone())]);
+        cache.set_ip_bans(vec![(ip, ban.clone())]);
+        cache.save().unwrap();
+
+        let mut cache2 = PeerCache::new(cache_path);
+        cache2.load().unwrap();
+        assert_eq!(cache2.reputation(), vec![(peer_id, record)]);
+        assert_eq!(cache2.ip_bans(), vec![(ip, ban)]);
+    }
+
+    #[test]
+    fn test_peer_cache_latency_roundtrip() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("peers.json");
+
+        let peer_id = PeerId::random();
+        let record = CachedLatency {
+            avg_ms: 42.5,
+            last_ms: 38.0,
+            samples: 7,
+            updated_at: 1_700_000_000,
+        };
+
+        let mut cache = PeerCache::new(cache_path.clone());
+        cache.set_latency(vec![(peer_id, record.clone())]);
+        cache.save().unwrap();
+
+        let mut cache2 = PeerCache::new(cache_path);
+        cache2.load().unwrap();
+        assert_eq!(cache2.latency(), vec![(peer_id, record)]);
+    }
+
+    #[test]
+    fn test_peer_cache_loads_legacy_format() {
+        let dir = tempdir().unwrap();
+        let cache_path = dir.path().join("peers.json");
+
+        let peer_id = PeerId::random();
+        let legacy = format!(
+            r#"{{"{id}":{{"peer_id":"{id}","address":"/ip4/127.0.0.1/tcp/9000","last_seen":1,"success_count":3,"failure_count":0}}}}"#,
+            id = peer_id
+        );
+        std::fs::write(&cache_path, legacy).unwrap();
+
+        let mut cache = PeerCache::new(cache_path);
+        cache.load().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.reputation().is_empty());
+    }
+
     #[test]
     fn test_peer_cache_reliability_tracking() {
         let dir = tempdir().unwrap();
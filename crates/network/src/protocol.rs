@@ -1,4 +1,6 @@
-use opensyria_core::{Block, Transaction};
+use opensyria_consensus::SignedCheckpoint;
+use opensyria_core::{block::BlockHeader, Block, Transaction};
+use opensyria_storage::MAX_LOCATOR_HASHES;
 use serde::{Deserialize, Serialize};
 
 /// Maximum gossipsub message size: 512KB (reduced from 2MB for DoS protection)
@@ -8,6 +10,9 @@ pub const MAX_GOSSIPSUB_MESSAGE_SIZE: usize = 512 * 1024;
 /// Maximum blocks that can be requested in a single message
 pub const MAX_BLOCKS_PER_REQUEST: usize = 50;
 
+/// Maximum headers returned for a single GetHeaders request
+pub const MAX_HEADERS_PER_REQUEST: usize = 2000;
+
 /// Maximum bincode deserialization size (1MB)
 pub const MAX_BINCODE_SIZE: u64 = 1024 * 1024;
 
@@ -46,6 +51,15 @@ pub enum NetworkMessage {
     /// Response with requested blocks
     Blocks { blocks: Vec<Block> },
 
+    /// Request headers after the first locator hash on the peer's chain
+    GetHeaders {
+        locator: Vec<[u8; 32]>, // Validated against MAX_LOCATOR_HASHES on deserialization
+        stop_hash: [u8; 32],    // All zeros = no stop hash
+    },
+
+    /// Response with consecutive block headers
+    Headers { headers: Vec<BlockHeader> },
+
     /// Request the current chain tip height
     GetChainTip,
 
@@ -63,6 +77,9 @@ pub enum NetworkMessage {
 
     /// Response with peer list
     Peers { peers: Vec<String> },
+
+    /// Broadcast a checkpoint signed by checkpoint authority keys
+    Checkpoint { checkpoint: SignedCheckpoint },
 }
 
 /// Protocol configuration
@@ -147,6 +164,16 @@ impl NetworkMessage {
             }
         }
         
+        if let NetworkMessage::GetHeaders { locator, .. } = &msg {
+            if locator.len() > MAX_LOCATOR_HASHES {
+                return Err(ValidationError::DeserializationFailed(format!(
+                    "locator length {} exceeds limit {}",
+                    locator.len(),
+                    MAX_LOCATOR_HASHES
+                )));
+            }
+        }
+
         Ok(msg)
     }
 }
@@ -154,6 +181,7 @@ impl NetworkMessage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use opensyria_core::constants::CHAIN_ID_MAINNET;
     use opensyria_core::crypto::KeyPair;
 
     #[test]
@@ -178,6 +206,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_headers_locator_limit() {
+        let msg = NetworkMessage::GetHeaders {
+            locator: vec![[1u8; 32]; MAX_LOCATOR_HASHES],
+            stop_hash: [0u8; 32],
+        };
+        assert!(NetworkMessage::from_bytes(&msg.to_bytes().unwrap()).is_ok());
+
+        let msg = NetworkMessage::GetHeaders {
+            locator: vec![[1u8; 32]; MAX_LOCATOR_HASHES + 1],
+            stop_hash: [0u8; 32],
+        };
+        assert!(NetworkMessage::from_bytes(&msg.to_bytes().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_serialize_signed_checkpoint() {
+        let signer = KeyPair::generate();
+        let mut checkpoint = SignedCheckpoint::new(CHAIN_ID_MAINNET, 10_000, [7u8; 32]);
+        checkpoint.sign(&signer);
+
+        let msg = NetworkMessage::Checkpoint {
+            checkpoint: checkpoint.clone(),
+        };
+        let bytes = msg.to_bytes().unwrap();
+
+        match NetworkMessage::from_bytes(&bytes).unwrap() {
+            NetworkMessage::Checkpoint {
+                checkpoint: decoded,
+            } => {
+                assert_eq!(decoded, checkpoint);
+            }
+            _ => panic!("Wrong message type"),
+        }
+    }
+
     #[test]
     fn test_serialize_new_transaction() {
         let keypair = KeyPair::generate();
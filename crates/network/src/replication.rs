@@ -0,0 +1,965 @@
+//! Operator replication from a primary node to trusted secondaries
+//! النسخ المتماثل من العقدة الرئيسية إلى العقد الثانوية الموثوقة
+//!
+//! A secondary connects to the primary's replication listener over plain
+//! TCP. Both sides prove knowledge of a pre-shared secret with an
+//! HMAC-SHA256 challenge-response and derive a session key from the two
+//! nonces; every later frame carries an HMAC over its direction, a counter
+//! and the payload, so frames cannot be forged, replayed or reordered.
+//! Frames are not encrypted: run replication over a private network or a
+//! VPN when the chain data itself must stay confidential.
+//!
+//! The secondary downloads a RocksDB checkpoint of the blockchain and state
+//! databases (see [`opensyria_storage::replication`]), verifies every file
+//! against the manifest, then tails the primary's write-ahead log. When the
+//! WAL no longer reaches back far enough it starts over from a fresh
+//! checkpoint. Once caught up it compares content digests of both databases
+//! with the primary at the same sequence number.
+
+use anyhow::{anyhow, bail, Context, Result};
+use hmac::{Hmac, Mac};
+use opensyria_storage::replication::CheckpointFile;
+use opensyria_storage::{
+    BlockchainStorage, CheckpointManifest, ContentDigest, StateStorage, WalBatch,
+};
+use rand::RngCore;
+use sha2::Sha256;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Protocol identifier sent in the client greeting
+const PROTOCOL_MAGIC: [u8; 8] = *b"OSYREPL1";
+
+/// Shortest accepted pre-shared secret in bytes
+pub const MIN_SECRET_BYTES: usize = 32;
+
+/// Largest frame accepted from the other side
+pub const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+
+/// Bytes of checkpoint file content sent per chunk
+pub const CHECKPOINT_CHUNK_BYTES: u32 = 1024 * 1024;
+
+/// WAL data returned per update request (a single batch may exceed it)
+const WAL_RESPONSE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Pause between WAL polls once the secondary has caught up
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Pause before reconnecting after a failed session in follow mode
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Time allowed for the handshake to complete
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Digest comparisons attempted before giving up on a busy primary
+const DIGEST_ROUNDS: usize = 10;
+
+/// Cursor file kept next to the replicated databases
+const CURSOR_FILE: &str = "replication.cursor";
+
+/// Directory receiving a checkpoint before it replaces the databases
+const INCOMING_DIR: &str = "replication.incoming";
+
+/// Which of the node's databases a request refers to
+/// قاعدة البيانات المقصودة بالطلب
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum Database {
+    Blockchain,
+    State,
+}
+
+impl Database {
+    /// Both databases, in replication order
+    pub const ALL: [Database; 2] = [Database::Blockchain, Database::State];
+
+    /// Directory name of the database inside a node's data directory
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            Database::Blockchain => "blockchain",
+            Database::State => "state",
+        }
+    }
+}
+
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+enum Handshake {
+    Hello { magic: [u8; 8], nonce: [u8; 32] },
+    Challenge { nonce: [u8; 32], proof: [u8; 32] },
+    Proof { proof: [u8; 32] },
+}
+
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+enum Request {
+    /// Take a new checkpoint of both databases
+    Checkpoint,
+    /// Read part of a file of the current checkpoint
+    Chunk {
+        database: Database,
+        file: u32,
+        offset: u64,
+    },
+    /// WAL batches after `cursor`
+    Updates { database: Database, cursor: u64 },
+    /// Content digest of a database
+    Digest { database: Database },
+}
+
+#[derive(Debug, bincode::Encode, bincode::Decode)]
+enum Response {
+    Checkpoint {
+        blockchain: CheckpointManifest,
+        state: CheckpointManifest,
+    },
+    Chunk(Vec<u8>),
+    /// `None` when the primary's WAL no longer reaches back to the cursor
+    Updates(Option<Vec<WalBatch>>),
+    Digest(ContentDigest),
+    Error(String),
+}
+
+/// Read a pre-shared replication secret from a file
+/// قراءة السر المشترك للنسخ المتماثل من ملف
+///
+/// Surrounding whitespace is ignored so the file can be produced with
+/// e.g. `openssl rand -hex 32 > replication.key`.
+pub fn load_secret(path: &Path) -> Result<Vec<u8>> {
+    let content = std::fs::read(path)
+        .with_context(|| format!("reading replication secret {}", path.display()))?;
+    let secret = content.trim_ascii().to_vec();
+    if secret.len() < MIN_SECRET_BYTES {
+        bail!(
+            "replication secret must be at least {} bytes, got {}",
+            MIN_SECRET_BYTES,
+            secret.len()
+        );
+    }
+    Ok(secret)
+}
+
+fn mac(key: &[u8], parts: &[&[u8]]) -> HmacSha256 {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac
+}
+
+fn tag(key: &[u8], parts: &[&[u8]]) -> [u8; 32] {
+    mac(key, parts).finalize().into_bytes().into()
+}
+
+/// Which end of the connection sends a frame
+#[derive(Clone, Copy)]
+enum Side {
+    Primary,
+    Secondary,
+}
+
+impl Side {
+    fn byte(self) -> u8 {
+        match self {
+            Side::Primary => 1,
+            Side::Secondary => 2,
+        }
+    }
+
+    fn peer(self) -> Side {
+        match self {
+            Side::Primary => Side::Secondary,
+            Side::Secondary => Side::Primary,
+        }
+    }
+}
+
+/// Authenticated frame channel over a TCP stream
+struct Channel {
+    stream: TcpStream,
+    key: [u8; 32],
+    side: Side,
+    sent: u64,
+    received: u64,
+}
+
+async fn write_raw(stream: &mut TcpStream, payload: &[u8]) -> Result<()> {
+    stream
+        .write_all(&(payload.len() as u32).to_be_bytes())
+        .await?;
+    stream.write_all(payload).await?;
+    Ok(())
+}
+
+async fn read_raw(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let len = stream.read_u32().await? as usize;
+    if len > MAX_FRAME_BYTES {
+        bail!("frame of {} bytes exceeds the limit", len);
+    }
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload).await?;
+    Ok(payload)
+}
+
+fn encode<T: bincode::Encode>(value: &T) -> Result<Vec<u8>> {
+    Ok(bincode::encode_to_vec(value, bincode::config::standard())?)
+}
+
+fn decode<T: bincode::Decode<()>>(bytes: &[u8]) -> Result<T> {
+    let config = bincode::config::standard().with_limit::<MAX_FRAME_BYTES>();
+    Ok(bincode::decode_from_slice(bytes, config)?.0)
+}
+
+impl Channel {
+    /// Run the client half of the handshake
+    async fn connect(mut stream: TcpStream, secret: &[u8]) -> Result<Self> {
+        let mut client_nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut client_nonce);
+        let hello = Handshake::Hello {
+            magic: PROTOCOL_MAGIC,
+            nonce: client_nonce,
+        };
+        write_raw(&mut stream, &encode(&hello)?).await?;
+
+        let (server_nonce, proof) = match decode(&read_raw(&mut stream).await?)? {
+            Handshake::Challenge { nonce, proof } => (nonce, proof),
+            _ => bail!("unexpected handshake message"),
+        };
+        mac(secret, &[b"primary", &client_nonce, &server_nonce])
+            .verify_slice(&proof)
+            .map_err(|_| anyhow!("primary failed to prove the replication secret"))?;
+
+        let proof = tag(secret, &[b"secondary", &client_nonce, &server_nonce]);
+        write_raw(&mut stream, &encode(&Handshake::Proof { proof })?).await?;
+
+        Ok(Self::new(
+            stream,
+            secret,
+            &client_nonce,
+            &server_nonce,
+            Side::Secondary,
+        ))
+    }
+
+    /// Run the server half of the handshake
+    async fn accept(mut stream: TcpStream, secret: &[u8]) -> Result<Self> {
+        let client_nonce = match decode(&read_raw(&mut stream).await?)? {
+            Handshake::Hello { magic, nonce } if magic == PROTOCOL_MAGIC => nonce,
+            _ => bail!("not a replication client"),
+        };
+        let mut server_nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut server_nonce);
+        let proof = tag(secret, &[b"primary", &client_nonce, &server_nonce]);
+        let challenge = Handshake::Challenge {
+            nonce: server_nonce,
+            proof,
+        };
+        write_raw(&mut stream, &encode(&challenge)?).await?;
+
+        let proof = match decode(&read_raw(&mut stream).await?)? {
+            Handshake::Proof { proof } => proof,
+            _ => bail!("unexpected handshake message"),
+        };
+        mac(secret, &[b"secondary", &client_nonce, &server_nonce])
+            .verify_slice(&proof)
+            .map_err(|_| anyhow!("secondary failed to prove the replication secret"))?;
+
+        Ok(Self::new(
+            stream,
+            secret,
+            &client_nonce,
+            &server_nonce,
+            Side::Primary,
+        ))
+    }
+
+    fn new(
+        stream: TcpStream,
+        secret: &[u8],
+        client_nonce: &[u8; 32],
+        server_nonce: &[u8; 32],
+        side: Side,
+    ) -> Self {
+        Self {
+            stream,
+            key: tag(secret, &[b"session", client_nonce, server_nonce]),
+            side,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    async fn send<T: bincode::Encode>(&mut self, message: &T) -> Result<()> {
+        let mut frame = encode(message)?;
+        let counter = self.sent.to_be_bytes();
+        let mac = tag(&self.key, &[&[self.side.byte()], &counter, &frame]);
+        frame.extend_from_slice(&mac);
+        write_raw(&mut self.stream, &frame).await?;
+        self.sent += 1;
+        Ok(())
+    }
+
+    async fn receive<T: bincode::Decode<()>>(&mut self) -> Result<T> {
+        let mut frame = read_raw(&mut self.stream).await?;
+        if frame.len() < 32 {
+            bail!("truncated replication frame");
+        }
+        let received_tag = frame.split_off(frame.len() - 32);
+        let counter = self.received.to_be_bytes();
+        mac(&self.key, &[&[self.side.peer().byte()], &counter, &frame])
+            .verify_slice(&received_tag)
+            .map_err(|_| anyhow!("replication frame failed authentication"))?;
+        self.received += 1;
+        decode(&frame)
+    }
+}
+
+/// Serves checkpoints and WAL updates of a primary node to its secondaries
+/// خادم النسخ المتماثل في العقدة الرئيسية
+pub struct ReplicationServer {
+    secret: Arc<Vec<u8>>,
+    blockchain: Arc<RwLock<BlockchainStorage>>,
+    state: Arc<RwLock<StateStorage>>,
+    work_dir: PathBuf,
+}
+
+impl ReplicationServer {
+    /// Create a server; checkpoints are written below `work_dir`, which must
+    /// be on the same filesystem as the databases so files are hard-linked
+    pub fn new(
+        secret: Vec<u8>,
+        blockchain: Arc<RwLock<BlockchainStorage>>,
+        state: Arc<RwLock<StateStorage>>,
+        work_dir: PathBuf,
+    ) -> Self {
+        Self {
+            secret: Arc::new(secret),
+            blockchain,
+            state,
+            work_dir,
+        }
+    }
+
+    /// Accept secondaries until the listener fails
+    /// قبول اتصالات العقد الثانوية
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        // Checkpoints left over from a previous run are never reused
+        if self.work_dir.exists() {
+            std::fs::remove_dir_all(&self.work_dir)?;
+        }
+        let server = Arc::new(self);
+        let sessions = AtomicU64::new(0);
+        loop {
+            let (stream, addr) = listener.accept().await?;
+            let id = sessions.fetch_add(1, Ordering::Relaxed);
+            let server = server.clone();
+            tokio::spawn(async move {
+                let dir = server.work_dir.join(format!("session-{}", id));
+                match server.session(stream, &dir).await {
+                    Ok(()) => info!("Replication session with {} closed", addr),
+                    Err(e) => warn!("Replication session with {} failed: {}", addr, e),
+                }
+                if dir.exists() {
+                    let _ = std::fs::remove_dir_all(&dir);
+                }
+            });
+        }
+    }
+
+    async fn session(&self, stream: TcpStream, dir: &Path) -> Result<()> {
+        let mut channel =
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, Channel::accept(stream, &self.secret))
+                .await
+                .map_err(|_| anyhow!("handshake timed out"))??;
+        info!("Replication secondary authenticated");
+
+        let mut manifests: Option<(CheckpointManifest, CheckpointManifest)> = None;
+        loop {
+            let request: Request = match channel.receive().await {
+                Ok(request) => request,
+                // Secondary hung up between requests
+                Err(e) if is_eof(&e) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let response = match self.handle(request, dir, &mut manifests).await {
+                Ok(response) => response,
+                Err(e) => Response::Error(e.to_string()),
+            };
+            channel.send(&response).await?;
+        }
+    }
+
+    async fn handle(
+        &self,
+        request: Request,
+        dir: &Path,
+        manifests: &mut Option<(CheckpointManifest, CheckpointManifest)>,
+    ) -> Result<Response> {
+        let blockchain = self.blockchain.clone();
+        let state = self.state.clone();
+        let dir = dir.to_path_buf();
+        match request {
+            Request::Checkpoint => {
+                let taken = tokio::task::spawn_blocking(move || -> Result<_> {
+                    if dir.exists() {
+                        std::fs::remove_dir_all(&dir)?;
+                    }
+                    std::fs::create_dir_all(&dir)?;
+                    let blockchain = blockchain
+                        .blocking_read()
+                        .replication()
+                        .create_checkpoint(&dir.join(Database::Blockchain.dir_name()))?;
+                    let state = state
+                        .blocking_read()
+                        .replication()
+                        .create_checkpoint(&dir.join(Database::State.dir_name()))?;
+                    Ok((blockchain, state))
+                })
+                .await??;
+                info!(
+                    "Replication checkpoint taken: {} bytes",
+                    taken.0.total_bytes() + taken.1.total_bytes()
+                );
+                *manifests = Some(taken.clone());
+                Ok(Response::Checkpoint {
+                    blockchain: taken.0,
+                    state: taken.1,
+                })
+            }
+            Request::Chunk {
+                database,
+                file,
+                offset,
+            } => {
+                let (blockchain, state) = manifests
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("no checkpoint taken"))?;
+                let manifest = match database {
+                    Database::Blockchain => blockchain,
+                    Database::State => state,
+                };
+                let file = manifest
+                    .files
+                    .get(file as usize)
+                    .ok_or_else(|| anyhow!("no such checkpoint file"))?;
+                let path = dir.join(database.dir_name()).join(&file.name);
+                let chunk =
+                    tokio::task::spawn_blocking(move || read_chunk(&path, offset)).await??;
+                Ok(Response::Chunk(chunk))
+            }
+            Request::Updates { database, cursor } => {
+                let updates = tokio::task::spawn_blocking(move || match database {
+                    Database::Blockchain => blockchain
+                        .blocking_read()
+                        .replication()
+                        .updates_since(cursor, WAL_RESPONSE_BYTES),
+                    Database::State => state
+                        .blocking_read()
+                        .replication()
+                        .updates_since(cursor, WAL_RESPONSE_BYTES),
+                })
+                .await??;
+                Ok(Response::Updates(updates))
+            }
+            Request::Digest { database } => {
+                // The scan reads a RocksDB snapshot, so the storage lock is
+                // released before it starts and writers are not held up
+                let replication = match database {
+                    Database::Blockchain => blockchain.read().await.replication(),
+                    Database::State => state.read().await.replication(),
+                };
+                let digest =
+                    tokio::task::spawn_blocking(move || replication.content_digest()).await??;
+                Ok(Response::Digest(digest))
+            }
+        }
+    }
+}
+
+fn read_chunk(path: &Path, offset: u64) -> Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut chunk = Vec::with_capacity(CHECKPOINT_CHUNK_BYTES as usize);
+    file.take(u64::from(CHECKPOINT_CHUNK_BYTES))
+        .read_to_end(&mut chunk)?;
+    Ok(chunk)
+}
+
+fn is_eof(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::UnexpectedEof)
+}
+
+/// Primary WAL positions the secondary's databases have reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+struct ReplicaCursor {
+    blockchain: u64,
+    state: u64,
+}
+
+impl ReplicaCursor {
+    fn get(&self, database: Database) -> u64 {
+        match database {
+            Database::Blockchain => self.blockchain,
+            Database::State => self.state,
+        }
+    }
+
+    fn set(&mut self, database: Database, sequence: u64) {
+        match database {
+            Database::Blockchain => self.blockchain = sequence,
+            Database::State => self.state = sequence,
+        }
+    }
+}
+
+/// Outcome of one replication pass
+/// نتيجة جولة نسخ متماثل
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReplicationReport {
+    /// Whether a fresh checkpoint was installed
+    pub bootstrapped: bool,
+    /// WAL batches applied across both databases
+    pub batches_applied: u64,
+    /// Verified primary sequence numbers (blockchain, state)
+    pub sequences: (u64, u64),
+    /// Verified key/value entries (blockchain, state)
+    pub entries: (u64, u64),
+}
+
+/// Databases of a secondary opened for applying updates
+struct Replica {
+    blockchain: BlockchainStorage,
+    state: StateStorage,
+}
+
+impl Replica {
+    fn apply(&self, database: Database, cursor: u64, batches: &[WalBatch]) -> Result<u64> {
+        Ok(match database {
+            Database::Blockchain => self.blockchain.replication().apply(cursor, batches)?,
+            Database::State => self.state.replication().apply(cursor, batches)?,
+        })
+    }
+
+    fn digest(&self, database: Database) -> Result<ContentDigest> {
+        Ok(match database {
+            Database::Blockchain => self.blockchain.replication().content_digest()?,
+            Database::State => self.state.replication().content_digest()?,
+        })
+    }
+}
+
+/// Keeps a secondary's databases in step with a primary
+/// مزامنة قواعد بيانات العقدة الثانوية مع العقدة الرئيسية
+///
+/// `data_dir` uses the same layout as a network node (`blockchain/` and
+/// `state/`), so a secondary can be started as a node after replication.
+/// It must not be opened by a running node while the follower writes to it.
+pub struct ReplicationFollower {
+    secret: Vec<u8>,
+    data_dir: PathBuf,
+}
+
+impl ReplicationFollower {
+    pub fn new(secret: Vec<u8>, data_dir: PathBuf) -> Self {
+        Self { secret, data_dir }
+    }
+
+    /// Catch up with the primary once and verify both databases
+    /// اللحاق بالعقدة الرئيسية مرة واحدة والتحقق من قواعد البيانات
+    pub async fn sync_once<A: ToSocketAddrs>(&self, primary: A) -> Result<ReplicationReport> {
+        let mut channel = self.connect(primary).await?;
+        let (mut replica, mut cursor, bootstrapped) = self.open(&mut channel).await?;
+        let mut report = ReplicationReport {
+            bootstrapped,
+            batches_applied: 0,
+            sequences: (0, 0),
+            entries: (0, 0),
+        };
+
+        for _ in 0..DIGEST_ROUNDS {
+            let (applied, gap) = self.catch_up(&mut channel, &replica, &mut cursor).await?;
+            report.batches_applied += applied;
+            if gap {
+                // WAL purged on the primary: start over from a new checkpoint
+                drop(replica);
+                self.reset()?;
+                (replica, cursor, _) = self.open(&mut channel).await?;
+                report.bootstrapped = true;
+                continue;
+            }
+            if let Some(verified) = self.verify(&mut channel, &replica, &cursor).await? {
+                report.sequences = (verified[0].sequence, verified[1].sequence);
+                report.entries = (verified[0].entries, verified[1].entries);
+                return Ok(report);
+            }
+        }
+        bail!("primary kept writing; could not compare digests at a common sequence")
+    }
+
+    /// Replicate continuously, reconnecting after failures
+    /// النسخ المتماثل المستمر مع إعادة الاتصال
+    pub async fn follow(&self, primary: &str) -> Result<()> {
+        loop {
+            if let Err(e) = self.follow_session(primary).await {
+                warn!("Replication from {} interrupted: {}", primary, e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    }
+
+    async fn follow_session(&self, primary: &str) -> Result<()> {
+        let report = self.sync_once(primary).await?;
+        info!(
+            "Replica verified at blockchain sequence {}, state sequence {}",
+            report.sequences.0, report.sequences.1
+        );
+
+        let mut channel = self.connect(primary).await?;
+        let (mut replica, mut cursor, _) = self.open(&mut channel).await?;
+        loop {
+            let (applied, gap) = self.catch_up(&mut channel, &replica, &mut cursor).await?;
+            if gap {
+                drop(replica);
+                self.reset()?;
+                (replica, cursor, _) = self.open(&mut channel).await?;
+                continue;
+            }
+            if applied == 0 {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        }
+    }
+
+    async fn connect<A: ToSocketAddrs>(&self, primary: A) -> Result<Channel> {
+        let stream = TcpStream::connect(primary).await?;
+        stream.set_nodelay(true)?;
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, Channel::connect(stream, &self.secret))
+            .await
+            .map_err(|_| anyhow!("handshake timed out"))?
+    }
+
+    /// Open the local databases, installing a checkpoint first if needed
+    async fn open(&self, channel: &mut Channel) -> Result<(Replica, ReplicaCursor, bool)> {
+        let (cursor, bootstrapped) = match self.load_cursor()? {
+            Some(cursor) => (cursor, false),
+            None => (self.bootstrap(channel).await?, true),
+        };
+        let replica = Replica {
+            blockchain: BlockchainStorage::open(self.data_dir.join("blockchain"))?,
+            state: StateStorage::open(self.data_dir.join("state"))?,
+        };
+        Ok((replica, cursor, bootstrapped))
+    }
+
+    /// Download and verify a checkpoint, then move it into place
+    async fn bootstrap(&self, channel: &mut Channel) -> Result<ReplicaCursor> {
+        channel.send(&Request::Checkpoint).await?;
+        let (blockchain, state) = match channel.receive().await? {
+            Response::Checkpoint { blockchain, state } => (blockchain, state),
+            other => bail!(
+                "unexpected response to checkpoint request: {}",
+                describe(&other)
+            ),
+        };
+        info!(
+            "Downloading replication checkpoint ({} bytes)",
+            blockchain.total_bytes() + state.total_bytes()
+        );
+
+        let incoming = self.data_dir.join(INCOMING_DIR);
+        if incoming.exists() {
+            std::fs::remove_dir_all(&incoming)?;
+        }
+        let mut cursor = ReplicaCursor {
+            blockchain: 0,
+            state: 0,
+        };
+        for (database, manifest) in [
+            (Database::Blockchain, &blockchain),
+            (Database::State, &state),
+        ] {
+            manifest.validate()?;
+            let dir = incoming.join(database.dir_name());
+            std::fs::create_dir_all(&dir)?;
+            for (index, file) in manifest.files.iter().enumerate() {
+                download(channel, database, index as u32, file, &dir.join(&file.name)).await?;
+            }
+            manifest.verify(&dir)?;
+            cursor.set(database, manifest.sequence);
+        }
+
+        self.reset()?;
+        for database in Database::ALL {
+            std::fs::rename(
+                incoming.join(database.dir_name()),
+                self.data_dir.join(database.dir_name()),
+            )?;
+        }
+        std::fs::remove_dir_all(&incoming)?;
+        self.save_cursor(&cursor)?;
+        info!("Replication checkpoint installed");
+        Ok(cursor)
+    }
+
+    /// Apply WAL updates until both databases are level with the primary;
+    /// returns the number of batches applied and whether a gap was hit
+    async fn catch_up(
+        &self,
+        channel: &mut Channel,
+        replica: &Replica,
+        cursor: &mut ReplicaCursor,
+    ) -> Result<(u64, bool)> {
+        let mut applied = 0u64;
+        for database in Database::ALL {
+            loop {
+                let position = cursor.get(database);
+                channel
+                    .send(&Request::Updates {
+                        database,
+                        cursor: position,
+                    })
+                    .await?;
+                let batches = match channel.receive().await? {
+                    Response::Updates(Some(batches)) => batches,
+                    Response::Updates(None) => return Ok((applied, true)),
+                    other => bail!(
+                        "unexpected response to update request: {}",
+                        describe(&other)
+                    ),
+                };
+                if batches.is_empty() {
+                    break;
+                }
+                cursor.set(database, replica.apply(database, position, &batches)?);
+                self.save_cursor(cursor)?;
+                applied += batches.len() as u64;
+            }
+        }
+        Ok((applied, false))
+    }
+
+    /// Compare both databases with the primary; `None` when the primary has
+    /// moved past the secondary's cursor in the meantime
+    async fn verify(
+        &self,
+        channel: &mut Channel,
+        replica: &Replica,
+        cursor: &ReplicaCursor,
+    ) -> Result<Option<[ContentDigest; 2]>> {
+        let mut verified = Vec::with_capacity(2);
+        for database in Database::ALL {
+            channel.send(&Request::Digest { database }).await?;
+            let remote = match channel.receive().await? {
+                Response::Digest(digest) => digest,
+                other => bail!(
+                    "unexpected response to digest request: {}",
+                    describe(&other)
+                ),
+            };
+            if remote.sequence != cursor.get(database) {
+                return Ok(None);
+            }
+            let local = replica.digest(database)?;
+            if local.entries != remote.entries || local.digest != remote.digest {
+                bail!(
+                    "{} database differs from the primary at sequence {} \
+                     ({} local entries, {} on primary)",
+                    database.dir_name(),
+                    remote.sequence,
+                    local.entries,
+                    remote.entries
+                );
+            }
+            verified.push(ContentDigest {
+                sequence: remote.sequence,
+                ..local
+            });
+        }
+        Ok(Some([verified[0], verified[1]]))
+    }
+
+    fn cursor_path(&self) -> PathBuf {
+        self.data_dir.join(CURSOR_FILE)
+    }
+
+    fn load_cursor(&self) -> Result<Option<ReplicaCursor>> {
+        let path = self.cursor_path();
+        let databases_present = Database::ALL
+            .iter()
+            .all(|database| self.data_dir.join(database.dir_name()).exists());
+        if !path.exists() || !databases_present {
+            return Ok(None);
+        }
+        Ok(Some(decode(&std::fs::read(path)?)?))
+    }
+
+    fn save_cursor(&self, cursor: &ReplicaCursor) -> Result<()> {
+        let tmp = self.cursor_path().with_extension("tmp");
+        let mut file = std::fs::File::create(&tmp)?;
+        file.write_all(&encode(cursor)?)?;
+        file.sync_all()?;
+        std::fs::rename(tmp, self.cursor_path())?;
+        Ok(())
+    }
+
+    /// Remove the local databases and cursor so the next open re-bootstraps
+    fn reset(&self) -> Result<()> {
+        let cursor = self.cursor_path();
+        if cursor.exists() {
+            std::fs::remove_file(cursor)?;
+        }
+        for database in Database::ALL {
+            let dir = self.data_dir.join(database.dir_name());
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn download(
+    channel: &mut Channel,
+    database: Database,
+    index: u32,
+    file: &CheckpointFile,
+    path: &Path,
+) -> Result<()> {
+    let mut out = std::fs::File::create(path)?;
+    let mut offset = 0u64;
+    while offset < file.size {
+        channel
+            .send(&Request::Chunk {
+                database,
+                file: index,
+                offset,
+            })
+            .await?;
+        let chunk = match channel.receive().await? {
+            Response::Chunk(chunk) => chunk,
+            other => bail!("unexpected response to chunk request: {}", describe(&other)),
+        };
+        if chunk.is_empty() || offset + chunk.len() as u64 > file.size {
+            bail!("checkpoint file {} changed size during download", file.name);
+        }
+        out.write_all(&chunk)?;
+        offset += chunk.len() as u64;
+    }
+    out.sync_all()?;
+    Ok(())
+}
+
+fn describe(response: &Response) -> String {
+    match response {
+        Response::Error(e) => format!("primary error: {}", e),
+        other => format!("{:?}", std::mem::discriminant(other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+    use tempfile::TempDir;
+
+    const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    struct Primary {
+        blockchain: Arc<RwLock<BlockchainStorage>>,
+        state: Arc<RwLock<StateStorage>>,
+        addr: std::net::SocketAddr,
+    }
+
+    async fn start_primary(dir: &Path, secret: &[u8]) -> Primary {
+        let blockchain = Arc::new(RwLock::new(
+            BlockchainStorage::open(dir.join("blockchain")).unwrap(),
+        ));
+        let state = Arc::new(RwLock::new(StateStorage::open(dir.join("state")).unwrap()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = ReplicationServer::new(
+            secret.to_vec(),
+            blockchain.clone(),
+            state.clone(),
+            dir.join("replication"),
+        );
+        tokio::spawn(server.serve(listener));
+        Primary {
+            blockchain,
+            state,
+            addr,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secondary_bootstraps_tails_and_verifies() {
+        let primary_dir = TempDir::new().unwrap();
+        let secondary_dir = TempDir::new().unwrap();
+        let primary = start_primary(primary_dir.path(), SECRET).await;
+        let alice = KeyPair::generate().public_key();
+        primary
+            .state
+            .read()
+            .await
+            .set_balance(&alice, 1_000)
+            .unwrap();
+
+        let follower =
+            ReplicationFollower::new(SECRET.to_vec(), secondary_dir.path().to_path_buf());
+        let report = follower.sync_once(primary.addr).await.unwrap();
+        assert!(report.bootstrapped);
+
+        // Later writes arrive through the WAL, not a new checkpoint
+        let bob = KeyPair::generate().public_key();
+        primary.state.read().await.set_balance(&bob, 42).unwrap();
+        primary
+            .blockchain
+            .read()
+            .await
+            .set_chain_tip(&[7u8; 32])
+            .unwrap();
+        let report = follower.sync_once(primary.addr).await.unwrap();
+        assert!(!report.bootstrapped);
+        assert!(report.batches_applied >= 1);
+
+        let replica = StateStorage::open(secondary_dir.path().join("state")).unwrap();
+        assert_eq!(replica.get_balance(&alice).unwrap(), 1_000);
+        assert_eq!(replica.get_balance(&bob).unwrap(), 42);
+        let chain = BlockchainStorage::open(secondary_dir.path().join("blockchain")).unwrap();
+        assert_eq!(chain.get_chain_tip().unwrap(), Some([7u8; 32]));
+    }
+
+    #[tokio::test]
+    async fn test_wrong_secret_rejected() {
+        let primary_dir = TempDir::new().unwrap();
+        let secondary_dir = TempDir::new().unwrap();
+        let primary = start_primary(primary_dir.path(), SECRET).await;
+
+        let follower = ReplicationFollower::new(
+            b"ffffffffffffffffffffffffffffffff".to_vec(),
+            secondary_dir.path().to_path_buf(),
+        );
+        let error = follower.sync_once(primary.addr).await.unwrap_err();
+        assert!(error.to_string().contains("replication secret"));
+        assert!(!secondary_dir.path().join("state").exists());
+    }
+
+    #[test]
+    fn test_short_secret_rejected() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("replication.key");
+        std::fs::write(&path, "too-short\n").unwrap();
+        assert!(load_secret(&path).is_err());
+
+        std::fs::write(&path, format!("{}\n", "ab".repeat(32))).unwrap();
+        assert_eq!(load_secret(&path).unwrap().len(), 64);
+    }
+}
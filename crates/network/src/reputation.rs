@@ -1,5 +1,8 @@
+use crate::peer_cache::{CachedBan, CachedReputation};
 use libp2p::PeerId;
+use opensyria_core::clock::system_time;
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::time::{Duration, Instant};
 
 /// Peer reputation tracking for network security
@@ -8,9 +11,35 @@ pub struct PeerReputation {
     /// Reputation scores per peer
     scores: HashMap<PeerId, PeerScore>,
     /// Banned peers with ban expiration time
-    banned_peers: HashMap<PeerId, Instant>,
+    banned_peers: HashMap<PeerId, Ban>,
+    /// Banned IP addresses, from imported ban lists
+    banned_ips: HashMap<IpAddr, Ban>,
     /// Last time decay was applied
     last_decay: Instant,
+    /// Our own peer ID, never scored or banned
+    local_peer_id: Option<PeerId>,
+}
+
+/// A ban in force and why it was imposed
+#[derive(Debug, Clone)]
+struct Ban {
+    expires: Instant,
+    reason: String,
+}
+
+impl Ban {
+    fn new(duration: Duration, reason: impl Into<String>) -> Self {
+        Self {
+            expires: Instant::now() + duration,
+            reason: reason.into(),
+        }
+    }
+
+    /// Whether this ban lasts at least as long as `other`; expiries come
+    /// from whole Unix seconds, so sub-second differences do not count
+    fn outlasts(&self, other: &Ban) -> bool {
+        self.expires + Duration::from_secs(1) >= other.expires
+    }
 }
 
 /// Individual peer reputation score
@@ -50,12 +79,32 @@ impl PeerReputation {
         Self {
             scores: HashMap::new(),
             banned_peers: HashMap::new(),
+            banned_ips: HashMap::new(),
             last_decay: Instant::now(),
+            local_peer_id: None,
+        }
+    }
+
+    /// Reputation tracker for the node with the given stable peer ID
+    /// متتبع السمعة للعقدة ذات المعرف الثابت
+    pub fn for_local_peer(local_peer_id: PeerId) -> Self {
+        Self {
+            local_peer_id: Some(local_peer_id),
+            ..Self::new()
         }
     }
 
+    /// Peer ID the records are kept on behalf of
+    pub fn local_peer_id(&self) -> Option<PeerId> {
+        self.local_peer_id
+    }
+
     /// Register a new peer
     pub fn add_peer(&mut self, peer_id: PeerId) {
+        if self.local_peer_id == Some(peer_id) {
+            return;
+        }
+
         self.scores.entry(peer_id).or_insert(PeerScore {
             score: 0,
             invalid_blocks: 0,
@@ -72,8 +121,8 @@ impl PeerReputation {
         // Apply decay if interval elapsed
         self.maybe_apply_decay();
 
-        if let Some(ban_expires) = self.banned_peers.get(peer_id) {
-            if Instant::now() < *ban_expires {
+        if let Some(ban) = self.banned_peers.get(peer_id) {
+            if Instant::now() < ban.expires {
                 return true; // Still banned
             } else {
                 // Ban expired, remove from banned list and restore some reputation
@@ -89,22 +138,20 @@ impl PeerReputation {
     }
 
     /// Apply gradual reputation decay (move scores toward 0)
+    ///
+    /// One decay step is applied per elapsed interval, so a node that was busy
+    /// or idle for a while catches up instead of decaying only once.
     fn maybe_apply_decay(&mut self) {
-        if self.last_decay.elapsed() < Duration::from_secs(DECAY_INTERVAL_SECS) {
+        let steps = self.last_decay.elapsed().as_secs() / DECAY_INTERVAL_SECS;
+        if steps == 0 {
             return; // Not time yet
         }
 
         for score in self.scores.values_mut() {
-            if score.score < 0 {
-                // Negative scores move up toward 0
-                score.score = (score.score + DECAY_AMOUNT).min(0);
-            } else if score.score > 0 {
-                // Positive scores decay slightly (prevent infinite accumulation)
-                score.score = (score.score - 1).max(0);
-            }
+            score.score = decay_score(score.score, steps);
         }
 
-        self.last_decay = Instant::now();
+        self.last_decay += Duration::from_secs(steps * DECAY_INTERVAL_SECS);
     }
 
     /// Manually trigger reputation decay (for testing)
@@ -175,8 +222,11 @@ impl PeerReputation {
     fn check_ban_threshold(&mut self, peer_id: &PeerId) -> bool {
         if let Some(score) = self.scores.get(peer_id) {
             if score.score < PEER_SCORE_THRESHOLD_BAN {
-                let ban_until = Instant::now() + Duration::from_secs(BAN_DURATION_SECS);
-                self.banned_peers.insert(*peer_id, ban_until);
+                let ban = Ban::new(
+                    Duration::from_secs(BAN_DURATION_SECS),
+                    "Reputation score fell below the ban threshold",
+                );
+                self.banned_peers.insert(*peer_id, ban);
                 return true; // Peer should be banned
             }
         }
@@ -207,11 +257,211 @@ impl PeerReputation {
             .collect()
     }
 
+    /// Ban a peer for `duration` whatever its score; returns false for the
+    /// local peer, which is never banned
+    /// حظر نظير يدوياً لمدة محددة
+    pub fn ban(&mut self, peer_id: PeerId, duration: Duration) -> bool {
+        if self.local_peer_id == Some(peer_id) {
+            return false;
+        }
+        // Tracked so the ban is saved with the peer cache
+        self.add_peer(peer_id);
+        self.banned_peers
+            .insert(peer_id, Ban::new(duration, "Banned by operator"));
+        true
+    }
+
+    /// Ban a peer for at least `duration`, keeping a longer ban already in
+    /// force; returns whether the ban was imposed or lengthened
+    /// حظر نظير لمدة لا تقل عن المدة المحددة
+    pub fn ban_at_least(
+        &mut self,
+        peer_id: PeerId,
+        duration: Duration,
+        reason: impl Into<String>,
+    ) -> bool {
+        if self.local_peer_id == Some(peer_id) {
+            return false;
+        }
+        let ban = Ban::new(duration, reason);
+        if self
+            .banned_peers
+            .get(&peer_id)
+            .is_some_and(|current| current.outlasts(&ban))
+        {
+            return false;
+        }
+        self.add_peer(peer_id);
+        self.banned_peers.insert(peer_id, ban);
+        true
+    }
+
+    /// Refuse connections from `ip` for at least `duration`; returns whether
+    /// the ban was imposed or lengthened
+    /// حظر عنوان IP لمدة لا تقل عن المدة المحددة
+    pub fn ban_ip_at_least(
+        &mut self,
+        ip: IpAddr,
+        duration: Duration,
+        reason: impl Into<String>,
+    ) -> bool {
+        let ban = Ban::new(duration, reason);
+        if self
+            .banned_ips
+            .get(&ip)
+            .is_some_and(|current| current.outlasts(&ban))
+        {
+            return false;
+        }
+        self.banned_ips.insert(ip, ban);
+        true
+    }
+
+    /// Check if connections from `ip` are refused
+    pub fn is_ip_banned(&mut self, ip: &IpAddr) -> bool {
+        match self.banned_ips.get(ip) {
+            Some(ban) if Instant::now() < ban.expires => true,
+            Some(_) => {
+                self.banned_ips.remove(ip);
+                false
+            }
+            None => false,
+        }
+    }
+
     /// Remove peer from tracking
     pub fn remove_peer(&mut self, peer_id: &PeerId) {
         self.scores.remove(peer_id);
         self.banned_peers.remove(peer_id);
     }
+
+    /// Export scores and bans with wall-clock timestamps for the peer cache
+    /// تصدير الدرجات والحظر لحفظها في ذاكرة الأقران
+    pub fn snapshot(&self) -> Vec<(PeerId, CachedReputation)> {
+        let now = Instant::now();
+        let now_unix = system_time();
+
+        self.scores
+            .iter()
+            .map(|(peer_id, score)| {
+                let ban = self
+                    .banned_peers
+                    .get(peer_id)
+                    .filter(|ban| ban.expires > now);
+
+                (
+                    *peer_id,
+                    CachedReputation {
+                        score: score.score,
+                        invalid_blocks: score.invalid_blocks,
+                        invalid_txs: score.invalid_txs,
+                        valid_blocks: score.valid_blocks,
+                        valid_txs: score.valid_txs,
+                        banned_until: ban
+                            .map(|ban| now_unix + ban.expires.duration_since(now).as_secs()),
+                        ban_reason: ban.map(|ban| ban.reason.clone()),
+                        updated_at: now_unix,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Restore records saved by [`snapshot`](Self::snapshot)
+    /// استعادة السمعة المحفوظة عند بدء التشغيل
+    ///
+    /// Decay continues for the time the node was offline, and bans that have
+    /// not yet expired stay in force.
+    pub fn restore(&mut self, records: Vec<(PeerId, CachedReputation)>) {
+        let now = Instant::now();
+        let now_unix = system_time();
+
+        for (peer_id, record) in records {
+            if self.local_peer_id == Some(peer_id) {
+                continue;
+            }
+
+            let offline_steps = now_unix.saturating_sub(record.updated_at) / DECAY_INTERVAL_SECS;
+            let mut score = decay_score(record.score, offline_steps);
+
+            match record.banned_until {
+                Some(until) if until > now_unix => {
+                    let ban = Ban {
+                        expires: now + Duration::from_secs(until - now_unix),
+                        reason: record.ban_reason.clone().unwrap_or_default(),
+                    };
+                    self.banned_peers.insert(peer_id, ban);
+                }
+                // Ban ran out while we were offline: same fresh start as `is_banned`
+                Some(_) => score = score.max(PEER_SCORE_THRESHOLD_WARN),
+                None => {}
+            }
+
+            self.scores.insert(
+                peer_id,
+                PeerScore {
+                    score,
+                    invalid_blocks: record.invalid_blocks,
+                    invalid_txs: record.invalid_txs,
+                    valid_blocks: record.valid_blocks,
+                    valid_txs: record.valid_txs,
+                    last_violation: None,
+                    connected_at: now,
+                },
+            );
+        }
+    }
+
+    /// Export IP bans in force with wall-clock expiry for the peer cache
+    pub fn ip_bans(&self) -> Vec<(IpAddr, CachedBan)> {
+        let now = Instant::now();
+        let now_unix = system_time();
+
+        self.banned_ips
+            .iter()
+            .filter(|(_, ban)| ban.expires > now)
+            .map(|(ip, ban)| {
+                (
+                    *ip,
+                    CachedBan {
+                        banned_until: now_unix + ban.expires.duration_since(now).as_secs(),
+                        reason: ban.reason.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    /// Restore IP bans saved by [`ip_bans`](Self::ip_bans), dropping those
+    /// that expired while the node was down
+    pub fn restore_ip_bans(&mut self, bans: Vec<(IpAddr, CachedBan)>) {
+        let now = Instant::now();
+        let now_unix = system_time();
+
+        for (ip, saved) in bans {
+            if saved.banned_until > now_unix {
+                let ban = Ban {
+                    expires: now + Duration::from_secs(saved.banned_until - now_unix),
+                    reason: saved.reason,
+                };
+                self.banned_ips.insert(ip, ban);
+            }
+        }
+    }
+}
+
+/// Apply `steps` decay steps to a score
+fn decay_score(score: i32, steps: u64) -> i32 {
+    let steps = steps.min(i32::MAX as u64) as i32;
+    if score < 0 {
+        // Negative scores move up toward 0
+        score
+            .saturating_add(DECAY_AMOUNT.saturating_mul(steps))
+            .min(0)
+    } else {
+        // Positive scores decay slightly (prevent infinite accumulation)
+        score.saturating_sub(steps).max(0)
+    }
 }
 
 impl Default for PeerReputation {
@@ -240,6 +490,48 @@ mod tests {
         assert!(reputation.is_banned(&peer_id));
     }
 
+    #[test]
+    fn test_manual_ban() {
+        let local = PeerId::random();
+        let mut reputation = PeerReputation::for_local_peer(local);
+        let peer_id = PeerId::random();
+
+        assert!(reputation.ban(peer_id, Duration::from_secs(BAN_DURATION_SECS)));
+        assert!(reputation.is_banned(&peer_id));
+        assert!(!reputation.ban(local, Duration::from_secs(BAN_DURATION_SECS)));
+        assert!(!reputation.is_banned(&local));
+
+        let mut restarted = PeerReputation::new();
+        restarted.restore(reputation.snapshot());
+        assert!(restarted.is_banned(&peer_id));
+    }
+
+    #[test]
+    fn test_ban_at_least_never_shortens() {
+        let mut reputation = PeerReputation::new();
+        let peer_id = PeerId::random();
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+
+        assert!(reputation.ban(peer_id, Duration::from_secs(BAN_DURATION_SECS)));
+        assert!(!reputation.ban_at_least(peer_id, Duration::from_secs(60), "shorter"));
+        assert!(reputation.ban_at_least(
+            peer_id,
+            Duration::from_secs(2 * BAN_DURATION_SECS),
+            "longer"
+        ));
+        let (_, record) = reputation.snapshot().pop().unwrap();
+        assert_eq!(record.ban_reason.as_deref(), Some("longer"));
+
+        assert!(reputation.ban_ip_at_least(ip, Duration::from_secs(BAN_DURATION_SECS), "spam"));
+        assert!(!reputation.ban_ip_at_least(ip, Duration::from_secs(60), "spam"));
+        assert!(reputation.is_ip_banned(&ip));
+
+        let mut restarted = PeerReputation::new();
+        restarted.restore_ip_bans(reputation.ip_bans());
+        assert!(restarted.is_ip_banned(&ip));
+        assert!(!restarted.is_ip_banned(&"203.0.113.8".parse().unwrap()));
+    }
+
     #[test]
     fn test_peer_reputation_rewards() {
         let mut reputation = PeerReputation::new();
@@ -256,4 +548,82 @@ mod tests {
         assert_eq!(score.score, 20); // 10 blocks * 2 points
         assert_eq!(score.valid_blocks, 10);
     }
+
+    #[test]
+    fn test_decay_catches_up_over_elapsed_intervals() {
+        let mut reputation = PeerReputation::new();
+        let peer_id = PeerId::random();
+
+        reputation.add_peer(peer_id);
+        reputation.penalize_invalid_block(&peer_id); // -10
+
+        // Three intervals elapsed: three decay steps
+        reputation.last_decay = Instant::now() - Duration::from_secs(DECAY_INTERVAL_SECS * 3);
+        reputation.is_banned(&peer_id);
+        assert_eq!(
+            reputation.get_score(&peer_id).unwrap().score,
+            PENALTY_INVALID_BLOCK + 3 * DECAY_AMOUNT
+        );
+    }
+
+    #[test]
+    fn test_ban_survives_restart() {
+        let mut reputation = PeerReputation::new();
+        let peer_id = PeerId::random();
+
+        reputation.add_peer(peer_id);
+        for _ in 0..11 {
+            reputation.penalize_invalid_block(&peer_id);
+        }
+        assert!(reputation.is_banned(&peer_id));
+
+        let mut restarted = PeerReputation::new();
+        restarted.restore(reputation.snapshot());
+        assert!(restarted.is_banned(&peer_id));
+        assert_eq!(restarted.get_score(&peer_id).unwrap().invalid_blocks, 11);
+    }
+
+    #[test]
+    fn test_restore_applies_offline_decay() {
+        let peer_id = PeerId::random();
+        let saved_at = system_time() - DECAY_INTERVAL_SECS * 10;
+
+        let mut reputation = PeerReputation::new();
+        reputation.restore(vec![(
+            peer_id,
+            CachedReputation {
+                score: -150,
+                invalid_blocks: 15,
+                invalid_txs: 0,
+                valid_blocks: 0,
+                valid_txs: 0,
+                // Ban expired while the node was down
+                banned_until: Some(saved_at + 60),
+                ban_reason: None,
+                updated_at: saved_at,
+            },
+        )]);
+
+        assert!(!reputation.is_banned(&peer_id));
+        // -150 + 10 * 2 = -130, lifted to the post-ban fresh start
+        assert_eq!(
+            reputation.get_score(&peer_id).unwrap().score,
+            PEER_SCORE_THRESHOLD_WARN
+        );
+    }
+
+    #[test]
+    fn test_local_peer_never_scored() {
+        let local = PeerId::random();
+        let mut reputation = PeerReputation::for_local_peer(local);
+
+        reputation.add_peer(local);
+        for _ in 0..11 {
+            reputation.penalize_invalid_block(&local);
+        }
+
+        assert!(reputation.get_score(&local).is_none());
+        assert!(!reputation.is_banned(&local));
+        assert_eq!(reputation.local_peer_id(), Some(local));
+    }
 }
@@ -0,0 +1,112 @@
+//! Kademlia peer routing
+//! توجيه الأقران عبر جدول كاديمليا
+//!
+//! The DHT lets nodes find peers beyond their LAN and bootstrap list. Every
+//! full node advertises a provider record under its network's key, so a node
+//! short of outbound peers can look up others running the same network. The
+//! DHT runs under its own protocol name, keeping it apart from other libp2p
+//! networks, and can be disabled for private networks.
+
+use crate::bootstrap::NetworkType;
+use libp2p::{
+    kad::{self, store::MemoryStore},
+    multiaddr::Protocol,
+    Multiaddr, PeerId, StreamProtocol,
+};
+
+/// Kademlia protocol name for the OpenSyria DHT
+pub const KADEMLIA_PROTOCOL: StreamProtocol = StreamProtocol::new("/opensyria/kad/1.0.0");
+
+/// How often the routing table is refreshed with a bootstrap query (seconds)
+pub const KADEMLIA_REFRESH_INTERVAL_SECS: u64 = 300;
+
+/// Provider key under which full nodes of `network` advertise themselves
+pub fn full_node_key(network: NetworkType) -> kad::RecordKey {
+    let name = match network {
+        NetworkType::Mainnet => "mainnet",
+        NetworkType::Testnet => "testnet",
+    };
+    kad::RecordKey::new(&format!("/opensyria/{}/full-node", name))
+}
+
+/// Peer ID from a trailing `/p2p/<peer id>` component, which Kademlia needs
+/// to route to a bootstrap address
+pub fn peer_id_from_addr(addr: &Multiaddr) -> Option<PeerId> {
+    match addr.iter().last() {
+        Some(Protocol::P2p(peer_id)) => Some(peer_id),
+        _ => None,
+    }
+}
+
+/// Routing table occupancy, for metrics
+/// إحصاءات جدول التوجيه
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KademliaStats {
+    /// Whether the DHT is running
+    pub enabled: bool,
+    /// Peers in each non-empty k-bucket, keyed by bucket index (log2 distance)
+    pub buckets: Vec<(u32, usize)>,
+}
+
+impl KademliaStats {
+    /// Snapshot the routing table of a running DHT
+    pub fn from_behaviour(kademlia: &mut kad::Behaviour<MemoryStore>) -> Self {
+        let buckets = kademlia
+            .kbuckets()
+            .map(|bucket| {
+                let index = bucket.range().0.ilog2().unwrap_or_default();
+                (index, bucket.num_entries())
+            })
+            .collect();
+        Self {
+            enabled: true,
+            buckets,
+        }
+    }
+
+    /// Total peers in the routing table
+    pub fn routing_peers(&self) -> usize {
+        self.buckets.iter().map(|(_, peers)| peers).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_id_from_addr() {
+        let peer_id = PeerId::random();
+        let addr: Multiaddr = format!("/ip4/10.0.0.1/tcp/9000/p2p/{}", peer_id)
+            .parse()
+            .unwrap();
+        assert_eq!(peer_id_from_addr(&addr), Some(peer_id));
+
+        let bare: Multiaddr = "/ip4/10.0.0.1/tcp/9000".parse().unwrap();
+        assert_eq!(peer_id_from_addr(&bare), None);
+    }
+
+    #[test]
+    fn test_routing_stats() {
+        assert_ne!(
+            full_node_key(NetworkType::Mainnet),
+            full_node_key(NetworkType::Testnet)
+        );
+
+        let local = PeerId::random();
+        let mut kademlia = kad::Behaviour::new(local, MemoryStore::new(local));
+        assert_eq!(
+            KademliaStats::from_behaviour(&mut kademlia).routing_peers(),
+            0
+        );
+
+        let addr: Multiaddr = "/ip4/10.0.0.1/tcp/9000".parse().unwrap();
+        for _ in 0..5 {
+            kademlia.add_address(&PeerId::random(), addr.clone());
+        }
+        let stats = KademliaStats::from_behaviour(&mut kademlia);
+        assert!(stats.enabled);
+        assert_eq!(stats.routing_peers(), 5);
+        assert!(stats.buckets.iter().all(|(index, _)| *index < 256));
+    }
+}
@@ -0,0 +1,472 @@
+//! Opt-in propagation latency telemetry
+//! قياس زمن انتشار الكتل والمعاملات بموافقة العقد
+//!
+//! A node started with a telemetry collector notes when it first sees each
+//! gossiped block and transaction hash. The delay from the publisher's send
+//! stamp (see [`crate::gossip::stamp`]) is put into a fixed-bucket
+//! histogram, and every [`TelemetryConfig::interval`] the histograms are
+//! posted to the collector as a [`PropagationReport`] and reset. Hashes,
+//! peers and addresses never leave the node.
+//!
+//! Fixed buckets make reports from many nodes additive: a collector keeps
+//! them in a [`TelemetryAggregator`], sums the histograms and reads
+//! network-wide percentiles off the total, which is what gossip tuning
+//! (mesh sizes, `target_propagation_ms`) needs.
+
+use opensyria_core::clock::system_time_millis;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets (ms); delays above the last
+/// bound are taken for clock skew and dropped
+pub const LATENCY_BUCKETS_MS: [u64; 11] = [
+    25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000,
+];
+
+/// Hashes remembered for first-seen detection
+const FIRST_SEEN_CAPACITY: usize = 20_000;
+
+/// Default interval between reports
+pub const DEFAULT_TELEMETRY_INTERVAL_SECS: u64 = 300;
+
+/// Reports a collector keeps before dropping the oldest
+const MAX_COLLECTED_REPORTS: usize = 10_000;
+
+/// Longest node label a collector accepts
+const MAX_NODE_LABEL_LEN: usize = 128;
+
+/// What a first-seen hash belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemKind {
+    Block,
+    Transaction,
+}
+
+/// Delay counts per [`LATENCY_BUCKETS_MS`] bucket
+/// مدرج تكراري لأزمنة الانتشار
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub counts: Vec<u64>,
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        Self {
+            counts: vec![0; LATENCY_BUCKETS_MS.len()],
+        }
+    }
+}
+
+impl LatencyHistogram {
+    /// Count a delay; returns false if it is beyond the last bucket
+    pub fn record(&mut self, delay_ms: u64) -> bool {
+        match LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&bound| delay_ms <= bound)
+        {
+            Some(bucket) => {
+                self.counts[bucket] += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add another histogram's counts
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (count, added) in self.counts.iter_mut().zip(&other.counts) {
+            *count = count.saturating_add(*added);
+        }
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile (0.0..=1.0)
+    pub fn percentile(&self, q: f64) -> Option<u64> {
+        let total = self.samples();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (count, bound) in self.counts.iter().zip(LATENCY_BUCKETS_MS) {
+            seen += count;
+            if seen >= rank {
+                return Some(bound);
+            }
+        }
+        LATENCY_BUCKETS_MS.last().copied()
+    }
+
+    /// Sample count and the usual percentiles
+    pub fn summary(&self) -> LatencySummary {
+        LatencySummary {
+            samples: self.samples(),
+            p50_ms: self.percentile(0.5),
+            p90_ms: self.percentile(0.9),
+            p99_ms: self.percentile(0.99),
+        }
+    }
+}
+
+/// Percentiles read off a histogram (bucket upper bounds, ms)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencySummary {
+    pub samples: u64,
+    pub p50_ms: Option<u64>,
+    pub p90_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+}
+
+/// Propagation histograms of one node over one interval
+/// تقرير زمن الانتشار لعقدة واحدة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PropagationReport {
+    /// Label of the reporting node (its peer ID)
+    pub node: String,
+    pub period_start_ms: u64,
+    pub period_end_ms: u64,
+    pub blocks: LatencyHistogram,
+    pub transactions: LatencyHistogram,
+}
+
+impl PropagationReport {
+    /// Reject reports a collector cannot merge
+    pub fn validate(&self) -> Result<(), String> {
+        if self.node.is_empty() || self.node.len() > MAX_NODE_LABEL_LEN {
+            return Err(format!(
+                "Node label must be 1 to {} bytes",
+                MAX_NODE_LABEL_LEN
+            ));
+        }
+        if self.blocks.counts.len() != LATENCY_BUCKETS_MS.len()
+            || self.transactions.counts.len() != LATENCY_BUCKETS_MS.len()
+        {
+            return Err(format!(
+                "Histograms must have {} buckets",
+                LATENCY_BUCKETS_MS.len()
+            ));
+        }
+        if self.period_end_ms < self.period_start_ms {
+            return Err("Report period ends before it starts".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// First-seen times of gossiped hashes and their propagation delays
+/// أوقات أول رؤية للكتل والمعاملات
+#[derive(Debug)]
+pub struct PropagationTelemetry {
+    node: String,
+    seen: HashSet<[u8; 32]>,
+    order: VecDeque<[u8; 32]>,
+    blocks: LatencyHistogram,
+    transactions: LatencyHistogram,
+    period_start_ms: u64,
+}
+
+impl PropagationTelemetry {
+    pub fn new(node: impl Into<String>) -> Self {
+        Self {
+            node: node.into(),
+            seen: HashSet::new(),
+            order: VecDeque::new(),
+            blocks: LatencyHistogram::default(),
+            transactions: LatencyHistogram::default(),
+            period_start_ms: system_time_millis(),
+        }
+    }
+
+    /// Note a gossiped hash; the delay from its send stamp is counted the
+    /// first time the hash is seen. Returns that delay.
+    pub fn observe(
+        &mut self,
+        kind: ItemKind,
+        hash: [u8; 32],
+        sent_at_ms: Option<u64>,
+    ) -> Option<u64> {
+        self.observe_at(kind, hash, sent_at_ms, system_time_millis())
+    }
+
+    fn observe_at(
+        &mut self,
+        kind: ItemKind,
+        hash: [u8; 32],
+        sent_at_ms: Option<u64>,
+        now_ms: u64,
+    ) -> Option<u64> {
+        if !self.seen.insert(hash) {
+            return None;
+        }
+        if self.order.len() == FIRST_SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        self.order.push_back(hash);
+
+        let delay = now_ms.checked_sub(sent_at_ms?)?;
+        let histogram = match kind {
+            ItemKind::Block => &mut self.blocks,
+            ItemKind::Transaction => &mut self.transactions,
+        };
+        histogram.record(delay).then_some(delay)
+    }
+
+    /// Report the interval since the last report and start a new one
+    pub fn take_report(&mut self) -> PropagationReport {
+        self.take_report_at(system_time_millis())
+    }
+
+    fn take_report_at(&mut self, now_ms: u64) -> PropagationReport {
+        let report = PropagationReport {
+            node: self.node.clone(),
+            period_start_ms: self.period_start_ms,
+            period_end_ms: now_ms,
+            blocks: std::mem::take(&mut self.blocks),
+            transactions: std::mem::take(&mut self.transactions),
+        };
+        self.period_start_ms = now_ms;
+        report
+    }
+}
+
+/// Where and how often to send reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryConfig {
+    /// URL reports are POSTed to as JSON
+    pub collector: String,
+    pub interval: Duration,
+    /// Bearer token the collector authorizes reports with
+    pub api_key: Option<String>,
+}
+
+impl TelemetryConfig {
+    pub fn new(collector: impl Into<String>) -> Self {
+        Self {
+            collector: collector.into(),
+            interval: Duration::from_secs(DEFAULT_TELEMETRY_INTERVAL_SECS),
+            api_key: None,
+        }
+    }
+}
+
+/// Tracks first-seen times and posts reports to the collector
+/// مرسل تقارير زمن الانتشار
+#[derive(Debug)]
+pub struct TelemetryReporter {
+    config: TelemetryConfig,
+    http: reqwest::Client,
+    tracker: PropagationTelemetry,
+}
+
+impl TelemetryReporter {
+    pub fn new(config: TelemetryConfig, node: impl Into<String>) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config,
+            http,
+            tracker: PropagationTelemetry::new(node),
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        self.config.interval
+    }
+
+    /// See [`PropagationTelemetry::observe`]
+    pub fn observe(
+        &mut self,
+        kind: ItemKind,
+        hash: [u8; 32],
+        sent_at_ms: Option<u64>,
+    ) -> Option<u64> {
+        self.tracker.observe(kind, hash, sent_at_ms)
+    }
+
+    /// Take the current report and send it in the background; an interval
+    /// without samples is not sent
+    pub fn flush(&mut self) {
+        let report = self.tracker.take_report();
+        if report.blocks.samples() + report.transactions.samples() == 0 {
+            return;
+        }
+
+        let mut request = self.http.post(&self.config.collector).json(&report);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let collector = self.config.collector.clone();
+        tokio::spawn(async move {
+            match request.send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => tracing::debug!("Sent propagation telemetry to {}", collector),
+                Err(e) => tracing::warn!("Failed to send telemetry to {}: {}", collector, e),
+            }
+        });
+    }
+}
+
+/// Network-wide propagation latency from the collected reports
+/// زمن الانتشار على مستوى الشبكة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkLatency {
+    /// Distinct nodes reporting within the window
+    pub nodes: usize,
+    pub window_secs: u64,
+    pub blocks: LatencySummary,
+    pub transactions: LatencySummary,
+}
+
+/// Collector side: keeps recent reports and merges them
+/// مجمع تقارير الانتشار
+#[derive(Debug)]
+pub struct TelemetryAggregator {
+    window: Duration,
+    reports: VecDeque<(u64, PropagationReport)>,
+}
+
+impl TelemetryAggregator {
+    /// Merge reports received within the last `window`
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            reports: VecDeque::new(),
+        }
+    }
+
+    /// Accept a report from a node
+    pub fn submit(&mut self, report: PropagationReport) -> Result<(), String> {
+        self.submit_at(report, system_time_millis())
+    }
+
+    fn submit_at(&mut self, report: PropagationReport, now_ms: u64) -> Result<(), String> {
+        report.validate()?;
+        self.prune(now_ms);
+        if self.reports.len() == MAX_COLLECTED_REPORTS {
+            self.reports.pop_front();
+        }
+        self.reports.push_back((now_ms, report));
+        Ok(())
+    }
+
+    fn prune(&mut self, now_ms: u64) {
+        let cutoff = now_ms.saturating_sub(self.window.as_millis() as u64);
+        while self.reports.front().is_some_and(|(at, _)| *at < cutoff) {
+            self.reports.pop_front();
+        }
+    }
+
+    /// Percentiles over every report in the window
+    pub fn summary(&mut self) -> NetworkLatency {
+        self.summary_at(system_time_millis())
+    }
+
+    fn summary_at(&mut self, now_ms: u64) -> NetworkLatency {
+        self.prune(now_ms);
+        let mut blocks = LatencyHistogram::default();
+        let mut transactions = LatencyHistogram::default();
+        let mut nodes = HashSet::new();
+        for (_, report) in &self.reports {
+            blocks.merge(&report.blocks);
+            transactions.merge(&report.transactions);
+            nodes.insert(report.node.as_str());
+        }
+
+        NetworkLatency {
+            nodes: nodes.len(),
+            window_secs: self.window.as_secs(),
+            blocks: blocks.summary(),
+            transactions: transactions.summary(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_percentiles() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(0.5), None);
+
+        for delay in [10, 20, 30, 40, 80, 90, 200, 400, 900, 3_000] {
+            assert!(histogram.record(delay));
+        }
+        assert!(!histogram.record(120_000));
+        assert_eq!(histogram.samples(), 10);
+        assert_eq!(histogram.percentile(0.5), Some(100));
+        assert_eq!(histogram.percentile(0.9), Some(1_000));
+        assert_eq!(histogram.percentile(1.0), Some(5_000));
+    }
+
+    #[test]
+    fn test_only_first_sighting_counts() {
+        let mut telemetry = PropagationTelemetry::new("node-a");
+        let hash = [7u8; 32];
+        assert_eq!(
+            telemetry.observe_at(ItemKind::Block, hash, Some(1_000), 1_300),
+            Some(300)
+        );
+        assert_eq!(
+            telemetry.observe_at(ItemKind::Block, hash, Some(1_000), 1_900),
+            None
+        );
+        // Unstamped gossip is remembered but has no delay
+        assert_eq!(
+            telemetry.observe_at(ItemKind::Transaction, [8u8; 32], None, 2_000),
+            None
+        );
+
+        let report = telemetry.take_report_at(5_000);
+        assert_eq!(report.blocks.samples(), 1);
+        assert_eq!(report.transactions.samples(), 0);
+        assert_eq!(report.period_end_ms, 5_000);
+        assert_eq!(telemetry.take_report_at(6_000).period_start_ms, 5_000);
+    }
+
+    #[test]
+    fn test_aggregator_merges_nodes_within_window() {
+        let report = |node: &str, delays: &[u64]| {
+            let mut blocks = LatencyHistogram::default();
+            for &delay in delays {
+                blocks.record(delay);
+            }
+            PropagationReport {
+                node: node.to_string(),
+                period_start_ms: 0,
+                period_end_ms: 1,
+                blocks,
+                transactions: LatencyHistogram::default(),
+            }
+        };
+
+        let mut aggregator = TelemetryAggregator::new(Duration::from_secs(60));
+        aggregator.submit_at(report("a", &[20; 9]), 0).unwrap();
+        aggregator.submit_at(report("b", &[20; 9]), 50_000).unwrap();
+        aggregator
+            .submit_at(report("b", &[2_000; 2]), 55_000)
+            .unwrap();
+
+        let summary = aggregator.summary_at(59_000);
+        assert_eq!(summary.nodes, 2);
+        assert_eq!(summary.blocks.samples, 20);
+        assert_eq!(summary.blocks.p50_ms, Some(25));
+        assert_eq!(summary.blocks.p99_ms, Some(2_500));
+        assert_eq!(summary.transactions.p50_ms, None);
+
+        // Node a's report ages out of the window
+        assert_eq!(aggregator.summary_at(70_000).nodes, 1);
+
+        let mut malformed = report("c", &[]);
+        malformed.blocks.counts.pop();
+        assert!(aggregator.submit_at(malformed, 70_000).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+//! TCP and QUIC transports
+//! وسائل النقل عبر TCP و QUIC
+//!
+//! QUIC handles mobile and lossy links better than TCP: it needs one round
+//! trip to connect, recovers from loss per stream and survives address
+//! changes. The node listens on both by default. A TCP listen address
+//! `/ip4/0.0.0.0/tcp/9000` gets a QUIC twin `/ip4/0.0.0.0/udp/9000/quic-v1`,
+//! and peer addresses are tried QUIC-first.
+
+use anyhow::{bail, Result};
+use futures::future::Either;
+use libp2p::{
+    core::{
+        muxing::StreamMuxerBox,
+        transport::{Boxed, OptionalTransport},
+        upgrade,
+    },
+    identity::Keypair,
+    multiaddr::Protocol,
+    noise, quic, tcp, yamux, Multiaddr, PeerId, Transport,
+};
+
+/// Which transports the node runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransportConfig {
+    /// TCP with noise and yamux
+    pub tcp: bool,
+    /// QUIC v1 (TLS and multiplexing built in)
+    pub quic: bool,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            tcp: true,
+            quic: true,
+        }
+    }
+}
+
+/// Build the node transport from the enabled protocols
+pub fn build_transport(
+    local_key: &Keypair,
+    config: TransportConfig,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>> {
+    if !config.tcp && !config.quic {
+        bail!("At least one of TCP and QUIC must be enabled");
+    }
+
+    let tcp = if config.tcp {
+        let transport = tcp::tokio::Transport::default()
+            .upgrade(upgrade::Version::V1Lazy)
+            .authenticate(noise::Config::new(local_key)?)
+            .multiplex(yamux::Config::default())
+            .boxed();
+        OptionalTransport::some(transport)
+    } else {
+        OptionalTransport::none()
+    };
+
+    let quic = if config.quic {
+        let transport = quic::tokio::Transport::new(quic::Config::new(local_key))
+            .map(|(peer_id, connection), _| (peer_id, StreamMuxerBox::new(connection)));
+        OptionalTransport::some(transport)
+    } else {
+        OptionalTransport::none()
+    };
+
+    Ok(quic
+        .or_transport(tcp)
+        .map(|output, _| match output {
+            Either::Left(output) | Either::Right(output) => output,
+        })
+        .boxed())
+}
+
+/// Whether `addr` is a QUIC address
+pub fn is_quic(addr: &Multiaddr) -> bool {
+    addr.iter().any(|p| matches!(p, Protocol::QuicV1))
+}
+
+/// QUIC twin of a TCP address: `/tcp/<port>` becomes `/udp/<port>/quic-v1`
+pub fn tcp_to_quic(addr: &Multiaddr) -> Option<Multiaddr> {
+    let mut converted = Multiaddr::empty();
+    let mut found = false;
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Tcp(port) if !found => {
+                converted.push(Protocol::Udp(port));
+                converted.push(Protocol::QuicV1);
+                found = true;
+            }
+            other => converted.push(other),
+        }
+    }
+    found.then_some(converted)
+}
+
+/// TCP twin of a QUIC address: `/udp/<port>/quic-v1` becomes `/tcp/<port>`
+pub fn quic_to_tcp(addr: &Multiaddr) -> Option<Multiaddr> {
+    if !is_quic(addr) {
+        return None;
+    }
+    let mut converted = Multiaddr::empty();
+    for protocol in addr.iter() {
+        match protocol {
+            Protocol::Udp(port) => converted.push(Protocol::Tcp(port)),
+            Protocol::QuicV1 => {}
+            other => converted.push(other),
+        }
+    }
+    Some(converted)
+}
+
+/// Addresses to listen on for a configured listen address, QUIC first
+pub fn listen_addrs(addr: &Multiaddr, config: TransportConfig) -> Vec<Multiaddr> {
+    let (quic, tcp) = if is_quic(addr) {
+        (Some(addr.clone()), quic_to_tcp(addr))
+    } else {
+        (tcp_to_quic(addr), Some(addr.clone()))
+    };
+
+    let mut addrs = Vec::new();
+    if config.quic {
+        addrs.extend(quic);
+    }
+    if config.tcp {
+        addrs.extend(tcp);
+    }
+    addrs
+}
+
+/// Order peer addresses QUIC-first, dropping those no enabled transport can dial
+pub fn prefer_quic(addrs: Vec<Multiaddr>, config: TransportConfig) -> Vec<Multiaddr> {
+    let (quic, tcp): (Vec<_>, Vec<_>) = addrs.into_iter().partition(is_quic);
+    let mut ordered = Vec::new();
+    if config.quic {
+        ordered.extend(quic);
+    }
+    if config.tcp {
+        ordered.extend(tcp);
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dual_stack_listen_addrs() {
+        let tcp: Multiaddr = "/ip4/0.0.0.0/tcp/9000".parse().unwrap();
+        let quic: Multiaddr = "/ip4/0.0.0.0/udp/9000/quic-v1".parse().unwrap();
+
+        assert_eq!(tcp_to_quic(&tcp), Some(quic.clone()));
+        assert_eq!(quic_to_tcp(&quic), Some(tcp.clone()));
+        assert_eq!(tcp_to_quic(&quic), None);
+
+        let both = TransportConfig::default();
+        assert_eq!(listen_addrs(&tcp, both), vec![quic.clone(), tcp.clone()]);
+        assert_eq!(listen_addrs(&quic, both), vec![quic.clone(), tcp.clone()]);
+
+        let tcp_only = TransportConfig {
+            tcp: true,
+            quic: false,
+        };
+        assert_eq!(listen_addrs(&tcp, tcp_only), vec![tcp.clone()]);
+
+        let quic_only = TransportConfig {
+            tcp: false,
+            quic: true,
+        };
+        assert_eq!(listen_addrs(&tcp, quic_only), vec![quic]);
+    }
+
+    #[test]
+    fn test_prefer_quic() {
+        let tcp: Multiaddr = "/ip4/10.0.0.1/tcp/9000".parse().unwrap();
+        let quic: Multiaddr = "/ip4/10.0.0.1/udp/9000/quic-v1".parse().unwrap();
+        let addrs = vec![tcp.clone(), quic.clone()];
+
+        assert_eq!(
+            prefer_quic(addrs.clone(), TransportConfig::default()),
+            vec![quic, tcp.clone()]
+        );
+        let tcp_only = TransportConfig {
+            tcp: true,
+            quic: false,
+        };
+        assert_eq!(prefer_quic(addrs, tcp_only), vec![tcp]);
+    }
+
+    #[test]
+    fn test_build_transport() {
+        let key = Keypair::generate_ed25519();
+        assert!(build_transport(&key, TransportConfig::default()).is_ok());
+        assert!(build_transport(
+            &key,
+            TransportConfig {
+                tcp: false,
+                quic: false,
+            }
+        )
+        .is_err());
+    }
+}
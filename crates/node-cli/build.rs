@@ -0,0 +1,61 @@
+//! Embed build provenance for `opensyria-node version --verbose`
+//! تضمين معلومات البناء في الملف التنفيذي
+//!
+//! Only inputs that are identical across reproducible builds of the same
+//! commit are recorded: no timestamps, hostnames or paths.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=OPENSYRIA_GIT_COMMIT");
+    if let Some(head_log) = git(&["rev-parse", "--git-path", "logs/HEAD"]) {
+        println!("cargo:rerun-if-changed={}", head_log);
+    }
+
+    // Release builds from a source tarball have no .git; the release
+    // script passes the commit explicitly
+    let commit = std::env::var("OPENSYRIA_GIT_COMMIT")
+        .ok()
+        .filter(|commit| !commit.is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version = Command::new(rustc)
+        .arg("-V")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .map(|version| version.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|feature| feature.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=OPENSYRIA_BUILD_COMMIT={}", commit);
+    println!("cargo:rustc-env=OPENSYRIA_BUILD_RUSTC={}", rustc_version);
+    println!(
+        "cargo:rustc-env=OPENSYRIA_BUILD_FEATURES={}",
+        features.join(",")
+    );
+    println!(
+        "cargo:rustc-env=OPENSYRIA_BUILD_TARGET={}",
+        std::env::var("TARGET").unwrap_or_default()
+    );
+    println!(
+        "cargo:rustc-env=OPENSYRIA_BUILD_PROFILE={}",
+        std::env::var("PROFILE").unwrap_or_default()
+    );
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
@@ -0,0 +1,88 @@
+//! Build provenance embedded at compile time
+//! معلومات البناء المضمنة وقت الترجمة
+//!
+//! Everything here comes from `build.rs` and the built-in chain parameters,
+//! so two reproducible builds of the same commit report identical values.
+
+use opensyria_core::ChainParams;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Networks whose parameters are compiled into every build
+const BUILT_IN_NETWORKS: [&str; 3] = ["mainnet", "testnet", "regtest"];
+
+/// What this binary was built from
+/// المصدر الذي بني منه هذا الملف التنفيذي
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    /// Crate version
+    pub version: String,
+    /// Git commit, or `unknown` when built outside a checkout
+    pub git_commit: String,
+    /// Target triple
+    pub target: String,
+    /// Cargo profile (`release` or `debug`)
+    pub profile: String,
+    /// Compiler version string
+    pub rustc: String,
+    /// Enabled cargo features
+    pub features: Vec<String>,
+    /// Hex hash of each built-in network's chain parameters
+    pub chain_params_hashes: BTreeMap<String, String>,
+}
+
+impl BuildInfo {
+    /// Provenance of the running binary
+    pub fn current() -> Self {
+        let chain_params_hashes = BUILT_IN_NETWORKS
+            .iter()
+            .map(|network| {
+                let params = ChainParams::for_network(network).expect("built-in network");
+                (network.to_string(), hex::encode(params.params_hash()))
+            })
+            .collect();
+
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_commit: env!("OPENSYRIA_BUILD_COMMIT").to_string(),
+            target: env!("OPENSYRIA_BUILD_TARGET").to_string(),
+            profile: env!("OPENSYRIA_BUILD_PROFILE").to_string(),
+            rustc: env!("OPENSYRIA_BUILD_RUSTC").to_string(),
+            features: env!("OPENSYRIA_BUILD_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(str::to_string)
+                .collect(),
+            chain_params_hashes,
+        }
+    }
+
+    /// Chain params hash for `network`, if it is built in
+    pub fn chain_params_hash(&self, network: &str) -> Option<&str> {
+        self.chain_params_hashes.get(network).map(String::as_str)
+    }
+
+    /// Commit abbreviated for one-line version output
+    pub fn short_commit(&self) -> &str {
+        self.git_commit.get(..12).unwrap_or(&self.git_commit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current_build_info() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_commit.is_empty());
+        assert!(!info.target.is_empty());
+        assert_eq!(info.chain_params_hashes.len(), BUILT_IN_NETWORKS.len());
+        assert_eq!(
+            info.chain_params_hash("mainnet"),
+            Some(hex::encode(ChainParams::mainnet().params_hash()).as_str())
+        );
+        assert_eq!(info.chain_params_hash("devnet"), None);
+    }
+}
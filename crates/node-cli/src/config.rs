@@ -1,9 +1,21 @@
 /// Node configuration management
 /// إدارة تكوين العقدة
 
+use crate::notify::Topic;
 use anyhow::{Context, Result};
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::{CoinbaseSplit, PayoutShare, MAX_COINBASE_EXTRA_DATA};
+use opensyria_mempool::MempoolConfig;
+use opensyria_metrics::server::{MetricsAuth, MetricsServerConfig, MetricsTlsConfig};
+use opensyria_network::banlist::{
+    BanListPolicy, BanListSubscriptions, DEFAULT_BAN_LIST_REFRESH_SECS,
+    DEFAULT_MAX_IMPORTED_BAN_SECS,
+};
+use opensyria_network::{NetworkType, PeerId};
+use opensyria_storage::{StateOptions, StorageLimits, StorageTier, Watchlist};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 
 /// Node configuration
@@ -25,6 +37,26 @@ pub struct NodeConfig {
     /// Daemon configuration
     #[serde(default)]
     pub daemon: DaemonConfig,
+
+    /// Prometheus metrics endpoint configuration
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// Block and transaction notification socket configuration
+    #[serde(default)]
+    pub notify: NotifyConfig,
+
+    /// Database and mempool memory budgets
+    #[serde(default)]
+    pub resources: ResourcesConfig,
+
+    /// Ban lists shared by cooperating operators
+    #[serde(default)]
+    pub ban_lists: BanListsConfig,
+
+    /// Header-only storage for watcher nodes
+    #[serde(default)]
+    pub thin: ThinConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +72,22 @@ pub struct NetworkConfig {
     /// Maximum number of peers
     #[serde(default = "default_max_peers")]
     pub max_peers: usize,
+
+    /// Chain to join: "mainnet" or "testnet"
+    #[serde(default = "default_chain")]
+    pub chain: String,
+}
+
+impl NetworkConfig {
+    /// Network type named by `chain`
+    /// نوع الشبكة المحدد في `chain`
+    pub fn network_type(&self) -> Result<NetworkType> {
+        match self.chain.as_str() {
+            "mainnet" => Ok(NetworkType::Mainnet),
+            "testnet" => Ok(NetworkType::Testnet),
+            other => anyhow::bail!("Unknown network chain: {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +102,25 @@ pub struct MiningConfig {
 
     /// Mining address (hex public key)
     pub mining_address: Option<String>,
+
+    /// Split the block reward across several addresses (overrides `mining_address`)
+    #[serde(default)]
+    pub payout_split: Vec<PayoutShareConfig>,
+
+    /// Miner or pool tag written into every coinbase (UTF-8, at most 32 bytes)
+    #[serde(default)]
+    pub extra_data: Option<String>,
+}
+
+/// One coinbase payout address and its share
+/// عنوان دفع وحصته من مكافأة الكتلة
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayoutShareConfig {
+    /// Payout address (hex public key)
+    pub address: String,
+
+    /// Share of the block reward and fees in percent
+    pub percent: u8,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,6 +140,124 @@ pub struct DaemonConfig {
     /// Log rotation: number of backup files to keep
     #[serde(default = "default_log_backups")]
     pub log_backups: usize,
+
+    /// Log filter, e.g. `info,opensyria_network=debug` (`RUST_LOG` wins at startup)
+    #[serde(default = "default_log_level")]
+    pub log_level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// Serve the Prometheus endpoint
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bind address for the metrics server
+    #[serde(default = "default_metrics_bind")]
+    pub bind: String,
+
+    /// PEM certificate chain; enables HTTPS together with `tls_key`
+    pub tls_cert: Option<String>,
+
+    /// PEM private key for `tls_cert`
+    pub tls_key: Option<String>,
+
+    /// Basic-auth username (requires `basic_auth_password`)
+    pub basic_auth_username: Option<String>,
+
+    /// Basic-auth password
+    pub basic_auth_password: Option<String>,
+
+    /// Static bearer token (alternative to basic auth)
+    pub bearer_token: Option<String>,
+
+    /// Client IPs allowed to scrape `/metrics` (empty = any client)
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotifyConfig {
+    /// Publish notifications to TCP subscribers
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bind address for the notification socket
+    #[serde(default = "default_notify_bind")]
+    pub bind: String,
+
+    /// Topics to publish: `newtemplate`, `newblock`, `newtx`
+    #[serde(default = "default_notify_topics")]
+    pub topics: Vec<String>,
+
+    /// Maximum number of connected subscribers
+    #[serde(default = "default_notify_max_clients")]
+    pub max_clients: usize,
+}
+
+/// Memory and open-file budgets, for running on small machines
+/// ميزانيات الذاكرة والملفات المفتوحة
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesConfig {
+    /// RocksDB block cache shared by the blockchain and state databases, in MB
+    #[serde(default = "default_block_cache_mb")]
+    pub block_cache_mb: usize,
+
+    /// RocksDB write buffers across both databases, in MB
+    #[serde(default = "default_write_buffer_mb")]
+    pub write_buffer_mb: usize,
+
+    /// Open database files across both databases (-1 = unlimited)
+    #[serde(default = "default_max_open_files")]
+    pub max_open_files: i32,
+
+    /// Pending transactions held in the mempool, in MB
+    #[serde(default = "default_mempool_max_mb")]
+    pub mempool_max_mb: usize,
+
+    /// Directory on a fast device (SSD) for the hot part of the state
+    /// database; blocks stay in the data directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_fast_path: Option<PathBuf>,
+
+    /// State database kept on the fast path before spilling back to the data
+    /// directory, in GB
+    #[serde(default = "default_state_fast_path_gb")]
+    pub state_fast_path_gb: u64,
+}
+
+/// Signed ban lists accepted from other operators
+/// قوائم الحظر الموقعة المقبولة من مشغلين آخرين
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanListsConfig {
+    /// Peer IDs of operators whose signed ban lists are accepted
+    #[serde(default)]
+    pub trusted_issuers: Vec<String>,
+
+    /// URLs or file paths of ban lists a running node fetches
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+
+    /// Longest ban an imported list may impose, in hours after it was issued
+    #[serde(default = "default_max_ban_hours")]
+    pub max_ban_hours: u64,
+
+    /// Minutes between fetches of the subscribed lists
+    #[serde(default = "default_ban_list_refresh_mins")]
+    pub refresh_mins: u64,
+}
+
+/// Thin storage keeping block headers and the transactions of watched addresses
+/// التخزين الخفيف للرؤوس ومعاملات العناوين المراقبة
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThinConfig {
+    /// Run the network node in thin mode (relay-only, no block serving)
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Hex public keys whose transactions are kept
+    #[serde(default)]
+    pub watchlist: Vec<String>,
 }
 
 // Default values
@@ -88,6 +273,10 @@ fn default_max_peers() -> usize {
     50
 }
 
+fn default_chain() -> String {
+    "mainnet".to_string()
+}
+
 fn default_difficulty() -> u32 {
     16
 }
@@ -100,6 +289,10 @@ fn default_log_file() -> String {
     "~/.opensyria/node/opensyria.log".to_string()
 }
 
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
 fn default_log_max_size_mb() -> u64 {
     100
 }
@@ -108,12 +301,65 @@ fn default_log_backups() -> usize {
     7
 }
 
+fn default_metrics_bind() -> String {
+    "127.0.0.1:9615".to_string()
+}
+
+fn default_notify_bind() -> String {
+    "127.0.0.1:28332".to_string()
+}
+
+fn default_notify_topics() -> Vec<String> {
+    Topic::ALL
+        .iter()
+        .map(|topic| topic.as_str().to_string())
+        .collect()
+}
+
+fn default_notify_max_clients() -> usize {
+    16
+}
+
+const MB: usize = 1024 * 1024;
+
+/// Descriptors kept free for sockets, logs and the WAL when checking `max_open_files`
+const RESERVED_FILE_DESCRIPTORS: u64 = 256;
+
+fn default_block_cache_mb() -> usize {
+    StorageLimits::default().block_cache_bytes / MB
+}
+
+fn default_write_buffer_mb() -> usize {
+    StorageLimits::default().write_buffer_bytes / MB
+}
+
+fn default_max_open_files() -> i32 {
+    StorageLimits::default().max_open_files
+}
+
+fn default_mempool_max_mb() -> usize {
+    MempoolConfig::default().max_total_bytes / MB
+}
+
+fn default_state_fast_path_gb() -> u64 {
+    64
+}
+
+fn default_max_ban_hours() -> u64 {
+    DEFAULT_MAX_IMPORTED_BAN_SECS / 3600
+}
+
+fn default_ban_list_refresh_mins() -> u64 {
+    DEFAULT_BAN_LIST_REFRESH_SECS / 60
+}
+
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             port: default_port(),
             bootstrap_nodes: Vec::new(),
             max_peers: default_max_peers(),
+            chain: default_chain(),
         }
     }
 }
@@ -124,8 +370,62 @@ impl Default for MiningConfig {
             difficulty: default_difficulty(),
             threads: default_mining_threads(),
             mining_address: None,
+            payout_split: Vec::new(),
+            extra_data: None,
+        }
+    }
+}
+
+impl MiningConfig {
+    /// Coinbase payout addresses, if configured
+    /// عناوين دفع مكافأة الكتلة إن وجدت
+    ///
+    /// `payout_split` takes precedence; otherwise `mining_address` receives
+    /// the whole reward.
+    pub fn coinbase_split(&self) -> Result<Option<CoinbaseSplit>> {
+        if !self.payout_split.is_empty() {
+            let shares = self
+                .payout_split
+                .iter()
+                .map(|share| {
+                    Ok(PayoutShare {
+                        address: parse_address(&share.address)?,
+                        percent: share.percent,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(Some(
+                CoinbaseSplit::new(shares).context("Invalid mining payout_split")?,
+            ));
+        }
+
+        match &self.mining_address {
+            Some(address) => Ok(Some(CoinbaseSplit::single(parse_address(address)?))),
+            None => Ok(None),
         }
     }
+
+    /// Coinbase extra data, empty if no tag is configured
+    /// البيانات الإضافية لمعاملة الكوين بيس
+    pub fn coinbase_extra_data(&self) -> Result<Vec<u8>> {
+        let extra_data = self.extra_data.clone().unwrap_or_default().into_bytes();
+        if extra_data.len() > MAX_COINBASE_EXTRA_DATA {
+            anyhow::bail!(
+                "Mining extra_data is {} bytes (max {})",
+                extra_data.len(),
+                MAX_COINBASE_EXTRA_DATA
+            );
+        }
+        Ok(extra_data)
+    }
+}
+
+fn parse_address(address: &str) -> Result<PublicKey> {
+    if address.len() != 64 {
+        anyhow::bail!("Mining address must be 64 hex characters (32 bytes)");
+    }
+    PublicKey::from_hex(address)
+        .map_err(|e| anyhow::anyhow!("Invalid mining address {}: {:?}", address, e))
 }
 
 impl Default for DaemonConfig {
@@ -135,10 +435,283 @@ impl Default for DaemonConfig {
             log_file: default_log_file(),
             log_max_size_mb: default_log_max_size_mb(),
             log_backups: default_log_backups(),
+            log_level: default_log_level(),
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_metrics_bind(),
+            tls_cert: None,
+            tls_key: None,
+            basic_auth_username: None,
+            basic_auth_password: None,
+            bearer_token: None,
+            allowed_ips: Vec::new(),
+        }
+    }
+}
+
+impl MetricsConfig {
+    /// Build the metrics server configuration
+    /// بناء تكوين خادم المقاييس
+    pub fn to_server_config(&self) -> Result<MetricsServerConfig> {
+        let bind_addr = self
+            .bind
+            .parse()
+            .context(format!("Invalid metrics bind address: {}", self.bind))?;
+
+        let tls = match (&self.tls_cert, &self.tls_key) {
+            (Some(cert), Some(key)) => Some(MetricsTlsConfig {
+                cert_path: PathBuf::from(shellexpand::tilde(cert).to_string()),
+                key_path: PathBuf::from(shellexpand::tilde(key).to_string()),
+            }),
+            (None, None) => None,
+            _ => anyhow::bail!("Metrics TLS requires both tls_cert and tls_key"),
+        };
+
+        let auth = match (
+            &self.basic_auth_username,
+            &self.basic_auth_password,
+            &self.bearer_token,
+        ) {
+            (None, None, None) => MetricsAuth::None,
+            (Some(username), Some(password), None) => MetricsAuth::Basic {
+                username: username.clone(),
+                password: password.clone(),
+            },
+            (None, None, Some(token)) => MetricsAuth::Bearer {
+                token: token.clone(),
+            },
+            (_, _, Some(_)) => {
+                anyhow::bail!("Metrics basic auth and bearer token are mutually exclusive")
+            }
+            _ => anyhow::bail!(
+                "Metrics basic auth requires both basic_auth_username and basic_auth_password"
+            ),
+        };
+
+        let allowed_ips = self
+            .allowed_ips
+            .iter()
+            .map(|ip| {
+                ip.parse()
+                    .context(format!("Invalid metrics allowlist IP: {}", ip))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(MetricsServerConfig {
+            bind_addr,
+            tls,
+            auth,
+            allowed_ips,
+        })
+    }
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_notify_bind(),
+            topics: default_notify_topics(),
+            max_clients: default_notify_max_clients(),
+        }
+    }
+}
+
+impl NotifyConfig {
+    /// Parsed bind address
+    /// عنوان الربط بعد التحليل
+    pub fn bind_addr(&self) -> Result<SocketAddr> {
+        self.bind
+            .parse()
+            .context(format!("Invalid notify bind address: {}", self.bind))
+    }
+
+    /// Parsed topic list
+    /// قائمة المواضيع بعد التحليل
+    pub fn topics(&self) -> Result<Vec<Topic>> {
+        self.topics
+            .iter()
+            .map(|name| {
+                Topic::parse(name).ok_or_else(|| anyhow::anyhow!("Unknown notify topic: {}", name))
+            })
+            .collect()
+    }
+}
+
+impl Default for ResourcesConfig {
+    fn default() -> Self {
+        Self {
+            block_cache_mb: default_block_cache_mb(),
+            write_buffer_mb: default_write_buffer_mb(),
+            max_open_files: default_max_open_files(),
+            mempool_max_mb: default_mempool_max_mb(),
+            state_fast_path: None,
+            state_fast_path_gb: default_state_fast_path_gb(),
+        }
+    }
+}
+
+impl ResourcesConfig {
+    /// Budgets to open the databases with
+    /// ميزانيات فتح قواعد البيانات
+    pub fn storage_limits(&self) -> StorageLimits {
+        StorageLimits {
+            block_cache_bytes: self.block_cache_mb.saturating_mul(MB),
+            write_buffer_bytes: self.write_buffer_mb.saturating_mul(MB),
+            max_open_files: self.max_open_files,
+        }
+    }
+
+    /// Layout of the state database at `db` relative to the data directory,
+    /// mirrored under the fast path so two nodes never share SST files
+    /// توزيع قاعدة بيانات الحالة على المسار السريع
+    pub fn state_options(&self, db: impl AsRef<Path>) -> StateOptions {
+        let options = StateOptions::default();
+        match &self.state_fast_path {
+            Some(fast_path) => options.with_fast_tier(StorageTier::new(
+                fast_path.join(db),
+                self.state_fast_path_gb.saturating_mul(1 << 30),
+            )),
+            None => options,
+        }
+    }
+
+    /// Mempool size limit in bytes
+    pub fn mempool_max_bytes(&self) -> usize {
+        self.mempool_max_mb.saturating_mul(MB)
+    }
+
+    /// Pool policy of a node running with these budgets
+    pub fn mempool_config(&self) -> MempoolConfig {
+        MempoolConfig {
+            max_total_bytes: self.mempool_max_bytes(),
+            ..MempoolConfig::default()
+        }
+    }
+
+    /// Memory the configured budgets add up to
+    pub fn memory_bytes(&self) -> u64 {
+        (self.storage_limits().memory_bytes() as u64)
+            .saturating_add(self.mempool_max_bytes() as u64)
+    }
+
+    /// Warnings about budgets this machine cannot honour
+    /// تحذيرات عن ميزانيات تتجاوز موارد النظام
+    pub fn preflight(&self) -> Vec<String> {
+        self.preflight_against(system_memory_bytes(), open_file_limit())
+    }
+
+    fn preflight_against(&self, memory: Option<u64>, file_limit: Option<u64>) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let configured = self.memory_bytes();
+        if let Some(memory) = memory.filter(|&memory| configured > memory) {
+            warnings.push(format!(
+                "Configured memory budgets ({} MB) exceed system memory ({} MB); lower block_cache_mb, write_buffer_mb or mempool_max_mb",
+                configured / MB as u64,
+                memory / MB as u64
+            ));
+        }
+        if let (Ok(files), Some(limit)) = (u64::try_from(self.max_open_files), file_limit) {
+            if files + RESERVED_FILE_DESCRIPTORS > limit {
+                warnings.push(format!(
+                    "max_open_files ({}) leaves too few of the process's {} file descriptors for sockets; lower it or raise `ulimit -n`",
+                    files, limit
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Total memory from `/proc/meminfo` (None where it is unavailable)
+fn system_memory_bytes() -> Option<u64> {
+    let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+    let kb = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Soft open-file limit from `/proc/self/limits` (None if unlimited or unavailable)
+fn open_file_limit() -> Option<u64> {
+    let limits = fs::read_to_string("/proc/self/limits").ok()?;
+    limits
+        .lines()
+        .find_map(|line| line.strip_prefix("Max open files"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+impl Default for BanListsConfig {
+    fn default() -> Self {
+        Self {
+            trusted_issuers: Vec::new(),
+            subscriptions: Vec::new(),
+            max_ban_hours: default_max_ban_hours(),
+            refresh_mins: default_ban_list_refresh_mins(),
         }
     }
 }
 
+impl BanListsConfig {
+    /// Trusted issuers and the local ban cap
+    /// المصدرون الموثوقون والحد الأقصى للحظر
+    pub fn policy(&self) -> Result<BanListPolicy> {
+        let issuers = self
+            .trusted_issuers
+            .iter()
+            .map(|issuer| {
+                issuer
+                    .parse::<PeerId>()
+                    .context(format!("Invalid trusted ban list issuer: {}", issuer))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(BanListPolicy::new(issuers)
+            .with_max_ban(std::time::Duration::from_secs(self.max_ban_hours * 3600)))
+    }
+
+    /// Lists a running node fetches (None without subscriptions)
+    pub fn subscriptions(&self) -> Result<Option<BanListSubscriptions>> {
+        if self.subscriptions.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(BanListSubscriptions {
+            refresh: std::time::Duration::from_secs(self.refresh_mins * 60),
+            ..BanListSubscriptions::new(self.subscriptions.clone(), self.policy()?)
+        }))
+    }
+}
+
+impl ThinConfig {
+    /// Addresses a thin node keeps (None when thin mode is off)
+    /// العناوين التي تحتفظ بها العقدة الخفيفة
+    pub fn watchlist(&self) -> Result<Option<Watchlist>> {
+        let addresses = self
+            .watchlist
+            .iter()
+            .map(|address| {
+                PublicKey::from_hex(address).map_err(|e| {
+                    anyhow::anyhow!("Invalid thin watchlist address {}: {:?}", address, e)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(self.enabled.then(|| Watchlist::new(addresses)))
+    }
+}
+
 impl Default for NodeConfig {
     fn default() -> Self {
         Self {
@@ -146,6 +719,11 @@ impl Default for NodeConfig {
             network: NetworkConfig::default(),
             mining: MiningConfig::default(),
             daemon: DaemonConfig::default(),
+            metrics: MetricsConfig::default(),
+            notify: NotifyConfig::default(),
+            resources: ResourcesConfig::default(),
+            ban_lists: BanListsConfig::default(),
+            thin: ThinConfig::default(),
         }
     }
 }
@@ -200,6 +778,8 @@ impl NodeConfig {
             anyhow::bail!("Invalid port: must be > 0");
         }
 
+        self.network.network_type()?;
+
         // Validate bootstrap nodes
         for node in &self.network.bootstrap_nodes {
             if node.is_empty() {
@@ -221,13 +801,55 @@ impl NodeConfig {
             anyhow::bail!("Mining threads must be > 0");
         }
 
-        // Validate mining address if provided
+        // Validate mining address and payout split if provided
         if let Some(addr) = &self.mining.mining_address {
             if addr.len() != 64 {
                 anyhow::bail!("Mining address must be 64 hex characters (32 bytes)");
             }
             hex::decode(addr).context("Invalid mining address: not valid hex")?;
         }
+        self.mining.coinbase_split()?;
+        self.mining.coinbase_extra_data()?;
+
+        // Validate the daemon log filter
+        opensyria_metrics::telemetry::validate_filter(&self.daemon.log_level)
+            .map_err(|e| anyhow::anyhow!("Invalid daemon log_level: {}", e))?;
+
+        // Validate metrics endpoint settings
+        self.metrics.to_server_config()?;
+
+        // Validate notification socket settings
+        self.notify.bind_addr()?;
+        self.notify.topics()?;
+        if self.notify.max_clients == 0 {
+            anyhow::bail!("Notify max_clients must be > 0");
+        }
+
+        // Validate resource budgets
+        if self.resources.block_cache_mb == 0 || self.resources.write_buffer_mb == 0 {
+            anyhow::bail!("Resources block_cache_mb and write_buffer_mb must be > 0");
+        }
+        if self.resources.mempool_max_mb == 0 {
+            anyhow::bail!("Resources mempool_max_mb must be > 0");
+        }
+        if self.resources.max_open_files != -1 && self.resources.max_open_files < 32 {
+            anyhow::bail!("Resources max_open_files must be -1 (unlimited) or at least 32");
+        }
+        if self.resources.state_fast_path.is_some() && self.resources.state_fast_path_gb == 0 {
+            anyhow::bail!("Resources state_fast_path_gb must be > 0 when state_fast_path is set");
+        }
+
+        // Validate ban list sharing
+        self.ban_lists.policy()?;
+        if self.ban_lists.max_ban_hours == 0 || self.ban_lists.refresh_mins == 0 {
+            anyhow::bail!("Ban lists max_ban_hours and refresh_mins must be > 0");
+        }
+        if !self.ban_lists.subscriptions.is_empty() && self.ban_lists.trusted_issuers.is_empty() {
+            anyhow::bail!("Ban list subscriptions need at least one trusted issuer");
+        }
+
+        // Validate the thin node watchlist
+        self.thin.watchlist()?;
 
         Ok(())
     }
@@ -261,6 +883,9 @@ bootstrap_nodes = [
 # Maximum number of peers | الحد الأقصى لعدد الأقران
 max_peers = 50
 
+# Chain to join: mainnet or testnet | الشبكة المراد الانضمام إليها
+chain = "mainnet"
+
 [mining]
 # Mining difficulty (1-255) | صعوبة التعدين
 difficulty = 16
@@ -271,6 +896,20 @@ threads = 4
 # Mining reward address (hex public key) | عنوان مكافأة التعدين
 # mining_address = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
 
+# Split the block reward across addresses; percentages must add up to 100
+# تقسيم مكافأة الكتلة بين عدة عناوين (مجموع النسب 100)
+# [[mining.payout_split]]
+# address = "0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef"
+# percent = 90
+#
+# [[mining.payout_split]]
+# address = "fedcba9876543210fedcba9876543210fedcba9876543210fedcba9876543210"
+# percent = 10
+
+# Miner or pool tag written into each coinbase (max 32 bytes)
+# وسم المعدّن أو المجمع في معاملة الكوين بيس (32 بايت كحد أقصى)
+# extra_data = "/my-pool/"
+
 [daemon]
 # Enable auto-mining when running as daemon | تفعيل التعدين التلقائي عند التشغيل كخدمة
 auto_mine = false
@@ -283,6 +922,85 @@ log_max_size_mb = 100
 
 # Number of log backup files to keep | عدد ملفات السجل الاحتياطية للاحتفاظ بها
 log_backups = 7
+
+# Log filter; applied again on SIGHUP | مرشح السجلات، يُعاد تطبيقه عند SIGHUP
+log_level = "info"
+
+[metrics]
+# Serve Prometheus metrics | تفعيل نقطة نهاية مقاييس Prometheus
+enabled = false
+
+# Bind address | عنوان الربط
+bind = "127.0.0.1:9615"
+
+# HTTPS certificate and key (PEM) | شهادة ومفتاح HTTPS
+# tls_cert = "~/.opensyria/metrics-cert.pem"
+# tls_key = "~/.opensyria/metrics-key.pem"
+
+# Basic auth or bearer token for /metrics | المصادقة لنقطة /metrics
+# basic_auth_username = "prometheus"
+# basic_auth_password = "change-me"
+# bearer_token = "change-me"
+
+# Client IPs allowed to scrape (empty = any) | عناوين IP المسموح لها بالجمع
+allowed_ips = []
+
+[notify]
+# Push newline-delimited JSON notifications to TCP subscribers
+# إرسال إشعارات JSON إلى المشتركين عبر TCP
+enabled = false
+
+# Bind address | عنوان الربط
+bind = "127.0.0.1:28332"
+
+# Topics to publish | المواضيع المنشورة
+topics = ["newtemplate", "newblock", "newtx"]
+
+# Maximum connected subscribers | الحد الأقصى للمشتركين
+max_clients = 16
+
+[resources]
+# Block cache shared by both databases, in MB | ذاكرة الكتل المؤقتة المشتركة
+block_cache_mb = 256
+
+# Write buffers across both databases, in MB | ذاكرة الكتابة لقاعدتي البيانات
+write_buffer_mb = 256
+
+# Open database files, -1 = unlimited | عدد ملفات قاعدة البيانات المفتوحة
+max_open_files = -1
+
+# Pending transactions held in the mempool, in MB | حجم مجمع المعاملات
+mempool_max_mb = 64
+
+# Keep the hot state database on a fast disk; set before the first sync
+# إبقاء قاعدة بيانات الحالة النشطة على قرص سريع
+# state_fast_path = "/mnt/ssd/opensyria"
+
+# State kept on the fast path before spilling to the data directory, in GB
+# حجم الحالة على المسار السريع
+state_fast_path_gb = 64
+
+[ban_lists]
+# Peer IDs of operators whose signed ban lists are accepted
+# معرفات المشغلين الموثوقين لقوائم الحظر
+trusted_issuers = []
+
+# URLs or files of signed ban lists to fetch | روابط قوائم الحظر المشترك بها
+# subscriptions = ["https://ops.example.org/banlist.json"]
+
+# Longest ban an imported list may impose, in hours | أقصى مدة حظر مستورد
+max_ban_hours = 24
+
+# Minutes between fetches | الدقائق بين عمليات الجلب
+refresh_mins = 15
+
+[thin]
+# Keep only block headers and watched transactions; set before the first sync
+# الاحتفاظ برؤوس الكتل ومعاملات العناوين المراقبة فقط
+enabled = false
+
+# Hex public keys whose transactions are kept | العناوين المراقبة
+watchlist = []
 "#;
 
         // Create parent directory if it doesn't exist
@@ -328,6 +1046,14 @@ mod tests {
         config.network.port = 9000;
         assert!(config.validate().is_ok());
 
+        // Unknown chain
+        config.network.chain = "devnet".to_string();
+        assert!(config.validate().is_err());
+
+        config.network.chain = "testnet".to_string();
+        assert_eq!(config.network.network_type().unwrap(), NetworkType::Testnet);
+        assert!(config.validate().is_ok());
+
         // Invalid difficulty
         config.mining.difficulty = 0;
         assert!(config.validate().is_err());
@@ -339,6 +1065,182 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_payout_split_config() {
+        let mut config = NodeConfig::default();
+        assert!(config.mining.coinbase_split().unwrap().is_none());
+
+        let operator = "11".repeat(32);
+        let provider = "22".repeat(32);
+        config.mining.mining_address = Some(operator.clone());
+        let split = config.mining.coinbase_split().unwrap().unwrap();
+        assert_eq!(split.shares()[0].percent, 100);
+
+        config.mining.payout_split = vec![
+            PayoutShareConfig {
+                address: operator,
+                percent: 90,
+            },
+            PayoutShareConfig {
+                address: provider,
+                percent: 5,
+            },
+        ];
+        assert!(config.validate().is_err());
+
+        config.mining.payout_split[1].percent = 10;
+        assert!(config.validate().is_ok());
+        let split = config.mining.coinbase_split().unwrap().unwrap();
+        assert_eq!(split.shares().len(), 2);
+        assert_eq!(split.amounts(1_000)[1].1, 100);
+
+        assert!(config.mining.coinbase_extra_data().unwrap().is_empty());
+        config.mining.extra_data = Some("/syria-pool/".to_string());
+        assert_eq!(
+            config.mining.coinbase_extra_data().unwrap(),
+            b"/syria-pool/"
+        );
+        config.mining.extra_data = Some("x".repeat(33));
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_metrics_config() {
+        let mut config = NodeConfig::default();
+        assert!(!config.metrics.enabled);
+        let server = config.metrics.to_server_config().unwrap();
+        assert!(server.tls.is_none());
+        assert!(matches!(server.auth, MetricsAuth::None));
+
+        config.metrics.bearer_token = Some("token".to_string());
+        config.metrics.allowed_ips = vec!["10.0.0.5".to_string()];
+        let server = config.metrics.to_server_config().unwrap();
+        assert!(matches!(server.auth, MetricsAuth::Bearer { .. }));
+        assert_eq!(
+            server.allowed_ips,
+            vec!["10.0.0.5".parse::<std::net::IpAddr>().unwrap()]
+        );
+
+        // Conflicting auth schemes
+        config.metrics.basic_auth_username = Some("prometheus".to_string());
+        config.metrics.basic_auth_password = Some("scrape".to_string());
+        assert!(config.validate().is_err());
+
+        // Half-configured TLS
+        config.metrics.bearer_token = None;
+        config.metrics.tls_cert = Some("cert.pem".to_string());
+        assert!(config.validate().is_err());
+
+        config.metrics.tls_cert = None;
+        config.metrics.allowed_ips = vec!["not-an-ip".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_notify_config() {
+        let mut config = NodeConfig::default();
+        assert!(!config.notify.enabled);
+        assert_eq!(config.notify.topics().unwrap(), Topic::ALL.to_vec());
+
+        config.notify.topics = vec!["newblock".to_string()];
+        assert_eq!(config.notify.topics().unwrap(), vec![Topic::NewBlock]);
+
+        config.notify.topics.push("hashblock".to_string());
+        assert!(config.validate().is_err());
+
+        config.notify.topics.pop();
+        config.notify.bind = "localhost".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_resources_config() {
+        let mut config = NodeConfig::default();
+        assert_eq!(
+            config.resources.storage_limits(),
+            opensyria_storage::StorageLimits::default()
+        );
+        assert_eq!(
+            config.resources.mempool_max_bytes(),
+            MempoolConfig::default().max_total_bytes
+        );
+
+        config.resources.block_cache_mb = 64;
+        config.resources.write_buffer_mb = 32;
+        config.resources.mempool_max_mb = 32;
+        config.resources.max_open_files = 1_000;
+        assert!(config.validate().is_ok());
+        assert_eq!(config.resources.memory_bytes(), 128 * MB as u64);
+
+        // A 512 MB VPS fits; a 100 MB one and a 1024-descriptor limit do not
+        let gb = 1024 * MB as u64;
+        assert!(config
+            .resources
+            .preflight_against(Some(gb / 2), Some(4_096))
+            .is_empty());
+        let warnings = config
+            .resources
+            .preflight_against(Some(100 * MB as u64), Some(1_024));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].contains("exceed system memory"));
+
+        config.resources.max_open_files = 8;
+        assert!(config.validate().is_err());
+        config.resources.max_open_files = 1_000;
+
+        assert_eq!(config.resources.state_options("state").fast_tier, None);
+        config.resources.state_fast_path = Some(PathBuf::from("/mnt/ssd/opensyria"));
+        assert_eq!(
+            config.resources.state_options("network/state").fast_tier,
+            Some(StorageTier::new(
+                "/mnt/ssd/opensyria/network/state",
+                64 << 30
+            ))
+        );
+        config.resources.state_fast_path_gb = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_ban_lists_config() {
+        let mut config = NodeConfig::default();
+        assert!(config.ban_lists.subscriptions().unwrap().is_none());
+
+        config.ban_lists.subscriptions = vec!["https://ops.example.org/banlist.json".to_string()];
+        assert!(config.validate().is_err());
+
+        let issuer = PeerId::random();
+        config.ban_lists.trusted_issuers = vec![issuer.to_string()];
+        config.ban_lists.max_ban_hours = 6;
+        assert!(config.validate().is_ok());
+        let subscriptions = config.ban_lists.subscriptions().unwrap().unwrap();
+        assert!(subscriptions.policy.trusted_issuers.contains(&issuer));
+        assert_eq!(subscriptions.policy.max_ban.as_secs(), 6 * 3600);
+        assert_eq!(subscriptions.refresh.as_secs(), 15 * 60);
+
+        config
+            .ban_lists
+            .trusted_issuers
+            .push("not-a-peer-id".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_thin_config() {
+        let mut config = NodeConfig::default();
+        assert!(config.thin.watchlist().unwrap().is_none());
+
+        let address = opensyria_core::crypto::KeyPair::generate().public_key();
+        config.thin.enabled = true;
+        config.thin.watchlist = vec![address.to_hex()];
+        assert!(config.validate().is_ok());
+        let watchlist = config.thin.watchlist().unwrap().unwrap();
+        assert_eq!(watchlist.addresses(), vec![address]);
+
+        config.thin.watchlist.push("not-an-address".to_string());
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_save_and_load() {
         let dir = tempdir().unwrap();
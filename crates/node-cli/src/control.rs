@@ -0,0 +1,244 @@
+//! Local control socket for a running daemon
+//! مقبس التحكم المحلي للخدمة العاملة
+//!
+//! JSON-RPC 2.0, one request and one response per line, on a Unix socket in
+//! the data directory:
+//!
+//! ```text
+//! {"jsonrpc":"2.0","id":1,"method":"reloadconfig"}
+//! {"jsonrpc":"2.0","id":1,"result":{"applied":["daemon.log_level"],"restart_required":[]}}
+//! ```
+//!
+//! `validatetemplate` takes `{"block": <block JSON>}` as its params and
+//! answers with a [`TemplateCheck`](crate::node::TemplateCheck).
+//!
+//! A request may carry the caller's W3C trace context in `traceparent` and
+//! `tracestate` members beside `method`; the call's span then joins that
+//! trace.
+//!
+//! The socket file is readable and writable by the daemon's user only, and
+//! nothing else is checked. Requests are handed to the daemon loop, which
+//! owns the node.
+
+use anyhow::{Context, Result};
+use opensyria_metrics::telemetry::link_remote_parent;
+use serde_json::{json, Value};
+use std::path::Path;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+/// Socket file name inside the daemon's data directory
+pub const CONTROL_SOCKET: &str = "daemon.sock";
+
+/// Methods the daemon answers
+pub const METHODS: &[&str] = &["reloadconfig", "validatetemplate"];
+
+/// JSON-RPC error codes
+const RPC_PARSE_ERROR: i64 = -32700;
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+const RPC_SERVER_ERROR: i64 = -32000;
+
+/// A method call waiting for the daemon loop to answer it
+pub struct ControlRequest {
+    pub method: String,
+    /// The call's `params`, `null` when absent
+    pub params: Value,
+    pub reply: oneshot::Sender<Result<Value, String>>,
+    /// Span to answer the call in, linked to the caller's trace
+    pub span: tracing::Span,
+}
+
+/// Accept control connections on `path`, passing calls to `requests`
+///
+/// A socket file left by a daemon that is gone is replaced; one answered by
+/// a live daemon is an error.
+pub async fn serve(path: &Path, requests: mpsc::Sender<ControlRequest>) -> Result<JoinHandle<()>> {
+    #[cfg(unix)]
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::UnixListener;
+
+        if is_live(path).await {
+            anyhow::bail!("Another daemon is using {}", path.display());
+        }
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path)
+            .context(format!("Failed to bind control socket {}", path.display()))?;
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+
+        Ok(tokio::spawn(async move {
+            loop {
+                let stream = match listener.accept().await {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Control socket accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let requests = requests.clone();
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let mut response = handle(&line, &requests).await.to_string();
+                        response.push('\n');
+                        if writer.write_all(response.as_bytes()).await.is_err() {
+                            return;
+                        }
+                    }
+                });
+            }
+        }))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = requests;
+        anyhow::bail!("Control socket {} needs a Unix platform", path.display())
+    }
+}
+
+/// Answer one request line
+async fn handle(line: &str, requests: &mpsc::Sender<ControlRequest>) -> Value {
+    let request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return error(Value::Null, RPC_PARSE_ERROR, e.to_string()),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request
+        .get("method")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if !METHODS.contains(&method) {
+        return error(
+            id,
+            RPC_METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        );
+    }
+
+    let span = tracing::info_span!("control_rpc", method = %method);
+    let member = |name: &str| request.get(name).and_then(Value::as_str);
+    link_remote_parent(&span, member("traceparent"), member("tracestate"));
+
+    let (reply, answer) = oneshot::channel();
+    let request = ControlRequest {
+        method: method.to_string(),
+        params: request.get("params").cloned().unwrap_or(Value::Null),
+        reply,
+        span,
+    };
+    if requests.send(request).await.is_err() {
+        return error(id, RPC_SERVER_ERROR, "Daemon is shutting down".to_string());
+    }
+    match answer.await {
+        Ok(Ok(result)) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Ok(Err(message)) => error(id, RPC_SERVER_ERROR, message),
+        Err(_) => error(
+            id,
+            RPC_SERVER_ERROR,
+            "Daemon dropped the request".to_string(),
+        ),
+    }
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Whether a daemon answers on the control socket at `path`
+pub async fn is_live(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        tokio::net::UnixStream::connect(path).await.is_ok()
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// Call `method` with `params` on the daemon listening on `path`
+/// استدعاء طريقة على الخدمة العاملة
+pub async fn call(path: &Path, method: &str, params: Value) -> Result<Value> {
+    #[cfg(unix)]
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::UnixStream;
+
+        let stream = UnixStream::connect(path)
+            .await
+            .context(format!("No daemon listening on {}", path.display()))?;
+        let (reader, mut writer) = stream.into_split();
+        let mut request = json!({ "jsonrpc": "2.0", "id": 1, "method": method });
+        if !params.is_null() {
+            request["params"] = params;
+        }
+        let mut request = request.to_string();
+        request.push('\n');
+        writer.write_all(request.as_bytes()).await?;
+
+        let line = BufReader::new(reader)
+            .lines()
+            .next_line()
+            .await?
+            .context("Daemon closed the control socket")?;
+        let mut response: Value = serde_json::from_str(&line)?;
+        if let Some(error) = response.get("error") {
+            anyhow::bail!(
+                "{}",
+                error["message"]
+                    .as_str()
+                    .unwrap_or("Daemon returned an error")
+            );
+        }
+        Ok(response["result"].take())
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (method, params);
+        anyhow::bail!("Control socket {} needs a Unix platform", path.display())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_control_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONTROL_SOCKET);
+        let (sender, mut receiver) = mpsc::channel(1);
+        let server = serve(&path, sender).await.unwrap();
+        tokio::spawn(async move {
+            while let Some(request) = receiver.recv().await {
+                let result = json!({ "method": request.method, "params": request.params });
+                let _ = request.reply.send(Ok(result));
+            }
+        });
+
+        let result = call(&path, "reloadconfig", Value::Null).await.unwrap();
+        assert_eq!(result["method"], "reloadconfig");
+        assert!(result["params"].is_null());
+        let params = json!({ "block": { "transactions": [] } });
+        let result = call(&path, "validatetemplate", params.clone())
+            .await
+            .unwrap();
+        assert_eq!(result["params"], params);
+        let err = call(&path, "stop", Value::Null).await.unwrap_err();
+        assert!(err.to_string().contains("Method not found"));
+
+        // The socket is taken while the daemon lives
+        assert!(is_live(&path).await);
+        let (sender, _) = mpsc::channel(1);
+        assert!(serve(&path, sender).await.is_err());
+        server.abort();
+    }
+}
@@ -1,5 +1,11 @@
+pub mod build_info;
 pub mod config;
+pub mod control;
 pub mod node;
+pub mod notify;
+pub mod preflight;
+pub mod reload;
+pub mod release;
 
 pub use config::NodeConfig;
 pub use node::Node;
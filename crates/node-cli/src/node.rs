@@ -1,21 +1,101 @@
 use anyhow::{Context, Result};
 use colored::*;
-use opensyria_consensus::{MiningStats, ProofOfWork};
-use opensyria_core::{crypto::PublicKey, Block, Transaction};
+use opensyria_consensus::{MiningStats, ProofOfWork, TemplateInfo};
+use opensyria_core::anchor::{AnchorPublisher, ANCHOR_DIR};
+use opensyria_core::audit::{AuditEvent, AuditLog, BalanceChange};
+use opensyria_core::{
+    asset::AssetOperation,
+    constants::MIN_TRANSACTION_FEE,
+    contract::ContractCall,
+    crypto::{KeyPair, PublicKey},
+    delegation::{DelegationError, HotKeyDelegation},
+    dex::DexOperation,
+    oracle::{OracleError, OracleUpdate},
+    policy::{policy_day, PolicyError, SpendingPolicy},
+    transaction::TransactionError,
+    Anomaly, Block, ChainParams, CoinbaseSplit, SafeMode, Transaction,
+};
 use opensyria_governance::{
-    GovernanceConfig, GovernanceManager, GovernanceStorage, ProposalType, Vote,
+    EmergencyAction, EmergencyCouncil, GovernanceConfig, GovernanceManager, GovernanceStorage,
+    MotionStatus, ProposalType, Vote,
 };
-use opensyria_storage::Storage;
+use opensyria_mempool::{check_replacement, MempoolError};
+use opensyria_storage::{RuleViolation, StateOptions, Storage, StorageLimits};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Audit log file name inside the data directory; auditing is enabled
+/// when this file exists
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+
+/// Result of checking a proposed block template
+/// نتيجة التحقق من قالب كتلة مقترح
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct TemplateCheck {
+    /// Block hash, hex
+    pub hash: String,
+    pub transactions: usize,
+    /// Height the block would take, when it passes
+    pub height: Option<u64>,
+    /// Fees of its non-coinbase transactions, when it passes
+    pub fees: u64,
+    /// First rule the block breaks
+    pub rule: Option<String>,
+    /// Offending transaction, for per-transaction rules
+    pub tx_index: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// Validated, unmined next block
+/// قالب كتلة جاهز للتعدين
+pub struct BlockTemplate {
+    /// Height the block takes
+    pub height: u64,
+    pub block: Block,
+}
+
+impl BlockTemplate {
+    /// Find the proof of work with `threads` (all cores by default); this
+    /// does not touch the node, so it can run on a blocking thread
+    pub fn mine(self, threads: Option<usize>) -> Block {
+        let pow = ProofOfWork::new(self.block.header.difficulty);
+        pow.mine_parallel(self.block, threads).0
+    }
+
+    /// Find the proof of work on one thread, giving up once `stop` is set;
+    /// the flag is checked every `check_interval`
+    pub fn mine_until_stopped(self, check_interval: Duration, stop: &AtomicBool) -> Option<Block> {
+        let pow = ProofOfWork::new(self.block.header.difficulty);
+        let mined = pow.mine_until(self.block, check_interval, || stop.load(Ordering::Relaxed));
+        mined.map(|(block, _stats)| block)
+    }
+
+    /// Parent and fees the template was built from, for [`TemplatePolicy`]
+    ///
+    /// [`TemplatePolicy`]: opensyria_consensus::TemplatePolicy
+    pub fn info(&self) -> TemplateInfo {
+        let transactions: Vec<Transaction> = self
+            .block
+            .transactions
+            .iter()
+            .filter(|tx| !tx.is_coinbase())
+            .cloned()
+            .collect();
+        TemplateInfo::new(self.block.header.previous_hash, &transactions)
+    }
+}
 
 /// Blockchain node with mining and transaction processing
 pub struct Node {
     storage: Storage,
     governance_storage: GovernanceStorage,
     pending_transactions: HashMap<[u8; 32], Transaction>,
-    #[allow(dead_code)]
+    coinbase_split: Option<CoinbaseSplit>,
+    coinbase_extra_data: Vec<u8>,
+    audit: Option<Arc<AuditLog>>,
     data_dir: PathBuf,
 }
 
@@ -24,7 +104,8 @@ impl Node {
     pub fn init(data_dir: PathBuf, _difficulty: u32) -> Result<Self> {
         std::fs::create_dir_all(&data_dir).context("Failed to create data directory")?;
 
-        let storage = Storage::open(data_dir.clone()).context("Failed to open storage")?;
+        let mut storage = Storage::open(data_dir.clone()).context("Failed to open storage")?;
+        let audit = Self::open_audit_log(&data_dir, &mut storage)?;
 
         // Initialize governance
         let gov_dir = data_dir.join("governance");
@@ -55,13 +136,34 @@ impl Node {
             storage,
             governance_storage,
             pending_transactions: HashMap::new(),
+            coinbase_split: None,
+            coinbase_extra_data: Vec::new(),
+            audit,
             data_dir,
         })
     }
 
     /// Open existing blockchain node
     pub fn open(data_dir: PathBuf) -> Result<Self> {
-        let storage = Storage::open(data_dir.clone()).context("Failed to open storage")?;
+        Self::open_with_limits(data_dir, StorageLimits::default())
+    }
+
+    /// Open existing blockchain node within database memory and file budgets
+    /// فتح العقدة ضمن ميزانيات الذاكرة والملفات
+    pub fn open_with_limits(data_dir: PathBuf, limits: StorageLimits) -> Result<Self> {
+        Self::open_with_options(data_dir, limits, &StateOptions::default())
+    }
+
+    /// Open existing blockchain node with the state database laid out by `state`
+    /// فتح العقدة مع توزيع قاعدة بيانات الحالة
+    pub fn open_with_options(
+        data_dir: PathBuf,
+        limits: StorageLimits,
+        state: &StateOptions,
+    ) -> Result<Self> {
+        let mut storage = Storage::open_with_options(data_dir.clone(), limits, state)
+            .context("Failed to open storage")?;
+        let audit = Self::open_audit_log(&data_dir, &mut storage)?;
 
         let gov_dir = data_dir.join("governance");
         let governance_storage =
@@ -82,10 +184,118 @@ impl Node {
             storage,
             governance_storage,
             pending_transactions: HashMap::new(),
+            coinbase_split: None,
+            coinbase_extra_data: Vec::new(),
+            audit,
             data_dir,
         })
     }
 
+    /// Attach the audit log to state storage if auditing is enabled
+    fn open_audit_log(data_dir: &Path, storage: &mut Storage) -> Result<Option<Arc<AuditLog>>> {
+        let path = data_dir.join(AUDIT_LOG_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let audit = Arc::new(
+            AuditLog::open(&path)
+                .with_context(|| format!("Refusing to extend audit log {}", path.display()))?,
+        );
+        storage.state.set_audit_log(audit.clone())?;
+        tracing::info!(
+            "Audit log enabled at {} ({} entries)",
+            path.display(),
+            audit.len()
+        );
+        Ok(Some(audit))
+    }
+
+    /// Publish a signed chain-state anchor every `interval` blocks this node applies
+    /// نشر مراسي حالة السلسلة الموقعة
+    ///
+    /// Anchors and their signing key live in the data directory; see
+    /// [`opensyria_core::anchor`].
+    #[allow(dead_code)]
+    pub fn enable_anchoring(&mut self, interval: u64) -> Result<Arc<AnchorPublisher>> {
+        let anchors = Arc::new(
+            AnchorPublisher::open(&self.data_dir, interval)
+                .context("Failed to open anchor directory")?,
+        );
+        self.storage.state.set_anchor_publisher(anchors.clone());
+        tracing::info!(
+            "Publishing chain-state anchors every {} blocks to {}",
+            interval,
+            anchors.dir().display()
+        );
+        Ok(anchors)
+    }
+
+    /// Directory holding the anchors published from this data directory
+    #[allow(dead_code)]
+    pub fn anchor_dir(&self) -> PathBuf {
+        self.data_dir.join(ANCHOR_DIR)
+    }
+
+    /// Safe mode marker for this node's data directory
+    pub fn safe_mode(&self) -> SafeMode {
+        SafeMode::new(&self.data_dir)
+    }
+
+    /// Check the chain against built-in checkpoints and the supply invariant
+    /// تدقيق السلسلة مقابل نقاط الفحص المدمجة وثبات العرض الكلي
+    ///
+    /// Enters safe mode on the first anomaly found. Returns the anomaly only
+    /// if it newly put the node into safe mode. The supply invariant reads
+    /// every balance; long-running callers use [`Self::audit_checkpoints`]
+    /// and run [`StateStorage::supply_check`](opensyria_storage::StateStorage::supply_check)
+    /// on a blocking thread.
+    pub fn audit_chain(&self) -> Result<Option<Anomaly>> {
+        if let Some(anomaly) = self.audit_checkpoints()? {
+            return Ok(Some(anomaly));
+        }
+        match self.storage.state.check_supply_invariant()? {
+            Some(anomaly) => self.enter_safe_mode(anomaly),
+            None => Ok(None),
+        }
+    }
+
+    /// Check the chain against built-in and emergency council checkpoints,
+    /// entering safe mode on a conflict; see [`Self::audit_chain`]
+    pub fn audit_checkpoints(&self) -> Result<Option<Anomaly>> {
+        let mut checkpoints = opensyria_consensus::CheckpointRegistry::new(false);
+        let council = self.load_governance()?.council_checkpoints();
+        let council = council
+            .into_iter()
+            .map(|(height, hash)| opensyria_consensus::Checkpoint { height, hash });
+        for (checkpoint, e) in checkpoints.set_council(council) {
+            tracing::warn!(
+                "Ignoring council checkpoint at height {}: {}",
+                checkpoint.height,
+                e
+            );
+        }
+        let blockchain = &self.storage.blockchain;
+        match blockchain.find_checkpoint_conflict(&checkpoints)? {
+            Some(anomaly) => self.enter_safe_mode(anomaly),
+            None => Ok(None),
+        }
+    }
+
+    /// Stop mining until an operator runs `resume`; returns the anomaly only
+    /// if it newly put the node into safe mode
+    pub fn enter_safe_mode(&self, anomaly: Anomaly) -> Result<Option<Anomaly>> {
+        let height = self.get_height()?;
+        if !self.safe_mode().enter(anomaly.clone(), height)? {
+            return Ok(None);
+        }
+        tracing::error!(
+            "Entering safe mode: {}. Mining and relaying stopped; run `resume` after review",
+            anomaly
+        );
+        Ok(Some(anomaly))
+    }
+
     /// Get current blockchain height
     pub fn get_height(&self) -> Result<u64> {
         self.storage
@@ -132,6 +342,37 @@ impl Node {
             .context("Failed to get balance")
     }
 
+    /// Validate and produce blocks with the parameters of another network
+    /// استخدام معاملات شبكة أخرى للتحقق من الكتل وإنتاجها
+    pub fn set_chain_params(&mut self, params: ChainParams) {
+        self.storage.blockchain.set_chain_params(params);
+    }
+
+    /// Pay block rewards according to `split` (None = a fresh address per block)
+    /// تحديد عناوين دفع مكافأة الكتلة
+    pub fn set_coinbase_split(&mut self, split: Option<CoinbaseSplit>) {
+        self.coinbase_split = split;
+    }
+
+    /// Write `extra_data` (a miner or pool tag) into every coinbase
+    /// تحديد وسم المعدّن في معاملات الكوين بيس
+    pub fn set_coinbase_extra_data(&mut self, extra_data: Vec<u8>) {
+        self.coinbase_extra_data = extra_data;
+    }
+
+    /// Coinbase transactions for a block at `height` collecting `fees`
+    pub fn coinbase_transactions(&self, height: u64, fees: u64) -> Result<Vec<Transaction>> {
+        let split = match &self.coinbase_split {
+            Some(split) => split.clone(),
+            None => CoinbaseSplit::single(self.get_miner_address()?),
+        };
+        split
+            .with_extra_data(self.coinbase_extra_data.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid coinbase extra data: {}", e))?
+            .coinbase_transactions(opensyria_core::CHAIN_ID_MAINNET, height, fees)
+            .map_err(|e| anyhow::anyhow!("Failed to create coinbase transaction: {:?}", e))
+    }
+
     /// Get miner address (temporary: generates new address each time)
     /// TODO: Load from wallet configuration
     fn get_miner_address(&self) -> Result<PublicKey> {
@@ -148,6 +389,12 @@ impl Node {
         // Verify signature
         tx.verify().context("Transaction verification failed")?;
 
+        for account in [tx.from, tx.fee_payer()] {
+            if self.storage.state.is_frozen(&account)? {
+                anyhow::bail!("Account {} is frozen by governance", account.to_hex());
+            }
+        }
+
         // Check nonce
         let expected_nonce = self.storage.state.get_nonce(&tx.from)?;
         if tx.nonce != expected_nonce {
@@ -158,16 +405,48 @@ impl Node {
             );
         }
 
-        // Process transfer
-        let total = tx.amount + tx.fee;
+        let sender_before = self.storage.state.get_balance(&tx.from)?;
+        let receiver_before = self.storage.state.get_balance(&tx.to)?;
+
+        // Process transfer; a sponsor pays the fee separately
+        let payer_before = self.storage.state.get_balance(&tx.fee_payer())?;
+        if tx.fee_payer() != tx.from && payer_before < tx.fee {
+            anyhow::bail!("Fee payer has insufficient balance");
+        }
         self.storage
             .state
-            .transfer(&tx.from, &tx.to, total)
+            .transfer(&tx.from, &tx.to, tx.debit(&tx.from))
             .context("Transfer failed")?;
+        if tx.fee_payer() != tx.from {
+            self.storage.state.sub_balance(&tx.fee_payer(), tx.fee)?;
+        }
 
         // Increment nonce
         self.storage.state.increment_nonce(&tx.from)?;
 
+        if let Some(audit) = &self.audit {
+            let mut balances = vec![
+                BalanceChange::new(
+                    &tx.from,
+                    sender_before,
+                    self.storage.state.get_balance(&tx.from)?,
+                ),
+                BalanceChange::new(
+                    &tx.to,
+                    receiver_before,
+                    self.storage.state.get_balance(&tx.to)?,
+                ),
+            ];
+            if tx.fee_payer() != tx.from {
+                balances.push(BalanceChange::new(
+                    &tx.fee_payer(),
+                    payer_before,
+                    self.storage.state.get_balance(&tx.fee_payer())?,
+                ));
+            }
+            audit.record(AuditEvent::transaction(&tx, false, balances))?;
+        }
+
         tracing::info!(
             "Processed transaction: {} SYL from {}... to {}...",
             tx.amount as f64 / 1_000_000.0,
@@ -178,18 +457,33 @@ impl Node {
         Ok(())
     }
 
-    /// Start mining blocks
-    pub fn start_mining(&mut self, block_count: u32, difficulty: u32, verbose: bool) -> Result<()> {
+    /// Start mining blocks, at the difficulty the chain requires unless one
+    /// is given
+    pub fn start_mining(
+        &mut self,
+        block_count: u32,
+        difficulty: Option<u32>,
+        verbose: bool,
+    ) -> Result<()> {
         println!("{}", "═".repeat(60).cyan());
         println!("{}", "  OpenSyria Mining Node  ".cyan().bold());
         println!("{}", "═".repeat(60).cyan());
         println!();
 
-        let pow = ProofOfWork::new(difficulty);
+        // Never extend a chain that contradicts a checkpoint or the supply
+        self.audit_chain()?;
+
         let current_height = self.get_height()?;
 
         println!("{}: {}", "Starting height".yellow(), current_height);
-        println!("{}: {}", "Difficulty".yellow(), difficulty);
+        println!(
+            "{}: {}",
+            "Difficulty".yellow(),
+            match difficulty {
+                Some(difficulty) => difficulty.to_string(),
+                None => "chain schedule".to_string(),
+            }
+        );
         println!(
             "{}: {}",
             "Target blocks".yellow(),
@@ -205,33 +499,42 @@ impl Node {
         let mining_start = Instant::now();
 
         loop {
+            if let Some(record) = self.safe_mode().record()? {
+                anyhow::bail!(
+                    "Node is in safe mode ({}); mining stopped. Run `resume` after review",
+                    record.anomaly
+                );
+            }
+
             // Get current tip
             let tip = self.get_tip()?.context("No tip block found")?;
             let previous_hash = tip.hash();
             let new_height = current_height + mined_count as u64 + 1;
 
-            // Get miner address (use first wallet address or generate one)
-            let miner_address = self.get_miner_address()?;
-
             // Calculate total fees from pending transactions
             let total_fees: u64 = 0; // TODO: sum fees from pending transactions when mempool integrated
 
-            // Create coinbase transaction
-            let coinbase = Transaction::coinbase(
-                opensyria_core::CHAIN_ID_MAINNET,
-                miner_address,
-                new_height,
-                total_fees,
-            )
-            .context("Failed to create coinbase transaction")?;
-
-            // Create transactions vector with coinbase first
-            let transactions = vec![coinbase];
+            // Create coinbase transactions, one per payout address, first
+            let transactions = self.coinbase_transactions(new_height, total_fees)?;
             // TODO: Add pending transactions from mempool
 
             // Create new block with coinbase
+            let difficulty = match difficulty {
+                Some(difficulty) => difficulty,
+                None => self.storage.blockchain.next_difficulty()?,
+            };
+            let pow = ProofOfWork::new(difficulty);
             let block = Block::new(previous_hash, transactions, difficulty);
 
+            // Check the payout split against consensus rules before mining
+            block
+                .validate_coinbase_with_params(
+                    new_height,
+                    self.storage.state.get_total_supply()?,
+                    self.storage.blockchain.chain_params(),
+                )
+                .map_err(|e| anyhow::anyhow!("Invalid coinbase: {}", e))?;
+
             if verbose {
                 println!(
                     "{} {}",
@@ -303,33 +606,284 @@ impl Node {
         Ok(())
     }
 
+    /// Check a proposed block template as the next block without mining it
+    /// التحقق من قالب كتلة مقترح دون تعدينه أو تعديل التخزين
+    ///
+    /// Runs every consensus and state rule except proof of work and returns
+    /// the height the block would take, or the first rule it breaks.
+    pub fn check_block_template(&self, block: &Block) -> Result<u64, RuleViolation> {
+        self.storage.check_block(block, false)
+    }
+
+    /// [`Self::check_block_template`] as a report for `mine validate-template`
+    /// and the daemon's `validatetemplate` control method
+    pub fn validate_template(&self, block: &Block) -> TemplateCheck {
+        let mut check = TemplateCheck {
+            hash: hex::encode(block.hash()),
+            transactions: block.transactions.len(),
+            height: None,
+            fees: 0,
+            rule: None,
+            tx_index: None,
+            error: None,
+        };
+        match self.check_block_template(block) {
+            Ok(height) => {
+                check.height = Some(height);
+                let transfers = block.transactions.iter().filter(|tx| !tx.is_coinbase());
+                check.fees = transfers.map(|tx| tx.fee).sum();
+            }
+            Err(violation) => {
+                check.rule = Some(violation.rule.to_string());
+                check.tx_index = violation.tx_index;
+                check.error = Some(violation.error.to_string());
+            }
+        }
+        check
+    }
+
+    /// Mine `count` blocks paying their rewards to `address` (regtest only)
+    /// تعدين عدد من الكتل لصالح عنوان محدد (شبكة الاختبار المحلية فقط)
+    ///
+    /// While a mock time is set the mock clock is advanced whenever a block
+    /// has to be later than it, so any number of blocks can be generated at
+    /// once. Returns the block hashes in chain order.
+    #[allow(dead_code)]
+    pub fn generate_to_address(
+        &mut self,
+        count: u32,
+        address: &PublicKey,
+    ) -> Result<Vec<[u8; 32]>> {
+        let params = self.storage.blockchain.chain_params();
+        if params.network != "regtest" {
+            anyhow::bail!(
+                "Block generation is only available on regtest (this node runs {})",
+                params.network
+            );
+        }
+
+        let mut hashes = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            hashes.push(self.mine_block_to(address, Some(1))?.hash());
+        }
+        Ok(hashes)
+    }
+
+    /// Mine one block on the tip paying the reward and fees to `address`
+    /// تعدين كتلة واحدة لصالح عنوان محدد
+    ///
+    /// Builds a [`BlockTemplate`], mines it with `threads` (all cores by
+    /// default) and applies it. Mining holds `self` throughout; callers that
+    /// share the node should build the template, mine it without the node
+    /// and hand it to [`Self::submit_mined_block`] instead.
+    #[allow(dead_code)]
+    pub fn mine_block_to(&mut self, address: &PublicKey, threads: Option<usize>) -> Result<Block> {
+        let template = self.block_template(address)?;
+        let height = template.height;
+        let block = template.mine(threads);
+        if !self.submit_mined_block(height, &block)? {
+            anyhow::bail!("Chain tip moved while mining");
+        }
+        Ok(block)
+    }
+
+    /// Unmined block on the tip paying the reward and fees to `address`
+    /// إنشاء قالب كتلة على رأس السلسلة لصالح عنوان محدد
+    ///
+    /// The block includes pending transactions at the difficulty the chain
+    /// requires and passes every state rule. Its timestamp is the clock
+    /// time, kept strictly after the previous block and at most
+    /// [`MAX_TIMESTAMP_INCREASE_SECS`] past it, so a chain whose tip is old
+    /// catches up with the clock over several blocks.
+    ///
+    /// [`MAX_TIMESTAMP_INCREASE_SECS`]: opensyria_core::constants::MAX_TIMESTAMP_INCREASE_SECS
+    pub fn block_template(&mut self, address: &PublicKey) -> Result<BlockTemplate> {
+        self.block_template_paying(CoinbaseSplit::single(*address))
+    }
+
+    /// [`Self::block_template`] paying the configured payout split, for the
+    /// daemon's miner (a fresh address when none is configured)
+    pub fn mining_template(&mut self) -> Result<BlockTemplate> {
+        let split = match &self.coinbase_split {
+            Some(split) => split.clone(),
+            None => CoinbaseSplit::single(self.get_miner_address()?),
+        };
+        self.block_template_paying(split)
+    }
+
+    fn block_template_paying(&mut self, split: CoinbaseSplit) -> Result<BlockTemplate> {
+        if self.safe_mode().is_active() {
+            anyhow::bail!("Node is in safe mode; not mining");
+        }
+        let params = self.storage.blockchain.chain_params().clone();
+        let tip = self.get_tip()?.context("No tip block found")?;
+        let tip_height = self.get_height()?;
+        let height = tip_height + 1;
+        if split.shares().len() > 1 && !params.coinbase_split_active(height) {
+            anyhow::bail!(
+                "Payout splits activate at height {}; this block is {}",
+                params.coinbase_split_height,
+                height
+            );
+        }
+
+        let selected = self.template_transactions(height, 100);
+        let fees = selected
+            .iter()
+            .try_fold(0u64, |acc, tx| acc.checked_add(tx.fee));
+        let fees = fees.context("Transaction fees overflow")?;
+        let reward = params.block_reward(height);
+        let mut transactions = split
+            .with_extra_data(self.coinbase_extra_data.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid coinbase extra data: {}", e))?
+            .coinbase_transactions_with_reward(params.chain_id, height, reward, fees)
+            .map_err(|e| anyhow::anyhow!("Failed to create coinbase transaction: {:?}", e))?;
+        transactions.extend(selected.iter().cloned());
+
+        // Strictly after the previous block and the median time past
+        let mut earliest = tip.header.timestamp;
+        if tip_height >= 11 {
+            earliest = earliest.max(self.storage.blockchain.get_median_time_past(tip_height)?);
+        }
+        let latest = tip.header.timestamp + opensyria_core::constants::MAX_TIMESTAMP_INCREASE_SECS;
+        let now = opensyria_core::clock::now();
+        let timestamp = now.clamp(earliest + 1, latest);
+        if opensyria_core::clock::mock_time().is_some() && timestamp > now {
+            opensyria_core::clock::set_mock_time(Some(timestamp));
+        } else if timestamp > now + opensyria_core::constants::MAX_FUTURE_DRIFT_SECS {
+            anyhow::bail!(
+                "Next block would be {}s ahead of the clock; set a mock time to generate faster",
+                timestamp - now
+            );
+        }
+
+        let difficulty = self.storage.blockchain.next_difficulty()?;
+        let mut block = Block::new(tip.hash(), transactions, difficulty);
+        block.header.timestamp = timestamp;
+        self.storage
+            .state
+            .check_block(height, &block)
+            .map_err(|violation| anyhow::anyhow!("Block template is invalid: {}", violation))?;
+        Ok(BlockTemplate { height, block })
+    }
+
+    /// Append and apply a block mined from a [`BlockTemplate`]
+    /// إضافة كتلة معدّنة من قالب إلى السلسلة
+    ///
+    /// Returns false, changing nothing, if the tip has moved since the
+    /// template was built.
+    pub fn submit_mined_block(&mut self, height: u64, block: &Block) -> Result<bool> {
+        let tip = self.get_tip()?.context("No tip block found")?;
+        if tip.hash() != block.header.previous_hash || self.get_height()? + 1 != height {
+            return Ok(false);
+        }
+
+        self.storage
+            .blockchain
+            .append_block(block, Some(&self.storage.state))
+            .context("Failed to append mined block")?;
+        self.storage
+            .state
+            .apply_block(height, block)
+            .context("Failed to apply mined block")?;
+        self.storage.state.publish_anchor(height, &block.hash());
+        self.remove_confirmed(block);
+
+        tracing::info!("Mined block {} ({})", height, hex::encode(block.hash()));
+        Ok(true)
+    }
+
+    /// Pending transactions for the block at `height`, in block order
+    /// اختيار المعاملات المعلقة لقالب الكتلة
+    ///
+    /// Due governance deposit settlements come first. Up to `limit` pending
+    /// transactions follow, each kept only if it still applies on top of the
+    /// state and those picked before it; the rest are dropped from the
+    /// pending pool.
+    pub fn template_transactions(&mut self, height: u64, limit: usize) -> Vec<Transaction> {
+        let mut selected = match self.deposit_settlements(height) {
+            Ok(settlements) => settlements,
+            Err(e) => {
+                tracing::warn!("Skipping deposit settlements: {}", e);
+                Vec::new()
+            }
+        };
+        if let Err(violation) = self.storage.state.check_block_at_height(height, &selected) {
+            tracing::warn!("Skipping deposit settlements: {}", violation);
+            selected.clear();
+        }
+        for tx in opensyria_core::select_transactions(self.get_pending_transactions(), limit) {
+            selected.push(tx);
+            if let Err(violation) = self.storage.state.check_block_at_height(height, &selected) {
+                let tx = selected.pop().expect("just pushed");
+                tracing::warn!(
+                    "Dropping pending transaction {}: {}",
+                    hex::encode(tx.hash()),
+                    violation
+                );
+                self.pending_transactions.remove(&tx.hash());
+            }
+        }
+        selected
+    }
+
+    /// Refunds and burns of governance deposits due at `height`
+    fn deposit_settlements(&self, height: u64) -> Result<Vec<Transaction>> {
+        let chain_id = self.storage.blockchain.chain_params().chain_id;
+        let manager = self.load_governance()?;
+        Ok(manager.deposit_settlements(&self.storage.state, height, chain_id)?)
+    }
+
+    /// Remove transactions a block has confirmed from the pending pool
+    pub fn remove_confirmed(&mut self, block: &Block) {
+        for tx in &block.transactions {
+            self.pending_transactions.remove(&tx.hash());
+        }
+    }
+
     // ===== Governance Methods =====
 
     /// Load governance manager from storage
     pub fn load_governance(&self) -> Result<GovernanceManager> {
-        if !self.governance_storage.has_snapshot()? {
-            let config = GovernanceConfig::default();
-            return Ok(GovernanceManager::new(config));
-        }
+        let mut manager = if self.governance_storage.has_snapshot()? {
+            let snapshot = self.governance_storage.load_snapshot()?;
+            GovernanceManager::from_snapshot(snapshot)
+        } else {
+            GovernanceManager::new(GovernanceConfig::default())
+        };
 
-        let snapshot = self.governance_storage.load_snapshot()?;
-        Ok(GovernanceManager::from_snapshot(snapshot))
+        manager.set_processed_height(self.governance_storage.load_processed_height()?);
+
+        if let Some(audit) = &self.audit {
+            manager.set_audit_log(audit.clone());
+        }
+        Ok(manager)
     }
 
     /// Save governance manager to storage
-    pub fn save_governance(&self, manager: &GovernanceManager) -> Result<()> {
+    ///
+    /// Lifecycle events the manager recorded are queued for a running
+    /// network node to publish on its event bus.
+    pub fn save_governance(&self, manager: &mut GovernanceManager) -> Result<()> {
         let snapshot = manager.create_snapshot();
         self.governance_storage.save_snapshot(&snapshot)?;
+        let events = manager.take_events();
+        self.governance_storage.queue_events(&events)?;
+        if let Some(height) = manager.processed_height() {
+            self.governance_storage.save_processed_height(height)?;
+        }
         Ok(())
     }
 
-    /// Create a new governance proposal
+    /// Create a new governance proposal backed by the deposit escrowed by
+    /// transaction `deposit_escrow`
     pub fn create_proposal(
         &self,
         proposer: PublicKey,
         proposal_type: ProposalType,
         title: String,
         description: String,
+        deposit_escrow: [u8; 32],
     ) -> Result<u64> {
         let mut manager = self.load_governance()?;
 
@@ -352,10 +906,11 @@ impl Node {
             description,
             current_height,
             total_voting_power,
+            deposit_escrow,
             &self.storage.state,
         )?;
 
-        self.save_governance(&manager)?;
+        self.save_governance(&mut manager)?;
 
         Ok(proposal_id)
     }
@@ -372,7 +927,7 @@ impl Node {
 
         manager.vote_blocking(proposal_id, voter, vote, &self.storage.state, current_height)?;
 
-        self.save_governance(&manager)?;
+        self.save_governance(&mut manager)?;
 
         Ok(())
     }
@@ -389,6 +944,14 @@ impl Node {
         let newly_finalized = (after_stats.passed_proposals + after_stats.rejected_proposals)
             - (before_stats.passed_proposals + before_stats.rejected_proposals);
 
+        // Record deposits whose refund or burn has been mined; blocks this
+        // node mines carry the settlements themselves
+        manager.settle_deposits(&self.storage.state, current_height)?;
+
+        // Drop kept balances no open proposal can vote with any more
+        let oldest_open = manager.oldest_open_balance_snapshot().unwrap_or(u64::MAX);
+        self.storage.state.prune_balance_snapshots(oldest_open)?;
+
         // Execute ready proposals
         let ready_ids: Vec<u64> = manager
             .get_ready_for_execution(current_height)
@@ -400,11 +963,128 @@ impl Node {
             self.execute_proposal(&mut manager, proposal_id)?;
         }
 
-        self.save_governance(&manager)?;
+        // Execute approved emergency council motions
+        let ready_motions: Vec<u64> = manager
+            .get_ready_emergency_actions(current_height)
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+
+        for motion_id in ready_motions {
+            self.execute_emergency_action(&mut manager, motion_id)?;
+        }
+
+        self.save_governance(&mut manager)?;
 
         Ok(newly_finalized)
     }
 
+    /// Configure the emergency council (only allowed once)
+    pub fn init_emergency_council(&self, members: Vec<PublicKey>, threshold: u8) -> Result<()> {
+        let mut manager = self.load_governance()?;
+        if manager.emergency_council().is_some() {
+            anyhow::bail!("Emergency council is already configured");
+        }
+
+        manager.set_emergency_council(EmergencyCouncil::new(members, threshold)?);
+        self.save_governance(&mut manager)?;
+        Ok(())
+    }
+
+    /// Open an emergency motion signed by a council member
+    pub fn propose_emergency_action(
+        &self,
+        member: &KeyPair,
+        action: EmergencyAction,
+        reason: String,
+    ) -> Result<u64> {
+        let mut manager = self.load_governance()?;
+        let current_height = self.storage.blockchain.get_chain_height()?;
+
+        let hash = opensyria_governance::council::motion_signing_hash(
+            manager.next_motion_id(),
+            &action,
+            &reason,
+        );
+        let motion_id = manager.propose_emergency_action(
+            member.public_key(),
+            action,
+            reason,
+            member.sign(&hash),
+            current_height,
+        )?;
+
+        self.save_governance(&mut manager)?;
+        Ok(motion_id)
+    }
+
+    /// Add a council member's approval to an emergency motion
+    pub fn approve_emergency_action(
+        &self,
+        motion_id: u64,
+        member: &KeyPair,
+    ) -> Result<MotionStatus> {
+        let mut manager = self.load_governance()?;
+        let current_height = self.storage.blockchain.get_chain_height()?;
+
+        let hash = manager
+            .get_emergency_motion(motion_id)
+            .ok_or_else(|| anyhow::anyhow!("Emergency motion {} not found", motion_id))?
+            .signing_hash();
+        let status = manager.approve_emergency_action(
+            motion_id,
+            member.public_key(),
+            member.sign(&hash),
+            current_height,
+        )?;
+
+        self.save_governance(&mut manager)?;
+        Ok(status)
+    }
+
+    /// Execute an approved emergency council motion
+    fn execute_emergency_action(
+        &self,
+        manager: &mut GovernanceManager,
+        motion_id: u64,
+    ) -> Result<()> {
+        let motion = manager
+            .get_emergency_motion(motion_id)
+            .ok_or_else(|| anyhow::anyhow!("Emergency motion not found"))?;
+
+        tracing::warn!(
+            "Executing emergency motion {}: {}",
+            motion_id,
+            motion.reason
+        );
+
+        match &motion.action {
+            EmergencyAction::PauseProposal { proposal_id } => {
+                tracing::warn!("Pausing execution of proposal {}", proposal_id);
+            }
+            EmergencyAction::PublishCheckpoint { height, hash } => {
+                // Enforced from the governance snapshot once the motion is
+                // marked executed; see `GovernanceManager::council_checkpoints`
+                tracing::warn!(
+                    "Council checkpoint at height {}: {}",
+                    height,
+                    hex::encode(hash)
+                );
+            }
+            EmergencyAction::FreezeAccount { address } => {
+                tracing::warn!("Emergency freeze of account {}", address.to_hex());
+                motion
+                    .action
+                    .apply_to_state(motion_id, &self.storage.state)?;
+            }
+        }
+
+        let current_height = self.storage.blockchain.get_chain_height()?;
+        manager.mark_emergency_executed(motion_id, current_height)?;
+
+        Ok(())
+    }
+
     /// Execute a passed proposal
     fn execute_proposal(&self, manager: &mut GovernanceManager, proposal_id: u64) -> Result<()> {
         let proposal = manager
@@ -437,6 +1117,40 @@ impl Node {
                 // Non-binding, just log
                 tracing::info!("Text proposal (non-binding)");
             }
+            ProposalType::FreezeAccount { address, reason } => {
+                tracing::warn!("Freezing account {}: {}", address.to_hex(), reason);
+                proposal
+                    .proposal_type
+                    .apply_to_state(proposal_id, &self.storage.state)?;
+            }
+            ProposalType::UnfreezeAccount { address, reason } => {
+                tracing::info!("Unfreezing account {}: {}", address.to_hex(), reason);
+                proposal
+                    .proposal_type
+                    .apply_to_state(proposal_id, &self.storage.state)?;
+            }
+            ProposalType::AddOracle { oracle, reason } => {
+                tracing::info!("Whitelisting oracle {}: {}", oracle.to_hex(), reason);
+                proposal
+                    .proposal_type
+                    .apply_to_state(proposal_id, &self.storage.state)?;
+            }
+            ProposalType::RemoveOracle { oracle, reason } => {
+                tracing::info!("Removing oracle {}: {}", oracle.to_hex(), reason);
+                proposal
+                    .proposal_type
+                    .apply_to_state(proposal_id, &self.storage.state)?;
+            }
+            ProposalType::RevokeEmergencyAction { motion_id, reason } => {
+                let motion_id = *motion_id;
+                tracing::info!("Revoking emergency motion {}: {}", motion_id, reason);
+                let current_height = self.storage.blockchain.get_chain_height()?;
+                let motion =
+                    manager.revoke_emergency_action(motion_id, proposal_id, current_height)?;
+                if motion.status == MotionStatus::Executed {
+                    motion.action.revert_state(motion_id, &self.storage.state)?;
+                }
+            }
             _ => {
                 tracing::warn!("Unimplemented proposal type execution");
             }
@@ -491,36 +1205,461 @@ impl Node {
         self.pending_transactions.values().cloned().collect()
     }
 
-    /// Add transaction to pending pool
+    /// Check a transaction against current state and the pending pool
+    /// without adding it; this is exactly what submission enforces
+    /// التحقق من معاملة مقابل الحالة والمعاملات المعلقة دون إضافتها
     #[allow(dead_code)]
-    pub fn add_transaction_to_mempool(&mut self, transaction: Transaction) -> Result<()> {
-        // Verify transaction
-        transaction
-            .verify()
-            .context("Invalid transaction signature")?;
+    pub fn check_transaction(&self, transaction: &Transaction) -> Result<(), MempoolError> {
+        self.check_pending(transaction, None)
+    }
 
-        // Check sender has sufficient balance
-        let balance = self.storage.state.get_balance(&transaction.from)?;
-        let nonce = self.storage.state.get_nonce(&transaction.from)?;
+    /// Check a transaction as if `replaced` were no longer pending
+    fn check_pending(
+        &self,
+        transaction: &Transaction,
+        replaced: Option<&Transaction>,
+    ) -> Result<(), MempoolError> {
+        let storage_error =
+            |e: opensyria_storage::StorageError| MempoolError::Storage(e.to_string());
+        let replaced_hash = replaced.map(Transaction::hash);
+        let others = || {
+            self.pending_transactions
+                .iter()
+                .filter(move |(hash, _)| Some(**hash) != replaced_hash)
+                .map(|(_, pending)| pending)
+        };
 
-        let total_cost = transaction.amount + transaction.fee;
-        if balance < total_cost {
-            anyhow::bail!("Insufficient balance");
+        let tx_hash = transaction.hash();
+        if self.pending_transactions.contains_key(&tx_hash) {
+            return Err(MempoolError::DuplicateTransaction(hex::encode(tx_hash)));
         }
 
-        // Check nonce
-        if transaction.nonce != nonce {
-            anyhow::bail!(
-                "Invalid nonce. Expected {}, got {}",
-                nonce,
-                transaction.nonce
-            );
+        if let Err(e) = transaction.verify() {
+            return Err(MempoolError::ValidationFailed(format!(
+                "Invalid signature: {}",
+                e
+            )));
         }
 
+        // Transactions signed for another network are not replayed here
+        let params = self.storage.blockchain.chain_params();
+        let height = self
+            .storage
+            .blockchain
+            .get_chain_height()
+            .map_err(storage_error)?;
+        if let Err(TransactionError::WrongChainId { expected, got }) =
+            transaction.validate_chain_id(params, height + 1)
+        {
+            return Err(MempoolError::WrongChainId { expected, got });
+        }
+
+        if transaction.fee < MIN_TRANSACTION_FEE {
+            return Err(MempoolError::FeeTooLow {
+                min: MIN_TRANSACTION_FEE,
+                got: transaction.fee,
+            });
+        }
+
+        let state = &self.storage.state;
+        let payer = transaction.fee_payer();
+        for account in [transaction.from, payer] {
+            if state.is_frozen(&account).map_err(storage_error)? {
+                return Err(MempoolError::AccountFrozen(account.to_hex()));
+            }
+        }
+
+        // Pending transactions spend first: amounts sent and fees paid,
+        // including fees sponsored for others, and value locked by buy orders
+        let debit = |tx: &Transaction, account: &PublicKey| -> u64 {
+            let locked = if tx.from == *account {
+                DexOperation::escrowed_syl(tx)
+            } else {
+                0
+            };
+            tx.debit(account).saturating_add(locked)
+        };
+        let pending_debit = |account: &PublicKey| -> u64 {
+            others()
+                .map(|pending| debit(pending, account))
+                .fold(0u64, u64::saturating_add)
+        };
+        let pending_count = others()
+            .filter(|pending| pending.from == transaction.from)
+            .count() as u64;
+
+        let mut accounts = vec![transaction.from];
+        if payer != transaction.from {
+            accounts.push(payer);
+        }
+        for account in &accounts {
+            let balance = state.get_balance(account).map_err(storage_error)?;
+            let available = balance.saturating_sub(pending_debit(account));
+            let required = debit(transaction, account);
+            if available < required {
+                return Err(MempoolError::InsufficientBalance {
+                    required,
+                    available,
+                });
+            }
+        }
+
+        if let Some(Err(e)) = ContractCall::from_transaction(transaction) {
+            return Err(MempoolError::InvalidContract(e.to_string()));
+        }
+        if let Some(Err(e)) = AssetOperation::from_transaction(transaction) {
+            return Err(MempoolError::InvalidAsset(e.to_string()));
+        }
+        if let Some(Err(e)) = DexOperation::from_transaction(transaction) {
+            return Err(MempoolError::InvalidOrder(e.to_string()));
+        }
+        if let Some(update) = OracleUpdate::from_transaction(transaction) {
+            let now = opensyria_core::clock::now();
+            update
+                .and_then(|update| update.check_clock(now))
+                .map_err(|e| MempoolError::InvalidOracleUpdate(e.to_string()))?;
+            if !state.is_oracle(&transaction.from).map_err(storage_error)? {
+                return Err(MempoolError::InvalidOracleUpdate(
+                    OracleError::NotAnOracle(transaction.from.to_hex()).to_string(),
+                ));
+            }
+        }
+
+        // Asset operations hold against the state after the sender's earlier
+        // pending ones: the asset exists, the issuer mints within the cap and
+        // transfers and sell orders are covered by the asset balance
+        let is_asset_operation =
+            |tx: &Transaction| AssetOperation::is_asset(tx) || DexOperation::is_dex(tx);
+        if is_asset_operation(transaction) {
+            let mut operations: Vec<Transaction> = others()
+                .filter(|pending| {
+                    pending.from == transaction.from
+                        && pending.nonce < transaction.nonce
+                        && is_asset_operation(pending)
+                })
+                .cloned()
+                .collect();
+            operations.sort_by_key(|pending| pending.nonce);
+            operations.push(transaction.clone());
+            state
+                .check_asset_operations(&operations)
+                .map_err(|e| match e {
+                    opensyria_storage::StorageError::AssetViolation(reason) => {
+                        MempoolError::InvalidAsset(reason)
+                    }
+                    opensyria_storage::StorageError::DexViolation(reason) => {
+                        MempoolError::InvalidOrder(reason)
+                    }
+                    e => storage_error(e),
+                })?;
+        }
+
+        // Spending policies, counting pending spends toward the day's limit
+        let policy_violation = |e: PolicyError| MempoolError::PolicyViolation(e.to_string());
+        if let Some(Err(e)) = SpendingPolicy::from_update(transaction) {
+            return Err(policy_violation(e));
+        }
+        let day = policy_day(state.get_state_height().map_err(storage_error)? + 1);
+        for account in &accounts {
+            let Some(policy) = state.get_spending_policy(account).map_err(storage_error)? else {
+                continue;
+            };
+            let spent = state
+                .get_policy_spent(account, day)
+                .map_err(storage_error)?
+                .saturating_add(pending_debit(account));
+            if *account == transaction.from {
+                policy.check(transaction, spent).map_err(policy_violation)?;
+            } else {
+                policy
+                    .check_daily(transaction.fee, spent)
+                    .map_err(policy_violation)?;
+            }
+        }
+
+        // Hot key spends, pending ones included, stay within the allowance
+        let delegation_violation =
+            |e: DelegationError| MempoolError::DelegationViolation(e.to_string());
+        if let Some(Err(e)) = HotKeyDelegation::from_update(transaction) {
+            return Err(delegation_violation(e));
+        }
+        if transaction.delegate.is_some() {
+            let delegation = state
+                .get_delegation(&transaction.from)
+                .map_err(storage_error)?;
+            let spent = others()
+                .filter(|pending| pending.from == transaction.from && pending.delegate.is_some())
+                .map(|pending| pending.debit(&pending.from))
+                .fold(
+                    state
+                        .get_delegation_spent(&transaction.from, day)
+                        .map_err(storage_error)?,
+                    u64::saturating_add,
+                );
+            HotKeyDelegation::authorize(delegation.as_ref(), transaction, spent)
+                .map_err(delegation_violation)?;
+        }
+
+        // A replacement takes over the nonce of the transaction it replaces
+        let expected = match replaced {
+            Some(replaced) => replaced.nonce,
+            None => state.get_nonce(&transaction.from).map_err(storage_error)? + pending_count,
+        };
+        if transaction.nonce != expected {
+            return Err(MempoolError::InvalidNonce {
+                expected,
+                got: transaction.nonce,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Drop every pending transaction; returns how many were dropped
+    /// إفراغ مجمع المعاملات المعلقة
+    #[allow(dead_code)]
+    pub fn clear_pending_transactions(&mut self) -> usize {
+        let count = self.pending_transactions.len();
+        self.pending_transactions.clear();
+        count
+    }
+
+    /// Add transaction to pending pool
+    #[allow(dead_code)]
+    pub fn add_transaction_to_mempool(&mut self, transaction: Transaction) -> Result<()> {
+        if self.safe_mode().is_active() {
+            anyhow::bail!("Node is in safe mode; not accepting transactions");
+        }
+
+        self.check_transaction(&transaction)?;
+
         // Add to pending pool
         let tx_hash = transaction.hash();
         self.pending_transactions.insert(tx_hash, transaction);
 
         Ok(())
     }
+
+    /// Replace the pending transaction with the same sender and nonce by one
+    /// paying a high enough fee rate (see [`check_replacement`]); returns the
+    /// replaced transaction
+    /// استبدال معاملة معلقة بأخرى ذات رسوم أعلى
+    #[allow(dead_code)]
+    pub fn replace_pending_transaction(&mut self, transaction: Transaction) -> Result<Transaction> {
+        if self.safe_mode().is_active() {
+            anyhow::bail!("Node is in safe mode; not accepting transactions");
+        }
+
+        let replaced = self
+            .pending_transactions
+            .values()
+            .find(|pending| pending.from == transaction.from && pending.nonce == transaction.nonce)
+            .cloned()
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No pending transaction from {} with nonce {}",
+                    transaction.from.to_hex(),
+                    transaction.nonce
+                )
+            })?;
+
+        check_replacement(&replaced, &transaction)?;
+        self.check_pending(&transaction, Some(&replaced))?;
+
+        self.pending_transactions.remove(&replaced.hash());
+        self.pending_transactions
+            .insert(transaction.hash(), transaction);
+
+        Ok(replaced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_generate_to_address() {
+        let dir = tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 0).unwrap();
+        let address = KeyPair::generate().public_key();
+        assert!(node.generate_to_address(1, &address).is_err());
+
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let hashes = node.generate_to_address(3, &address).unwrap();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(node.get_height().unwrap(), 4);
+        assert_eq!(node.get_tip().unwrap().unwrap().hash(), hashes[2]);
+
+        let rewards: u64 = (2..=4).map(|height| params.block_reward(height)).sum();
+        assert_eq!(node.get_balance(&address).unwrap(), rewards);
+
+        // A pinned clock is advanced instead of running into the drift limit
+        let pinned = node.get_tip().unwrap().unwrap().header.timestamp + 1;
+        opensyria_core::clock::set_mock_time(Some(pinned));
+        node.generate_to_address(100, &address).unwrap();
+        let tip = node.get_tip().unwrap().unwrap();
+        opensyria_core::clock::set_mock_time(None);
+        assert_eq!(tip.header.timestamp, pinned + 99);
+        assert_eq!(node.get_height().unwrap(), 104);
+    }
+
+    #[test]
+    fn test_mining_template_pays_split() {
+        let dir = tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 0).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let pool = KeyPair::generate().public_key();
+        let miner = KeyPair::generate().public_key();
+        let split = CoinbaseSplit::new(vec![
+            opensyria_core::PayoutShare {
+                address: pool,
+                percent: 90,
+            },
+            opensyria_core::PayoutShare {
+                address: miner,
+                percent: 10,
+            },
+        ])
+        .unwrap();
+        node.set_coinbase_split(Some(split));
+        node.set_coinbase_extra_data(b"pool".to_vec());
+
+        let template = node.mining_template().unwrap();
+        assert_eq!(template.height, 2);
+        let coinbases: Vec<_> = template.block.transactions.iter().collect();
+        assert_eq!(coinbases.len(), 2);
+        assert_eq!(coinbases[0].to, pool);
+        assert_eq!(coinbases[1].to, miner);
+        let paid: u64 = coinbases.iter().map(|tx| tx.amount).sum();
+        assert_eq!(paid, params.block_reward(2));
+        assert!(coinbases[0].data.as_ref().unwrap().ends_with(b"pool"));
+        assert_eq!(template.info().tip, node.get_tip().unwrap().unwrap().hash());
+
+        // A stopped miner gives up instead of finding the block
+        let stop = AtomicBool::new(true);
+        let mut hard = node.mining_template().unwrap();
+        hard.block.header.difficulty = 255;
+        assert!(hard.mine_until_stopped(Duration::ZERO, &stop).is_none());
+
+        let block = template.mine_until_stopped(Duration::from_secs(1), &AtomicBool::new(false));
+        assert!(node.submit_mined_block(2, &block.unwrap()).unwrap());
+        let reward = params.block_reward(2);
+        assert_eq!(node.get_balance(&miner).unwrap(), reward / 10);
+    }
+
+    #[test]
+    fn test_replace_pending_transaction() {
+        let dir = tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 0).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let sender = KeyPair::generate();
+        node.generate_to_address(2, &sender.public_key()).unwrap();
+
+        let to = KeyPair::generate().public_key();
+        let signed = |nonce: u64, fee: u64| {
+            let tx = Transaction::new_with_chain_id(
+                params.chain_id,
+                sender.public_key(),
+                to,
+                1_000,
+                fee,
+                nonce,
+            );
+            let signature = sender.sign(&tx.signing_hash());
+            tx.with_signature(signature)
+        };
+        let stuck = signed(0, MIN_TRANSACTION_FEE);
+        node.add_transaction_to_mempool(stuck.clone()).unwrap();
+        node.add_transaction_to_mempool(signed(1, MIN_TRANSACTION_FEE))
+            .unwrap();
+
+        // Too small a bump, or a nonce with nothing to replace, is refused
+        assert!(node
+            .replace_pending_transaction(signed(0, MIN_TRANSACTION_FEE + 5))
+            .is_err());
+        assert!(node
+            .replace_pending_transaction(signed(2, 10 * MIN_TRANSACTION_FEE))
+            .is_err());
+
+        let fee = opensyria_mempool::replacement_fee(&stuck, stuck.weight());
+        let bumped = signed(0, fee);
+        let replaced = node.replace_pending_transaction(bumped.clone()).unwrap();
+        assert_eq!(replaced.hash(), stuck.hash());
+        let pending: Vec<_> = node
+            .get_pending_transactions()
+            .iter()
+            .map(Transaction::hash)
+            .collect();
+        assert_eq!(pending.len(), 2);
+        assert!(pending.contains(&bumped.hash()));
+        assert!(!pending.contains(&stuck.hash()));
+    }
+
+    #[test]
+    fn test_asset_operations_checked_before_mining() {
+        let dir = tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 0).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let issuer = KeyPair::generate();
+        node.generate_to_address(2, &issuer.public_key()).unwrap();
+
+        let signed = |operation: AssetOperation, nonce: u64| {
+            let tx = operation.transaction(
+                params.chain_id,
+                issuer.public_key(),
+                MIN_TRANSACTION_FEE,
+                nonce,
+            );
+            let signature = issuer.sign(&tx.signing_hash());
+            tx.with_signature(signature)
+        };
+        let asset = opensyria_core::AssetId::derive(&issuer.public_key(), 0);
+        let mint = |amount: u64, nonce: u64| {
+            signed(
+                AssetOperation::Mint {
+                    asset,
+                    to: issuer.public_key(),
+                    amount,
+                },
+                nonce,
+            )
+        };
+
+        // Minting an asset nobody created is refused
+        assert!(matches!(
+            node.check_transaction(&mint(10, 0)),
+            Err(MempoolError::InvalidAsset(_))
+        ));
+
+        // Once the create is pending, a mint within the cap is accepted
+        let create = AssetOperation::Create {
+            symbol: "WHEAT".to_string(),
+            name: "Wheat".to_string(),
+            decimals: 0,
+            max_supply: 100,
+        };
+        node.add_transaction_to_mempool(signed(create, 0)).unwrap();
+        assert!(matches!(
+            node.check_transaction(&mint(101, 1)),
+            Err(MempoolError::InvalidAsset(_))
+        ));
+        node.add_transaction_to_mempool(mint(100, 1)).unwrap();
+
+        let address = KeyPair::generate().public_key();
+        let block = node.mine_block_to(&address, Some(1)).unwrap();
+        assert_eq!(block.transactions.len(), 3);
+        assert!(node.get_pending_transactions().is_empty());
+        assert_eq!(
+            node.get_state()
+                .get_asset_balance(&issuer.public_key(), &asset)
+                .unwrap(),
+            100
+        );
+    }
 }
@@ -0,0 +1,489 @@
+//! Push notifications for mining software
+//! إشعارات فورية لبرامج التعدين
+//!
+//! A plain TCP publisher in the spirit of bitcoind's `zmqpub*` sockets.
+//! Every connected client receives one JSON object per line:
+//!
+//! ```text
+//! {"topic":"newblock","height":1204,"hash":"00ab...","sequence":7}
+//! {"topic":"newtx","hash":"9f1c...","fee":1000,"sequence":8}
+//! {"topic":"newtemplate","height":1205,"prev_hash":"00ab...","tx_count":12,"total_fees":15000,"sequence":9}
+//! ```
+//!
+//! `sequence` grows by one per notification so a client can spot gaps.
+//! Clients that fall more than the channel capacity behind are
+//! disconnected rather than slowing the daemon down; they reconnect and
+//! fetch the current template.
+
+use anyhow::{Context, Result};
+use opensyria_core::constants::MAX_REORG_DEPTH;
+use opensyria_core::{Block, Transaction};
+use opensyria_storage::{BlockchainStorage, StorageError};
+use serde::Serialize;
+use std::collections::{HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, Semaphore};
+use tokio::task::JoinHandle;
+
+/// Notifications buffered per client before it is considered too slow
+const CLIENT_BUFFER: usize = 1024;
+
+/// Most blocks announced by one catch-up; older ones are skipped
+pub const MAX_CATCH_UP_BLOCKS: u64 = 1_000;
+
+/// Notification topic
+/// موضوع الإشعار
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topic {
+    /// The block template miners should work on changed
+    NewTemplate,
+    /// A block was connected to the chain
+    NewBlock,
+    /// A transaction entered the mempool
+    NewTx,
+}
+
+impl Topic {
+    /// Every topic
+    pub const ALL: [Topic; 3] = [Topic::NewTemplate, Topic::NewBlock, Topic::NewTx];
+
+    /// Name used in the config file and on the wire
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Topic::NewTemplate => "newtemplate",
+            Topic::NewBlock => "newblock",
+            Topic::NewTx => "newtx",
+        }
+    }
+
+    /// Parse a topic name
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|topic| topic.as_str() == name)
+    }
+}
+
+/// One notification sent to subscribers
+/// إشعار واحد يرسل إلى المشتركين
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "topic", rename_all = "lowercase")]
+pub enum Notification {
+    /// Miners should rebuild their work on a new template
+    NewTemplate {
+        /// Height of the block being built
+        height: u64,
+        /// Parent block hash (hex)
+        prev_hash: String,
+        /// Transactions selected from the mempool
+        tx_count: usize,
+        /// Fees paid by the selected transactions
+        total_fees: u64,
+    },
+    /// A block was connected
+    NewBlock {
+        /// Block height
+        height: u64,
+        /// Block hash (hex)
+        hash: String,
+    },
+    /// A transaction was accepted into the mempool
+    NewTx {
+        /// Transaction hash (hex)
+        hash: String,
+        /// Fee paid
+        fee: u64,
+    },
+}
+
+impl Notification {
+    /// Notification for a block connected at `height`
+    pub fn new_block(height: u64, block: &Block) -> Self {
+        Notification::NewBlock {
+            height,
+            hash: hex::encode(block.hash()),
+        }
+    }
+
+    /// Notification for a transaction entering the mempool
+    pub fn new_tx(tx: &Transaction) -> Self {
+        Notification::NewTx {
+            hash: hex::encode(tx.hash()),
+            fee: tx.fee,
+        }
+    }
+
+    /// Notification for a template built on `prev_hash` at `height`
+    pub fn new_template(height: u64, prev_hash: [u8; 32], transactions: &[Transaction]) -> Self {
+        Notification::NewTemplate {
+            height,
+            prev_hash: hex::encode(prev_hash),
+            tx_count: transactions.len(),
+            total_fees: opensyria_consensus::template::total_fees(transactions),
+        }
+    }
+
+    /// Topic this notification is published under
+    pub fn topic(&self) -> Topic {
+        match self {
+            Notification::NewTemplate { .. } => Topic::NewTemplate,
+            Notification::NewBlock { .. } => Topic::NewBlock,
+            Notification::NewTx { .. } => Topic::NewTx,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    #[serde(flatten)]
+    notification: &'a Notification,
+    sequence: u64,
+}
+
+/// Publishes notifications to every connected client
+/// ينشر الإشعارات إلى جميع العملاء المتصلين
+#[derive(Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<Arc<str>>,
+    topics: Arc<RwLock<HashSet<Topic>>>,
+    sequence: Arc<AtomicU64>,
+}
+
+impl Notifier {
+    /// Create a publisher for `topics` with no socket attached
+    pub fn new(topics: impl IntoIterator<Item = Topic>) -> Self {
+        let (sender, _) = broadcast::channel(CLIENT_BUFFER);
+        Self {
+            sender,
+            topics: Arc::new(RwLock::new(topics.into_iter().collect())),
+            sequence: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Whether `topic` is published at all
+    pub fn is_enabled(&self, topic: Topic) -> bool {
+        self.topics
+            .read()
+            .map(|topics| topics.contains(&topic))
+            .unwrap_or(false)
+    }
+
+    /// Publish `topics` from now on; connected clients stay connected
+    pub fn set_topics(&self, topics: impl IntoIterator<Item = Topic>) {
+        if let Ok(mut current) = self.topics.write() {
+            *current = topics.into_iter().collect();
+        }
+    }
+
+    /// Send a notification to every connected client
+    ///
+    /// Disabled topics are dropped, and nothing is serialized while no
+    /// client is connected.
+    pub fn publish(&self, notification: Notification) {
+        if !self.is_enabled(notification.topic()) || self.sender.receiver_count() == 0 {
+            return;
+        }
+        let envelope = Envelope {
+            notification: &notification,
+            sequence: self.sequence.fetch_add(1, Ordering::Relaxed),
+        };
+        match serde_json::to_string(&envelope) {
+            Ok(mut line) => {
+                line.push('\n');
+                let _ = self.sender.send(line.into());
+            }
+            Err(e) => tracing::warn!("Failed to encode notification: {}", e),
+        }
+    }
+
+    /// Number of connected clients
+    pub fn client_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Accept subscribers on `bind_addr`, at most `max_clients` at a time
+    ///
+    /// Returns the bound address (useful with port 0) and the accept task.
+    pub async fn serve(
+        &self,
+        bind_addr: SocketAddr,
+        max_clients: usize,
+    ) -> Result<(SocketAddr, JoinHandle<()>)> {
+        let listener = TcpListener::bind(bind_addr).await.context(format!(
+            "Failed to bind notification socket on {}",
+            bind_addr
+        ))?;
+        let local_addr = listener.local_addr()?;
+        let slots = Arc::new(Semaphore::new(max_clients));
+        let sender = self.sender.clone();
+
+        let handle = tokio::spawn(async move {
+            loop {
+                let (stream, peer) = match listener.accept().await {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        tracing::warn!("Notification socket accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let Ok(slot) = slots.clone().try_acquire_owned() else {
+                    tracing::debug!("Rejecting notification client {}: too many clients", peer);
+                    continue;
+                };
+                let receiver = sender.subscribe();
+                tokio::spawn(async move {
+                    stream_to_client(stream, receiver).await;
+                    tracing::debug!("Notification client {} disconnected", peer);
+                    drop(slot);
+                });
+            }
+        });
+
+        Ok((local_addr, handle))
+    }
+}
+
+async fn stream_to_client(mut stream: TcpStream, mut receiver: broadcast::Receiver<Arc<str>>) {
+    loop {
+        let line = match receiver.recv().await {
+            Ok(line) => line,
+            // Too slow to keep up: drop it so it resyncs on reconnect
+            Err(broadcast::error::RecvError::Lagged(_)) => return,
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        if stream.write_all(line.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Derives notifications from successive views of the chain and mempool
+/// يستنتج الإشعارات من حالات السلسلة والمجمع المتتالية
+///
+/// The daemon polls its node rather than receiving events, so this keeps
+/// what was last announced and publishes only what changed. Blocks are
+/// tracked by hash, so a reorg to a chain of the same height is announced.
+pub struct ChainWatcher {
+    notifier: Notifier,
+    /// Announced blocks still within reorg depth, oldest first
+    announced: VecDeque<(u64, [u8; 32])>,
+    seen_txs: HashSet<[u8; 32]>,
+    template: Option<([u8; 32], Vec<[u8; 32]>)>,
+}
+
+impl ChainWatcher {
+    /// Start watching from the block `tip` at `height`; earlier blocks are
+    /// not announced
+    pub fn new(notifier: Notifier, height: u64, tip: [u8; 32]) -> Self {
+        Self {
+            notifier,
+            announced: VecDeque::from([(height, tip)]),
+            seen_txs: HashSet::new(),
+            template: None,
+        }
+    }
+
+    /// Announce a block connected at `height`
+    pub fn block_connected(&mut self, height: u64, block: &Block) {
+        // Blocks at or above `height` were replaced by a reorg
+        while matches!(self.announced.back(), Some((announced, _)) if *announced >= height) {
+            self.announced.pop_back();
+        }
+        if self.announced.len() as u64 > MAX_REORG_DEPTH {
+            self.announced.pop_front();
+        }
+        self.announced.push_back((height, block.hash()));
+        self.notifier
+            .publish(Notification::new_block(height, block));
+    }
+
+    /// Height of the last announced block
+    pub fn height(&self) -> u64 {
+        self.announced.back().map_or(0, |(height, _)| *height)
+    }
+
+    /// Hash of the last announced block
+    pub fn tip(&self) -> Option<[u8; 32]> {
+        self.announced.back().map(|(_, hash)| *hash)
+    }
+
+    /// Work to find the blocks not announced yet; run it off the event loop
+    /// and pass the blocks to [`Self::block_connected`]
+    pub fn catch_up(&self) -> CatchUp {
+        CatchUp {
+            announced: self.announced.iter().copied().collect(),
+        }
+    }
+
+    /// Publisher the notifications go through
+    pub fn notifier(&self) -> &Notifier {
+        &self.notifier
+    }
+
+    /// Announce new mempool transactions and template changes
+    ///
+    /// `pending` is the whole mempool and `selected` the transactions a
+    /// template built on `tip` would include.
+    pub fn mempool_changed(
+        &mut self,
+        tip: [u8; 32],
+        pending: &[Transaction],
+        selected: &[Transaction],
+    ) {
+        let current: HashSet<[u8; 32]> = pending.iter().map(|tx| tx.hash()).collect();
+        for tx in pending {
+            if !self.seen_txs.contains(&tx.hash()) {
+                self.notifier.publish(Notification::new_tx(tx));
+            }
+        }
+        self.seen_txs = current;
+
+        let template = (tip, selected.iter().map(|tx| tx.hash()).collect());
+        if self.template.as_ref() != Some(&template) {
+            self.notifier
+                .publish(Notification::new_template(self.height() + 1, tip, selected));
+            self.template = Some(template);
+        }
+    }
+}
+
+/// Blocks connected since a [`ChainWatcher`] last announced one
+/// الكتل المتصلة منذ آخر إشعار
+pub struct CatchUp {
+    announced: Vec<(u64, [u8; 32])>,
+}
+
+impl CatchUp {
+    /// Blocks above the highest announced block still on the chain, by
+    /// height, at most [`MAX_CATCH_UP_BLOCKS`] of them
+    pub fn run(&self, blockchain: &BlockchainStorage) -> Result<Vec<(u64, Block)>, StorageError> {
+        let height = blockchain.get_chain_height()?;
+        let mut start = self.announced.first().map_or(0, |(height, _)| *height);
+        for &(announced, hash) in self.announced.iter().rev() {
+            if announced > height {
+                continue;
+            }
+            if blockchain.get_block_hash_by_height(announced)? == Some(hash) {
+                start = announced + 1;
+                break;
+            }
+        }
+
+        let start = start.max((height + 1).saturating_sub(MAX_CATCH_UP_BLOCKS));
+        if start > height {
+            return Ok(Vec::new());
+        }
+        let blocks = blockchain.get_block_range(start, height)?;
+        Ok((start..).zip(blocks).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    fn transfer(nonce: u64, fee: u64) -> Transaction {
+        let sender = KeyPair::generate();
+        let tx = Transaction::new(
+            sender.public_key(),
+            KeyPair::generate().public_key(),
+            10,
+            fee,
+            nonce,
+        );
+        let signature = sender.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_topic_names() {
+        for topic in Topic::ALL {
+            assert_eq!(Topic::parse(topic.as_str()), Some(topic));
+        }
+        assert_eq!(Topic::parse("hashblock"), None);
+    }
+
+    #[test]
+    fn test_set_topics_reaches_clones() {
+        let notifier = Notifier::new([Topic::NewBlock]);
+        notifier.clone().set_topics([Topic::NewTx]);
+        assert!(notifier.is_enabled(Topic::NewTx));
+        assert!(!notifier.is_enabled(Topic::NewBlock));
+    }
+
+    #[test]
+    fn test_notification_catch_up() {
+        use crate::node::Node;
+        use opensyria_core::ChainParams;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 0).unwrap();
+        node.set_chain_params(ChainParams::regtest());
+        let genesis = node.get_tip().unwrap().unwrap().hash();
+        let mut watcher = ChainWatcher::new(Notifier::new([]), 1, genesis);
+
+        let address = KeyPair::generate().public_key();
+        let hashes = node.generate_to_address(3, &address).unwrap();
+        let blocks = watcher.catch_up().run(node.get_blockchain()).unwrap();
+        let found: Vec<_> = blocks.iter().map(|(h, b)| (*h, b.hash())).collect();
+        assert_eq!(found, vec![(2, hashes[0]), (3, hashes[1]), (4, hashes[2])]);
+        for (height, block) in &blocks {
+            watcher.block_connected(*height, block);
+        }
+        assert_eq!(watcher.tip(), Some(hashes[2]));
+        let blocks = watcher.catch_up().run(node.get_blockchain()).unwrap();
+        assert!(blocks.is_empty());
+
+        // A tip replaced at the same height is announced again
+        watcher.block_connected(4, &Block::genesis());
+        let blocks = watcher.catch_up().run(node.get_blockchain()).unwrap();
+        assert_eq!(blocks.len(), 1);
+        assert_eq!((blocks[0].0, blocks[0].1.hash()), (4, hashes[2]));
+    }
+
+    #[tokio::test]
+    async fn test_client_receives_enabled_topics() {
+        let notifier = Notifier::new([Topic::NewBlock, Topic::NewTemplate]);
+        let (addr, server) = notifier
+            .serve("127.0.0.1:0".parse().unwrap(), 4)
+            .await
+            .unwrap();
+
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut lines = BufReader::new(stream).lines();
+        while notifier.client_count() == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        let tx = transfer(0, 1_000);
+        let block = Block::genesis();
+        let mut watcher = ChainWatcher::new(notifier.clone(), 0, [0u8; 32]);
+        let pending = std::slice::from_ref(&tx);
+        watcher.mempool_changed(block.hash(), pending, pending);
+        // Same view again: nothing new to announce
+        watcher.mempool_changed(block.hash(), pending, pending);
+        watcher.block_connected(1, &block);
+
+        // newtx is disabled, so the template comes first
+        let template: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(template["topic"], "newtemplate");
+        assert_eq!(template["height"], 1);
+        assert_eq!(template["tx_count"], 1);
+        assert_eq!(template["total_fees"], 1_000);
+        assert_eq!(template["sequence"], 0);
+
+        let announced: serde_json::Value =
+            serde_json::from_str(&lines.next_line().await.unwrap().unwrap()).unwrap();
+        assert_eq!(announced["topic"], "newblock");
+        assert_eq!(announced["height"], 1);
+        assert_eq!(announced["hash"], hex::encode(block.hash()));
+        assert_eq!(announced["sequence"], 1);
+
+        server.abort();
+    }
+}
@@ -0,0 +1,584 @@
+//! Environment checks run by `doctor` and before the node starts
+//! فحوصات البيئة قبل تشغيل العقدة
+//!
+//! Each check turns a condition that would otherwise surface later as a
+//! RocksDB I/O error, a rejected block or a bind failure into a message in
+//! English and Arabic that says what to do about it: free disk space, clock
+//! skew against an NTP server, whether the configured ports are free,
+//! whether another process holds the database lock, data directory
+//! permissions and schema versions this binary cannot read.
+
+use crate::config::ResourcesConfig;
+use opensyria_core::constants::MAX_FUTURE_DRIFT_SECS;
+use opensyria_storage::{BlockchainStorage, MigrationOptions, StateStorage};
+use std::fs;
+use std::net::{SocketAddr, TcpListener, ToSocketAddrs, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// NTP server queried when none is configured
+pub const DEFAULT_NTP_SERVER: &str = "pool.ntp.org:123";
+
+/// Below this much free space the node refuses to start
+pub const MIN_FREE_DISK_BYTES: u64 = 1024 * 1024 * 1024;
+
+/// Below this much free space the node warns
+pub const LOW_FREE_DISK_BYTES: u64 = 5 * 1024 * 1024 * 1024;
+
+/// Clock skew worth a warning; above [`MAX_FUTURE_DRIFT_SECS`] peers reject
+/// the blocks this node mines
+pub const CLOCK_SKEW_WARN_SECS: u64 = 15;
+
+/// How long to wait for the NTP server
+const NTP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Seconds between the NTP epoch (1900) and the Unix epoch
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// Outcome of one check, with what to do about it
+/// نتيجة فحص واحد مع الإجراء المطلوب
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub message: String,
+    pub message_ar: String,
+}
+
+impl CheckResult {
+    fn new(
+        name: impl Into<String>,
+        status: CheckStatus,
+        message: impl Into<String>,
+        message_ar: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            message: message.into(),
+            message_ar: message_ar.into(),
+        }
+    }
+}
+
+/// Which store a database directory holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseKind {
+    Blockchain,
+    State,
+}
+
+/// Checks to run against a data directory
+/// الفحوصات المطلوب تشغيلها على مجلد البيانات
+#[derive(Debug, Clone)]
+pub struct Preflight {
+    data_dir: PathBuf,
+    databases: Vec<(PathBuf, DatabaseKind)>,
+    ports: Vec<(String, SocketAddr)>,
+    ntp_server: Option<String>,
+    resources: Option<ResourcesConfig>,
+}
+
+impl Preflight {
+    /// Check the data directory and its disk only
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            databases: Vec::new(),
+            ports: Vec::new(),
+            ntp_server: None,
+            resources: None,
+        }
+    }
+
+    /// Also check the databases the daemon keeps in the data directory
+    pub fn with_daemon_databases(mut self) -> Self {
+        self.databases.extend([
+            (self.data_dir.join("blocks"), DatabaseKind::Blockchain),
+            (self.data_dir.join("state"), DatabaseKind::State),
+        ]);
+        self
+    }
+
+    /// Also check the databases a P2P node keeps in `network_dir`
+    pub fn with_network_databases(mut self, network_dir: &Path) -> Self {
+        self.databases.extend([
+            (network_dir.join("blockchain"), DatabaseKind::Blockchain),
+            (network_dir.join("state"), DatabaseKind::State),
+        ]);
+        self
+    }
+
+    /// Also check that `addr` can be bound
+    pub fn with_port(mut self, name: impl Into<String>, addr: SocketAddr) -> Self {
+        self.ports.push((name.into(), addr));
+        self
+    }
+
+    /// Also compare the local clock with `server` (host:port)
+    pub fn with_ntp_server(mut self, server: impl Into<String>) -> Self {
+        self.ntp_server = Some(server.into());
+        self
+    }
+
+    /// Also compare the configured memory and file budgets with the machine
+    pub fn with_resources(mut self, resources: ResourcesConfig) -> Self {
+        self.resources = Some(resources);
+        self
+    }
+
+    /// Run every configured check
+    /// تشغيل جميع الفحوصات
+    pub fn run(&self) -> Vec<CheckResult> {
+        let mut results = vec![
+            check_data_dir(&self.data_dir),
+            check_disk_space(&self.data_dir),
+        ];
+        if let Some(server) = &self.ntp_server {
+            results.push(check_clock(server));
+        }
+        for (name, addr) in &self.ports {
+            results.push(check_port(name, *addr));
+        }
+        for (path, kind) in &self.databases {
+            if let Some(result) = check_database(path, *kind) {
+                results.push(result);
+            }
+        }
+        if let Some(resources) = &self.resources {
+            for warning in resources.preflight() {
+                results.push(CheckResult::new(
+                    "resources",
+                    CheckStatus::Warn,
+                    warning,
+                    "ميزانيات الذاكرة أو الملفات المكوّنة تتجاوز موارد النظام؛ خفّضها في قسم [resources]",
+                ));
+            }
+        }
+        results
+    }
+}
+
+/// Worst status among `results`
+pub fn worst_status(results: &[CheckResult]) -> CheckStatus {
+    results
+        .iter()
+        .map(|result| result.status)
+        .max()
+        .unwrap_or(CheckStatus::Ok)
+}
+
+/// TCP address of a `/ip4/<addr>/tcp/<port>` or `/ip6/...` multiaddr
+pub fn tcp_socket_addr(multiaddr: &str) -> Option<SocketAddr> {
+    let parts: Vec<&str> = multiaddr.trim_start_matches('/').split('/').collect();
+    match parts.as_slice() {
+        ["ip4" | "ip6", ip, "tcp", port, ..] => {
+            Some(SocketAddr::new(ip.parse().ok()?, port.parse().ok()?))
+        }
+        _ => None,
+    }
+}
+
+fn check_data_dir(data_dir: &Path) -> CheckResult {
+    const NAME: &str = "data directory";
+    if !data_dir.exists() {
+        return match nearest_existing(data_dir) {
+            Some(parent) if is_writable(&parent) => CheckResult::new(
+                NAME,
+                CheckStatus::Ok,
+                format!("{} will be created", data_dir.display()),
+                "سيتم إنشاء مجلد البيانات",
+            ),
+            _ => CheckResult::new(
+                NAME,
+                CheckStatus::Fail,
+                format!(
+                    "{} does not exist and cannot be created; create it or pass another --data-dir",
+                    data_dir.display()
+                ),
+                "مجلد البيانات غير موجود ولا يمكن إنشاؤه؛ أنشئه أو اختر مجلداً آخر عبر --data-dir",
+            ),
+        };
+    }
+    if !data_dir.is_dir() {
+        return CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!("{} is not a directory", data_dir.display()),
+            "مسار البيانات ليس مجلداً",
+        );
+    }
+    if !is_writable(data_dir) {
+        return CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "{} is not writable by this user; fix its owner or permissions",
+                data_dir.display()
+            ),
+            "لا يمكن الكتابة في مجلد البيانات؛ صحّح المالك أو الصلاحيات",
+        );
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(data_dir).map_or(0, |m| m.permissions().mode());
+        if mode & 0o002 != 0 {
+            return CheckResult::new(
+                NAME,
+                CheckStatus::Warn,
+                format!(
+                    "{} is world-writable and holds node keys; run `chmod 700 {}`",
+                    data_dir.display(),
+                    data_dir.display()
+                ),
+                "مجلد البيانات قابل للكتابة من أي مستخدم ويحتوي مفاتيح العقدة؛ نفّذ chmod 700",
+            );
+        }
+    }
+
+    CheckResult::new(
+        NAME,
+        CheckStatus::Ok,
+        format!("{} is writable", data_dir.display()),
+        "مجلد البيانات قابل للكتابة",
+    )
+}
+
+fn check_disk_space(data_dir: &Path) -> CheckResult {
+    const NAME: &str = "disk space";
+    let Some(free) = nearest_existing(data_dir).and_then(|path| free_disk_bytes(&path)) else {
+        return CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            "Could not determine free disk space (`df` unavailable)",
+            "تعذّر تحديد المساحة الحرة على القرص",
+        );
+    };
+
+    let gib = free as f64 / (1024.0 * 1024.0 * 1024.0);
+    if free < MIN_FREE_DISK_BYTES {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "Only {:.2} GiB free; the databases stop writing when the disk fills. Free space or move --data-dir",
+                gib
+            ),
+            format!(
+                "المساحة الحرة {:.2} جيجابايت فقط؛ أفرغ مساحة أو انقل مجلد البيانات",
+                gib
+            ),
+        )
+    } else if free < LOW_FREE_DISK_BYTES {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            format!("{:.1} GiB free; consider pruning or a larger disk", gib),
+            format!(
+                "المساحة الحرة {:.1} جيجابايت؛ فكّر في التقليم أو قرص أكبر",
+                gib
+            ),
+        )
+    } else {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Ok,
+            format!("{:.1} GiB free", gib),
+            format!("المساحة الحرة {:.1} جيجابايت", gib),
+        )
+    }
+}
+
+fn check_clock(server: &str) -> CheckResult {
+    const NAME: &str = "clock";
+    let skew = match clock_skew_secs(server) {
+        Ok(skew) => skew,
+        Err(e) => {
+            return CheckResult::new(
+                NAME,
+                CheckStatus::Warn,
+                format!("Could not reach NTP server {}: {}", server, e),
+                "تعذّر الوصول إلى خادم NTP للتحقق من الساعة",
+            )
+        }
+    };
+
+    let abs = skew.unsigned_abs();
+    let direction = if skew > 0 { "behind" } else { "ahead of" };
+    if abs > MAX_FUTURE_DRIFT_SECS {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Fail,
+            format!(
+                "Clock is {}s {} {}; peers reject blocks more than {}s off. Enable NTP (e.g. `timedatectl set-ntp true`)",
+                abs, direction, server, MAX_FUTURE_DRIFT_SECS
+            ),
+            format!(
+                "الساعة منحرفة {} ثانية؛ يرفض الأقران الكتل المنحرفة أكثر من {} ثانية. فعّل مزامنة NTP",
+                abs, MAX_FUTURE_DRIFT_SECS
+            ),
+        )
+    } else if abs > CLOCK_SKEW_WARN_SECS {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Warn,
+            format!(
+                "Clock is {}s {} {}; enable NTP before it drifts further",
+                abs, direction, server
+            ),
+            format!(
+                "الساعة منحرفة {} ثانية؛ فعّل مزامنة NTP قبل أن يزداد الانحراف",
+                abs
+            ),
+        )
+    } else {
+        CheckResult::new(
+            NAME,
+            CheckStatus::Ok,
+            format!("Within {}s of {}", abs, server),
+            format!("الساعة ضمن {} ثانية من خادم NTP", abs),
+        )
+    }
+}
+
+fn check_port(name: &str, addr: SocketAddr) -> CheckResult {
+    match TcpListener::bind(addr) {
+        Ok(_) => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            format!("{} is free", addr),
+            format!("المنفذ {} متاح", addr.port()),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!(
+                "{} is already in use; stop the other process (another node?) or pick another port",
+                addr
+            ),
+            format!(
+                "المنفذ {} مستخدم؛ أوقف العملية الأخرى أو اختر منفذاً آخر",
+                addr.port()
+            ),
+        ),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!(
+                "Not allowed to bind {}; ports below 1024 need extra privileges",
+                addr
+            ),
+            format!(
+                "لا توجد صلاحية لاستخدام المنفذ {}؛ المنافذ دون 1024 تتطلب صلاحيات إضافية",
+                addr.port()
+            ),
+        ),
+        Err(e) => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!("Cannot bind {}: {}", addr, e),
+            format!("تعذّر ربط المنفذ {}", addr.port()),
+        ),
+    }
+}
+
+/// Open the database for a dry-run migration, which reads the stored schema
+/// version and takes the lock; None if there is no database yet
+fn check_database(path: &Path, kind: DatabaseKind) -> Option<CheckResult> {
+    if !path.join("CURRENT").exists() {
+        return None;
+    }
+    let name = format!("database {}", path.display());
+    let options = MigrationOptions {
+        dry_run: true,
+        backup: false,
+    };
+    let report = match kind {
+        DatabaseKind::Blockchain => BlockchainStorage::migrate(path.to_path_buf(), options),
+        DatabaseKind::State => StateStorage::migrate(path.to_path_buf(), options),
+    };
+
+    Some(match report {
+        Ok(report) if report.is_noop() => CheckResult::new(
+            name,
+            CheckStatus::Ok,
+            format!("Schema v{} is current", report.from),
+            "مخطط قاعدة البيانات محدّث",
+        ),
+        Ok(report) => CheckResult::new(
+            name,
+            CheckStatus::Warn,
+            format!(
+                "Schema v{} will be upgraded on start ({} migrations); run `migrate --dry-run` to review",
+                report.from,
+                report.applied.len()
+            ),
+            "سيتم ترقية مخطط قاعدة البيانات عند التشغيل؛ راجع الترحيلات عبر migrate --dry-run",
+        ),
+        Err(e) if e.is_lock_held() => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            "Locked by another process; stop the running node before starting another on this data directory",
+            "قاعدة البيانات مقفلة من عملية أخرى؛ أوقف العقدة العاملة قبل تشغيل أخرى على المجلد نفسه",
+        ),
+        Err(e @ opensyria_storage::StorageError::SchemaTooNew { .. }) => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            e.to_string(),
+            "قاعدة البيانات كُتبت بإصدار أحدث من هذا البرنامج؛ حدّث العقدة",
+        ),
+        Err(e) => CheckResult::new(
+            name,
+            CheckStatus::Fail,
+            format!("Cannot open: {}; run `db doctor` to look for corruption", e),
+            "تعذّر فتح قاعدة البيانات؛ شغّل db doctor للبحث عن التلف",
+        ),
+    })
+}
+
+fn nearest_existing(path: &Path) -> Option<PathBuf> {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().ok()?.join(path)
+    };
+    absolute
+        .ancestors()
+        .find(|p| p.exists())
+        .map(Path::to_path_buf)
+}
+
+/// Create and remove a probe file, which also catches read-only mounts
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(".opensyria-preflight");
+    let writable = fs::write(&probe, b"").is_ok();
+    let _ = fs::remove_file(&probe);
+    writable
+}
+
+/// Available bytes on the filesystem holding `path`, from POSIX `df -Pk`
+fn free_disk_bytes(path: &Path) -> Option<u64> {
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let kb: u64 = stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+/// Seconds the local clock is behind `server` (negative if ahead), by SNTP
+fn clock_skew_secs(server: &str) -> std::io::Result<i64> {
+    let addr = server
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| std::io::Error::other("no address"))?;
+    let socket = UdpSocket::bind(if addr.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    })?;
+    socket.set_read_timeout(Some(NTP_TIMEOUT))?;
+
+    // LI = 0, version 3, mode 3 (client)
+    let mut request = [0u8; 48];
+    request[0] = 0x1b;
+    let sent = unix_secs_f64();
+    socket.send_to(&request, addr)?;
+    let mut response = [0u8; 48];
+    let (len, _) = socket.recv_from(&mut response)?;
+    let received = unix_secs_f64();
+    if len < 48 {
+        return Err(std::io::Error::other("short NTP response"));
+    }
+
+    // Transmit timestamp: seconds and fraction since 1900
+    let seconds = u32::from_be_bytes(response[40..44].try_into().unwrap()) as u64;
+    let fraction = u32::from_be_bytes(response[44..48].try_into().unwrap()) as f64;
+    if seconds < NTP_UNIX_OFFSET {
+        return Err(std::io::Error::other("invalid NTP timestamp"));
+    }
+    let server_time = (seconds - NTP_UNIX_OFFSET) as f64 + fraction / 4_294_967_296.0;
+    Ok((server_time - (sent + received) / 2.0).round() as i64)
+}
+
+fn unix_secs_f64() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_port_and_data_dir_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        let taken = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = taken.local_addr().unwrap();
+
+        let results = Preflight::new(dir.path().join("node"))
+            .with_port("p2p", addr)
+            .run();
+        let status = |name: &str| {
+            results
+                .iter()
+                .find(|result| result.name == name)
+                .map(|result| result.status)
+        };
+        assert_eq!(status("data directory"), Some(CheckStatus::Ok));
+        assert_eq!(status("p2p"), Some(CheckStatus::Fail));
+        assert!(results.iter().all(|result| !result.message_ar.is_empty()));
+        assert_eq!(worst_status(&results), CheckStatus::Fail);
+
+        drop(taken);
+        let results = Preflight::new(dir.path()).with_port("p2p", addr).run();
+        assert_eq!(results[2].status, CheckStatus::Ok);
+    }
+
+    #[test]
+    fn test_database_check_reports_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let preflight = Preflight::new(dir.path()).with_daemon_databases();
+        // No databases yet: nothing to report
+        assert_eq!(preflight.run().len(), 2);
+
+        drop(opensyria_storage::Storage::open(dir.path().to_path_buf()).unwrap());
+        let results = preflight.run();
+        assert_eq!(results.len(), 4);
+        assert!(results[2..]
+            .iter()
+            .all(|result| result.status == CheckStatus::Ok));
+    }
+
+    #[test]
+    fn test_tcp_socket_addr() {
+        assert_eq!(
+            tcp_socket_addr("/ip4/0.0.0.0/tcp/9000"),
+            Some("0.0.0.0:9000".parse().unwrap())
+        );
+        assert_eq!(
+            tcp_socket_addr("/ip6/::1/tcp/9001/p2p/12D3KooW"),
+            Some("[::1]:9001".parse().unwrap())
+        );
+        assert_eq!(tcp_socket_addr("/ip4/0.0.0.0/udp/9000/quic-v1"), None);
+    }
+}
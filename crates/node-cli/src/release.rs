@@ -0,0 +1,350 @@
+//! Signed release manifests
+//! بيانات الإصدار الموقعة
+//!
+//! A release manifest lists the SHA-256 of every published artifact and is
+//! signed by M-of-N release keys. Anything that installs a new binary must
+//! call [`ReleaseAuthority::verify_artifact`] first: it checks the signatures
+//! and that the file on disk is the one the signers listed.
+
+use crate::build_info::BuildInfo;
+use opensyria_core::crypto::{KeyPair, PublicKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+const RELEASE_SIGNING_DOMAIN: &[u8] = b"opensyria-release-v1";
+
+/// Errors building or verifying a release manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReleaseError {
+    /// Manifest or artifact could not be read or written
+    Io(String),
+    /// Manifest JSON is malformed
+    Parse(String),
+    /// Release key configuration is unusable
+    InvalidAuthority(&'static str),
+    /// Manifest lacks enough valid release key signatures
+    InsufficientSignatures { got: usize, required: usize },
+    /// Artifact is not listed in the manifest
+    UnknownArtifact(String),
+    /// Artifact contents differ from the manifest
+    ArtifactMismatch { name: String },
+}
+
+impl std::fmt::Display for ReleaseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReleaseError::Io(e) => write!(f, "Release file error: {}", e),
+            ReleaseError::Parse(e) => write!(f, "Invalid release manifest: {}", e),
+            ReleaseError::InvalidAuthority(reason) => {
+                write!(f, "Invalid release authority: {}", reason)
+            }
+            ReleaseError::InsufficientSignatures { got, required } => {
+                write!(
+                    f,
+                    "Release has {} valid signatures, {} required",
+                    got, required
+                )
+            }
+            ReleaseError::UnknownArtifact(name) => {
+                write!(f, "Artifact {} is not listed in the release manifest", name)
+            }
+            ReleaseError::ArtifactMismatch { name } => {
+                write!(f, "Artifact {} does not match the release manifest", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReleaseError {}
+
+/// One published file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseArtifact {
+    /// File name, without directories
+    pub name: String,
+    /// Hex SHA-256 of the file contents
+    pub sha256: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Signature by one release key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseSignature {
+    /// Hex public key of the release key
+    pub signer: String,
+    /// Hex Ed25519 signature over [`ReleaseManifest::signing_hash`]
+    pub signature: String,
+}
+
+/// Release description signed by release keys
+/// وصف الإصدار الموقع من مفاتيح الإصدار
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReleaseManifest {
+    pub version: String,
+    pub git_commit: String,
+    /// Hex chain params hash per built-in network, as in [`BuildInfo`]
+    pub chain_params_hashes: BTreeMap<String, String>,
+    pub artifacts: Vec<ReleaseArtifact>,
+    #[serde(default)]
+    pub signatures: Vec<ReleaseSignature>,
+}
+
+impl ReleaseManifest {
+    /// Unsigned manifest describing the build `info`, with no artifacts yet
+    pub fn new(info: &BuildInfo) -> Self {
+        Self {
+            version: info.version.clone(),
+            git_commit: info.git_commit.clone(),
+            chain_params_hashes: info.chain_params_hashes.clone(),
+            artifacts: Vec::new(),
+            signatures: Vec::new(),
+        }
+    }
+
+    /// Hash and list a file, replacing any artifact with the same name
+    /// إضافة ملف إلى قائمة الملفات المنشورة
+    ///
+    /// Existing signatures no longer cover the manifest and are dropped.
+    pub fn add_artifact(&mut self, path: &Path) -> Result<&ReleaseArtifact, ReleaseError> {
+        let name = artifact_name(path)?;
+        let (sha256, size) = hash_file(path)?;
+        self.artifacts.retain(|artifact| artifact.name != name);
+        self.artifacts.push(ReleaseArtifact { name, sha256, size });
+        self.artifacts.sort_by(|a, b| a.name.cmp(&b.name));
+        self.signatures.clear();
+        Ok(self.artifacts.last().expect("artifact just added"))
+    }
+
+    /// Artifact listed under `name`
+    pub fn artifact(&self, name: &str) -> Option<&ReleaseArtifact> {
+        self.artifacts.iter().find(|artifact| artifact.name == name)
+    }
+
+    /// Message signed by release keys: every field except the signatures
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let unsigned = Self {
+            signatures: Vec::new(),
+            ..self.clone()
+        };
+        let encoded = serde_json::to_vec(&unsigned).expect("manifest serializes to JSON");
+        let mut hasher = Sha256::new();
+        hasher.update(RELEASE_SIGNING_DOMAIN);
+        hasher.update(&encoded);
+        hasher.finalize().into()
+    }
+
+    /// Add a signature from a release key, replacing any earlier one by the same key
+    /// إضافة توقيع من مفتاح إصدار
+    pub fn sign(&mut self, keypair: &KeyPair) {
+        let signer = keypair.public_key().to_hex();
+        let signature = hex::encode(keypair.sign(&self.signing_hash()));
+        self.signatures.retain(|sig| sig.signer != signer);
+        self.signatures.push(ReleaseSignature { signer, signature });
+    }
+
+    /// Networks whose chain params hash differs from the build `info`
+    ///
+    /// A non-empty result means the release changes consensus rules.
+    pub fn chain_params_changes(&self, info: &BuildInfo) -> Vec<String> {
+        self.chain_params_hashes
+            .iter()
+            .filter(|(network, hash)| {
+                info.chain_params_hash(network)
+                    .is_some_and(|current| current != hash.as_str())
+            })
+            .map(|(network, _)| network.clone())
+            .collect()
+    }
+
+    /// Parse a manifest from JSON
+    pub fn from_json(json: &str) -> Result<Self, ReleaseError> {
+        serde_json::from_str(json).map_err(|e| ReleaseError::Parse(e.to_string()))
+    }
+
+    /// Pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("manifest serializes to JSON")
+    }
+
+    /// Load a manifest file
+    pub fn load(path: &Path) -> Result<Self, ReleaseError> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| ReleaseError::Io(format!("{}: {}", path.display(), e)))?;
+        Self::from_json(&json)
+    }
+
+    /// Write the manifest to a file
+    pub fn save(&self, path: &Path) -> Result<(), ReleaseError> {
+        std::fs::write(path, self.to_json())
+            .map_err(|e| ReleaseError::Io(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// M-of-N set of keys allowed to sign releases
+/// مجموعة مفاتيح مخولة بتوقيع الإصدارات (M من N)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseAuthority {
+    keys: Vec<PublicKey>,
+    threshold: usize,
+}
+
+impl ReleaseAuthority {
+    /// Create an authority requiring `threshold` of `keys` to sign
+    pub fn new(keys: Vec<PublicKey>, threshold: usize) -> Result<Self, ReleaseError> {
+        let mut unique = HashSet::new();
+        if !keys.iter().all(|key| unique.insert(*key)) {
+            return Err(ReleaseError::InvalidAuthority("duplicate release key"));
+        }
+        if threshold == 0 || threshold > keys.len() {
+            return Err(ReleaseError::InvalidAuthority(
+                "threshold must be between 1 and the number of keys",
+            ));
+        }
+        Ok(Self { keys, threshold })
+    }
+
+    /// Check that at least `threshold` distinct release keys signed the manifest
+    /// التحقق من توقيع عدد كافٍ من مفاتيح الإصدار
+    pub fn verify(&self, manifest: &ReleaseManifest) -> Result<(), ReleaseError> {
+        let message = manifest.signing_hash();
+        let mut signers = HashSet::new();
+
+        for sig in &manifest.signatures {
+            let Ok(signer) = PublicKey::from_hex(&sig.signer) else {
+                continue;
+            };
+            let valid = hex::decode(&sig.signature)
+                .is_ok_and(|signature| signer.verify(&message, &signature).is_ok());
+            if valid && self.keys.contains(&signer) {
+                signers.insert(signer);
+            }
+        }
+
+        if signers.len() < self.threshold {
+            return Err(ReleaseError::InsufficientSignatures {
+                got: signers.len(),
+                required: self.threshold,
+            });
+        }
+        Ok(())
+    }
+
+    /// Verify the manifest, then check that the file at `path` is the
+    /// artifact it lists under the same name
+    /// التحقق من البيانات ثم من تطابق الملف معها قبل تثبيته
+    pub fn verify_artifact<'a>(
+        &self,
+        manifest: &'a ReleaseManifest,
+        path: &Path,
+    ) -> Result<&'a ReleaseArtifact, ReleaseError> {
+        self.verify(manifest)?;
+
+        let name = artifact_name(path)?;
+        let artifact = manifest
+            .artifact(&name)
+            .ok_or_else(|| ReleaseError::UnknownArtifact(name.clone()))?;
+        let (sha256, size) = hash_file(path)?;
+        if sha256 != artifact.sha256 || size != artifact.size {
+            return Err(ReleaseError::ArtifactMismatch { name });
+        }
+        Ok(artifact)
+    }
+}
+
+fn artifact_name(path: &Path) -> Result<String, ReleaseError> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| ReleaseError::Io(format!("{}: not a file name", path.display())))
+}
+
+fn hash_file(path: &Path) -> Result<(String, u64), ReleaseError> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| ReleaseError::Io(format!("{}: {}", path.display(), e)))?;
+    let mut hasher = Sha256::new();
+    let size = std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| ReleaseError::Io(format!("{}: {}", path.display(), e)))?;
+    Ok((hex::encode(hasher.finalize()), size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest_with_artifact(dir: &Path) -> (ReleaseManifest, std::path::PathBuf) {
+        let path = dir.join("opensyria-node-x86_64-linux.tar.gz");
+        std::fs::write(&path, b"release bytes").unwrap();
+        let mut manifest = ReleaseManifest::new(&BuildInfo::current());
+        manifest.add_artifact(&path).unwrap();
+        (manifest, path)
+    }
+
+    #[test]
+    fn test_threshold_signatures_required() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut manifest, path) = manifest_with_artifact(dir.path());
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate()).collect();
+        let authority =
+            ReleaseAuthority::new(keys.iter().map(|k| k.public_key()).collect(), 2).unwrap();
+
+        manifest.sign(&keys[0]);
+        manifest.sign(&keys[0]);
+        assert_eq!(
+            authority.verify(&manifest),
+            Err(ReleaseError::InsufficientSignatures {
+                got: 1,
+                required: 2
+            })
+        );
+
+        // Outsiders do not count towards the threshold
+        manifest.sign(&KeyPair::generate());
+        assert!(authority.verify(&manifest).is_err());
+
+        manifest.sign(&keys[2]);
+        let manifest = ReleaseManifest::from_json(&manifest.to_json()).unwrap();
+        let artifact = authority.verify_artifact(&manifest, &path).unwrap();
+        assert_eq!(artifact.size, 13);
+    }
+
+    #[test]
+    fn test_tampering_is_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let (mut manifest, path) = manifest_with_artifact(dir.path());
+        let key = KeyPair::generate();
+        let authority = ReleaseAuthority::new(vec![key.public_key()], 1).unwrap();
+        manifest.sign(&key);
+
+        let mut edited = manifest.clone();
+        edited.version = "99.0.0".to_string();
+        assert!(authority.verify(&edited).is_err());
+
+        std::fs::write(&path, b"patched bytes").unwrap();
+        assert!(matches!(
+            authority.verify_artifact(&manifest, &path),
+            Err(ReleaseError::ArtifactMismatch { .. })
+        ));
+
+        let other = dir.path().join("other.bin");
+        std::fs::write(&other, b"release bytes").unwrap();
+        assert!(matches!(
+            authority.verify_artifact(&manifest, &other),
+            Err(ReleaseError::UnknownArtifact(_))
+        ));
+    }
+
+    #[test]
+    fn test_chain_params_changes() {
+        let info = BuildInfo::current();
+        let mut manifest = ReleaseManifest::new(&info);
+        assert!(manifest.chain_params_changes(&info).is_empty());
+
+        manifest
+            .chain_params_hashes
+            .insert("testnet".to_string(), "00".repeat(32));
+        assert_eq!(manifest.chain_params_changes(&info), vec!["testnet"]);
+    }
+}
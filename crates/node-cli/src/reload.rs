@@ -0,0 +1,281 @@
+//! Configuration reload without a restart
+//! إعادة تحميل التكوين دون إعادة التشغيل
+//!
+//! `kill -HUP <pid>` or `opensyria-node-cli network reload` makes a running
+//! daemon read its config file again. Settings listed in [`RELOADABLE`] take
+//! effect at once. Any other change is reported as needing a restart and
+//! left alone, so it keeps being reported until the daemon restarts. A file
+//! that fails to parse or validate changes nothing. A setting the
+//! environment overrides, such as `daemon.log_level` under `RUST_LOG`, is
+//! reported as overridden and never applied.
+
+use crate::config::NodeConfig;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Settings a running daemon applies on reload, as `section.key`
+///
+/// `network.*` is not here: the daemon runs no P2P node, and `network start`
+/// takes its peer and rate limits as flags rather than from this file, so
+/// there is nothing running for a reload to change.
+pub const RELOADABLE: &[&str] = &[
+    "daemon.log_level",
+    "mining.payout_split",
+    "mining.extra_data",
+    "notify.topics",
+];
+
+/// Outcome of a reload
+/// نتيجة إعادة التحميل
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Changed settings now in effect
+    pub applied: Vec<String>,
+    /// Changed settings that take effect on the next start
+    pub restart_required: Vec<String>,
+    /// Changed settings the environment overrides, left alone
+    #[serde(default)]
+    pub overridden: Vec<String>,
+}
+
+/// Settings a running daemon is using, and where they came from
+/// الإعدادات التي تستخدمها الخدمة العاملة
+pub struct ConfigReloader {
+    path: PathBuf,
+    active: NodeConfig,
+    overridden: Vec<String>,
+}
+
+impl ConfigReloader {
+    /// Track `active`, loaded from `path` (or the defaults if it does not exist)
+    pub fn new(path: PathBuf, active: NodeConfig) -> Self {
+        Self {
+            path,
+            active,
+            overridden: Vec::new(),
+        }
+    }
+
+    /// Never apply `setting`, e.g. because an environment variable set at
+    /// startup takes precedence over the file
+    pub fn with_override(mut self, setting: &str) -> Self {
+        self.overridden.push(setting.to_string());
+        self
+    }
+
+    /// Settings in effect
+    pub fn active(&self) -> &NodeConfig {
+        &self.active
+    }
+
+    /// Read the file again and apply what can be applied
+    ///
+    /// `apply` gets the new config and the reloadable settings that changed.
+    /// They only count as active once it succeeds.
+    pub fn reload(
+        &mut self,
+        apply: impl FnOnce(&NodeConfig, &[String]) -> Result<()>,
+    ) -> Result<ReloadReport> {
+        let config = if self.path.exists() {
+            NodeConfig::load_from_file(&self.path)?
+        } else {
+            NodeConfig::default()
+        };
+
+        let (overridden, changed): (Vec<String>, Vec<String>) =
+            changed_settings(&self.active, &config)?
+                .into_iter()
+                .partition(|setting| self.overridden.contains(setting));
+        let (applied, restart_required): (Vec<String>, Vec<String>) = changed
+            .into_iter()
+            .partition(|setting| RELOADABLE.contains(&setting.as_str()));
+        apply(&config, &applied)?;
+
+        // Restart-only settings stay as they were started with
+        let mut active = serde_json::to_value(&self.active)?;
+        let new = serde_json::to_value(&config)?;
+        for setting in &applied {
+            let path: Vec<&str> = setting.split('.').collect();
+            set_setting(&mut active, &path, lookup(&new, &path).cloned());
+        }
+        self.active = serde_json::from_value(active).context("Failed to merge reloaded config")?;
+
+        Ok(ReloadReport {
+            applied,
+            restart_required,
+            overridden,
+        })
+    }
+}
+
+/// Settings that differ between two configs, as sorted `section.key` names
+pub fn changed_settings(old: &NodeConfig, new: &NodeConfig) -> Result<Vec<String>> {
+    let mut before = BTreeMap::new();
+    let mut after = BTreeMap::new();
+    flatten("", serde_json::to_value(old)?, &mut before);
+    flatten("", serde_json::to_value(new)?, &mut after);
+
+    let mut changed: Vec<String> = before
+        .keys()
+        .chain(after.keys())
+        .filter(|key| before.get(*key) != after.get(*key))
+        .cloned()
+        .collect();
+    changed.sort();
+    changed.dedup();
+    Ok(changed)
+}
+
+/// Collect leaf values by dotted path; arrays count as one setting
+fn flatten(prefix: &str, value: Value, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let path = if prefix.is_empty() {
+                    key
+                } else {
+                    format!("{}.{}", prefix, key)
+                };
+                flatten(&path, value, out);
+            }
+        }
+        // An absent optional setting and an explicit null are the same
+        Value::Null => {}
+        value => {
+            out.insert(prefix.to_string(), value);
+        }
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+fn set_setting(value: &mut Value, path: &[&str], setting: Option<Value>) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        let Some(fields) = current.as_object_mut() else {
+            return;
+        };
+        current = fields
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    if let Some(fields) = current.as_object_mut() {
+        match setting {
+            Some(setting) => fields.insert(last.to_string(), setting),
+            None => fields.remove(*last),
+        };
+    }
+}
+
+/// SIGHUP on Unix; never fires elsewhere
+/// إشارة SIGHUP على أنظمة Unix
+pub struct HangupSignal {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+impl HangupSignal {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+                .context("Failed to listen for SIGHUP")?,
+        })
+    }
+
+    /// Wait for the next SIGHUP
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        if self.signal.recv().await.is_some() {
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_reports_applied_and_restart_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let started = NodeConfig::default();
+        started.save_to_file(&path).unwrap();
+        let mut reloader = ConfigReloader::new(path.clone(), started);
+
+        // Nothing changed
+        let report = reloader.reload(|_, _| Ok(())).unwrap();
+        assert_eq!(report, ReloadReport::default());
+
+        let mut edited = NodeConfig::default();
+        edited.daemon.log_level = "info,opensyria_network=debug".to_string();
+        edited.mining.extra_data = Some("pool-7".to_string());
+        edited.resources.block_cache_mb = 64;
+        edited.save_to_file(&path).unwrap();
+
+        let mut seen = Vec::new();
+        let report = reloader
+            .reload(|config, applied| {
+                assert_eq!(config.daemon.log_level, "info,opensyria_network=debug");
+                seen = applied.to_vec();
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(report.applied, ["daemon.log_level", "mining.extra_data"]);
+        assert_eq!(report.applied, seen);
+        assert_eq!(report.restart_required, ["resources.block_cache_mb"]);
+        assert_eq!(
+            reloader.active().mining.extra_data.as_deref(),
+            Some("pool-7")
+        );
+        assert_eq!(reloader.active().resources.block_cache_mb, 256);
+
+        // Still pending until a restart; applied settings are not repeated
+        let report = reloader.reload(|_, _| Ok(())).unwrap();
+        assert!(report.applied.is_empty());
+        assert_eq!(report.restart_required, ["resources.block_cache_mb"]);
+
+        // A broken file changes nothing
+        std::fs::write(&path, "[daemon]\nlog_level = \"info,opensyria=loud\"\n").unwrap();
+        assert!(reloader.reload(|_, _| Ok(())).is_err());
+        assert_eq!(
+            reloader.active().daemon.log_level,
+            "info,opensyria_network=debug"
+        );
+    }
+
+    #[test]
+    fn test_reload_leaves_overridden_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let mut reloader = ConfigReloader::new(path.clone(), NodeConfig::default())
+            .with_override("daemon.log_level");
+
+        let mut edited = NodeConfig::default();
+        edited.daemon.log_level = "debug".to_string();
+        edited.save_to_file(&path).unwrap();
+
+        let report = reloader
+            .reload(|_, applied| {
+                assert!(applied.is_empty());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(report.overridden, ["daemon.log_level"]);
+        assert!(report.applied.is_empty());
+        assert_eq!(
+            reloader.active().daemon.log_level,
+            NodeConfig::default().daemon.log_level
+        );
+    }
+}
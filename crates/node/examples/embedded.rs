@@ -0,0 +1,48 @@
+//! Run a testnet node inside a service and log what it sees
+//! تشغيل عقدة على الشبكة التجريبية داخل خدمة وتسجيل أحداثها
+//!
+//! ```bash
+//! cargo run -p opensyria-node --example embedded -- /tmp/embedded-node
+//! ```
+
+use opensyria_node::{NodeConfig, NodeEvent, NodeHandle, PeerEvent};
+use tokio::sync::broadcast::error::RecvError;
+
+#[tokio::main]
+async fn main() -> opensyria_node::Result<()> {
+    let data_dir = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "embedded-node".to_string());
+
+    let mut config = NodeConfig::testnet(data_dir);
+    config.network.listen_addr = "/ip4/0.0.0.0/tcp/0".parse().expect("valid multiaddr");
+
+    let mut node = NodeHandle::open(config).await?;
+    println!("Peer ID: {}", node.local_peer_id());
+    println!("Chain height: {}", node.height().await?);
+
+    let mut events = node.subscribe_events();
+    node.start_network().await?;
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(NodeEvent::BlockConnected { height, block }) => {
+                    println!("Block {} connected with {} tx(s)", height, block.transactions.len());
+                }
+                Ok(NodeEvent::TxAdded(tx)) => {
+                    println!("Transaction {} pending", hex::encode(tx.hash()));
+                }
+                Ok(NodeEvent::Peer(PeerEvent::Connected(peer))) => {
+                    println!("Peer connected: {} ({} total)", peer, node.peer_count().await?);
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("Mempool size at shutdown: {}", node.mempool_size().await);
+    node.shutdown().await
+}
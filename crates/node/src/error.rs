@@ -0,0 +1,43 @@
+//! Errors returned by the embedding API
+//! الأخطاء التي تعيدها واجهة التضمين
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, NodeError>;
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum NodeError {
+    #[error("Storage error: {0}")]
+    Storage(#[from] opensyria_storage::StorageError),
+
+    #[error("Governance storage error: {0}")]
+    Governance(#[from] opensyria_governance::StorageError),
+
+    #[error("Transaction rejected: {0}")]
+    Rejected(#[from] opensyria_mempool::MempoolError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Network error: {0}")]
+    Network(String),
+
+    #[error("Network already started")]
+    AlreadyStarted,
+
+    #[error("Network is not running")]
+    NotRunning,
+
+    #[error("Node is in safe mode; not accepting transactions")]
+    SafeMode,
+
+    #[error("Relay-only node keeps no account state")]
+    RelayOnly,
+}
+
+impl NodeError {
+    pub(crate) fn network(e: impl std::fmt::Display) -> Self {
+        NodeError::Network(e.to_string())
+    }
+}
@@ -0,0 +1,133 @@
+//! Governance finalization driven by connected blocks
+//! إنهاء مقترحات الحوكمة مع كل كتلة متصلة
+
+use crate::error::Result;
+use opensyria_consensus::{Checkpoint, CheckpointRegistry};
+use opensyria_core::{EventBus, GovernanceEvent, NodeEvent};
+use opensyria_governance::{GovernanceManager, GovernanceStorage};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+/// Finalize governance proposals at a block the network node connected,
+/// returning the lifecycle events this produced
+/// معالجة مقترحات الحوكمة عند كتلة متصلة وإرجاع أحداث دورة الحياة
+///
+/// Governance storage is opened only for the call, so `governance` commands
+/// can still run while the network node is up. Events those commands queued,
+/// such as `VotingStarted` and `Executed`, come first.
+pub fn process_governance_at(data_dir: &Path, height: u64) -> Result<Vec<GovernanceEvent>> {
+    let gov_dir = data_dir.join("governance");
+    if !gov_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let storage = GovernanceStorage::open(&gov_dir)?;
+    if !storage.has_snapshot()? {
+        return Ok(Vec::new());
+    }
+
+    let mut events = storage.take_events()?;
+    let mut manager = GovernanceManager::from_snapshot(storage.load_snapshot()?);
+    manager.set_processed_height(storage.load_processed_height()?);
+    manager.process_proposals(height);
+    storage.save_snapshot(&manager.create_snapshot())?;
+    storage.save_processed_height(height)?;
+    events.extend(manager.take_events());
+    Ok(events)
+}
+
+/// Move governance processing back below a disconnected block
+/// إرجاع ارتفاع معالجة الحوكمة عند فصل كتلة
+///
+/// Proposals that became executable above the new tip are announced again
+/// once the replacing chain reaches that height.
+pub fn rewind_governance_to(data_dir: &Path, height: u64) -> Result<()> {
+    let gov_dir = data_dir.join("governance");
+    if !gov_dir.exists() {
+        return Ok(());
+    }
+    GovernanceStorage::open(&gov_dir)?.rewind_processed_height(height)?;
+    Ok(())
+}
+
+/// Checkpoints published by executed emergency council motions
+/// نقاط الفحص التي نشرها مجلس الطوارئ
+pub fn council_checkpoints_at(data_dir: &Path) -> Result<Vec<Checkpoint>> {
+    let gov_dir = data_dir.join("governance");
+    if !gov_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let storage = GovernanceStorage::open(&gov_dir)?;
+    if !storage.has_snapshot()? {
+        return Ok(Vec::new());
+    }
+
+    let manager = GovernanceManager::from_snapshot(storage.load_snapshot()?);
+    let checkpoints = manager.council_checkpoints().into_iter();
+    Ok(checkpoints
+        .map(|(height, hash)| Checkpoint { height, hash })
+        .collect())
+}
+
+/// Load council checkpoints into `checkpoints`, replacing those of motions
+/// revoked since the last refresh
+async fn refresh_council_checkpoints(data_dir: PathBuf, checkpoints: &RwLock<CheckpointRegistry>) {
+    match tokio::task::spawn_blocking(move || council_checkpoints_at(&data_dir)).await {
+        Ok(Ok(council)) => {
+            for (checkpoint, e) in checkpoints.write().await.set_council(council) {
+                tracing::warn!(
+                    "Ignoring council checkpoint at height {}: {}",
+                    checkpoint.height,
+                    e
+                );
+            }
+        }
+        Ok(Err(e)) => tracing::warn!("Loading council checkpoints failed: {}", e),
+        Err(e) => tracing::warn!("Council checkpoint task panicked: {}", e),
+    }
+}
+
+/// Process governance at every connected block and publish the resulting
+/// lifecycle events on `event_bus`
+/// معالجة الحوكمة عند كل كتلة متصلة ونشر أحداثها
+///
+/// Checkpoints published by the emergency council are loaded into
+/// `checkpoints` on start and after every block, so an executed or revoked
+/// motion takes effect without a restart.
+pub fn spawn_governance_feed(
+    mut blocks: broadcast::Receiver<NodeEvent>,
+    event_bus: EventBus,
+    data_dir: PathBuf,
+    checkpoints: Arc<RwLock<CheckpointRegistry>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        refresh_council_checkpoints(data_dir.clone(), &checkpoints).await;
+        loop {
+            let dir = data_dir.clone();
+            let result = match blocks.recv().await {
+                Ok(NodeEvent::BlockConnected { height, .. }) => {
+                    tokio::task::spawn_blocking(move || process_governance_at(&dir, height)).await
+                }
+                Ok(NodeEvent::BlockDisconnected { height, .. }) => {
+                    let parent = height.saturating_sub(1);
+                    tokio::task::spawn_blocking(move || rewind_governance_to(&dir, parent))
+                        .await
+                        .map(|rewound| rewound.map(|()| Vec::new()))
+                }
+                Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            match result {
+                Ok(Ok(governance_events)) => {
+                    for event in governance_events {
+                        event_bus.publish(NodeEvent::Governance(event));
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("Governance processing failed: {}", e),
+                Err(e) => tracing::warn!("Governance task panicked: {}", e),
+            }
+            refresh_council_checkpoints(data_dir.clone(), &checkpoints).await;
+        }
+    })
+}
@@ -0,0 +1,432 @@
+//! Handle to an embedded node
+//! مقبض العقدة المضمنة
+
+use crate::error::{NodeError, Result};
+use crate::governance::spawn_governance_feed;
+use libp2p::{Multiaddr, PeerId};
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::{Block, EventBus, NodeEvent, SafeMode, Transaction};
+use opensyria_mempool::Mempool;
+use opensyria_network::{NetworkCommand, NetworkNode, NetworkType};
+use opensyria_storage::{BlockchainStorage, StateStorage};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
+use tokio::task::JoinHandle;
+
+/// P2P settings for an embedded node
+pub type NetworkConfig = opensyria_network::NodeConfig;
+
+/// Embedded node configuration
+/// تكوين العقدة المضمنة
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// Root data directory, laid out like the CLI's: chain data under
+    /// `network/`, governance under `governance/`
+    pub data_dir: PathBuf,
+
+    /// P2P settings; `network.data_dir` is set from `data_dir`
+    pub network: NetworkConfig,
+}
+
+impl NodeConfig {
+    /// Mainnet node stored under `data_dir`
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self::with_network_type(data_dir, NetworkType::Mainnet)
+    }
+
+    /// Testnet node stored under `data_dir`
+    pub fn testnet(data_dir: impl Into<PathBuf>) -> Self {
+        Self::with_network_type(data_dir, NetworkType::Testnet)
+    }
+
+    fn with_network_type(data_dir: impl Into<PathBuf>, network_type: NetworkType) -> Self {
+        let data_dir = data_dir.into();
+        let mut network = NetworkConfig::with_network_type(network_type);
+        network.data_dir = data_dir.join("network");
+        Self { data_dir, network }
+    }
+}
+
+/// Background tasks of a started node
+struct Running {
+    commands: mpsc::UnboundedSender<NetworkCommand>,
+    event_loop: JoinHandle<anyhow::Result<()>>,
+    governance_feed: JoinHandle<()>,
+}
+
+/// A full node running inside the host process
+/// عقدة كاملة تعمل داخل العملية المضيفة
+///
+/// Queries read the same storage and mempool the network event loop
+/// writes, so they reflect gossip as soon as it is applied.
+pub struct NodeHandle {
+    data_dir: PathBuf,
+    listen_addr: Multiaddr,
+    local_peer_id: String,
+    blockchain: Arc<RwLock<BlockchainStorage>>,
+    /// None on relay-only nodes
+    state: Option<Arc<RwLock<StateStorage>>>,
+    mempool: Arc<RwLock<Mempool>>,
+    event_bus: EventBus,
+    safe_mode: SafeMode,
+    /// Network node until `start_network` moves it into the event loop
+    node: Option<Box<NetworkNode>>,
+    running: Option<Running>,
+}
+
+impl NodeHandle {
+    /// Open the node's storage and identity without touching the network
+    /// فتح تخزين العقدة وهويتها دون الاتصال بالشبكة
+    pub async fn open(mut config: NodeConfig) -> Result<Self> {
+        config.network.data_dir = config.data_dir.join("network");
+        std::fs::create_dir_all(&config.network.data_dir)?;
+
+        let listen_addr = config.network.listen_addr.clone();
+        let safe_mode = SafeMode::new(&config.network.data_dir);
+        let (node, _network_events) = NetworkNode::new(config.network)
+            .await
+            .map_err(NodeError::network)?;
+
+        Ok(Self {
+            data_dir: config.data_dir,
+            listen_addr,
+            local_peer_id: node.local_peer_id().to_string(),
+            blockchain: node.blockchain(),
+            state: node.state(),
+            mempool: node.mempool(),
+            event_bus: node.event_bus().clone(),
+            safe_mode,
+            node: Some(Box::new(node)),
+            running: None,
+        })
+    }
+
+    /// Listen for peers and run the event loop in the background
+    /// بدء الاستماع للأقران وتشغيل حلقة الأحداث في الخلفية
+    ///
+    /// Governance proposals are finalized as blocks connect, as in the CLI.
+    pub async fn start_network(&mut self) -> Result<()> {
+        let Some(mut node) = self.node.take() else {
+            return Err(NodeError::AlreadyStarted);
+        };
+        if let Err(e) = node.listen(self.listen_addr.clone()).await {
+            self.node = Some(node);
+            return Err(NodeError::network(e));
+        }
+
+        self.running = Some(Running {
+            commands: node.command_sender(),
+            governance_feed: spawn_governance_feed(
+                self.event_bus.subscribe(),
+                self.event_bus.clone(),
+                self.data_dir.clone(),
+                node.checkpoints(),
+            ),
+            event_loop: tokio::spawn(async move { node.run().await }),
+        });
+        Ok(())
+    }
+
+    /// Whether the network event loop is running
+    pub fn is_running(&self) -> bool {
+        self.running
+            .as_ref()
+            .is_some_and(|running| !running.event_loop.is_finished())
+    }
+
+    /// Stop the event loop, saving peer reputation, and wait for it to exit
+    /// إيقاف حلقة الأحداث وانتظار خروجها
+    pub async fn shutdown(self) -> Result<()> {
+        let Some(running) = self.running else {
+            return Ok(());
+        };
+        let _ = running.commands.send(NetworkCommand::Shutdown);
+        let result = running.event_loop.await;
+        running.governance_feed.abort();
+        result
+            .map_err(NodeError::network)?
+            .map_err(NodeError::network)
+    }
+
+    /// Subscribe to blocks, mempool, peer and governance events
+    /// الاشتراك في أحداث العقدة
+    pub fn subscribe_events(&self) -> broadcast::Receiver<NodeEvent> {
+        self.event_bus.subscribe()
+    }
+
+    /// Event bus the node publishes on, for services that fan events out
+    /// to their own subscribers
+    pub fn event_bus(&self) -> &EventBus {
+        &self.event_bus
+    }
+
+    /// Block storage shared with the network event loop
+    /// تخزين الكتل المشترك مع حلقة أحداث الشبكة
+    pub fn blockchain(&self) -> Arc<RwLock<BlockchainStorage>> {
+        self.blockchain.clone()
+    }
+
+    /// Account state shared with the network event loop; `None` on
+    /// relay-only nodes
+    pub fn state_storage(&self) -> Option<Arc<RwLock<StateStorage>>> {
+        self.state.clone()
+    }
+
+    /// Mempool shared with the network event loop
+    /// مجمع المعاملات المشترك مع حلقة أحداث الشبكة
+    pub fn mempool(&self) -> Arc<RwLock<Mempool>> {
+        self.mempool.clone()
+    }
+
+    /// Add a transaction to the mempool and, once the network is running,
+    /// gossip it to peers
+    /// إرسال معاملة إلى مجمع المعاملات وبثها للأقران
+    ///
+    /// A transaction the mempool accepted stays there even if gossip fails
+    /// (for example with no peers connected yet).
+    pub async fn submit_tx(&self, tx: Transaction) -> Result<()> {
+        if self.safe_mode.is_active() {
+            return Err(NodeError::SafeMode);
+        }
+        self.mempool
+            .write()
+            .await
+            .add_transaction(tx.clone())
+            .await?;
+
+        if self.running.is_some() {
+            if let Err(e) = self
+                .request(|reply| NetworkCommand::BroadcastTransaction { tx, reply })
+                .await?
+            {
+                tracing::warn!("Transaction accepted but not broadcast: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace the pending transaction with the same sender and nonce by a
+    /// higher-fee one and, once the network is running, gossip it
+    /// استبدال معاملة معلقة برسوم أعلى وبثها للأقران
+    ///
+    /// Without a pending transaction to replace, `tx` is submitted like any
+    /// other.
+    pub async fn replace_tx(&self, tx: Transaction) -> Result<()> {
+        if self.safe_mode.is_active() {
+            return Err(NodeError::SafeMode);
+        }
+        self.mempool
+            .write()
+            .await
+            .replace_transaction(tx.clone())
+            .await?;
+
+        if self.running.is_some() {
+            if let Err(e) = self
+                .request(|reply| NetworkCommand::BroadcastTransaction { tx, reply })
+                .await?
+            {
+                tracing::warn!("Replacement accepted but not broadcast: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Dial a peer; requires a running network
+    pub async fn dial(&self, addr: Multiaddr) -> Result<()> {
+        self.request(|reply| NetworkCommand::Dial { addr, reply })
+            .await?
+            .map_err(NodeError::network)
+    }
+
+    /// Ban a peer for `duration` and disconnect it; requires a running network
+    pub async fn ban_peer(&self, peer_id: PeerId, duration: Duration) -> Result<()> {
+        self.request(|reply| NetworkCommand::BanPeer {
+            peer_id,
+            duration,
+            reply,
+        })
+        .await?
+        .map_err(NodeError::network)
+    }
+
+    /// Number of connected peers (0 before the network starts)
+    pub async fn peer_count(&self) -> Result<usize> {
+        if self.running.is_none() {
+            return Ok(0);
+        }
+        self.request(|reply| NetworkCommand::PeerCount { reply })
+            .await
+    }
+
+    /// Send a request to the event loop and wait for its reply
+    async fn request<T>(
+        &self,
+        command: impl FnOnce(oneshot::Sender<T>) -> NetworkCommand,
+    ) -> Result<T> {
+        let Some(running) = &self.running else {
+            return Err(NodeError::NotRunning);
+        };
+        let (reply, response) = oneshot::channel();
+        running
+            .commands
+            .send(command(reply))
+            .map_err(|_| NodeError::NotRunning)?;
+        response.await.map_err(|_| NodeError::NotRunning)
+    }
+
+    /// Libp2p peer ID of this node
+    pub fn local_peer_id(&self) -> &str {
+        &self.local_peer_id
+    }
+
+    /// Root data directory
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// Safe mode marker; while set, transactions are refused
+    pub fn safe_mode(&self) -> &SafeMode {
+        &self.safe_mode
+    }
+
+    /// Current chain height
+    pub async fn height(&self) -> Result<u64> {
+        Ok(self.blockchain.read().await.get_chain_height()?)
+    }
+
+    /// Latest block, if any
+    pub async fn tip(&self) -> Result<Option<Block>> {
+        let blockchain = self.blockchain.read().await;
+        match blockchain.get_chain_tip()? {
+            Some(hash) => Ok(blockchain.get_block(&hash)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Block at `height` on the main chain
+    pub async fn block_by_height(&self, height: u64) -> Result<Option<Block>> {
+        Ok(self.blockchain.read().await.get_block_by_height(height)?)
+    }
+
+    /// Block with the given hash
+    pub async fn block_by_hash(&self, hash: &[u8; 32]) -> Result<Option<Block>> {
+        Ok(self.blockchain.read().await.get_block(hash)?)
+    }
+
+    /// Confirmed balance of `address`
+    pub async fn balance(&self, address: &PublicKey) -> Result<u64> {
+        Ok(self.state()?.read().await.get_balance(address)?)
+    }
+
+    /// Next nonce `address` must use, ignoring pending transactions
+    pub async fn nonce(&self, address: &PublicKey) -> Result<u64> {
+        Ok(self.state()?.read().await.get_nonce(address)?)
+    }
+
+    fn state(&self) -> Result<&Arc<RwLock<StateStorage>>> {
+        self.state.as_ref().ok_or(NodeError::RelayOnly)
+    }
+
+    /// Up to `max_count` pending transactions in block order
+    pub async fn pending_transactions(&self, max_count: usize) -> Vec<Transaction> {
+        self.mempool
+            .read()
+            .await
+            .select_block_transactions(max_count)
+    }
+
+    /// Number of pending transactions
+    pub async fn mempool_size(&self) -> usize {
+        self.mempool.read().await.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    fn local_config(dir: &Path) -> NodeConfig {
+        let mut config = NodeConfig::testnet(dir);
+        config.network.listen_addr = "/ip4/127.0.0.1/tcp/0".parse().unwrap();
+        config.network.bootstrap_peers.clear();
+        config.network.enable_mdns = false;
+        config.network.enable_kademlia = false;
+        config
+    }
+
+    fn transfer(sender: &KeyPair, nonce: u64) -> Transaction {
+        let tx = Transaction::new(
+            sender.public_key(),
+            KeyPair::generate().public_key(),
+            10_000,
+            1_000,
+            nonce,
+        );
+        let signature = sender.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[tokio::test]
+    async fn test_node_handle_lifecycle() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = NodeHandle::open(local_config(dir.path())).await.unwrap();
+        assert!(!node.is_running());
+        assert_eq!(node.peer_count().await.unwrap(), 0);
+        let peer: Multiaddr = "/ip4/127.0.0.1/tcp/1".parse().unwrap();
+        assert!(matches!(node.dial(peer).await, Err(NodeError::NotRunning)));
+
+        // Rejections keep the mempool's reason
+        let sender = KeyPair::generate();
+        assert!(matches!(
+            node.submit_tx(transfer(&sender, 0)).await,
+            Err(NodeError::Rejected(_))
+        ));
+
+        node.state()
+            .unwrap()
+            .write()
+            .await
+            .set_balance(&sender.public_key(), 1_000_000)
+            .unwrap();
+        assert_eq!(node.balance(&sender.public_key()).await.unwrap(), 1_000_000);
+        node.submit_tx(transfer(&sender, 0)).await.unwrap();
+        assert_eq!(node.mempool_size().await, 1);
+
+        node.start_network().await.unwrap();
+        assert!(node.is_running());
+        assert!(matches!(
+            node.start_network().await,
+            Err(NodeError::AlreadyStarted)
+        ));
+        assert_eq!(node.peer_count().await.unwrap(), 0);
+
+        // Accepted even though there is nobody to gossip it to
+        node.submit_tx(transfer(&sender, 1)).await.unwrap();
+        assert_eq!(node.pending_transactions(10).await.len(), 2);
+
+        node.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_relay_only_node_keeps_no_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = local_config(dir.path());
+        config.network.relay_only = true;
+        let node = NodeHandle::open(config).await.unwrap();
+
+        assert!(!dir.path().join("network").join("state").exists());
+        let address = KeyPair::generate().public_key();
+        assert!(matches!(
+            node.balance(&address).await,
+            Err(NodeError::RelayOnly)
+        ));
+        assert!(matches!(
+            node.submit_tx(transfer(&KeyPair::generate(), 0)).await,
+            Err(NodeError::Rejected(_))
+        ));
+    }
+}
@@ -0,0 +1,43 @@
+//! Embed an OpenSyria full node in another Rust service
+//! تضمين عقدة OpenSyria كاملة في خدمة Rust أخرى
+//!
+//! [`NodeHandle`] is the supported way to run a node in-process: open it,
+//! start the network, subscribe to events, submit transactions and query
+//! the chain. The CLI stays a thin front end; services should depend on
+//! this crate rather than on `opensyria-node-cli`.
+//!
+//! ```no_run
+//! use opensyria_node::{NodeConfig, NodeEvent, NodeHandle};
+//!
+//! # async fn run() -> opensyria_node::Result<()> {
+//! let mut node = NodeHandle::open(NodeConfig::testnet("/var/lib/my-service/node")).await?;
+//! node.start_network().await?;
+//!
+//! let mut events = node.subscribe_events();
+//! while let Ok(event) = events.recv().await {
+//!     if let NodeEvent::BlockConnected { height, .. } = event {
+//!         println!("height {}", height);
+//!     }
+//! }
+//! node.shutdown().await
+//! # }
+//! ```
+//!
+//! Everything re-exported here is covered by semver; reach into the
+//! component crates only for what this surface does not offer yet.
+
+pub mod error;
+pub mod governance;
+mod handle;
+
+pub use error::{NodeError, Result};
+pub use governance::{
+    council_checkpoints_at, process_governance_at, rewind_governance_to, spawn_governance_feed,
+};
+pub use handle::{NetworkConfig, NodeConfig, NodeHandle};
+
+pub use libp2p::Multiaddr;
+pub use opensyria_core::crypto::PublicKey;
+pub use opensyria_core::{Block, GovernanceEvent, NodeEvent, PeerEvent, Transaction};
+pub use opensyria_mempool::{DefaultPolicy, MempoolError, PolicyEngine};
+pub use opensyria_network::NetworkType;
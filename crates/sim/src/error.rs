@@ -0,0 +1,24 @@
+//! Errors raised while setting up a simulation
+//! أخطاء إعداد المحاكاة
+
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, SimError>;
+
+#[derive(Error, Debug)]
+pub enum SimError {
+    #[error("Unknown scenario: {0}")]
+    UnknownScenario(String),
+
+    #[error("Invalid scenario: {0}")]
+    InvalidScenario(String),
+
+    #[error("Scenario script error: {0}")]
+    Script(#[from] serde_json::Error),
+
+    #[error("Storage error: {0}")]
+    Storage(#[from] opensyria_storage::StorageError),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,211 @@
+use clap::{Args, Parser, Subcommand};
+use colored::*;
+use opensyria_sim::{Report, Scenario, Simulation, BUILTIN_SCENARIOS};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+#[derive(Parser)]
+#[command(name = "sim")]
+#[command(about = "OpenSyria network simulator | محاكي شبكة الليرة الرقمية", long_about = None)]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// List built-in scenarios | عرض السيناريوهات المدمجة
+    List,
+
+    /// Run one scenario with one seed | تشغيل سيناريو ببذرة واحدة
+    Run {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+
+        /// Seed; the same seed replays the same run
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
+
+    /// Run a scenario over many seeds and list the failing ones | تشغيل عدة بذور
+    Sweep {
+        #[command(flatten)]
+        scenario: ScenarioArgs,
+
+        /// Number of seeds to run
+        #[arg(long, default_value_t = 100)]
+        seeds: u64,
+
+        /// First seed
+        #[arg(long, default_value_t = 0)]
+        start_seed: u64,
+    },
+}
+
+#[derive(Args)]
+struct ScenarioArgs {
+    /// Built-in scenario name
+    #[arg(long, default_value = "partition-heal", conflicts_with = "script")]
+    scenario: String,
+
+    /// JSON scenario script
+    #[arg(long)]
+    script: Option<PathBuf>,
+}
+
+impl ScenarioArgs {
+    fn load(&self) -> opensyria_sim::Result<Scenario> {
+        match &self.script {
+            Some(path) => Scenario::from_file(path),
+            None => Scenario::builtin(&self.scenario),
+        }
+    }
+
+    /// Arguments that select the same scenario again
+    fn reproduce(&self) -> String {
+        match &self.script {
+            Some(path) => format!("--script {}", path.display()),
+            None => format!("--scenario {}", self.scenario),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        Commands::List => {
+            for name in BUILTIN_SCENARIOS {
+                let scenario = Scenario::builtin(name).expect("built-in scenario");
+                println!(
+                    "{:<16} {} nodes, {}s, {} steps",
+                    name.cyan(),
+                    scenario.nodes,
+                    scenario.duration_secs,
+                    scenario.steps.len()
+                );
+            }
+            Ok(true)
+        }
+        Commands::Run { scenario, seed } => run(&scenario, seed),
+        Commands::Sweep {
+            scenario,
+            seeds,
+            start_seed,
+        } => sweep(&scenario, start_seed, seeds),
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(args: &ScenarioArgs, seed: u64) -> opensyria_sim::Result<bool> {
+    let report = Simulation::new(args.load()?, seed)?.run();
+    print_report(&report);
+    if !report.passed() {
+        println!();
+        println!(
+            "{} sim run {} --seed {}",
+            "Reproduce with:".yellow(),
+            args.reproduce(),
+            seed
+        );
+    }
+    Ok(report.passed())
+}
+
+fn sweep(args: &ScenarioArgs, start_seed: u64, seeds: u64) -> opensyria_sim::Result<bool> {
+    let scenario = args.load()?;
+    let mut failures = Vec::new();
+    for seed in start_seed..start_seed.saturating_add(seeds) {
+        let report = Simulation::new(scenario.clone(), seed)?.run();
+        if report.passed() {
+            println!(
+                "{} seed {:<6} height {:<5} deepest reorg {}",
+                "✓".green(),
+                seed,
+                report.height(),
+                report.deepest_reorg()
+            );
+        } else {
+            println!("{} seed {:<6} {}", "✗".red(), seed, report.violations[0]);
+            failures.push(seed);
+        }
+    }
+
+    println!();
+    if failures.is_empty() {
+        println!("{} {} seeds passed", "✓".green().bold(), seeds);
+        return Ok(true);
+    }
+    println!(
+        "{} {} of {} seeds failed",
+        "✗".red().bold(),
+        failures.len(),
+        seeds
+    );
+    for seed in failures {
+        println!("  sim run {} --seed {}", args.reproduce(), seed);
+    }
+    Ok(false)
+}
+
+fn print_report(report: &Report) {
+    println!("{}", "═".repeat(60).cyan());
+    println!(
+        "{}",
+        format!("  Simulation: {} (seed {})  ", report.scenario, report.seed)
+            .cyan()
+            .bold()
+    );
+    println!("{}", "═".repeat(60).cyan());
+    println!();
+    println!(
+        "{}: {}s in {} events",
+        "Virtual time".yellow(),
+        report.elapsed_secs,
+        report.events
+    );
+    println!(
+        "{}: {} mined, height {}",
+        "Blocks".yellow(),
+        report.blocks_mined(),
+        report.height()
+    );
+    println!("{}: {}", "Deepest reorg".yellow(), report.deepest_reorg());
+    println!(
+        "{}: {} sent, {} delivered, {} dropped, {} partitioned",
+        "Messages".yellow(),
+        report.network.sent,
+        report.network.delivered,
+        report.network.dropped,
+        report.network.partitioned
+    );
+    println!();
+    for (node, ((height, tip), stats)) in report.tips.iter().zip(&report.nodes).enumerate() {
+        println!(
+            "  node {:<3} height {:<5} tip {}  mined {:<4} reorgs {:<3} rejected {}",
+            node,
+            height,
+            hex::encode(&tip[..8]).dimmed(),
+            stats.mined,
+            stats.reorgs,
+            stats.rejected
+        );
+    }
+    println!();
+
+    if report.passed() {
+        println!("{}", "✓ All invariants held".green().bold());
+    } else {
+        println!("{}", "✗ Invariants violated".red().bold());
+        for violation in &report.violations {
+            println!("  - {}", violation);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Virtual links between simulated nodes
+//! الروابط الافتراضية بين العقد المحاكاة
+//!
+//! Every node can reach every other one unless a partition separates them.
+//! Each message is lost with the link's drop rate or delivered after the
+//! base latency plus a uniform jitter. A partition also cuts messages
+//! already in flight when they arrive.
+
+use crate::error::{Result, SimError};
+use opensyria_core::{Block, Transaction};
+use rand::rngs::StdRng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Latency and loss applied to every link
+/// زمن الاستجابة ونسبة الفقد لكل رابط
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LinkConditions {
+    /// Base one-way delay in milliseconds
+    pub latency_ms: u64,
+    /// Extra delay drawn uniformly from `0..=jitter_ms`
+    pub jitter_ms: u64,
+    /// Chance in `[0, 1)` that a message is lost
+    pub drop_rate: f64,
+}
+
+impl Default for LinkConditions {
+    fn default() -> Self {
+        Self {
+            latency_ms: 200,
+            jitter_ms: 100,
+            drop_rate: 0.0,
+        }
+    }
+}
+
+impl LinkConditions {
+    pub fn validate(&self) -> Result<()> {
+        if !(0.0..1.0).contains(&self.drop_rate) {
+            return Err(SimError::InvalidScenario(format!(
+                "drop rate {} is outside [0, 1)",
+                self.drop_rate
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Gossip exchanged between nodes
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Newly connected block
+    Block(Block),
+    /// Request for a block by hash
+    GetBlock([u8; 32]),
+    /// Periodic tip announcement
+    Tip { hash: [u8; 32], height: u64 },
+    /// Transfer for the mempool
+    Transaction(Transaction),
+}
+
+/// Message counters for a run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkStats {
+    pub sent: u64,
+    pub delivered: u64,
+    /// Lost to the drop rate
+    pub dropped: u64,
+    /// Cut by a partition, at sending or on arrival
+    pub partitioned: u64,
+}
+
+/// Links and partitions of the simulated network
+pub(crate) struct Network {
+    link: LinkConditions,
+    /// Partition group of each node; `None` when fully connected
+    groups: Option<Vec<usize>>,
+    pub stats: NetworkStats,
+}
+
+impl Network {
+    pub fn new(link: LinkConditions) -> Self {
+        Self {
+            link,
+            groups: None,
+            stats: NetworkStats::default(),
+        }
+    }
+
+    pub fn set_link(&mut self, link: LinkConditions) {
+        self.link = link;
+    }
+
+    /// Split `nodes` nodes into `groups`; unlisted nodes form one more group
+    pub fn partition(&mut self, nodes: usize, groups: &[Vec<usize>]) {
+        let mut assignment = vec![groups.len(); nodes];
+        for (group, members) in groups.iter().enumerate() {
+            for node in members {
+                assignment[*node] = group;
+            }
+        }
+        self.groups = Some(assignment);
+    }
+
+    pub fn heal(&mut self) {
+        self.groups = None;
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.groups.is_some()
+    }
+
+    pub fn connected(&self, a: usize, b: usize) -> bool {
+        self.groups
+            .as_ref()
+            .is_none_or(|groups| groups[a] == groups[b])
+    }
+
+    /// Delay in milliseconds for a message from `from` to `to`, or `None`
+    /// if it is lost
+    pub fn send(&mut self, rng: &mut StdRng, from: usize, to: usize) -> Option<u64> {
+        self.stats.sent += 1;
+        if !self.connected(from, to) {
+            self.stats.partitioned += 1;
+            return None;
+        }
+        if rng.gen::<f64>() < self.link.drop_rate {
+            self.stats.dropped += 1;
+            return None;
+        }
+        Some(self.link.latency_ms + rng.gen_range(0..=self.link.jitter_ms))
+    }
+
+    /// Whether a message still gets through when it arrives
+    pub fn deliver(&mut self, from: usize, to: usize) -> bool {
+        if !self.connected(from, to) {
+            self.stats.partitioned += 1;
+            return false;
+        }
+        self.stats.delivered += 1;
+        true
+    }
+}
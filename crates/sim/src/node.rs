@@ -0,0 +1,530 @@
+//! Simulated node on the node's own storage
+//! عقدة محاكاة على تخزين العقدة نفسه
+//!
+//! Each node keeps its best chain and state in a [`Storage`] of its own,
+//! in a temporary directory, and connects blocks the way the network node
+//! does: every state rule is checked, then the block is appended with the
+//! consensus checks and applied to the state. Blocks off the best chain and
+//! blocks waiting for their parent stay in memory; the node follows the
+//! heaviest branch (blocks all carry the same difficulty, so the longest
+//! one), and an equally long branch does not displace the tip seen first.
+//! A reorganization disconnects the best chain down to the fork with
+//! [`BlockchainStorage::disconnect_to_height`], which also refuses any
+//! deeper than the chain's `max_reorg_depth`.
+//!
+//! [`BlockchainStorage::disconnect_to_height`]: opensyria_storage::BlockchainStorage::disconnect_to_height
+
+use crate::error::Result;
+use opensyria_consensus::ProofOfWork;
+use opensyria_core::constants::MAX_TIMESTAMP_INCREASE_SECS;
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::{Block, ChainParams, CoinbaseSplit, KeyPair, Transaction};
+use opensyria_storage::{Storage, StorageError};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tempfile::TempDir;
+
+/// Pending transfers kept per node
+const MAX_MEMPOOL_SIZE: usize = 5_000;
+
+/// Transfers taken into one mined block
+const MAX_BLOCK_TRANSFERS: usize = 100;
+
+/// Unwrap a storage result; a node cannot go on without its databases
+fn stored<T>(result: std::result::Result<T, StorageError>) -> T {
+    result.unwrap_or_else(|e| panic!("simulated node storage failed: {}", e))
+}
+
+/// Counters for one node
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeStats {
+    /// Blocks this node mined
+    pub mined: u64,
+    /// Tip switches that disconnected at least one block
+    pub reorgs: u64,
+    /// Most blocks disconnected by one reorganization
+    pub deepest_reorg: u64,
+    /// Reorganizations refused for exceeding `max_reorg_depth`
+    pub refused_reorgs: u64,
+    /// Blocks rejected as invalid
+    pub rejected: u64,
+}
+
+/// Block in the tree with its height
+struct Entry {
+    block: Block,
+    height: u64,
+}
+
+/// Blocks that joined the best chain and an ancestor still missing
+#[derive(Debug, Default)]
+pub(crate) struct BlockOutcome {
+    /// Blocks connected to the best chain, oldest first
+    pub connected: Vec<Block>,
+    /// Parent to fetch so a stashed block can connect
+    pub missing: Option<[u8; 32]>,
+}
+
+/// Simulated full node
+/// عقدة كاملة محاكاة
+pub struct SimNode {
+    pub id: usize,
+    key: KeyPair,
+    params: ChainParams,
+    /// Best chain and the state at its tip
+    storage: Storage,
+    /// Every valid or not yet checked block seen, best chain included
+    blocks: HashMap<[u8; 32], Entry>,
+    invalid: HashSet<[u8; 32]>,
+    /// Pending transfers by sender and nonce
+    mempool: BTreeMap<([u8; 32], u64), Transaction>,
+    /// Blocks waiting for their parent, by parent hash
+    orphans: BTreeMap<[u8; 32], Vec<Block>>,
+    /// Whether the node takes part in mining
+    pub mining: bool,
+    pub stats: NodeStats,
+    /// Holds the databases; dropped after `storage` closes them
+    _dir: TempDir,
+}
+
+impl SimNode {
+    /// Node holding only the genesis block (height 1)
+    pub fn new(id: usize, key: KeyPair, params: ChainParams) -> Result<Self> {
+        let dir = tempfile::tempdir()?;
+        let mut storage = Storage::open(dir.path().to_path_buf())?;
+        storage.blockchain.set_chain_params(params.clone());
+
+        let genesis = Block::genesis();
+        storage.blockchain.append_block(&genesis, None)?;
+        storage.state.apply_block(1, &genesis)?;
+
+        let hash = genesis.hash();
+        let mut blocks = HashMap::new();
+        blocks.insert(
+            hash,
+            Entry {
+                block: genesis,
+                height: 1,
+            },
+        );
+        Ok(Self {
+            id,
+            key,
+            params,
+            storage,
+            blocks,
+            invalid: HashSet::new(),
+            mempool: BTreeMap::new(),
+            orphans: BTreeMap::new(),
+            mining: true,
+            stats: NodeStats::default(),
+            _dir: dir,
+        })
+    }
+
+    /// Payout address of this node
+    pub fn address(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    pub fn tip(&self) -> [u8; 32] {
+        stored(self.storage.blockchain.get_chain_tip()).expect("chain holds genesis")
+    }
+
+    pub fn height(&self) -> u64 {
+        stored(self.storage.blockchain.get_chain_height())
+    }
+
+    /// Balance of `address` at the tip
+    pub fn balance(&self, address: &PublicKey) -> u64 {
+        stored(self.storage.state.get_balance(address))
+    }
+
+    /// Sum of all balances at the tip
+    pub fn supply(&self) -> u64 {
+        stored(self.storage.state.compute_total_supply())
+    }
+
+    /// Coins the reward schedule issues up to `height` (genesis pays none)
+    pub fn expected_supply(params: &ChainParams, height: u64) -> u64 {
+        (2..=height).map(|h| params.block_reward(h)).sum()
+    }
+
+    pub fn knows(&self, hash: &[u8; 32]) -> bool {
+        self.blocks.contains_key(hash) || self.invalid.contains(hash)
+    }
+
+    /// Valid block with the given hash, to serve to peers
+    pub fn block(&self, hash: &[u8; 32]) -> Option<&Block> {
+        if self.invalid.contains(hash) {
+            return None;
+        }
+        self.blocks.get(hash).map(|entry| &entry.block)
+    }
+
+    pub fn mempool_size(&self) -> usize {
+        self.mempool.len()
+    }
+
+    /// Blocks waiting for a missing parent
+    pub fn orphan_count(&self) -> usize {
+        self.orphans.values().map(Vec::len).sum()
+    }
+
+    /// Take a block from a peer or from this node's miner
+    /// استقبال كتلة من قرين أو من المعدّن المحلي
+    ///
+    /// Only the proof of work and merkle root are checked on arrival; every
+    /// other rule is checked by storage when the block's branch is about to
+    /// become the best chain.
+    pub(crate) fn receive_block(&mut self, block: Block) -> BlockOutcome {
+        let mut outcome = BlockOutcome::default();
+        let hash = block.hash();
+        if self.knows(&hash) {
+            return outcome;
+        }
+        if !block.header.meets_difficulty() || !block.verify_merkle_root() {
+            self.invalid.insert(hash);
+            self.stats.rejected += 1;
+            return outcome;
+        }
+        let parent = block.header.previous_hash;
+        if !self.blocks.contains_key(&parent) {
+            self.orphans.entry(parent).or_default().push(block);
+            outcome.missing = Some(parent);
+            return outcome;
+        }
+
+        let mut pending = vec![block];
+        while let Some(block) = pending.pop() {
+            let hash = block.hash();
+            if self.knows(&hash) {
+                continue;
+            }
+            let parent = block.header.previous_hash;
+            if self.invalid.contains(&parent) {
+                self.invalid.insert(hash);
+                self.stats.rejected += 1;
+                continue;
+            }
+
+            let height = self.blocks[&parent].height + 1;
+            self.blocks.insert(hash, Entry { block, height });
+            if height > self.height() {
+                self.try_switch(hash, &mut outcome.connected);
+            }
+            if let Some(children) = self.orphans.remove(&hash) {
+                pending.extend(children);
+            }
+        }
+        outcome
+    }
+
+    fn on_best_chain(&self, hash: &[u8; 32]) -> bool {
+        let height = self.blocks[hash].height;
+        stored(self.storage.blockchain.get_block_hash_by_height(height)) == Some(*hash)
+    }
+
+    /// Connect `block` on the tip as the network node does: state rules
+    /// first, then the consensus checks while appending, then the state
+    fn connect(&self, block: &Block) -> std::result::Result<(), StorageError> {
+        let height = self.storage.blockchain.get_chain_height()? + 1;
+        let state = &self.storage.state;
+        state
+            .check_block(height, block)
+            .map_err(|violation| violation.error)?;
+        self.storage.blockchain.append_block(block, Some(state))?;
+        state.apply_block(height, block)
+    }
+
+    /// Move the tip to `new_tip` if its branch checks out, or to the
+    /// longest valid part of it if that still beats the current tip
+    fn try_switch(&mut self, new_tip: [u8; 32], connected: &mut Vec<Block>) {
+        let mut branch = Vec::new();
+        let mut cursor = new_tip;
+        while !self.on_best_chain(&cursor) {
+            branch.push(cursor);
+            cursor = self.blocks[&cursor].block.header.previous_hash;
+        }
+        branch.reverse();
+        let fork_height = self.blocks[&cursor].height;
+        let height = self.height();
+        if height - fork_height > self.params.max_reorg_depth {
+            self.stats.refused_reorgs += 1;
+            return;
+        }
+
+        let state = Some(&self.storage.state);
+        let disconnected = stored(
+            self.storage
+                .blockchain
+                .disconnect_to_height(fork_height, state),
+        );
+        let mut valid = 0;
+        for hash in &branch {
+            if self.invalid.contains(hash) || self.connect(&self.blocks[hash].block).is_err() {
+                break;
+            }
+            valid += 1;
+        }
+        if valid < branch.len() {
+            // The failing block and everything built on it in this branch;
+            // they stay in the tree so their descendants are refused too
+            if self.invalid.insert(branch[valid]) {
+                self.stats.rejected += 1;
+            }
+            self.invalid.extend(&branch[valid + 1..]);
+            branch.truncate(valid);
+            if fork_height + valid as u64 <= height {
+                // Back onto the chain the branch failed to beat
+                stored(
+                    self.storage
+                        .blockchain
+                        .disconnect_to_height(fork_height, state),
+                );
+                for block in &disconnected {
+                    stored(self.connect(block));
+                }
+                return;
+            }
+        }
+
+        if !disconnected.is_empty() {
+            self.stats.reorgs += 1;
+            self.stats.deepest_reorg = self.stats.deepest_reorg.max(disconnected.len() as u64);
+        }
+
+        // Transfers from the abandoned branch go back to the mempool
+        for block in &disconnected {
+            for tx in block.transactions.iter().filter(|tx| !tx.is_coinbase()) {
+                self.mempool
+                    .entry((tx.from.0, tx.nonce))
+                    .or_insert_with(|| tx.clone());
+            }
+        }
+        let state = &self.storage.state;
+        self.mempool
+            .retain(|(from, nonce), _| *nonce >= stored(state.get_nonce(&PublicKey(*from))));
+
+        connected.extend(branch.iter().map(|hash| self.blocks[hash].block.clone()));
+    }
+
+    /// Take a gossiped transfer into the mempool; false if it is not new or
+    /// not acceptable
+    pub(crate) fn receive_transaction(&mut self, tx: Transaction) -> bool {
+        let key = (tx.from.0, tx.nonce);
+        if tx.is_coinbase()
+            || tx.chain_id != self.params.chain_id
+            || self.mempool.len() >= MAX_MEMPOOL_SIZE
+            || self.mempool.contains_key(&key)
+            || tx.nonce < stored(self.storage.state.get_nonce(&tx.from))
+            || tx.validate_fee().is_err()
+            || tx.verify().is_err()
+        {
+            return false;
+        }
+        self.mempool.insert(key, tx);
+        true
+    }
+
+    /// Transfer of `amount` to `to` on top of this node's pending ones,
+    /// signed by this node, if the node can afford it
+    ///
+    /// With a `fee_payer` the fee is left to that account, which still has
+    /// to sign with [`sponsor`](Self::sponsor). The transfer is not added to
+    /// the mempool; hand it to
+    /// [`receive_transaction`](Self::receive_transaction) once signed.
+    pub(crate) fn create_transfer(
+        &self,
+        to: PublicKey,
+        amount: u64,
+        fee_payer: Option<PublicKey>,
+    ) -> Option<Transaction> {
+        let from = self.address();
+        let fee = self.params.min_transaction_fee;
+        let committed: u64 = self.mempool.values().map(|tx| tx.debit(&from)).sum();
+        let available = self.balance(&from).saturating_sub(committed);
+        let debit = if fee_payer.is_some() {
+            amount
+        } else {
+            amount + fee
+        };
+        if available < debit {
+            return None;
+        }
+
+        let nonce = self
+            .mempool
+            .range((from.0, 0)..=(from.0, u64::MAX))
+            .next_back()
+            .map(|(_, tx)| tx.nonce + 1)
+            .unwrap_or_else(|| stored(self.storage.state.get_nonce(&from)));
+        let mut tx =
+            Transaction::new_with_chain_id(self.params.chain_id, from, to, amount, fee, nonce);
+        if let Some(payer) = fee_payer {
+            tx = tx.with_fee_payer(payer);
+        }
+        let signature = self.key.sign(&tx.signing_hash());
+        Some(tx.with_signature(signature))
+    }
+
+    /// Sign `tx` as its fee payer
+    /// توقيع معاملة بصفة دافع الرسوم
+    pub(crate) fn sponsor(&self, tx: Transaction) -> Transaction {
+        let signature = self.key.sign(&tx.sponsor_signing_hash());
+        tx.with_sponsor_signature(signature)
+    }
+
+    /// Mine a block on the tip with virtual time `timestamp`; the caller
+    /// connects it with [`receive_block`](Self::receive_block)
+    /// تعدين كتلة فوق القمة
+    ///
+    /// Pending transfers are taken in order while the state still accepts
+    /// them; the rest wait for a later block.
+    pub(crate) fn mine_block(&mut self, timestamp: u64) -> Block {
+        let height = self.height() + 1;
+        let state = &self.storage.state;
+        let mut transfers = Vec::new();
+        for tx in self.mempool.values() {
+            if transfers.len() == MAX_BLOCK_TRANSFERS {
+                break;
+            }
+            transfers.push(tx.clone());
+            if state.check_block_at_height(height, &transfers).is_err() {
+                transfers.pop();
+            }
+        }
+
+        let parent_timestamp = self.blocks[&self.tip()].block.header.timestamp;
+        let timestamp = timestamp.clamp(
+            parent_timestamp + 1,
+            parent_timestamp + MAX_TIMESTAMP_INCREASE_SECS,
+        );
+        let fees: u64 = transfers.iter().map(|tx| tx.fee).sum();
+        let reward = self.params.block_reward(height);
+        let mut transactions = CoinbaseSplit::single(self.address())
+            .coinbase_transactions_with_reward(self.params.chain_id, height, reward, fees)
+            .expect("a single payout never fails");
+        // The coinbase header carries the wall clock after the height; the
+        // virtual time keeps runs reproducible
+        for coinbase in &mut transactions {
+            if let Some(data) = coinbase.data.as_mut() {
+                data[8..16].copy_from_slice(&timestamp.to_le_bytes());
+            }
+        }
+        transactions.extend(transfers);
+
+        let difficulty = stored(self.storage.blockchain.next_difficulty());
+        let mut block = Block::new(self.tip(), transactions, difficulty);
+        block.header.timestamp = timestamp;
+        let (block, _) = ProofOfWork::new(difficulty).mine(block);
+
+        self.stats.mined += 1;
+        block
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: usize) -> SimNode {
+        let key = KeyPair::from_bytes(&[id as u8 + 1; 32]).unwrap();
+        SimNode::new(id, key, ChainParams::regtest()).unwrap()
+    }
+
+    /// Mine on `node`'s tip `secs` after genesis and connect the block
+    fn mine(node: &mut SimNode, secs: u64) -> Block {
+        let block = node.mine_block(opensyria_core::GENESIS_TIMESTAMP + secs);
+        assert_eq!(node.receive_block(block.clone()).connected.len(), 1);
+        block
+    }
+
+    #[test]
+    fn test_mined_blocks_connect_on_peers() {
+        let mut miner = node(0);
+        let mut peer = node(1);
+        let first = mine(&mut miner, 120);
+        let second = mine(&mut miner, 240);
+
+        // Out of order: the child waits for its parent
+        let outcome = peer.receive_block(second.clone());
+        assert_eq!(outcome.missing, Some(first.hash()));
+        assert_eq!(peer.orphan_count(), 1);
+        let outcome = peer.receive_block(first);
+        assert_eq!(outcome.connected.len(), 2);
+        assert_eq!(peer.tip(), second.hash());
+        assert_eq!(peer.orphan_count(), 0);
+        assert_eq!(
+            peer.supply(),
+            SimNode::expected_supply(&ChainParams::regtest(), 3)
+        );
+    }
+
+    #[test]
+    fn test_inflated_coinbase_rejected() {
+        let mut miner = node(0);
+        let mut peer = node(1);
+        let honest = mine(&mut miner, 120);
+
+        let mut transactions = honest.transactions.clone();
+        transactions[0].amount += 1;
+        let mut inflated = Block::new(honest.header.previous_hash, transactions, 8);
+        inflated.header.timestamp = honest.header.timestamp;
+        let (inflated, _) = ProofOfWork::new(8).mine(inflated);
+
+        let outcome = peer.receive_block(inflated);
+        assert!(outcome.connected.is_empty());
+        assert_eq!(peer.height(), 1);
+        assert_eq!(peer.stats.rejected, 1);
+        assert_eq!(peer.receive_block(honest).connected.len(), 1);
+    }
+
+    #[test]
+    fn test_transfers_survive_reorg() {
+        let mut alice = node(0);
+        let mut bob = node(1);
+        let funding = mine(&mut alice, 120);
+        bob.receive_block(funding);
+
+        let tx = alice.create_transfer(bob.address(), 1_000, None).unwrap();
+        assert!(alice.receive_transaction(tx));
+        let confirmed = mine(&mut alice, 240);
+        assert_eq!(alice.mempool_size(), 0);
+
+        // Bob's longer branch never saw the transfer
+        let branch: Vec<Block> = (0..2).map(|i| mine(&mut bob, 300 + i)).collect();
+        for block in branch {
+            alice.receive_block(block);
+        }
+        assert_eq!(alice.tip(), bob.tip());
+        assert_eq!(alice.stats.reorgs, 1);
+        assert_eq!(alice.stats.deepest_reorg, 1);
+        assert!(!alice.on_best_chain(&confirmed.hash()));
+        assert_eq!(alice.mempool_size(), 1);
+    }
+
+    #[test]
+    fn test_sponsor_pays_fee() {
+        let params = ChainParams::regtest();
+        let mut alice = node(0);
+        let mut bob = node(1);
+        let carol = node(2);
+        bob.receive_block(mine(&mut alice, 120));
+        alice.receive_block(mine(&mut bob, 240));
+
+        // Bob pays the fee for Alice's transfer to Carol
+        let tx = alice
+            .create_transfer(carol.address(), 1_000, Some(bob.address()))
+            .unwrap();
+        let unsigned = tx.clone();
+        assert!(!alice.receive_transaction(unsigned));
+        assert!(alice.receive_transaction(bob.sponsor(tx)));
+        mine(&mut alice, 360);
+
+        let fee = params.min_transaction_fee;
+        let alice_balance = params.block_reward(2) + params.block_reward(4) + fee - 1_000;
+        assert_eq!(alice.balance(&alice.address()), alice_balance);
+        assert_eq!(alice.balance(&bob.address()), params.block_reward(3) - fee);
+        assert_eq!(alice.balance(&carol.address()), 1_000);
+        assert_eq!(alice.supply(), SimNode::expected_supply(&params, 4));
+    }
+}
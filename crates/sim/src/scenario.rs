@@ -0,0 +1,328 @@
+//! Scripted simulation scenarios
+//! سيناريوهات المحاكاة المبرمجة
+//!
+//! A scenario fixes the node count, run length, hash power split and a list
+//! of timed network actions. Scenarios are built in or loaded from JSON:
+//!
+//! ```json
+//! {
+//!   "name": "split-brain",
+//!   "nodes": 6,
+//!   "duration_secs": 3600,
+//!   "settle_secs": 900,
+//!   "link": { "latency_ms": 150, "jitter_ms": 50, "drop_rate": 0.05 },
+//!   "steps": [
+//!     { "at_secs": 300, "action": "partition", "groups": [[0, 1, 2], [3, 4, 5]] },
+//!     { "at_secs": 1500, "action": "heal" }
+//!   ]
+//! }
+//! ```
+
+use crate::error::{Result, SimError};
+use crate::network::LinkConditions;
+use opensyria_core::ChainParams;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Names accepted by [`Scenario::builtin`]
+pub const BUILTIN_SCENARIOS: &[&str] = &["partition-heal", "lossy", "minority-reorg", "flapping"];
+
+/// Network change applied at a scripted time
+/// تغيير في الشبكة يطبق في وقت محدد
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Split the nodes; nodes in no group share one extra group
+    Partition { groups: Vec<Vec<usize>> },
+    /// Reconnect every node
+    Heal,
+    /// Change latency, jitter and loss on every link
+    SetLink(LinkConditions),
+    /// Stop the given nodes mining
+    StopMining { nodes: Vec<usize> },
+    /// Let the given nodes mine again
+    StartMining { nodes: Vec<usize> },
+}
+
+/// Action and the virtual second it happens at
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub at_secs: u64,
+    #[serde(flatten)]
+    pub action: Action,
+}
+
+/// Simulation script
+/// سيناريو المحاكاة
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    /// Number of nodes, numbered from 0
+    pub nodes: usize,
+    /// Virtual run length in seconds
+    pub duration_secs: u64,
+    /// Mean seconds between blocks across the whole network
+    #[serde(default = "default_block_interval")]
+    pub block_interval_secs: u64,
+    /// Mean seconds between transfers started by each node (0 disables them)
+    #[serde(default = "default_spend_interval")]
+    pub spend_interval_secs: u64,
+    /// Relative hash power per node; empty means equal shares
+    #[serde(default)]
+    pub hash_power: Vec<u32>,
+    /// Link conditions at the start of the run
+    #[serde(default)]
+    pub link: LinkConditions,
+    /// Timed actions
+    #[serde(default)]
+    pub steps: Vec<Step>,
+    /// Closing window: miners stop, node 0 mines one block a quarter and
+    /// one half way through, and the rest lets them spread, so a healed
+    /// network has a single longest tip to agree on
+    #[serde(default)]
+    pub settle_secs: u64,
+}
+
+fn default_block_interval() -> u64 {
+    ChainParams::regtest().target_block_time_secs
+}
+
+fn default_spend_interval() -> u64 {
+    300
+}
+
+impl Scenario {
+    /// Built-in scenario by name, see [`BUILTIN_SCENARIOS`]
+    /// الحصول على سيناريو مدمج بالاسم
+    pub fn builtin(name: &str) -> Result<Self> {
+        let scenario = match name {
+            // Two halves mine apart for 20 minutes, then rejoin
+            "partition-heal" => Self {
+                link: LinkConditions {
+                    latency_ms: 200,
+                    jitter_ms: 100,
+                    drop_rate: 0.01,
+                },
+                steps: vec![
+                    Step {
+                        at_secs: 600,
+                        action: Action::Partition {
+                            groups: vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7]],
+                        },
+                    },
+                    Step {
+                        at_secs: 1800,
+                        action: Action::Heal,
+                    },
+                ],
+                ..Self::plain(name, 8, 3600)
+            },
+            // Slow links that lose almost a third of all messages
+            "lossy" => Self {
+                link: LinkConditions {
+                    latency_ms: 800,
+                    jitter_ms: 600,
+                    drop_rate: 0.3,
+                },
+                ..Self::plain(name, 8, 3600)
+            },
+            // Two weaker miners are cut off for half an hour and then have to
+            // give up their branch for the majority's longer one
+            "minority-reorg" => Self {
+                hash_power: vec![2, 2, 3, 3, 3, 3],
+                steps: vec![
+                    Step {
+                        at_secs: 300,
+                        action: Action::Partition {
+                            groups: vec![vec![0, 1]],
+                        },
+                    },
+                    Step {
+                        at_secs: 2100,
+                        action: Action::Heal,
+                    },
+                ],
+                ..Self::plain(name, 6, 3600)
+            },
+            // Splits that keep changing shape, with a lossy stretch
+            "flapping" => Self {
+                steps: vec![
+                    Step {
+                        at_secs: 600,
+                        action: Action::Partition {
+                            groups: vec![(0..5).collect(), (5..10).collect()],
+                        },
+                    },
+                    Step {
+                        at_secs: 900,
+                        action: Action::Partition {
+                            groups: vec![
+                                (0..10).step_by(2).collect(),
+                                (1..10).step_by(2).collect(),
+                            ],
+                        },
+                    },
+                    Step {
+                        at_secs: 1200,
+                        action: Action::Heal,
+                    },
+                    Step {
+                        at_secs: 1500,
+                        action: Action::Partition {
+                            groups: vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7, 8]],
+                        },
+                    },
+                    Step {
+                        at_secs: 2400,
+                        action: Action::Heal,
+                    },
+                    Step {
+                        at_secs: 2700,
+                        action: Action::SetLink(LinkConditions {
+                            latency_ms: 1500,
+                            jitter_ms: 1000,
+                            drop_rate: 0.2,
+                        }),
+                    },
+                    Step {
+                        at_secs: 3600,
+                        action: Action::SetLink(LinkConditions::default()),
+                    },
+                ],
+                ..Self::plain(name, 10, 5400)
+            },
+            _ => return Err(SimError::UnknownScenario(name.to_string())),
+        };
+        Ok(scenario)
+    }
+
+    /// Fully connected network with default links and a 15 minute settle
+    fn plain(name: &str, nodes: usize, duration_secs: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            nodes,
+            duration_secs,
+            block_interval_secs: default_block_interval(),
+            spend_interval_secs: default_spend_interval(),
+            hash_power: Vec::new(),
+            link: LinkConditions::default(),
+            steps: Vec::new(),
+            settle_secs: 900,
+        }
+    }
+
+    /// Parse a scenario script
+    pub fn from_json(json: &str) -> Result<Self> {
+        let scenario: Self = serde_json::from_str(json)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Load a scenario script from a file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Check node indices, shares and times are usable
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |reason: String| Err(SimError::InvalidScenario(reason));
+        if self.nodes < 2 {
+            return invalid("at least 2 nodes are needed".to_string());
+        }
+        if self.block_interval_secs == 0 {
+            return invalid("block interval must be positive".to_string());
+        }
+        if self.settle_secs > self.duration_secs {
+            return invalid("settle window is longer than the run".to_string());
+        }
+        if !self.hash_power.is_empty() {
+            if self.hash_power.len() != self.nodes {
+                return invalid(format!(
+                    "{} hash power shares for {} nodes",
+                    self.hash_power.len(),
+                    self.nodes
+                ));
+            }
+            if self.hash_power.iter().all(|share| *share == 0) {
+                return invalid("no node has hash power".to_string());
+            }
+        }
+        self.link.validate()?;
+
+        for step in &self.steps {
+            if step.at_secs > self.duration_secs {
+                return invalid(format!("step at {}s is after the end", step.at_secs));
+            }
+            let nodes: Vec<usize> = match &step.action {
+                Action::Partition { groups } => groups.iter().flatten().copied().collect(),
+                Action::StopMining { nodes } | Action::StartMining { nodes } => nodes.clone(),
+                Action::SetLink(link) => {
+                    link.validate()?;
+                    Vec::new()
+                }
+                Action::Heal => Vec::new(),
+            };
+            if let Some(node) = nodes.iter().find(|node| **node >= self.nodes) {
+                return invalid(format!(
+                    "step at {}s names unknown node {}",
+                    step.at_secs, node
+                ));
+            }
+            if let Action::Partition { groups } = &step.action {
+                let mut seen = vec![false; self.nodes];
+                for node in groups.iter().flatten() {
+                    if std::mem::replace(&mut seen[*node], true) {
+                        return invalid(format!(
+                            "step at {}s puts node {} in two groups",
+                            step.at_secs, node
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Hash power share of each node
+    pub fn shares(&self) -> Vec<u32> {
+        if self.hash_power.is_empty() {
+            vec![1; self.nodes]
+        } else {
+            self.hash_power.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_scenarios_are_valid() {
+        for name in BUILTIN_SCENARIOS {
+            Scenario::builtin(name).unwrap().validate().unwrap();
+        }
+        assert!(matches!(
+            Scenario::builtin("nope"),
+            Err(SimError::UnknownScenario(_))
+        ));
+    }
+
+    #[test]
+    fn test_script_round_trip_and_checks() {
+        let scenario = Scenario::builtin("flapping").unwrap();
+        let json = serde_json::to_string(&scenario).unwrap();
+        assert_eq!(Scenario::from_json(&json).unwrap(), scenario);
+
+        let script = r#"{
+            "name": "bad",
+            "nodes": 3,
+            "duration_secs": 600,
+            "steps": [{ "at_secs": 60, "action": "partition", "groups": [[0, 1], [1, 2]] }]
+        }"#;
+        assert!(matches!(
+            Scenario::from_json(script),
+            Err(SimError::InvalidScenario(_))
+        ));
+    }
+}
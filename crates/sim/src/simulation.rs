@@ -0,0 +1,568 @@
+//! Discrete-event simulation loop and invariant checks
+//! حلقة المحاكاة بالأحداث المنفصلة وفحص الثوابت
+//!
+//! Events sit in a queue ordered by virtual time and insertion order; the
+//! clock jumps to each event as it is taken, so an hour of network time
+//! runs in well under a second. Block discovery is a Poisson process per
+//! node, weighted by hash power, and only the winning nonce search is
+//! actually performed.
+
+use crate::error::Result;
+use crate::network::{Message, Network, NetworkStats};
+use crate::node::{BlockOutcome, NodeStats, SimNode};
+use crate::scenario::{Action, Scenario};
+use opensyria_core::constants::GENESIS_TIMESTAMP;
+use opensyria_core::{ChainParams, KeyPair};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// Milliseconds between tip announcements from each node
+const ANNOUNCE_INTERVAL_MS: u64 = 5_000;
+
+/// Share of transfers whose fee the recipient pays
+const SPONSORED_SHARE: f64 = 0.25;
+
+/// Events processed before a run is treated as livelocked
+const MAX_EVENTS: u64 = 20_000_000;
+
+/// Mean block intervals without any tip change, while someone mines,
+/// before the chain counts as stalled
+const STALL_INTERVALS: u64 = 20;
+
+/// Invariant broken during a run
+/// ثابت تم خرقه أثناء التشغيل
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// A node's balances add up to something other than the reward schedule
+    SupplyMismatch {
+        node: usize,
+        height: u64,
+        expected: u64,
+        actual: u64,
+    },
+    /// Nodes still disagree on the tip after the network healed and settled
+    Diverged { tips: Vec<(u64, [u8; 32])> },
+    /// No node's tip moved for `idle_secs` although miners were running
+    Stalled { at_secs: u64, idle_secs: u64 },
+    /// The event budget ran out before the virtual clock reached the end
+    EventBudgetExceeded { at_secs: u64 },
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Violation::SupplyMismatch {
+                node,
+                height,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} holds {} at height {} where the schedule issued {}",
+                node, actual, height, expected
+            ),
+            Violation::Diverged { tips } => {
+                write!(f, "nodes did not converge:")?;
+                for (node, (height, hash)) in tips.iter().enumerate() {
+                    write!(f, " {}@{}:{}", node, height, hex::encode(&hash[..4]))?;
+                }
+                Ok(())
+            }
+            Violation::Stalled { at_secs, idle_secs } => write!(
+                f,
+                "chain stalled for {}s at {}s while miners were running",
+                idle_secs, at_secs
+            ),
+            Violation::EventBudgetExceeded { at_secs } => {
+                write!(f, "event budget exhausted at {}s", at_secs)
+            }
+        }
+    }
+}
+
+/// Outcome of one run
+/// نتيجة تشغيل واحد
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub scenario: String,
+    pub seed: u64,
+    /// Virtual seconds simulated
+    pub elapsed_secs: u64,
+    /// Events processed
+    pub events: u64,
+    /// Tip height and hash of each node at the end
+    pub tips: Vec<(u64, [u8; 32])>,
+    pub nodes: Vec<NodeStats>,
+    pub network: NetworkStats,
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Blocks mined across all nodes
+    pub fn blocks_mined(&self) -> u64 {
+        self.nodes.iter().map(|stats| stats.mined).sum()
+    }
+
+    /// Highest tip reached
+    pub fn height(&self) -> u64 {
+        self.tips
+            .iter()
+            .map(|(height, _)| *height)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Most blocks any node disconnected in one reorganization
+    pub fn deepest_reorg(&self) -> u64 {
+        self.nodes
+            .iter()
+            .map(|stats| stats.deepest_reorg)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+enum Event {
+    Mine(usize),
+    Spend(usize),
+    Announce(usize),
+    Deliver {
+        from: usize,
+        to: usize,
+        message: Box<Message>,
+    },
+    Step(usize),
+    /// Start of the settle window: every miner stops
+    Settle,
+    /// Node 0 mines one closing block
+    Close,
+}
+
+struct Scheduled {
+    at_ms: u64,
+    seq: u64,
+    event: Event,
+}
+
+// Reversed so the max-heap pops the earliest event, ties in insertion order
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at_ms, other.seq).cmp(&(self.at_ms, self.seq))
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at_ms, self.seq) == (other.at_ms, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+/// Simulated network of nodes driven by one seed
+/// شبكة عقد محاكاة تقودها بذرة واحدة
+pub struct Simulation {
+    scenario: Scenario,
+    seed: u64,
+    params: ChainParams,
+    rng: StdRng,
+    now_ms: u64,
+    seq: u64,
+    queue: BinaryHeap<Scheduled>,
+    nodes: Vec<SimNode>,
+    network: Network,
+    shares: Vec<u32>,
+    events: u64,
+    /// Last time any node's tip moved
+    last_progress_ms: u64,
+    violations: Vec<Violation>,
+}
+
+impl Simulation {
+    /// Set up `scenario`; every random choice of the run derives from `seed`
+    pub fn new(scenario: Scenario, seed: u64) -> Result<Self> {
+        scenario.validate()?;
+        let params = ChainParams::regtest();
+        let mut rng = StdRng::seed_from_u64(seed);
+        let nodes = (0..scenario.nodes)
+            .map(|id| {
+                let key = KeyPair::from_bytes(&rng.gen()).expect("any 32 bytes are a key");
+                SimNode::new(id, key, params.clone())
+            })
+            .collect::<Result<_>>()?;
+
+        let mut sim = Self {
+            network: Network::new(scenario.link),
+            shares: scenario.shares(),
+            scenario,
+            seed,
+            params,
+            rng,
+            now_ms: 0,
+            seq: 0,
+            queue: BinaryHeap::new(),
+            nodes,
+            events: 0,
+            last_progress_ms: 0,
+            violations: Vec::new(),
+        };
+
+        let count = sim.nodes.len() as u64;
+        for node in 0..sim.nodes.len() {
+            sim.schedule_mining(node);
+            sim.schedule_spend(node);
+            sim.schedule(
+                ANNOUNCE_INTERVAL_MS * node as u64 / count,
+                Event::Announce(node),
+            );
+        }
+        for (index, step) in sim.scenario.steps.iter().enumerate() {
+            let at_ms = step.at_secs * 1000;
+            sim.seq += 1;
+            sim.queue.push(Scheduled {
+                at_ms,
+                seq: sim.seq,
+                event: Event::Step(index),
+            });
+        }
+        if sim.scenario.settle_secs > 0 {
+            let end_ms = sim.scenario.duration_secs * 1000;
+            let settle_ms = sim.scenario.settle_secs * 1000;
+            sim.schedule(end_ms - settle_ms, Event::Settle);
+            sim.schedule(end_ms - settle_ms * 3 / 4, Event::Close);
+            sim.schedule(end_ms - settle_ms / 2, Event::Close);
+        }
+        Ok(sim)
+    }
+
+    /// Run to the end of the scenario and check the invariants
+    /// تشغيل السيناريو حتى نهايته وفحص الثوابت
+    pub fn run(mut self) -> Report {
+        let end_ms = self.scenario.duration_secs * 1000;
+        while self.queue.peek().is_some_and(|next| next.at_ms <= end_ms) {
+            let next = self.queue.pop().expect("peeked");
+            self.now_ms = next.at_ms;
+            self.events += 1;
+            if self.events > MAX_EVENTS {
+                self.violations.push(Violation::EventBudgetExceeded {
+                    at_secs: self.now_ms / 1000,
+                });
+                break;
+            }
+            self.handle(next.event);
+            self.check_progress();
+        }
+
+        // Only a healed, settled network is expected to agree
+        let tips: Vec<(u64, [u8; 32])> = self
+            .nodes
+            .iter()
+            .map(|node| (node.height(), node.tip()))
+            .collect();
+        if self.scenario.settle_secs > 0
+            && !self.network.is_partitioned()
+            && tips.iter().any(|tip| *tip != tips[0])
+        {
+            self.violations
+                .push(Violation::Diverged { tips: tips.clone() });
+        }
+
+        Report {
+            scenario: self.scenario.name,
+            seed: self.seed,
+            elapsed_secs: self.now_ms / 1000,
+            events: self.events,
+            tips,
+            nodes: self.nodes.iter().map(|node| node.stats.clone()).collect(),
+            network: self.network.stats,
+            violations: self.violations,
+        }
+    }
+
+    /// Queue `event` `delay_ms` from now
+    fn schedule(&mut self, delay_ms: u64, event: Event) {
+        self.seq += 1;
+        self.queue.push(Scheduled {
+            at_ms: self.now_ms + delay_ms,
+            seq: self.seq,
+            event,
+        });
+    }
+
+    /// Exponentially distributed delay with the given mean
+    fn exponential_ms(&mut self, mean_ms: f64) -> u64 {
+        let uniform: f64 = self.rng.gen();
+        ((-(1.0 - uniform).ln() * mean_ms) as u64).max(1)
+    }
+
+    fn schedule_mining(&mut self, node: usize) {
+        let share = self.shares[node];
+        if share == 0 {
+            return;
+        }
+        let total: u64 = self.shares.iter().map(|share| *share as u64).sum();
+        let mean_ms =
+            (self.scenario.block_interval_secs * 1000) as f64 * total as f64 / share as f64;
+        let delay = self.exponential_ms(mean_ms);
+        self.schedule(delay, Event::Mine(node));
+    }
+
+    fn schedule_spend(&mut self, node: usize) {
+        if self.scenario.spend_interval_secs == 0 {
+            return;
+        }
+        let delay = self.exponential_ms((self.scenario.spend_interval_secs * 1000) as f64);
+        self.schedule(delay, Event::Spend(node));
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Mine(node) => {
+                self.schedule_mining(node);
+                if self.nodes[node].mining {
+                    self.mine(node);
+                }
+            }
+            Event::Spend(node) => {
+                self.schedule_spend(node);
+                let count = self.nodes.len();
+                let to = (node + self.rng.gen_range(1..count)) % count;
+                let amount = self
+                    .rng
+                    .gen_range(1..=self.params.initial_block_reward / 10);
+                let recipient = self.nodes[to].address();
+                let sponsored = self.rng.gen_bool(SPONSORED_SHARE);
+                let fee_payer = sponsored.then_some(recipient);
+                let Some(tx) = self.nodes[node].create_transfer(recipient, amount, fee_payer)
+                else {
+                    return;
+                };
+                let tx = if sponsored {
+                    self.nodes[to].sponsor(tx)
+                } else {
+                    tx
+                };
+                if self.nodes[node].receive_transaction(tx.clone()) {
+                    self.broadcast(node, None, Message::Transaction(tx));
+                }
+            }
+            Event::Announce(node) => {
+                self.schedule(ANNOUNCE_INTERVAL_MS, Event::Announce(node));
+                let tip = Message::Tip {
+                    hash: self.nodes[node].tip(),
+                    height: self.nodes[node].height(),
+                };
+                self.broadcast(node, None, tip);
+            }
+            Event::Deliver { from, to, message } => {
+                if self.network.deliver(from, to) {
+                    self.receive(to, from, *message);
+                }
+            }
+            Event::Step(index) => {
+                let action = self.scenario.steps[index].action.clone();
+                self.apply(action);
+            }
+            Event::Settle => {
+                for node in &mut self.nodes {
+                    node.mining = false;
+                }
+            }
+            Event::Close => self.mine(0),
+        }
+    }
+
+    fn mine(&mut self, node: usize) {
+        let timestamp = GENESIS_TIMESTAMP + self.now_ms / 1000;
+        let block = self.nodes[node].mine_block(timestamp);
+        let outcome = self.nodes[node].receive_block(block);
+        self.after_block(node, None, outcome);
+    }
+
+    fn apply(&mut self, action: Action) {
+        match action {
+            Action::Partition { groups } => self.network.partition(self.nodes.len(), &groups),
+            Action::Heal => self.network.heal(),
+            Action::SetLink(link) => self.network.set_link(link),
+            Action::StopMining { nodes } => {
+                for node in nodes {
+                    self.nodes[node].mining = false;
+                }
+            }
+            Action::StartMining { nodes } => {
+                for node in nodes {
+                    self.nodes[node].mining = true;
+                }
+                self.last_progress_ms = self.now_ms;
+            }
+        }
+    }
+
+    /// Handle a message `to` got from `from`
+    fn receive(&mut self, to: usize, from: usize, message: Message) {
+        match message {
+            Message::Block(block) => {
+                let outcome = self.nodes[to].receive_block(block);
+                self.after_block(to, Some(from), outcome);
+            }
+            Message::GetBlock(hash) => {
+                if let Some(block) = self.nodes[to].block(&hash).cloned() {
+                    self.send(to, from, Message::Block(block));
+                }
+            }
+            Message::Tip { hash, height } => {
+                let node = &self.nodes[to];
+                if height > node.height() && !node.knows(&hash) {
+                    self.send(to, from, Message::GetBlock(hash));
+                }
+            }
+            Message::Transaction(tx) => {
+                if self.nodes[to].receive_transaction(tx.clone()) {
+                    self.broadcast(to, Some(from), Message::Transaction(tx));
+                }
+            }
+        }
+    }
+
+    /// Fetch what is missing, relay what connected and check the supply
+    fn after_block(&mut self, node: usize, from: Option<usize>, outcome: BlockOutcome) {
+        if let (Some(missing), Some(peer)) = (outcome.missing, from) {
+            self.send(node, peer, Message::GetBlock(missing));
+        }
+        if outcome.connected.is_empty() {
+            return;
+        }
+        self.last_progress_ms = self.now_ms;
+
+        let height = self.nodes[node].height();
+        let expected = SimNode::expected_supply(&self.params, height);
+        let actual = self.nodes[node].supply();
+        let reported = self.violations.iter().any(|violation| {
+            matches!(violation, Violation::SupplyMismatch { node: n, .. } if *n == node)
+        });
+        if actual != expected && !reported {
+            self.violations.push(Violation::SupplyMismatch {
+                node,
+                height,
+                expected,
+                actual,
+            });
+        }
+
+        for block in outcome.connected {
+            self.broadcast(node, from, Message::Block(block));
+        }
+    }
+
+    fn check_progress(&mut self) {
+        let limit_ms = STALL_INTERVALS * self.scenario.block_interval_secs * 1000;
+        let idle_ms = self.now_ms - self.last_progress_ms;
+        let mining = self
+            .nodes
+            .iter()
+            .zip(&self.shares)
+            .any(|(node, share)| node.mining && *share > 0);
+        if mining && idle_ms > limit_ms {
+            self.violations.push(Violation::Stalled {
+                at_secs: self.now_ms / 1000,
+                idle_secs: idle_ms / 1000,
+            });
+            self.last_progress_ms = self.now_ms;
+        }
+    }
+
+    fn send(&mut self, from: usize, to: usize, message: Message) {
+        if let Some(delay) = self.network.send(&mut self.rng, from, to) {
+            let message = Box::new(message);
+            self.schedule(delay, Event::Deliver { from, to, message });
+        }
+    }
+
+    /// Send to every node but `from` and `except`
+    fn broadcast(&mut self, from: usize, except: Option<usize>, message: Message) {
+        for to in 0..self.nodes.len() {
+            if to != from && Some(to) != except {
+                self.send(from, to, message.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let mut scenario = Scenario::builtin("lossy").unwrap();
+        scenario.spend_interval_secs = 900;
+        let first = Simulation::new(scenario.clone(), 11).unwrap().run();
+        let second = Simulation::new(scenario.clone(), 11).unwrap().run();
+        assert_eq!(first, second);
+        assert!(first.passed(), "{:?}", first.violations);
+
+        let other = Simulation::new(scenario, 12).unwrap().run();
+        assert_ne!(first.tips, other.tips);
+    }
+
+    #[test]
+    fn test_partitions_heal_and_converge() {
+        for name in ["partition-heal", "minority-reorg"] {
+            let mut scenario = Scenario::builtin(name).unwrap();
+            scenario.spend_interval_secs = 1200;
+            let report = Simulation::new(scenario, 3).unwrap().run();
+            assert!(report.passed(), "{}: {:?}", name, report.violations);
+            assert!(report.height() > 10);
+            assert!(report.deepest_reorg() > 0, "{} had no reorg", name);
+        }
+    }
+
+    #[test]
+    fn test_unhealed_partition_is_not_convergence_failure() {
+        let mut scenario = Scenario::builtin("partition-heal").unwrap();
+        scenario.steps.truncate(1);
+        scenario.spend_interval_secs = 0;
+        let report = Simulation::new(scenario, 5).unwrap().run();
+        assert!(report.passed(), "{:?}", report.violations);
+        assert_ne!(report.tips[0], report.tips[7]);
+    }
+
+    #[test]
+    fn test_stall_detected() {
+        let scenario = Scenario::builtin("partition-heal").unwrap();
+        let mut sim = Simulation::new(scenario, 1).unwrap();
+        sim.now_ms = STALL_INTERVALS * 120_000;
+        sim.check_progress();
+        assert!(sim.violations.is_empty());
+
+        sim.now_ms += 1;
+        sim.check_progress();
+        assert_eq!(
+            sim.violations,
+            vec![Violation::Stalled {
+                at_secs: 2400,
+                idle_secs: 2400
+            }]
+        );
+
+        // Idle miners are no stall
+        sim.apply(Action::StopMining {
+            nodes: (0..8).collect(),
+        });
+        sim.now_ms *= 2;
+        sim.check_progress();
+        assert_eq!(sim.violations.len(), 1);
+    }
+}
@@ -0,0 +1,77 @@
+//! Asset state while applying blocks
+//! حالة الأصول أثناء تطبيق الكتل
+//!
+//! Asset registry entries and balances touched by a block are read once and
+//! updated in memory, so each operation sees the ones before it; the result
+//! is staged into the block's write batch.
+
+use crate::state::StateStorage;
+use crate::StorageError;
+use opensyria_core::asset::{AssetError, AssetId, AssetInfo};
+use opensyria_core::crypto::PublicKey;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+/// Registry entries and balances touched by a block
+#[derive(Debug, Default)]
+pub(crate) struct AssetLedger {
+    /// Entry per asset; `None` if the asset does not exist
+    pub assets: HashMap<AssetId, Option<AssetInfo>>,
+    pub balances: HashMap<(PublicKey, AssetId), u64>,
+}
+
+impl AssetLedger {
+    pub fn asset(
+        &mut self,
+        state: &StateStorage,
+        asset: &AssetId,
+    ) -> Result<&mut Option<AssetInfo>, StorageError> {
+        Ok(match self.assets.entry(*asset) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(state.get_asset(asset)?),
+        })
+    }
+
+    pub fn existing_asset(
+        &mut self,
+        state: &StateStorage,
+        asset: &AssetId,
+    ) -> Result<&mut AssetInfo, StorageError> {
+        self.asset(state, asset)?.as_mut().ok_or_else(|| {
+            StorageError::AssetViolation(AssetError::UnknownAsset(asset.to_hex()).to_string())
+        })
+    }
+
+    pub fn balance(
+        &mut self,
+        state: &StateStorage,
+        address: &PublicKey,
+        asset: &AssetId,
+    ) -> Result<&mut u64, StorageError> {
+        Ok(match self.balances.entry((*address, *asset)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(state.get_asset_balance(address, asset)?),
+        })
+    }
+
+    /// Take `amount` from `address`, which must hold it
+    pub fn debit(
+        &mut self,
+        state: &StateStorage,
+        address: &PublicKey,
+        asset: &AssetId,
+        amount: u64,
+    ) -> Result<(), StorageError> {
+        let balance = self.balance(state, address, asset)?;
+        *balance = balance.checked_sub(amount).ok_or_else(|| {
+            StorageError::AssetViolation(
+                AssetError::InsufficientAssetBalance {
+                    available: *balance,
+                    requested: amount,
+                }
+                .to_string(),
+            )
+        })?;
+        Ok(())
+    }
+}
@@ -1,17 +1,298 @@
-use crate::StorageError;
-use opensyria_core::{Block, block::BlockError, Transaction};
-use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, DB, BlockBasedOptions, Cache};
-use std::path::PathBuf;
+use crate::doctor::{
+    self, decodes, DoctorOptions, DoctorReport, KeyShape, Namespace, RebuildReport, Suffix,
+    ValueShape, CF_QUARANTINE,
+};
+use crate::limits::StorageResources;
+use crate::migration::{self, Migration, MigrationOptions, MigrationReport, MigrationStep, Schema};
+use crate::replication::{Replication, WAL_RETENTION_MB, WAL_RETENTION_SECS};
+use crate::thin::{self, Watchlist};
+use crate::{RuleViolation, StorageError};
+use opensyria_consensus::DifficultyAdjuster;
+use opensyria_core::{
+    anchor::ChainAnchor,
+    block::{BlockError, BlockHeader, BlockV1},
+    crypto::PublicKey,
+    transaction::TransactionError,
+    Anomaly, Block, BlockProducer, ChainParams, DepositOperation, EventBus, NodeEvent, Transaction,
+};
+use rocksdb::{BlockBasedOptions, ColumnFamilyDescriptor, Options, WriteBatch, DB};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Column family names for secondary indexes
 const CF_TX_INDEX: &str = "tx_index";       // tx_hash → (block_height, tx_index)
 const CF_ADDRESS_INDEX: &str = "address_index"; // address → Vec<tx_hash>
 const CF_BLOCK_HASH_INDEX: &str = "block_hash_index"; // block_hash → height
+const CF_STALE_BLOCKS: &str = "stale_blocks"; // height (BE) ‖ block_hash → reverted block
+
+/// Every column family, in the order hashed by replication digests
+pub(crate) const COLUMN_FAMILIES: &[&str] = &[
+    "default",
+    CF_TX_INDEX,
+    CF_ADDRESS_INDEX,
+    CF_BLOCK_HASH_INDEX,
+    CF_STALE_BLOCKS,
+    CF_QUARANTINE,
+];
+
+/// Key layout versions of the blockchain database, oldest first
+pub(crate) const SCHEMA: Schema = Schema {
+    name: "blockchain",
+    migrations: &[
+        migration::BASELINE,
+        Migration {
+            version: 2,
+            description: "re-encode blocks stored in the pre-sponsorship transaction layout",
+            backup: true,
+            run: reencode_legacy_blocks,
+        },
+        Migration {
+            version: 3,
+            description: "index block producers",
+            backup: false,
+            run: index_block_producers,
+        },
+    ],
+    column_families: COLUMN_FAMILIES,
+};
+
+/// Rewrite every block still stored with [`BlockV1`] transactions in the
+/// current layout; blocks already rewritten are skipped
+fn reencode_legacy_blocks(step: &mut MigrationStep<'_>) -> Result<(), StorageError> {
+    let db = step.db();
+    for item in db.iterator(rocksdb::IteratorMode::Start) {
+        let (key, data) = item?;
+        // Blocks are keyed by their bare hash; every other key is prefixed
+        if key.len() != 32 {
+            continue;
+        }
+        step.progress(1);
+        if let (block, true) = decode_stored_block(&data)? {
+            step.put_cf("default", &key, &crate::bincode_helpers::serialize(&block)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the producer of every block on the height mapping
+fn index_block_producers(step: &mut MigrationStep<'_>) -> Result<(), StorageError> {
+    let db = step.db();
+    for item in db.prefix_iterator(HEIGHT_PREFIX) {
+        let (key, hash) = item?;
+        if !key.starts_with(HEIGHT_PREFIX) {
+            break;
+        }
+        step.progress(1);
+        let height = std::str::from_utf8(&key[HEIGHT_PREFIX.len()..])
+            .ok()
+            .and_then(|digits| digits.parse::<u64>().ok());
+        let (Some(height), Some(data)) = (height, db.get(&hash)?) else {
+            continue;
+        };
+        let block: Block = crate::bincode_helpers::deserialize(&data)?;
+        if let Some(producer) = block.producer() {
+            step.put_cf(
+                "default",
+                &BlockchainStorage::producer_key(height),
+                &crate::bincode_helpers::serialize(&producer)?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Key prefix for signed checkpoints (followed by big-endian height)
+const CHECKPOINT_PREFIX: &[u8] = b"signed_checkpoint_";
+
+/// Height of the snapshot block an empty chain was started from
+const SNAPSHOT_BASE_KEY: &[u8] = b"snapshot_base";
+
+/// Height and timestamp of the block opening the retarget window the
+/// snapshot base falls in, when that block is not stored
+const SNAPSHOT_WINDOW_KEY: &[u8] = b"snapshot_window";
+
+/// Decode a stored block, and whether it used the legacy [`BlockV1`] layout
+///
+/// The current layout must consume every byte. A legacy block never does:
+/// its last transaction lacks the trailing option tags, and any other one is
+/// followed by a chain ID whose varint prefix is not a valid option tag.
+fn decode_stored_block(data: &[u8]) -> Result<(Block, bool), StorageError> {
+    let config = bincode::config::standard();
+    if let Ok((block, read)) = bincode::decode_from_slice::<Block, _>(data, config) {
+        if read == data.len() {
+            return Ok((block, false));
+        }
+    }
+    match bincode::decode_from_slice::<BlockV1, _>(data, config) {
+        Ok((legacy, read)) if read == data.len() => Ok((legacy.into(), true)),
+        _ => Ok((crate::bincode_helpers::deserialize(data)?, false)),
+    }
+}
+
+/// Decode only the header of a stored block; every block layout starts
+/// with it, so the transactions are never decoded
+fn decode_stored_header(data: &[u8]) -> Result<BlockHeader, StorageError> {
+    Ok(crate::bincode_helpers::deserialize(data)?)
+}
+
+/// Prefix of the height → block hash mapping (followed by decimal height)
+const HEIGHT_PREFIX: &[u8] = b"height_";
+
+/// Prefix of the height → [`BlockProducer`] index (followed by big-endian height)
+const PRODUCER_PREFIX: &[u8] = b"producer_";
+
+/// Key layout checked by [`BlockchainStorage::examine`]; blocks are keyed by
+/// their bare hash, so they come after every prefixed namespace
+const NAMESPACES: &[Namespace] = {
+    use KeyShape::{Exact, Prefix};
+    use ValueShape::Decodes;
+    const fn ns(
+        name: &'static str,
+        column_family: &'static str,
+        key: KeyShape,
+        value: ValueShape,
+    ) -> Namespace {
+        Namespace {
+            name,
+            column_family,
+            key,
+            value,
+        }
+    }
+    &[
+        ns(
+            "chain_height",
+            "default",
+            Exact(b"chain_height"),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "chain_tip",
+            "default",
+            Exact(b"chain_tip"),
+            ValueShape::Len(32),
+        ),
+        ns(
+            "snapshot_base",
+            "default",
+            Exact(SNAPSHOT_BASE_KEY),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "snapshot_window",
+            "default",
+            Exact(SNAPSHOT_WINDOW_KEY),
+            ValueShape::Len(16),
+        ),
+        ns(
+            "schema_version",
+            "default",
+            Exact(migration::SCHEMA_VERSION_KEY),
+            ValueShape::Len(4),
+        ),
+        ns(
+            "height",
+            "default",
+            Prefix(HEIGHT_PREFIX, Suffix::Digits),
+            ValueShape::Len(32),
+        ),
+        ns(
+            "producer",
+            "default",
+            Prefix(PRODUCER_PREFIX, Suffix::Len(8)),
+            Decodes(decodes::<BlockProducer>),
+        ),
+        ns(
+            "signed_checkpoint",
+            "default",
+            Prefix(CHECKPOINT_PREFIX, Suffix::Len(8)),
+            Decodes(decodes::<opensyria_consensus::SignedCheckpoint>),
+        ),
+        ns(
+            "block",
+            "default",
+            KeyShape::Len(32),
+            Decodes(decodes::<Block>),
+        ),
+        ns(
+            "tx_index",
+            CF_TX_INDEX,
+            KeyShape::Len(32),
+            Decodes(decodes::<(u64, usize)>),
+        ),
+        ns(
+            "address_index",
+            CF_ADDRESS_INDEX,
+            KeyShape::Len(32),
+            Decodes(decodes::<Vec<[u8; 32]>>),
+        ),
+        ns(
+            "address_index_legacy",
+            CF_ADDRESS_INDEX,
+            Prefix(b"addr_", Suffix::Len(64)),
+            Decodes(decodes::<Vec<[u8; 32]>>),
+        ),
+        ns(
+            "block_hash_index",
+            CF_BLOCK_HASH_INDEX,
+            KeyShape::Len(32),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "stale_block",
+            CF_STALE_BLOCKS,
+            KeyShape::Len(40),
+            Decodes(decodes::<Block>),
+        ),
+    ]
+};
+
+/// Blocks re-indexed between index writes during [`BlockchainStorage::rebuild_indexes`]
+const REBUILD_BATCH_BLOCKS: usize = 1_000;
+
+/// Maximum number of hashes in a block locator
+pub const MAX_LOCATOR_HASHES: usize = 64;
+
+/// Heights over which [`BlockchainStorage::orphan_rate`] is usually reported
+pub const ORPHAN_RATE_WINDOW: u64 = 1_000;
+
+/// Heights sampled by a block locator for a chain of height `tip`
+/// الارتفاعات المستخدمة في محدد موقع الكتل
+///
+/// The ten most recent heights are listed one by one, after which the step
+/// doubles each time, always ending with the genesis block at height 1.
+pub fn block_locator_heights(tip: u64) -> Vec<u64> {
+    let mut heights = Vec::new();
+    let mut height = tip;
+    let mut step = 1u64;
+
+    while height > 1 && heights.len() < MAX_LOCATOR_HASHES - 1 {
+        heights.push(height);
+        if heights.len() >= 10 {
+            step = step.saturating_mul(2);
+        }
+        height = height.saturating_sub(step);
+    }
+    if tip >= 1 {
+        heights.push(1);
+    }
+    heights
+}
 
 /// Blockchain storage using RocksDB with secondary indexes
 /// التخزين المستمر لسلسلة الكتل باستخدام RocksDB مع الفهارس الثانوية
 pub struct BlockchainStorage {
-    db: DB,
+    /// Shared with [`Replication`] handles, which outlive read locks
+    db: Arc<DB>,
+    /// Optional bus notified when blocks are connected or disconnected
+    event_bus: Option<EventBus>,
+    /// Consensus parameters of the network this chain belongs to
+    params: ChainParams,
+    /// Addresses kept by a thin node (None stores full blocks)
+    watchlist: Option<Watchlist>,
 }
 
 impl BlockchainStorage {
@@ -20,25 +301,130 @@ impl BlockchainStorage {
     /// 
     /// ✅  PERFORMANCE FIX (P1-002): Bloom filters enabled for 10x read speedup
     /// ✅  PERF-P2-004: Optimized compaction strategy for production
+    ///
+    /// Pending schema migrations run first; a database written by a newer
+    /// binary is refused with [`StorageError::SchemaTooNew`].
     pub fn open(path: PathBuf) -> Result<Self, StorageError> {
+        Self::open_with_resources(path, &StorageResources::default())
+    }
+
+    /// Open blockchain storage drawing on a cache and write buffer budget
+    /// shared with other databases
+    /// فتح تخزين سلسلة الكتل ضمن ميزانية ذاكرة مشتركة
+    ///
+    /// A database created by [`Self::open_thin`] stays thin.
+    pub fn open_with_resources(
+        path: PathBuf,
+        resources: &StorageResources,
+    ) -> Result<Self, StorageError> {
+        Self::open_as(path, resources, None)
+    }
+
+    /// Open thin storage keeping headers and the transactions of `watchlist`
+    /// فتح تخزين خفيف يحفظ الرؤوس ومعاملات العناوين المراقبة فقط
+    ///
+    /// The watchlist is recorded with the database; a different one replaces
+    /// it for blocks appended from now on. A database that already holds
+    /// full blocks is refused with [`StorageError::ThinNode`].
+    pub fn open_thin(
+        path: PathBuf,
+        resources: &StorageResources,
+        watchlist: &Watchlist,
+    ) -> Result<Self, StorageError> {
+        Self::open_as(path, resources, Some(watchlist))
+    }
+
+    fn open_as(
+        path: PathBuf,
+        resources: &StorageResources,
+        watchlist: Option<&Watchlist>,
+    ) -> Result<Self, StorageError> {
+        // Checked before RocksDB creates the directory
+        let watchlist = thin::resolve_watchlist(&path, watchlist)?;
+        let db = Self::open_db(&path, resources)?;
+        migration::migrate(&db, &path, &SCHEMA, MigrationOptions::default())?;
+
+        Ok(Self {
+            db: Arc::new(db),
+            event_bus: None,
+            params: ChainParams::default(),
+            watchlist,
+        })
+    }
+
+    /// Run pending schema migrations on the database at `path` and close it
+    /// تشغيل ترحيلات المخطط المعلقة على قاعدة البيانات
+    pub fn migrate(
+        path: PathBuf,
+        options: MigrationOptions,
+    ) -> Result<MigrationReport, StorageError> {
+        let db = Self::open_db(&path, &StorageResources::default())?;
+        migration::migrate(&db, &path, &SCHEMA, options)
+    }
+
+    /// Open the chain of a running node as a read-only secondary instance
+    /// فتح سلسلة عقدة عاملة كنسخة ثانوية للقراءة فقط
+    ///
+    /// The node keeps its lock on `path` and goes on writing; RocksDB keeps
+    /// the secondary's own logs under `secondary_path`. Blocks the node wrote
+    /// since opening become visible after [`Self::catch_up`]. Nothing is
+    /// migrated, so the database must already be at the current schema.
+    pub fn open_secondary(path: PathBuf, secondary_path: PathBuf) -> Result<Self, StorageError> {
+        let (mut opts, cf_descriptors) = Self::db_options(&StorageResources::default());
+        // Secondaries must keep every table file of the primary open
+        opts.set_max_open_files(-1);
+        let db =
+            DB::open_cf_descriptors_as_secondary(&opts, &path, &secondary_path, cf_descriptors)?;
+
+        let current = SCHEMA.current_version();
+        match migration::stored_version(&db)? {
+            Some(version) if version == current => {}
+            found => {
+                return Err(StorageError::MigrationError(format!(
+                    "{} schema is at version {}, expected {}; start the node to migrate it",
+                    SCHEMA.name,
+                    found.unwrap_or(0),
+                    current
+                )))
+            }
+        }
+
+        Ok(Self {
+            db: Arc::new(db),
+            event_bus: None,
+            params: ChainParams::default(),
+            watchlist: thin::recorded_watchlist(&path)?,
+        })
+    }
+
+    /// Catch a secondary opened by [`Self::open_secondary`] up with what
+    /// the node has written since
+    /// مزامنة النسخة الثانوية مع ما كتبته العقدة
+    pub fn catch_up(&self) -> Result<(), StorageError> {
+        Ok(self.db.try_catch_up_with_primary()?)
+    }
+
+    fn open_db(path: &Path, resources: &StorageResources) -> Result<DB, StorageError> {
+        let (opts, cf_descriptors) = Self::db_options(resources);
+        Ok(DB::open_cf_descriptors(&opts, path, cf_descriptors)?)
+    }
+
+    fn db_options(resources: &StorageResources) -> (Options, Vec<ColumnFamilyDescriptor>) {
         let mut opts = Options::default();
         opts.create_if_missing(true);
         opts.create_missing_column_families(true);
         
         // PERFORMANCE FIX: Enable bloom filters for all column families
         // Dramatically reduces disk I/O for non-existent keys
-        let cache = Cache::new_lru_cache(256 * 1024 * 1024); // 256MB cache
         let mut block_opts = BlockBasedOptions::default();
         block_opts.set_bloom_filter(10.0, false);
-        block_opts.set_block_cache(&cache);
-        opts.set_block_based_table_factory(&block_opts);
+
+        // Block cache, memtables and open files come out of the shared budget
+        resources.apply(&mut opts, &mut block_opts);
         
         // Enable LZ4 compression for better disk usage
         opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
         
-        // Optimize write buffer for better write performance
-        opts.set_write_buffer_size(64 * 1024 * 1024); // 64MB
-        
         // PERF-P2-004: Level-based compaction strategy
         // Optimizes for blockchain workload (sequential writes, random reads)
         opts.set_max_background_jobs(4); // Allow parallel compaction
@@ -57,11 +443,15 @@ impl BlockchainStorage {
         // Periodic compaction every 7 days to clean up old data
         opts.set_periodic_compaction_seconds(7 * 24 * 3600);
 
+        // Keep archived WAL files so replication secondaries can catch up
+        opts.set_wal_ttl_seconds(WAL_RETENTION_SECS);
+        opts.set_wal_size_limit_mb(WAL_RETENTION_MB);
+
         // Define column families for secondary indexes with same optimizations
         let mut cf_opts = Options::default();
         let mut cf_block_opts = BlockBasedOptions::default();
         cf_block_opts.set_bloom_filter(10.0, false);
-        cf_opts.set_block_based_table_factory(&cf_block_opts);
+        resources.apply(&mut cf_opts, &mut cf_block_opts);
         cf_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
         
         // Apply compaction settings to column families
@@ -74,18 +464,227 @@ impl BlockchainStorage {
             ColumnFamilyDescriptor::new("default", opts.clone()),
             ColumnFamilyDescriptor::new(CF_TX_INDEX, cf_opts.clone()),
             ColumnFamilyDescriptor::new(CF_ADDRESS_INDEX, cf_opts.clone()),
-            ColumnFamilyDescriptor::new(CF_BLOCK_HASH_INDEX, cf_opts),
+            ColumnFamilyDescriptor::new(CF_BLOCK_HASH_INDEX, cf_opts.clone()),
+            ColumnFamilyDescriptor::new(CF_STALE_BLOCKS, cf_opts.clone()),
+            ColumnFamilyDescriptor::new(CF_QUARANTINE, cf_opts),
         ];
 
-        let db = DB::open_cf_descriptors(&opts, path, cf_descriptors)?;
+        (opts, cf_descriptors)
+    }
 
-        Ok(Self { db })
+    /// Checkpoint, WAL and digest access for operator replication
+    /// الوصول إلى نقاط التفتيش وسجل الكتابة لغرض النسخ المتماثل
+    pub fn replication(&self) -> Replication {
+        Replication::new(self.db.clone(), COLUMN_FAMILIES)
+    }
+
+    /// Handle on the same database for reads on another thread
+    /// مقبض على قاعدة البيانات نفسها للقراءة من خيط آخر
+    ///
+    /// The handle publishes no events, so it must not be used to change the chain.
+    pub fn reader(&self) -> BlockchainStorage {
+        BlockchainStorage {
+            db: Arc::clone(&self.db),
+            event_bus: None,
+            params: self.params.clone(),
+            watchlist: self.watchlist.clone(),
+        }
+    }
+
+    /// Check every key and value against the blockchain key layout
+    /// فحص كل مفتاح وقيمة مقابل تخطيط مفاتيح سلسلة الكتل
+    pub fn examine(&self, options: DoctorOptions) -> Result<DoctorReport, StorageError> {
+        doctor::examine(&self.db, SCHEMA.name, COLUMN_FAMILIES, NAMESPACES, options)
+    }
+
+    /// Rebuild the height mapping and secondary indexes from stored blocks
+    /// إعادة بناء فهرس الارتفاعات والفهارس الثانوية من الكتل المخزنة
+    ///
+    /// The chain is walked back from the tip through `previous_hash` links to
+    /// genesis, or to the snapshot base for chains started from a snapshot.
+    /// Every `height_` key and every index entry is then dropped and written
+    /// again exactly as [`Self::append_block`] would have. Fails with
+    /// [`StorageError::InvalidChain`] if a block on the way is missing or
+    /// undecodable, since the indexes cannot be derived without it.
+    pub fn rebuild_indexes(&self) -> Result<RebuildReport, StorageError> {
+        let snapshot_base = self.snapshot_base()?;
+        let mut hashes = Vec::new();
+        let mut next = self.get_chain_tip()?.filter(|hash| *hash != [0u8; 32]);
+        while let Some(hash) = next {
+            let block = self.load_block(&hash)?.ok_or(StorageError::InvalidChain)?;
+            hashes.push(hash);
+            let previous = block.header.previous_hash;
+            // Only the snapshot base block is stored without its parent
+            next = if previous == [0u8; 32]
+                || (snapshot_base.is_some() && self.db.get(previous)?.is_none())
+            {
+                None
+            } else {
+                Some(previous)
+            };
+        }
+        hashes.reverse();
+        let first_height = match snapshot_base {
+            Some(base) if !hashes.is_empty() => base,
+            _ => 1,
+        };
+
+        // Drop the derived data
+        let mut batch = WriteBatch::default();
+        for item in self.db.prefix_iterator(HEIGHT_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(HEIGHT_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+        for item in self.db.prefix_iterator(PRODUCER_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(PRODUCER_PREFIX) {
+                break;
+            }
+            batch.delete(key);
+        }
+        for cf_name in [CF_TX_INDEX, CF_ADDRESS_INDEX, CF_BLOCK_HASH_INDEX] {
+            let cf = self
+                .db
+                .cf_handle(cf_name)
+                .ok_or(StorageError::ColumnFamilyNotFound)?;
+            for item in self.db.iterator_cf(&cf, rocksdb::IteratorMode::Start) {
+                let (key, _) = item?;
+                batch.delete_cf(&cf, key);
+            }
+        }
+        self.db.write(batch)?;
+
+        let cf_tx = self
+            .db
+            .cf_handle(CF_TX_INDEX)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let cf_addr = self
+            .db
+            .cf_handle(CF_ADDRESS_INDEX)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let cf_block_hash = self
+            .db
+            .cf_handle(CF_BLOCK_HASH_INDEX)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let mut report = RebuildReport {
+            blocks: 0,
+            transactions: 0,
+            height: 0,
+        };
+        for (chunk_index, chunk) in hashes.chunks(REBUILD_BATCH_BLOCKS).enumerate() {
+            let mut batch = WriteBatch::default();
+            let mut addresses: HashMap<[u8; 32], Vec<[u8; 32]>> = HashMap::new();
+            for (offset, hash) in chunk.iter().enumerate() {
+                let height = first_height + (chunk_index * REBUILD_BATCH_BLOCKS + offset) as u64;
+                let block = self.load_block(hash)?.ok_or(StorageError::InvalidChain)?;
+                batch.put(format!("height_{}", height).as_bytes(), hash);
+                batch.put_cf(&cf_block_hash, hash, height.to_le_bytes());
+                if let Some(producer) = block.producer() {
+                    batch.put(
+                        Self::producer_key(height),
+                        crate::bincode_helpers::serialize(&producer)?,
+                    );
+                }
+                for (tx_idx, tx) in block.transactions.iter().enumerate() {
+                    let tx_hash = tx.hash();
+                    let tx_location = crate::bincode_helpers::serialize(&(height, tx_idx))?;
+                    batch.put_cf(&cf_tx, tx_hash, tx_location);
+                    if !tx.is_coinbase() && self.indexes_address(&tx.from) {
+                        addresses.entry(tx.from.0).or_default().push(tx_hash);
+                    }
+                    if self.indexes_address(&tx.to) {
+                        addresses.entry(tx.to.0).or_default().push(tx_hash);
+                    }
+                    report.transactions += 1;
+                }
+                report.blocks += 1;
+                report.height = height;
+            }
+            // Earlier chunks are already written, so lists extend what is stored
+            for (address, hashes) in addresses {
+                let mut tx_list: Vec<[u8; 32]> = match self.db.get_cf(&cf_addr, address)? {
+                    Some(data) => crate::bincode_helpers::deserialize(&data)?,
+                    None => Vec::new(),
+                };
+                tx_list.extend(hashes);
+                batch.put_cf(
+                    &cf_addr,
+                    address,
+                    crate::bincode_helpers::serialize(&tx_list)?,
+                );
+            }
+            self.db.write(batch)?;
+        }
+        self.set_chain_height(report.height)?;
+
+        Ok(report)
+    }
+
+    /// Publish BlockConnected / BlockDisconnected events on the given bus
+    /// نشر أحداث ربط وفصل الكتل على ناقل الأحداث
+    pub fn set_event_bus(&mut self, event_bus: EventBus) {
+        self.event_bus = Some(event_bus);
+    }
+
+    /// Use the given network parameters for coinbase and reorg checks
+    /// استخدام معاملات الشبكة المحددة للتحقق من المكافأة وإعادة التنظيم
+    pub fn set_chain_params(&mut self, params: ChainParams) {
+        self.params = params;
+    }
+
+    /// Network parameters used for validation
+    pub fn chain_params(&self) -> &ChainParams {
+        &self.params
+    }
+
+    fn publish(&self, event: NodeEvent) {
+        if let Some(bus) = &self.event_bus {
+            bus.publish(event);
+        }
+    }
+
+    /// Whether only headers and watched transactions are stored
+    /// هل يخزن الرؤوس والمعاملات المراقبة فقط
+    pub fn is_thin(&self) -> bool {
+        self.watchlist.is_some()
+    }
+
+    /// Addresses kept by a thin node
+    pub fn watchlist(&self) -> Option<&Watchlist> {
+        self.watchlist.as_ref()
+    }
+
+    /// Fail with [`StorageError::ThinNode`] if `work` needs full blocks
+    fn require_full(&self, work: &str) -> Result<(), StorageError> {
+        if self.is_thin() {
+            return Err(StorageError::ThinNode(work.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Whether transactions of `address` go into the address index
+    fn indexes_address(&self, address: &PublicKey) -> bool {
+        self.watchlist
+            .as_ref()
+            .is_none_or(|watchlist| watchlist.contains(address))
+    }
+
+    /// What is written for `block`: all of it, or its thin form
+    fn stored_block<'a>(&self, block: &'a Block) -> Cow<'a, Block> {
+        match &self.watchlist {
+            Some(watchlist) => Cow::Owned(watchlist.filter_block(block)),
+            None => Cow::Borrowed(block),
+        }
     }
 
     /// Save block to storage
+    #[tracing::instrument(name = "db_write", skip_all, fields(op = "put_block"))]
     pub fn put_block(&self, block: &Block) -> Result<(), StorageError> {
         let hash = block.hash();
-        let data = crate::bincode_helpers::serialize(block)?;
+        let data = crate::bincode_helpers::serialize(&self.stored_block(block))?;
 
         self.db.put(hash, &data)?;
 
@@ -96,10 +695,19 @@ impl BlockchainStorage {
     }
 
     /// Get block by hash
+    ///
+    /// Fails with [`StorageError::ThinNode`] on a thin node; use
+    /// [`Self::get_header`] for what it keeps.
     pub fn get_block(&self, hash: &[u8; 32]) -> Result<Option<Block>, StorageError> {
+        self.require_full("full blocks")?;
+        self.load_block(hash)
+    }
+
+    /// Block as stored, which on a thin node is its filtered form
+    fn load_block(&self, hash: &[u8; 32]) -> Result<Option<Block>, StorageError> {
         match self.db.get(hash)? {
             Some(data) => {
-                let block: Block = crate::bincode_helpers::deserialize(&data)?;
+                let (block, _) = decode_stored_block(&data)?;
                 Ok(Some(block))
             }
             None => Ok(None),
@@ -136,6 +744,11 @@ impl BlockchainStorage {
 
     /// Get block hash by height
     pub fn get_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
+        self.require_full("full blocks")?;
+        self.load_block_by_height(height)
+    }
+
+    fn load_block_by_height(&self, height: u64) -> Result<Option<Block>, StorageError> {
         let key = format!("height_{}", height);
         match self.db.get(key.as_bytes())? {
             Some(hash_data) => {
@@ -144,12 +757,113 @@ impl BlockchainStorage {
                 }
                 let mut hash = [0u8; 32];
                 hash.copy_from_slice(&hash_data);
-                self.get_block(&hash)
+                self.load_block(&hash)
             }
             None => Ok(None),
         }
     }
 
+    /// Get block header by hash, on full and thin nodes alike
+    /// الحصول على رأس الكتلة بواسطة التجزئة
+    pub fn get_header(&self, hash: &[u8; 32]) -> Result<Option<BlockHeader>, StorageError> {
+        match self.db.get_pinned(hash)? {
+            Some(data) => Ok(Some(decode_stored_header(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Get the header at a height of the active chain
+    /// الحصول على رأس الكتلة عند ارتفاع معين
+    pub fn get_header_by_height(&self, height: u64) -> Result<Option<BlockHeader>, StorageError> {
+        match self.get_block_hash_by_height(height)? {
+            Some(hash) => self.get_header(&hash),
+            None => Ok(None),
+        }
+    }
+
+    /// Total work of the active chain's blocks above `height`
+    /// مجموع عمل كتل السلسلة النشطة فوق ارتفاع معين
+    pub fn work_above(&self, height: u64) -> Result<u128, StorageError> {
+        let mut work: u128 = 0;
+        for height in height + 1..=self.get_chain_height()? {
+            if let Some(header) = self.get_header_by_height(height)? {
+                work = work.saturating_add(header.work());
+            }
+        }
+        Ok(work)
+    }
+
+    /// Get block hash at a height of the active chain without loading the block
+    pub fn get_block_hash_by_height(&self, height: u64) -> Result<Option<[u8; 32]>, StorageError> {
+        let key = format!("height_{}", height);
+        match self.db.get(key.as_bytes())? {
+            Some(hash_data) => Ok(hash_data.try_into().ok()),
+            None => Ok(None),
+        }
+    }
+
+    /// Build a block locator: exponentially spaced hashes back from the tip
+    /// إنشاء محدد موقع الكتل من القمة نحو كتلة التكوين
+    ///
+    /// Only the height index is read, so the locator stays valid when block
+    /// bodies or historical state have been pruned.
+    pub fn get_block_locator(&self) -> Result<Vec<[u8; 32]>, StorageError> {
+        let tip = self.get_chain_height()?;
+        let mut locator = Vec::new();
+        for height in block_locator_heights(tip) {
+            if let Some(hash) = self.get_block_hash_by_height(height)? {
+                locator.push(hash);
+            }
+        }
+        Ok(locator)
+    }
+
+    /// Height of the most recent locator entry that is on our active chain
+    /// إيجاد نقطة التفرع المشتركة من محدد الموقع
+    ///
+    /// Returns 0 when no locator hash is known, meaning the chains share no
+    /// blocks and syncing must start from genesis.
+    pub fn find_locator_fork(&self, locator: &[[u8; 32]]) -> Result<u64, StorageError> {
+        for hash in locator {
+            if let Some(height) = self.get_block_height_by_hash(hash)? {
+                // The hash index may still hold entries for reverted blocks
+                if self.get_block_hash_by_height(height)? == Some(*hash) {
+                    return Ok(height);
+                }
+            }
+        }
+        Ok(0)
+    }
+
+    /// Headers following the fork point of `locator`, up to `stop_hash` or `max_headers`
+    /// رؤوس الكتل بعد نقطة التفرع حتى تجزئة التوقف أو الحد الأقصى
+    ///
+    /// Only headers are decoded, and `max_headers` bounds the heights read.
+    pub fn get_headers_after_locator(
+        &self,
+        locator: &[[u8; 32]],
+        stop_hash: Option<[u8; 32]>,
+        max_headers: usize,
+    ) -> Result<Vec<BlockHeader>, StorageError> {
+        let start = self.find_locator_fork(locator)? + 1;
+        let end = self
+            .get_chain_height()?
+            .min(start.saturating_add(max_headers as u64).saturating_sub(1));
+        let mut headers = Vec::with_capacity(end.saturating_add(1).saturating_sub(start) as usize);
+
+        for height in start..=end {
+            let Some(header) = self.get_header_by_height(height)? else {
+                break;
+            };
+            let hash = header.hash();
+            headers.push(header);
+            if stop_hash == Some(hash) {
+                break;
+            }
+        }
+        Ok(headers)
+    }
+
     /// Get current blockchain height
     pub fn get_chain_height(&self) -> Result<u64, StorageError> {
         match self.db.get(b"chain_height")? {
@@ -229,7 +943,7 @@ impl BlockchainStorage {
             let (block_height, tx_index): (u64, usize) = crate::bincode_helpers::deserialize(&location_data)?;
             
             // Fetch block and extract transaction
-            if let Some(block) = self.get_block_by_height(block_height)? {
+            if let Some(block) = self.load_block_by_height(block_height)? {
                 if let Some(tx) = block.transactions.get(tx_index) {
                     return Ok(Some((tx.clone(), block_height)));
                 }
@@ -241,14 +955,27 @@ impl BlockchainStorage {
 
     /// Get all transaction hashes for an address (O(1) lookup using index)
     /// الحصول على جميع تجزئات المعاملات لعنوان (بحث O(1) باستخدام الفهرس)
+    ///
+    /// A thin node only answers for addresses on its watchlist.
     pub fn get_address_transactions(&self, address: &[u8; 32]) -> Result<Vec<[u8; 32]>, StorageError> {
+        if !self.indexes_address(&PublicKey(*address)) {
+            return Err(StorageError::ThinNode(format!(
+                "address {} is not on the watchlist",
+                hex::encode(address)
+            )));
+        }
         let addr_cf = self.db.cf_handle(CF_ADDRESS_INDEX)
             .ok_or(StorageError::ColumnFamilyNotFound)?;
         
-        let addr_key = format!("addr_{}", hex::encode(address));
-        
-        let tx_hashes: Vec<[u8; 32]> = self.db
-            .get_cf(&addr_cf, addr_key.as_bytes())?
+        // Blocks index under the raw address; older databases used `addr_{hex}`
+        let data = match self.db.get_cf(&addr_cf, address)? {
+            Some(data) => Some(data),
+            None => {
+                let addr_key = format!("addr_{}", hex::encode(address));
+                self.db.get_cf(&addr_cf, addr_key.as_bytes())?
+            }
+        };
+        let tx_hashes: Vec<[u8; 32]> = data
             .map(|data| crate::bincode_helpers::deserialize(&data).unwrap_or_default())
             .unwrap_or_default();
         
@@ -304,8 +1031,8 @@ impl BlockchainStorage {
         let mut timestamps = Vec::new();
         
         for height in start_height..=current_height {
-            if let Some(block) = self.get_block_by_height(height)? {
-                timestamps.push(block.header.timestamp);
+            if let Some(header) = self.get_header_by_height(height)? {
+                timestamps.push(header.timestamp);
             }
         }
         
@@ -319,45 +1046,163 @@ impl BlockchainStorage {
         Ok(timestamps[median_idx])
     }
 
-    /// Append block to chain (validates and stores)
-    /// 
-    /// ✅  SECURITY FIX (CRITICAL-004): Now validates coinbase against current supply
-    /// Requires state_storage parameter to check total supply and prevent inflation attacks.
-    /// Ensures MAX_SUPPLY is never exceeded.
-    pub fn append_block(
+    /// Difficulty the next block must have under the chain parameters
+    /// الصعوبة المطلوبة للكتلة التالية وفق معاملات السلسلة
+    ///
+    /// The first block after genesis has `genesis_difficulty`. Later blocks
+    /// keep their parent's difficulty, except every
+    /// `difficulty_adjustment_interval` blocks, where [`DifficultyAdjuster`]
+    /// retargets it from how long the last interval took.
+    pub fn next_difficulty(&self) -> Result<u32, StorageError> {
+        let height = self.get_chain_height()?;
+        let tip = match self.get_chain_tip()? {
+            Some(tip_hash) => self.get_header(&tip_hash)?,
+            None => None,
+        };
+        let Some(tip) = tip.filter(|_| height > 1) else {
+            return Ok(self.params.genesis_difficulty);
+        };
+
+        let interval = self.params.difficulty_adjustment_interval as u64;
+        if (height - 1) % interval != 0 {
+            return Ok(tip.difficulty);
+        }
+        let window_start = self.window_start_timestamp(height - interval)?;
+
+        let actual_time = tip.timestamp.saturating_sub(window_start);
+        Ok(DifficultyAdjuster::from_params(&self.params).adjust(
+            tip.difficulty,
+            Duration::from_secs(actual_time),
+            interval as u32,
+        ))
+    }
+
+    /// Timestamp of the block at `height`, which opens a retarget window
+    ///
+    /// Below a snapshot base the block is not stored and the timestamp the
+    /// snapshot carried is used instead.
+    fn window_start_timestamp(&self, height: u64) -> Result<u64, StorageError> {
+        if let Some(header) = self.get_header_by_height(height)? {
+            return Ok(header.timestamp);
+        }
+        if let Some(data) = self.db.get(SNAPSHOT_WINDOW_KEY)? {
+            let window: [u8; 16] = data.try_into().map_err(|_| StorageError::InvalidChain)?;
+            let (start, timestamp) = window.split_at(8);
+            if start == height.to_le_bytes() {
+                let timestamp: [u8; 8] = timestamp.try_into().expect("8 bytes");
+                return Ok(u64::from_le_bytes(timestamp));
+            }
+        }
+        Err(StorageError::InvalidSnapshot(format!(
+            "retarget window start at height {} is not stored",
+            height
+        )))
+    }
+
+    /// First height of the retarget window a snapshot at `height` must carry
+    /// headers from, if the next retarget looks below `height`
+    fn snapshot_window_start(&self, height: u64) -> Option<u64> {
+        let interval = self.params.difficulty_adjustment_interval as u64;
+        // Next tip height at or above `height` that triggers a retarget
+        let retarget = height + (interval + 1 - height % interval) % interval;
+        (retarget > 1).then(|| retarget - interval)
+    }
+
+    /// Headers a state snapshot at `height` carries so the chain restored
+    /// from it can compute its next retarget
+    /// رؤوس نافذة تعديل الصعوبة التي تحملها لقطة الحالة
+    ///
+    /// They run from the start of the retarget window up to the parent of
+    /// the block at `height`, and link to it by hash.
+    pub fn snapshot_window_headers(&self, height: u64) -> Result<Vec<BlockHeader>, StorageError> {
+        let Some(start) = self.snapshot_window_start(height) else {
+            return Ok(Vec::new());
+        };
+        (start..height)
+            .map(|height| {
+                self.get_header_by_height(height)?.ok_or_else(|| {
+                    StorageError::InvalidSnapshot(format!(
+                        "header at height {} is not stored",
+                        height
+                    ))
+                })
+            })
+            .collect()
+    }
+
+    /// Check `block` against every consensus rule for the next height
+    /// without storing it
+    /// التحقق من الكتلة التالية مقابل قواعد الإجماع دون تخزينها
+    ///
+    /// Returns the height the block would take. Rules are checked in the
+    /// order [`append_block`](Self::append_block) applies them and the first
+    /// one broken is reported. `check_pow` is off for block templates, which
+    /// are checked before any work is done on them.
+    pub fn check_next_block(
         &self,
         block: &Block,
         state_storage: Option<&crate::state::StateStorage>,
-    ) -> Result<(), StorageError> {
+        check_pow: bool,
+    ) -> Result<u64, RuleViolation> {
         // Get current tip
         let current_height = self.get_chain_height()?;
         let current_tip = self.get_chain_tip()?;
 
         // 1. Verify proof of work (skip for genesis block)
         let is_genesis = current_height == 0 && block.header.previous_hash == [0u8; 32];
-        if !is_genesis && !block.header.meets_difficulty() {
-            return Err(StorageError::InvalidProofOfWork);
+        if check_pow && !is_genesis && !block.header.meets_difficulty() {
+            return Err(RuleViolation::new(
+                "proof_of_work",
+                StorageError::InvalidProofOfWork,
+            ));
+        }
+
+        // Difficulty must follow this network's retarget schedule once the
+        // rule is active
+        if !is_genesis && self.params.difficulty_schedule_active(current_height + 1) {
+            let expected = self.next_difficulty()?;
+            if block.header.difficulty != expected {
+                return Err(RuleViolation::new(
+                    "difficulty",
+                    StorageError::UnexpectedDifficulty {
+                        expected,
+                        got: block.header.difficulty,
+                    },
+                ));
+            }
         }
 
-        // 2. Verify transaction signatures
-        block.verify_transactions()
-            .map_err(|_| StorageError::InvalidTransaction)?;
+        // 2. Verify transaction signatures (coinbase and deposit settlements
+        // carry none; state checks settlements against their escrow)
+        for (index, tx) in block.transactions.iter().enumerate() {
+            let unsigned = tx.is_coinbase() || DepositOperation::is_settlement(tx);
+            if !unsigned && tx.verify().is_err() {
+                return Err(
+                    RuleViolation::new("signature", StorageError::InvalidTransaction).at(index),
+                );
+            }
+        }
 
         // 3. Verify merkle root
         if !block.verify_merkle_root() {
-            return Err(StorageError::InvalidMerkleRoot);
+            return Err(RuleViolation::new(
+                "merkle_root",
+                StorageError::InvalidMerkleRoot,
+            ));
         }
 
         // 4. Validate timestamp against previous block and median-time-past (skip for genesis)
         if !is_genesis {
             if let Some(tip_hash) = current_tip {
-                if let Some(prev_block) = self.get_block(&tip_hash)? {
-                    block.validate_timestamp(prev_block.header.timestamp)
+                if let Some(prev_header) = self.get_header(&tip_hash)? {
+                    block
+                        .validate_timestamp(prev_header.timestamp)
                         .map_err(|e| match e {
                             BlockError::TimestampTooFarFuture => StorageError::TimestampTooFarFuture,
                             BlockError::TimestampDecreased => StorageError::TimestampDecreased,
                             _ => StorageError::InvalidChain,
-                        })?;
+                        })
+                        .map_err(|error| RuleViolation::new("timestamp", error))?;
                 }
             }
 
@@ -365,21 +1210,25 @@ impl BlockchainStorage {
             if current_height >= 11 {
                 let median_time = self.get_median_time_past(current_height)?;
                 if block.header.timestamp <= median_time {
-                    return Err(StorageError::TimestampDecreased);
+                    return Err(RuleViolation::new(
+                        "median_time_past",
+                        StorageError::TimestampDecreased,
+                    ));
                 }
             }
         }
 
         // 5. Validate previous hash matches
-        if let Some(tip_hash) = current_tip {
-            if block.header.previous_hash != tip_hash {
-                return Err(StorageError::InvalidChain);
-            }
-        } else if current_height == 0 {
+        let linked = match current_tip {
+            Some(tip_hash) => block.header.previous_hash == tip_hash,
             // Genesis block should have zero previous hash
-            if block.header.previous_hash != [0u8; 32] {
-                return Err(StorageError::InvalidChain);
-            }
+            None => current_height != 0 || block.header.previous_hash == [0u8; 32],
+        };
+        if !linked {
+            return Err(RuleViolation::new(
+                "previous_hash",
+                StorageError::InvalidChain,
+            ));
         }
 
         // Calculate new height
@@ -394,7 +1243,8 @@ impl BlockchainStorage {
                 0 // If no state storage provided, skip supply check (backward compatibility)
             };
             
-            block.validate_coinbase(new_height, current_supply)
+            block
+                .validate_coinbase_with_params(new_height, current_supply, &self.params)
                 .map_err(|e| match e {
                     BlockError::MissingCoinbase => StorageError::MissingCoinbase,
                     BlockError::InvalidCoinbaseAmount => StorageError::InvalidCoinbaseAmount,
@@ -402,21 +1252,68 @@ impl BlockchainStorage {
                     BlockError::SupplyOverflow => StorageError::BalanceOverflow,
                     BlockError::MaxSupplyExceeded { .. } => StorageError::InvalidChain,
                     _ => StorageError::InvalidTransaction,
-                })?;
+                })
+                .map_err(|error| RuleViolation::new("coinbase", error))?;
+        }
+
+        // 7. Validate transaction fees and, past the fork, chain IDs
+        for (index, tx) in block.transactions.iter().enumerate() {
+            tx.validate_fee().map_err(|_| {
+                RuleViolation::new("fee", StorageError::InvalidTransaction).at(index)
+            })?;
+            if let Err(TransactionError::WrongChainId { expected, got }) =
+                tx.validate_chain_id(&self.params, new_height)
+            {
+                return Err(RuleViolation::new(
+                    "chain_id",
+                    StorageError::WrongChainId { expected, got },
+                )
+                .at(index));
+            }
         }
 
-        // 7. Validate transaction fees
-        for tx in &block.transactions {
-            tx.validate_fee()
-                .map_err(|_| StorageError::InvalidTransaction)?;
+        // 8. Contract transactions may not buy more than the block's gas
+        let gas = opensyria_core::contract::block_gas(&block.transactions);
+        if gas > opensyria_core::contract::MAX_GAS_PER_BLOCK {
+            return Err(RuleViolation::new(
+                "block_gas",
+                StorageError::ContractError(format!(
+                    "block buys {} gas (max {})",
+                    gas,
+                    opensyria_core::contract::MAX_GAS_PER_BLOCK
+                )),
+            ));
         }
 
+        Ok(new_height)
+    }
+
+    /// Append block to chain (validates and stores)
+    ///
+    /// ✅  SECURITY FIX (CRITICAL-004): Now validates coinbase against current supply
+    /// Requires state_storage parameter to check total supply and prevent inflation attacks.
+    /// Ensures MAX_SUPPLY is never exceeded.
+    #[tracing::instrument(
+        name = "append_block",
+        skip_all,
+        fields(tx_count = block.transactions.len())
+    )]
+    pub fn append_block(
+        &self,
+        block: &Block,
+        state_storage: Option<&crate::state::StateStorage>,
+    ) -> Result<(), StorageError> {
+        let new_height = self
+            .check_next_block(block, state_storage, true)
+            .map_err(|violation| violation.error)?;
+
         // Use atomic batch for all storage operations
         let mut batch = WriteBatch::default();
         let block_hash = block.hash();
 
-        // Store block
-        let block_data = crate::bincode_helpers::serialize(block)?;
+        // Store block (a thin node keeps its header and watched transactions)
+        let stored = self.stored_block(block);
+        let block_data = crate::bincode_helpers::serialize(&stored)?;
         batch.put(block_hash, &block_data);
 
         // Update height mapping
@@ -434,8 +1331,23 @@ impl BlockchainStorage {
             .ok_or(StorageError::ColumnFamilyNotFound)?;
         batch.put_cf(&cf_block_hash, block_hash, new_height.to_le_bytes());
 
+        // Index block producer
+        if let Some(producer) = block.producer() {
+            batch.put(
+                Self::producer_key(new_height),
+                crate::bincode_helpers::serialize(&producer)?,
+            );
+        }
+
+        // A block reconnected after a reorg is no longer stale
+        let cf_stale = self
+            .db
+            .cf_handle(CF_STALE_BLOCKS)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        batch.delete_cf(&cf_stale, Self::stale_key(new_height, &block_hash));
+
         // Index transactions
-        for (tx_idx, tx) in block.transactions.iter().enumerate() {
+        for (tx_idx, tx) in stored.transactions.iter().enumerate() {
             let tx_hash = tx.hash();
             
             // Index: tx_hash → (block_height, tx_index)
@@ -445,7 +1357,7 @@ impl BlockchainStorage {
             batch.put_cf(&cf_tx, tx_hash, tx_location);
             
             // Index: from_address → append tx_hash
-            if !tx.is_coinbase() {
+            if !tx.is_coinbase() && self.indexes_address(&tx.from) {
                 let cf_addr = self.db.cf_handle(CF_ADDRESS_INDEX)
                     .ok_or(StorageError::ColumnFamilyNotFound)?;
                 let addr_key = tx.from.0;
@@ -462,23 +1374,37 @@ impl BlockchainStorage {
             }
             
             // Index: to_address → append tx_hash
-            let cf_addr = self.db.cf_handle(CF_ADDRESS_INDEX)
-                .ok_or(StorageError::ColumnFamilyNotFound)?;
-            let addr_key = tx.to.0;
-            
-            let mut tx_list: Vec<[u8; 32]> = if let Some(data) = self.db.get_cf(&cf_addr, addr_key)? {
-                crate::bincode_helpers::deserialize(&data)?
-            } else {
-                Vec::new()
-            };
-            
-            tx_list.push(tx_hash);
-            batch.put_cf(&cf_addr, addr_key, crate::bincode_helpers::serialize(&tx_list)?);
+            if self.indexes_address(&tx.to) {
+                let cf_addr = self
+                    .db
+                    .cf_handle(CF_ADDRESS_INDEX)
+                    .ok_or(StorageError::ColumnFamilyNotFound)?;
+                let addr_key = tx.to.0;
+
+                let mut tx_list: Vec<[u8; 32]> =
+                    if let Some(data) = self.db.get_cf(&cf_addr, addr_key)? {
+                        crate::bincode_helpers::deserialize(&data)?
+                    } else {
+                        Vec::new()
+                    };
+
+                tx_list.push(tx_hash);
+                batch.put_cf(
+                    &cf_addr,
+                    addr_key,
+                    crate::bincode_helpers::serialize(&tx_list)?,
+                );
+            }
         }
 
         // Commit atomic batch
         self.db.write(batch)?;
 
+        self.publish(NodeEvent::BlockConnected {
+            height: new_height,
+            block: block.clone(),
+        });
+
         Ok(())
     }
 
@@ -508,6 +1434,302 @@ impl BlockchainStorage {
         Ok(())
     }
 
+    /// Append block after checking it against built-in and received checkpoints
+    /// إضافة كتلة بعد التحقق منها مقابل نقاط الفحص المدمجة والمستلمة
+    pub fn append_block_with_registry(
+        &self,
+        block: &Block,
+        checkpoints: &opensyria_consensus::CheckpointRegistry,
+        state_storage: Option<&crate::state::StateStorage>,
+    ) -> Result<(), StorageError> {
+        let new_height = self.get_chain_height()? + 1;
+        let block_hash = block.hash();
+
+        if let Err(e) = checkpoints.verify(new_height, &block_hash) {
+            return Err(StorageError::CheckpointMismatch {
+                height: new_height,
+                expected: format!("{}", e),
+                got: format!("{:x?}", &block_hash[..4]),
+            });
+        }
+
+        self.append_block(block, state_storage)
+    }
+
+    /// Persist a signed checkpoint received from the network
+    /// حفظ نقطة فحص موقعة مستلمة من الشبكة
+    pub fn put_signed_checkpoint(
+        &self,
+        checkpoint: &opensyria_consensus::SignedCheckpoint,
+    ) -> Result<(), StorageError> {
+        let mut key = CHECKPOINT_PREFIX.to_vec();
+        key.extend_from_slice(&checkpoint.height.to_be_bytes());
+        self.db
+            .put(key, crate::bincode_helpers::serialize(checkpoint)?)?;
+        Ok(())
+    }
+
+    /// Load all persisted signed checkpoints, ordered by height
+    pub fn get_signed_checkpoints(
+        &self,
+    ) -> Result<Vec<opensyria_consensus::SignedCheckpoint>, StorageError> {
+        let mut checkpoints = Vec::new();
+        for item in self.db.prefix_iterator(CHECKPOINT_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(CHECKPOINT_PREFIX) {
+                break;
+            }
+            checkpoints.push(crate::bincode_helpers::deserialize(&value)?);
+        }
+        Ok(checkpoints)
+    }
+
+    /// Find a checkpoint contradicted by the stored chain
+    /// البحث عن نقطة فحص تتعارض مع السلسلة المخزنة
+    ///
+    /// Blocks are checked against checkpoints on append, but a checkpoint
+    /// received after its height was stored can only be reconciled here.
+    pub fn find_checkpoint_conflict(
+        &self,
+        checkpoints: &opensyria_consensus::CheckpointRegistry,
+    ) -> Result<Option<Anomaly>, StorageError> {
+        for checkpoint in checkpoints.all() {
+            if let Some(got) = self.get_block_hash_by_height(checkpoint.height)? {
+                if got != checkpoint.hash {
+                    return Ok(Some(Anomaly::CheckpointMismatch {
+                        height: checkpoint.height,
+                        expected: checkpoint.hash,
+                        got,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Start an empty chain from the block a state snapshot was taken at
+    /// بدء سلسلة فارغة من كتلة لقطة الحالة
+    ///
+    /// The block becomes the tip at the anchor's height without the blocks
+    /// before it, which the snapshot makes unnecessary; later blocks are
+    /// appended and validated as usual. The block's proof of work and merkle
+    /// root are still checked, but its link to earlier history rests on
+    /// `anchor`, which must come from a trusted source and name this block.
+    ///
+    /// `window_headers` are the snapshot's headers from
+    /// [`Self::snapshot_window_headers`]; they must link to the block by hash
+    /// and keep the timestamp the next retarget measures from.
+    pub fn import_snapshot_base(
+        &self,
+        anchor: &ChainAnchor,
+        block: &Block,
+        window_headers: &[BlockHeader],
+    ) -> Result<(), StorageError> {
+        let height = anchor.height;
+        if height == 0 || self.get_chain_height()? != 0 {
+            return Err(StorageError::InvalidChain);
+        }
+        if hex::encode(block.hash()) != anchor.block_hash {
+            return Err(StorageError::InvalidSnapshot(
+                "base block does not match the trusted anchor".to_string(),
+            ));
+        }
+        if !block.header.meets_difficulty() {
+            return Err(StorageError::InvalidProofOfWork);
+        }
+        if !block.verify_merkle_root() {
+            return Err(StorageError::InvalidMerkleRoot);
+        }
+        let window_start = self.snapshot_window_start(height);
+        if window_headers.len() as u64 != window_start.map_or(0, |start| height - start) {
+            return Err(StorageError::InvalidSnapshot(
+                "wrong number of retarget window headers".to_string(),
+            ));
+        }
+        let mut parent = block.header.previous_hash;
+        for header in window_headers.iter().rev() {
+            if header.hash() != parent || !header.meets_difficulty() {
+                return Err(StorageError::InvalidSnapshot(
+                    "retarget window headers do not lead to the base block".to_string(),
+                ));
+            }
+            parent = header.previous_hash;
+        }
+
+        let mut batch = WriteBatch::default();
+        let block_hash = block.hash();
+        if let (Some(start), Some(header)) = (window_start, window_headers.first()) {
+            let mut window = start.to_le_bytes().to_vec();
+            window.extend_from_slice(&header.timestamp.to_le_bytes());
+            batch.put(SNAPSHOT_WINDOW_KEY, window);
+        }
+        batch.put(
+            block_hash,
+            crate::bincode_helpers::serialize(&self.stored_block(block))?,
+        );
+        batch.put(format!("height_{}", height).as_bytes(), block_hash);
+        batch.put(b"chain_height", height.to_le_bytes());
+        batch.put(b"chain_tip", block_hash);
+        batch.put(SNAPSHOT_BASE_KEY, height.to_le_bytes());
+        if let Some(producer) = block.producer() {
+            batch.put(
+                Self::producer_key(height),
+                crate::bincode_helpers::serialize(&producer)?,
+            );
+        }
+
+        let cf_block_hash = self
+            .db
+            .cf_handle(CF_BLOCK_HASH_INDEX)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        batch.put_cf(&cf_block_hash, block_hash, height.to_le_bytes());
+
+        self.db.write(batch)?;
+
+        self.publish(NodeEvent::BlockConnected {
+            height,
+            block: block.clone(),
+        });
+
+        Ok(())
+    }
+
+    /// Height the chain was started from by [`Self::import_snapshot_base`]
+    ///
+    /// Blocks below this height are not stored and cannot be reverted to.
+    pub fn snapshot_base(&self) -> Result<Option<u64>, StorageError> {
+        match self.db.get(SNAPSHOT_BASE_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.try_into().map_err(|_| StorageError::InvalidChain)?;
+                Ok(Some(u64::from_le_bytes(bytes)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn producer_key(height: u64) -> Vec<u8> {
+        let mut key = PRODUCER_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Miner of the block at `height`
+    /// منتج الكتلة عند ارتفاع معين
+    pub fn get_block_producer(&self, height: u64) -> Result<Option<BlockProducer>, StorageError> {
+        match self.db.get(Self::producer_key(height))? {
+            Some(data) => Ok(Some(crate::bincode_helpers::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Miners of the blocks in [start, end], in height order
+    /// منتجو الكتل ضمن نطاق من الارتفاعات
+    ///
+    /// Read from the producer index, so blocks themselves are not decoded.
+    pub fn get_block_producers(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<(u64, BlockProducer)>, StorageError> {
+        let mut producers = Vec::new();
+        let from = Self::producer_key(start);
+        let mode = rocksdb::IteratorMode::From(&from, rocksdb::Direction::Forward);
+        for item in self.db.iterator(mode) {
+            let (key, value) = item?;
+            let Some(suffix) = key.strip_prefix(PRODUCER_PREFIX) else {
+                break;
+            };
+            let height_bytes: [u8; 8] =
+                suffix.try_into().map_err(|_| StorageError::InvalidChain)?;
+            let height = u64::from_be_bytes(height_bytes);
+            if height > end {
+                break;
+            }
+            producers.push((height, crate::bincode_helpers::deserialize(&value)?));
+        }
+        Ok(producers)
+    }
+
+    fn stale_key(height: u64, hash: &[u8; 32]) -> Vec<u8> {
+        let mut key = height.to_be_bytes().to_vec();
+        key.extend_from_slice(hash);
+        key
+    }
+
+    /// Blocks reverted from height `height`, in hash order
+    /// الكتل المتروكة عند ارتفاع معين
+    pub fn get_stale_blocks(&self, height: u64) -> Result<Vec<Block>, StorageError> {
+        self.require_full("stale blocks")?;
+        let cf = self
+            .db
+            .cf_handle(CF_STALE_BLOCKS)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let prefix = height.to_be_bytes();
+        let mut blocks = Vec::new();
+        let mode = rocksdb::IteratorMode::From(&prefix, rocksdb::Direction::Forward);
+        for item in self.db.iterator_cf(&cf, mode) {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            blocks.push(crate::bincode_helpers::deserialize(&value)?);
+        }
+        Ok(blocks)
+    }
+
+    /// Up to `limit` stale blocks with their original heights, highest first
+    /// أحدث الكتل المتروكة مع ارتفاعاتها الأصلية
+    pub fn get_recent_stale_blocks(&self, limit: usize) -> Result<Vec<(u64, Block)>, StorageError> {
+        self.require_full("stale blocks")?;
+        let cf = self
+            .db
+            .cf_handle(CF_STALE_BLOCKS)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let mut blocks = Vec::new();
+        for item in self
+            .db
+            .iterator_cf(&cf, rocksdb::IteratorMode::End)
+            .take(limit)
+        {
+            let (key, value) = item?;
+            let height_bytes: [u8; 8] = key
+                .get(..8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(StorageError::InvalidChain)?;
+            blocks.push((
+                u64::from_be_bytes(height_bytes),
+                crate::bincode_helpers::deserialize(&value)?,
+            ));
+        }
+        Ok(blocks)
+    }
+
+    /// Stale blocks per block produced over the last `window` heights
+    /// معدل الكتل المتروكة خلال آخر عدد من الكتل
+    ///
+    /// Counts stale blocks at heights above `tip - window` against the main
+    /// chain blocks and stale blocks at those heights together.
+    pub fn orphan_rate(&self, window: u64) -> Result<f64, StorageError> {
+        let height = self.get_chain_height()?;
+        let start = height.saturating_sub(window) + 1;
+        let cf = self
+            .db
+            .cf_handle(CF_STALE_BLOCKS)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let from = start.to_be_bytes();
+        let mode = rocksdb::IteratorMode::From(&from, rocksdb::Direction::Forward);
+        let mut stale = 0u64;
+        for item in self.db.iterator_cf(&cf, mode) {
+            item?;
+            stale += 1;
+        }
+        let produced = (height + 1 - start) + stale;
+        if produced == 0 {
+            return Ok(0.0);
+        }
+        Ok(stale as f64 / produced as f64)
+    }
+
     /// Get blocks in range [start_height, end_height]
     pub fn get_block_range(&self, start: u64, end: u64) -> Result<Vec<Block>, StorageError> {
         let mut blocks = Vec::new();
@@ -525,6 +1747,8 @@ impl BlockchainStorage {
 
     /// Revert blockchain to specified height (for chain reorganizations)
     /// إعادة سلسلة الكتل إلى ارتفاع محدد (لإعادة تنظيم السلسلة)
+    ///
+    /// On a thin node the reverted blocks hold only what was stored.
     pub fn revert_to_height(&self, target_height: u64) -> Result<Vec<Block>, StorageError> {
         let current_height = self.get_chain_height()?;
 
@@ -532,28 +1756,50 @@ impl BlockchainStorage {
             return Ok(Vec::new()); // Nothing to revert
         }
 
+        // History below a snapshot base was never stored
+        if let Some(base) = self.snapshot_base()? {
+            if target_height < base {
+                return Err(StorageError::ReorgTooDeep {
+                    depth: current_height - target_height,
+                    max: current_height - base,
+                });
+            }
+        }
+
         let mut reverted_blocks = Vec::new();
+        let mut reverted_heights = Vec::new();
 
         // Collect blocks that will be reverted (for state rollback)
         for height in (target_height + 1)..=current_height {
-            if let Some(block) = self.get_block_by_height(height)? {
+            if let Some(block) = self.load_block_by_height(height)? {
                 reverted_blocks.push(block);
+                reverted_heights.push(height);
             }
         }
 
         // Use atomic batch to remove all blocks at once
         let mut batch = WriteBatch::default();
+        let cf_stale = self
+            .db
+            .cf_handle(CF_STALE_BLOCKS)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
 
         for height in (target_height + 1)..=current_height {
-            if let Some(block) = self.get_block_by_height(height)? {
+            if let Some(block) = self.load_block_by_height(height)? {
                 let block_hash = block.hash();
 
-                // Delete block data
+                // Move block data to the stale blocks kept for forensics
+                batch.put_cf(
+                    &cf_stale,
+                    Self::stale_key(height, &block_hash),
+                    crate::bincode_helpers::serialize(&block)?,
+                );
                 batch.delete(&block_hash);
 
                 // Delete height index
                 let height_key = format!("height_{}", height);
                 batch.delete(height_key.as_bytes());
+                batch.delete(Self::producer_key(height));
             }
         }
 
@@ -562,8 +1808,8 @@ impl BlockchainStorage {
 
         // Update chain tip to target height's block
         if target_height > 0 {
-            if let Some(block) = self.get_block_by_height(target_height)? {
-                batch.put(b"chain_tip", &block.hash());
+            if let Some(hash) = self.get_block_hash_by_height(target_height)? {
+                batch.put(b"chain_tip", hash);
             }
         } else {
             // Reverted to genesis
@@ -573,39 +1819,82 @@ impl BlockchainStorage {
         // Commit all changes atomically
         self.db.write(batch)?;
 
+        // Announce disconnections from the old tip downwards
+        for (height, block) in reverted_heights.iter().zip(&reverted_blocks).rev() {
+            self.publish(NodeEvent::BlockDisconnected {
+                height: *height,
+                block: block.clone(),
+            });
+        }
+
         Ok(reverted_blocks)
     }
 
-    /// Handle chain reorganization - revert to fork point and apply new blocks
-    /// معالجة إعادة تنظيم السلسلة - العودة إلى نقطة التفرع وتطبيق الكتل الجديدة
-    pub fn reorganize(
+    /// Revert the chain to `target_height` and roll the state back with it
+    /// إعادة السلسلة والحالة إلى ارتفاع محدد
+    ///
+    /// While the state follows the chain, each disconnected block is reverted
+    /// on it newest first, leaving the state at `target_height`; state that
+    /// was never in step is left alone. Returns the disconnected blocks in
+    /// ascending height.
+    pub fn disconnect_to_height(
         &self,
-        fork_height: u64,
-        new_blocks: Vec<Block>,
+        target_height: u64,
         state_storage: Option<&crate::state::StateStorage>,
     ) -> Result<Vec<Block>, StorageError> {
-        use opensyria_core::MAX_REORG_DEPTH;
-        
         let current_height = self.get_chain_height()?;
-        
+
         // Enforce maximum reorganization depth
-        let reorg_depth = current_height.saturating_sub(fork_height);
-        if reorg_depth > MAX_REORG_DEPTH {
+        let reorg_depth = current_height.saturating_sub(target_height);
+        if reorg_depth > self.params.max_reorg_depth {
             return Err(StorageError::ReorgTooDeep {
                 depth: reorg_depth,
-                max: MAX_REORG_DEPTH,
+                max: self.params.max_reorg_depth,
             });
         }
 
+        let follows = match state_storage {
+            Some(state) => state.get_state_height()? == current_height,
+            None => false,
+        };
+        let disconnected = self.revert_to_height(target_height)?;
+        if let (true, Some(state)) = (follows, state_storage) {
+            for (offset, block) in disconnected.iter().enumerate().rev() {
+                let height = target_height + 1 + offset as u64;
+                state.revert_block_atomic(height, &block.transactions)?;
+            }
+        }
+        Ok(disconnected)
+    }
+
+    /// Handle chain reorganization - revert to fork point and apply new blocks
+    /// معالجة إعادة تنظيم السلسلة - العودة إلى نقطة التفرع وتطبيق الكتل الجديدة
+    ///
+    /// The state, while it follows the chain, is rolled back to the fork point
+    /// and then replays the new blocks.
+    pub fn reorganize(
+        &self,
+        fork_height: u64,
+        new_blocks: Vec<Block>,
+        state_storage: Option<&crate::state::StateStorage>,
+    ) -> Result<Vec<Block>, StorageError> {
         // Step 1: Revert to fork point
-        let reverted_blocks = self.revert_to_height(fork_height)?;
+        let reverted_blocks = self.disconnect_to_height(fork_height, state_storage)?;
 
         // Step 2: Apply new blocks with supply validation
         for block in new_blocks {
+            let height = self.get_chain_height()?;
+            let follows = match state_storage {
+                Some(state) => state.get_state_height()? == height,
+                None => false,
+            };
             self.append_block(&block, state_storage)?;
+            if let (true, Some(state)) = (follows, state_storage) {
+                state.apply_block(height + 1, &block)?;
+            }
         }
 
-        // Return reverted blocks so state can be rolled back
+        // Return reverted blocks so the mempool can take their transactions back
         Ok(reverted_blocks)
     }
 
@@ -699,6 +1988,7 @@ impl BlockchainStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use opensyria_core::constants::CHAIN_ID_MAINNET;
     use tempfile::tempdir;
 
     /// Helper function to mine a block for testing
@@ -834,6 +2124,294 @@ mod tests {
         assert_eq!(b3.hash(), block3_fork.hash());
     }
 
+    #[test]
+    fn test_signed_checkpoints_persist_and_gate_blocks() {
+        use opensyria_consensus::{CheckpointAuthority, CheckpointRegistry, SignedCheckpoint};
+        use opensyria_core::crypto::KeyPair;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let signer = KeyPair::generate();
+        let authority = CheckpointAuthority::new(vec![signer.public_key()], 1).unwrap();
+
+        let block = Block::genesis();
+        let mut wrong = SignedCheckpoint::new(CHAIN_ID_MAINNET, 1, [9u8; 32]);
+        wrong.sign(&signer);
+        let mut right = SignedCheckpoint::new(CHAIN_ID_MAINNET, 2, [5u8; 32]);
+        right.sign(&signer);
+        storage.put_signed_checkpoint(&right).unwrap();
+        storage.put_signed_checkpoint(&wrong).unwrap();
+
+        let loaded = storage.get_signed_checkpoints().unwrap();
+        assert_eq!(loaded, vec![wrong, right]);
+
+        let mut registry = CheckpointRegistry::new(false).with_authority(authority);
+        for checkpoint in loaded {
+            registry.add_signed(checkpoint).unwrap();
+        }
+        assert!(matches!(
+            storage.append_block_with_registry(&block, &registry, None),
+            Err(StorageError::CheckpointMismatch { height: 1, .. })
+        ));
+        assert_eq!(storage.get_chain_height().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_checkpoint_conflict_with_stored_chain() {
+        use opensyria_consensus::{CheckpointAuthority, CheckpointRegistry, SignedCheckpoint};
+        use opensyria_core::crypto::KeyPair;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        let signer = KeyPair::generate();
+        let authority = CheckpointAuthority::new(vec![signer.public_key()], 1).unwrap();
+        let mut registry = CheckpointRegistry::new(false).with_authority(authority);
+        assert_eq!(storage.find_checkpoint_conflict(&registry).unwrap(), None);
+
+        // A checkpoint for a height we have not stored yet is not a conflict
+        let mut ahead = SignedCheckpoint::new(CHAIN_ID_MAINNET, 2, [5u8; 32]);
+        ahead.sign(&signer);
+        registry.add_signed(ahead).unwrap();
+        assert_eq!(storage.find_checkpoint_conflict(&registry).unwrap(), None);
+
+        let mut wrong = SignedCheckpoint::new(CHAIN_ID_MAINNET, 1, [9u8; 32]);
+        wrong.sign(&signer);
+        registry.add_signed(wrong).unwrap();
+        assert_eq!(
+            storage.find_checkpoint_conflict(&registry).unwrap(),
+            Some(Anomaly::CheckpointMismatch {
+                height: 1,
+                expected: [9u8; 32],
+                got: genesis.hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_import_snapshot_base() {
+        let dir = tempdir().unwrap();
+        let mut storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let params = ChainParams::regtest();
+        storage.set_chain_params(params.clone());
+        assert_eq!(storage.snapshot_base().unwrap(), None);
+
+        // Headers 91..=99 open the retarget window the base at 100 falls in
+        let difficulty = params.genesis_difficulty;
+        let mut window = Vec::new();
+        let mut parent = [3u8; 32];
+        for offset in 0..9 {
+            let mut block = Block::new(parent, vec![], difficulty);
+            block.header.timestamp -= 1_000 - offset * 100;
+            let block = mine_block(block);
+            parent = block.hash();
+            window.push(block.header);
+        }
+        let mut base = Block::new(parent, vec![], difficulty);
+        base.header.timestamp -= 10;
+        let base = mine_block(base);
+        let anchor = |height, block: &Block| ChainAnchor::new(height, &block.hash(), &[0u8; 32], 0);
+
+        // The block must be the one the trusted anchor names, and the window
+        // headers must lead to it
+        let other = mine_block(Block::new([4u8; 32], vec![], difficulty));
+        assert!(storage
+            .import_snapshot_base(&anchor(100, &other), &other, &window)
+            .is_err());
+        assert!(storage
+            .import_snapshot_base(&anchor(100, &base), &base, &window[1..])
+            .is_err());
+        let mut broken = window.clone();
+        broken[4].timestamp += 1;
+        assert!(storage
+            .import_snapshot_base(&anchor(100, &base), &base, &broken)
+            .is_err());
+        storage
+            .import_snapshot_base(&anchor(100, &base), &base, &window)
+            .unwrap();
+        assert_eq!(storage.get_chain_height().unwrap(), 100);
+        assert_eq!(storage.get_chain_tip().unwrap(), Some(base.hash()));
+        assert_eq!(storage.snapshot_base().unwrap(), Some(100));
+        assert_eq!(
+            storage.get_block_height_by_hash(&base.hash()).unwrap(),
+            Some(100)
+        );
+
+        // The window start's timestamp is kept for the retarget at 101
+        let mut next = Block::new(base.hash(), vec![], difficulty);
+        next.header.timestamp = base.header.timestamp + 60;
+        let next = mine_block(next);
+        storage.put_block(&next).unwrap();
+        storage.set_block_height(101, &next.hash()).unwrap();
+        storage.set_chain_height(101).unwrap();
+        storage.set_chain_tip(&next.hash()).unwrap();
+        assert!(storage.next_difficulty().is_ok());
+
+        // Only an empty chain can be started from a snapshot
+        assert!(storage
+            .import_snapshot_base(&anchor(200, &next), &next, &[])
+            .is_err());
+
+        // History below the base cannot be reverted to
+        assert!(matches!(
+            storage.revert_to_height(50),
+            Err(StorageError::ReorgTooDeep { .. })
+        ));
+        assert_eq!(storage.revert_to_height(100).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_window_headers() {
+        let dir = tempdir().unwrap();
+        let mut storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        storage.set_chain_params(ChainParams::regtest());
+
+        let mut previous = Block::genesis();
+        for height in 1..=25u64 {
+            let block = if height == 1 {
+                previous.clone()
+            } else {
+                Block::new(previous.hash(), vec![], 8)
+            };
+            storage.put_block(&block).unwrap();
+            storage.set_block_height(height, &block.hash()).unwrap();
+            previous = block;
+        }
+        storage.set_chain_height(25).unwrap();
+
+        // The retarget at tip 21 measures from 11; a snapshot at 21 needs
+        // 11..=20, one at 22 looks ahead to the retarget at 31
+        let heights = |height| -> Vec<[u8; 32]> {
+            storage
+                .snapshot_window_headers(height)
+                .unwrap()
+                .iter()
+                .map(BlockHeader::hash)
+                .collect()
+        };
+        let hashes = |range: std::ops::Range<u64>| -> Vec<[u8; 32]> {
+            range
+                .map(|height| storage.get_block_hash_by_height(height).unwrap().unwrap())
+                .collect()
+        };
+        assert_eq!(heights(21), hashes(11..21));
+        assert_eq!(heights(22), hashes(21..22));
+        assert_eq!(heights(25), hashes(21..25));
+        assert!(heights(1).is_empty());
+    }
+
+    #[test]
+    fn test_block_locator_heights() {
+        assert!(block_locator_heights(0).is_empty());
+        assert_eq!(block_locator_heights(1), vec![1]);
+        assert_eq!(block_locator_heights(5), vec![5, 4, 3, 2, 1]);
+
+        let heights = block_locator_heights(1_000_000);
+        assert_eq!(
+            &heights[..10],
+            &(999_991..=1_000_000).rev().collect::<Vec<_>>()[..]
+        );
+        assert_eq!(heights[10], 999_989);
+        assert_eq!(heights[11], 999_985);
+        assert_eq!(*heights.last().unwrap(), 1);
+        assert!(heights.len() <= MAX_LOCATOR_HASHES);
+        assert!(heights.windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn test_locator_finds_fork_and_serves_headers() {
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let cf = storage.db.cf_handle(CF_BLOCK_HASH_INDEX).unwrap();
+
+        let mut previous = Block::genesis();
+        let mut chain = Vec::new();
+        for height in 1..=30u64 {
+            let block = if height == 1 {
+                previous.clone()
+            } else {
+                Block::new(previous.hash(), vec![], 16)
+            };
+            storage.put_block(&block).unwrap();
+            storage.set_block_height(height, &block.hash()).unwrap();
+            storage
+                .db
+                .put_cf(&cf, block.hash(), height.to_le_bytes())
+                .unwrap();
+            previous = block.clone();
+            chain.push(block);
+        }
+        storage.set_chain_height(30).unwrap();
+
+        let locator = storage.get_block_locator().unwrap();
+        assert_eq!(locator[0], chain[29].hash());
+        assert_eq!(*locator.last().unwrap(), chain[0].hash());
+
+        // A peer that diverged after height 20 sends unknown hashes first
+        let peer_locator = [[0xAB; 32], chain[19].hash(), chain[0].hash()];
+        assert_eq!(storage.find_locator_fork(&peer_locator).unwrap(), 20);
+        assert_eq!(storage.find_locator_fork(&[[0xCD; 32]]).unwrap(), 0);
+
+        let headers = storage
+            .get_headers_after_locator(&peer_locator, Some(chain[24].hash()), 100)
+            .unwrap();
+        assert_eq!(headers.len(), 5);
+        assert_eq!(headers[0].hash(), chain[20].hash());
+        assert_eq!(headers[4].hash(), chain[24].hash());
+
+        let headers = storage
+            .get_headers_after_locator(&peer_locator, None, 2)
+            .unwrap();
+        assert_eq!(headers.len(), 2);
+        assert!(storage
+            .get_headers_after_locator(&peer_locator, None, 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_revert_publishes_disconnect_events() {
+        use opensyria_core::{EventBus, NodeEvent};
+
+        let dir = tempdir().unwrap();
+        let mut storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let bus = EventBus::default();
+        storage.set_event_bus(bus.clone());
+
+        // Lay out heights 1..=3 directly, bypassing block validation
+        let mut previous = Block::genesis();
+        let mut chain = Vec::new();
+        for height in 1..=3u64 {
+            let block = if height == 1 {
+                previous.clone()
+            } else {
+                Block::new(previous.hash(), vec![], 16)
+            };
+            storage.put_block(&block).unwrap();
+            storage.set_block_height(height, &block.hash()).unwrap();
+            previous = block.clone();
+            chain.push(block);
+        }
+        storage.set_chain_height(3).unwrap();
+        storage.set_chain_tip(&previous.hash()).unwrap();
+
+        let mut events = bus.subscribe();
+        storage.revert_to_height(1).unwrap();
+
+        for (expected_height, expected) in [(3u64, &chain[2]), (2, &chain[1])] {
+            match events.try_recv().unwrap() {
+                NodeEvent::BlockDisconnected { height, block } => {
+                    assert_eq!(height, expected_height);
+                    assert_eq!(block.hash(), expected.hash());
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert!(events.try_recv().is_err());
+    }
+
     #[test]
     fn test_indexed_transaction_lookup() {
         use opensyria_core::{Transaction, crypto::KeyPair};
@@ -953,4 +2531,376 @@ mod tests {
         let height = storage.get_block_height_by_hash(&fake_hash).unwrap();
         assert_eq!(height, None);
     }
+
+    #[test]
+    fn test_rebuild_indexes_from_blocks() {
+        use opensyria_core::crypto::KeyPair;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        // Blocks written without indexes, as if the index entries were lost
+        let sender_key = KeyPair::generate();
+        let recipient = KeyPair::generate().public_key();
+        let mut tx = Transaction::new(sender_key.public_key(), recipient, 1000, 10, 0);
+        let signature = sender_key.sign(&tx.signing_hash());
+        tx = tx.with_signature(signature);
+        let block2 = Block::new(genesis.hash(), vec![tx.clone()], 16);
+        let block3 = Block::new(block2.hash(), vec![], 16);
+        storage.put_block(&block2).unwrap();
+        storage.put_block(&block3).unwrap();
+        storage.set_chain_tip(&block3.hash()).unwrap();
+        storage.db.put(b"height_7", [1u8; 32]).unwrap();
+
+        let report = storage.rebuild_indexes().unwrap();
+        assert_eq!(
+            report,
+            RebuildReport {
+                blocks: 3,
+                transactions: 1,
+                height: 3
+            }
+        );
+        assert_eq!(storage.get_chain_height().unwrap(), 3);
+        assert_eq!(
+            storage.get_block_by_height(2).unwrap().unwrap().hash(),
+            block2.hash()
+        );
+        assert!(storage.db.get(b"height_7").unwrap().is_none());
+        assert_eq!(
+            storage.get_block_height_by_hash(&block3.hash()).unwrap(),
+            Some(3)
+        );
+        let (found, height) = storage
+            .get_transaction_by_hash(&tx.hash())
+            .unwrap()
+            .unwrap();
+        assert_eq!((found.hash(), height), (tx.hash(), 2));
+        let cf_addr = storage.db.cf_handle(CF_ADDRESS_INDEX).unwrap();
+        let listed = storage.db.get_cf(&cf_addr, recipient.0).unwrap().unwrap();
+        let listed: Vec<[u8; 32]> = crate::bincode_helpers::deserialize(&listed).unwrap();
+        assert_eq!(listed, vec![tx.hash()]);
+        assert!(storage
+            .examine(DoctorOptions::default())
+            .unwrap()
+            .is_healthy());
+
+        // A missing block below the tip cannot be indexed around
+        storage.db.delete(block2.hash()).unwrap();
+        assert!(matches!(
+            storage.rebuild_indexes(),
+            Err(StorageError::InvalidChain)
+        ));
+    }
+
+    #[test]
+    fn test_block_producer_index() {
+        use opensyria_core::crypto::KeyPair;
+        use opensyria_core::CoinbaseSplit;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        let pool = KeyPair::generate().public_key();
+        let solo = KeyPair::generate().public_key();
+        let tagged = CoinbaseSplit::single(pool)
+            .with_extra_data(b"/syria-pool/".to_vec())
+            .unwrap()
+            .coinbase_transactions(opensyria_core::CHAIN_ID_MAINNET, 2, 0)
+            .unwrap();
+        let untagged = Transaction::coinbase(opensyria_core::CHAIN_ID_MAINNET, solo, 3, 0).unwrap();
+        let block2 = Block::new(genesis.hash(), tagged, 16);
+        let block3 = Block::new(block2.hash(), vec![untagged], 16);
+        storage.put_block(&block2).unwrap();
+        storage.put_block(&block3).unwrap();
+        storage.set_chain_tip(&block3.hash()).unwrap();
+        storage.rebuild_indexes().unwrap();
+
+        let producers = storage.get_block_producers(1, 3).unwrap();
+        assert_eq!(
+            producers,
+            vec![
+                (2, block2.producer().unwrap()),
+                (3, block3.producer().unwrap())
+            ]
+        );
+        assert_eq!(producers[0].1.tag(), Some("/syria-pool/"));
+        assert_eq!(
+            storage.get_block_producer(3).unwrap().unwrap().address,
+            solo
+        );
+
+        // Databases from before the index are backfilled when opened
+        storage
+            .db
+            .delete(BlockchainStorage::producer_key(2))
+            .unwrap();
+        storage
+            .db
+            .put(migration::SCHEMA_VERSION_KEY, 1u32.to_le_bytes())
+            .unwrap();
+        drop(storage);
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.get_block_producers(2, 2).unwrap().len(), 1);
+
+        storage.revert_to_height(2).unwrap();
+        assert!(storage.get_block_producer(3).unwrap().is_none());
+        assert_eq!(storage.get_block_producers(0, u64::MAX).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_next_difficulty_follows_chain_params() {
+        let dir = tempdir().unwrap();
+        let mut storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let params = ChainParams::testnet();
+        storage.set_chain_params(params.clone());
+
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+        assert_eq!(
+            storage.next_difficulty().unwrap(),
+            params.genesis_difficulty
+        );
+
+        // A block at another difficulty is rejected before anything else
+        let wrong = mine_block(Block::new(
+            genesis.hash(),
+            vec![],
+            params.genesis_difficulty + 1,
+        ));
+        assert!(matches!(
+            storage.append_block(&wrong, None),
+            Err(StorageError::UnexpectedDifficulty { expected, .. })
+                if expected == params.genesis_difficulty
+        ));
+
+        // Before the schedule activates the difficulty is not checked
+        let mut legacy = params.clone();
+        legacy.difficulty_schedule_height = 10;
+        storage.set_chain_params(legacy);
+        let rule = storage
+            .check_next_block(&wrong, None, true)
+            .err()
+            .map(|v| v.rule);
+        assert_ne!(rule, Some("difficulty"));
+        storage.set_chain_params(params.clone());
+
+        // One full interval at a tenth of the target block time
+        let interval = params.difficulty_adjustment_interval as u64;
+        let mut tip = genesis.clone();
+        for n in 1..=interval {
+            let mut block = Block::new(tip.hash(), vec![], params.genesis_difficulty);
+            block.header.timestamp =
+                genesis.header.timestamp + n * params.target_block_time_secs / 10;
+            storage.put_block(&block).unwrap();
+            storage.set_chain_tip(&block.hash()).unwrap();
+            storage.rebuild_indexes().unwrap();
+            tip = block;
+            if n < interval {
+                assert_eq!(
+                    storage.next_difficulty().unwrap(),
+                    params.genesis_difficulty
+                );
+            }
+        }
+
+        // Retargeted up, by at most a quarter
+        assert_eq!(
+            storage.next_difficulty().unwrap(),
+            params.genesis_difficulty * 5 / 4
+        );
+
+        // Regtest bounds keep the difficulty at the minimum
+        let regtest = ChainParams::regtest();
+        storage.set_chain_params(regtest.clone());
+        assert_eq!(storage.next_difficulty().unwrap(), regtest.min_difficulty);
+    }
+
+    #[test]
+    fn test_check_next_block_without_pow() {
+        use opensyria_core::crypto::KeyPair;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        // An unmined template passes once proof of work is skipped
+        let miner = KeyPair::generate().public_key();
+        let coinbase =
+            Transaction::coinbase(opensyria_core::CHAIN_ID_MAINNET, miner, 2, 0).unwrap();
+        let difficulty = storage.next_difficulty().unwrap();
+        let mut template = Block::new(genesis.hash(), vec![coinbase], difficulty);
+        template.header.timestamp = genesis.header.timestamp + 60;
+        while template.header.meets_difficulty() {
+            template.header.nonce += 1;
+        }
+        assert_eq!(storage.check_next_block(&template, None, false).unwrap(), 2);
+        let violation = storage.check_next_block(&template, None, true).unwrap_err();
+        assert_eq!(violation.rule, "proof_of_work");
+
+        template.header.merkle_root = [7u8; 32];
+        let violation = storage
+            .check_next_block(&template, None, false)
+            .unwrap_err();
+        assert_eq!((violation.rule, violation.tx_index), ("merkle_root", None));
+        assert!(matches!(violation.error, StorageError::InvalidMerkleRoot));
+
+        // Nothing was stored
+        assert_eq!(storage.get_chain_height().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_wrong_chain_id_rejected() {
+        use opensyria_core::crypto::KeyPair;
+
+        let dir = tempdir().unwrap();
+        let mut storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let params = ChainParams::testnet();
+        storage.set_chain_params(params.clone());
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        // Testnet enforces chain IDs from genesis, so a mainnet transaction
+        // cannot be replayed into a block
+        let miner = KeyPair::generate().public_key();
+        let replayed =
+            Transaction::coinbase(opensyria_core::CHAIN_ID_MAINNET, miner, 2, 0).unwrap();
+        let mut block = Block::new(genesis.hash(), vec![replayed], params.genesis_difficulty);
+        block.header.timestamp = genesis.header.timestamp + params.target_block_time_secs;
+        assert!(matches!(
+            storage.append_block(&mine_block(block), None),
+            Err(StorageError::WrongChainId { expected, got })
+                if expected == params.chain_id && got == opensyria_core::CHAIN_ID_MAINNET
+        ));
+    }
+
+    #[test]
+    fn test_secondary_follows_primary() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("blocks");
+        let storage = BlockchainStorage::open(path.clone()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        let secondary =
+            BlockchainStorage::open_secondary(path, dir.path().join("secondary")).unwrap();
+        assert_eq!(secondary.get_chain_tip().unwrap(), Some(genesis.hash()));
+
+        let block = Block::new(genesis.hash(), vec![], 16);
+        storage.put_block(&block).unwrap();
+        storage.set_chain_tip(&block.hash()).unwrap();
+        secondary.catch_up().unwrap();
+        assert_eq!(secondary.get_chain_tip().unwrap(), Some(block.hash()));
+    }
+
+    #[test]
+    fn test_reverted_blocks_kept_as_stale() {
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+        let block2 = Block::new(genesis.hash(), vec![], 16);
+        let block3 = Block::new(block2.hash(), vec![], 16);
+        storage.put_block(&block2).unwrap();
+        storage.put_block(&block3).unwrap();
+        storage.set_chain_tip(&block3.hash()).unwrap();
+        storage.rebuild_indexes().unwrap();
+        assert_eq!(storage.orphan_rate(1000).unwrap(), 0.0);
+
+        storage.revert_to_height(1).unwrap();
+        assert!(storage.get_block(&block3.hash()).unwrap().is_none());
+        let stale = storage.get_stale_blocks(3).unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].hash(), block3.hash());
+        assert!(storage.get_stale_blocks(1).unwrap().is_empty());
+
+        let recent = storage.get_recent_stale_blocks(10).unwrap();
+        assert_eq!(
+            recent
+                .iter()
+                .map(|(h, b)| (*h, b.hash()))
+                .collect::<Vec<_>>(),
+            vec![(3, block3.hash()), (2, block2.hash())]
+        );
+        assert_eq!(storage.get_recent_stale_blocks(1).unwrap().len(), 1);
+
+        // One main chain block (genesis) against two stale ones
+        let rate = storage.orphan_rate(1000).unwrap();
+        assert!((rate - 2.0 / 3.0).abs() < 1e-9);
+        assert!(storage
+            .examine(DoctorOptions::default())
+            .unwrap()
+            .is_healthy());
+    }
+
+    #[test]
+    fn test_legacy_transaction_layout_decodes() {
+        use opensyria_core::crypto::KeyPair;
+        use opensyria_core::TransactionV1;
+
+        let dir = tempdir().unwrap();
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        let sender = KeyPair::generate();
+        let miner = KeyPair::generate().public_key();
+        let coinbase =
+            Transaction::coinbase(opensyria_core::CHAIN_ID_MAINNET, miner, 2, 0).unwrap();
+        let transfer = Transaction::new(sender.public_key(), miner, 1_000, 100, 0);
+        let transfer = transfer
+            .clone()
+            .with_signature(sender.sign(&transfer.signing_hash()));
+        let block = Block::new(genesis.hash(), vec![coinbase, transfer], 16);
+
+        // Written as an older release encoded it
+        let transactions: Vec<TransactionV1> = block
+            .transactions
+            .iter()
+            .map(|tx| TransactionV1 {
+                chain_id: tx.chain_id,
+                from: tx.from,
+                to: tx.to,
+                amount: tx.amount,
+                fee: tx.fee,
+                nonce: tx.nonce,
+                signature: tx.signature.clone(),
+                data: tx.data.clone(),
+            })
+            .collect();
+        let legacy = BlockV1 {
+            header: block.header.clone(),
+            transactions,
+        };
+        storage
+            .db
+            .put(
+                block.hash(),
+                crate::bincode_helpers::serialize(&legacy).unwrap(),
+            )
+            .unwrap();
+
+        let stored = storage.get_block(&block.hash()).unwrap().unwrap();
+        assert!(stored.verify_merkle_root());
+        assert!(stored.transactions[1].verify().is_ok());
+
+        // Databases from before the layout change are re-encoded when opened
+        storage
+            .db
+            .put(migration::SCHEMA_VERSION_KEY, 1u32.to_le_bytes())
+            .unwrap();
+        drop(storage);
+        let storage = BlockchainStorage::open(dir.path().to_path_buf()).unwrap();
+        let data = storage.db.get(block.hash()).unwrap().unwrap();
+        assert!(!decode_stored_block(&data).unwrap().1);
+        assert_eq!(
+            storage.get_block(&block.hash()).unwrap().unwrap().hash(),
+            block.hash()
+        );
+    }
 }
@@ -0,0 +1,102 @@
+//! Contract execution while applying blocks
+//! تنفيذ العقود أثناء تطبيق الكتل
+//!
+//! Contract code, storage and receipts live in their own column family.
+//! Transactions run in block order; each one sees the balances and contract
+//! storage left by the transactions before it. Every contract transaction
+//! gets a [`ContractReceipt`] holding its result and what is needed to undo
+//! it on a reorg.
+
+use crate::state::StateStorage;
+use crate::StorageError;
+use opensyria_contracts::{ContractHost, HostFailure};
+use opensyria_core::crypto::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Outcome of one contract transaction
+/// إيصال تنفيذ معاملة العقد
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub struct ContractReceipt {
+    /// Gas consumed out of the transaction's limit
+    pub gas_used: u64,
+    /// Why execution failed; `None` on success
+    pub error: Option<String>,
+    /// Data returned by the contract
+    pub output: Vec<u8>,
+    /// Whether this transaction stored the contract's code
+    pub deployed: bool,
+    /// Payments made from the contract's balance
+    pub transfers: Vec<(PublicKey, u64)>,
+    /// Value each written storage key had before, to restore on revert
+    pub undo: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl ContractReceipt {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Contract writes made by a block, applied in one batch with the rest
+#[derive(Debug, Default)]
+pub(crate) struct ContractChanges {
+    /// Code stored by deployments
+    pub codes: HashMap<PublicKey, Vec<u8>>,
+    /// Final value of every storage key the block wrote
+    pub storage: HashMap<(PublicKey, Vec<u8>), Option<Vec<u8>>>,
+    /// Receipts keyed by transaction hash
+    pub receipts: Vec<([u8; 32], ContractReceipt)>,
+}
+
+/// Chain state as seen by a contract partway through a block
+pub(crate) struct BlockHost<'a> {
+    pub state: &'a StateStorage,
+    pub changes: &'a ContractChanges,
+    /// Net balance changes of the block's transactions so far
+    pub running: &'a HashMap<PublicKey, i128>,
+}
+
+impl BlockHost<'_> {
+    pub fn code(&self, contract: &PublicKey) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.changes.codes.get(contract) {
+            Some(code) => Ok(Some(code.clone())),
+            None => self.state.get_contract_code(contract),
+        }
+    }
+
+    pub fn storage(
+        &self,
+        contract: &PublicKey,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.changes.storage.get(&(*contract, key.to_vec())) {
+            Some(value) => Ok(value.clone()),
+            None => self.state.get_contract_storage(contract, key),
+        }
+    }
+}
+
+impl ContractHost for BlockHost<'_> {
+    fn storage_read(
+        &self,
+        contract: &PublicKey,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, HostFailure> {
+        self.storage(contract, key)
+            .map_err(|e| HostFailure(e.to_string()))
+    }
+
+    fn balance(&self, address: &PublicKey) -> Result<u64, HostFailure> {
+        let stored = self
+            .state
+            .get_balance(address)
+            .map_err(|e| HostFailure(e.to_string()))?;
+        let change = self.running.get(address).copied().unwrap_or(0);
+        // Spends may run ahead of receipts within a block; the block's
+        // final balances are checked once every transaction has run
+        Ok((stored as i128 + change).clamp(0, u64::MAX as i128) as u64)
+    }
+}
@@ -0,0 +1,353 @@
+//! Order book matching while applying blocks
+//! مطابقة دفتر الأوامر أثناء تطبيق الكتل
+//!
+//! Orders touched by a block are read once and updated in memory, alongside
+//! the block's [`AssetLedger`], so each operation sees the ones before it.
+//! Book sides are never loaded whole: the block's insertions and removals
+//! are kept aside and merged with the stored index only while looking for
+//! the best resting order. Every placement or cancellation gets a [`DexReceipt`]
+//! recording its settlement, which is also what reverting it needs.
+
+use crate::assets::AssetLedger;
+use crate::state::StateStorage;
+use crate::StorageError;
+use opensyria_core::asset::AssetId;
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::dex::{
+    escrow_address, DexError, Order, OrderId, OrderSide, OrderStatus, Trade, MAX_FILLS_PER_ORDER,
+};
+use opensyria_core::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Settlement of one DEX transaction
+/// إيصال تسوية عملية التداول
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub struct DexReceipt {
+    /// Fills of a placed order against resting orders, in matching order
+    pub trades: Vec<Trade>,
+    /// SYL returned to a buyer whose fills were below its limit price
+    pub refund: u64,
+    /// SYL or asset units a cancellation, or a placement cut off at
+    /// [`MAX_FILLS_PER_ORDER`], released from escrow
+    pub released: u64,
+}
+
+/// Changes a block made to the book index of one side of a market
+#[derive(Debug, Default)]
+pub(crate) struct BookSide {
+    /// Book keys inserted, with their order
+    pub added: BTreeMap<Vec<u8>, OrderId>,
+    /// Stored book keys deleted
+    pub removed: BTreeSet<Vec<u8>>,
+}
+
+/// Orders, book sides, trades and receipts touched by a block
+#[derive(Debug, Default)]
+pub(crate) struct OrderBook {
+    /// Entry per order; `None` if the order does not exist
+    pub orders: HashMap<OrderId, Option<Order>>,
+    /// Changes to each side touched by the block
+    pub sides: HashMap<(AssetId, OrderSide), BookSide>,
+    /// Trade index writes; `None` deletes
+    pub trades: Vec<(Vec<u8>, Option<Trade>)>,
+    /// Receipt writes by transaction hash; `None` deletes
+    pub receipts: Vec<([u8; 32], Option<DexReceipt>)>,
+}
+
+fn violation(e: DexError) -> StorageError {
+    StorageError::DexViolation(e.to_string())
+}
+
+/// Move SYL between accounts in the block's balance changes
+fn move_syl(changes: &mut HashMap<PublicKey, i128>, from: PublicKey, to: PublicKey, amount: u64) {
+    *changes.entry(from).or_insert(0) -= amount as i128;
+    *changes.entry(to).or_insert(0) += amount as i128;
+}
+
+/// Move asset units between accounts in the block's ledger
+fn move_asset(
+    state: &StateStorage,
+    ledger: &mut AssetLedger,
+    asset: &AssetId,
+    from: &PublicKey,
+    to: &PublicKey,
+    amount: u64,
+) -> Result<(), StorageError> {
+    ledger.debit(state, from, asset, amount)?;
+    // Supply is capped by a u64, so no balance can overflow
+    *ledger.balance(state, to, asset)? += amount;
+    Ok(())
+}
+
+/// Put what `order` still locks into escrow, or take it back out
+fn lock(
+    state: &StateStorage,
+    ledger: &mut AssetLedger,
+    changes: &mut HashMap<PublicKey, i128>,
+    order: &Order,
+    release: bool,
+) -> Result<(), StorageError> {
+    let (from, to) = if release {
+        (escrow_address(), order.owner)
+    } else {
+        (order.owner, escrow_address())
+    };
+    match order.side {
+        OrderSide::Buy => move_syl(changes, from, to, order.locked()),
+        OrderSide::Sell => move_asset(state, ledger, &order.asset, &from, &to, order.locked())?,
+    }
+    Ok(())
+}
+
+impl OrderBook {
+    pub fn order(
+        &mut self,
+        state: &StateStorage,
+        id: &OrderId,
+    ) -> Result<&mut Option<Order>, StorageError> {
+        Ok(match self.orders.entry(*id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(state.get_order(id)?),
+        })
+    }
+
+    fn existing_order(
+        &mut self,
+        state: &StateStorage,
+        id: &OrderId,
+    ) -> Result<&mut Order, StorageError> {
+        self.order(state, id)?
+            .as_mut()
+            .ok_or_else(|| violation(DexError::UnknownOrder(id.to_hex())))
+    }
+
+    /// Best resting order on one side, merging the block's changes with the
+    /// stored index
+    fn best(
+        &mut self,
+        state: &StateStorage,
+        asset: &AssetId,
+        side: OrderSide,
+    ) -> Result<Option<OrderId>, StorageError> {
+        let changes = self.sides.entry((*asset, side)).or_default();
+        let stored = state
+            .book_side(asset, side)
+            .find(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(true, |(key, _)| !changes.removed.contains(&key[..]))
+            })
+            .transpose()?;
+        Ok(match (stored, changes.added.first_key_value()) {
+            (Some((stored_key, _)), Some((added_key, id))) if **added_key <= *stored_key => {
+                Some(*id)
+            }
+            (Some((_, id)), _) => Some(id),
+            (None, added) => added.map(|(_, id)| *id),
+        })
+    }
+
+    fn rest(&mut self, order: &Order) {
+        let changes = self.sides.entry((order.asset, order.side)).or_default();
+        let key = StateStorage::order_book_key(order);
+        changes.removed.remove(&key);
+        changes.added.insert(key, order.id);
+    }
+
+    fn unrest(&mut self, order: &Order) {
+        let changes = self.sides.entry((order.asset, order.side)).or_default();
+        let key = StateStorage::order_book_key(order);
+        changes.added.remove(&key);
+        changes.removed.insert(key);
+    }
+
+    /// Place a new order and match it against the book
+    ///
+    /// Its `remaining` must equal its `amount`; its id is the hash of the
+    /// placing transaction.
+    pub fn place(
+        &mut self,
+        state: &StateStorage,
+        ledger: &mut AssetLedger,
+        changes: &mut HashMap<PublicKey, i128>,
+        mut order: Order,
+    ) -> Result<(), StorageError> {
+        ledger.existing_asset(state, &order.asset)?;
+        let (asset, side, price) = (order.asset, order.side, order.price);
+        let escrow = escrow_address();
+        lock(state, ledger, changes, &order, false)?;
+
+        let mut receipt = DexReceipt::default();
+        while order.remaining > 0 && receipt.trades.len() < MAX_FILLS_PER_ORDER {
+            let Some(maker_id) = self.best(state, &asset, side.opposite())? else {
+                break;
+            };
+            let mut maker = self.existing_order(state, &maker_id)?.clone();
+            if !side.crosses(price, maker.price) {
+                break;
+            }
+
+            let fill = order.remaining.min(maker.remaining);
+            let (buyer, seller) = match side {
+                OrderSide::Buy => (order.owner, maker.owner),
+                OrderSide::Sell => (maker.owner, order.owner),
+            };
+            move_asset(state, ledger, &asset, &escrow, &buyer, fill)?;
+            move_syl(changes, escrow, seller, maker.price * fill);
+            if side == OrderSide::Buy {
+                receipt.refund += (price - maker.price) * fill;
+            }
+
+            order.remaining -= fill;
+            maker.remaining -= fill;
+            if maker.remaining == 0 {
+                maker.status = OrderStatus::Filled;
+                self.unrest(&maker);
+            }
+            *self.order(state, &maker_id)? = Some(maker.clone());
+
+            let trade = Trade {
+                asset,
+                price: maker.price,
+                amount: fill,
+                maker: maker_id,
+                taker: order.id,
+                buyer,
+                seller,
+                height: order.height,
+            };
+            let key = StateStorage::trade_key(
+                &asset,
+                order.height,
+                order.index,
+                receipt.trades.len() as u32,
+            );
+            self.trades.push((key, Some(trade.clone())));
+            receipt.trades.push(trade);
+        }
+
+        if receipt.refund > 0 {
+            move_syl(changes, escrow, order.owner, receipt.refund);
+        }
+        if order.remaining == 0 {
+            order.status = OrderStatus::Filled;
+        } else if receipt.trades.len() == MAX_FILLS_PER_ORDER {
+            lock(state, ledger, changes, &order, true)?;
+            receipt.released = order.locked();
+            order.status = OrderStatus::Cancelled;
+        } else {
+            self.rest(&order);
+        }
+        let id = order.id;
+        self.receipts.push((id.0, Some(receipt)));
+        *self.order(state, &id)? = Some(order);
+        Ok(())
+    }
+
+    /// Cancel an open order of the sender and release what it locks
+    pub fn cancel(
+        &mut self,
+        state: &StateStorage,
+        ledger: &mut AssetLedger,
+        changes: &mut HashMap<PublicKey, i128>,
+        tx: &Transaction,
+        id: &OrderId,
+    ) -> Result<(), StorageError> {
+        let mut order = self.existing_order(state, id)?.clone();
+        if order.owner != tx.from {
+            return Err(violation(DexError::NotOrderOwner(tx.from.to_hex())));
+        }
+        if order.status != OrderStatus::Open {
+            return Err(violation(DexError::OrderClosed(id.to_hex())));
+        }
+
+        lock(state, ledger, changes, &order, true)?;
+        self.unrest(&order);
+        let receipt = DexReceipt {
+            released: order.locked(),
+            ..Default::default()
+        };
+        order.status = OrderStatus::Cancelled;
+        *self.order(state, id)? = Some(order);
+        self.receipts.push((tx.hash(), Some(receipt)));
+        Ok(())
+    }
+
+    /// Undo [`Self::place`]; later operations of the block must already be
+    /// undone
+    pub fn unplace(
+        &mut self,
+        state: &StateStorage,
+        ledger: &mut AssetLedger,
+        changes: &mut HashMap<PublicKey, i128>,
+        tx: &Transaction,
+    ) -> Result<(), StorageError> {
+        let receipt = state
+            .get_dex_receipt(&tx.hash())?
+            .ok_or(StorageError::InvalidChain)?;
+        let mut order = self
+            .order(state, &OrderId::of(tx))?
+            .take()
+            .ok_or(StorageError::InvalidChain)?;
+        match order.status {
+            OrderStatus::Open => self.unrest(&order),
+            // Cut off at MAX_FILLS_PER_ORDER: escrow the remainder again
+            OrderStatus::Cancelled => lock(state, ledger, changes, &order, false)?,
+            OrderStatus::Filled => {}
+        }
+
+        let escrow = escrow_address();
+        move_syl(changes, order.owner, escrow, receipt.refund);
+        for (seq, trade) in receipt.trades.iter().enumerate().rev() {
+            let mut maker = self.existing_order(state, &trade.maker)?.clone();
+            maker.remaining += trade.amount;
+            if maker.status == OrderStatus::Filled {
+                maker.status = OrderStatus::Open;
+                self.rest(&maker);
+            }
+            *self.order(state, &trade.maker)? = Some(maker);
+
+            move_asset(
+                state,
+                ledger,
+                &trade.asset,
+                &trade.buyer,
+                &escrow,
+                trade.amount,
+            )?;
+            move_syl(changes, trade.seller, escrow, trade.price * trade.amount);
+            let key = StateStorage::trade_key(&trade.asset, order.height, order.index, seq as u32);
+            self.trades.push((key, None));
+        }
+
+        order.remaining = order.amount;
+        lock(state, ledger, changes, &order, true)?;
+        self.receipts.push((tx.hash(), None));
+        Ok(())
+    }
+
+    /// Undo [`Self::cancel`]
+    pub fn uncancel(
+        &mut self,
+        state: &StateStorage,
+        ledger: &mut AssetLedger,
+        changes: &mut HashMap<PublicKey, i128>,
+        tx: &Transaction,
+        id: &OrderId,
+    ) -> Result<(), StorageError> {
+        let mut order = self.existing_order(state, id)?.clone();
+        if order.status != OrderStatus::Cancelled {
+            return Err(StorageError::InvalidChain);
+        }
+        order.status = OrderStatus::Open;
+        lock(state, ledger, changes, &order, false)?;
+        self.rest(&order);
+        *self.order(state, id)? = Some(order);
+        self.receipts.push((tx.hash(), None));
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+//! Key/value namespace audit and corruption repair
+//! تدقيق مساحات أسماء المفاتيح وإصلاح التلف
+//!
+//! Each database describes its key layout as a list of [`Namespace`]s: the
+//! shape of the keys (an exact key, or a prefix followed by a suffix of known
+//! length) and of the values (a fixed length or a bincode type that must
+//! decode completely). [`examine`] walks every column family, counts keys and
+//! bytes per namespace, and flags entries whose key or value does not fit.
+//! Flagged entries can be moved into the [`CF_QUARANTINE`] column family,
+//! where they no longer disturb the node but stay available for inspection.
+//!
+//! Keys that match no namespace are counted as unknown but never moved:
+//! they may belong to a newer binary or to a layout this table misses.
+
+use crate::StorageError;
+use rocksdb::{IteratorMode, WriteBatch, DB};
+
+/// Column family holding quarantined entries, keyed by
+/// `<column family> 0x00 <original key>`
+pub const CF_QUARANTINE: &str = "quarantine";
+
+/// Quarantine moves committed per write batch
+const QUARANTINE_BATCH_OPS: usize = 10_000;
+
+/// Shape of the key bytes following a namespace prefix
+#[derive(Debug, Clone, Copy)]
+pub enum Suffix {
+    /// Exactly this many bytes
+    Len(usize),
+    /// At least this many bytes
+    AtLeast(usize),
+    /// One or more ASCII digits
+    Digits,
+}
+
+/// Which keys belong to a namespace
+#[derive(Debug, Clone, Copy)]
+pub enum KeyShape {
+    /// This exact key
+    Exact(&'static [u8]),
+    /// A prefix followed by a suffix of the given shape
+    Prefix(&'static [u8], Suffix),
+    /// Any key of exactly this length that no prefix claims
+    Len(usize),
+}
+
+/// Expected value of a namespace
+#[derive(Debug, Clone, Copy)]
+pub enum ValueShape {
+    /// Exactly this many bytes
+    Len(usize),
+    /// A bincode value that decodes without leftover bytes
+    Decodes(fn(&[u8]) -> bool),
+    /// Opaque bytes (contract code and storage, partial transactions)
+    Any,
+}
+
+/// One kind of key stored in a column family
+/// نوع واحد من المفاتيح المخزنة في عائلة أعمدة
+#[derive(Debug, Clone, Copy)]
+pub struct Namespace {
+    /// Name shown in reports
+    pub name: &'static str,
+    /// Column family the keys live in
+    pub column_family: &'static str,
+    pub key: KeyShape,
+    pub value: ValueShape,
+}
+
+/// Whether `bytes` is exactly one bincode-encoded `T`
+pub(crate) fn decodes<T: bincode::Decode<()>>(bytes: &[u8]) -> bool {
+    matches!(
+        bincode::decode_from_slice::<T, _>(bytes, bincode::config::standard()),
+        Ok((_, read)) if read == bytes.len()
+    )
+}
+
+impl Namespace {
+    /// `Some(true)` if `key` belongs here, `Some(false)` if it carries this
+    /// namespace's prefix but has a malformed suffix, `None` otherwise
+    fn claims(&self, key: &[u8]) -> Option<bool> {
+        match self.key {
+            KeyShape::Exact(exact) => (key == exact).then_some(true),
+            KeyShape::Prefix(prefix, suffix) => {
+                let rest = key.strip_prefix(prefix)?;
+                Some(match suffix {
+                    Suffix::Len(len) => rest.len() == len,
+                    Suffix::AtLeast(len) => rest.len() >= len,
+                    Suffix::Digits => !rest.is_empty() && rest.iter().all(u8::is_ascii_digit),
+                })
+            }
+            KeyShape::Len(len) => (key.len() == len).then_some(true),
+        }
+    }
+
+    fn check_value(&self, value: &[u8]) -> Result<(), String> {
+        match self.value {
+            ValueShape::Len(len) if value.len() != len => {
+                Err(format!("value is {} bytes, expected {}", value.len(), len))
+            }
+            ValueShape::Decodes(check) if !check(value) => Err("value does not decode".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How [`examine`] treats corrupt entries
+/// خيارات فحص قاعدة البيانات
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DoctorOptions {
+    /// Move corrupt entries to the quarantine column family
+    pub quarantine: bool,
+    /// Corrupt entries listed individually in the report
+    pub max_reported: usize,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            quarantine: false,
+            max_reported: 100,
+        }
+    }
+}
+
+/// Keys and bytes found in one namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceStats {
+    pub name: &'static str,
+    pub column_family: &'static str,
+    pub keys: u64,
+    pub bytes: u64,
+    pub corrupt: u64,
+}
+
+/// One entry that does not fit its namespace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    pub column_family: &'static str,
+    pub key: Vec<u8>,
+    /// Namespace the key claims to belong to
+    pub namespace: &'static str,
+    pub reason: String,
+}
+
+/// Findings of a database scan
+/// نتائج فحص قاعدة البيانات
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorReport {
+    pub database: &'static str,
+    pub namespaces: Vec<NamespaceStats>,
+    /// Keys matching no namespace, per column family
+    pub unknown: Vec<(&'static str, u64)>,
+    /// First corrupt entries, up to [`DoctorOptions::max_reported`]
+    pub corrupt: Vec<CorruptEntry>,
+    pub corrupt_total: u64,
+    /// Entries moved to quarantine by this scan
+    pub quarantined: u64,
+    /// Entries already in quarantine before this scan
+    pub previously_quarantined: u64,
+}
+
+impl DoctorReport {
+    /// Whether the scan found nothing wrong
+    pub fn is_healthy(&self) -> bool {
+        self.corrupt_total == 0
+    }
+}
+
+/// Outcome of [`crate::BlockchainStorage::rebuild_indexes`]
+/// نتيجة إعادة بناء الفهارس
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RebuildReport {
+    /// Blocks re-indexed, from genesis or the snapshot base up to the tip
+    pub blocks: u64,
+    pub transactions: u64,
+    /// Chain height written back
+    pub height: u64,
+}
+
+/// Scan `column_families` of `db` against `namespaces`
+/// فحص عائلات الأعمدة مقابل مساحات الأسماء
+pub(crate) fn examine(
+    db: &DB,
+    database: &'static str,
+    column_families: &'static [&'static str],
+    namespaces: &[Namespace],
+    options: DoctorOptions,
+) -> Result<DoctorReport, StorageError> {
+    let quarantine_cf = db
+        .cf_handle(CF_QUARANTINE)
+        .ok_or(StorageError::ColumnFamilyNotFound)?;
+    let mut report = DoctorReport {
+        database,
+        namespaces: namespaces
+            .iter()
+            .map(|ns| NamespaceStats {
+                name: ns.name,
+                column_family: ns.column_family,
+                keys: 0,
+                bytes: 0,
+                corrupt: 0,
+            })
+            .collect(),
+        unknown: Vec::new(),
+        corrupt: Vec::new(),
+        corrupt_total: 0,
+        quarantined: 0,
+        previously_quarantined: db.iterator_cf(&quarantine_cf, IteratorMode::Start).count() as u64,
+    };
+
+    let mut batch = WriteBatch::default();
+    for &cf_name in column_families {
+        if cf_name == CF_QUARANTINE {
+            continue;
+        }
+        let cf = db
+            .cf_handle(cf_name)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let mut unknown = 0u64;
+        for item in db.iterator_cf(&cf, IteratorMode::Start) {
+            let (key, value) = item?;
+            let claimed = namespaces
+                .iter()
+                .enumerate()
+                .filter(|(_, ns)| ns.column_family == cf_name)
+                .find_map(|(index, ns)| ns.claims(&key).map(|ok| (index, ns, ok)));
+            let Some((index, namespace, key_ok)) = claimed else {
+                unknown += 1;
+                continue;
+            };
+
+            let stats = &mut report.namespaces[index];
+            stats.keys += 1;
+            stats.bytes += (key.len() + value.len()) as u64;
+            let problem = if key_ok {
+                namespace.check_value(&value).err()
+            } else {
+                Some(format!("malformed key of {} bytes", key.len()))
+            };
+            let Some(reason) = problem else {
+                continue;
+            };
+
+            stats.corrupt += 1;
+            report.corrupt_total += 1;
+            if report.corrupt.len() < options.max_reported {
+                report.corrupt.push(CorruptEntry {
+                    column_family: cf_name,
+                    key: key.to_vec(),
+                    namespace: namespace.name,
+                    reason,
+                });
+            }
+            if options.quarantine {
+                let mut quarantined_key = cf_name.as_bytes().to_vec();
+                quarantined_key.push(0);
+                quarantined_key.extend_from_slice(&key);
+                batch.put_cf(&quarantine_cf, quarantined_key, &value);
+                batch.delete_cf(&cf, &key);
+                report.quarantined += 1;
+                if batch.len() >= QUARANTINE_BATCH_OPS {
+                    db.write(std::mem::take(&mut batch))?;
+                }
+            }
+        }
+        if unknown > 0 {
+            report.unknown.push((cf_name, unknown));
+        }
+    }
+    if !batch.is_empty() {
+        db.write(batch)?;
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BALANCE: Namespace = Namespace {
+        name: "balance",
+        column_family: "default",
+        key: KeyShape::Prefix(b"balance_", Suffix::Len(32)),
+        value: ValueShape::Len(8),
+    };
+
+    #[test]
+    fn test_prefix_claims_and_malformed_suffix() {
+        let mut key = b"balance_".to_vec();
+        key.extend_from_slice(&[1u8; 32]);
+        assert_eq!(BALANCE.claims(&key), Some(true));
+        assert_eq!(BALANCE.claims(b"balance_short"), Some(false));
+        assert_eq!(BALANCE.claims(b"nonce_"), None);
+        assert!(BALANCE.check_value(&7u64.to_le_bytes()).is_ok());
+        assert!(BALANCE.check_value(b"xyz").is_err());
+    }
+
+    #[test]
+    fn test_decodes_rejects_trailing_bytes() {
+        let mut bytes = bincode::encode_to_vec(42u64, bincode::config::standard()).unwrap();
+        assert!(decodes::<u64>(&bytes));
+        bytes.push(0);
+        assert!(!decodes::<u64>(&bytes));
+        assert!(!decodes::<Vec<[u8; 32]>>(&[5]));
+    }
+}
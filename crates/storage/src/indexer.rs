@@ -1,7 +1,7 @@
 /// Secondary indexes for fast blockchain queries
 /// فهارس ثانوية لاستعلامات سريعة على سلسلة الكتل
 
-use opensyria_core::{block::Block, crypto::PublicKey};
+use opensyria_core::{block::Block, crypto::PublicKey, FeeRateStats};
 use rocksdb::{ColumnFamilyDescriptor, Options, WriteBatch, DB};
 use std::path::Path;
 use std::sync::Arc;
@@ -76,6 +76,18 @@ impl BlockchainIndexer {
         let block_hash = block.hash();
         batch.put_cf(&block_hash_cf, block_hash, height.to_le_bytes());
 
+        // Fee-rate percentiles for fee market reporting
+        let stats_cf = self
+            .db
+            .cf_handle(Self::CF_STATS_CACHE)
+            .ok_or_else(|| StorageError::InvalidChain)?;
+        let fee_stats = FeeRateStats::from_block(block, height);
+        batch.put_cf(
+            &stats_cf,
+            Self::fee_stats_key(height),
+            crate::bincode_helpers::serialize(&fee_stats)?,
+        );
+
         // Index each transaction
         for (tx_idx, tx) in block.transactions.iter().enumerate() {
             let tx_hash = tx.hash();
@@ -237,9 +249,29 @@ impl BlockchainIndexer {
         }
     }
 
+    fn fee_stats_key(height: u64) -> Vec<u8> {
+        let mut key = b"fee_stats_".to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Get fee-rate statistics recorded when the block at `height` was indexed
+    /// الحصول على إحصاءات معدلات الرسوم للكتلة
+    pub fn get_fee_stats(&self, height: u64) -> Result<Option<FeeRateStats>, StorageError> {
+        let cf = self
+            .db
+            .cf_handle(Self::CF_STATS_CACHE)
+            .ok_or_else(|| StorageError::InvalidChain)?;
+
+        match self.db.get_cf(&cf, Self::fee_stats_key(height))? {
+            Some(data) => Ok(Some(crate::bincode_helpers::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Remove block from indexes (for reorganization)
     /// إزالة الكتلة من الفهارس (لإعادة التنظيم)
-    pub fn remove_block_from_index(&self, block: &Block, _height: u64) -> Result<(), StorageError> {
+    pub fn remove_block_from_index(&self, block: &Block, height: u64) -> Result<(), StorageError> {
         let mut batch = WriteBatch::default();
 
         let tx_cf = self
@@ -259,6 +291,10 @@ impl BlockchainIndexer {
         let block_hash = block.hash();
         batch.delete_cf(&block_hash_cf, block_hash);
 
+        if let Some(stats_cf) = self.db.cf_handle(Self::CF_STATS_CACHE) {
+            batch.delete_cf(&stats_cf, Self::fee_stats_key(height));
+        }
+
         // Remove each transaction from indexes
         for tx in &block.transactions {
             let tx_hash = tx.hash();
@@ -453,4 +489,21 @@ mod tests {
         let unique_hashes: std::collections::HashSet<_> = tx_hashes.iter().collect();
         assert_eq!(tx_hashes.len(), unique_hashes.len());
     }
+
+    #[test]
+    fn test_fee_stats_indexed_and_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = BlockchainIndexer::open(temp_dir.path().join("index")).unwrap();
+
+        let block = create_test_block(5, 4);
+        indexer.index_block(&block, 5).unwrap();
+
+        let stats = indexer.get_fee_stats(5).unwrap().unwrap();
+        assert_eq!(stats, FeeRateStats::from_block(&block, 5));
+        assert_eq!(stats.tx_count, 4);
+        assert_eq!(stats.total_fees, 40);
+
+        indexer.remove_block_from_index(&block, 5).unwrap();
+        assert!(indexer.get_fee_stats(5).unwrap().is_none());
+    }
 }
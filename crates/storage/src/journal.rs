@@ -0,0 +1,330 @@
+//! Double-entry accounting journal per block
+//! دفتر اليومية المحاسبي مزدوج القيد لكل كتلة
+//!
+//! Every movement of SYL is written as a debit to the account value leaves
+//! and a credit to the account it reaches, with a [`JournalReason`]. Value
+//! created or destroyed outside of accounts goes through system accounts:
+//! coinbase rewards are debited to [`JournalAccount::Issuance`], fees are
+//! credited to [`JournalAccount::Fees`] (the coinbase then issues the
+//! miner's share again, as `total_supply` counts it), governance deposits
+//! are held by [`JournalAccount::Treasury`], which stands for the deposit
+//! escrow account, and burned deposits end in [`JournalAccount::Burned`].
+//! The debits and credits of a journal therefore
+//! always sum to the same amount, and the lines of an address sum to its
+//! exact balance change in the block.
+//!
+//! Contract and DEX settlement are posted as one net line per address and
+//! subsystem, since both run over the whole block at once.
+
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::deposit::{escrow_account, DepositOperation};
+use opensyria_core::Transaction;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Account a journal line is posted to
+/// الحساب الذي يُقيد عليه السطر
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAccount {
+    Address(PublicKey),
+    /// Source of newly issued coins
+    Issuance,
+    /// Sink of transaction fees
+    Fees,
+    /// Proposal deposits held by the deposit escrow account
+    Treasury,
+    /// Destroyed coins
+    Burned,
+}
+
+impl JournalAccount {
+    /// Account holding `address`'s balance; the deposit escrow account is
+    /// the treasury
+    pub fn holding(address: PublicKey) -> Self {
+        if address == escrow_account() {
+            JournalAccount::Treasury
+        } else {
+            JournalAccount::Address(address)
+        }
+    }
+}
+
+impl std::fmt::Display for JournalAccount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JournalAccount::Address(address) => write!(f, "{}", address.to_hex()),
+            JournalAccount::Issuance => write!(f, "issuance"),
+            JournalAccount::Fees => write!(f, "fees"),
+            JournalAccount::Treasury => write!(f, "treasury"),
+            JournalAccount::Burned => write!(f, "burned"),
+        }
+    }
+}
+
+/// Why value moved
+/// سبب حركة القيمة
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalReason {
+    Transfer,
+    Fee,
+    Coinbase,
+    /// Deposit escrowed by or refunded from governance
+    Treasury,
+    Burn,
+    /// Net settlement of contract executions
+    Contract,
+    /// Net settlement of DEX escrow and fills
+    Dex,
+}
+
+/// Side of a journal line
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalSide {
+    /// Value leaves the account
+    Debit,
+    /// Value reaches the account
+    Credit,
+}
+
+/// One debit or credit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct JournalLine {
+    /// Transaction the line belongs to; `None` for net settlement and
+    /// governance lines
+    pub tx_hash: Option<[u8; 32]>,
+    pub account: JournalAccount,
+    pub reason: JournalReason,
+    pub side: JournalSide,
+    pub amount: u64,
+}
+
+/// Lines posted at one height
+/// قيود الكتلة عند ارتفاع معين
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct BlockJournal {
+    pub height: u64,
+    pub lines: Vec<JournalLine>,
+}
+
+impl BlockJournal {
+    /// Empty journal for `height`
+    pub fn new(height: u64) -> Self {
+        Self {
+            height,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Coinbase, transfer and fee lines of `transactions`, in block order
+    pub fn from_transactions(height: u64, transactions: &[Transaction]) -> Self {
+        let mut journal = Self::new(height);
+        for tx in transactions {
+            let tx_hash = Some(tx.hash());
+            let to = JournalAccount::Address(tx.to);
+            if tx.is_coinbase() {
+                journal.post(
+                    tx_hash,
+                    JournalReason::Coinbase,
+                    JournalAccount::Issuance,
+                    to,
+                    tx.amount,
+                );
+                continue;
+            }
+            match DepositOperation::from_transaction(tx) {
+                Some(Ok(DepositOperation::Burn { .. })) => journal.post(
+                    tx_hash,
+                    JournalReason::Burn,
+                    JournalAccount::Treasury,
+                    JournalAccount::Burned,
+                    tx.amount,
+                ),
+                Some(_) => journal.post(
+                    tx_hash,
+                    JournalReason::Treasury,
+                    JournalAccount::holding(tx.from),
+                    JournalAccount::holding(tx.to),
+                    tx.amount,
+                ),
+                None => journal.post(
+                    tx_hash,
+                    JournalReason::Transfer,
+                    JournalAccount::Address(tx.from),
+                    to,
+                    tx.amount,
+                ),
+            }
+            let payer = JournalAccount::Address(tx.fee_payer());
+            journal.post(
+                tx_hash,
+                JournalReason::Fee,
+                payer,
+                JournalAccount::Fees,
+                tx.fee,
+            );
+        }
+        journal
+    }
+
+    /// Debit `debit` and credit `credit` with `amount`; zero amounts are skipped
+    pub fn post(
+        &mut self,
+        tx_hash: Option<[u8; 32]>,
+        reason: JournalReason,
+        debit: JournalAccount,
+        credit: JournalAccount,
+        amount: u64,
+    ) {
+        if amount == 0 {
+            return;
+        }
+        for (account, side) in [(debit, JournalSide::Debit), (credit, JournalSide::Credit)] {
+            self.lines.push(JournalLine {
+                tx_hash,
+                account,
+                reason,
+                side,
+                amount,
+            });
+        }
+    }
+
+    /// Post the difference between two sets of balance changes as one line
+    /// per address, ordered by address
+    ///
+    /// The movements behind the difference are transfers between
+    /// addresses, so the resulting lines balance.
+    pub(crate) fn post_net(
+        &mut self,
+        reason: JournalReason,
+        before: &HashMap<PublicKey, i128>,
+        after: &HashMap<PublicKey, i128>,
+    ) {
+        let mut net: Vec<(PublicKey, i128)> = after
+            .iter()
+            .map(|(address, change)| (*address, change - before.get(address).copied().unwrap_or(0)))
+            .filter(|(_, delta)| *delta != 0)
+            .collect();
+        net.sort_by_key(|(address, _)| address.0);
+        for (address, delta) in net {
+            let side = if delta < 0 {
+                JournalSide::Debit
+            } else {
+                JournalSide::Credit
+            };
+            self.lines.push(JournalLine {
+                tx_hash: None,
+                account: JournalAccount::Address(address),
+                reason,
+                side,
+                amount: delta.unsigned_abs() as u64,
+            });
+        }
+    }
+
+    /// Sum of debits and sum of credits
+    pub fn totals(&self) -> (u128, u128) {
+        self.lines
+            .iter()
+            .fold((0, 0), |(debits, credits), line| match line.side {
+                JournalSide::Debit => (debits + line.amount as u128, credits),
+                JournalSide::Credit => (debits, credits + line.amount as u128),
+            })
+    }
+
+    /// Whether debits equal credits
+    pub fn is_balanced(&self) -> bool {
+        let (debits, credits) = self.totals();
+        debits == credits
+    }
+
+    /// Net change of `account` over the journal, credits minus debits
+    pub fn net_change(&self, account: &JournalAccount) -> i128 {
+        self.lines
+            .iter()
+            .filter(|line| line.account == *account)
+            .map(|line| match line.side {
+                JournalSide::Debit => -(line.amount as i128),
+                JournalSide::Credit => line.amount as i128,
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_transactions_post_balanced_lines() {
+        let miner = KeyPair::generate().public_key();
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate().public_key();
+        let coinbase = Transaction::coinbase(1, miner, 5, 10).unwrap();
+        let transfer = Transaction::new(alice.public_key(), bob, 700, 10, 0);
+
+        let journal = BlockJournal::from_transactions(5, &[coinbase.clone(), transfer]);
+        assert!(journal.is_balanced());
+        assert_eq!(journal.lines.len(), 6);
+        let alice = JournalAccount::Address(alice.public_key());
+        assert_eq!(journal.net_change(&alice), -710);
+        assert_eq!(journal.net_change(&JournalAccount::Address(bob)), 700);
+        assert_eq!(journal.net_change(&JournalAccount::Fees), 10);
+        assert_eq!(
+            journal.net_change(&JournalAccount::Issuance),
+            -(coinbase.amount as i128)
+        );
+    }
+
+    #[test]
+    fn test_post_net_lines_per_address() {
+        let alice = KeyPair::generate().public_key();
+        let escrow = KeyPair::generate().public_key();
+        let before = HashMap::from([(alice, -5)]);
+        let after = HashMap::from([(alice, -45), (escrow, 40)]);
+
+        let mut journal = BlockJournal::new(1);
+        journal.post_net(JournalReason::Dex, &before, &after);
+        assert!(journal.is_balanced());
+        assert_eq!(journal.lines.len(), 2);
+        assert_eq!(journal.net_change(&JournalAccount::Address(alice)), -40);
+        assert!(journal.lines.iter().all(|line| line.tx_hash.is_none()));
+    }
+}
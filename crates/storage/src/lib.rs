@@ -1,13 +1,39 @@
+mod assets;
 pub mod blockchain;
+pub mod contracts;
+pub mod dex;
+pub mod doctor;
 pub mod indexer;
+pub mod journal;
+pub mod limits;
+pub mod migration;
 pub mod state;
 pub mod pruning;
+pub mod replication;
+pub mod snapshot;
+pub mod supply;
+pub mod thin;
+pub mod tiers;
 
-pub use blockchain::BlockchainStorage;
+pub use blockchain::{
+    block_locator_heights, BlockchainStorage, MAX_LOCATOR_HASHES, ORPHAN_RATE_WINDOW,
+};
+pub use contracts::ContractReceipt;
+pub use dex::DexReceipt;
+pub use doctor::{DoctorOptions, DoctorReport, RebuildReport};
 pub use indexer::BlockchainIndexer;
-pub use state::StateStorage;
+pub use journal::{BlockJournal, JournalAccount, JournalLine, JournalReason, JournalSide};
+pub use limits::{StorageLimits, StorageResources};
+pub use migration::{MigrationOptions, MigrationReport};
+pub use state::{StateStorage, SupplyCheck};
 pub use pruning::{PruningMode, StatePruner};
+pub use replication::{CheckpointManifest, ContentDigest, Replication, WalBatch};
+pub use snapshot::{SnapshotAssembler, SnapshotManifest, SnapshotStore};
+pub use supply::{EmissionProjection, EraIssuance, EraProjection, SupplyReport, SupplyTotals};
+pub use thin::Watchlist;
+pub use tiers::{ColumnFamilyTuning, StateOptions, StorageTier};
 
+use opensyria_core::error_code::{self, CodedError, ErrorCode};
 use std::path::PathBuf;
 
 /// Bincode 2.0 serialization helpers with standard configuration  
@@ -38,12 +64,42 @@ pub struct Storage {
 impl Storage {
     /// Open storage at specified path
     pub fn open(path: PathBuf) -> Result<Self, StorageError> {
-        let blockchain = BlockchainStorage::open(path.join("blocks"))?;
-        let state = StateStorage::open(path.join("state"))?;
+        Self::open_with_limits(path, StorageLimits::default())
+    }
+
+    /// Open storage with both databases sharing one memory and file budget
+    /// فتح التخزين بميزانية ذاكرة وملفات مشتركة بين قاعدتي البيانات
+    pub fn open_with_limits(path: PathBuf, limits: StorageLimits) -> Result<Self, StorageError> {
+        Self::open_with_options(path, limits, &StateOptions::default())
+    }
+
+    /// Open storage with the state database laid out and tuned by `state`;
+    /// blocks always stay under `path`
+    /// فتح التخزين مع توزيع قاعدة بيانات الحالة على مسار سريع
+    pub fn open_with_options(
+        path: PathBuf,
+        limits: StorageLimits,
+        state: &StateOptions,
+    ) -> Result<Self, StorageError> {
+        let resources = StorageResources::new(limits);
+        let blockchain = BlockchainStorage::open_with_resources(path.join("blocks"), &resources)?;
+        let state = StateStorage::open_with_options(path.join("state"), &resources, state)?;
 
         Ok(Self { blockchain, state })
     }
 
+    /// Run pending schema migrations on both databases without opening them for use
+    /// تشغيل ترحيلات المخطط المعلقة على قاعدتي البيانات
+    pub fn migrate(
+        path: PathBuf,
+        options: MigrationOptions,
+    ) -> Result<Vec<MigrationReport>, StorageError> {
+        Ok(vec![
+            BlockchainStorage::migrate(path.join("blocks"), options)?,
+            StateStorage::migrate(path.join("state"), options)?,
+        ])
+    }
+
     /// Validate and apply block with full state validation (defense-in-depth)
     /// 
     /// SECURITY: This method provides an additional layer of validation beyond
@@ -53,6 +109,11 @@ impl Storage {
     /// 
     /// ✅  SECURITY FIX (CRITICAL-004): Now passes state storage to append_block
     /// for total supply enforcement in coinbase validation.
+    #[tracing::instrument(
+        name = "block_validation",
+        skip_all,
+        fields(tx_count = block.transactions.len())
+    )]
     pub fn validate_and_apply_block(&self, block: &opensyria_core::Block) -> Result<(), StorageError> {
         // First, validate block structure (PoW, merkle root, coinbase with supply check, etc.)
         self.blockchain.append_block(block, Some(&self.state))?;
@@ -64,6 +125,24 @@ impl Storage {
         Ok(())
     }
 
+    /// Check a proposed next block against every consensus and state rule
+    /// without writing anything; returns the height it would take
+    /// التحقق من كتلة مقترحة مقابل قواعد الإجماع والحالة دون تعديل التخزين
+    ///
+    /// With `check_pow` off, block templates can be checked before any work
+    /// is done on them.
+    pub fn check_block(
+        &self,
+        block: &opensyria_core::Block,
+        check_pow: bool,
+    ) -> Result<u64, RuleViolation> {
+        let height = self
+            .blockchain
+            .check_next_block(block, Some(&self.state), check_pow)?;
+        self.state.check_block(height, block)?;
+        Ok(height)
+    }
+
     /// Validate block without applying (for testing/validation)
     pub fn validate_block_state(&self, block: &opensyria_core::Block) -> Result<(), StorageError> {
         // Verify all non-coinbase transactions have sufficient balance
@@ -72,13 +151,14 @@ impl Storage {
                 continue;
             }
 
-            // Check sender balance
+            // Check sender balance, and the fee payer's when sponsored
             let balance = self.state.get_balance(&tx.from)?;
-            let required = tx.amount
-                .checked_add(tx.fee)
-                .ok_or(StorageError::BalanceOverflow)?;
-
-            if balance < required {
+            if balance < tx.debit(&tx.from) {
+                return Err(StorageError::InsufficientBalance);
+            }
+            if tx.fee_payer() != tx.from
+                && self.state.get_balance(&tx.fee_payer())? < tx.fee
+            {
                 return Err(StorageError::InsufficientBalance);
             }
 
@@ -102,6 +182,8 @@ pub enum StorageError {
     InsufficientBalance,
     BalanceOverflow,
     InvalidProofOfWork,
+    UnexpectedDifficulty { expected: u32, got: u32 },
+    WrongChainId { expected: u32, got: u32 },
     InvalidTransaction,
     InvalidMerkleRoot,
     TimestampTooFarFuture,
@@ -112,6 +194,22 @@ pub enum StorageError {
     CheckpointMismatch { height: u64, expected: String, got: String },
     ReorgTooDeep { depth: u64, max: u64 },
     ColumnFamilyNotFound,
+    AuditError(String),
+    AnchorError(String),
+    AccountFrozen(String),
+    InvalidSnapshot(String),
+    PolicyViolation(String),
+    DelegationViolation(String),
+    ContractError(String),
+    AssetViolation(String),
+    DexViolation(String),
+    OracleViolation(String),
+    DepositViolation(String),
+    ReplicationError(String),
+    SchemaTooNew { database: String, found: u32, supported: u32 },
+    MigrationError(String),
+    TierError(String),
+    ThinNode(String),
 }
 
 impl std::fmt::Display for StorageError {
@@ -124,6 +222,12 @@ impl std::fmt::Display for StorageError {
             StorageError::InsufficientBalance => write!(f, "Insufficient balance"),
             StorageError::BalanceOverflow => write!(f, "Balance overflow"),
             StorageError::InvalidProofOfWork => write!(f, "Invalid proof of work"),
+            StorageError::UnexpectedDifficulty { expected, got } => {
+                write!(f, "Block difficulty {} does not match required {}", got, expected)
+            }
+            StorageError::WrongChainId { expected, got } => {
+                write!(f, "Transaction signed for chain {}, expected {}", got, expected)
+            }
             StorageError::InvalidTransaction => write!(f, "Invalid transaction in block"),
             StorageError::InvalidMerkleRoot => write!(f, "Invalid merkle root"),
             StorageError::TimestampTooFarFuture => write!(f, "Block timestamp too far in future"),
@@ -138,12 +242,136 @@ impl std::fmt::Display for StorageError {
                 write!(f, "Reorganization too deep: {} blocks (max {})", depth, max)
             }
             StorageError::ColumnFamilyNotFound => write!(f, "RocksDB column family not found"),
+            StorageError::AuditError(e) => write!(f, "Audit log error: {}", e),
+            StorageError::AnchorError(e) => write!(f, "Anchor error: {}", e),
+            StorageError::AccountFrozen(address) => write!(f, "Account {} is frozen", address),
+            StorageError::InvalidSnapshot(e) => write!(f, "Invalid state snapshot: {}", e),
+            StorageError::PolicyViolation(e) => write!(f, "Spending policy violation: {}", e),
+            StorageError::DelegationViolation(e) => {
+                write!(f, "Hot key delegation violation: {}", e)
+            }
+            StorageError::ContractError(e) => write!(f, "Contract error: {}", e),
+            StorageError::AssetViolation(e) => write!(f, "Asset violation: {}", e),
+            StorageError::DexViolation(e) => write!(f, "Order book violation: {}", e),
+            StorageError::OracleViolation(e) => write!(f, "Oracle violation: {}", e),
+            StorageError::DepositViolation(e) => write!(f, "Deposit violation: {}", e),
+            StorageError::ReplicationError(e) => write!(f, "Replication error: {}", e),
+            StorageError::SchemaTooNew { database, found, supported } => write!(
+                f,
+                "{} database has schema version {}, newer than the {} this binary supports; upgrade the node",
+                database, found, supported
+            ),
+            StorageError::MigrationError(e) => write!(f, "Migration error: {}", e),
+            StorageError::TierError(e) => write!(f, "Storage tier error: {}", e),
+            StorageError::ThinNode(e) => {
+                write!(f, "Not available on a thin node (headers and watched transactions only): {}", e)
+            }
         }
     }
 }
 
 impl std::error::Error for StorageError {}
 
+impl CodedError for StorageError {
+    fn error_code(&self) -> ErrorCode {
+        match self {
+            StorageError::DatabaseError(_) => error_code::DATABASE_ERROR,
+            StorageError::SerializationError(_) => error_code::SERIALIZATION_ERROR,
+            StorageError::BlockNotFound => error_code::BLOCK_NOT_FOUND,
+            StorageError::InvalidChain => error_code::INVALID_CHAIN,
+            StorageError::InsufficientBalance => error_code::INSUFFICIENT_BALANCE,
+            StorageError::BalanceOverflow => error_code::BALANCE_OVERFLOW,
+            StorageError::InvalidProofOfWork => error_code::INVALID_PROOF_OF_WORK,
+            StorageError::UnexpectedDifficulty { .. } => error_code::UNEXPECTED_DIFFICULTY,
+            StorageError::WrongChainId { .. } => error_code::WRONG_CHAIN_ID,
+            StorageError::InvalidTransaction => error_code::INVALID_BLOCK_TRANSACTION,
+            StorageError::InvalidMerkleRoot => error_code::INVALID_MERKLE_ROOT,
+            StorageError::TimestampTooFarFuture => error_code::TIMESTAMP_TOO_FAR_FUTURE,
+            StorageError::TimestampDecreased => error_code::TIMESTAMP_DECREASED,
+            StorageError::MissingCoinbase => error_code::MISSING_COINBASE,
+            StorageError::InvalidCoinbaseAmount => error_code::INVALID_COINBASE_AMOUNT,
+            StorageError::MultipleCoinbase => error_code::MULTIPLE_COINBASE,
+            StorageError::CheckpointMismatch { .. } => error_code::CHECKPOINT_MISMATCH,
+            StorageError::ReorgTooDeep { .. } => error_code::REORG_TOO_DEEP,
+            StorageError::ColumnFamilyNotFound => error_code::COLUMN_FAMILY_MISSING,
+            StorageError::AuditError(_) => error_code::AUDIT_ERROR,
+            StorageError::AnchorError(_) => error_code::ANCHOR_ERROR,
+            StorageError::AccountFrozen(_) => error_code::ACCOUNT_FROZEN,
+            StorageError::InvalidSnapshot(_) => error_code::INVALID_SNAPSHOT,
+            StorageError::PolicyViolation(_) => error_code::POLICY_VIOLATION,
+            StorageError::DelegationViolation(_) => error_code::DELEGATION_VIOLATION,
+            StorageError::ContractError(_) => error_code::CONTRACT_ERROR,
+            StorageError::AssetViolation(_) => error_code::INVALID_ASSET,
+            StorageError::DexViolation(_) => error_code::INVALID_ORDER,
+            StorageError::OracleViolation(_) => error_code::INVALID_ORACLE_UPDATE,
+            StorageError::DepositViolation(_) => error_code::INVALID_DEPOSIT,
+            StorageError::ReplicationError(_) => error_code::REPLICATION_ERROR,
+            StorageError::SchemaTooNew { .. } => error_code::SCHEMA_TOO_NEW,
+            StorageError::MigrationError(_) => error_code::MIGRATION_ERROR,
+            StorageError::TierError(_) => error_code::STORAGE_TIER_ERROR,
+            StorageError::ThinNode(_) => error_code::THIN_NODE,
+        }
+    }
+}
+
+impl StorageError {
+    /// Whether the database could not be opened because another process holds its lock
+    pub fn is_lock_held(&self) -> bool {
+        matches!(self, StorageError::DatabaseError(e) if e.to_string().contains("lock file"))
+    }
+}
+
+/// The consensus rule a block breaks, with the transaction it broke it on
+/// قاعدة الإجماع التي تخالفها الكتلة
+#[derive(Debug)]
+pub struct RuleViolation {
+    /// Stable rule name, e.g. `merkle_root` or `balance`
+    pub rule: &'static str,
+    /// Index of the offending transaction, for per-transaction rules
+    pub tx_index: Option<usize>,
+    pub error: StorageError,
+}
+
+impl RuleViolation {
+    pub fn new(rule: &'static str, error: StorageError) -> Self {
+        Self {
+            rule,
+            tx_index: None,
+            error,
+        }
+    }
+
+    /// Attribute the violation to the transaction at `index`
+    pub fn at(mut self, index: usize) -> Self {
+        self.tx_index = Some(index);
+        self
+    }
+}
+
+impl std::fmt::Display for RuleViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.tx_index {
+            Some(index) => write!(f, "{} (transaction {}): {}", self.rule, index, self.error),
+            None => write!(f, "{}: {}", self.rule, self.error),
+        }
+    }
+}
+
+impl std::error::Error for RuleViolation {}
+
+impl CodedError for RuleViolation {
+    fn error_code(&self) -> ErrorCode {
+        self.error.error_code()
+    }
+}
+
+/// Failures reading the databases while a block is checked
+impl From<StorageError> for RuleViolation {
+    fn from(error: StorageError) -> Self {
+        Self::new("storage", error)
+    }
+}
+
 impl From<rocksdb::Error> for StorageError {
     fn from(e: rocksdb::Error) -> Self {
         StorageError::DatabaseError(e)
@@ -0,0 +1,130 @@
+//! Memory and file-handle budgets shared by the blockchain and state databases
+//! ميزانيات الذاكرة والملفات المفتوحة المشتركة بين قاعدتي البيانات
+//!
+//! Each database used to size its own block cache and memtables, so a node
+//! held roughly 320 MB of cache plus 64 MB per column family in write
+//! buffers before doing anything useful. [`StorageResources`] builds one
+//! block cache and one write buffer manager from a [`StorageLimits`] and
+//! hands them to both databases, so the configured numbers bound the total.
+
+use rocksdb::{BlockBasedOptions, Cache, Options, WriteBufferManager};
+use serde::{Deserialize, Serialize};
+
+const MB: usize = 1024 * 1024;
+
+/// Memtable size a single column family may grow to before flushing
+const MAX_MEMTABLE_BYTES: usize = 64 * MB;
+
+/// Configured storage budgets
+/// ميزانيات التخزين المكوّنة
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageLimits {
+    /// Block cache shared by both databases (bytes)
+    pub block_cache_bytes: usize,
+    /// Memtables across both databases and all column families (bytes)
+    pub write_buffer_bytes: usize,
+    /// Open SST files across both databases (-1 = unlimited)
+    pub max_open_files: i32,
+}
+
+impl Default for StorageLimits {
+    fn default() -> Self {
+        Self {
+            block_cache_bytes: 256 * MB,
+            write_buffer_bytes: 256 * MB,
+            max_open_files: -1,
+        }
+    }
+}
+
+impl StorageLimits {
+    /// Memory the databases may use for cache and memtables together
+    pub fn memory_bytes(&self) -> usize {
+        self.block_cache_bytes
+            .saturating_add(self.write_buffer_bytes)
+    }
+
+    /// Open files each of the two databases may hold
+    fn max_open_files_per_db(&self) -> i32 {
+        if self.max_open_files < 0 {
+            -1
+        } else {
+            // RocksDB needs a handful of descriptors for the WAL and manifest
+            (self.max_open_files / 2).max(16)
+        }
+    }
+}
+
+/// Cache and write buffer manager built once and shared by both databases
+/// الذاكرة المؤقتة ومدير ذاكرة الكتابة المشتركان
+#[derive(Clone)]
+pub struct StorageResources {
+    limits: StorageLimits,
+    cache: Cache,
+    write_buffers: WriteBufferManager,
+}
+
+impl StorageResources {
+    pub fn new(limits: StorageLimits) -> Self {
+        Self {
+            limits,
+            cache: Cache::new_lru_cache(limits.block_cache_bytes),
+            write_buffers: WriteBufferManager::new_write_buffer_manager(
+                limits.write_buffer_bytes,
+                false,
+            ),
+        }
+    }
+
+    pub fn limits(&self) -> &StorageLimits {
+        &self.limits
+    }
+
+    /// Point `opts` and its table options at the shared budgets
+    pub(crate) fn apply(&self, opts: &mut Options, block_opts: &mut BlockBasedOptions) {
+        block_opts.set_block_cache(&self.cache);
+        opts.set_block_based_table_factory(block_opts);
+        opts.set_write_buffer_manager(&self.write_buffers);
+        opts.set_write_buffer_size(self.memtable_bytes());
+        opts.set_max_open_files(self.limits.max_open_files_per_db());
+    }
+
+    /// Memtable size that lets several column families share a small budget
+    fn memtable_bytes(&self) -> usize {
+        (self.limits.write_buffer_bytes / 4).clamp(MB, MAX_MEMTABLE_BYTES)
+    }
+}
+
+impl Default for StorageResources {
+    fn default() -> Self {
+        Self::new(StorageLimits::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_split_across_databases() {
+        let limits = StorageLimits {
+            block_cache_bytes: 32 * MB,
+            write_buffer_bytes: 16 * MB,
+            max_open_files: 500,
+        };
+        assert_eq!(limits.memory_bytes(), 48 * MB);
+        assert_eq!(limits.max_open_files_per_db(), 250);
+        assert_eq!(StorageResources::new(limits).memtable_bytes(), 4 * MB);
+
+        let tiny = StorageLimits {
+            max_open_files: 10,
+            ..limits
+        };
+        assert_eq!(tiny.max_open_files_per_db(), 16);
+        assert_eq!(StorageLimits::default().max_open_files_per_db(), -1);
+        assert_eq!(
+            StorageResources::default().memtable_bytes(),
+            MAX_MEMTABLE_BYTES
+        );
+    }
+}
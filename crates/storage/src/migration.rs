@@ -0,0 +1,441 @@
+//! Versioned schemas and ordered migrations for the chain databases
+//! مخططات مرقمة وترحيلات مرتبة لقواعد بيانات السلسلة
+//!
+//! Every database records the version of its key layout under
+//! [`SCHEMA_VERSION_KEY`]. When a database is opened, the [`Migration`]s of
+//! its [`Schema`] above the stored version run in order, each one committing
+//! the new version together with its last write. A database written by a
+//! newer binary is refused rather than misread.
+//!
+//! Migrations may be interrupted (crash, power loss) after some of their
+//! batches were committed; the stored version is only raised at the end, so
+//! the migration runs again from the start and must therefore be idempotent.
+//! Migrations that rewrite existing keys set [`Migration::backup`], which
+//! makes the runner take a RocksDB checkpoint of the database first.
+
+use crate::StorageError;
+use rocksdb::{checkpoint::Checkpoint, IteratorMode, WriteBatch, DB};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+/// Key in the default column family holding the schema version (u32 LE)
+pub const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+/// Writes buffered before a migration commits a batch
+const MIGRATION_BATCH_OPS: usize = 10_000;
+
+/// Keys processed between progress log lines
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+/// One step in the evolution of a key layout
+/// خطوة واحدة في تطور تخطيط المفاتيح
+pub struct Migration {
+    /// Schema version the database has after this migration
+    pub version: u32,
+    /// What the migration changes, shown in progress output
+    pub description: &'static str,
+    /// Take a checkpoint of the database before running
+    pub backup: bool,
+    /// Rewrites the database through the given step
+    pub run: fn(&mut MigrationStep<'_>) -> Result<(), StorageError>,
+}
+
+/// Key layout of one database and the migrations leading up to it
+/// تخطيط مفاتيح قاعدة بيانات والترحيلات المؤدية إليه
+pub struct Schema {
+    /// Database name used in messages and backup directory names
+    pub name: &'static str,
+    /// Migrations in ascending version order
+    pub migrations: &'static [Migration],
+    /// Every column family, checked to tell a new database from a legacy one
+    pub column_families: &'static [&'static str],
+}
+
+impl Schema {
+    /// Version written by this binary
+    pub fn current_version(&self) -> u32 {
+        self.migrations.last().map_or(0, |m| m.version)
+    }
+}
+
+/// Migration for databases created before schemas were versioned
+pub(crate) const BASELINE: Migration = Migration {
+    version: 1,
+    description: "record the schema version",
+    backup: false,
+    run: |_| Ok(()),
+};
+
+/// How migrations are run
+/// طريقة تشغيل الترحيلات
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationOptions {
+    /// Count the changes each migration would make without writing them
+    pub dry_run: bool,
+    /// Honour [`Migration::backup`]; disable only when a backup exists elsewhere
+    pub backup: bool,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        Self {
+            dry_run: false,
+            backup: true,
+        }
+    }
+}
+
+/// Result of one migration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub description: &'static str,
+    /// Keys written or deleted (or that would be, in a dry run)
+    pub changes: u64,
+}
+
+/// What happened to a database's schema
+/// تقرير ترحيل مخطط قاعدة البيانات
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub database: &'static str,
+    /// Stored version before migrating (0 for legacy databases)
+    pub from: u32,
+    /// Version after migrating (unchanged in a dry run)
+    pub to: u32,
+    pub applied: Vec<AppliedMigration>,
+    /// Checkpoint taken before the first rewriting migration
+    pub backup: Option<PathBuf>,
+    pub dry_run: bool,
+}
+
+impl MigrationReport {
+    /// Whether the database was (or would be) changed
+    pub fn is_noop(&self) -> bool {
+        self.applied.is_empty()
+    }
+}
+
+/// Write access handed to a running migration
+/// واجهة الكتابة المتاحة للترحيل أثناء تشغيله
+pub struct MigrationStep<'a> {
+    db: &'a DB,
+    dry_run: bool,
+    batch: WriteBatch,
+    changes: u64,
+    processed: u64,
+    label: String,
+}
+
+impl<'a> MigrationStep<'a> {
+    /// Database being migrated, for reading
+    pub fn db(&self) -> &'a DB {
+        self.db
+    }
+
+    /// Whether writes are only counted
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Write `key` in column family `cf`
+    pub fn put_cf(&mut self, cf: &str, key: &[u8], value: &[u8]) -> Result<(), StorageError> {
+        let handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        self.batch.put_cf(&handle, key, value);
+        self.changed()
+    }
+
+    /// Delete `key` from column family `cf`
+    pub fn delete_cf(&mut self, cf: &str, key: &[u8]) -> Result<(), StorageError> {
+        let handle = self
+            .db
+            .cf_handle(cf)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        self.batch.delete_cf(&handle, key);
+        self.changed()
+    }
+
+    /// Record that `keys` more keys were examined, logging progress
+    pub fn progress(&mut self, keys: u64) {
+        let before = self.processed / PROGRESS_INTERVAL;
+        self.processed += keys;
+        if self.processed / PROGRESS_INTERVAL > before {
+            info!(
+                "{}: {} keys processed, {} changes",
+                self.label, self.processed, self.changes
+            );
+        }
+    }
+
+    fn changed(&mut self) -> Result<(), StorageError> {
+        self.changes += 1;
+        if self.batch.len() >= MIGRATION_BATCH_OPS {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StorageError> {
+        let batch = std::mem::take(&mut self.batch);
+        if !self.dry_run && !batch.is_empty() {
+            self.db.write(batch)?;
+        }
+        Ok(())
+    }
+
+    /// Commit the remaining writes together with the new version
+    fn finish(mut self, version: u32) -> Result<u64, StorageError> {
+        if !self.dry_run {
+            self.batch.put(SCHEMA_VERSION_KEY, version.to_le_bytes());
+            self.flush()?;
+        }
+        Ok(self.changes)
+    }
+}
+
+/// Schema version stored in `db`, `None` if it was never recorded
+pub fn stored_version(db: &DB) -> Result<Option<u32>, StorageError> {
+    match db.get(SCHEMA_VERSION_KEY)? {
+        Some(bytes) => {
+            let bytes: [u8; 4] = bytes.as_slice().try_into().map_err(|_| {
+                StorageError::MigrationError("malformed schema version".to_string())
+            })?;
+            Ok(Some(u32::from_le_bytes(bytes)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Whether `db` holds no keys other than the schema version
+fn is_new(db: &DB, schema: &Schema) -> Result<bool, StorageError> {
+    for name in schema.column_families {
+        let cf = db
+            .cf_handle(name)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        for item in db.iterator_cf(&cf, IteratorMode::Start) {
+            let (key, _) = item?;
+            if &*key != SCHEMA_VERSION_KEY {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Bring `db`, stored at `path`, up to the current version of `schema`
+/// ترقية قاعدة البيانات إلى الإصدار الحالي من المخطط
+pub fn migrate(
+    db: &DB,
+    path: &Path,
+    schema: &Schema,
+    options: MigrationOptions,
+) -> Result<MigrationReport, StorageError> {
+    let current = schema.current_version();
+    let from = match stored_version(db)? {
+        Some(version) => version,
+        // Nothing to migrate: a new database starts at the current layout
+        None if is_new(db, schema)? => {
+            if !options.dry_run {
+                db.put(SCHEMA_VERSION_KEY, current.to_le_bytes())?;
+            }
+            current
+        }
+        None => 0,
+    };
+    if from > current {
+        return Err(StorageError::SchemaTooNew {
+            database: schema.name.to_string(),
+            found: from,
+            supported: current,
+        });
+    }
+
+    let mut report = MigrationReport {
+        database: schema.name,
+        from,
+        to: from,
+        applied: Vec::new(),
+        backup: None,
+        dry_run: options.dry_run,
+    };
+    let pending: Vec<&Migration> = schema
+        .migrations
+        .iter()
+        .filter(|m| m.version > from)
+        .collect();
+    if pending.is_empty() {
+        return Ok(report);
+    }
+
+    info!(
+        "{} schema is at version {}, migrating to {}{}",
+        schema.name,
+        from,
+        current,
+        if options.dry_run { " (dry run)" } else { "" }
+    );
+    if options.backup && !options.dry_run && pending.iter().any(|m| m.backup) {
+        let backup = backup_path(path, from);
+        Checkpoint::new(db)?.create_checkpoint(&backup)?;
+        info!("Backed up {} database to {}", schema.name, backup.display());
+        report.backup = Some(backup);
+    }
+
+    for migration in pending {
+        let label = format!("{} v{}", schema.name, migration.version);
+        info!("{}: {}", label, migration.description);
+        let mut step = MigrationStep {
+            db,
+            dry_run: options.dry_run,
+            batch: WriteBatch::default(),
+            changes: 0,
+            processed: 0,
+            label,
+        };
+        (migration.run)(&mut step).map_err(|e| {
+            StorageError::MigrationError(format!(
+                "{} migration to version {} failed: {}",
+                schema.name, migration.version, e
+            ))
+        })?;
+        let changes = step.finish(migration.version)?;
+        report.applied.push(AppliedMigration {
+            version: migration.version,
+            description: migration.description,
+            changes,
+        });
+        if !options.dry_run {
+            report.to = migration.version;
+        }
+    }
+    Ok(report)
+}
+
+/// Sibling directory receiving the pre-migration checkpoint
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "db".to_string());
+    path.with_file_name(format!("{}.backup-v{}-{}", name, version, secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rocksdb::Options;
+    use tempfile::TempDir;
+
+    fn open(path: &Path) -> DB {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        DB::open(&opts, path).unwrap()
+    }
+
+    /// Renames every `old_` key to `new_`
+    fn rename_keys(step: &mut MigrationStep<'_>) -> Result<(), StorageError> {
+        let db = step.db();
+        for item in db.prefix_iterator(b"old_") {
+            let (key, value) = item?;
+            if !key.starts_with(b"old_") {
+                break;
+            }
+            let mut renamed = b"new_".to_vec();
+            renamed.extend_from_slice(&key[4..]);
+            step.put_cf("default", &renamed, &value)?;
+            step.delete_cf("default", &key)?;
+            step.progress(1);
+        }
+        Ok(())
+    }
+
+    const TEST_SCHEMA: Schema = Schema {
+        name: "test",
+        migrations: &[
+            BASELINE,
+            Migration {
+                version: 2,
+                description: "rename old_ keys",
+                backup: true,
+                run: rename_keys,
+            },
+        ],
+        column_families: &["default"],
+    };
+
+    #[test]
+    fn test_new_database_is_stamped_without_migrating() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("db");
+        let db = open(&path);
+
+        let report = migrate(&db, &path, &TEST_SCHEMA, MigrationOptions::default()).unwrap();
+        assert!(report.is_noop());
+        assert_eq!(stored_version(&db).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_legacy_database_dry_run_then_migrate() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("db");
+        let db = open(&path);
+        db.put(b"old_a", b"1").unwrap();
+        db.put(b"old_b", b"2").unwrap();
+
+        let dry_run = MigrationOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let report = migrate(&db, &path, &TEST_SCHEMA, dry_run).unwrap();
+        assert_eq!((report.from, report.to), (0, 0));
+        assert_eq!(report.applied[1].changes, 4);
+        assert_eq!(stored_version(&db).unwrap(), None);
+        assert!(db.get(b"old_a").unwrap().is_some());
+
+        let report = migrate(&db, &path, &TEST_SCHEMA, MigrationOptions::default()).unwrap();
+        assert_eq!((report.from, report.to), (0, 2));
+        assert!(report.backup.as_ref().unwrap().exists());
+        assert_eq!(db.get(b"new_b").unwrap(), Some(b"2".to_vec()));
+        assert!(db.get(b"old_a").unwrap().is_none());
+        assert_eq!(stored_version(&db).unwrap(), Some(2));
+
+        // Already current: nothing runs again
+        let report = migrate(&db, &path, &TEST_SCHEMA, MigrationOptions::default()).unwrap();
+        assert!(report.is_noop());
+    }
+
+    #[test]
+    fn test_newer_schema_refused() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("db");
+        let db = open(&path);
+        db.put(SCHEMA_VERSION_KEY, 3u32.to_le_bytes()).unwrap();
+
+        let result = migrate(&db, &path, &TEST_SCHEMA, MigrationOptions::default());
+        assert!(matches!(
+            result,
+            Err(StorageError::SchemaTooNew {
+                found: 3,
+                supported: 2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for schema in [&crate::blockchain::SCHEMA, &crate::state::SCHEMA] {
+            let versions: Vec<u32> = schema.migrations.iter().map(|m| m.version).collect();
+            assert!(versions.windows(2).all(|w| w[0] < w[1]), "{}", schema.name);
+            assert_eq!(versions.first(), Some(&1));
+        }
+    }
+}
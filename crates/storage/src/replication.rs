@@ -0,0 +1,410 @@
+//! Operator replication of RocksDB databases between trusted nodes
+//! النسخ المتماثل لقواعد بيانات RocksDB بين العقد الموثوقة
+//!
+//! Unlike the P2P state snapshots in [`crate::snapshot`], which rebuild state
+//! from untrusted peers at a block boundary, replication copies the raw
+//! database of a node the operator controls. A secondary first installs a
+//! RocksDB checkpoint of the primary (hard-linked SST files plus manifest,
+//! described by a [`CheckpointManifest`] so every file can be verified),
+//! then tails the primary's write-ahead log from the checkpoint's sequence
+//! number, applying each [`WalBatch`] verbatim. Once caught up, both sides
+//! compute a [`ContentDigest`] at the same sequence number and compare them.
+//!
+//! The checkpoint may already contain writes made after its recorded
+//! sequence number; replaying them is harmless because every batch is a
+//! list of plain puts and deletes applied in primary order.
+
+use crate::StorageError;
+use rocksdb::{checkpoint::Checkpoint, IteratorMode, WriteBatch, DB};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long archived WAL files are kept for secondaries to catch up from
+pub const WAL_RETENTION_SECS: u64 = 6 * 3600;
+
+/// Upper bound on the size of archived WAL files, in megabytes
+pub const WAL_RETENTION_MB: u64 = 1024;
+
+/// Maximum number of files accepted in a checkpoint manifest
+pub const MAX_CHECKPOINT_FILES: usize = 65_536;
+
+/// Attempts at finding a moment with no concurrent writes for a digest
+const DIGEST_ATTEMPTS: usize = 50;
+
+/// One file of a database checkpoint
+/// ملف واحد من نقطة تفتيش قاعدة البيانات
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct CheckpointFile {
+    /// File name inside the checkpoint directory
+    pub name: String,
+    /// Size in bytes
+    pub size: u64,
+    /// SHA-256 of the file content
+    pub sha256: [u8; 32],
+}
+
+/// Description of a database checkpoint taken on the primary
+/// وصف نقطة تفتيش قاعدة البيانات المأخوذة من العقدة الرئيسية
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct CheckpointManifest {
+    /// WAL sequence number the checkpoint contains at least
+    pub sequence: u64,
+    /// Every file of the checkpoint, sorted by name
+    pub files: Vec<CheckpointFile>,
+}
+
+impl CheckpointManifest {
+    /// Describe the checkpoint stored in `dir`
+    /// وصف نقطة التفتيش المخزنة في المجلد
+    pub fn scan(dir: &Path, sequence: u64) -> Result<Self, StorageError> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let entry = entry.map_err(io_error)?;
+            if entry.file_type().map_err(io_error)?.is_file() {
+                names.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        names.sort();
+
+        let mut files = Vec::with_capacity(names.len());
+        for name in names {
+            let (size, sha256) = hash_file(&dir.join(&name))?;
+            files.push(CheckpointFile { name, size, sha256 });
+        }
+        Ok(Self { sequence, files })
+    }
+
+    /// Total size of all files in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Reject manifests that could escape the target directory or are absurdly large
+    /// رفض البيانات الوصفية غير الصالحة
+    pub fn validate(&self) -> Result<(), StorageError> {
+        if self.files.is_empty() || self.files.len() > MAX_CHECKPOINT_FILES {
+            return Err(StorageError::ReplicationError(format!(
+                "checkpoint lists {} files",
+                self.files.len()
+            )));
+        }
+        for file in &self.files {
+            let name = &file.name;
+            if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+                return Err(StorageError::ReplicationError(format!(
+                    "invalid checkpoint file name {:?}",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `dir` holds exactly the files of this manifest
+    /// التحقق من أن المجلد يحتوي على ملفات نقطة التفتيش بالضبط
+    pub fn verify(&self, dir: &Path) -> Result<(), StorageError> {
+        self.validate()?;
+        let found = Self::scan(dir, self.sequence)?;
+        if found.files.len() != self.files.len() {
+            return Err(StorageError::ReplicationError(format!(
+                "checkpoint has {} files, manifest lists {}",
+                found.files.len(),
+                self.files.len()
+            )));
+        }
+        for (expected, got) in self.files.iter().zip(&found.files) {
+            if expected != got {
+                return Err(StorageError::ReplicationError(format!(
+                    "checkpoint file {} does not match the manifest",
+                    expected.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One write batch read from the primary's write-ahead log
+/// دفعة كتابة واحدة من سجل الكتابة المسبقة
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct WalBatch {
+    /// Sequence number of the first operation in the batch
+    pub sequence: u64,
+    /// Number of operations, each consuming one sequence number
+    pub count: u32,
+    /// Serialized RocksDB write batch
+    pub data: Vec<u8>,
+}
+
+impl WalBatch {
+    /// Sequence number of the last operation in the batch
+    pub fn last_sequence(&self) -> u64 {
+        self.sequence + u64::from(self.count.max(1)) - 1
+    }
+}
+
+/// Hash of the complete database content at a sequence number
+/// بصمة كامل محتوى قاعدة البيانات عند رقم تسلسلي
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode,
+)]
+pub struct ContentDigest {
+    /// WAL sequence number the digest was taken at
+    pub sequence: u64,
+    /// Number of key/value entries across all column families
+    pub entries: u64,
+    /// SHA-256 over every column family, key and value in order
+    pub digest: [u8; 32],
+}
+
+/// Replication access to one database, see [`crate::BlockchainStorage::replication`]
+/// الوصول إلى قاعدة بيانات لغرض النسخ المتماثل
+///
+/// The handle shares the database, so it may be kept after the lock on the
+/// storage it came from is released.
+pub struct Replication {
+    db: Arc<DB>,
+    column_families: &'static [&'static str],
+}
+
+impl Replication {
+    pub(crate) fn new(db: Arc<DB>, column_families: &'static [&'static str]) -> Self {
+        Self {
+            db,
+            column_families,
+        }
+    }
+
+    /// Sequence number of the most recent write
+    pub fn sequence(&self) -> u64 {
+        self.db.latest_sequence_number()
+    }
+
+    /// Write a checkpoint of the database to `dir`, which must not exist yet
+    /// إنشاء نقطة تفتيش لقاعدة البيانات في مجلد جديد
+    pub fn create_checkpoint(&self, dir: &Path) -> Result<CheckpointManifest, StorageError> {
+        // Read before checkpointing: the checkpoint holds at least this much
+        let sequence = self.db.latest_sequence_number();
+        Checkpoint::new(&self.db)?.create_checkpoint(dir)?;
+        CheckpointManifest::scan(dir, sequence)
+    }
+
+    /// WAL batches following `cursor`, up to roughly `max_bytes` of data
+    /// دفعات سجل الكتابة التالية للمؤشر
+    ///
+    /// Returns `None` when the retained WAL no longer reaches back to
+    /// `cursor`, in which case the secondary must start from a new checkpoint.
+    pub fn updates_since(
+        &self,
+        cursor: u64,
+        max_bytes: usize,
+    ) -> Result<Option<Vec<WalBatch>>, StorageError> {
+        let latest = self.db.latest_sequence_number();
+        if cursor == latest {
+            return Ok(Some(Vec::new()));
+        }
+        if cursor > latest {
+            return Ok(None);
+        }
+        let updates = match self.db.get_updates_since(cursor) {
+            Ok(updates) => updates,
+            Err(_) => return Ok(None),
+        };
+
+        let mut batches = Vec::new();
+        let mut next = cursor + 1;
+        let mut bytes = 0usize;
+        for update in updates {
+            let (sequence, batch) = update?;
+            if sequence != next {
+                if batches.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            let wal = WalBatch {
+                sequence,
+                count: batch.len() as u32,
+                data: batch.data().to_vec(),
+            };
+            next = wal.last_sequence() + 1;
+            bytes += wal.data.len();
+            batches.push(wal);
+            if bytes >= max_bytes {
+                break;
+            }
+        }
+        if batches.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(batches))
+    }
+
+    /// Apply batches received from the primary and return the new cursor
+    /// تطبيق الدفعات المستلمة من العقدة الرئيسية
+    pub fn apply(&self, cursor: u64, batches: &[WalBatch]) -> Result<u64, StorageError> {
+        let mut cursor = cursor;
+        for wal in batches {
+            if wal.sequence != cursor + 1 {
+                return Err(StorageError::ReplicationError(format!(
+                    "expected WAL sequence {}, got {}",
+                    cursor + 1,
+                    wal.sequence
+                )));
+            }
+            let batch = WriteBatch::from_data(&wal.data);
+            if batch.len() != wal.count as usize {
+                return Err(StorageError::ReplicationError(format!(
+                    "WAL batch {} holds {} operations, expected {}",
+                    wal.sequence,
+                    batch.len(),
+                    wal.count
+                )));
+            }
+            self.db.write(batch)?;
+            cursor = wal.last_sequence();
+        }
+        Ok(cursor)
+    }
+
+    /// Hash the whole database at a moment with no concurrent writes
+    /// حساب بصمة كامل قاعدة البيانات
+    pub fn content_digest(&self) -> Result<ContentDigest, StorageError> {
+        for _ in 0..DIGEST_ATTEMPTS {
+            let sequence = self.db.latest_sequence_number();
+            let snapshot = self.db.snapshot();
+            if self.db.latest_sequence_number() != sequence {
+                std::thread::sleep(Duration::from_millis(20));
+                continue;
+            }
+
+            let mut hasher = Sha256::new();
+            let mut entries = 0u64;
+            for name in self.column_families {
+                let cf = self
+                    .db
+                    .cf_handle(name)
+                    .ok_or(StorageError::ColumnFamilyNotFound)?;
+                hasher.update((name.len() as u64).to_le_bytes());
+                hasher.update(name.as_bytes());
+                for item in snapshot.iterator_cf(&cf, IteratorMode::Start) {
+                    let (key, value) = item?;
+                    hasher.update((key.len() as u64).to_le_bytes());
+                    hasher.update(&key);
+                    hasher.update((value.len() as u64).to_le_bytes());
+                    hasher.update(&value);
+                    entries += 1;
+                }
+            }
+            return Ok(ContentDigest {
+                sequence,
+                entries,
+                digest: hasher.finalize().into(),
+            });
+        }
+        Err(StorageError::ReplicationError(
+            "database kept changing while computing its digest".to_string(),
+        ))
+    }
+}
+
+fn hash_file(path: &Path) -> Result<(u64, [u8; 32]), StorageError> {
+    let mut file = fs::File::open(path).map_err(io_error)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf).map_err(io_error)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok((size, hasher.finalize().into()))
+}
+
+fn io_error(e: std::io::Error) -> StorageError {
+    StorageError::ReplicationError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateStorage;
+    use opensyria_core::crypto::KeyPair;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_checkpoint_then_wal_tail_converges() {
+        let dir = TempDir::new().unwrap();
+        let primary = StateStorage::open(dir.path().join("primary")).unwrap();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        primary.set_balance(&alice, 1_000).unwrap();
+
+        let checkpoint = dir.path().join("checkpoint");
+        let manifest = primary
+            .replication()
+            .create_checkpoint(&checkpoint)
+            .unwrap();
+        manifest.verify(&checkpoint).unwrap();
+
+        primary.set_balance(&bob, 250).unwrap();
+        primary.set_balance(&alice, 750).unwrap();
+
+        let secondary = StateStorage::open(checkpoint).unwrap();
+        let batches = primary
+            .replication()
+            .updates_since(manifest.sequence, usize::MAX)
+            .unwrap()
+            .unwrap();
+        let cursor = secondary
+            .replication()
+            .apply(manifest.sequence, &batches)
+            .unwrap();
+
+        assert_eq!(cursor, primary.replication().sequence());
+        assert_eq!(secondary.get_balance(&bob).unwrap(), 250);
+        assert_eq!(
+            secondary.replication().content_digest().unwrap().digest,
+            primary.replication().content_digest().unwrap().digest
+        );
+    }
+
+    #[test]
+    fn test_out_of_order_batches_rejected() {
+        let dir = TempDir::new().unwrap();
+        let storage = StateStorage::open(dir.path().join("state")).unwrap();
+        let start = storage.replication().sequence();
+        storage
+            .set_balance(&KeyPair::generate().public_key(), 5)
+            .unwrap();
+        let batches = storage
+            .replication()
+            .updates_since(start, usize::MAX)
+            .unwrap()
+            .unwrap();
+
+        let result = storage.replication().apply(start + 1, &batches);
+        assert!(matches!(result, Err(StorageError::ReplicationError(_))));
+    }
+
+    #[test]
+    fn test_manifest_rejects_path_traversal() {
+        let manifest = CheckpointManifest {
+            sequence: 1,
+            files: vec![CheckpointFile {
+                name: "../CURRENT".to_string(),
+                size: 0,
+                sha256: [0u8; 32],
+            }],
+        };
+        assert!(manifest.validate().is_err());
+    }
+}
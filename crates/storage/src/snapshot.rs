@@ -0,0 +1,365 @@
+//! State snapshots for fast sync over P2P
+//! لقطات الحالة للمزامنة السريعة عبر الشبكة
+//!
+//! A snapshot is the complete key/value content of [`crate::StateStorage`]
+//! at a block height, cut into chunks of at most [`SNAPSHOT_CHUNK_BYTES`].
+//! The [`SnapshotManifest`] commits to the block hash and the SHA-256 of
+//! every chunk, so a new node only has to trust the manifest (checked
+//! against a checkpoint when one exists) and can verify each chunk from an
+//! untrusted peer as it arrives. After restoring the state it replays only
+//! the blocks above the snapshot height. The manifest also carries the
+//! headers of the current retarget window, so the restored chain can compute
+//! its next difficulty without the blocks below the snapshot.
+
+use crate::StorageError;
+use opensyria_core::BlockHeader;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Target size of one snapshot chunk in bytes
+pub const SNAPSHOT_CHUNK_BYTES: usize = 256 * 1024;
+
+/// Maximum number of chunks accepted in a manifest from a peer
+pub const MAX_SNAPSHOT_CHUNKS: usize = 16_384;
+
+/// Manifest file name inside a snapshot directory
+const MANIFEST_FILE: &str = "manifest.bin";
+
+/// Description of a state snapshot: what it is and how to verify its pieces
+/// وصف لقطة الحالة وكيفية التحقق من أجزائها
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, bincode::Encode, bincode::Decode)]
+pub struct SnapshotManifest {
+    /// Chain height the state corresponds to
+    pub height: u64,
+    /// Hash of the block at `height`
+    pub block_hash: [u8; 32],
+    /// Total number of key/value entries across all chunks
+    pub entries: u64,
+    /// SHA-256 of each chunk, in order
+    pub chunk_hashes: Vec<[u8; 32]>,
+    /// Headers from the start of the retarget window up to the parent of
+    /// the block at `height`, linked to it by hash
+    pub window_headers: Vec<BlockHeader>,
+}
+
+impl SnapshotManifest {
+    /// Identifier committing to the whole manifest
+    pub fn id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(b"opensyria-state-snapshot");
+        hasher.update(self.height.to_le_bytes());
+        hasher.update(self.block_hash);
+        hasher.update(self.entries.to_le_bytes());
+        for hash in &self.chunk_hashes {
+            hasher.update(hash);
+        }
+        for header in &self.window_headers {
+            hasher.update(header.hash());
+        }
+        hasher.finalize().into()
+    }
+
+    /// Number of chunks in the snapshot
+    pub fn chunk_count(&self) -> usize {
+        self.chunk_hashes.len()
+    }
+}
+
+/// SHA-256 of an encoded chunk
+pub fn chunk_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// Decode a chunk back into its key/value entries
+pub(crate) fn decode_chunk(data: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>, StorageError> {
+    Ok(crate::bincode_helpers::deserialize(data)?)
+}
+
+/// Collects chunks from peers and verifies each against the manifest
+/// تجميع أجزاء اللقطة من الأقران والتحقق من كل جزء
+#[derive(Debug)]
+pub struct SnapshotAssembler {
+    manifest: SnapshotManifest,
+    chunks: Vec<Option<Vec<u8>>>,
+    received: usize,
+}
+
+impl SnapshotAssembler {
+    /// Start assembling the snapshot described by `manifest`
+    pub fn new(manifest: SnapshotManifest) -> Result<Self, StorageError> {
+        if manifest.chunk_count() > MAX_SNAPSHOT_CHUNKS {
+            return Err(StorageError::InvalidSnapshot(format!(
+                "{} chunks exceeds the limit of {}",
+                manifest.chunk_count(),
+                MAX_SNAPSHOT_CHUNKS
+            )));
+        }
+        let chunks = vec![None; manifest.chunk_count()];
+        Ok(Self {
+            manifest,
+            chunks,
+            received: 0,
+        })
+    }
+
+    /// Manifest being assembled
+    pub fn manifest(&self) -> &SnapshotManifest {
+        &self.manifest
+    }
+
+    /// Up to `limit` chunk indices that have not been received yet
+    pub fn missing(&self, limit: usize) -> Vec<u32> {
+        self.chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| chunk.is_none())
+            .map(|(index, _)| index as u32)
+            .take(limit)
+            .collect()
+    }
+
+    /// Add a chunk, rejecting it unless its hash matches the manifest
+    ///
+    /// Returns `Ok(false)` if the chunk had already been received.
+    pub fn add_chunk(&mut self, index: u32, data: Vec<u8>) -> Result<bool, StorageError> {
+        let expected = self
+            .manifest
+            .chunk_hashes
+            .get(index as usize)
+            .ok_or_else(|| {
+                StorageError::InvalidSnapshot(format!("chunk {} out of range", index))
+            })?;
+        if chunk_hash(&data) != *expected {
+            return Err(StorageError::InvalidSnapshot(format!(
+                "chunk {} hash mismatch",
+                index
+            )));
+        }
+
+        let slot = &mut self.chunks[index as usize];
+        if slot.is_some() {
+            return Ok(false);
+        }
+        *slot = Some(data);
+        self.received += 1;
+        Ok(true)
+    }
+
+    /// Number of verified chunks received so far
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    /// Check if every chunk has been received
+    pub fn is_complete(&self) -> bool {
+        self.received == self.chunks.len()
+    }
+
+    /// Verified chunks in order, or `None` while some are still missing
+    pub fn into_chunks(self) -> Option<(SnapshotManifest, Vec<Vec<u8>>)> {
+        let chunks = self.chunks.into_iter().collect::<Option<Vec<_>>>()?;
+        Some((self.manifest, chunks))
+    }
+}
+
+/// Snapshots kept on disk for serving to peers
+/// اللقطات المحفوظة على القرص لخدمة الأقران
+///
+/// Each snapshot lives in `<dir>/<height>/` as `manifest.bin` plus one
+/// `chunk_<index>.bin` per chunk.
+#[derive(Debug, Clone)]
+pub struct SnapshotStore {
+    dir: PathBuf,
+}
+
+impl SnapshotStore {
+    /// Snapshot store rooted at `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn snapshot_dir(&self, height: u64) -> PathBuf {
+        self.dir.join(height.to_string())
+    }
+
+    /// Save a snapshot created by [`crate::StateStorage::create_snapshot`]
+    pub fn save(
+        &self,
+        manifest: &SnapshotManifest,
+        chunks: &[Vec<u8>],
+    ) -> Result<(), StorageError> {
+        let dir = self.snapshot_dir(manifest.height);
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+        for (index, chunk) in chunks.iter().enumerate() {
+            std::fs::write(dir.join(format!("chunk_{}.bin", index)), chunk).map_err(io_error)?;
+        }
+        // Manifest last: a snapshot is only listed once all its chunks exist
+        let manifest_data = crate::bincode_helpers::serialize(manifest)?;
+        std::fs::write(dir.join(MANIFEST_FILE), manifest_data).map_err(io_error)?;
+        Ok(())
+    }
+
+    /// Heights of complete snapshots, ascending
+    pub fn heights(&self) -> Result<Vec<u64>, StorageError> {
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        let mut heights: Vec<u64> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().join(MANIFEST_FILE).exists())
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+        heights.sort_unstable();
+        Ok(heights)
+    }
+
+    /// Manifest of the snapshot at `height`
+    pub fn manifest(&self, height: u64) -> Result<Option<SnapshotManifest>, StorageError> {
+        match read_optional(&self.snapshot_dir(height).join(MANIFEST_FILE))? {
+            Some(data) => Ok(Some(crate::bincode_helpers::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Raw chunk `index` of the snapshot at `height`
+    pub fn chunk(&self, height: u64, index: u32) -> Result<Option<Vec<u8>>, StorageError> {
+        read_optional(
+            &self
+                .snapshot_dir(height)
+                .join(format!("chunk_{}.bin", index)),
+        )
+    }
+
+    /// Delete all but the `keep` most recent snapshots
+    pub fn prune(&self, keep: usize) -> Result<usize, StorageError> {
+        let heights = self.heights()?;
+        let stale = heights.len().saturating_sub(keep);
+        for height in &heights[..stale] {
+            std::fs::remove_dir_all(self.snapshot_dir(*height)).map_err(io_error)?;
+        }
+        Ok(stale)
+    }
+}
+
+fn read_optional(path: &Path) -> Result<Option<Vec<u8>>, StorageError> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(io_error(e)),
+    }
+}
+
+fn io_error(e: std::io::Error) -> StorageError {
+    StorageError::InvalidSnapshot(format!("I/O error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateStorage;
+    use opensyria_core::crypto::{KeyPair, PublicKey};
+    use tempfile::tempdir;
+
+    fn populated_state(dir: &Path, accounts: usize) -> (StateStorage, Vec<PublicKey>) {
+        let state = StateStorage::open(dir.to_path_buf()).unwrap();
+        let mut addresses = Vec::new();
+        for i in 0..accounts {
+            let address = KeyPair::generate().public_key();
+            state.set_balance(&address, 1_000 + i as u64).unwrap();
+            state.set_nonce(&address, i as u64).unwrap();
+            addresses.push(address);
+        }
+        state.increase_supply(10_000_000).unwrap();
+        (state, addresses)
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let dir = tempdir().unwrap();
+        let (source, addresses) = populated_state(&dir.path().join("source"), 50);
+        let (manifest, chunks) = source.create_snapshot(42, [7u8; 32], Vec::new()).unwrap();
+        assert_eq!(manifest.height, 42);
+        assert_eq!(manifest.chunk_count(), chunks.len());
+
+        let store = SnapshotStore::new(dir.path().join("snapshots"));
+        store.save(&manifest, &chunks).unwrap();
+        assert_eq!(store.heights().unwrap(), vec![42]);
+        let served = store.manifest(42).unwrap().unwrap();
+        assert_eq!(served.id(), manifest.id());
+
+        let mut assembler = SnapshotAssembler::new(served).unwrap();
+        for index in assembler.missing(usize::MAX) {
+            let chunk = store.chunk(42, index).unwrap().unwrap();
+            assert!(assembler.add_chunk(index, chunk).unwrap());
+        }
+        assert!(assembler.is_complete());
+        let (manifest, chunks) = assembler.into_chunks().unwrap();
+
+        // The accounts must hash to the root the caller trusts
+        let target = StateStorage::open(dir.path().join("target")).unwrap();
+        assert!(matches!(
+            target.restore_snapshot(&manifest, &chunks, &[0u8; 32]),
+            Err(StorageError::InvalidSnapshot(_))
+        ));
+        assert!(target.is_empty().unwrap());
+        let state_root = source.state_tree().unwrap().root();
+        target
+            .restore_snapshot(&manifest, &chunks, &state_root)
+            .unwrap();
+        for address in &addresses {
+            assert_eq!(
+                target.get_balance(address).unwrap(),
+                source.get_balance(address).unwrap()
+            );
+            assert_eq!(
+                target.get_nonce(address).unwrap(),
+                source.get_nonce(address).unwrap()
+            );
+        }
+        assert_eq!(target.get_total_supply().unwrap(), 10_000_000);
+        assert_eq!(target.get_state_height().unwrap(), 42);
+
+        // Restoring over existing state is refused
+        assert!(target
+            .restore_snapshot(&manifest, &chunks, &state_root)
+            .is_err());
+    }
+
+    #[test]
+    fn test_corrupted_chunk_rejected() {
+        let dir = tempdir().unwrap();
+        let (source, _) = populated_state(dir.path(), 5);
+        let (manifest, mut chunks) = source.create_snapshot(10, [1u8; 32], Vec::new()).unwrap();
+
+        let mut assembler = SnapshotAssembler::new(manifest).unwrap();
+        chunks[0].push(0);
+        assert!(matches!(
+            assembler.add_chunk(0, chunks[0].clone()),
+            Err(StorageError::InvalidSnapshot(_))
+        ));
+        assert!(assembler.add_chunk(99, Vec::new()).is_err());
+        assert_eq!(assembler.received(), 0);
+        assert!(!assembler.is_complete());
+    }
+
+    #[test]
+    fn test_prune_keeps_latest() {
+        let dir = tempdir().unwrap();
+        let (state, _) = populated_state(&dir.path().join("state"), 3);
+        let store = SnapshotStore::new(dir.path().join("snapshots"));
+        for height in [100, 200, 300] {
+            let (manifest, chunks) = state
+                .create_snapshot(height, [0u8; 32], Vec::new())
+                .unwrap();
+            store.save(&manifest, &chunks).unwrap();
+        }
+
+        assert_eq!(store.prune(2).unwrap(), 1);
+        assert_eq!(store.heights().unwrap(), vec![200, 300]);
+        assert!(store.manifest(100).unwrap().is_none());
+    }
+}
@@ -1,27 +1,521 @@
-use crate::StorageError;
+use crate::assets::AssetLedger;
+use crate::contracts::{BlockHost, ContractChanges, ContractReceipt};
+use crate::dex::{DexReceipt, OrderBook};
+use crate::doctor::{
+    self, decodes, DoctorOptions, DoctorReport, KeyShape, Namespace, Suffix, ValueShape,
+    CF_QUARANTINE,
+};
+use crate::journal::{BlockJournal, JournalAccount, JournalReason};
+use crate::limits::StorageResources;
+use crate::migration::{
+    self, Migration, MigrationOptions, MigrationReport, MigrationStep, Schema, SCHEMA_VERSION_KEY,
+};
+use crate::replication::{Replication, WAL_RETENTION_MB, WAL_RETENTION_SECS};
+use crate::snapshot::{chunk_hash, decode_chunk, SnapshotManifest, SNAPSHOT_CHUNK_BYTES};
+use crate::supply::SupplyTotals;
+use crate::tiers::{ColumnFamilyTuning, StateOptions};
+use crate::{RuleViolation, StorageError};
+use opensyria_contracts::{CallContext, ContractVm, Execution, ExecutionFailure};
+use opensyria_core::anchor::{AnchorPublisher, ChainAnchor, SignedAnchor};
+use opensyria_core::asset::{AssetError, AssetId, AssetInfo, AssetOperation};
+use opensyria_core::audit::{AuditEvent, AuditLog, BalanceChange};
+use opensyria_core::contract::{ContractCall, MAX_GAS_PER_BLOCK};
 use opensyria_core::crypto::PublicKey;
+use opensyria_core::delegation::{DelegationError, HotKeyDelegation};
+use opensyria_core::deposit::{
+    escrow_account, DepositError, DepositEscrow, DepositOperation, DepositSettlement,
+};
+use opensyria_core::dex::{DexOperation, Order, OrderId, OrderSide, OrderStatus, Trade};
 use opensyria_core::multisig::MultisigAccount;
-use opensyria_core::Transaction;
-use rocksdb::{Options, WriteBatch, DB, BlockBasedOptions};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use opensyria_core::oracle::{OracleError, OracleRate, OracleUpdate};
+use opensyria_core::policy::{policy_day, SpendingPolicy};
+use opensyria_core::state_proof::{AccountLeaf, StateTree};
+use opensyria_core::{Anomaly, Block, BlockHeader, ChainParams, Transaction};
+use rocksdb::{BlockBasedOptions, ColumnFamilyDescriptor, Options, WriteBatch, DB};
+use std::collections::{hash_map::Entry, BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use dashmap::DashMap;
 use tokio::sync::Mutex;
 
+/// Sum of the balances `items` yields, stopping after the last balance key
+fn balance_total(
+    items: impl Iterator<Item = Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>>,
+) -> Result<u64, StorageError> {
+    let prefix = b"balance_";
+    let mut total = 0u64;
+    for item in items {
+        let (key, value) = item?;
+        if !key.starts_with(prefix) {
+            break;
+        }
+        if let (32, Ok(bytes)) = (key.len() - prefix.len(), <[u8; 8]>::try_from(&*value)) {
+            // SECURITY: Use checked_add to prevent overflow in sum calculation
+            total = total
+                .checked_add(u64::from_le_bytes(bytes))
+                .ok_or(StorageError::BalanceOverflow)?;
+        }
+    }
+    Ok(total)
+}
+
+/// Supply invariant check over one consistent view of the state
+/// فحص ثبات العرض الكلي على لقطة متسقة من الحالة
+///
+/// See [`StateStorage::check_supply_invariant`]. The check reads a RocksDB
+/// snapshot, so blocks applied while it runs do not affect it.
+pub struct SupplyCheck {
+    db: Arc<DB>,
+}
+
+impl SupplyCheck {
+    /// Compare the recorded supply with the balance total
+    pub fn run(&self) -> Result<Option<Anomaly>, StorageError> {
+        let snapshot = self.db.snapshot();
+        let recorded = match snapshot.get(TOTAL_SUPPLY_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.try_into().map_err(|_| StorageError::InvalidChain)?;
+                u64::from_le_bytes(bytes)
+            }
+            None => 0,
+        };
+        if recorded == 0 {
+            return Ok(None);
+        }
+
+        let computed = balance_total(snapshot.iterator(rocksdb::IteratorMode::From(
+            b"balance_",
+            rocksdb::Direction::Forward,
+        )))?;
+        if computed > recorded {
+            return Ok(Some(Anomaly::SupplyInvariant { recorded, computed }));
+        }
+        Ok(None)
+    }
+}
+
+/// Work for the anchor thread, in the order blocks were applied and reverted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AnchorTask {
+    Publish { height: u64, block_hash: [u8; 32] },
+    Retract { height: u64 },
+}
+
+/// Anchor publisher fed by a thread of its own
+///
+/// An anchor commits to the state root, which reads every account, so the
+/// block path only queues a task. Tasks run in order: the anchor of a
+/// reverted block is retracted before the replacing block's is written.
+struct AnchorWorker {
+    publisher: Arc<AnchorPublisher>,
+    tasks: std::sync::mpsc::Sender<AnchorTask>,
+}
+
+impl AnchorWorker {
+    fn spawn(db: Arc<DB>, publisher: Arc<AnchorPublisher>) -> Self {
+        let (tasks, queue) = std::sync::mpsc::channel();
+        let worker = publisher.clone();
+        // Ends once the state storage, and with it the sender, is dropped
+        std::thread::spawn(move || {
+            for task in queue {
+                if let Err(e) = run_anchor_task(&db, &worker, task) {
+                    tracing::warn!("Anchor task {:?} failed: {}", task, e);
+                }
+            }
+        });
+        Self { publisher, tasks }
+    }
+
+    fn queue(&self, task: AnchorTask) {
+        // The thread only stops when this sender is dropped
+        let _ = self.tasks.send(task);
+    }
+}
+
+/// Publish or retract one anchor
+///
+/// A publish reads a RocksDB snapshot and is skipped if the state has
+/// already moved past the anchor height, as it may during a fast sync.
+fn run_anchor_task(
+    db: &DB,
+    publisher: &AnchorPublisher,
+    task: AnchorTask,
+) -> Result<Option<SignedAnchor>, StorageError> {
+    let (height, block_hash) = match task {
+        AnchorTask::Publish { height, block_hash } => (height, block_hash),
+        AnchorTask::Retract { height } => {
+            publisher
+                .retract(height)
+                .map_err(|e| StorageError::AnchorError(e.to_string()))?;
+            return Ok(None);
+        }
+    };
+
+    let snapshot = db.snapshot();
+    let read_u64 = |key: &[u8]| -> Result<u64, StorageError> {
+        match snapshot.get(key)? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.try_into().map_err(|_| StorageError::InvalidChain)?;
+                Ok(u64::from_le_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    };
+    if read_u64(STATE_HEIGHT_KEY)? != height {
+        return Ok(None);
+    }
+
+    let mut accounts: BTreeMap<[u8; 32], AccountLeaf> = BTreeMap::new();
+    for prefix in [b"balance_".as_slice(), b"nonce_".as_slice()] {
+        let mode = rocksdb::IteratorMode::From(prefix, rocksdb::Direction::Forward);
+        for item in snapshot.iterator(mode) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            StateStorage::add_account_entry(&mut accounts, &key, &value);
+        }
+    }
+
+    let anchor = ChainAnchor::new(
+        height,
+        &block_hash,
+        &StateStorage::account_tree(accounts).root(),
+        read_u64(TOTAL_SUPPLY_KEY)?,
+    );
+    publisher
+        .publish(anchor)
+        .map(Some)
+        .map_err(|e| StorageError::AnchorError(e.to_string()))
+}
+
 /// State storage for account balances and metadata
 /// تخزين حالة أرصدة الحسابات والبيانات الوصفية
 /// 
 /// SECURITY: Uses per-address locking to prevent TOCTOU race conditions
 /// in concurrent multisig transaction execution
 pub struct StateStorage {
-    db: DB,
+    /// Shared with [`Replication`] and [`SupplyCheck`] handles, which
+    /// outlive read locks
+    db: Arc<DB>,
     /// Per-address locks for atomic multisig operations
     /// Prevents double-spend via concurrent execution with same nonce
     address_locks: Arc<DashMap<[u8; 32], Arc<Mutex<()>>>>,
+    /// Optional compliance audit trail for applied and reverted blocks
+    audit: Option<Arc<AuditLog>>,
+    /// Optional publisher of signed chain-state anchors
+    anchors: Option<AnchorWorker>,
+    /// Runs contract transactions while blocks are applied
+    contracts: ContractVm,
+}
+
+/// Column family holding contract code, storage and receipts
+const CF_CONTRACT_STATE: &str = "contract_state";
+
+/// Every column family, in the order hashed by replication digests
+const COLUMN_FAMILIES: &[&str] = &["default", CF_CONTRACT_STATE, CF_QUARANTINE];
+
+/// Key layout versions of the state database, oldest first
+pub(crate) const SCHEMA: Schema = Schema {
+    name: "state",
+    migrations: &[
+        migration::BASELINE,
+        Migration {
+            version: 2,
+            description: "key daily spending records by account and day",
+            backup: true,
+            run: split_day_spent_records,
+        },
+        Migration {
+            version: 3,
+            description: "record running supply totals per height",
+            backup: false,
+            run: record_supply_totals,
+        },
+    ],
+    column_families: COLUMN_FAMILIES,
+};
+
+/// Move `prefix ‖ account → day ‖ spent` records to `prefix ‖ account ‖ day → spent`
+fn split_day_spent_records(step: &mut MigrationStep<'_>) -> Result<(), StorageError> {
+    let db = step.db();
+    for prefix in [POLICY_SPENT_PREFIX, DELEGATION_SPENT_PREFIX] {
+        for item in db.prefix_iterator(prefix) {
+            let (key, value) = item?;
+            if !key.starts_with(prefix) {
+                break;
+            }
+            step.progress(1);
+            if key.len() != prefix.len() + 32 || value.len() != 16 {
+                continue;
+            }
+            let mut new_key = key.to_vec();
+            new_key.extend_from_slice(&value[..8]);
+            step.put_cf("default", &new_key, &value[8..])?;
+            step.delete_cf("default", &key)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the [`SupplyTotals`] of every height that has a journal
+fn record_supply_totals(step: &mut MigrationStep<'_>) -> Result<(), StorageError> {
+    let db = step.db();
+    let mut totals = SupplyTotals::default();
+    for item in db.prefix_iterator(JOURNAL_PREFIX) {
+        let (key, value) = item?;
+        if !key.starts_with(JOURNAL_PREFIX) {
+            break;
+        }
+        step.progress(1);
+        let journal: BlockJournal = crate::bincode_helpers::deserialize(&value)?;
+        totals = totals.with_block(&journal);
+        step.put_cf(
+            "default",
+            &StateStorage::supply_totals_key(journal.height),
+            &crate::bincode_helpers::serialize(&totals)?,
+        )?;
+    }
+    Ok(())
 }
+const CONTRACT_CODE_PREFIX: &[u8] = b"code_";
+/// Contract storage, keyed by contract address then the contract's key
+const CONTRACT_STORAGE_PREFIX: &[u8] = b"storage_";
+/// Receipts keyed by transaction hash
+const CONTRACT_RECEIPT_PREFIX: &[u8] = b"receipt_";
+/// Prefix of contract state entries in snapshots; sorts after every key of
+/// the default column family
+const CONTRACT_SNAPSHOT_PREFIX: &[u8] = b"~contract_";
 
 const TOTAL_SUPPLY_KEY: &[u8] = b"total_supply";
+const FROZEN_PREFIX: &[u8] = b"frozen_";
+const SPENDING_POLICY_PREFIX: &[u8] = b"spend_policy_";
+/// Amount spent under a daily limit, keyed by account then big-endian day
+const POLICY_SPENT_PREFIX: &[u8] = b"policy_spent_";
+/// Policy replaced by a policy update, keyed by the update's hash, for reorgs
+const POLICY_UNDO_PREFIX: &[u8] = b"policy_undo_";
+const DELEGATION_PREFIX: &[u8] = b"hot_delegation_";
+/// Amount moved by a hot key, keyed by account then big-endian day
+const DELEGATION_SPENT_PREFIX: &[u8] = b"delegation_spent_";
+/// Delegation replaced by a delegation update, keyed by the update's hash
+const DELEGATION_UNDO_PREFIX: &[u8] = b"delegation_undo_";
+/// Asset registry entries, keyed by asset id
+const ASSET_INFO_PREFIX: &[u8] = b"asset_info_";
+/// Asset balances, keyed by holder then asset id
+const ASSET_BALANCE_PREFIX: &[u8] = b"asset_bal_";
+/// Orders, keyed by order id, kept after they are filled or cancelled
+const DEX_ORDER_PREFIX: &[u8] = b"dex_order_";
+/// Open orders by asset, side, then price-time priority
+const DEX_BOOK_PREFIX: &[u8] = b"dex_book_";
+/// Trades by asset, then big-endian height, position in block and fill
+const DEX_TRADE_PREFIX: &[u8] = b"dex_trade_";
+/// DEX receipts, keyed by transaction hash
+const DEX_RECEIPT_PREFIX: &[u8] = b"dex_receipt_";
+/// Whitelisted oracle keys, with the proposal that added them
+const ORACLE_KEY_PREFIX: &[u8] = b"oracle_key_";
+/// Published rates by pair, a zero byte, then big-endian quote timestamp
+const ORACLE_RATE_PREFIX: &[u8] = b"oracle_rate_";
+/// Whitelist changes waiting for the next block, keyed by oracle key: the
+/// adding proposal, or `None` for a removal
+const ORACLE_PENDING_PREFIX: &[u8] = b"oracle_pending_";
+/// Whitelist changes a block applied, keyed by big-endian height
+const ORACLE_UNDO_PREFIX: &[u8] = b"oracle_undo_";
+/// Proposal deposit escrows, keyed by the escrowing transaction's hash
+const DEPOSIT_ESCROW_PREFIX: &[u8] = b"deposit_escrow_";
+/// Audit events committed with their block but not yet in the audit log,
+/// keyed by big-endian staging order
+const AUDIT_PENDING_PREFIX: &[u8] = b"audit_pending_";
+/// Oracle key, its whitelist entry before a block, and the change the
+/// block applied
+type OracleChange = ([u8; 32], Option<u64>, Option<u64>);
+/// Height of the last block applied through [`StateStorage::apply_block_at_height`]
+const STATE_HEIGHT_KEY: &[u8] = b"state_height";
+/// Latest balance snapshot opened with [`StateStorage::open_balance_snapshot`]
+const BALANCE_SNAPSHOT_EPOCH_KEY: &[u8] = b"balance_snapshot_epoch";
+/// Copy-on-write balances, keyed by address then big-endian snapshot ID
+const BALANCE_SNAPSHOT_PREFIX: &[u8] = b"balsnap_";
+/// Accounting journals, keyed by big-endian height
+const JOURNAL_PREFIX: &[u8] = b"journal_";
+/// Cumulative [`SupplyTotals`], keyed by big-endian height
+const SUPPLY_TOTALS_PREFIX: &[u8] = b"supply_totals_";
+/// Node-local keys that are never part of a snapshot
+const SNAPSHOT_EXCLUDED_PREFIXES: &[&[u8]] = &[
+    b"partial_multisig_",
+    STATE_HEIGHT_KEY,
+    BALANCE_SNAPSHOT_EPOCH_KEY,
+    BALANCE_SNAPSHOT_PREFIX,
+    SCHEMA_VERSION_KEY,
+    JOURNAL_PREFIX,
+    SUPPLY_TOTALS_PREFIX,
+    AUDIT_PENDING_PREFIX,
+];
+
+/// Key layout checked by [`StateStorage::examine`]; exact keys come before
+/// the prefixes they share a start with
+const NAMESPACES: &[Namespace] = {
+    use KeyShape::{Exact, Prefix};
+    use Suffix::{AtLeast, Len};
+    use ValueShape::{Any, Decodes};
+    const fn ns(name: &'static str, key: KeyShape, value: ValueShape) -> Namespace {
+        Namespace {
+            name,
+            column_family: "default",
+            key,
+            value,
+        }
+    }
+    const fn contract(name: &'static str, key: KeyShape, value: ValueShape) -> Namespace {
+        Namespace {
+            name,
+            column_family: CF_CONTRACT_STATE,
+            key,
+            value,
+        }
+    }
+    &[
+        ns("total_supply", Exact(TOTAL_SUPPLY_KEY), ValueShape::Len(8)),
+        ns("state_height", Exact(STATE_HEIGHT_KEY), ValueShape::Len(8)),
+        ns(
+            "balance_snapshot_epoch",
+            Exact(BALANCE_SNAPSHOT_EPOCH_KEY),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "schema_version",
+            Exact(SCHEMA_VERSION_KEY),
+            ValueShape::Len(4),
+        ),
+        ns("balance", Prefix(b"balance_", Len(32)), ValueShape::Len(8)),
+        ns("nonce", Prefix(b"nonce_", Len(32)), ValueShape::Len(8)),
+        ns("frozen", Prefix(FROZEN_PREFIX, Len(32)), ValueShape::Len(8)),
+        ns(
+            "multisig",
+            Prefix(b"multisig_", Len(32)),
+            Decodes(decodes::<MultisigAccount>),
+        ),
+        ns(
+            "partial_multisig",
+            Prefix(b"partial_multisig_", Len(32)),
+            Any,
+        ),
+        ns(
+            "spend_policy",
+            Prefix(SPENDING_POLICY_PREFIX, Len(32)),
+            Decodes(decodes::<SpendingPolicy>),
+        ),
+        ns(
+            "policy_spent",
+            Prefix(POLICY_SPENT_PREFIX, Len(40)),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "policy_undo",
+            Prefix(POLICY_UNDO_PREFIX, Len(32)),
+            Decodes(decodes::<Option<SpendingPolicy>>),
+        ),
+        ns(
+            "hot_delegation",
+            Prefix(DELEGATION_PREFIX, Len(32)),
+            Decodes(decodes::<HotKeyDelegation>),
+        ),
+        ns(
+            "delegation_spent",
+            Prefix(DELEGATION_SPENT_PREFIX, Len(40)),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "delegation_undo",
+            Prefix(DELEGATION_UNDO_PREFIX, Len(32)),
+            Decodes(decodes::<Option<HotKeyDelegation>>),
+        ),
+        ns(
+            "asset_info",
+            Prefix(ASSET_INFO_PREFIX, Len(32)),
+            Decodes(decodes::<AssetInfo>),
+        ),
+        ns(
+            "asset_balance",
+            Prefix(ASSET_BALANCE_PREFIX, Len(64)),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "dex_order",
+            Prefix(DEX_ORDER_PREFIX, Len(32)),
+            Decodes(decodes::<Order>),
+        ),
+        ns(
+            "dex_book",
+            Prefix(DEX_BOOK_PREFIX, Len(53)),
+            ValueShape::Len(32),
+        ),
+        ns(
+            "dex_trade",
+            Prefix(DEX_TRADE_PREFIX, Len(48)),
+            Decodes(decodes::<Trade>),
+        ),
+        ns(
+            "dex_receipt",
+            Prefix(DEX_RECEIPT_PREFIX, Len(32)),
+            Decodes(decodes::<DexReceipt>),
+        ),
+        ns(
+            "oracle_key",
+            Prefix(ORACLE_KEY_PREFIX, Len(32)),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "oracle_rate",
+            Prefix(ORACLE_RATE_PREFIX, AtLeast(9)),
+            Decodes(decodes::<OracleRate>),
+        ),
+        ns(
+            "oracle_pending",
+            Prefix(ORACLE_PENDING_PREFIX, Len(32)),
+            Decodes(decodes::<Option<u64>>),
+        ),
+        ns(
+            "oracle_undo",
+            Prefix(ORACLE_UNDO_PREFIX, Len(8)),
+            Decodes(decodes::<Vec<OracleChange>>),
+        ),
+        ns(
+            "deposit_escrow",
+            Prefix(DEPOSIT_ESCROW_PREFIX, Len(32)),
+            Decodes(decodes::<DepositEscrow>),
+        ),
+        ns(
+            "audit_pending",
+            Prefix(AUDIT_PENDING_PREFIX, Len(8)),
+            Decodes(|bytes| serde_json::from_slice::<AuditEvent>(bytes).is_ok()),
+        ),
+        ns(
+            "balance_snapshot",
+            Prefix(BALANCE_SNAPSHOT_PREFIX, Len(40)),
+            ValueShape::Len(8),
+        ),
+        ns(
+            "journal",
+            Prefix(JOURNAL_PREFIX, Len(8)),
+            Decodes(decodes::<BlockJournal>),
+        ),
+        ns(
+            "supply_totals",
+            Prefix(SUPPLY_TOTALS_PREFIX, Len(8)),
+            Decodes(decodes::<SupplyTotals>),
+        ),
+        contract("contract_code", Prefix(CONTRACT_CODE_PREFIX, Len(32)), Any),
+        contract(
+            "contract_storage",
+            Prefix(CONTRACT_STORAGE_PREFIX, AtLeast(32)),
+            Any,
+        ),
+        contract(
+            "contract_receipt",
+            Prefix(CONTRACT_RECEIPT_PREFIX, Len(32)),
+            Decodes(decodes::<ContractReceipt>),
+        ),
+    ]
+};
 
 impl StateStorage {
     /// Open state storage at path
@@ -29,30 +523,268 @@ impl StateStorage {
     /// ✅  PERFORMANCE FIX (P1-002): Bloom filters enabled for 10x read speedup
     /// Bloom filters provide probabilistic membership testing that dramatically
     /// reduces disk I/O for non-existent keys (most balance queries).
+    ///
+    /// Pending schema migrations run first; a database written by a newer
+    /// binary is refused with [`StorageError::SchemaTooNew`].
     pub fn open(path: PathBuf) -> Result<Self, StorageError> {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        
-        // PERFORMANCE FIX: Enable bloom filters for faster key lookups
-        // 10 bits per key provides ~1% false positive rate while giving ~10x speedup
-        let mut block_opts = BlockBasedOptions::default();
-        block_opts.set_bloom_filter(10.0, false);
-        opts.set_block_based_table_factory(&block_opts);
-        
-        // Enable compression to reduce disk usage
-        opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
-        
-        // Optimize for point lookups (balance queries)
-        opts.optimize_for_point_lookup(64); // 64MB block cache
+        Self::open_with_resources(path, &StorageResources::default())
+    }
+
+    /// Open state storage drawing on a cache and write buffer budget shared
+    /// with other databases
+    /// فتح تخزين الحالة ضمن ميزانية ذاكرة مشتركة
+    pub fn open_with_resources(
+        path: PathBuf,
+        resources: &StorageResources,
+    ) -> Result<Self, StorageError> {
+        Self::open_with_options(path, resources, &StateOptions::default())
+    }
 
-        let db = DB::open(&opts, path)?;
+    /// Open state storage with a fast path tier and per column family tuning
+    /// فتح تخزين الحالة مع مسار سريع وضبط لكل عائلة أعمدة
+    ///
+    /// See [`crate::tiers`] for how the fast path is recorded and reused.
+    pub fn open_with_options(
+        path: PathBuf,
+        resources: &StorageResources,
+        options: &StateOptions,
+    ) -> Result<Self, StorageError> {
+        let db = Self::open_db(&path, resources, options)?;
+        migration::migrate(&db, &path, &SCHEMA, MigrationOptions::default())?;
 
         Ok(Self {
-            db,
+            db: Arc::new(db),
             address_locks: Arc::new(DashMap::new()),
+            audit: None,
+            anchors: None,
+            contracts: ContractVm::new(),
         })
     }
 
+    /// Run pending schema migrations on the database at `path` and close it
+    /// تشغيل ترحيلات المخطط المعلقة على قاعدة البيانات
+    pub fn migrate(
+        path: PathBuf,
+        options: MigrationOptions,
+    ) -> Result<MigrationReport, StorageError> {
+        let db = Self::open_db(
+            &path,
+            &StorageResources::default(),
+            &StateOptions::default(),
+        )?;
+        migration::migrate(&db, &path, &SCHEMA, options)
+    }
+
+    fn open_db(
+        path: &Path,
+        resources: &StorageResources,
+        options: &StateOptions,
+    ) -> Result<DB, StorageError> {
+        let mut opts = Self::column_family_options(resources, &options.accounts);
+        opts.create_if_missing(true);
+
+        // Keep archived WAL files so replication secondaries can catch up
+        opts.set_wal_ttl_seconds(WAL_RETENTION_SECS);
+        opts.set_wal_size_limit_mb(WAL_RETENTION_MB);
+
+        // Hot SST files on the fast path, the rest in `path`
+        options.apply_tier(path, &mut opts)?;
+
+        opts.create_missing_column_families(true);
+        let cf_descriptors = vec![
+            ColumnFamilyDescriptor::new("default", opts.clone()),
+            ColumnFamilyDescriptor::new(
+                CF_CONTRACT_STATE,
+                Self::column_family_options(resources, &options.contracts),
+            ),
+            ColumnFamilyDescriptor::new(
+                CF_QUARANTINE,
+                Self::column_family_options(resources, &options.quarantine),
+            ),
+        ];
+        Ok(DB::open_cf_descriptors(&opts, path, cf_descriptors)?)
+    }
+
+    /// Options for one column family, drawing on the shared budgets
+    fn column_family_options(resources: &StorageResources, tuning: &ColumnFamilyTuning) -> Options {
+        let mut opts = Options::default();
+
+        // Optimize for point lookups (balance queries); its private block
+        // cache is replaced by the shared one below
+        opts.optimize_for_point_lookup(64);
+
+        let mut block_opts = BlockBasedOptions::default();
+        tuning.apply(&mut opts, &mut block_opts);
+
+        // Block cache, memtables and open files come out of the shared budget
+        resources.apply(&mut opts, &mut block_opts);
+        opts
+    }
+
+    /// Checkpoint, WAL and digest access for operator replication
+    /// الوصول إلى نقاط التفتيش وسجل الكتابة لغرض النسخ المتماثل
+    pub fn replication(&self) -> Replication {
+        Replication::new(self.db.clone(), COLUMN_FAMILIES)
+    }
+
+    /// Check every key and value against the state key layout
+    /// فحص كل مفتاح وقيمة مقابل تخطيط مفاتيح الحالة
+    ///
+    /// State entries are primary data, so corrupt ones can only be reported
+    /// or quarantined; they are restored by re-syncing state.
+    pub fn examine(&self, options: DoctorOptions) -> Result<DoctorReport, StorageError> {
+        doctor::examine(&self.db, SCHEMA.name, COLUMN_FAMILIES, NAMESPACES, options)
+    }
+
+    /// Record every transaction applied or reverted by the block methods
+    /// تسجيل كل معاملة مطبقة أو معكوسة في سجل التدقيق
+    ///
+    /// Events are committed in the block's own batch and appended to the
+    /// log right after, so a crash in between cannot lose them: events left
+    /// over from one are appended here, before any new block.
+    pub fn set_audit_log(&mut self, audit: Arc<AuditLog>) -> Result<(), StorageError> {
+        self.audit = Some(audit);
+        self.flush_audit_events()
+    }
+
+    /// Attached audit log, if any
+    pub fn audit_log(&self) -> Option<&Arc<AuditLog>> {
+        self.audit.as_ref()
+    }
+
+    /// Publish a signed chain-state anchor at every anchor height
+    /// نشر مرساة موقعة لحالة السلسلة كل عدد من الكتل
+    ///
+    /// Anchors are computed and written on a thread of their own. Reverting
+    /// a block retracts its anchor.
+    pub fn set_anchor_publisher(&mut self, anchors: Arc<AnchorPublisher>) {
+        self.anchors = Some(AnchorWorker::spawn(self.db.clone(), anchors));
+    }
+
+    /// Attached anchor publisher, if any
+    pub fn anchor_publisher(&self) -> Option<&Arc<AnchorPublisher>> {
+        self.anchors.as_ref().map(|worker| &worker.publisher)
+    }
+
+    /// Queue the anchor for the block `block_hash` at `height` if one is due
+    ///
+    /// Call after the block was applied: the anchor commits to the state
+    /// root and supply, so it is skipped unless the state is still at
+    /// `height` when the anchor thread gets to it.
+    pub fn publish_anchor(&self, height: u64, block_hash: &[u8; 32]) {
+        if let Some(anchors) = &self.anchors {
+            if anchors.publisher.is_due(height) {
+                anchors.queue(AnchorTask::Publish {
+                    height,
+                    block_hash: *block_hash,
+                });
+            }
+        }
+    }
+
+    /// Build per-transaction audit events by replaying balances in order
+    fn audit_events(
+        &self,
+        transactions: &[Transaction],
+        reverted: bool,
+    ) -> Result<Vec<AuditEvent>, StorageError> {
+        let mut balances: HashMap<PublicKey, u64> = HashMap::new();
+        let mut events = Vec::with_capacity(transactions.len());
+
+        let ordered: Box<dyn Iterator<Item = &Transaction>> = if reverted {
+            Box::new(transactions.iter().rev())
+        } else {
+            Box::new(transactions.iter())
+        };
+        for tx in ordered {
+            let mut changes = Vec::with_capacity(2);
+            let mut adjust = |address: &PublicKey, delta: i128| -> Result<(), StorageError> {
+                let before = match balances.get(address) {
+                    Some(balance) => *balance,
+                    None => self.get_balance(address)?,
+                };
+                let after = u64::try_from(before as i128 + delta)
+                    .map_err(|_| StorageError::InsufficientBalance)?;
+                balances.insert(*address, after);
+                changes.push(BalanceChange::new(address, before, after));
+                Ok(())
+            };
+
+            let sign: i128 = if reverted { -1 } else { 1 };
+            if !tx.is_coinbase() {
+                adjust(&tx.from, -sign * tx.debit(&tx.from) as i128)?;
+                if tx.fee_payer() != tx.from {
+                    adjust(&tx.fee_payer(), -sign * tx.fee as i128)?;
+                }
+            }
+            adjust(&tx.to, sign * tx.amount as i128)?;
+
+            events.push(AuditEvent::transaction(tx, reverted, changes));
+        }
+        Ok(events)
+    }
+
+    /// Stage audit events in the batch that commits the changes they describe
+    fn stage_audit_events(
+        &self,
+        batch: &mut WriteBatch,
+        events: Vec<AuditEvent>,
+    ) -> Result<(), StorageError> {
+        let first = self
+            .pending_audit_events()?
+            .last()
+            .map_or(0, |(id, _)| id + 1);
+        for (id, event) in (first..).zip(events) {
+            let value =
+                serde_json::to_vec(&event).map_err(|e| StorageError::AuditError(e.to_string()))?;
+            batch.put(Self::audit_pending_key(id), value);
+        }
+        Ok(())
+    }
+
+    /// Staged audit events in the order they were committed
+    fn pending_audit_events(&self) -> Result<Vec<(u64, AuditEvent)>, StorageError> {
+        let mut pending = Vec::new();
+        for item in self.db.prefix_iterator(AUDIT_PENDING_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(AUDIT_PENDING_PREFIX) {
+                break;
+            }
+            let id_bytes: [u8; 8] = key[AUDIT_PENDING_PREFIX.len()..]
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            let event = serde_json::from_slice(&value)
+                .map_err(|e| StorageError::AuditError(e.to_string()))?;
+            pending.push((u64::from_be_bytes(id_bytes), event));
+        }
+        Ok(pending)
+    }
+
+    /// Append staged audit events to the attached log, then clear them
+    fn flush_audit_events(&self) -> Result<(), StorageError> {
+        let Some(audit) = &self.audit else {
+            return Ok(());
+        };
+        let last_logged = audit.last_event();
+        for (index, (id, event)) in self.pending_audit_events()?.into_iter().enumerate() {
+            // Only the first event can have been appended before a crash
+            // kept it from being cleared
+            if index > 0 || last_logged.as_ref() != Some(&event) {
+                audit
+                    .record(event)
+                    .map_err(|e| StorageError::AuditError(e.to_string()))?;
+            }
+            self.db.delete(Self::audit_pending_key(id))?;
+        }
+        Ok(())
+    }
+
+    fn audit_pending_key(id: u64) -> Vec<u8> {
+        let mut key = AUDIT_PENDING_PREFIX.to_vec();
+        key.extend_from_slice(&id.to_be_bytes());
+        key
+    }
+
     /// Get account balance
     pub fn get_balance(&self, address: &PublicKey) -> Result<u64, StorageError> {
         let key = Self::balance_key(address);
@@ -68,11 +800,117 @@ impl StateStorage {
 
     /// Set account balance
     pub fn set_balance(&self, address: &PublicKey, amount: u64) -> Result<(), StorageError> {
-        let key = Self::balance_key(address);
-        self.db.put(&key, amount.to_le_bytes())?;
+        let mut batch = WriteBatch::default();
+        self.preserve_balance(&mut batch, address, self.balance_snapshot_epoch()?)?;
+        batch.put(Self::balance_key(address), amount.to_le_bytes());
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Open a balance snapshot and return its ID
+    /// فتح لقطة أرصدة وإرجاع معرفها
+    ///
+    /// Nothing is copied up front. The first time an account's balance
+    /// changes after the snapshot opened, its previous balance is kept, so
+    /// [`Self::get_balance_at_snapshot`] costs one lookup per account and
+    /// opening a snapshot costs the same with ten or ten million accounts.
+    pub fn open_balance_snapshot(&self) -> Result<u64, StorageError> {
+        let epoch = self.balance_snapshot_epoch()? + 1;
+        self.db
+            .put(BALANCE_SNAPSHOT_EPOCH_KEY, epoch.to_le_bytes())?;
+        Ok(epoch)
+    }
+
+    /// Balance `address` held when `snapshot` was opened
+    pub fn get_balance_at_snapshot(
+        &self,
+        address: &PublicKey,
+        snapshot: u64,
+    ) -> Result<u64, StorageError> {
+        // The earliest change at or after the snapshot kept the balance it had
+        let start = Self::balance_snapshot_key(address, snapshot);
+        let address_prefix = &start[..BALANCE_SNAPSHOT_PREFIX.len() + 32];
+        let mut iter = self.db.iterator(rocksdb::IteratorMode::From(
+            &start,
+            rocksdb::Direction::Forward,
+        ));
+        if let Some(item) = iter.next() {
+            let (key, value) = item?;
+            if key.starts_with(address_prefix) {
+                let bytes: [u8; 8] = value
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| StorageError::InvalidChain)?;
+                return Ok(u64::from_le_bytes(bytes));
+            }
+        }
+        // Unchanged since the snapshot
+        self.get_balance(address)
+    }
+
+    /// Drop kept balances that no snapshot from `oldest_open` on can read
+    ///
+    /// Returns how many were removed. Pass one past the latest snapshot ID
+    /// when no snapshot is in use.
+    pub fn prune_balance_snapshots(&self, oldest_open: u64) -> Result<usize, StorageError> {
+        let mut batch = WriteBatch::default();
+        let mut pruned = 0;
+        for item in self.db.prefix_iterator(BALANCE_SNAPSHOT_PREFIX) {
+            let (key, _) = item?;
+            if !key.starts_with(BALANCE_SNAPSHOT_PREFIX) {
+                break;
+            }
+            let epoch_bytes: [u8; 8] = key[key.len() - 8..]
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            if u64::from_be_bytes(epoch_bytes) < oldest_open {
+                batch.delete(&key);
+                pruned += 1;
+            }
+        }
+        if pruned > 0 {
+            self.db.write(batch)?;
+        }
+        Ok(pruned)
+    }
+
+    /// Latest opened balance snapshot, or zero if none was opened
+    fn balance_snapshot_epoch(&self) -> Result<u64, StorageError> {
+        match self.db.get(BALANCE_SNAPSHOT_EPOCH_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.try_into().map_err(|_| StorageError::InvalidChain)?;
+                Ok(u64::from_le_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Keep `address`'s current balance for snapshot `epoch` before the
+    /// first change to it since that snapshot opened
+    fn preserve_balance(
+        &self,
+        batch: &mut WriteBatch,
+        address: &PublicKey,
+        epoch: u64,
+    ) -> Result<(), StorageError> {
+        if epoch == 0 {
+            return Ok(());
+        }
+        let key = Self::balance_snapshot_key(address, epoch);
+        if self.db.get(&key)?.is_none() {
+            batch.put(&key, self.get_balance(address)?.to_le_bytes());
+        }
         Ok(())
     }
 
+    fn balance_snapshot_key(address: &PublicKey, epoch: u64) -> Vec<u8> {
+        let mut key = Vec::with_capacity(BALANCE_SNAPSHOT_PREFIX.len() + 40);
+        key.extend_from_slice(BALANCE_SNAPSHOT_PREFIX);
+        key.extend_from_slice(&address.0);
+        key.extend_from_slice(&epoch.to_be_bytes());
+        key
+    }
+
     /// Add to account balance
     pub fn add_balance(&self, address: &PublicKey, amount: u64) -> Result<(), StorageError> {
         let current = self.get_balance(address)?;
@@ -133,16 +971,38 @@ impl StateStorage {
     /// Should ONLY be called in debug/audit mode, not in production block validation.
     pub fn verify_total_supply(&self) -> Result<bool, StorageError> {
         let recorded_supply = self.get_total_supply()?;
-        let balances = self.get_all_balances()?;
-        
-        // SECURITY: Use checked_add to prevent overflow in sum calculation
-        let computed_supply = balances.values()
-            .try_fold(0u64, |acc, &balance| acc.checked_add(balance))
-            .ok_or(StorageError::BalanceOverflow)?;
-        
+        let computed_supply = self.compute_total_supply()?;
+
         Ok(recorded_supply == computed_supply)
     }
 
+    /// Sum of all account balances (O(n), see [`Self::verify_total_supply`])
+    pub fn compute_total_supply(&self) -> Result<u64, StorageError> {
+        balance_total(self.db.prefix_iterator(b"balance_"))
+    }
+
+    /// Check that balances never exceed the recorded supply
+    /// التحقق من أن مجموع الأرصدة لا يتجاوز العرض المسجل
+    ///
+    /// Coinbase amounts include recycled fees, so the recorded supply may
+    /// exceed the balance total; only the reverse means coins appeared from
+    /// nowhere. State that never recorded a supply (not built through
+    /// [`Self::apply_block_atomic`]) is not checked.
+    ///
+    /// Reads every balance; callers on a hot path run [`Self::supply_check`]
+    /// on a blocking thread instead.
+    pub fn check_supply_invariant(&self) -> Result<Option<Anomaly>, StorageError> {
+        self.supply_check().run()
+    }
+
+    /// Handle running [`Self::check_supply_invariant`] without the state
+    /// borrowed, so the scan can run after the lock is released
+    pub fn supply_check(&self) -> SupplyCheck {
+        SupplyCheck {
+            db: self.db.clone(),
+        }
+    }
+
     /// Subtract from account balance (returns error if insufficient)
     pub fn sub_balance(&self, address: &PublicKey, amount: u64) -> Result<(), StorageError> {
         let current = self.get_balance(address)?;
@@ -328,6 +1188,62 @@ impl StateStorage {
         Ok(count)
     }
 
+    /// Merkle tree over every account's balance and nonce
+    /// شجرة ميركل لأرصدة الحسابات وأعدادها التسلسلية
+    ///
+    /// Accounts with zero balance and zero nonce are left out, so the root
+    /// does not depend on whether zero balances were pruned. This scans the
+    /// whole account set; callers serving many proofs should cache the tree
+    /// per chain tip.
+    pub fn state_tree(&self) -> Result<StateTree, StorageError> {
+        let mut accounts: BTreeMap<[u8; 32], AccountLeaf> = BTreeMap::new();
+
+        for prefix in [b"balance_".as_slice(), b"nonce_".as_slice()] {
+            for item in self.db.prefix_iterator(prefix) {
+                let (key, value) = item?;
+                if !key.starts_with(prefix) {
+                    break;
+                }
+                Self::add_account_entry(&mut accounts, &key, &value);
+            }
+        }
+
+        Ok(Self::account_tree(accounts))
+    }
+
+    /// Fold a `balance_` or `nonce_` entry into its account's leaf
+    fn add_account_entry(accounts: &mut BTreeMap<[u8; 32], AccountLeaf>, key: &[u8], value: &[u8]) {
+        for prefix in [b"balance_".as_slice(), b"nonce_".as_slice()] {
+            let Some(address) = key.strip_prefix(prefix) else {
+                continue;
+            };
+            let (Ok(address), Ok(value)) =
+                (<[u8; 32]>::try_from(address), <[u8; 8]>::try_from(value))
+            else {
+                return;
+            };
+
+            let leaf = accounts.entry(address).or_insert(AccountLeaf {
+                address: PublicKey(address),
+                balance: 0,
+                nonce: 0,
+            });
+            if prefix == b"balance_" {
+                leaf.balance = u64::from_le_bytes(value);
+            } else {
+                leaf.nonce = u64::from_le_bytes(value);
+            }
+        }
+    }
+
+    fn account_tree(accounts: BTreeMap<[u8; 32], AccountLeaf>) -> StateTree {
+        StateTree::new(
+            accounts
+                .into_values()
+                .filter(|leaf| leaf.balance > 0 || leaf.nonce > 0),
+        )
+    }
+
     // Helper functions
     fn balance_key(address: &PublicKey) -> Vec<u8> {
         let mut key = Vec::with_capacity(40);
@@ -343,6 +1259,20 @@ impl StateStorage {
         key
     }
 
+    fn frozen_key(address: &PublicKey) -> Vec<u8> {
+        let mut key = Vec::with_capacity(39);
+        key.extend_from_slice(FROZEN_PREFIX);
+        key.extend_from_slice(&address.0);
+        key
+    }
+
+    fn prefixed_key(prefix: &[u8], id: &[u8; 32]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(prefix.len() + 32);
+        key.extend_from_slice(prefix);
+        key.extend_from_slice(id);
+        key
+    }
+
     fn multisig_key(address: &PublicKey) -> Vec<u8> {
         let mut key = Vec::with_capacity(48);
         key.extend_from_slice(b"multisig_");
@@ -350,530 +1280,3822 @@ impl StateStorage {
         key
     }
 
-    /// Store multisig account configuration
-    pub fn store_multisig_account(&self, account: &MultisigAccount) -> Result<(), StorageError> {
-        let address = account.address();
-        let key = Self::multisig_key(&address);
-
-        // Serialize multisig account using bincode
-        let serialized = crate::bincode_helpers::serialize(account).map_err(|_e| StorageError::InvalidChain)?;
-
-        self.db.put(&key, &serialized)?;
+    /// Freeze an address by governance decision
+    /// تجميد عنوان بقرار من الحوكمة
+    ///
+    /// Frozen accounts can still receive funds but cannot spend them.
+    pub fn freeze_account(
+        &self,
+        address: &PublicKey,
+        proposal_id: u64,
+    ) -> Result<(), StorageError> {
+        self.db
+            .put(Self::frozen_key(address), proposal_id.to_le_bytes())?;
         Ok(())
     }
 
-    /// Get multisig account configuration
-    pub fn get_multisig_account(
-        &self,
-        address: &PublicKey,
-    ) -> Result<Option<MultisigAccount>, StorageError> {
-        let key = Self::multisig_key(address);
+    /// Lift a freeze
+    /// رفع التجميد عن عنوان
+    pub fn unfreeze_account(&self, address: &PublicKey) -> Result<(), StorageError> {
+        self.db.delete(Self::frozen_key(address))?;
+        Ok(())
+    }
 
-        match self.db.get(&key)? {
-            Some(data) => {
-                let account: MultisigAccount =
-                    crate::bincode_helpers::deserialize(&data).map_err(|_| StorageError::InvalidChain)?;
-                Ok(Some(account))
+    /// Governance proposal that froze `address`, if it is frozen
+    pub fn get_freeze(&self, address: &PublicKey) -> Result<Option<u64>, StorageError> {
+        match self.db.get(Self::frozen_key(address))? {
+            Some(data) if data.len() == 8 => {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&data);
+                Ok(Some(u64::from_le_bytes(bytes)))
             }
+            Some(_) => Err(StorageError::SerializationError(
+                "Invalid frozen account record".to_string(),
+            )),
             None => Ok(None),
         }
     }
 
-    /// Check if an address is a multisig account
-    pub fn is_multisig_account(&self, address: &PublicKey) -> Result<bool, StorageError> {
-        let key = Self::multisig_key(address);
-        Ok(self.db.get(&key)?.is_some())
-    }
-
-    /// Get multisig nonce
-    pub fn get_multisig_nonce(&self, address: &PublicKey) -> Result<u64, StorageError> {
-        // Multisig accounts use the same nonce mechanism as regular accounts
-        self.get_nonce(address)
+    /// Check whether an address is frozen
+    pub fn is_frozen(&self, address: &PublicKey) -> Result<bool, StorageError> {
+        Ok(self.db.get(Self::frozen_key(address))?.is_some())
     }
 
-    /// Increment multisig nonce
-    pub fn increment_multisig_nonce(&self, address: &PublicKey) -> Result<(), StorageError> {
-        self.increment_nonce(address)
+    /// All frozen addresses with the proposal that froze them
+    pub fn get_frozen_accounts(&self) -> Result<Vec<(PublicKey, u64)>, StorageError> {
+        let mut frozen = Vec::new();
+        for item in self.db.prefix_iterator(FROZEN_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(FROZEN_PREFIX) {
+                break;
+            }
+            if key.len() == FROZEN_PREFIX.len() + 32 && value.len() == 8 {
+                let mut pk_bytes = [0u8; 32];
+                pk_bytes.copy_from_slice(&key[FROZEN_PREFIX.len()..]);
+                let mut id_bytes = [0u8; 8];
+                id_bytes.copy_from_slice(&value);
+                frozen.push((PublicKey(pk_bytes), u64::from_le_bytes(id_bytes)));
+            }
+        }
+        Ok(frozen)
     }
 
-    /// Validate and execute a multisig transaction with nonce checking
-    /// 
-    /// ✅  SECURITY FIX: Uses per-address mutex to prevent TOCTOU race conditions.
-    /// The lock ensures that nonce check and state update are atomic operations.
-    /// 
-    /// This prevents double-spend attacks where two concurrent transactions
-    /// with the same nonce could both pass validation and execute.
-    /// 
-    /// THREAD-SAFE: Multiple threads can execute multisig transactions concurrently,
-    /// but transactions for the same address are serialized.
-    pub async fn execute_multisig_transaction(
+    /// Spending policy attached to `address`
+    /// سياسة الإنفاق المرتبطة بالعنوان
+    pub fn get_spending_policy(
         &self,
-        multisig_tx: &opensyria_core::MultisigTransaction,
-    ) -> Result<(), StorageError> {
-        let multisig_address = multisig_tx.account.address();
-
-        // SECURITY FIX: Acquire per-address lock before any checks
-        // This prevents concurrent execution of transactions for same address
-        let lock = self.address_locks
-            .entry(multisig_address.0)
-            .or_insert_with(|| Arc::new(Mutex::new(())))
-            .clone();
-        
-        let _guard = lock.lock().await;
+        address: &PublicKey,
+    ) -> Result<Option<SpendingPolicy>, StorageError> {
+        match self
+            .db
+            .get(Self::prefixed_key(SPENDING_POLICY_PREFIX, &address.0))?
+        {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| {
+                    StorageError::SerializationError("Invalid spending policy record".to_string())
+                }),
+            None => Ok(None),
+        }
+    }
 
-        // Now all operations are atomic within the lock scope
+    /// Amount `address` has spent under its policy in day window `day`
+    pub fn get_policy_spent(&self, address: &PublicKey, day: u64) -> Result<u64, StorageError> {
+        self.day_spent(POLICY_SPENT_PREFIX, address, day)
+    }
 
-        // 1. Verify multisig account exists
-        let stored_account = self
-            .get_multisig_account(&multisig_address)?
-            .ok_or(StorageError::InvalidTransaction)?;
+    fn day_spent_key(prefix: &[u8], address: &PublicKey, day: u64) -> Vec<u8> {
+        let mut key = Self::prefixed_key(prefix, &address.0);
+        key.extend_from_slice(&day.to_be_bytes());
+        key
+    }
 
-        // Verify configuration matches
-        if stored_account != multisig_tx.account {
-            return Err(StorageError::InvalidTransaction);
+    fn day_spent(&self, prefix: &[u8], address: &PublicKey, day: u64) -> Result<u64, StorageError> {
+        match self.db.get(Self::day_spent_key(prefix, address, day))? {
+            Some(data) => data
+                .as_slice()
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| {
+                    StorageError::SerializationError("Invalid daily spending record".to_string())
+                }),
+            None => Ok(0),
         }
+    }
 
-        // 2. CRITICAL: Verify nonce to prevent replay attacks
-        // This read is now protected by the mutex
-        let current_nonce = self.get_nonce(&multisig_address)?;
-        if multisig_tx.nonce != current_nonce {
-            return Err(StorageError::InvalidTransaction);
+    fn put_day_spent(
+        batch: &mut WriteBatch,
+        prefix: &[u8],
+        address: &PublicKey,
+        day: u64,
+        spent: u64,
+    ) {
+        let key = Self::day_spent_key(prefix, address, day);
+        if spent == 0 {
+            batch.delete(key);
+        } else {
+            batch.put(key, spent.to_be_bytes());
         }
+    }
 
-        // 3. Verify signatures meet threshold
-        if let Err(_) = multisig_tx.verify() {
-            return Err(StorageError::InvalidTransaction);
+    fn put_policy(
+        &self,
+        batch: &mut WriteBatch,
+        address: &PublicKey,
+        policy: Option<&SpendingPolicy>,
+    ) -> Result<(), StorageError> {
+        let key = Self::prefixed_key(SPENDING_POLICY_PREFIX, &address.0);
+        match policy {
+            Some(policy) => batch.put(
+                key,
+                crate::bincode_helpers::serialize(policy)
+                    .map_err(|e| StorageError::SerializationError(e.to_string()))?,
+            ),
+            None => batch.delete(key),
         }
+        Ok(())
+    }
 
-        // 4. Check expiry if set
-        // Note: This requires block height context, handled by caller
+    /// Check spending policies for a block, in order, and stage the
+    /// resulting policy and spending records in `batch`
+    fn apply_spending_policies(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        day: u64,
+    ) -> Result<(), StorageError> {
+        let violation =
+            |e: opensyria_core::PolicyError| StorageError::PolicyViolation(e.to_string());
+        let mut policies: HashMap<PublicKey, Option<SpendingPolicy>> = HashMap::new();
+        let mut spent: HashMap<PublicKey, u64> = HashMap::new();
+        let mut updated = Vec::new();
+
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let mut accounts = vec![tx.from];
+            if tx.fee_payer() != tx.from {
+                accounts.push(tx.fee_payer());
+            }
+            for account in accounts {
+                let policy = match policies.entry(account) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.get_spending_policy(&account)?),
+                };
+                let Some(policy) = policy else {
+                    continue;
+                };
+                let spent_today = match spent.get(&account) {
+                    Some(spent) => *spent,
+                    None => self.get_policy_spent(&account, day)?,
+                };
+                if account == tx.from {
+                    policy.check(tx, spent_today).map_err(violation)?;
+                } else {
+                    policy.check_daily(tx.fee, spent_today).map_err(violation)?;
+                }
+                spent.insert(account, spent_today.saturating_add(tx.debit(&account)));
+            }
 
-        // 5. Check balance (total = amount + fee)
-        let balance = self.get_balance(&multisig_address)?;
-        let total_required = multisig_tx
-            .amount
-            .checked_add(multisig_tx.fee)
-            .ok_or(StorageError::BalanceOverflow)?;
+            // Updates apply from the next transaction on
+            if let Some(update) = SpendingPolicy::from_update(tx) {
+                let update = update.map_err(violation)?;
+                let previous =
+                    policies.insert(tx.from, (!update.is_unrestricted()).then_some(update));
+                batch.put(
+                    Self::prefixed_key(POLICY_UNDO_PREFIX, &tx.hash()),
+                    crate::bincode_helpers::serialize(&previous.flatten())
+                        .map_err(|e| StorageError::SerializationError(e.to_string()))?,
+                );
+                updated.push(tx.from);
+            }
+        }
 
-        if balance < total_required {
-            return Err(StorageError::InsufficientBalance);
+        for address in updated {
+            self.put_policy(batch, &address, policies[&address].as_ref())?;
         }
+        for (address, spent) in spent {
+            Self::put_day_spent(batch, POLICY_SPENT_PREFIX, &address, day, spent);
+        }
+        Ok(())
+    }
 
-        // 6. Execute atomically: transfer + increment nonce
-        // While WriteBatch itself is atomic, the protection comes from the mutex
-        // preventing concurrent access to the same address
-        let mut batch = WriteBatch::default();
+    /// Undo [`Self::apply_spending_policies`] for transactions applied in `day`
+    fn revert_spending_policies(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        day: u64,
+    ) -> Result<(), StorageError> {
+        // Replay the block backwards with the policies in force before each
+        // transaction, so exactly the debits that were counted come off
+        let mut policies: HashMap<PublicKey, Option<SpendingPolicy>> = HashMap::new();
+        let mut spent: HashMap<PublicKey, u64> = HashMap::new();
+        for tx in transactions.iter().rev().filter(|tx| !tx.is_coinbase()) {
+            if let Some(Ok(_)) = SpendingPolicy::from_update(tx) {
+                let undo_key = Self::prefixed_key(POLICY_UNDO_PREFIX, &tx.hash());
+                let data = self.db.get(&undo_key)?.ok_or_else(|| {
+                    StorageError::SerializationError("Missing policy undo record".to_string())
+                })?;
+                let previous: Option<SpendingPolicy> =
+                    crate::bincode_helpers::deserialize(&data)
+                        .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+                self.put_policy(batch, &tx.from, previous.as_ref())?;
+                policies.insert(tx.from, previous);
+                batch.delete(undo_key);
+            }
 
-        // Deduct from multisig account
-        let new_balance = balance - total_required;
-        let balance_key = Self::balance_key(&multisig_address);
-        batch.put(&balance_key, new_balance.to_le_bytes());
+            let mut accounts = vec![tx.from];
+            if tx.fee_payer() != tx.from {
+                accounts.push(tx.fee_payer());
+            }
+            for account in accounts {
+                let policy = match policies.entry(account) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.get_spending_policy(&account)?),
+                };
+                if policy.is_none() {
+                    continue;
+                }
+                let amount = match spent.entry(account) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.get_policy_spent(&account, day)?),
+                };
+                *amount = amount.saturating_sub(tx.debit(&account));
+            }
+        }
+        for (address, amount) in spent {
+            Self::put_day_spent(batch, POLICY_SPENT_PREFIX, &address, day, amount);
+        }
+        Ok(())
+    }
 
-        // Credit recipient
-        let recipient_balance = self.get_balance(&multisig_tx.to)?;
-        let new_recipient_balance = recipient_balance
-            .checked_add(multisig_tx.amount)
-            .ok_or(StorageError::BalanceOverflow)?;
-        let recipient_key = Self::balance_key(&multisig_tx.to);
-        batch.put(&recipient_key, new_recipient_balance.to_le_bytes());
+    /// Hot key delegation set by `address`
+    /// تفويض المفتاح الساخن للعنوان
+    pub fn get_delegation(
+        &self,
+        address: &PublicKey,
+    ) -> Result<Option<HotKeyDelegation>, StorageError> {
+        match self
+            .db
+            .get(Self::prefixed_key(DELEGATION_PREFIX, &address.0))?
+        {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| {
+                    StorageError::SerializationError("Invalid delegation record".to_string())
+                }),
+            None => Ok(None),
+        }
+    }
 
-        // CRITICAL: Increment nonce to prevent replay
-        let nonce_key = Self::nonce_key(&multisig_address);
-        batch.put(&nonce_key, (current_nonce + 1).to_le_bytes());
+    /// Amount the hot key has moved from `address` in day window `day`
+    pub fn get_delegation_spent(&self, address: &PublicKey, day: u64) -> Result<u64, StorageError> {
+        self.day_spent(DELEGATION_SPENT_PREFIX, address, day)
+    }
 
-        // Atomic commit
-        self.db.write(batch)?;
+    fn put_delegation(
+        &self,
+        batch: &mut WriteBatch,
+        address: &PublicKey,
+        delegation: Option<&HotKeyDelegation>,
+    ) -> Result<(), StorageError> {
+        let key = Self::prefixed_key(DELEGATION_PREFIX, &address.0);
+        match delegation {
+            Some(delegation) => batch.put(
+                key,
+                crate::bincode_helpers::serialize(delegation)
+                    .map_err(|e| StorageError::SerializationError(e.to_string()))?,
+            ),
+            None => batch.delete(key),
+        }
+        Ok(())
+    }
 
-        // Lock released here when _guard drops
+    /// Check delegated transactions for a block against their allowances, in
+    /// order, and stage the resulting delegation and spending records
+    fn apply_delegations(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        day: u64,
+    ) -> Result<(), StorageError> {
+        let violation = |e: DelegationError| StorageError::DelegationViolation(e.to_string());
+        let mut delegations: HashMap<PublicKey, Option<HotKeyDelegation>> = HashMap::new();
+        let mut spent: HashMap<PublicKey, u64> = HashMap::new();
+        let mut updated = Vec::new();
+
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let update = HotKeyDelegation::from_update(tx);
+            if tx.delegate.is_none() && update.is_none() {
+                continue;
+            }
+            let delegation = match delegations.entry(tx.from) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(self.get_delegation(&tx.from)?),
+            };
+
+            if tx.delegate.is_some() {
+                let spent_today = match spent.get(&tx.from) {
+                    Some(spent) => *spent,
+                    None => self.get_delegation_spent(&tx.from, day)?,
+                };
+                HotKeyDelegation::authorize(delegation.as_ref(), tx, spent_today)
+                    .map_err(violation)?;
+                spent.insert(tx.from, spent_today.saturating_add(tx.debit(&tx.from)));
+            }
+
+            if let Some(update) = update {
+                let previous = std::mem::replace(delegation, update.map_err(violation)?);
+                batch.put(
+                    Self::prefixed_key(DELEGATION_UNDO_PREFIX, &tx.hash()),
+                    crate::bincode_helpers::serialize(&previous)
+                        .map_err(|e| StorageError::SerializationError(e.to_string()))?,
+                );
+                updated.push(tx.from);
+            }
+        }
 
+        for address in updated {
+            self.put_delegation(batch, &address, delegations[&address].as_ref())?;
+        }
+        for (address, spent) in spent {
+            Self::put_day_spent(batch, DELEGATION_SPENT_PREFIX, &address, day, spent);
+        }
         Ok(())
     }
 
-    /// Store partial multisig transaction (for incremental signing)
-    pub fn store_partial_multisig(
+    /// Undo [`Self::apply_delegations`] for transactions applied in `day`
+    fn revert_delegations(
         &self,
-        tx_hash: &[u8; 32],
-        transaction_data: &[u8],
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        day: u64,
     ) -> Result<(), StorageError> {
-        let key = Self::partial_multisig_key(tx_hash);
-        self.db.put(&key, transaction_data)?;
+        let mut spent: HashMap<PublicKey, u64> = HashMap::new();
+        for tx in transactions.iter().rev().filter(|tx| !tx.is_coinbase()) {
+            if let Some(Ok(_)) = HotKeyDelegation::from_update(tx) {
+                let undo_key = Self::prefixed_key(DELEGATION_UNDO_PREFIX, &tx.hash());
+                let data = self.db.get(&undo_key)?.ok_or_else(|| {
+                    StorageError::SerializationError("Missing delegation undo record".to_string())
+                })?;
+                let previous: Option<HotKeyDelegation> = crate::bincode_helpers::deserialize(&data)
+                    .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+                self.put_delegation(batch, &tx.from, previous.as_ref())?;
+                batch.delete(undo_key);
+            }
+
+            // Every delegated transaction was counted when it was applied
+            if tx.delegate.is_some() {
+                let amount = match spent.entry(tx.from) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => entry.insert(self.get_delegation_spent(&tx.from, day)?),
+                };
+                *amount = amount.saturating_sub(tx.debit(&tx.from));
+            }
+        }
+        for (address, amount) in spent {
+            Self::put_day_spent(batch, DELEGATION_SPENT_PREFIX, &address, day, amount);
+        }
         Ok(())
     }
 
-    /// Get partial multisig transaction
-    pub fn get_partial_multisig(&self, tx_hash: &[u8; 32]) -> Result<Option<Vec<u8>>, StorageError> {
-        let key = Self::partial_multisig_key(tx_hash);
-        Ok(self.db.get(&key)?)
+    fn contract_storage_key(contract: &PublicKey, key: &[u8]) -> Vec<u8> {
+        let mut storage_key = Self::prefixed_key(CONTRACT_STORAGE_PREFIX, &contract.0);
+        storage_key.extend_from_slice(key);
+        storage_key
     }
 
-    /// Delete partial multisig transaction (after execution or expiry)
-    pub fn delete_partial_multisig(&self, tx_hash: &[u8; 32]) -> Result<(), StorageError> {
-        let key = Self::partial_multisig_key(tx_hash);
-        self.db.delete(&key)?;
-        Ok(())
+    /// Wasm code deployed at `contract`
+    /// الحصول على شيفرة العقد
+    pub fn get_contract_code(&self, contract: &PublicKey) -> Result<Option<Vec<u8>>, StorageError> {
+        let cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        Ok(self
+            .db
+            .get_cf(&cf, Self::prefixed_key(CONTRACT_CODE_PREFIX, &contract.0))?)
     }
 
-    fn partial_multisig_key(tx_hash: &[u8; 32]) -> Vec<u8> {
-        let mut key = Vec::with_capacity(48);
-        key.extend_from_slice(b"partial_multisig_");
-        key.extend_from_slice(tx_hash);
-        key
+    /// Value `contract` stored under `key`
+    /// الحصول على قيمة من تخزين العقد
+    pub fn get_contract_storage(
+        &self,
+        contract: &PublicKey,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>, StorageError> {
+        let cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        Ok(self
+            .db
+            .get_cf(&cf, Self::contract_storage_key(contract, key))?)
     }
 
-    /// Apply block transactions atomically (all-or-nothing)
-    /// تطبيق معاملات الكتلة بشكل ذري (كل شيء أو لا شيء)
-    /// 
-    /// ✅  SECURITY FIX (CRITICAL-003): Atomic nonce validation and increment
-    /// This method now validates nonces WITHIN the atomic batch operation to prevent
-    /// TOCTOU (Time-Of-Check-Time-Of-Use) race conditions. The nonce check and 
-    /// increment are performed atomically using RocksDB's WriteBatch.
-    /// 
-    /// THREAD-SAFE: Multiple threads can call this concurrently, but RocksDB
-    /// ensures that WriteBatch commits are serialized at the database level.
-    pub fn apply_block_atomic(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
-        let mut batch = WriteBatch::default();
-        
-        // Track balance/nonce changes in memory before batching
-        let mut balance_changes: HashMap<PublicKey, i128> = HashMap::new();
-        let mut nonce_changes: HashMap<PublicKey, u64> = HashMap::new();
-        let mut nonce_validations: HashMap<PublicKey, Vec<u64>> = HashMap::new();
-        let mut supply_increase: u64 = 0;
+    /// Receipt of the contract transaction with hash `tx_hash`
+    /// الحصول على إيصال معاملة العقد
+    pub fn get_contract_receipt(
+        &self,
+        tx_hash: &[u8; 32],
+    ) -> Result<Option<ContractReceipt>, StorageError> {
+        let cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        match self
+            .db
+            .get_cf(&cf, Self::prefixed_key(CONTRACT_RECEIPT_PREFIX, tx_hash))?
+        {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| {
+                    StorageError::SerializationError("Invalid contract receipt".to_string())
+                }),
+            None => Ok(None),
+        }
+    }
 
-        // Calculate all state changes AND track required nonces
+    /// Run the block's contract transactions in order, adding their
+    /// transfers to `balance_changes`
+    ///
+    /// A failed execution moves the transaction's value back to the sender;
+    /// its fee stays spent.
+    fn execute_contracts(
+        &self,
+        transactions: &[Transaction],
+        height: u64,
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<ContractChanges, StorageError> {
+        let mut changes = ContractChanges::default();
+        if !transactions.iter().any(ContractCall::is_contract) {
+            return Ok(changes);
+        }
+
+        let mut running: HashMap<PublicKey, i128> = HashMap::new();
+        let mut block_gas = 0u64;
         for tx in transactions {
-            // Skip coinbase transactions (miner rewards)
             if tx.is_coinbase() {
-                *balance_changes.entry(tx.to).or_insert(0) += tx.amount as i128;
-                supply_increase = supply_increase
-                    .checked_add(tx.amount)
-                    .ok_or(StorageError::BalanceOverflow)?;
+                *running.entry(tx.to).or_insert(0) += tx.amount as i128;
                 continue;
             }
+            *running.entry(tx.from).or_insert(0) -= tx.amount as i128;
+            *running.entry(tx.fee_payer()).or_insert(0) -= tx.fee as i128;
+            *running.entry(tx.to).or_insert(0) += tx.amount as i128;
 
-            // Regular transactions: deduct from sender, add to receiver
-            let total_debit = tx
-                .amount
-                .checked_add(tx.fee)
-                .ok_or(StorageError::BalanceOverflow)?;
-
-            *balance_changes.entry(tx.from).or_insert(0) -= total_debit as i128;
-            *balance_changes.entry(tx.to).or_insert(0) += tx.amount as i128;
-            
-            // SECURITY FIX: Track expected nonce for validation
-            nonce_validations.entry(tx.from).or_insert_with(Vec::new).push(tx.nonce);
-            
-            // Track nonce increment
-            *nonce_changes.entry(tx.from).or_insert(0) += 1;
-        }
+            let Some(call) = ContractCall::from_transaction(tx) else {
+                continue;
+            };
+            let call = call.map_err(|e| StorageError::ContractError(e.to_string()))?;
+            block_gas = block_gas.saturating_add(call.gas_limit());
+            if block_gas > MAX_GAS_PER_BLOCK {
+                return Err(StorageError::ContractError(format!(
+                    "block buys more than {} gas",
+                    MAX_GAS_PER_BLOCK
+                )));
+            }
 
-        // CRITICAL SECURITY FIX: Validate nonces are sequential per address
-        // This prevents nonce gaps, duplicates, or replay attacks
-        for (address, tx_nonces) in &nonce_validations {
-            let current_nonce = self.get_nonce(address)?;
-            
-            // Check that transaction nonces are sequential starting from current_nonce
-            let mut expected_nonce = current_nonce;
-            for &tx_nonce in tx_nonces {
-                if tx_nonce != expected_nonce {
-                    // CRITICAL: Nonce mismatch indicates replay attack or out-of-order execution
-                    return Err(StorageError::InvalidTransaction);
+            let host = BlockHost {
+                state: self,
+                changes: &changes,
+                running: &running,
+            };
+            let context = CallContext {
+                contract: tx.to,
+                caller: tx.from,
+                value: tx.amount,
+                height,
+            };
+            let execution = match &call {
+                ContractCall::Deploy { code, gas_limit } => {
+                    if host.code(&tx.to)?.is_some() {
+                        return Err(StorageError::ContractError(
+                            "contract address already has code".to_string(),
+                        ));
+                    }
+                    self.contracts.deploy(code, context, &host, *gas_limit)
+                }
+                ContractCall::Call {
+                    method,
+                    args,
+                    gas_limit,
+                } => match host.code(&tx.to)? {
+                    Some(code) => self
+                        .contracts
+                        .call(&code, method, args, context, &host, *gas_limit),
+                    None => Ok(Execution {
+                        gas_used: 0,
+                        outcome: Err(ExecutionFailure::NoContract),
+                    }),
+                },
+            }
+            .map_err(|e| StorageError::ContractError(e.to_string()))?;
+
+            let mut receipt = ContractReceipt {
+                gas_used: execution.gas_used,
+                ..Default::default()
+            };
+            match execution.outcome {
+                Ok(effects) => {
+                    for key in effects.writes.keys() {
+                        receipt.undo.push((key.clone(), host.storage(&tx.to, key)?));
+                    }
+                    for (key, value) in effects.writes {
+                        changes.storage.insert((tx.to, key), value);
+                    }
+                    for (to, amount) in &effects.transfers {
+                        for changes in [&mut running, &mut *balance_changes] {
+                            *changes.entry(tx.to).or_insert(0) -= *amount as i128;
+                            *changes.entry(*to).or_insert(0) += *amount as i128;
+                        }
+                    }
+                    if let ContractCall::Deploy { code, .. } = call {
+                        changes.codes.insert(tx.to, code);
+                        receipt.deployed = true;
+                    }
+                    receipt.transfers = effects.transfers;
+                    receipt.output = effects.output;
+                }
+                Err(failure) => {
+                    for changes in [&mut running, &mut *balance_changes] {
+                        *changes.entry(tx.from).or_insert(0) += tx.amount as i128;
+                        *changes.entry(tx.to).or_insert(0) -= tx.amount as i128;
+                    }
+                    receipt.error = Some(failure.to_string());
                 }
-                expected_nonce += 1;
             }
+            changes.receipts.push((tx.hash(), receipt));
         }
+        Ok(changes)
+    }
 
-        // Check total supply will not exceed MAX_SUPPLY
-        if supply_increase > 0 {
-            use opensyria_core::MAX_SUPPLY;
-            let current_supply = self.get_total_supply()?;
-            let new_supply = current_supply
-                .checked_add(supply_increase)
-                .ok_or(StorageError::BalanceOverflow)?;
-            if new_supply > MAX_SUPPLY {
-                return Err(StorageError::InvalidChain);
+    /// Record code, storage and receipts from [`Self::execute_contracts`]
+    fn put_contract_changes(
+        &self,
+        batch: &mut WriteBatch,
+        changes: ContractChanges,
+    ) -> Result<(), StorageError> {
+        let cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        for (contract, code) in changes.codes {
+            batch.put_cf(
+                &cf,
+                Self::prefixed_key(CONTRACT_CODE_PREFIX, &contract.0),
+                code,
+            );
+        }
+        for ((contract, key), value) in changes.storage {
+            let storage_key = Self::contract_storage_key(&contract, &key);
+            match value {
+                Some(value) => batch.put_cf(&cf, storage_key, value),
+                None => batch.delete_cf(&cf, storage_key),
             }
         }
+        for (tx_hash, receipt) in changes.receipts {
+            batch.put_cf(
+                &cf,
+                Self::prefixed_key(CONTRACT_RECEIPT_PREFIX, &tx_hash),
+                crate::bincode_helpers::serialize(&receipt)?,
+            );
+        }
+        Ok(())
+    }
 
-        // Validate all balances are sufficient
-        for (address, change) in &balance_changes {
-            let current_balance = self.get_balance(address)?;
-            let new_balance = (current_balance as i128) + change;
-            
-            if new_balance < 0 {
-                return Err(StorageError::InsufficientBalance);
+    /// Undo [`Self::execute_contracts`] for reverted transactions, adding the
+    /// reversed transfers to `balance_changes`
+    fn revert_contracts(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<(), StorageError> {
+        let cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        // Newest first, so each key ends at the value it had before the block
+        for tx in transactions
+            .iter()
+            .rev()
+            .filter(|tx| ContractCall::is_contract(tx))
+        {
+            let tx_hash = tx.hash();
+            let receipt = self.get_contract_receipt(&tx_hash)?.ok_or_else(|| {
+                StorageError::SerializationError("Missing contract receipt".to_string())
+            })?;
+
+            if receipt.succeeded() {
+                for (to, amount) in &receipt.transfers {
+                    *balance_changes.entry(tx.to).or_insert(0) += *amount as i128;
+                    *balance_changes.entry(*to).or_insert(0) -= *amount as i128;
+                }
+                for (key, previous) in receipt.undo {
+                    let storage_key = Self::contract_storage_key(&tx.to, &key);
+                    match previous {
+                        Some(value) => batch.put_cf(&cf, storage_key, value),
+                        None => batch.delete_cf(&cf, storage_key),
+                    }
+                }
+                if receipt.deployed {
+                    batch.delete_cf(&cf, Self::prefixed_key(CONTRACT_CODE_PREFIX, &tx.to.0));
+                }
+            } else {
+                // The value never left the sender
+                *balance_changes.entry(tx.from).or_insert(0) -= tx.amount as i128;
+                *balance_changes.entry(tx.to).or_insert(0) += tx.amount as i128;
             }
+            batch.delete_cf(&cf, Self::prefixed_key(CONTRACT_RECEIPT_PREFIX, &tx_hash));
         }
+        Ok(())
+    }
 
-        // Apply balance changes to batch
-        for (address, change) in balance_changes {
-            let current_balance = self.get_balance(&address)?;
-            let new_balance = ((current_balance as i128) + change) as u64;
-            
-            let key = Self::balance_key(&address);
-            batch.put(&key, new_balance.to_le_bytes());
+    fn asset_balance_key(address: &PublicKey, asset: &AssetId) -> Vec<u8> {
+        let mut key = Self::prefixed_key(ASSET_BALANCE_PREFIX, &address.0);
+        key.extend_from_slice(&asset.0);
+        key
+    }
+
+    /// Registry entry of `asset`
+    /// الحصول على سجل الأصل
+    pub fn get_asset(&self, asset: &AssetId) -> Result<Option<AssetInfo>, StorageError> {
+        match self
+            .db
+            .get(Self::prefixed_key(ASSET_INFO_PREFIX, &asset.0))?
+        {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| StorageError::SerializationError("Invalid asset record".to_string())),
+            None => Ok(None),
         }
+    }
 
-        // Apply nonce changes to batch (ATOMIC with balance updates)
-        for (address, increment) in nonce_changes {
-            let current_nonce = self.get_nonce(&address)?;
-            let new_nonce = current_nonce + increment;
-            
-            let key = Self::nonce_key(&address);
-            batch.put(&key, new_nonce.to_le_bytes());
+    /// Every registered asset, in id order
+    pub fn get_assets(&self) -> Result<Vec<(AssetId, AssetInfo)>, StorageError> {
+        let mut assets = Vec::new();
+        for item in self.db.prefix_iterator(ASSET_INFO_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(ASSET_INFO_PREFIX) {
+                break;
+            }
+            let id: [u8; 32] = key[ASSET_INFO_PREFIX.len()..]
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            let info = crate::bincode_helpers::deserialize(&value).map_err(|_| {
+                StorageError::SerializationError("Invalid asset record".to_string())
+            })?;
+            assets.push((AssetId(id), info));
         }
+        Ok(assets)
+    }
 
-        // Update total supply if there were coinbase transactions
-        if supply_increase > 0 {
+    /// Balance of `asset` held by `address`
+    /// الحصول على رصيد الأصل
+    pub fn get_asset_balance(
+        &self,
+        address: &PublicKey,
+        asset: &AssetId,
+    ) -> Result<u64, StorageError> {
+        match self.db.get(Self::asset_balance_key(address, asset))? {
+            Some(data) => {
+                let bytes: [u8; 8] = data
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| StorageError::InvalidChain)?;
+                Ok(u64::from_le_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Every non-zero asset balance of `address`, in asset id order
+    /// الحصول على جميع أرصدة الأصول للعنوان
+    pub fn get_asset_balances(
+        &self,
+        address: &PublicKey,
+    ) -> Result<Vec<(AssetId, u64)>, StorageError> {
+        let prefix = Self::prefixed_key(ASSET_BALANCE_PREFIX, &address.0);
+        let mut balances = Vec::new();
+        for item in self.db.prefix_iterator(&prefix) {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let id: [u8; 32] = key[prefix.len()..]
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            let bytes: [u8; 8] = value
+                .as_ref()
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            balances.push((AssetId(id), u64::from_le_bytes(bytes)));
+        }
+        Ok(balances)
+    }
+
+    fn put_asset_ledger(batch: &mut WriteBatch, ledger: AssetLedger) -> Result<(), StorageError> {
+        for (id, info) in ledger.assets {
+            let key = Self::prefixed_key(ASSET_INFO_PREFIX, &id.0);
+            match info {
+                Some(info) => batch.put(key, crate::bincode_helpers::serialize(&info)?),
+                None => batch.delete(key),
+            }
+        }
+        for ((address, asset), balance) in ledger.balances {
+            let key = Self::asset_balance_key(&address, &asset);
+            match balance {
+                0 => batch.delete(key),
+                balance => batch.put(key, balance.to_le_bytes()),
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the block's asset and DEX operations in order and stage the
+    /// resulting registry entries, balances and orders
+    ///
+    /// SYL moved into and out of order escrow joins `balance_changes`.
+    fn apply_assets(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        height: u64,
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<(), StorageError> {
+        let violation = |e: AssetError| StorageError::AssetViolation(e.to_string());
+        let mut ledger = AssetLedger::default();
+        let mut book = OrderBook::default();
+
+        for (index, tx) in transactions.iter().enumerate() {
+            if tx.is_coinbase() {
+                continue;
+            }
+            if let Some(operation) = DexOperation::from_transaction(tx) {
+                match operation.map_err(|e| StorageError::DexViolation(e.to_string()))? {
+                    DexOperation::Place {
+                        asset,
+                        side,
+                        price,
+                        amount,
+                    } => {
+                        let order = Order {
+                            id: OrderId::of(tx),
+                            owner: tx.from,
+                            asset,
+                            side,
+                            price,
+                            amount,
+                            remaining: amount,
+                            height,
+                            index: index as u32,
+                            status: OrderStatus::Open,
+                        };
+                        book.place(self, &mut ledger, balance_changes, order)?;
+                    }
+                    DexOperation::Cancel { order } => {
+                        book.cancel(self, &mut ledger, balance_changes, tx, &order)?;
+                    }
+                }
+                continue;
+            }
+            let Some(operation) = AssetOperation::from_transaction(tx) else {
+                continue;
+            };
+            match operation.map_err(violation)? {
+                AssetOperation::Create {
+                    symbol,
+                    name,
+                    decimals,
+                    max_supply,
+                } => {
+                    let id = AssetId::derive(&tx.from, tx.nonce);
+                    let entry = ledger.asset(self, &id)?;
+                    if entry.is_some() {
+                        return Err(violation(AssetError::AssetExists(id.to_hex())));
+                    }
+                    *entry = Some(AssetInfo {
+                        issuer: Some(tx.from),
+                        symbol,
+                        name,
+                        decimals,
+                        max_supply,
+                        supply: 0,
+                    });
+                }
+                AssetOperation::Mint { asset, to, amount } => {
+                    let info = ledger.existing_asset(self, &asset)?;
+                    info.authorize_issuer(&tx.from).map_err(violation)?;
+                    info.supply = info.minted_supply(amount).map_err(violation)?;
+                    // Supply is capped by a u64, so no balance can overflow
+                    *ledger.balance(self, &to, &asset)? += amount;
+                }
+                AssetOperation::Transfer { asset, to, amount } => {
+                    ledger.existing_asset(self, &asset)?;
+                    ledger.debit(self, &tx.from, &asset, amount)?;
+                    *ledger.balance(self, &to, &asset)? += amount;
+                }
+                AssetOperation::Burn { asset, amount } => {
+                    ledger.debit(self, &tx.from, &asset, amount)?;
+                    ledger.existing_asset(self, &asset)?.supply -= amount;
+                }
+                AssetOperation::SetIssuer { asset, issuer } => {
+                    let info = ledger.existing_asset(self, &asset)?;
+                    info.authorize_issuer(&tx.from).map_err(violation)?;
+                    info.issuer = issuer;
+                }
+            }
+        }
+        Self::put_asset_ledger(batch, ledger)?;
+        Self::put_order_book(batch, book)
+    }
+
+    /// Undo [`Self::apply_assets`] for reverted transactions, adding the
+    /// reversed escrow movements to `balance_changes`
+    ///
+    /// Every asset operation can be inverted from the transaction alone: only
+    /// the issuer can hand over the role, so the previous issuer is the
+    /// sender. DEX operations are inverted from their receipts.
+    fn revert_assets(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<(), StorageError> {
+        let mut ledger = AssetLedger::default();
+        let mut book = OrderBook::default();
+        let credit = |balance: &mut u64, amount: u64| -> Result<(), StorageError> {
+            *balance = balance
+                .checked_add(amount)
+                .ok_or(StorageError::BalanceOverflow)?;
+            Ok(())
+        };
+        let debit = |balance: &mut u64, amount: u64| -> Result<(), StorageError> {
+            *balance = balance
+                .checked_sub(amount)
+                .ok_or(StorageError::InvalidChain)?;
+            Ok(())
+        };
+
+        for tx in transactions.iter().rev().filter(|tx| !tx.is_coinbase()) {
+            match DexOperation::from_transaction(tx) {
+                Some(Ok(DexOperation::Place { .. })) => {
+                    book.unplace(self, &mut ledger, balance_changes, tx)?;
+                    continue;
+                }
+                Some(Ok(DexOperation::Cancel { order })) => {
+                    book.uncancel(self, &mut ledger, balance_changes, tx, &order)?;
+                    continue;
+                }
+                _ => {}
+            }
+            let Some(Ok(operation)) = AssetOperation::from_transaction(tx) else {
+                continue;
+            };
+            match operation {
+                AssetOperation::Create { .. } => {
+                    let id = AssetId::derive(&tx.from, tx.nonce);
+                    let entry = ledger.asset(self, &id)?;
+                    if entry.as_ref().is_none_or(|info| info.supply != 0) {
+                        return Err(StorageError::InvalidChain);
+                    }
+                    *entry = None;
+                }
+                AssetOperation::Mint { asset, to, amount } => {
+                    debit(ledger.balance(self, &to, &asset)?, amount)?;
+                    let info = ledger.asset(self, &asset)?;
+                    let info = info.as_mut().ok_or(StorageError::InvalidChain)?;
+                    debit(&mut info.supply, amount)?;
+                }
+                AssetOperation::Transfer { asset, to, amount } => {
+                    debit(ledger.balance(self, &to, &asset)?, amount)?;
+                    credit(ledger.balance(self, &tx.from, &asset)?, amount)?;
+                }
+                AssetOperation::Burn { asset, amount } => {
+                    credit(ledger.balance(self, &tx.from, &asset)?, amount)?;
+                    let info = ledger.asset(self, &asset)?;
+                    let info = info.as_mut().ok_or(StorageError::InvalidChain)?;
+                    credit(&mut info.supply, amount)?;
+                }
+                AssetOperation::SetIssuer { asset, .. } => {
+                    let info = ledger.asset(self, &asset)?;
+                    info.as_mut().ok_or(StorageError::InvalidChain)?.issuer = Some(tx.from);
+                }
+            }
+        }
+        Self::put_asset_ledger(batch, ledger)?;
+        Self::put_order_book(batch, book)
+    }
+
+    fn book_side_prefix(asset: &AssetId, side: OrderSide) -> Vec<u8> {
+        let mut key = Self::prefixed_key(DEX_BOOK_PREFIX, &asset.0);
+        key.push(match side {
+            OrderSide::Buy => 0,
+            OrderSide::Sell => 1,
+        });
+        key
+    }
+
+    /// Book index key of `order`; keys sort best price first, then oldest
+    pub(crate) fn order_book_key(order: &Order) -> Vec<u8> {
+        let priority = match order.side {
+            OrderSide::Buy => u64::MAX - order.price,
+            OrderSide::Sell => order.price,
+        };
+        let mut key = Self::book_side_prefix(&order.asset, order.side);
+        key.extend_from_slice(&priority.to_be_bytes());
+        key.extend_from_slice(&order.height.to_be_bytes());
+        key.extend_from_slice(&order.index.to_be_bytes());
+        key
+    }
+
+    pub(crate) fn trade_key(asset: &AssetId, height: u64, index: u32, fill: u32) -> Vec<u8> {
+        let mut key = Self::prefixed_key(DEX_TRADE_PREFIX, &asset.0);
+        key.extend_from_slice(&height.to_be_bytes());
+        key.extend_from_slice(&index.to_be_bytes());
+        key.extend_from_slice(&fill.to_be_bytes());
+        key
+    }
+
+    /// Order with `id`, whatever its status
+    /// الحصول على أمر التداول
+    pub fn get_order(&self, id: &OrderId) -> Result<Option<Order>, StorageError> {
+        match self.db.get(Self::prefixed_key(DEX_ORDER_PREFIX, &id.0))? {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| StorageError::SerializationError("Invalid order record".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Book index entries of one side of the `asset` market, best first,
+    /// read as the iterator advances
+    pub(crate) fn book_side(
+        &self,
+        asset: &AssetId,
+        side: OrderSide,
+    ) -> impl Iterator<Item = Result<(Box<[u8]>, OrderId), StorageError>> + '_ {
+        let prefix = Self::book_side_prefix(asset, side);
+        self.db
+            .prefix_iterator(prefix.clone())
+            .take_while(move |item| {
+                item.as_ref()
+                    .map_or(true, |(key, _)| key.starts_with(&prefix))
+            })
+            .map(|item| {
+                let (key, value) = item?;
+                let id: [u8; 32] = value
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| StorageError::InvalidChain)?;
+                Ok((key, OrderId(id)))
+            })
+    }
+
+    /// Open orders on one side of the `asset` market, best price first and
+    /// oldest first within a price
+    /// الحصول على الأوامر المفتوحة
+    pub fn get_open_orders(
+        &self,
+        asset: &AssetId,
+        side: OrderSide,
+    ) -> Result<Vec<Order>, StorageError> {
+        self.book_side(asset, side)
+            .map(|entry| {
+                let (_, id) = entry?;
+                self.get_order(&id)?.ok_or(StorageError::InvalidChain)
+            })
+            .collect()
+    }
+
+    /// Up to `limit` most recent trades of `asset`, newest first
+    /// الحصول على آخر الصفقات
+    pub fn get_trades(&self, asset: &AssetId, limit: usize) -> Result<Vec<Trade>, StorageError> {
+        let prefix = Self::prefixed_key(DEX_TRADE_PREFIX, &asset.0);
+        let mut end = prefix.clone();
+        end.extend_from_slice(&[0xff; 16]);
+        let mut trades = Vec::new();
+        for item in self.db.iterator(rocksdb::IteratorMode::From(
+            &end,
+            rocksdb::Direction::Reverse,
+        )) {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) || trades.len() >= limit {
+                break;
+            }
+            trades.push(crate::bincode_helpers::deserialize(&value).map_err(|_| {
+                StorageError::SerializationError("Invalid trade record".to_string())
+            })?);
+        }
+        Ok(trades)
+    }
+
+    /// Settlement of a DEX transaction
+    pub fn get_dex_receipt(&self, tx_hash: &[u8; 32]) -> Result<Option<DexReceipt>, StorageError> {
+        match self
+            .db
+            .get(Self::prefixed_key(DEX_RECEIPT_PREFIX, tx_hash))?
+        {
+            Some(data) => crate::bincode_helpers::deserialize(&data)
+                .map(Some)
+                .map_err(|_| StorageError::SerializationError("Invalid dex receipt".to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn put_order_book(batch: &mut WriteBatch, book: OrderBook) -> Result<(), StorageError> {
+        for (id, order) in book.orders {
+            let key = Self::prefixed_key(DEX_ORDER_PREFIX, &id.0);
+            match order {
+                Some(order) => batch.put(key, crate::bincode_helpers::serialize(&order)?),
+                None => batch.delete(key),
+            }
+        }
+        for side in book.sides.into_values() {
+            for key in side.removed {
+                batch.delete(key);
+            }
+            for (key, id) in side.added {
+                batch.put(key, id.0);
+            }
+        }
+        for (key, trade) in book.trades {
+            match trade {
+                Some(trade) => batch.put(key, crate::bincode_helpers::serialize(&trade)?),
+                None => batch.delete(key),
+            }
+        }
+        for (tx_hash, receipt) in book.receipts {
+            let key = Self::prefixed_key(DEX_RECEIPT_PREFIX, &tx_hash);
+            match receipt {
+                Some(receipt) => batch.put(key, crate::bincode_helpers::serialize(&receipt)?),
+                None => batch.delete(key),
+            }
+        }
+        Ok(())
+    }
+
+    fn oracle_key(oracle: &PublicKey) -> Vec<u8> {
+        Self::prefixed_key(ORACLE_KEY_PREFIX, &oracle.0)
+    }
+
+    /// Rate history prefix of `pair`; the separator keeps `SYP/US` from
+    /// matching `SYP/USD`
+    fn oracle_pair_prefix(pair: &str) -> Vec<u8> {
+        let mut key = Vec::with_capacity(ORACLE_RATE_PREFIX.len() + pair.len() + 1);
+        key.extend_from_slice(ORACLE_RATE_PREFIX);
+        key.extend_from_slice(pair.as_bytes());
+        key.push(0);
+        key
+    }
+
+    fn oracle_rate_key(pair: &str, timestamp: u64) -> Vec<u8> {
+        let mut key = Self::oracle_pair_prefix(pair);
+        key.extend_from_slice(&timestamp.to_be_bytes());
+        key
+    }
+
+    /// Whitelist an oracle key by governance decision
+    /// إضافة مفتاح أوراكل بقرار من الحوكمة
+    ///
+    /// Takes effect with the next block applied, in the same batch, so a
+    /// reorganization that reverts that block undoes it too.
+    pub fn add_oracle(&self, oracle: &PublicKey, proposal_id: u64) -> Result<(), StorageError> {
+        self.stage_oracle_change(oracle, Some(proposal_id))
+    }
+
+    /// Remove an oracle key from the whitelist; rates it already published
+    /// stay on chain
+    /// إزالة مفتاح أوراكل
+    ///
+    /// Takes effect with the next block applied, like [`Self::add_oracle`].
+    pub fn remove_oracle(&self, oracle: &PublicKey) -> Result<(), StorageError> {
+        self.stage_oracle_change(oracle, None)
+    }
+
+    fn stage_oracle_change(
+        &self,
+        oracle: &PublicKey,
+        change: Option<u64>,
+    ) -> Result<(), StorageError> {
+        self.db.put(
+            Self::prefixed_key(ORACLE_PENDING_PREFIX, &oracle.0),
+            crate::bincode_helpers::serialize(&change)?,
+        )?;
+        Ok(())
+    }
+
+    fn oracle_undo_key(height: u64) -> Vec<u8> {
+        let mut key = ORACLE_UNDO_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Whitelist entry of `oracle`: the proposal that added it
+    fn oracle_entry(&self, oracle: &PublicKey) -> Result<Option<u64>, StorageError> {
+        match self.db.get(Self::oracle_key(oracle))? {
+            Some(data) => {
+                let id: [u8; 8] = data
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| StorageError::InvalidChain)?;
+                Ok(Some(u64::from_le_bytes(id)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn put_oracle_entry(batch: &mut WriteBatch, oracle: &PublicKey, entry: Option<u64>) {
+        match entry {
+            Some(proposal_id) => batch.put(Self::oracle_key(oracle), proposal_id.to_le_bytes()),
+            None => batch.delete(Self::oracle_key(oracle)),
+        }
+    }
+
+    /// Stage the whitelist changes waiting for the block at `height`, with
+    /// the entries they replace for reverting it
+    ///
+    /// Returns the whitelist as the block sees it, for the keys that changed.
+    fn apply_oracle_whitelist(
+        &self,
+        batch: &mut WriteBatch,
+        height: u64,
+    ) -> Result<HashMap<PublicKey, bool>, StorageError> {
+        let mut changes: Vec<OracleChange> = Vec::new();
+        for item in self.db.prefix_iterator(ORACLE_PENDING_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(ORACLE_PENDING_PREFIX) {
+                break;
+            }
+            let oracle: [u8; 32] = key[ORACLE_PENDING_PREFIX.len()..]
+                .try_into()
+                .map_err(|_| StorageError::InvalidChain)?;
+            let change: Option<u64> = crate::bincode_helpers::deserialize(&value)?;
+            let previous = self.oracle_entry(&PublicKey(oracle))?;
+            Self::put_oracle_entry(batch, &PublicKey(oracle), change);
+            batch.delete(&key);
+            changes.push((oracle, previous, change));
+        }
+        if changes.is_empty() {
+            return Ok(HashMap::new());
+        }
+        batch.put(
+            Self::oracle_undo_key(height),
+            crate::bincode_helpers::serialize(&changes)?,
+        );
+        Ok(changes
+            .into_iter()
+            .map(|(oracle, _, change)| (PublicKey(oracle), change.is_some()))
+            .collect())
+    }
+
+    /// Undo [`Self::apply_oracle_whitelist`] for the block at `height`,
+    /// staging its changes again for the next block
+    fn revert_oracle_whitelist(
+        &self,
+        batch: &mut WriteBatch,
+        height: u64,
+    ) -> Result<(), StorageError> {
+        let undo_key = Self::oracle_undo_key(height);
+        let Some(data) = self.db.get(&undo_key)? else {
+            return Ok(());
+        };
+        let changes: Vec<OracleChange> = crate::bincode_helpers::deserialize(&data)?;
+        for (oracle, previous, change) in changes {
+            Self::put_oracle_entry(batch, &PublicKey(oracle), previous);
+            // A decision staged since then is newer and stands
+            let pending = Self::prefixed_key(ORACLE_PENDING_PREFIX, &oracle);
+            if self.db.get(&pending)?.is_none() {
+                batch.put(pending, crate::bincode_helpers::serialize(&change)?);
+            }
+        }
+        batch.delete(undo_key);
+        Ok(())
+    }
+
+    /// Check whether a key may publish rates
+    pub fn is_oracle(&self, key: &PublicKey) -> Result<bool, StorageError> {
+        Ok(self.db.get(Self::oracle_key(key))?.is_some())
+    }
+
+    /// All whitelisted oracle keys with the proposal that added them
+    pub fn get_oracles(&self) -> Result<Vec<(PublicKey, u64)>, StorageError> {
+        let mut oracles = Vec::new();
+        for item in self.db.prefix_iterator(ORACLE_KEY_PREFIX) {
+            let (key, value) = item?;
+            if !key.starts_with(ORACLE_KEY_PREFIX) {
+                break;
+            }
+            let (Ok(oracle), Ok(id)) = (
+                <[u8; 32]>::try_from(&key[ORACLE_KEY_PREFIX.len()..]),
+                <[u8; 8]>::try_from(value.as_ref()),
+            ) else {
+                continue;
+            };
+            oracles.push((PublicKey(oracle), u64::from_le_bytes(id)));
+        }
+        Ok(oracles)
+    }
+
+    /// Up to `limit` most recent rates of `pair`, newest first
+    /// سجل أسعار زوج العملات
+    pub fn get_oracle_rate_history(
+        &self,
+        pair: &str,
+        limit: usize,
+    ) -> Result<Vec<OracleRate>, StorageError> {
+        let prefix = Self::oracle_pair_prefix(pair);
+        let end = Self::oracle_rate_key(pair, u64::MAX);
+        let mut rates = Vec::new();
+        for item in self.db.iterator(rocksdb::IteratorMode::From(
+            &end,
+            rocksdb::Direction::Reverse,
+        )) {
+            let (key, value) = item?;
+            if !key.starts_with(&prefix) || rates.len() >= limit {
+                break;
+            }
+            rates.push(crate::bincode_helpers::deserialize(&value).map_err(|_| {
+                StorageError::SerializationError("Invalid oracle rate record".to_string())
+            })?);
+        }
+        Ok(rates)
+    }
+
+    /// Newest rate published for `pair`
+    /// أحدث سعر صرف لزوج العملات
+    pub fn get_oracle_rate(&self, pair: &str) -> Result<Option<OracleRate>, StorageError> {
+        Ok(self.get_oracle_rate_history(pair, 1)?.pop())
+    }
+
+    /// Newest rate of every pair ever published, ordered by pair
+    pub fn get_oracle_rates(&self) -> Result<Vec<OracleRate>, StorageError> {
+        let mut rates = Vec::new();
+        let mut from = ORACLE_RATE_PREFIX.to_vec();
+        loop {
+            let next = self
+                .db
+                .iterator(rocksdb::IteratorMode::From(
+                    &from,
+                    rocksdb::Direction::Forward,
+                ))
+                .next()
+                .transpose()?;
+            let Some((key, _)) = next.filter(|(key, _)| key.starts_with(ORACLE_RATE_PREFIX)) else {
+                break;
+            };
+            let pair_end = key[ORACLE_RATE_PREFIX.len()..]
+                .iter()
+                .position(|b| *b == 0)
+                .ok_or(StorageError::InvalidChain)?;
+            let pair = String::from_utf8_lossy(
+                &key[ORACLE_RATE_PREFIX.len()..ORACLE_RATE_PREFIX.len() + pair_end],
+            )
+            .into_owned();
+            rates.extend(self.get_oracle_rate(&pair)?);
+
+            // Skip the rest of this pair's history
+            from = Self::oracle_pair_prefix(&pair);
+            *from.last_mut().expect("prefix ends in the separator") = 1;
+        }
+        Ok(rates)
+    }
+
+    /// Check a block's oracle updates against the whitelist and the rates
+    /// before them, and stage the new rates
+    ///
+    /// With the block's `timestamp`, quotes may be dated at most
+    /// [`MAX_RATE_FUTURE_SECS`](opensyria_core::oracle::MAX_RATE_FUTURE_SECS)
+    /// after it. `whitelist` holds the keys whose whitelisting this block
+    /// changes.
+    fn apply_oracle_updates(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        height: u64,
+        timestamp: Option<u64>,
+        whitelist: &HashMap<PublicKey, bool>,
+    ) -> Result<(), StorageError> {
+        let violation = |e: OracleError| StorageError::OracleViolation(e.to_string());
+        let mut latest: HashMap<String, u64> = HashMap::new();
+
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let Some(update) = OracleUpdate::from_transaction(tx) else {
+                continue;
+            };
+            let update = update.map_err(violation)?;
+            let whitelisted = match whitelist.get(&tx.from) {
+                Some(whitelisted) => *whitelisted,
+                None => self.is_oracle(&tx.from)?,
+            };
+            if !whitelisted {
+                return Err(violation(OracleError::NotAnOracle(tx.from.to_hex())));
+            }
+            if let Some(timestamp) = timestamp {
+                update.check_clock(timestamp).map_err(violation)?;
+            }
+
+            for quote in update.rates {
+                let newest = match latest.get(&quote.pair) {
+                    Some(timestamp) => Some(*timestamp),
+                    None => self
+                        .get_oracle_rate(&quote.pair)?
+                        .map(|rate| rate.quote.timestamp),
+                };
+                if let Some(newest) = newest.filter(|newest| quote.timestamp <= *newest) {
+                    return Err(violation(OracleError::StaleRate {
+                        pair: quote.pair,
+                        timestamp: quote.timestamp,
+                        latest: newest,
+                    }));
+                }
+
+                latest.insert(quote.pair.clone(), quote.timestamp);
+                let key = Self::oracle_rate_key(&quote.pair, quote.timestamp);
+                let rate = OracleRate {
+                    quote,
+                    publisher: tx.from,
+                    height,
+                };
+                batch.put(key, crate::bincode_helpers::serialize(&rate)?);
+            }
+        }
+        Ok(())
+    }
+
+    /// Undo [`Self::apply_oracle_updates`] for reverted transactions
+    fn revert_oracle_updates(batch: &mut WriteBatch, transactions: &[Transaction]) {
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            if let Some(Ok(update)) = OracleUpdate::from_transaction(tx) {
+                for quote in update.rates {
+                    batch.delete(Self::oracle_rate_key(&quote.pair, quote.timestamp));
+                }
+            }
+        }
+    }
+
+    fn deposit_escrow_key(escrow: &[u8; 32]) -> Vec<u8> {
+        Self::prefixed_key(DEPOSIT_ESCROW_PREFIX, escrow)
+    }
+
+    /// Proposal deposit escrowed by transaction `escrow`, settled or not
+    /// وديعة الاقتراح المحتجزة بمعاملة معينة
+    pub fn get_deposit_escrow(
+        &self,
+        escrow: &[u8; 32],
+    ) -> Result<Option<DepositEscrow>, StorageError> {
+        match self.db.get(Self::deposit_escrow_key(escrow))? {
+            Some(data) => Ok(Some(crate::bincode_helpers::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Check a block's deposit escrows and settlements and stage their
+    /// records
+    ///
+    /// Settlements must close an open escrow past its release height with
+    /// exactly its amount, and refunds must pay its proposer. Burned amounts
+    /// leave the escrow account's balance; their total is returned so the
+    /// supply shrinks with them.
+    fn apply_deposits(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        height: u64,
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<u64, StorageError> {
+        let violation = |e: DepositError| StorageError::DepositViolation(e.to_string());
+        let mut escrows: HashMap<[u8; 32], DepositEscrow> = HashMap::new();
+        let mut burned = 0u64;
+
+        for tx in transactions.iter().filter(|tx| !tx.is_coinbase()) {
+            let Some(operation) = DepositOperation::from_transaction(tx) else {
+                continue;
+            };
+            let (id, refund) = match operation.map_err(violation)? {
+                DepositOperation::Escrow { release_height } => {
+                    let escrow = DepositEscrow {
+                        proposer: tx.from,
+                        amount: tx.amount,
+                        release_height,
+                        settlement: None,
+                    };
+                    batch.put(
+                        Self::deposit_escrow_key(&tx.hash()),
+                        crate::bincode_helpers::serialize(&escrow)?,
+                    );
+                    escrows.insert(tx.hash(), escrow);
+                    continue;
+                }
+                DepositOperation::Refund { escrow } => (escrow, true),
+                DepositOperation::Burn { escrow } => (escrow, false),
+            };
+
+            let mut escrow = match escrows.remove(&id) {
+                Some(escrow) => escrow,
+                None => self
+                    .get_deposit_escrow(&id)?
+                    .ok_or_else(|| violation(DepositError::UnknownEscrow(hex::encode(id))))?,
+            };
+            let wrong = |reason: &str| violation(DepositError::WrongSettlement(reason.to_string()));
+            if escrow.settlement.is_some() {
+                return Err(wrong("escrow already settled"));
+            }
+            if height < escrow.release_height {
+                return Err(wrong("escrow not released yet"));
+            }
+            if tx.amount != escrow.amount {
+                return Err(wrong("amount differs from the escrow"));
+            }
+            if refund && tx.to != escrow.proposer {
+                return Err(wrong("refund does not pay the proposer"));
+            }
+            if !refund {
+                *balance_changes.entry(escrow_account()).or_insert(0) -= tx.amount as i128;
+                burned = burned
+                    .checked_add(tx.amount)
+                    .ok_or(StorageError::BalanceOverflow)?;
+            }
+
+            escrow.settlement = Some(if refund {
+                DepositSettlement::Refunded
+            } else {
+                DepositSettlement::Burned
+            });
+            batch.put(
+                Self::deposit_escrow_key(&id),
+                crate::bincode_helpers::serialize(&escrow)?,
+            );
+            escrows.insert(id, escrow);
+        }
+        Ok(burned)
+    }
+
+    /// Undo [`Self::apply_deposits`] for reverted transactions, returning
+    /// the amount burned so the supply can be restored
+    fn revert_deposits(
+        &self,
+        batch: &mut WriteBatch,
+        transactions: &[Transaction],
+        balance_changes: &mut HashMap<PublicKey, i128>,
+    ) -> Result<u64, StorageError> {
+        let mut burned = 0u64;
+        // Newest first, so an escrow settled in its own block ends deleted
+        for tx in transactions.iter().rev().filter(|tx| !tx.is_coinbase()) {
+            let id = match DepositOperation::from_transaction(tx) {
+                Some(Ok(DepositOperation::Escrow { .. })) => {
+                    batch.delete(Self::deposit_escrow_key(&tx.hash()));
+                    continue;
+                }
+                Some(Ok(DepositOperation::Refund { escrow })) => escrow,
+                Some(Ok(DepositOperation::Burn { escrow })) => {
+                    *balance_changes.entry(escrow_account()).or_insert(0) += tx.amount as i128;
+                    burned = burned
+                        .checked_add(tx.amount)
+                        .ok_or(StorageError::BalanceOverflow)?;
+                    escrow
+                }
+                _ => continue,
+            };
+            let mut escrow = self
+                .get_deposit_escrow(&id)?
+                .ok_or(StorageError::InvalidChain)?;
+            escrow.settlement = None;
+            batch.put(
+                Self::deposit_escrow_key(&id),
+                crate::bincode_helpers::serialize(&escrow)?,
+            );
+        }
+        Ok(burned)
+    }
+
+    /// Store multisig account configuration
+    pub fn store_multisig_account(&self, account: &MultisigAccount) -> Result<(), StorageError> {
+        let address = account.address();
+        let key = Self::multisig_key(&address);
+
+        // Serialize multisig account using bincode
+        let serialized = crate::bincode_helpers::serialize(account).map_err(|_e| StorageError::InvalidChain)?;
+
+        self.db.put(&key, &serialized)?;
+        Ok(())
+    }
+
+    /// Get multisig account configuration
+    pub fn get_multisig_account(
+        &self,
+        address: &PublicKey,
+    ) -> Result<Option<MultisigAccount>, StorageError> {
+        let key = Self::multisig_key(address);
+
+        match self.db.get(&key)? {
+            Some(data) => {
+                let account: MultisigAccount =
+                    crate::bincode_helpers::deserialize(&data).map_err(|_| StorageError::InvalidChain)?;
+                Ok(Some(account))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Check if an address is a multisig account
+    pub fn is_multisig_account(&self, address: &PublicKey) -> Result<bool, StorageError> {
+        let key = Self::multisig_key(address);
+        Ok(self.db.get(&key)?.is_some())
+    }
+
+    /// Get multisig nonce
+    pub fn get_multisig_nonce(&self, address: &PublicKey) -> Result<u64, StorageError> {
+        // Multisig accounts use the same nonce mechanism as regular accounts
+        self.get_nonce(address)
+    }
+
+    /// Increment multisig nonce
+    pub fn increment_multisig_nonce(&self, address: &PublicKey) -> Result<(), StorageError> {
+        self.increment_nonce(address)
+    }
+
+    /// Validate and execute a multisig transaction with nonce checking
+    ///
+    /// ✅  SECURITY FIX: Uses per-address mutex to prevent TOCTOU race conditions.
+    /// The lock ensures that nonce check and state update are atomic operations.
+    ///
+    /// This prevents double-spend attacks where two concurrent transactions
+    /// with the same nonce could both pass validation and execute.
+    ///
+    /// THREAD-SAFE: Multiple threads can execute multisig transactions concurrently,
+    /// but transactions for the same address are serialized.
+    ///
+    /// Signatures must be bound to `params`' chain once replay protection is
+    /// active at the next block height.
+    pub async fn execute_multisig_transaction(
+        &self,
+        multisig_tx: &opensyria_core::MultisigTransaction,
+        params: &ChainParams,
+    ) -> Result<(), StorageError> {
+        let multisig_address = multisig_tx.account.address();
+
+        // SECURITY FIX: Acquire per-address lock before any checks
+        // This prevents concurrent execution of transactions for same address
+        let lock = self.address_locks
+            .entry(multisig_address.0)
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+
+        let _guard = lock.lock().await;
+
+        // Now all operations are atomic within the lock scope
+
+        // 1. Verify multisig account exists
+        let stored_account = self
+            .get_multisig_account(&multisig_address)?
+            .ok_or(StorageError::InvalidTransaction)?;
+
+        // Verify configuration matches
+        if stored_account != multisig_tx.account {
+            return Err(StorageError::InvalidTransaction);
+        }
+
+        // 2. CRITICAL: Verify nonce to prevent replay attacks
+        // This read is now protected by the mutex
+        let current_nonce = self.get_nonce(&multisig_address)?;
+        if multisig_tx.nonce != current_nonce {
+            return Err(StorageError::InvalidTransaction);
+        }
+
+        // 3. Verify signatures meet threshold
+        if let Err(_) = multisig_tx.verify() {
+            return Err(StorageError::InvalidTransaction);
+        }
+        multisig_tx
+            .validate_chain_id(params, self.get_state_height()? + 1)
+            .map_err(|_| StorageError::InvalidTransaction)?;
+
+        if self.is_frozen(&multisig_address)? {
+            return Err(StorageError::AccountFrozen(multisig_address.to_hex()));
+        }
+
+        // 4. Check expiry if set
+        // Note: This requires block height context, handled by caller
+
+        // 5. Check balance (total = amount + fee)
+        let balance = self.get_balance(&multisig_address)?;
+        let total_required = multisig_tx
+            .amount
+            .checked_add(multisig_tx.fee)
+            .ok_or(StorageError::BalanceOverflow)?;
+
+        if balance < total_required {
+            return Err(StorageError::InsufficientBalance);
+        }
+
+        // 6. Execute atomically: transfer + increment nonce
+        // While WriteBatch itself is atomic, the protection comes from the mutex
+        // preventing concurrent access to the same address
+        let mut batch = WriteBatch::default();
+        let epoch = self.balance_snapshot_epoch()?;
+        self.preserve_balance(&mut batch, &multisig_address, epoch)?;
+        self.preserve_balance(&mut batch, &multisig_tx.to, epoch)?;
+
+        // Deduct from multisig account
+        let new_balance = balance - total_required;
+        let balance_key = Self::balance_key(&multisig_address);
+        batch.put(&balance_key, new_balance.to_le_bytes());
+
+        // Credit recipient
+        let recipient_balance = self.get_balance(&multisig_tx.to)?;
+        let new_recipient_balance = recipient_balance
+            .checked_add(multisig_tx.amount)
+            .ok_or(StorageError::BalanceOverflow)?;
+        let recipient_key = Self::balance_key(&multisig_tx.to);
+        batch.put(&recipient_key, new_recipient_balance.to_le_bytes());
+
+        // CRITICAL: Increment nonce to prevent replay
+        let nonce_key = Self::nonce_key(&multisig_address);
+        batch.put(&nonce_key, (current_nonce + 1).to_le_bytes());
+
+        // Atomic commit
+        self.db.write(batch)?;
+
+        // Lock released here when _guard drops
+
+        Ok(())
+    }
+
+    /// Store partial multisig transaction (for incremental signing)
+    pub fn store_partial_multisig(
+        &self,
+        tx_hash: &[u8; 32],
+        transaction_data: &[u8],
+    ) -> Result<(), StorageError> {
+        let key = Self::partial_multisig_key(tx_hash);
+        self.db.put(&key, transaction_data)?;
+        Ok(())
+    }
+
+    /// Get partial multisig transaction
+    pub fn get_partial_multisig(&self, tx_hash: &[u8; 32]) -> Result<Option<Vec<u8>>, StorageError> {
+        let key = Self::partial_multisig_key(tx_hash);
+        Ok(self.db.get(&key)?)
+    }
+
+    /// Delete partial multisig transaction (after execution or expiry)
+    pub fn delete_partial_multisig(&self, tx_hash: &[u8; 32]) -> Result<(), StorageError> {
+        let key = Self::partial_multisig_key(tx_hash);
+        self.db.delete(&key)?;
+        Ok(())
+    }
+
+    fn partial_multisig_key(tx_hash: &[u8; 32]) -> Vec<u8> {
+        let mut key = Vec::with_capacity(48);
+        key.extend_from_slice(b"partial_multisig_");
+        key.extend_from_slice(tx_hash);
+        key
+    }
+
+    /// Apply block transactions atomically (all-or-nothing)
+    /// تطبيق معاملات الكتلة بشكل ذري (كل شيء أو لا شيء)
+    ///
+    /// ✅  SECURITY FIX (CRITICAL-003): Atomic nonce validation and increment
+    /// This method now validates nonces WITHIN the atomic batch operation to prevent
+    /// TOCTOU (Time-Of-Check-Time-Of-Use) race conditions. The nonce check and
+    /// increment are performed atomically using RocksDB's WriteBatch.
+    ///
+    /// THREAD-SAFE: Multiple threads can call this concurrently, but RocksDB
+    /// ensures that WriteBatch commits are serialized at the database level.
+    pub fn apply_block_atomic(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.apply_block_batch(transactions, None, None)
+    }
+
+    /// Apply the block at `height` and record it as the state height
+    /// تطبيق الكتلة عند الارتفاع المحدد وتسجيله كارتفاع الحالة
+    ///
+    /// The height is written in the same batch as the balances, so the state
+    /// height always names the last block reflected in the state. The block's
+    /// accounting journal is written in that batch too, replacing any journal
+    /// left at `height` by a block since reorganized away.
+    pub fn apply_block_at_height(
+        &self,
+        height: u64,
+        transactions: &[Transaction],
+    ) -> Result<(), StorageError> {
+        self.apply_block_batch(transactions, Some(height), None)
+    }
+
+    /// Apply `block` as the block at `height`
+    /// تطبيق كتلة كاملة عند الارتفاع المحدد
+    ///
+    /// Like [`Self::apply_block_at_height`], and also holds the block's
+    /// oracle quotes to its timestamp.
+    pub fn apply_block(&self, height: u64, block: &Block) -> Result<(), StorageError> {
+        self.apply_block_batch(
+            &block.transactions,
+            Some(height),
+            Some(block.header.timestamp),
+        )
+    }
+
+    #[tracing::instrument(
+        name = "db_write",
+        skip_all,
+        fields(op = "apply_block", tx_count = transactions.len())
+    )]
+    fn apply_block_batch(
+        &self,
+        transactions: &[Transaction],
+        height: Option<u64>,
+        timestamp: Option<u64>,
+    ) -> Result<(), StorageError> {
+        let mut batch = self
+            .block_batch(transactions, height, timestamp)
+            .map_err(|violation| violation.error)?;
+
+        if self.audit.is_some() {
+            let events = self.audit_events(transactions, false)?;
+            self.stage_audit_events(&mut batch, events)?;
+        }
+
+        // Atomic commit - ALL or NOTHING
+        // RocksDB guarantees this entire batch is applied atomically
+        self.db.write(batch)?;
+
+        self.flush_audit_events()
+    }
+
+    /// Check that `transactions` apply cleanly as the block at `height`,
+    /// without writing anything
+    /// التحقق من إمكانية تطبيق معاملات كتلة دون كتابة أي شيء
+    ///
+    /// Runs every state rule block application runs (frozen accounts,
+    /// spending policies, delegations, assets and orders, oracles, nonces,
+    /// supply, contracts and balances) and drops the resulting writes.
+    pub fn check_block_at_height(
+        &self,
+        height: u64,
+        transactions: &[Transaction],
+    ) -> Result<(), RuleViolation> {
+        self.block_batch(transactions, Some(height), None).map(drop)
+    }
+
+    /// Check that `block` applies cleanly as the block at `height`, without
+    /// writing anything
+    /// التحقق من إمكانية تطبيق كتلة كاملة دون كتابة أي شيء
+    ///
+    /// Like [`Self::check_block_at_height`], and also holds the block's
+    /// oracle quotes to its timestamp.
+    pub fn check_block(&self, height: u64, block: &Block) -> Result<(), RuleViolation> {
+        self.block_batch(
+            &block.transactions,
+            Some(height),
+            Some(block.header.timestamp),
+        )
+        .map(drop)
+    }
+
+    /// Check the asset and DEX operations in `transactions` against the
+    /// current state, as if they were the next block
+    /// التحقق من عمليات الأصول والتداول مقابل الحالة الحالية
+    ///
+    /// Covers asset existence, issuer authority, supply caps, asset balances
+    /// and the asset escrow of sell orders. Other rules are left to
+    /// [`Self::check_block_at_height`]; nothing is written.
+    pub fn check_asset_operations(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        let height = self.get_state_height()? + 1;
+        let mut balance_changes = HashMap::new();
+        self.apply_assets(
+            &mut WriteBatch::default(),
+            transactions,
+            height,
+            &mut balance_changes,
+        )
+    }
+
+    /// Writes applying `transactions` would make, after validating them
+    ///
+    /// Oracle quotes are held to the block's `timestamp` when it is known.
+    fn block_batch(
+        &self,
+        transactions: &[Transaction],
+        height: Option<u64>,
+        timestamp: Option<u64>,
+    ) -> Result<WriteBatch, RuleViolation> {
+        let mut batch = WriteBatch::default();
+
+        // Track balance/nonce changes in memory before batching
+        let mut balance_changes: HashMap<PublicKey, i128> = HashMap::new();
+        let mut nonce_changes: HashMap<PublicKey, u64> = HashMap::new();
+        let mut nonce_validations: HashMap<PublicKey, Vec<u64>> = HashMap::new();
+        let mut fee_payers: Vec<PublicKey> = Vec::new();
+        let mut supply_increase: u64 = 0;
+
+        // Calculate all state changes AND track required nonces
+        for tx in transactions {
+            // Skip coinbase transactions (miner rewards)
+            if tx.is_coinbase() {
+                *balance_changes.entry(tx.to).or_insert(0) += tx.amount as i128;
+                supply_increase = supply_increase
+                    .checked_add(tx.amount)
+                    .ok_or(RuleViolation::new("supply", StorageError::BalanceOverflow))?;
+                continue;
+            }
+
+            // Regular transactions: deduct amount from sender and fee from
+            // whoever pays it, add to receiver
+            *balance_changes.entry(tx.from).or_insert(0) -= tx.amount as i128;
+            *balance_changes.entry(tx.fee_payer()).or_insert(0) -= tx.fee as i128;
+            *balance_changes.entry(tx.to).or_insert(0) += tx.amount as i128;
+            if tx.fee_payer() != tx.from {
+                fee_payers.push(tx.fee_payer());
+            }
+
+            // SECURITY FIX: Track expected nonce for validation
+            nonce_validations.entry(tx.from).or_insert_with(Vec::new).push(tx.nonce);
+
+            // Track nonce increment
+            *nonce_changes.entry(tx.from).or_insert(0) += 1;
+        }
+
+        // Governance-frozen accounts may not spend or sponsor fees
+        for address in nonce_validations.keys().chain(&fee_payers) {
+            if self.is_frozen(address)? {
+                return Err(RuleViolation::new(
+                    "frozen_account",
+                    StorageError::AccountFrozen(address.to_hex()),
+                ));
+            }
+        }
+
+        // Spending policies, with updates taking effect for later transactions
+        let policy_height = match height {
+            Some(height) => height,
+            None => self.get_state_height()? + 1,
+        };
+        self.apply_spending_policies(&mut batch, transactions, policy_day(policy_height))
+            .map_err(|e| RuleViolation::new("spending_policy", e))?;
+        self.apply_delegations(&mut batch, transactions, policy_day(policy_height))
+            .map_err(|e| RuleViolation::new("delegation", e))?;
+        let mut journal = BlockJournal::from_transactions(policy_height, transactions);
+        let before_settlement = balance_changes.clone();
+        self.apply_assets(
+            &mut batch,
+            transactions,
+            policy_height,
+            &mut balance_changes,
+        )
+        .map_err(|e| RuleViolation::new("asset", e))?;
+        journal.post_net(JournalReason::Dex, &before_settlement, &balance_changes);
+        let whitelist = self
+            .apply_oracle_whitelist(&mut batch, policy_height)
+            .map_err(|e| RuleViolation::new("oracle", e))?;
+        self.apply_oracle_updates(
+            &mut batch,
+            transactions,
+            policy_height,
+            timestamp,
+            &whitelist,
+        )
+        .map_err(|e| RuleViolation::new("oracle", e))?;
+        let burned = self
+            .apply_deposits(
+                &mut batch,
+                transactions,
+                policy_height,
+                &mut balance_changes,
+            )
+            .map_err(|e| RuleViolation::new("deposit", e))?;
+
+        // CRITICAL SECURITY FIX: Validate nonces are sequential per address
+        // This prevents nonce gaps, duplicates, or replay attacks
+        for (address, tx_nonces) in &nonce_validations {
+            let current_nonce = self.get_nonce(address)?;
+
+            // Check that transaction nonces are sequential starting from current_nonce
+            for (nth, &tx_nonce) in tx_nonces.iter().enumerate() {
+                let expected_nonce = current_nonce + nth as u64;
+                if tx_nonce != expected_nonce {
+                    // CRITICAL: Nonce mismatch indicates replay attack or out-of-order execution
+                    let violation = RuleViolation::new("nonce", StorageError::InvalidTransaction);
+                    return Err(
+                        match transactions
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, tx)| !tx.is_coinbase() && tx.from == *address)
+                            .nth(nth)
+                        {
+                            Some((index, _)) => violation.at(index),
+                            None => violation,
+                        },
+                    );
+                }
+            }
+        }
+
+        // Check total supply will not exceed MAX_SUPPLY
+        if supply_increase > 0 {
+            use opensyria_core::MAX_SUPPLY;
+            let current_supply = self.get_total_supply()?;
+            let new_supply = current_supply
+                .checked_add(supply_increase)
+                .ok_or(RuleViolation::new("supply", StorageError::BalanceOverflow))?;
+            if new_supply > MAX_SUPPLY {
+                return Err(RuleViolation::new("supply", StorageError::InvalidChain));
+            }
+        }
+
+        // Contracts run in block order; their payments join the balance changes
+        let before_settlement = balance_changes.clone();
+        let contract_changes = self
+            .execute_contracts(transactions, policy_height, &mut balance_changes)
+            .map_err(|e| RuleViolation::new("contract", e))?;
+        self.put_contract_changes(&mut batch, contract_changes)?;
+        journal.post_net(
+            JournalReason::Contract,
+            &before_settlement,
+            &balance_changes,
+        );
+
+        // Validate all balances are sufficient
+        for (address, change) in &balance_changes {
+            let current_balance = self.get_balance(address)?;
+            let new_balance = (current_balance as i128) + change;
+
+            if new_balance < 0 {
+                return Err(RuleViolation::new(
+                    "balance",
+                    StorageError::InsufficientBalance,
+                ));
+            }
+            if new_balance > u64::MAX as i128 {
+                return Err(RuleViolation::new("balance", StorageError::BalanceOverflow));
+            }
+        }
+
+        // Apply balance changes to batch
+        let epoch = self.balance_snapshot_epoch()?;
+        for (address, change) in balance_changes {
+            let current_balance = self.get_balance(&address)?;
+            let new_balance = ((current_balance as i128) + change) as u64;
+            self.preserve_balance(&mut batch, &address, epoch)?;
+
+            let key = Self::balance_key(&address);
+            batch.put(&key, new_balance.to_le_bytes());
+        }
+
+        // Apply nonce changes to batch (ATOMIC with balance updates)
+        for (address, increment) in nonce_changes {
+            let current_nonce = self.get_nonce(&address)?;
+            let new_nonce = current_nonce + increment;
+
+            let key = Self::nonce_key(&address);
+            batch.put(&key, new_nonce.to_le_bytes());
+        }
+
+        // Update total supply if there were coinbase transactions or burns
+        if supply_increase > 0 || burned > 0 {
             let current_supply = self.get_total_supply()?;
-            let new_supply = current_supply + supply_increase;
+            let new_supply = (current_supply + supply_increase)
+                .checked_sub(burned)
+                .ok_or(RuleViolation::new("supply", StorageError::InvalidChain))?;
+            batch.put(TOTAL_SUPPLY_KEY, new_supply.to_le_bytes());
+        }
+
+        if let Some(height) = height {
+            let totals = match height.checked_sub(1) {
+                Some(parent) => self.get_supply_totals(parent)?,
+                None => SupplyTotals::default(),
+            }
+            .with_block(&journal);
+            batch.put(STATE_HEIGHT_KEY, height.to_le_bytes());
+            batch.put(
+                Self::journal_key(height),
+                crate::bincode_helpers::serialize(&journal).map_err(StorageError::from)?,
+            );
+            batch.put(
+                Self::supply_totals_key(height),
+                crate::bincode_helpers::serialize(&totals).map_err(StorageError::from)?,
+            );
+        }
+
+        Ok(batch)
+    }
+
+    /// Revert the block at `height` atomically (for chain reorgs)
+    /// عكس معاملات الكتلة بشكل ذري (لإعادة تنظيم السلسلة)
+    ///
+    /// The state height moves back to `height - 1` and the block's accounting
+    /// journal and supply totals are deleted in the same batch, so they keep
+    /// describing only blocks reflected in the state.
+    #[tracing::instrument(
+        name = "db_write",
+        skip_all,
+        fields(op = "revert_block", tx_count = transactions.len())
+    )]
+    pub fn revert_block_atomic(
+        &self,
+        height: u64,
+        transactions: &[Transaction],
+    ) -> Result<(), StorageError> {
+        // Genesis is never reverted
+        let parent_height = height.checked_sub(1).ok_or(StorageError::InvalidChain)?;
+        let mut batch = WriteBatch::default();
+
+        // Net changes per address, so accounts touched by several
+        // transactions are restored exactly
+        let mut balance_changes: HashMap<PublicKey, i128> = HashMap::new();
+        let mut nonce_changes: HashMap<PublicKey, u64> = HashMap::new();
+        let mut supply_decrease: u64 = 0;
+
+        for tx in transactions {
+            // Coinbase: take back the reward and the coins it minted
+            if tx.is_coinbase() {
+                *balance_changes.entry(tx.to).or_insert(0) -= tx.amount as i128;
+                supply_decrease = supply_decrease
+                    .checked_add(tx.amount)
+                    .ok_or(StorageError::BalanceOverflow)?;
+                continue;
+            }
+
+            // Return amount to sender and fee to whoever paid it, deduct
+            // from receiver
+            *balance_changes.entry(tx.from).or_insert(0) += tx.amount as i128;
+            *balance_changes.entry(tx.fee_payer()).or_insert(0) += tx.fee as i128;
+            *balance_changes.entry(tx.to).or_insert(0) -= tx.amount as i128;
+            *nonce_changes.entry(tx.from).or_insert(0) += 1;
+        }
+
+        self.revert_contracts(&mut batch, transactions, &mut balance_changes)?;
+        self.revert_assets(&mut batch, transactions, &mut balance_changes)?;
+        let burned = self.revert_deposits(&mut batch, transactions, &mut balance_changes)?;
+
+        let epoch = self.balance_snapshot_epoch()?;
+        for (address, change) in balance_changes {
+            let new_balance = (self.get_balance(&address)? as i128) + change;
+            if new_balance < 0 {
+                return Err(StorageError::InsufficientBalance);
+            }
+            let new_balance =
+                u64::try_from(new_balance).map_err(|_| StorageError::BalanceOverflow)?;
+            self.preserve_balance(&mut batch, &address, epoch)?;
+            batch.put(Self::balance_key(&address), new_balance.to_le_bytes());
+        }
+
+        // Decrement sender nonces
+        for (address, decrement) in nonce_changes {
+            let new_nonce = self
+                .get_nonce(&address)?
+                .checked_sub(decrement)
+                .ok_or(StorageError::InvalidChain)?;
+            batch.put(Self::nonce_key(&address), new_nonce.to_le_bytes());
+        }
+
+        if supply_decrease > 0 || burned > 0 {
+            let new_supply = self
+                .get_total_supply()?
+                .checked_add(burned)
+                .and_then(|supply| supply.checked_sub(supply_decrease))
+                .ok_or(StorageError::InvalidChain)?;
             batch.put(TOTAL_SUPPLY_KEY, new_supply.to_le_bytes());
         }
 
-        // Atomic commit - ALL or NOTHING
-        // RocksDB guarantees this entire batch is applied atomically
-        self.db.write(batch)?;
+        self.revert_spending_policies(&mut batch, transactions, policy_day(height))?;
+        self.revert_delegations(&mut batch, transactions, policy_day(height))?;
+        Self::revert_oracle_updates(&mut batch, transactions);
+        self.revert_oracle_whitelist(&mut batch, height)?;
+        batch.put(STATE_HEIGHT_KEY, parent_height.to_le_bytes());
+        batch.delete(Self::journal_key(height));
+        batch.delete(Self::supply_totals_key(height));
+
+        if self.audit.is_some() {
+            let events = self.audit_events(transactions, true)?;
+            self.stage_audit_events(&mut batch, events)?;
+        }
+
+        // Atomic commit
+        self.db.write(batch)?;
+
+        if let Some(anchors) = &self.anchors {
+            if anchors.publisher.is_due(height) {
+                anchors.queue(AnchorTask::Retract { height });
+            }
+        }
+        self.flush_audit_events()
+    }
+
+    /// Height of the last block applied with [`Self::apply_block_at_height`]
+    ///
+    /// Zero if the state was never advanced block by block, in which case
+    /// it cannot be assumed to follow the chain.
+    pub fn get_state_height(&self) -> Result<u64, StorageError> {
+        match self.db.get(STATE_HEIGHT_KEY)? {
+            Some(data) => {
+                let bytes: [u8; 8] = data
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| StorageError::InvalidChain)?;
+                Ok(u64::from_le_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn journal_key(height: u64) -> Vec<u8> {
+        let mut key = JOURNAL_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Accounting journal of the block applied at `height`
+    /// دفتر اليومية المحاسبي للكتلة عند الارتفاع المحدد
+    ///
+    /// `None` for heights not applied through [`Self::apply_block_at_height`].
+    pub fn get_block_journal(&self, height: u64) -> Result<Option<BlockJournal>, StorageError> {
+        match self.db.get(Self::journal_key(height))? {
+            Some(data) => Ok(Some(crate::bincode_helpers::deserialize(&data)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn supply_totals_key(height: u64) -> Vec<u8> {
+        let mut key = SUPPLY_TOTALS_PREFIX.to_vec();
+        key.extend_from_slice(&height.to_be_bytes());
+        key
+    }
+
+    /// Supply totals of the blocks applied up to `height`
+    /// المجاميع التراكمية للعرض حتى الارتفاع المحدد
+    ///
+    /// Heights without a record of their own, such as those of a state
+    /// restored from a snapshot, take the totals of the nearest height below.
+    pub fn get_supply_totals(&self, height: u64) -> Result<SupplyTotals, StorageError> {
+        let key = Self::supply_totals_key(height);
+        let mut iter = self.db.iterator(rocksdb::IteratorMode::From(
+            &key,
+            rocksdb::Direction::Reverse,
+        ));
+        match iter.next().transpose()? {
+            Some((key, value)) if key.starts_with(SUPPLY_TOTALS_PREFIX) => {
+                Ok(crate::bincode_helpers::deserialize(&value)?)
+            }
+            _ => Ok(SupplyTotals::default()),
+        }
+    }
+
+    /// Append a movement made outside block application to the journal at
+    /// `height`
+    /// إضافة حركة خارج تطبيق الكتلة إلى دفتر اليومية
+    ///
+    /// The supply totals of `height` and every later block are adjusted in
+    /// the same batch.
+    pub fn post_journal(
+        &self,
+        height: u64,
+        reason: JournalReason,
+        debit: JournalAccount,
+        credit: JournalAccount,
+        amount: u64,
+    ) -> Result<(), StorageError> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let mut journal = self
+            .get_block_journal(height)?
+            .unwrap_or_else(|| BlockJournal::new(height));
+        let mut movement = BlockJournal::new(height);
+        movement.post(None, reason, debit, credit, amount);
+        journal.lines.extend(movement.lines.iter().cloned());
+
+        let mut batch = WriteBatch::default();
+        batch.put(
+            Self::journal_key(height),
+            crate::bincode_helpers::serialize(&journal)?,
+        );
+        let burned = movement.net_change(&JournalAccount::Burned);
+        let treasury = movement.net_change(&JournalAccount::Treasury);
+        if burned != 0 || treasury != 0 {
+            let start = Self::supply_totals_key(height);
+            for item in self.db.iterator(rocksdb::IteratorMode::From(
+                &start,
+                rocksdb::Direction::Forward,
+            )) {
+                let (key, value) = item?;
+                if !key.starts_with(SUPPLY_TOTALS_PREFIX) {
+                    break;
+                }
+                let mut totals: SupplyTotals = crate::bincode_helpers::deserialize(&value)?;
+                totals.burned += burned;
+                totals.treasury += treasury;
+                batch.put(&key, crate::bincode_helpers::serialize(&totals)?);
+            }
+        }
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Check if the state holds no entries besides its schema version
+    pub fn is_empty(&self) -> Result<bool, StorageError> {
+        for item in self.db.iterator(rocksdb::IteratorMode::Start) {
+            if &*item?.0 != SCHEMA_VERSION_KEY {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Export the state as a chunked snapshot at `height`
+    /// تصدير الحالة كلقطة مجزأة عند الارتفاع المحدد
+    ///
+    /// Entries are read from a consistent RocksDB snapshot in key order, so
+    /// two nodes with the same state produce identical chunks.
+    /// `window_headers` come from
+    /// [`BlockchainStorage::snapshot_window_headers`](crate::BlockchainStorage::snapshot_window_headers).
+    pub fn create_snapshot(
+        &self,
+        height: u64,
+        block_hash: [u8; 32],
+        window_headers: Vec<BlockHeader>,
+    ) -> Result<(SnapshotManifest, Vec<Vec<u8>>), StorageError> {
+        let contract_cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let db_snapshot = self.db.snapshot();
+        let mut chunks = Vec::new();
+        let mut chunk_hashes = Vec::new();
+        let mut entries: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut chunk_bytes = 0;
+        let mut total_entries = 0u64;
+
+        // Contract state follows the default column family under a prefix
+        // that sorts after every state key
+        let contract_entries = db_snapshot
+            .iterator_cf(&contract_cf, rocksdb::IteratorMode::Start)
+            .map(|item| {
+                item.map(|(key, value)| {
+                    let mut prefixed = CONTRACT_SNAPSHOT_PREFIX.to_vec();
+                    prefixed.extend_from_slice(&key);
+                    (prefixed.into_boxed_slice(), value)
+                })
+            });
+        for item in db_snapshot
+            .iterator(rocksdb::IteratorMode::Start)
+            .chain(contract_entries)
+        {
+            let (key, value) = item?;
+            if SNAPSHOT_EXCLUDED_PREFIXES
+                .iter()
+                .any(|prefix| key.starts_with(prefix))
+            {
+                continue;
+            }
+
+            chunk_bytes += key.len() + value.len();
+            entries.push((key.into_vec(), value.into_vec()));
+            total_entries += 1;
+
+            if chunk_bytes >= SNAPSHOT_CHUNK_BYTES {
+                let chunk = crate::bincode_helpers::serialize(&entries)?;
+                chunk_hashes.push(chunk_hash(&chunk));
+                chunks.push(chunk);
+                entries.clear();
+                chunk_bytes = 0;
+            }
+        }
+        if !entries.is_empty() {
+            let chunk = crate::bincode_helpers::serialize(&entries)?;
+            chunk_hashes.push(chunk_hash(&chunk));
+            chunks.push(chunk);
+        }
+
+        let manifest = SnapshotManifest {
+            height,
+            block_hash,
+            entries: total_entries,
+            chunk_hashes,
+            window_headers,
+        };
+        Ok((manifest, chunks))
+    }
+
+    /// Rebuild an empty state from verified snapshot chunks
+    /// إعادة بناء حالة فارغة من أجزاء لقطة تم التحقق منها
+    ///
+    /// Chunks are checked against the manifest again, keys must be strictly
+    /// ascending and never node-local, balances may not exceed the recorded
+    /// supply, and the accounts must hash to `state_root`, which the caller
+    /// takes from a trusted anchor rather than from the serving peer.
+    /// Nothing is written unless every check passes.
+    pub fn restore_snapshot(
+        &self,
+        manifest: &SnapshotManifest,
+        chunks: &[Vec<u8>],
+        state_root: &[u8; 32],
+    ) -> Result<(), StorageError> {
+        if !self.is_empty()? {
+            return Err(StorageError::InvalidSnapshot(
+                "state is not empty".to_string(),
+            ));
+        }
+        if chunks.len() != manifest.chunk_count() {
+            return Err(StorageError::InvalidSnapshot(format!(
+                "expected {} chunks, got {}",
+                manifest.chunk_count(),
+                chunks.len()
+            )));
+        }
+
+        let contract_cf = self
+            .db
+            .cf_handle(CF_CONTRACT_STATE)
+            .ok_or(StorageError::ColumnFamilyNotFound)?;
+        let mut batch = WriteBatch::default();
+        let mut previous_key: Option<Vec<u8>> = None;
+        let mut total_entries = 0u64;
+        let mut recorded_supply = 0u64;
+        let mut balance_sum = 0u64;
+        let mut accounts: BTreeMap<[u8; 32], AccountLeaf> = BTreeMap::new();
+
+        for (index, (chunk, expected)) in chunks.iter().zip(&manifest.chunk_hashes).enumerate() {
+            if chunk_hash(chunk) != *expected {
+                return Err(StorageError::InvalidSnapshot(format!(
+                    "chunk {} hash mismatch",
+                    index
+                )));
+            }
+
+            for (key, value) in decode_chunk(chunk)? {
+                if previous_key
+                    .as_ref()
+                    .is_some_and(|previous| *previous >= key)
+                    || SNAPSHOT_EXCLUDED_PREFIXES
+                        .iter()
+                        .any(|prefix| key.starts_with(prefix))
+                {
+                    return Err(StorageError::InvalidSnapshot(format!(
+                        "unexpected key in chunk {}",
+                        index
+                    )));
+                }
+
+                if key == TOTAL_SUPPLY_KEY || key.starts_with(b"balance_") {
+                    let bytes: [u8; 8] = value.as_slice().try_into().map_err(|_| {
+                        StorageError::InvalidSnapshot(format!(
+                            "malformed amount in chunk {}",
+                            index
+                        ))
+                    })?;
+                    let amount = u64::from_le_bytes(bytes);
+                    if key == TOTAL_SUPPLY_KEY {
+                        recorded_supply = amount;
+                    } else {
+                        balance_sum = balance_sum
+                            .checked_add(amount)
+                            .ok_or(StorageError::BalanceOverflow)?;
+                    }
+                }
+
+                match key.strip_prefix(CONTRACT_SNAPSHOT_PREFIX) {
+                    Some(contract_key) => batch.put_cf(&contract_cf, contract_key, &value),
+                    None => {
+                        Self::add_account_entry(&mut accounts, &key, &value);
+                        batch.put(&key, &value)
+                    }
+                }
+                previous_key = Some(key);
+                total_entries += 1;
+            }
+        }
+
+        if total_entries != manifest.entries {
+            return Err(StorageError::InvalidSnapshot(format!(
+                "expected {} entries, got {}",
+                manifest.entries, total_entries
+            )));
+        }
+        if balance_sum > recorded_supply {
+            return Err(StorageError::InvalidSnapshot(format!(
+                "balances total {} but recorded supply is {}",
+                balance_sum, recorded_supply
+            )));
+        }
+        if Self::account_tree(accounts).root() != *state_root {
+            return Err(StorageError::InvalidSnapshot(
+                "state root does not match the trusted anchor".to_string(),
+            ));
+        }
+
+        batch.put(STATE_HEIGHT_KEY, manifest.height.to_le_bytes());
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// Compact the database to reclaim disk space
+    /// ضغط قاعدة البيانات لاستعادة مساحة القرص
+    pub fn compact_database(&self) -> Result<(), StorageError> {
+        self.db.compact_range::<&[u8], &[u8]>(None, None);
+        Ok(())
+    }
+
+    /// Prune zero-balance accounts older than specified height
+    /// حذف الحسابات ذات الرصيد الصفري
+    pub fn prune_zero_balances(&self) -> Result<usize, StorageError> {
+        let mut batch = WriteBatch::default();
+        let mut pruned_count = 0;
+
+        let balances = self.get_all_balances()?;
+        for (address, balance) in balances {
+            if balance == 0 {
+                let key = Self::balance_key(&address);
+                batch.delete(&key);
+                pruned_count += 1;
+            }
+        }
+
+        if pruned_count > 0 {
+            self.db.write(batch)?;
+        }
+
+        Ok(pruned_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+    use std::sync::Arc;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_balance_operations() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let kp = KeyPair::generate();
+        let addr = kp.public_key();
+
+        // Initial balance is 0
+        assert_eq!(storage.get_balance(&addr).unwrap(), 0);
+
+        // Set balance
+        storage.set_balance(&addr, 1_000_000).unwrap();
+        assert_eq!(storage.get_balance(&addr).unwrap(), 1_000_000);
+
+        // Add balance
+        storage.add_balance(&addr, 500_000).unwrap();
+        assert_eq!(storage.get_balance(&addr).unwrap(), 1_500_000);
+
+        // Subtract balance
+        storage.sub_balance(&addr, 300_000).unwrap();
+        assert_eq!(storage.get_balance(&addr).unwrap(), 1_200_000);
+
+        // Insufficient balance should error
+        assert!(storage.sub_balance(&addr, 2_000_000).is_err());
+    }
+
+    #[test]
+    fn test_state_tree_proves_balances() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        let carol = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 1_000_000).unwrap();
+        storage.set_nonce(&alice, 2).unwrap();
+        storage.set_nonce(&bob, 1).unwrap();
+        storage.set_balance(&carol, 0).unwrap();
+        // Shares the `balance_` key prefix but is not an account
+        storage.open_balance_snapshot().unwrap();
+
+        let tree = storage.state_tree().unwrap();
+        assert_eq!(tree.len(), 2);
+        let proof = tree.prove(&alice).unwrap();
+        assert_eq!((proof.leaf.balance, proof.leaf.nonce), (1_000_000, 2));
+        assert!(proof.verify(&tree.root()));
+        assert_eq!(tree.prove(&bob).unwrap().leaf.balance, 0);
+        assert!(tree.prove(&carol).is_none());
+
+        // Pruning zero balances leaves the root unchanged
+        storage.prune_zero_balances().unwrap();
+        assert_eq!(storage.state_tree().unwrap().root(), tree.root());
+    }
+
+    #[test]
+    fn test_transfer() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+
+        // Give Alice initial balance
+        storage.set_balance(&alice, 1_000_000).unwrap();
+
+        // Transfer to Bob
+        storage.transfer(&alice, &bob, 300_000).unwrap();
+
+        assert_eq!(storage.get_balance(&alice).unwrap(), 700_000);
+        assert_eq!(storage.get_balance(&bob).unwrap(), 300_000);
+    }
+
+    #[test]
+    fn test_nonce_operations() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let kp = KeyPair::generate();
+        let addr = kp.public_key();
+
+        assert_eq!(storage.get_nonce(&addr).unwrap(), 0);
+
+        storage.set_nonce(&addr, 5).unwrap();
+        assert_eq!(storage.get_nonce(&addr).unwrap(), 5);
+
+        storage.increment_nonce(&addr).unwrap();
+        assert_eq!(storage.get_nonce(&addr).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_get_all_balances() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+
+        storage.set_balance(&alice, 1_000_000).unwrap();
+        storage.set_balance(&bob, 2_000_000).unwrap();
+
+        let balances = storage.get_all_balances().unwrap();
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(*balances.get(&alice).unwrap(), 1_000_000);
+        assert_eq!(*balances.get(&bob).unwrap(), 2_000_000);
+    }
+
+    #[test]
+    fn test_atomic_block_apply() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice_kp = KeyPair::generate();
+        let bob_kp = KeyPair::generate();
+        let charlie_kp = KeyPair::generate();
+
+        let alice = alice_kp.public_key();
+        let bob = bob_kp.public_key();
+        let charlie = charlie_kp.public_key();
+
+        // Give Alice initial balance
+        storage.set_balance(&alice, 2_000_000).unwrap();
+
+        // Create transactions: (from, to, amount, fee, nonce)
+        let tx1 = Transaction::new(alice, bob, 1_000_000, 500, 0); // 500 fee, nonce 0
+        let tx2 = Transaction::new(alice, charlie, 500_000, 500, 1); // 500 fee, nonce 1
+
+        let transactions = vec![tx1, tx2];
+
+        // Apply atomically
+        storage.apply_block_atomic(&transactions).unwrap();
+
+        // Verify all changes applied
+        // Alice: 2M - (1M + 500) - (500K + 500) = 499,000
+        assert_eq!(storage.get_balance(&alice).unwrap(), 499_000);
+        assert_eq!(storage.get_balance(&bob).unwrap(), 1_000_000);
+        assert_eq!(storage.get_balance(&charlie).unwrap(), 500_000);
+        assert_eq!(storage.get_nonce(&alice).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_balance_snapshots_copy_on_write() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 1_000).unwrap();
+        storage.set_balance(&bob, 2_000).unwrap();
+
+        let first = storage.open_balance_snapshot().unwrap();
+        storage.set_balance(&alice, 5_000).unwrap();
+        let second = storage.open_balance_snapshot().unwrap();
+        storage.set_balance(&alice, 9_000).unwrap();
+
+        // Each snapshot sees the balance at the moment it was opened
+        assert_eq!(
+            storage.get_balance_at_snapshot(&alice, first).unwrap(),
+            1_000
+        );
+        assert_eq!(
+            storage.get_balance_at_snapshot(&alice, second).unwrap(),
+            5_000
+        );
+        // Untouched accounts read through to the current balance
+        assert_eq!(storage.get_balance_at_snapshot(&bob, first).unwrap(), 2_000);
+
+        // Pruning drops records only the closed snapshot needed
+        assert_eq!(storage.prune_balance_snapshots(second).unwrap(), 1);
+        assert_eq!(
+            storage.get_balance_at_snapshot(&alice, second).unwrap(),
+            5_000
+        );
+        assert_eq!(storage.get_balance(&alice).unwrap(), 9_000);
+    }
+
+    #[test]
+    fn test_supply_invariant() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let miner = KeyPair::generate().public_key();
+
+        // Nothing recorded yet, nothing to check
+        storage.set_balance(&miner, 1_000).unwrap();
+        assert_eq!(storage.check_supply_invariant().unwrap(), None);
+        storage.set_balance(&miner, 0).unwrap();
+
+        let coinbase =
+            Transaction::coinbase(opensyria_core::CHAIN_ID_MAINNET, miner, 1, 0).unwrap();
+        storage.apply_block_atomic(&[coinbase.clone()]).unwrap();
+        assert_eq!(storage.compute_total_supply().unwrap(), coinbase.amount);
+        assert_eq!(storage.check_supply_invariant().unwrap(), None);
+
+        // Coins that no coinbase issued
+        let forger = KeyPair::generate().public_key();
+        storage.set_balance(&forger, 7).unwrap();
+        assert_eq!(
+            storage.check_supply_invariant().unwrap(),
+            Some(Anomaly::SupplyInvariant {
+                recorded: coinbase.amount,
+                computed: coinbase.amount + 7,
+            })
+        );
+    }
+
+    #[test]
+    fn test_atomic_block_revert() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice_kp = KeyPair::generate();
+        let bob_kp = KeyPair::generate();
+
+        let alice = alice_kp.public_key();
+        let bob = bob_kp.public_key();
+
+        // Setup: Alice sends to Bob
+        storage.set_balance(&alice, 2_000_000).unwrap();
+        storage.set_nonce(&alice, 0).unwrap();
+
+        let tx = Transaction::new(alice, bob, 1_000_000, 1_000, 0); // 1K fee
+        storage.apply_block_atomic(&vec![tx.clone()]).unwrap();
+
+        // Verify applied: Alice pays 1M + 1K fee
+        assert_eq!(storage.get_balance(&alice).unwrap(), 999_000); // 2M - 1M - 1K
+        assert_eq!(storage.get_balance(&bob).unwrap(), 1_000_000);
+        assert_eq!(storage.get_nonce(&alice).unwrap(), 1);
+
+        // Revert transaction
+        storage.revert_block_atomic(1, &[tx]).unwrap();
+
+        // Verify reverted
+        assert_eq!(storage.get_balance(&alice).unwrap(), 2_000_000);
+        assert_eq!(storage.get_balance(&bob).unwrap(), 0);
+        assert_eq!(storage.get_nonce(&alice).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_check_block_reports_rule_without_writing() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 2_000_000).unwrap();
+
+        let ok = Transaction::new(alice, bob, 1_000_000, 1_000, 0);
+        storage
+            .check_block_at_height(2, std::slice::from_ref(&ok))
+            .unwrap();
+        assert_eq!(storage.get_balance(&alice).unwrap(), 2_000_000);
+        assert_eq!(storage.get_nonce(&alice).unwrap(), 0);
+
+        // The second transaction reuses nonce 0
+        let replayed = Transaction::new(alice, bob, 1_000, 1_000, 0);
+        let violation = storage
+            .check_block_at_height(2, &[ok.clone(), replayed])
+            .unwrap_err();
+        assert_eq!((violation.rule, violation.tx_index), ("nonce", Some(1)));
+
+        let overdraft = Transaction::new(alice, bob, 1_500_000, 1_000, 1);
+        let violation = storage
+            .check_block_at_height(2, &[ok, overdraft])
+            .unwrap_err();
+        assert_eq!(violation.rule, "balance");
+        assert_eq!(storage.get_balance(&bob).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_sponsored_fee_charged_to_payer() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        let sponsor = KeyPair::generate().public_key();
+
+        // Alice can cover the amount but not the fee
+        storage.set_balance(&alice, 1_000_000).unwrap();
+        storage.set_balance(&sponsor, 5_000).unwrap();
+
+        let tx = Transaction::new(alice, bob, 1_000_000, 1_000, 0).with_fee_payer(sponsor);
+        storage.apply_block_atomic(&[tx.clone()]).unwrap();
+
+        assert_eq!(storage.get_balance(&alice).unwrap(), 0);
+        assert_eq!(storage.get_balance(&sponsor).unwrap(), 4_000);
+        assert_eq!(storage.get_balance(&bob).unwrap(), 1_000_000);
+        assert_eq!(storage.get_nonce(&alice).unwrap(), 1);
+        assert_eq!(storage.get_nonce(&sponsor).unwrap(), 0);
+
+        storage.revert_block_atomic(1, &[tx]).unwrap();
+        assert_eq!(storage.get_balance(&alice).unwrap(), 1_000_000);
+        assert_eq!(storage.get_balance(&sponsor).unwrap(), 5_000);
+
+        // A sponsor that cannot pay rejects the whole block
+        let broke = KeyPair::generate().public_key();
+        let tx = Transaction::new(alice, bob, 1_000_000, 1_000, 0).with_fee_payer(broke);
+        assert!(matches!(
+            storage.apply_block_atomic(&[tx]),
+            Err(StorageError::InsufficientBalance)
+        ));
+
+        storage.freeze_account(&sponsor, 1).unwrap();
+        let tx = Transaction::new(alice, bob, 1_000_000, 1_000, 0).with_fee_payer(sponsor);
+        assert!(matches!(
+            storage.apply_block_atomic(&[tx]),
+            Err(StorageError::AccountFrozen(_))
+        ));
+    }
+
+    #[test]
+    fn test_spending_policy_enforced_and_reverted() {
+        use opensyria_core::policy::{SpendingPolicy, BLOCKS_PER_DAY};
+
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let owner = KeyPair::generate();
+        let cosigner = KeyPair::generate();
+        let alice = owner.public_key();
+        let bob = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 10_000_000).unwrap();
+
+        let policy = SpendingPolicy {
+            daily_limit: Some(1_600_000),
+            cosigner: Some(cosigner.public_key()),
+            cosign_threshold: 500_000,
+            allowlist: Vec::new(),
+        };
+        let update = policy.update_transaction(963, alice, 100, 0);
+        storage.apply_block_at_height(1, &[update.clone()]).unwrap();
+        assert_eq!(storage.get_spending_policy(&alice).unwrap(), Some(policy));
+
+        // Above the threshold without the cosigner
+        let large = Transaction::new(alice, bob, 600_000, 100, 1);
+        assert!(matches!(
+            storage.apply_block_at_height(2, &[large.clone()]),
+            Err(StorageError::PolicyViolation(_))
+        ));
+        let cosigned = large
+            .clone()
+            .with_cosignature(cosigner.sign(&large.cosigning_hash()));
+        storage
+            .apply_block_at_height(2, &[cosigned.clone()])
+            .unwrap();
+
+        // The day's spending counts against the limit until the window rolls
+        let small = |nonce| Transaction::new(alice, bob, 450_000, 100, nonce);
+        let spends = vec![small(2), small(3), small(4)];
+        assert!(matches!(
+            storage.apply_block_at_height(3, &spends),
+            Err(StorageError::PolicyViolation(_))
+        ));
+        storage.apply_block_at_height(3, &spends[..2]).unwrap();
+        assert_eq!(storage.get_policy_spent(&alice, 0).unwrap(), 1_500_300);
+        storage
+            .apply_block_at_height(BLOCKS_PER_DAY, &[small(4)])
+            .unwrap();
+        assert_eq!(storage.get_policy_spent(&alice, 1).unwrap(), 450_100);
+
+        // Each block comes off the day it was counted in
+        storage
+            .revert_block_atomic(BLOCKS_PER_DAY, &[small(4)])
+            .unwrap();
+        assert_eq!(storage.get_policy_spent(&alice, 1).unwrap(), 0);
+        assert_eq!(storage.get_policy_spent(&alice, 0).unwrap(), 1_500_300);
+        storage.revert_block_atomic(3, &spends[..2]).unwrap();
+        assert_eq!(storage.get_policy_spent(&alice, 0).unwrap(), 600_100);
+
+        // Reverting the update restores the account's earlier (absent) policy
+        storage.revert_block_atomic(2, &[cosigned]).unwrap();
+        storage.revert_block_atomic(1, &[update]).unwrap();
+        assert_eq!(storage.get_spending_policy(&alice).unwrap(), None);
+        assert_eq!(storage.get_policy_spent(&alice, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_day_spent_records_migrated() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let alice = KeyPair::generate().public_key();
+
+        // Written as an older release kept it: one record per account
+        let mut record = 3u64.to_be_bytes().to_vec();
+        record.extend_from_slice(&1_000u64.to_be_bytes());
+        let old_key = StateStorage::prefixed_key(POLICY_SPENT_PREFIX, &alice.0);
+        storage.db.put(&old_key, &record).unwrap();
+        storage
+            .db
+            .put(SCHEMA_VERSION_KEY, 1u32.to_le_bytes())
+            .unwrap();
+        drop(storage);
+
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.get_policy_spent(&alice, 3).unwrap(), 1_000);
+        assert!(storage.db.get(&old_key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_hot_key_delegation_allowance() {
+        use opensyria_core::delegation::HotKeyDelegation;
+        use opensyria_core::policy::BLOCKS_PER_DAY;
+
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+
+        let cold = KeyPair::generate().public_key();
+        let hot = KeyPair::generate().public_key();
+        let miner = KeyPair::generate().public_key();
+        storage.set_balance(&cold, 100_000_000).unwrap();
+
+        let payout =
+            |amount, nonce| Transaction::new(cold, miner, amount, 100, nonce).with_delegate(hot);
+        assert!(matches!(
+            storage.apply_block_at_height(1, &[payout(1_000_000, 0)]),
+            Err(StorageError::DelegationViolation(_))
+        ));
+
+        let delegation = HotKeyDelegation {
+            hot_key: hot,
+            daily_allowance: 2_000_000,
+        };
+        let grant = delegation.grant_transaction(963, cold, 100, 0);
+        storage
+            .apply_block_at_height(1, &[grant.clone(), payout(1_000_000, 1)])
+            .unwrap();
+        assert_eq!(storage.get_delegation(&cold).unwrap(), Some(delegation));
+        assert_eq!(storage.get_delegation_spent(&cold, 0).unwrap(), 1_000_100);
+
+        // The allowance holds within the day and renews after it
+        assert!(matches!(
+            storage.apply_block_at_height(2, &[payout(1_000_000, 2)]),
+            Err(StorageError::DelegationViolation(_))
+        ));
+        storage
+            .apply_block_at_height(BLOCKS_PER_DAY, &[payout(1_000_000, 2)])
+            .unwrap();
+
+        // The hot key cannot revoke or widen its own delegation
+        let revoke = HotKeyDelegation::revoke_transaction(963, cold, 100, 3).with_delegate(hot);
+        assert!(matches!(
+            storage.apply_block_at_height(BLOCKS_PER_DAY + 1, &[revoke]),
+            Err(StorageError::DelegationViolation(_))
+        ));
+        let revoke = HotKeyDelegation::revoke_transaction(963, cold, 100, 3);
+        storage
+            .apply_block_at_height(BLOCKS_PER_DAY + 1, &[revoke.clone()])
+            .unwrap();
+        assert_eq!(storage.get_delegation(&cold).unwrap(), None);
+
+        storage
+            .revert_block_atomic(BLOCKS_PER_DAY + 1, &[revoke])
+            .unwrap();
+        assert_eq!(
+            storage.get_delegation(&cold).unwrap().map(|d| d.hot_key),
+            Some(hot)
+        );
+
+        // Reverting across the day boundary leaves the earlier day intact
+        assert_eq!(storage.get_delegation_spent(&cold, 1).unwrap(), 1_000_100);
+        storage
+            .revert_block_atomic(BLOCKS_PER_DAY, &[payout(1_000_000, 2)])
+            .unwrap();
+        assert_eq!(storage.get_delegation_spent(&cold, 1).unwrap(), 0);
+        assert_eq!(storage.get_delegation_spent(&cold, 0).unwrap(), 1_000_100);
+        storage
+            .revert_block_atomic(1, &[grant, payout(1_000_000, 1)])
+            .unwrap();
+        assert_eq!(storage.get_delegation_spent(&cold, 0).unwrap(), 0);
+        assert_eq!(storage.get_delegation(&cold).unwrap(), None);
+    }
+
+    #[test]
+    fn test_block_gas_limit_enforced() {
+        use opensyria_core::contract::{
+            gas_fee, ContractCall, MAX_GAS_PER_TRANSACTION, MIN_GAS_PRICE,
+        };
+
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let alice = KeyPair::generate().public_key();
+        let contract = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 100_000_000).unwrap();
+
+        let call = ContractCall::Call {
+            method: "run".to_string(),
+            args: Vec::new(),
+            gas_limit: MAX_GAS_PER_TRANSACTION,
+        };
+        let fee = gas_fee(MAX_GAS_PER_TRANSACTION, MIN_GAS_PRICE);
+        let calls: Vec<Transaction> = (0..6)
+            .map(|nonce| call.call_transaction(963, alice, contract, 0, fee, nonce))
+            .collect();
+
+        let limit = (MAX_GAS_PER_BLOCK / MAX_GAS_PER_TRANSACTION) as usize;
+        assert!(matches!(
+            storage.apply_block_at_height(1, &calls),
+            Err(StorageError::ContractError(_))
+        ));
+        storage.apply_block_at_height(1, &calls[..limit]).unwrap();
+    }
+
+    #[test]
+    fn test_contract_deploy_call_and_revert() {
+        use opensyria_core::contract::{contract_address, ContractCall};
+
+        // Counter starting at 100; `refund` pays the value sent back, `crash` traps
+        const CONTRACT: &str = r#"
+            (module
+              (import "env" "input_read" (func $input_read (param i32)))
+              (import "env" "storage_read" (func $storage_read (param i32 i32 i32 i32) (result i32)))
+              (import "env" "storage_write" (func $storage_write (param i32 i32 i32 i32)))
+              (import "env" "caller" (func $caller (param i32)))
+              (import "env" "value" (func $value (result i64)))
+              (import "env" "transfer" (func $transfer (param i32 i64) (result i32)))
+              (memory (export "memory") 1)
+              (data (i32.const 0) "n")
+              (func (export "init")
+                (i64.store (i32.const 8) (i64.const 100))
+                (call $storage_write (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8)))
+              (func (export "add")
+                (drop (call $storage_read (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8)))
+                (call $input_read (i32.const 16))
+                (i64.store (i32.const 8)
+                  (i64.add (i64.load (i32.const 8)) (i64.load8_u (i32.const 16))))
+                (call $storage_write (i32.const 0) (i32.const 1) (i32.const 8) (i32.const 8)))
+              (func (export "refund")
+                (call $caller (i32.const 32))
+                (drop (call $transfer (i32.const 32) (call $value))))
+              (func (export "crash") unreachable))
+        "#;
+
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let alice = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 1_000_000).unwrap();
+        storage.increase_supply(1_000_000).unwrap();
+
+        let fee = 10_000;
+        let deploy = ContractCall::Deploy {
+            code: wat::parse_str(CONTRACT).unwrap(),
+            gas_limit: 100_000,
+        }
+        .deploy_transaction(963, alice, 5_000, fee, 0);
+        let contract = contract_address(&alice, 0);
+        assert_eq!(deploy.to, contract);
+        storage.apply_block_at_height(1, &[deploy.clone()]).unwrap();
+        assert!(storage.get_contract_code(&contract).unwrap().is_some());
+        assert_eq!(
+            storage.get_contract_storage(&contract, b"n").unwrap(),
+            Some(100u64.to_le_bytes().to_vec())
+        );
+        assert_eq!(storage.get_balance(&contract).unwrap(), 5_000);
+
+        // Snapshots carry contract state
+        let (manifest, chunks) = storage.create_snapshot(1, [0; 32], Vec::new()).unwrap();
+        let restored_dir = tempdir().unwrap();
+        let restored = StateStorage::open(restored_dir.path().to_path_buf()).unwrap();
+        restored
+            .restore_snapshot(&manifest, &chunks, &storage.state_tree().unwrap().root())
+            .unwrap();
+        assert_eq!(
+            restored.get_contract_code(&contract).unwrap(),
+            storage.get_contract_code(&contract).unwrap()
+        );
+        assert_eq!(
+            restored.get_contract_storage(&contract, b"n").unwrap(),
+            Some(100u64.to_le_bytes().to_vec())
+        );
+
+        let call = |method: &str, args: Vec<u8>, value, nonce| {
+            ContractCall::Call {
+                method: method.to_string(),
+                args,
+                gas_limit: 100_000,
+            }
+            .call_transaction(963, alice, contract, value, fee, nonce)
+        };
+        let add = call("add", vec![5], 0, 1);
+        let refund = call("refund", Vec::new(), 2_000, 2);
+        let crash = call("crash", Vec::new(), 3_000, 3);
+        let block = [add.clone(), refund.clone(), crash.clone()];
+        storage.apply_block_at_height(2, &block).unwrap();
+
+        assert_eq!(
+            storage.get_contract_storage(&contract, b"n").unwrap(),
+            Some(105u64.to_le_bytes().to_vec())
+        );
+        let receipt = storage
+            .get_contract_receipt(&refund.hash())
+            .unwrap()
+            .unwrap();
+        assert!(receipt.succeeded());
+        assert_eq!(receipt.transfers, vec![(alice, 2_000)]);
+        let receipt = storage
+            .get_contract_receipt(&crash.hash())
+            .unwrap()
+            .unwrap();
+        assert!(!receipt.succeeded());
+        // Value of the failed call stays with the sender; every fee is spent
+        assert_eq!(storage.get_balance(&contract).unwrap(), 5_000);
+        assert_eq!(
+            storage.get_balance(&alice).unwrap(),
+            1_000_000 - 5_000 - 4 * fee
+        );
+
+        storage.revert_block_atomic(2, &block).unwrap();
+        assert_eq!(
+            storage.get_contract_storage(&contract, b"n").unwrap(),
+            Some(100u64.to_le_bytes().to_vec())
+        );
+        assert!(storage.get_contract_receipt(&add.hash()).unwrap().is_none());
+        assert_eq!(
+            storage.get_balance(&alice).unwrap(),
+            1_000_000 - 5_000 - fee
+        );
 
-        Ok(())
+        storage.revert_block_atomic(1, &[deploy]).unwrap();
+        assert!(storage.get_contract_code(&contract).unwrap().is_none());
+        assert!(storage
+            .get_contract_storage(&contract, b"n")
+            .unwrap()
+            .is_none());
+        assert_eq!(storage.get_balance(&contract).unwrap(), 0);
+        assert_eq!(storage.get_balance(&alice).unwrap(), 1_000_000);
     }
 
-    /// Revert block transactions atomically (for chain reorgs)
-    /// عكس معاملات الكتلة بشكل ذري (لإعادة تنظيم السلسلة)
-    pub fn revert_block_atomic(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
-        let mut batch = WriteBatch::default();
+    #[test]
+    fn test_asset_lifecycle_and_revert() {
+        use opensyria_core::asset::{AssetId, AssetOperation};
 
-        // Reverse all operations in reverse order
-        for tx in transactions.iter().rev() {
-            // Skip coinbase transactions
-            if tx.is_coinbase() {
-                let receiver_balance = self.get_balance(&tx.to)?;
-                if receiver_balance < tx.amount {
-                    return Err(StorageError::InsufficientBalance);
-                }
-                let new_receiver_balance = receiver_balance - tx.amount;
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let issuer = KeyPair::generate().public_key();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
+        storage.set_balance(&issuer, 1_000_000).unwrap();
+        storage.set_balance(&alice, 1_000_000).unwrap();
 
-                let receiver_key = Self::balance_key(&tx.to);
-                batch.put(&receiver_key, new_receiver_balance.to_le_bytes());
-                continue;
+        let create = AssetOperation::Create {
+            symbol: "SOAP".to_string(),
+            name: "Aleppo Soap".to_string(),
+            decimals: 0,
+            max_supply: 1_000,
+        }
+        .transaction(963, issuer, 100, 0);
+        let soap = AssetId::derive(&issuer, 0);
+        let mint = |amount, nonce| {
+            AssetOperation::Mint {
+                asset: soap,
+                to: alice,
+                amount,
             }
+            .transaction(963, issuer, 100, nonce)
+        };
+        storage
+            .apply_block_at_height(1, &[create.clone(), mint(600, 1)])
+            .unwrap();
+        assert_eq!(storage.get_asset(&soap).unwrap().unwrap().supply, 600);
+        assert_eq!(storage.get_asset_balance(&alice, &soap).unwrap(), 600);
+
+        // The cap and the issuer role are enforced
+        assert!(matches!(
+            storage.apply_block_at_height(2, &[mint(401, 2)]),
+            Err(StorageError::AssetViolation(_))
+        ));
+        let forged = AssetOperation::Mint {
+            asset: soap,
+            to: alice,
+            amount: 1,
+        }
+        .transaction(963, alice, 100, 0);
+        assert!(matches!(
+            storage.apply_block_at_height(2, &[forged]),
+            Err(StorageError::AssetViolation(_))
+        ));
+
+        let transfer = AssetOperation::Transfer {
+            asset: soap,
+            to: bob,
+            amount: 250,
+        }
+        .transaction(963, alice, 100, 0);
+        let burn = AssetOperation::Burn {
+            asset: soap,
+            amount: 50,
+        }
+        .transaction(963, alice, 100, 1);
+        let renounce = AssetOperation::SetIssuer {
+            asset: soap,
+            issuer: None,
+        }
+        .transaction(963, issuer, 100, 2);
+        let block = [transfer, burn, renounce];
+        storage.apply_block_at_height(2, &block).unwrap();
+        assert_eq!(
+            storage.get_asset_balances(&alice).unwrap(),
+            vec![(soap, 300)]
+        );
+        assert_eq!(storage.get_asset_balance(&bob, &soap).unwrap(), 250);
+        let info = storage.get_asset(&soap).unwrap().unwrap();
+        assert_eq!((info.supply, info.issuer), (550, None));
+        assert!(storage.apply_block_at_height(3, &[mint(1, 3)]).is_err());
+
+        storage.revert_block_atomic(2, &block).unwrap();
+        assert_eq!(storage.get_asset_balance(&alice, &soap).unwrap(), 600);
+        assert_eq!(storage.get_asset_balances(&bob).unwrap(), Vec::new());
+        let info = storage.get_asset(&soap).unwrap().unwrap();
+        assert_eq!((info.supply, info.issuer), (600, Some(issuer)));
+
+        storage
+            .revert_block_atomic(1, &[create, mint(600, 1)])
+            .unwrap();
+        assert_eq!(storage.get_asset(&soap).unwrap(), None);
+        assert_eq!(storage.get_assets().unwrap(), Vec::new());
+        assert_eq!(storage.get_asset_balance(&alice, &soap).unwrap(), 0);
+    }
 
-            // Return funds to sender
-            let sender_balance = self.get_balance(&tx.from)?;
-            let total_credit = tx
-                .amount
-                .checked_add(tx.fee)
-                .ok_or(StorageError::BalanceOverflow)?;
-            let new_sender_balance = sender_balance
-                .checked_add(total_credit)
-                .ok_or(StorageError::BalanceOverflow)?;
+    #[test]
+    fn test_order_matching_and_revert() {
+        use opensyria_core::asset::{AssetId, AssetOperation};
+        use opensyria_core::dex::escrow_address;
 
-            let sender_key = Self::balance_key(&tx.from);
-            batch.put(&sender_key, new_sender_balance.to_le_bytes());
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let issuer = KeyPair::generate().public_key();
+        let seller = KeyPair::generate().public_key();
+        let buyer = KeyPair::generate().public_key();
+        for account in [issuer, seller, buyer] {
+            storage.set_balance(&account, 1_000_000).unwrap();
+        }
 
-            // Deduct from receiver
-            let receiver_balance = self.get_balance(&tx.to)?;
-            if receiver_balance < tx.amount {
-                return Err(StorageError::InsufficientBalance);
+        let soap = AssetId::derive(&issuer, 0);
+        let create = AssetOperation::Create {
+            symbol: "SOAP".to_string(),
+            name: "Aleppo Soap".to_string(),
+            decimals: 0,
+            max_supply: 1_000,
+        }
+        .transaction(963, issuer, 100, 0);
+        let mint = AssetOperation::Mint {
+            asset: soap,
+            to: seller,
+            amount: 100,
+        }
+        .transaction(963, issuer, 100, 1);
+        storage.apply_block_at_height(1, &[create, mint]).unwrap();
+
+        let place = |side, price, amount, from, nonce| {
+            DexOperation::Place {
+                asset: soap,
+                side,
+                price,
+                amount,
+            }
+            .transaction(963, from, 100, nonce)
+        };
+        let ask_high = place(OrderSide::Sell, 10, 40, seller, 0);
+        let ask_low = place(OrderSide::Sell, 8, 30, seller, 1);
+        let bid = place(OrderSide::Buy, 12, 50, buyer, 0);
+        let block = [ask_high.clone(), ask_low, bid.clone()];
+        storage.apply_block_at_height(2, &block).unwrap();
+
+        // The bid takes the cheaper ask first and pays each maker's price
+        assert_eq!(storage.get_asset_balance(&buyer, &soap).unwrap(), 50);
+        assert_eq!(storage.get_balance(&buyer).unwrap(), 1_000_000 - 100 - 440);
+        assert_eq!(storage.get_balance(&seller).unwrap(), 1_000_000 - 200 + 440);
+        assert_eq!(storage.get_balance(&escrow_address()).unwrap(), 0);
+        assert_eq!(
+            storage.get_asset_balance(&escrow_address(), &soap).unwrap(),
+            20
+        );
+        let journal = storage.get_block_journal(2).unwrap().unwrap();
+        assert!(journal.is_balanced());
+        assert_eq!(journal.net_change(&JournalAccount::Address(buyer)), -540);
+        assert_eq!(
+            journal.net_change(&JournalAccount::Address(escrow_address())),
+            0
+        );
+        let trades = storage.get_trades(&soap, 10).unwrap();
+        let fills: Vec<_> = trades.iter().map(|t| (t.price, t.amount)).collect();
+        assert_eq!(fills, vec![(10, 20), (8, 30)]);
+        let receipt = storage.get_dex_receipt(&bid.hash()).unwrap().unwrap();
+        assert_eq!(receipt.refund, 160);
+        let asks = storage.get_open_orders(&soap, OrderSide::Sell).unwrap();
+        assert_eq!(asks.len(), 1);
+        assert_eq!(
+            (asks[0].id, asks[0].remaining),
+            (OrderId::of(&ask_high), 20)
+        );
+        assert!(storage
+            .get_open_orders(&soap, OrderSide::Buy)
+            .unwrap()
+            .is_empty());
+
+        // Only the owner can cancel, and only once
+        let cancel = |from, nonce| {
+            DexOperation::Cancel {
+                order: OrderId::of(&ask_high),
             }
-            let new_receiver_balance = receiver_balance - tx.amount;
+            .transaction(963, from, 100, nonce)
+        };
+        assert!(matches!(
+            storage.apply_block_at_height(3, &[cancel(buyer, 1)]),
+            Err(StorageError::DexViolation(_))
+        ));
+        storage
+            .apply_block_at_height(3, &[cancel(seller, 2)])
+            .unwrap();
+        assert_eq!(storage.get_asset_balance(&seller, &soap).unwrap(), 50);
+        assert!(storage
+            .get_open_orders(&soap, OrderSide::Sell)
+            .unwrap()
+            .is_empty());
+        assert!(storage
+            .apply_block_at_height(4, &[cancel(seller, 3)])
+            .is_err());
+
+        storage
+            .revert_block_atomic(3, &[cancel(seller, 2)])
+            .unwrap();
+        let asks = storage.get_open_orders(&soap, OrderSide::Sell).unwrap();
+        assert_eq!(asks[0].status, OrderStatus::Open);
+        assert_eq!(storage.get_state_height().unwrap(), 2);
+
+        storage.revert_block_atomic(2, &block).unwrap();
+        assert_eq!(storage.get_state_height().unwrap(), 1);
+        assert_eq!(storage.get_asset_balance(&seller, &soap).unwrap(), 100);
+        assert_eq!(storage.get_asset_balance(&buyer, &soap).unwrap(), 0);
+        assert_eq!(storage.get_balance(&buyer).unwrap(), 1_000_000);
+        assert_eq!(storage.get_balance(&seller).unwrap(), 1_000_000);
+        assert_eq!(storage.get_order(&OrderId::of(&bid)).unwrap(), None);
+        assert!(storage.get_trades(&soap, 10).unwrap().is_empty());
+        assert!(storage
+            .get_open_orders(&soap, OrderSide::Sell)
+            .unwrap()
+            .is_empty());
+    }
 
-            let receiver_key = Self::balance_key(&tx.to);
-            batch.put(&receiver_key, new_receiver_balance.to_le_bytes());
+    #[test]
+    fn test_order_fills_capped_per_placement() {
+        use opensyria_core::asset::{AssetId, AssetOperation};
+        use opensyria_core::dex::{escrow_address, MAX_FILLS_PER_ORDER};
 
-            // Decrement sender nonce
-            let sender_nonce = self.get_nonce(&tx.from)?;
-            if sender_nonce == 0 {
-                return Err(StorageError::InvalidChain);
-            }
-            let nonce_key = Self::nonce_key(&tx.from);
-            batch.put(&nonce_key, (sender_nonce - 1).to_le_bytes());
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let seller = KeyPair::generate().public_key();
+        let buyer = KeyPair::generate().public_key();
+        for account in [seller, buyer] {
+            storage.set_balance(&account, 1_000_000).unwrap();
         }
 
-        // Atomic commit
-        self.db.write(batch)?;
+        let soap = AssetId::derive(&seller, 0);
+        let create = AssetOperation::Create {
+            symbol: "SOAP".to_string(),
+            name: "Aleppo Soap".to_string(),
+            decimals: 0,
+            max_supply: 1_000,
+        }
+        .transaction(963, seller, 100, 0);
+        let mint = AssetOperation::Mint {
+            asset: soap,
+            to: seller,
+            amount: 100,
+        }
+        .transaction(963, seller, 100, 1);
+        storage.apply_block_at_height(1, &[create, mint]).unwrap();
+
+        let place = |side, amount, from, nonce| {
+            DexOperation::Place {
+                asset: soap,
+                side,
+                price: 10,
+                amount,
+            }
+            .transaction(963, from, 100, nonce)
+        };
+        let asks = MAX_FILLS_PER_ORDER as u64 + 1;
+        let block: Vec<_> = (0..asks)
+            .map(|n| place(OrderSide::Sell, 1, seller, n + 2))
+            .collect();
+        storage.apply_block_at_height(2, &block).unwrap();
+
+        // The bid stops after the last fill allowed and releases the rest
+        let bid = place(OrderSide::Buy, asks, buyer, 0);
+        storage
+            .apply_block_at_height(3, std::slice::from_ref(&bid))
+            .unwrap();
+        let order = storage.get_order(&OrderId::of(&bid)).unwrap().unwrap();
+        assert_eq!(order.status, OrderStatus::Cancelled);
+        assert_eq!(order.remaining, 1);
+        let receipt = storage.get_dex_receipt(&bid.hash()).unwrap().unwrap();
+        assert_eq!(receipt.trades.len(), MAX_FILLS_PER_ORDER);
+        assert_eq!(receipt.released, 10);
+        assert_eq!(storage.get_balance(&escrow_address()).unwrap(), 0);
+        assert_eq!(
+            storage.get_balance(&buyer).unwrap(),
+            1_000_000 - 100 - 10 * MAX_FILLS_PER_ORDER as u64
+        );
+        assert_eq!(
+            storage
+                .get_open_orders(&soap, OrderSide::Sell)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(storage.get_block_journal(3).unwrap().unwrap().is_balanced());
 
-        Ok(())
+        storage.revert_block_atomic(3, &[bid]).unwrap();
+        assert_eq!(storage.get_balance(&buyer).unwrap(), 1_000_000);
+        assert_eq!(storage.get_balance(&escrow_address()).unwrap(), 0);
+        assert_eq!(
+            storage
+                .get_open_orders(&soap, OrderSide::Sell)
+                .unwrap()
+                .len(),
+            asks as usize
+        );
     }
 
-    /// Compact the database to reclaim disk space
-    /// ضغط قاعدة البيانات لاستعادة مساحة القرص
-    pub fn compact_database(&self) -> Result<(), StorageError> {
-        self.db.compact_range::<&[u8], &[u8]>(None, None);
-        Ok(())
-    }
+    #[test]
+    fn test_deposit_escrow_settled_once_and_reverted() {
+        use opensyria_core::deposit::escrow_account;
 
-    /// Prune zero-balance accounts older than specified height
-    /// حذف الحسابات ذات الرصيد الصفري
-    pub fn prune_zero_balances(&self) -> Result<usize, StorageError> {
-        let mut batch = WriteBatch::default();
-        let mut pruned_count = 0;
-        
-        let balances = self.get_all_balances()?;
-        for (address, balance) in balances {
-            if balance == 0 {
-                let key = Self::balance_key(&address);
-                batch.delete(&key);
-                pruned_count += 1;
-            }
-        }
-        
-        if pruned_count > 0 {
-            self.db.write(batch)?;
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let proposer = KeyPair::generate().public_key();
+        storage.set_balance(&proposer, 10_000).unwrap();
+        storage.increase_supply(10_000).unwrap();
+
+        let first = DepositOperation::escrow(963, proposer, 1_000, 3, 100, 0);
+        let second = DepositOperation::escrow(963, proposer, 2_000, 3, 100, 1);
+        storage
+            .apply_block_at_height(1, &[first.clone(), second.clone()])
+            .unwrap();
+        assert_eq!(storage.get_balance(&escrow_account()).unwrap(), 3_000);
+        let escrow = storage.get_deposit_escrow(&first.hash()).unwrap().unwrap();
+        assert_eq!((escrow.proposer, escrow.amount), (proposer, 1_000));
+        assert_eq!(escrow.settlement, None);
+
+        let refund = |id: [u8; 32], amount, nonce| {
+            DepositOperation::Refund { escrow: id }.settlement(963, proposer, amount, nonce)
+        };
+        let burn = DepositOperation::Burn {
+            escrow: second.hash(),
         }
-        
-        Ok(pruned_count)
+        .settlement(963, proposer, 2_000, 1);
+
+        // Not before release, not for another amount, and not twice
+        assert!(matches!(
+            storage.apply_block_at_height(2, &[refund(first.hash(), 1_000, 0)]),
+            Err(StorageError::DepositViolation(_))
+        ));
+        assert!(storage
+            .apply_block_at_height(3, &[refund(first.hash(), 2_000, 0)])
+            .is_err());
+        assert!(storage
+            .apply_block_at_height(3, &[refund([7; 32], 1_000, 0)])
+            .is_err());
+        let settlements = [refund(first.hash(), 1_000, 0), burn];
+        assert!(storage
+            .apply_block_at_height(3, &[settlements[0].clone(), refund(first.hash(), 1_000, 1)])
+            .is_err());
+        storage.apply_block_at_height(3, &settlements).unwrap();
+
+        assert_eq!(storage.get_balance(&escrow_account()).unwrap(), 0);
+        assert_eq!(
+            storage.get_balance(&proposer).unwrap(),
+            10_000 - 200 - 2_000
+        );
+        assert_eq!(storage.get_total_supply().unwrap(), 10_000 - 2_000);
+        assert_eq!(
+            storage
+                .get_deposit_escrow(&second.hash())
+                .unwrap()
+                .unwrap()
+                .settlement,
+            Some(DepositSettlement::Burned)
+        );
+        let journal = storage.get_block_journal(3).unwrap().unwrap();
+        assert!(journal.is_balanced());
+        assert_eq!(journal.net_change(&JournalAccount::Treasury), -3_000);
+        assert_eq!(journal.net_change(&JournalAccount::Burned), 2_000);
+
+        storage.revert_block_atomic(3, &settlements).unwrap();
+        assert_eq!(storage.get_balance(&escrow_account()).unwrap(), 3_000);
+        assert_eq!(storage.get_total_supply().unwrap(), 10_000);
+        assert_eq!(
+            storage
+                .get_deposit_escrow(&first.hash())
+                .unwrap()
+                .unwrap()
+                .settlement,
+            None
+        );
+        storage
+            .revert_block_atomic(1, &[first.clone(), second])
+            .unwrap();
+        assert_eq!(storage.get_deposit_escrow(&first.hash()).unwrap(), None);
+        assert_eq!(storage.get_balance(&proposer).unwrap(), 10_000);
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use opensyria_core::crypto::KeyPair;
-    use std::sync::Arc;
-    use tempfile::tempdir;
 
     #[test]
-    fn test_balance_operations() {
+    fn test_oracle_rates_apply_and_revert() {
+        use opensyria_core::oracle::{OracleUpdate, RateQuote, MAX_RATE_FUTURE_SECS};
+
         let dir = tempdir().unwrap();
         let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let oracle = KeyPair::generate().public_key();
+        let outsider = KeyPair::generate().public_key();
+        for account in [oracle, outsider] {
+            storage.set_balance(&account, 1_000_000).unwrap();
+        }
+        let publish = |from, nonce, rates: &[(&str, u64, u64)]| {
+            OracleUpdate {
+                rates: rates
+                    .iter()
+                    .map(|(pair, rate, timestamp)| RateQuote {
+                        pair: pair.to_string(),
+                        rate: *rate,
+                        timestamp: *timestamp,
+                    })
+                    .collect(),
+            }
+            .transaction(963, from, 100, nonce)
+        };
 
-        let kp = KeyPair::generate();
-        let addr = kp.public_key();
-
-        // Initial balance is 0
-        assert_eq!(storage.get_balance(&addr).unwrap(), 0);
-
-        // Set balance
-        storage.set_balance(&addr, 1_000_000).unwrap();
-        assert_eq!(storage.get_balance(&addr).unwrap(), 1_000_000);
-
-        // Add balance
-        storage.add_balance(&addr, 500_000).unwrap();
-        assert_eq!(storage.get_balance(&addr).unwrap(), 1_500_000);
+        // Only whitelisted keys may publish
+        let first = publish(
+            oracle,
+            0,
+            &[("SYP/USD", 13_000, 100), ("SYP/EUR", 15_000, 100)],
+        );
+        assert!(matches!(
+            storage.apply_block_at_height(1, std::slice::from_ref(&first)),
+            Err(StorageError::OracleViolation(_))
+        ));
+        // Whitelist changes take effect with the next block, in its batch
+        storage.add_oracle(&oracle, 4).unwrap();
+        assert!(storage.get_oracles().unwrap().is_empty());
+        assert!(storage
+            .apply_block_at_height(1, &[publish(outsider, 0, &[("SYP/USD", 1, 100)])])
+            .is_err());
+
+        // Quotes may not run ahead of the block's timestamp
+        let ahead = publish(oracle, 0, &[("SYP/USD", 1, 100 + MAX_RATE_FUTURE_SECS + 1)]);
+        let mut block = Block::new([0; 32], vec![ahead], 16);
+        block.header.timestamp = 100;
+        assert!(storage.check_block(1, &block).is_err());
+        block.transactions = vec![first.clone()];
+        storage.check_block(1, &block).unwrap();
+        storage.apply_block(1, &block).unwrap();
+        assert_eq!(storage.get_oracles().unwrap(), vec![(oracle, 4)]);
+
+        // Rates must move forward in time, within a block too
+        let second = publish(oracle, 1, &[("SYP/USD", 13_500, 200)]);
+        let replay = publish(oracle, 2, &[("SYP/USD", 12_000, 200)]);
+        assert!(storage
+            .apply_block_at_height(2, &[second.clone(), replay])
+            .is_err());
+        storage
+            .apply_block_at_height(2, std::slice::from_ref(&second))
+            .unwrap();
+
+        let latest = storage.get_oracle_rate("SYP/USD").unwrap().unwrap();
+        assert_eq!((latest.quote.rate, latest.height), (13_500, 2));
+        assert_eq!(latest.publisher, oracle);
+        assert_eq!(
+            storage
+                .get_oracle_rate_history("SYP/USD", 10)
+                .unwrap()
+                .len(),
+            2
+        );
+        let pairs: Vec<_> = storage
+            .get_oracle_rates()
+            .unwrap()
+            .into_iter()
+            .map(|rate| (rate.quote.pair, rate.quote.rate))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![
+                ("SYP/EUR".to_string(), 15_000),
+                ("SYP/USD".to_string(), 13_500)
+            ]
+        );
+        assert_eq!(storage.get_oracle_rate("SYP/US").unwrap(), None);
 
-        // Subtract balance
-        storage.sub_balance(&addr, 300_000).unwrap();
-        assert_eq!(storage.get_balance(&addr).unwrap(), 1_200_000);
+        storage.revert_block_atomic(2, &[second]).unwrap();
+        let latest = storage.get_oracle_rate("SYP/USD").unwrap().unwrap();
+        assert_eq!(latest.quote.rate, 13_000);
 
-        // Insufficient balance should error
-        assert!(storage.sub_balance(&addr, 2_000_000).is_err());
+        // Reverting the block that applied the whitelist change stages it again
+        storage.revert_block_atomic(1, &[first]).unwrap();
+        assert!(storage.get_oracles().unwrap().is_empty());
+        storage.apply_block_at_height(1, &[]).unwrap();
+        assert_eq!(storage.get_oracles().unwrap(), vec![(oracle, 4)]);
     }
 
     #[test]
-    fn test_transfer() {
+    fn test_anchors_published_at_interval() {
         let dir = tempdir().unwrap();
-        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let storage = StateStorage::open(dir.path().join("state")).unwrap();
+        let publisher = AnchorPublisher::open(dir.path(), 2).unwrap();
+        let publish = |height: u64| {
+            let task = AnchorTask::Publish {
+                height,
+                block_hash: [height as u8; 32],
+            };
+            run_anchor_task(&storage.db, &publisher, task).unwrap()
+        };
 
         let alice = KeyPair::generate().public_key();
-        let bob = KeyPair::generate().public_key();
-
-        // Give Alice initial balance
         storage.set_balance(&alice, 1_000_000).unwrap();
+        storage.apply_block_at_height(1, &[]).unwrap();
+        storage.apply_block_at_height(2, &[]).unwrap();
+        let anchor = publish(2).unwrap();
+        assert!(anchor.verify().is_ok());
+        assert_eq!(
+            anchor.anchor.state_root,
+            hex::encode(storage.state_tree().unwrap().root())
+        );
+        assert_eq!(
+            anchor.anchor.total_supply,
+            storage.get_total_supply().unwrap()
+        );
 
-        // Transfer to Bob
-        storage.transfer(&alice, &bob, 300_000).unwrap();
+        // A state that is not at the anchor height has nothing to commit to
+        assert_eq!(publish(4), None);
+        assert_eq!(
+            opensyria_core::anchor::anchor_heights(publisher.dir()).unwrap(),
+            vec![2]
+        );
 
-        assert_eq!(storage.get_balance(&alice).unwrap(), 700_000);
-        assert_eq!(storage.get_balance(&bob).unwrap(), 300_000);
+        // Reverting the anchored block retracts its anchor
+        let retract = AnchorTask::Retract { height: 2 };
+        assert_eq!(
+            run_anchor_task(&storage.db, &publisher, retract).unwrap(),
+            None
+        );
+        assert!(opensyria_core::anchor::anchor_heights(publisher.dir())
+            .unwrap()
+            .is_empty());
     }
 
     #[test]
-    fn test_nonce_operations() {
+    fn test_reverted_anchor_replaced_in_order() {
         let dir = tempdir().unwrap();
-        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
-
-        let kp = KeyPair::generate();
-        let addr = kp.public_key();
-
-        assert_eq!(storage.get_nonce(&addr).unwrap(), 0);
-
-        storage.set_nonce(&addr, 5).unwrap();
-        assert_eq!(storage.get_nonce(&addr).unwrap(), 5);
-
-        storage.increment_nonce(&addr).unwrap();
-        assert_eq!(storage.get_nonce(&addr).unwrap(), 6);
+        let mut storage = StateStorage::open(dir.path().join("state")).unwrap();
+        let publisher = Arc::new(AnchorPublisher::open(dir.path(), 1).unwrap());
+        storage.set_anchor_publisher(publisher.clone());
+
+        storage.apply_block_at_height(1, &[]).unwrap();
+        storage.publish_anchor(1, &[1u8; 32]);
+        storage.revert_block_atomic(1, &[]).unwrap();
+        storage.apply_block_at_height(1, &[]).unwrap();
+        storage.publish_anchor(1, &[2u8; 32]);
+
+        // The thread ends with the replacing block's anchor in place
+        let replaced = hex::encode([2u8; 32]);
+        let mut published = None;
+        for _ in 0..100 {
+            published = opensyria_core::anchor::load_anchor(publisher.dir(), 1)
+                .unwrap()
+                .map(|anchor| anchor.anchor.block_hash);
+            if published.as_ref() == Some(&replaced) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(published, Some(replaced));
     }
 
     #[test]
-    fn test_get_all_balances() {
+    fn test_block_apply_and_revert_are_audited() {
+        use opensyria_core::audit::{verify_audit_log, AuditEvent};
+
         let dir = tempdir().unwrap();
-        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let mut storage = StateStorage::open(dir.path().join("state")).unwrap();
+        let audit_path = dir.path().join("audit.log");
+        storage
+            .set_audit_log(Arc::new(AuditLog::open(&audit_path).unwrap()))
+            .unwrap();
 
         let alice = KeyPair::generate().public_key();
         let bob = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 2_000_000).unwrap();
 
-        storage.set_balance(&alice, 1_000_000).unwrap();
-        storage.set_balance(&bob, 2_000_000).unwrap();
-
-        let balances = storage.get_all_balances().unwrap();
+        let tx1 = Transaction::new(alice, bob, 1_000_000, 1_000, 0);
+        let tx2 = Transaction::new(alice, bob, 500_000, 1_000, 1);
+        let transactions = vec![tx1, tx2];
+        storage.apply_block_atomic(&transactions).unwrap();
+        storage.revert_block_atomic(1, &transactions).unwrap();
+
+        assert_eq!(verify_audit_log(&audit_path).unwrap().entries, 4);
+        let entries: Vec<AuditEvent> = std::fs::read_to_string(&audit_path)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                serde_json::from_str::<opensyria_core::AuditEntry>(line)
+                    .unwrap()
+                    .event
+            })
+            .collect();
+
+        // Second transaction sees the balances left by the first
+        match &entries[1] {
+            AuditEvent::Transaction {
+                reverted, balances, ..
+            } => {
+                assert!(!reverted);
+                assert_eq!((balances[0].before, balances[0].after), (999_000, 498_000));
+                assert_eq!(
+                    (balances[1].before, balances[1].after),
+                    (1_000_000, 1_500_000)
+                );
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        // Reverts are logged in reverse order and restore the original balances
+        match &entries[3] {
+            AuditEvent::Transaction {
+                reverted,
+                nonce,
+                balances,
+                ..
+            } => {
+                assert!(reverted);
+                assert_eq!(*nonce, 0);
+                assert_eq!(balances[0].after, 2_000_000);
+                assert_eq!(balances[1].after, 0);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+        assert!(storage.pending_audit_events().unwrap().is_empty());
 
-        assert_eq!(balances.len(), 2);
-        assert_eq!(*balances.get(&alice).unwrap(), 1_000_000);
-        assert_eq!(*balances.get(&bob).unwrap(), 2_000_000);
+        // Events committed before a crash are appended on restart, once
+        let mut batch = WriteBatch::default();
+        let replayed = storage.audit_events(&transactions, false).unwrap();
+        let staged = vec![entries[3].clone(), replayed[0].clone()];
+        storage.stage_audit_events(&mut batch, staged).unwrap();
+        storage.db.write(batch).unwrap();
+        storage
+            .set_audit_log(Arc::new(AuditLog::open(&audit_path).unwrap()))
+            .unwrap();
+        assert_eq!(verify_audit_log(&audit_path).unwrap().entries, 5);
+        assert!(storage.pending_audit_events().unwrap().is_empty());
     }
 
     #[test]
-    fn test_atomic_block_apply() {
+    fn test_block_journal_reconciles_balances() {
         let dir = tempdir().unwrap();
         let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
-
-        let alice_kp = KeyPair::generate();
-        let bob_kp = KeyPair::generate();
-        let charlie_kp = KeyPair::generate();
-
-        let alice = alice_kp.public_key();
-        let bob = bob_kp.public_key();
-        let charlie = charlie_kp.public_key();
-
-        // Give Alice initial balance
+        let miner = KeyPair::generate().public_key();
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
         storage.set_balance(&alice, 2_000_000).unwrap();
 
-        // Create transactions: (from, to, amount, fee, nonce)
-        let tx1 = Transaction::new(alice, bob, 1_000_000, 500, 0); // 500 fee, nonce 0
-        let tx2 = Transaction::new(alice, charlie, 500_000, 500, 1); // 500 fee, nonce 1
-
-        let transactions = vec![tx1, tx2];
-
-        // Apply atomically
-        storage.apply_block_atomic(&transactions).unwrap();
+        let coinbase = Transaction::coinbase(1, miner, 1, 2_000).unwrap();
+        let transactions = vec![
+            coinbase.clone(),
+            Transaction::new(alice, bob, 1_000_000, 1_000, 0),
+            Transaction::new(alice, bob, 500_000, 1_000, 1),
+        ];
+        storage.apply_block_at_height(1, &transactions).unwrap();
+
+        let journal = storage.get_block_journal(1).unwrap().unwrap();
+        assert!(journal.is_balanced());
+        for (address, before) in [(miner, 0), (alice, 2_000_000), (bob, 0)] {
+            let after = storage.get_balance(&address).unwrap() as i128;
+            assert_eq!(
+                journal.net_change(&JournalAccount::Address(address)),
+                after - before
+            );
+        }
+        assert_eq!(journal.net_change(&JournalAccount::Fees), 2_000);
+        assert_eq!(
+            journal.net_change(&JournalAccount::Issuance),
+            -(coinbase.amount as i128)
+        );
 
-        // Verify all changes applied
-        // Alice: 2M - (1M + 500) - (500K + 500) = 499,000
-        assert_eq!(storage.get_balance(&alice).unwrap(), 499_000); 
-        assert_eq!(storage.get_balance(&bob).unwrap(), 1_000_000);
-        assert_eq!(storage.get_balance(&charlie).unwrap(), 500_000);
-        assert_eq!(storage.get_nonce(&alice).unwrap(), 2);
+        // Movements outside the block join its journal
+        storage
+            .post_journal(
+                1,
+                JournalReason::Burn,
+                JournalAccount::Treasury,
+                JournalAccount::Burned,
+                50,
+            )
+            .unwrap();
+        let journal = storage.get_block_journal(1).unwrap().unwrap();
+        assert!(journal.is_balanced());
+        assert_eq!(journal.net_change(&JournalAccount::Burned), 50);
+        assert!(storage.get_block_journal(2).unwrap().is_none());
+
+        // Supply totals follow the journal
+        let totals = storage.get_supply_totals(1).unwrap();
+        assert_eq!((totals.blocks, totals.coinbase), (1, coinbase.amount));
+        assert_eq!((totals.burned, totals.treasury), (50, -50));
+        assert_eq!(storage.get_supply_totals(9).unwrap(), totals);
+
+        // Reverting the block drops its journal and totals
+        storage.revert_block_atomic(1, &transactions).unwrap();
+        assert!(storage.get_block_journal(1).unwrap().is_none());
+        assert_eq!(
+            storage.get_supply_totals(1).unwrap(),
+            SupplyTotals::default()
+        );
+        assert_eq!(storage.get_state_height().unwrap(), 0);
     }
 
     #[test]
-    fn test_atomic_block_revert() {
+    fn test_supply_totals_migrated() {
         let dir = tempdir().unwrap();
         let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let miner = KeyPair::generate().public_key();
+        for height in 1..=3 {
+            let coinbase = Transaction::coinbase(1, miner, height, 0).unwrap();
+            storage.apply_block_at_height(height, &[coinbase]).unwrap();
+        }
+        let expected = storage.get_supply_totals(3).unwrap();
+        assert_eq!(expected.blocks, 3);
+
+        // Written before totals were kept
+        for height in 1..=3 {
+            storage
+                .db
+                .delete(StateStorage::supply_totals_key(height))
+                .unwrap();
+        }
+        storage
+            .db
+            .put(SCHEMA_VERSION_KEY, 2u32.to_le_bytes())
+            .unwrap();
+        drop(storage);
 
-        let alice_kp = KeyPair::generate();
-        let bob_kp = KeyPair::generate();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        assert_eq!(storage.get_supply_totals(3).unwrap(), expected);
+        assert_eq!(storage.get_supply_totals(1).unwrap().blocks, 1);
+    }
 
-        let alice = alice_kp.public_key();
-        let bob = bob_kp.public_key();
+    #[test]
+    fn test_frozen_account_cannot_spend() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
 
-        // Setup: Alice sends to Bob
+        let alice = KeyPair::generate().public_key();
+        let bob = KeyPair::generate().public_key();
         storage.set_balance(&alice, 2_000_000).unwrap();
-        storage.set_nonce(&alice, 0).unwrap();
-
-        let tx = Transaction::new(alice, bob, 1_000_000, 1_000, 0); // 1K fee
-        storage.apply_block_atomic(&vec![tx.clone()]).unwrap();
-
-        // Verify applied: Alice pays 1M + 1K fee
-        assert_eq!(storage.get_balance(&alice).unwrap(), 999_000); // 2M - 1M - 1K
-        assert_eq!(storage.get_balance(&bob).unwrap(), 1_000_000);
-        assert_eq!(storage.get_nonce(&alice).unwrap(), 1);
+        storage.set_balance(&bob, 2_000_000).unwrap();
 
-        // Revert transaction
-        storage.revert_block_atomic(&vec![tx]).unwrap();
+        storage.freeze_account(&alice, 7).unwrap();
+        assert_eq!(storage.get_freeze(&alice).unwrap(), Some(7));
+        assert_eq!(storage.get_frozen_accounts().unwrap(), vec![(alice, 7)]);
 
-        // Verify reverted
+        // Spending is rejected, receiving is not
+        let spend = Transaction::new(alice, bob, 1_000, 100, 0);
+        assert!(matches!(
+            storage.apply_block_atomic(&[spend.clone()]),
+            Err(StorageError::AccountFrozen(_))
+        ));
         assert_eq!(storage.get_balance(&alice).unwrap(), 2_000_000);
-        assert_eq!(storage.get_balance(&bob).unwrap(), 0);
-        assert_eq!(storage.get_nonce(&alice).unwrap(), 0);
+        storage
+            .apply_block_atomic(&[Transaction::new(bob, alice, 1_000, 100, 0)])
+            .unwrap();
+
+        storage.unfreeze_account(&alice).unwrap();
+        assert!(!storage.is_frozen(&alice).unwrap());
+        storage.apply_block_atomic(&[spend]).unwrap();
     }
 
     #[tokio::test]
@@ -927,11 +5149,15 @@ mod tests {
         let tx2 = tx.clone();
 
         let handle1 = tokio::spawn(async move {
-            storage1.execute_multisig_transaction(&tx1).await
+            storage1
+                .execute_multisig_transaction(&tx1, &ChainParams::mainnet())
+                .await
         });
 
         let handle2 = tokio::spawn(async move {
-            storage2.execute_multisig_transaction(&tx2).await
+            storage2
+                .execute_multisig_transaction(&tx2, &ChainParams::mainnet())
+                .await
         });
 
         let (result1, result2) = tokio::join!(handle1, handle2);
@@ -971,4 +5197,44 @@ mod tests {
         // Should error on overflow instead of saturating
         assert!(storage.add_balance(&alice, 200).is_err());
     }
+
+    #[test]
+    fn test_examine_quarantines_corrupt_entries() {
+        let dir = tempdir().unwrap();
+        let storage = StateStorage::open(dir.path().to_path_buf()).unwrap();
+        let alice = KeyPair::generate().public_key();
+        storage.set_balance(&alice, 500).unwrap();
+        storage
+            .db
+            .put(b"balance_short", 1u64.to_le_bytes())
+            .unwrap();
+        let mut bad_value = b"balance_".to_vec();
+        bad_value.extend_from_slice(&[7u8; 32]);
+        storage.db.put(&bad_value, b"xyz").unwrap();
+        storage.db.put(b"future_feature_key", b"value").unwrap();
+
+        let report = storage.examine(DoctorOptions::default()).unwrap();
+        assert_eq!(report.corrupt_total, 2);
+        let balances = report
+            .namespaces
+            .iter()
+            .find(|ns| ns.name == "balance")
+            .unwrap();
+        assert_eq!((balances.keys, balances.corrupt), (3, 2));
+        assert_eq!(report.unknown, vec![("default", 1)]);
+        assert_eq!(report.quarantined, 0);
+
+        let options = DoctorOptions {
+            quarantine: true,
+            ..Default::default()
+        };
+        assert_eq!(storage.examine(options).unwrap().quarantined, 2);
+
+        // Unknown keys stay where they are
+        let report = storage.examine(options).unwrap();
+        assert!(report.is_healthy());
+        assert_eq!(report.previously_quarantined, 2);
+        assert_eq!(report.unknown, vec![("default", 1)]);
+        assert_eq!(storage.get_balance(&alice).unwrap(), 500);
+    }
 }
@@ -0,0 +1,268 @@
+//! Supply and emission report
+//! تقرير العرض الكلي والإصدار
+//!
+//! Every block applied to the state writes its [`SupplyTotals`], the
+//! cumulative coinbase, burned and treasury amounts up to its height, in the
+//! same batch as its journal. [`SupplyReport::build`] therefore reads two
+//! records per halving era instead of walking the chain. The remaining
+//! emission is projected from the chain parameters alone: each era pays its
+//! block reward until the reward halves to zero or the next block would
+//! exceed `max_supply`.
+
+use crate::{BlockJournal, BlockchainStorage, JournalAccount, JournalReason, JournalSide};
+use crate::{StateStorage, StorageError};
+use opensyria_core::ChainParams;
+use serde::{Deserialize, Serialize};
+
+/// Running totals of the blocks applied up to a height
+/// المجاميع التراكمية للكتل المطبقة حتى ارتفاع معين
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    bincode::Encode,
+    bincode::Decode,
+)]
+pub struct SupplyTotals {
+    /// Blocks applied, genesis included
+    pub blocks: u64,
+    /// Coinbase paid, including fees
+    pub coinbase: u64,
+    /// Net amount credited to [`JournalAccount::Burned`]
+    pub burned: i128,
+    /// Net amount credited to [`JournalAccount::Treasury`]
+    pub treasury: i128,
+}
+
+impl SupplyTotals {
+    /// Totals once the block posting `journal` is applied
+    pub fn with_block(self, journal: &BlockJournal) -> Self {
+        let coinbase: u64 = journal
+            .lines
+            .iter()
+            .filter(|line| {
+                line.reason == JournalReason::Coinbase && line.side == JournalSide::Credit
+            })
+            .map(|line| line.amount)
+            .sum();
+        Self {
+            blocks: self.blocks + 1,
+            coinbase: self.coinbase.saturating_add(coinbase),
+            burned: self.burned + journal.net_change(&JournalAccount::Burned),
+            treasury: self.treasury + journal.net_change(&JournalAccount::Treasury),
+        }
+    }
+}
+
+/// Coinbase paid during one halving era
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraIssuance {
+    /// Era number, starting at 0
+    pub era: u64,
+    pub start_height: u64,
+    /// Last height of the era that is on the chain
+    pub end_height: u64,
+    /// Reward per block in this era
+    pub block_reward: u64,
+    /// Blocks found on the chain
+    pub blocks: u64,
+    /// Block rewards alone (`block_reward` × `blocks`)
+    pub subsidy: u64,
+    /// Coinbase actually paid, including fees
+    pub coinbase: u64,
+}
+
+/// Projected issuance of one era, from the tip onwards
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EraProjection {
+    pub era: u64,
+    pub start_height: u64,
+    pub end_height: u64,
+    pub block_reward: u64,
+    /// Rewards paid between `start_height` and `end_height`
+    pub issued: u64,
+    /// Supply once `end_height` is mined
+    pub supply_at_end: u64,
+}
+
+/// Emission still to come under the chain parameters
+/// الإصدار المتبقي وفق معاملات السلسلة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmissionProjection {
+    pub eras: Vec<EraProjection>,
+    /// Height of the last block paying a reward
+    pub final_height: u64,
+    /// Supply once issuance ends
+    pub final_supply: u64,
+    pub blocks_remaining: u64,
+    /// `blocks_remaining` at the target block time
+    pub estimated_secs_remaining: u64,
+}
+
+impl EmissionProjection {
+    /// Project issuance after `height` starting from `supply`
+    pub fn project(params: &ChainParams, height: u64, supply: u64) -> Self {
+        let mut eras = Vec::new();
+        let mut height_now = height;
+        let mut supply_now = supply;
+        loop {
+            let next = height_now + 1;
+            let block_reward = params.block_reward(next);
+            if block_reward == 0 || supply_now >= params.max_supply {
+                break;
+            }
+
+            let era = (next - 1) / params.halving_interval;
+            let era_blocks = (era + 1) * params.halving_interval - height_now;
+            // A block whose reward would pass max_supply is invalid
+            let room_blocks = (params.max_supply - supply_now) / block_reward;
+            let blocks = era_blocks.min(room_blocks);
+            if blocks == 0 {
+                break;
+            }
+
+            let issued = blocks * block_reward;
+            height_now += blocks;
+            supply_now += issued;
+            eras.push(EraProjection {
+                era,
+                start_height: next,
+                end_height: height_now,
+                block_reward,
+                issued,
+                supply_at_end: supply_now,
+            });
+            if blocks < era_blocks {
+                break;
+            }
+        }
+
+        let blocks_remaining = height_now - height;
+        Self {
+            eras,
+            final_height: height_now,
+            final_supply: supply_now,
+            blocks_remaining,
+            estimated_secs_remaining: blocks_remaining
+                .saturating_mul(params.target_block_time_secs),
+        }
+    }
+}
+
+/// Supply, issuance by era, burned and treasury amounts, and projection
+/// تقرير العرض الكلي والإصدار حسب الحقبة والمبالغ المحروقة والخزينة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplyReport {
+    pub network: String,
+    pub height: u64,
+    pub total_supply: u64,
+    pub max_supply: u64,
+    /// `max_supply` minus `total_supply`
+    pub remaining_supply: u64,
+    /// Burned governance deposits
+    pub burned: u64,
+    /// Deposits held by governance
+    pub treasury: u64,
+    /// `total_supply` minus `treasury`
+    pub circulating: u64,
+    pub eras: Vec<EraIssuance>,
+    pub projection: EmissionProjection,
+}
+
+impl SupplyReport {
+    /// Build the report for the current tip
+    pub fn build(
+        blockchain: &BlockchainStorage,
+        state: &StateStorage,
+    ) -> Result<Self, StorageError> {
+        let params = blockchain.chain_params();
+        let height = blockchain.get_chain_height()?;
+
+        let mut eras: Vec<EraIssuance> = Vec::new();
+        for era in 0..height.div_ceil(params.halving_interval) {
+            let start_height = era * params.halving_interval + 1;
+            let end_height = ((era + 1) * params.halving_interval).min(height);
+            let before = state.get_supply_totals(start_height - 1)?;
+            let after = state.get_supply_totals(end_height)?;
+            let blocks = after.blocks.saturating_sub(before.blocks);
+            if blocks == 0 {
+                continue;
+            }
+            let block_reward = params.block_reward(start_height);
+            eras.push(EraIssuance {
+                era,
+                start_height,
+                end_height,
+                block_reward,
+                blocks,
+                subsidy: block_reward.saturating_mul(blocks),
+                coinbase: after.coinbase.saturating_sub(before.coinbase),
+            });
+        }
+
+        let totals = state.get_supply_totals(height)?;
+        let burned = totals.burned.clamp(0, u64::MAX as i128) as u64;
+        let treasury = totals.treasury.clamp(0, u64::MAX as i128) as u64;
+
+        let total_supply = state.get_total_supply()?;
+        Ok(Self {
+            network: params.network.clone(),
+            height,
+            total_supply,
+            max_supply: params.max_supply,
+            remaining_supply: params.max_supply.saturating_sub(total_supply),
+            burned,
+            treasury,
+            circulating: total_supply.saturating_sub(treasury),
+            eras,
+            projection: EmissionProjection::project(params, height, total_supply),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projection_follows_halvings_to_max_supply() {
+        let params = ChainParams::regtest();
+        let projection = EmissionProjection::project(&params, 0, 0);
+
+        let first = &projection.eras[0];
+        assert_eq!(first.start_height, 1);
+        assert_eq!(first.end_height, params.halving_interval);
+        assert_eq!(first.block_reward, params.initial_block_reward);
+        assert_eq!(
+            projection.eras[1].block_reward,
+            params.initial_block_reward / 2
+        );
+
+        assert!(projection.final_supply <= params.max_supply);
+        let issued: u64 = projection.eras.iter().map(|era| era.issued).sum();
+        assert_eq!(issued, projection.final_supply);
+        assert_eq!(projection.blocks_remaining, projection.final_height);
+
+        // Projecting from part way through an era continues where it left off
+        let later = EmissionProjection::project(&params, 10, 10 * params.initial_block_reward);
+        assert_eq!(later.eras[0].start_height, 11);
+        assert_eq!(later.final_supply, projection.final_supply);
+    }
+
+    #[test]
+    fn test_projection_stops_at_max_supply() {
+        let params = ChainParams {
+            max_supply: ChainParams::regtest().initial_block_reward * 5 / 2,
+            ..ChainParams::regtest()
+        };
+        let projection = EmissionProjection::project(&params, 0, 0);
+        assert_eq!(projection.final_height, 2);
+        assert_eq!(projection.final_supply, params.initial_block_reward * 2);
+        assert_eq!(projection.eras.len(), 1);
+    }
+}
@@ -0,0 +1,251 @@
+//! Thin (header-only) storage for watcher nodes
+//! التخزين الخفيف (الرؤوس فقط) لعقد المراقبة
+//!
+//! A monitoring node follows the chain tip and a handful of addresses, so
+//! storing every block body is wasted disk. In thin mode the blockchain
+//! database keeps each block's header, its coinbase (which names the
+//! producer) and only the transactions that send from or pay to an address
+//! on the [`Watchlist`]. Stored blocks no longer match their merkle roots,
+//! so anything that needs a full block — serving blocks to peers, replaying
+//! state, reading an unwatched address — fails with
+//! [`StorageError::ThinNode`] instead of returning partial data.
+//!
+//! The watchlist is recorded in [`THIN_FILE`] inside the blockchain
+//! directory when the database is created, and later opens pick it up
+//! without being told. A full database cannot be thinned in place.
+//! Addresses added to the watchlist later are only collected from then on.
+
+use crate::StorageError;
+use opensyria_core::{crypto::PublicKey, Block, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+/// File in the blockchain directory recording its watchlist
+pub const THIN_FILE: &str = "thin_mode.json";
+
+/// Addresses whose transactions a thin node keeps
+/// العناوين التي تحتفظ العقدة الخفيفة بمعاملاتها
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Watchlist {
+    addresses: HashSet<PublicKey>,
+}
+
+impl Watchlist {
+    pub fn new(addresses: impl IntoIterator<Item = PublicKey>) -> Self {
+        Self {
+            addresses: addresses.into_iter().collect(),
+        }
+    }
+
+    pub fn contains(&self, address: &PublicKey) -> bool {
+        self.addresses.contains(address)
+    }
+
+    /// Watched addresses in byte order
+    pub fn addresses(&self) -> Vec<PublicKey> {
+        let mut addresses: Vec<PublicKey> = self.addresses.iter().copied().collect();
+        addresses.sort_by_key(|address| address.0);
+        addresses
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Whether `tx` sends from or pays to a watched address
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        (!tx.is_coinbase() && self.contains(&tx.from)) || self.contains(&tx.to)
+    }
+
+    /// The part of `block` a thin node stores: the header, the coinbase and
+    /// the watched transactions, in block order
+    /// الجزء المخزن من الكتلة في الوضع الخفيف
+    pub fn filter_block(&self, block: &Block) -> Block {
+        Block {
+            header: block.header.clone(),
+            transactions: block
+                .transactions
+                .iter()
+                .filter(|tx| tx.is_coinbase() || self.matches(tx))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// On-disk form of [`THIN_FILE`]
+#[derive(Serialize, Deserialize)]
+struct ThinRecord {
+    /// Hex-encoded public keys
+    watchlist: Vec<String>,
+}
+
+/// Watchlist recorded for the blockchain database at `dir`, if it is thin
+/// قائمة المراقبة المسجلة لقاعدة بيانات سلسلة الكتل
+pub fn recorded_watchlist(dir: &Path) -> Result<Option<Watchlist>, StorageError> {
+    let file = dir.join(THIN_FILE);
+    if !file.exists() {
+        return Ok(None);
+    }
+    let invalid = |e: String| StorageError::ThinNode(format!("{}: {}", file.display(), e));
+    let json = fs::read(&file).map_err(|e| invalid(e.to_string()))?;
+    let record: ThinRecord = serde_json::from_slice(&json).map_err(|e| invalid(e.to_string()))?;
+    let addresses = record
+        .watchlist
+        .iter()
+        .map(|hex| PublicKey::from_hex(hex).map_err(|e| invalid(format!("{}: {}", hex, e))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Some(Watchlist::new(addresses)))
+}
+
+/// Watchlist the database at `dir` runs with, recording the requested one
+/// if the database is being created or the list changed
+pub(crate) fn resolve_watchlist(
+    dir: &Path,
+    requested: Option<&Watchlist>,
+) -> Result<Option<Watchlist>, StorageError> {
+    let recorded = recorded_watchlist(dir)?;
+    let watchlist = match (requested, recorded) {
+        (None, recorded) => return Ok(recorded),
+        (Some(requested), Some(recorded)) if requested == &recorded => return Ok(Some(recorded)),
+        (Some(_), None) if dir.join("CURRENT").exists() => {
+            return Err(StorageError::ThinNode(format!(
+                "{} holds a full chain and cannot be thinned in place; start the thin node from an empty directory",
+                dir.display()
+            )));
+        }
+        // New database, or a changed watchlist
+        (Some(requested), _) => requested.clone(),
+    };
+
+    let record = ThinRecord {
+        watchlist: watchlist
+            .addresses()
+            .iter()
+            .map(PublicKey::to_hex)
+            .collect(),
+    };
+    let json = serde_json::to_vec_pretty(&record)
+        .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+    fs::create_dir_all(dir).map_err(thin_error)?;
+    fs::write(dir.join(THIN_FILE), json).map_err(thin_error)?;
+    Ok(Some(watchlist))
+}
+
+fn thin_error(e: std::io::Error) -> StorageError {
+    StorageError::ThinNode(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BlockchainStorage;
+    use opensyria_core::crypto::KeyPair;
+    use opensyria_core::MIN_TRANSACTION_FEE;
+
+    fn mine_block(mut block: Block) -> Block {
+        while !block.header.meets_difficulty() {
+            block.header.nonce += 1;
+        }
+        block
+    }
+
+    fn transfer(from: &KeyPair, to: PublicKey, nonce: u64) -> Transaction {
+        let tx = Transaction::new(from.public_key(), to, 1_000, MIN_TRANSACTION_FEE, nonce);
+        let signature = from.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_thin_node_keeps_headers_and_watched_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("blockchain");
+        let watched = KeyPair::generate();
+        let other = KeyPair::generate();
+        let miner = KeyPair::generate().public_key();
+        let watchlist = Watchlist::new([watched.public_key()]);
+
+        let storage =
+            BlockchainStorage::open_thin(path.clone(), &Default::default(), &watchlist).unwrap();
+        let genesis = Block::genesis();
+        storage.append_block(&genesis, None).unwrap();
+
+        let incoming = transfer(&other, watched.public_key(), 0);
+        let unrelated = transfer(&other, miner, 1);
+        let coinbase = Transaction::coinbase(
+            opensyria_core::CHAIN_ID_MAINNET,
+            miner,
+            2,
+            2 * MIN_TRANSACTION_FEE,
+        )
+        .unwrap();
+        let mut block = Block::new(
+            genesis.hash(),
+            vec![coinbase, incoming.clone(), unrelated.clone()],
+            storage.next_difficulty().unwrap(),
+        );
+        block.header.timestamp = genesis.header.timestamp + 60;
+        let block = mine_block(block);
+        storage.append_block(&block, None).unwrap();
+
+        // Headers and the chain index are complete
+        assert_eq!(storage.get_chain_tip().unwrap(), Some(block.hash()));
+        assert_eq!(
+            storage.get_header_by_height(2).unwrap().unwrap().hash(),
+            block.hash()
+        );
+        assert_eq!(
+            storage.get_block_producer(2).unwrap().unwrap().address,
+            miner
+        );
+
+        // Only watched transactions are kept
+        let (found, height) = storage
+            .get_transaction_by_hash(&incoming.hash())
+            .unwrap()
+            .unwrap();
+        assert_eq!((found.hash(), height), (incoming.hash(), 2));
+        assert!(storage
+            .get_transaction_by_hash(&unrelated.hash())
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            storage
+                .get_address_transactions(&watched.public_key().0)
+                .unwrap(),
+            vec![incoming.hash()]
+        );
+
+        // Work needing full blocks is refused
+        for result in [
+            storage.get_block(&block.hash()).map(|_| ()),
+            storage.get_block_by_height(2).map(|_| ()),
+            storage
+                .get_address_transactions(&other.public_key().0)
+                .map(|_| ()),
+        ] {
+            assert!(matches!(result, Err(StorageError::ThinNode(_))));
+        }
+        drop(storage);
+
+        // Plain opens stay thin
+        let storage = BlockchainStorage::open(path.clone()).unwrap();
+        assert_eq!(storage.watchlist(), Some(&watchlist));
+        drop(storage);
+        assert_eq!(recorded_watchlist(&path).unwrap(), Some(watchlist.clone()));
+
+        // A full database cannot be thinned in place
+        let full = dir.path().join("full");
+        drop(BlockchainStorage::open(full.clone()).unwrap());
+        assert!(matches!(
+            BlockchainStorage::open_thin(full, &Default::default(), &watchlist),
+            Err(StorageError::ThinNode(_))
+        ));
+    }
+}
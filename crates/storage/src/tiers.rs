@@ -0,0 +1,237 @@
+//! Tiered storage and per column family tuning for the state database
+//! التخزين المتدرج وضبط عائلات الأعمدة لقاعدة بيانات الحالة
+//!
+//! Block validation reads a balance and a nonce for every transaction, so on
+//! spinning disks state lookups dominate. A [`StorageTier`] puts the state
+//! database's SST files on a fast directory (an SSD) up to a target size,
+//! while the blockchain database and any overflow stay on bulk storage.
+//! RocksDB fills the fast path with the newest levels first, which is where
+//! recently touched accounts live.
+//!
+//! Every SST file records which path it was written to, so the tier is
+//! recorded in [`TIERS_FILE`] inside the state directory when the database
+//! is created. Later opens, including `doctor` and `migrate`, pick it up
+//! without being told; asking for a different fast path is refused.
+
+use crate::StorageError;
+use rocksdb::{BlockBasedOptions, DBCompressionType, DBPath, Options};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File in the state directory recording its fast path
+pub const TIERS_FILE: &str = "storage_tiers.json";
+
+/// Fast directory holding the hot part of the state database
+/// المسار السريع الذي يحمل الجزء النشط من قاعدة بيانات الحالة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageTier {
+    /// Directory on the fast device
+    pub path: PathBuf,
+    /// SST bytes to keep on the fast path before spilling to bulk storage
+    pub target_bytes: u64,
+}
+
+impl StorageTier {
+    pub fn new(path: impl Into<PathBuf>, target_bytes: u64) -> Self {
+        Self {
+            path: path.into(),
+            target_bytes,
+        }
+    }
+
+    /// Fast path first, then the state directory itself without a size limit
+    fn db_paths(&self, state_dir: &Path) -> Result<Vec<DBPath>, StorageError> {
+        fs::create_dir_all(&self.path).map_err(tier_error)?;
+        Ok(vec![
+            DBPath::new(&self.path, self.target_bytes)?,
+            DBPath::new(state_dir, u64::MAX)?,
+        ])
+    }
+}
+
+/// RocksDB settings for one column family
+/// إعدادات عائلة أعمدة واحدة
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnFamilyTuning {
+    /// Bloom filter bits per key; 10 gives ~1% false positives, 0 disables
+    pub bloom_bits_per_key: f64,
+    /// Keep index and filter blocks in the shared block cache, pinned for L0
+    pub pin_filters: bool,
+    /// Compress SST files with LZ4
+    pub compress: bool,
+}
+
+impl Default for ColumnFamilyTuning {
+    fn default() -> Self {
+        Self {
+            bloom_bits_per_key: 10.0,
+            pin_filters: false,
+            compress: true,
+        }
+    }
+}
+
+impl ColumnFamilyTuning {
+    /// Tuning for the balance and nonce working set read on every transaction
+    pub fn hot() -> Self {
+        Self {
+            pin_filters: true,
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn apply(&self, opts: &mut Options, block_opts: &mut BlockBasedOptions) {
+        if self.bloom_bits_per_key > 0.0 {
+            block_opts.set_bloom_filter(self.bloom_bits_per_key, false);
+        }
+        if self.pin_filters {
+            block_opts.set_cache_index_and_filter_blocks(true);
+            block_opts.set_pin_l0_filter_and_index_blocks_in_cache(true);
+        }
+        opts.set_compression_type(if self.compress {
+            DBCompressionType::Lz4
+        } else {
+            DBCompressionType::None
+        });
+    }
+}
+
+/// How to lay out and tune the state database
+/// طريقة توزيع قاعدة بيانات الحالة وضبطها
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateOptions {
+    /// Fast path for the hot working set (None keeps everything in the state
+    /// directory, or uses the path recorded when the database was created)
+    pub fast_tier: Option<StorageTier>,
+    /// Balances, nonces and other account records (the default column family)
+    pub accounts: ColumnFamilyTuning,
+    /// Contract storage
+    pub contracts: ColumnFamilyTuning,
+    /// Quarantined records awaiting operator review
+    pub quarantine: ColumnFamilyTuning,
+}
+
+impl Default for StateOptions {
+    fn default() -> Self {
+        Self {
+            fast_tier: None,
+            accounts: ColumnFamilyTuning::hot(),
+            contracts: ColumnFamilyTuning::default(),
+            quarantine: ColumnFamilyTuning::default(),
+        }
+    }
+}
+
+impl StateOptions {
+    pub fn with_fast_tier(mut self, tier: StorageTier) -> Self {
+        self.fast_tier = Some(tier);
+        self
+    }
+
+    /// Point `opts` at the tier recorded for `state_dir`, recording the
+    /// requested one if the database is being created
+    pub(crate) fn apply_tier(
+        &self,
+        state_dir: &Path,
+        opts: &mut Options,
+    ) -> Result<(), StorageError> {
+        if let Some(tier) = resolve_tier(state_dir, self.fast_tier.as_ref())? {
+            opts.set_db_paths(&tier.db_paths(state_dir)?);
+        }
+        Ok(())
+    }
+}
+
+/// Fast path recorded for the state database at `state_dir`, if any
+/// المسار السريع المسجل لقاعدة بيانات الحالة
+pub fn recorded_tier(state_dir: &Path) -> Result<Option<StorageTier>, StorageError> {
+    let file = state_dir.join(TIERS_FILE);
+    if !file.exists() {
+        return Ok(None);
+    }
+    let json = fs::read(&file).map_err(tier_error)?;
+    serde_json::from_slice(&json)
+        .map(Some)
+        .map_err(|e| StorageError::TierError(format!("{}: {}", file.display(), e)))
+}
+
+fn resolve_tier(
+    state_dir: &Path,
+    requested: Option<&StorageTier>,
+) -> Result<Option<StorageTier>, StorageError> {
+    let recorded = recorded_tier(state_dir)?;
+    let tier = match (requested, recorded) {
+        (None, recorded) => return Ok(recorded),
+        (Some(requested), Some(recorded)) if requested.path != recorded.path => {
+            return Err(StorageError::TierError(format!(
+                "state database was created with fast path {}, not {}",
+                recorded.path.display(),
+                requested.path.display()
+            )));
+        }
+        (Some(requested), Some(recorded)) if requested == &recorded => return Ok(Some(recorded)),
+        (Some(requested), None) if state_dir.join("CURRENT").exists() => {
+            return Err(StorageError::TierError(format!(
+                "cannot move an existing state database onto fast path {}; resync or restore a snapshot into an empty directory",
+                requested.path.display()
+            )));
+        }
+        // New database, or a new target size for the recorded path
+        (Some(requested), _) => requested.clone(),
+    };
+
+    fs::create_dir_all(state_dir).map_err(tier_error)?;
+    let json = serde_json::to_vec_pretty(&tier)
+        .map_err(|e| StorageError::SerializationError(e.to_string()))?;
+    fs::write(state_dir.join(TIERS_FILE), json).map_err(tier_error)?;
+    Ok(Some(tier))
+}
+
+fn tier_error(e: std::io::Error) -> StorageError {
+    StorageError::TierError(e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StateStorage;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_fast_tier_is_recorded() {
+        let bulk = tempfile::tempdir().unwrap();
+        let fast = tempfile::tempdir().unwrap();
+        let state_dir = bulk.path().join("state");
+        let tier = StorageTier::new(fast.path().join("state"), 1 << 30);
+        let address = KeyPair::generate().public_key();
+
+        let options = StateOptions::default().with_fast_tier(tier.clone());
+        let state =
+            StateStorage::open_with_options(state_dir.clone(), &Default::default(), &options)
+                .unwrap();
+        state.set_balance(&address, 42).unwrap();
+        drop(state);
+        assert_eq!(recorded_tier(&state_dir).unwrap(), Some(tier.clone()));
+
+        // Plain opens find the fast path on their own
+        let state = StateStorage::open(state_dir.clone()).unwrap();
+        assert_eq!(state.get_balance(&address).unwrap(), 42);
+        drop(state);
+
+        let elsewhere = StateOptions::default()
+            .with_fast_tier(StorageTier::new(bulk.path().join("other"), 1 << 30));
+        assert!(matches!(
+            StateStorage::open_with_options(state_dir, &Default::default(), &elsewhere),
+            Err(StorageError::TierError(_))
+        ));
+
+        // An existing database without a tier cannot gain one in place
+        let plain_dir = bulk.path().join("plain");
+        drop(StateStorage::open(plain_dir.clone()).unwrap());
+        assert!(matches!(
+            StateStorage::open_with_options(plain_dir, &Default::default(), &options),
+            Err(StorageError::TierError(_))
+        ));
+    }
+}
@@ -0,0 +1,501 @@
+//! Node control endpoints for operators
+//! نقاط التحكم بالعقدة للمشغلين
+//!
+//! Start and stop the built-in miner and tune its threads,
+//! dial or ban a peer, flush the pending transaction pool and rotate API
+//! keys, and manage the passkeys operators log in with. Every route
+//! requires an API key with the `admin` permission or a passkey login
+//! session (see [`crate::auth::webauthn`]), and the routes are served on
+//! their own listener (`127.0.0.1:8081` by default) rather than the public
+//! API, so they are reachable only from the node's host unless the
+//! operator binds them elsewhere.
+
+use axum::{
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{Json, Response},
+    routing::{delete, get, post},
+    Extension, Router,
+};
+use opensyria_core::crypto::PublicKey;
+use opensyria_network::{reputation::BAN_DURATION_SECS, Multiaddr, NetworkCommand, PeerId};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+
+use crate::{
+    auth::{
+        webauthn::{self, AdminAuth},
+        ApiKey, Permission,
+    },
+    models::ErrorResponse,
+    rate_limit, AppState,
+};
+
+/// Default address of the admin listener
+pub const DEFAULT_ADMIN_BIND: &str = "127.0.0.1:8081";
+
+/// Most mining threads an operator may request
+pub const MAX_MINER_THREADS: usize = 256;
+
+/// Pause between mined blocks, which also lets API requests take the node
+const MINER_PAUSE: Duration = Duration::from_secs(1);
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// Miner settings and progress
+/// حالة المعدّن وإعداداته
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MinerStatus {
+    pub running: bool,
+    /// Hex public key receiving block rewards
+    pub address: Option<String>,
+    /// Mining threads; `None` uses all cores
+    pub threads: Option<usize>,
+    /// Blocks mined since the miner was last started
+    pub blocks_mined: u64,
+    /// Why the miner last stopped on its own
+    pub last_error: Option<String>,
+}
+
+/// Background miner driven by the admin endpoints
+/// المعدّن الخلفي المتحكم به عبر نقاط الإدارة
+#[derive(Default)]
+pub struct Miner {
+    status: Mutex<MinerStatus>,
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Miner {
+    /// Current settings and progress
+    pub fn status(&self) -> MinerStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Change thread settings; a running miner picks them up from its next
+    /// block
+    pub fn configure(&self, threads: Option<usize>) {
+        self.status.lock().unwrap().threads = threads;
+    }
+
+    /// Start mining to `address`; returns false if already running
+    /// بدء التعدين لصالح عنوان محدد
+    pub fn start(&self, state: Arc<AppState>, address: PublicKey) -> bool {
+        let mut task = self.task.lock().unwrap();
+        {
+            let mut status = self.status.lock().unwrap();
+            if status.running {
+                return false;
+            }
+            status.running = true;
+            status.address = Some(address.to_hex());
+            status.blocks_mined = 0;
+            status.last_error = None;
+        }
+        *task = Some(tokio::spawn(run_miner(state, address)));
+        true
+    }
+
+    /// Stop mining; returns false if the miner was not running
+    /// إيقاف التعدين
+    pub fn stop(&self) -> bool {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+        std::mem::replace(&mut self.status.lock().unwrap().running, false)
+    }
+
+    fn record_block(&self) {
+        self.status.lock().unwrap().blocks_mined += 1;
+    }
+
+    fn record_failure(&self, error: String) {
+        let mut status = self.status.lock().unwrap();
+        status.running = false;
+        status.last_error = Some(error);
+    }
+}
+
+/// Mine blocks one at a time until stopped or a block fails
+///
+/// The node is locked only to build each template and to submit the mined
+/// block; the proof of work runs on a blocking thread meanwhile. A block
+/// whose tip moved on in between is dropped and mining starts over.
+async fn run_miner(state: Arc<AppState>, address: PublicKey) {
+    loop {
+        let threads = state.miner.status().threads;
+        match mine_one(&state, &address, threads).await {
+            Ok(true) => state.miner.record_block(),
+            Ok(false) => tracing::debug!("Chain tip moved while mining; starting over"),
+            Err(e) => {
+                tracing::warn!("Miner stopped: {:#}", e);
+                state.miner.record_failure(format!("{:#}", e));
+                return;
+            }
+        }
+        tokio::time::sleep(MINER_PAUSE).await;
+    }
+}
+
+/// Mine one block; false if it went stale before it could be submitted
+async fn mine_one(
+    state: &AppState,
+    address: &PublicKey,
+    threads: Option<usize>,
+) -> anyhow::Result<bool> {
+    let template = state.node.write().await.block_template(address)?;
+    let height = template.height;
+    let block = tokio::task::spawn_blocking(move || template.mine(threads)).await?;
+    state.node.write().await.submit_mined_block(height, &block)
+}
+
+/// Request to start the miner
+#[derive(Debug, Deserialize)]
+pub struct StartMiningRequest {
+    /// Hex public key receiving block rewards
+    pub address: String,
+    #[serde(default)]
+    pub threads: Option<usize>,
+}
+
+/// New miner settings; omitted fields return to their defaults
+#[derive(Debug, Deserialize)]
+pub struct MiningSettingsRequest {
+    #[serde(default)]
+    pub threads: Option<usize>,
+}
+
+/// Peer to dial
+#[derive(Debug, Deserialize)]
+pub struct DialPeerRequest {
+    /// libp2p multiaddress, e.g. `/ip4/203.0.113.7/tcp/9000`
+    pub address: String,
+}
+
+/// Peer to ban
+#[derive(Debug, Deserialize)]
+pub struct BanPeerRequest {
+    pub peer_id: String,
+    /// Ban length; defaults to the reputation system's ban duration
+    #[serde(default)]
+    pub duration_secs: Option<u64>,
+}
+
+/// Pending transactions dropped by a flush
+#[derive(Debug, Serialize)]
+pub struct FlushMempoolResponse {
+    pub removed: usize,
+}
+
+/// Replacement for a rotated API key
+#[derive(Debug, Serialize)]
+pub struct RotateKeyResponse {
+    /// ID of the new key
+    pub id: String,
+    /// New raw key; shown only once
+    pub key: String,
+    /// ID of the key it replaces, now revoked
+    pub replaced: String,
+}
+
+/// Refuse keys without the `admin` permission
+/// رفض المفاتيح التي لا تملك صلاحية الإدارة
+pub async fn require_admin(
+    Extension(key): Extension<ApiKey>,
+    request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<ErrorResponse>)> {
+    if !key.permissions.contains(&Permission::Admin) {
+        return Err(error(StatusCode::FORBIDDEN, "Admin API key required"));
+    }
+    Ok(next.run(request).await)
+}
+
+/// Check the requested mining thread count
+fn validate_threads(threads: Option<usize>) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if threads.is_some_and(|threads| threads == 0 || threads > MAX_MINER_THREADS) {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            format!("threads must be between 1 and {}", MAX_MINER_THREADS),
+        ));
+    }
+    Ok(())
+}
+
+/// Send a command to the attached P2P node and wait for its reply
+async fn network_request<T>(
+    state: &AppState,
+    command: impl FnOnce(oneshot::Sender<anyhow::Result<T>>) -> NetworkCommand,
+) -> Result<T, (StatusCode, Json<ErrorResponse>)> {
+    let network = state.network.as_ref().ok_or_else(|| {
+        error(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No P2P node is attached to this API",
+        )
+    })?;
+    let stopped = || error(StatusCode::SERVICE_UNAVAILABLE, "P2P node has stopped");
+
+    let (reply, response) = oneshot::channel();
+    network.send(command(reply)).map_err(|_| stopped())?;
+    response
+        .await
+        .map_err(|_| stopped())?
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+/// GET /api/v1/admin/mining - miner status
+/// حالة المعدّن
+pub async fn mining_status(State(state): State<Arc<AppState>>) -> Json<MinerStatus> {
+    Json(state.miner.status())
+}
+
+/// POST /api/v1/admin/mining/start - start the miner
+/// بدء التعدين
+pub async fn start_mining(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<StartMiningRequest>,
+) -> ApiResult<MinerStatus> {
+    let address = PublicKey::from_hex(&request.address)
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "Invalid address format"))?;
+    validate_threads(request.threads)?;
+    if state.miner.status().running {
+        return Err(error(StatusCode::CONFLICT, "Miner is already running"));
+    }
+
+    state.miner.configure(request.threads);
+    if !state.miner.start(state.clone(), address) {
+        return Err(error(StatusCode::CONFLICT, "Miner is already running"));
+    }
+    Ok(Json(state.miner.status()))
+}
+
+/// POST /api/v1/admin/mining/stop - stop the miner
+/// إيقاف التعدين
+pub async fn stop_mining(State(state): State<Arc<AppState>>) -> Json<MinerStatus> {
+    state.miner.stop();
+    Json(state.miner.status())
+}
+
+/// POST /api/v1/admin/mining/settings - set mining threads
+/// ضبط عدد خيوط التعدين
+pub async fn set_mining_settings(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MiningSettingsRequest>,
+) -> ApiResult<MinerStatus> {
+    validate_threads(request.threads)?;
+    state.miner.configure(request.threads);
+    Ok(Json(state.miner.status()))
+}
+
+/// POST /api/v1/admin/peers/dial - connect to a peer
+/// الاتصال بنظير
+pub async fn dial_peer(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<DialPeerRequest>,
+) -> ApiResult<serde_json::Value> {
+    let addr: Multiaddr = request
+        .address
+        .parse()
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "Invalid multiaddress"))?;
+    network_request(&state, |reply| NetworkCommand::Dial {
+        addr: addr.clone(),
+        reply,
+    })
+    .await?;
+    Ok(Json(serde_json::json!({ "dialed": addr.to_string() })))
+}
+
+/// POST /api/v1/admin/peers/ban - ban and disconnect a peer
+/// حظر نظير وقطع الاتصال به
+pub async fn ban_peer(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BanPeerRequest>,
+) -> ApiResult<serde_json::Value> {
+    let peer_id: PeerId = request
+        .peer_id
+        .parse()
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "Invalid peer ID"))?;
+    let duration_secs = request.duration_secs.unwrap_or(BAN_DURATION_SECS);
+    network_request(&state, |reply| NetworkCommand::BanPeer {
+        peer_id,
+        duration: Duration::from_secs(duration_secs),
+        reply,
+    })
+    .await?;
+    Ok(Json(serde_json::json!({
+        "banned": peer_id.to_string(),
+        "duration_secs": duration_secs,
+    })))
+}
+
+/// POST /api/v1/admin/mempool/flush - drop all pending transactions
+/// إفراغ مجمع المعاملات
+pub async fn flush_mempool(State(state): State<Arc<AppState>>) -> Json<FlushMempoolResponse> {
+    let removed = state.node.write().await.clear_pending_transactions();
+    tracing::info!("Flushed {} pending transactions", removed);
+    Json(FlushMempoolResponse { removed })
+}
+
+/// POST /api/v1/admin/keys/{id}/rotate - replace an API key
+/// تدوير مفتاح API
+pub async fn rotate_api_key(
+    State(state): State<Arc<AppState>>,
+    Path(key_id): Path<String>,
+) -> ApiResult<RotateKeyResponse> {
+    let (id, key) = state
+        .api_key_manager
+        .rotate_key(&key_id)
+        .await
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "No active API key with that ID"))?;
+    tracing::info!("Rotated API key {} to {}", key_id, id);
+    Ok(Json(RotateKeyResponse {
+        id,
+        key,
+        replaced: key_id,
+    }))
+}
+
+/// Router for the admin listener: admin keys only, rate limited
+/// موجّه نقاط الإدارة
+pub fn admin_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/admin/mining", get(mining_status))
+        .route("/api/v1/admin/mining/start", post(start_mining))
+        .route("/api/v1/admin/mining/stop", post(stop_mining))
+        .route("/api/v1/admin/mining/settings", post(set_mining_settings))
+        .route("/api/v1/admin/peers/dial", post(dial_peer))
+        .route("/api/v1/admin/peers/ban", post(ban_peer))
+        .route("/api/v1/admin/mempool/flush", post(flush_mempool))
+        .route("/api/v1/admin/keys/{id}/rotate", post(rotate_api_key))
+        .with_state(state.clone())
+        .merge(
+            Router::new()
+                .route("/api/v1/admin/passkeys", get(webauthn::list_passkeys))
+                .route(
+                    "/api/v1/admin/passkeys/{id}",
+                    delete(webauthn::remove_passkey),
+                )
+                .route(
+                    "/api/v1/admin/passkeys/register/start",
+                    post(webauthn::start_registration),
+                )
+                .route(
+                    "/api/v1/admin/passkeys/register/finish",
+                    post(webauthn::finish_registration),
+                )
+                .with_state(state.passkeys.clone()),
+        )
+        .layer(middleware::from_fn(require_admin))
+        .layer(middleware::from_fn_with_state(
+            AdminAuth {
+                api_keys: state.api_key_manager.clone(),
+                passkeys: state.passkeys.clone(),
+            },
+            webauthn::admin_auth_middleware,
+        ))
+        // Logging in is how an operator gets a session, so these two routes
+        // sit outside the admin check
+        .merge(
+            Router::new()
+                .route(
+                    "/api/v1/admin/passkeys/login/start",
+                    post(webauthn::start_authentication),
+                )
+                .route(
+                    "/api/v1/admin/passkeys/login/finish",
+                    post(webauthn::finish_authentication),
+                )
+                .with_state(state.passkeys.clone()),
+        )
+        .layer(middleware::from_fn_with_state(
+            state.rate_limiter.clone(),
+            rate_limit::rate_limit_middleware,
+        ))
+}
+
+/// Whether the admin listener is bound to this host only
+pub fn is_loopback(addr: &SocketAddr) -> bool {
+    addr.ip().is_loopback()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{
+        webauthn::{PasskeyManager, WebAuthnConfig},
+        ApiKeyManager,
+    };
+    use axum::body::Body;
+    use tower::ServiceExt;
+
+    async fn status_with_key(manager: Arc<ApiKeyManager>, key: Option<&str>) -> StatusCode {
+        let router = Router::new()
+            .route("/admin", get(|| async { "ok" }))
+            .layer(middleware::from_fn(require_admin))
+            .layer(middleware::from_fn_with_state(
+                AdminAuth {
+                    api_keys: manager,
+                    passkeys: Arc::new(PasskeyManager::new(WebAuthnConfig::default())),
+                },
+                webauthn::admin_auth_middleware,
+            ));
+        let mut request = Request::builder().uri("/admin");
+        if let Some(key) = key {
+            request = request.header("Authorization", format!("Bearer {}", key));
+        }
+        router
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_admin_routes_require_admin_key() {
+        let manager = Arc::new(ApiKeyManager::new());
+        let (_, admin) = manager
+            .generate_key("admin".to_string(), vec![Permission::Admin], None)
+            .await;
+        let (_, reader) = manager
+            .generate_key("reader".to_string(), vec![Permission::ReadBalance], None)
+            .await;
+
+        assert_eq!(
+            status_with_key(manager.clone(), None).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status_with_key(manager.clone(), Some(&reader)).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status_with_key(manager, Some(&admin)).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_default_bind_is_loopback() {
+        assert!(is_loopback(&DEFAULT_ADMIN_BIND.parse().unwrap()));
+        assert!(!is_loopback(&"0.0.0.0:8081".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_miner_settings_and_stop() {
+        let miner = Miner::default();
+        assert!(!miner.stop());
+
+        miner.configure(Some(4));
+        let status = miner.status();
+        assert!(!status.running);
+        assert_eq!(status.threads, Some(4));
+
+        miner.record_failure("no tip".to_string());
+        assert_eq!(miner.status().last_error.as_deref(), Some("no tip"));
+    }
+}
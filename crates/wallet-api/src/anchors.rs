@@ -0,0 +1,192 @@
+//! Signed chain-state anchors
+//! مراسي حالة السلسلة الموقعة
+//!
+//! With `--anchor-interval N` the node signs a digest of the tip hash, state
+//! root and total supply at every N-th block and writes it to
+//! `<data-dir>/anchors/<height>.json`. `GET /api/v1/anchors` lists the most
+//! recent anchors and `GET /api/v1/anchors/{height}` (or `latest`) returns
+//! one, so a third party can fetch the anchors of two independent nodes at
+//! the same height and compare their digests. Both are also reachable as the
+//! JSON-RPC methods `listanchors` and `getanchor`.
+//!
+//! Anchors are served from the data directory whether or not this process
+//! publishes them, so a P2P node writing into the same directory is covered.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use opensyria_core::anchor::{anchor_heights, load_anchor, SignedAnchor};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{models::ErrorResponse, AppState};
+
+/// Anchors listed when no limit is given
+pub const DEFAULT_ANCHOR_LIMIT: usize = 20;
+
+/// Most anchors listed by one request
+pub const MAX_ANCHOR_LIMIT: usize = 500;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListAnchorsQuery {
+    pub limit: Option<usize>,
+}
+
+/// An anchor with the result of checking its digest and signature
+#[derive(Debug, Serialize)]
+pub struct AnchorResponse {
+    #[serde(flatten)]
+    pub anchor: SignedAnchor,
+    pub verified: bool,
+}
+
+impl From<SignedAnchor> for AnchorResponse {
+    fn from(anchor: SignedAnchor) -> Self {
+        let verified = anchor.verify().is_ok();
+        Self { anchor, verified }
+    }
+}
+
+/// Most recent anchors, newest first
+#[derive(Debug, Serialize)]
+pub struct AnchorList {
+    /// Blocks between anchors, if this process publishes them
+    pub interval: Option<u64>,
+    /// Key signing this process's anchors (hex)
+    pub signer: Option<String>,
+    /// Heights with an anchor
+    pub total: usize,
+    pub anchors: Vec<AnchorResponse>,
+}
+
+/// GET /api/v1/anchors - most recent chain-state anchors
+/// أحدث مراسي حالة السلسلة
+pub async fn list_anchors(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ListAnchorsQuery>,
+) -> ApiResult<AnchorList> {
+    let node = state.node.read().await;
+    let publisher = node.get_state().anchor_publisher().cloned();
+    let dir = node.anchor_dir();
+    drop(node);
+
+    let heights = anchor_heights(&dir)
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_ANCHOR_LIMIT)
+        .min(MAX_ANCHOR_LIMIT);
+
+    let mut anchors = Vec::new();
+    for &height in heights.iter().rev().take(limit) {
+        match load_anchor(&dir, height) {
+            Ok(Some(anchor)) => anchors.push(anchor.into()),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Skipping unreadable anchor {}: {}", height, e),
+        }
+    }
+
+    Ok(Json(AnchorList {
+        interval: publisher.as_ref().map(|p| p.interval()),
+        signer: publisher.as_ref().map(|p| p.signer().to_hex()),
+        total: heights.len(),
+        anchors,
+    }))
+}
+
+/// GET /api/v1/anchors/{height} - the anchor at a height, or `latest`
+/// مرساة حالة السلسلة عند ارتفاع محدد
+pub async fn get_anchor(
+    State(state): State<Arc<AppState>>,
+    Path(height): Path<String>,
+) -> ApiResult<AnchorResponse> {
+    let dir = state.node.read().await.anchor_dir();
+
+    let height = if height == "latest" {
+        anchor_heights(&dir)
+            .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .last()
+            .copied()
+            .ok_or_else(|| error(StatusCode::NOT_FOUND, "No anchors published"))?
+    } else {
+        height
+            .parse::<u64>()
+            .map_err(|_| error(StatusCode::BAD_REQUEST, "Height must be a number or latest"))?
+    };
+
+    match load_anchor(&dir, height) {
+        Ok(Some(anchor)) => Ok(Json(anchor.into())),
+        Ok(None) => Err(error(
+            StatusCode::NOT_FOUND,
+            format!("No anchor at height {}", height),
+        )),
+        Err(e) => Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use opensyria_core::anchor::ChainAnchor;
+    use opensyria_node_cli::Node;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_anchors_served_from_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 16).unwrap();
+        let publisher = node.enable_anchoring(5).unwrap();
+        publisher
+            .publish(ChainAnchor::new(5, &[1; 32], &[2; 32], 100))
+            .unwrap();
+        let mut tampered = publisher
+            .publish(ChainAnchor::new(10, &[3; 32], &[4; 32], 200))
+            .unwrap();
+        tampered.anchor.total_supply += 1;
+        std::fs::write(
+            publisher.dir().join("10.json"),
+            serde_json::to_vec(&tampered).unwrap(),
+        )
+        .unwrap();
+
+        let app = crate::api::create_router(Arc::new(AppState::new(node)));
+        let get = |uri: &str| {
+            app.clone()
+                .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        };
+
+        let response = get("/api/v1/anchors").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let list: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(list["interval"], 5);
+        assert_eq!(list["total"], 2);
+        assert_eq!(list["anchors"][0]["height"], 10);
+        assert_eq!(list["anchors"][0]["verified"], false);
+        assert_eq!(list["anchors"][1]["verified"], true);
+
+        let response = get("/api/v1/anchors/5").await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let anchor: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(anchor["total_supply"], 100);
+        assert_eq!(anchor["signer"], publisher.signer().to_hex());
+
+        let response = get("/api/v1/anchors/latest").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = get("/api/v1/anchors/15").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
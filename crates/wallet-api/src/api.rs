@@ -1,46 +1,184 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
     middleware,
     response::{IntoResponse, Json},
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Router,
 };
 use std::sync::Arc;
 
 use opensyria_core::{
-    crypto::PublicKey,
-    transaction::Transaction,
+    confirmations::confirmations, crypto::PublicKey, error_code, transaction::Transaction,
+    CodedError, ConfirmationTargets, ErrorCode,
 };
+use opensyria_http::{FieldsLayer, LocaleLayer};
+use opensyria_mempool::{ConformanceError, ConformanceReport, ConformanceSuite, MempoolError};
 
-use crate::{auth, models::*, rate_limit, AppState};
+use crate::{
+    anchors, auth, batch, digest, events, fee_bump, latency, load_shed, message, models::*,
+    node_pool, oracle, payment, proof, rate_limit, regtest, simulate, stealth, telemetry, wallets,
+    watchtower, AppState,
+};
 
 /// Create API router with authentication and rate limiting
 pub fn create_router(state: Arc<AppState>) -> Router {
     // Create protected routes that require authentication
     let protected_routes = Router::new()
         .route("/api/v1/transaction/submit", post(submit_transaction))
+        .route("/api/v1/transaction/raw", post(send_raw_transaction))
         .route("/api/v1/mempool/status", get(get_mempool_status))
+        .route(
+            "/api/v1/mempool/conformance",
+            get(run_builtin_conformance).post(run_conformance),
+        )
+        .route("/api/v1/wallets", get(wallets::list_wallets))
+        .route(
+            "/api/v1/wallets/{name}/unlock",
+            post(wallets::unlock_wallet),
+        )
+        .route("/api/v1/wallets/{name}/lock", post(wallets::lock_wallet))
+        .route("/api/v1/wallets/{name}/send", post(wallets::wallet_send))
+        .route(
+            "/api/v1/wallets/{name}/history",
+            get(wallets::wallet_history),
+        )
+        .route(
+            "/api/v1/wallets/{name}/fee-bump",
+            get(fee_bump::get_fee_bump)
+                .put(fee_bump::set_fee_bump)
+                .delete(fee_bump::delete_fee_bump),
+        )
+        .route(
+            "/api/v1/wallets/{name}/watchtower",
+            get(watchtower::list_watches).post(watchtower::register_watch),
+        )
+        .route(
+            "/api/v1/wallets/{name}/watchtower/receipts",
+            get(watchtower::watch_receipts),
+        )
+        .route(
+            "/api/v1/wallets/{name}/watchtower/{id}",
+            delete(watchtower::delete_watch),
+        )
+        .route("/api/v1/metrics/latency", get(latency::endpoint_latency))
+        .route("/metrics", get(latency::prometheus_metrics))
+        .route("/api/v1/nodes", get(node_pool::node_pool_status))
+        .route(
+            "/api/v1/digests",
+            get(digest::list_digests).post(digest::create_digest),
+        )
+        .route("/api/v1/digests/{id}", delete(digest::delete_digest))
+        .route(
+            "/api/v1/digests/{id}/schedule",
+            put(digest::reschedule_digest),
+        )
+        .route("/api/v1/digests/{id}/preview", get(digest::preview_digest))
+        .route(
+            "/api/v1/regtest/generate",
+            post(regtest::generate_to_address),
+        )
+        .route("/api/v1/regtest/mocktime", post(regtest::set_mock_time))
+        .route(
+            "/api/v1/mining/validate-template",
+            post(simulate::validate_block_template),
+        )
+        .route(
+            "/api/v1/telemetry/propagation",
+            post(telemetry::submit_report),
+        )
         .layer(middleware::from_fn_with_state(
             state.api_key_manager.clone(),
             auth::auth_middleware,
         ));
 
-    // Public routes (read-only)
-    let public_routes = Router::new()
+    // Public chain reads, served by a backend node when a pool is configured
+    let pooled_routes = Router::new()
         .route("/api/v1/account/{address}/balance", get(get_balance))
+        .route("/api/v1/account/{address}/assets", get(get_asset_balances))
+        .route(
+            "/api/v1/account/{address}/history",
+            get(get_account_history),
+        )
         .route("/api/v1/blockchain/info", get(get_blockchain_info))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            node_pool::pooled_read_middleware,
+        ));
+
+    // Public routes (read-only)
+    let public_routes = Router::new()
+        .merge(pooled_routes)
+        .route("/api/v1/transaction/decode", post(decode_raw_transaction))
+        .route("/api/v1/tx/simulate", post(simulate::simulate_transaction))
+        .route("/api/v1/message/verify", post(message::verify_message))
+        .route(
+            "/api/v1/payment-request",
+            post(payment::create_payment_request),
+        )
+        .route(
+            "/api/v1/payment-request/parse",
+            post(payment::parse_payment_request),
+        )
+        .route("/api/v1/events", get(events::address_events))
+        .route("/api/v1/node/info", get(get_node_info))
+        .route("/api/v1/errors", get(list_error_codes))
+        .route("/api/v1/rates", get(oracle::get_rates))
+        .route(
+            "/api/v1/stealth/announcements",
+            get(stealth::get_announcements),
+        )
+        .route(
+            "/api/v1/proof/balance/{address}",
+            get(proof::balance_proof).route_layer(middleware::from_fn_with_state(
+                Arc::new(rate_limit::RateLimiter::with_config(
+                    proof::rate_limit_config(),
+                )),
+                rate_limit::rate_limit_middleware,
+            )),
+        )
+        .route("/api/v1/anchors", get(anchors::list_anchors))
+        .route("/api/v1/anchors/{height}", get(anchors::get_anchor))
+        .route(
+            "/api/v1/telemetry/propagation",
+            get(telemetry::network_latency),
+        )
         .route("/health", get(health_check));
 
-    // Combine routes and apply rate limiting to all
-    Router::new()
+    // Combine routes and apply rate limiting to all. Load shedding runs
+    // before rate limiting so an overloaded node refuses requests as cheaply
+    // as possible; latency is recorded outermost so rejected (401/429/503)
+    // requests are timed as well. `?fields=` trims each GET response, then
+    // `?locale=` adds display strings to what is left
+    let api = Router::new()
         .merge(protected_routes)
         .merge(public_routes)
+        .layer(FieldsLayer)
+        .layer(LocaleLayer)
         .layer(middleware::from_fn_with_state(
             state.rate_limiter.clone(),
             rate_limit::rate_limit_middleware,
         ))
-        .with_state(state)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            load_shed::load_shed_middleware,
+        ))
+        .layer(latency::latency_layer())
+        .with_state(state.clone());
+
+    // Batch routes dispatch each item back through `api`, so every item is
+    // authenticated, rate limited and shed like a standalone request
+    let batch_routes = Router::new()
+        .route("/api/v1/batch", post(batch::rest_batch))
+        .route("/api/v1/rpc", post(batch::rpc))
+        .layer(middleware::from_fn_with_state(
+            state.rate_limiter.clone(),
+            rate_limit::rate_limit_middleware,
+        ))
+        .layer(latency::latency_layer())
+        .with_state(api.clone());
+
+    api.merge(batch_routes)
 }
 
 /// Health check endpoint
@@ -51,6 +189,24 @@ async fn health_check() -> impl IntoResponse {
     }))
 }
 
+/// Build provenance and chain parameters of the node behind this API
+async fn get_node_info(State(state): State<Arc<AppState>>) -> Json<NodeInfoResponse> {
+    let node = state.node.read().await;
+    let params = node.get_blockchain().chain_params();
+
+    Json(NodeInfoResponse {
+        build: opensyria_node_cli::build_info::BuildInfo::current(),
+        network: params.network.clone(),
+        chain_id: params.chain_id,
+        chain_params_hash: hex::encode(params.params_hash()),
+    })
+}
+
+/// Every error code responses can carry
+async fn list_error_codes() -> Json<&'static [ErrorCode]> {
+    Json(error_code::REGISTRY)
+}
+
 /// Submit a signed transaction
 async fn submit_transaction(
     State(state): State<Arc<AppState>>,
@@ -60,9 +216,10 @@ async fn submit_transaction(
     let from = PublicKey::from_hex(&request.from).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid sender address".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid sender address",
+            )),
         )
     })?;
 
@@ -70,9 +227,10 @@ async fn submit_transaction(
     let to = PublicKey::from_hex(&request.to).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid recipient address".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid recipient address",
+            )),
         )
     })?;
 
@@ -80,9 +238,10 @@ async fn submit_transaction(
     let signature_bytes = hex::decode(&request.signature).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid signature format".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid signature format",
+            )),
         )
     })?;
 
@@ -91,18 +250,21 @@ async fn submit_transaction(
     let state_storage = node.get_state();
     let _balance = state_storage.get_balance(&from).unwrap_or(0);
     let nonce = state_storage.get_nonce(&from).unwrap_or(0);
+    let chain_id = node.get_blockchain().chain_params().chain_id;
 
     // Create transaction with signature
-    let transaction = Transaction::new(from, to, request.amount, request.fee, nonce)
-        .with_signature(signature_bytes);
+    let transaction =
+        Transaction::new_with_chain_id(chain_id, from, to, request.amount, request.fee, nonce)
+            .with_signature(signature_bytes);
 
     // Verify signature
     if transaction.verify().is_err() {
         return Err((
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid transaction signature".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                error_code::INVALID_SIGNATURE,
+                "Invalid transaction signature",
+            )),
         ));
     }
 
@@ -112,20 +274,112 @@ async fn submit_transaction(
     // Add to mempool
     drop(node); // Release read lock
     let mut node = state.node.write().await;
+    let added = node.add_transaction_to_mempool(transaction.clone());
+    drop(node);
 
-    match node.add_transaction_to_mempool(transaction) {
+    match added {
         Ok(_) => Ok(Json(TransactionResponse {
             success: true,
             tx_hash: Some(tx_hash),
             message: "Transaction submitted successfully".to_string(),
+            relayed: node_pool::relay_to_pool(&state, &transaction),
         })),
-        Err(e) => Err((
+        Err(e) => Err(submit_error(e)),
+    }
+}
+
+/// Decode a raw transaction without submitting it
+async fn decode_raw_transaction(
+    Json(request): Json<RawTransactionRequest>,
+) -> Result<Json<DecodedTransaction>, (StatusCode, Json<ErrorResponse>)> {
+    let tx = parse_raw_transaction(&request.hex)?;
+
+    Ok(Json(DecodedTransaction {
+        hash: hex::encode(tx.hash()),
+        chain_id: tx.chain_id,
+        from: tx.from.to_hex(),
+        to: tx.to.to_hex(),
+        amount: tx.amount,
+        fee: tx.fee,
+        nonce: tx.nonce,
+        data: tx.data.as_ref().map(hex::encode),
+        signature: hex::encode(&tx.signature),
+        signature_valid: tx.verify().is_ok(),
+        weight: tx.weight(),
+        fee_rate: tx.fee_rate(),
+    }))
+}
+
+/// Submit a signed raw transaction exactly as encoded
+async fn send_raw_transaction(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RawTransactionRequest>,
+) -> Result<Json<TransactionResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let transaction = parse_raw_transaction(&request.hex)?;
+    if transaction.verify().is_err() {
+        return Err((
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Failed to submit transaction: {}", e),
-            }),
-        )),
+            Json(ErrorResponse::new(
+                error_code::INVALID_SIGNATURE,
+                "Invalid transaction signature",
+            )),
+        ));
     }
+
+    let tx_hash = hex::encode(transaction.hash());
+    let mut node = state.node.write().await;
+    let added = if request.replace {
+        node.replace_pending_transaction(transaction.clone())
+            .map(drop)
+    } else {
+        node.add_transaction_to_mempool(transaction.clone())
+    };
+    drop(node);
+    added.map_err(submit_error)?;
+
+    Ok(Json(TransactionResponse {
+        success: true,
+        tx_hash: Some(tx_hash),
+        message: "Transaction submitted successfully".to_string(),
+        relayed: node_pool::relay_onward(
+            &state,
+            &transaction,
+            request.replace,
+            node_pool::relay_hops(&headers),
+        ),
+    }))
+}
+
+/// Rejection of a submitted transaction, with the mempool's code
+///
+/// Everything the node checks is a [`MempoolError`]; the only other refusal
+/// is safe mode.
+fn submit_error(e: anyhow::Error) -> (StatusCode, Json<ErrorResponse>) {
+    let code = e
+        .downcast_ref::<MempoolError>()
+        .map_or(error_code::UNAVAILABLE, CodedError::error_code);
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::new(
+            code,
+            format!("Failed to submit transaction: {}", e),
+        )),
+    )
+}
+
+pub(crate) fn parse_raw_transaction(
+    raw: &str,
+) -> Result<Transaction, (StatusCode, Json<ErrorResponse>)> {
+    Transaction::from_raw_hex(raw).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                e.to_string(),
+            )),
+        )
+    })
 }
 
 /// Get account balance
@@ -137,9 +391,10 @@ async fn get_balance(
     let public_key = PublicKey::from_hex(&address).map_err(|_| {
         (
             StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid address format".to_string(),
-            }),
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid address format",
+            )),
         )
     })?;
 
@@ -148,14 +403,162 @@ async fn get_balance(
     let state_storage = node.get_state();
     let balance = state_storage.get_balance(&public_key).unwrap_or(0);
     let nonce = state_storage.get_nonce(&public_key).unwrap_or(0);
+    let (pending_incoming, pending_outgoing) =
+        pending_amounts(&node.get_pending_transactions(), &public_key);
 
     Ok(Json(BalanceResponse {
         address,
         balance,
         nonce,
+        pending_incoming,
+        pending_outgoing,
     }))
 }
 
+/// Get native asset balances of an account
+async fn get_asset_balances(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(address): axum::extract::Path<String>,
+) -> Result<Json<AssetBalancesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let public_key = PublicKey::from_hex(&address).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid address format",
+            )),
+        )
+    })?;
+    let storage_error = |e: opensyria_storage::StorageError| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to load assets: {}", e),
+            )),
+        )
+    };
+
+    let node = state.node.read().await;
+    let state_storage = node.get_state();
+    let mut assets = Vec::new();
+    for (id, balance) in state_storage
+        .get_asset_balances(&public_key)
+        .map_err(storage_error)?
+    {
+        if let Some(info) = state_storage.get_asset(&id).map_err(storage_error)? {
+            assets.push(AssetBalanceEntry {
+                asset_id: id.to_hex(),
+                symbol: info.symbol,
+                name: info.name,
+                decimals: info.decimals,
+                balance,
+            });
+        }
+    }
+
+    Ok(Json(AssetBalancesResponse { address, assets }))
+}
+
+/// Default and maximum number of entries returned by the history endpoint
+const DEFAULT_HISTORY_LIMIT: usize = 50;
+const MAX_HISTORY_LIMIT: usize = 500;
+
+/// Get confirmed and pending transactions of an account
+async fn get_account_history(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(address): axum::extract::Path<String>,
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<AccountHistoryResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let public_key = PublicKey::from_hex(&address).map_err(|_| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Invalid address format",
+            )),
+        )
+    })?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_HISTORY_LIMIT)
+        .min(MAX_HISTORY_LIMIT);
+
+    let node = state.node.read().await;
+    let blockchain = node.get_blockchain();
+    let tip_height = blockchain.get_chain_height().unwrap_or(0);
+    let targets = ConfirmationTargets::from_params(blockchain.chain_params());
+
+    // Pending transactions are the newest, so they come first
+    let mut transactions: Vec<_> = node
+        .get_pending_transactions()
+        .iter()
+        .filter(|tx| tx.from == public_key || tx.to == public_key)
+        .map(|tx| transaction_details(tx, None, tip_height, &targets))
+        .collect();
+
+    let hashes = blockchain
+        .get_address_transactions(&public_key.0)
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    e.error_code(),
+                    format!("Failed to load history: {}", e),
+                )),
+            )
+        })?;
+    for hash in hashes.iter().rev() {
+        if transactions.len() >= limit {
+            break;
+        }
+        if let Ok(Some((tx, height))) = blockchain.get_transaction_by_hash(hash) {
+            transactions.push(transaction_details(&tx, Some(height), tip_height, &targets));
+        }
+    }
+    transactions.truncate(limit);
+
+    Ok(Json(AccountHistoryResponse {
+        address,
+        transactions,
+    }))
+}
+
+/// Mempool amounts (incoming, outgoing including fees) touching `address`
+fn pending_amounts(pending: &[Transaction], address: &PublicKey) -> (u64, u64) {
+    let (mut incoming, mut outgoing) = (0u64, 0u64);
+    for tx in pending {
+        if tx.to == *address {
+            incoming = incoming.saturating_add(tx.amount);
+        }
+        if tx.from == *address {
+            outgoing = outgoing.saturating_add(tx.amount.saturating_add(tx.fee));
+        }
+    }
+    (incoming, outgoing)
+}
+
+pub(crate) fn transaction_details(
+    tx: &Transaction,
+    block_height: Option<u64>,
+    tip_height: u64,
+    targets: &ConfirmationTargets,
+) -> TransactionDetails {
+    TransactionDetails {
+        hash: hex::encode(tx.hash()),
+        from: tx.from.to_hex(),
+        to: tx.to.to_hex(),
+        amount: tx.amount,
+        fee: tx.fee,
+        nonce: tx.nonce,
+        block_height,
+        confirmed: block_height.is_some(),
+        confirmations: block_height.map_or(0, |height| confirmations(height, tip_height)),
+        status: targets.status(tx, block_height, tip_height),
+        data: tx.data.as_ref().map(hex::encode),
+    }
+}
+
 /// Get blockchain info
 async fn get_blockchain_info(
     State(state): State<Arc<AppState>>,
@@ -166,9 +569,10 @@ async fn get_blockchain_info(
     let chain_height = blockchain.get_chain_height().map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Failed to get chain height: {}", e),
-            }),
+            Json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get chain height: {}", e),
+            )),
         )
     })?;
 
@@ -177,9 +581,10 @@ async fn get_blockchain_info(
         .map_err(|e| {
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: format!("Failed to get chain tip: {}", e),
-                }),
+                Json(ErrorResponse::new(
+                    e.error_code(),
+                    format!("Failed to get chain tip: {}", e),
+                )),
             )
         })?
         .unwrap_or([0u8; 32]);
@@ -187,9 +592,10 @@ async fn get_blockchain_info(
     let latest_block = blockchain.get_block_by_height(chain_height).map_err(|e| {
         (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse {
-                error: format!("Failed to get latest block: {}", e),
-            }),
+            Json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to get latest block: {}", e),
+            )),
         )
     })?;
 
@@ -227,3 +633,69 @@ async fn get_mempool_status(
         total_fees,
     }))
 }
+
+/// GET /api/v1/mempool/conformance - run the shipped policy vectors
+async fn run_builtin_conformance(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<ConformanceReport>, (StatusCode, Json<ErrorResponse>)> {
+    conformance_report(&state, &ConformanceSuite::builtin()).await
+}
+
+/// POST /api/v1/mempool/conformance - run caller-supplied policy vectors
+async fn run_conformance(
+    State(state): State<Arc<AppState>>,
+    Json(suite): Json<ConformanceSuite>,
+) -> Result<Json<ConformanceReport>, (StatusCode, Json<ErrorResponse>)> {
+    conformance_report(&state, &suite).await
+}
+
+/// Run a suite against the node's pool policy
+async fn conformance_report(
+    state: &AppState,
+    suite: &ConformanceSuite,
+) -> Result<Json<ConformanceReport>, (StatusCode, Json<ErrorResponse>)> {
+    state.conformance.run(suite).await.map(Json).map_err(|e| {
+        let status = match e {
+            ConformanceError::InvalidSuite(_) => StatusCode::BAD_REQUEST,
+            ConformanceError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (
+            status,
+            Json(ErrorResponse::from_status(status, e.to_string())),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_pending_amounts() {
+        let me = KeyPair::generate().public_key();
+        let other = KeyPair::generate().public_key();
+        let pending = vec![
+            Transaction::new(other, me, 500, 10, 0),
+            Transaction::new(me, other, 200, 5, 0),
+            Transaction::new(me, other, 100, 5, 1),
+            Transaction::new(other, other, 999, 1, 1),
+        ];
+
+        assert_eq!(pending_amounts(&pending, &me), (500, 310));
+        assert_eq!(pending_amounts(&[], &me), (0, 0));
+    }
+
+    #[test]
+    fn test_submit_error_codes() {
+        let rejected = MempoolError::FeeTooLow { min: 10, got: 1 };
+        let (status, Json(body)) = submit_error(rejected.into());
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, error_code::FEE_TOO_LOW);
+        assert!(body.error.contains("fee too low"));
+
+        let (_, Json(body)) = submit_error(anyhow::anyhow!("Node is in safe mode"));
+        assert_eq!(body.code.code, "unavailable");
+        assert!(body.code.retryable);
+    }
+}
@@ -11,11 +11,15 @@ use axum::{
     middleware::Next,
     response::{Json, Response},
 };
+use opensyria_core::error_code::{self, ErrorCode};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+pub mod webauthn;
+
 /// API Key structure
 /// بنية مفتاح API
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,6 +38,23 @@ pub struct ApiKey {
     pub expires_at: Option<u64>,
     /// Whether key is currently active
     pub active: bool,
+    /// Wallets this key may operate on (ignored for admin keys)
+    #[serde(default)]
+    pub wallets: Vec<String>,
+}
+
+impl ApiKey {
+    /// Whether the key's expiration time has passed
+    fn is_expired(&self) -> bool {
+        let now = opensyria_core::clock::system_time();
+        self.expires_at.is_some_and(|expires_at| now > expires_at)
+    }
+
+    /// Whether this key may act on the named wallet
+    /// التحقق من صلاحية المفتاح للوصول إلى المحفظة
+    pub fn can_access_wallet(&self, wallet: &str) -> bool {
+        self.permissions.contains(&Permission::Admin) || self.wallets.iter().any(|w| w == wallet)
+    }
 }
 
 /// API permissions
@@ -49,6 +70,8 @@ pub enum Permission {
     ReadBlockchain,
     /// Access mempool
     ReadMempool,
+    /// Send propagation telemetry reports to the collector
+    SubmitTelemetry,
     /// Full admin access
     Admin,
 }
@@ -58,6 +81,8 @@ pub enum Permission {
 #[derive(Clone)]
 pub struct ApiKeyManager {
     keys: Arc<RwLock<HashMap<String, ApiKey>>>,
+    /// SHA-256 of keys that passed Argon2 verification, to their IDs
+    verified: Arc<RwLock<HashMap<[u8; 32], String>>>,
 }
 
 impl ApiKeyManager {
@@ -65,6 +90,7 @@ impl ApiKeyManager {
     pub fn new() -> Self {
         Self {
             keys: Arc::new(RwLock::new(HashMap::new())),
+            verified: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
@@ -75,12 +101,24 @@ impl ApiKeyManager {
         name: String,
         permissions: Vec<Permission>,
         expires_at: Option<u64>,
+    ) -> (String, String) {
+        self.generate_wallet_key(name, permissions, Vec::new(), expires_at)
+            .await
+    }
+
+    /// Generate a new API key scoped to specific wallets
+    /// إنشاء مفتاح API مقيد بمحافظ محددة
+    pub async fn generate_wallet_key(
+        &self,
+        name: String,
+        permissions: Vec<Permission>,
+        wallets: Vec<String>,
+        expires_at: Option<u64>,
     ) -> (String, String) {
         use rand::Rng;
 
         // Generate random 32-byte key
-        let mut rng = rand::thread_rng();
-        let key_bytes: [u8; 32] = rng.gen();
+        let key_bytes: [u8; 32] = rand::thread_rng().gen();
         let api_key = format!("osy_{}", hex::encode(key_bytes));
 
         // SECURITY: Hash the key with Argon2 (not SHA-256!) for storage
@@ -107,6 +145,7 @@ impl ApiKeyManager {
             created_at: timestamp,
             expires_at,
             active: true,
+            wallets,
         };
 
         let mut keys = self.keys.write().await;
@@ -118,6 +157,10 @@ impl ApiKeyManager {
     /// Verify an API key and return associated metadata
     /// التحقق من مفتاح API وإرجاع البيانات المرتبطة
     pub async fn verify_key(&self, api_key: &str) -> Option<ApiKey> {
+        if let Some(entry) = self.cached_key(api_key).await {
+            return Some(entry);
+        }
+
         let keys = self.keys.read().await;
 
         // SECURITY: Use constant-time Argon2 verification (prevents timing attacks)
@@ -131,18 +174,14 @@ impl ApiKeyManager {
                     .verify_password(api_key.as_bytes(), &parsed_hash)
                     .is_ok()
                 {
-                    // Check expiration
-                    if let Some(expires_at) = entry.expires_at {
-                        let now = std::time::SystemTime::now()
-                            .duration_since(std::time::UNIX_EPOCH)
-                            .unwrap_or_else(|_| std::time::Duration::from_secs(0))
-                            .as_secs();
-
-                        if now > expires_at {
-                            return None; // Key expired
-                        }
+                    if entry.is_expired() {
+                        return None; // Key expired
                     }
 
+                    self.verified
+                        .write()
+                        .await
+                        .insert(Sha256::digest(api_key).into(), entry.id.clone());
                     return Some(entry.clone());
                 }
             }
@@ -151,6 +190,18 @@ impl ApiKeyManager {
         None
     }
 
+    /// A key verified before, checked without Argon2. Revoked and expired
+    /// keys are not returned
+    /// مفتاح تم التحقق منه سابقاً
+    pub async fn cached_key(&self, api_key: &str) -> Option<ApiKey> {
+        let digest: [u8; 32] = Sha256::digest(api_key).into();
+        let id = self.verified.read().await.get(&digest).cloned()?;
+        let keys = self.keys.read().await;
+        keys.get(&id)
+            .filter(|entry| entry.active && !entry.is_expired())
+            .cloned()
+    }
+
     /// Revoke an API key
     /// إبطال مفتاح API
     pub async fn revoke_key(&self, key_id: &str) -> bool {
@@ -163,6 +214,23 @@ impl ApiKeyManager {
         }
     }
 
+    /// Replace an active key with a new one carrying the same name,
+    /// permissions, wallets and expiry; the old key stops working at once.
+    /// Returns the new key ID and raw key
+    /// تدوير مفتاح API
+    pub async fn rotate_key(&self, key_id: &str) -> Option<(String, String)> {
+        let old = {
+            let mut keys = self.keys.write().await;
+            let key = keys.get_mut(key_id).filter(|key| key.active)?;
+            key.active = false;
+            key.clone()
+        };
+        Some(
+            self.generate_wallet_key(old.name, old.permissions, old.wallets, old.expires_at)
+                .await,
+        )
+    }
+
     /// List all API keys (without showing actual keys)
     /// قائمة بجميع مفاتيح API (دون إظهار المفاتيح الفعلية)
     pub async fn list_keys(&self) -> Vec<ApiKey> {
@@ -181,6 +249,8 @@ impl ApiKeyManager {
 #[derive(Debug, Serialize)]
 pub struct AuthError {
     pub error: String,
+    #[serde(flatten)]
+    pub code: ErrorCode,
 }
 
 /// Extract and validate API key from request headers
@@ -188,11 +258,24 @@ pub struct AuthError {
 pub async fn auth_middleware(
     State(key_manager): State<Arc<ApiKeyManager>>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response, (StatusCode, Json<AuthError>)> {
-    // Extract API key from Authorization header
-    let api_key = headers
+    let api_key = bearer_token(&headers)?;
+    let key_entry = authenticate(&key_manager, api_key).await?;
+
+    // TODO: Add permission checking based on endpoint
+    // For now, just verify the key exists
+
+    // Expose the verified key to handlers for wallet scoping
+    request.extensions_mut().insert(key_entry);
+
+    Ok(next.run(request).await)
+}
+
+/// Extract the bearer token from the Authorization header
+fn bearer_token(headers: &HeaderMap) -> Result<&str, (StatusCode, Json<AuthError>)> {
+    headers
         .get("Authorization")
         .and_then(|v| v.to_str().ok())
         .and_then(|v| v.strip_prefix("Bearer "))
@@ -201,16 +284,23 @@ pub async fn auth_middleware(
                 StatusCode::UNAUTHORIZED,
                 Json(AuthError {
                     error: "Missing or invalid Authorization header".to_string(),
+                    code: error_code::UNAUTHORIZED,
                 }),
             )
-        })?;
+        })
+}
 
-    // Verify API key
+/// Verify an API key and check it is active
+async fn authenticate(
+    key_manager: &ApiKeyManager,
+    api_key: &str,
+) -> Result<ApiKey, (StatusCode, Json<AuthError>)> {
     let key_entry = key_manager.verify_key(api_key).await.ok_or_else(|| {
         (
             StatusCode::UNAUTHORIZED,
             Json(AuthError {
                 error: "Invalid or expired API key".to_string(),
+                code: error_code::UNAUTHORIZED,
             }),
         )
     })?;
@@ -221,14 +311,12 @@ pub async fn auth_middleware(
             StatusCode::UNAUTHORIZED,
             Json(AuthError {
                 error: "API key has been revoked".to_string(),
+                code: error_code::UNAUTHORIZED,
             }),
         ));
     }
 
-    // TODO: Add permission checking based on endpoint
-    // For now, just verify the key exists
-
-    Ok(next.run(request).await)
+    Ok(key_entry)
 }
 
 /// Create a default admin API key for initial setup
@@ -262,8 +350,10 @@ mod tests {
         assert!(api_key.starts_with("osy_"));
 
         // Verify the key
+        assert!(manager.cached_key(&api_key).await.is_none());
         let verified = manager.verify_key(&api_key).await;
         assert!(verified.is_some());
+        assert_eq!(manager.cached_key(&api_key).await.unwrap().id, key_id);
 
         let key_entry = verified.unwrap();
         assert_eq!(key_entry.id, key_id);
@@ -293,10 +383,38 @@ mod tests {
         // Revoke the key
         assert!(manager.revoke_key(&key_id).await);
 
-        // Key should no longer work
+        // Key should no longer work, even though it was verified before
+        assert!(manager.cached_key(&api_key).await.is_none());
         assert!(manager.verify_key(&api_key).await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_rotate_key() {
+        let manager = ApiKeyManager::new();
+
+        let (key_id, api_key) = manager
+            .generate_wallet_key(
+                "ops".to_string(),
+                vec![Permission::ReadBalance],
+                vec!["savings".to_string()],
+                None,
+            )
+            .await;
+        assert!(manager.verify_key(&api_key).await.is_some());
+
+        let (new_id, new_key) = manager.rotate_key(&key_id).await.unwrap();
+        assert_ne!(new_id, key_id);
+        assert!(manager.verify_key(&api_key).await.is_none());
+
+        let rotated = manager.verify_key(&new_key).await.unwrap();
+        assert_eq!(rotated.name, "ops");
+        assert_eq!(rotated.permissions, vec![Permission::ReadBalance]);
+        assert_eq!(rotated.wallets, vec!["savings".to_string()]);
+
+        // A revoked key cannot be rotated back to life
+        assert!(manager.rotate_key(&key_id).await.is_none());
+    }
+
     #[tokio::test]
     async fn test_expired_key() {
         let manager = ApiKeyManager::new();
@@ -345,6 +463,7 @@ mod tests {
             created_at: 0,
             expires_at: None,
             active: true,
+            wallets: vec![],
         };
 
         assert!(ApiKeyManager::has_permission(&key, &Permission::ReadBalance));
@@ -368,6 +487,7 @@ mod tests {
             created_at: 0,
             expires_at: None,
             active: true,
+            wallets: vec![],
         };
 
         // Admin should have all permissions
@@ -0,0 +1,1378 @@
+//! WebAuthn passkeys for the admin endpoints
+//! مفاتيح المرور (WebAuthn) لنقاط الإدارة
+//!
+//! An operator holding an admin API key registers a passkey, then logs in
+//! with it: the authenticator signs a fresh challenge together with the
+//! relying party ID and the page origin, so an assertion phished on another
+//! site is useless here. A login returns a short-lived admin session token,
+//! sent as `Authorization: Bearer` like an API key. Admin routes accept a
+//! session or, unless passkey-only mode is on and a passkey exists, an admin
+//! API key.
+//!
+//! Ed25519 (COSE algorithm -8), ECDSA P-256 (ES256, -7) and RSA PKCS#1
+//! v1.5 (RS256, -257) credentials are supported; each passkey keeps the
+//! algorithm it registered with. Attestation statements are not checked:
+//! registration already requires admin access, so the authenticator's make
+//! is not trusted for anything.
+
+use super::{authenticate, bearer_token, ApiKey, ApiKeyManager, AuthError, Permission};
+use axum::{
+    extract::{Path, Request, State},
+    http::{HeaderMap, StatusCode},
+    middleware::Next,
+    response::{Json, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::value::Value;
+use opensyria_core::clock::system_time;
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::error_code;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Seconds a registration or login challenge stays valid
+pub const CHALLENGE_TIMEOUT_SECS: u64 = 300;
+
+/// Seconds an admin session from a passkey login lasts
+pub const ADMIN_SESSION_SECS: u64 = 900;
+
+/// Most outstanding challenges per ceremony; login challenges are handed
+/// out without authentication, so new ones are refused beyond this rather
+/// than dropping one an operator may be about to answer
+pub const MAX_PENDING_CHALLENGES: usize = 64;
+
+/// COSE algorithm identifier of EdDSA
+const COSE_ALG_EDDSA: i64 = -8;
+/// COSE algorithm identifier of ECDSA with P-256 and SHA-256
+const COSE_ALG_ES256: i64 = -7;
+/// COSE algorithm identifier of RSASSA-PKCS1-v1_5 with SHA-256
+const COSE_ALG_RS256: i64 = -257;
+/// Algorithms offered at registration, most preferred first
+const SUPPORTED_ALGORITHMS: [i64; 3] = [COSE_ALG_EDDSA, COSE_ALG_ES256, COSE_ALG_RS256];
+
+/// COSE key type of octet key pairs
+const COSE_KTY_OKP: i128 = 1;
+/// COSE key type of elliptic curve keys with x and y coordinates
+const COSE_KTY_EC2: i128 = 2;
+/// COSE key type of RSA keys
+const COSE_KTY_RSA: i128 = 3;
+/// COSE curve identifier of Ed25519
+const COSE_CRV_ED25519: i128 = 6;
+/// COSE curve identifier of P-256
+const COSE_CRV_P256: i128 = 1;
+
+/// RSA modulus sizes accepted, in bytes
+const RSA_MODULUS_BYTES: std::ops::RangeInclusive<usize> = 256..=1024;
+
+/// Authenticator data flag: user present
+const FLAG_USER_PRESENT: u8 = 0x01;
+/// Authenticator data flag: user verified (PIN or biometric)
+const FLAG_USER_VERIFIED: u8 = 0x04;
+/// Authenticator data flag: attested credential data included
+const FLAG_ATTESTED_CREDENTIAL: u8 = 0x40;
+
+/// Longest credential ID accepted, as in the WebAuthn specification
+const MAX_CREDENTIAL_ID_LEN: usize = 1023;
+
+/// Relying party settings
+/// إعدادات الطرف المعتمد
+#[derive(Debug, Clone)]
+pub struct WebAuthnConfig {
+    /// Relying party ID: the domain of the admin page, e.g. `localhost`
+    pub rp_id: String,
+    /// Name authenticators show during registration
+    pub rp_name: String,
+    /// Exact origin of the admin page, e.g. `http://localhost:8081`
+    pub origin: String,
+    /// Refuse admin API keys once a passkey is registered
+    pub passkey_only: bool,
+}
+
+impl Default for WebAuthnConfig {
+    fn default() -> Self {
+        Self {
+            rp_id: "localhost".to_string(),
+            rp_name: "OpenSyria Node".to_string(),
+            origin: "http://localhost:8081".to_string(),
+            passkey_only: false,
+        }
+    }
+}
+
+/// A registered passkey
+/// مفتاح مرور مسجّل
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasskeyCredential {
+    /// Credential ID, base64url
+    pub id: String,
+    /// Operator-chosen label
+    pub name: String,
+    /// COSE algorithm the passkey signs with; files written before other
+    /// algorithms were accepted hold Ed25519 keys
+    #[serde(default = "default_algorithm")]
+    pub alg: i64,
+    /// Hex public key: raw Ed25519, an uncompressed P-256 point or a PKCS#1
+    /// RSA key
+    pub public_key: String,
+    /// Signature counter last reported by the authenticator
+    pub sign_count: u32,
+    /// Whether registration verified the user (PIN or biometric)
+    pub user_verified: bool,
+    pub created_at: u64,
+    pub last_used_at: Option<u64>,
+}
+
+/// Errors in a registration or login ceremony
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasskeyError {
+    /// A field is not valid base64url, JSON or CBOR
+    Malformed(&'static str),
+    /// The challenge was never issued, already used or expired
+    UnknownChallenge,
+    /// Client data is for another ceremony type
+    WrongCeremony,
+    /// Client data names another origin
+    WrongOrigin(String),
+    /// Authenticator data is for another relying party
+    WrongRelyingParty,
+    /// The user did not touch the authenticator
+    UserNotPresent,
+    /// The authenticator did not verify the user by PIN or biometric
+    UserNotVerified,
+    /// Too many challenges of this ceremony are outstanding
+    TooManyChallenges,
+    /// Credential public key is not an Ed25519, ES256 or RS256 COSE key
+    UnsupportedKey,
+    /// Credential is already registered
+    DuplicateCredential,
+    /// Credential is not registered
+    UnknownCredential,
+    InvalidSignature,
+    /// Signature counter did not increase, a sign of a cloned authenticator
+    CounterRegression {
+        stored: u32,
+        got: u32,
+    },
+    /// Passkeys could not be saved
+    Storage(String),
+}
+
+impl std::fmt::Display for PasskeyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PasskeyError::Malformed(field) => write!(f, "Malformed {}", field),
+            PasskeyError::UnknownChallenge => write!(f, "Unknown or expired challenge"),
+            PasskeyError::WrongCeremony => write!(f, "Client data is for another ceremony"),
+            PasskeyError::WrongOrigin(origin) => write!(f, "Unexpected origin {}", origin),
+            PasskeyError::WrongRelyingParty => write!(f, "Authenticator data is for another site"),
+            PasskeyError::UserNotPresent => write!(f, "User presence was not confirmed"),
+            PasskeyError::UserNotVerified => {
+                write!(f, "The authenticator did not verify the user")
+            }
+            PasskeyError::TooManyChallenges => {
+                write!(
+                    f,
+                    "Too many passkey ceremonies in progress; try again later"
+                )
+            }
+            PasskeyError::UnsupportedKey => {
+                write!(
+                    f,
+                    "Only Ed25519 (-8), ES256 (-7) and RS256 (-257) passkeys are supported"
+                )
+            }
+            PasskeyError::DuplicateCredential => write!(f, "Passkey is already registered"),
+            PasskeyError::UnknownCredential => write!(f, "Passkey is not registered"),
+            PasskeyError::InvalidSignature => write!(f, "Invalid passkey signature"),
+            PasskeyError::CounterRegression { stored, got } => write!(
+                f,
+                "Signature counter went from {} to {}; the authenticator may be cloned",
+                stored, got
+            ),
+            PasskeyError::Storage(e) => write!(f, "Failed to save passkeys: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for PasskeyError {}
+
+/// Options for `navigator.credentials.create()`; binary fields are base64url
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreationOptions {
+    pub challenge: String,
+    pub rp: RelyingParty,
+    pub user: UserEntity,
+    pub pub_key_cred_params: Vec<CredentialParameter>,
+    pub timeout: u64,
+    pub attestation: &'static str,
+    pub exclude_credentials: Vec<CredentialDescriptor>,
+    pub authenticator_selection: AuthenticatorSelection,
+}
+
+/// Options for `navigator.credentials.get()`; binary fields are base64url
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RequestOptions {
+    pub challenge: String,
+    pub rp_id: String,
+    pub timeout: u64,
+    pub allow_credentials: Vec<CredentialDescriptor>,
+    pub user_verification: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RelyingParty {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserEntity {
+    pub id: String,
+    pub name: String,
+    pub display_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialParameter {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub alg: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CredentialDescriptor {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthenticatorSelection {
+    pub resident_key: &'static str,
+    pub user_verification: &'static str,
+}
+
+/// Credential returned by `navigator.credentials.create()`, as produced by
+/// `PublicKeyCredential.toJSON()`
+#[derive(Debug, Deserialize)]
+pub struct RegistrationCredential {
+    pub id: String,
+    pub response: AttestationResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttestationResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "attestationObject")]
+    pub attestation_object: String,
+}
+
+/// Credential returned by `navigator.credentials.get()`, as produced by
+/// `PublicKeyCredential.toJSON()`
+#[derive(Debug, Deserialize)]
+pub struct AuthenticationCredential {
+    pub id: String,
+    pub response: AssertionResponse,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AssertionResponse {
+    #[serde(rename = "clientDataJSON")]
+    pub client_data_json: String,
+    #[serde(rename = "authenticatorData")]
+    pub authenticator_data: String,
+    pub signature: String,
+}
+
+/// Client data collected by the browser and signed over by the authenticator
+#[derive(Debug, Deserialize)]
+struct ClientData {
+    #[serde(rename = "type")]
+    kind: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Parsed authenticator data
+struct AuthenticatorData {
+    rp_id_hash: [u8; 32],
+    flags: u8,
+    sign_count: u32,
+    /// Credential ID and public key, present at registration
+    attested: Option<(Vec<u8>, CoseKey)>,
+}
+
+/// Credential public key with the algorithm it signs with
+struct CoseKey {
+    alg: i64,
+    /// In the form [`verify_signature`] takes
+    public_key: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ceremony {
+    Registration,
+    Authentication,
+}
+
+struct Session {
+    credential_id: String,
+    name: String,
+    expires_at: u64,
+}
+
+/// Registered passkeys, outstanding challenges and admin sessions
+/// إدارة مفاتيح المرور والجلسات الإدارية
+pub struct PasskeyManager {
+    config: WebAuthnConfig,
+    credentials: RwLock<HashMap<String, PasskeyCredential>>,
+    /// Base64url challenge to its ceremony and expiry
+    challenges: RwLock<HashMap<String, (Ceremony, u64)>>,
+    /// SHA-256 of session tokens
+    sessions: RwLock<HashMap<[u8; 32], Session>>,
+    /// JSON file passkeys are kept in; `None` keeps them in memory
+    path: Option<PathBuf>,
+}
+
+impl PasskeyManager {
+    /// In-memory manager
+    pub fn new(config: WebAuthnConfig) -> Self {
+        Self {
+            config,
+            credentials: RwLock::new(HashMap::new()),
+            challenges: RwLock::new(HashMap::new()),
+            sessions: RwLock::new(HashMap::new()),
+            path: None,
+        }
+    }
+
+    /// Manager keeping passkeys in a JSON file, loading any already there
+    /// تحميل مفاتيح المرور من ملف
+    pub fn open(config: WebAuthnConfig, path: PathBuf) -> Result<Self, PasskeyError> {
+        let credentials: Vec<PasskeyCredential> = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .map_err(|e| PasskeyError::Storage(format!("{}: {}", path.display(), e)))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(PasskeyError::Storage(format!("{}: {}", path.display(), e))),
+        };
+        let manager = Self {
+            path: Some(path),
+            ..Self::new(config)
+        };
+        *manager.credentials.try_write().expect("not yet shared") = credentials
+            .into_iter()
+            .map(|credential| (credential.id.clone(), credential))
+            .collect();
+        Ok(manager)
+    }
+
+    pub fn config(&self) -> &WebAuthnConfig {
+        &self.config
+    }
+
+    /// Registered passkeys
+    pub async fn list(&self) -> Vec<PasskeyCredential> {
+        let mut credentials: Vec<_> = self.credentials.read().await.values().cloned().collect();
+        credentials.sort_by_key(|credential| credential.created_at);
+        credentials
+    }
+
+    /// Whether admin API keys are refused: passkey-only mode with at least
+    /// one passkey to log in with
+    pub async fn api_keys_refused(&self) -> bool {
+        self.config.passkey_only && !self.credentials.read().await.is_empty()
+    }
+
+    /// Start registering a passkey
+    /// بدء تسجيل مفتاح مرور
+    pub async fn start_registration(
+        &self,
+        user_name: &str,
+    ) -> Result<CreationOptions, PasskeyError> {
+        let challenge = self.issue_challenge(Ceremony::Registration).await?;
+        Ok(CreationOptions {
+            challenge,
+            rp: RelyingParty {
+                id: self.config.rp_id.clone(),
+                name: self.config.rp_name.clone(),
+            },
+            user: UserEntity {
+                id: URL_SAFE_NO_PAD.encode(random_bytes::<16>()),
+                name: user_name.to_string(),
+                display_name: user_name.to_string(),
+            },
+            pub_key_cred_params: SUPPORTED_ALGORITHMS
+                .iter()
+                .map(|&alg| CredentialParameter {
+                    kind: "public-key",
+                    alg,
+                })
+                .collect(),
+            timeout: CHALLENGE_TIMEOUT_SECS * 1000,
+            attestation: "none",
+            exclude_credentials: self.descriptors().await,
+            authenticator_selection: AuthenticatorSelection {
+                resident_key: "preferred",
+                user_verification: "required",
+            },
+        })
+    }
+
+    /// Verify a new credential and store its public key
+    /// التحقق من مفتاح مرور جديد وحفظه
+    pub async fn finish_registration(
+        &self,
+        name: String,
+        credential: &RegistrationCredential,
+    ) -> Result<PasskeyCredential, PasskeyError> {
+        let client_data_json = decode(&credential.response.client_data_json, "clientDataJSON")?;
+        self.check_client_data(&client_data_json, Ceremony::Registration)
+            .await?;
+
+        let attestation = decode(&credential.response.attestation_object, "attestationObject")?;
+        let auth_data = parse_attestation_object(&attestation)?;
+        let data = parse_authenticator_data(&auth_data)?;
+        self.check_authenticator_data(&data)?;
+        let (credential_id, public_key) = data
+            .attested
+            .ok_or(PasskeyError::Malformed("attested credential data"))?;
+        let id = URL_SAFE_NO_PAD.encode(&credential_id);
+        if decode(&credential.id, "credential ID")? != credential_id {
+            return Err(PasskeyError::Malformed("credential ID"));
+        }
+
+        let entry = PasskeyCredential {
+            id: id.clone(),
+            name,
+            alg: public_key.alg,
+            public_key: hex::encode(public_key.public_key),
+            sign_count: data.sign_count,
+            user_verified: data.flags & FLAG_USER_VERIFIED != 0,
+            created_at: system_time(),
+            last_used_at: None,
+        };
+        let mut credentials = self.credentials.write().await;
+        if credentials.contains_key(&id) {
+            return Err(PasskeyError::DuplicateCredential);
+        }
+        credentials.insert(id, entry.clone());
+        self.save(&credentials)?;
+        Ok(entry)
+    }
+
+    /// Start a passkey login
+    /// بدء تسجيل الدخول بمفتاح مرور
+    pub async fn start_authentication(&self) -> Result<RequestOptions, PasskeyError> {
+        Ok(RequestOptions {
+            challenge: self.issue_challenge(Ceremony::Authentication).await?,
+            rp_id: self.config.rp_id.clone(),
+            timeout: CHALLENGE_TIMEOUT_SECS * 1000,
+            allow_credentials: self.descriptors().await,
+            user_verification: "required",
+        })
+    }
+
+    /// Verify an assertion and open an admin session; returns the session
+    /// token and its expiry
+    /// التحقق من توقيع مفتاح المرور وفتح جلسة إدارية
+    pub async fn finish_authentication(
+        &self,
+        credential: &AuthenticationCredential,
+    ) -> Result<(String, u64), PasskeyError> {
+        let client_data_json = decode(&credential.response.client_data_json, "clientDataJSON")?;
+        self.check_client_data(&client_data_json, Ceremony::Authentication)
+            .await?;
+
+        let auth_data = decode(&credential.response.authenticator_data, "authenticatorData")?;
+        let data = parse_authenticator_data(&auth_data)?;
+        self.check_authenticator_data(&data)?;
+        let signature = decode(&credential.response.signature, "signature")?;
+
+        let mut credentials = self.credentials.write().await;
+        let id = URL_SAFE_NO_PAD.encode(decode(&credential.id, "credential ID")?);
+        let stored = credentials
+            .get_mut(&id)
+            .ok_or(PasskeyError::UnknownCredential)?;
+
+        let mut message = auth_data.clone();
+        message.extend_from_slice(&Sha256::digest(&client_data_json));
+        let public_key = hex::decode(&stored.public_key)
+            .map_err(|_| PasskeyError::Malformed("stored public key"))?;
+        verify_signature(stored.alg, &public_key, &message, &signature)?;
+
+        // Authenticators without a counter always report zero
+        if (data.sign_count != 0 || stored.sign_count != 0) && data.sign_count <= stored.sign_count
+        {
+            return Err(PasskeyError::CounterRegression {
+                stored: stored.sign_count,
+                got: data.sign_count,
+            });
+        }
+        stored.sign_count = data.sign_count;
+        stored.last_used_at = Some(system_time());
+        let name = stored.name.clone();
+        self.save(&credentials)?;
+        drop(credentials);
+
+        let token = format!("osy_admin_{}", hex::encode(random_bytes::<32>()));
+        let now = system_time();
+        let expires_at = now + ADMIN_SESSION_SECS;
+        let mut sessions = self.sessions.write().await;
+        sessions.retain(|_, session| session.expires_at > now);
+        sessions.insert(
+            Sha256::digest(&token).into(),
+            Session {
+                credential_id: id,
+                name,
+                expires_at,
+            },
+        );
+        Ok((token, expires_at))
+    }
+
+    /// Admin identity of a live session token
+    /// التحقق من رمز الجلسة الإدارية
+    pub async fn session(&self, token: &str) -> Option<ApiKey> {
+        let digest: [u8; 32] = Sha256::digest(token).into();
+        let sessions = self.sessions.read().await;
+        let session = sessions
+            .get(&digest)
+            .filter(|session| session.expires_at > system_time())?;
+        // Removing the passkey ends its sessions
+        if !self
+            .credentials
+            .read()
+            .await
+            .contains_key(&session.credential_id)
+        {
+            return None;
+        }
+        Some(ApiKey {
+            id: format!("passkey_{}", session.credential_id),
+            key_hash: String::new(),
+            name: session.name.clone(),
+            permissions: vec![Permission::Admin],
+            created_at: session.expires_at - ADMIN_SESSION_SECS,
+            expires_at: Some(session.expires_at),
+            active: true,
+            wallets: Vec::new(),
+        })
+    }
+
+    /// Remove a passkey; its sessions end with it
+    /// حذف مفتاح مرور
+    pub async fn remove(&self, id: &str) -> Result<bool, PasskeyError> {
+        let mut credentials = self.credentials.write().await;
+        if credentials.remove(id).is_none() {
+            return Ok(false);
+        }
+        self.save(&credentials)?;
+        Ok(true)
+    }
+
+    /// New challenge for a ceremony, refused once that ceremony has
+    /// [`MAX_PENDING_CHALLENGES`] outstanding so login requests cannot
+    /// crowd out a registration or each other's live challenges
+    async fn issue_challenge(&self, ceremony: Ceremony) -> Result<String, PasskeyError> {
+        let challenge = URL_SAFE_NO_PAD.encode(random_bytes::<32>());
+        let now = system_time();
+        let mut challenges = self.challenges.write().await;
+        challenges.retain(|_, (_, expires_at)| *expires_at > now);
+        let pending = challenges
+            .values()
+            .filter(|(issued_for, _)| *issued_for == ceremony)
+            .count();
+        if pending >= MAX_PENDING_CHALLENGES {
+            return Err(PasskeyError::TooManyChallenges);
+        }
+        challenges.insert(challenge.clone(), (ceremony, now + CHALLENGE_TIMEOUT_SECS));
+        Ok(challenge)
+    }
+
+    /// Check client data against an outstanding challenge, using it up
+    async fn check_client_data(&self, json: &[u8], ceremony: Ceremony) -> Result<(), PasskeyError> {
+        let client_data: ClientData =
+            serde_json::from_slice(json).map_err(|_| PasskeyError::Malformed("clientDataJSON"))?;
+        let expected_kind = match ceremony {
+            Ceremony::Registration => "webauthn.create",
+            Ceremony::Authentication => "webauthn.get",
+        };
+        if client_data.kind != expected_kind {
+            return Err(PasskeyError::WrongCeremony);
+        }
+        match self.challenges.write().await.remove(&client_data.challenge) {
+            Some((issued_for, expires_at))
+                if issued_for == ceremony && expires_at > system_time() => {}
+            _ => return Err(PasskeyError::UnknownChallenge),
+        }
+        if client_data.origin != self.config.origin {
+            return Err(PasskeyError::WrongOrigin(client_data.origin));
+        }
+        Ok(())
+    }
+
+    fn check_authenticator_data(&self, data: &AuthenticatorData) -> Result<(), PasskeyError> {
+        let rp_id_hash: [u8; 32] = Sha256::digest(self.config.rp_id.as_bytes()).into();
+        if data.rp_id_hash != rp_id_hash {
+            return Err(PasskeyError::WrongRelyingParty);
+        }
+        if data.flags & FLAG_USER_PRESENT == 0 {
+            return Err(PasskeyError::UserNotPresent);
+        }
+        // Admin access needs the PIN or biometric, not just a touch
+        if data.flags & FLAG_USER_VERIFIED == 0 {
+            return Err(PasskeyError::UserNotVerified);
+        }
+        Ok(())
+    }
+
+    async fn descriptors(&self) -> Vec<CredentialDescriptor> {
+        self.credentials
+            .read()
+            .await
+            .keys()
+            .map(|id| CredentialDescriptor {
+                kind: "public-key",
+                id: id.clone(),
+            })
+            .collect()
+    }
+
+    fn save(&self, credentials: &HashMap<String, PasskeyCredential>) -> Result<(), PasskeyError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut list: Vec<_> = credentials.values().collect();
+        list.sort_by_key(|credential| credential.created_at);
+        let json = serde_json::to_string_pretty(&list)
+            .map_err(|e| PasskeyError::Storage(e.to_string()))?;
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, json)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| PasskeyError::Storage(format!("{}: {}", path.display(), e)))
+    }
+}
+
+/// `authData` of an attestation object
+fn parse_attestation_object(bytes: &[u8]) -> Result<Vec<u8>, PasskeyError> {
+    let malformed = PasskeyError::Malformed("attestationObject");
+    let value: Value = ciborium::de::from_reader(bytes).map_err(|_| malformed.clone())?;
+    let map = value.as_map().ok_or(malformed.clone())?;
+    map.iter()
+        .find(|(key, _)| key.as_text() == Some("authData"))
+        .and_then(|(_, value)| value.as_bytes())
+        .cloned()
+        .ok_or(malformed)
+}
+
+fn parse_authenticator_data(bytes: &[u8]) -> Result<AuthenticatorData, PasskeyError> {
+    let malformed = PasskeyError::Malformed("authenticatorData");
+    if bytes.len() < 37 {
+        return Err(malformed);
+    }
+    let rp_id_hash: [u8; 32] = bytes[..32].try_into().expect("32 bytes");
+    let flags = bytes[32];
+    let sign_count = u32::from_be_bytes(bytes[33..37].try_into().expect("4 bytes"));
+
+    let attested = if flags & FLAG_ATTESTED_CREDENTIAL != 0 {
+        // AAGUID (16 bytes), then a big-endian length and the credential ID
+        let rest = bytes.get(37 + 16..).ok_or(malformed.clone())?;
+        let len_bytes: [u8; 2] = rest
+            .get(..2)
+            .ok_or(malformed.clone())?
+            .try_into()
+            .expect("2 bytes");
+        let len = u16::from_be_bytes(len_bytes) as usize;
+        if len > MAX_CREDENTIAL_ID_LEN {
+            return Err(malformed);
+        }
+        let credential_id = rest.get(2..2 + len).ok_or(malformed.clone())?.to_vec();
+        let cose_key: Value = ciborium::de::from_reader(&rest[2 + len..])
+            .map_err(|_| PasskeyError::Malformed("credential public key"))?;
+        Some((credential_id, parse_cose_key(&cose_key)?))
+    } else {
+        None
+    };
+
+    Ok(AuthenticatorData {
+        rp_id_hash,
+        flags,
+        sign_count,
+        attested,
+    })
+}
+
+/// Public key and algorithm of a COSE_Key
+fn parse_cose_key(key: &Value) -> Result<CoseKey, PasskeyError> {
+    let map = key.as_map().ok_or(PasskeyError::UnsupportedKey)?;
+    let field = |label: i128| {
+        map.iter()
+            .find(|(key, _)| key.as_integer().map(i128::from) == Some(label))
+            .map(|(_, value)| value)
+    };
+    let integer = |label| field(label).and_then(Value::as_integer).map(i128::from);
+    let bytes = |label| {
+        field(label)
+            .and_then(Value::as_bytes)
+            .ok_or(PasskeyError::UnsupportedKey)
+    };
+    let coordinate = |label| {
+        bytes(label).and_then(|c| {
+            <[u8; 32]>::try_from(c.as_slice()).map_err(|_| PasskeyError::UnsupportedKey)
+        })
+    };
+
+    let alg = integer(3)
+        .and_then(|alg| i64::try_from(alg).ok())
+        .ok_or(PasskeyError::UnsupportedKey)?;
+    let public_key = match (alg, integer(1)) {
+        (COSE_ALG_EDDSA, Some(COSE_KTY_OKP)) if integer(-1) == Some(COSE_CRV_ED25519) => {
+            coordinate(-2)?.to_vec()
+        }
+        // Uncompressed SEC1 point
+        (COSE_ALG_ES256, Some(COSE_KTY_EC2)) if integer(-1) == Some(COSE_CRV_P256) => {
+            let mut point = vec![0x04];
+            point.extend_from_slice(&coordinate(-2)?);
+            point.extend_from_slice(&coordinate(-3)?);
+            point
+        }
+        (COSE_ALG_RS256, Some(COSE_KTY_RSA)) => {
+            let (n, e) = (bytes(-1)?, bytes(-2)?);
+            let modulus = n.iter().skip_while(|&&b| b == 0).count();
+            if !RSA_MODULUS_BYTES.contains(&modulus) {
+                return Err(PasskeyError::UnsupportedKey);
+            }
+            rsa_public_key_der(n, e)
+        }
+        _ => return Err(PasskeyError::UnsupportedKey),
+    };
+    Ok(CoseKey { alg, public_key })
+}
+
+/// Check an assertion signature over `message` with a stored public key
+fn verify_signature(
+    alg: i64,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> Result<(), PasskeyError> {
+    use ring::signature::{UnparsedPublicKey, ECDSA_P256_SHA256_ASN1, RSA_PKCS1_2048_8192_SHA256};
+
+    let valid = match alg {
+        COSE_ALG_EDDSA => {
+            let key = <[u8; 32]>::try_from(public_key)
+                .map_err(|_| PasskeyError::Malformed("stored public key"))?;
+            PublicKey(key).verify(message, signature).is_ok()
+        }
+        // WebAuthn ECDSA signatures are DER-encoded
+        COSE_ALG_ES256 => UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, public_key)
+            .verify(message, signature)
+            .is_ok(),
+        COSE_ALG_RS256 => UnparsedPublicKey::new(&RSA_PKCS1_2048_8192_SHA256, public_key)
+            .verify(message, signature)
+            .is_ok(),
+        _ => return Err(PasskeyError::UnsupportedKey),
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(PasskeyError::InvalidSignature)
+    }
+}
+
+/// PKCS#1 `RSAPublicKey` DER of a big-endian modulus and exponent
+fn rsa_public_key_der(n: &[u8], e: &[u8]) -> Vec<u8> {
+    fn with_length(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut der = vec![tag];
+        let len = content.len();
+        if len < 0x80 {
+            der.push(len as u8);
+        } else {
+            let len_bytes: Vec<u8> = len
+                .to_be_bytes()
+                .into_iter()
+                .skip_while(|&b| b == 0)
+                .collect();
+            der.push(0x80 | len_bytes.len() as u8);
+            der.extend_from_slice(&len_bytes);
+        }
+        der.extend_from_slice(content);
+        der
+    }
+    // Unsigned integers need a leading zero when the top bit is set
+    fn integer(bytes: &[u8]) -> Vec<u8> {
+        let trimmed: Vec<u8> = bytes.iter().copied().skip_while(|&b| b == 0).collect();
+        let mut content = Vec::with_capacity(trimmed.len() + 1);
+        if trimmed.first().is_none_or(|&b| b & 0x80 != 0) {
+            content.push(0);
+        }
+        content.extend_from_slice(&trimmed);
+        with_length(0x02, &content)
+    }
+
+    let mut sequence = integer(n);
+    sequence.extend(integer(e));
+    with_length(0x30, &sequence)
+}
+
+fn default_algorithm() -> i64 {
+    COSE_ALG_EDDSA
+}
+
+/// Decode base64url, with or without padding
+fn decode(value: &str, field: &'static str) -> Result<Vec<u8>, PasskeyError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| PasskeyError::Malformed(field))
+}
+
+fn random_bytes<const N: usize>() -> [u8; N] {
+    use rand::RngCore;
+    let mut bytes = [0u8; N];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    bytes
+}
+
+/// Credentials accepted on admin routes
+/// بيانات الاعتماد المقبولة في نقاط الإدارة
+#[derive(Clone)]
+pub struct AdminAuth {
+    pub api_keys: Arc<ApiKeyManager>,
+    pub passkeys: Arc<PasskeyManager>,
+}
+
+/// Authenticate an admin request by passkey session, or by API key unless
+/// passkey-only mode refuses keys
+/// مصادقة طلبات الإدارة بجلسة مفتاح المرور أو بمفتاح API
+pub async fn admin_auth_middleware(
+    State(auth): State<AdminAuth>,
+    headers: HeaderMap,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, (StatusCode, Json<AuthError>)> {
+    let token = bearer_token(&headers)?;
+    let key = match auth.passkeys.session(token).await {
+        Some(session) => session,
+        None if auth.passkeys.api_keys_refused().await => {
+            return Err((
+                StatusCode::UNAUTHORIZED,
+                Json(AuthError {
+                    error: "Passkey login required for admin access".to_string(),
+                    code: error_code::UNAUTHORIZED,
+                }),
+            ));
+        }
+        None => authenticate(&auth.api_keys, token).await?,
+    };
+    request.extensions_mut().insert(key);
+    Ok(next.run(request).await)
+}
+
+type PasskeyResult<T> = Result<Json<T>, (StatusCode, Json<AuthError>)>;
+
+fn passkey_error(e: PasskeyError) -> (StatusCode, Json<AuthError>) {
+    let status = match e {
+        PasskeyError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        PasskeyError::DuplicateCredential => StatusCode::CONFLICT,
+        PasskeyError::TooManyChallenges => StatusCode::TOO_MANY_REQUESTS,
+        PasskeyError::Malformed(_) | PasskeyError::UnsupportedKey => StatusCode::BAD_REQUEST,
+        _ => StatusCode::UNAUTHORIZED,
+    };
+    (
+        status,
+        Json(AuthError {
+            error: e.to_string(),
+            code: error_code::for_http_status(status.as_u16()),
+        }),
+    )
+}
+
+/// Options passed to `navigator.credentials.create()`/`get()`
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicKeyOptions<T> {
+    pub public_key: T,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartRegistrationRequest {
+    /// Account name the authenticator shows; defaults to `admin`
+    #[serde(default)]
+    pub user_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FinishRegistrationRequest {
+    /// Label for the passkey
+    pub name: String,
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdminSessionResponse {
+    /// Bearer token for the admin routes
+    pub token: String,
+    pub expires_at: u64,
+}
+
+/// POST /api/v1/admin/passkeys/register/start - registration options
+/// خيارات تسجيل مفتاح مرور
+pub async fn start_registration(
+    State(passkeys): State<Arc<PasskeyManager>>,
+    body: Option<Json<StartRegistrationRequest>>,
+) -> PasskeyResult<PublicKeyOptions<CreationOptions>> {
+    let request = body.map(|Json(body)| body).unwrap_or_default();
+    let user_name = request.user_name.unwrap_or_else(|| "admin".to_string());
+    Ok(Json(PublicKeyOptions {
+        public_key: passkeys
+            .start_registration(&user_name)
+            .await
+            .map_err(passkey_error)?,
+    }))
+}
+
+/// POST /api/v1/admin/passkeys/register/finish - store a new passkey
+/// حفظ مفتاح مرور جديد
+pub async fn finish_registration(
+    State(passkeys): State<Arc<PasskeyManager>>,
+    Json(request): Json<FinishRegistrationRequest>,
+) -> PasskeyResult<PasskeyCredential> {
+    let credential = passkeys
+        .finish_registration(request.name, &request.credential)
+        .await
+        .map_err(passkey_error)?;
+    tracing::info!("Registered admin passkey {}", credential.name);
+    Ok(Json(credential))
+}
+
+/// GET /api/v1/admin/passkeys - registered passkeys
+/// قائمة مفاتيح المرور
+pub async fn list_passkeys(
+    State(passkeys): State<Arc<PasskeyManager>>,
+) -> Json<Vec<PasskeyCredential>> {
+    Json(passkeys.list().await)
+}
+
+/// DELETE /api/v1/admin/passkeys/{id} - remove a passkey
+/// حذف مفتاح مرور
+pub async fn remove_passkey(
+    State(passkeys): State<Arc<PasskeyManager>>,
+    Path(id): Path<String>,
+) -> PasskeyResult<serde_json::Value> {
+    if !passkeys.remove(&id).await.map_err(passkey_error)? {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(AuthError {
+                error: "No passkey with that ID".to_string(),
+                code: error_code::NOT_FOUND,
+            }),
+        ));
+    }
+    tracing::info!("Removed admin passkey {}", id);
+    Ok(Json(serde_json::json!({ "removed": id })))
+}
+
+/// POST /api/v1/admin/passkeys/login/start - login options
+/// خيارات تسجيل الدخول بمفتاح مرور
+pub async fn start_authentication(
+    State(passkeys): State<Arc<PasskeyManager>>,
+) -> PasskeyResult<PublicKeyOptions<RequestOptions>> {
+    Ok(Json(PublicKeyOptions {
+        public_key: passkeys
+            .start_authentication()
+            .await
+            .map_err(passkey_error)?,
+    }))
+}
+
+/// POST /api/v1/admin/passkeys/login/finish - verify an assertion and
+/// return an admin session token
+/// تسجيل الدخول بمفتاح مرور
+pub async fn finish_authentication(
+    State(passkeys): State<Arc<PasskeyManager>>,
+    Json(credential): Json<AuthenticationCredential>,
+) -> PasskeyResult<AdminSessionResponse> {
+    let (token, expires_at) = passkeys
+        .finish_authentication(&credential)
+        .await
+        .map_err(passkey_error)?;
+    Ok(Json(AdminSessionResponse { token, expires_at }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    use ring::rand::SystemRandom;
+    use ring::rsa::{KeyPair as RsaKeyPair, PublicKeyComponents};
+    use ring::signature::{EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, RSA_PKCS1_SHA256};
+
+    /// 2048-bit RSA test key, PKCS#8 PEM
+    const RS256_KEY_PEM: &str = include_str!("../../vectors/passkey_rs256.pem");
+
+    enum CredentialKey {
+        Ed25519(KeyPair),
+        Es256(EcdsaKeyPair),
+        Rs256(RsaKeyPair),
+    }
+
+    impl CredentialKey {
+        fn es256() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            Self::Es256(
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap(),
+            )
+        }
+
+        fn rs256() -> Self {
+            let base64: String = RS256_KEY_PEM
+                .lines()
+                .filter(|line| !line.starts_with("-----"))
+                .collect();
+            let der = base64::engine::general_purpose::STANDARD
+                .decode(base64)
+                .unwrap();
+            Self::Rs256(RsaKeyPair::from_pkcs8(&der).unwrap())
+        }
+
+        fn cose_key(&self) -> Value {
+            let entries = match self {
+                CredentialKey::Ed25519(keypair) => vec![
+                    (1, Value::from(COSE_KTY_OKP as i64)),
+                    (3, Value::from(COSE_ALG_EDDSA)),
+                    (-1, Value::from(COSE_CRV_ED25519 as i64)),
+                    (-2, Value::Bytes(keypair.public_key().0.to_vec())),
+                ],
+                CredentialKey::Es256(keypair) => {
+                    let point = ring::signature::KeyPair::public_key(keypair).as_ref();
+                    vec![
+                        (1, Value::from(COSE_KTY_EC2 as i64)),
+                        (3, Value::from(COSE_ALG_ES256)),
+                        (-1, Value::from(COSE_CRV_P256 as i64)),
+                        (-2, Value::Bytes(point[1..33].to_vec())),
+                        (-3, Value::Bytes(point[33..].to_vec())),
+                    ]
+                }
+                CredentialKey::Rs256(keypair) => {
+                    let components = PublicKeyComponents::<Vec<u8>>::from(keypair.public());
+                    vec![
+                        (1, Value::from(COSE_KTY_RSA as i64)),
+                        (3, Value::from(COSE_ALG_RS256)),
+                        (-1, Value::Bytes(components.n)),
+                        (-2, Value::Bytes(components.e)),
+                    ]
+                }
+            };
+            Value::Map(
+                entries
+                    .into_iter()
+                    .map(|(label, value)| (Value::from(label), value))
+                    .collect(),
+            )
+        }
+
+        fn sign(&self, message: &[u8]) -> Vec<u8> {
+            let rng = SystemRandom::new();
+            match self {
+                CredentialKey::Ed25519(keypair) => keypair.sign(message).to_vec(),
+                CredentialKey::Es256(keypair) => {
+                    keypair.sign(&rng, message).unwrap().as_ref().to_vec()
+                }
+                CredentialKey::Rs256(keypair) => {
+                    let mut signature = vec![0u8; keypair.public().modulus_len()];
+                    keypair
+                        .sign(&RSA_PKCS1_SHA256, &rng, message, &mut signature)
+                        .unwrap();
+                    signature
+                }
+            }
+        }
+    }
+
+    /// Software authenticator holding one credential
+    struct Authenticator {
+        key: CredentialKey,
+        credential_id: Vec<u8>,
+        counter: u32,
+        /// Whether the user is verified by PIN or biometric
+        verifies: bool,
+    }
+
+    impl Authenticator {
+        fn new() -> Self {
+            Self::with_key(CredentialKey::Ed25519(KeyPair::generate()))
+        }
+
+        fn with_key(key: CredentialKey) -> Self {
+            Self {
+                key,
+                credential_id: random_bytes::<16>().to_vec(),
+                counter: 0,
+                verifies: true,
+            }
+        }
+
+        fn client_data(kind: &str, challenge: &str, origin: &str) -> Vec<u8> {
+            serde_json::to_vec(&serde_json::json!({
+                "type": kind,
+                "challenge": challenge,
+                "origin": origin,
+            }))
+            .unwrap()
+        }
+
+        fn auth_data(&self, rp_id: &str, attested: bool) -> Vec<u8> {
+            let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+            let flags = FLAG_USER_PRESENT
+                | if self.verifies { FLAG_USER_VERIFIED } else { 0 }
+                | if attested {
+                    FLAG_ATTESTED_CREDENTIAL
+                } else {
+                    0
+                };
+            data.push(flags);
+            data.extend_from_slice(&self.counter.to_be_bytes());
+            if attested {
+                data.extend_from_slice(&[0u8; 16]);
+                data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+                data.extend_from_slice(&self.credential_id);
+                ciborium::ser::into_writer(&self.key.cose_key(), &mut data).unwrap();
+            }
+            data
+        }
+
+        fn register(&self, options: &CreationOptions, origin: &str) -> RegistrationCredential {
+            let attestation = Value::Map(vec![
+                (Value::from("fmt"), Value::from("none")),
+                (Value::from("attStmt"), Value::Map(Vec::new())),
+                (
+                    Value::from("authData"),
+                    Value::Bytes(self.auth_data(&options.rp.id, true)),
+                ),
+            ]);
+            let mut attestation_object = Vec::new();
+            ciborium::ser::into_writer(&attestation, &mut attestation_object).unwrap();
+            RegistrationCredential {
+                id: URL_SAFE_NO_PAD.encode(&self.credential_id),
+                response: AttestationResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(Self::client_data(
+                        "webauthn.create",
+                        &options.challenge,
+                        origin,
+                    )),
+                    attestation_object: URL_SAFE_NO_PAD.encode(attestation_object),
+                },
+            }
+        }
+
+        fn assert(&mut self, options: &RequestOptions, origin: &str) -> AuthenticationCredential {
+            self.counter += 1;
+            let client_data = Self::client_data("webauthn.get", &options.challenge, origin);
+            let auth_data = self.auth_data(&options.rp_id, false);
+            let mut message = auth_data.clone();
+            message.extend_from_slice(&Sha256::digest(&client_data));
+            AuthenticationCredential {
+                id: URL_SAFE_NO_PAD.encode(&self.credential_id),
+                response: AssertionResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(client_data),
+                    authenticator_data: URL_SAFE_NO_PAD.encode(auth_data),
+                    signature: URL_SAFE_NO_PAD.encode(self.key.sign(&message)),
+                },
+            }
+        }
+    }
+
+    async fn registered() -> (PasskeyManager, Authenticator) {
+        let manager = PasskeyManager::new(WebAuthnConfig::default());
+        let authenticator = Authenticator::new();
+        let options = manager.start_registration("admin").await.unwrap();
+        let credential = authenticator.register(&options, &manager.config().origin);
+        manager
+            .finish_registration("laptop".to_string(), &credential)
+            .await
+            .unwrap();
+        (manager, authenticator)
+    }
+
+    #[tokio::test]
+    async fn test_register_and_log_in() {
+        let (manager, mut authenticator) = registered().await;
+        let listed = manager.list().await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "laptop");
+
+        let options = manager.start_authentication().await.unwrap();
+        assert_eq!(options.allow_credentials.len(), 1);
+        let assertion = authenticator.assert(&options, &manager.config().origin);
+        let (token, _) = manager.finish_authentication(&assertion).await.unwrap();
+
+        let session = manager.session(&token).await.unwrap();
+        assert!(session.permissions.contains(&Permission::Admin));
+        assert!(manager.session("osy_admin_forged").await.is_none());
+
+        // The challenge is spent, so the same assertion cannot be replayed
+        assert_eq!(
+            manager.finish_authentication(&assertion).await,
+            Err(PasskeyError::UnknownChallenge)
+        );
+
+        // Removing the passkey ends its session
+        assert!(manager.remove(&listed[0].id).await.unwrap());
+        assert!(manager.session(&token).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_phished_or_cloned_assertions_rejected() {
+        let (manager, mut authenticator) = registered().await;
+
+        // Assertion collected by a page on another origin
+        let options = manager.start_authentication().await.unwrap();
+        let phished = authenticator.assert(&options, "https://opensyria-login.example");
+        assert!(matches!(
+            manager.finish_authentication(&phished).await,
+            Err(PasskeyError::WrongOrigin(_))
+        ));
+
+        // Tampered signature
+        let options = manager.start_authentication().await.unwrap();
+        let mut tampered = authenticator.assert(&options, &manager.config().origin);
+        tampered.response.signature = URL_SAFE_NO_PAD.encode([0u8; 64]);
+        assert_eq!(
+            manager.finish_authentication(&tampered).await,
+            Err(PasskeyError::InvalidSignature)
+        );
+
+        // A clone replaying an old counter value
+        let options = manager.start_authentication().await.unwrap();
+        let assertion = authenticator.assert(&options, &manager.config().origin);
+        manager.finish_authentication(&assertion).await.unwrap();
+        authenticator.counter -= 1;
+        let options = manager.start_authentication().await.unwrap();
+        let cloned = authenticator.assert(&options, &manager.config().origin);
+        assert!(matches!(
+            manager.finish_authentication(&cloned).await,
+            Err(PasskeyError::CounterRegression { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unverified_user_and_challenge_budget() {
+        let (manager, mut authenticator) = registered().await;
+
+        // A touch without PIN or biometric does not log in
+        authenticator.verifies = false;
+        let options = manager.start_authentication().await.unwrap();
+        let assertion = authenticator.assert(&options, &manager.config().origin);
+        assert_eq!(
+            manager.finish_authentication(&assertion).await,
+            Err(PasskeyError::UserNotVerified)
+        );
+
+        // Login challenges beyond the budget are refused, not evicted, and
+        // leave registration its own budget
+        let first = manager.start_authentication().await.unwrap();
+        for _ in 1..MAX_PENDING_CHALLENGES {
+            manager.start_authentication().await.unwrap();
+        }
+        assert_eq!(
+            manager.start_authentication().await.err(),
+            Some(PasskeyError::TooManyChallenges)
+        );
+        manager.start_registration("admin").await.unwrap();
+        authenticator.verifies = true;
+        let assertion = authenticator.assert(&first, &manager.config().origin);
+        manager.finish_authentication(&assertion).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_ecdsa_and_rsa_passkeys() {
+        let manager = PasskeyManager::new(WebAuthnConfig::default());
+        let origin = manager.config().origin.clone();
+        for (key, alg) in [
+            (CredentialKey::es256(), COSE_ALG_ES256),
+            (CredentialKey::rs256(), COSE_ALG_RS256),
+        ] {
+            let mut authenticator = Authenticator::with_key(key);
+            let options = manager.start_registration("admin").await.unwrap();
+            let offered: Vec<i64> = options.pub_key_cred_params.iter().map(|p| p.alg).collect();
+            assert_eq!(offered, SUPPORTED_ALGORITHMS);
+            let registered = manager
+                .finish_registration(
+                    "platform".to_string(),
+                    &authenticator.register(&options, &origin),
+                )
+                .await
+                .unwrap();
+            assert_eq!(registered.alg, alg);
+
+            let options = manager.start_authentication().await.unwrap();
+            let assertion = authenticator.assert(&options, &origin);
+            manager.finish_authentication(&assertion).await.unwrap();
+
+            // Tampered signature
+            let options = manager.start_authentication().await.unwrap();
+            let mut tampered = authenticator.assert(&options, &origin);
+            let mut signature = decode(&tampered.response.signature, "signature").unwrap();
+            *signature.last_mut().unwrap() ^= 1;
+            tampered.response.signature = URL_SAFE_NO_PAD.encode(signature);
+            assert_eq!(
+                manager.finish_authentication(&tampered).await,
+                Err(PasskeyError::InvalidSignature)
+            );
+        }
+
+        // Passkeys saved before the algorithm was recorded are Ed25519
+        let legacy: PasskeyCredential = serde_json::from_value(serde_json::json!({
+            "id": "AAAA",
+            "name": "laptop",
+            "public_key": hex::encode([0u8; 32]),
+            "sign_count": 0,
+            "user_verified": false,
+            "created_at": 0,
+            "last_used_at": null,
+        }))
+        .unwrap();
+        assert_eq!(legacy.alg, COSE_ALG_EDDSA);
+    }
+
+    #[tokio::test]
+    async fn test_passkeys_persist_and_gate_api_keys() {
+        let dir =
+            std::env::temp_dir().join(format!("passkeys_{}", hex::encode(random_bytes::<4>())));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("passkeys.json");
+        let config = WebAuthnConfig {
+            passkey_only: true,
+            ..WebAuthnConfig::default()
+        };
+
+        let manager = PasskeyManager::open(config.clone(), path.clone()).unwrap();
+        assert!(!manager.api_keys_refused().await);
+        let authenticator = Authenticator::new();
+        let options = manager.start_registration("admin").await.unwrap();
+        let credential = authenticator.register(&options, &config.origin);
+        manager
+            .finish_registration("yubikey".to_string(), &credential)
+            .await
+            .unwrap();
+        assert!(manager.api_keys_refused().await);
+
+        let reopened = PasskeyManager::open(config, path).unwrap();
+        assert_eq!(reopened.list().await[0].name, "yubikey");
+        assert!(reopened.api_keys_refused().await);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,917 @@
+//! Batch request execution for the RPC and REST surfaces
+//! تنفيذ الطلبات المجمعة لواجهات RPC و REST
+//!
+//! Every batch item is dispatched through the regular API router, so it is
+//! subject to the same authentication and rate limiting as a standalone call.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::Json,
+    Router,
+};
+use opensyria_core::error_code;
+use opensyria_metrics::http::RequestTimer;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tracing::Instrument;
+
+use crate::latency;
+use crate::models::ErrorResponse;
+
+/// Maximum number of items accepted in a single batch
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// Maximum response body size collected per batch item (1 MB)
+const MAX_ITEM_RESPONSE_BYTES: usize = 1024 * 1024;
+
+/// Endpoints that may not be nested inside a batch
+const BATCH_ENDPOINTS: [&str; 2] = ["/api/v1/batch", "/api/v1/rpc"];
+
+/// Streaming endpoints whose responses never complete
+const STREAMING_ENDPOINTS: [&str; 1] = ["/api/v1/events"];
+
+/// JSON-RPC error codes
+const RPC_INVALID_REQUEST: i64 = -32600;
+const RPC_METHOD_NOT_FOUND: i64 = -32601;
+const RPC_INVALID_PARAMS: i64 = -32602;
+const RPC_SERVER_ERROR: i64 = -32000;
+
+/// A single REST call inside a `/api/v1/batch` request
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchRequestItem {
+    /// HTTP method (GET or POST)
+    pub method: String,
+    /// Request path, e.g. `/api/v1/account/<address>/balance`
+    pub path: String,
+    /// Optional JSON body for POST requests
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+/// Result of a single REST call inside a batch
+#[derive(Debug, Serialize)]
+pub struct BatchResponseItem {
+    /// HTTP status code of the item
+    pub status: u16,
+    /// JSON response body of the item
+    pub body: Value,
+}
+
+/// JSON-RPC 2.0 request object
+#[derive(Debug, Deserialize)]
+pub struct RpcRequest {
+    #[serde(default)]
+    pub jsonrpc: Option<String>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default)]
+    pub id: Value,
+}
+
+/// JSON-RPC 2.0 error object
+#[derive(Debug, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    /// Registered error code of a failed REST call, see `/api/v1/errors`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// JSON-RPC 2.0 response object
+#[derive(Debug, Serialize)]
+pub struct RpcResponse {
+    pub jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+impl RpcResponse {
+    fn success(id: Value, result: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            result: Some(result),
+            error: None,
+            id,
+        }
+    }
+
+    fn failure(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            result: None,
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+            id,
+        }
+    }
+}
+
+/// Check batch size limits
+fn validate_batch_size(len: usize) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if len == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                error_code::INVALID_REQUEST,
+                "Batch must contain at least one request",
+            )),
+        ));
+    }
+
+    if len > MAX_BATCH_SIZE {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(ErrorResponse::new(
+                error_code::PAYLOAD_TOO_LARGE,
+                format!("Batch size {} exceeds maximum of {}", len, MAX_BATCH_SIZE),
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Dispatch one batch item through the API router
+async fn dispatch(
+    api: Router,
+    authorization: Option<header::HeaderValue>,
+    item: BatchRequestItem,
+) -> BatchResponseItem {
+    if BATCH_ENDPOINTS.contains(&item.path.as_str()) {
+        return BatchResponseItem {
+            status: StatusCode::BAD_REQUEST.as_u16(),
+            body: json!({ "error": "Nested batch requests are not allowed" }),
+        };
+    }
+    let route = item.path.split('?').next().unwrap_or_default();
+    if STREAMING_ENDPOINTS.contains(&route) {
+        return BatchResponseItem {
+            status: StatusCode::BAD_REQUEST.as_u16(),
+            body: json!({ "error": "Streaming endpoints cannot be batched" }),
+        };
+    }
+
+    let method = match item.method.to_ascii_uppercase().as_str() {
+        "GET" => Method::GET,
+        "POST" => Method::POST,
+        other => {
+            return BatchResponseItem {
+                status: StatusCode::METHOD_NOT_ALLOWED.as_u16(),
+                body: json!({ "error": format!("Unsupported method in batch: {}", other) }),
+            }
+        }
+    };
+
+    let mut builder = Request::builder().method(method).uri(&item.path);
+    if let Some(auth) = authorization {
+        builder = builder.header(header::AUTHORIZATION, auth);
+    }
+
+    let request = match item.body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    };
+
+    let request = match request {
+        Ok(request) => request,
+        Err(e) => {
+            return BatchResponseItem {
+                status: StatusCode::BAD_REQUEST.as_u16(),
+                body: json!({ "error": format!("Invalid batch item: {}", e) }),
+            }
+        }
+    };
+
+    let response = match api.oneshot(request).await {
+        Ok(response) => response,
+        Err(never) => match never {},
+    };
+
+    let status = response.status().as_u16();
+    let body = match to_bytes(response.into_body(), MAX_ITEM_RESPONSE_BYTES).await {
+        Ok(bytes) if bytes.is_empty() => Value::Null,
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        Err(e) => json!({ "error": format!("Failed to read response: {}", e) }),
+    };
+
+    BatchResponseItem { status, body }
+}
+
+/// Execute batch items concurrently, returning results in request order
+async fn execute_batch(
+    api: &Router,
+    headers: &HeaderMap,
+    items: Vec<BatchRequestItem>,
+) -> Vec<BatchResponseItem> {
+    let items = items.into_iter().map(|item| (None, item)).collect();
+    execute_timed_batch(api, headers, items).await
+}
+
+/// Like [`execute_batch`], additionally timing items labelled with a JSON-RPC method
+async fn execute_timed_batch(
+    api: &Router,
+    headers: &HeaderMap,
+    items: Vec<(Option<String>, BatchRequestItem)>,
+) -> Vec<BatchResponseItem> {
+    let authorization = headers.get(header::AUTHORIZATION).cloned();
+
+    let handles: Vec<_> = items
+        .into_iter()
+        .map(|(rpc_method, item)| {
+            let dispatched = dispatch(api.clone(), authorization.clone(), item);
+            tokio::spawn(async move {
+                let Some(rpc_method) = rpc_method else {
+                    return dispatched.await;
+                };
+                let timer = RequestTimer::start(latency::RPC_SERVICE, &rpc_method, "POST");
+                let result = dispatched.instrument(timer.span().clone()).await;
+                timer.finish(result.status);
+                result
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(handles.len());
+    for handle in handles {
+        // A panicking item only fails its own slot
+        results.push(handle.await.unwrap_or_else(|e| BatchResponseItem {
+            status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+            body: json!({ "error": format!("Batch item failed: {}", e) }),
+        }));
+    }
+
+    results
+}
+
+/// POST /api/v1/batch - execute several REST calls in one round trip
+/// تنفيذ عدة طلبات REST في طلب واحد
+pub async fn rest_batch(
+    State(api): State<Router>,
+    headers: HeaderMap,
+    Json(items): Json<Vec<BatchRequestItem>>,
+) -> Result<Json<Vec<BatchResponseItem>>, (StatusCode, Json<ErrorResponse>)> {
+    validate_batch_size(items.len())?;
+
+    Ok(Json(execute_batch(&api, &headers, items).await))
+}
+
+/// Map a JSON-RPC call onto the equivalent REST request
+fn rpc_to_rest(request: &RpcRequest) -> Result<BatchRequestItem, RpcError> {
+    let get = |path: String| BatchRequestItem {
+        method: "GET".to_string(),
+        path,
+        body: None,
+    };
+
+    match request.method.as_str() {
+        "get_balance" => {
+            let address = address_param(request)?;
+            Ok(get(format!("/api/v1/account/{}/balance", address)))
+        }
+        "get_history" => {
+            let address = address_param(request)?;
+            let path = match request.params.get("limit").and_then(Value::as_u64) {
+                Some(limit) => format!("/api/v1/account/{}/history?limit={}", address, limit),
+                None => format!("/api/v1/account/{}/history", address),
+            };
+            Ok(get(path))
+        }
+        "get_blockchain_info" => Ok(get("/api/v1/blockchain/info".to_string())),
+        "get_mempool_status" => Ok(get("/api/v1/mempool/status".to_string())),
+        "getinfo" => Ok(get("/api/v1/node/info".to_string())),
+        "health" => Ok(get("/health".to_string())),
+        "submit_transaction" => {
+            if !request.params.is_object() {
+                return Err(RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: "submit_transaction requires a transaction object".to_string(),
+                    data: None,
+                });
+            }
+
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/transaction/submit".to_string(),
+                body: Some(request.params.clone()),
+            })
+        }
+        "decoderawtransaction" | "sendrawtransaction" | "simulaterawtransaction" => {
+            let raw = request
+                .params
+                .get("hex")
+                .or_else(|| request.params.get(0))
+                .and_then(Value::as_str)
+                .ok_or_else(|| RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: format!("{} requires a hex parameter", request.method),
+                    data: None,
+                })?;
+
+            let path = match request.method.as_str() {
+                "decoderawtransaction" => "/api/v1/transaction/decode",
+                "simulaterawtransaction" => "/api/v1/tx/simulate",
+                _ => "/api/v1/transaction/raw",
+            };
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: path.to_string(),
+                body: Some(json!({ "hex": raw })),
+            })
+        }
+        "verifymessage" => {
+            // Positional order follows `verifymessage address signature message`
+            let param = |name: &str, index: usize| {
+                request
+                    .params
+                    .get(name)
+                    .or_else(|| request.params.get(index))
+                    .and_then(Value::as_str)
+            };
+            let (Some(address), Some(signature), Some(message)) = (
+                param("address", 0),
+                param("signature", 1),
+                param("message", 2),
+            ) else {
+                return Err(RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: "verifymessage requires address, signature and message parameters"
+                        .to_string(),
+                    data: None,
+                });
+            };
+
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/message/verify".to_string(),
+                body: Some(json!({
+                    "address": address,
+                    "message": message,
+                    "signature": signature,
+                })),
+            })
+        }
+        "validateblocktemplate" => {
+            let block = request
+                .params
+                .get("block")
+                .or_else(|| request.params.get(0))
+                .filter(|block| block.is_object())
+                .ok_or_else(|| RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: "validateblocktemplate requires a block parameter".to_string(),
+                    data: None,
+                })?;
+
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/mining/validate-template".to_string(),
+                body: Some(json!({ "block": block })),
+            })
+        }
+        "getanchor" => {
+            let height = match request
+                .params
+                .get("height")
+                .or_else(|| request.params.get(0))
+            {
+                None | Some(Value::Null) => "latest".to_string(),
+                Some(height) => height
+                    .as_u64()
+                    .map(|h| h.to_string())
+                    .ok_or_else(|| RpcError {
+                        code: RPC_INVALID_PARAMS,
+                        message: "getanchor height must be a number".to_string(),
+                        data: None,
+                    })?,
+            };
+            Ok(get(format!("/api/v1/anchors/{}", height)))
+        }
+        "listanchors" => {
+            let path = match request
+                .params
+                .get("limit")
+                .or_else(|| request.params.get(0))
+                .and_then(Value::as_u64)
+            {
+                Some(limit) => format!("/api/v1/anchors?limit={}", limit),
+                None => "/api/v1/anchors".to_string(),
+            };
+            Ok(get(path))
+        }
+        "generatetoaddress" => {
+            let blocks = request
+                .params
+                .get("nblocks")
+                .or_else(|| request.params.get(0))
+                .and_then(Value::as_u64);
+            let address = request
+                .params
+                .get("address")
+                .or_else(|| request.params.get(1))
+                .and_then(Value::as_str);
+            let (Some(blocks), Some(address)) = (blocks, address) else {
+                return Err(RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: "generatetoaddress requires nblocks and address parameters"
+                        .to_string(),
+                    data: None,
+                });
+            };
+
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/regtest/generate".to_string(),
+                body: Some(json!({ "blocks": blocks, "address": address })),
+            })
+        }
+        "setmocktime" => {
+            let timestamp = request
+                .params
+                .get("timestamp")
+                .or_else(|| request.params.get(0))
+                .and_then(Value::as_u64)
+                .ok_or_else(|| RpcError {
+                    code: RPC_INVALID_PARAMS,
+                    message: "setmocktime requires a timestamp parameter".to_string(),
+                    data: None,
+                })?;
+
+            Ok(BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/regtest/mocktime".to_string(),
+                body: Some(json!({ "timestamp": timestamp })),
+            })
+        }
+        other => Err(RpcError {
+            code: RPC_METHOD_NOT_FOUND,
+            message: format!("Method not found: {}", other),
+            data: None,
+        }),
+    }
+}
+
+/// Hex address from `{"address": ...}` or the first positional parameter
+fn address_param(request: &RpcRequest) -> Result<&str, RpcError> {
+    let address = request
+        .params
+        .get("address")
+        .or_else(|| request.params.get(0))
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError {
+            code: RPC_INVALID_PARAMS,
+            message: format!("{} requires an address parameter", request.method),
+            data: None,
+        })?;
+
+    if !address.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(RpcError {
+            code: RPC_INVALID_PARAMS,
+            message: "Invalid address format".to_string(),
+            data: None,
+        });
+    }
+
+    Ok(address)
+}
+
+/// Convert a REST result into a JSON-RPC response
+fn to_rpc_response(id: Value, item: BatchResponseItem) -> RpcResponse {
+    if (200..300).contains(&item.status) {
+        return RpcResponse::success(id, item.body);
+    }
+
+    let message = item
+        .body
+        .get("error")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("Request failed with status {}", item.status));
+
+    let mut response = RpcResponse::failure(id, RPC_SERVER_ERROR - i64::from(item.status), message);
+    if let (Some(error), Some(body)) = (response.error.as_mut(), item.body.as_object()) {
+        if body.contains_key("code") {
+            let mut data = body.clone();
+            data.remove("error");
+            error.data = Some(Value::Object(data));
+        }
+    }
+    response
+}
+
+/// POST /api/v1/rpc - JSON-RPC 2.0 endpoint accepting single or batch calls
+/// نقطة نهاية JSON-RPC تقبل طلباً واحداً أو مجموعة طلبات
+pub async fn rpc(
+    State(api): State<Router>,
+    headers: HeaderMap,
+    Json(payload): Json<Value>,
+) -> Result<Json<Value>, (StatusCode, Json<ErrorResponse>)> {
+    let (calls, is_batch) = match payload {
+        Value::Array(calls) => {
+            validate_batch_size(calls.len())?;
+            (calls, true)
+        }
+        single => (vec![single], false),
+    };
+
+    // Parse every call up front so malformed entries keep their slot
+    let mut responses: Vec<Option<RpcResponse>> = Vec::with_capacity(calls.len());
+    let mut pending = Vec::new();
+
+    for (index, call) in calls.into_iter().enumerate() {
+        let id = call.get("id").cloned().unwrap_or(Value::Null);
+        let parsed = serde_json::from_value::<RpcRequest>(call).map_err(|e| RpcError {
+            code: RPC_INVALID_REQUEST,
+            message: format!("Invalid request: {}", e),
+            data: None,
+        });
+
+        let rest = parsed.and_then(|request| {
+            if request.jsonrpc.as_deref().is_some_and(|v| v != "2.0") {
+                return Err(RpcError {
+                    code: RPC_INVALID_REQUEST,
+                    message: "Unsupported jsonrpc version".to_string(),
+                    data: None,
+                });
+            }
+            rpc_to_rest(&request).map(|item| (request.id, (Some(request.method), item)))
+        });
+
+        match rest {
+            Ok((id, item)) => {
+                responses.push(None);
+                pending.push((index, id, item));
+            }
+            Err(error) => responses.push(Some(RpcResponse::failure(id, error.code, error.message))),
+        }
+    }
+
+    let (slots, ids_items): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .map(|(i, id, item)| (i, (id, item)))
+        .unzip();
+    let (ids, items): (Vec<_>, Vec<_>) = ids_items.into_iter().unzip();
+
+    let results = execute_timed_batch(&api, &headers, items).await;
+    for ((slot, id), result) in slots.into_iter().zip(ids).zip(results) {
+        responses[slot] = Some(to_rpc_response(id, result));
+    }
+
+    let responses: Vec<Value> = responses
+        .into_iter()
+        .flatten()
+        .map(|r| serde_json::to_value(r).unwrap_or(Value::Null))
+        .collect();
+
+    if is_batch {
+        Ok(Json(Value::Array(responses)))
+    } else {
+        Ok(Json(responses.into_iter().next().unwrap_or(Value::Null)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::routing::get;
+
+    fn test_router() -> Router {
+        let api: Router = Router::new()
+            .route(
+                "/health",
+                get(|| async { Json(json!({ "status": "healthy" })) }),
+            )
+            .route(
+                "/api/v1/blockchain/info",
+                get(|| async {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({ "error": "storage offline" })),
+                    )
+                }),
+            );
+
+        let batch_routes = Router::new()
+            .route("/api/v1/batch", axum::routing::post(rest_batch))
+            .route("/api/v1/rpc", axum::routing::post(rpc))
+            .with_state(api.clone());
+
+        api.merge(batch_routes)
+    }
+
+    #[tokio::test]
+    async fn test_batch_preserves_order_and_isolates_errors() {
+        let api = test_router();
+        let items = vec![
+            BatchRequestItem {
+                method: "GET".to_string(),
+                path: "/api/v1/blockchain/info".to_string(),
+                body: None,
+            },
+            BatchRequestItem {
+                method: "GET".to_string(),
+                path: "/health".to_string(),
+                body: None,
+            },
+            BatchRequestItem {
+                method: "DELETE".to_string(),
+                path: "/health".to_string(),
+                body: None,
+            },
+        ];
+
+        let results = execute_batch(&api, &HeaderMap::new(), items).await;
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].status, 500);
+        assert_eq!(results[1].status, 200);
+        assert_eq!(results[1].body["status"], "healthy");
+        assert_eq!(results[2].status, 405);
+    }
+
+    #[tokio::test]
+    async fn test_nested_batch_rejected() {
+        let api = test_router();
+        let items = vec![
+            BatchRequestItem {
+                method: "POST".to_string(),
+                path: "/api/v1/batch".to_string(),
+                body: Some(json!([])),
+            },
+            BatchRequestItem {
+                method: "GET".to_string(),
+                path: "/api/v1/events?addresses=abcd".to_string(),
+                body: None,
+            },
+        ];
+
+        let results = execute_batch(&api, &HeaderMap::new(), items).await;
+        assert_eq!(results[0].status, 400);
+        assert_eq!(results[1].status, 400);
+    }
+
+    #[test]
+    fn test_batch_size_limits() {
+        assert!(validate_batch_size(0).is_err());
+        assert!(validate_batch_size(1).is_ok());
+        assert!(validate_batch_size(MAX_BATCH_SIZE).is_ok());
+        assert!(validate_batch_size(MAX_BATCH_SIZE + 1).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rpc_batch_mixed_results() {
+        let api = test_router();
+        let payload = json!([
+            { "jsonrpc": "2.0", "method": "health", "id": 1 },
+            { "jsonrpc": "2.0", "method": "unknown_method", "id": 2 },
+            { "jsonrpc": "2.0", "method": "get_blockchain_info", "id": 3 },
+            { "jsonrpc": "2.0", "id": 4 }
+        ]);
+
+        let Json(response) = rpc(State(api), HeaderMap::new(), Json(payload))
+            .await
+            .unwrap();
+        let responses = response.as_array().unwrap();
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["status"], "healthy");
+        assert_eq!(responses[1]["error"]["code"], RPC_METHOD_NOT_FOUND);
+        assert_eq!(responses[2]["error"]["message"], "storage offline");
+        assert_eq!(responses[3]["error"]["code"], RPC_INVALID_REQUEST);
+    }
+
+    #[test]
+    fn test_rpc_error_carries_error_code() {
+        let body = serde_json::to_value(ErrorResponse::new(
+            error_code::FEE_TOO_LOW,
+            "Failed to submit transaction",
+        ))
+        .unwrap();
+        let response = to_rpc_response(json!(7), BatchResponseItem { status: 400, body });
+        let error = serde_json::to_value(response.error.unwrap()).unwrap();
+        assert_eq!(error["code"], RPC_SERVER_ERROR - 400);
+        assert_eq!(error["message"], "Failed to submit transaction");
+        assert_eq!(error["data"]["code"], "fee_too_low");
+        assert_eq!(error["data"]["number"], 3003);
+        assert_eq!(error["data"]["retryable"], false);
+    }
+
+    #[test]
+    fn test_rpc_get_balance_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "get_balance".to_string(),
+            params: json!(["abcd"]),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/account/abcd/balance");
+
+        let request = RpcRequest {
+            params: json!({ "address": "../health" }),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_rpc_get_history_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "get_history".to_string(),
+            params: json!({ "address": "abcd", "limit": 10 }),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/account/abcd/history?limit=10");
+
+        let request = RpcRequest {
+            params: json!([]),
+            ..request
+        };
+        let error = rpc_to_rest(&request).unwrap_err();
+        assert_eq!(error.code, RPC_INVALID_PARAMS);
+        assert_eq!(error.message, "get_history requires an address parameter");
+    }
+
+    #[test]
+    fn test_rpc_getinfo() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "getinfo".to_string(),
+            params: Value::Null,
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.method, "GET");
+        assert_eq!(item.path, "/api/v1/node/info");
+    }
+
+    #[test]
+    fn test_rpc_anchor_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "getanchor".to_string(),
+            params: json!([1000]),
+            id: json!(1),
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap().path, "/api/v1/anchors/1000");
+
+        let request = RpcRequest {
+            params: Value::Null,
+            ..request
+        };
+        assert_eq!(
+            rpc_to_rest(&request).unwrap().path,
+            "/api/v1/anchors/latest"
+        );
+
+        let request = RpcRequest {
+            params: json!({ "height": "../health" }),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+
+        let request = RpcRequest {
+            method: "listanchors".to_string(),
+            params: json!({ "limit": 5 }),
+            ..request
+        };
+        assert_eq!(
+            rpc_to_rest(&request).unwrap().path,
+            "/api/v1/anchors?limit=5"
+        );
+    }
+
+    #[test]
+    fn test_rpc_raw_transaction_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "sendrawtransaction".to_string(),
+            params: json!(["01abcd"]),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.method, "POST");
+        assert_eq!(item.path, "/api/v1/transaction/raw");
+        assert_eq!(item.body, Some(json!({ "hex": "01abcd" })));
+
+        let request = RpcRequest {
+            method: "simulaterawtransaction".to_string(),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap().path, "/api/v1/tx/simulate");
+
+        let request = RpcRequest {
+            method: "decoderawtransaction".to_string(),
+            params: json!({ "hex": "01abcd" }),
+            ..request
+        };
+        assert_eq!(
+            rpc_to_rest(&request).unwrap().path,
+            "/api/v1/transaction/decode"
+        );
+
+        let request = RpcRequest {
+            params: json!({}),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_rpc_verify_message_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "verifymessage".to_string(),
+            params: json!(["abcd", "01ff", "hello"]),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/message/verify");
+        let body = json!({ "address": "abcd", "message": "hello", "signature": "01ff" });
+        assert_eq!(item.body, Some(body.clone()));
+
+        let request = RpcRequest {
+            params: body,
+            ..request
+        };
+        assert_eq!(
+            rpc_to_rest(&request).unwrap().path,
+            "/api/v1/message/verify"
+        );
+
+        let request = RpcRequest {
+            params: json!(["abcd"]),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_rpc_validate_block_template_params() {
+        let block = json!({ "header": {}, "transactions": [] });
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "validateblocktemplate".to_string(),
+            params: json!([block]),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/mining/validate-template");
+        assert_eq!(item.body, Some(json!({ "block": block })));
+
+        let request = RpcRequest {
+            params: json!({ "block": block }),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap().body, item.body);
+
+        let request = RpcRequest {
+            params: json!(["00ff"]),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_rpc_regtest_params() {
+        let request = RpcRequest {
+            jsonrpc: Some("2.0".to_string()),
+            method: "generatetoaddress".to_string(),
+            params: json!([101, "abcd"]),
+            id: json!(1),
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/regtest/generate");
+        assert_eq!(item.body, Some(json!({ "blocks": 101, "address": "abcd" })));
+
+        let request = RpcRequest {
+            params: json!({ "nblocks": 1 }),
+            ..request
+        };
+        assert_eq!(rpc_to_rest(&request).unwrap_err().code, RPC_INVALID_PARAMS);
+
+        let request = RpcRequest {
+            method: "setmocktime".to_string(),
+            params: json!([1_760_000_000]),
+            ..request
+        };
+        let item = rpc_to_rest(&request).unwrap();
+        assert_eq!(item.path, "/api/v1/regtest/mocktime");
+        assert_eq!(item.body, Some(json!({ "timestamp": 1_760_000_000 })));
+    }
+}
@@ -0,0 +1,1095 @@
+//! Daily activity digests by webhook or email
+//! ملخصات النشاط اليومية عبر الويب هوك أو البريد الإلكتروني
+//!
+//! A subscription lists up to [`MAX_DIGEST_ADDRESSES`] addresses and a
+//! delivery target. Once a day, at `send_hour` in the subscription's UTC
+//! offset, the scheduler totals what each address received, sent and paid
+//! in fees over the previous 24 hours and delivers one summary with the
+//! transaction list, newest first. Webhooks receive the summary as JSON;
+//! email goes through the SMTP relay given with `--digest-smtp-relay` as
+//! plain text.
+//!
+//! The relay is spoken to without TLS or authentication, so it should be a
+//! local mail server that forwards on the node's behalf. A delivery that
+//! fails is retried [`MAX_DELIVERY_ATTEMPTS`] times, then that day is
+//! skipped. Days missed while the server was down are not sent late; the
+//! next digest covers the 24 hours before it.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use opensyria_core::{clock::system_time, crypto::PublicKey, Block, UNITS_PER_LIRA};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::{
+    auth::{ApiKey, Permission},
+    events::parse_addresses,
+    models::ErrorResponse,
+    AppState,
+};
+
+/// Length of the period a digest covers
+pub const DIGEST_PERIOD_SECS: u64 = 86_400;
+
+/// Most addresses in one subscription
+pub const MAX_DIGEST_ADDRESSES: usize = 20;
+
+/// Most subscriptions per API key
+pub const MAX_SUBSCRIPTIONS_PER_KEY: usize = 10;
+
+/// Most transactions listed in one digest; totals still count the rest
+pub const MAX_DIGEST_TRANSACTIONS: usize = 200;
+
+/// Deliveries tried per digest before that day is skipped
+pub const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+
+/// Wait before retrying a failed delivery
+pub const RETRY_DELAY_SECS: u64 = 600;
+
+/// Interval between checks for due digests
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Blocks scanned back from the tip for one digest
+const MAX_SCAN_BLOCKS: u64 = 20_000;
+
+/// Largest UTC offset accepted, in seconds
+const MAX_UTC_OFFSET_SECS: i64 = 14 * 3600;
+
+/// Where a digest is delivered
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DigestDelivery {
+    /// POST the digest as JSON
+    Webhook { url: String },
+    /// Send the digest as a plain text email
+    Email { to: String },
+}
+
+/// A daily digest subscription
+/// اشتراك في الملخص اليومي
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestSubscription {
+    pub id: String,
+    /// ID of the API key that created it
+    pub owner: String,
+    /// Hex addresses summarized
+    pub addresses: Vec<String>,
+    pub delivery: DigestDelivery,
+    /// UTC offset the send hour is in, e.g. `+03:00`
+    pub timezone: String,
+    /// Local hour (0-23) the digest is sent at
+    pub send_hour: u8,
+    /// End of the period the next digest covers
+    pub next_due: u64,
+    /// Failed deliveries of the next digest
+    #[serde(default)]
+    pub attempts: u32,
+    /// When a failed delivery is retried
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<u64>,
+    pub last_sent_at: Option<u64>,
+    pub last_error: Option<String>,
+    pub created_at: u64,
+}
+
+impl DigestSubscription {
+    fn watched(&self) -> Vec<PublicKey> {
+        self.addresses
+            .iter()
+            .filter_map(|address| PublicKey::from_hex(address).ok())
+            .collect()
+    }
+}
+
+/// Activity of one address over a digest period
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AddressSummary {
+    pub address: String,
+    /// Units received, block rewards included
+    pub received: u64,
+    /// Units sent
+    pub sent: u64,
+    /// Units paid in fees, for itself or as a sponsor
+    pub fees_paid: u64,
+    pub transactions: usize,
+}
+
+/// A confirmed transaction in a digest
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DigestTransaction {
+    pub hash: String,
+    pub block_height: u64,
+    pub timestamp: u64,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+/// Summary of the watched addresses over one period
+/// ملخص نشاط العناوين خلال فترة
+#[derive(Debug, Clone, Serialize)]
+pub struct Digest {
+    pub subscription_id: String,
+    pub period_start: u64,
+    pub period_end: u64,
+    pub timezone: String,
+    pub addresses: Vec<AddressSummary>,
+    /// Newest first, at most [`MAX_DIGEST_TRANSACTIONS`]
+    pub transactions: Vec<DigestTransaction>,
+    /// Whether older transactions were left out of the list
+    pub truncated: bool,
+}
+
+impl Digest {
+    fn new(subscription: &DigestSubscription, period_end: u64) -> Self {
+        Self {
+            subscription_id: subscription.id.clone(),
+            period_start: period_end.saturating_sub(DIGEST_PERIOD_SECS),
+            period_end,
+            timezone: subscription.timezone.clone(),
+            addresses: subscription
+                .addresses
+                .iter()
+                .map(|address| AddressSummary {
+                    address: address.clone(),
+                    ..AddressSummary::default()
+                })
+                .collect(),
+            transactions: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    /// Count a block's transactions touching the watched addresses; blocks
+    /// are added newest first
+    fn add_block(&mut self, height: u64, block: &Block, watched: &HashMap<PublicKey, usize>) {
+        for tx in block.transactions.iter().rev() {
+            let fee_payer = tx
+                .sponsor
+                .as_ref()
+                .map_or(&tx.from, |sponsor| &sponsor.payer);
+            let mut touched = HashSet::new();
+            if let Some(&index) = watched.get(&tx.to) {
+                self.addresses[index].received += tx.amount;
+                touched.insert(index);
+            }
+            if let Some(&index) = watched.get(&tx.from) {
+                if !tx.is_coinbase() {
+                    self.addresses[index].sent += tx.amount;
+                    touched.insert(index);
+                }
+            }
+            if let Some(&index) = watched.get(fee_payer) {
+                if !tx.is_coinbase() {
+                    self.addresses[index].fees_paid += tx.fee;
+                    touched.insert(index);
+                }
+            }
+            if touched.is_empty() {
+                continue;
+            }
+            for index in touched {
+                self.addresses[index].transactions += 1;
+            }
+            if self.transactions.len() == MAX_DIGEST_TRANSACTIONS {
+                self.truncated = true;
+                continue;
+            }
+            self.transactions.push(DigestTransaction {
+                hash: hex::encode(tx.hash()),
+                block_height: height,
+                timestamp: block.header.timestamp,
+                from: tx.from.to_hex(),
+                to: tx.to.to_hex(),
+                amount: tx.amount,
+                fee: tx.fee,
+            });
+        }
+    }
+
+    /// Email subject line
+    pub fn subject(&self) -> String {
+        format!(
+            "OpenSyria daily summary to {}",
+            format_time(self.period_end, &self.timezone)
+        )
+    }
+
+    /// Plain text rendering for email
+    pub fn to_text(&self) -> String {
+        let mut text = format!(
+            "Activity from {} to {}\n\n",
+            format_time(self.period_start, &self.timezone),
+            format_time(self.period_end, &self.timezone)
+        );
+        for summary in &self.addresses {
+            text.push_str(&format!(
+                "{}\n  received {}, sent {}, fees {}, {} transaction(s)\n",
+                summary.address,
+                format_lira(summary.received),
+                format_lira(summary.sent),
+                format_lira(summary.fees_paid),
+                summary.transactions
+            ));
+        }
+        if self.transactions.is_empty() {
+            text.push_str("\nNo transactions.\n");
+            return text;
+        }
+        text.push_str("\nTransactions, newest first:\n");
+        for tx in &self.transactions {
+            text.push_str(&format!(
+                "{}  {} -> {}  {} (fee {})  block {}  {}\n",
+                format_time(tx.timestamp, &self.timezone),
+                tx.from,
+                tx.to,
+                format_lira(tx.amount),
+                format_lira(tx.fee),
+                tx.block_height,
+                tx.hash
+            ));
+        }
+        if self.truncated {
+            text.push_str(&format!(
+                "Only the newest {} transactions are listed.\n",
+                MAX_DIGEST_TRANSACTIONS
+            ));
+        }
+        text
+    }
+}
+
+/// SMTP relay email digests are sent through
+#[derive(Debug, Clone)]
+pub struct SmtpRelay {
+    /// `host:port` of the relay
+    pub address: String,
+    /// Sender address
+    pub from: String,
+}
+
+/// Delivery settings
+#[derive(Debug, Clone)]
+pub struct DigestConfig {
+    /// Relay for email digests; email subscriptions are refused without one
+    pub smtp: Option<SmtpRelay>,
+    /// Timeout for each webhook request or SMTP session
+    pub request_timeout: Duration,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            smtp: None,
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Digest subscriptions and their delivery
+/// جدولة الملخصات اليومية وإرسالها
+pub struct DigestScheduler {
+    config: DigestConfig,
+    http: reqwest::Client,
+    subscriptions: RwLock<HashMap<String, DigestSubscription>>,
+    /// JSON file subscriptions are kept in; `None` keeps them in memory
+    path: Option<PathBuf>,
+}
+
+impl DigestScheduler {
+    /// In-memory scheduler
+    pub fn new(config: DigestConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            http,
+            subscriptions: RwLock::new(HashMap::new()),
+            path: None,
+        }
+    }
+
+    /// Scheduler keeping subscriptions in a JSON file, loading any already
+    /// there
+    pub fn open(config: DigestConfig, path: PathBuf) -> anyhow::Result<Self> {
+        let subscriptions: Vec<DigestSubscription> = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let scheduler = Self {
+            path: Some(path),
+            ..Self::new(config)
+        };
+        *scheduler.subscriptions.try_write().expect("not yet shared") = subscriptions
+            .into_iter()
+            .map(|subscription| (subscription.id.clone(), subscription))
+            .collect();
+        Ok(scheduler)
+    }
+
+    /// Validate and store a subscription
+    /// إنشاء اشتراك جديد
+    pub async fn subscribe(
+        &self,
+        owner: &str,
+        request: CreateDigestRequest,
+    ) -> Result<DigestSubscription, String> {
+        let addresses = parse_addresses(&request.addresses.join(","))?;
+        if addresses.len() > MAX_DIGEST_ADDRESSES {
+            return Err(format!(
+                "Too many addresses: {} (max {})",
+                addresses.len(),
+                MAX_DIGEST_ADDRESSES
+            ));
+        }
+        let delivery = match (request.webhook_url, request.email) {
+            (Some(url), None) => {
+                let parsed = reqwest::Url::parse(&url).map_err(|_| "Invalid webhook URL")?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err("Webhook URL must be http or https".to_string());
+                }
+                DigestDelivery::Webhook { url }
+            }
+            (None, Some(to)) => {
+                if self.config.smtp.is_none() {
+                    return Err("Email digests are not enabled on this server".to_string());
+                }
+                if !is_valid_email(&to) {
+                    return Err(format!("Invalid email address: {}", to));
+                }
+                DigestDelivery::Email { to }
+            }
+            _ => return Err("Give exactly one of webhook_url and email".to_string()),
+        };
+        let (timezone, offset) = schedule(request.timezone.as_deref(), request.send_hour)?;
+
+        let now = system_time();
+        let subscription = DigestSubscription {
+            id: format!("digest_{}", hex::encode(rand::random::<[u8; 8]>())),
+            owner: owner.to_string(),
+            addresses: addresses.iter().map(PublicKey::to_hex).collect(),
+            delivery,
+            timezone,
+            send_hour: request.send_hour,
+            next_due: next_delivery(now, offset, request.send_hour),
+            attempts: 0,
+            retry_at: None,
+            last_sent_at: None,
+            last_error: None,
+            created_at: now,
+        };
+
+        let mut subscriptions = self.subscriptions.write().await;
+        let owned = subscriptions.values().filter(|s| s.owner == owner).count();
+        if owned >= MAX_SUBSCRIPTIONS_PER_KEY {
+            return Err(format!(
+                "At most {} digest subscriptions per API key",
+                MAX_SUBSCRIPTIONS_PER_KEY
+            ));
+        }
+        subscriptions.insert(subscription.id.clone(), subscription.clone());
+        self.save(&subscriptions);
+        Ok(subscription)
+    }
+
+    /// Change when a subscription is delivered
+    pub async fn reschedule(
+        &self,
+        id: &str,
+        timezone: Option<&str>,
+        send_hour: u8,
+    ) -> Result<Option<DigestSubscription>, String> {
+        let (timezone, offset) = schedule(timezone, send_hour)?;
+        let mut subscriptions = self.subscriptions.write().await;
+        let Some(subscription) = subscriptions.get_mut(id) else {
+            return Ok(None);
+        };
+        subscription.timezone = timezone;
+        subscription.send_hour = send_hour;
+        subscription.next_due = next_delivery(system_time(), offset, send_hour);
+        subscription.attempts = 0;
+        subscription.retry_at = None;
+        let updated = subscription.clone();
+        self.save(&subscriptions);
+        Ok(Some(updated))
+    }
+
+    pub async fn get(&self, id: &str) -> Option<DigestSubscription> {
+        self.subscriptions.read().await.get(id).cloned()
+    }
+
+    /// Subscriptions of one API key, or all of them for `None`
+    pub async fn list(&self, owner: Option<&str>) -> Vec<DigestSubscription> {
+        let mut subscriptions: Vec<_> = self
+            .subscriptions
+            .read()
+            .await
+            .values()
+            .filter(|s| owner.is_none_or(|owner| s.owner == owner))
+            .cloned()
+            .collect();
+        subscriptions.sort_by_key(|s| s.created_at);
+        subscriptions
+    }
+
+    pub async fn remove(&self, id: &str) -> bool {
+        let mut subscriptions = self.subscriptions.write().await;
+        let removed = subscriptions.remove(id).is_some();
+        if removed {
+            self.save(&subscriptions);
+        }
+        removed
+    }
+
+    /// Build and deliver every digest that is due
+    pub async fn run_due(&self, state: &AppState) {
+        let now = system_time();
+        let due: Vec<_> = self
+            .subscriptions
+            .read()
+            .await
+            .values()
+            .filter(|s| s.retry_at.unwrap_or(s.next_due) <= now)
+            .cloned()
+            .collect();
+
+        for subscription in due {
+            let period_end = latest_due(subscription.next_due, now);
+            let result = match collect_digest(state, &subscription, period_end).await {
+                Ok(digest) => self.deliver(&subscription.delivery, &digest).await,
+                Err(e) => Err(e),
+            };
+            self.record(&subscription.id, period_end, result).await;
+        }
+    }
+
+    /// Check for due digests every [`CHECK_INTERVAL`] until the state is
+    /// dropped
+    pub fn spawn_scheduler(self: &Arc<Self>, state: &Arc<AppState>) -> JoinHandle<()> {
+        let state: Weak<AppState> = Arc::downgrade(state);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+                state.digests.run_due(&state).await;
+            }
+        })
+    }
+
+    async fn deliver(&self, delivery: &DigestDelivery, digest: &Digest) -> Result<(), String> {
+        match delivery {
+            DigestDelivery::Webhook { url } => {
+                let response = self
+                    .http
+                    .post(url)
+                    .header("X-OpenSyria-Event", "daily_digest")
+                    .json(digest)
+                    .send()
+                    .await
+                    .map_err(|e| e.to_string())?;
+                if !response.status().is_success() {
+                    return Err(format!("webhook returned {}", response.status()));
+                }
+                Ok(())
+            }
+            DigestDelivery::Email { to } => {
+                let relay = self
+                    .config
+                    .smtp
+                    .as_ref()
+                    .ok_or("no SMTP relay configured")?;
+                tokio::time::timeout(
+                    self.config.request_timeout,
+                    send_email(relay, to, &digest.subject(), &digest.to_text()),
+                )
+                .await
+                .map_err(|_| "SMTP relay timed out".to_string())?
+            }
+        }
+    }
+
+    /// Move a subscription on after a delivery attempt
+    async fn record(&self, id: &str, period_end: u64, result: Result<(), String>) {
+        let now = system_time();
+        let mut subscriptions = self.subscriptions.write().await;
+        // Removed while the digest was being delivered
+        let Some(subscription) = subscriptions.get_mut(id) else {
+            return;
+        };
+        match result {
+            Ok(()) => {
+                subscription.last_sent_at = Some(now);
+                subscription.last_error = None;
+            }
+            Err(e) => {
+                tracing::warn!("Digest {} delivery failed: {}", id, e);
+                subscription.last_error = Some(e);
+                subscription.attempts += 1;
+                if subscription.attempts < MAX_DELIVERY_ATTEMPTS {
+                    subscription.retry_at = Some(now + RETRY_DELAY_SECS);
+                    self.save(&subscriptions);
+                    return;
+                }
+            }
+        }
+        subscription.attempts = 0;
+        subscription.retry_at = None;
+        subscription.next_due = period_end + DIGEST_PERIOD_SECS;
+        self.save(&subscriptions);
+    }
+
+    fn save(&self, subscriptions: &HashMap<String, DigestSubscription>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut list: Vec<_> = subscriptions.values().collect();
+        list.sort_by_key(|s| s.created_at);
+        let tmp = path.with_extension("json.tmp");
+        let result = serde_json::to_string_pretty(&list)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&tmp, json))
+            .and_then(|_| std::fs::rename(&tmp, path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save digest subscriptions: {}", e);
+        }
+    }
+}
+
+/// Summarize a subscription's addresses over the 24 hours before
+/// `period_end`
+async fn collect_digest(
+    state: &AppState,
+    subscription: &DigestSubscription,
+    period_end: u64,
+) -> Result<Digest, String> {
+    let mut digest = Digest::new(subscription, period_end);
+    let watched: HashMap<PublicKey, usize> = subscription
+        .watched()
+        .into_iter()
+        .enumerate()
+        .map(|(index, address)| (address, index))
+        .collect();
+
+    let node = state.node.read().await;
+    let blockchain = node.get_blockchain();
+    let tip = blockchain
+        .get_chain_height()
+        .map_err(|e| format!("failed to read chain height: {}", e))?;
+    for height in (tip.saturating_sub(MAX_SCAN_BLOCKS)..=tip).rev() {
+        let block = match blockchain.get_block_by_height(height) {
+            Ok(Some(block)) => block,
+            Ok(None) => continue,
+            Err(e) => return Err(format!("failed to read block {}: {}", height, e)),
+        };
+        if block.header.timestamp < digest.period_start {
+            break;
+        }
+        if block.header.timestamp < period_end {
+            digest.add_block(height, &block, &watched);
+        }
+    }
+    Ok(digest)
+}
+
+/// Send a plain text email through an SMTP relay
+async fn send_email(relay: &SmtpRelay, to: &str, subject: &str, body: &str) -> Result<(), String> {
+    let stream = TcpStream::connect(&relay.address)
+        .await
+        .map_err(|e| format!("failed to connect to SMTP relay: {}", e))?;
+    let mut smtp = BufReader::new(stream);
+    smtp_reply(&mut smtp, 220).await?;
+    smtp_command(&mut smtp, "EHLO opensyria-wallet-api", 250).await?;
+    smtp_command(&mut smtp, &format!("MAIL FROM:<{}>", relay.from), 250).await?;
+    smtp_command(&mut smtp, &format!("RCPT TO:<{}>", to), 250).await?;
+    smtp_command(&mut smtp, "DATA", 354).await?;
+
+    let date = chrono::Utc::now().to_rfc2822();
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=utf-8\r\n\r\n",
+        relay.from, to, subject, date
+    );
+    for line in body.lines() {
+        // Dot-stuffing keeps a line of "." from ending the message
+        if line.starts_with('.') {
+            message.push('.');
+        }
+        message.push_str(line);
+        message.push_str("\r\n");
+    }
+    message.push_str(".\r\n");
+    smtp.get_mut()
+        .write_all(message.as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    smtp_reply(&mut smtp, 250).await?;
+    smtp_command(&mut smtp, "QUIT", 221).await.ok();
+    Ok(())
+}
+
+async fn smtp_command(
+    smtp: &mut BufReader<TcpStream>,
+    command: &str,
+    expected: u16,
+) -> Result<(), String> {
+    smtp.get_mut()
+        .write_all(format!("{}\r\n", command).as_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+    smtp_reply(smtp, expected).await
+}
+
+/// Read a possibly multi-line reply and check its code
+async fn smtp_reply(smtp: &mut BufReader<TcpStream>, expected: u16) -> Result<(), String> {
+    loop {
+        let mut line = String::new();
+        if smtp.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("SMTP relay closed the connection".to_string());
+        }
+        let code: u16 = line
+            .get(..3)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| format!("invalid SMTP reply: {}", line.trim_end()))?;
+        // "250-" continues a multi-line reply, "250 " ends it
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        if code != expected && !(expected == 250 && code == 251) {
+            return Err(format!("SMTP relay replied {}", line.trim_end()));
+        }
+        return Ok(());
+    }
+}
+
+/// Plain address without display names, whitespace or header-breaking
+/// characters
+fn is_valid_email(address: &str) -> bool {
+    let Some((local, domain)) = address.split_once('@') else {
+        return false;
+    };
+    address.len() <= 254
+        && !local.is_empty()
+        && domain.contains('.')
+        && !domain.contains('@')
+        && !address
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || c == '<' || c == '>')
+}
+
+/// Parse `UTC`, `Z`, `+03:00`, `-0530` or `+3` into seconds east of UTC
+pub fn parse_utc_offset(timezone: &str) -> Option<i64> {
+    let timezone = timezone.trim();
+    if timezone.eq_ignore_ascii_case("utc") || timezone == "Z" {
+        return Some(0);
+    }
+    let (sign, rest) = match timezone.as_bytes().first()? {
+        b'+' => (1, &timezone[1..]),
+        b'-' => (-1, &timezone[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match rest.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if rest.len() == 4 => rest.split_at(2),
+        None => (rest, "0"),
+    };
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    if !(0..60).contains(&minutes) {
+        return None;
+    }
+    let offset = sign * (hours * 3600 + minutes * 60);
+    (offset.abs() <= MAX_UTC_OFFSET_SECS).then_some(offset)
+}
+
+/// Normalized timezone and its offset for a requested schedule
+fn schedule(timezone: Option<&str>, send_hour: u8) -> Result<(String, i64), String> {
+    if send_hour > 23 {
+        return Err("send_hour must be 0-23".to_string());
+    }
+    let timezone = timezone.unwrap_or("UTC");
+    let offset = parse_utc_offset(timezone).ok_or_else(|| {
+        format!(
+            "Invalid timezone {}; use a UTC offset like +03:00",
+            timezone
+        )
+    })?;
+    let name = format!(
+        "{}{:02}:{:02}",
+        if offset < 0 { '-' } else { '+' },
+        offset.abs() / 3600,
+        offset.abs() % 3600 / 60
+    );
+    Ok((name, offset))
+}
+
+/// First time after `now` that the local clock at `offset` reads
+/// `send_hour`:00
+pub fn next_delivery(now: u64, offset: i64, send_hour: u8) -> u64 {
+    let local = now as i64 + offset;
+    let period = DIGEST_PERIOD_SECS as i64;
+    let mut due = local - local.rem_euclid(period) + send_hour as i64 * 3600;
+    if due <= local {
+        due += period;
+    }
+    (due - offset) as u64
+}
+
+/// Latest scheduled time at or before `now`, skipping days missed while the
+/// server was down
+fn latest_due(next_due: u64, now: u64) -> u64 {
+    if now <= next_due {
+        return next_due;
+    }
+    next_due + (now - next_due) / DIGEST_PERIOD_SECS * DIGEST_PERIOD_SECS
+}
+
+fn format_time(timestamp: u64, timezone: &str) -> String {
+    let offset = parse_utc_offset(timezone)
+        .and_then(|offset| chrono::FixedOffset::east_opt(offset as i32))
+        .unwrap_or(chrono::FixedOffset::east_opt(0).expect("zero offset"));
+    chrono::DateTime::from_timestamp(timestamp as i64, 0)
+        .map(|time| {
+            time.with_timezone(&offset)
+                .format("%Y-%m-%d %H:%M %:z")
+                .to_string()
+        })
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+fn format_lira(units: u64) -> String {
+    format!(
+        "{}.{:06} SYL",
+        units / UNITS_PER_LIRA,
+        units % UNITS_PER_LIRA
+    )
+}
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// Subscription owned by the caller, or any for admin keys
+async fn owned(
+    state: &AppState,
+    key: &ApiKey,
+    id: &str,
+) -> Result<DigestSubscription, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .digests
+        .get(id)
+        .await
+        .filter(|s| s.owner == key.id || key.permissions.contains(&Permission::Admin))
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "No digest subscription with that ID"))
+}
+
+fn default_send_hour() -> u8 {
+    8
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDigestRequest {
+    /// Hex addresses to summarize
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub email: Option<String>,
+    /// UTC offset, e.g. `+03:00`; defaults to UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// Local hour to send at; defaults to 8
+    #[serde(default = "default_send_hour")]
+    pub send_hour: u8,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleRequest {
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default = "default_send_hour")]
+    pub send_hour: u8,
+}
+
+/// POST /api/v1/digests - subscribe to a daily digest
+/// الاشتراك في الملخص اليومي
+pub async fn create_digest(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Json(request): Json<CreateDigestRequest>,
+) -> ApiResult<DigestSubscription> {
+    state
+        .digests
+        .subscribe(&key.id, request)
+        .await
+        .map(Json)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))
+}
+
+/// GET /api/v1/digests - the caller's subscriptions, or all for admin keys
+pub async fn list_digests(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+) -> Json<Vec<DigestSubscription>> {
+    let owner = (!key.permissions.contains(&Permission::Admin)).then_some(key.id.as_str());
+    Json(state.digests.list(owner).await)
+}
+
+/// PUT /api/v1/digests/{id}/schedule - change the timezone or send hour
+pub async fn reschedule_digest(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(id): Path<String>,
+    Json(request): Json<ScheduleRequest>,
+) -> ApiResult<DigestSubscription> {
+    owned(&state, &key, &id).await?;
+    state
+        .digests
+        .reschedule(&id, request.timezone.as_deref(), request.send_hour)
+        .await
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))?
+        .map(Json)
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "No digest subscription with that ID"))
+}
+
+/// GET /api/v1/digests/{id}/preview - the digest of the last 24 hours
+pub async fn preview_digest(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(id): Path<String>,
+) -> ApiResult<Digest> {
+    let subscription = owned(&state, &key, &id).await?;
+    collect_digest(&state, &subscription, system_time())
+        .await
+        .map(Json)
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e))
+}
+
+/// DELETE /api/v1/digests/{id} - unsubscribe
+pub async fn delete_digest(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(id): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    owned(&state, &key, &id).await?;
+    state.digests.remove(&id).await;
+    Ok(Json(serde_json::json!({ "removed": id })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, routing::post, Router};
+    use opensyria_core::{crypto::KeyPair, Transaction};
+
+    fn subscription(addresses: &[PublicKey], delivery: DigestDelivery) -> DigestSubscription {
+        DigestSubscription {
+            id: "digest_test".to_string(),
+            owner: "key_test".to_string(),
+            addresses: addresses.iter().map(PublicKey::to_hex).collect(),
+            delivery,
+            timezone: "+03:00".to_string(),
+            send_hour: 8,
+            next_due: 0,
+            attempts: 0,
+            retry_at: None,
+            last_sent_at: None,
+            last_error: None,
+            created_at: 0,
+        }
+    }
+
+    #[test]
+    fn test_timezones_and_schedule() {
+        assert_eq!(parse_utc_offset("UTC"), Some(0));
+        assert_eq!(parse_utc_offset("+03:00"), Some(3 * 3600));
+        assert_eq!(parse_utc_offset("-0530"), Some(-(5 * 3600 + 30 * 60)));
+        assert_eq!(parse_utc_offset("+3"), Some(3 * 3600));
+        assert_eq!(parse_utc_offset("+15:00"), None);
+        assert_eq!(parse_utc_offset("Asia/Damascus"), None);
+        assert_eq!(schedule(Some("+3"), 8).unwrap().0, "+03:00");
+        assert!(schedule(None, 24).is_err());
+
+        // 2026-10-18 04:00 UTC is 07:00 in Damascus (+03:00)
+        let now = 1_792_296_000;
+        let due = next_delivery(now, 3 * 3600, 8);
+        assert_eq!(due, now + 3600);
+        // Just after the send hour, the next one is a day later
+        assert_eq!(next_delivery(due, 3 * 3600, 8), due + DIGEST_PERIOD_SECS);
+        assert_eq!(next_delivery(now, 0, 8), now + 4 * 3600);
+
+        // Days missed while down are skipped, not sent late
+        assert_eq!(latest_due(due, due - 10), due);
+        assert_eq!(
+            latest_due(due, due + 3 * DIGEST_PERIOD_SECS + 5),
+            due + 3 * DIGEST_PERIOD_SECS
+        );
+    }
+
+    #[test]
+    fn test_digest_totals() {
+        let watched = KeyPair::generate().public_key();
+        let other = KeyPair::generate().public_key();
+        let sponsor = KeyPair::generate().public_key();
+        let incoming = Transaction::new(other, watched, 5_000_000, 100, 0);
+        let outgoing = Transaction::new(watched, other, 2_000_000, 200, 0);
+        let mut sponsored = Transaction::new(watched, other, 1_000_000, 300, 1);
+        sponsored.sponsor = Some(opensyria_core::transaction::FeeSponsor {
+            payer: sponsor,
+            signature: Vec::new(),
+        });
+        let unrelated = Transaction::new(other, sponsor, 7, 100, 1);
+        let mut block = Block::new(
+            [0u8; 32],
+            vec![incoming, outgoing, sponsored, unrelated],
+            16,
+        );
+        block.header.timestamp = 1_000;
+
+        let sub = subscription(
+            &[watched],
+            DigestDelivery::Webhook {
+                url: "http://localhost/hook".to_string(),
+            },
+        );
+        let mut digest = Digest::new(&sub, 2_000);
+        digest.add_block(7, &block, &HashMap::from([(watched, 0)]));
+
+        let summary = &digest.addresses[0];
+        assert_eq!(summary.received, 5_000_000);
+        assert_eq!(summary.sent, 3_000_000);
+        assert_eq!(summary.fees_paid, 200);
+        assert_eq!(summary.transactions, 3);
+        assert_eq!(digest.transactions.len(), 3);
+        // Newest first within the block
+        assert_eq!(digest.transactions[0].amount, 1_000_000);
+        assert!(digest.to_text().contains("received 5.000000 SYL"));
+    }
+
+    #[test]
+    fn test_email_validation() {
+        assert!(is_valid_email("operator@example.sy"));
+        assert!(!is_valid_email("operator"));
+        assert!(!is_valid_email("a@b@example.sy"));
+        assert!(!is_valid_email("op@example.sy\r\nBcc: x@y.z"));
+        assert!(!is_valid_email("Op <op@example.sy>"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_and_email_delivery() {
+        // Webhook receiver
+        let (hook_tx, mut hook_rx) = tokio::sync::mpsc::unbounded_channel();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let router = Router::new().route(
+            "/hook",
+            post(move |body: Bytes| async move {
+                hook_tx.send(body).unwrap();
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        // SMTP relay accepting one message
+        let relay = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let relay_addr = relay.local_addr().unwrap().to_string();
+        let received = tokio::spawn(async move {
+            let (stream, _) = relay.accept().await.unwrap();
+            let mut smtp = BufReader::new(stream);
+            smtp.get_mut().write_all(b"220 relay\r\n").await.unwrap();
+            let mut data = String::new();
+            let mut in_data = false;
+            loop {
+                let mut line = String::new();
+                if smtp.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let reply: &[u8] = if in_data {
+                    if line != ".\r\n" {
+                        data.push_str(&line);
+                        continue;
+                    }
+                    in_data = false;
+                    b"250 queued\r\n"
+                } else if line.starts_with("EHLO") {
+                    b"250-relay\r\n250 8BITMIME\r\n"
+                } else if line.starts_with("DATA") {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line.starts_with("QUIT") {
+                    smtp.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                smtp.get_mut().write_all(reply).await.unwrap();
+            }
+            data
+        });
+
+        let scheduler = DigestScheduler::new(DigestConfig {
+            smtp: Some(SmtpRelay {
+                address: relay_addr,
+                from: "node@example.sy".to_string(),
+            }),
+            ..DigestConfig::default()
+        });
+        let watched = KeyPair::generate().public_key();
+        let sub = subscription(&[watched], DigestDelivery::Webhook { url });
+        let digest = Digest::new(&sub, 1_792_296_000);
+
+        scheduler.deliver(&sub.delivery, &digest).await.unwrap();
+        let body: serde_json::Value =
+            serde_json::from_slice(&hook_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(body["subscription_id"], "digest_test");
+        assert_eq!(body["addresses"][0]["address"], watched.to_hex());
+
+        let email = DigestDelivery::Email {
+            to: "operator@example.sy".to_string(),
+        };
+        scheduler.deliver(&email, &digest).await.unwrap();
+        let message = received.await.unwrap();
+        assert!(message.contains("To: operator@example.sy\r\n"));
+        assert!(message.contains("Subject: OpenSyria daily summary to 2026-10-18 07:00 +03:00"));
+        assert!(message.contains("No transactions."));
+
+        // Failed deliveries are retried, then the day is skipped
+        scheduler
+            .subscriptions
+            .write()
+            .await
+            .insert(sub.id.clone(), sub.clone());
+        for _ in 0..MAX_DELIVERY_ATTEMPTS - 1 {
+            scheduler
+                .record(&sub.id, 100, Err("down".to_string()))
+                .await;
+            assert!(scheduler.get(&sub.id).await.unwrap().retry_at.is_some());
+        }
+        scheduler
+            .record(&sub.id, 100, Err("down".to_string()))
+            .await;
+        let skipped = scheduler.get(&sub.id).await.unwrap();
+        assert_eq!(skipped.retry_at, None);
+        assert_eq!(skipped.next_due, 100 + DIGEST_PERIOD_SECS);
+        assert_eq!(skipped.last_error.as_deref(), Some("down"));
+    }
+}
@@ -0,0 +1,632 @@
+//! Address activity stream over server-sent events
+//! بث نشاط العناوين عبر أحداث الخادم المرسلة
+//!
+//! `GET /api/v1/events?addresses=a,b` streams activity for up to
+//! [`MAX_STREAM_ADDRESSES`] addresses to clients that cannot keep a WebSocket
+//! open. An `unconfirmed` event is sent once when a transaction touching an
+//! address enters the mempool, and a `confirmed` event when a block includes
+//! it. Confirmed events carry the id `height:index`; a client reconnecting
+//! with `Last-Event-ID` receives every confirmed event after that id, up to
+//! [`MAX_REPLAY_BLOCKS`] blocks back. Unconfirmed events have no id and are
+//! not replayed. Comment heartbeats keep idle connections (and proxies) open.
+//!
+//! The node has no event bus here, so one [`EventHub`] poller reads new
+//! blocks and the mempool for every stream, and each stream keeps what
+//! touches its addresses. If a block already reported is replaced by a
+//! reorg, the poller rescans from the fork point and the streams report the
+//! new block's transactions. A client may hold [`MAX_STREAMS_PER_CLIENT`]
+//! streams and the server [`MAX_STREAMS`]; further requests get `429`.
+
+use axum::{
+    extract::{Extension, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures::stream::{self, Stream};
+use opensyria_core::{crypto::PublicKey, error_code, Block, ConfirmationTargets, Transaction};
+use opensyria_node_cli::Node;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, RwLock};
+
+use crate::{api::transaction_details, models::ErrorResponse, AppState};
+
+/// Maximum number of addresses per stream
+pub const MAX_STREAM_ADDRESSES: usize = 100;
+
+/// Streams one client may hold open at once
+pub const MAX_STREAMS_PER_CLIENT: usize = 4;
+
+/// Streams the server holds open at once
+pub const MAX_STREAMS: usize = 1_000;
+
+/// How far back `Last-Event-ID` may resume, in blocks
+pub const MAX_REPLAY_BLOCKS: u64 = 1_000;
+
+/// Interval between storage and mempool polls
+pub const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Interval between heartbeat comments on an idle stream
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Blocks scanned per poll or replay step, so a long replay does not hold
+/// the node lock
+const MAX_BLOCKS_PER_POLL: u64 = 100;
+
+/// Recently scanned blocks kept to detect reorgs
+const REORG_WINDOW: usize = 100;
+
+/// Events buffered per client before its stream waits, and findings
+/// buffered for streams before the slowest is dropped
+const STREAM_BUFFER: usize = 256;
+
+#[derive(Debug, Deserialize)]
+pub struct EventsQuery {
+    /// Comma-separated hex addresses
+    pub addresses: String,
+}
+
+/// Position of a confirmed event, encoded as `height:index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventCursor {
+    pub height: u64,
+    pub index: usize,
+}
+
+impl EventCursor {
+    /// Parse a `Last-Event-ID` value
+    pub fn parse(id: &str) -> Option<Self> {
+        let (height, index) = id.trim().split_once(':')?;
+        Some(Self {
+            height: height.parse().ok()?,
+            index: index.parse().ok()?,
+        })
+    }
+
+    pub fn to_id(self) -> String {
+        format!("{}:{}", self.height, self.index)
+    }
+}
+
+/// Parse the `addresses` query parameter
+pub fn parse_addresses(raw: &str) -> Result<Vec<PublicKey>, String> {
+    let mut addresses = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let address =
+            PublicKey::from_hex(part).map_err(|_| format!("Invalid address: {}", part))?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    if addresses.is_empty() {
+        return Err("At least one address is required".to_string());
+    }
+    if addresses.len() > MAX_STREAM_ADDRESSES {
+        return Err(format!(
+            "Too many addresses: {} (max {})",
+            addresses.len(),
+            MAX_STREAM_ADDRESSES
+        ));
+    }
+    Ok(addresses)
+}
+
+/// Transactions in `block` touching `addresses`, with their index in the block
+pub fn matching_transactions<'a>(
+    block: &'a Block,
+    addresses: &HashSet<PublicKey>,
+) -> Vec<(usize, &'a Transaction)> {
+    block
+        .transactions
+        .iter()
+        .enumerate()
+        .filter(|(_, tx)| addresses.contains(&tx.from) || addresses.contains(&tx.to))
+        .collect()
+}
+
+/// Height the stream starts scanning at, given the tip and an optional
+/// `Last-Event-ID` (new streams start after the current tip)
+fn start_height(tip_height: u64, resume: Option<EventCursor>) -> u64 {
+    match resume {
+        Some(cursor) => cursor
+            .height
+            .max(tip_height.saturating_sub(MAX_REPLAY_BLOCKS))
+            .min(tip_height + 1),
+        None => tip_height + 1,
+    }
+}
+
+/// Stream activity for the listed addresses
+pub async fn address_events(
+    State(state): State<Arc<AppState>>,
+    client: Option<Extension<SocketAddr>>,
+    headers: HeaderMap,
+    Query(query): Query<EventsQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, Json<ErrorResponse>)> {
+    let addresses = parse_addresses(&query.addresses).map_err(|error| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(error_code::INVALID_REQUEST, error)),
+        )
+    })?;
+    let resume = headers
+        .get("last-event-id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(EventCursor::parse);
+
+    // Same client key as the rate limiter
+    let client = client.map_or(IpAddr::from([127, 0, 0, 1]), |Extension(addr)| addr.ip());
+    let slot = state.events.open(client).map_err(|error| {
+        (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ErrorResponse::new(error_code::RATE_LIMITED, error)),
+        )
+    })?;
+
+    let (tip_height, targets) = {
+        let node = state.node.read().await;
+        let blockchain = node.get_blockchain();
+        (
+            blockchain.get_chain_height().unwrap_or(0),
+            ConfirmationTargets::from_params(blockchain.chain_params()),
+        )
+    };
+    let activity = state.events.subscribe(&state.node, tip_height);
+
+    let next_height = start_height(tip_height, resume);
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    let stream = AddressStream {
+        node: state.node.clone(),
+        addresses: addresses.into_iter().collect(),
+        targets,
+        from_height: next_height,
+        next_height,
+        // Only skip within the resumed block if replay starts there
+        skip_through: resume.filter(|cursor| cursor.height == next_height),
+        replayed: HashMap::new(),
+        seen_pending: HashSet::new(),
+        _slot: slot,
+    };
+    tokio::spawn(stream.run(resume.is_some(), activity, tx));
+
+    let events = stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|event| (Ok(event), rx))
+    });
+    Ok(Sse::new(events).keep_alive(
+        KeepAlive::new()
+            .interval(HEARTBEAT_INTERVAL)
+            .text("heartbeat"),
+    ))
+}
+
+/// What the shared poller found, sent to every open stream
+#[derive(Debug)]
+enum Activity {
+    /// A block newly at `height`, including one replacing a reorged block
+    Block {
+        height: u64,
+        hash: [u8; 32],
+        block: Block,
+        tip_height: u64,
+    },
+    /// Everything pending in the mempool
+    Pending {
+        transactions: Vec<Transaction>,
+        tip_height: u64,
+    },
+}
+
+#[derive(Debug, Default)]
+struct HubState {
+    /// Open streams per client
+    clients: HashMap<IpAddr, usize>,
+    /// Open streams in total
+    total: usize,
+    /// Whether the shared poller task is running
+    polling: bool,
+}
+
+/// Open event streams and the one poller feeding them
+/// مركز بث الأحداث المشترك
+///
+/// Each poll reads new blocks and the mempool once, whatever the number of
+/// streams, and stops once the last stream closes.
+#[derive(Debug)]
+pub struct EventHub {
+    sender: broadcast::Sender<Arc<Activity>>,
+    state: Mutex<HubState>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self {
+            sender: broadcast::channel(STREAM_BUFFER).0,
+            state: Mutex::new(HubState::default()),
+        }
+    }
+}
+
+/// One open stream, released when dropped
+#[derive(Debug)]
+pub struct StreamSlot {
+    hub: Arc<EventHub>,
+    client: IpAddr,
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut state = self.hub.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.total -= 1;
+        if let Some(count) = state.clients.get_mut(&self.client) {
+            *count -= 1;
+            if *count == 0 {
+                state.clients.remove(&self.client);
+            }
+        }
+    }
+}
+
+impl EventHub {
+    /// Reserve a stream for `client` within [`MAX_STREAMS_PER_CLIENT`] and
+    /// [`MAX_STREAMS`]
+    pub fn open(self: &Arc<Self>, client: IpAddr) -> Result<StreamSlot, String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.total >= MAX_STREAMS {
+            return Err("Too many event streams open, retry later".to_string());
+        }
+        let count = state.clients.entry(client).or_insert(0);
+        if *count >= MAX_STREAMS_PER_CLIENT {
+            return Err(format!(
+                "Too many event streams from this client (max {})",
+                MAX_STREAMS_PER_CLIENT
+            ));
+        }
+        *count += 1;
+        state.total += 1;
+        Ok(StreamSlot {
+            hub: self.clone(),
+            client,
+        })
+    }
+
+    /// Receive the poller's findings, starting it after `tip_height` if no
+    /// stream is open
+    fn subscribe(
+        self: &Arc<Self>,
+        node: &Arc<RwLock<Node>>,
+        tip_height: u64,
+    ) -> broadcast::Receiver<Arc<Activity>> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let receiver = self.sender.subscribe();
+        if !state.polling {
+            state.polling = true;
+            let poller = ActivityPoller {
+                hub: self.clone(),
+                node: node.clone(),
+                next_height: tip_height + 1,
+                scanned: VecDeque::new(),
+            };
+            tokio::spawn(poller.run());
+        }
+        receiver
+    }
+}
+
+/// Shared scan of new blocks and the mempool
+struct ActivityPoller {
+    hub: Arc<EventHub>,
+    node: Arc<RwLock<Node>>,
+    /// Next block height to scan
+    next_height: u64,
+    /// Recently scanned (height, hash) pairs
+    scanned: VecDeque<(u64, [u8; 32])>,
+}
+
+impl ActivityPoller {
+    async fn run(mut self) {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+            {
+                // Checked under the lock `subscribe` takes, so a new stream
+                // either sees the poller stop or is counted here
+                let mut state = self.hub.state.lock().unwrap_or_else(|e| e.into_inner());
+                if self.hub.sender.receiver_count() == 0 {
+                    state.polling = false;
+                    return;
+                }
+            }
+            for activity in self.poll().await {
+                let _ = self.hub.sender.send(Arc::new(activity));
+            }
+        }
+    }
+
+    async fn poll(&mut self) -> Vec<Activity> {
+        let node = self.node.read().await;
+        let blockchain = node.get_blockchain();
+        let tip_height = match blockchain.get_chain_height() {
+            Ok(height) => height,
+            Err(e) => {
+                tracing::warn!("Event poller failed to read chain height: {}", e);
+                return Vec::new();
+            }
+        };
+
+        // Rewind past blocks that a reorg replaced
+        while let Some(&(height, hash)) = self.scanned.back() {
+            match blockchain.get_block_hash_by_height(height) {
+                Ok(Some(current)) if current == hash => break,
+                _ => {
+                    self.scanned.pop_back();
+                    self.next_height = height;
+                }
+            }
+        }
+
+        let mut activity = Vec::new();
+        let last = tip_height.min(self.next_height + MAX_BLOCKS_PER_POLL - 1);
+        while self.next_height <= last {
+            let height = self.next_height;
+            let block = match blockchain.get_block_by_height(height) {
+                Ok(Some(block)) => block,
+                _ => break,
+            };
+            let hash = block.hash();
+            self.scanned.push_back((height, hash));
+            if self.scanned.len() > REORG_WINDOW {
+                self.scanned.pop_front();
+            }
+            activity.push(Activity::Block {
+                height,
+                hash,
+                block,
+                tip_height,
+            });
+            self.next_height += 1;
+        }
+
+        activity.push(Activity::Pending {
+            transactions: node.get_pending_transactions(),
+            tip_height,
+        });
+        activity
+    }
+}
+
+/// One client's stream: replays blocks after its `Last-Event-ID`, then
+/// filters the shared poller's findings
+struct AddressStream {
+    node: Arc<RwLock<Node>>,
+    addresses: HashSet<PublicKey>,
+    targets: ConfirmationTargets,
+    /// Blocks below this height were on the chain before the stream began
+    from_height: u64,
+    /// Next block height to replay
+    next_height: u64,
+    /// Events at or before this cursor were already delivered
+    skip_through: Option<EventCursor>,
+    /// Hashes of the replayed blocks, so the poller's copies are skipped
+    replayed: HashMap<u64, [u8; 32]>,
+    /// Pending transactions already reported as unconfirmed
+    seen_pending: HashSet<[u8; 32]>,
+    _slot: StreamSlot,
+}
+
+impl AddressStream {
+    async fn run(
+        mut self,
+        replay: bool,
+        mut activity: broadcast::Receiver<Arc<Activity>>,
+        tx: mpsc::Sender<Event>,
+    ) {
+        if replay {
+            loop {
+                let events = match self.replay().await {
+                    Some(events) => events,
+                    None => break,
+                };
+                for event in events {
+                    if tx.send(event).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }
+
+        loop {
+            let found = tokio::select! {
+                found = activity.recv() => found,
+                // Stop once the client has gone, even if it was idle
+                _ = tx.closed() => return,
+            };
+            let found = match found {
+                Ok(found) => found,
+                // A stream this far behind closes; the client resumes with
+                // its `Last-Event-ID`
+                Err(broadcast::error::RecvError::Lagged(_)) => return,
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            for event in self.filter(&found) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Events of the next [`MAX_BLOCKS_PER_POLL`] blocks up to the tip;
+    /// `None` once the tip is reached
+    async fn replay(&mut self) -> Option<Vec<Event>> {
+        let node = self.node.clone();
+        let node = node.read().await;
+        let blockchain = node.get_blockchain();
+        let tip_height = blockchain.get_chain_height().ok()?;
+        if self.next_height > tip_height {
+            return None;
+        }
+
+        let mut events = Vec::new();
+        let last = tip_height.min(self.next_height + MAX_BLOCKS_PER_POLL - 1);
+        while self.next_height <= last {
+            let height = self.next_height;
+            let Ok(Some(block)) = blockchain.get_block_by_height(height) else {
+                return None;
+            };
+            let hash = block.hash();
+            events.extend(self.confirmed(height, &block, tip_height));
+            self.replayed.insert(height, hash);
+            self.next_height += 1;
+        }
+        Some(events)
+    }
+
+    fn filter(&mut self, activity: &Activity) -> Vec<Event> {
+        match activity {
+            Activity::Block {
+                height,
+                hash,
+                block,
+                tip_height,
+            } => {
+                if *height < self.from_height || self.replayed.get(height) == Some(hash) {
+                    return Vec::new();
+                }
+                self.replayed.clear();
+                self.confirmed(*height, block, *tip_height)
+            }
+            Activity::Pending {
+                transactions,
+                tip_height,
+            } => self.unconfirmed(transactions, *tip_height),
+        }
+    }
+
+    fn confirmed(&mut self, height: u64, block: &Block, tip_height: u64) -> Vec<Event> {
+        let mut events = Vec::new();
+        for (index, tx) in matching_transactions(block, &self.addresses) {
+            let cursor = EventCursor { height, index };
+            if self
+                .skip_through
+                .is_some_and(|skip| skip.height == height && index <= skip.index)
+            {
+                continue;
+            }
+            let details = transaction_details(tx, Some(height), tip_height, &self.targets);
+            events.push(
+                Event::default()
+                    .event("confirmed")
+                    .id(cursor.to_id())
+                    .data(serde_json::to_string(&details).unwrap_or_default()),
+            );
+        }
+        self.skip_through = self.skip_through.filter(|skip| skip.height > height);
+        events
+    }
+
+    fn unconfirmed(&mut self, pending: &[Transaction], tip_height: u64) -> Vec<Event> {
+        let mut events = Vec::new();
+        let mut still_pending = HashSet::new();
+        for tx in pending
+            .iter()
+            .filter(|tx| self.addresses.contains(&tx.from) || self.addresses.contains(&tx.to))
+        {
+            let hash = tx.hash();
+            still_pending.insert(hash);
+            if self.seen_pending.contains(&hash) {
+                continue;
+            }
+            let details = transaction_details(tx, None, tip_height, &self.targets);
+            events.push(
+                Event::default()
+                    .event("unconfirmed")
+                    .data(serde_json::to_string(&details).unwrap_or_default()),
+            );
+        }
+        self.seen_pending = still_pending;
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_event_cursor_round_trip() {
+        let cursor = EventCursor {
+            height: 42,
+            index: 3,
+        };
+        assert_eq!(cursor.to_id(), "42:3");
+        assert_eq!(EventCursor::parse(" 42:3 "), Some(cursor));
+        assert_eq!(EventCursor::parse("42"), None);
+        assert_eq!(EventCursor::parse("a:1"), None);
+    }
+
+    #[test]
+    fn test_parse_addresses() {
+        let a = KeyPair::generate().public_key();
+        let b = KeyPair::generate().public_key();
+        let raw = format!("{}, {},{}", a.to_hex(), b.to_hex(), a.to_hex());
+        assert_eq!(parse_addresses(&raw).unwrap(), vec![a, b]);
+
+        assert!(parse_addresses("").is_err());
+        assert!(parse_addresses("zz").is_err());
+        let too_many = (0..=MAX_STREAM_ADDRESSES)
+            .map(|_| KeyPair::generate().public_key().to_hex())
+            .collect::<Vec<_>>()
+            .join(",");
+        assert!(parse_addresses(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_streams_capped_per_client_and_released() {
+        let hub = Arc::new(EventHub::default());
+        let client = IpAddr::from([10, 0, 0, 1]);
+        let slots: Vec<_> = (0..MAX_STREAMS_PER_CLIENT)
+            .map(|_| hub.open(client).unwrap())
+            .collect();
+        assert!(hub.open(client).is_err());
+        assert!(hub.open(IpAddr::from([10, 0, 0, 2])).is_ok());
+
+        drop(slots);
+        assert!(hub.open(client).is_ok());
+        assert_eq!(hub.state.lock().unwrap().total, 0);
+    }
+
+    #[test]
+    fn test_start_height_clamps_replay() {
+        assert_eq!(start_height(500, None), 501);
+        let cursor = |height| Some(EventCursor { height, index: 0 });
+        assert_eq!(start_height(500, cursor(450)), 450);
+        assert_eq!(start_height(5_000, cursor(1)), 5_000 - MAX_REPLAY_BLOCKS);
+        // A cursor from a longer, since-replaced chain
+        assert_eq!(start_height(500, cursor(900)), 501);
+    }
+
+    #[test]
+    fn test_matching_transactions() {
+        let watched = KeyPair::generate().public_key();
+        let other = KeyPair::generate().public_key();
+        let incoming = Transaction::new(other, watched, 10, 100, 0);
+        let unrelated = Transaction::new(other, other, 10, 100, 1);
+        let outgoing = Transaction::new(watched, other, 10, 100, 0);
+        let block = Block::new([0u8; 32], vec![incoming, unrelated, outgoing], 16);
+
+        let addresses = HashSet::from([watched]);
+        let indices: Vec<usize> = matching_transactions(&block, &addresses)
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(indices, vec![0, 2]);
+    }
+}
@@ -0,0 +1,733 @@
+//! Automatic fee bumping of stuck wallet transactions
+//! رفع رسوم معاملات المحفظة العالقة تلقائياً
+//!
+//! A fee bump policy gives a named wallet a fee cap and, optionally, a
+//! webhook. While the wallet is unlocked, the bumper looks at its pending
+//! transactions every [`CHECK_INTERVAL`]. A transaction that has waited more
+//! than `stuck_blocks` blocks while paying less than the fee floor (the low
+//! estimate over the last [`FEE_FLOOR_BLOCKS`] blocks) is re-signed with the
+//! fee the floor asks for. The new fee is always at least the 10% more a
+//! replacement needs and never more than the cap. The re-signed transaction
+//! replaces the pending one, is relayed to backend nodes and gossiped through
+//! an attached P2P node as a replacement, and its wait starts over.
+//!
+//! The webhook is told of every bump, and once when the cap leaves no room
+//! for another. Locked wallets are skipped, since re-signing needs the key.
+//! Sponsored and hot-key transactions are skipped too, as the wallet key
+//! alone cannot re-sign them. Signing a bump counts as use of the wallet
+//! and restarts its idle timer.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use opensyria_core::{
+    clock::system_time, constants::MIN_TRANSACTION_FEE, crypto::PublicKey, FeeEstimate,
+    FeeRateStats, Transaction,
+};
+use opensyria_mempool::replacement_fee;
+use opensyria_network::NetworkCommand;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::sync::{oneshot, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::{
+    auth::{ApiKey, Permission},
+    models::ErrorResponse,
+    node_pool,
+    wallets::authorize,
+    AppState,
+};
+
+/// Interval between checks for stuck transactions
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Recent blocks the fee floor is estimated from
+pub const FEE_FLOOR_BLOCKS: u64 = 20;
+
+/// Blocks a transaction may wait below the floor before it is bumped, when
+/// the policy does not say
+pub const DEFAULT_STUCK_BLOCKS: u64 = 3;
+
+/// Longest wait a policy may set, in blocks
+pub const MAX_STUCK_BLOCKS: u64 = 1_000;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// Fee bumping settings of one wallet
+/// إعدادات رفع الرسوم لمحفظة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBumpPolicy {
+    pub wallet: String,
+    /// Highest fee a bumped transaction may pay
+    pub max_fee: u64,
+    /// Blocks a transaction waits below the fee floor before it is bumped
+    pub stuck_blocks: u64,
+    /// Where bumps are reported as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    pub updated_at: u64,
+}
+
+/// Request to set a wallet's fee bump policy
+#[derive(Debug, Deserialize)]
+pub struct SetFeeBumpRequest {
+    pub max_fee: u64,
+    #[serde(default)]
+    pub stuck_blocks: Option<u64>,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+}
+
+/// What happened to a stuck transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeBumpEvent {
+    /// Replaced by a copy paying a higher fee
+    Bumped,
+    /// Still below the floor, but the cap allows no further bump
+    CapReached,
+}
+
+/// Report of a bump, as POSTed to the policy's webhook
+/// إشعار برفع رسوم معاملة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeeBumpNotification {
+    pub event: FeeBumpEvent,
+    pub wallet: String,
+    pub address: String,
+    pub nonce: u64,
+    /// Hash of the stuck transaction
+    pub tx_hash: String,
+    /// Hash of the transaction replacing it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_hash: Option<String>,
+    pub old_fee: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_fee: Option<u64>,
+    /// Fee the stuck transaction would pay at the current floor
+    pub floor_fee: u64,
+    pub max_fee: u64,
+    /// Blocks the stuck transaction had been pending
+    pub pending_blocks: u64,
+    pub height: u64,
+    /// Backend nodes the replacement is being relayed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed: Option<usize>,
+}
+
+/// Bumper settings
+#[derive(Debug, Clone)]
+pub struct FeeBumpConfig {
+    /// Wait of policies that do not set `stuck_blocks`
+    pub default_stuck_blocks: u64,
+    /// Timeout for each webhook request
+    pub request_timeout: Duration,
+}
+
+impl Default for FeeBumpConfig {
+    fn default() -> Self {
+        Self {
+            default_stuck_blocks: DEFAULT_STUCK_BLOCKS,
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// A pending transaction being watched
+#[derive(Debug, Clone, Copy)]
+struct Watched {
+    /// Height at which it was first seen pending
+    since: u64,
+    /// Whether the cap was already reported
+    capped: bool,
+}
+
+/// Fee bump policies and the task applying them
+/// مدير رفع الرسوم التلقائي
+pub struct FeeBumper {
+    config: FeeBumpConfig,
+    http: reqwest::Client,
+    policies: RwLock<HashMap<String, FeeBumpPolicy>>,
+    /// JSON file policies are kept in; `None` keeps them in memory
+    path: Option<PathBuf>,
+    watched: Mutex<HashMap<[u8; 32], Watched>>,
+}
+
+impl FeeBumper {
+    /// In-memory bumper
+    pub fn new(config: FeeBumpConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            config,
+            http,
+            policies: RwLock::new(HashMap::new()),
+            path: None,
+            watched: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Bumper keeping policies in a JSON file, loading any already there
+    pub fn open(config: FeeBumpConfig, path: PathBuf) -> anyhow::Result<Self> {
+        let policies: Vec<FeeBumpPolicy> = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let bumper = Self {
+            path: Some(path),
+            ..Self::new(config)
+        };
+        *bumper.policies.try_write().expect("not yet shared") = policies
+            .into_iter()
+            .map(|policy| (policy.wallet.clone(), policy))
+            .collect();
+        Ok(bumper)
+    }
+
+    /// Validate and store a wallet's policy, replacing any it had
+    /// تعيين سياسة رفع الرسوم لمحفظة
+    pub async fn set(
+        &self,
+        wallet: &str,
+        request: SetFeeBumpRequest,
+    ) -> Result<FeeBumpPolicy, String> {
+        if request.max_fee < MIN_TRANSACTION_FEE {
+            return Err(format!(
+                "max_fee must be at least the minimum fee of {}",
+                MIN_TRANSACTION_FEE
+            ));
+        }
+        let stuck_blocks = request
+            .stuck_blocks
+            .unwrap_or(self.config.default_stuck_blocks);
+        if !(1..=MAX_STUCK_BLOCKS).contains(&stuck_blocks) {
+            return Err(format!(
+                "stuck_blocks must be between 1 and {}",
+                MAX_STUCK_BLOCKS
+            ));
+        }
+        if let Some(url) = &request.webhook_url {
+            let parsed = reqwest::Url::parse(url).map_err(|_| "Invalid webhook URL")?;
+            if !matches!(parsed.scheme(), "http" | "https") {
+                return Err("Webhook URL must be http or https".to_string());
+            }
+        }
+
+        let policy = FeeBumpPolicy {
+            wallet: wallet.to_string(),
+            max_fee: request.max_fee,
+            stuck_blocks,
+            webhook_url: request.webhook_url,
+            updated_at: system_time(),
+        };
+        let mut policies = self.policies.write().await;
+        policies.insert(wallet.to_string(), policy.clone());
+        self.save(&policies);
+        Ok(policy)
+    }
+
+    pub async fn get(&self, wallet: &str) -> Option<FeeBumpPolicy> {
+        self.policies.read().await.get(wallet).cloned()
+    }
+
+    pub async fn remove(&self, wallet: &str) -> bool {
+        let mut policies = self.policies.write().await;
+        let removed = policies.remove(wallet).is_some();
+        if removed {
+            self.save(&policies);
+        }
+        removed
+    }
+
+    /// Bump every stuck transaction of unlocked wallets with a policy
+    pub async fn run_once(&self, state: &AppState) -> Vec<FeeBumpNotification> {
+        let policies = self.policies.read().await.clone();
+        let wallets: Vec<(FeeBumpPolicy, PublicKey)> = state
+            .wallet_manager
+            .list()
+            .await
+            .into_iter()
+            .filter_map(|wallet| {
+                let policy = policies.get(&wallet.name)?.clone();
+                Some((policy, PublicKey::from_hex(&wallet.address).ok()?))
+            })
+            .collect();
+
+        let node = state.node.read().await;
+        let blockchain = node.get_blockchain();
+        let height = blockchain.get_chain_height().unwrap_or(0);
+        let floor_rate = fee_floor(blockchain, height);
+        let pending = node.get_pending_transactions();
+        drop(node);
+
+        // Forget transactions that were mined or dropped, start the wait of
+        // new ones
+        let mut candidates = Vec::new();
+        {
+            let mut watched = self.watched.lock().unwrap();
+            let hashes: HashSet<[u8; 32]> = pending.iter().map(Transaction::hash).collect();
+            watched.retain(|hash, _| hashes.contains(hash));
+            for tx in pending {
+                let Some((policy, _)) = wallets.iter().find(|(_, address)| tx.from == *address)
+                else {
+                    continue;
+                };
+                let entry = *watched.entry(tx.hash()).or_insert(Watched {
+                    since: height,
+                    capped: false,
+                });
+                let waited = height.saturating_sub(entry.since);
+                if waited > policy.stuck_blocks && can_rebroadcast(&tx) {
+                    candidates.push((policy.clone(), tx, entry, waited));
+                }
+            }
+        }
+        let Some(floor_rate) = floor_rate else {
+            return Vec::new();
+        };
+
+        let mut notifications = Vec::new();
+        for (policy, stuck, entry, waited) in candidates {
+            let floor_fee = FeeEstimate::fee_for(floor_rate, stuck.weight(), MIN_TRANSACTION_FEE);
+            if stuck.fee >= floor_fee {
+                continue;
+            }
+            let mut notification = FeeBumpNotification {
+                event: FeeBumpEvent::CapReached,
+                wallet: policy.wallet.clone(),
+                address: stuck.from.to_hex(),
+                nonce: stuck.nonce,
+                tx_hash: hex::encode(stuck.hash()),
+                replacement_hash: None,
+                old_fee: stuck.fee,
+                new_fee: None,
+                floor_fee,
+                max_fee: policy.max_fee,
+                pending_blocks: waited,
+                height,
+                relayed: None,
+            };
+
+            let Some(fee) = bumped_fee(&stuck, floor_rate, policy.max_fee) else {
+                if !entry.capped {
+                    self.mark_capped(&stuck);
+                    self.notify(&policy, &notification).await;
+                    notifications.push(notification);
+                }
+                continue;
+            };
+            let Some(bumped) = self.resign(state, &policy.wallet, &stuck, fee).await else {
+                continue;
+            };
+            if let Err(e) = state
+                .node
+                .write()
+                .await
+                .replace_pending_transaction(bumped.clone())
+            {
+                tracing::warn!(
+                    "Failed to bump fee of {} from wallet '{}': {}",
+                    notification.tx_hash,
+                    policy.wallet,
+                    e
+                );
+                continue;
+            }
+            self.watched.lock().unwrap().insert(
+                bumped.hash(),
+                Watched {
+                    since: height,
+                    capped: false,
+                },
+            );
+            tracing::info!(
+                "Bumped fee of {} from wallet '{}': {} -> {}",
+                notification.tx_hash,
+                policy.wallet,
+                stuck.fee,
+                fee
+            );
+
+            notification.event = FeeBumpEvent::Bumped;
+            notification.replacement_hash = Some(hex::encode(bumped.hash()));
+            notification.new_fee = Some(fee);
+            notification.relayed = node_pool::relay_replacement_to_pool(state, &bumped);
+            broadcast_replacement(state, &bumped).await;
+            self.notify(&policy, &notification).await;
+            notifications.push(notification);
+        }
+        notifications
+    }
+
+    /// Check for stuck transactions every [`CHECK_INTERVAL`] until the state
+    /// is dropped
+    pub fn spawn(self: &Arc<Self>, state: &Arc<AppState>) -> JoinHandle<()> {
+        let state: Weak<AppState> = Arc::downgrade(state);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(state) = state.upgrade() else {
+                    break;
+                };
+                state.fee_bumper.run_once(&state).await;
+            }
+        })
+    }
+
+    /// Copy of `stuck` paying `fee`, signed by the wallet; `None` if it was
+    /// locked meanwhile
+    async fn resign(
+        &self,
+        state: &AppState,
+        wallet: &str,
+        stuck: &Transaction,
+        fee: u64,
+    ) -> Option<Transaction> {
+        let mut bumped = stuck.clone();
+        bumped.fee = fee;
+        let signing_hash = bumped.signing_hash();
+        bumped.signature = state
+            .wallet_manager
+            .with_keypair(wallet, |keypair| {
+                (keypair.public_key() == stuck.from).then(|| keypair.sign(&signing_hash))
+            })
+            .await
+            .flatten()?;
+        Some(bumped)
+    }
+
+    fn mark_capped(&self, stuck: &Transaction) {
+        if let Some(entry) = self.watched.lock().unwrap().get_mut(&stuck.hash()) {
+            entry.capped = true;
+        }
+    }
+
+    async fn notify(&self, policy: &FeeBumpPolicy, notification: &FeeBumpNotification) {
+        let Some(url) = &policy.webhook_url else {
+            return;
+        };
+        let result = self
+            .http
+            .post(url)
+            .header("X-OpenSyria-Event", "fee_bump")
+            .json(notification)
+            .send()
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|response| {
+                if response.status().is_success() {
+                    Ok(())
+                } else {
+                    Err(format!("webhook returned {}", response.status()))
+                }
+            });
+        if let Err(e) = result {
+            tracing::warn!(
+                "Fee bump webhook of wallet '{}' failed: {}",
+                policy.wallet,
+                e
+            );
+        }
+    }
+
+    fn save(&self, policies: &HashMap<String, FeeBumpPolicy>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut list: Vec<_> = policies.values().collect();
+        list.sort_by(|a, b| a.wallet.cmp(&b.wallet));
+        let tmp = path.with_extension("json.tmp");
+        let result = serde_json::to_string_pretty(&list)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&tmp, json))
+            .and_then(|_| std::fs::rename(&tmp, path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save fee bump policies: {}", e);
+        }
+    }
+}
+
+impl Default for FeeBumper {
+    fn default() -> Self {
+        Self::new(FeeBumpConfig::default())
+    }
+}
+
+/// Fee rate that recent blocks suggest confirms eventually; `None` when
+/// they held no transactions
+fn fee_floor(blockchain: &opensyria_storage::BlockchainStorage, height: u64) -> Option<u64> {
+    let stats: Vec<FeeRateStats> = (height.saturating_sub(FEE_FLOOR_BLOCKS - 1)..=height)
+        .filter_map(|h| {
+            let block = blockchain.get_block_by_height(h).ok()??;
+            Some(FeeRateStats::from_block(&block, h))
+        })
+        .collect();
+    FeeEstimate::from_stats(&stats).map(|estimate| estimate.low)
+}
+
+/// Whether the wallet key alone can re-sign `tx` with another fee
+fn can_rebroadcast(tx: &Transaction) -> bool {
+    tx.sponsor.is_none() && tx.delegate.is_none() && !tx.is_coinbase()
+}
+
+/// Fee `stuck` is re-signed with to pay `floor_rate`, raised to what a
+/// replacement needs and limited to `max_fee`; `None` when the cap leaves
+/// no valid replacement
+/// حساب الرسوم الجديدة لمعاملة عالقة
+pub fn bumped_fee(stuck: &Transaction, floor_rate: u64, max_fee: u64) -> Option<u64> {
+    // A larger fee can encode in more bytes, so settle the weight first
+    let weight_at = |fee: u64| {
+        let mut tx = stuck.clone();
+        tx.fee = fee;
+        tx.weight()
+    };
+    let mut fee = stuck.fee;
+    for _ in 0..4 {
+        let weight = weight_at(fee);
+        let needed = FeeEstimate::fee_for(floor_rate, weight, MIN_TRANSACTION_FEE)
+            .max(replacement_fee(stuck, weight))
+            .min(max_fee);
+        if needed == fee {
+            break;
+        }
+        fee = needed;
+    }
+    (fee > stuck.fee && fee >= replacement_fee(stuck, weight_at(fee))).then_some(fee)
+}
+
+/// Gossip a replacement through the attached P2P node, if any
+async fn broadcast_replacement(state: &AppState, tx: &Transaction) {
+    let Some(network) = &state.network else {
+        return;
+    };
+    let (reply, response) = oneshot::channel();
+    let command = NetworkCommand::ReplaceTransaction {
+        tx: tx.clone(),
+        reply,
+    };
+    let result = match network.send(command) {
+        Ok(()) => response.await.map_err(anyhow::Error::from).and_then(|r| r),
+        Err(_) => Err(anyhow::anyhow!("P2P node has stopped")),
+    };
+    if let Err(e) = result {
+        tracing::warn!(
+            "Bumped transaction {} not gossiped: {}",
+            hex::encode(tx.hash()),
+            e
+        );
+    }
+}
+
+/// GET /api/v1/wallets/{name}/fee-bump - the wallet's fee bump policy
+pub async fn get_fee_bump(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<FeeBumpPolicy> {
+    authorize(&key, &name, Some(Permission::ReadBalance))?;
+    state.fee_bumper.get(&name).await.map(Json).ok_or_else(|| {
+        api_error(
+            StatusCode::NOT_FOUND,
+            format!("Wallet '{}' has no fee bump policy", name),
+        )
+    })
+}
+
+/// PUT /api/v1/wallets/{name}/fee-bump - bump stuck transactions up to a cap
+pub async fn set_fee_bump(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+    Json(request): Json<SetFeeBumpRequest>,
+) -> ApiResult<FeeBumpPolicy> {
+    authorize(&key, &name, Some(Permission::SubmitTransaction))?;
+    state
+        .fee_bumper
+        .set(&name, request)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))
+}
+
+/// DELETE /api/v1/wallets/{name}/fee-bump - stop bumping the wallet's fees
+pub async fn delete_fee_bump(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    authorize(&key, &name, Some(Permission::SubmitTransaction))?;
+    let removed = state.fee_bumper.remove(&name).await;
+    Ok(Json(
+        serde_json::json!({ "wallet": name, "removed": removed }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, routing::post, Router};
+    use opensyria_core::{crypto::KeyPair, ChainParams};
+    use opensyria_node_cli::Node;
+
+    fn signed(from: &KeyPair, chain_id: u32, nonce: u64, fee: u64) -> Transaction {
+        let tx = Transaction::new_with_chain_id(
+            chain_id,
+            from.public_key(),
+            KeyPair::generate().public_key(),
+            1_000,
+            fee,
+            nonce,
+        );
+        let signature = from.sign(&tx.signing_hash());
+        tx.with_signature(signature)
+    }
+
+    #[test]
+    fn test_bumped_fee_respects_floor_and_cap() {
+        let stuck = signed(&KeyPair::generate(), 1, 0, MIN_TRANSACTION_FEE);
+        let weight = stuck.weight();
+
+        // The floor asks for more than the replacement minimum
+        let rate = 10 * MIN_TRANSACTION_FEE * 1000 / weight as u64;
+        let fee = bumped_fee(&stuck, rate, u64::MAX).unwrap();
+        assert!(fee >= FeeEstimate::fee_for(rate, weight, MIN_TRANSACTION_FEE));
+
+        // A floor barely above the fee still bumps by the replacement minimum
+        let fee = bumped_fee(&stuck, stuck.fee_rate() + 1, u64::MAX).unwrap();
+        assert_eq!(fee, replacement_fee(&stuck, weight));
+
+        // The cap limits the bump, and stops it once no replacement fits
+        assert_eq!(bumped_fee(&stuck, rate, 5 * MIN_TRANSACTION_FEE), Some(500));
+        assert_eq!(bumped_fee(&stuck, rate, MIN_TRANSACTION_FEE + 5), None);
+    }
+
+    #[tokio::test]
+    async fn test_policy_validation() {
+        let bumper = FeeBumper::default();
+        let request = |max_fee, stuck_blocks, webhook_url: Option<&str>| SetFeeBumpRequest {
+            max_fee,
+            stuck_blocks,
+            webhook_url: webhook_url.map(str::to_string),
+        };
+
+        assert!(bumper.set("alice", request(1, None, None)).await.is_err());
+        assert!(bumper
+            .set("alice", request(1_000, Some(0), None))
+            .await
+            .is_err());
+        assert!(bumper
+            .set("alice", request(1_000, None, Some("ftp://example.sy")))
+            .await
+            .is_err());
+
+        let policy = bumper
+            .set(
+                "alice",
+                request(1_000, None, Some("https://example.sy/hook")),
+            )
+            .await
+            .unwrap();
+        assert_eq!(policy.stuck_blocks, DEFAULT_STUCK_BLOCKS);
+        assert_eq!(bumper.get("alice").await, Some(policy));
+        assert!(bumper.remove("alice").await);
+        assert!(!bumper.remove("alice").await);
+    }
+
+    #[tokio::test]
+    async fn test_stuck_transaction_is_bumped_and_reported() {
+        let (hook_tx, mut hook_rx) = tokio::sync::mpsc::unbounded_channel();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let router = Router::new().route(
+            "/hook",
+            post(move |body: Bytes| async move {
+                hook_tx.send(body).unwrap();
+            }),
+        );
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 16).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let wallet = KeyPair::generate();
+        let other = KeyPair::generate();
+        node.generate_to_address(1, &wallet.public_key()).unwrap();
+        node.generate_to_address(1, &other.public_key()).unwrap();
+
+        // A block of well-paying transactions sets the floor
+        node.add_transaction_to_mempool(signed(&other, params.chain_id, 0, 50_000))
+            .unwrap();
+        node.generate_to_address(1, &other.public_key()).unwrap();
+        let stuck = signed(&wallet, params.chain_id, 0, MIN_TRANSACTION_FEE);
+        node.add_transaction_to_mempool(stuck.clone()).unwrap();
+
+        let state = AppState::new(node);
+        state.wallet_manager.insert("alice", wallet.clone()).await;
+        state
+            .fee_bumper
+            .set(
+                "alice",
+                SetFeeBumpRequest {
+                    max_fee: 1_000_000,
+                    stuck_blocks: Some(2),
+                    webhook_url: Some(url),
+                },
+            )
+            .await
+            .unwrap();
+
+        // Not yet waited long enough
+        assert!(state.fee_bumper.run_once(&state).await.is_empty());
+        state
+            .fee_bumper
+            .watched
+            .lock()
+            .unwrap()
+            .get_mut(&stuck.hash())
+            .unwrap()
+            .since -= 3;
+
+        let notifications = state.fee_bumper.run_once(&state).await;
+        assert_eq!(notifications.len(), 1);
+        let bump = &notifications[0];
+        assert_eq!(bump.event, FeeBumpEvent::Bumped);
+        assert_eq!(bump.pending_blocks, 3);
+        let new_fee = bump.new_fee.unwrap();
+        assert!(new_fee >= bump.floor_fee && new_fee <= 1_000_000);
+
+        let pending = state.node.read().await.get_pending_transactions();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].fee, new_fee);
+        assert_eq!(pending[0].nonce, stuck.nonce);
+        assert!(pending[0].verify().is_ok());
+        assert_eq!(bump.replacement_hash, Some(hex::encode(pending[0].hash())));
+
+        let body: FeeBumpNotification =
+            serde_json::from_slice(&hook_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(&body, bump);
+
+        // The replacement starts a new wait, and locked wallets are left alone
+        assert!(state.fee_bumper.run_once(&state).await.is_empty());
+        state.wallet_manager.lock("alice").await;
+        state.fee_bumper.watched.lock().unwrap().clear();
+        assert!(state.fee_bumper.run_once(&state).await.is_empty());
+        assert!(state.fee_bumper.watched.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+//! Request latency labels for the wallet API
+//! تسميات قياس زمن الطلبات لواجهة المحفظة
+//!
+//! The middleware and the `/metrics` handlers live in [`opensyria_http`],
+//! shared with the explorer.
+
+use axum::{extract::MatchedPath, http::Extensions};
+use opensyria_http::LatencyLayer;
+
+pub use opensyria_http::{endpoint_latency, prometheus_metrics};
+
+/// Service label for REST endpoints
+pub const SERVICE: &str = "wallet-api";
+
+/// Service label for individual JSON-RPC calls
+pub const RPC_SERVICE: &str = "wallet-api-rpc";
+
+/// Time every request under its route template and status
+pub fn latency_layer() -> LatencyLayer {
+    LatencyLayer::new(SERVICE, route_template)
+}
+
+fn route_template(extensions: &Extensions) -> Option<&str> {
+    extensions.get::<MatchedPath>().map(MatchedPath::as_str)
+}
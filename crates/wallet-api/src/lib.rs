@@ -1,12 +1,33 @@
+use opensyria_network::NetworkCommand;
 use opensyria_node_cli::Node;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
 
+pub mod admin;
+pub mod anchors;
 pub mod api;
 pub mod auth;
+pub mod batch;
+pub mod digest;
+pub mod events;
+pub mod fee_bump;
+pub mod latency;
+pub mod load_shed;
+pub mod message;
 pub mod models;
+pub mod node_pool;
+pub mod oracle;
+pub mod payment;
+pub mod proof;
 pub mod rate_limit;
+pub mod regtest;
 pub mod server;
+pub mod simulate;
+pub mod stealth;
+pub mod telemetry;
+pub mod wallets;
+pub mod watchtower;
 
 pub use server::start_server;
 
@@ -15,6 +36,36 @@ pub struct AppState {
     pub node: Arc<RwLock<Node>>,
     pub api_key_manager: Arc<auth::ApiKeyManager>,
     pub rate_limiter: Arc<rate_limit::RateLimiter>,
+    /// Sheds low-priority requests while the node is overloaded
+    pub load_shedder: Arc<load_shed::LoadShedder>,
+    pub wallet_manager: Arc<wallets::WalletManager>,
+    /// Backend nodes for read failover and transaction relay
+    pub node_pool: Option<Arc<node_pool::NodePool>>,
+    /// State tree of the current tip for balance proofs
+    pub state_trees: Arc<proof::StateTreeCache>,
+    /// Exchange rate feeds signed for `/api/v1/rates`
+    pub oracle: Option<Arc<oracle::RateOracle>>,
+    /// Background miner controlled by the admin endpoints
+    pub miner: Arc<admin::Miner>,
+    /// Command channel of the P2P node, for the peer admin endpoints
+    pub network: Option<mpsc::UnboundedSender<NetworkCommand>>,
+    /// Address of the admin listener; `None` disables the admin endpoints
+    pub admin_bind: Option<SocketAddr>,
+    /// Passkeys and login sessions for the admin endpoints
+    pub passkeys: Arc<auth::webauthn::PasskeyManager>,
+    /// Daily activity digests by webhook or email
+    pub digests: Arc<digest::DigestScheduler>,
+    /// Automatic fee bumping of stuck wallet transactions
+    pub fee_bumper: Arc<fee_bump::FeeBumper>,
+    /// Submits refund and expiry transactions at their deadlines; `None`
+    /// disables it
+    pub watchtower: Option<Arc<watchtower::Watchtower>>,
+    /// Propagation reports of P2P nodes; `None` refuses them
+    pub telemetry: Option<Arc<std::sync::Mutex<opensyria_network::TelemetryAggregator>>>,
+    /// Open address event streams and their shared poller
+    pub events: Arc<events::EventHub>,
+    /// Runs mempool policy vectors under the node's pool policy
+    pub conformance: Arc<opensyria_mempool::ConformanceRunner>,
 }
 
 impl AppState {
@@ -23,6 +74,101 @@ impl AppState {
             node: Arc::new(RwLock::new(node)),
             api_key_manager: Arc::new(auth::ApiKeyManager::new()),
             rate_limiter: Arc::new(rate_limit::RateLimiter::new()),
+            load_shedder: Arc::new(load_shed::LoadShedder::default()),
+            wallet_manager: Arc::new(wallets::WalletManager::default()),
+            node_pool: None,
+            state_trees: Arc::new(proof::StateTreeCache::default()),
+            oracle: None,
+            miner: Arc::new(admin::Miner::default()),
+            network: None,
+            admin_bind: admin::DEFAULT_ADMIN_BIND.parse().ok(),
+            passkeys: Arc::new(auth::webauthn::PasskeyManager::new(
+                auth::webauthn::WebAuthnConfig::default(),
+            )),
+            digests: Arc::new(digest::DigestScheduler::new(
+                digest::DigestConfig::default(),
+            )),
+            fee_bumper: Arc::new(fee_bump::FeeBumper::default()),
+            watchtower: None,
+            telemetry: None,
+            events: Arc::new(events::EventHub::default()),
+            conformance: Arc::new(opensyria_mempool::ConformanceRunner::new(
+                opensyria_mempool::MempoolConfig::default(),
+            )),
         }
     }
+
+    /// Serve named encrypted wallets from the given storage
+    pub fn with_wallet_manager(mut self, wallet_manager: wallets::WalletManager) -> Self {
+        self.wallet_manager = Arc::new(wallet_manager);
+        self
+    }
+
+    /// Route public reads and relay transactions through backend nodes
+    pub fn with_node_pool(mut self, node_pool: Arc<node_pool::NodePool>) -> Self {
+        self.node_pool = Some(node_pool);
+        self
+    }
+
+    /// Override the load shedding limits
+    pub fn with_load_shedding(mut self, config: load_shed::LoadShedConfig) -> Self {
+        self.load_shedder = Arc::new(load_shed::LoadShedder::new(config));
+        self
+    }
+
+    /// Serve signed rates from exchange rate feeds
+    pub fn with_oracle(mut self, oracle: Arc<oracle::RateOracle>) -> Self {
+        self.oracle = Some(oracle);
+        self
+    }
+
+    /// Run mempool policy vectors under the node's pool policy
+    pub fn with_mempool_config(mut self, config: opensyria_mempool::MempoolConfig) -> Self {
+        self.conformance = Arc::new(opensyria_mempool::ConformanceRunner::new(config));
+        self
+    }
+
+    /// Let the admin endpoints dial and ban peers through a P2P node
+    pub fn with_network(mut self, commands: mpsc::UnboundedSender<NetworkCommand>) -> Self {
+        self.network = Some(commands);
+        self
+    }
+
+    /// Serve the admin endpoints on another address, or not at all
+    pub fn with_admin_bind(mut self, admin_bind: Option<SocketAddr>) -> Self {
+        self.admin_bind = admin_bind;
+        self
+    }
+
+    /// Let operators log in to the admin endpoints with these passkeys
+    pub fn with_passkeys(mut self, passkeys: auth::webauthn::PasskeyManager) -> Self {
+        self.passkeys = Arc::new(passkeys);
+        self
+    }
+
+    /// Deliver daily digests with these subscriptions and settings
+    pub fn with_digests(mut self, digests: digest::DigestScheduler) -> Self {
+        self.digests = Arc::new(digests);
+        self
+    }
+
+    /// Bump stuck wallet transactions with these policies and settings
+    pub fn with_fee_bumper(mut self, fee_bumper: fee_bump::FeeBumper) -> Self {
+        self.fee_bumper = Arc::new(fee_bumper);
+        self
+    }
+
+    /// Act on registered multisig and escrow deadlines
+    pub fn with_watchtower(mut self, watchtower: watchtower::Watchtower) -> Self {
+        self.watchtower = Some(Arc::new(watchtower));
+        self
+    }
+
+    /// Collect propagation reports, merging those received within `window`
+    pub fn with_telemetry_collector(mut self, window: std::time::Duration) -> Self {
+        self.telemetry = Some(Arc::new(std::sync::Mutex::new(
+            opensyria_network::TelemetryAggregator::new(window),
+        )));
+        self
+    }
 }
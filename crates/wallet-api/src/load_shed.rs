@@ -0,0 +1,436 @@
+//! Load shedding for public API nodes
+//! تخفيف الحمل لعقد واجهة البرمجة العامة
+//!
+//! An overloaded node that queues everything serves everyone slowly. The
+//! [`LoadShedder`] tracks requests in flight and a moving average of their
+//! latency, and refuses the least important traffic first with
+//! `503 Service Unavailable` and `Retry-After`:
+//!
+//! 1. anonymous reads, shed first
+//! 2. reads with a verified API key
+//! 3. transaction submissions, shed only when the node is full
+//!
+//! Each priority may fill its share of [`LoadShedConfig::max_in_flight`];
+//! reads are also shed while recent latency is above their multiple of
+//! [`LoadShedConfig::target_latency`]. `/health` and `/metrics` are never
+//! shed so monitoring keeps working under load.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use opensyria_core::error_code::{self, ErrorCode};
+use opensyria_metrics::{HTTP_REQUESTS_IN_FLIGHT, HTTP_REQUESTS_SHED};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{latency::SERVICE, AppState};
+
+/// Latency older than this says nothing about current load
+const LATENCY_STALE_AFTER: Duration = Duration::from_secs(5);
+
+/// Weight of each new sample in the latency moving average
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Routes that submit transactions
+const SUBMISSION_ROUTES: &[&str] = &[
+    "/api/v1/transaction/submit",
+    "/api/v1/transaction/raw",
+    "/api/v1/wallets/{name}/send",
+];
+
+/// Routes that are never shed
+const EXEMPT_ROUTES: &[&str] = &["/health", "/metrics"];
+
+/// Request priority, lowest first
+/// أولوية الطلب
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    AnonymousRead,
+    KeyedRead,
+    Submission,
+}
+
+impl Priority {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Priority::AnonymousRead => "anonymous_read",
+            Priority::KeyedRead => "keyed_read",
+            Priority::Submission => "submission",
+        }
+    }
+
+    /// Percentage of `max_in_flight` this priority may fill
+    fn capacity_percent(&self) -> usize {
+        match self {
+            Priority::AnonymousRead => 50,
+            Priority::KeyedRead => 80,
+            Priority::Submission => 100,
+        }
+    }
+
+    /// Multiple of the target latency above which this priority is shed
+    fn latency_multiple(&self) -> Option<u32> {
+        match self {
+            Priority::AnonymousRead => Some(1),
+            Priority::KeyedRead => Some(2),
+            Priority::Submission => None,
+        }
+    }
+}
+
+/// Why a request was shed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShedReason {
+    /// Too many requests in flight for its priority
+    QueueDepth,
+    /// Recent requests are too slow for its priority
+    Latency,
+}
+
+impl ShedReason {
+    /// Metric label
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ShedReason::QueueDepth => "queue_depth",
+            ShedReason::Latency => "latency",
+        }
+    }
+}
+
+/// Load shedding limits
+/// حدود تخفيف الحمل
+#[derive(Debug, Clone)]
+pub struct LoadShedConfig {
+    /// Requests in flight at which even submissions are shed
+    pub max_in_flight: usize,
+    /// Latency above which anonymous reads are shed (keyed reads at twice it)
+    pub target_latency: Duration,
+    /// Sent in `Retry-After`
+    pub retry_after: Duration,
+}
+
+impl Default for LoadShedConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight: 256,
+            target_latency: Duration::from_millis(500),
+            retry_after: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Moving average of request latency
+#[derive(Debug, Clone, Copy)]
+struct LatencyAverage {
+    average: Duration,
+    updated: Instant,
+}
+
+/// Admits or sheds requests by priority
+/// محدد قبول الطلبات حسب الأولوية
+pub struct LoadShedder {
+    config: LoadShedConfig,
+    in_flight: AtomicUsize,
+    latency: Mutex<Option<LatencyAverage>>,
+}
+
+impl Default for LoadShedder {
+    fn default() -> Self {
+        Self::new(LoadShedConfig::default())
+    }
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadShedConfig) -> Self {
+        Self {
+            config,
+            in_flight: AtomicUsize::new(0),
+            latency: Mutex::new(None),
+        }
+    }
+
+    pub fn config(&self) -> &LoadShedConfig {
+        &self.config
+    }
+
+    /// Requests currently admitted and not yet answered
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    /// Latency moving average, unless too old to reflect current load
+    pub fn recent_latency(&self) -> Option<Duration> {
+        let latency = *self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        latency
+            .filter(|l| l.updated.elapsed() < LATENCY_STALE_AFTER)
+            .map(|l| l.average)
+    }
+
+    /// Whether a request of `priority` would be admitted now
+    pub fn check(&self, priority: Priority) -> Result<(), ShedReason> {
+        if self.in_flight() >= self.capacity(priority) {
+            return Err(ShedReason::QueueDepth);
+        }
+        self.check_latency(priority)
+    }
+
+    /// Admit a request of `priority`, counting it in flight until the
+    /// returned guard is dropped
+    pub fn try_admit(self: &Arc<Self>, priority: Priority) -> Result<InFlight, ShedReason> {
+        self.check_latency(priority)?;
+        let capacity = self.capacity(priority);
+        self.in_flight
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < capacity).then_some(n + 1)
+            })
+            .map_err(|_| ShedReason::QueueDepth)?;
+        HTTP_REQUESTS_IN_FLIGHT.with_label_values(&[SERVICE]).inc();
+
+        Ok(InFlight {
+            shedder: self.clone(),
+            started: Instant::now(),
+        })
+    }
+
+    fn capacity(&self, priority: Priority) -> usize {
+        (self.config.max_in_flight * priority.capacity_percent() / 100).max(1)
+    }
+
+    fn check_latency(&self, priority: Priority) -> Result<(), ShedReason> {
+        match (priority.latency_multiple(), self.recent_latency()) {
+            (Some(multiple), Some(latency)) if latency > self.config.target_latency * multiple => {
+                Err(ShedReason::Latency)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record_latency(&self, elapsed: Duration) {
+        let mut latency = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        let average = match *latency {
+            Some(previous) if previous.updated.elapsed() < LATENCY_STALE_AFTER => {
+                previous.average.mul_f64(1.0 - LATENCY_SMOOTHING)
+                    + elapsed.mul_f64(LATENCY_SMOOTHING)
+            }
+            _ => elapsed,
+        };
+        *latency = Some(LatencyAverage {
+            average,
+            updated: Instant::now(),
+        });
+    }
+}
+
+/// An admitted request; its latency is recorded when dropped
+pub struct InFlight {
+    shedder: Arc<LoadShedder>,
+    started: Instant,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.shedder.in_flight.fetch_sub(1, Ordering::AcqRel);
+        HTTP_REQUESTS_IN_FLIGHT.with_label_values(&[SERVICE]).dec();
+        self.shedder.record_latency(self.started.elapsed());
+    }
+}
+
+/// Request refused under load
+#[derive(Debug)]
+pub struct Overloaded {
+    pub reason: ShedReason,
+    pub retry_after: Duration,
+}
+
+#[derive(Serialize)]
+struct OverloadedResponse {
+    error: String,
+    #[serde(flatten)]
+    code: ErrorCode,
+    retry_after_secs: u64,
+}
+
+impl IntoResponse for Overloaded {
+    fn into_response(self) -> Response {
+        let retry_after_secs = self.retry_after.as_secs().max(1);
+        let body = OverloadedResponse {
+            error: "Node is overloaded, retry later".to_string(),
+            code: error_code::UNAVAILABLE,
+            retry_after_secs,
+        };
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(body),
+        )
+            .into_response()
+    }
+}
+
+/// Route template of a request, or its path if unrouted
+fn route(request: &Request) -> &str {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or_else(|| request.uri().path())
+}
+
+/// Whether the request submits a transaction
+pub fn is_submission(method: &Method, route: &str) -> bool {
+    method == Method::POST && SUBMISSION_ROUTES.contains(&route)
+}
+
+/// Shed requests by priority while the node is overloaded
+/// برمجية وسيطة لتخفيف الحمل
+pub async fn load_shed_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, Overloaded> {
+    let route = route(&request).to_string();
+    if EXEMPT_ROUTES.contains(&route.as_str()) {
+        return Ok(next.run(request).await);
+    }
+
+    let priority = if is_submission(request.method(), &route) {
+        Priority::Submission
+    } else {
+        let api_key = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string);
+        read_priority(&state, api_key.as_deref()).await
+    };
+
+    let shedder = &state.load_shedder;
+    let _in_flight = shedder.try_admit(priority).map_err(|reason| {
+        HTTP_REQUESTS_SHED
+            .with_label_values(&[SERVICE, priority.as_str(), reason.as_str()])
+            .inc();
+        Overloaded {
+            reason,
+            retry_after: shedder.config().retry_after,
+        }
+    })?;
+
+    Ok(next.run(request).await)
+}
+
+/// Keyed if the request carries a valid API key. Keys not seen before are
+/// only verified, with Argon2, while anonymous reads are still admitted,
+/// so unknown keys cost nothing extra under load
+async fn read_priority(state: &AppState, api_key: Option<&str>) -> Priority {
+    let Some(api_key) = api_key else {
+        return Priority::AnonymousRead;
+    };
+
+    let keys = &state.api_key_manager;
+    let known = keys.cached_key(api_key).await.is_some()
+        || (state.load_shedder.check(Priority::AnonymousRead).is_ok()
+            && keys.verify_key(api_key).await.is_some());
+    if known {
+        Priority::KeyedRead
+    } else {
+        Priority::AnonymousRead
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shedder(max_in_flight: usize) -> Arc<LoadShedder> {
+        Arc::new(LoadShedder::new(LoadShedConfig {
+            max_in_flight,
+            target_latency: Duration::from_millis(100),
+            retry_after: Duration::from_secs(5),
+        }))
+    }
+
+    #[test]
+    fn test_lowest_priority_shed_first_by_queue_depth() {
+        let shedder = shedder(10);
+
+        let mut admitted: Vec<InFlight> = (0..5)
+            .map(|_| shedder.try_admit(Priority::AnonymousRead).unwrap())
+            .collect();
+        assert_eq!(
+            shedder.try_admit(Priority::AnonymousRead).err(),
+            Some(ShedReason::QueueDepth)
+        );
+
+        for _ in 0..3 {
+            admitted.push(shedder.try_admit(Priority::KeyedRead).unwrap());
+        }
+        assert!(shedder.try_admit(Priority::KeyedRead).is_err());
+
+        for _ in 0..2 {
+            admitted.push(shedder.try_admit(Priority::Submission).unwrap());
+        }
+        assert!(shedder.try_admit(Priority::Submission).is_err());
+        assert_eq!(shedder.in_flight(), 10);
+
+        // Finished requests free their slots
+        admitted.truncate(4);
+        assert_eq!(shedder.in_flight(), 4);
+        assert!(shedder.try_admit(Priority::AnonymousRead).is_ok());
+    }
+
+    #[test]
+    fn test_reads_shed_by_latency() {
+        let shedder = shedder(10);
+        shedder.record_latency(Duration::from_millis(150));
+
+        assert_eq!(
+            shedder.try_admit(Priority::AnonymousRead).err(),
+            Some(ShedReason::Latency)
+        );
+        assert!(shedder.try_admit(Priority::KeyedRead).is_ok());
+
+        shedder.record_latency(Duration::from_secs(1));
+        assert!(shedder.try_admit(Priority::KeyedRead).is_err());
+        // Submissions are only shed when the node is full
+        assert!(shedder.try_admit(Priority::Submission).is_ok());
+
+        // Fast requests bring the average back down
+        for _ in 0..20 {
+            shedder.record_latency(Duration::from_millis(10));
+        }
+        assert!(shedder.try_admit(Priority::AnonymousRead).is_ok());
+    }
+
+    #[test]
+    fn test_stale_latency_ignored() {
+        let shedder = shedder(10);
+        *shedder.latency.lock().unwrap() = Some(LatencyAverage {
+            average: Duration::from_secs(10),
+            updated: Instant::now() - LATENCY_STALE_AFTER,
+        });
+        assert_eq!(shedder.recent_latency(), None);
+        assert!(shedder.try_admit(Priority::AnonymousRead).is_ok());
+    }
+
+    #[test]
+    fn test_overloaded_response() {
+        let response = Overloaded {
+            reason: ShedReason::QueueDepth,
+            retry_after: Duration::from_secs(7),
+        }
+        .into_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "7");
+
+        assert!(is_submission(&Method::POST, "/api/v1/wallets/{name}/send"));
+        assert!(!is_submission(&Method::GET, "/api/v1/transaction/submit"));
+        assert!(!is_submission(&Method::POST, "/api/v1/tx/simulate"));
+    }
+}
@@ -1,7 +1,24 @@
 use clap::Parser;
-use opensyria_node_cli::Node;
-use opensyria_wallet_api::{start_server, AppState};
+use opensyria_core::ChainParams;
+use opensyria_metrics::telemetry::{init_tracing, OtlpConfig};
+use opensyria_node_cli::{config::NodeConfig, Node};
+use opensyria_wallet::EncryptedWalletStorage;
+use opensyria_wallet_api::{
+    auth::webauthn::{PasskeyManager, WebAuthnConfig},
+    digest::{DigestConfig, DigestScheduler, SmtpRelay},
+    fee_bump::{FeeBumpConfig, FeeBumper, MAX_STUCK_BLOCKS},
+    load_shed::LoadShedConfig,
+    node_pool::{NodePool, NodePoolConfig},
+    oracle::{load_feeds, RateOracle, RateOracleConfig},
+    start_server,
+    wallets::WalletManager,
+    watchtower::Watchtower,
+    AppState,
+};
+use std::net::SocketAddr;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "opensyria-wallet-api")]
@@ -27,35 +44,258 @@ struct Cli {
     #[arg(long)]
     tls_key: Option<PathBuf>,
 
+    /// Chain parameters of the node (mainnet, testnet, regtest); regtest
+    /// enables the block generation and mock time endpoints
+    #[arg(long, default_value = "mainnet")]
+    network: String,
+
+    /// Encrypted wallet directory served by the multi-wallet endpoints
+    #[arg(long, default_value = "~/.opensyria/wallet")]
+    wallet_dir: String,
+
+    /// Backend node wallet API for read failover and transaction relay
+    /// (repeatable)
+    #[arg(long = "backend")]
+    backends: Vec<String>,
+
+    /// API key presented to backend nodes when relaying transactions
+    #[arg(long)]
+    backend_api_key: Option<String>,
+
+    /// Seconds between backend health checks
+    #[arg(long, default_value = "10")]
+    backend_health_interval: u64,
+
+    /// JSON file listing exchange rate feeds served signed at /api/v1/rates
+    #[arg(long)]
+    oracle_feeds: Option<PathBuf>,
+
+    /// Private key signing rate attestations, as hex or a file containing it
+    #[arg(long, requires = "oracle_feeds")]
+    oracle_key: Option<String>,
+
+    /// Seconds between rate feed refreshes
+    #[arg(long, default_value = "60")]
+    oracle_interval: u64,
+
+    /// Requests in flight at which even transaction submissions are shed
+    /// with 503; anonymous reads are shed at half of it
+    #[arg(long, default_value = "256")]
+    max_in_flight: usize,
+
+    /// Average latency in milliseconds above which anonymous reads are shed
+    /// (keyed reads at twice it)
+    #[arg(long, default_value = "500")]
+    shed_latency_ms: u64,
+
+    /// Address of the admin endpoints (miner, peers, mempool, key rotation);
+    /// keep it on loopback unless a firewall guards the port
+    #[arg(long, default_value = "127.0.0.1:8081")]
+    admin_bind: SocketAddr,
+
+    /// Do not serve the admin endpoints
+    #[arg(long, default_value = "false")]
+    no_admin: bool,
+
+    /// WebAuthn relying party ID for admin passkeys: the host name of the
+    /// admin page
+    #[arg(long, default_value = "localhost")]
+    webauthn_rp_id: String,
+
+    /// Origin of the admin page passkeys are used from
+    #[arg(long, default_value = "http://localhost:8081")]
+    webauthn_origin: String,
+
+    /// Refuse admin API keys once a passkey is registered
+    #[arg(long, default_value = "false")]
+    admin_passkey_only: bool,
+
+    /// SMTP relay (host:port) for email digests; a local mail server, as
+    /// TLS and authentication are not used
+    #[arg(long)]
+    digest_smtp_relay: Option<String>,
+
+    /// Sender address of email digests
+    #[arg(long, default_value = "opensyria@localhost")]
+    digest_smtp_from: String,
+
+    /// Blocks a wallet transaction may wait below the fee floor before it
+    /// is bumped, for fee bump policies that do not set their own
+    #[arg(long, default_value = "3")]
+    fee_bump_stuck_blocks: u64,
+
+    /// Run the watchtower, which submits the refund and expiry transactions
+    /// wallets register once their deadline height is reached
+    #[arg(long, default_value = "false")]
+    watchtower: bool,
+
+    /// Accept propagation reports from P2P nodes at
+    /// /api/v1/telemetry/propagation
+    #[arg(long, default_value = "false")]
+    collect_telemetry: bool,
+
+    /// Seconds of propagation reports merged into the network percentiles
+    #[arg(long, default_value = "3600")]
+    telemetry_window: u64,
+
+    /// Blocks between signed chain-state anchors written to the data
+    /// directory and served at /api/v1/anchors (0 disables)
+    #[arg(long, default_value = "0")]
+    anchor_interval: u64,
+
+    /// Node config file whose mempool budget the conformance endpoints run
+    /// under (defaults to ~/.opensyria/config.toml when it exists)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
     /// Require HTTPS (fails if TLS not configured)
     #[arg(long, default_value = "false")]
     require_tls: bool,
+    /// Export traces to this OTLP/HTTP collector (e.g. http://localhost:4318)
+    #[arg(long)]
+    otlp_endpoint: Option<String>,
+
+    /// Fraction of traces to export when --otlp-endpoint is set
+    #[arg(long, default_value = "1.0")]
+    otlp_sample_ratio: f64,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
-    // Expand tilde in data_dir
-    let data_dir = if cli.data_dir.starts_with("~") {
-        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        PathBuf::from(cli.data_dir.replacen("~", &home, 1))
-    } else {
-        PathBuf::from(cli.data_dir)
+    let otlp = cli.otlp_endpoint.clone().map(|endpoint| OtlpConfig {
+        sample_ratio: cli.otlp_sample_ratio,
+        ..OtlpConfig::new(endpoint, "opensyria-wallet-api")
+    });
+    let _telemetry = init_tracing("info", otlp)
+        .map_err(|e| anyhow::anyhow!("Failed to initialize tracing: {}", e))?;
+
+    // Expand tilde in data_dir and wallet_dir
+    let expand = |path: &str| {
+        if path.starts_with("~") {
+            let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+            PathBuf::from(path.replacen("~", &home, 1))
+        } else {
+            PathBuf::from(path)
+        }
     };
+    let data_dir = expand(&cli.data_dir);
+    let wallet_dir = expand(&cli.wallet_dir);
 
     // Open node
     println!("📂 Opening node at: {}", data_dir.display());
-    let node = Node::open(data_dir)?;
+    let mut node = Node::open(data_dir.clone())?;
+    node.set_chain_params(ChainParams::for_network(&cli.network)?);
+    if cli.anchor_interval > 0 {
+        node.enable_anchoring(cli.anchor_interval)?;
+    }
     let chain_height = node
         .get_blockchain()
         .get_chain_height()
         .map_err(|e| anyhow::anyhow!("Failed to get chain height: {}", e))?;
     println!("✅ Node opened successfully");
     println!("   Chain height: {}", chain_height);
+    println!("   Network: {}", cli.network);
+
+    let passkeys = PasskeyManager::open(
+        WebAuthnConfig {
+            rp_id: cli.webauthn_rp_id.clone(),
+            origin: cli.webauthn_origin.clone(),
+            passkey_only: cli.admin_passkey_only,
+            ..WebAuthnConfig::default()
+        },
+        data_dir.join("admin_passkeys.json"),
+    )?;
+    let digests = DigestScheduler::open(
+        DigestConfig {
+            smtp: cli.digest_smtp_relay.clone().map(|address| SmtpRelay {
+                address,
+                from: cli.digest_smtp_from.clone(),
+            }),
+            ..DigestConfig::default()
+        },
+        data_dir.join("digest_subscriptions.json"),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to load digest subscriptions: {}", e))?;
+    let fee_bumper = FeeBumper::open(
+        FeeBumpConfig {
+            default_stuck_blocks: cli.fee_bump_stuck_blocks.clamp(1, MAX_STUCK_BLOCKS),
+            ..FeeBumpConfig::default()
+        },
+        data_dir.join("fee_bump_policies.json"),
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to load fee bump policies: {}", e))?;
+    let config_path = cli
+        .config
+        .clone()
+        .unwrap_or_else(NodeConfig::default_config_path);
+    let node_config = if config_path.exists() {
+        NodeConfig::load_from_file(&config_path)?
+    } else {
+        NodeConfig::default()
+    };
 
     // Create app state
-    let state = AppState::new(node);
+    let wallet_storage = EncryptedWalletStorage::with_path(wallet_dir)?;
+    let mut state = AppState::new(node)
+        .with_wallet_manager(WalletManager::new(Some(wallet_storage)))
+        .with_load_shedding(LoadShedConfig {
+            max_in_flight: cli.max_in_flight.max(1),
+            target_latency: Duration::from_millis(cli.shed_latency_ms.max(1)),
+            ..LoadShedConfig::default()
+        })
+        .with_admin_bind((!cli.no_admin).then_some(cli.admin_bind))
+        .with_passkeys(passkeys)
+        .with_digests(digests)
+        .with_fee_bumper(fee_bumper)
+        .with_mempool_config(node_config.resources.mempool_config());
+    if cli.watchtower {
+        let watchtower = Watchtower::open(
+            data_dir.join("watchtower.json"),
+            data_dir.join("watchtower_receipts.jsonl"),
+        )
+        .map_err(|e| anyhow::anyhow!("Failed to load watchtower: {}", e))?;
+        println!("🗼 Watchtower enabled");
+        state = state.with_watchtower(watchtower);
+    }
+    if cli.collect_telemetry {
+        state = state.with_telemetry_collector(Duration::from_secs(cli.telemetry_window.max(60)));
+    }
+    state.wallet_manager.spawn_auto_lock();
+
+    if !cli.backends.is_empty() {
+        let config = NodePoolConfig {
+            health_interval: Duration::from_secs(cli.backend_health_interval.max(1)),
+            ..NodePoolConfig::default()
+        };
+        let pool = Arc::new(NodePool::new(
+            cli.backends.clone(),
+            cli.backend_api_key.clone(),
+            config,
+        ));
+        pool.spawn_health_checks();
+        println!("🔀 Backend nodes: {}", cli.backends.join(", "));
+        state = state.with_node_pool(pool);
+    }
+
+    if let Some(path) = &cli.oracle_feeds {
+        let feeds =
+            load_feeds(path).map_err(|e| anyhow::anyhow!("Failed to load oracle feeds: {}", e))?;
+        let key = cli
+            .oracle_key
+            .as_deref()
+            .ok_or_else(|| anyhow::anyhow!("--oracle-key is required with --oracle-feeds"))?;
+        let keypair = opensyria_wallet::sweep::parse_private_key(key)?;
+        let config = RateOracleConfig {
+            refresh_interval: Duration::from_secs(cli.oracle_interval.max(1)),
+            ..RateOracleConfig::default()
+        };
+        let oracle = Arc::new(RateOracle::new(feeds, keypair, config));
+        oracle.spawn_refresh();
+        println!("💱 Rate oracle: {}", oracle.signer().to_hex());
+        state = state.with_oracle(oracle);
+    }
 
     // Validate TLS configuration
     if cli.require_tls && (cli.tls_cert.is_none() || cli.tls_key.is_none()) {
@@ -0,0 +1,57 @@
+//! Signed message verification
+//! التحقق من الرسائل الموقعة
+//!
+//! `POST /api/v1/message/verify` checks an address ownership proof made with
+//! `wallet sign-message`. The body is the signed message envelope itself
+//! (see [`opensyria_core::message`]). A signature that does not verify is
+//! still a successful call: the response is `200` with `valid: false` and
+//! the reason.
+
+use axum::response::Json;
+use opensyria_core::SignedMessage;
+use serde::Serialize;
+
+/// Result of checking a signed message
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifyMessageResponse {
+    /// Whether the signature was made by the address over the message
+    pub valid: bool,
+    pub address: String,
+    pub message: String,
+    pub error: Option<String>,
+}
+
+/// POST /api/v1/message/verify - check a signed message
+pub async fn verify_message(Json(signed): Json<SignedMessage>) -> Json<VerifyMessageResponse> {
+    let error = signed.verify().err().map(|e| e.to_string());
+
+    Json(VerifyMessageResponse {
+        valid: error.is_none(),
+        address: signed.address,
+        message: signed.message,
+        error,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[tokio::test]
+    async fn test_verify_message() {
+        let keypair = KeyPair::generate();
+        let signed = SignedMessage::sign("prove it", &keypair).unwrap();
+        let response = verify_message(Json(signed.clone())).await.0;
+        assert!(response.valid);
+        assert_eq!(response.address, keypair.public_key().to_hex());
+
+        let forged = SignedMessage {
+            message: "prove something else".to_string(),
+            ..signed
+        };
+        let response = verify_message(Json(forged)).await.0;
+        assert!(!response.valid);
+        assert!(response.error.is_some());
+    }
+}
@@ -1,3 +1,7 @@
+use axum::http::StatusCode;
+use opensyria_core::error_code::{self, CodedError, ErrorCode};
+use opensyria_core::ConfirmationStatus;
+use opensyria_node_cli::build_info::BuildInfo;
 use serde::{Deserialize, Serialize};
 
 /// Request to submit a transaction
@@ -15,12 +19,48 @@ pub struct SubmitTransactionRequest {
     pub signature: String,
 }
 
+/// Request carrying a canonical raw transaction
+#[derive(Debug, Deserialize)]
+pub struct RawTransactionRequest {
+    /// Raw transaction hex (version, payload, checksum)
+    pub hex: String,
+    /// Replace the pending transaction with the same sender and nonce,
+    /// which the new one must outbid
+    #[serde(default)]
+    pub replace: bool,
+}
+
+/// Decoded raw transaction
+#[derive(Debug, Serialize)]
+pub struct DecodedTransaction {
+    pub hash: String,
+    pub chain_id: u32,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub nonce: u64,
+    /// Hex-encoded metadata, if any
+    pub data: Option<String>,
+    pub signature: String,
+    /// Whether the signature verifies
+    pub signature_valid: bool,
+    /// Encoded size in bytes
+    pub weight: usize,
+    /// Fee in base units per 1000 bytes
+    pub fee_rate: u64,
+}
+
 /// Response after submitting a transaction
 #[derive(Debug, Serialize)]
 pub struct TransactionResponse {
     pub success: bool,
     pub tx_hash: Option<String>,
     pub message: String,
+    /// Backend nodes the transaction is being relayed to, when a node pool is
+    /// configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relayed: Option<usize>,
 }
 
 /// Balance query response
@@ -29,6 +69,27 @@ pub struct BalanceResponse {
     pub address: String,
     pub balance: u64,
     pub nonce: u64,
+    /// Amount waiting in the mempool to be received
+    pub pending_incoming: u64,
+    /// Amount plus fees waiting in the mempool to be spent
+    pub pending_outgoing: u64,
+}
+
+/// Native asset balances of an account
+#[derive(Debug, Serialize)]
+pub struct AssetBalancesResponse {
+    pub address: String,
+    pub assets: Vec<AssetBalanceEntry>,
+}
+
+/// Balance of one native asset
+#[derive(Debug, Serialize)]
+pub struct AssetBalanceEntry {
+    pub asset_id: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub balance: u64,
 }
 
 /// Blockchain info response
@@ -40,6 +101,19 @@ pub struct BlockchainInfoResponse {
     pub total_transactions: u64,
 }
 
+/// Node build and chain parameters response
+#[derive(Debug, Serialize)]
+pub struct NodeInfoResponse {
+    #[serde(flatten)]
+    pub build: BuildInfo,
+    /// Network the node is serving
+    pub network: String,
+    /// Chain ID transactions must be signed for
+    pub chain_id: u32,
+    /// Hex hash of the chain parameters in use
+    pub chain_params_hash: String,
+}
+
 /// Transaction details
 #[derive(Debug, Serialize)]
 pub struct TransactionDetails {
@@ -51,6 +125,27 @@ pub struct TransactionDetails {
     pub nonce: u64,
     pub block_height: Option<u64>,
     pub confirmed: bool,
+    /// Blocks on top of and including the confirming block
+    pub confirmations: u64,
+    /// Pending, confirming (n/N) or final
+    pub status: ConfirmationStatus,
+    /// Hex transaction data; encrypted memos stay encrypted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+/// Confirmed and pending transactions of an address, newest first
+#[derive(Debug, Serialize)]
+pub struct AccountHistoryResponse {
+    pub address: String,
+    pub transactions: Vec<TransactionDetails>,
+}
+
+/// Query parameters for account history
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    /// Maximum number of entries (default 50, max 500)
+    pub limit: Option<usize>,
 }
 
 /// Mempool status
@@ -61,7 +156,31 @@ pub struct MempoolStatus {
 }
 
 /// Error response
+///
+/// `error` is for people; clients branch on the registered `code` (see
+/// [`opensyria_core::error_code`]), which is flattened next to it.
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
     pub error: String,
+    #[serde(flatten)]
+    pub code: ErrorCode,
+}
+
+impl ErrorResponse {
+    pub fn new(code: ErrorCode, error: impl Into<String>) -> Self {
+        Self {
+            error: error.into(),
+            code,
+        }
+    }
+
+    /// Error without a domain code, classified by its HTTP status
+    pub fn from_status(status: StatusCode, error: impl Into<String>) -> Self {
+        Self::new(error_code::for_http_status(status.as_u16()), error)
+    }
+
+    /// Error carrying the code of a storage, mempool or governance error
+    pub fn from_error(error: &impl CodedError) -> Self {
+        Self::new(error.error_code(), error.to_string())
+    }
 }
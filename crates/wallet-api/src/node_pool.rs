@@ -0,0 +1,573 @@
+//! Backend node pool with health checking and failover
+//! مجموعة العقد الخلفية مع فحص الصحة والتبديل التلقائي
+//!
+//! A wallet API can front several backend nodes, each reached through its own
+//! wallet API. Public reads are forwarded to the healthiest backend (highest
+//! chain height, then lowest latency) and fail over to the next one on a
+//! connection error or 5xx; if no backend answers, the local node serves the
+//! request. Accepted transactions are relayed to every backend in the
+//! background so they reach the network even while some nodes restart. Each
+//! transaction is relayed once per [`RELAY_SEEN_TTL`], and relays carry a hop
+//! count so pools pointing at each other stop after [`MAX_RELAY_HOPS`]. A
+//! backend is marked unhealthy after [`NodePoolConfig::failure_threshold`]
+//! consecutive failures and healthy again on its next successful check or
+//! request.
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::Next,
+    response::{Json, Response},
+};
+use futures::future::join_all;
+use opensyria_core::Transaction;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::AppState;
+
+/// Response header naming the backend (or `local`) that served a read
+pub const SERVED_BY_HEADER: &str = "x-served-by";
+
+/// Request header carrying how many pools a relayed transaction has crossed
+pub const RELAY_HOPS_HEADER: &str = "x-relay-hops";
+
+/// Relays arriving with this many hops are accepted but not relayed further
+pub const MAX_RELAY_HOPS: u32 = 2;
+
+/// How long a relayed transaction is remembered and not relayed again
+pub const RELAY_SEEN_TTL: Duration = Duration::from_secs(600);
+
+/// Cap on remembered relayed transactions
+const MAX_SEEN_RELAYS: usize = 10_000;
+
+/// Pool timing and failure policy
+#[derive(Debug, Clone)]
+pub struct NodePoolConfig {
+    /// Interval between health checks
+    pub health_interval: Duration,
+    /// Timeout for health checks and forwarded requests
+    pub request_timeout: Duration,
+    /// Consecutive failures before a backend is taken out of rotation
+    pub failure_threshold: u32,
+}
+
+impl Default for NodePoolConfig {
+    fn default() -> Self {
+        Self {
+            health_interval: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(5),
+            failure_threshold: 2,
+        }
+    }
+}
+
+/// Health of one backend as reported by `GET /api/v1/nodes`
+#[derive(Debug, Clone, Serialize)]
+pub struct BackendStatus {
+    pub url: String,
+    pub healthy: bool,
+    /// Chain height from the last successful check
+    pub chain_height: Option<u64>,
+    /// Round trip of the last successful check
+    pub latency_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    pub last_error: Option<String>,
+}
+
+/// How many backends accepted a relayed transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelaySummary {
+    pub accepted: usize,
+    pub total: usize,
+}
+
+#[derive(Deserialize)]
+struct InfoBody {
+    chain_height: u64,
+}
+
+/// Pool of backend wallet APIs
+/// مجموعة واجهات المحفظة الخلفية
+pub struct NodePool {
+    backends: Vec<RwLock<BackendStatus>>,
+    api_key: Option<String>,
+    config: NodePoolConfig,
+    http: reqwest::Client,
+    /// Transactions relayed recently, by hash
+    seen: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl NodePool {
+    /// Create a pool for the given backend URLs; backends start healthy
+    /// until a check says otherwise
+    pub fn new(urls: Vec<String>, api_key: Option<String>, config: NodePoolConfig) -> Self {
+        let backends = urls
+            .into_iter()
+            .map(|url| {
+                RwLock::new(BackendStatus {
+                    url: url.trim_end_matches('/').to_string(),
+                    healthy: true,
+                    chain_height: None,
+                    latency_ms: None,
+                    consecutive_failures: 0,
+                    last_error: None,
+                })
+            })
+            .collect();
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            backends,
+            api_key,
+            config,
+            http,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.backends.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.backends.is_empty()
+    }
+
+    /// Snapshot of every backend
+    pub async fn status(&self) -> Vec<BackendStatus> {
+        let mut status = Vec::with_capacity(self.backends.len());
+        for backend in &self.backends {
+            status.push(backend.read().await.clone());
+        }
+        status
+    }
+
+    /// Check every backend once, concurrently
+    pub async fn check_health(&self) {
+        join_all((0..self.backends.len()).map(|index| self.check_backend(index))).await;
+    }
+
+    async fn check_backend(&self, index: usize) {
+        let url = self.backends[index].read().await.url.clone();
+        let started = Instant::now();
+        let result = async {
+            let response = self
+                .http
+                .get(format!("{}/api/v1/blockchain/info", url))
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("health check returned {}", response.status()));
+            }
+            response
+                .json::<InfoBody>()
+                .await
+                .map_err(|e| format!("invalid health response: {}", e))
+        }
+        .await;
+
+        match result {
+            Ok(info) => {
+                let mut backend = self.backends[index].write().await;
+                backend.chain_height = Some(info.chain_height);
+                backend.latency_ms = Some(started.elapsed().as_millis() as u64);
+                mark_success(&mut backend);
+            }
+            Err(error) => self.record_failure(index, error).await,
+        }
+    }
+
+    /// Run health checks every `health_interval` until the pool is dropped
+    pub fn spawn_health_checks(self: &Arc<Self>) -> JoinHandle<()> {
+        let pool = Arc::downgrade(self);
+        let interval = self.config.health_interval;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(pool) = pool.upgrade() else {
+                    break;
+                };
+                pool.check_health().await;
+            }
+        })
+    }
+
+    async fn record_failure(&self, index: usize, error: String) {
+        let mut backend = self.backends[index].write().await;
+        backend.consecutive_failures += 1;
+        if backend.healthy && backend.consecutive_failures >= self.config.failure_threshold {
+            tracing::warn!("Backend node {} is unhealthy: {}", backend.url, error);
+            backend.healthy = false;
+        }
+        backend.last_error = Some(error);
+    }
+
+    /// Healthy backends in read preference order
+    async fn read_order(&self) -> Vec<usize> {
+        let status = self.status().await;
+        read_order(&status)
+    }
+
+    /// Forward a read to the healthiest backend, failing over on connection
+    /// errors and 5xx responses. Returns `None` if no backend answered.
+    pub async fn forward_read(&self, path_and_query: &str) -> Option<(String, reqwest::Response)> {
+        for index in self.read_order().await {
+            let url = self.backends[index].read().await.url.clone();
+            match self
+                .http
+                .get(format!("{}{}", url, path_and_query))
+                .send()
+                .await
+            {
+                Ok(response) if !response.status().is_server_error() => {
+                    mark_success(&mut *self.backends[index].write().await);
+                    return Some((url, response));
+                }
+                Ok(response) => {
+                    let error = format!("{} returned {}", path_and_query, response.status());
+                    self.record_failure(index, error).await;
+                }
+                Err(e) => self.record_failure(index, e.to_string()).await,
+            }
+        }
+        None
+    }
+
+    /// Submit `tx` to every backend, healthy or not, and wait for the results
+    pub async fn relay(&self, tx: &Transaction) -> RelaySummary {
+        self.submit(tx, false, 1).await
+    }
+
+    /// Relay `tx` to every backend in the background, `replace` asking them
+    /// to replace their pending transaction with the same sender and nonce
+    ///
+    /// `hops` is how many pools `tx` already crossed. Returns the number of
+    /// backends the relay was started for: zero if `tx` was relayed within
+    /// [`RELAY_SEEN_TTL`] or already crossed [`MAX_RELAY_HOPS`] pools.
+    pub fn spawn_relay(self: &Arc<Self>, tx: &Transaction, replace: bool, hops: u32) -> usize {
+        if hops >= MAX_RELAY_HOPS || !self.mark_seen(tx.hash()) {
+            return 0;
+        }
+        let pool = Arc::clone(self);
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let summary = pool.submit(&tx, replace, hops + 1).await;
+            report_relay(&tx, summary);
+        });
+        self.backends.len()
+    }
+
+    /// Remember `hash` as relayed; false if it already was within the TTL
+    fn mark_seen(&self, hash: [u8; 32]) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if seen
+            .get(&hash)
+            .is_some_and(|at| now.duration_since(*at) < RELAY_SEEN_TTL)
+        {
+            return false;
+        }
+        if seen.len() >= MAX_SEEN_RELAYS {
+            seen.retain(|_, at| now.duration_since(*at) < RELAY_SEEN_TTL);
+            if seen.len() >= MAX_SEEN_RELAYS {
+                seen.clear();
+            }
+        }
+        seen.insert(hash, now);
+        true
+    }
+
+    async fn submit(&self, tx: &Transaction, replace: bool, hops: u32) -> RelaySummary {
+        let body = json!({ "hex": tx.to_raw_hex(), "replace": replace });
+        let results = join_all((0..self.backends.len()).map(|index| {
+            let body = &body;
+            async move {
+                let url = self.backends[index].read().await.url.clone();
+                let mut request = self
+                    .http
+                    .post(format!("{}/api/v1/transaction/raw", url))
+                    .header(RELAY_HOPS_HEADER, hops)
+                    .json(body);
+                if let Some(key) = &self.api_key {
+                    request = request.bearer_auth(key);
+                }
+                match request.send().await {
+                    Ok(response) if response.status().is_success() => {
+                        mark_success(&mut *self.backends[index].write().await);
+                        true
+                    }
+                    // The backend is up but rejected the transaction (e.g. it
+                    // already has it)
+                    Ok(response) if !response.status().is_server_error() => {
+                        tracing::debug!("Backend {} rejected relay: {}", url, response.status());
+                        false
+                    }
+                    Ok(response) => {
+                        let error = format!("relay returned {}", response.status());
+                        self.record_failure(index, error).await;
+                        false
+                    }
+                    Err(e) => {
+                        self.record_failure(index, e.to_string()).await;
+                        false
+                    }
+                }
+            }
+        }))
+        .await;
+
+        RelaySummary {
+            accepted: results.into_iter().filter(|accepted| *accepted).count(),
+            total: self.backends.len(),
+        }
+    }
+}
+
+fn mark_success(backend: &mut BackendStatus) {
+    if !backend.healthy {
+        tracing::info!("Backend node {} is healthy again", backend.url);
+    }
+    backend.healthy = true;
+    backend.consecutive_failures = 0;
+    backend.last_error = None;
+}
+
+/// Healthy backends, highest chain height first, then lowest latency
+fn read_order(status: &[BackendStatus]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..status.len()).filter(|&i| status[i].healthy).collect();
+    order.sort_by_key(|&i| {
+        (
+            std::cmp::Reverse(status[i].chain_height.unwrap_or(0)),
+            status[i].latency_ms.unwrap_or(u64::MAX),
+        )
+    });
+    order
+}
+
+/// GET /api/v1/nodes - health of the backend nodes (empty without a pool)
+pub async fn node_pool_status(State(state): State<Arc<AppState>>) -> Json<Vec<BackendStatus>> {
+    match &state.node_pool {
+        Some(pool) => Json(pool.status().await),
+        None => Json(Vec::new()),
+    }
+}
+
+/// Relay a transaction submitted here to the pool, if one is configured
+pub fn relay_to_pool(state: &AppState, tx: &Transaction) -> Option<usize> {
+    relay_onward(state, tx, false, 0)
+}
+
+/// Relay a replacement submitted here to the pool, if one is configured
+pub fn relay_replacement_to_pool(state: &AppState, tx: &Transaction) -> Option<usize> {
+    relay_onward(state, tx, true, 0)
+}
+
+/// Relay a transaction that already crossed `hops` pools, if a pool is
+/// configured. See [`NodePool::spawn_relay`].
+pub fn relay_onward(state: &AppState, tx: &Transaction, replace: bool, hops: u32) -> Option<usize> {
+    let pool = state.node_pool.as_ref()?;
+    Some(pool.spawn_relay(tx, replace, hops))
+}
+
+/// Hop count of an incoming relay; zero for direct submissions
+pub fn relay_hops(headers: &HeaderMap) -> u32 {
+    headers
+        .get(RELAY_HOPS_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0)
+}
+
+fn report_relay(tx: &Transaction, summary: RelaySummary) {
+    if summary.accepted < summary.total {
+        tracing::warn!(
+            "Transaction {} relayed to {}/{} backend nodes",
+            hex::encode(tx.hash()),
+            summary.accepted,
+            summary.total
+        );
+    }
+}
+
+/// Serve public reads from the pool, falling back to the local node
+pub async fn pooled_read_middleware(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(pool) = state.node_pool.clone() else {
+        return next.run(request).await;
+    };
+    if request.method() != Method::GET {
+        return next.run(request).await;
+    }
+
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+
+    if let Some((url, upstream)) = pool.forward_read(&path_and_query).await {
+        let status = upstream.status().as_u16();
+        let content_type = upstream.headers().get(header::CONTENT_TYPE).cloned();
+        if let Ok(bytes) = upstream.bytes().await {
+            let mut response = Response::new(Body::from(bytes));
+            *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::OK);
+            if let Some(content_type) = content_type {
+                response
+                    .headers_mut()
+                    .insert(header::CONTENT_TYPE, content_type);
+            }
+            if let Ok(value) = HeaderValue::from_str(&url) {
+                response.headers_mut().insert(SERVED_BY_HEADER, value);
+            }
+            return response;
+        }
+    }
+
+    let mut response = next.run(request).await;
+    response
+        .headers_mut()
+        .insert(SERVED_BY_HEADER, HeaderValue::from_static("local"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+
+    fn backend(url: &str, healthy: bool, height: u64, latency: u64) -> BackendStatus {
+        BackendStatus {
+            url: url.to_string(),
+            healthy,
+            chain_height: Some(height),
+            latency_ms: Some(latency),
+            consecutive_failures: 0,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_read_order_prefers_height_then_latency() {
+        let status = vec![
+            backend("a", true, 10, 5),
+            backend("b", true, 12, 50),
+            backend("c", false, 20, 1),
+            backend("d", true, 12, 10),
+        ];
+        assert_eq!(read_order(&status), vec![3, 1, 0]);
+    }
+
+    async fn serve(router: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// An address nothing listens on
+    async fn dead_url() -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_failover_and_relay() {
+        let live = serve(
+            Router::new()
+                .route(
+                    "/api/v1/blockchain/info",
+                    get(|| async { Json(json!({ "chain_height": 7 })) }),
+                )
+                .route(
+                    "/api/v1/transaction/raw",
+                    axum::routing::post(|| async { Json(json!({ "success": true })) }),
+                ),
+        )
+        .await;
+        let dead = dead_url().await;
+
+        let config = NodePoolConfig {
+            failure_threshold: 1,
+            ..NodePoolConfig::default()
+        };
+        let pool = NodePool::new(vec![dead.clone(), format!("{}/", live)], None, config);
+
+        // Both start healthy; the read fails over past the dead backend
+        let (served_by, response) = pool.forward_read("/api/v1/blockchain/info").await.unwrap();
+        assert_eq!(served_by, live);
+        assert!(response.status().is_success());
+
+        let status = pool.status().await;
+        assert!(!status[0].healthy);
+        assert!(status[0].last_error.is_some());
+        assert!(status[1].healthy);
+
+        pool.check_health().await;
+        let status = pool.status().await;
+        assert_eq!(status[1].chain_height, Some(7));
+        assert!(!status[0].healthy);
+
+        let tx = Transaction::new(
+            opensyria_core::crypto::KeyPair::generate().public_key(),
+            opensyria_core::crypto::KeyPair::generate().public_key(),
+            10,
+            100,
+            0,
+        );
+        assert_eq!(
+            pool.relay(&tx).await,
+            RelaySummary {
+                accepted: 1,
+                total: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_relay_once_within_hop_limit() {
+        let (hops_tx, mut hops_rx) = tokio::sync::mpsc::unbounded_channel();
+        let live = serve(Router::new().route(
+            "/api/v1/transaction/raw",
+            axum::routing::post(move |headers: HeaderMap| {
+                let hops_tx = hops_tx.clone();
+                async move {
+                    let _ = hops_tx.send(relay_hops(&headers));
+                    Json(json!({ "success": true }))
+                }
+            }),
+        ))
+        .await;
+        let pool = Arc::new(NodePool::new(vec![live], None, NodePoolConfig::default()));
+        let tx = Transaction::new(
+            opensyria_core::crypto::KeyPair::generate().public_key(),
+            opensyria_core::crypto::KeyPair::generate().public_key(),
+            10,
+            100,
+            0,
+        );
+
+        // Relayed in the background with the hop count bumped
+        assert_eq!(pool.spawn_relay(&tx, false, 1), 1);
+        assert_eq!(hops_rx.recv().await, Some(2));
+
+        // Not relayed again, nor past the hop limit
+        assert_eq!(pool.spawn_relay(&tx, false, 0), 0);
+        let other = Transaction::new(tx.from, tx.to, 11, 100, 1);
+        assert_eq!(pool.spawn_relay(&other, false, MAX_RELAY_HOPS), 0);
+    }
+}
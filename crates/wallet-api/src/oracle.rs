@@ -0,0 +1,360 @@
+//! Exchange rate feeds and signed rate attestations
+//! مصادر أسعار الصرف وشهادات الأسعار الموقعة
+//!
+//! When started with `--oracle-feeds`, the wallet API polls the configured
+//! price feeds every [`RateOracleConfig::refresh_interval`], takes the median
+//! of the sources reporting each pair, and signs it with the oracle key as a
+//! [`RateAttestation`]. `GET /api/v1/rates` serves these attestations next
+//! to the rates whitelisted oracles published on chain; the on-chain rates
+//! are served whether or not feeds are configured.
+//!
+//! Signing an attestation does not publish it. A whitelisted key publishes
+//! rates on chain with an [`OracleUpdate`](opensyria_core::oracle::OracleUpdate)
+//! transaction.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use futures::future::join_all;
+use opensyria_core::crypto::{KeyPair, PublicKey};
+use opensyria_core::oracle::{
+    format_rate, validate_pair, OracleRate, RateAttestation, RateQuote, RATE_SCALE,
+};
+use opensyria_core::CodedError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::{models::ErrorResponse, AppState};
+
+/// One source of a rate
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedConfig {
+    /// Pair the feed reports, e.g. `SYP/USD`
+    pub pair: String,
+    /// URL returning JSON
+    pub url: String,
+    /// JSON pointer to the rate in the response, e.g. `/rates/SYP`
+    pub pointer: String,
+    /// Whether the feed reports the inverse of `pair`
+    #[serde(default)]
+    pub invert: bool,
+}
+
+/// Load feeds from a JSON file holding an array of [`FeedConfig`]
+pub fn load_feeds(path: &Path) -> anyhow::Result<Vec<FeedConfig>> {
+    let text = std::fs::read_to_string(path)?;
+    let feeds: Vec<FeedConfig> = serde_json::from_str(&text)?;
+    for feed in &feeds {
+        validate_pair(&feed.pair).map_err(|e| anyhow::anyhow!("{}", e))?;
+    }
+    Ok(feeds)
+}
+
+/// Polling policy
+#[derive(Debug, Clone)]
+pub struct RateOracleConfig {
+    /// Interval between feed refreshes
+    pub refresh_interval: Duration,
+    /// Timeout for each feed request
+    pub request_timeout: Duration,
+}
+
+impl Default for RateOracleConfig {
+    fn default() -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(60),
+            request_timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Signed rates from the configured feeds
+/// أوراكل أسعار الصرف
+pub struct RateOracle {
+    feeds: Vec<FeedConfig>,
+    keypair: KeyPair,
+    config: RateOracleConfig,
+    http: reqwest::Client,
+    /// Latest attestation per pair; a pair keeps its last rate while all of
+    /// its feeds fail
+    attestations: RwLock<BTreeMap<String, RateAttestation>>,
+}
+
+impl RateOracle {
+    pub fn new(feeds: Vec<FeedConfig>, keypair: KeyPair, config: RateOracleConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            feeds,
+            keypair,
+            config,
+            http,
+            attestations: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Key that signs the attestations
+    pub fn signer(&self) -> PublicKey {
+        self.keypair.public_key()
+    }
+
+    /// Latest attestation of every pair, ordered by pair
+    pub async fn attestations(&self) -> Vec<RateAttestation> {
+        self.attestations.read().await.values().cloned().collect()
+    }
+
+    /// Poll every feed once, concurrently, and sign the median of each pair
+    pub async fn refresh(&self) {
+        let results = join_all(self.feeds.iter().map(|feed| self.fetch(feed))).await;
+
+        let mut by_pair: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+        for (feed, result) in self.feeds.iter().zip(results) {
+            match result {
+                Ok(rate) => by_pair.entry(&feed.pair).or_default().push(rate),
+                Err(error) => tracing::warn!("Rate feed {} failed: {}", feed.url, error),
+            }
+        }
+
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        let mut attestations = self.attestations.write().await;
+        for (pair, mut rates) in by_pair {
+            let quote = RateQuote {
+                pair: pair.to_string(),
+                rate: median(&mut rates),
+                timestamp,
+            };
+            attestations.insert(
+                quote.pair.clone(),
+                RateAttestation::sign(quote, &self.keypair),
+            );
+        }
+    }
+
+    async fn fetch(&self, feed: &FeedConfig) -> Result<u64, String> {
+        let response = self
+            .http
+            .get(&feed.url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("feed returned {}", response.status()));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| format!("invalid feed response: {}", e))?;
+        let value = body
+            .pointer(&feed.pointer)
+            .ok_or_else(|| format!("no value at {}", feed.pointer))?;
+        parse_rate(value, feed.invert).ok_or_else(|| format!("invalid rate {}", value))
+    }
+
+    /// Refresh feeds every `refresh_interval` until the oracle is dropped
+    pub fn spawn_refresh(self: &Arc<Self>) -> JoinHandle<()> {
+        let oracle = Arc::downgrade(self);
+        let interval = self.config.refresh_interval;
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let Some(oracle) = oracle.upgrade() else {
+                    break;
+                };
+                oracle.refresh().await;
+            }
+        })
+    }
+}
+
+/// Fixed-point rate from a JSON number or numeric string
+fn parse_rate(value: &Value, invert: bool) -> Option<u64> {
+    let rate = match value {
+        Value::Number(number) => number.as_f64()?,
+        Value::String(text) => text.trim().parse().ok()?,
+        _ => return None,
+    };
+    let rate = if invert { 1.0 / rate } else { rate };
+    let scaled = (rate * RATE_SCALE as f64).round();
+    (scaled.is_finite() && scaled >= 1.0 && scaled < u64::MAX as f64).then_some(scaled as u64)
+}
+
+/// Median of a non-empty list, averaging the middle two of an even count
+fn median(rates: &mut [u64]) -> u64 {
+    rates.sort_unstable();
+    let middle = rates.len() / 2;
+    if rates.len() % 2 == 0 {
+        rates[middle - 1] / 2 + rates[middle] / 2 + (rates[middle - 1] % 2 + rates[middle] % 2) / 2
+    } else {
+        rates[middle]
+    }
+}
+
+/// A signed rate from this server's feeds
+#[derive(Debug, Clone, Serialize)]
+pub struct AttestedRate {
+    pub pair: String,
+    /// Fixed-point rate, 8 decimals
+    pub rate: u64,
+    /// Rate as a decimal string, for display
+    pub rate_decimal: String,
+    pub timestamp: u64,
+    pub signer: String,
+    /// Hex signature over the quote, see `RateAttestation::signing_hash`
+    pub signature: String,
+}
+
+impl From<RateAttestation> for AttestedRate {
+    fn from(attestation: RateAttestation) -> Self {
+        Self {
+            rate_decimal: format_rate(attestation.quote.rate),
+            pair: attestation.quote.pair,
+            rate: attestation.quote.rate,
+            timestamp: attestation.quote.timestamp,
+            signer: attestation.signer.to_hex(),
+            signature: hex::encode(attestation.signature),
+        }
+    }
+}
+
+/// The newest rate of a pair published on chain
+#[derive(Debug, Clone, Serialize)]
+pub struct OnchainRate {
+    pub pair: String,
+    pub rate: u64,
+    pub rate_decimal: String,
+    pub timestamp: u64,
+    /// Oracle key that published it
+    pub publisher: String,
+    /// Block that included it
+    pub height: u64,
+}
+
+impl From<OracleRate> for OnchainRate {
+    fn from(rate: OracleRate) -> Self {
+        Self {
+            rate_decimal: format_rate(rate.quote.rate),
+            pair: rate.quote.pair,
+            rate: rate.quote.rate,
+            timestamp: rate.quote.timestamp,
+            publisher: rate.publisher.to_hex(),
+            height: rate.height,
+        }
+    }
+}
+
+/// Response of `GET /api/v1/rates`
+#[derive(Debug, Clone, Serialize)]
+pub struct RatesResponse {
+    /// Key signing `attestations`; `None` without configured feeds
+    pub signer: Option<String>,
+    pub attestations: Vec<AttestedRate>,
+    pub onchain: Vec<OnchainRate>,
+}
+
+/// GET /api/v1/rates - signed feed rates and the newest on-chain rates
+pub async fn get_rates(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RatesResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let (signer, attestations) = match &state.oracle {
+        Some(oracle) => (
+            Some(oracle.signer().to_hex()),
+            oracle
+                .attestations()
+                .await
+                .into_iter()
+                .map(AttestedRate::from)
+                .collect(),
+        ),
+        None => (None, Vec::new()),
+    };
+
+    let onchain = state
+        .node
+        .read()
+        .await
+        .get_state()
+        .get_oracle_rates()
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    e.error_code(),
+                    format!("Failed to load rates: {}", e),
+                )),
+            )
+        })?
+        .into_iter()
+        .map(OnchainRate::from)
+        .collect();
+
+    Ok(Json(RatesResponse {
+        signer,
+        attestations,
+        onchain,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::get, Router};
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_rate_and_median() {
+        assert_eq!(parse_rate(&json!(13000.5), false), Some(1_300_050_000_000));
+        assert_eq!(parse_rate(&json!("0.25"), false), Some(25_000_000));
+        assert_eq!(parse_rate(&json!(4), true), Some(25_000_000));
+        assert_eq!(parse_rate(&json!(0), false), None);
+        assert_eq!(parse_rate(&json!(-1.5), false), None);
+        assert_eq!(parse_rate(&json!({ "rate": 1 }), false), None);
+
+        assert_eq!(median(&mut [5, 1, 3]), 3);
+        assert_eq!(median(&mut [4, 1, 3, 10]), 3);
+        assert_eq!(median(&mut [u64::MAX, u64::MAX]), u64::MAX);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_signs_median_of_feeds() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let router = Router::new()
+            .route(
+                "/a",
+                get(|| async { Json(json!({ "rates": { "SYP": 13000 } })) }),
+            )
+            .route("/b", get(|| async { Json(json!({ "price": "13100" })) }))
+            .route("/c", get(|| async { Json(json!({ "usd": 0.0001 })) }));
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let feed = |path: &str, pair: &str, pointer: &str, invert| FeedConfig {
+            pair: pair.to_string(),
+            url: format!("{}{}", url, path),
+            pointer: pointer.to_string(),
+            invert,
+        };
+        let feeds = vec![
+            feed("/a", "SYP/USD", "/rates/SYP", false),
+            feed("/b", "SYP/USD", "/price", false),
+            feed("/c", "SYP/USD", "/usd", true),
+            feed("/c", "USD/SYP", "/missing", false),
+        ];
+        let oracle = RateOracle::new(feeds, KeyPair::generate(), RateOracleConfig::default());
+        oracle.refresh().await;
+
+        let attestations = oracle.attestations().await;
+        assert_eq!(attestations.len(), 1);
+        assert_eq!(attestations[0].quote.pair, "SYP/USD");
+        assert_eq!(attestations[0].quote.rate, 13_000 * RATE_SCALE);
+        assert_eq!(attestations[0].signer, oracle.signer());
+        assert!(attestations[0].verify().is_ok());
+    }
+}
@@ -0,0 +1,131 @@
+//! Payment request URIs
+//! روابط طلب الدفع
+//!
+//! `POST /api/v1/payment-request` builds an `opensyria:` URI that a merchant
+//! can show as a QR code, and `POST /api/v1/payment-request/parse` turns a
+//! scanned URI back into its fields so a client can pre-fill a transfer. Both
+//! use [`opensyria_wallet::uri`], the same encoding as `wallet qr` and
+//! `wallet send --uri`. Neither touches chain state.
+
+use axum::{http::StatusCode, response::Json};
+use opensyria_core::crypto::PublicKey;
+use opensyria_wallet::uri::{format_syl_amount, PaymentRequest};
+use serde::{Deserialize, Serialize};
+
+use crate::models::ErrorResponse;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn bad_request(message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(ErrorResponse::from_status(StatusCode::BAD_REQUEST, message)),
+    )
+}
+
+/// Fields of a payment request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentRequestFields {
+    /// Hex-encoded recipient public key
+    pub address: String,
+    /// Amount in smallest units
+    pub amount: Option<u64>,
+    pub memo: Option<String>,
+    pub label: Option<String>,
+    /// Unix time in seconds after which the request should not be paid
+    pub expires: Option<u64>,
+}
+
+/// URI to parse
+#[derive(Debug, Deserialize)]
+pub struct ParsePaymentRequest {
+    pub uri: String,
+}
+
+/// A payment request with its URI
+#[derive(Debug, Serialize)]
+pub struct PaymentRequestResponse {
+    pub uri: String,
+    #[serde(flatten)]
+    pub request: PaymentRequestFields,
+    /// Amount in SYL, for display
+    pub amount_syl: Option<String>,
+    /// Whether the request has already expired
+    pub expired: bool,
+}
+
+impl From<&PaymentRequest> for PaymentRequestResponse {
+    fn from(request: &PaymentRequest) -> Self {
+        let now = chrono::Utc::now().timestamp() as u64;
+        Self {
+            uri: request.to_uri(),
+            request: PaymentRequestFields {
+                address: request.address.to_hex(),
+                amount: request.amount,
+                memo: request.memo.clone(),
+                label: request.label.clone(),
+                expires: request.expires,
+            },
+            amount_syl: request.amount.map(format_syl_amount),
+            expired: request.is_expired(now),
+        }
+    }
+}
+
+/// POST /api/v1/payment-request - build an `opensyria:` URI
+pub async fn create_payment_request(
+    Json(fields): Json<PaymentRequestFields>,
+) -> ApiResult<PaymentRequestResponse> {
+    let address = PublicKey::from_hex(&fields.address)
+        .map_err(|_| bad_request("Invalid recipient address"))?;
+    let request = PaymentRequest {
+        address,
+        amount: fields.amount,
+        memo: fields.memo,
+        label: fields.label,
+        expires: fields.expires,
+    };
+    request.validate().map_err(|e| bad_request(e.to_string()))?;
+
+    Ok(Json(PaymentRequestResponse::from(&request)))
+}
+
+/// POST /api/v1/payment-request/parse - decode an `opensyria:` URI
+pub async fn parse_payment_request(
+    Json(body): Json<ParsePaymentRequest>,
+) -> ApiResult<PaymentRequestResponse> {
+    let request = PaymentRequest::parse(&body.uri).map_err(|e| bad_request(e.to_string()))?;
+    Ok(Json(PaymentRequestResponse::from(&request)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[tokio::test]
+    async fn test_create_then_parse() {
+        let fields = PaymentRequestFields {
+            address: KeyPair::generate().public_key().to_hex(),
+            amount: Some(2_500_000),
+            memo: Some("Invoice 7".to_string()),
+            label: None,
+            expires: None,
+        };
+
+        let Json(created) = create_payment_request(Json(fields.clone())).await.unwrap();
+        assert_eq!(created.amount_syl.as_deref(), Some("2.5"));
+        assert!(!created.expired);
+
+        let Json(parsed) = parse_payment_request(Json(ParsePaymentRequest { uri: created.uri }))
+            .await
+            .unwrap();
+        assert_eq!(parsed.request, fields);
+
+        let bad = ParsePaymentRequest {
+            uri: "opensyria:xyz".to_string(),
+        };
+        let (status, _) = parse_payment_request(Json(bad)).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
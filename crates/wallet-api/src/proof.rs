@@ -0,0 +1,284 @@
+//! Balance proofs against the state root
+//! إثباتات الأرصدة مقابل جذر الحالة
+//!
+//! `GET /api/v1/proof/balance/{address}` returns the account leaf and the
+//! Merkle path from it to the state root at the chain tip. A light wallet
+//! that already trusts the root can then check a balance served by an
+//! untrusted provider. State roots are computed by nodes and not yet
+//! committed in block headers, so the wallet must get the root from a source
+//! it trusts (its own node, or several independent providers agreeing)
+//! before a proof means anything. A proof checked only against the root in
+//! the same response is exactly as trustworthy as the node that served it:
+//! a dishonest node can make up a state, root included.
+//!
+//! The tree is built off the async workers once per tip and shared by all
+//! requests at that tip; the endpoint has its own, tighter rate limit
+//! ([`rate_limit_config`]) because a request after every new block pays
+//! for a full state scan.
+//!
+//! Nodes keep only the tip state. `?block=<height|hash>` pins the request to
+//! a block; if the tip has moved on, the response is `409` and the wallet
+//! retries against the new tip.
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use opensyria_core::crypto::PublicKey;
+use opensyria_core::{AccountLeaf, StateProof, StateTree};
+use opensyria_storage::StorageError;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinError;
+
+use crate::rate_limit::RateLimitConfig;
+use crate::{models::ErrorResponse, AppState};
+
+/// Rate limit of the balance proof endpoint, per client IP
+pub fn rate_limit_config() -> RateLimitConfig {
+    RateLimitConfig {
+        max_requests: 20,
+        window: Duration::from_secs(60),
+        ban_duration: Duration::from_secs(60),
+    }
+}
+
+/// Why no tree could be built for a tip
+enum TreeError {
+    Storage(StorageError),
+    /// A block arrived between reading the tip and building its tree
+    TipMoved,
+    Task(JoinError),
+}
+
+/// State tree of the current tip, rebuilt when the tip changes
+#[derive(Default)]
+pub struct StateTreeCache {
+    tree: Mutex<Option<([u8; 32], Arc<StateTree>)>>,
+}
+
+impl StateTreeCache {
+    /// Tree for `tip`, building it with `build` on a blocking thread on a
+    /// miss; requests for the same tip wait for that one build
+    async fn get_or_build(
+        &self,
+        tip: [u8; 32],
+        build: impl FnOnce() -> Result<StateTree, TreeError> + Send + 'static,
+    ) -> Result<Arc<StateTree>, TreeError> {
+        let mut cached = self.tree.lock().await;
+        if let Some((hash, tree)) = cached.as_ref() {
+            if *hash == tip {
+                return Ok(tree.clone());
+            }
+        }
+        let tree = tokio::task::spawn_blocking(build)
+            .await
+            .map_err(TreeError::Task)??;
+        let tree = Arc::new(tree);
+        *cached = Some((tip, tree.clone()));
+        Ok(tree)
+    }
+}
+
+/// Query parameters for balance proofs
+#[derive(Debug, Deserialize)]
+pub struct ProofQuery {
+    /// Block height or hex hash the proof must be against (default: tip)
+    pub block: Option<String>,
+}
+
+/// Account leaf and Merkle path to a block's state root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceProofResponse {
+    pub address: String,
+    pub balance: u64,
+    pub nonce: u64,
+    /// Position of the account among all accounts sorted by address
+    pub index: u64,
+    /// Number of accounts in the state
+    pub leaf_count: u64,
+    /// Hex sibling hashes from the leaf level up
+    pub siblings: Vec<String>,
+    /// Hex state root the path leads to
+    pub state_root: String,
+    pub block_height: u64,
+    pub block_hash: String,
+}
+
+impl BalanceProofResponse {
+    fn new(proof: StateProof, state_root: [u8; 32], height: u64, hash: [u8; 32]) -> Self {
+        Self {
+            address: proof.leaf.address.to_hex(),
+            balance: proof.leaf.balance,
+            nonce: proof.leaf.nonce,
+            index: proof.index,
+            leaf_count: proof.leaf_count,
+            siblings: proof.siblings.iter().map(hex::encode).collect(),
+            state_root: hex::encode(state_root),
+            block_height: height,
+            block_hash: hex::encode(hash),
+        }
+    }
+
+    /// Decode back into a proof a client can verify, or `None` if malformed
+    pub fn to_proof(&self) -> Option<StateProof> {
+        let siblings = self
+            .siblings
+            .iter()
+            .map(|sibling| hex::decode(sibling).ok()?.try_into().ok())
+            .collect::<Option<Vec<[u8; 32]>>>()?;
+
+        Some(StateProof {
+            leaf: AccountLeaf {
+                address: PublicKey::from_hex(&self.address).ok()?,
+                balance: self.balance,
+                nonce: self.nonce,
+            },
+            index: self.index,
+            leaf_count: self.leaf_count,
+            siblings,
+        })
+    }
+}
+
+/// Whether `block` names the tip at `height` with `hash`
+fn is_tip(block: &str, height: u64, hash: &[u8; 32]) -> bool {
+    match block.parse::<u64>() {
+        Ok(requested) => requested == height,
+        Err(_) => block.eq_ignore_ascii_case(&hex::encode(hash)),
+    }
+}
+
+fn error(status: StatusCode, message: String) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// GET /api/v1/proof/balance/{address} - account leaf with its Merkle path
+/// إثبات رصيد الحساب مع مسار ميركل
+pub async fn balance_proof(
+    State(state): State<Arc<AppState>>,
+    Path(address): Path<String>,
+    Query(query): Query<ProofQuery>,
+) -> Result<Json<BalanceProofResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let public_key = PublicKey::from_hex(&address).map_err(|_| {
+        error(
+            StatusCode::BAD_REQUEST,
+            "Invalid address format".to_string(),
+        )
+    })?;
+
+    let internal = |e: StorageError| {
+        error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to read chain state: {}", e),
+        )
+    };
+    let (height, tip) = {
+        let node = state.node.read().await;
+        let blockchain = node.get_blockchain();
+        let height = blockchain.get_chain_height().map_err(internal)?;
+        let tip = blockchain
+            .get_chain_tip()
+            .map_err(internal)?
+            .unwrap_or([0u8; 32]);
+        (height, tip)
+    };
+    let moved = || {
+        error(
+            StatusCode::CONFLICT,
+            format!(
+                "State is only kept for the chain tip (height {}, hash {})",
+                height,
+                hex::encode(tip)
+            ),
+        )
+    };
+
+    if let Some(block) = query.block.as_deref() {
+        if !is_tip(block, height, &tip) {
+            return Err(moved());
+        }
+    }
+
+    let node = state.node.clone();
+    let tree = state
+        .state_trees
+        .get_or_build(tip, move || {
+            let node = node.blocking_read();
+            let current = node
+                .get_blockchain()
+                .get_chain_tip()
+                .map_err(TreeError::Storage)?
+                .unwrap_or([0u8; 32]);
+            if current != tip {
+                return Err(TreeError::TipMoved);
+            }
+            node.get_state().state_tree().map_err(TreeError::Storage)
+        })
+        .await
+        .map_err(|e| match e {
+            TreeError::Storage(e) => internal(e),
+            TreeError::TipMoved => moved(),
+            TreeError::Task(e) => error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("State tree build failed: {}", e),
+            ),
+        })?;
+    let proof = tree.prove(&public_key).ok_or_else(|| {
+        error(
+            StatusCode::NOT_FOUND,
+            "Address has no account in the state at this block".to_string(),
+        )
+    })?;
+
+    Ok(Json(BalanceProofResponse::new(
+        proof,
+        tree.root(),
+        height,
+        tip,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_response_round_trips_to_verifiable_proof() {
+        let leaves: Vec<AccountLeaf> = (0..5)
+            .map(|i| AccountLeaf {
+                address: KeyPair::generate().public_key(),
+                balance: 10_000 + i,
+                nonce: i,
+            })
+            .collect();
+        let tree = StateTree::new(leaves.clone());
+        let proof = tree.prove(&leaves[3].address).unwrap();
+
+        let response = BalanceProofResponse::new(proof.clone(), tree.root(), 7, [9u8; 32]);
+        let json = serde_json::to_string(&response).unwrap();
+        let decoded: BalanceProofResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.to_proof(), Some(proof));
+
+        // A provider lying about the balance is caught
+        let lying = BalanceProofResponse {
+            balance: 1_000_000,
+            ..decoded
+        };
+        let root: [u8; 32] = hex::decode(&lying.state_root).unwrap().try_into().unwrap();
+        assert!(!lying.to_proof().unwrap().verify(&root));
+    }
+
+    #[test]
+    fn test_block_selector_matches_tip_only() {
+        let hash = [0xabu8; 32];
+        assert!(is_tip("12", 12, &hash));
+        assert!(!is_tip("11", 12, &hash));
+        assert!(is_tip(&hex::encode(hash).to_uppercase(), 12, &hash));
+        assert!(!is_tip(&hex::encode([0u8; 32]), 12, &hash));
+    }
+}
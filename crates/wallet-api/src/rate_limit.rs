@@ -7,6 +7,7 @@ use axum::{
     middleware::Next,
     response::{IntoResponse, Json, Response},
 };
+use opensyria_core::error_code::{self, ErrorCode};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::net::IpAddr;
@@ -171,6 +172,8 @@ pub enum RateLimitError {
 #[derive(Serialize)]
 struct RateLimitResponse {
     error: String,
+    #[serde(flatten)]
+    code: ErrorCode,
     limit: Option<usize>,
     window_secs: Option<u64>,
 }
@@ -185,6 +188,7 @@ impl IntoResponse for RateLimitError {
                         "Rate limit exceeded: {} requests per {} seconds",
                         limit, window_secs
                     ),
+                    code: error_code::RATE_LIMITED,
                     limit: Some(limit),
                     window_secs: Some(window_secs),
                 },
@@ -193,6 +197,7 @@ impl IntoResponse for RateLimitError {
                 StatusCode::FORBIDDEN,
                 RateLimitResponse {
                     error: "IP address is temporarily banned due to excessive requests".to_string(),
+                    code: error_code::RATE_LIMITED,
                     limit: None,
                     window_secs: None,
                 },
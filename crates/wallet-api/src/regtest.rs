@@ -0,0 +1,116 @@
+//! Block production and clock control for regtest
+//! إنتاج الكتل والتحكم بالساعة في شبكة الاختبار المحلية
+//!
+//! Integration tests of downstream applications need to confirm their
+//! transactions without running a miner. On a regtest node,
+//! `POST /api/v1/regtest/generate` mines blocks to an address on demand and
+//! `POST /api/v1/regtest/mocktime` pins the node clock, so block timestamps
+//! are the same on every run. Both are refused on other networks and are
+//! also reachable as the JSON-RPC methods `generatetoaddress` and
+//! `setmocktime`.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use opensyria_core::{clock, crypto::PublicKey};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{models::ErrorResponse, AppState};
+
+/// Most blocks generated by one request
+pub const MAX_GENERATE_BLOCKS: u32 = 1000;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// Blocks to mine and the address receiving their rewards
+#[derive(Debug, Deserialize)]
+pub struct GenerateRequest {
+    pub blocks: u32,
+    /// Hex-encoded public key
+    pub address: String,
+}
+
+/// Hashes of the generated blocks
+#[derive(Debug, Serialize)]
+pub struct GenerateResponse {
+    /// Hex block hashes in chain order
+    pub blocks: Vec<String>,
+    /// Chain height after the last block
+    pub height: u64,
+}
+
+/// Mock time to set; 0 returns to the system clock
+#[derive(Debug, Deserialize)]
+pub struct MockTimeRequest {
+    pub timestamp: u64,
+}
+
+/// Clock after the change
+#[derive(Debug, Serialize)]
+pub struct MockTimeResponse {
+    /// Mock time in Unix seconds, if set
+    pub mock_time: Option<u64>,
+    /// Time the node now uses for new blocks
+    pub now: u64,
+}
+
+/// Refuse unless the node runs regtest
+async fn require_regtest(state: &AppState) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let node = state.node.read().await;
+    let network = &node.get_blockchain().chain_params().network;
+    if network != "regtest" {
+        return Err(error(
+            StatusCode::FORBIDDEN,
+            format!("Only available on regtest (this node runs {})", network),
+        ));
+    }
+    Ok(())
+}
+
+/// POST /api/v1/regtest/generate - mine blocks to an address
+/// تعدين كتل لصالح عنوان محدد
+pub async fn generate_to_address(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<GenerateRequest>,
+) -> ApiResult<GenerateResponse> {
+    require_regtest(&state).await?;
+    if request.blocks == 0 || request.blocks > MAX_GENERATE_BLOCKS {
+        return Err(error(
+            StatusCode::BAD_REQUEST,
+            format!("blocks must be between 1 and {}", MAX_GENERATE_BLOCKS),
+        ));
+    }
+    let address = PublicKey::from_hex(&request.address)
+        .map_err(|_| error(StatusCode::BAD_REQUEST, "Invalid address format"))?;
+
+    let mut node = state.node.write().await;
+    let hashes = node
+        .generate_to_address(request.blocks, &address)
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let height = node
+        .get_height()
+        .map_err(|e| error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(GenerateResponse {
+        blocks: hashes.iter().map(hex::encode).collect(),
+        height,
+    }))
+}
+
+/// POST /api/v1/regtest/mocktime - pin the node clock
+/// تثبيت ساعة العقدة على وقت محدد
+pub async fn set_mock_time(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MockTimeRequest>,
+) -> ApiResult<MockTimeResponse> {
+    require_regtest(&state).await?;
+    clock::set_mock_time(Some(request.timestamp).filter(|&timestamp| timestamp > 0));
+
+    Ok(Json(MockTimeResponse {
+        mock_time: clock::mock_time(),
+        now: clock::now(),
+    }))
+}
@@ -5,11 +5,13 @@ use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::set_header::SetResponseHeaderLayer;
 use tower_http::trace::TraceLayer;
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::{api, auth::Permission, AppState};
+use crate::{admin, api, auth::Permission, batch, AppState};
 
 /// Start the wallet API server with optional TLS
+///
+/// The caller installs the tracing subscriber (see `opensyria_metrics::telemetry`).
 pub async fn start_server(
     state: AppState,
     host: &str,
@@ -17,14 +19,6 @@ pub async fn start_server(
     tls_cert: Option<PathBuf>,
     tls_key: Option<PathBuf>,
 ) -> anyhow::Result<()> {
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info".into()),
-        )
-        .init();
-
     // Create default admin API key if none exists
     let key_count = state.api_key_manager.list_keys().await.len();
     if key_count == 0 {
@@ -42,7 +36,36 @@ pub async fn start_server(
         info!("   Use as: Authorization: Bearer {}", raw_key);
     }
 
+    let load_shedding = state.load_shedder.config().clone();
     let state = Arc::new(state);
+    state.digests.spawn_scheduler(&state);
+    state.fee_bumper.spawn(&state);
+    if let Some(watchtower) = &state.watchtower {
+        watchtower.spawn(&state);
+    }
+
+    // Node control endpoints get their own listener, loopback by default
+    if let Some(admin_addr) = state.admin_bind {
+        if !admin::is_loopback(&admin_addr) {
+            warn!(
+                "⚠️  Admin endpoints bound to non-loopback address {}",
+                admin_addr
+            );
+        }
+        let listener = tokio::net::TcpListener::bind(admin_addr).await?;
+        let admin_app = admin::admin_router(state.clone()).layer(TraceLayer::new_for_http());
+        info!("🛠️  Admin API (admin keys only) on http://{}", admin_addr);
+        info!("   GET  /api/v1/admin/mining");
+        info!("   POST /api/v1/admin/mining/start | stop | settings");
+        info!("   POST /api/v1/admin/peers/dial | ban");
+        info!("   POST /api/v1/admin/mempool/flush");
+        info!("   POST /api/v1/admin/keys/:id/rotate");
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, admin_app).await {
+                warn!("Admin API server stopped: {}", e);
+            }
+        });
+    }
 
     // Create security headers
     let security_headers = ServiceBuilder::new()
@@ -90,12 +113,35 @@ pub async fn start_server(
             info!("📡 Endpoints:");
             info!("   [PROTECTED] POST /api/v1/transaction/submit");
             info!("   [PROTECTED] GET  /api/v1/mempool/status");
+            info!("   [PROTECTED] GET  /api/v1/wallets");
+            info!("   [PROTECTED] POST /api/v1/wallets/:name/unlock | lock | send");
+            info!("   [PROTECTED] GET  /api/v1/wallets/:name/history");
+            info!("   [PROTECTED] GET | PUT | DELETE /api/v1/wallets/:name/fee-bump");
+            info!("   [PROTECTED] GET | POST /api/v1/wallets/:name/watchtower | DELETE .../:id");
+            info!("   [PROTECTED] GET  /api/v1/wallets/:name/watchtower/receipts");
+            info!("   [PROTECTED] GET  /metrics | /api/v1/metrics/latency");
+            info!("   [PROTECTED] GET  /api/v1/nodes");
+            info!("   [PROTECTED] POST /api/v1/mining/validate-template");
+            info!("   [PROTECTED] GET  /api/v1/digests | POST | DELETE /api/v1/digests/:id");
             info!("   [PUBLIC]    GET  /api/v1/account/:address/balance");
             info!("   [PUBLIC]    GET  /api/v1/blockchain/info");
+            info!("   [PUBLIC]    GET  /api/v1/proof/balance/:address?block=...");
+            info!("   [PUBLIC]    POST /api/v1/message/verify");
+            info!("   [PUBLIC]    GET  /api/v1/events?addresses=... (server-sent events)");
+            info!("   [PUBLIC]    GET  /api/v1/node/info");
             info!("   [PUBLIC]    GET  /health");
+            info!(
+                "   [MIXED]     POST /api/v1/batch (max {} items)",
+                batch::MAX_BATCH_SIZE
+            );
+            info!("   [MIXED]     POST /api/v1/rpc   (JSON-RPC 2.0, single or batch)");
             info!("");
             info!("🔐 Authentication: Bearer token required for protected endpoints");
             info!("🚦 Rate limiting: 100 requests per minute per IP");
+            info!(
+                "🧯 Load shedding: 503 beyond {} requests in flight (anonymous reads first)",
+                load_shedding.max_in_flight
+            );
             info!("🛡️  Security headers enabled: HSTS, X-Frame-Options, CSP");
 
             axum_server::bind_rustls(addr.parse()?, config)
@@ -111,12 +157,35 @@ pub async fn start_server(
             info!("📡 Endpoints:");
             info!("   [PROTECTED] POST /api/v1/transaction/submit");
             info!("   [PROTECTED] GET  /api/v1/mempool/status");
+            info!("   [PROTECTED] GET  /api/v1/wallets");
+            info!("   [PROTECTED] POST /api/v1/wallets/:name/unlock | lock | send");
+            info!("   [PROTECTED] GET  /api/v1/wallets/:name/history");
+            info!("   [PROTECTED] GET | PUT | DELETE /api/v1/wallets/:name/fee-bump");
+            info!("   [PROTECTED] GET | POST /api/v1/wallets/:name/watchtower | DELETE .../:id");
+            info!("   [PROTECTED] GET  /api/v1/wallets/:name/watchtower/receipts");
+            info!("   [PROTECTED] GET  /metrics | /api/v1/metrics/latency");
+            info!("   [PROTECTED] GET  /api/v1/nodes");
+            info!("   [PROTECTED] POST /api/v1/mining/validate-template");
+            info!("   [PROTECTED] GET  /api/v1/digests | POST | DELETE /api/v1/digests/:id");
             info!("   [PUBLIC]    GET  /api/v1/account/:address/balance");
             info!("   [PUBLIC]    GET  /api/v1/blockchain/info");
+            info!("   [PUBLIC]    GET  /api/v1/proof/balance/:address?block=...");
+            info!("   [PUBLIC]    POST /api/v1/message/verify");
+            info!("   [PUBLIC]    GET  /api/v1/events?addresses=... (server-sent events)");
+            info!("   [PUBLIC]    GET  /api/v1/node/info");
             info!("   [PUBLIC]    GET  /health");
+            info!(
+                "   [MIXED]     POST /api/v1/batch (max {} items)",
+                batch::MAX_BATCH_SIZE
+            );
+            info!("   [MIXED]     POST /api/v1/rpc   (JSON-RPC 2.0, single or batch)");
             info!("");
             info!("🔐 Authentication: Bearer token required for protected endpoints");
             info!("🚦 Rate limiting: 100 requests per minute per IP");
+            info!(
+                "🧯 Load shedding: 503 beyond {} requests in flight (anonymous reads first)",
+                load_shedding.max_in_flight
+            );
             info!("⚠️  PRODUCTION WARNING: Use --tls-cert and --tls-key for HTTPS");
 
             axum::serve(listener, app).await?;
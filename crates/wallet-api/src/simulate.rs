@@ -0,0 +1,275 @@
+//! Transaction and block template dry runs against current state
+//! محاكاة المعاملات وقوالب الكتل مقابل الحالة الحالية
+//!
+//! `POST /api/v1/tx/simulate` runs a signed raw transaction through the
+//! mempool's admission checks (signature, fee floor, frozen accounts, balance
+//! and nonce including the sender's pending transactions) without adding it
+//! or relaying it. With a P2P node attached its mempool answers; otherwise
+//! the node's pending pool, which submission fills, does. A rejected
+//! transaction is still a successful simulation: the response is `200` with
+//! `valid: false` and the exact error the mempool would return, plus a
+//! stable `code` wallets can match on.
+//!
+//! `POST /api/v1/mining/validate-template` does the same for a proposed next
+//! block: every consensus and state rule except proof of work is checked,
+//! and a rejected template names the rule it breaks.
+
+use axum::{extract::State, http::StatusCode, response::Json};
+use opensyria_core::{error_code, Block, Transaction};
+use opensyria_mempool::MempoolError;
+use opensyria_network::NetworkCommand;
+use opensyria_storage::RuleViolation;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+use crate::{
+    api::parse_raw_transaction,
+    models::{ErrorResponse, RawTransactionRequest},
+    AppState,
+};
+
+/// Why a simulated transaction would be rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationError {
+    /// Stable machine-readable reason, e.g. `insufficient_balance`
+    pub code: &'static str,
+    /// Message submission would return
+    pub message: String,
+    /// Reason-specific values (expected nonce, available balance, ...)
+    #[serde(skip_serializing_if = "Value::is_null")]
+    pub details: Value,
+}
+
+impl From<&MempoolError> for SimulationError {
+    fn from(error: &MempoolError) -> Self {
+        let details = match error {
+            MempoolError::FeeTooLow { min, got } => json!({ "min_fee": min, "fee": got }),
+            MempoolError::InsufficientBalance {
+                required,
+                available,
+            } => json!({ "required": required, "available": available }),
+            MempoolError::InvalidNonce { expected, got } => {
+                json!({ "expected": expected, "nonce": got })
+            }
+            MempoolError::WrongChainId { expected, got } => {
+                json!({ "expected_chain_id": expected, "chain_id": got })
+            }
+            _ => Value::Null,
+        };
+        Self {
+            code: error.code(),
+            message: error.to_string(),
+            details,
+        }
+    }
+}
+
+/// Result of a dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationResponse {
+    /// Whether submission would accept the transaction right now
+    pub valid: bool,
+    pub tx_hash: String,
+    pub fee: u64,
+    /// Fee in base units per 1000 bytes
+    pub fee_rate: u64,
+    /// Sender balance after this and its pending transactions confirm
+    pub balance_after: Option<u64>,
+    pub error: Option<SimulationError>,
+}
+
+/// POST /api/v1/tx/simulate - validate a signed raw transaction without
+/// broadcasting it
+pub async fn simulate_transaction(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<RawTransactionRequest>,
+) -> Result<Json<SimulationResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let tx = parse_raw_transaction(&request.hex)?;
+
+    let safe_mode = state.node.read().await.safe_mode().is_active();
+    let checked = if safe_mode {
+        Err(SimulationError {
+            code: "safe_mode",
+            message: "Node is in safe mode; not accepting transactions".to_string(),
+            details: Value::Null,
+        })
+    } else {
+        check_in_mempool(&state, &tx).await?
+    };
+
+    let node = state.node.read().await;
+    let balance_after = checked.as_ref().ok().map(|pending| {
+        let pending_outgoing: u64 = pending
+            .iter()
+            .map(|pending| pending.amount + pending.fee)
+            .sum();
+        node.get_state()
+            .get_balance(&tx.from)
+            .unwrap_or(0)
+            .saturating_sub(pending_outgoing)
+            .saturating_sub(tx.amount + tx.fee)
+    });
+    let error = checked.err();
+
+    Ok(Json(SimulationResponse {
+        valid: error.is_none(),
+        tx_hash: hex::encode(tx.hash()),
+        fee: tx.fee,
+        fee_rate: tx.fee_rate(),
+        balance_after,
+        error,
+    }))
+}
+
+/// Run `tx` through the attached P2P node's mempool, or the node's pending
+/// pool without one; `Ok` holds the sender's pending transactions
+async fn check_in_mempool(
+    state: &AppState,
+    tx: &Transaction,
+) -> Result<Result<Vec<Transaction>, SimulationError>, (StatusCode, Json<ErrorResponse>)> {
+    let Some(network) = &state.network else {
+        let node = state.node.read().await;
+        return Ok(node
+            .check_transaction(tx)
+            .map(|()| {
+                node.get_pending_transactions()
+                    .into_iter()
+                    .filter(|pending| pending.from == tx.from)
+                    .collect()
+            })
+            .map_err(|e| SimulationError::from(&e)));
+    };
+
+    let stopped = || {
+        (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(
+                error_code::UNAVAILABLE,
+                "P2P node has stopped",
+            )),
+        )
+    };
+    let (reply, response) = oneshot::channel();
+    let command = NetworkCommand::CheckTransaction {
+        tx: tx.clone(),
+        reply,
+    };
+    network.send(command).map_err(|_| stopped())?;
+    let result = response.await.map_err(|_| stopped())?;
+    Ok(result.map_err(|e| SimulationError::from(&e)))
+}
+
+/// Block template to check, as returned by block serialization
+#[derive(Debug, Deserialize)]
+pub struct BlockTemplateRequest {
+    pub block: Block,
+}
+
+/// Consensus or state rule a block template breaks
+#[derive(Debug, Clone, Serialize)]
+pub struct TemplateViolation {
+    /// Rule name, e.g. `merkle_root`, `nonce` or `balance`
+    pub rule: &'static str,
+    /// Index of the offending transaction in the block, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_index: Option<usize>,
+    pub message: String,
+}
+
+impl From<&RuleViolation> for TemplateViolation {
+    fn from(violation: &RuleViolation) -> Self {
+        Self {
+            rule: violation.rule,
+            tx_index: violation.tx_index,
+            message: violation.error.to_string(),
+        }
+    }
+}
+
+/// Result of a block template dry run
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockTemplateResponse {
+    /// Whether the block would be accepted once its proof of work is found
+    pub valid: bool,
+    pub hash: String,
+    /// Height the block would take
+    pub height: Option<u64>,
+    pub tx_count: usize,
+    /// Fees paid by the block's non-coinbase transactions
+    pub fees: u64,
+    pub error: Option<TemplateViolation>,
+}
+
+/// POST /api/v1/mining/validate-template - check a proposed next block
+/// without mining or storing it
+pub async fn validate_block_template(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<BlockTemplateRequest>,
+) -> Json<BlockTemplateResponse> {
+    let block = request.block;
+    let result = state.node.read().await.check_block_template(&block);
+    let fees = block
+        .transactions
+        .iter()
+        .filter(|tx| !tx.is_coinbase())
+        .fold(0u64, |acc, tx| acc.saturating_add(tx.fee));
+
+    Json(BlockTemplateResponse {
+        valid: result.is_ok(),
+        hash: hex::encode(block.hash()),
+        height: result.as_ref().ok().copied(),
+        tx_count: block.transactions.len(),
+        fees,
+        error: result.as_ref().err().map(TemplateViolation::from),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulation_error_codes() {
+        let error = SimulationError::from(&MempoolError::InvalidNonce {
+            expected: 4,
+            got: 2,
+        });
+        assert_eq!(error.code, "invalid_nonce");
+        assert_eq!(error.message, "Invalid nonce: expected 4, got 2");
+        assert_eq!(error.details, json!({ "expected": 4, "nonce": 2 }));
+
+        let error = SimulationError::from(&MempoolError::FeeTooLow { min: 100, got: 1 });
+        assert_eq!(error.code, "fee_too_low");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap()["details"]["min_fee"],
+            100
+        );
+
+        let frozen = SimulationError::from(&MempoolError::AccountFrozen("ab".to_string()));
+        assert_eq!(frozen.code, "account_frozen");
+        assert!(serde_json::to_value(&frozen)
+            .unwrap()
+            .get("details")
+            .is_none());
+    }
+
+    #[test]
+    fn test_template_violation_fields() {
+        use opensyria_storage::StorageError;
+
+        let violation = RuleViolation::new("nonce", StorageError::InvalidTransaction).at(2);
+        let value = serde_json::to_value(TemplateViolation::from(&violation)).unwrap();
+        assert_eq!(value["rule"], "nonce");
+        assert_eq!(value["tx_index"], 2);
+
+        let violation = RuleViolation::new("merkle_root", StorageError::InvalidMerkleRoot);
+        let value = serde_json::to_value(TemplateViolation::from(&violation)).unwrap();
+        assert!(value.get("tx_index").is_none());
+        assert_eq!(
+            value["message"],
+            StorageError::InvalidMerkleRoot.to_string()
+        );
+    }
+}
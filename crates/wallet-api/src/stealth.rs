@@ -0,0 +1,158 @@
+//! Stealth payment announcements
+//! إعلانات الدفعات الخفية
+//!
+//! `GET /api/v1/stealth/announcements?from_height=N` lists the stealth
+//! payments confirmed in up to [`MAX_SCAN_BLOCKS`] blocks from height `N`.
+//! Telling which of them belong to a wallet takes its scan key, which never
+//! leaves the wallet: it requests pages from `next_height` until it passes
+//! `tip_height` and checks every announcement locally. The page that reaches
+//! the tip also lists stealth payments waiting in the mempool.
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use opensyria_core::{Block, CodedError, StealthAnnouncement, Transaction};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::{models::ErrorResponse, AppState};
+
+/// Most blocks scanned per request
+pub const MAX_SCAN_BLOCKS: u64 = 1_000;
+
+#[derive(Debug, Deserialize)]
+pub struct AnnouncementsQuery {
+    #[serde(default)]
+    pub from_height: u64,
+}
+
+/// A stealth payment as published on chain
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StealthAnnouncementView {
+    pub hash: String,
+    /// `None` while in the mempool
+    pub block_height: Option<u64>,
+    /// One-time address paid
+    pub to: String,
+    pub amount: u64,
+    /// Hex transaction data holding the announcement
+    pub data: String,
+}
+
+impl StealthAnnouncementView {
+    fn from_transaction(tx: &Transaction, block_height: Option<u64>) -> Option<Self> {
+        let data = tx.data.as_deref()?;
+        StealthAnnouncement::from_data(data)?;
+        Some(Self {
+            hash: hex::encode(tx.hash()),
+            block_height,
+            to: tx.to.to_hex(),
+            amount: tx.amount,
+            data: hex::encode(data),
+        })
+    }
+}
+
+/// Response of `GET /api/v1/stealth/announcements`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StealthAnnouncementsResponse {
+    pub from_height: u64,
+    /// Height to request next; past `tip_height` once the scan is complete
+    pub next_height: u64,
+    pub tip_height: u64,
+    pub announcements: Vec<StealthAnnouncementView>,
+    /// Stealth payments in the mempool, on the page that reaches the tip
+    pub pending: Vec<StealthAnnouncementView>,
+}
+
+/// Well-formed stealth payments in `block`
+fn block_announcements(block: &Block, height: u64) -> Vec<StealthAnnouncementView> {
+    block
+        .transactions
+        .iter()
+        .filter_map(|tx| StealthAnnouncementView::from_transaction(tx, Some(height)))
+        .collect()
+}
+
+/// GET /api/v1/stealth/announcements - stealth payments from a height on
+/// قائمة الدفعات الخفية ابتداءً من ارتفاع معين
+pub async fn get_announcements(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<AnnouncementsQuery>,
+) -> Result<Json<StealthAnnouncementsResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let node = state.node.read().await;
+    let blockchain = node.get_blockchain();
+    let internal = |e: opensyria_storage::StorageError| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(
+                e.error_code(),
+                format!("Failed to read blocks: {}", e),
+            )),
+        )
+    };
+    let tip_height = blockchain.get_chain_height().map_err(internal)?;
+
+    let mut announcements = Vec::new();
+    let mut next_height = query.from_height;
+    let last = tip_height.min(query.from_height.saturating_add(MAX_SCAN_BLOCKS - 1));
+    while next_height <= last {
+        let Some(block) = blockchain
+            .get_block_by_height(next_height)
+            .map_err(internal)?
+        else {
+            break;
+        };
+        announcements.extend(block_announcements(&block, next_height));
+        next_height += 1;
+    }
+
+    let pending = if next_height > tip_height {
+        node.get_pending_transactions()
+            .iter()
+            .filter_map(|tx| StealthAnnouncementView::from_transaction(tx, None))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Json(StealthAnnouncementsResponse {
+        from_height: query.from_height,
+        next_height,
+        tip_height,
+        announcements,
+        pending,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+    use opensyria_core::StealthKeys;
+
+    #[test]
+    fn test_block_announcements_lists_stealth_payments() {
+        let sender = KeyPair::generate().public_key();
+        let recipient = StealthKeys::from_account(&KeyPair::generate());
+        let payment = recipient.address().pay().unwrap();
+        let stealth = payment.transaction(sender, 700, 100, 0, None).unwrap();
+        let plain = Transaction::new(sender, payment.address, 700, 100, 1);
+        let malformed = Transaction::new(sender, payment.address, 700, 100, 2)
+            .with_data(opensyria_core::stealth::STEALTH_TAG.to_vec());
+
+        let block = Block::new([0u8; 32], vec![plain, stealth.clone(), malformed], 16);
+        let views = block_announcements(&block, 12);
+        assert_eq!(views.len(), 1);
+        assert_eq!(views[0].hash, hex::encode(stealth.hash()));
+        assert_eq!(views[0].block_height, Some(12));
+
+        // The view carries everything the wallet needs to scan
+        let data = hex::decode(&views[0].data).unwrap();
+        let announcement = StealthAnnouncement::from_data(&data).unwrap();
+        let to = opensyria_core::crypto::PublicKey::from_hex(&views[0].to).unwrap();
+        assert!(recipient.scan_key().detect(&to, &announcement).is_some());
+    }
+}
@@ -0,0 +1,142 @@
+//! Propagation telemetry collector
+//! مجمع قياسات زمن الانتشار
+//!
+//! Started with `--collect-telemetry`, the wallet API accepts the
+//! propagation reports of P2P nodes run with `--telemetry-collector` at
+//! `POST /api/v1/telemetry/propagation` and serves the network-wide block
+//! and transaction latency percentiles over the last `--telemetry-window` at
+//! `GET /api/v1/telemetry/propagation`. Reports only carry histograms, so
+//! nodes share nothing about what they saw or whom they saw it from.
+//! Submitting needs an API key with the `submit_telemetry` permission, so
+//! only operators handed a key can shape the published figures.
+
+use axum::{extract::State, http::StatusCode, response::Json, Extension};
+use opensyria_network::{NetworkLatency, PropagationReport, TelemetryAggregator};
+use std::sync::{Arc, Mutex};
+
+use crate::{
+    auth::{ApiKey, ApiKeyManager, Permission},
+    models::ErrorResponse,
+    AppState,
+};
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+fn collector(
+    state: &AppState,
+) -> Result<&Mutex<TelemetryAggregator>, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .telemetry
+        .as_deref()
+        .ok_or_else(|| error(StatusCode::NOT_FOUND, "Telemetry collection is disabled"))
+}
+
+/// POST /api/v1/telemetry/propagation - accept a node's propagation report
+/// استقبال تقرير زمن الانتشار من عقدة
+pub async fn submit_report(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Json(report): Json<PropagationReport>,
+) -> ApiResult<serde_json::Value> {
+    let collector = collector(&state)?;
+    if !ApiKeyManager::has_permission(&key, &Permission::SubmitTelemetry) {
+        return Err(error(
+            StatusCode::FORBIDDEN,
+            "API key lacks permission SubmitTelemetry",
+        ));
+    }
+    collector
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .submit(report)
+        .map_err(|e| error(StatusCode::BAD_REQUEST, e))?;
+    Ok(Json(serde_json::json!({ "accepted": true })))
+}
+
+/// GET /api/v1/telemetry/propagation - network-wide latency percentiles
+/// نسب زمن الانتشار على مستوى الشبكة
+pub async fn network_latency(State(state): State<Arc<AppState>>) -> ApiResult<NetworkLatency> {
+    let summary = collector(&state)?
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .summary();
+    Ok(Json(summary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use opensyria_network::telemetry::LatencyHistogram;
+    use opensyria_node_cli::Node;
+    use std::time::Duration;
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_collector_merges_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        let node = Node::init(dir.path().to_path_buf(), 16).unwrap();
+        let state = AppState::new(node).with_telemetry_collector(Duration::from_secs(3600));
+        let (_, reporter) = state
+            .api_key_manager
+            .generate_key(
+                "reporter".to_string(),
+                vec![Permission::SubmitTelemetry],
+                None,
+            )
+            .await;
+        let (_, reader) = state
+            .api_key_manager
+            .generate_key("reader".to_string(), vec![Permission::ReadBalance], None)
+            .await;
+        let app = crate::api::create_router(Arc::new(state));
+
+        let mut blocks = LatencyHistogram::default();
+        blocks.record(300);
+        let submit = |node: &str, key: Option<&str>| {
+            let report = PropagationReport {
+                node: node.to_string(),
+                period_start_ms: 0,
+                period_end_ms: 1,
+                blocks: blocks.clone(),
+                transactions: LatencyHistogram::default(),
+            };
+            let mut request = Request::post("/api/v1/telemetry/propagation")
+                .header("content-type", "application/json");
+            if let Some(key) = key {
+                request = request.header("Authorization", format!("Bearer {}", key));
+            }
+            let request = request
+                .body(Body::from(serde_json::to_vec(&report).unwrap()))
+                .unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(submit("forged", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(submit("forged", Some(&reader)).await, StatusCode::FORBIDDEN);
+        for node in ["peer-a", "peer-b"] {
+            assert_eq!(submit(node, Some(&reporter)).await, StatusCode::OK);
+        }
+
+        let response = app
+            .oneshot(
+                Request::get("/api/v1/telemetry/propagation")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let latency: NetworkLatency = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(latency.nodes, 2);
+        assert_eq!(latency.blocks.samples, 2);
+        assert_eq!(latency.blocks.p50_ms, Some(500));
+    }
+}
@@ -0,0 +1,588 @@
+//! Multi-wallet sessions for the wallet API
+//! جلسات المحافظ المتعددة لواجهة برمجة التطبيقات
+//!
+//! Named encrypted wallets are unlocked into short-lived in-memory sessions.
+//! Every wallet-scoped request is checked against the wallet list of the
+//! calling API key, and the sending address is always derived from the
+//! unlocked wallet itself, so one tenant can never spend from or read the
+//! history of another tenant's wallet.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use opensyria_core::{
+    confirmations::confirmations,
+    crypto::{KeyPair, PublicKey},
+    transaction::Transaction,
+    ConfirmationStatus, ConfirmationTargets,
+};
+use opensyria_wallet::{memo::read_memo, AutoLockedKey, EncryptedWalletStorage, Keyfile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::{sync::RwLock, task::JoinHandle};
+use zeroize::Zeroizing;
+
+use crate::{
+    auth::{ApiKey, ApiKeyManager, Permission},
+    models::{ErrorResponse, TransactionResponse},
+    node_pool, AppState,
+};
+
+/// Idle time after which an unlocked wallet is locked again (15 minutes)
+pub const DEFAULT_SESSION_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// How often idle sessions are locked without waiting for a request
+pub const AUTO_LOCK_INTERVAL: Duration = Duration::from_secs(30);
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// A wallet whose private key is held in memory until idle
+struct WalletSession {
+    key: AutoLockedKey,
+    address: PublicKey,
+    unlocked_at: u64,
+}
+
+/// Summary of an unlocked wallet
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletInfo {
+    pub name: String,
+    pub address: String,
+    pub unlocked_at: u64,
+}
+
+/// Manages unlocked wallet sessions
+/// مدير جلسات المحافظ المفتوحة
+pub struct WalletManager {
+    storage: Option<Arc<EncryptedWalletStorage>>,
+    sessions: RwLock<HashMap<String, WalletSession>>,
+    session_timeout: Duration,
+}
+
+impl WalletManager {
+    /// Create a manager loading wallets from `storage`
+    /// Without storage, wallets can only be added via [`WalletManager::insert`]
+    pub fn new(storage: Option<EncryptedWalletStorage>) -> Self {
+        Self {
+            storage: storage.map(Arc::new),
+            sessions: RwLock::new(HashMap::new()),
+            session_timeout: DEFAULT_SESSION_TIMEOUT,
+        }
+    }
+
+    /// Override the idle session timeout
+    pub fn with_session_timeout(mut self, timeout: Duration) -> Self {
+        self.session_timeout = timeout;
+        self
+    }
+
+    /// Decrypt a named wallet from storage and open a session for it.
+    /// Failed attempts back off exponentially per wallet. Decryption runs on
+    /// a blocking thread, as Argon2 takes a while.
+    /// فتح محفظة مسماة باستخدام كلمة المرور
+    pub async fn unlock(
+        &self,
+        name: &str,
+        password: &str,
+        keyfile: Option<Keyfile>,
+    ) -> anyhow::Result<WalletInfo> {
+        let storage = self
+            .storage
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Wallet storage is not configured"))?;
+        let account = storage.load_account(name)?;
+        let password = Zeroizing::new(password.to_string());
+        let keypair = tokio::task::spawn_blocking(move || {
+            storage.unlock_account(&account, &password, keyfile.as_ref())
+        })
+        .await??;
+        Ok(self.insert(name, keypair).await)
+    }
+
+    /// Open a session for an already decrypted keypair
+    pub async fn insert(&self, name: &str, keypair: KeyPair) -> WalletInfo {
+        let unlocked_at = opensyria_core::clock::system_time();
+        let info = WalletInfo {
+            name: name.to_string(),
+            address: keypair.public_key().to_hex(),
+            unlocked_at,
+        };
+
+        self.sessions.write().await.insert(
+            name.to_string(),
+            WalletSession {
+                address: keypair.public_key(),
+                key: AutoLockedKey::new(keypair, self.session_timeout),
+                unlocked_at,
+            },
+        );
+
+        info
+    }
+
+    /// Close a wallet session, dropping its key from memory
+    pub async fn lock(&self, name: &str) -> bool {
+        self.sessions.write().await.remove(name).is_some()
+    }
+
+    /// Unlocked wallets, after pruning idle sessions
+    pub async fn list(&self) -> Vec<WalletInfo> {
+        let mut sessions = self.sessions.write().await;
+        self.prune(&mut sessions);
+        let mut wallets: Vec<WalletInfo> = sessions
+            .iter()
+            .map(|(name, session)| WalletInfo {
+                name: name.clone(),
+                address: session.address.to_hex(),
+                unlocked_at: session.unlocked_at,
+            })
+            .collect();
+        wallets.sort_by(|a, b| a.name.cmp(&b.name));
+        wallets
+    }
+
+    /// Address of an unlocked wallet
+    pub async fn address(&self, name: &str) -> Option<PublicKey> {
+        self.with_keypair(name, |keypair| keypair.public_key())
+            .await
+    }
+
+    /// Run `f` with the wallet's keypair, refreshing its idle timer
+    /// Returns `None` if the wallet is locked or its session expired
+    pub async fn with_keypair<T>(&self, name: &str, f: impl FnOnce(&KeyPair) -> T) -> Option<T> {
+        let mut sessions = self.sessions.write().await;
+        self.prune(&mut sessions);
+        sessions.get_mut(name)?.key.with_keypair(f)
+    }
+
+    /// Lock idle sessions now; returns how many were locked
+    pub async fn lock_idle(&self) -> usize {
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        self.prune(&mut sessions);
+        before - sessions.len()
+    }
+
+    /// Lock idle sessions every [`AUTO_LOCK_INTERVAL`] until the manager is
+    /// dropped, so idle keys leave memory even when no request arrives
+    pub fn spawn_auto_lock(self: &Arc<Self>) -> JoinHandle<()> {
+        let manager = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(AUTO_LOCK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let locked = manager.lock_idle().await;
+                if locked > 0 {
+                    tracing::info!("Locked {} idle wallet session(s)", locked);
+                }
+            }
+        })
+    }
+
+    fn prune(&self, sessions: &mut HashMap<String, WalletSession>) {
+        sessions.retain(|_, session| !session.key.lock_if_idle());
+    }
+}
+
+impl Default for WalletManager {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+/// Ensure the API key may act on `wallet` with `permission`
+pub(crate) fn authorize(
+    key: &ApiKey,
+    wallet: &str,
+    permission: Option<Permission>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if !key.can_access_wallet(wallet) {
+        return Err(api_error(
+            StatusCode::FORBIDDEN,
+            format!("API key is not authorized for wallet '{}'", wallet),
+        ));
+    }
+    if let Some(permission) = permission {
+        if !ApiKeyManager::has_permission(key, &permission) {
+            return Err(api_error(
+                StatusCode::FORBIDDEN,
+                format!("API key lacks permission {:?}", permission),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Request to unlock a wallet
+#[derive(Debug, Deserialize)]
+pub struct UnlockWalletRequest {
+    pub password: String,
+    /// Hex-encoded keyfile contents, for wallets created with a keyfile
+    #[serde(default)]
+    pub keyfile: Option<String>,
+}
+
+/// Request to send from an unlocked wallet
+#[derive(Debug, Deserialize)]
+pub struct WalletSendRequest {
+    /// Hex-encoded recipient public key
+    pub to: String,
+    /// Amount in smallest units
+    pub amount: u64,
+    /// Transaction fee
+    pub fee: u64,
+}
+
+/// Transaction history entry for a wallet
+#[derive(Debug, Serialize)]
+pub struct WalletHistoryEntry {
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub block_height: Option<u64>,
+    pub confirmed: bool,
+    /// Blocks on top of and including the confirming block
+    pub confirmations: u64,
+    /// Pending, confirming (n/N) or final
+    pub status: ConfirmationStatus,
+    /// Memo text, decrypted if it was encrypted to this wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Whether the memo was encrypted, readable or not
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub memo_encrypted: bool,
+}
+
+/// Wallet history response
+#[derive(Debug, Serialize)]
+pub struct WalletHistoryResponse {
+    pub wallet: String,
+    pub address: String,
+    pub transactions: Vec<WalletHistoryEntry>,
+}
+
+/// GET /api/v1/wallets - unlocked wallets visible to the caller
+pub async fn list_wallets(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+) -> ApiResult<Vec<WalletInfo>> {
+    let wallets = state
+        .wallet_manager
+        .list()
+        .await
+        .into_iter()
+        .filter(|wallet| key.can_access_wallet(&wallet.name))
+        .collect();
+    Ok(Json(wallets))
+}
+
+/// POST /api/v1/wallets/{name}/unlock
+pub async fn unlock_wallet(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+    Json(request): Json<UnlockWalletRequest>,
+) -> ApiResult<WalletInfo> {
+    authorize(&key, &name, None)?;
+    let keyfile = request
+        .keyfile
+        .as_deref()
+        .map(|keyfile| {
+            let contents = hex::decode(keyfile)
+                .map_err(|_| api_error(StatusCode::BAD_REQUEST, "Keyfile must be hex-encoded"))?;
+            Keyfile::from_bytes(&contents)
+                .map_err(|e| api_error(StatusCode::BAD_REQUEST, e.to_string()))
+        })
+        .transpose()?;
+    state
+        .wallet_manager
+        .unlock(&name, &request.password, keyfile)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            api_error(
+                StatusCode::UNAUTHORIZED,
+                format!("Failed to unlock wallet: {}", e),
+            )
+        })
+}
+
+/// POST /api/v1/wallets/{name}/lock
+pub async fn lock_wallet(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<serde_json::Value> {
+    authorize(&key, &name, None)?;
+    let locked = state.wallet_manager.lock(&name).await;
+    Ok(Json(
+        serde_json::json!({ "wallet": name, "locked": locked }),
+    ))
+}
+
+/// POST /api/v1/wallets/{name}/send - sign and submit from the wallet's own address
+pub async fn wallet_send(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+    Json(request): Json<WalletSendRequest>,
+) -> ApiResult<TransactionResponse> {
+    authorize(&key, &name, Some(Permission::SubmitTransaction))?;
+
+    let to = PublicKey::from_hex(&request.to)
+        .map_err(|_| api_error(StatusCode::BAD_REQUEST, "Invalid recipient address"))?;
+    let from = state
+        .wallet_manager
+        .address(&name)
+        .await
+        .ok_or_else(|| api_error(StatusCode::LOCKED, format!("Wallet '{}' is locked", name)))?;
+
+    let mut node = state.node.write().await;
+
+    // Account for this sender's transactions already waiting in the mempool
+    let pending = node
+        .get_pending_transactions()
+        .iter()
+        .filter(|tx| tx.from == from)
+        .count() as u64;
+    let nonce = node.get_state().get_nonce(&from).unwrap_or(0) + pending;
+
+    let chain_id = node.get_blockchain().chain_params().chain_id;
+    let mut transaction =
+        Transaction::new_with_chain_id(chain_id, from, to, request.amount, request.fee, nonce);
+    let signing_hash = transaction.signing_hash();
+    transaction.signature = state
+        .wallet_manager
+        .with_keypair(&name, |keypair| keypair.sign(&signing_hash))
+        .await
+        .ok_or_else(|| api_error(StatusCode::LOCKED, format!("Wallet '{}' is locked", name)))?;
+
+    let tx_hash = hex::encode(transaction.hash());
+    node.add_transaction_to_mempool(transaction.clone())
+        .map_err(|e| {
+            api_error(
+                StatusCode::BAD_REQUEST,
+                format!("Failed to submit transaction: {}", e),
+            )
+        })?;
+    drop(node);
+
+    Ok(Json(TransactionResponse {
+        success: true,
+        tx_hash: Some(tx_hash),
+        message: format!("Transaction submitted from wallet '{}'", name),
+        relayed: node_pool::relay_to_pool(&state, &transaction),
+    }))
+}
+
+/// GET /api/v1/wallets/{name}/history - confirmed and pending transactions
+pub async fn wallet_history(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<WalletHistoryResponse> {
+    authorize(&key, &name, Some(Permission::ReadBalance))?;
+
+    let address = state
+        .wallet_manager
+        .address(&name)
+        .await
+        .ok_or_else(|| api_error(StatusCode::LOCKED, format!("Wallet '{}' is locked", name)))?;
+
+    let node = state.node.read().await;
+    let blockchain = node.get_blockchain();
+    let tip_height = blockchain.get_chain_height().unwrap_or(0);
+    let targets = ConfirmationTargets::from_params(blockchain.chain_params());
+
+    let mut found = Vec::new();
+    let hashes = blockchain
+        .get_address_transactions(&address.0)
+        .map_err(|e| {
+            api_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to load history: {}", e),
+            )
+        })?;
+    for hash in hashes {
+        if let Ok(Some((tx, height))) = blockchain.get_transaction_by_hash(&hash) {
+            found.push((tx, Some(height)));
+        }
+    }
+    for tx in node.get_pending_transactions() {
+        if tx.from == address || tx.to == address {
+            found.push((tx, None));
+        }
+    }
+
+    // Memos encrypted to the wallet are decrypted with its session key
+    let transactions = state
+        .wallet_manager
+        .with_keypair(&name, |keypair| {
+            found
+                .iter()
+                .map(|(tx, height)| history_entry(tx, *height, tip_height, &targets, keypair))
+                .collect()
+        })
+        .await
+        .ok_or_else(|| api_error(StatusCode::LOCKED, format!("Wallet '{}' is locked", name)))?;
+
+    Ok(Json(WalletHistoryResponse {
+        wallet: name,
+        address: address.to_hex(),
+        transactions,
+    }))
+}
+
+fn history_entry(
+    tx: &Transaction,
+    block_height: Option<u64>,
+    tip_height: u64,
+    targets: &ConfirmationTargets,
+    keypair: &KeyPair,
+) -> WalletHistoryEntry {
+    let memo = tx
+        .data
+        .as_deref()
+        .and_then(|data| read_memo(data, Some(keypair)));
+    WalletHistoryEntry {
+        hash: hex::encode(tx.hash()),
+        from: tx.from.to_hex(),
+        to: tx.to.to_hex(),
+        amount: tx.amount,
+        fee: tx.fee,
+        block_height,
+        confirmed: block_height.is_some(),
+        confirmations: block_height.map_or(0, |height| confirmations(height, tip_height)),
+        status: targets.status(tx, block_height, tip_height),
+        memo: memo
+            .as_ref()
+            .and_then(|memo| memo.text())
+            .map(str::to_string),
+        memo_encrypted: memo.is_some_and(|memo| memo.is_encrypted()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_for(wallets: &[&str], permissions: Vec<Permission>) -> ApiKey {
+        ApiKey {
+            id: "key_test".to_string(),
+            key_hash: String::new(),
+            name: "tenant".to_string(),
+            permissions,
+            created_at: 0,
+            expires_at: None,
+            active: true,
+            wallets: wallets.iter().map(|w| w.to_string()).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_isolated_by_name() {
+        let manager = WalletManager::default();
+        let alice = KeyPair::generate();
+        let bob = KeyPair::generate();
+        manager.insert("alice", alice.clone()).await;
+        manager.insert("bob", bob.clone()).await;
+
+        assert_eq!(manager.address("alice").await, Some(alice.public_key()));
+        assert_eq!(manager.address("bob").await, Some(bob.public_key()));
+        assert_eq!(manager.list().await.len(), 2);
+
+        assert!(manager.lock("alice").await);
+        assert!(manager.address("alice").await.is_none());
+        assert_eq!(manager.address("bob").await, Some(bob.public_key()));
+    }
+
+    #[tokio::test]
+    async fn test_idle_session_expires() {
+        let manager = WalletManager::default().with_session_timeout(Duration::ZERO);
+        manager.insert("alice", KeyPair::generate()).await;
+
+        assert!(manager.address("alice").await.is_none());
+        assert!(manager.list().await.is_empty());
+
+        manager.insert("bob", KeyPair::generate()).await;
+        assert_eq!(manager.lock_idle().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unlock_from_encrypted_storage() {
+        let dir = std::env::temp_dir().join(format!("wallet_api_sessions_{}", std::process::id()));
+        let storage = EncryptedWalletStorage::with_path(dir.clone()).unwrap();
+        let account = opensyria_wallet::EncryptedAccount::new("savings".to_string(), "pw").unwrap();
+        let address = account.address;
+        storage.save_account(&account).unwrap();
+
+        let manager = WalletManager::new(Some(storage));
+        assert!(manager.unlock("savings", "wrong", None).await.is_err());
+
+        let info = manager.unlock("savings", "pw", None).await.unwrap();
+        assert_eq!(info.address, address.to_hex());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_authorize_respects_wallet_scope() {
+        let key = key_for(&["alice"], vec![Permission::SubmitTransaction]);
+
+        assert!(authorize(&key, "alice", Some(Permission::SubmitTransaction)).is_ok());
+        assert_eq!(
+            authorize(&key, "bob", Some(Permission::SubmitTransaction))
+                .unwrap_err()
+                .0,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            authorize(&key, "alice", Some(Permission::ReadBalance))
+                .unwrap_err()
+                .0,
+            StatusCode::FORBIDDEN
+        );
+
+        let admin = key_for(&[], vec![Permission::Admin]);
+        assert!(authorize(&admin, "bob", Some(Permission::ReadBalance)).is_ok());
+    }
+
+    #[test]
+    fn test_history_decrypts_memos_to_wallet() {
+        let wallet = KeyPair::generate();
+        let other = KeyPair::generate();
+        let memo = opensyria_core::EncryptedMemo::encrypt(&wallet.public_key(), b"Invoice 7")
+            .unwrap()
+            .to_data();
+        let tx =
+            Transaction::new(other.public_key(), wallet.public_key(), 10, 1, 0).with_data(memo);
+        let targets = ConfirmationTargets::default();
+
+        let entry = history_entry(&tx, Some(3), 5, &targets, &wallet);
+        assert_eq!(entry.memo.as_deref(), Some("Invoice 7"));
+        assert!(entry.memo_encrypted);
+
+        let entry = history_entry(&tx, Some(3), 5, &targets, &other);
+        assert_eq!(entry.memo, None);
+        assert!(entry.memo_encrypted);
+
+        let plain = tx.with_data(b"Order 42".to_vec());
+        let entry = history_entry(&plain, None, 5, &targets, &other);
+        assert_eq!(entry.memo.as_deref(), Some("Order 42"));
+        assert!(!entry.memo_encrypted);
+    }
+}
@@ -0,0 +1,835 @@
+//! Watchtower for multisig and escrow timeouts
+//! برج المراقبة لمهل التوقيع المتعدد والضمان
+//!
+//! Escrow contracts and multisig proposals with an expiry need someone to
+//! act once the deadline passes, or the funds stay stuck. Started with
+//! `--watchtower`, the wallet API keeps a list of watches registered by
+//! named wallets. Every [`CHECK_INTERVAL`] it submits the action of each
+//! watch whose deadline height has been reached:
+//!
+//! - a contract call, such as an escrow's `refund` method, signed by the
+//!   wallet that registered the watch (skipped while it is locked), or
+//! - a transaction signed in advance, such as the refund the signers of a
+//!   multisig proposal agreed on when it was made.
+//!
+//! Every attempt is appended to the receipts log, with the transaction hash
+//! the on-chain contract receipt is stored under. A rejected submission
+//! is retried on the next check, up to [`MAX_ATTEMPTS`] times.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use opensyria_core::{
+    clock::system_time,
+    constants::MIN_TRANSACTION_FEE,
+    contract::{gas_fee, ContractCall, MIN_GAS_PRICE},
+    crypto::PublicKey,
+    Transaction,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+use crate::{
+    auth::{ApiKey, Permission},
+    models::ErrorResponse,
+    node_pool,
+    wallets::authorize,
+    AppState,
+};
+
+/// Interval between checks for due watches
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Submissions of a watch before it is given up as failed
+pub const MAX_ATTEMPTS: u32 = 3;
+
+/// Watches one wallet may have waiting
+pub const MAX_WATCHES_PER_WALLET: usize = 100;
+
+/// Receipts kept in memory for the API; the log file keeps them all
+const MAX_RECENT_RECEIPTS: usize = 1_000;
+
+/// Longest label, in bytes
+const MAX_LABEL_LEN: usize = 100;
+
+type ApiResult<T> = Result<Json<T>, (StatusCode, Json<ErrorResponse>)>;
+
+fn api_error(status: StatusCode, message: impl Into<String>) -> (StatusCode, Json<ErrorResponse>) {
+    (status, Json(ErrorResponse::from_status(status, message)))
+}
+
+/// What the watchtower submits at the deadline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WatchAction {
+    /// Call a contract method, signed by the wallet of the watch
+    ContractCall {
+        /// Contract address (hex)
+        contract: String,
+        method: String,
+        /// Call input (hex)
+        #[serde(default)]
+        args: String,
+        gas_limit: u64,
+        /// Transaction fee; 0 pays the gas limit at the minimum gas price
+        #[serde(default)]
+        fee: u64,
+    },
+    /// Broadcast a transaction that was signed in advance
+    Presigned { transaction: Transaction },
+}
+
+impl WatchAction {
+    fn kind(&self) -> &'static str {
+        match self {
+            WatchAction::ContractCall { .. } => "contract_call",
+            WatchAction::Presigned { .. } => "presigned",
+        }
+    }
+}
+
+/// Where a watch stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchStatus {
+    /// Waiting for its deadline, or for a retry
+    Pending,
+    /// Its transaction was accepted into the mempool
+    Submitted,
+    /// Every attempt was rejected
+    Failed,
+}
+
+/// A deadline the watchtower acts on
+/// مهلة يراقبها برج المراقبة
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Watch {
+    pub id: String,
+    /// Wallet that registered it and signs contract calls
+    pub wallet: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Height from which the action is submitted
+    pub deadline: u64,
+    pub action: WatchAction,
+    pub status: WatchStatus,
+    /// Rejected submissions so far
+    #[serde(default)]
+    pub attempts: u32,
+    /// Hash of the submitted transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_hash: Option<String>,
+    pub created_at: u64,
+}
+
+/// Request to register a watch
+#[derive(Debug, Deserialize)]
+pub struct RegisterWatchRequest {
+    pub deadline: u64,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub action: WatchAction,
+}
+
+/// Outcome of one submission attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiptOutcome {
+    Submitted,
+    Rejected,
+}
+
+/// Entry of the receipts log
+/// إيصال إجراء نفذه برج المراقبة
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchtowerReceipt {
+    pub watch_id: String,
+    pub wallet: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// `contract_call` or `presigned`
+    pub action: String,
+    pub outcome: ReceiptOutcome,
+    pub tx_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub deadline: u64,
+    /// Chain height when the action was taken
+    pub height: u64,
+    /// Backend nodes the transaction is being relayed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relayed: Option<usize>,
+    pub at: u64,
+}
+
+/// Registered watches and the task acting on them
+/// مدير برج المراقبة
+pub struct Watchtower {
+    watches: RwLock<HashMap<String, Watch>>,
+    receipts: std::sync::Mutex<VecDeque<WatchtowerReceipt>>,
+    /// JSON file watches are kept in; `None` keeps them in memory
+    path: Option<PathBuf>,
+    /// JSON lines file receipts are appended to
+    receipts_path: Option<PathBuf>,
+}
+
+impl Watchtower {
+    /// In-memory watchtower
+    pub fn new() -> Self {
+        Self {
+            watches: RwLock::new(HashMap::new()),
+            receipts: std::sync::Mutex::new(VecDeque::new()),
+            path: None,
+            receipts_path: None,
+        }
+    }
+
+    /// Watchtower keeping watches in `path` and appending receipts to
+    /// `receipts_path`, loading any already there
+    pub fn open(path: PathBuf, receipts_path: PathBuf) -> anyhow::Result<Self> {
+        let watches: Vec<Watch> = match std::fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut receipts = VecDeque::new();
+        match std::fs::read_to_string(&receipts_path) {
+            Ok(log) => {
+                for line in log.lines().filter(|line| !line.trim().is_empty()) {
+                    receipts.push_back(serde_json::from_str(line)?);
+                    if receipts.len() > MAX_RECENT_RECEIPTS {
+                        receipts.pop_front();
+                    }
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(Self {
+            watches: RwLock::new(
+                watches
+                    .into_iter()
+                    .map(|watch| (watch.id.clone(), watch))
+                    .collect(),
+            ),
+            receipts: std::sync::Mutex::new(receipts),
+            path: Some(path),
+            receipts_path: Some(receipts_path),
+        })
+    }
+
+    /// Validate and store a watch for `wallet`
+    /// تسجيل مهلة جديدة للمراقبة
+    pub async fn register(
+        &self,
+        wallet: &str,
+        request: RegisterWatchRequest,
+        height: u64,
+    ) -> Result<Watch, String> {
+        if request.deadline <= height {
+            return Err(format!(
+                "deadline must be above the current height {}",
+                height
+            ));
+        }
+        if request
+            .label
+            .as_ref()
+            .is_some_and(|label| label.len() > MAX_LABEL_LEN)
+        {
+            return Err(format!("label may be at most {} bytes", MAX_LABEL_LEN));
+        }
+
+        let action = match request.action {
+            WatchAction::ContractCall {
+                contract,
+                method,
+                args,
+                gas_limit,
+                fee,
+            } => {
+                PublicKey::from_hex(&contract).map_err(|_| "Invalid contract address")?;
+                ContractCall::Call {
+                    method: method.clone(),
+                    args: hex::decode(&args).map_err(|_| "args must be hex")?,
+                    gas_limit,
+                }
+                .validate()
+                .map_err(|e| e.to_string())?;
+                let required = gas_fee(gas_limit, MIN_GAS_PRICE).max(MIN_TRANSACTION_FEE);
+                let fee = if fee == 0 { required } else { fee };
+                if fee < required {
+                    return Err(format!(
+                        "fee {} does not cover the gas limit (minimum {})",
+                        fee, required
+                    ));
+                }
+                WatchAction::ContractCall {
+                    contract,
+                    method,
+                    args,
+                    gas_limit,
+                    fee,
+                }
+            }
+            WatchAction::Presigned { transaction } => {
+                transaction
+                    .verify()
+                    .map_err(|e| format!("Invalid presigned transaction: {}", e))?;
+                WatchAction::Presigned { transaction }
+            }
+        };
+
+        let watch = Watch {
+            id: format!("watch_{}", hex::encode(rand::random::<[u8; 8]>())),
+            wallet: wallet.to_string(),
+            label: request.label,
+            deadline: request.deadline,
+            action,
+            status: WatchStatus::Pending,
+            attempts: 0,
+            tx_hash: None,
+            created_at: system_time(),
+        };
+
+        let mut watches = self.watches.write().await;
+        let waiting = watches
+            .values()
+            .filter(|w| w.wallet == wallet && w.status == WatchStatus::Pending)
+            .count();
+        if waiting >= MAX_WATCHES_PER_WALLET {
+            return Err(format!(
+                "At most {} pending watches per wallet",
+                MAX_WATCHES_PER_WALLET
+            ));
+        }
+        watches.insert(watch.id.clone(), watch.clone());
+        self.save(&watches);
+        Ok(watch)
+    }
+
+    /// Watches of a wallet, by deadline
+    pub async fn list(&self, wallet: &str) -> Vec<Watch> {
+        let mut watches: Vec<_> = self
+            .watches
+            .read()
+            .await
+            .values()
+            .filter(|w| w.wallet == wallet)
+            .cloned()
+            .collect();
+        watches.sort_by(|a, b| (a.deadline, a.created_at).cmp(&(b.deadline, b.created_at)));
+        watches
+    }
+
+    /// Remove a wallet's watch; `false` if it has none with this ID
+    pub async fn remove(&self, wallet: &str, id: &str) -> bool {
+        let mut watches = self.watches.write().await;
+        if watches.get(id).is_none_or(|w| w.wallet != wallet) {
+            return false;
+        }
+        watches.remove(id);
+        self.save(&watches);
+        true
+    }
+
+    /// Recent receipts of a wallet, oldest first
+    pub fn receipts(&self, wallet: &str) -> Vec<WatchtowerReceipt> {
+        self.receipts
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.wallet == wallet)
+            .cloned()
+            .collect()
+    }
+
+    /// Submit the action of every watch whose deadline has been reached
+    pub async fn run_once(&self, state: &AppState) -> Vec<WatchtowerReceipt> {
+        let height = state
+            .node
+            .read()
+            .await
+            .get_blockchain()
+            .get_chain_height()
+            .unwrap_or(0);
+        let due: Vec<Watch> = self
+            .watches
+            .read()
+            .await
+            .values()
+            .filter(|w| w.status == WatchStatus::Pending && w.deadline <= height)
+            .cloned()
+            .collect();
+
+        let mut receipts = Vec::new();
+        for watch in due {
+            let mut node = state.node.write().await;
+            let tx = match &watch.action {
+                WatchAction::Presigned { transaction } => transaction.clone(),
+                WatchAction::ContractCall {
+                    contract,
+                    method,
+                    args,
+                    gas_limit,
+                    fee,
+                } => {
+                    let Some(from) = state.wallet_manager.address(&watch.wallet).await else {
+                        continue;
+                    };
+                    // Both were checked at registration
+                    let (Ok(contract), Ok(args)) =
+                        (PublicKey::from_hex(contract), hex::decode(args))
+                    else {
+                        continue;
+                    };
+                    let pending = node
+                        .get_pending_transactions()
+                        .iter()
+                        .filter(|tx| tx.from == from)
+                        .count() as u64;
+                    let nonce = node.get_state().get_nonce(&from).unwrap_or(0) + pending;
+                    let chain_id = node.get_blockchain().chain_params().chain_id;
+                    let call = ContractCall::Call {
+                        method: method.clone(),
+                        args,
+                        gas_limit: *gas_limit,
+                    };
+                    let mut tx = call.call_transaction(chain_id, from, contract, 0, *fee, nonce);
+                    let signing_hash = tx.signing_hash();
+                    let Some(signature) = state
+                        .wallet_manager
+                        .with_keypair(&watch.wallet, |keypair| keypair.sign(&signing_hash))
+                        .await
+                    else {
+                        continue;
+                    };
+                    tx.signature = signature;
+                    tx
+                }
+            };
+
+            let added = node.add_transaction_to_mempool(tx.clone());
+            drop(node);
+
+            let mut receipt = WatchtowerReceipt {
+                watch_id: watch.id.clone(),
+                wallet: watch.wallet.clone(),
+                label: watch.label.clone(),
+                action: watch.action.kind().to_string(),
+                outcome: ReceiptOutcome::Submitted,
+                tx_hash: hex::encode(tx.hash()),
+                error: None,
+                deadline: watch.deadline,
+                height,
+                relayed: None,
+                at: system_time(),
+            };
+            match added {
+                Ok(()) => {
+                    tracing::info!(
+                        "Watchtower submitted {} for watch {} of wallet '{}'",
+                        receipt.tx_hash,
+                        watch.id,
+                        watch.wallet
+                    );
+                    receipt.relayed = node_pool::relay_to_pool(state, &tx);
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Watchtower submission for watch {} of wallet '{}' was rejected: {}",
+                        watch.id,
+                        watch.wallet,
+                        e
+                    );
+                    receipt.outcome = ReceiptOutcome::Rejected;
+                    receipt.error = Some(e.to_string());
+                }
+            }
+            self.record(&receipt).await;
+            receipts.push(receipt);
+        }
+        receipts
+    }
+
+    /// Check for due watches every [`CHECK_INTERVAL`] until the state is
+    /// dropped
+    pub fn spawn(self: &Arc<Self>, state: &Arc<AppState>) -> JoinHandle<()> {
+        let tower = Arc::downgrade(self);
+        let state: Weak<AppState> = Arc::downgrade(state);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let (Some(tower), Some(state)) = (tower.upgrade(), state.upgrade()) else {
+                    break;
+                };
+                tower.run_once(&state).await;
+            }
+        })
+    }
+
+    /// Apply a receipt to its watch and append it to the log
+    async fn record(&self, receipt: &WatchtowerReceipt) {
+        let mut watches = self.watches.write().await;
+        if let Some(watch) = watches.get_mut(&receipt.watch_id) {
+            match receipt.outcome {
+                ReceiptOutcome::Submitted => {
+                    watch.status = WatchStatus::Submitted;
+                    watch.tx_hash = Some(receipt.tx_hash.clone());
+                }
+                ReceiptOutcome::Rejected => {
+                    watch.attempts += 1;
+                    if watch.attempts >= MAX_ATTEMPTS {
+                        watch.status = WatchStatus::Failed;
+                    }
+                }
+            }
+            self.save(&watches);
+        }
+        drop(watches);
+
+        let mut receipts = self.receipts.lock().unwrap();
+        receipts.push_back(receipt.clone());
+        if receipts.len() > MAX_RECENT_RECEIPTS {
+            receipts.pop_front();
+        }
+        let Some(path) = &self.receipts_path else {
+            return;
+        };
+        let result = serde_json::to_string(receipt)
+            .map_err(std::io::Error::other)
+            .and_then(|line| {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)?;
+                writeln!(file, "{}", line)
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to append to the watchtower receipts log: {}", e);
+        }
+    }
+
+    fn save(&self, watches: &HashMap<String, Watch>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let mut list: Vec<_> = watches.values().collect();
+        list.sort_by(|a, b| a.id.cmp(&b.id));
+        let tmp = path.with_extension("json.tmp");
+        let result = serde_json::to_string_pretty(&list)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(&tmp, json))
+            .and_then(|_| std::fs::rename(&tmp, path));
+        if let Err(e) = result {
+            tracing::warn!("Failed to save watchtower watches: {}", e);
+        }
+    }
+}
+
+impl Default for Watchtower {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn watchtower(state: &AppState) -> Result<&Watchtower, (StatusCode, Json<ErrorResponse>)> {
+    state
+        .watchtower
+        .as_deref()
+        .ok_or_else(|| api_error(StatusCode::NOT_FOUND, "The watchtower is disabled"))
+}
+
+/// GET /api/v1/wallets/{name}/watchtower - the wallet's watches
+pub async fn list_watches(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<Vec<Watch>> {
+    authorize(&key, &name, Some(Permission::ReadBalance))?;
+    Ok(Json(watchtower(&state)?.list(&name).await))
+}
+
+/// POST /api/v1/wallets/{name}/watchtower - act on a deadline
+pub async fn register_watch(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+    Json(request): Json<RegisterWatchRequest>,
+) -> ApiResult<Watch> {
+    authorize(&key, &name, Some(Permission::SubmitTransaction))?;
+    let tower = watchtower(&state)?;
+    let height = state
+        .node
+        .read()
+        .await
+        .get_blockchain()
+        .get_chain_height()
+        .unwrap_or(0);
+    tower
+        .register(&name, request, height)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(StatusCode::BAD_REQUEST, e))
+}
+
+/// DELETE /api/v1/wallets/{name}/watchtower/{id} - stop watching
+pub async fn delete_watch(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path((name, id)): Path<(String, String)>,
+) -> ApiResult<serde_json::Value> {
+    authorize(&key, &name, Some(Permission::SubmitTransaction))?;
+    let removed = watchtower(&state)?.remove(&name, &id).await;
+    Ok(Json(serde_json::json!({ "id": id, "removed": removed })))
+}
+
+/// GET /api/v1/wallets/{name}/watchtower/receipts - actions taken for the wallet
+pub async fn watch_receipts(
+    State(state): State<Arc<AppState>>,
+    Extension(key): Extension<ApiKey>,
+    Path(name): Path<String>,
+) -> ApiResult<Vec<WatchtowerReceipt>> {
+    authorize(&key, &name, Some(Permission::ReadBalance))?;
+    Ok(Json(watchtower(&state)?.receipts(&name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::{crypto::KeyPair, ChainParams};
+    use opensyria_node_cli::Node;
+
+    fn contract_call(contract: &PublicKey) -> WatchAction {
+        WatchAction::ContractCall {
+            contract: contract.to_hex(),
+            method: "refund".to_string(),
+            args: String::new(),
+            gas_limit: 10_000,
+            fee: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_register_validation() {
+        let tower = Watchtower::default();
+        let contract = KeyPair::generate().public_key();
+        let request = |deadline, action| RegisterWatchRequest {
+            deadline,
+            label: None,
+            action,
+        };
+
+        assert!(tower
+            .register("alice", request(5, contract_call(&contract)), 5)
+            .await
+            .is_err());
+        let bad_args = WatchAction::ContractCall {
+            contract: contract.to_hex(),
+            method: "refund".to_string(),
+            args: "zz".to_string(),
+            gas_limit: 10_000,
+            fee: 0,
+        };
+        assert!(tower
+            .register("alice", request(9, bad_args), 5)
+            .await
+            .is_err());
+        let unsigned = Transaction::new(contract, contract, 1_000, 100, 0);
+        assert!(tower
+            .register(
+                "alice",
+                request(
+                    9,
+                    WatchAction::Presigned {
+                        transaction: unsigned
+                    }
+                ),
+                5
+            )
+            .await
+            .is_err());
+
+        let watch = tower
+            .register("alice", request(9, contract_call(&contract)), 5)
+            .await
+            .unwrap();
+        let WatchAction::ContractCall { fee, .. } = &watch.action else {
+            panic!("expected a contract call");
+        };
+        assert_eq!(*fee, gas_fee(10_000, MIN_GAS_PRICE));
+        assert_eq!(tower.list("alice").await.len(), 1);
+        assert!(!tower.remove("bob", &watch.id).await);
+        assert!(tower.remove("alice", &watch.id).await);
+        assert!(tower.list("alice").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_due_watches_are_submitted_and_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = tempfile::tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 16).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let wallet = KeyPair::generate();
+        let signer = KeyPair::generate();
+        node.generate_to_address(1, &wallet.public_key()).unwrap();
+        node.generate_to_address(1, &signer.public_key()).unwrap();
+        let height = node.get_blockchain().get_chain_height().unwrap();
+
+        let tower = Watchtower::open(
+            files.path().join("watchtower.json"),
+            files.path().join("watchtower_receipts.jsonl"),
+        )
+        .unwrap();
+        let state = AppState::new(node).with_watchtower(tower);
+        let tower = state.watchtower.clone().unwrap();
+
+        let escrow = KeyPair::generate().public_key();
+        let call = tower
+            .register(
+                "alice",
+                RegisterWatchRequest {
+                    deadline: height + 1,
+                    label: Some("escrow #12".to_string()),
+                    action: contract_call(&escrow),
+                },
+                height,
+            )
+            .await
+            .unwrap();
+        let refund = Transaction::new_with_chain_id(
+            params.chain_id,
+            signer.public_key(),
+            KeyPair::generate().public_key(),
+            10_000,
+            1_000,
+            0,
+        );
+        let signature = signer.sign(&refund.signing_hash());
+        let refund = refund.with_signature(signature);
+        let presigned = tower
+            .register(
+                "alice",
+                RegisterWatchRequest {
+                    deadline: height + 1,
+                    label: None,
+                    action: WatchAction::Presigned {
+                        transaction: refund.clone(),
+                    },
+                },
+                height,
+            )
+            .await
+            .unwrap();
+
+        // Nothing is due before the deadline
+        assert!(tower.run_once(&state).await.is_empty());
+        state
+            .node
+            .write()
+            .await
+            .generate_to_address(1, &signer.public_key())
+            .unwrap();
+
+        // The presigned refund goes out; the call waits for the wallet key
+        let receipts = tower.run_once(&state).await;
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].watch_id, presigned.id);
+        assert_eq!(receipts[0].outcome, ReceiptOutcome::Submitted);
+        assert_eq!(receipts[0].tx_hash, hex::encode(refund.hash()));
+
+        state.wallet_manager.insert("alice", wallet.clone()).await;
+        let receipts = tower.run_once(&state).await;
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].watch_id, call.id);
+        assert_eq!(receipts[0].outcome, ReceiptOutcome::Submitted);
+        assert!(tower.run_once(&state).await.is_empty());
+
+        let pending = state.node.read().await.get_pending_transactions();
+        let sent = pending
+            .iter()
+            .find(|tx| tx.from == wallet.public_key())
+            .unwrap();
+        assert_eq!(sent.to, escrow);
+        assert!(ContractCall::is_contract(sent));
+        assert!(sent.verify().is_ok());
+        assert_eq!(hex::encode(sent.hash()), receipts[0].tx_hash);
+
+        let watches = tower.list("alice").await;
+        assert!(watches.iter().all(|w| w.status == WatchStatus::Submitted));
+
+        // Both survive a restart, receipts included
+        let reopened = Watchtower::open(
+            files.path().join("watchtower.json"),
+            files.path().join("watchtower_receipts.jsonl"),
+        )
+        .unwrap();
+        assert_eq!(reopened.list("alice").await.len(), 2);
+        assert_eq!(reopened.receipts("alice"), tower.receipts("alice"));
+        assert_eq!(reopened.receipts("alice").len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_watch_gives_up_after_max_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut node = Node::init(dir.path().to_path_buf(), 16).unwrap();
+        let params = ChainParams::regtest();
+        node.set_chain_params(params.clone());
+        let miner = KeyPair::generate();
+        node.generate_to_address(2, &miner.public_key()).unwrap();
+
+        let state = AppState::new(node).with_watchtower(Watchtower::default());
+        let tower = state.watchtower.clone().unwrap();
+
+        // Signed by an account with no funds
+        let broke = KeyPair::generate();
+        let refund = Transaction::new_with_chain_id(
+            params.chain_id,
+            broke.public_key(),
+            miner.public_key(),
+            10_000,
+            1_000,
+            0,
+        );
+        let signature = broke.sign(&refund.signing_hash());
+        let watch = tower
+            .register(
+                "alice",
+                RegisterWatchRequest {
+                    deadline: 1,
+                    label: None,
+                    action: WatchAction::Presigned {
+                        transaction: refund.with_signature(signature),
+                    },
+                },
+                0,
+            )
+            .await
+            .unwrap();
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let receipts = tower.run_once(&state).await;
+            assert_eq!(receipts.len(), 1);
+            assert_eq!(receipts[0].outcome, ReceiptOutcome::Rejected);
+            assert!(receipts[0].error.is_some());
+            assert_eq!(tower.list("alice").await[0].attempts, attempt);
+        }
+        assert!(tower.run_once(&state).await.is_empty());
+        let watches = tower.list("alice").await;
+        assert_eq!(watches[0].id, watch.id);
+        assert_eq!(watches[0].status, WatchStatus::Failed);
+        assert_eq!(tower.receipts("alice").len(), MAX_ATTEMPTS as usize);
+    }
+}
@@ -0,0 +1,196 @@
+//! Native asset operations from the wallet
+//! عمليات الأصول الأصلية من المحفظة
+//!
+//! Asset amounts are entered in base units; `decimals` only affects how
+//! balances are displayed.
+
+use crate::selection::{select, SelectionStrategy, SelectionTarget};
+use anyhow::{anyhow, bail, Result};
+use opensyria_core::{
+    asset::AssetOperation,
+    constants::CHAIN_ID_MAINNET,
+    crypto::KeyPair,
+    dex::{DexOperation, Order, OrderSide},
+    transaction::Transaction,
+};
+
+/// Build and sign a transaction carrying an asset operation
+/// إنشاء وتوقيع معاملة عملية أصل
+pub fn build_asset_transaction(
+    keypair: &KeyPair,
+    operation: &AssetOperation,
+    nonce: u64,
+    fee: u64,
+) -> Result<Transaction> {
+    operation
+        .validate()
+        .map_err(|e| anyhow!("Invalid asset operation: {}", e))?;
+
+    let tx = operation.transaction(CHAIN_ID_MAINNET, keypair.public_key(), fee, nonce);
+    let signature = keypair.sign(&tx.signing_hash());
+    let tx = tx.with_signature(signature);
+    tx.verify()
+        .map_err(|e| anyhow!("Failed to sign asset transaction: {:?}", e))?;
+
+    Ok(tx)
+}
+
+/// Cancel enough of the sender's open orders to release `target` from escrow
+/// إلغاء أوامر مفتوحة كافية لتحرير المبلغ المطلوب من الضمان
+///
+/// `orders` must lock the same unit: all buy orders (SYL), or sell orders of
+/// one asset. Cancellations take consecutive nonces from `nonce`.
+pub fn build_escrow_release(
+    keypair: &KeyPair,
+    orders: &[Order],
+    target: &SelectionTarget,
+    strategy: SelectionStrategy,
+    nonce: u64,
+    fee: u64,
+) -> Result<Vec<Transaction>> {
+    let owner = keypair.public_key();
+    let own: Vec<Order> = orders
+        .iter()
+        .filter(|order| order.owner == owner)
+        .cloned()
+        .collect();
+    let mixed = own.windows(2).any(|pair| {
+        pair[0].side != pair[1].side
+            || (pair[0].side == OrderSide::Sell && pair[0].asset != pair[1].asset)
+    });
+    if mixed {
+        bail!("Orders to release must all lock SYL or all lock the same asset");
+    }
+
+    let selection = select(&own, target, strategy).map_err(|e| anyhow!("{}", e))?;
+    selection
+        .selected
+        .iter()
+        .zip(nonce..)
+        .map(|(order, nonce)| {
+            let tx = DexOperation::Cancel { order: order.id }.transaction(
+                CHAIN_ID_MAINNET,
+                owner,
+                fee,
+                nonce,
+            );
+            let signature = keypair.sign(&tx.signing_hash());
+            let tx = tx.with_signature(signature);
+            tx.verify()
+                .map_err(|e| anyhow!("Failed to sign cancel transaction: {:?}", e))?;
+            Ok(tx)
+        })
+        .collect()
+}
+
+/// Format base units with `decimals` places
+/// تنسيق الوحدات الأساسية بعدد المنازل العشرية
+pub fn format_asset_amount(amount: u64, decimals: u8) -> String {
+    if decimals == 0 {
+        return amount.to_string();
+    }
+    let scale = 10u128.pow(decimals as u32);
+    let whole = amount as u128 / scale;
+    let fraction = format!(
+        "{:0width$}",
+        amount as u128 % scale,
+        width = decimals as usize
+    );
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::{
+        asset::AssetId,
+        dex::{OrderId, OrderStatus},
+    };
+
+    #[test]
+    fn test_format_asset_amount() {
+        assert_eq!(format_asset_amount(1_250, 2), "12.5");
+        assert_eq!(format_asset_amount(5, 2), "0.05");
+        assert_eq!(format_asset_amount(300, 2), "3");
+        assert_eq!(format_asset_amount(7, 0), "7");
+        assert_eq!(format_asset_amount(u64::MAX, 18), "18.446744073709551615");
+    }
+
+    #[test]
+    fn test_asset_transaction_is_signed_operation() {
+        let issuer = KeyPair::generate();
+        let operation = AssetOperation::Burn {
+            asset: AssetId::derive(&issuer.public_key(), 0),
+            amount: 10,
+        };
+
+        let tx = build_asset_transaction(&issuer, &operation, 4, 100).unwrap();
+        assert_eq!(tx.nonce, 4);
+        assert_eq!(AssetOperation::from_transaction(&tx), Some(Ok(operation)));
+
+        let zero = AssetOperation::Burn {
+            asset: AssetId::derive(&issuer.public_key(), 0),
+            amount: 0,
+        };
+        assert!(build_asset_transaction(&issuer, &zero, 0, 100).is_err());
+    }
+
+    #[test]
+    fn test_escrow_release_cancels_selected_orders() {
+        let trader = KeyPair::generate();
+        let asset = AssetId::derive(&trader.public_key(), 0);
+        let order = |n: u8, price: u64, remaining: u64, status: OrderStatus| Order {
+            id: OrderId([n; 32]),
+            owner: trader.public_key(),
+            asset,
+            side: OrderSide::Buy,
+            price,
+            amount: remaining,
+            remaining,
+            height: 1,
+            index: n as u32,
+            status,
+        };
+        let orders = vec![
+            order(1, 10, 7, OrderStatus::Open),
+            order(2, 10, 3, OrderStatus::Open),
+            order(3, 10, 20, OrderStatus::Cancelled),
+            order(4, 10, 5, OrderStatus::Open),
+        ];
+
+        let txs = build_escrow_release(
+            &trader,
+            &orders,
+            &SelectionTarget::new(80),
+            SelectionStrategy::BranchAndBound,
+            9,
+            100,
+        )
+        .unwrap();
+        let cancelled: Vec<_> = txs
+            .iter()
+            .map(|tx| match DexOperation::from_transaction(tx) {
+                Some(Ok(DexOperation::Cancel { order })) => order.0[0],
+                other => panic!("unexpected operation {:?}", other),
+            })
+            .collect();
+        assert_eq!(cancelled, vec![4, 2]);
+        assert_eq!(txs[1].nonce, 10);
+
+        // The cancelled order's escrow is already released
+        assert!(build_escrow_release(
+            &trader,
+            &orders,
+            &SelectionTarget::new(160),
+            SelectionStrategy::LargestFirst,
+            0,
+            100,
+        )
+        .is_err());
+    }
+}
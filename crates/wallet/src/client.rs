@@ -0,0 +1,233 @@
+//! Minimal HTTP client for the wallet API
+//! عميل HTTP بسيط لواجهة برمجة تطبيقات المحفظة
+
+use anyhow::{anyhow, Context, Result};
+use opensyria_core::{crypto::PublicKey, transaction::Transaction, ConfirmationStatus};
+use serde::Deserialize;
+use serde_json::json;
+
+/// Default wallet API endpoint
+pub const DEFAULT_NODE_URL: &str = "http://127.0.0.1:8080";
+
+/// Account balance and nonce as reported by the node
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct AccountState {
+    pub balance: u64,
+    pub nonce: u64,
+    /// Unconfirmed amount waiting in the mempool to be received
+    #[serde(default)]
+    pub pending_incoming: u64,
+    /// Unconfirmed amount plus fees waiting in the mempool to be spent
+    #[serde(default)]
+    pub pending_outgoing: u64,
+}
+
+impl AccountState {
+    /// Confirmed balance minus what pending transactions already spend
+    pub fn spendable(&self) -> u64 {
+        self.balance.saturating_sub(self.pending_outgoing)
+    }
+}
+
+/// A confirmed or pending transaction touching an address
+#[derive(Debug, Clone, Deserialize)]
+pub struct HistoryEntry {
+    pub hash: String,
+    pub from: String,
+    pub to: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub nonce: u64,
+    pub block_height: Option<u64>,
+    pub confirmed: bool,
+    /// Absent from nodes that predate confirmation tracking
+    #[serde(default)]
+    pub status: Option<ConfirmationStatus>,
+    /// Hex transaction data, holding the memo if there is one
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+/// Balance of one native asset held by an address
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetBalance {
+    pub asset_id: String,
+    pub symbol: String,
+    pub name: String,
+    pub decimals: u8,
+    pub balance: u64,
+}
+
+/// A stealth payment published on chain or waiting in the mempool
+#[derive(Debug, Clone, Deserialize)]
+pub struct StealthAnnouncementEntry {
+    pub hash: String,
+    pub block_height: Option<u64>,
+    /// One-time address paid
+    pub to: String,
+    pub amount: u64,
+    /// Hex transaction data holding the announcement
+    pub data: String,
+}
+
+/// One page of stealth announcements
+#[derive(Debug, Clone, Deserialize)]
+pub struct StealthAnnouncementsPage {
+    pub next_height: u64,
+    pub tip_height: u64,
+    pub announcements: Vec<StealthAnnouncementEntry>,
+    #[serde(default)]
+    pub pending: Vec<StealthAnnouncementEntry>,
+}
+
+#[derive(Deserialize)]
+struct AssetBalancesResponse {
+    assets: Vec<AssetBalance>,
+}
+
+#[derive(Deserialize)]
+struct HistoryResponse {
+    transactions: Vec<HistoryEntry>,
+}
+
+#[derive(Deserialize)]
+struct SubmitResponse {
+    tx_hash: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Blocking client for a node's wallet API
+/// عميل متزامن لواجهة محفظة العقدة
+pub struct NodeClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::blocking::Client,
+}
+
+impl NodeClient {
+    /// Create a client for the wallet API at `base_url`
+    pub fn new(base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+            http: reqwest::blocking::Client::new(),
+        }
+    }
+
+    /// Fetch balance and nonce for an address
+    pub fn get_account(&self, address: &PublicKey) -> Result<AccountState> {
+        let url = format!(
+            "{}/api/v1/account/{}/balance",
+            self.base_url,
+            address.to_hex()
+        );
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        Self::parse(response)
+    }
+
+    /// Fetch up to `limit` transactions of an address, pending first then newest confirmed
+    pub fn get_history(&self, address: &PublicKey, limit: usize) -> Result<Vec<HistoryEntry>> {
+        let url = format!(
+            "{}/api/v1/account/{}/history?limit={}",
+            self.base_url,
+            address.to_hex(),
+            limit
+        );
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        let history: HistoryResponse = Self::parse(response)?;
+        Ok(history.transactions)
+    }
+
+    /// Fetch the native asset balances of an address
+    pub fn get_assets(&self, address: &PublicKey) -> Result<Vec<AssetBalance>> {
+        let url = format!(
+            "{}/api/v1/account/{}/assets",
+            self.base_url,
+            address.to_hex()
+        );
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        let assets: AssetBalancesResponse = Self::parse(response)?;
+        Ok(assets.assets)
+    }
+
+    /// Fetch stealth announcements from `from_height`, one page of blocks
+    pub fn get_stealth_announcements(&self, from_height: u64) -> Result<StealthAnnouncementsPage> {
+        let url = format!(
+            "{}/api/v1/stealth/announcements?from_height={}",
+            self.base_url, from_height
+        );
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        Self::parse(response)
+    }
+
+    /// Submit a signed transaction, returning its hash
+    pub fn submit_transaction(&self, tx: &Transaction) -> Result<String> {
+        let url = format!("{}/api/v1/transaction/submit", self.base_url);
+        let mut request = self.http.post(&url).json(&json!({
+            "from": tx.from.to_hex(),
+            "to": tx.to.to_hex(),
+            "amount": tx.amount,
+            "fee": tx.fee,
+            "signature": hex::encode(&tx.signature),
+        }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        let submitted: SubmitResponse = Self::parse(response)?;
+        Ok(submitted.tx_hash.unwrap_or_else(|| hex::encode(tx.hash())))
+    }
+
+    /// Submit a signed transaction in raw encoding, keeping its data payload
+    pub fn submit_raw_transaction(&self, tx: &Transaction) -> Result<String> {
+        let url = format!("{}/api/v1/transaction/raw", self.base_url);
+        let mut request = self
+            .http
+            .post(&url)
+            .json(&json!({ "hex": tx.to_raw_hex() }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .with_context(|| format!("Failed to reach node at {}", self.base_url))?;
+        let submitted: SubmitResponse = Self::parse(response)?;
+        Ok(submitted.tx_hash.unwrap_or_else(|| hex::encode(tx.hash())))
+    }
+
+    fn parse<T: serde::de::DeserializeOwned>(response: reqwest::blocking::Response) -> Result<T> {
+        let status = response.status();
+        if !status.is_success() {
+            let message = response
+                .json::<ErrorBody>()
+                .map(|body| body.error)
+                .unwrap_or_else(|_| status.to_string());
+            return Err(anyhow!("Node returned {}: {}", status, message));
+        }
+        response.json().context("Invalid response from node")
+    }
+}
@@ -1,6 +1,8 @@
 /// Encrypted wallet storage with AES-256-GCM and Argon2 password hashing
 /// تخزين المحفظة المشفرة مع AES-256-GCM وتجزئة كلمة المرور Argon2
 
+use crate::kdf::{KdfParams, WALLET_FORMAT_VERSION};
+use crate::lock::UnlockAttempts;
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
@@ -8,17 +10,113 @@ use aes_gcm::{
 use anyhow::{anyhow, Context, Result};
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
-    Argon2, PasswordHash, PasswordVerifier,
+    Algorithm, Argon2, PasswordHash, PasswordVerifier, Version,
 };
 use opensyria_core::crypto::{KeyPair, PublicKey};
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use zeroize::Zeroize;
+
+/// Random bytes written to a new keyfile
+pub const KEYFILE_LEN: usize = 64;
+
+/// Keyfile used as a second factor: its SHA-256 digest is the Argon2 secret
+/// ملف مفتاح يُستخدم كعامل ثانٍ مع كلمة المرور
+pub struct Keyfile([u8; 32]);
+
+impl Keyfile {
+    /// Key material from the contents of any non-empty file
+    pub fn from_bytes(contents: &[u8]) -> Result<Self> {
+        if contents.is_empty() {
+            return Err(anyhow!("Keyfile is empty"));
+        }
+        Ok(Self(Sha256::digest(contents).into()))
+    }
+
+    /// Read a keyfile from disk
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut contents =
+            fs::read(path).with_context(|| format!("Failed to read keyfile {}", path.display()))?;
+        let keyfile = Self::from_bytes(&contents);
+        contents.zeroize();
+        keyfile
+    }
+
+    /// Write a new random keyfile readable only by its owner
+    pub fn generate(path: &Path) -> Result<Self> {
+        if path.exists() {
+            return Err(anyhow!("Keyfile {} already exists", path.display()));
+        }
+        let mut contents = [0u8; KEYFILE_LEN];
+        OsRng.fill_bytes(&mut contents);
+        fs::write(path, contents).context("Failed to write keyfile")?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+                .context("Failed to set secure keyfile permissions")?;
+        }
+        let keyfile = Self::from_bytes(&contents);
+        contents.zeroize();
+        keyfile
+    }
+}
+
+impl Drop for Keyfile {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Argon2 with `kdf` parameters and the keyfile digest as secret, if any
+fn argon2<'k>(keyfile: Option<&'k Keyfile>, kdf: &KdfParams) -> Result<Argon2<'k>> {
+    let params = kdf.argon2_params()?;
+    match keyfile {
+        Some(keyfile) => {
+            Argon2::new_with_secret(&keyfile.0, Algorithm::default(), Version::default(), params)
+                .map_err(|e| anyhow!("Failed to set up Argon2: {}", e))
+        }
+        None => Ok(Argon2::new(
+            Algorithm::default(),
+            Version::default(),
+            params,
+        )),
+    }
+}
+
+/// Encryption key for the private key. Format 2 and later derive it in a
+/// separate pass over `key_salt`, so the stored verification hash does not
+/// reveal it; format 1 uses the verification hash itself.
+fn encryption_key(
+    argon2: &Argon2<'_>,
+    password: &str,
+    password_hash: &PasswordHash<'_>,
+    key_salt: Option<&[u8; 16]>,
+) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    match key_salt {
+        Some(salt) => argon2
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|e| anyhow!("Failed to derive encryption key: {}", e))?,
+        None => {
+            let hash = password_hash
+                .hash
+                .ok_or_else(|| anyhow!("Password hash has no output"))?;
+            key.copy_from_slice(&hash.as_bytes()[..32]);
+        }
+    }
+    Ok(key)
+}
 
 /// Encrypted account with password-protected private key
 /// حساب مشفر مع مفتاح خاص محمي بكلمة مرور
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EncryptedAccount {
     pub name: String,
     pub address: PublicKey,
@@ -31,22 +129,79 @@ pub struct EncryptedAccount {
     /// Salt for password hashing
     pub salt: String,
     pub created_at: u64,
-    /// Encryption version for future upgrades
+    /// File format version (see [`crate::kdf`])
     pub version: u32,
+    /// Argon2 parameters the account was encrypted with
+    #[serde(default = "KdfParams::legacy")]
+    pub kdf: KdfParams,
+    /// Whether a keyfile is mixed into the key derivation
+    #[serde(default)]
+    pub requires_keyfile: bool,
+    /// Salt of the separate encryption key derivation (format 2 and later)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_salt: Option<[u8; 16]>,
 }
 
 impl EncryptedAccount {
     /// Create new encrypted account with password protection
     pub fn new(name: String, password: &str) -> Result<Self> {
+        Self::new_with_keyfile(name, password, None)
+    }
+
+    /// Create new encrypted account protected by a password and, optionally, a keyfile
+    /// إنشاء حساب مشفر جديد محمي بكلمة مرور وملف مفتاح اختياري
+    pub fn new_with_keyfile(
+        name: String,
+        password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<Self> {
+        Self::new_with_params(name, password, keyfile, KdfParams::default())
+    }
+
+    /// Create new encrypted account with explicit Argon2 parameters
+    pub fn new_with_params(
+        name: String,
+        password: &str,
+        keyfile: Option<&Keyfile>,
+        kdf: KdfParams,
+    ) -> Result<Self> {
         let keypair = KeyPair::generate();
-        let private_key = keypair.private_key_bytes();
-        
-        Self::from_private_key(name, &private_key, password)
+        keypair.with_private_key(|private_key| {
+            Self::from_private_key_with_params(name, private_key, password, keyfile, kdf)
+        })
     }
 
     /// Create encrypted account from existing private key
     /// إنشاء حساب مشفر من مفتاح خاص موجود
     pub fn from_private_key(name: String, private_key: &[u8; 32], password: &str) -> Result<Self> {
+        Self::from_private_key_with_keyfile(name, private_key, password, None)
+    }
+
+    /// Create encrypted account from existing private key, optionally requiring a keyfile
+    pub fn from_private_key_with_keyfile(
+        name: String,
+        private_key: &[u8; 32],
+        password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<Self> {
+        Self::from_private_key_with_params(
+            name,
+            private_key,
+            password,
+            keyfile,
+            KdfParams::default(),
+        )
+    }
+
+    /// Create encrypted account from existing private key with explicit Argon2 parameters
+    pub fn from_private_key_with_params(
+        name: String,
+        private_key: &[u8; 32],
+        password: &str,
+        keyfile: Option<&Keyfile>,
+        kdf: KdfParams,
+    ) -> Result<Self> {
+        kdf.validate()?;
         let keypair = KeyPair::from_bytes(private_key)?;
         
         let timestamp = std::time::SystemTime::now()
@@ -54,11 +209,37 @@ impl EncryptedAccount {
             .unwrap()
             .as_secs();
 
+        let mut account = Self {
+            name,
+            address: keypair.public_key(),
+            encrypted_key: Vec::new(),
+            nonce: [0u8; 12],
+            password_hash: String::new(),
+            salt: String::new(),
+            created_at: timestamp,
+            version: WALLET_FORMAT_VERSION,
+            kdf,
+            requires_keyfile: false,
+            key_salt: None,
+        };
+        account.encrypt(private_key, password, keyfile, kdf)?;
+        Ok(account)
+    }
+
+    /// Encrypt `private_key` in the current format under a fresh salt and nonce
+    fn encrypt(
+        &mut self,
+        private_key: &[u8; 32],
+        password: &str,
+        keyfile: Option<&Keyfile>,
+        kdf: KdfParams,
+    ) -> Result<()> {
+        let argon2 = argon2(keyfile, &kdf)?;
+
         // Generate salt for password hashing
         let salt = SaltString::generate(&mut OsRng);
         
         // Hash password with Argon2
-        let argon2 = Argon2::default();
         let password_hash = argon2
             .hash_password(password.as_bytes(), &salt)
             .map_err(|e| anyhow!("Failed to hash password: {}", e))?
@@ -67,8 +248,9 @@ impl EncryptedAccount {
         // Derive encryption key from password
         let parsed_hash = PasswordHash::new(&password_hash)
             .map_err(|e| anyhow!("Failed to parse password hash: {}", e))?;
-        let hash_bytes = parsed_hash.hash.unwrap();
-        let encryption_key = &hash_bytes.as_bytes()[..32];
+        let mut key_salt = [0u8; 16];
+        OsRng.fill_bytes(&mut key_salt);
+        let mut encryption_key = encryption_key(&argon2, password, &parsed_hash, Some(&key_salt))?;
 
         // Generate random nonce for AES-GCM
         let mut nonce_bytes = [0u8; 12];
@@ -76,57 +258,85 @@ impl EncryptedAccount {
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         // Encrypt private key
-        let cipher = Aes256Gcm::new_from_slice(encryption_key)
+        let cipher = Aes256Gcm::new_from_slice(&encryption_key)
             .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        encryption_key.zeroize();
         let encrypted_key = cipher
             .encrypt(nonce, private_key.as_ref())
             .map_err(|e| anyhow!("Encryption failed: {}", e))?;
 
-        Ok(Self {
-            name,
-            address: keypair.public_key(),
-            encrypted_key,
-            nonce: nonce_bytes,
-            password_hash,
-            salt: salt.to_string(),
-            created_at: timestamp,
-            version: 1,
-        })
+        self.encrypted_key = encrypted_key;
+        self.nonce = nonce_bytes;
+        self.password_hash = password_hash;
+        self.salt = salt.to_string();
+        self.version = WALLET_FORMAT_VERSION;
+        self.kdf = kdf;
+        self.requires_keyfile = keyfile.is_some();
+        self.key_salt = Some(key_salt);
+        Ok(())
+    }
+
+    /// Whether the account is in an older format or weaker than `target`
+    /// and should be re-encrypted at its next unlock
+    pub fn needs_upgrade(&self, target: &KdfParams) -> bool {
+        self.version < WALLET_FORMAT_VERSION || self.kdf.cost() < target.cost()
     }
 
     /// Decrypt and get keypair (requires correct password)
     /// فك التشفير والحصول على زوج المفاتيح (يتطلب كلمة مرور صحيحة)
     pub fn decrypt_keypair(&self, password: &str) -> Result<KeyPair> {
+        self.decrypt_keypair_with_keyfile(password, None)
+    }
+
+    /// Decrypt with the password and, for keyfile accounts, the keyfile
+    /// فك التشفير بكلمة المرور وملف المفتاح
+    pub fn decrypt_keypair_with_keyfile(
+        &self,
+        password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<KeyPair> {
+        if self.requires_keyfile && keyfile.is_none() {
+            return Err(anyhow!("Account '{}' requires a keyfile", self.name));
+        }
+        let argon2 = argon2(keyfile.filter(|_| self.requires_keyfile), &self.kdf)?;
+
         // Verify password
         let parsed_hash = PasswordHash::new(&self.password_hash)
             .map_err(|e| anyhow!("Failed to parse password hash: {}", e))?;
         
-        Argon2::default()
+        argon2
             .verify_password(password.as_bytes(), &parsed_hash)
             .map_err(|_| anyhow!("Invalid password"))?;
 
         // Derive decryption key from password
-        let hash_bytes = parsed_hash.hash.unwrap();
-        let encryption_key = &hash_bytes.as_bytes()[..32];
+        let mut encryption_key =
+            encryption_key(&argon2, password, &parsed_hash, self.key_salt.as_ref())?;
 
         // Decrypt private key
-        let cipher = Aes256Gcm::new_from_slice(encryption_key)
+        let cipher = Aes256Gcm::new_from_slice(&encryption_key)
             .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
+        encryption_key.zeroize();
         let nonce = Nonce::from_slice(&self.nonce);
 
-        let decrypted_key = cipher
+        let mut decrypted_key = cipher
             .decrypt(nonce, self.encrypted_key.as_ref())
             .map_err(|_| anyhow!("Decryption failed - invalid password or corrupted wallet"))?;
 
         let mut private_key = [0u8; 32];
         private_key.copy_from_slice(&decrypted_key);
+        decrypted_key.zeroize();
 
-        KeyPair::from_bytes(&private_key).map_err(|e| anyhow!("{}", e))
+        let keypair = KeyPair::from_bytes(&private_key).map_err(|e| anyhow!("{}", e));
+        private_key.zeroize();
+        keypair
     }
 
     /// Verify password without decrypting
     /// التحقق من كلمة المرور دون فك التشفير
     pub fn verify_password(&self, password: &str) -> bool {
+        if self.requires_keyfile {
+            return false;
+        }
         if let Ok(parsed_hash) = PasswordHash::new(&self.password_hash) {
             Argon2::default()
                 .verify_password(password.as_bytes(), &parsed_hash)
@@ -139,43 +349,24 @@ impl EncryptedAccount {
     /// Change password (requires current password)
     /// تغيير كلمة المرور (يتطلب كلمة المرور الحالية)
     pub fn change_password(&mut self, old_password: &str, new_password: &str) -> Result<()> {
-        // Decrypt with old password to get private key
-        let keypair = self.decrypt_keypair(old_password)?;
-        let private_key = keypair.private_key_bytes();
-
-        // Generate new salt and hash
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2
-            .hash_password(new_password.as_bytes(), &salt)
-            .map_err(|e| anyhow!("Failed to hash new password: {}", e))?
-            .to_string();
-
-        // Derive new encryption key
-        let parsed_hash = PasswordHash::new(&password_hash)
-            .map_err(|e| anyhow!("Failed to parse password hash: {}", e))?;
-        let hash_bytes = parsed_hash.hash.unwrap();
-        let encryption_key = &hash_bytes.as_bytes()[..32];
-
-        // Generate new nonce
-        let mut nonce_bytes = [0u8; 12];
-        OsRng.fill_bytes(&mut nonce_bytes);
-        let nonce = Nonce::from_slice(&nonce_bytes);
-
-        // Re-encrypt with new password
-        let cipher = Aes256Gcm::new_from_slice(encryption_key)
-            .map_err(|e| anyhow!("Failed to create cipher: {}", e))?;
-        let encrypted_key = cipher
-            .encrypt(nonce, private_key.as_ref())
-            .map_err(|e| anyhow!("Encryption failed: {}", e))?;
+        self.change_password_with_keyfile(old_password, new_password, None)
+    }
 
-        // Update stored values
-        self.encrypted_key = encrypted_key;
-        self.nonce = nonce_bytes;
-        self.password_hash = password_hash;
-        self.salt = salt.to_string();
+    /// Change password of an account, keeping its keyfile requirement
+    pub fn change_password_with_keyfile(
+        &mut self,
+        old_password: &str,
+        new_password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<()> {
+        // Decrypt with old password to get private key
+        let keypair = self.decrypt_keypair_with_keyfile(old_password, keyfile)?;
 
-        Ok(())
+        // Re-encrypt under a new salt, nonce and password
+        let keyfile = keyfile.filter(|_| self.requires_keyfile);
+        let kdf = self.kdf;
+        keypair
+            .with_private_key(|private_key| self.encrypt(private_key, new_password, keyfile, kdf))
     }
 }
 
@@ -194,6 +385,10 @@ impl EncryptedAccount {
 /// - Salt generation with OsRng (proper entropy)
 pub struct EncryptedWalletStorage {
     wallet_dir: PathBuf,
+    /// Argon2 parameters accounts are upgraded to at unlock
+    kdf: KdfParams,
+    /// One lock per account, held for the whole of an unlock
+    unlocks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
 }
 
 impl EncryptedWalletStorage {
@@ -206,13 +401,33 @@ impl EncryptedWalletStorage {
 
         fs::create_dir_all(&wallet_dir).context("Failed to create wallet directory")?;
 
-        Ok(Self { wallet_dir })
+        Ok(Self {
+            wallet_dir,
+            kdf: KdfParams::default(),
+            unlocks: Mutex::new(HashMap::new()),
+        })
     }
 
     /// Create encrypted wallet storage in custom directory
     pub fn with_path(path: PathBuf) -> Result<Self> {
         fs::create_dir_all(&path).context("Failed to create wallet directory")?;
-        Ok(Self { wallet_dir: path })
+        Ok(Self {
+            wallet_dir: path,
+            kdf: KdfParams::default(),
+            unlocks: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Set the Argon2 parameters accounts are upgraded to at unlock
+    pub fn with_kdf_params(mut self, kdf: KdfParams) -> Result<Self> {
+        kdf.validate()?;
+        self.kdf = kdf;
+        Ok(self)
+    }
+
+    /// Argon2 parameters accounts are upgraded to at unlock
+    pub fn kdf_params(&self) -> KdfParams {
+        self.kdf
     }
 
     /// Save encrypted account to disk
@@ -239,17 +454,32 @@ impl EncryptedWalletStorage {
         let json = serde_json::to_string_pretty(account)
             .context("Failed to serialize encrypted account")?;
 
-        fs::write(&path, json).context("Failed to write encrypted account file")?;
-        
+        // Write a temporary file and rename it over the account, so a crash
+        // never leaves a half-written account behind
+        let temp = self.wallet_dir.join(format!("{}.tmp", filename));
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options
+            .open(&temp)
+            .context("Failed to write encrypted account file")?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.sync_all())
+            .context("Failed to write encrypted account file")?;
+
         // SECURITY FIX: Set secure file permissions (owner read/write only)
         #[cfg(unix)]
         {
             use std::os::unix::fs::PermissionsExt;
-            let mut perms = fs::metadata(&path)?.permissions();
+            let mut perms = fs::metadata(&temp)?.permissions();
             perms.set_mode(0o600); // Owner read/write only
-            fs::set_permissions(&path, perms)
-                .context("Failed to set secure file permissions")?;
+            fs::set_permissions(&temp, perms).context("Failed to set secure file permissions")?;
         }
+        fs::rename(&temp, &path).context("Failed to replace encrypted account file")?;
 
         // SECURITY FIX: Set secure permissions on Windows
         #[cfg(windows)]
@@ -343,10 +573,125 @@ impl EncryptedWalletStorage {
 
         fs::remove_file(&path)
             .context(format!("Failed to delete encrypted account '{}'", name))?;
+        let attempts = self.attempts_path(name)?;
+        if attempts.exists() {
+            fs::remove_file(attempts).context("Failed to delete unlock attempts")?;
+        }
 
         Ok(())
     }
 
+    /// Unlock attempt counter of an account
+    fn attempts_path(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty()
+            || name.contains('/')
+            || name.contains('\\')
+            || name.contains("..")
+            || name.starts_with('.')
+        {
+            return Err(anyhow!("Invalid account name"));
+        }
+        Ok(self.wallet_dir.join(format!("{}.attempts.json", name)))
+    }
+
+    /// Decrypt an account, refusing while it is backing off from failed
+    /// attempts and counting this attempt if it fails. An account in an
+    /// older format or with weaker Argon2 parameters is re-encrypted.
+    /// فك تشفير الحساب مع تأخير تصاعدي بعد المحاولات الفاشلة
+    ///
+    /// Unlocks of one account run one at a time, and the attempt is counted
+    /// before the slow decryption and cleared only once it succeeds, so
+    /// neither parallel guesses nor a process killed mid-attempt escape the
+    /// backoff. This blocks for the whole Argon2 run; async callers should
+    /// call it from a blocking thread.
+    pub fn unlock_account(
+        &self,
+        account: &EncryptedAccount,
+        password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<KeyPair> {
+        if account.requires_keyfile && keyfile.is_none() {
+            return Err(anyhow!("Account '{}' requires a keyfile", account.name));
+        }
+
+        let lock = self
+            .unlocks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(account.name.clone())
+            .or_default()
+            .clone();
+        let _unlocking = lock.lock().unwrap_or_else(PoisonError::into_inner);
+
+        let path = self.attempts_path(&account.name)?;
+        let mut attempts = UnlockAttempts::load(&path)?;
+        let now = opensyria_core::clock::system_time();
+        let wait = attempts.locked_for(now);
+        if wait > 0 {
+            return Err(anyhow!(
+                "Account '{}' is locked for {}s after {} failed attempts",
+                account.name,
+                wait,
+                attempts.failures
+            ));
+        }
+        attempts.record_failure(now);
+        attempts.save(&path)?;
+
+        let keypair = account.decrypt_keypair_with_keyfile(password, keyfile)?;
+        fs::remove_file(&path).context("Failed to reset unlock attempts")?;
+        if account.needs_upgrade(&self.kdf) {
+            self.upgrade_account(account, &keypair, password, keyfile)?;
+        }
+        Ok(keypair)
+    }
+
+    /// Re-encrypt an unlocked account in the current format
+    ///
+    /// The old file is copied to `<name>.enc.json.v<version>.bak` first and
+    /// the new one replaces it atomically. The backup is removed once the
+    /// new file decrypts to the same key, and restored if it does not.
+    fn upgrade_account(
+        &self,
+        account: &EncryptedAccount,
+        keypair: &KeyPair,
+        password: &str,
+        keyfile: Option<&Keyfile>,
+    ) -> Result<()> {
+        let path = self.wallet_dir.join(format!("{}.enc.json", account.name));
+        let backup = self.wallet_dir.join(format!(
+            "{}.enc.json.v{}.bak",
+            account.name, account.version
+        ));
+        fs::copy(&path, &backup).context("Failed to back up account before upgrade")?;
+
+        // Never lower the cost of an account encrypted with stronger parameters
+        let kdf = if account.kdf.cost() > self.kdf.cost() {
+            account.kdf
+        } else {
+            self.kdf
+        };
+        let mut upgraded = account.clone();
+        let keyfile = keyfile.filter(|_| account.requires_keyfile);
+        keypair.with_private_key(|private_key| {
+            upgraded.encrypt(private_key, password, keyfile, kdf)
+        })?;
+        self.save_account(&upgraded)?;
+
+        let verified = self
+            .load_account(&account.name)
+            .and_then(|saved| saved.decrypt_keypair_with_keyfile(password, keyfile))
+            .is_ok_and(|saved| saved.public_key() == keypair.public_key());
+        if !verified {
+            fs::rename(&backup, &path).context("Failed to restore account after upgrade")?;
+            return Err(anyhow!(
+                "Upgraded account '{}' did not verify; the old file was restored",
+                account.name
+            ));
+        }
+        fs::remove_file(&backup).context("Failed to remove account backup after upgrade")
+    }
+
     /// Check if account exists
     pub fn account_exists(&self, name: &str) -> bool {
         let filename = format!("{}.enc.json", name);
@@ -366,7 +711,7 @@ mod tests {
             .expect("Failed to create encrypted account");
 
         assert_eq!(account.name, "test_account");
-        assert_eq!(account.version, 1);
+        assert_eq!(account.version, WALLET_FORMAT_VERSION);
         assert_eq!(account.nonce.len(), 12);
         assert!(!account.encrypted_key.is_empty());
     }
@@ -492,4 +837,143 @@ mod tests {
 
         assert!(!storage.account_exists("to_delete"));
     }
+
+    #[test]
+    fn test_keyfile_second_factor() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("wallet.key");
+        let keyfile = Keyfile::generate(&path).unwrap();
+        assert!(Keyfile::generate(&path).is_err());
+
+        let mut account =
+            EncryptedAccount::new_with_keyfile("erin".to_string(), "password", Some(&keyfile))
+                .unwrap();
+        assert!(account.requires_keyfile);
+        assert_eq!(account.version, WALLET_FORMAT_VERSION);
+
+        // The stored verification hash no longer yields the encryption key
+        let parsed = PasswordHash::new(&account.password_hash).unwrap();
+        let hash = parsed.hash.unwrap();
+        let leaked = Aes256Gcm::new_from_slice(&hash.as_bytes()[..32]).unwrap();
+        assert!(leaked
+            .decrypt(
+                Nonce::from_slice(&account.nonce),
+                account.encrypted_key.as_ref()
+            )
+            .is_err());
+
+        assert!(account.decrypt_keypair("password").is_err());
+        let other = Keyfile::from_bytes(b"another keyfile").unwrap();
+        assert!(account
+            .decrypt_keypair_with_keyfile("password", Some(&other))
+            .is_err());
+
+        let loaded = Keyfile::load(&path).unwrap();
+        let keypair = account
+            .decrypt_keypair_with_keyfile("password", Some(&loaded))
+            .unwrap();
+        assert_eq!(keypair.public_key(), account.address);
+
+        account
+            .change_password_with_keyfile("password", "new password", Some(&loaded))
+            .unwrap();
+        assert!(account.requires_keyfile);
+        assert!(account
+            .decrypt_keypair_with_keyfile("new password", Some(&loaded))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_failed_unlocks_back_off() {
+        let dir = tempdir().unwrap();
+        let storage = EncryptedWalletStorage::with_path(dir.path().to_path_buf()).unwrap();
+        let account = EncryptedAccount::new("frank".to_string(), "password").unwrap();
+        storage.save_account(&account).unwrap();
+
+        for _ in 0..crate::lock::FREE_UNLOCK_ATTEMPTS {
+            let err = storage.unlock_account(&account, "wrong", None).unwrap_err();
+            assert!(err.to_string().contains("Invalid password"));
+        }
+        assert!(storage.unlock_account(&account, "password", None).is_ok());
+        assert!(!dir.path().join("frank.attempts.json").exists());
+
+        // The counter was reset, so three more failures are free again
+        for _ in 0..=crate::lock::FREE_UNLOCK_ATTEMPTS {
+            storage.unlock_account(&account, "wrong", None).unwrap_err();
+        }
+        let err = storage
+            .unlock_account(&account, "password", None)
+            .unwrap_err();
+        assert!(err.to_string().contains("locked for"));
+
+        // The counter survives reopening the storage
+        let reopened = EncryptedWalletStorage::with_path(dir.path().to_path_buf()).unwrap();
+        assert!(reopened.unlock_account(&account, "password", None).is_err());
+        assert_eq!(reopened.list_accounts().unwrap(), vec!["frank".to_string()]);
+
+        reopened.delete_account("frank").unwrap();
+        assert!(!dir.path().join("frank.attempts.json").exists());
+    }
+
+    /// Account file as written before format versions carried KDF parameters
+    fn format_1_account_json(name: &str, password: &str, keypair: &KeyPair) -> String {
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap();
+        let hash = password_hash.hash.unwrap();
+        let cipher = Aes256Gcm::new_from_slice(&hash.as_bytes()[..32]).unwrap();
+        let mut nonce = [0u8; 12];
+        OsRng.fill_bytes(&mut nonce);
+        let encrypted_key = keypair
+            .with_private_key(|key| cipher.encrypt(Nonce::from_slice(&nonce), key.as_ref()))
+            .unwrap();
+
+        serde_json::json!({
+            "name": name,
+            "address": keypair.public_key(),
+            "encrypted_key": encrypted_key,
+            "nonce": nonce,
+            "password_hash": password_hash.to_string(),
+            "salt": salt.as_str(),
+            "created_at": 0,
+            "version": 1,
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn test_format_1_account_upgraded_at_unlock() {
+        let dir = tempdir().unwrap();
+        let storage = EncryptedWalletStorage::with_path(dir.path().to_path_buf()).unwrap();
+        let keypair = KeyPair::generate();
+        let json = format_1_account_json("grace", "password", &keypair);
+        fs::write(dir.path().join("grace.enc.json"), json).unwrap();
+
+        let account = storage.load_account("grace").unwrap();
+        assert_eq!(account.version, 1);
+        assert_eq!(account.kdf, KdfParams::LEGACY);
+        assert!(account.needs_upgrade(&storage.kdf_params()));
+
+        let unlocked = storage.unlock_account(&account, "password", None).unwrap();
+        assert_eq!(unlocked.public_key(), keypair.public_key());
+        // The backup goes once the upgraded file has been checked
+        assert!(!dir.path().join("grace.enc.json.v1.bak").exists());
+        assert!(!dir.path().join("grace.enc.json.tmp").exists());
+
+        let upgraded = storage.load_account("grace").unwrap();
+        assert_eq!(upgraded.version, WALLET_FORMAT_VERSION);
+        assert_eq!(upgraded.kdf, KdfParams::RECOMMENDED);
+        assert!(upgraded.key_salt.is_some());
+        assert!(!upgraded.needs_upgrade(&storage.kdf_params()));
+        let keypair_again = upgraded.decrypt_keypair("password").unwrap();
+        assert_eq!(keypair_again.public_key(), keypair.public_key());
+
+        // A storage targeting weaker parameters does not downgrade the account
+        let legacy_target = EncryptedWalletStorage::with_path(dir.path().to_path_buf())
+            .unwrap()
+            .with_kdf_params(KdfParams::LEGACY)
+            .unwrap();
+        assert!(!upgraded.needs_upgrade(&legacy_target.kdf_params()));
+    }
 }
@@ -0,0 +1,72 @@
+//! Fee estimation from recent blocks
+//! تقدير الرسوم من الكتل الأخيرة
+//!
+//! The explorer reports per-block fee-rate percentiles (see
+//! `opensyria_core::fees`); this module turns those into a fee for a
+//! transaction of a given weight.
+
+use anyhow::{anyhow, Context, Result};
+use opensyria_core::{
+    crypto::KeyPair, transaction::Transaction, FeeEstimate, FeeRateStats, MIN_TRANSACTION_FEE,
+};
+use serde::Deserialize;
+
+/// Default block explorer endpoint
+pub const DEFAULT_EXPLORER_URL: &str = "http://127.0.0.1:3000";
+
+/// Fee statistics as returned by the explorer's `/api/stats/fees`
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeeStats {
+    pub blocks: Vec<FeeRateStats>,
+    pub estimate: Option<FeeEstimate>,
+}
+
+/// Fetch fee statistics for the last `blocks` blocks from a block explorer
+/// جلب إحصاءات الرسوم من مستكشف الكتل
+pub fn fetch_fee_stats(explorer_url: &str, blocks: u64) -> Result<FeeStats> {
+    let url = format!(
+        "{}/api/stats/fees?blocks={}",
+        explorer_url.trim_end_matches('/'),
+        blocks
+    );
+    let response = reqwest::blocking::get(&url)
+        .with_context(|| format!("Failed to reach explorer at {}", explorer_url))?;
+    if !response.status().is_success() {
+        return Err(anyhow!("Explorer returned {}", response.status()));
+    }
+    response
+        .json()
+        .context("Invalid fee statistics from explorer")
+}
+
+/// Weight of a standard signed transfer
+pub fn transfer_weight() -> usize {
+    let from = KeyPair::generate();
+    let tx = Transaction::new(from.public_key(), KeyPair::generate().public_key(), 1, 1, 0);
+    let signature = from.sign(&tx.signing_hash());
+    tx.with_signature(signature).weight()
+}
+
+/// Fee paying `rate` for a transaction of `weight`, never below the protocol minimum
+/// الرسوم المقترحة لمعاملة بوزن معين
+pub fn suggest_fee(rate: u64, weight: usize) -> u64 {
+    FeeEstimate::fee_for(rate, weight, MIN_TRANSACTION_FEE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_fee_respects_minimum() {
+        let weight = transfer_weight();
+        assert!(weight > 64);
+
+        assert_eq!(suggest_fee(0, weight), MIN_TRANSACTION_FEE);
+        let rate = MIN_TRANSACTION_FEE * 1000;
+        assert_eq!(
+            suggest_fee(rate, weight),
+            MIN_TRANSACTION_FEE * weight as u64
+        );
+    }
+}
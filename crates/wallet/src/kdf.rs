@@ -0,0 +1,121 @@
+//! Argon2 parameters and wallet file format versions
+//! معاملات Argon2 وإصدارات تنسيق ملف المحفظة
+//!
+//! Every account file records its format `version` and the `kdf` parameters
+//! it was encrypted with, so the recommended parameters can be raised over
+//! time without breaking existing wallets. Formats:
+//!
+//! - 1: the encryption key is the Argon2 verification hash itself, with the
+//!   Argon2 crate defaults (files have no `kdf` field)
+//! - 2: keyfile accounts; the encryption key has its own Argon2 pass over
+//!   `key_salt`
+//! - 3: like 2 for every account, with the parameters in `kdf`
+//!
+//! An account in an older format, or with weaker parameters than the
+//! wallet's target, is re-encrypted the next time it is unlocked.
+
+use anyhow::{anyhow, Result};
+use argon2::Params;
+use serde::{Deserialize, Serialize};
+
+/// Format written for new and upgraded accounts
+pub const WALLET_FORMAT_VERSION: u32 = 3;
+
+/// Argon2 cost parameters
+/// معاملات تكلفة Argon2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// Memory in KiB
+    pub memory_kib: u32,
+    /// Passes over memory
+    pub iterations: u32,
+    /// Lanes
+    pub parallelism: u32,
+}
+
+impl KdfParams {
+    /// Parameters of format 1 and 2 accounts (Argon2 crate defaults)
+    pub const LEGACY: Self = Self {
+        memory_kib: 19 * 1024,
+        iterations: 2,
+        parallelism: 1,
+    };
+
+    /// Parameters for new accounts: more memory than [`Self::LEGACY`] at
+    /// about the same unlock time
+    pub const RECOMMENDED: Self = Self {
+        memory_kib: 46 * 1024,
+        iterations: 1,
+        parallelism: 1,
+    };
+
+    /// Parameters of accounts written before the `kdf` field existed
+    pub fn legacy() -> Self {
+        Self::LEGACY
+    }
+
+    /// Relative cost to an attacker: memory times passes
+    pub fn cost(&self) -> u64 {
+        self.memory_kib as u64 * self.iterations as u64
+    }
+
+    /// Check the parameters are accepted by Argon2 and not weaker than
+    /// [`Self::LEGACY`]
+    pub fn validate(&self) -> Result<()> {
+        self.argon2_params()?;
+        if self.cost() < Self::LEGACY.cost() {
+            return Err(anyhow!(
+                "KDF parameters m={} KiB, t={} are weaker than the minimum m={} KiB, t={}",
+                self.memory_kib,
+                self.iterations,
+                Self::LEGACY.memory_kib,
+                Self::LEGACY.iterations
+            ));
+        }
+        Ok(())
+    }
+
+    /// Argon2 parameters with a 32-byte output
+    pub fn argon2_params(&self) -> Result<Params> {
+        Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(Params::DEFAULT_OUTPUT_LEN),
+        )
+        .map_err(|e| anyhow!("Invalid KDF parameters: {}", e))
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::RECOMMENDED
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kdf_params() {
+        let legacy = KdfParams::LEGACY.argon2_params().unwrap();
+        assert_eq!(legacy.m_cost(), Params::DEFAULT_M_COST);
+        assert_eq!(legacy.t_cost(), Params::DEFAULT_T_COST);
+        assert_eq!(legacy.p_cost(), Params::DEFAULT_P_COST);
+        assert!(KdfParams::RECOMMENDED.cost() > KdfParams::LEGACY.cost());
+        assert!(KdfParams::default().validate().is_ok());
+
+        let weak = KdfParams {
+            memory_kib: 8 * 1024,
+            ..KdfParams::LEGACY
+        };
+        assert!(weak.validate().is_err());
+
+        let no_lanes = KdfParams {
+            parallelism: 0,
+            ..KdfParams::RECOMMENDED
+        };
+        assert!(no_lanes.validate().is_err());
+    }
+}
@@ -1,9 +1,24 @@
+pub mod assets;
+pub mod client;
 pub mod encrypted;
+pub mod fees;
+pub mod kdf;
+pub mod lock;
+pub mod memo;
 pub mod mnemonic;
+pub mod selection;
 pub mod storage;
+pub mod stealth;
+pub mod sweep;
+pub mod uri;
 
-pub use encrypted::{EncryptedAccount, EncryptedWalletStorage};
+pub use encrypted::{EncryptedAccount, EncryptedWalletStorage, Keyfile};
+pub use kdf::KdfParams;
+pub use lock::AutoLockedKey;
 pub use mnemonic::{display_mnemonic_warning, HDWallet};
+pub use selection::{Selection, SelectionStrategy, SelectionTarget, Spendable};
 pub use storage::WalletStorage;
+pub use client::NodeClient;
+pub use uri::PaymentRequest;
 
 
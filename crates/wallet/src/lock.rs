@@ -0,0 +1,169 @@
+//! Unlock attempt backoff and automatic key locking
+//! تأخير محاولات فتح القفل والقفل التلقائي للمفاتيح
+//!
+//! Failed unlocks are counted per account in `<name>.attempts.json` next to
+//! the account file. After [`FREE_UNLOCK_ATTEMPTS`] failures every further
+//! attempt must wait twice as long as the last, up to [`MAX_UNLOCK_BACKOFF_SECS`].
+//! A successful unlock clears the counter. Decrypted keys kept in memory are
+//! wrapped in [`AutoLockedKey`], which drops them after an idle timeout.
+
+use anyhow::{Context, Result};
+use opensyria_core::crypto::KeyPair;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// Failed unlocks allowed before backoff starts
+pub const FREE_UNLOCK_ATTEMPTS: u32 = 3;
+
+/// Wait after the first failure past the free attempts (seconds)
+pub const BASE_UNLOCK_BACKOFF_SECS: u64 = 2;
+
+/// Longest wait between unlock attempts (seconds)
+pub const MAX_UNLOCK_BACKOFF_SECS: u64 = 3600;
+
+/// Failed unlock attempts of one account
+/// محاولات فتح القفل الفاشلة لحساب واحد
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnlockAttempts {
+    /// Consecutive failures since the last successful unlock
+    pub failures: u32,
+    /// Unix time of the last failure
+    pub last_failure: u64,
+}
+
+impl UnlockAttempts {
+    /// Read the counter, or start from zero if there is none
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = fs::read_to_string(path).context("Failed to read unlock attempts")?;
+        serde_json::from_str(&json).context("Failed to parse unlock attempts")
+    }
+
+    /// Write the counter
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(path, json).context("Failed to write unlock attempts")
+    }
+
+    /// Seconds to wait after the current number of failures
+    pub fn backoff_secs(&self) -> u64 {
+        let excess = self.failures.saturating_sub(FREE_UNLOCK_ATTEMPTS);
+        if excess == 0 {
+            return 0;
+        }
+        // Shifting drops high bits rather than failing, so double by
+        // multiplication to saturate
+        1u64.checked_shl(excess - 1)
+            .and_then(|factor| BASE_UNLOCK_BACKOFF_SECS.checked_mul(factor))
+            .unwrap_or(u64::MAX)
+            .min(MAX_UNLOCK_BACKOFF_SECS)
+    }
+
+    /// Seconds until the next attempt is allowed at `now`
+    pub fn locked_for(&self, now: u64) -> u64 {
+        self.last_failure
+            .saturating_add(self.backoff_secs())
+            .saturating_sub(now)
+    }
+
+    /// Count a failed attempt made at `now`
+    pub fn record_failure(&mut self, now: u64) {
+        self.failures = self.failures.saturating_add(1);
+        self.last_failure = now;
+    }
+}
+
+/// A decrypted keypair dropped from memory once unused for `timeout`
+/// زوج مفاتيح مفكوك يُحذف من الذاكرة بعد مهلة عدم الاستخدام
+pub struct AutoLockedKey {
+    keypair: Option<KeyPair>,
+    timeout: Duration,
+    last_used: Instant,
+}
+
+impl AutoLockedKey {
+    /// Hold `keypair` until it has been idle for `timeout`
+    pub fn new(keypair: KeyPair, timeout: Duration) -> Self {
+        Self {
+            keypair: Some(keypair),
+            timeout,
+            last_used: Instant::now(),
+        }
+    }
+
+    /// Run `f` with the keypair and restart the idle timer; `None` once locked
+    pub fn with_keypair<T>(&mut self, f: impl FnOnce(&KeyPair) -> T) -> Option<T> {
+        self.lock_if_idle();
+        let keypair = self.keypair.as_ref()?;
+        self.last_used = Instant::now();
+        Some(f(keypair))
+    }
+
+    /// Drop the keypair if it has been idle too long; returns whether it is locked
+    pub fn lock_if_idle(&mut self) -> bool {
+        if self.last_used.elapsed() >= self.timeout {
+            self.keypair = None;
+        }
+        self.keypair.is_none()
+    }
+
+    /// Drop the keypair now
+    pub fn lock(&mut self) {
+        self.keypair = None;
+    }
+
+    /// Whether the keypair has been dropped
+    pub fn is_locked(&self) -> bool {
+        self.keypair.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_exponentially() {
+        let mut attempts = UnlockAttempts::default();
+        for _ in 0..FREE_UNLOCK_ATTEMPTS {
+            attempts.record_failure(1_000);
+            assert_eq!(attempts.locked_for(1_000), 0);
+        }
+
+        attempts.record_failure(1_000);
+        assert_eq!(attempts.backoff_secs(), 2);
+        attempts.record_failure(1_000);
+        assert_eq!(attempts.backoff_secs(), 4);
+        assert_eq!(attempts.locked_for(1_001), 3);
+        assert_eq!(attempts.locked_for(1_004), 0);
+
+        // Past 64 doublings the wait stays at the cap instead of wrapping
+        let excesses = [64, 65, 200, u32::MAX - FREE_UNLOCK_ATTEMPTS];
+        for failures in excesses.map(|excess| FREE_UNLOCK_ATTEMPTS + excess) {
+            attempts.failures = failures;
+            assert_eq!(attempts.backoff_secs(), MAX_UNLOCK_BACKOFF_SECS);
+        }
+        attempts.last_failure = u64::MAX;
+        assert_eq!(attempts.locked_for(1_000), u64::MAX - 1_000);
+    }
+
+    #[test]
+    fn test_auto_locked_key() {
+        let keypair = KeyPair::generate();
+        let address = keypair.public_key();
+
+        let mut key = AutoLockedKey::new(keypair.clone(), Duration::from_secs(60));
+        assert_eq!(key.with_keypair(|k| k.public_key()), Some(address));
+        key.lock();
+        assert!(key.is_locked());
+        assert!(key.with_keypair(|k| k.public_key()).is_none());
+
+        let mut idle = AutoLockedKey::new(keypair, Duration::ZERO);
+        assert!(idle.lock_if_idle());
+        assert!(idle.with_keypair(|k| k.public_key()).is_none());
+    }
+}
@@ -1,14 +1,43 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use colored::*;
-use opensyria_core::transaction::Transaction;
-use opensyria_wallet::{EncryptedWalletStorage, WalletStorage};
+use opensyria_core::{
+    asset::{AssetId, AssetOperation},
+    transaction::Transaction,
+    ConfirmationStatus, SignedMessage, StealthAddress, StealthKeys,
+};
+use opensyria_wallet::{
+    assets, fees,
+    memo::{self, MemoText},
+    stealth, sweep, uri, EncryptedWalletStorage, KdfParams, Keyfile, NodeClient, PaymentRequest,
+    WalletStorage,
+};
 use rpassword::read_password;
 
 #[derive(Parser)]
 #[command(name = "wallet")]
 #[command(about = "Syrian Digital Lira Wallet (OpenSyria) | محفظة الليرة السورية الرقمية (أوبن سيريا)", long_about = None)]
 struct Cli {
+    /// Wallet API URL of the node used for balances, history and submission | عنوان واجهة العقدة
+    #[arg(long, global = true, default_value = opensyria_wallet::client::DEFAULT_NODE_URL)]
+    node_url: String,
+
+    /// Keyfile required as a second factor alongside the password | ملف المفتاح كعامل ثانٍ
+    #[arg(long, global = true)]
+    keyfile: Option<std::path::PathBuf>,
+
+    /// Argon2 memory in KiB for new accounts and upgrades | ذاكرة Argon2 بالكيلوبايت
+    #[arg(long, global = true, default_value_t = KdfParams::RECOMMENDED.memory_kib)]
+    kdf_memory_kib: u32,
+
+    /// Argon2 passes for new accounts and upgrades | عدد تمريرات Argon2
+    #[arg(long, global = true, default_value_t = KdfParams::RECOMMENDED.iterations)]
+    kdf_iterations: u32,
+
+    /// Argon2 lanes for new accounts and upgrades | عدد مسارات Argon2
+    #[arg(long, global = true, default_value_t = KdfParams::RECOMMENDED.parallelism)]
+    kdf_parallelism: u32,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -33,10 +62,26 @@ enum Commands {
         mnemonic: Option<String>,
     },
 
-    /// Display QR code for account address | عرض رمز QR لعنوان الحساب
+    /// Display a payment request QR code for an account | عرض رمز QR لطلب دفع
     Qr {
         /// Account name | اسم الحساب
         name: String,
+
+        /// Requested amount in Lira | المبلغ المطلوب بالليرة
+        #[arg(short, long)]
+        amount: Option<String>,
+
+        /// Memo stored with the payment | ملاحظة مرفقة بالدفعة
+        #[arg(short, long)]
+        memo: Option<String>,
+
+        /// Recipient name shown to the payer | اسم المستلم
+        #[arg(short, long)]
+        label: Option<String>,
+
+        /// Minutes until the request expires | دقائق حتى انتهاء صلاحية الطلب
+        #[arg(long)]
+        expires_in: Option<u64>,
     },
 
     /// Migrate plaintext wallet to encrypted | ترحيل محفظة نصية إلى مشفرة
@@ -54,19 +99,75 @@ enum Commands {
         name: String,
     },
 
+    /// Show confirmed and pending balance from the node | عرض الرصيد المؤكد والمعلق
+    Balance {
+        /// Account name | اسم الحساب
+        name: String,
+    },
+
+    /// Show recent transactions from the node | عرض المعاملات الأخيرة
+    History {
+        /// Account name | اسم الحساب
+        name: String,
+
+        /// Maximum number of transactions | الحد الأقصى لعدد المعاملات
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Show native asset balances from the node | عرض أرصدة الأصول
+    Assets {
+        /// Account name | اسم الحساب
+        name: String,
+    },
+
+    /// Create, mint, transfer or burn a native asset | إدارة الأصول الأصلية
+    Asset {
+        /// Sender account name | اسم حساب المرسل
+        #[arg(short, long)]
+        from: String,
+
+        /// Transaction fee | رسوم المعاملة
+        #[arg(short = 'f', long, default_value = "0.0001")]
+        fee: f64,
+
+        /// Wallet API URL of the node (overrides --node-url) | عنوان واجهة العقدة
+        #[arg(long)]
+        node: Option<String>,
+
+        /// API key for transaction submission | مفتاح API لإرسال المعاملة
+        #[arg(long)]
+        api_key: Option<String>,
+
+        #[command(subcommand)]
+        action: AssetAction,
+    },
+
     /// Create and sign a transaction | إنشاء معاملة جديدة
     Send {
         /// Sender account name | اسم حساب المرسل
         #[arg(short, long)]
         from: String,
 
-        /// Recipient address (hex) | عنوان المستلم
-        #[arg(short, long)]
-        to: String,
+        /// Recipient address (hex) or stealth address | عنوان المستلم أو العنوان الخفي
+        #[arg(short, long, required_unless_present = "uri", conflicts_with = "uri")]
+        to: Option<String>,
 
         /// Amount in Lira | المبلغ بالليرة
         #[arg(short, long)]
-        amount: f64,
+        amount: Option<f64>,
+
+        /// Payment request URI (opensyria:...) | رابط طلب الدفع
+        #[arg(long)]
+        uri: Option<String>,
+
+        /// Memo stored with the payment, encrypted for stealth addresses | ملاحظة مرفقة بالدفعة
+        #[arg(short, long, conflicts_with = "uri")]
+        memo: Option<String>,
+
+        /// Encrypt the memo so only the recipient can read it | تشفير الملاحظة للمستلم
+        #[arg(long)]
+        encrypt_memo: bool,
 
         /// Transaction fee | رسوم المعاملة
         #[arg(short = 'f', long, default_value = "0.0001")]
@@ -77,6 +178,111 @@ enum Commands {
         nonce: u64,
     },
 
+    /// Receive payments privately with a stealth address | الدفعات الخفية
+    Stealth {
+        #[command(subcommand)]
+        action: StealthAction,
+    },
+
+    /// Sweep the full balance of a private key to an address | كنس رصيد مفتاح خاص إلى عنوان
+    Sweep {
+        /// Private key as hex, or path to a file containing it | المفتاح الخاص أو مسار ملفه
+        #[arg(long)]
+        private_key: String,
+
+        /// Destination address (hex) | عنوان الوجهة
+        #[arg(short, long)]
+        to: String,
+
+        /// Transaction fee | رسوم المعاملة
+        #[arg(short = 'f', long, default_value = "0.0001")]
+        fee: f64,
+
+        /// Wallet API URL of the node (overrides --node-url) | عنوان واجهة العقدة
+        #[arg(long)]
+        node: Option<String>,
+
+        /// API key for transaction submission | مفتاح API لإرسال المعاملة
+        #[arg(long)]
+        api_key: Option<String>,
+    },
+
+    /// Merge small balances from several accounts into one | دمج الأرصدة الصغيرة في حساب واحد
+    Consolidate {
+        /// Destination account name or address (hex) | حساب أو عنوان الوجهة
+        #[arg(short, long)]
+        to: String,
+
+        /// Source account names (defaults to all other accounts) | حسابات المصدر
+        #[arg(long, value_delimiter = ',')]
+        from: Vec<String>,
+
+        /// Fee per consolidation transaction | رسوم كل معاملة دمج
+        #[arg(short = 'f', long, default_value = "0.0001")]
+        fee: f64,
+
+        /// Skip accounts where the fee exceeds this fraction of the balance | الحد الأقصى لنسبة الرسوم
+        #[arg(long, default_value = "0.05")]
+        max_fee_ratio: f64,
+
+        /// Wallet API URL of the node (overrides --node-url) | عنوان واجهة العقدة
+        #[arg(long)]
+        node: Option<String>,
+
+        /// API key for transaction submission | مفتاح API لإرسال المعاملة
+        #[arg(long)]
+        api_key: Option<String>,
+    },
+
+    /// Suggest fees from recent blocks | اقتراح الرسوم من الكتل الأخيرة
+    EstimateFee {
+        /// Number of recent blocks to consider | عدد الكتل الأخيرة
+        #[arg(short, long, default_value = "10")]
+        blocks: u64,
+
+        /// Block explorer URL | عنوان مستكشف الكتل
+        #[arg(long, default_value = fees::DEFAULT_EXPLORER_URL)]
+        explorer: String,
+    },
+
+    /// Sign a message proving control of an account's address | توقيع رسالة لإثبات ملكية العنوان
+    SignMessage {
+        /// Account name | اسم الحساب
+        name: String,
+
+        /// Message text to sign | نص الرسالة
+        message: String,
+
+        /// Write the signed message JSON to a file | حفظ الرسالة الموقعة في ملف
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+
+    /// Verify a signed message | التحقق من رسالة موقعة
+    VerifyMessage {
+        /// Signed message JSON file | ملف الرسالة الموقعة
+        #[arg(required_unless_present_all = ["address", "message", "signature"])]
+        file: Option<std::path::PathBuf>,
+
+        /// Signing address (hex) | عنوان الموقّع
+        #[arg(long, conflicts_with = "file")]
+        address: Option<String>,
+
+        /// Message text | نص الرسالة
+        #[arg(long, conflicts_with = "file")]
+        message: Option<String>,
+
+        /// Signature (hex) | التوقيع
+        #[arg(long, conflicts_with = "file")]
+        signature: Option<String>,
+    },
+
+    /// Generate a random keyfile for use with --keyfile | إنشاء ملف مفتاح عشوائي
+    Keyfile {
+        /// Where to write the keyfile | مسار ملف المفتاح
+        path: std::path::PathBuf,
+    },
+
     /// Delete an account | حذف حساب
     Delete {
         /// Account name | اسم الحساب
@@ -84,9 +290,139 @@ enum Commands {
     },
 }
 
+#[derive(Subcommand)]
+enum AssetAction {
+    /// Register a new asset with this account as issuer | إنشاء أصل جديد
+    Create {
+        /// Ticker symbol, uppercase letters and digits | رمز الأصل
+        #[arg(short, long)]
+        symbol: String,
+
+        /// Display name | اسم الأصل
+        #[arg(short, long)]
+        name: String,
+
+        /// Decimal places used for display | المنازل العشرية
+        #[arg(short, long, default_value = "0")]
+        decimals: u8,
+
+        /// Supply cap in base units | الحد الأقصى للمعروض
+        #[arg(short, long)]
+        max_supply: u64,
+    },
+
+    /// Issue new units to an address (issuer only) | إصدار وحدات جديدة
+    Mint {
+        /// Asset ID (hex) | معرّف الأصل
+        #[arg(long)]
+        asset: String,
+
+        /// Recipient address (hex) | عنوان المستلم
+        #[arg(short, long)]
+        to: String,
+
+        /// Amount in base units | المبلغ بالوحدات الأساسية
+        #[arg(short, long)]
+        amount: u64,
+    },
+
+    /// Send units of an asset | تحويل وحدات أصل
+    Transfer {
+        /// Asset ID (hex) | معرّف الأصل
+        #[arg(long)]
+        asset: String,
+
+        /// Recipient address (hex) | عنوان المستلم
+        #[arg(short, long)]
+        to: String,
+
+        /// Amount in base units | المبلغ بالوحدات الأساسية
+        #[arg(short, long)]
+        amount: u64,
+    },
+
+    /// Destroy units of an asset | إتلاف وحدات أصل
+    Burn {
+        /// Asset ID (hex) | معرّف الأصل
+        #[arg(long)]
+        asset: String,
+
+        /// Amount in base units | المبلغ بالوحدات الأساسية
+        #[arg(short, long)]
+        amount: u64,
+    },
+
+    /// Hand the issuer role to another address, or renounce it | نقل صلاحية الإصدار
+    SetIssuer {
+        /// Asset ID (hex) | معرّف الأصل
+        #[arg(long)]
+        asset: String,
+
+        /// New issuer address (hex) | عنوان المُصدر الجديد
+        #[arg(long, required_unless_present = "renounce")]
+        issuer: Option<String>,
+
+        /// Give up the issuer role so no more units can be minted | التخلي عن صلاحية الإصدار
+        #[arg(long, conflicts_with = "issuer")]
+        renounce: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum StealthAction {
+    /// Show the account's stealth address | عرض العنوان الخفي للحساب
+    Address {
+        /// Account name | اسم الحساب
+        name: String,
+    },
+
+    /// Find payments to the account's stealth address | البحث عن الدفعات الخفية
+    Scan {
+        /// Account name | اسم الحساب
+        name: String,
+
+        /// Block height to start scanning from | ارتفاع الكتلة لبدء المسح
+        #[arg(long, default_value = "0")]
+        from_height: u64,
+    },
+
+    /// Move received stealth payments to an address | نقل الدفعات الخفية إلى عنوان
+    Sweep {
+        /// Account name | اسم الحساب
+        name: String,
+
+        /// Destination address (hex), the account's own address by default | عنوان الوجهة
+        #[arg(short, long)]
+        to: Option<String>,
+
+        /// Fee per sweep transaction | رسوم كل معاملة
+        #[arg(short = 'f', long, default_value = "0.0001")]
+        fee: f64,
+
+        /// Block height to start scanning from | ارتفاع الكتلة لبدء المسح
+        #[arg(long, default_value = "0")]
+        from_height: u64,
+
+        /// API key for transaction submission | مفتاح API لإرسال المعاملة
+        #[arg(long)]
+        api_key: Option<String>,
+    },
+}
+
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    let encrypted_storage = EncryptedWalletStorage::new()?;
+    let kdf = KdfParams {
+        memory_kib: cli.kdf_memory_kib,
+        iterations: cli.kdf_iterations,
+        parallelism: cli.kdf_parallelism,
+    };
+    let encrypted_storage = EncryptedWalletStorage::new()?.with_kdf_params(kdf)?;
+    let keyfile = match &cli.keyfile {
+        Some(path) if !matches!(cli.command, Commands::Keyfile { .. }) => {
+            Some(Keyfile::load(path)?)
+        }
+        _ => None,
+    };
 
     match cli.command {
         Commands::Create { name } => {
@@ -106,7 +442,12 @@ fn main() -> Result<()> {
                 return Ok(());
             }
 
-            let account = opensyria_wallet::encrypted::EncryptedAccount::new(name.clone(), &password)?;
+            let account = opensyria_wallet::encrypted::EncryptedAccount::new_with_params(
+                name.clone(),
+                &password,
+                keyfile.as_ref(),
+                kdf,
+            )?;
             encrypted_storage.save_account(&account)?;
 
             println!(
@@ -156,11 +497,14 @@ fn main() -> Result<()> {
             let private_key = keypair.private_key_bytes();
             
             // Create encrypted account from HD wallet
-            let account = opensyria_wallet::encrypted::EncryptedAccount::from_private_key(
-                name.clone(),
-                &private_key,
-                &password
-            )?;
+            let account =
+                opensyria_wallet::encrypted::EncryptedAccount::from_private_key_with_params(
+                    name.clone(),
+                    &private_key,
+                    &password,
+                    keyfile.as_ref(),
+                    kdf,
+                )?;
             encrypted_storage.save_account(&account)?;
 
             println!(
@@ -176,25 +520,36 @@ fn main() -> Result<()> {
             );
         }
 
-        Commands::Qr { name } => {
+        Commands::Qr {
+            name,
+            amount,
+            memo,
+            label,
+            expires_in,
+        } => {
             let account = encrypted_storage.load_account(&name)?;
             let address = account.address.to_hex();
-            
+
+            let mut request = PaymentRequest::new(account.address);
+            request.amount = amount.as_deref().map(uri::parse_syl_amount).transpose()?;
+            request.memo = memo;
+            request.label = label;
+            request.expires = expires_in
+                .map(|minutes| chrono::Utc::now().timestamp() as u64 + minutes.saturating_mul(60));
+            request.validate()?;
+            let payment_uri = request.to_uri();
+
             println!();
             println!("{}", format!("QR Code for {} | رمز QR لـ {}", name, name).cyan().bold());
             println!("{}", "─".repeat(50).dimmed());
             println!();
-            
-            match qr2term::print_qr(&address) {
-                Ok(_) => {
-                    println!();
-                    println!("{}: {}", "Address | العنوان".cyan(), address);
-                }
-                Err(e) => {
-                    println!("{}", format!("✗ Failed to generate QR code: {}", e).red());
-                    println!("{}: {}", "Address | العنوان".cyan(), address);
-                }
+
+            if let Err(e) = qr2term::print_qr(&payment_uri) {
+                println!("{}", format!("✗ Failed to generate QR code: {}", e).red());
             }
+            println!();
+            println!("{}: {}", "Address | العنوان".cyan(), address);
+            println!("{}: {}", "Payment URI | رابط الدفع".cyan(), payment_uri);
         }
 
         Commands::Migrate { name } => {
@@ -216,12 +571,15 @@ fn main() -> Result<()> {
             
             // Create encrypted account from plaintext
             let private_key = old_account.keypair()?.private_key_bytes();
-            let encrypted_account = opensyria_wallet::encrypted::EncryptedAccount::from_private_key(
-                name.clone(),
-                &private_key,
-                &password
-            )?;
-            
+            let encrypted_account =
+                opensyria_wallet::encrypted::EncryptedAccount::from_private_key_with_params(
+                    name.clone(),
+                    &private_key,
+                    &password,
+                    keyfile.as_ref(),
+                    kdf,
+                )?;
+
             encrypted_storage.save_account(&encrypted_account)?;
             plaintext_storage.delete_account(&name)?;
             
@@ -271,31 +629,326 @@ fn main() -> Result<()> {
                 account.address.to_hex()
             );
             println!("{}: {}", "Created | تاريخ الإنشاء".cyan(), created);
+            println!(
+                "{}: v{} (Argon2 m={} KiB, t={}, p={})",
+                "Format | التنسيق".cyan(),
+                account.version,
+                account.kdf.memory_kib,
+                account.kdf.iterations,
+                account.kdf.parallelism
+            );
+            if account.needs_upgrade(&kdf) {
+                println!(
+                    "{}",
+                    "Will be re-encrypted at next unlock | سيعاد تشفيره عند فتح القفل التالي"
+                        .yellow()
+                );
+            }
             println!();
-            println!("Balance | الرصيد: {} (coming soon)", "0.00 SYL".bold());
+
+            let client = NodeClient::new(&cli.node_url, None);
+            match client.get_account(&account.address) {
+                Ok(state) => println!(
+                    "{}: {}",
+                    "Balance | الرصيد".cyan(),
+                    format!("{} SYL", state.balance as f64 / 1_000_000.0).bold()
+                ),
+                Err(e) => println!(
+                    "{}: {}",
+                    "Balance | الرصيد".cyan(),
+                    format!("unavailable ({})", e).dimmed()
+                ),
+            }
+        }
+
+        Commands::Balance { name } => {
+            let account = encrypted_storage.load_account(&name)?;
+            let client = NodeClient::new(&cli.node_url, None);
+            let state = client.get_account(&account.address)?;
+
+            println!("{}", "Account Balance | رصيد الحساب".cyan().bold());
+            println!("{}", "─".repeat(50).dimmed());
+            println!();
+            println!("{}: {}", "Name | الاسم".cyan(), name);
+            println!(
+                "{}: {}",
+                "Confirmed | المؤكد".cyan(),
+                format!("{} SYL", state.balance as f64 / 1_000_000.0).bold()
+            );
+            if state.pending_incoming > 0 {
+                println!(
+                    "{}: +{} SYL",
+                    "Pending incoming | وارد معلق".cyan(),
+                    state.pending_incoming as f64 / 1_000_000.0
+                );
+            }
+            if state.pending_outgoing > 0 {
+                println!(
+                    "{}: -{} SYL",
+                    "Pending outgoing | صادر معلق".cyan(),
+                    state.pending_outgoing as f64 / 1_000_000.0
+                );
+            }
+            println!(
+                "{}: {} SYL",
+                "Spendable | القابل للإنفاق".cyan(),
+                state.spendable() as f64 / 1_000_000.0
+            );
+            println!("{}: {}", "Nonce | الرقم".cyan(), state.nonce);
+        }
+
+        Commands::History { name, limit } => {
+            let account = encrypted_storage.load_account(&name)?;
+            let address = account.address.to_hex();
+            let client = NodeClient::new(&cli.node_url, None);
+            let history = client.get_history(&account.address, limit)?;
+
+            if history.is_empty() {
+                println!("{}", "No transactions found | لا توجد معاملات".yellow());
+                return Ok(());
+            }
+
+            // Memos encrypted to us are decrypted, which needs the key
+            let data: Vec<Option<Vec<u8>>> = history
+                .iter()
+                .map(|entry| entry.data.as_deref().and_then(|d| hex::decode(d).ok()))
+                .collect();
+            let has_encrypted_memo = history.iter().zip(&data).any(|(entry, data)| {
+                entry.to == address
+                    && data
+                        .as_deref()
+                        .is_some_and(opensyria_core::EncryptedMemo::is_encrypted_memo)
+            });
+            let keypair = if has_encrypted_memo {
+                println!(
+                    "{}",
+                    "Enter password to read memos | أدخل كلمة المرور لقراءة الملاحظات: ".cyan()
+                );
+                Some(encrypted_storage.unlock_account(
+                    &account,
+                    &read_password()?,
+                    keyfile.as_ref(),
+                )?)
+            } else {
+                None
+            };
+
+            println!("{}", "Transaction History | سجل المعاملات".cyan().bold());
+            println!("{}", "─".repeat(50).dimmed());
+
+            for (entry, data) in history.into_iter().zip(data) {
+                let (direction, counterparty) = if entry.from == address {
+                    ("→".red(), &entry.to)
+                } else {
+                    ("←".green(), &entry.from)
+                };
+                let status = match (entry.block_height, entry.status) {
+                    (Some(height), Some(status)) if status.is_final() => {
+                        format!("#{} {}", height, status).green()
+                    }
+                    (Some(height), Some(status @ ConfirmationStatus::Confirming { .. })) => {
+                        format!("#{} {}", height, status).yellow()
+                    }
+                    (Some(height), _) => format!("#{}", height).normal(),
+                    (None, _) => "pending | معلق".yellow(),
+                };
+                println!(
+                    "{} {} SYL {}... [{}] {}",
+                    direction,
+                    entry.amount as f64 / 1_000_000.0,
+                    &counterparty[..16.min(counterparty.len())],
+                    status,
+                    format!("{}...", &entry.hash[..16.min(entry.hash.len())]).dimmed()
+                );
+                match data.and_then(|data| memo::read_memo(&data, keypair.as_ref())) {
+                    Some(MemoText::Plain(text)) => {
+                        println!("    {}: {}", "Memo | الملاحظة".dimmed(), text)
+                    }
+                    Some(MemoText::Decrypted(text)) => {
+                        println!("    {}: 🔒 {}", "Memo | الملاحظة".dimmed(), text)
+                    }
+                    Some(MemoText::Encrypted) => {
+                        println!("    {}", "🔒 Encrypted memo | ملاحظة مشفرة".dimmed())
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        Commands::Assets { name } => {
+            let account = encrypted_storage.load_account(&name)?;
+            let client = NodeClient::new(&cli.node_url, None);
+            let balances = client.get_assets(&account.address)?;
+
+            println!("{}", "Asset Balances | أرصدة الأصول".cyan().bold());
+            println!("{}", "─".repeat(50).dimmed());
+            println!();
+            if balances.is_empty() {
+                println!("{}", "No assets | لا توجد أصول".yellow());
+            }
+            for asset in balances {
+                println!(
+                    "{} {} ({})",
+                    assets::format_asset_amount(asset.balance, asset.decimals).bold(),
+                    asset.symbol.green(),
+                    asset.name
+                );
+                println!("  {}: {}", "ID | المعرّف".dimmed(), asset.asset_id.dimmed());
+            }
+        }
+
+        Commands::Asset {
+            from,
+            fee,
+            node,
+            api_key,
+            action,
+        } => {
+            let account = encrypted_storage.load_account(&from)?;
+            let client = NodeClient::new(node.as_deref().unwrap_or(&cli.node_url), api_key);
+            let nonce = client.get_account(&account.address)?.nonce;
+
+            let operation = match action {
+                AssetAction::Create {
+                    symbol,
+                    name,
+                    decimals,
+                    max_supply,
+                } => AssetOperation::Create {
+                    symbol,
+                    name,
+                    decimals,
+                    max_supply,
+                },
+                AssetAction::Mint { asset, to, amount } => AssetOperation::Mint {
+                    asset: AssetId::from_hex(&asset)?,
+                    to: opensyria_core::crypto::PublicKey::from_hex(&to)?,
+                    amount,
+                },
+                AssetAction::Transfer { asset, to, amount } => AssetOperation::Transfer {
+                    asset: AssetId::from_hex(&asset)?,
+                    to: opensyria_core::crypto::PublicKey::from_hex(&to)?,
+                    amount,
+                },
+                AssetAction::Burn { asset, amount } => AssetOperation::Burn {
+                    asset: AssetId::from_hex(&asset)?,
+                    amount,
+                },
+                AssetAction::SetIssuer { asset, issuer, .. } => AssetOperation::SetIssuer {
+                    asset: AssetId::from_hex(&asset)?,
+                    issuer: issuer
+                        .map(|issuer| opensyria_core::crypto::PublicKey::from_hex(&issuer))
+                        .transpose()?,
+                },
+            };
+
+            println!("{}", "Enter password | أدخل كلمة المرور: ".cyan());
+            let password = read_password()?;
+            let keypair =
+                encrypted_storage.unlock_account(&account, &password, keyfile.as_ref())?;
+
+            let fee_units = (fee * 1_000_000.0) as u64;
+            let tx = assets::build_asset_transaction(&keypair, &operation, nonce, fee_units)?;
+            let tx_hash = client.submit_raw_transaction(&tx)?;
+
+            println!(
+                "{}",
+                "✓ Asset operation submitted | تم إرسال عملية الأصل".green()
+            );
+            println!();
+            if let AssetOperation::Create { symbol, .. } = &operation {
+                println!(
+                    "{}: {} ({})",
+                    "Asset | الأصل".cyan(),
+                    AssetId::derive(&account.address, nonce).to_hex(),
+                    symbol
+                );
+            }
+            println!("{}: {} SYL", "Fee | الرسوم".cyan(), fee);
+            println!("{}: {}", "Transaction | المعاملة".cyan(), tx_hash);
         }
 
         Commands::Send {
             from,
             to,
             amount,
+            uri: payment_uri,
+            memo,
+            encrypt_memo,
             fee,
             nonce,
         } => {
             let account = encrypted_storage.load_account(&from)?;
-            
-            println!("{}", "Enter password | أدخل كلمة المرور: ".cyan());
-            let password = read_password()?;
-            
-            let keypair = account.decrypt_keypair(&password)?;
-            let recipient = opensyria_core::crypto::PublicKey::from_hex(&to)?;
+
+            // Pre-fill recipient, amount and memo from a payment request. A
+            // stealth address is paid at a fresh one-time address instead
+            let (mut request, stealth_payment) = match (payment_uri, to) {
+                (Some(payment_uri), _) => {
+                    let request = PaymentRequest::parse(&payment_uri)?;
+                    if request.is_expired(chrono::Utc::now().timestamp() as u64) {
+                        anyhow::bail!("Payment request has expired | انتهت صلاحية طلب الدفع");
+                    }
+                    (request, None)
+                }
+                (None, Some(to)) if StealthAddress::is_stealth_address(&to) => {
+                    let payment = StealthAddress::parse(&to)?.pay()?;
+                    (PaymentRequest::new(payment.address), Some(payment))
+                }
+                (None, Some(to)) => (
+                    PaymentRequest::new(opensyria_core::crypto::PublicKey::from_hex(&to)?),
+                    None,
+                ),
+                (None, None) => unreachable!("clap requires --to or --uri"),
+            };
+            if memo.is_some() {
+                request.memo = memo;
+                request.validate()?;
+            }
 
             // Convert Lira to smallest unit (1 Lira = 1_000_000 units)
-            let amount_units = (amount * 1_000_000.0) as u64;
+            let amount_units = match (request.amount, amount) {
+                (Some(requested), None) => requested,
+                (requested, Some(amount)) => {
+                    let units = (amount * 1_000_000.0) as u64;
+                    if requested.is_some_and(|requested| requested != units) {
+                        anyhow::bail!(
+                            "--amount differs from the payment request | المبلغ يختلف عن طلب الدفع"
+                        );
+                    }
+                    units
+                }
+                (None, None) => anyhow::bail!(
+                    "--amount is required when the payment request has none | المبلغ مطلوب"
+                ),
+            };
             let fee_units = (fee * 1_000_000.0) as u64;
 
+            if let Some(label) = &request.label {
+                println!("{}: {}", "Paying | الدفع إلى".cyan(), label);
+            }
+            println!("{}", "Enter password | أدخل كلمة المرور: ".cyan());
+            let password = read_password()?;
+
+            let keypair =
+                encrypted_storage.unlock_account(&account, &password, keyfile.as_ref())?;
+            let recipient = request.address;
+            let to = recipient.to_hex();
+
             let mut tx =
                 Transaction::new(account.address, recipient, amount_units, fee_units, nonce);
+            match &stealth_payment {
+                Some(payment) => tx = tx.with_data(payment.data(request.memo_data().as_deref())?),
+                None => {
+                    let memo = if encrypt_memo {
+                        request.encrypted_memo_data()?
+                    } else {
+                        request.memo_data()
+                    };
+                    if let Some(memo) = memo {
+                        tx = tx.with_data(memo);
+                    }
+                }
+            }
 
             let sig_hash = tx.signing_hash();
             tx = tx.with_signature(keypair.sign(&sig_hash));
@@ -315,12 +968,394 @@ fn main() -> Result<()> {
             println!();
             println!("{}: {}", "From | من".cyan(), from);
             println!("{}: {}...", "To | إلى".cyan(), &to[..16]);
-            println!("{}: {} SYL", "Amount | المبلغ".cyan(), amount);
+            if stealth_payment.is_some() {
+                println!(
+                    "{}",
+                    "🕶 One-time stealth address | عنوان خفي لمرة واحدة".dimmed()
+                );
+            }
+            println!(
+                "{}: {} SYL",
+                "Amount | المبلغ".cyan(),
+                uri::format_syl_amount(amount_units)
+            );
             println!("{}: {} SYL", "Fee | الرسوم".cyan(), fee);
             println!("{}: {}", "Nonce | الرقم".cyan(), nonce);
+            if let Some(memo) = &request.memo {
+                println!("{}: {}", "Memo | الملاحظة".cyan(), memo);
+                if encrypt_memo || stealth_payment.is_some() {
+                    println!(
+                        "{}",
+                        "🔒 Encrypted for the recipient | مشفرة للمستلم".dimmed()
+                    );
+                }
+            }
             println!();
             println!("{}", "Signed Transaction (JSON):".dimmed());
             println!("{}", tx_json.dimmed());
+            println!();
+            println!("{}", "Raw Transaction (hex):".dimmed());
+            println!("{}", tx.to_raw_hex());
+        }
+
+        Commands::Stealth { action } => {
+            let name = match &action {
+                StealthAction::Address { name }
+                | StealthAction::Scan { name, .. }
+                | StealthAction::Sweep { name, .. } => name.clone(),
+            };
+            let account = encrypted_storage.load_account(&name)?;
+            println!("{}", "Enter password | أدخل كلمة المرور: ".cyan());
+            let password = read_password()?;
+            let keys = StealthKeys::from_account(&encrypted_storage.unlock_account(
+                &account,
+                &password,
+                keyfile.as_ref(),
+            )?);
+
+            match action {
+                StealthAction::Address { .. } => {
+                    println!(
+                        "{}",
+                        format!("Stealth address of {} | العنوان الخفي لـ {}", name, name)
+                            .cyan()
+                            .bold()
+                    );
+                    println!("{}", "─".repeat(50).dimmed());
+                    println!();
+                    println!("{}", keys.address());
+                    println!();
+                    println!(
+                        "{}",
+                        "Each payment arrives at a new one-time address | كل دفعة تصل إلى عنوان جديد"
+                            .dimmed()
+                    );
+                }
+
+                StealthAction::Scan { from_height, .. } => {
+                    let client = NodeClient::new(&cli.node_url, None);
+                    let scan = stealth::scan(&client, keys.scan_key(), from_height)?;
+
+                    println!("{}", "Stealth Payments | الدفعات الخفية".cyan().bold());
+                    println!("{}", "─".repeat(50).dimmed());
+                    if scan.payments.is_empty() {
+                        println!("{}", "No payments found | لا توجد دفعات".yellow());
+                    }
+                    for payment in &scan.payments {
+                        let status = match payment.block_height {
+                            Some(height) => format!("#{}", height).normal(),
+                            None => "pending | معلق".yellow(),
+                        };
+                        println!(
+                            "{} {} SYL {}... [{}]",
+                            "←".green(),
+                            uri::format_syl_amount(payment.amount),
+                            &payment.output.address.to_hex()[..16],
+                            status
+                        );
+                        if let Some(memo) = payment.memo() {
+                            println!("  {}: {}", "Memo | الملاحظة".dimmed(), memo);
+                        }
+                    }
+                    println!();
+                    println!(
+                        "{}: {}",
+                        "Scanned through height | تم المسح حتى الارتفاع".cyan(),
+                        scan.next_height.saturating_sub(1)
+                    );
+                }
+
+                StealthAction::Sweep {
+                    to,
+                    fee,
+                    from_height,
+                    api_key,
+                    ..
+                } => {
+                    let destination = match to {
+                        Some(to) => opensyria_core::crypto::PublicKey::from_hex(&to)?,
+                        None => account.address,
+                    };
+                    let fee_units = (fee * 1_000_000.0) as u64;
+                    let client = NodeClient::new(&cli.node_url, api_key);
+                    let scan = stealth::scan(&client, keys.scan_key(), from_height)?;
+
+                    let mut swept = 0u64;
+                    for payment in scan.payments {
+                        if payment.block_height.is_none() {
+                            continue;
+                        }
+                        let state = client.get_account(&payment.output.address)?;
+                        if state.balance <= fee_units {
+                            continue;
+                        }
+
+                        let spend_key = keys.spend_key(&payment.output);
+                        let tx = stealth::build_stealth_sweep(
+                            &spend_key,
+                            destination,
+                            state.balance,
+                            state.nonce,
+                            fee_units,
+                        )?;
+                        let tx_hash = client.submit_transaction(&tx)?;
+                        swept += tx.amount;
+
+                        println!(
+                            "{} {}... → {} SYL ({})",
+                            "✓".green(),
+                            &payment.output.address.to_hex()[..16],
+                            uri::format_syl_amount(tx.amount),
+                            &tx_hash[..16.min(tx_hash.len())]
+                        );
+                    }
+
+                    println!();
+                    println!(
+                        "{}: {} SYL",
+                        "Total swept | إجمالي النقل".cyan(),
+                        uri::format_syl_amount(swept)
+                    );
+                }
+            }
+        }
+
+        Commands::Sweep {
+            private_key,
+            to,
+            fee,
+            node,
+            api_key,
+        } => {
+            let keypair = sweep::parse_private_key(&private_key)?;
+            let source = keypair.public_key();
+            let recipient = opensyria_core::crypto::PublicKey::from_hex(&to)?;
+            let fee_units = (fee * 1_000_000.0) as u64;
+
+            println!("{}: {}", "Source | المصدر".cyan(), source.to_hex());
+
+            let client = NodeClient::new(node.as_deref().unwrap_or(&cli.node_url), api_key);
+            let account = client.get_account(&source)?;
+            println!(
+                "{}: {} SYL (nonce {})",
+                "Balance | الرصيد".cyan(),
+                account.balance as f64 / 1_000_000.0,
+                account.nonce
+            );
+
+            let tx = sweep::build_sweep_transaction(
+                &keypair,
+                recipient,
+                account.balance,
+                account.nonce,
+                fee_units,
+            )?;
+            let tx_hash = client.submit_transaction(&tx)?;
+
+            println!("{}", "✓ Sweep submitted | تم إرسال الكنس".green());
+            println!();
+            println!("{}: {}...", "To | إلى".cyan(), &to[..16.min(to.len())]);
+            println!(
+                "{}: {} SYL",
+                "Amount | المبلغ".cyan(),
+                tx.amount as f64 / 1_000_000.0
+            );
+            println!("{}: {} SYL", "Fee | الرسوم".cyan(), fee);
+            println!("{}: {}", "Transaction | المعاملة".cyan(), tx_hash);
+        }
+
+        Commands::Consolidate {
+            to,
+            from,
+            fee,
+            max_fee_ratio,
+            node,
+            api_key,
+        } => {
+            let (destination, destination_name) =
+                match opensyria_core::crypto::PublicKey::from_hex(&to) {
+                    Ok(address) => (address, None),
+                    Err(_) => (
+                        encrypted_storage.load_account(&to)?.address,
+                        Some(to.clone()),
+                    ),
+                };
+            let sources = if from.is_empty() {
+                encrypted_storage
+                    .list_accounts()?
+                    .into_iter()
+                    .filter(|name| Some(name) != destination_name.as_ref())
+                    .collect()
+            } else {
+                from
+            };
+            let fee_units = (fee * 1_000_000.0) as u64;
+            let client = NodeClient::new(node.as_deref().unwrap_or(&cli.node_url), api_key);
+
+            println!(
+                "{}: {}...",
+                "Consolidating into | الدمج إلى".cyan(),
+                &destination.to_hex()[..16]
+            );
+            println!();
+
+            let mut merged = 0u64;
+            for name in sources {
+                let account = encrypted_storage.load_account(&name)?;
+                if account.address == destination {
+                    continue;
+                }
+
+                let state = client.get_account(&account.address)?;
+                if !sweep::consolidation_is_favorable(state.balance, fee_units, max_fee_ratio) {
+                    println!(
+                        "{} {} ({} SYL)",
+                        "↷ Skipping | تخطي".dimmed(),
+                        name,
+                        state.balance as f64 / 1_000_000.0
+                    );
+                    continue;
+                }
+
+                println!(
+                    "{} '{}': ",
+                    "Enter password for | أدخل كلمة المرور لـ".cyan(),
+                    name
+                );
+                let password = read_password()?;
+                let keypair =
+                    encrypted_storage.unlock_account(&account, &password, keyfile.as_ref())?;
+
+                let tx = sweep::build_sweep_transaction(
+                    &keypair,
+                    destination,
+                    state.balance,
+                    state.nonce,
+                    fee_units,
+                )?;
+                let tx_hash = client.submit_transaction(&tx)?;
+                merged += tx.amount;
+
+                println!(
+                    "{} {} → {} SYL ({})",
+                    "✓".green(),
+                    name,
+                    tx.amount as f64 / 1_000_000.0,
+                    &tx_hash[..16.min(tx_hash.len())]
+                );
+            }
+
+            println!();
+            println!(
+                "{}: {} SYL",
+                "Total consolidated | إجمالي الدمج".cyan(),
+                merged as f64 / 1_000_000.0
+            );
+        }
+
+        Commands::EstimateFee { blocks, explorer } => {
+            let stats = fees::fetch_fee_stats(&explorer, blocks)?;
+            let Some(estimate) = stats.estimate else {
+                println!(
+                    "{}",
+                    "No transactions in recent blocks; the minimum fee applies | لا توجد معاملات حديثة"
+                        .yellow()
+                );
+                println!(
+                    "{}: {} SYL",
+                    "Fee | الرسوم".cyan(),
+                    fees::suggest_fee(0, fees::transfer_weight()) as f64 / 1_000_000.0
+                );
+                return Ok(());
+            };
+
+            let weight = fees::transfer_weight();
+            println!(
+                "{} ({} blocks, {} bytes per transfer)",
+                "Fee estimate | تقدير الرسوم".cyan().bold(),
+                estimate.blocks,
+                weight
+            );
+            for (label, rate) in [
+                ("Low | منخفض", estimate.low),
+                ("Medium | متوسط", estimate.medium),
+                ("High | مرتفع", estimate.high),
+            ] {
+                println!(
+                    "  {}: {} SYL ({} units/KB)",
+                    label.cyan(),
+                    fees::suggest_fee(rate, weight) as f64 / 1_000_000.0,
+                    rate
+                );
+            }
+        }
+
+        Commands::SignMessage {
+            name,
+            message,
+            output,
+        } => {
+            let account = encrypted_storage.load_account(&name)?;
+            println!("{}", "Enter password | أدخل كلمة المرور: ".cyan());
+            let password = read_password()?;
+            let keypair =
+                encrypted_storage.unlock_account(&account, &password, keyfile.as_ref())?;
+
+            let signed = SignedMessage::sign(&message, &keypair)?;
+            let json = serde_json::to_string_pretty(&signed)?;
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, &json)?;
+                    println!(
+                        "{}: {}",
+                        "✓ Signed message written | تم حفظ الرسالة الموقعة".green(),
+                        path.display()
+                    );
+                }
+                None => println!("{}", json),
+            }
+        }
+
+        Commands::VerifyMessage {
+            file,
+            address,
+            message,
+            signature,
+        } => {
+            let signed = match (file, address, message, signature) {
+                (Some(path), ..) => serde_json::from_str(&std::fs::read_to_string(&path)?)?,
+                (None, Some(address), Some(message), Some(signature)) => SignedMessage {
+                    address,
+                    message,
+                    signature,
+                },
+                _ => anyhow::bail!(
+                    "Give a signed message file or --address, --message and --signature | حدد ملف الرسالة أو العنوان والرسالة والتوقيع"
+                ),
+            };
+
+            match signed.verify() {
+                Ok(address) => {
+                    println!("{}", "✓ Signature is valid | التوقيع صالح".green().bold());
+                    println!("{}: {}", "Address | العنوان".cyan(), address.to_hex());
+                    println!("{}: {}", "Message | الرسالة".cyan(), signed.message);
+                }
+                Err(e) => anyhow::bail!("Signature is not valid | التوقيع غير صالح: {}", e),
+            }
+        }
+
+        Commands::Keyfile { path } => {
+            Keyfile::generate(&path)?;
+            println!(
+                "{}: {}",
+                "✓ Keyfile written | تم إنشاء ملف المفتاح".green(),
+                path.display()
+            );
+            println!(
+                "{}",
+                "⚠ Back it up: accounts created with it cannot be opened without it | احفظ نسخة منه"
+                    .yellow()
+            );
         }
 
         Commands::Delete { name } => {
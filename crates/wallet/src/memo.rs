@@ -0,0 +1,80 @@
+//! Reading transaction memos
+//! قراءة ملاحظات المعاملات
+//!
+//! Memos are plain text or encrypted to the recipient (see
+//! [`opensyria_core::memo`]). Given the account's key, [`read_memo`] decrypts
+//! memos sent to it; memos it cannot decrypt, such as those it sent to
+//! someone else, stay [`MemoText::Encrypted`].
+
+use opensyria_core::{crypto::KeyPair, Memo};
+
+/// What the wallet can show of a memo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoText {
+    Plain(String),
+    /// Encrypted memo decrypted with the account's key
+    Decrypted(String),
+    /// Encrypted memo the account cannot read
+    Encrypted,
+}
+
+impl MemoText {
+    pub fn is_encrypted(&self) -> bool {
+        !matches!(self, MemoText::Plain(_))
+    }
+
+    /// Memo text, unless it stayed encrypted
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            MemoText::Plain(text) | MemoText::Decrypted(text) => Some(text),
+            MemoText::Encrypted => None,
+        }
+    }
+}
+
+/// Memo in transaction `data`, decrypted with `keypair` when possible
+pub fn read_memo(data: &[u8], keypair: Option<&KeyPair>) -> Option<MemoText> {
+    match Memo::from_data(data) {
+        Ok(Some(Memo::Plain(text))) => {
+            Some(MemoText::Plain(String::from_utf8_lossy(&text).into_owned()))
+        }
+        Ok(Some(Memo::Encrypted(memo))) => Some(
+            keypair
+                .and_then(|keypair| memo.decrypt(keypair).ok())
+                .map_or(MemoText::Encrypted, |text| {
+                    MemoText::Decrypted(String::from_utf8_lossy(&text).into_owned())
+                }),
+        ),
+        // Unknown versions and malformed memos are shown as encrypted
+        Err(_) => Some(MemoText::Encrypted),
+        Ok(None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::EncryptedMemo;
+
+    #[test]
+    fn test_read_memo() {
+        let recipient = KeyPair::generate();
+        let sender = KeyPair::generate();
+        let data = EncryptedMemo::encrypt(&recipient.public_key(), "Rent – إيجار".as_bytes())
+            .unwrap()
+            .to_data();
+
+        assert_eq!(
+            read_memo(&data, Some(&recipient)),
+            Some(MemoText::Decrypted("Rent – إيجار".to_string()))
+        );
+        assert_eq!(read_memo(&data, Some(&sender)), Some(MemoText::Encrypted));
+        assert_eq!(read_memo(&data, None), Some(MemoText::Encrypted));
+
+        assert_eq!(
+            read_memo(b"Order 42", None),
+            Some(MemoText::Plain("Order 42".to_string()))
+        );
+        assert_eq!(read_memo(b"opensyria-dex-v1", Some(&recipient)), None);
+    }
+}
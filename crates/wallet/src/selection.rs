@@ -0,0 +1,328 @@
+//! Choosing which locked entries to consume
+//! اختيار المدخلات المقفلة المراد استهلاكها
+//!
+//! SYL balances are plain accounts, but DEX orders, asset lockups and
+//! future HTLCs hold value in discrete entries that are consumed whole. A
+//! flow that needs `target` units picks entries with [`select`]:
+//!
+//! - [`SelectionStrategy::LargestFirst`] takes the largest entries until the
+//!   target is covered, consuming as few entries as possible.
+//! - [`SelectionStrategy::BranchAndBound`] searches for a set whose total
+//!   lands within `change_tolerance` of the target, so no change has to be
+//!   returned. If none exists within the search budget it falls back to
+//!   largest-first.
+//!
+//! Every entry costs `cost_per_entry` to consume (usually the fee of the
+//! transaction releasing it), which counts against what it contributes.
+
+use opensyria_core::dex::{Order, OrderStatus};
+use std::cmp::Reverse;
+
+/// Branch-and-bound steps tried before falling back to largest-first
+pub const BNB_MAX_TRIES: usize = 100_000;
+
+/// A discrete entry holding value that is consumed whole
+pub trait Spendable {
+    /// Units the entry releases when consumed
+    fn value(&self) -> u64;
+
+    /// Whether the entry can be consumed now
+    fn is_spendable(&self) -> bool {
+        true
+    }
+}
+
+impl Spendable for u64 {
+    fn value(&self) -> u64 {
+        *self
+    }
+}
+
+/// An open DEX order releases what it still holds in escrow when cancelled:
+/// SYL for buy orders, asset units for sell orders
+impl Spendable for Order {
+    fn value(&self) -> u64 {
+        self.locked()
+    }
+
+    fn is_spendable(&self) -> bool {
+        self.status == OrderStatus::Open
+    }
+}
+
+/// How to pick entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionStrategy {
+    LargestFirst,
+    /// Prefer a set needing no change; largest-first otherwise
+    #[default]
+    BranchAndBound,
+}
+
+/// Amount to cover and what consuming entries costs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SelectionTarget {
+    pub amount: u64,
+    /// Cost of consuming one entry, deducted from its value
+    pub cost_per_entry: u64,
+    /// Excess over `amount` accepted without returning change
+    pub change_tolerance: u64,
+}
+
+impl SelectionTarget {
+    pub fn new(amount: u64) -> Self {
+        Self {
+            amount,
+            cost_per_entry: 0,
+            change_tolerance: 0,
+        }
+    }
+}
+
+/// Entries picked to cover a target
+#[derive(Debug, PartialEq, Eq)]
+pub struct Selection<'a, T> {
+    pub selected: Vec<&'a T>,
+    /// Value of the selected entries after their cost
+    pub effective_total: u64,
+    /// Excess over the target; zero-change selections leave it to the
+    /// tolerance
+    pub change: u64,
+}
+
+impl<T> Selection<'_, T> {
+    /// Whether the excess is too large to leave unreturned
+    pub fn needs_change(&self, target: &SelectionTarget) -> bool {
+        self.change > target.change_tolerance
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectionError {
+    /// The spendable entries do not cover the target after costs
+    Insufficient { available: u64, target: u64 },
+}
+
+impl std::fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SelectionError::Insufficient { available, target } => write!(
+                f,
+                "Spendable entries cover {} of the {} needed",
+                available, target
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SelectionError {}
+
+/// Pick entries from `candidates` covering `target`
+/// اختيار المدخلات التي تغطي المبلغ المطلوب
+pub fn select<'a, T: Spendable>(
+    candidates: &'a [T],
+    target: &SelectionTarget,
+    strategy: SelectionStrategy,
+) -> Result<Selection<'a, T>, SelectionError> {
+    // Entries worth less than their cost only make the selection worse
+    let mut pool: Vec<(&'a T, u64)> = candidates
+        .iter()
+        .filter(|entry| entry.is_spendable())
+        .filter_map(|entry| {
+            let effective = entry.value().checked_sub(target.cost_per_entry)?;
+            (effective > 0).then_some((entry, effective))
+        })
+        .collect();
+    pool.sort_by_key(|(_, effective)| Reverse(*effective));
+
+    let available = pool.iter().map(|(_, effective)| *effective).sum::<u64>();
+    if available < target.amount {
+        return Err(SelectionError::Insufficient {
+            available,
+            target: target.amount,
+        });
+    }
+
+    if strategy == SelectionStrategy::BranchAndBound {
+        if let Some(indices) = branch_and_bound(&pool, target) {
+            return Ok(selection(&pool, indices, target.amount));
+        }
+    }
+    Ok(largest_first(&pool, target.amount))
+}
+
+fn selection<'a, T>(pool: &[(&'a T, u64)], indices: Vec<usize>, amount: u64) -> Selection<'a, T> {
+    let effective_total = indices.iter().map(|&i| pool[i].1).sum::<u64>();
+    Selection {
+        selected: indices.into_iter().map(|i| pool[i].0).collect(),
+        effective_total,
+        change: effective_total - amount,
+    }
+}
+
+/// `pool` is sorted largest first and covers `amount`
+fn largest_first<'a, T>(pool: &[(&'a T, u64)], amount: u64) -> Selection<'a, T> {
+    let mut total = 0u64;
+    let mut indices = Vec::new();
+    for (i, (_, effective)) in pool.iter().enumerate() {
+        if total >= amount && !indices.is_empty() {
+            break;
+        }
+        total += effective;
+        indices.push(i);
+    }
+    selection(pool, indices, amount)
+}
+
+/// Depth-first search over include/exclude decisions, largest entry first,
+/// for the set with the least excess within the tolerance
+fn branch_and_bound<T>(pool: &[(&T, u64)], target: &SelectionTarget) -> Option<Vec<usize>> {
+    let upper = target.amount.saturating_add(target.change_tolerance);
+    // Value still available from each position onwards, for pruning
+    let mut remaining = vec![0u64; pool.len() + 1];
+    for i in (0..pool.len()).rev() {
+        remaining[i] = remaining[i + 1] + pool[i].1;
+    }
+
+    let mut best: Option<(u64, Vec<usize>)> = None;
+    let mut current = Vec::new();
+    let mut tries = 0;
+    search(
+        pool,
+        &remaining,
+        0,
+        0,
+        target.amount,
+        upper,
+        &mut current,
+        &mut best,
+        &mut tries,
+    );
+    best.map(|(_, indices)| indices)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search<T>(
+    pool: &[(&T, u64)],
+    remaining: &[u64],
+    index: usize,
+    total: u64,
+    lower: u64,
+    upper: u64,
+    current: &mut Vec<usize>,
+    best: &mut Option<(u64, Vec<usize>)>,
+    tries: &mut usize,
+) {
+    *tries += 1;
+    if *tries > BNB_MAX_TRIES || total > upper {
+        return;
+    }
+    if total >= lower {
+        let excess = total - lower;
+        if best
+            .as_ref()
+            .is_none_or(|(best_excess, _)| excess < *best_excess)
+        {
+            *best = Some((excess, current.clone()));
+        }
+        // Adding more only grows the excess
+        return;
+    }
+    if index == pool.len() || total + remaining[index] < lower {
+        return;
+    }
+    if best.as_ref().is_some_and(|(excess, _)| *excess == 0) {
+        return;
+    }
+
+    current.push(index);
+    search(
+        pool,
+        remaining,
+        index + 1,
+        total + pool[index].1,
+        lower,
+        upper,
+        current,
+        best,
+        tries,
+    );
+    current.pop();
+    search(
+        pool,
+        remaining,
+        index + 1,
+        total,
+        lower,
+        upper,
+        current,
+        best,
+        tries,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(selection: &Selection<'_, u64>) -> Vec<u64> {
+        selection.selected.iter().map(|&&v| v).collect()
+    }
+
+    #[test]
+    fn test_largest_first_uses_fewest_entries() {
+        let entries = [10u64, 70, 30, 50];
+        let selection = select(
+            &entries,
+            &SelectionTarget::new(90),
+            SelectionStrategy::LargestFirst,
+        )
+        .unwrap();
+        assert_eq!(values(&selection), vec![70, 50]);
+        assert_eq!(selection.change, 30);
+    }
+
+    #[test]
+    fn test_branch_and_bound_avoids_change() {
+        let entries = [10u64, 70, 30, 50];
+        let target = SelectionTarget::new(90);
+        let selection = select(&entries, &target, SelectionStrategy::BranchAndBound).unwrap();
+        let mut picked = values(&selection);
+        picked.sort_unstable();
+        assert_eq!(picked, vec![10, 30, 50]);
+        assert_eq!(selection.change, 0);
+        assert!(!selection.needs_change(&target));
+
+        // No exact match within tolerance falls back to largest-first
+        let selection = select(
+            &[40u64, 40, 40],
+            &SelectionTarget::new(50),
+            SelectionStrategy::BranchAndBound,
+        )
+        .unwrap();
+        assert_eq!(values(&selection), vec![40, 40]);
+        assert_eq!(selection.change, 30);
+    }
+
+    #[test]
+    fn test_costs_and_shortfall() {
+        let target = SelectionTarget {
+            amount: 100,
+            cost_per_entry: 5,
+            change_tolerance: 10,
+        };
+        // The 5-unit entry is worth nothing once its cost is paid
+        let selection =
+            select(&[5u64, 60, 55], &target, SelectionStrategy::BranchAndBound).unwrap();
+        assert_eq!(selection.effective_total, 105);
+        assert_eq!(selection.change, 5);
+
+        assert_eq!(
+            select(&[5u64, 60, 45], &target, SelectionStrategy::LargestFirst),
+            Err(SelectionError::Insufficient {
+                available: 95,
+                target: 100
+            })
+        );
+    }
+}
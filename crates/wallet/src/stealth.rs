@@ -0,0 +1,171 @@
+//! Receiving stealth payments
+//! استلام الدفعات الخفية
+//!
+//! Every account has a stealth address derived from its key (see
+//! [`opensyria_core::stealth`]). Payments to it land on one-time addresses
+//! only the account can find: [`scan`] pages through the node's stealth
+//! announcements and checks each one locally, so the node never learns which
+//! payments are ours. [`build_stealth_sweep`] then moves a payment from its
+//! one-time address to a regular one.
+
+use anyhow::{anyhow, bail, Result};
+use opensyria_core::{
+    crypto::PublicKey,
+    stealth::{StealthOutput, StealthScanKey, StealthSpendKey},
+    transaction::Transaction,
+    StealthAnnouncement,
+};
+
+use crate::client::{NodeClient, StealthAnnouncementEntry};
+
+/// A stealth payment that belongs to us
+#[derive(Clone)]
+pub struct ReceivedPayment {
+    pub output: StealthOutput,
+    pub hash: String,
+    /// `None` while in the mempool
+    pub block_height: Option<u64>,
+    pub amount: u64,
+}
+
+impl ReceivedPayment {
+    /// Decrypted memo as text, if the sender left one
+    pub fn memo(&self) -> Option<String> {
+        (!self.output.memo.is_empty())
+            .then(|| String::from_utf8_lossy(&self.output.memo).into_owned())
+    }
+}
+
+/// Result of scanning the chain for our payments
+pub struct StealthScan {
+    pub payments: Vec<ReceivedPayment>,
+    /// Height to resume scanning from next time
+    pub next_height: u64,
+}
+
+/// Announcements among `entries` that pay our stealth address
+pub fn find_payments(
+    scan_key: &StealthScanKey,
+    entries: &[StealthAnnouncementEntry],
+) -> Vec<ReceivedPayment> {
+    entries
+        .iter()
+        .filter_map(|entry| {
+            let to = PublicKey::from_hex(&entry.to).ok()?;
+            let data = hex::decode(&entry.data).ok()?;
+            let announcement = StealthAnnouncement::from_data(&data)?;
+            Some(ReceivedPayment {
+                output: scan_key.detect(&to, &announcement)?,
+                hash: entry.hash.clone(),
+                block_height: entry.block_height,
+                amount: entry.amount,
+            })
+        })
+        .collect()
+}
+
+/// Scan announcements from `from_height` to the tip, then the mempool
+/// مسح السلسلة بحثاً عن الدفعات الخفية
+pub fn scan(
+    client: &NodeClient,
+    scan_key: &StealthScanKey,
+    from_height: u64,
+) -> Result<StealthScan> {
+    let mut payments = Vec::new();
+    let mut height = from_height;
+    loop {
+        let page = client.get_stealth_announcements(height)?;
+        payments.extend(find_payments(scan_key, &page.announcements));
+        if page.next_height > page.tip_height {
+            payments.extend(find_payments(scan_key, &page.pending));
+            return Ok(StealthScan {
+                payments,
+                next_height: page.next_height,
+            });
+        }
+        if page.next_height <= height {
+            bail!("Node stopped scanning at height {}", height);
+        }
+        height = page.next_height;
+    }
+}
+
+/// Build and sign a transaction moving `balance - fee` from a one-time
+/// address to `to`
+/// إنشاء وتوقيع معاملة تنقل رصيد العنوان لمرة واحدة
+pub fn build_stealth_sweep(
+    spend_key: &StealthSpendKey,
+    to: PublicKey,
+    balance: u64,
+    nonce: u64,
+    fee: u64,
+) -> Result<Transaction> {
+    let from = spend_key.public_key();
+    let amount = balance
+        .checked_sub(fee)
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| anyhow!("Balance {} does not cover the fee {}", balance, fee))?;
+
+    let tx = Transaction::new(from, to, amount, fee, nonce);
+    let signature = spend_key.sign(&tx.signing_hash());
+    let tx = tx.with_signature(signature);
+    tx.verify()
+        .map_err(|e| anyhow!("Failed to sign stealth sweep: {:?}", e))?;
+
+    Ok(tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::{crypto::KeyPair, StealthKeys};
+
+    fn entry(tx: &Transaction, block_height: Option<u64>) -> StealthAnnouncementEntry {
+        StealthAnnouncementEntry {
+            hash: hex::encode(tx.hash()),
+            block_height,
+            to: tx.to.to_hex(),
+            amount: tx.amount,
+            data: hex::encode(tx.data.as_deref().unwrap_or_default()),
+        }
+    }
+
+    #[test]
+    fn test_find_and_sweep_payment() {
+        let keys = StealthKeys::from_account(&KeyPair::generate());
+        let other = StealthKeys::from_account(&KeyPair::generate());
+        let sender = KeyPair::generate().public_key();
+
+        let ours = keys
+            .address()
+            .pay()
+            .unwrap()
+            .transaction(sender, 2_000_000, 100, 0, Some("Rent".as_bytes()))
+            .unwrap();
+        let theirs = other
+            .address()
+            .pay()
+            .unwrap()
+            .transaction(sender, 3_000_000, 100, 1, None)
+            .unwrap();
+
+        let found = find_payments(
+            keys.scan_key(),
+            &[entry(&theirs, Some(4)), entry(&ours, None)],
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].hash, hex::encode(ours.hash()));
+        assert_eq!(found[0].amount, 2_000_000);
+        assert_eq!(found[0].block_height, None);
+        assert_eq!(found[0].memo().as_deref(), Some("Rent"));
+
+        let spend_key = keys.spend_key(&found[0].output);
+        let destination = KeyPair::generate().public_key();
+        let sweep = build_stealth_sweep(&spend_key, destination, 2_000_000, 0, 100).unwrap();
+        assert_eq!(sweep.from, ours.to);
+        assert_eq!(sweep.amount, 1_999_900);
+        assert!(sweep.verify().is_ok());
+
+        assert!(build_stealth_sweep(&spend_key, destination, 100, 0, 100).is_err());
+    }
+}
@@ -0,0 +1,136 @@
+//! Sweep the full balance of an imported key to another address
+//! كنس الرصيد الكامل لمفتاح مستورد إلى عنوان آخر
+
+use anyhow::{anyhow, Context, Result};
+use opensyria_core::{
+    crypto::{KeyPair, PublicKey},
+    transaction::Transaction,
+    DUST_THRESHOLD,
+};
+use std::path::Path;
+use zeroize::Zeroize;
+
+/// Parse a private key given as hex or as a path to a file containing hex
+/// تحليل مفتاح خاص مُعطى كنص سداسي عشري أو كمسار ملف
+pub fn parse_private_key(input: &str) -> Result<KeyPair> {
+    let mut text = if Path::new(input).is_file() {
+        std::fs::read_to_string(input)
+            .with_context(|| format!("Failed to read private key file {}", input))?
+    } else {
+        input.to_string()
+    };
+
+    let trimmed = text.trim();
+    let hex_key = trimmed.strip_prefix("0x").unwrap_or(trimmed);
+    let decoded = hex::decode(hex_key);
+    text.zeroize();
+
+    let mut bytes = decoded.map_err(|_| anyhow!("Private key must be 64 hex characters"))?;
+    if bytes.len() != 32 {
+        bytes.zeroize();
+        return Err(anyhow!("Private key must be 32 bytes, got {}", bytes.len()));
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&bytes);
+    bytes.zeroize();
+
+    let keypair = KeyPair::from_bytes(&key).map_err(|e| anyhow!("Invalid private key: {}", e));
+    key.zeroize();
+    keypair
+}
+
+/// Build and sign a transaction moving `balance - fee` to `to`
+/// إنشاء وتوقيع معاملة تنقل كامل الرصيد ناقص الرسوم
+pub fn build_sweep_transaction(
+    keypair: &KeyPair,
+    to: PublicKey,
+    balance: u64,
+    nonce: u64,
+    fee: u64,
+) -> Result<Transaction> {
+    let from = keypair.public_key();
+    if from == to {
+        return Err(anyhow!(
+            "Sweep destination must differ from the source address"
+        ));
+    }
+
+    let amount = balance
+        .checked_sub(fee)
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| anyhow!("Balance {} does not cover the fee {}", balance, fee))?;
+
+    let tx = Transaction::new(from, to, amount, fee, nonce);
+    let signature = keypair.sign(&tx.signing_hash());
+    let tx = tx.with_signature(signature);
+    tx.verify()
+        .map_err(|e| anyhow!("Failed to sign sweep transaction: {:?}", e))?;
+
+    Ok(tx)
+}
+
+/// Whether moving `balance` in one transaction is worth paying `fee`
+/// التحقق من جدوى دمج الرصيد مقارنة بالرسوم
+///
+/// The consolidated amount must clear the dust threshold and the fee may not
+/// exceed `max_fee_ratio` of the balance being moved.
+pub fn consolidation_is_favorable(balance: u64, fee: u64, max_fee_ratio: f64) -> bool {
+    match balance.checked_sub(fee) {
+        Some(amount) if amount >= DUST_THRESHOLD => fee as f64 <= balance as f64 * max_fee_ratio,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_private_key_hex_and_file() {
+        let keypair = KeyPair::generate();
+        let hex_key = keypair.with_private_key(|key| hex::encode(key));
+
+        let parsed = parse_private_key(&format!("0x{}", hex_key)).unwrap();
+        assert_eq!(parsed.public_key(), keypair.public_key());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("paper.key");
+        std::fs::write(&path, format!("{}\n", hex_key)).unwrap();
+        let parsed = parse_private_key(path.to_str().unwrap()).unwrap();
+        assert_eq!(parsed.public_key(), keypair.public_key());
+
+        assert!(parse_private_key("abcd").is_err());
+    }
+
+    #[test]
+    fn test_sweep_moves_balance_minus_fee() {
+        let source = KeyPair::generate();
+        let destination = KeyPair::generate().public_key();
+
+        let tx = build_sweep_transaction(&source, destination, 5_000_000, 3, 100).unwrap();
+        assert_eq!(tx.amount, 4_999_900);
+        assert_eq!(tx.fee, 100);
+        assert_eq!(tx.nonce, 3);
+        assert!(tx.verify().is_ok());
+    }
+
+    #[test]
+    fn test_sweep_rejects_insufficient_balance() {
+        let source = KeyPair::generate();
+        let destination = KeyPair::generate().public_key();
+
+        assert!(build_sweep_transaction(&source, destination, 100, 0, 100).is_err());
+        assert!(build_sweep_transaction(&source, source.public_key(), 1_000, 0, 100).is_err());
+    }
+
+    #[test]
+    fn test_consolidation_fee_conditions() {
+        // 100 unit fee on 10_000 units is 1%
+        assert!(consolidation_is_favorable(10_000, 100, 0.05));
+        assert!(!consolidation_is_favorable(10_000, 100, 0.005));
+        // Result would itself be dust
+        assert!(!consolidation_is_favorable(DUST_THRESHOLD, 100, 1.0));
+        assert!(!consolidation_is_favorable(50, 100, 1.0));
+    }
+}
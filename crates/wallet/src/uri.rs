@@ -0,0 +1,305 @@
+//! `opensyria:` payment request URIs
+//! روابط طلب الدفع `opensyria:`
+//!
+//! A payment request names the recipient and optionally the amount, a memo,
+//! a label for the recipient and an expiry time:
+//!
+//! ```text
+//! opensyria:<address hex>?amount=1.5&label=Bakery&memo=Order%2042&expires=1767225600
+//! ```
+//!
+//! `amount` is in SYL with at most six decimals and `expires` is a unix
+//! timestamp in seconds. Values are percent-encoded UTF-8. Unknown parameters
+//! are ignored unless they start with `req-`, which marks a parameter the
+//! payer must understand. The memo travels on chain as the transaction's
+//! `data`, so it is limited to [`MAX_MEMO_LEN`] bytes.
+
+use anyhow::{anyhow, bail, Result};
+use opensyria_core::{crypto::PublicKey, EncryptedMemo};
+
+use crate::assets::format_asset_amount;
+
+/// URI scheme of payment requests
+pub const PAYMENT_URI_SCHEME: &str = "opensyria";
+
+/// Longest memo, in bytes, carried in a transaction
+pub use opensyria_core::memo::MAX_MEMO_LEN;
+
+/// Longest label, in bytes
+pub const MAX_LABEL_LEN: usize = 64;
+
+/// Decimal places of SYL amounts (1 SYL = 1_000_000 units)
+const SYL_DECIMALS: usize = 6;
+
+/// Prefix reserved for protocol operations carried in transaction data
+const RESERVED_DATA_PREFIX: &str = "opensyria-";
+
+/// A request for payment to an address
+/// طلب دفع إلى عنوان
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentRequest {
+    pub address: PublicKey,
+    /// Amount in base units
+    pub amount: Option<u64>,
+    /// Message stored with the payment on chain
+    pub memo: Option<String>,
+    /// Name of the recipient, shown to the payer only
+    pub label: Option<String>,
+    /// Unix time in seconds after which the request should not be paid
+    pub expires: Option<u64>,
+}
+
+impl PaymentRequest {
+    pub fn new(address: PublicKey) -> Self {
+        Self {
+            address,
+            amount: None,
+            memo: None,
+            label: None,
+            expires: None,
+        }
+    }
+
+    /// Check amount, memo and label limits
+    pub fn validate(&self) -> Result<()> {
+        if self.amount == Some(0) {
+            bail!("Payment amount must be positive");
+        }
+        if let Some(memo) = &self.memo {
+            if memo.len() > MAX_MEMO_LEN {
+                bail!("Memo is longer than {} bytes", MAX_MEMO_LEN);
+            }
+            if memo.starts_with(RESERVED_DATA_PREFIX) {
+                bail!("Memo must not start with '{}'", RESERVED_DATA_PREFIX);
+            }
+        }
+        if self.label.as_ref().is_some_and(|l| l.len() > MAX_LABEL_LEN) {
+            bail!("Label is longer than {} bytes", MAX_LABEL_LEN);
+        }
+        Ok(())
+    }
+
+    /// Whether the request has expired at unix time `now`
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires.is_some_and(|expires| now >= expires)
+    }
+
+    /// Transaction data carrying the memo, if any
+    pub fn memo_data(&self) -> Option<Vec<u8>> {
+        self.memo.as_ref().map(|memo| memo.as_bytes().to_vec())
+    }
+
+    /// Transaction data carrying the memo encrypted to the recipient, if any
+    pub fn encrypted_memo_data(&self) -> Result<Option<Vec<u8>>> {
+        self.memo
+            .as_ref()
+            .map(|memo| {
+                EncryptedMemo::encrypt(&self.address, memo.as_bytes())
+                    .map(|memo| memo.to_data())
+                    .map_err(|e| anyhow!("{}", e))
+            })
+            .transpose()
+    }
+
+    /// Encode as an `opensyria:` URI
+    /// ترميز الطلب كرابط
+    pub fn to_uri(&self) -> String {
+        let mut params = Vec::new();
+        if let Some(amount) = self.amount {
+            params.push(format!("amount={}", format_syl_amount(amount)));
+        }
+        if let Some(label) = &self.label {
+            params.push(format!("label={}", percent_encode(label)));
+        }
+        if let Some(memo) = &self.memo {
+            params.push(format!("memo={}", percent_encode(memo)));
+        }
+        if let Some(expires) = self.expires {
+            params.push(format!("expires={}", expires));
+        }
+
+        let mut uri = format!("{}:{}", PAYMENT_URI_SCHEME, self.address.to_hex());
+        if !params.is_empty() {
+            uri.push('?');
+            uri.push_str(&params.join("&"));
+        }
+        uri
+    }
+
+    /// Parse and validate an `opensyria:` URI
+    /// تحليل رابط طلب الدفع
+    pub fn parse(uri: &str) -> Result<Self> {
+        let uri = uri.trim();
+        let (scheme, rest) = uri
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Not a payment URI: missing scheme"))?;
+        if !scheme.eq_ignore_ascii_case(PAYMENT_URI_SCHEME) {
+            bail!("Unsupported URI scheme '{}'", scheme);
+        }
+        // Tolerate the `opensyria://` form some QR generators produce
+        let rest = rest.strip_prefix("//").unwrap_or(rest);
+        let (address, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let address =
+            PublicKey::from_hex(address).map_err(|_| anyhow!("Invalid address in payment URI"))?;
+
+        let mut request = Self::new(address);
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = percent_decode(value)?;
+            let duplicate = match key {
+                "amount" => request.amount.replace(parse_syl_amount(&value)?).is_some(),
+                "memo" => request.memo.replace(value).is_some(),
+                "label" => request.label.replace(value).is_some(),
+                "expires" => request
+                    .expires
+                    .replace(
+                        value
+                            .parse()
+                            .map_err(|_| anyhow!("Invalid expiry '{}'", value))?,
+                    )
+                    .is_some(),
+                _ if key.starts_with("req-") => {
+                    bail!("Unsupported required parameter '{}'", key)
+                }
+                _ => false,
+            };
+            if duplicate {
+                bail!("Parameter '{}' appears more than once", key);
+            }
+        }
+
+        request.validate()?;
+        Ok(request)
+    }
+}
+
+/// Format base units as SYL, without trailing zeros
+pub fn format_syl_amount(amount: u64) -> String {
+    format_asset_amount(amount, SYL_DECIMALS as u8)
+}
+
+/// Parse a SYL amount such as `1.5` into base units, exactly
+/// تحويل مبلغ بالليرة إلى وحدات أساسية
+pub fn parse_syl_amount(s: &str) -> Result<u64> {
+    let invalid = || anyhow!("Invalid SYL amount '{}'", s);
+    let (whole, fraction) = s.split_once('.').unwrap_or((s, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(fraction) {
+        return Err(invalid());
+    }
+    if fraction.len() > SYL_DECIMALS {
+        bail!("SYL amounts have at most {} decimals", SYL_DECIMALS);
+    }
+
+    let whole: u64 = whole.parse().map_err(|_| invalid())?;
+    let fraction: u64 = if fraction.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", fraction, width = SYL_DECIMALS)
+            .parse()
+            .map_err(|_| invalid())?
+    };
+    whole
+        .checked_mul(10u64.pow(SYL_DECIMALS as u32))
+        .and_then(|units| units.checked_add(fraction))
+        .ok_or_else(invalid)
+}
+
+/// Percent-encode everything but unreserved URI characters
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = s
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| anyhow!("Invalid percent-encoding in '{}'", s))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| anyhow!("Payment URI value is not valid UTF-8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opensyria_core::crypto::KeyPair;
+
+    #[test]
+    fn test_round_trip() {
+        let address = KeyPair::generate().public_key();
+        let request = PaymentRequest {
+            address,
+            amount: Some(1_500_000),
+            memo: Some("Order #42 – شكراً".to_string()),
+            label: Some("Damascus Bakery".to_string()),
+            expires: Some(1_767_225_600),
+        };
+
+        let uri = request.to_uri();
+        assert!(uri.starts_with(&format!("opensyria:{}?amount=1.5&", address.to_hex())));
+        assert!(uri.contains("label=Damascus%20Bakery"));
+        assert_eq!(PaymentRequest::parse(&uri).unwrap(), request);
+
+        let bare = PaymentRequest::new(address);
+        assert_eq!(bare.to_uri(), format!("opensyria:{}", address.to_hex()));
+        assert_eq!(PaymentRequest::parse(&bare.to_uri()).unwrap(), bare);
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_requests() {
+        let address = KeyPair::generate().public_key().to_hex();
+        for uri in [
+            format!("bitcoin:{}", address),
+            "opensyria:not-an-address".to_string(),
+            format!("opensyria:{}?amount=0", address),
+            format!("opensyria:{}?amount=1.0000001", address),
+            format!("opensyria:{}?amount=1&amount=2", address),
+            format!("opensyria:{}?req-fee=1", address),
+            format!("opensyria:{}?memo=%ZZ", address),
+            format!("opensyria:{}?memo=opensyria-dex-v1", address),
+        ] {
+            assert!(PaymentRequest::parse(&uri).is_err(), "{}", uri);
+        }
+
+        // Unknown optional parameters are ignored
+        let uri = format!("OpenSyria:{}?message=hi", address);
+        assert!(PaymentRequest::parse(&uri).is_ok());
+    }
+
+    #[test]
+    fn test_amounts_and_expiry() {
+        assert_eq!(parse_syl_amount("0.3").unwrap(), 300_000);
+        assert_eq!(parse_syl_amount("12").unwrap(), 12_000_000);
+        assert_eq!(parse_syl_amount("0.000001").unwrap(), 1);
+        assert!(parse_syl_amount("1.").is_ok());
+        assert!(parse_syl_amount(".5").is_err());
+        assert!(parse_syl_amount("-1").is_err());
+        assert!(parse_syl_amount("99999999999999999").is_err());
+        assert_eq!(format_syl_amount(300_000), "0.3");
+
+        let mut request = PaymentRequest::new(KeyPair::generate().public_key());
+        assert!(!request.is_expired(u64::MAX));
+        request.expires = Some(100);
+        assert!(!request.is_expired(99));
+        assert!(request.is_expired(100));
+    }
+}
@@ -40,6 +40,10 @@ async fn create_test_node(
         max_inbound_peers: 50,
         max_outbound_peers: 10,
         max_peers_per_asn: 5,
+        network: opensyria_network::NetworkType::Mainnet,
+        checkpoint_authority: None,
+        identity_passphrase: None,
+        bandwidth: Default::default(),
     };
 
     NetworkNode::new(config)
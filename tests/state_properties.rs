@@ -0,0 +1,544 @@
+//! Property Tests for Account State
+//! اختبارات الخصائص لحالة الحسابات
+//!
+//! Random blocks of transfers, sponsored and delegated fees, asset transfers,
+//! DEX orders and coinbases are applied to a fresh `StateStorage` and then
+//! reverted newest first. After every step the suites check that coins and
+//! asset units are conserved, that no balance goes negative, that nonces only
+//! move by the number of transactions sent, that the state height and block
+//! journals follow the accepted blocks, and that reverting a block restores
+//! the exact state from before it was applied.
+//!
+//! Run with: cargo test --package opensyria-integration-tests --test state_properties
+
+use opensyria_core::{
+    asset::{AssetId, AssetOperation},
+    crypto::PublicKey,
+    delegation::HotKeyDelegation,
+    dex::{escrow_address, DexOperation, Order, OrderSide, Trade},
+    Transaction, CHAIN_ID_MAINNET,
+};
+use opensyria_storage::{BlockJournal, StateStorage};
+use proptest::prelude::*;
+use std::collections::HashMap;
+use tempfile::TempDir;
+
+/// Accounts in play; few enough that blocks often touch one address twice
+const ACCOUNTS: usize = 4;
+
+/// Highest block height a run reaches: the setup block, then up to seven
+/// generated blocks
+const MAX_HEIGHT: u64 = 8;
+
+/// Starting Lira balances and asset holdings of the test accounts
+#[derive(Debug, Clone)]
+struct Genesis {
+    balances: Vec<u64>,
+    assets: Vec<u64>,
+}
+
+/// One transaction before its nonce is assigned
+#[derive(Debug, Clone)]
+enum OpSpec {
+    /// Lira transfer, its fee optionally sponsored, optionally sent by the
+    /// sender's hot key
+    Transfer {
+        from: usize,
+        to: usize,
+        amount: u64,
+        fee: u64,
+        sponsor: Option<usize>,
+        delegated: bool,
+    },
+    /// Transfer of the test asset
+    AssetTransfer {
+        from: usize,
+        to: usize,
+        amount: u64,
+        fee: u64,
+    },
+    /// Limit order on the test asset's market
+    Order {
+        from: usize,
+        side: OrderSide,
+        price: u64,
+        amount: u64,
+        fee: u64,
+    },
+}
+
+impl OpSpec {
+    fn sender(&self) -> usize {
+        match *self {
+            OpSpec::Transfer { from, .. }
+            | OpSpec::AssetTransfer { from, .. }
+            | OpSpec::Order { from, .. } => from,
+        }
+    }
+
+    fn fee(&self) -> u64 {
+        match *self {
+            OpSpec::Transfer { fee, .. }
+            | OpSpec::AssetTransfer { fee, .. }
+            | OpSpec::Order { fee, .. } => fee,
+        }
+    }
+}
+
+/// Block contents before nonces are assigned
+#[derive(Debug, Clone)]
+struct BlockSpec {
+    coinbase: Option<(usize, u64)>,
+    ops: Vec<OpSpec>,
+}
+
+impl BlockSpec {
+    fn has_orders(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, OpSpec::Order { .. }))
+    }
+}
+
+/// Everything a block can change, at one point in time
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    /// Lira balances of the accounts, then of the DEX escrow
+    balances: Vec<u64>,
+    nonces: Vec<u64>,
+    supply: u64,
+    /// Asset balances of the accounts, then of the DEX escrow
+    asset_balances: Vec<u64>,
+    asset_supply: u64,
+    orders: Vec<Order>,
+    trades: Vec<Trade>,
+    /// Lira each account's hot key spent today
+    delegation_spent: Vec<u64>,
+    state_height: u64,
+    /// Journals at heights 0 to [`MAX_HEIGHT`]
+    journals: Vec<Option<BlockJournal>>,
+}
+
+/// Outcome of applying one block
+struct Applied {
+    height: u64,
+    transactions: Vec<Transaction>,
+    accepted: bool,
+    before: Snapshot,
+    after: Snapshot,
+}
+
+fn account(index: usize) -> PublicKey {
+    PublicKey([index as u8 + 1; 32])
+}
+
+fn hot_key(index: usize) -> PublicKey {
+    PublicKey([index as u8 + 101; 32])
+}
+
+/// The test asset, created by the first account in the setup block
+fn asset() -> AssetId {
+    AssetId::derive(&account(0), 0)
+}
+
+fn op_strategy() -> impl Strategy<Value = OpSpec> {
+    prop_oneof![
+        3 => (
+            0..ACCOUNTS,
+            0..ACCOUNTS,
+            0u64..400_000,
+            0u64..5_000,
+            prop::option::weighted(0.25, 0..ACCOUNTS),
+            prop::bool::weighted(0.25),
+        )
+            .prop_map(|(from, to, amount, fee, sponsor, delegated)| OpSpec::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                // A sender paying its own fee is not sponsored
+                sponsor: sponsor.filter(|payer| *payer != from),
+                delegated,
+            }),
+        1 => (0..ACCOUNTS, 0..ACCOUNTS, 1u64..600, 0u64..5_000).prop_map(
+            |(from, to, amount, fee)| OpSpec::AssetTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            }
+        ),
+        1 => (0..ACCOUNTS, any::<bool>(), 1u64..20, 1u64..200, 0u64..5_000).prop_map(
+            |(from, buy, price, amount, fee)| OpSpec::Order {
+                from,
+                side: if buy { OrderSide::Buy } else { OrderSide::Sell },
+                price,
+                amount,
+                fee,
+            }
+        ),
+    ]
+}
+
+fn block_strategy() -> impl Strategy<Value = BlockSpec> {
+    (
+        prop::option::of((0..ACCOUNTS, 1u64..1_000_000)),
+        prop::collection::vec(op_strategy(), 0..6),
+    )
+        .prop_map(|(coinbase, ops)| BlockSpec { coinbase, ops })
+}
+
+fn genesis_strategy() -> impl Strategy<Value = Genesis> {
+    (
+        prop::collection::vec(0u64..1_000_000, ACCOUNTS),
+        prop::collection::vec(0u64..1_000, ACCOUNTS),
+    )
+        .prop_map(|(balances, assets)| Genesis { balances, assets })
+}
+
+/// Fund the accounts and record the matching supply, then apply a setup
+/// block at height 1 that creates the test asset, hands out the holdings
+/// and gives every account a hot key no block can exhaust
+fn open_state(genesis: &Genesis) -> (TempDir, StateStorage) {
+    let dir = TempDir::new().unwrap();
+    let state = StateStorage::open(dir.path().to_path_buf()).unwrap();
+    for (index, balance) in genesis.balances.iter().enumerate() {
+        state.set_balance(&account(index), *balance).unwrap();
+    }
+    state
+        .increase_supply(genesis.balances.iter().sum())
+        .unwrap();
+
+    let issuer = account(0);
+    let mut setup = vec![AssetOperation::Create {
+        symbol: "SOAP".to_string(),
+        name: "Aleppo Soap".to_string(),
+        decimals: 0,
+        max_supply: u64::MAX,
+    }
+    .transaction(CHAIN_ID_MAINNET, issuer, 0, 0)];
+    let mut issuer_nonce = 1;
+    for (index, amount) in genesis.assets.iter().enumerate() {
+        if *amount > 0 {
+            let mint = AssetOperation::Mint {
+                asset: asset(),
+                to: account(index),
+                amount: *amount,
+            };
+            setup.push(mint.transaction(CHAIN_ID_MAINNET, issuer, 0, issuer_nonce));
+            issuer_nonce += 1;
+        }
+    }
+    for index in 0..ACCOUNTS {
+        let delegation = HotKeyDelegation {
+            hot_key: hot_key(index),
+            daily_allowance: u64::MAX,
+        };
+        let nonce = if index == 0 { issuer_nonce } else { 0 };
+        setup.push(delegation.grant_transaction(CHAIN_ID_MAINNET, account(index), 0, nonce));
+    }
+    state.apply_block_at_height(1, &setup).unwrap();
+    (dir, state)
+}
+
+fn snapshot(state: &StateStorage) -> Snapshot {
+    let holders: Vec<PublicKey> = (0..ACCOUNTS)
+        .map(account)
+        .chain([escrow_address()])
+        .collect();
+    Snapshot {
+        balances: holders
+            .iter()
+            .map(|holder| state.get_balance(holder).unwrap())
+            .collect(),
+        nonces: (0..ACCOUNTS)
+            .map(|index| state.get_nonce(&account(index)).unwrap())
+            .collect(),
+        supply: state.get_total_supply().unwrap(),
+        asset_balances: holders
+            .iter()
+            .map(|holder| state.get_asset_balance(holder, &asset()).unwrap())
+            .collect(),
+        asset_supply: state
+            .get_asset(&asset())
+            .unwrap()
+            .map_or(0, |info| info.supply),
+        orders: [OrderSide::Buy, OrderSide::Sell]
+            .into_iter()
+            .flat_map(|side| state.get_open_orders(&asset(), side).unwrap())
+            .collect(),
+        trades: state.get_trades(&asset(), 1_000).unwrap(),
+        delegation_spent: (0..ACCOUNTS)
+            .map(|index| state.get_delegation_spent(&account(index), 0).unwrap())
+            .collect(),
+        state_height: state.get_state_height().unwrap(),
+        journals: (0..=MAX_HEIGHT)
+            .map(|height| state.get_block_journal(height).unwrap())
+            .collect(),
+    }
+}
+
+/// Build the block's transactions with the next nonce for each sender
+fn build_block(spec: &BlockSpec, nonces: &[u64], height: u64) -> Vec<Transaction> {
+    let mut next_nonce = nonces.to_vec();
+    let mut transactions = Vec::new();
+    if let Some((miner, reward)) = spec.coinbase {
+        transactions.push(Transaction::new(
+            PublicKey::zero(),
+            account(miner),
+            reward,
+            0,
+            height,
+        ));
+    }
+    for op in &spec.ops {
+        let nonce = next_nonce[op.sender()];
+        next_nonce[op.sender()] += 1;
+        let tx = match *op {
+            OpSpec::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                sponsor,
+                delegated,
+            } => {
+                let mut tx = Transaction::new(account(from), account(to), amount, fee, nonce);
+                if let Some(payer) = sponsor {
+                    tx = tx.with_fee_payer(account(payer));
+                }
+                if delegated {
+                    tx = tx.with_delegate(hot_key(from));
+                }
+                tx
+            }
+            OpSpec::AssetTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => AssetOperation::Transfer {
+                asset: asset(),
+                to: account(to),
+                amount,
+            }
+            .transaction(CHAIN_ID_MAINNET, account(from), fee, nonce),
+            OpSpec::Order {
+                from,
+                side,
+                price,
+                amount,
+                fee,
+            } => DexOperation::Place {
+                asset: asset(),
+                side,
+                price,
+                amount,
+            }
+            .transaction(CHAIN_ID_MAINNET, account(from), fee, nonce),
+        };
+        transactions.push(tx);
+    }
+    transactions
+}
+
+/// Lira and asset balances after a block without orders by plain
+/// arithmetic, or `None` if any would go negative; Lira received anywhere in
+/// a block counts for the whole block, asset units only once received
+fn expected_balances(spec: &BlockSpec, before: &Snapshot) -> Option<(Vec<u64>, Vec<u64>)> {
+    let mut balances: Vec<i128> = before.balances.iter().map(|b| *b as i128).collect();
+    let mut assets = before.asset_balances.clone();
+    if let Some((miner, reward)) = spec.coinbase {
+        balances[miner] += reward as i128;
+    }
+    for op in &spec.ops {
+        match *op {
+            OpSpec::Transfer {
+                from,
+                to,
+                amount,
+                fee,
+                sponsor,
+                ..
+            } => {
+                balances[from] -= amount as i128;
+                balances[sponsor.unwrap_or(from)] -= fee as i128;
+                balances[to] += amount as i128;
+            }
+            OpSpec::AssetTransfer {
+                from,
+                to,
+                amount,
+                fee,
+            } => {
+                balances[from] -= fee as i128;
+                assets[from] = assets[from].checked_sub(amount)?;
+                assets[to] += amount;
+            }
+            OpSpec::Order { .. } => unreachable!("order matching is not modelled"),
+        }
+    }
+    let balances = balances
+        .into_iter()
+        .map(|balance| u64::try_from(balance).ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some((balances, assets))
+}
+
+/// Apply every block in order at the next height, recording the state
+/// around each one
+fn apply_all(state: &StateStorage, blocks: &[BlockSpec]) -> Vec<Applied> {
+    let mut applied = Vec::new();
+    for spec in blocks {
+        let before = snapshot(state);
+        let height = before.state_height + 1;
+        let transactions = build_block(spec, &before.nonces, height);
+        let accepted = state.apply_block_at_height(height, &transactions).is_ok();
+        applied.push(Applied {
+            height,
+            transactions,
+            accepted,
+            before,
+            after: snapshot(state),
+        });
+    }
+    applied
+}
+
+/// Property: every coin is accounted for after each block; transfers and
+/// orders only move coins, fees leave the balances until a coinbase pays
+/// them out, and only coinbases add to the recorded supply. Every asset unit
+/// is held by an account or by the escrow
+#[test]
+fn prop_supply_conserved_across_blocks() {
+    proptest!(|(
+        genesis in genesis_strategy(),
+        blocks in prop::collection::vec(block_strategy(), 1..8),
+    )| {
+        let (_dir, state) = open_state(&genesis);
+        for (spec, step) in blocks.iter().zip(apply_all(&state, &blocks)) {
+            let (minted, fees) = if step.accepted {
+                (
+                    spec.coinbase.map_or(0, |(_, reward)| reward),
+                    spec.ops.iter().map(OpSpec::fee).sum(),
+                )
+            } else {
+                (0, 0)
+            };
+            let before: u64 = step.before.balances.iter().sum();
+            let after: u64 = step.after.balances.iter().sum();
+            prop_assert_eq!(after, before + minted - fees);
+            prop_assert_eq!(step.after.supply, step.before.supply + minted);
+            prop_assert!(after <= step.after.supply);
+            prop_assert_eq!(state.check_supply_invariant().unwrap(), None);
+
+            prop_assert_eq!(step.after.asset_supply, step.before.asset_supply);
+            prop_assert_eq!(
+                step.after.asset_balances.iter().sum::<u64>(),
+                step.after.asset_supply
+            );
+        }
+    });
+}
+
+/// Property: a block is accepted exactly when no balance would go negative,
+/// and a rejected block changes nothing
+#[test]
+fn prop_balances_never_negative() {
+    proptest!(|(
+        genesis in genesis_strategy(),
+        blocks in prop::collection::vec(block_strategy(), 1..8),
+    )| {
+        let (_dir, state) = open_state(&genesis);
+        for (spec, step) in blocks.iter().zip(apply_all(&state, &blocks)) {
+            // Matching is not modelled, so blocks with orders are only held
+            // to changing nothing when rejected
+            let expected = if spec.has_orders() {
+                None
+            } else {
+                Some(expected_balances(spec, &step.before))
+            };
+            match expected {
+                Some(Some((balances, assets))) => {
+                    prop_assert!(step.accepted);
+                    prop_assert_eq!(&step.after.balances, &balances);
+                    prop_assert_eq!(&step.after.asset_balances, &assets);
+                }
+                Some(None) => {
+                    prop_assert!(!step.accepted);
+                    prop_assert_eq!(&step.after, &step.before);
+                }
+                None if !step.accepted => prop_assert_eq!(&step.after, &step.before),
+                None => {}
+            }
+        }
+    });
+}
+
+/// Property: applying a block moves each sender's nonce forward by the
+/// number of transactions it sent, and nothing else
+#[test]
+fn prop_nonces_monotonic() {
+    proptest!(|(
+        genesis in genesis_strategy(),
+        blocks in prop::collection::vec(block_strategy(), 1..8),
+    )| {
+        let (_dir, state) = open_state(&genesis);
+        for (spec, step) in blocks.iter().zip(apply_all(&state, &blocks)) {
+            let mut sent: HashMap<usize, u64> = HashMap::new();
+            if step.accepted {
+                for op in &spec.ops {
+                    *sent.entry(op.sender()).or_insert(0) += 1;
+                }
+            }
+            for index in 0..ACCOUNTS {
+                let count = sent.get(&index).copied().unwrap_or(0);
+                prop_assert!(step.after.nonces[index] >= step.before.nonces[index]);
+                prop_assert_eq!(step.after.nonces[index], step.before.nonces[index] + count);
+            }
+        }
+    });
+}
+
+/// Property: the state height names the last accepted block, which leaves a
+/// balanced journal at its height; a rejected block leaves neither
+#[test]
+fn prop_height_and_journal_follow_blocks() {
+    proptest!(|(
+        genesis in genesis_strategy(),
+        blocks in prop::collection::vec(block_strategy(), 1..8),
+    )| {
+        let (_dir, state) = open_state(&genesis);
+        for step in apply_all(&state, &blocks) {
+            let journal = &step.after.journals[step.height as usize];
+            prop_assert!(step.before.journals[step.height as usize].is_none());
+            if step.accepted {
+                prop_assert_eq!(step.after.state_height, step.height);
+                prop_assert!(journal.as_ref().is_some_and(BlockJournal::is_balanced));
+            } else {
+                prop_assert_eq!(step.after.state_height, step.height - 1);
+                prop_assert!(journal.is_none());
+            }
+        }
+    });
+}
+
+/// Property: reverting blocks newest first restores each earlier state
+/// exactly, including accounts touched several times in one block
+#[test]
+fn prop_apply_then_revert_is_identity() {
+    proptest!(|(
+        genesis in genesis_strategy(),
+        blocks in prop::collection::vec(block_strategy(), 1..8),
+    )| {
+        let (_dir, state) = open_state(&genesis);
+        let initial = snapshot(&state);
+        let applied = apply_all(&state, &blocks);
+
+        for step in applied.iter().rev().filter(|step| step.accepted) {
+            prop_assert_eq!(&snapshot(&state), &step.after);
+            state.revert_block_atomic(step.height, &step.transactions).unwrap();
+            prop_assert_eq!(&snapshot(&state), &step.before);
+            prop_assert_eq!(state.check_supply_invariant().unwrap(), None);
+        }
+        prop_assert_eq!(snapshot(&state), initial);
+    });
+}